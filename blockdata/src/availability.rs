@@ -0,0 +1,255 @@
+use crate::BlockHashData;
+use aleph_bft::{
+    ClockSource, Data, Hasher, Network, NetworkData, PartialMultisignature, Recipient, Signature,
+};
+use async_trait::async_trait;
+use futures::{future::pending, pin_mut, FutureExt};
+use log::{debug, trace};
+use std::{
+    cmp::min,
+    fmt::{Debug, Formatter, Result as FmtResult},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+const LOG_TARGET: &str = "AlephBFT-blockdata";
+
+/// How long messages are held back by blocks that are not available, by default.
+pub const DEFAULT_GIVE_UP_AFTER: Duration = Duration::from_secs(30);
+
+/// When to check again whether the blocks a message waits for became available.
+#[derive(Clone)]
+pub struct AvailabilityConfig {
+    /// The delay before the given recheck, counting from 0.
+    pub recheck_delay: Arc<dyn Fn(usize) -> Duration + Sync + Send + 'static>,
+    /// After this long the message is passed on even if some of its blocks are still not
+    /// available, as these are most likely never going to be.
+    pub give_up_after: Duration,
+}
+
+impl Default for AvailabilityConfig {
+    fn default() -> Self {
+        AvailabilityConfig {
+            // 50, 100, 200, ..., 1000, 1000, ...
+            recheck_delay: Arc::new(|t| {
+                min(
+                    Duration::from_millis(50 << min(t, 5)),
+                    Duration::from_secs(1),
+                )
+            }),
+            give_up_after: DEFAULT_GIVE_UP_AFTER,
+        }
+    }
+}
+
+impl Debug for AvailabilityConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("AvailabilityConfig")
+            .field("give up after", &self.give_up_after)
+            .finish()
+    }
+}
+
+/// Knows which blocks are available, i.e. have their bodies in the chain database.
+#[derive(Clone)]
+pub struct AvailabilityOracle<BH> {
+    has_block: Arc<dyn Fn(&BH) -> bool + Sync + Send + 'static>,
+    config: AvailabilityConfig,
+    clock: ClockSource,
+}
+
+impl<BH: Data> AvailabilityOracle<BH> {
+    pub fn new(
+        has_block: impl Fn(&BH) -> bool + Sync + Send + 'static,
+        config: AvailabilityConfig,
+    ) -> Self {
+        AvailabilityOracle {
+            has_block: Arc::new(has_block),
+            config,
+            clock: ClockSource::default(),
+        }
+    }
+
+    /// Measures the delays with the given clock, the same one as the session should use.
+    pub fn with_clock(self, clock: ClockSource) -> Self {
+        AvailabilityOracle { clock, ..self }
+    }
+
+    pub fn is_available(&self, hash: &BH) -> bool {
+        (self.has_block)(hash)
+    }
+
+    /// Wraps the network, so that messages reach the session only once the blocks they
+    /// propose are available.
+    pub fn wrap<N, D>(self, network: N) -> GatedNetwork<N, BH, D> {
+        GatedNetwork {
+            network,
+            oracle: self,
+            held: Vec::new(),
+        }
+    }
+}
+
+struct HeldMessage<BH, D> {
+    message: D,
+    missing: Vec<BH>,
+    arrived: Instant,
+    rechecks: usize,
+    next_recheck: Instant,
+}
+
+/// A network passing on messages only once the blocks proposed in them are available, see
+/// [`AvailabilityOracle::wrap`]. Messages waiting for different blocks might be passed on in a
+/// different order than they arrived in.
+pub struct GatedNetwork<N, BH, D> {
+    network: N,
+    oracle: AvailabilityOracle<BH>,
+    held: Vec<HeldMessage<BH, D>>,
+}
+
+impl<N, BH: Data, D> GatedNetwork<N, BH, D> {
+    /// Passes the message on right away if all the blocks are available, holds it otherwise.
+    fn on_message(&mut self, message: D, proposed: Vec<BlockHashData<BH>>) -> Option<D> {
+        let missing: Vec<_> = proposed
+            .into_iter()
+            .map(|BlockHashData(hash)| hash)
+            .filter(|hash| !self.oracle.is_available(hash))
+            .collect();
+        if missing.is_empty() {
+            return Some(message);
+        }
+        trace!(target: LOG_TARGET, "Holding a message back until {:?} are available.", missing);
+        let now = self.oracle.clock.now();
+        self.held.push(HeldMessage {
+            message,
+            missing,
+            arrived: now,
+            rechecks: 0,
+            next_recheck: now + (self.oracle.config.recheck_delay)(0),
+        });
+        None
+    }
+
+    /// Rechecks the messages that are due, returning one that should not be held anymore.
+    fn release(&mut self) -> Option<D> {
+        let now = self.oracle.clock.now();
+        let give_up_after = self.oracle.config.give_up_after;
+        for i in 0..self.held.len() {
+            let held = &mut self.held[i];
+            if held.next_recheck > now {
+                continue;
+            }
+            let oracle = &self.oracle;
+            held.missing.retain(|hash| !oracle.is_available(hash));
+            if !held.missing.is_empty()
+                && now.saturating_duration_since(held.arrived) < give_up_after
+            {
+                held.rechecks += 1;
+                held.next_recheck = now + (oracle.config.recheck_delay)(held.rechecks);
+                continue;
+            }
+            let held = self.held.swap_remove(i);
+            if !held.missing.is_empty() {
+                debug!(target: LOG_TARGET, "Blocks {:?} still not available after {:?}, passing the message on.", held.missing, give_up_after);
+            }
+            return Some(held.message);
+        }
+        None
+    }
+
+    fn next_recheck(&self) -> Option<Instant> {
+        self.held.iter().map(|held| held.next_recheck).min()
+    }
+}
+
+#[async_trait]
+impl<
+        H: Hasher,
+        BH: Data,
+        S: Signature,
+        MS: PartialMultisignature,
+        N: Network<NetworkData<H, BlockHashData<BH>, S, MS>>,
+    > Network<NetworkData<H, BlockHashData<BH>, S, MS>>
+    for GatedNetwork<N, BH, NetworkData<H, BlockHashData<BH>, S, MS>>
+{
+    fn send(&self, data: NetworkData<H, BlockHashData<BH>, S, MS>, recipient: Recipient) {
+        self.network.send(data, recipient)
+    }
+
+    async fn next_event(&mut self) -> Option<NetworkData<H, BlockHashData<BH>, S, MS>> {
+        loop {
+            if let Some(message) = self.release() {
+                return Some(message);
+            }
+            let recheck = match self.next_recheck() {
+                Some(at) => self.oracle.clock.sleep_until(at),
+                None => pending().boxed(),
+            };
+            let message = {
+                let next_event = self.network.next_event().fuse();
+                pin_mut!(next_event);
+                futures::select! {
+                    message = next_event => Some(message?),
+                    _ = recheck.fuse() => None,
+                }
+            };
+            if let Some(message) = message {
+                let proposed = message.included_data();
+                if let Some(message) = self.on_message(message, proposed) {
+                    return Some(message);
+                }
+            }
+        }
+    }
+
+    fn is_terminated(&self) -> bool {
+        self.network.is_terminated()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AvailabilityConfig, AvailabilityOracle, BlockHashData};
+    use parking_lot::Mutex;
+    use std::{collections::HashSet, sync::Arc, time::Duration};
+
+    fn oracle(
+        available: &Arc<Mutex<HashSet<u64>>>,
+        give_up_after: Duration,
+    ) -> AvailabilityOracle<u64> {
+        let available = available.clone();
+        AvailabilityOracle::new(
+            move |hash| available.lock().contains(hash),
+            AvailabilityConfig {
+                recheck_delay: Arc::new(|_| Duration::ZERO),
+                give_up_after,
+            },
+        )
+    }
+
+    #[test]
+    fn holds_messages_until_available() {
+        let available = Arc::new(Mutex::new(HashSet::from([1])));
+        let mut network = oracle(&available, Duration::from_secs(60)).wrap(());
+        assert_eq!(
+            network.on_message("ready", vec![BlockHashData(1)]),
+            Some("ready")
+        );
+        assert_eq!(
+            network.on_message("held", vec![BlockHashData(1), BlockHashData(2)]),
+            None
+        );
+        assert_eq!(network.release(), None);
+        available.lock().insert(2);
+        assert_eq!(network.release(), Some("held"));
+        assert_eq!(network.next_recheck(), None);
+    }
+
+    #[test]
+    fn gives_up_on_unavailable_blocks() {
+        let available = Arc::new(Mutex::new(HashSet::new()));
+        let mut network = oracle(&available, Duration::ZERO).wrap(());
+        assert_eq!(network.on_message("held", vec![BlockHashData(3)]), None);
+        assert_eq!(network.release(), Some("held"));
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{ancestor_at, BlockHashData, BlockId, ChainBackend, LastFinalized};
+use aleph_bft::{Data, FinalizationHandler};
+use log::{debug, trace};
+use std::marker::PhantomData;
+
+const LOG_TARGET: &str = "AlephBFT-blockdata";
+
+/// Finalizes the ordered blocks extending the last finalized one, passing every such block to the
+/// given callback, in increasing height. The ancestors of such a block are finalized with it, so
+/// they are not passed separately. Blocks at or below the last finalized one are skipped, as
+/// they are either finalized already or on another branch, and so are blocks with unknown
+/// headers, e.g. the ones that got pruned after a re-org.
+pub struct FinalizationAdapter<BH, B, F> {
+    backend: B,
+    last_finalized: LastFinalized<BH>,
+    finalize: F,
+    _phantom: PhantomData<BH>,
+}
+
+impl<BH: Data, B: ChainBackend<BH>, F: FnMut(BlockId<BH>) + Sync + Send + 'static>
+    FinalizationAdapter<BH, B, F>
+{
+    /// The last finalized block should be shared with the [`crate::BestBlockProvider`].
+    pub fn new(backend: B, last_finalized: LastFinalized<BH>, finalize: F) -> Self {
+        FinalizationAdapter {
+            backend,
+            last_finalized,
+            finalize,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<BH: Data, B: ChainBackend<BH>, F: FnMut(BlockId<BH>) + Sync + Send + 'static>
+    FinalizationHandler<BlockHashData<BH>> for FinalizationAdapter<BH, B, F>
+{
+    fn data_finalized(&mut self, BlockHashData(hash): BlockHashData<BH>) {
+        let Some(header) = self.backend.header(&hash) else {
+            debug!(target: LOG_TARGET, "Not finalizing {:?}, the block is not known.", hash);
+            return;
+        };
+        let block = BlockId::new(hash, header.number);
+        let last_finalized = self.last_finalized.get();
+        if block.number <= last_finalized.number {
+            trace!(target: LOG_TARGET, "Not finalizing {:?}, a block of this height is finalized already.", block);
+            return;
+        }
+        match ancestor_at(&self.backend, block.clone(), last_finalized.number) {
+            Some(ancestor) if ancestor == last_finalized.hash => {
+                trace!(target: LOG_TARGET, "Finalizing {:?}.", block);
+                self.last_finalized.set(block.clone());
+                (self.finalize)(block);
+            }
+            Some(_) => {
+                debug!(target: LOG_TARGET, "Not finalizing {:?}, it does not extend the finalized chain.", block)
+            }
+            None => {
+                debug!(target: LOG_TARGET, "Not finalizing {:?}, some of its ancestors are not known.", block)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        testing::{block, MockChain},
+        BlockHashData, BlockId, FinalizationAdapter, LastFinalized,
+    };
+    use aleph_bft::FinalizationHandler;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    fn adapter(
+        chain: MockChain,
+    ) -> (
+        impl FinalizationHandler<BlockHashData<u64>>,
+        Arc<Mutex<Vec<BlockId<u64>>>>,
+    ) {
+        let finalized = Arc::new(Mutex::new(Vec::new()));
+        let recorded = finalized.clone();
+        let adapter = FinalizationAdapter::new(
+            chain,
+            LastFinalized::new(BlockId::new(block(0, 0), 0)),
+            move |block| recorded.lock().push(block),
+        );
+        (adapter, finalized)
+    }
+
+    #[test]
+    fn finalizes_descendants_only_once() {
+        let (mut adapter, finalized) = adapter(MockChain::new(10));
+        for number in [3, 2, 3, 7, 5, 7, 8] {
+            adapter.data_finalized(BlockHashData(block(0, number)));
+        }
+        assert_eq!(
+            *finalized.lock(),
+            vec![
+                BlockId::new(block(0, 3), 3),
+                BlockId::new(block(0, 7), 7),
+                BlockId::new(block(0, 8), 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_other_branches_and_unknown_blocks() {
+        let chain = MockChain::new(4);
+        chain.import(1, 3, block(0, 2));
+        let (mut adapter, finalized) = adapter(chain.clone());
+        adapter.data_finalized(BlockHashData(block(0, 4)));
+        adapter.data_finalized(BlockHashData(block(1, 3)));
+        chain.import(1, 5, block(1, 3));
+        adapter.data_finalized(BlockHashData(block(1, 5)));
+        adapter.data_finalized(BlockHashData(block(2, 7)));
+        assert_eq!(*finalized.lock(), vec![BlockId::new(block(0, 4), 4)]);
+    }
+}
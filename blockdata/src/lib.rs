@@ -0,0 +1,136 @@
+//! Adapters for running AlephBFT as a finality gadget, where the ordered data are block hashes and
+//! a hash is available once the chain database holds the body of its block.
+//!
+//! * [`BestBlockProvider`] proposes the best block of the chain, a bounded number of blocks above
+//!   the last finalized one.
+//! * [`AvailabilityOracle`] holds back messages from the network until the blocks they propose
+//!   are available, rechecking on a configurable schedule. A block that never becomes available,
+//!   e.g. because it got orphaned by a re-org and pruned, stops holding the messages back after a
+//!   while, so consensus is not wedged by it.
+//! * [`FinalizationAdapter`] finalizes only the blocks extending the last finalized one, as
+//!   finalizing a block implies all its ancestors. Ordered hashes of unknown blocks, e.g. the ones
+//!   the oracle gave up on, and of blocks from other branches are skipped.
+//!
+//! All the nodes have to finalize the same blocks, so the oracle should wait long enough that
+//! a block still unavailable after that is not available to any honest node either.
+
+use aleph_bft::Data;
+use codec::{Decode, Encode};
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+mod availability;
+mod finalization;
+mod provider;
+#[cfg(test)]
+mod testing;
+
+pub use availability::{
+    AvailabilityConfig, AvailabilityOracle, GatedNetwork, DEFAULT_GIVE_UP_AFTER,
+};
+pub use finalization::FinalizationAdapter;
+pub use provider::{BestBlockProvider, DEFAULT_MAX_LEAD};
+
+/// The height of a block.
+pub type BlockNumber = u64;
+
+/// The data ordered by consensus, a hash of a block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Encode, Decode)]
+pub struct BlockHashData<BH>(pub BH);
+
+/// A block together with its height.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BlockId<BH> {
+    pub hash: BH,
+    pub number: BlockNumber,
+}
+
+impl<BH> BlockId<BH> {
+    pub fn new(hash: BH, number: BlockNumber) -> Self {
+        BlockId { hash, number }
+    }
+}
+
+/// The parts of a header the adapters need.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Header<BH> {
+    pub number: BlockNumber,
+    pub parent: BH,
+}
+
+/// The view of the chain database the adapters need, implemented by the application.
+pub trait ChainBackend<BH>: Send + Sync + 'static {
+    /// The header of the block, if we know it. Headers of pruned blocks may be gone.
+    fn header(&self, hash: &BH) -> Option<Header<BH>>;
+
+    /// The head of the best chain.
+    fn best_block(&self) -> BlockId<BH>;
+}
+
+/// The ancestor of the block at the given height, `None` if some header on the way is unknown or
+/// the block is lower than that.
+fn ancestor_at<BH: Data, B: ChainBackend<BH>>(
+    backend: &B,
+    block: BlockId<BH>,
+    number: BlockNumber,
+) -> Option<BH> {
+    if block.number < number {
+        return None;
+    }
+    let mut hash = block.hash;
+    for _ in number..block.number {
+        hash = backend.header(&hash)?.parent;
+    }
+    Some(hash)
+}
+
+/// The last block finalized by a [`FinalizationAdapter`], shared with the
+/// [`BestBlockProvider`] so that it does not propose blocks that are finalized already.
+#[derive(Clone, Debug)]
+pub struct LastFinalized<BH> {
+    block: Arc<Mutex<BlockId<BH>>>,
+}
+
+impl<BH: Data> LastFinalized<BH> {
+    /// Starts with the given block, usually the last block finalized before the session.
+    pub fn new(block: BlockId<BH>) -> Self {
+        LastFinalized {
+            block: Arc::new(Mutex::new(block)),
+        }
+    }
+
+    pub fn get(&self) -> BlockId<BH> {
+        self.block.lock().clone()
+    }
+
+    fn set(&self, block: BlockId<BH>) {
+        *self.block.lock() = block;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ancestor_at,
+        testing::{block, MockChain},
+        BlockId,
+    };
+
+    #[test]
+    fn finds_ancestors() {
+        let chain = MockChain::new(5);
+        let head = BlockId::new(block(0, 5), 5);
+        assert_eq!(ancestor_at(&chain, head, 5), Some(block(0, 5)));
+        assert_eq!(ancestor_at(&chain, head, 2), Some(block(0, 2)));
+        assert_eq!(ancestor_at(&chain, head, 0), Some(block(0, 0)));
+        assert_eq!(ancestor_at(&chain, head, 6), None);
+    }
+
+    #[test]
+    fn does_not_find_ancestors_through_unknown_blocks() {
+        let chain = MockChain::new(2);
+        chain.import(1, 3, block(0, 2));
+        chain.prune(block(0, 2));
+        assert_eq!(ancestor_at(&chain, BlockId::new(block(1, 3), 3), 1), None);
+    }
+}
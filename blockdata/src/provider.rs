@@ -0,0 +1,103 @@
+use crate::{ancestor_at, BlockHashData, BlockNumber, ChainBackend, LastFinalized};
+use aleph_bft::{Data, DataProvider};
+use async_trait::async_trait;
+use log::trace;
+use std::{cmp::min, marker::PhantomData};
+
+const LOG_TARGET: &str = "AlephBFT-blockdata";
+
+/// How many blocks above the last finalized one are proposed at most, by default.
+pub const DEFAULT_MAX_LEAD: BlockNumber = 20;
+
+/// Proposes the best block of the chain. Nothing is proposed while the best block is not above
+/// the last finalized one, as finalizing it would not change anything. A best block further than
+/// the maximal lead above the last finalized one is not proposed either, its ancestor at that
+/// height is proposed instead: the longer the unfinalized branch, the likelier it is to get
+/// re-orged.
+pub struct BestBlockProvider<BH, B> {
+    backend: B,
+    last_finalized: LastFinalized<BH>,
+    max_lead: BlockNumber,
+    _phantom: PhantomData<BH>,
+}
+
+impl<BH: Data, B: ChainBackend<BH>> BestBlockProvider<BH, B> {
+    /// The last finalized block should be shared with the [`crate::FinalizationAdapter`].
+    pub fn new(backend: B, last_finalized: LastFinalized<BH>) -> Self {
+        BestBlockProvider {
+            backend,
+            last_finalized,
+            max_lead: DEFAULT_MAX_LEAD,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Sets how many blocks above the last finalized one are proposed at most, at least one.
+    pub fn with_max_lead(self, max_lead: BlockNumber) -> Self {
+        BestBlockProvider {
+            max_lead: max_lead.max(1),
+            ..self
+        }
+    }
+
+    fn proposal(&self) -> Option<BH> {
+        let best = self.backend.best_block();
+        let last_finalized = self.last_finalized.get();
+        if best.number <= last_finalized.number {
+            return None;
+        }
+        let number = min(best.number, last_finalized.number + self.max_lead);
+        ancestor_at(&self.backend, best, number)
+    }
+}
+
+#[async_trait]
+impl<BH: Data, B: ChainBackend<BH>> DataProvider for BestBlockProvider<BH, B> {
+    type Output = BlockHashData<BH>;
+
+    async fn get_data(&mut self) -> Option<Self::Output> {
+        let proposal = self.proposal();
+        trace!(target: LOG_TARGET, "Proposing {:?}.", proposal);
+        proposal.map(BlockHashData)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        testing::{block, MockChain},
+        BestBlockProvider, BlockHashData, BlockId, LastFinalized,
+    };
+    use aleph_bft::DataProvider;
+    use futures::executor::block_on;
+
+    #[test]
+    fn proposes_best_block() {
+        let chain = MockChain::new(5);
+        let mut provider =
+            BestBlockProvider::new(chain, LastFinalized::new(BlockId::new(block(0, 2), 2)));
+        assert_eq!(
+            block_on(provider.get_data()),
+            Some(BlockHashData(block(0, 5)))
+        );
+    }
+
+    #[test]
+    fn proposes_nothing_when_everything_is_finalized() {
+        let chain = MockChain::new(5);
+        let mut provider =
+            BestBlockProvider::new(chain, LastFinalized::new(BlockId::new(block(0, 5), 5)));
+        assert_eq!(block_on(provider.get_data()), None);
+    }
+
+    #[test]
+    fn bounds_the_lead_above_finalized() {
+        let chain = MockChain::new(50);
+        let last_finalized = LastFinalized::new(BlockId::new(block(0, 10), 10));
+        let mut provider = BestBlockProvider::new(chain, last_finalized).with_max_lead(5);
+        assert_eq!(
+            block_on(provider.get_data()),
+            Some(BlockHashData(block(0, 15)))
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use crate::{BlockId, ChainBackend, Header};
+use parking_lot::Mutex;
+use std::{collections::HashMap, sync::Arc};
+
+/// The hash of the block of the given height on the given branch, the main one being 0.
+pub fn block(branch: u64, number: u64) -> u64 {
+    (branch << 32) | number
+}
+
+fn branch_of(hash: u64) -> u64 {
+    hash >> 32
+}
+
+/// The headers of the known blocks, together with the order they were imported in.
+type Blocks = HashMap<u64, (Header<u64>, usize)>;
+
+/// A chain database holding headers only, treating every known block as available. The best
+/// block is the highest one, the earliest imported one among blocks of the same height.
+#[derive(Clone)]
+pub struct MockChain {
+    blocks: Arc<Mutex<Blocks>>,
+}
+
+impl MockChain {
+    /// A chain with the main branch up to the given height.
+    pub fn new(height: u64) -> Self {
+        let chain = MockChain {
+            blocks: Arc::new(Mutex::new(HashMap::new())),
+        };
+        chain.import(0, 0, block(0, 0));
+        for number in 1..=height {
+            chain.import(0, number, block(0, number - 1));
+        }
+        chain
+    }
+
+    pub fn import(&self, branch: u64, number: u64, parent: u64) {
+        let mut blocks = self.blocks.lock();
+        let order = blocks.len();
+        blocks.insert(block(branch, number), (Header { number, parent }, order));
+    }
+
+    pub fn prune(&self, hash: u64) {
+        self.blocks.lock().remove(&hash);
+    }
+
+    pub fn has_block(&self, hash: &u64) -> bool {
+        self.blocks.lock().contains_key(hash)
+    }
+}
+
+impl ChainBackend<u64> for MockChain {
+    fn header(&self, hash: &u64) -> Option<Header<u64>> {
+        self.blocks.lock().get(hash).map(|(header, _)| *header)
+    }
+
+    fn best_block(&self) -> BlockId<u64> {
+        let blocks = self.blocks.lock();
+        let (hash, (header, _)) = blocks
+            .iter()
+            .max_by_key(|(_, (header, order))| (header.number, usize::MAX - order))
+            .expect("there is the genesis");
+        BlockId::new(*hash, header.number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        testing::{block, branch_of, MockChain},
+        AvailabilityConfig, AvailabilityOracle, BestBlockProvider, BlockHashData, BlockId,
+        FinalizationAdapter, LastFinalized,
+    };
+    use aleph_bft::{
+        create_config, run_session, DelayConfig, LocalIO, NodeCount, NodeIndex, SpawnHandle,
+        Terminator,
+    };
+    use aleph_bft_mock::{
+        Hasher64, Keychain, Loader, PartialMultisignature, Router, Saver, Signature, Spawner,
+    };
+    use futures::{
+        channel::{mpsc::unbounded, oneshot},
+        StreamExt,
+    };
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+    use tokio::time::{sleep, timeout};
+
+    type NetworkData =
+        aleph_bft::NetworkData<Hasher64, BlockHashData<u64>, Signature, PartialMultisignature>;
+
+    const N_MEMBERS: NodeCount = NodeCount(4);
+    const PUBLIC_HEIGHT: u64 = 3;
+    /// High enough to be reached only after the others give up on the orphaned block.
+    const FINAL_HEIGHT: u64 = 40;
+    const ORPHANED: u64 = 1;
+    const BLOCK_TIME: Duration = Duration::from_millis(100);
+    /// Long enough for the orphaned block to be pruned before anything proposing it is ordered.
+    const GIVE_UP_AFTER: Duration = Duration::from_secs(2);
+
+    fn delay_config() -> DelayConfig {
+        DelayConfig {
+            tick_interval: Duration::from_millis(5),
+            unit_rebroadcast_interval_min: Duration::from_millis(400),
+            unit_rebroadcast_interval_max: Duration::from_millis(500),
+            unit_creation_delay: Arc::new(|_| Duration::from_millis(50)),
+            coord_request_delay: Arc::new(|_| Duration::from_millis(100)),
+            coord_request_recipients: Arc::new(|t| if t == 0 { 3 } else { 1 }),
+            parent_request_delay: Arc::new(|_| Duration::from_millis(50)),
+            parent_request_recipients: Arc::new(|_| 1),
+            newest_request_delay: Arc::new(|_| Duration::from_millis(50)),
+            data_fetch_timeout: None,
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reorged_branch_is_never_finalized() {
+        let spawner = Spawner::new();
+        let (net_hub, networks) = Router::<NetworkData>::new(N_MEMBERS);
+        spawner.spawn("network-hub", net_hub);
+
+        let mut chains = Vec::new();
+        let mut finalized_rxs = Vec::new();
+        let mut exits = Vec::new();
+        let mut handles = Vec::new();
+        let orphan_checks = Arc::new(AtomicUsize::new(0));
+        for (network, _) in networks {
+            let node_ix = network.index();
+            let chain = MockChain::new(PUBLIC_HEIGHT);
+            if node_ix == NodeIndex(0) {
+                // Only we see this branch, before it gets orphaned.
+                chain.import(ORPHANED, PUBLIC_HEIGHT + 1, block(0, PUBLIC_HEIGHT));
+            }
+            let last_finalized = LastFinalized::new(BlockId::new(block(0, 0), 0));
+            let (finalized_tx, finalized_rx) = unbounded();
+            let oracle_chain = chain.clone();
+            let orphan_checks = orphan_checks.clone();
+            let oracle = AvailabilityOracle::new(
+                move |hash| {
+                    if branch_of(*hash) == ORPHANED {
+                        orphan_checks.fetch_add(1, Ordering::Relaxed);
+                    }
+                    oracle_chain.has_block(hash)
+                },
+                AvailabilityConfig {
+                    recheck_delay: Arc::new(|_| Duration::from_millis(20)),
+                    give_up_after: GIVE_UP_AFTER,
+                },
+            );
+            let local_io = LocalIO::new(
+                BestBlockProvider::new(chain.clone(), last_finalized.clone()),
+                FinalizationAdapter::new(chain.clone(), last_finalized, move |block| {
+                    let _ = finalized_tx.unbounded_send(block);
+                }),
+                Saver::new(),
+                Loader::new(vec![]),
+            );
+            let config = create_config(N_MEMBERS, node_ix, 0, 5000, delay_config(), Duration::ZERO)
+                .expect("the config is valid");
+            let (exit_tx, exit_rx) = oneshot::channel();
+            handles.push(spawner.spawn_essential("member", async move {
+                run_session(
+                    config,
+                    local_io,
+                    oracle.wrap(network),
+                    Keychain::new(N_MEMBERS, node_ix),
+                    spawner,
+                    Terminator::create_root(exit_rx, "AlephBFT-member"),
+                )
+                .await
+                .expect("the session should end cleanly")
+            }));
+            chains.push(chain);
+            finalized_rxs.push(finalized_rx);
+            exits.push(exit_tx);
+        }
+
+        // The orphaned block gets pruned long before the others stop waiting for it.
+        sleep(3 * BLOCK_TIME).await;
+        chains[0].prune(block(ORPHANED, PUBLIC_HEIGHT + 1));
+        let importer_chains = chains.clone();
+        spawner.spawn("importer", async move {
+            for number in PUBLIC_HEIGHT + 1.. {
+                for chain in &importer_chains {
+                    chain.import(0, number, block(0, number - 1));
+                }
+                sleep(BLOCK_TIME).await;
+            }
+        });
+
+        let mut finalized = Vec::new();
+        for finalized_rx in finalized_rxs {
+            let blocks: Vec<_> = timeout(
+                Duration::from_secs(60),
+                finalized_rx
+                    .take_while(|block| futures::future::ready(block.number < FINAL_HEIGHT))
+                    .collect(),
+            )
+            .await
+            .expect("the chain should get finalized");
+            finalized.push(blocks);
+        }
+        for exit_tx in exits {
+            let _ = exit_tx.send(());
+        }
+        for handle in handles {
+            let _ = handle.await;
+        }
+
+        assert!(
+            orphan_checks.load(Ordering::Relaxed) > 0,
+            "the orphaned block should be proposed"
+        );
+        for blocks in &finalized {
+            assert!(blocks.iter().all(|block| branch_of(block.hash) != ORPHANED));
+            assert!(blocks
+                .windows(2)
+                .all(|pair| pair[0].number < pair[1].number));
+            assert_eq!(blocks, &finalized[0]);
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use crate::ClockSource;
+use log::debug;
+use parking_lot::Mutex;
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+const LOG_TARGET: &str = "AlephBFT-admission";
+
+/// The upper bounds, in microseconds, of the histogram buckets. Durations longer than the last
+/// bound end up in an additional overflow bucket.
+pub const ADMISSION_LATENCY_BUCKETS: [u64; 21] = [
+    1,
+    2,
+    4,
+    8,
+    16,
+    32,
+    64,
+    128,
+    256,
+    512,
+    1 << 10,
+    2 << 10,
+    4 << 10,
+    8 << 10,
+    16 << 10,
+    32 << 10,
+    64 << 10,
+    128 << 10,
+    256 << 10,
+    512 << 10,
+    1 << 20,
+];
+
+/// The stages a unit broadcast by another node goes through before it is admitted to the dag.
+/// The units are decoded by the network before they reach the session, so decoding is not
+/// among them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum AdmissionStage {
+    /// Waiting between the network and the member.
+    NetworkQueue,
+    /// Waiting between the member and the runway.
+    RunwayQueue,
+    /// Checking the signature of the unit.
+    Verification,
+    /// Checking the session, round and control hash of the unit, and whether it is a fork.
+    Validation,
+    /// Adding the unit to the reconstruction of the dag.
+    Reconstruction,
+}
+
+impl AdmissionStage {
+    /// All the stages, in the order units go through them.
+    pub const ALL: [AdmissionStage; 5] = [
+        AdmissionStage::NetworkQueue,
+        AdmissionStage::RunwayQueue,
+        AdmissionStage::Verification,
+        AdmissionStage::Validation,
+        AdmissionStage::Reconstruction,
+    ];
+
+    fn position(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl Display for AdmissionStage {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let name = match self {
+            AdmissionStage::NetworkQueue => "network queue",
+            AdmissionStage::RunwayQueue => "runway queue",
+            AdmissionStage::Verification => "verification",
+            AdmissionStage::Validation => "validation",
+            AdmissionStage::Reconstruction => "reconstruction",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The time a single unit spent in every stage so far, carried along with the unit.
+pub(crate) struct AdmissionTrace {
+    clock: ClockSource,
+    started: Instant,
+    last: Instant,
+    stages: [Option<Duration>; AdmissionStage::ALL.len()],
+}
+
+impl AdmissionTrace {
+    fn start(clock: ClockSource) -> Self {
+        let now = clock.now();
+        AdmissionTrace {
+            clock,
+            started: now,
+            last: now,
+            stages: [None; AdmissionStage::ALL.len()],
+        }
+    }
+
+    /// Notes that the given stage ends now, it started when the previous one ended.
+    pub(crate) fn mark(&mut self, stage: AdmissionStage) {
+        let now = self.clock.now();
+        self.stages[stage.position()] = Some(now.saturating_duration_since(self.last));
+        self.last = now;
+    }
+
+    fn total(&self) -> Duration {
+        self.last.saturating_duration_since(self.started)
+    }
+}
+
+impl Display for AdmissionTrace {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "total {:?}", self.total())?;
+        for (stage, duration) in AdmissionStage::ALL.iter().zip(self.stages.iter()) {
+            if let Some(duration) = duration {
+                write!(f, ", {} {:?}", stage, duration)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Marks the end of the stage, if the unit is traced.
+pub(crate) fn mark_stage(trace: &mut Option<AdmissionTrace>, stage: AdmissionStage) {
+    if let Some(trace) = trace {
+        trace.mark(stage);
+    }
+}
+
+/// A histogram of durations with fixed, exponentially growing buckets.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AdmissionHistogram {
+    counts: [usize; ADMISSION_LATENCY_BUCKETS.len() + 1],
+    count: usize,
+    max: Duration,
+}
+
+impl AdmissionHistogram {
+    fn record(&mut self, duration: Duration) {
+        let micros = duration.as_micros();
+        let bucket = ADMISSION_LATENCY_BUCKETS.partition_point(|bound| u128::from(*bound) < micros);
+        self.counts[bucket] += 1;
+        self.count += 1;
+        self.max = self.max.max(duration);
+    }
+
+    /// The number of recorded durations.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The longest recorded duration.
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// The number of durations in every bucket, together with the upper bound of the bucket.
+    /// The bound of the overflow bucket is `None`.
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<Duration>, usize)> + '_ {
+        ADMISSION_LATENCY_BUCKETS
+            .iter()
+            .map(|bound| Some(Duration::from_micros(*bound)))
+            .chain(Some(None))
+            .zip(self.counts.iter().copied())
+    }
+
+    /// An upper estimate of the given quantile, i.e. the upper bound of the bucket containing it,
+    /// but never more than the longest recorded duration. `None` if nothing was recorded.
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((quantile * self.count as f64).ceil() as usize).clamp(1, self.count);
+        let mut seen = 0;
+        for (bound, count) in self.buckets() {
+            seen += count;
+            if seen >= rank {
+                return Some(bound.map_or(self.max, |bound| bound.min(self.max)));
+            }
+        }
+        Some(self.max)
+    }
+}
+
+impl Display for AdmissionHistogram {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let quantile = |quantile| self.quantile(quantile).unwrap_or_default();
+        write!(
+            f,
+            "p50 {:?}, p99 {:?}, max {:?}",
+            quantile(0.5),
+            quantile(0.99),
+            self.max
+        )
+    }
+}
+
+/// How long the units received from other nodes spent in every stage of admission.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AdmissionStats {
+    stages: [AdmissionHistogram; AdmissionStage::ALL.len()],
+    total: AdmissionHistogram,
+}
+
+impl AdmissionStats {
+    fn record(&mut self, trace: &AdmissionTrace) {
+        for (histogram, duration) in self.stages.iter_mut().zip(trace.stages.iter()) {
+            if let Some(duration) = duration {
+                histogram.record(*duration);
+            }
+        }
+        self.total.record(trace.total());
+    }
+
+    /// The time units spent in the given stage. Units rejected before reaching the stage are
+    /// not counted in it.
+    pub fn stage(&self, stage: AdmissionStage) -> &AdmissionHistogram {
+        &self.stages[stage.position()]
+    }
+
+    /// The time units spent in all the stages they reached together.
+    pub fn total(&self) -> &AdmissionHistogram {
+        &self.total
+    }
+}
+
+impl Display for AdmissionStats {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{} units, total - {}", self.total.count(), self.total)?;
+        for stage in AdmissionStage::ALL {
+            write!(f, "; {} - {}", stage, self.stage(stage))?;
+        }
+        Ok(())
+    }
+}
+
+struct SharedState {
+    stats: AdmissionStats,
+    slow_unit_threshold: Option<Duration>,
+}
+
+/// Allows the application to see where the units received from other nodes spend their time
+/// before they are admitted to the dag.
+#[derive(Clone)]
+pub struct AdmissionStatsHandle {
+    shared: Arc<Mutex<SharedState>>,
+}
+
+impl AdmissionStatsHandle {
+    /// The durations recorded so far.
+    pub fn stats(&self) -> AdmissionStats {
+        self.shared.lock().stats.clone()
+    }
+
+    /// Logs the full trace of every unit whose admission takes longer than the threshold, at
+    /// the debug level.
+    pub fn log_slower_than(&self, threshold: Duration) {
+        self.shared.lock().slow_unit_threshold = Some(threshold);
+    }
+}
+
+/// The part of the admission monitoring passed to the session, see [`admission_monitor`]. Units
+/// are not traced at all unless the session got a monitor created with the handle, so that
+/// tracing costs nothing but a branch per stage otherwise.
+#[derive(Clone, Default)]
+pub struct AdmissionMonitor {
+    shared: Option<Arc<Mutex<SharedState>>>,
+}
+
+impl AdmissionMonitor {
+    /// A new trace, if units are traced.
+    pub(crate) fn start_trace(&self, clock: &ClockSource) -> Option<AdmissionTrace> {
+        self.shared
+            .as_ref()
+            .map(|_| AdmissionTrace::start(clock.clone()))
+    }
+
+    pub(crate) fn record(&self, trace: Option<AdmissionTrace>) {
+        let (Some(shared), Some(trace)) = (&self.shared, trace) else {
+            return;
+        };
+        let mut shared = shared.lock();
+        shared.stats.record(&trace);
+        if let Some(threshold) = shared.slow_unit_threshold {
+            if trace.total() > threshold {
+                debug!(target: LOG_TARGET, "Slow unit admission: {}.", trace);
+            }
+        }
+    }
+
+    pub(crate) fn stats(&self) -> Option<AdmissionStats> {
+        self.shared
+            .as_ref()
+            .map(|shared| shared.lock().stats.clone())
+    }
+}
+
+/// Creates a handle for inspecting the admission of units together with the monitor that
+/// should be passed to the session with [`crate::LocalIO::with_admission_monitor`].
+pub fn admission_monitor() -> (AdmissionStatsHandle, AdmissionMonitor) {
+    let shared = Arc::new(Mutex::new(SharedState {
+        stats: AdmissionStats::default(),
+        slow_unit_threshold: None,
+    }));
+    (
+        AdmissionStatsHandle {
+            shared: shared.clone(),
+        },
+        AdmissionMonitor {
+            shared: Some(shared),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        admission::{admission_monitor, AdmissionMonitor, AdmissionStage, AdmissionTrace},
+        ClockSource,
+    };
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn records_every_stage_separately() {
+        let (handle, monitor) = admission_monitor();
+        let millis = Duration::from_millis;
+        let started = Instant::now();
+        monitor.record(Some(AdmissionTrace {
+            clock: ClockSource::default(),
+            started,
+            last: started + millis(11),
+            stages: [Some(millis(1)), None, Some(millis(10)), None, None],
+        }));
+
+        let stats = handle.stats();
+        assert_eq!(stats.total().count(), 1);
+        assert_eq!(stats.total().max(), millis(11));
+        assert_eq!(stats.stage(AdmissionStage::NetworkQueue).max(), millis(1));
+        assert_eq!(stats.stage(AdmissionStage::Verification).max(), millis(10));
+        assert_eq!(stats.stage(AdmissionStage::RunwayQueue).count(), 0);
+        assert_eq!(
+            stats.stage(AdmissionStage::Verification).quantile(0.5),
+            Some(millis(10))
+        );
+    }
+
+    #[test]
+    fn default_monitor_does_not_trace() {
+        let monitor = AdmissionMonitor::default();
+        assert!(monitor.start_trace(&ClockSource::default()).is_none());
+        assert!(monitor.stats().is_none());
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{NodeCount, NodeIndex};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
+
+/// How many completed RMCs are remembered. Messages about older ones are handled as usual again.
+pub(crate) const COMPLETED_RMCS_KEPT: usize = 1024;
+
+/// What to do with a message of an RMC, see [`CompletedRmcs::check`].
+#[derive(Debug, Eq, PartialEq)]
+pub enum CompletionCheck<M> {
+    /// The RMC is not known to be completed, so the message has to be handled as usual.
+    Pending,
+    /// The RMC is completed, so the message can be dropped.
+    Suppressed,
+    /// The RMC is completed, so the message can be dropped, but the sender should get the
+    /// given multisignature, as it is still collecting signatures.
+    Reply(M),
+}
+
+struct Completed<M> {
+    multisigned: M,
+    replied_to: HashSet<NodeIndex>,
+}
+
+/// The RMCs that completed recently, so that the messages peers keep sending about them until
+/// they see the completion are dropped before any other work. Optionally the sender of such a
+/// signature gets the multisignature, but every node at most once per RMC.
+pub struct CompletedRmcs<K, M> {
+    completed: HashMap<K, Completed<M>>,
+    order: VecDeque<K>,
+    capacity: usize,
+    n_members: NodeCount,
+    replies: bool,
+}
+
+impl<K: Hash + Eq + Clone, M: Clone> CompletedRmcs<K, M> {
+    pub fn new(capacity: usize, n_members: NodeCount, replies: bool) -> Self {
+        CompletedRmcs {
+            completed: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+            n_members,
+            replies,
+        }
+    }
+
+    /// Remembers the RMC of the given hash as completed with the given multisignature,
+    /// forgetting the oldest one if there are too many.
+    pub fn complete(&mut self, hash: K, multisigned: M) {
+        if self.completed.contains_key(&hash) {
+            return;
+        }
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.completed.remove(&oldest);
+            }
+        }
+        self.order.push_back(hash.clone());
+        self.completed.insert(
+            hash,
+            Completed {
+                multisigned,
+                replied_to: HashSet::new(),
+            },
+        );
+    }
+
+    /// Checks a message of the RMC of the given hash from the given sender, which is a single
+    /// signature unless it is complete.
+    pub fn check(&mut self, sender: NodeIndex, hash: &K, complete: bool) -> CompletionCheck<M> {
+        let Some(completed) = self.completed.get_mut(hash) else {
+            return CompletionCheck::Pending;
+        };
+        // A node sending the multisignature already has it, and the claimed sender is not
+        // authenticated, so we only ever answer each member of the committee once.
+        if !self.replies
+            || complete
+            || sender.0 >= self.n_members.0
+            || !completed.replied_to.insert(sender)
+        {
+            return CompletionCheck::Suppressed;
+        }
+        CompletionCheck::Reply(completed.multisigned.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        alerts::completed::{CompletedRmcs, CompletionCheck},
+        NodeCount, NodeIndex,
+    };
+
+    const N_MEMBERS: NodeCount = NodeCount(4);
+
+    #[test]
+    fn suppresses_messages_of_completed_rmcs_only() {
+        let mut completed = CompletedRmcs::new(10, N_MEMBERS, false);
+        assert_eq!(
+            completed.check(NodeIndex(1), &0, false),
+            CompletionCheck::Pending
+        );
+        completed.complete(0, "multisigned");
+        assert_eq!(
+            completed.check(NodeIndex(1), &0, false),
+            CompletionCheck::Suppressed
+        );
+        assert_eq!(
+            completed.check(NodeIndex(1), &1, false),
+            CompletionCheck::Pending
+        );
+    }
+
+    #[test]
+    fn replies_to_every_member_at_most_once() {
+        let mut completed = CompletedRmcs::new(10, N_MEMBERS, true);
+        completed.complete(0, "multisigned");
+        for sender in 0..10 {
+            let expected = match sender < N_MEMBERS.0 {
+                true => CompletionCheck::Reply("multisigned"),
+                false => CompletionCheck::Suppressed,
+            };
+            assert_eq!(completed.check(NodeIndex(sender), &0, false), expected);
+            assert_eq!(
+                completed.check(NodeIndex(sender), &0, false),
+                CompletionCheck::Suppressed
+            );
+        }
+        completed.complete(1, "other multisigned");
+        assert_eq!(
+            completed.check(NodeIndex(1), &1, true),
+            CompletionCheck::Suppressed
+        );
+        assert_eq!(
+            completed.check(NodeIndex(1), &1, false),
+            CompletionCheck::Reply("other multisigned")
+        );
+    }
+
+    #[test]
+    fn forgets_the_oldest_rmcs() {
+        let mut completed = CompletedRmcs::new(2, N_MEMBERS, true);
+        for hash in 0..3 {
+            completed.complete(hash, hash);
+        }
+        assert_eq!(
+            completed.check(NodeIndex(1), &0, false),
+            CompletionCheck::Pending
+        );
+        for hash in 1..3 {
+            assert_eq!(
+                completed.check(NodeIndex(1), &hash, false),
+                CompletionCheck::Reply(hash)
+            );
+        }
+        // Completing again after being forgotten starts over, with the replies.
+        completed.complete(0, 0);
+        assert_eq!(
+            completed.check(NodeIndex(1), &0, false),
+            CompletionCheck::Reply(0)
+        );
+        assert_eq!(
+            completed.check(NodeIndex(1), &1, false),
+            CompletionCheck::Pending
+        );
+    }
+}
@@ -1,8 +1,11 @@
 use crate::{
-    alerts::{Alert, AlertMessage, ForkProof, ForkingNotification},
-    units::Unit,
+    alerts::{
+        verify_fork_proof, Alert, AlertMessage, CompactAlert, ForkProof, ForkProofError,
+        ForkingNotification,
+    },
+    units::{SignatureVerifiedUnit, UncheckedSignedUnit, Unit},
     Data, Hasher, Keychain, MultiKeychain, Multisigned, NodeIndex, PartialMultisignature,
-    Recipient, SessionId, Signature, Signed, UncheckedSigned,
+    Recipient, SessionId, Signable, Signature, Signed, UncheckedSigned,
 };
 use aleph_bft_rmc::Message as RmcMessage;
 use aleph_bft_types::Round;
@@ -18,15 +21,19 @@ pub enum Error {
     IncorrectlySignedUnit(NodeIndex),
     SameRound(Round, NodeIndex),
     WrongCreator(NodeIndex),
+    TooManyUnits(usize, NodeIndex),
+    RoundTooHigh(Round, NodeIndex),
     // fork validity errors
     DifferentRounds(NodeIndex),
     SingleUnit(NodeIndex),
     WrongSession(NodeIndex),
+    RepeatedForker(NodeIndex, NodeIndex),
     // other errors
     IncorrectlySignedAlert,
     RepeatedAlert(NodeIndex, NodeIndex),
     UnknownAlertRequest,
     UnknownAlertRMC,
+    UnknownLegitUnits(NodeIndex),
 }
 
 impl Display for Error {
@@ -35,13 +42,17 @@ impl Display for Error {
             Error::IncorrectlySignedUnit(sender) => write!(f, "Incorrect commitment from {:?}: Some unit is incorrectly signed", sender),
             Error::SameRound(round, sender) => write!(f, "Incorrect commitment from {:?}: Two or more alerted units have the same round {:?}", sender, round),
             Error::WrongCreator(sender) => write!(f, "Incorrect commitment from {:?}: Some unit has a wrong creator", sender),
+            Error::TooManyUnits(count, sender) => write!(f, "Incorrect commitment from {:?}: {} units are more than the maximum", sender, count),
+            Error::RoundTooHigh(round, sender) => write!(f, "Incorrect commitment from {:?}: Some unit has round {:?}, beyond the last round of the session", sender, round),
             Error::DifferentRounds(sender) => write!(f, "Incorrect fork alert from {:?}: Forking units come from different rounds", sender),
             Error::SingleUnit(sender) => write!(f, "Incorrect fork alert from {:?}: Two copies of a single unit do not constitute a fork", sender),
             Error::WrongSession(sender) => write!(f, "Incorrect fork alert from {:?}: Wrong session", sender),
+            Error::RepeatedForker(forker, sender) => write!(f, "Incorrect fork alert from {:?}: More than one proof about {:?}", sender, forker),
             Error::IncorrectlySignedAlert => write!(f, "Received an incorrectly signed alert"),
             Error::RepeatedAlert(forker, sender) => write!(f, "We already know about an alert by {:?} about {:?}", sender, forker),
             Error::UnknownAlertRequest => write!(f, "Received a request for an unknown alert"),
             Error::UnknownAlertRMC => write!(f, "Completed an RMC for an unknown alert"),
+            Error::UnknownLegitUnits(sender) => write!(f, "Received units of an alert that is not waiting for them from {:?}", sender),
         }
     }
 }
@@ -56,15 +67,40 @@ pub type OnOwnAlertResponse<H, D, MK> = (
 );
 
 pub type OnNetworkAlertResponse<H, D, MK> = (
-    Option<ForkingNotification<H, D, <MK as Keychain>::Signature>>,
+    Vec<ForkingNotification<H, D, <MK as Keychain>::Signature>>,
     <H as Hasher>::Hash,
 );
 
-type OnAlertRequestResponse<H, D, MK> = (
-    UncheckedSigned<Alert<H, D, <MK as Keychain>::Signature>, <MK as Keychain>::Signature>,
+pub type OnNetworkCompactAlertResponse<H, D, MK> = (
+    Vec<ForkingNotification<H, D, <MK as Keychain>::Signature>>,
+    <H as Hasher>::Hash,
+    Option<AlertMessageFor<H, D, MK>>,
+);
+
+type AlertMessageFor<H, D, MK> = (
+    AlertMessage<H, D, <MK as Keychain>::Signature, <MK as MultiKeychain>::PartialMultisignature>,
     Recipient,
 );
 
+/// A compact alert whose legit units all arrived, together with the outcome of its confirmation,
+/// if it was already confirmed.
+pub type CompletedAlert<H, D, MK> = (
+    <H as Hasher>::Hash,
+    Option<Result<ForkingNotification<H, D, <MK as Keychain>::Signature>, Error>>,
+);
+
+/// The sender of an alert together with its proofs of the forks.
+type AlertProofs<'a, H, D, MK> = (
+    NodeIndex,
+    &'a [ForkProof<H, D, <MK as Keychain>::Signature>],
+);
+
+/// A compact alert we are fetching the legit units of.
+struct AwaitingUnits<H: Hasher, D: Data, MK: MultiKeychain> {
+    alert: Signed<CompactAlert<H, D, MK::Signature>, MK>,
+    confirmed: bool,
+}
+
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Decode, Encode)]
 pub enum RmcResponse<H: Hasher, S: Signature, MS: PartialMultisignature> {
     RmcMessage(RmcMessage<H::Hash, S, MS>),
@@ -82,6 +118,13 @@ pub struct Handler<H: Hasher, D: Data, MK: MultiKeychain> {
     known_forkers: HashMap<NodeIndex, ForkProof<H, D, MK::Signature>>,
     known_alerts: KnownAlerts<H, D, MK>,
     known_rmcs: HashMap<(NodeIndex, NodeIndex), H::Hash>,
+    max_legit_units: usize,
+    max_round: Round,
+    compact_alerts: bool,
+    aggregated_alerts: bool,
+    awaiting_units: HashMap<H::Hash, AwaitingUnits<H, D, MK>>,
+    // The units committed to by the alerts we hold in full, by their hashes.
+    legit_units: HashMap<H::Hash, UncheckedSignedUnit<H, D, MK::Signature>>,
 }
 
 impl<H: Hasher, D: Data, MK: MultiKeychain> Handler<H, D, MK> {
@@ -92,9 +135,56 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Handler<H, D, MK> {
             known_forkers: HashMap::new(),
             known_alerts: HashMap::new(),
             known_rmcs: HashMap::new(),
+            max_legit_units: usize::MAX,
+            max_round: Round::MAX,
+            compact_alerts: false,
+            aggregated_alerts: false,
+            awaiting_units: HashMap::new(),
+            legit_units: HashMap::new(),
         }
     }
 
+    /// Makes the handler reject alerts committing to more than the given number of units. An
+    /// honest alert commits to at most one unit of every round, so the maximal round of the
+    /// session bounds it.
+    pub fn with_max_legit_units(self, max_legit_units: usize) -> Self {
+        Handler {
+            max_legit_units,
+            ..self
+        }
+    }
+
+    /// Makes the handler reject alerts committing to units of rounds above the given one, which
+    /// nobody accepts in the session anyway.
+    pub fn with_max_round(self, max_round: Round) -> Self {
+        Handler { max_round, ..self }
+    }
+
+    /// Makes the handler sign our alerts with the hash of their compact form and send them as
+    /// [`CompactAlert`]s, both when raising them and when answering requests for them. Compact
+    /// alerts are understood regardless, and alerts signed in full are always sent in full.
+    pub fn with_compact_alerts(self, compact_alerts: bool) -> Self {
+        Handler {
+            compact_alerts,
+            ..self
+        }
+    }
+
+    /// Makes the handler combine our alerts raised at once into one, see [`Alert::aggregate`].
+    /// The releases predating aggregation cannot decode such alerts, so otherwise every alert is
+    /// about a single forker.
+    pub fn with_aggregated_alerts(self, aggregated_alerts: bool) -> Self {
+        Handler {
+            aggregated_alerts,
+            ..self
+        }
+    }
+
+    /// Whether our alerts raised at once should be combined into one.
+    pub fn aggregates_alerts(&self) -> bool {
+        self.aggregated_alerts
+    }
+
     fn is_forker(&self, forker: NodeIndex) -> bool {
         self.known_forkers.contains_key(&forker)
     }
@@ -104,93 +194,224 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Handler<H, D, MK> {
     }
 
     // Correctness rules:
-    // 1) All units must be created by forker
-    // 2) All units must come from different rounds
-    // 3) There must be fewer of them than the maximum defined in the configuration.
+    // 1) All units must be created by one of the forkers
+    // 2) All units of a forker must come from different rounds
+    // 3) All units must come from our session
+    // 4) There must be at most the maximum defined in the configuration for every forker of the
+    //    alert, and none of the units
+    //    can be above the last round of the session, which is checked as soon as the alert
+    //    arrives, see `check_commitment_bounds`.
+    // Note that the rounds are not bounded by the round of the fork, as we commit to all the units
+    // of the forker we hold, and some might be above it.
     // Note that these units will have to be validated before being used in the consensus.
     // This is alright, if someone uses their alert to commit to incorrect units it's their own
     // problem.
     fn verify_commitment(&self, alert: &Alert<H, D, MK::Signature>) -> Result<(), Error> {
+        let forkers = alert.forkers();
         let mut rounds = HashSet::new();
         for u in &alert.legit_units {
-            let u = match u.clone().check(&self.keychain) {
+            let u = match SignatureVerifiedUnit::verify(u.clone(), &self.keychain) {
                 Ok(u) => u,
                 Err(_) => return Err(Error::IncorrectlySignedUnit(alert.sender)),
             };
             let full_unit = u.as_signable();
-            if full_unit.creator() != alert.forker() {
+            if !forkers.contains(&full_unit.creator()) {
                 return Err(Error::WrongCreator(alert.sender));
             }
-            if rounds.contains(&full_unit.round()) {
+            if full_unit.session_id() != self.session_id {
+                return Err(Error::WrongSession(alert.sender));
+            }
+            if !rounds.insert((full_unit.creator(), full_unit.round())) {
                 return Err(Error::SameRound(full_unit.round(), alert.sender));
             }
-            rounds.insert(full_unit.round());
         }
         Ok(())
     }
 
+    /// The checks of the commitment that do not need any signatures, so that alerts failing them
+    /// are rejected before we take part in their RMC.
+    fn check_commitment_bounds(
+        &self,
+        sender: NodeIndex,
+        forkers: usize,
+        rounds: impl ExactSizeIterator<Item = Round>,
+    ) -> Result<(), Error> {
+        if rounds.len() > self.max_legit_units.saturating_mul(forkers) {
+            return Err(Error::TooManyUnits(rounds.len(), sender));
+        }
+        match rounds.max() {
+            Some(round) if round > self.max_round => Err(Error::RoundTooHigh(round, sender)),
+            _ => Ok(()),
+        }
+    }
+
     fn verify_fork(&self, alert: &Alert<H, D, MK::Signature>) -> Result<(), Error> {
-        let (u1, u2) = &alert.proof;
-        let (u1, u2) = {
-            let u1 = u1.clone().check(&self.keychain);
-            let u2 = u2.clone().check(&self.keychain);
-            match (u1, u2) {
-                (Ok(u1), Ok(u2)) => (u1, u2),
-                _ => return Err(Error::IncorrectlySignedUnit(alert.sender)),
+        self.verify_forks(alert.sender, alert.proofs())
+    }
+
+    /// Checks all the proofs of an alert, which have to be about distinct forkers.
+    fn verify_forks(
+        &self,
+        sender: NodeIndex,
+        proofs: &[ForkProof<H, D, MK::Signature>],
+    ) -> Result<(), Error> {
+        let mut forkers = HashSet::new();
+        for proof in proofs {
+            self.verify_fork_proof(sender, proof)?;
+            let forker = proof.0.as_signable().creator();
+            if !forkers.insert(forker) {
+                return Err(Error::RepeatedForker(forker, sender));
             }
-        };
-        let full_unit1 = u1.as_signable();
-        let full_unit2 = u2.as_signable();
-        if full_unit1.session_id() != self.session_id || full_unit2.session_id() != self.session_id
-        {
-            return Err(Error::WrongSession(alert.sender));
         }
-        if full_unit1 == full_unit2 {
-            return Err(Error::SingleUnit(alert.sender));
-        }
-        if full_unit1.creator() != full_unit2.creator() {
-            return Err(Error::WrongCreator(alert.sender));
+        Ok(())
+    }
+
+    fn verify_fork_proof(
+        &self,
+        sender: NodeIndex,
+        proof: &ForkProof<H, D, MK::Signature>,
+    ) -> Result<(), Error> {
+        match verify_fork_proof(proof, &self.keychain, self.session_id) {
+            Ok(_) => Ok(()),
+            Err(ForkProofError::BadSignature) => Err(Error::IncorrectlySignedUnit(sender)),
+            Err(ForkProofError::WrongSession) => Err(Error::WrongSession(sender)),
+            Err(ForkProofError::SameUnit) => Err(Error::SingleUnit(sender)),
+            Err(ForkProofError::DifferentCreators) => Err(Error::WrongCreator(sender)),
+            Err(ForkProofError::DifferentRounds) => Err(Error::DifferentRounds(sender)),
         }
-        if full_unit1.round() != full_unit2.round() {
-            return Err(Error::DifferentRounds(alert.sender));
+    }
+
+    /// Remembers the alert we now hold in full, together with the units it commits to.
+    fn add_known_alert(&mut self, hash: H::Hash, alert: Signed<Alert<H, D, MK::Signature>, MK>) {
+        for unit in &alert.as_signable().legit_units {
+            self.legit_units
+                .entry(Signable::hash(unit.as_signable()))
+                .or_insert_with(|| unit.clone());
         }
-        Ok(())
+        self.known_alerts.insert(hash, alert);
     }
 
     /// Registers the RMC but does not actually send it; the returned hash must be passed to `start_rmc()` separately
-    fn rmc_alert(
-        &mut self,
-        forker: NodeIndex,
-        alert: Signed<Alert<H, D, MK::Signature>, MK>,
-    ) -> H::Hash {
+    fn rmc_alert(&mut self, alert: Signed<Alert<H, D, MK::Signature>, MK>) -> H::Hash {
         let hash = alert.as_signable().hash();
-        self.known_rmcs
-            .insert((alert.as_signable().sender, forker), hash);
-        self.known_alerts.insert(hash, alert);
+        self.register_rmc(
+            alert.as_signable().sender,
+            &alert.as_signable().forkers(),
+            hash,
+        );
+        self.add_known_alert(hash, alert);
         hash
     }
 
-    /// Registers RMCs and messages but does not actually send them; make sure the returned values are forwarded to IO
+    /// Remembers that the RMC of the given hash is the one about all the forkers by the sender.
+    fn register_rmc(&mut self, sender: NodeIndex, forkers: &[NodeIndex], hash: H::Hash) {
+        for forker in forkers {
+            self.known_rmcs.insert((sender, *forker), hash);
+        }
+    }
+
+    /// Whether some RMC by the sender is about any of the forkers already.
+    fn known_rmc_about(&self, sender: NodeIndex, forkers: &[NodeIndex]) -> Option<NodeIndex> {
+        forkers
+            .iter()
+            .find(|forker| self.known_rmcs.contains_key(&(sender, **forker)))
+            .copied()
+    }
+
+    /// The sender and the forkers of the alert, if we know it at least in the compact form.
+    fn alert_id(&self, hash: &H::Hash) -> Option<(NodeIndex, Vec<NodeIndex>)> {
+        if let Some(alert) = self.known_alerts.get(hash) {
+            return Some((alert.as_signable().sender, alert.as_signable().forkers()));
+        }
+        self.awaiting_units.get(hash).map(|awaiting| {
+            (
+                awaiting.alert.as_signable().sender,
+                awaiting.alert.as_signable().forkers(),
+            )
+        })
+    }
+
+    /// Records the forkers of the proofs, returning notifications about the ones that are new
+    /// to us.
+    fn on_fork_proofs(
+        &mut self,
+        proofs: &[ForkProof<H, D, MK::Signature>],
+    ) -> Vec<ForkingNotification<H, D, MK::Signature>> {
+        let mut notifications = Vec::new();
+        for proof in proofs {
+            let forker = proof.0.as_signable().creator();
+            if !self.is_forker(forker) {
+                // We learn about this forker for the first time, need to send our own alert
+                self.on_new_forker_detected(forker, proof.clone());
+                notifications.push(ForkingNotification::Forker(proof.clone()));
+            }
+        }
+        notifications
+    }
+
+    fn alert_message(
+        &self,
+        alert: Signed<Alert<H, D, MK::Signature>, MK>,
+    ) -> AlertMessage<H, D, MK::Signature, MK::PartialMultisignature> {
+        match alert.as_signable().has_compact_hash() {
+            true => {
+                AlertMessage::CompactForkAlert(alert.into_unchecked().map_signable(Alert::compact))
+            }
+            false => AlertMessage::ForkAlert(alert.into_unchecked()),
+        }
+    }
+
+    fn missing_units(&self, alert: &CompactAlert<H, D, MK::Signature>) -> Vec<H::Hash> {
+        alert
+            .commitment()
+            .iter()
+            .map(|(_, hash)| *hash)
+            .filter(|hash| !self.legit_units.contains_key(hash))
+            .collect()
+    }
+
+    /// Registers RMCs and messages but does not actually send them; make sure the returned values are forwarded to IO.
+    /// If signing fails, the forker is still considered known, and the alert should be retried.
     pub fn on_own_alert(
         &mut self,
         alert: Alert<H, D, MK::Signature>,
-    ) -> OnOwnAlertResponse<H, D, MK> {
-        let forker = alert.forker();
-        self.known_forkers.insert(forker, alert.proof.clone());
-        let alert = Signed::sign(alert, &self.keychain);
-        let hash = self.rmc_alert(forker, alert.clone());
-        (
-            AlertMessage::ForkAlert(alert.into_unchecked()),
-            Recipient::Everyone,
-            hash,
-        )
+    ) -> Result<OnOwnAlertResponse<H, D, MK>, MK::SignError> {
+        for proof in alert.proofs() {
+            self.known_forkers
+                .insert(proof.0.as_signable().creator(), proof.clone());
+        }
+        let alert = match self.compact_alerts {
+            true => alert.with_compact_hash(),
+            false => alert,
+        };
+        let alert = Signed::sign(alert, &self.keychain)?;
+        let hash = self.rmc_alert(alert.clone());
+        Ok((self.alert_message(alert), Recipient::Everyone, hash))
     }
 
-    /// May return a `ForkingNotification`, which should be propagated
+    /// Records the forkers of our alert that is held back for now, so that alerts about them
+    /// coming from the network are not treated as news.
+    pub fn on_own_alert_queued(&mut self, alert: &Alert<H, D, MK::Signature>) {
+        for proof in alert.proofs() {
+            self.on_new_forker_detected(proof.0.as_signable().creator(), proof.clone());
+        }
+    }
+
+    /// May return `ForkingNotification`s about the forkers we did not know of, which should be
+    /// propagated
     pub fn on_network_alert(
         &mut self,
         alert: UncheckedSigned<Alert<H, D, MK::Signature>, MK::Signature>,
     ) -> Result<OnNetworkAlertResponse<H, D, MK>, Error> {
+        let contents = alert.as_signable();
+        self.check_commitment_bounds(
+            contents.sender,
+            contents.proofs().len(),
+            contents
+                .legit_units
+                .iter()
+                .map(|unit| unit.as_signable().round()),
+        )?;
         let alert = match alert.check(&self.keychain) {
             Ok(alert) => alert,
             Err(_) => {
@@ -198,26 +419,181 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Handler<H, D, MK> {
             }
         };
         let contents = alert.as_signable();
+        let sender = contents.sender;
         self.verify_fork(contents)?;
-        let forker = contents.forker();
-        let sender = alert.as_signable().sender;
-        if self.known_rmcs.contains_key(&(contents.sender, forker)) {
-            self.known_alerts.insert(contents.hash(), alert);
+        if let Some(forker) = self.known_rmc_about(sender, &contents.forkers()) {
+            self.add_known_alert(contents.hash(), alert);
             return Err(Error::RepeatedAlert(sender, forker));
         }
-        let maybe_notification = if self.is_forker(forker) {
-            None
-        } else {
-            // We learn about this forker for the first time, need to send our own alert
-            self.on_new_forker_detected(forker, contents.proof.clone());
-            Some(ForkingNotification::Forker(contents.proof.clone()))
-        };
-        let hash_for_rmc = self.rmc_alert(forker, alert);
+        let notifications = self.on_fork_proofs(contents.proofs());
+        let hash_for_rmc = self.rmc_alert(alert);
 
         // A response to a valid fork alert.
         // It should be handled by starting RMC on the contained hash
-        // and sending the contained notification (if present) to runway.
-        Ok((maybe_notification, hash_for_rmc))
+        // and sending the contained notifications to runway.
+        Ok((notifications, hash_for_rmc))
+    }
+
+    /// Like [`Self::on_network_alert`], but the legit units might be missing. Their hashes are then
+    /// returned in a request for them, and the RMC should only be started once they all arrived,
+    /// see [`Self::take_completed_alerts`].
+    pub fn on_network_compact_alert(
+        &mut self,
+        alert: UncheckedSigned<CompactAlert<H, D, MK::Signature>, MK::Signature>,
+    ) -> Result<OnNetworkCompactAlertResponse<H, D, MK>, Error> {
+        let contents = alert.as_signable();
+        self.check_commitment_bounds(
+            contents.sender,
+            contents.proofs().len(),
+            contents.commitment().iter().map(|(round, _)| *round),
+        )?;
+        let alert = match alert.check(&self.keychain) {
+            Ok(alert) => alert,
+            Err(_) => {
+                return Err(Error::IncorrectlySignedAlert);
+            }
+        };
+        let contents = alert.as_signable();
+        let sender = contents.sender;
+        self.verify_forks(sender, contents.proofs())?;
+        let forkers = contents.forkers();
+        let hash = contents.hash();
+        if let Some(forker) = self.known_rmc_about(sender, &forkers) {
+            return Err(Error::RepeatedAlert(sender, forker));
+        }
+        let notifications = self.on_fork_proofs(contents.proofs());
+        self.register_rmc(sender, &forkers, hash);
+        self.awaiting_units.insert(
+            hash,
+            AwaitingUnits {
+                alert,
+                confirmed: false,
+            },
+        );
+        let request = self.legit_units_request(&hash);
+        if request.is_none() {
+            // We already hold all the units, so the alert is complete right away.
+            self.complete_alert(hash);
+        }
+        Ok((notifications, hash, request))
+    }
+
+    /// A request for the units of the compact alert we are still missing. Before the alert is
+    /// confirmed only its sender has to hold them, afterwards at least one honest node does.
+    pub fn legit_units_request(&self, hash: &H::Hash) -> Option<AlertMessageFor<H, D, MK>> {
+        let awaiting = self.awaiting_units.get(hash)?;
+        let missing = self.missing_units(awaiting.alert.as_signable());
+        if missing.is_empty() {
+            return None;
+        }
+        let recipient = match awaiting.confirmed {
+            true => Recipient::Everyone,
+            false => Recipient::Node(awaiting.alert.as_signable().sender),
+        };
+        Some((
+            AlertMessage::LegitUnitsRequest(self.keychain.index(), *hash, missing),
+            recipient,
+        ))
+    }
+
+    /// Requests for the units of all the compact alerts that are still missing some.
+    pub fn legit_units_requests(&self) -> Vec<AlertMessageFor<H, D, MK>> {
+        self.awaiting_units
+            .keys()
+            .filter_map(|hash| self.legit_units_request(hash))
+            .collect()
+    }
+
+    pub fn is_awaiting_units(&self) -> bool {
+        !self.awaiting_units.is_empty()
+    }
+
+    /// Answers with the requested units we hold, if any.
+    pub fn on_legit_units_request(
+        &self,
+        node: NodeIndex,
+        hash: H::Hash,
+        units: Vec<H::Hash>,
+    ) -> Result<AlertMessageFor<H, D, MK>, Error> {
+        let units: Vec<_> = units
+            .iter()
+            .take(self.max_legit_units)
+            .filter_map(|unit| self.legit_units.get(unit).cloned())
+            .collect();
+        if units.is_empty() {
+            return Err(Error::UnknownAlertRequest);
+        }
+        Ok((
+            AlertMessage::LegitUnits(self.keychain.index(), hash, units),
+            Recipient::Node(node),
+        ))
+    }
+
+    /// Keeps the correctly signed units the compact alert of the given hash is missing, the
+    /// alerts that became complete are returned by [`Self::take_completed_alerts`].
+    pub fn on_legit_units(
+        &mut self,
+        sender: NodeIndex,
+        hash: H::Hash,
+        units: &[UncheckedSignedUnit<H, D, MK::Signature>],
+    ) -> Result<(), Error> {
+        let missing: HashSet<_> = match self.awaiting_units.get(&hash) {
+            Some(awaiting) => self
+                .missing_units(awaiting.alert.as_signable())
+                .into_iter()
+                .collect(),
+            None => return Err(Error::UnknownLegitUnits(sender)),
+        };
+        for unit in units {
+            let unit_hash = Signable::hash(unit.as_signable());
+            if !missing.contains(&unit_hash) || self.legit_units.contains_key(&unit_hash) {
+                continue;
+            }
+            if SignatureVerifiedUnit::verify(unit.clone(), &self.keychain).is_err() {
+                return Err(Error::IncorrectlySignedUnit(sender));
+            }
+            self.legit_units.insert(unit_hash, unit.clone());
+        }
+        Ok(())
+    }
+
+    /// Turns the compact alert into a full one, once we hold all its units.
+    fn complete_alert(&mut self, hash: H::Hash) -> Option<CompletedAlert<H, D, MK>> {
+        let awaiting = self.awaiting_units.get(&hash)?;
+        let units: Option<Vec<_>> = awaiting
+            .alert
+            .as_signable()
+            .commitment()
+            .iter()
+            .map(|(_, unit)| self.legit_units.get(unit).cloned())
+            .collect();
+        let units = units?;
+        let AwaitingUnits { alert, confirmed } = self.awaiting_units.remove(&hash)?;
+        // The units match the commitment, so the full alert has the same hash and signature.
+        let alert = match alert
+            .into_unchecked()
+            .map_signable(|alert| alert.complete(units))
+            .check(&self.keychain)
+        {
+            Ok(alert) => alert,
+            Err(_) => return Some((hash, Some(Err(Error::IncorrectlySignedAlert)))),
+        };
+        self.add_known_alert(hash, alert);
+        let confirmation = match confirmed {
+            true => Some(self.confirmed_units(&hash)),
+            false => None,
+        };
+        Some((hash, confirmation))
+    }
+
+    /// The compact alerts that got all their units, which should now take part in RMCs and, if
+    /// they were already confirmed, have their units passed on.
+    pub fn take_completed_alerts(&mut self) -> Vec<CompletedAlert<H, D, MK>> {
+        let hashes: Vec<_> = self.awaiting_units.keys().cloned().collect();
+        hashes
+            .into_iter()
+            .filter_map(|hash| self.complete_alert(hash))
+            .collect()
     }
 
     // returns AlerterResponse::{AlertRequest, RmcMessage} or None (no error, can't fail)
@@ -227,9 +603,11 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Handler<H, D, MK> {
         message: RmcMessage<H::Hash, MK::Signature, MK::PartialMultisignature>,
     ) -> RmcResponse<H, MK::Signature, MK::PartialMultisignature> {
         let hash = message.hash();
-        if let Some(alert) = self.known_alerts.get(hash) {
-            let alert_id = (alert.as_signable().sender, alert.as_signable().forker());
-            if self.known_rmcs.get(&alert_id) == Some(hash) || message.is_complete() {
+        if let Some((alert_sender, forkers)) = self.alert_id(hash) {
+            let registered = forkers
+                .iter()
+                .all(|forker| self.known_rmcs.get(&(alert_sender, *forker)) == Some(hash));
+            if registered || message.is_complete() {
                 // An internal RMC message, with the sender being the local node.
                 // It should be handled by sending the message.
                 RmcResponse::RmcMessage(message)
@@ -248,28 +626,61 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Handler<H, D, MK> {
         &self,
         node: NodeIndex,
         hash: H::Hash,
-    ) -> Result<OnAlertRequestResponse<H, D, MK>, Error> {
-        match self.known_alerts.get(&hash) {
-            Some(alert) => {
-                // A copy of a fork alert.
-                // It should be handled by sending the contained `Alert` via the network to the contained recipient.
-                Ok((alert.clone().into_unchecked(), Recipient::Node(node)))
-            }
+    ) -> Result<AlertMessageFor<H, D, MK>, Error> {
+        if let Some(alert) = self.known_alerts.get(&hash) {
+            // A copy of a fork alert.
+            // It should be handled by sending the contained `Alert` via the network to the contained recipient.
+            return Ok((self.alert_message(alert.clone()), Recipient::Node(node)));
+        }
+        match self.awaiting_units.get(&hash) {
+            // The requester fetches the units like we do.
+            Some(awaiting) => Ok((
+                AlertMessage::CompactForkAlert(awaiting.alert.clone().into_unchecked()),
+                Recipient::Node(node),
+            )),
             None => Err(Error::UnknownAlertRequest),
         }
     }
 
-    /// May return a `ForkingNotification`, which should be propagated
+    /// May return a `ForkingNotification`, which should be propagated. Returns nothing for a
+    /// compact alert still missing units, its notification is returned by
+    /// [`Self::take_completed_alerts`] once they arrive.
     pub fn alert_confirmed(
         &mut self,
         multisigned: Multisigned<H::Hash, MK>,
+    ) -> Result<Option<ForkingNotification<H, D, MK::Signature>>, Error> {
+        let hash = *multisigned.as_signable();
+        if let Some(awaiting) = self.awaiting_units.get_mut(&hash) {
+            awaiting.confirmed = true;
+            return Ok(None);
+        }
+        self.confirmed_units(&hash).map(Some)
+    }
+
+    /// The sender and the fork proofs of the known alert with the given hash, whether we hold it
+    /// in full or are still fetching its units.
+    pub fn alert_proofs(&self, hash: &H::Hash) -> Option<AlertProofs<H, D, MK>> {
+        if let Some(alert) = self.known_alerts.get(hash) {
+            let alert = alert.as_signable();
+            return Some((alert.sender, alert.proofs()));
+        }
+        self.awaiting_units.get(hash).map(|awaiting| {
+            let alert = awaiting.alert.as_signable();
+            (alert.sender, alert.proofs())
+        })
+    }
+
+    fn confirmed_units(
+        &mut self,
+        hash: &H::Hash,
     ) -> Result<ForkingNotification<H, D, MK::Signature>, Error> {
-        let alert = match self.known_alerts.get(multisigned.as_signable()) {
+        let alert = match self.known_alerts.get(hash) {
             Some(alert) => alert.as_signable(),
             None => return Err(Error::UnknownAlertRMC),
         };
-        let forker = alert.proof.0.as_signable().creator();
-        self.known_rmcs.insert((alert.sender, forker), alert.hash());
+        for forker in alert.forkers() {
+            self.known_rmcs.insert((alert.sender, forker), alert.hash());
+        }
         self.verify_commitment(alert)?;
         Ok(ForkingNotification::Units(alert.legit_units.clone()))
     }
@@ -277,18 +688,18 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Handler<H, D, MK> {
 
 #[cfg(test)]
 mod tests {
-    use crate::units::ControlHash;
     use crate::{
         alerts::{
             handler::{Error, Handler, RmcResponse},
-            Alert, AlertMessage, ForkProof, ForkingNotification,
+            Alert, AlertMessage, CompactAlert, ForkProof, ForkingNotification,
         },
-        units::{FullUnit, PreUnit},
-        PartiallyMultisigned, Recipient, Round,
+        units::{ControlHash, FullUnit, PreUnit, UncheckedSignedUnit},
+        Hasher, PartiallyMultisigned, Recipient, Round,
     };
     use aleph_bft_mock::{Data, Hasher64, Keychain, Signature};
     use aleph_bft_rmc::Message;
     use aleph_bft_types::{NodeCount, NodeIndex, NodeMap, Signable, Signed};
+    use codec::{Decode, Encode};
 
     type TestForkProof = ForkProof<Hasher64, Data, Signature>;
 
@@ -318,8 +729,12 @@ mod tests {
     ) -> TestForkProof {
         let unit_0 = full_unit(n_members, node_id, round, Some(0));
         let unit_1 = full_unit(n_members, node_id, round, Some(1));
-        let signed_unit_0 = Signed::sign(unit_0, keychain).into_unchecked();
-        let signed_unit_1 = Signed::sign(unit_1, keychain).into_unchecked();
+        let signed_unit_0 = Signed::sign(unit_0, keychain)
+            .expect("the keychain never fails")
+            .into_unchecked();
+        let signed_unit_1 = Signed::sign(unit_1, keychain)
+            .expect("the keychain never fails")
+            .into_unchecked();
         (signed_unit_0, signed_unit_1)
     }
 
@@ -333,10 +748,12 @@ mod tests {
         let mut this = Handler::new(own_keychain, 0);
         let fork_proof = make_fork_proof(forker_index, &forker_keychain, 0, n_members);
         let alert = Alert::new(own_index, fork_proof, vec![]);
-        let signed_alert = Signed::sign(alert.clone(), &this.keychain).into_unchecked();
+        let signed_alert = Signed::sign(alert.clone(), &this.keychain)
+            .expect("the keychain never fails")
+            .into_unchecked();
         let alert_hash = Signable::hash(&alert);
         assert_eq!(
-            this.on_own_alert(alert),
+            this.on_own_alert(alert).expect("the keychain never fails"),
             (
                 AlertMessage::ForkAlert(signed_alert),
                 Recipient::Everyone,
@@ -345,6 +762,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn sends_alerts_in_the_form_they_are_signed_in() {
+        let n_members = NodeCount(7);
+        let own_index = NodeIndex(0);
+        let alerter_index = NodeIndex(1);
+        let forkers = [NodeIndex(5), NodeIndex(6)];
+        let keychains: Vec<_> = (0..n_members.0)
+            .map(|i| Keychain::new(n_members, NodeIndex(i)))
+            .collect();
+        let mut this = Handler::new(keychains[own_index.0].clone(), 0).with_compact_alerts(true);
+        let fork_proof = make_fork_proof(forkers[0], &keychains[forkers[0].0], 0, n_members);
+        let alert = Alert::new(own_index, fork_proof, vec![]).with_compact_hash();
+        let signed_alert = Signed::sign(alert.clone(), &this.keychain)
+            .expect("the keychain never fails")
+            .into_unchecked();
+        let alert_hash = Signable::hash(&alert);
+        assert_eq!(
+            this.on_own_alert(Alert::new(own_index, alert.proofs()[0].clone(), vec![]))
+                .expect("the keychain never fails"),
+            (
+                AlertMessage::CompactForkAlert(signed_alert.map_signable(Alert::compact)),
+                Recipient::Everyone,
+                alert_hash,
+            ),
+        );
+
+        // Signed in full by its sender, so only sendable in full.
+        let fork_proof = make_fork_proof(forkers[1], &keychains[forkers[1].0], 0, n_members);
+        let alert = Alert::new(alerter_index, fork_proof, vec![]);
+        let alert_hash = Signable::hash(&alert);
+        let signed_alert = Signed::sign(alert, &keychains[alerter_index.0])
+            .expect("the keychain never fails")
+            .into_unchecked();
+        assert!(this.on_network_alert(signed_alert.clone()).is_ok());
+        assert_eq!(
+            this.on_alert_request(alerter_index, alert_hash),
+            Ok((
+                AlertMessage::ForkAlert(signed_alert),
+                Recipient::Node(alerter_index),
+            )),
+        );
+    }
+
     #[test]
     fn reacts_to_correctly_incoming_alert() {
         let n_members = NodeCount(7);
@@ -356,10 +816,12 @@ mod tests {
         let fork_proof = make_fork_proof(forker_index, &forker_keychain, 0, n_members);
         let alert = Alert::new(own_index, fork_proof.clone(), vec![]);
         let alert_hash = Signable::hash(&alert);
-        let signed_alert = Signed::sign(alert, &this.keychain).into_unchecked();
+        let signed_alert = Signed::sign(alert, &this.keychain)
+            .expect("the keychain never fails")
+            .into_unchecked();
         assert_eq!(
             this.on_network_alert(signed_alert),
-            Ok((Some(ForkingNotification::Forker(fork_proof)), alert_hash)),
+            Ok((vec![ForkingNotification::Forker(fork_proof)], alert_hash)),
         );
     }
 
@@ -376,8 +838,9 @@ mod tests {
         let fork_proof = make_fork_proof(forker_index, &forker_keychain, 0, n_members);
         let alert = Alert::new(alerter_index, fork_proof, vec![]);
         let alert_hash = Signable::hash(&alert);
-        let signed_alert_hash =
-            Signed::sign_with_index(alert_hash, &alerter_keychain).into_unchecked();
+        let signed_alert_hash = Signed::sign_with_index(alert_hash, &alerter_keychain)
+            .expect("the keychain never fails")
+            .into_unchecked();
         let response = this.on_rmc_message(alerter_index, Message::SignedHash(signed_alert_hash));
         assert_eq!(
             response,
@@ -392,21 +855,152 @@ mod tests {
         let forker_index = NodeIndex(6);
         let own_keychain = Keychain::new(n_members, own_index);
         let forker_keychain = Keychain::new(n_members, forker_index);
-        let mut this = Handler::new(own_keychain, 0);
+        let mut this = Handler::new(own_keychain.clone(), 0);
         let valid_unit = Signed::sign(
             full_unit(n_members, forker_index, 0, Some(0)),
             &forker_keychain,
         )
+        .expect("the keychain never fails")
         .into_unchecked();
         let wrong_fork_proof = (valid_unit.clone(), valid_unit);
         let wrong_alert = Alert::new(own_index, wrong_fork_proof, vec![]);
-        let signed_wrong_alert = Signed::sign(wrong_alert, &own_keychain).into_unchecked();
+        let signed_wrong_alert = Signed::sign(wrong_alert, &own_keychain)
+            .expect("the keychain never fails")
+            .into_unchecked();
         assert_eq!(
             this.on_network_alert(signed_wrong_alert),
             Err(Error::SingleUnit(own_index)),
         );
     }
 
+    #[test]
+    fn ignores_alert_committing_to_too_many_units() {
+        let n_members = NodeCount(7);
+        let own_index = NodeIndex(0);
+        let forker_index = NodeIndex(6);
+        let own_keychain = Keychain::new(n_members, own_index);
+        let forker_keychain = Keychain::new(n_members, forker_index);
+        let legit_units: Vec<_> = (1..4)
+            .map(|round| {
+                Signed::sign(
+                    full_unit(n_members, forker_index, round, Some(0)),
+                    &forker_keychain,
+                )
+                .expect("the keychain never fails")
+                .into_unchecked()
+            })
+            .collect();
+        let alert = Alert::new(
+            own_index,
+            make_fork_proof(forker_index, &forker_keychain, 0, n_members),
+            legit_units,
+        );
+        let signed_alert = Signed::sign(alert, &own_keychain)
+            .expect("the keychain never fails")
+            .into_unchecked();
+        let mut this: Handler<Hasher64, Data, _> =
+            Handler::new(own_keychain.clone(), 0).with_max_legit_units(2);
+        assert_eq!(
+            this.on_network_alert(signed_alert.clone()),
+            Err(Error::TooManyUnits(3, own_index)),
+        );
+        let mut this = Handler::new(own_keychain, 0).with_max_legit_units(3);
+        assert!(this.on_network_alert(signed_alert).is_ok());
+    }
+
+    #[test]
+    fn ignores_alert_committing_to_units_beyond_the_session() {
+        let n_members = NodeCount(7);
+        let own_index = NodeIndex(0);
+        let forker_index = NodeIndex(6);
+        let own_keychain = Keychain::new(n_members, own_index);
+        let forker_keychain = Keychain::new(n_members, forker_index);
+        let legit_units: Vec<_> = (1..4)
+            .map(|round| {
+                Signed::sign(
+                    full_unit(n_members, forker_index, round, Some(0)),
+                    &forker_keychain,
+                )
+                .expect("the keychain never fails")
+                .into_unchecked()
+            })
+            .collect();
+        let alert = Alert::new(
+            own_index,
+            make_fork_proof(forker_index, &forker_keychain, 0, n_members),
+            legit_units,
+        );
+        let signed_alert = Signed::sign(alert.clone(), &own_keychain)
+            .expect("the keychain never fails")
+            .into_unchecked();
+        let compact_alert = Signed::sign(alert.with_compact_hash(), &own_keychain)
+            .expect("the keychain never fails")
+            .into_unchecked()
+            .map_signable(Alert::compact);
+        let mut this: Handler<Hasher64, Data, _> =
+            Handler::new(own_keychain.clone(), 0).with_max_round(2);
+        assert_eq!(
+            this.on_network_alert(signed_alert.clone()),
+            Err(Error::RoundTooHigh(3, own_index)),
+        );
+        assert_eq!(
+            this.on_network_compact_alert(compact_alert),
+            Err(Error::RoundTooHigh(3, own_index)),
+        );
+        let mut this = Handler::new(own_keychain, 0).with_max_round(3);
+        assert!(this.on_network_alert(signed_alert).is_ok());
+    }
+
+    #[test]
+    fn rejects_commitment_to_units_of_another_session() {
+        let n_members = NodeCount(7);
+        let own_index = NodeIndex(0);
+        let forker_index = NodeIndex(6);
+        let keychains: Vec<_> = (0..n_members.0)
+            .map(|i| Keychain::new(n_members, NodeIndex(i)))
+            .collect();
+        let mut this = Handler::new(keychains[own_index.0].clone(), 0);
+        let other_session_unit = FullUnit::new(
+            PreUnit::new(
+                forker_index,
+                1,
+                ControlHash::new(&NodeMap::with_size(n_members)),
+            ),
+            Some(0),
+            1,
+        );
+        let legit_unit = Signed::sign(other_session_unit, &keychains[forker_index.0])
+            .expect("the keychain never fails")
+            .into_unchecked();
+        let alert = Alert::new(
+            own_index,
+            make_fork_proof(forker_index, &keychains[forker_index.0], 2, n_members),
+            vec![legit_unit],
+        );
+        let alert_hash = Signable::hash(&alert);
+        let signed_alert = Signed::sign(alert, &keychains[own_index.0])
+            .expect("the keychain never fails")
+            .into_unchecked();
+        assert!(this.on_network_alert(signed_alert).is_ok());
+        let mut multisigned_alert_hash = Signed::sign_with_index(alert_hash, &keychains[0])
+            .expect("the keychain never fails")
+            .into_partially_multisigned(&keychains[0]);
+        for keychain in &keychains[1..n_members.0 - 2] {
+            let signed_alert_hash =
+                Signed::sign_with_index(alert_hash, keychain).expect("the keychain never fails");
+            multisigned_alert_hash =
+                multisigned_alert_hash.add_signature(signed_alert_hash, &keychains[0]);
+        }
+        let multisigned_alert_hash = match multisigned_alert_hash {
+            PartiallyMultisigned::Complete { multisigned } => multisigned,
+            PartiallyMultisigned::Incomplete { .. } => unreachable!(),
+        };
+        assert_eq!(
+            this.alert_confirmed(multisigned_alert_hash),
+            Err(Error::WrongSession(own_index)),
+        );
+    }
+
     #[test]
     fn responds_to_alert_queries() {
         let n_members = NodeCount(7);
@@ -414,20 +1008,25 @@ mod tests {
         let forker_index = NodeIndex(6);
         let own_keychain = Keychain::new(n_members, own_index);
         let forker_keychain = Keychain::new(n_members, forker_index);
-        let mut this = Handler::new(own_keychain, 0);
+        let mut this = Handler::new(own_keychain.clone(), 0);
         let alert = Alert::new(
             own_index,
             make_fork_proof(forker_index, &forker_keychain, 0, n_members),
             vec![],
         );
         let alert_hash = Signable::hash(&alert);
-        let signed_alert = Signed::sign(alert, &own_keychain).into_unchecked();
+        let signed_alert = Signed::sign(alert, &own_keychain)
+            .expect("the keychain never fails")
+            .into_unchecked();
         this.on_network_alert(signed_alert.clone()).unwrap();
         for i in 1..n_members.0 {
             let node_id = NodeIndex(i);
             assert_eq!(
                 this.on_alert_request(node_id, alert_hash),
-                Ok((signed_alert.clone(), Recipient::Node(node_id),)),
+                Ok((
+                    AlertMessage::ForkAlert(signed_alert.clone()),
+                    Recipient::Node(node_id),
+                )),
             );
         }
     }
@@ -442,14 +1041,16 @@ mod tests {
         let keychains: Vec<_> = (0..n_members.0)
             .map(|i| Keychain::new(n_members, NodeIndex(i)))
             .collect();
-        let mut this = Handler::new(keychains[own_index.0], 0);
+        let mut this = Handler::new(keychains[own_index.0].clone(), 0);
         let fork_proof = make_fork_proof(forker_index, &keychains[forker_index.0], 0, n_members);
         let empty_alert = Alert::new(double_committer, fork_proof.clone(), vec![]);
         let empty_alert_hash = Signable::hash(&empty_alert);
-        let signed_empty_alert =
-            Signed::sign(empty_alert, &keychains[double_committer.0]).into_unchecked();
+        let signed_empty_alert = Signed::sign(empty_alert, &keychains[double_committer.0])
+            .expect("the keychain never fails")
+            .into_unchecked();
         let signed_empty_alert_hash =
             Signed::sign_with_index(empty_alert_hash, &keychains[double_committer.0])
+                .expect("the keychain never fails")
                 .into_unchecked();
         let multisigned_empty_alert_hash = signed_empty_alert_hash
             .check(&keychains[double_committer.0])
@@ -458,7 +1059,7 @@ mod tests {
         assert_eq!(
             this.on_network_alert(signed_empty_alert),
             Ok((
-                Some(ForkingNotification::Forker(fork_proof.clone())),
+                vec![ForkingNotification::Forker(fork_proof.clone())],
                 empty_alert_hash,
             )),
         );
@@ -470,10 +1071,12 @@ mod tests {
         let forker_unit = fork_proof.0.clone();
         let nonempty_alert = Alert::new(double_committer, fork_proof, vec![forker_unit]);
         let nonempty_alert_hash = Signable::hash(&nonempty_alert);
-        let signed_nonempty_alert =
-            Signed::sign(nonempty_alert, &keychains[double_committer.0]).into_unchecked();
+        let signed_nonempty_alert = Signed::sign(nonempty_alert, &keychains[double_committer.0])
+            .expect("the keychain never fails")
+            .into_unchecked();
         let signed_nonempty_alert_hash =
             Signed::sign_with_index(nonempty_alert_hash, &keychains[double_committer.0])
+                .expect("the keychain never fails")
                 .into_unchecked();
         let mut multisigned_nonempty_alert_hash = signed_nonempty_alert_hash
             .check(&keychains[double_committer.0])
@@ -483,6 +1086,7 @@ mod tests {
             let node_id = NodeIndex(i);
             let signed_nonempty_alert_hash =
                 Signed::sign_with_index(nonempty_alert_hash, &keychains[node_id.0])
+                    .expect("the keychain never fails")
                     .into_unchecked();
             multisigned_nonempty_alert_hash = multisigned_nonempty_alert_hash.add_signature(
                 signed_nonempty_alert_hash
@@ -512,26 +1116,29 @@ mod tests {
         let keychains: Vec<_> = (0..n_members.0)
             .map(|i| Keychain::new(n_members, NodeIndex(i)))
             .collect();
-        let mut this = Handler::new(keychains[own_index.0], 0);
+        let mut this = Handler::new(keychains[own_index.0].clone(), 0);
         let fork_proof = make_fork_proof(forker_index, &keychains[forker_index.0], 0, n_members);
         let empty_alert = Alert::new(double_committer, fork_proof.clone(), vec![]);
         let empty_alert_hash = Signable::hash(&empty_alert);
-        let signed_empty_alert =
-            Signed::sign(empty_alert, &keychains[double_committer.0]).into_unchecked();
+        let signed_empty_alert = Signed::sign(empty_alert, &keychains[double_committer.0])
+            .expect("the keychain never fails")
+            .into_unchecked();
         assert_eq!(
             this.on_network_alert(signed_empty_alert),
             Ok((
-                Some(ForkingNotification::Forker(fork_proof.clone())),
+                vec![ForkingNotification::Forker(fork_proof.clone())],
                 empty_alert_hash,
             )),
         );
         let forker_unit = fork_proof.0.clone();
         let nonempty_alert = Alert::new(double_committer, fork_proof, vec![forker_unit]);
         let nonempty_alert_hash = Signable::hash(&nonempty_alert);
-        let signed_nonempty_alert =
-            Signed::sign(nonempty_alert, &keychains[double_committer.0]).into_unchecked();
+        let signed_nonempty_alert = Signed::sign(nonempty_alert, &keychains[double_committer.0])
+            .expect("the keychain never fails")
+            .into_unchecked();
         let signed_nonempty_alert_hash =
             Signed::sign_with_index(nonempty_alert_hash, &keychains[double_committer.0])
+                .expect("the keychain never fails")
                 .into_unchecked();
         let mut multisigned_nonempty_alert_hash = signed_nonempty_alert_hash
             .check(&keychains[double_committer.0])
@@ -541,6 +1148,7 @@ mod tests {
             let node_id = NodeIndex(i);
             let signed_nonempty_alert_hash =
                 Signed::sign_with_index(nonempty_alert_hash, &keychains[node_id.0])
+                    .expect("the keychain never fails")
                     .into_unchecked();
             multisigned_nonempty_alert_hash = multisigned_nonempty_alert_hash.add_signature(
                 signed_nonempty_alert_hash
@@ -595,12 +1203,16 @@ mod tests {
         let keychains: Vec<_> = (0..n_members.0)
             .map(|i| Keychain::new(n_members, NodeIndex(i)))
             .collect();
-        let this = Handler::new(keychains[0], 0);
+        let this = Handler::new(keychains[0].clone(), 0);
         let fork_proof = {
             let unit_0 = full_unit(n_members, NodeIndex(6), 0, Some(0));
             let unit_1 = full_unit(n_members, NodeIndex(5), 0, Some(0));
-            let signed_unit_0 = Signed::sign(unit_0, &keychains[6]).into_unchecked();
-            let signed_unit_1 = Signed::sign(unit_1, &keychains[5]).into_unchecked();
+            let signed_unit_0 = Signed::sign(unit_0, &keychains[6])
+                .expect("the keychain never fails")
+                .into_unchecked();
+            let signed_unit_1 = Signed::sign(unit_1, &keychains[5])
+                .expect("the keychain never fails")
+                .into_unchecked();
             (signed_unit_0, signed_unit_1)
         };
         let sender = NodeIndex(0);
@@ -619,8 +1231,12 @@ mod tests {
         let fork_proof = {
             let unit_0 = full_unit(n_members, forker_index, 0, Some(0));
             let unit_1 = full_unit(n_members, forker_index, 1, Some(0));
-            let signed_unit_0 = Signed::sign(unit_0, &forker_keychain).into_unchecked();
-            let signed_unit_1 = Signed::sign(unit_1, &forker_keychain).into_unchecked();
+            let signed_unit_0 = Signed::sign(unit_0, &forker_keychain)
+                .expect("the keychain never fails")
+                .into_unchecked();
+            let signed_unit_1 = Signed::sign(unit_1, &forker_keychain)
+                .expect("the keychain never fails")
+                .into_unchecked();
             (signed_unit_0, signed_unit_1)
         };
         let alert = Alert::new(own_index, fork_proof, vec![]);
@@ -652,32 +1268,40 @@ mod tests {
         let keychains: Vec<_> = (0..n_members.0)
             .map(|i| Keychain::new(n_members, NodeIndex(i)))
             .collect();
-        let mut this = Handler::new(keychains[own_index.0], 0);
+        let mut this = Handler::new(keychains[own_index.0].clone(), 0);
         let fork_proof = if good_commitment {
             make_fork_proof(forker_index, &keychains[forker_index.0], 0, n_members)
         } else {
             let unit_0 = full_unit(n_members, forker_index, 0, Some(0));
             let unit_1 = full_unit(n_members, forker_index, 1, Some(1));
-            let signed_unit_0 = Signed::sign(unit_0, &keychains[forker_index.0]).into_unchecked();
-            let signed_unit_1 = Signed::sign(unit_1, &keychains[forker_index.0]).into_unchecked();
+            let signed_unit_0 = Signed::sign(unit_0, &keychains[forker_index.0])
+                .expect("the keychain never fails")
+                .into_unchecked();
+            let signed_unit_1 = Signed::sign(unit_1, &keychains[forker_index.0])
+                .expect("the keychain never fails")
+                .into_unchecked();
             (signed_unit_0, signed_unit_1)
         };
         let alert = Alert::new(own_index, fork_proof, vec![]);
         let alert_hash = Signable::hash(&alert);
-        let signed_alert = Signed::sign(alert, &keychains[own_index.0]).into_unchecked();
+        let signed_alert = Signed::sign(alert, &keychains[own_index.0])
+            .expect("the keychain never fails")
+            .into_unchecked();
         if make_known {
             let _ = this.on_network_alert(signed_alert);
         }
-        let signed_alert_hash =
-            Signed::sign_with_index(alert_hash, &keychains[own_index.0]).into_unchecked();
+        let signed_alert_hash = Signed::sign_with_index(alert_hash, &keychains[own_index.0])
+            .expect("the keychain never fails")
+            .into_unchecked();
         let mut multisigned_alert_hash = signed_alert_hash
             .check(&keychains[forker_index.0])
             .expect("the signature is correct")
             .into_partially_multisigned(&keychains[own_index.0]);
         for i in 1..n_members.0 - 1 {
             let node_id = NodeIndex(i);
-            let signed_alert_hash =
-                Signed::sign_with_index(alert_hash, &keychains[node_id.0]).into_unchecked();
+            let signed_alert_hash = Signed::sign_with_index(alert_hash, &keychains[node_id.0])
+                .expect("the keychain never fails")
+                .into_unchecked();
             multisigned_alert_hash = multisigned_alert_hash.add_signature(
                 signed_alert_hash
                     .check(&keychains[forker_index.0])
@@ -691,11 +1315,275 @@ mod tests {
             PartiallyMultisigned::Incomplete { .. } => unreachable!(),
         };
         let expected = match (make_known, good_commitment) {
-            (true, true) => Ok(ForkingNotification::Units(vec![])),
+            (true, true) => Ok(Some(ForkingNotification::Units(vec![]))),
             (true, false) => Err(Error::UnknownAlertRMC),
             (false, true) => Err(Error::UnknownAlertRMC),
             (false, false) => Err(Error::UnknownAlertRMC),
         };
         assert_eq!(this.alert_confirmed(multisigned_alert_hash), expected);
     }
+
+    #[test]
+    fn completes_compact_alert_with_fetched_units() {
+        let n_members = NodeCount(7);
+        let own_index = NodeIndex(0);
+        let alerter_index = NodeIndex(1);
+        let other_honest_node = NodeIndex(2);
+        let forker_index = NodeIndex(6);
+        let keychains: Vec<_> = (0..n_members.0)
+            .map(|i| Keychain::new(n_members, NodeIndex(i)))
+            .collect();
+        let mut this = Handler::new(keychains[own_index.0].clone(), 0);
+        let fork_proof = make_fork_proof(forker_index, &keychains[forker_index.0], 1, n_members);
+        let legit_unit = Signed::sign(
+            full_unit(n_members, forker_index, 0, Some(0)),
+            &keychains[forker_index.0],
+        )
+        .expect("the keychain never fails")
+        .into_unchecked();
+        let legit_unit_hash = Signable::hash(legit_unit.as_signable());
+        let alert = Alert::new(alerter_index, fork_proof.clone(), vec![legit_unit.clone()])
+            .with_compact_hash();
+        let alert_hash = Signable::hash(&alert);
+        let signed_alert = Signed::sign(alert, &keychains[alerter_index.0])
+            .expect("the keychain never fails")
+            .into_unchecked();
+        let compact_alert = signed_alert.clone().map_signable(Alert::compact);
+        assert_eq!(
+            this.on_network_compact_alert(compact_alert),
+            Ok((
+                vec![ForkingNotification::Forker(fork_proof)],
+                alert_hash,
+                Some((
+                    AlertMessage::LegitUnitsRequest(own_index, alert_hash, vec![legit_unit_hash]),
+                    Recipient::Node(alerter_index),
+                )),
+            )),
+        );
+
+        // Confirmed before the units arrived, so anyone might have them now.
+        let mut multisigned_alert_hash = Signed::sign_with_index(alert_hash, &keychains[0])
+            .expect("the keychain never fails")
+            .into_partially_multisigned(&keychains[0]);
+        for keychain in &keychains[1..n_members.0 - 2] {
+            let signed_alert_hash =
+                Signed::sign_with_index(alert_hash, keychain).expect("the keychain never fails");
+            multisigned_alert_hash =
+                multisigned_alert_hash.add_signature(signed_alert_hash, &keychains[0]);
+        }
+        let multisigned_alert_hash = match multisigned_alert_hash {
+            PartiallyMultisigned::Complete { multisigned } => multisigned,
+            PartiallyMultisigned::Incomplete { .. } => unreachable!(),
+        };
+        assert_eq!(this.alert_confirmed(multisigned_alert_hash), Ok(None));
+        assert_eq!(
+            this.legit_units_request(&alert_hash),
+            Some((
+                AlertMessage::LegitUnitsRequest(own_index, alert_hash, vec![legit_unit_hash]),
+                Recipient::Everyone,
+            )),
+        );
+        assert!(this.take_completed_alerts().is_empty());
+
+        assert_eq!(
+            this.on_legit_units(other_honest_node, alert_hash, &[legit_unit.clone()]),
+            Ok(()),
+        );
+        assert_eq!(
+            this.take_completed_alerts(),
+            vec![(
+                alert_hash,
+                Some(Ok(ForkingNotification::Units(vec![legit_unit]))),
+            )],
+        );
+        assert!(!this.is_awaiting_units());
+        assert_eq!(
+            this.on_alert_request(other_honest_node, alert_hash),
+            Ok((
+                AlertMessage::CompactForkAlert(signed_alert.map_signable(Alert::compact)),
+                Recipient::Node(other_honest_node),
+            )),
+        );
+    }
+
+    fn legit_unit(
+        n_members: NodeCount,
+        creator: NodeIndex,
+        keychain: &Keychain,
+        round: Round,
+    ) -> UncheckedSignedUnit<Hasher64, Data, Signature> {
+        Signed::sign(full_unit(n_members, creator, round, Some(0)), keychain)
+            .expect("the keychain never fails")
+            .into_unchecked()
+    }
+
+    #[test]
+    fn reacts_to_aggregated_alert_with_one_rmc() {
+        let n_members = NodeCount(7);
+        let own_index = NodeIndex(0);
+        let alerter_index = NodeIndex(1);
+        let forkers = [NodeIndex(5), NodeIndex(6)];
+        let keychains: Vec<_> = (0..n_members.0)
+            .map(|i| Keychain::new(n_members, NodeIndex(i)))
+            .collect();
+        let fork_proofs: Vec<_> = forkers
+            .iter()
+            .map(|forker| make_fork_proof(*forker, &keychains[forker.0], 1, n_members))
+            .collect();
+        // Both forkers have a unit of the same round, which is fine as they are different nodes.
+        let legit_units: Vec<_> = forkers
+            .iter()
+            .map(|forker| legit_unit(n_members, *forker, &keychains[forker.0], 0))
+            .collect();
+        let alert = Alert::aggregated(alerter_index, fork_proofs.clone(), legit_units);
+        let alert_hash = Signable::hash(&alert);
+        let signed_alert = Signed::sign(alert.clone(), &keychains[alerter_index.0])
+            .expect("the keychain never fails")
+            .into_unchecked();
+        // One unit per forker is within the bound.
+        let mut this = Handler::new(keychains[own_index.0].clone(), 0).with_max_legit_units(1);
+        assert_eq!(
+            this.on_network_alert(signed_alert),
+            Ok((
+                fork_proofs
+                    .into_iter()
+                    .map(ForkingNotification::Forker)
+                    .collect(),
+                alert_hash,
+            )),
+        );
+        assert_eq!(this.verify_commitment(&alert), Ok(()));
+        let signed_alert_hash = Signed::sign_with_index(alert_hash, &keychains[alerter_index.0])
+            .expect("the keychain never fails")
+            .into_unchecked();
+        let message = Message::SignedHash(signed_alert_hash);
+        assert_eq!(
+            this.on_rmc_message(alerter_index, message.clone()),
+            RmcResponse::RmcMessage(message),
+        );
+
+        // Another alert of the same sender about one of the forkers is a repeated one.
+        let repeated_alert = Alert::new(
+            alerter_index,
+            make_fork_proof(forkers[1], &keychains[forkers[1].0], 2, n_members),
+            vec![],
+        );
+        let signed_repeated_alert = Signed::sign(repeated_alert, &keychains[alerter_index.0])
+            .expect("the keychain never fails")
+            .into_unchecked();
+        assert_eq!(
+            this.on_network_alert(signed_repeated_alert),
+            Err(Error::RepeatedAlert(alerter_index, forkers[1])),
+        );
+    }
+
+    #[test]
+    fn rejects_aggregated_alert_with_repeated_forker() {
+        let n_members = NodeCount(7);
+        let own_index = NodeIndex(0);
+        let alerter_index = NodeIndex(1);
+        let forker_index = NodeIndex(6);
+        let keychains: Vec<_> = (0..n_members.0)
+            .map(|i| Keychain::new(n_members, NodeIndex(i)))
+            .collect();
+        let fork_proofs = (0..2)
+            .map(|round| {
+                make_fork_proof(forker_index, &keychains[forker_index.0], round, n_members)
+            })
+            .collect();
+        let alert = Alert::aggregated(alerter_index, fork_proofs, vec![]);
+        let signed_alert = Signed::sign(alert, &keychains[alerter_index.0])
+            .expect("the keychain never fails")
+            .into_unchecked();
+        let mut this: Handler<Hasher64, Data, _> = Handler::new(keychains[own_index.0].clone(), 0);
+        assert_eq!(
+            this.on_network_alert(signed_alert),
+            Err(Error::RepeatedForker(forker_index, alerter_index)),
+        );
+    }
+
+    #[test]
+    fn rejects_aggregated_commitment_to_units_of_other_nodes() {
+        let n_members = NodeCount(7);
+        let own_index = NodeIndex(0);
+        let alerter_index = NodeIndex(1);
+        let forkers = [NodeIndex(5), NodeIndex(6)];
+        let keychains: Vec<_> = (0..n_members.0)
+            .map(|i| Keychain::new(n_members, NodeIndex(i)))
+            .collect();
+        let fork_proofs = forkers
+            .iter()
+            .map(|forker| make_fork_proof(*forker, &keychains[forker.0], 1, n_members))
+            .collect();
+        let honest_index = NodeIndex(4);
+        let legit_units = vec![legit_unit(
+            n_members,
+            honest_index,
+            &keychains[honest_index.0],
+            0,
+        )];
+        let alert = Alert::aggregated(alerter_index, fork_proofs, legit_units);
+        let this: Handler<Hasher64, Data, _> = Handler::new(keychains[own_index.0].clone(), 0);
+        assert_eq!(
+            this.verify_commitment(&alert),
+            Err(Error::WrongCreator(alerter_index)),
+        );
+    }
+
+    #[test]
+    fn encodes_single_proof_alerts_like_before_aggregation() {
+        let n_members = NodeCount(7);
+        let alerter_index = NodeIndex(1);
+        let forkers = [NodeIndex(5), NodeIndex(6)];
+        let keychains: Vec<_> = (0..n_members.0)
+            .map(|i| Keychain::new(n_members, NodeIndex(i)))
+            .collect();
+        let fork_proofs: Vec<_> = forkers
+            .iter()
+            .map(|forker| make_fork_proof(*forker, &keychains[forker.0], 1, n_members))
+            .collect();
+        let legit_units = vec![legit_unit(
+            n_members,
+            forkers[0],
+            &keychains[forkers[0].0],
+            0,
+        )];
+
+        let alert = Alert::new(alerter_index, fork_proofs[0].clone(), legit_units.clone());
+        assert_eq!(
+            alert.encode(),
+            (alerter_index, &fork_proofs[0], &legit_units).encode(),
+        );
+        assert_eq!(Signable::hash(&alert), Hasher64::hash(&alert.encode()));
+        assert_ne!(
+            Signable::hash(&alert),
+            Signable::hash(&alert.clone().compact()),
+        );
+        let alert = alert.with_compact_hash();
+        assert_eq!(
+            Signable::hash(&alert),
+            Signable::hash(&alert.clone().compact()),
+        );
+
+        let aggregated = Alert::aggregated(alerter_index, fork_proofs, legit_units);
+        let decoded = Alert::decode(&mut &aggregated.encode()[..]).expect("encoding is correct");
+        assert_eq!(decoded, aggregated);
+        assert_eq!(Signable::hash(&decoded), Signable::hash(&aggregated));
+        assert_eq!(decoded.forkers(), forkers.to_vec());
+        let compact = aggregated.compact();
+        let decoded =
+            CompactAlert::decode(&mut &compact.encode()[..]).expect("encoding is correct");
+        assert_eq!(decoded, compact);
+    }
+
+    #[test]
+    fn rejects_aggregated_encoding_with_a_single_proof() {
+        let n_members = NodeCount(7);
+        let forker_index = NodeIndex(6);
+        let forker_keychain = Keychain::new(n_members, forker_index);
+        let fork_proof = make_fork_proof(forker_index, &forker_keychain, 1, n_members);
+        let no_units: Vec<UncheckedSignedUnit<Hasher64, Data, Signature>> = Vec::new();
+        let encoded = (u64::MAX, NodeIndex(1), vec![fork_proof], no_units).encode();
+        assert!(Alert::<Hasher64, Data, Signature>::decode(&mut &encoded[..]).is_err());
+    }
 }
@@ -1,32 +1,97 @@
 use crate::{
+    decoding::decode_bounded_vec,
     units::{UncheckedSignedUnit, Unit},
     Data, Hasher, Index, Keychain, MultiKeychain, Multisigned, NodeIndex, PartialMultisignature,
-    Signable, Signature, UncheckedSigned,
+    Round, Signable, Signature, UncheckedSigned,
 };
 use aleph_bft_rmc::Message as RmcMessage;
-use codec::{Decode, Encode};
+use codec::{Decode, Encode, Error as CodecError, Input, Output};
 use derivative::Derivative;
 use parking_lot::RwLock;
 use std::ops::Deref;
 
+mod completed;
 mod handler;
+mod proof;
+mod replay;
 mod service;
+mod throttle;
 
 pub use handler::Handler;
+pub use proof::{decode_fork_proof, verify_fork_proof, ForkProofError};
+pub use replay::{replay_alerts, ReplayedAlert};
 pub use service::{Service, IO};
 
 pub type ForkProof<H, D, S> = (UncheckedSignedUnit<H, D, S>, UncheckedSignedUnit<H, D, S>);
 
+/// A unit of the forker an alert commits to, identified by its round and hash.
+pub type UnitCommitment<H> = (Round, <H as Hasher>::Hash);
+
 pub type NetworkMessage<H, D, MK> =
     AlertMessage<H, D, <MK as Keychain>::Signature, <MK as MultiKeychain>::PartialMultisignature>;
 
-#[derive(Debug, Decode, Derivative, Encode)]
+/// Takes the place of the sender in the encoding of an alert with more than one fork proof, no
+/// node index is encoded like that. Alerts with a single proof are encoded like before they could
+/// carry more, so that nodes running older versions still understand them.
+const AGGREGATED: u64 = u64::MAX;
+
+fn encode_alert<H: Hasher, D: Data, S: Signature, U: Encode, T: Output + ?Sized>(
+    sender: NodeIndex,
+    proofs: &[ForkProof<H, D, S>],
+    legit_units: &[U],
+    dest: &mut T,
+) {
+    match proofs {
+        [proof] => {
+            sender.encode_to(dest);
+            proof.encode_to(dest);
+        }
+        proofs => {
+            AGGREGATED.encode_to(dest);
+            sender.encode_to(dest);
+            proofs.encode_to(dest);
+        }
+    }
+    legit_units.encode_to(dest);
+}
+
+type DecodedAlert<H, D, S, U> = (NodeIndex, Vec<ForkProof<H, D, S>>, Vec<U>);
+
+fn decode_alert<H: Hasher, D: Data, S: Signature, U: Decode, I: Input>(
+    input: &mut I,
+) -> Result<DecodedAlert<H, D, S, U>, CodecError> {
+    let (sender, proofs) = match u64::decode(input)? {
+        AGGREGATED => {
+            let sender = NodeIndex::decode(input)?;
+            let proofs = decode_bounded_vec(input)?;
+            if proofs.len() < 2 {
+                return Err("an aggregated alert carries at least two fork proofs".into());
+            }
+            (sender, proofs)
+        }
+        sender => (NodeIndex(sender as usize), vec![ForkProof::decode(input)?]),
+    };
+    Ok((sender, proofs, decode_bounded_vec(input)?))
+}
+
+fn forkers<H: Hasher, D: Data, S: Signature>(proofs: &[ForkProof<H, D, S>]) -> Vec<NodeIndex> {
+    proofs
+        .iter()
+        .map(|(unit, _)| unit.as_signable().creator())
+        .collect()
+}
+
+/// An alert about one or more forkers, each with a proof of their fork, committing to the units
+/// of the forkers its sender holds.
+#[derive(Debug, Derivative)]
 #[derivative(Eq, PartialEq, Hash)]
 pub struct Alert<H: Hasher, D: Data, S: Signature> {
     sender: NodeIndex,
-    proof: ForkProof<H, D, S>,
+    proofs: Vec<ForkProof<H, D, S>>,
     legit_units: Vec<UncheckedSignedUnit<H, D, S>>,
-    #[codec(skip)]
+    // Whether the alert is signed in its compact form, see `Alert::with_compact_hash`. Not
+    // encoded, as the message carrying the alert tells.
+    compact_hash: bool,
     #[derivative(PartialEq = "ignore")]
     #[derivative(Hash = "ignore")]
     hash: RwLock<Option<H::Hash>>,
@@ -40,50 +105,147 @@ impl<H: Hasher, D: Data, S: Signature> Clone for Alert<H, D, S> {
         };
         Alert {
             sender: self.sender,
-            proof: self.proof.clone(),
+            proofs: self.proofs.clone(),
             legit_units: self.legit_units.clone(),
+            compact_hash: self.compact_hash,
             hash: RwLock::new(hash),
         }
     }
 }
 
+impl<H: Hasher, D: Data, S: Signature> Encode for Alert<H, D, S> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        encode_alert(self.sender, &self.proofs, &self.legit_units, dest)
+    }
+}
+
+impl<H: Hasher, D: Data, S: Signature> Decode for Alert<H, D, S> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        let (sender, proofs, legit_units) = decode_alert(input)?;
+        Ok(Alert::aggregated(sender, proofs, legit_units))
+    }
+}
+
 impl<H: Hasher, D: Data, S: Signature> Alert<H, D, S> {
     pub fn new(
         sender: NodeIndex,
         proof: ForkProof<H, D, S>,
         legit_units: Vec<UncheckedSignedUnit<H, D, S>>,
+    ) -> Alert<H, D, S> {
+        Alert::aggregated(sender, vec![proof], legit_units)
+    }
+
+    /// An alert about all the forkers of the proofs at once, which should be distinct. The legit
+    /// units are those of all the forkers.
+    pub fn aggregated(
+        sender: NodeIndex,
+        proofs: Vec<ForkProof<H, D, S>>,
+        legit_units: Vec<UncheckedSignedUnit<H, D, S>>,
     ) -> Alert<H, D, S> {
         Alert {
             sender,
-            proof,
+            proofs,
             legit_units,
+            compact_hash: false,
+            hash: RwLock::new(None),
+        }
+    }
+
+    /// The alert hashed like its compact form, i.e. committing to the legit units by their
+    /// hashes only, so that a signature of either is valid for both. Otherwise the hash of an
+    /// alert is the hash of its encoding, like before compact alerts existed, and it is only
+    /// sent in full.
+    pub fn with_compact_hash(self) -> Self {
+        Alert {
+            compact_hash: true,
             hash: RwLock::new(None),
+            ..self
         }
     }
 
+    /// Whether the alert is hashed like its compact form, see [`Self::with_compact_hash`].
+    pub fn has_compact_hash(&self) -> bool {
+        self.compact_hash
+    }
+
+    /// Combines alerts of the same sender into one, so that they take part in a single RMC. An
+    /// alert about a forker that an earlier one is about already is left out.
+    pub fn aggregate(alerts: Vec<Alert<H, D, S>>) -> Option<Alert<H, D, S>> {
+        let mut alerts = alerts.into_iter();
+        let Alert {
+            sender,
+            mut proofs,
+            mut legit_units,
+            ..
+        } = alerts.next()?;
+        for alert in alerts {
+            let known = forkers(&proofs);
+            if alert.sender != sender || alert.forkers().iter().any(|f| known.contains(f)) {
+                continue;
+            }
+            proofs.extend(alert.proofs);
+            legit_units.extend(alert.legit_units);
+        }
+        Some(Alert::aggregated(sender, proofs, legit_units))
+    }
+
     fn hash(&self) -> H::Hash {
         let hash = *self.hash.read();
         match hash {
             Some(hash) => hash,
             None => {
-                let hash = self.using_encoded(H::hash);
+                let hash = match self.compact_hash {
+                    true => alert_hash(self.sender, &self.proofs, &self.commitment()),
+                    false => self.using_encoded(H::hash),
+                };
                 *self.hash.write() = Some(hash);
                 hash
             }
         }
     }
 
-    /// Simplified forker check, should only be called for alerts that have already been checked to
-    /// contain valid proofs.
-    pub fn forker(&self) -> NodeIndex {
-        self.proof.0.as_signable().creator()
+    pub fn proofs(&self) -> &[ForkProof<H, D, S>] {
+        &self.proofs
+    }
+
+    /// The legit units, identified by their rounds and hashes.
+    pub fn commitment(&self) -> Vec<UnitCommitment<H>> {
+        self.legit_units
+            .iter()
+            .map(|unit| {
+                let full_unit = unit.as_signable();
+                (full_unit.round(), Signable::hash(full_unit))
+            })
+            .collect()
+    }
+
+    /// The alert with the legit units replaced by their rounds and hashes. It has the same hash
+    /// only if the alert is hashed like it, see [`Self::with_compact_hash`].
+    pub fn compact(self) -> CompactAlert<H, D, S> {
+        let legit_units = self.commitment();
+        let hash = match self.compact_hash {
+            true => RwLock::new(*self.hash.read()),
+            false => RwLock::new(None),
+        };
+        CompactAlert {
+            sender: self.sender,
+            proofs: self.proofs,
+            legit_units,
+            hash,
+        }
+    }
+
+    /// Simplified forkers check, should only be called for alerts that have already been checked
+    /// to contain valid proofs.
+    pub fn forkers(&self) -> Vec<NodeIndex> {
+        forkers(&self.proofs)
     }
 
-    pub fn included_data(&self) -> Vec<D> {
+    pub fn included_data_with_creators(&self) -> Vec<(D, NodeIndex)> {
         // Only legit units might end up in the DAG, we can ignore the fork proof.
         self.legit_units
             .iter()
-            .filter_map(|uu| uu.as_signable().data().clone())
+            .flat_map(|uu| uu.as_signable().included_data_with_creator())
             .collect()
     }
 }
@@ -101,8 +263,112 @@ impl<H: Hasher, D: Data, S: Signature> Signable for Alert<H, D, S> {
     }
 }
 
+fn alert_hash<H: Hasher, D: Data, S: Signature>(
+    sender: NodeIndex,
+    proofs: &[ForkProof<H, D, S>],
+    commitment: &[UnitCommitment<H>],
+) -> H::Hash {
+    let mut encoded = Vec::new();
+    encode_alert(sender, proofs, commitment, &mut encoded);
+    H::hash(&encoded)
+}
+
+/// An [`Alert`] committing to the legit units by their rounds and hashes only, so that the units
+/// recipients already hold are not sent again. It has the same hash as the full alert hashed like
+/// it, see [`Alert::with_compact_hash`], so the signature of either is valid for both.
+#[derive(Debug, Derivative)]
+#[derivative(Eq, PartialEq, Hash)]
+pub struct CompactAlert<H: Hasher, D: Data, S: Signature> {
+    sender: NodeIndex,
+    proofs: Vec<ForkProof<H, D, S>>,
+    legit_units: Vec<UnitCommitment<H>>,
+    #[derivative(PartialEq = "ignore")]
+    #[derivative(Hash = "ignore")]
+    hash: RwLock<Option<H::Hash>>,
+}
+
+impl<H: Hasher, D: Data, S: Signature> Clone for CompactAlert<H, D, S> {
+    fn clone(&self) -> Self {
+        let hash = match self.hash.try_read() {
+            None => None,
+            Some(guard) => *guard.deref(),
+        };
+        CompactAlert {
+            sender: self.sender,
+            proofs: self.proofs.clone(),
+            legit_units: self.legit_units.clone(),
+            hash: RwLock::new(hash),
+        }
+    }
+}
+
+impl<H: Hasher, D: Data, S: Signature> Encode for CompactAlert<H, D, S> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        encode_alert(self.sender, &self.proofs, &self.legit_units, dest)
+    }
+}
+
+impl<H: Hasher, D: Data, S: Signature> Decode for CompactAlert<H, D, S> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        let (sender, proofs, legit_units) = decode_alert(input)?;
+        Ok(CompactAlert {
+            sender,
+            proofs,
+            legit_units,
+            hash: RwLock::new(None),
+        })
+    }
+}
+
+impl<H: Hasher, D: Data, S: Signature> CompactAlert<H, D, S> {
+    fn hash(&self) -> H::Hash {
+        let hash = *self.hash.read();
+        match hash {
+            Some(hash) => hash,
+            None => {
+                let hash = alert_hash(self.sender, &self.proofs, &self.legit_units);
+                *self.hash.write() = Some(hash);
+                hash
+            }
+        }
+    }
+
+    /// Simplified forkers check, should only be called for alerts that have already been checked
+    /// to contain valid proofs.
+    pub fn forkers(&self) -> Vec<NodeIndex> {
+        forkers(&self.proofs)
+    }
+
+    pub fn proofs(&self) -> &[ForkProof<H, D, S>] {
+        &self.proofs
+    }
+
+    pub fn commitment(&self) -> &[UnitCommitment<H>] {
+        &self.legit_units
+    }
+
+    /// The full alert, given the legit units in the order of the commitment. It only has the same
+    /// hash if the units match the commitment.
+    pub fn complete(self, legit_units: Vec<UncheckedSignedUnit<H, D, S>>) -> Alert<H, D, S> {
+        Alert::aggregated(self.sender, self.proofs, legit_units).with_compact_hash()
+    }
+}
+
+impl<H: Hasher, D: Data, S: Signature> Index for CompactAlert<H, D, S> {
+    fn index(&self) -> NodeIndex {
+        self.sender
+    }
+}
+
+impl<H: Hasher, D: Data, S: Signature> Signable for CompactAlert<H, D, S> {
+    type Hash = H::Hash;
+    fn hash(&self) -> Self::Hash {
+        self.hash()
+    }
+}
+
 /// A message concerning alerts.
-#[derive(Clone, Eq, PartialEq, Hash, Debug, Decode, Encode)]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Encode)]
 pub enum AlertMessage<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> {
     /// Alert regarding forks, signed by the person claiming misconduct.
     ForkAlert(UncheckedSigned<Alert<H, D, S>, S>),
@@ -110,14 +376,68 @@ pub enum AlertMessage<H: Hasher, D: Data, S: Signature, MS: PartialMultisignatur
     RmcMessage(NodeIndex, RmcMessage<H::Hash, S, MS>),
     /// A request by a node for a fork alert identified by the given hash.
     AlertRequest(NodeIndex, H::Hash),
+    /// Alert regarding forks like [`Self::ForkAlert`], but committing to the legit units by their
+    /// hashes, see [`CompactAlert`].
+    CompactForkAlert(UncheckedSigned<CompactAlert<H, D, S>, S>),
+    /// A request by a node for the legit units of the given hashes, committed to by the fork
+    /// alert of the given hash.
+    LegitUnitsRequest(NodeIndex, H::Hash, Vec<H::Hash>),
+    /// The legit units committed to by the fork alert of the given hash, sent by a node in
+    /// response to [`Self::LegitUnitsRequest`].
+    LegitUnits(NodeIndex, H::Hash, Vec<UncheckedSignedUnit<H, D, S>>),
+}
+
+// Decoded like the derive would, but with the lists bounded, see [`crate::MAX_DECODED_ITEMS`].
+impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> Decode
+    for AlertMessage<H, D, S, MS>
+{
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        Ok(match input.read_byte()? {
+            0 => Self::ForkAlert(Decode::decode(input)?),
+            1 => Self::RmcMessage(Decode::decode(input)?, Decode::decode(input)?),
+            2 => Self::AlertRequest(Decode::decode(input)?, Decode::decode(input)?),
+            3 => Self::CompactForkAlert(Decode::decode(input)?),
+            4 => Self::LegitUnitsRequest(
+                Decode::decode(input)?,
+                Decode::decode(input)?,
+                decode_bounded_vec(input)?,
+            ),
+            5 => Self::LegitUnits(
+                Decode::decode(input)?,
+                Decode::decode(input)?,
+                decode_bounded_vec(input)?,
+            ),
+            _ => return Err("unknown variant of an alert message".into()),
+        })
+    }
 }
 
 impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> AlertMessage<H, D, S, MS> {
-    pub fn included_data(&self) -> Vec<D> {
+    pub fn included_data_with_creators(&self) -> Vec<(D, NodeIndex)> {
+        match self {
+            Self::ForkAlert(unchecked_alert) => {
+                unchecked_alert.as_signable().included_data_with_creators()
+            }
+            Self::LegitUnits(_, _, units) => units
+                .iter()
+                .flat_map(|uu| uu.as_signable().included_data_with_creator())
+                .collect(),
+            Self::RmcMessage(_, _)
+            | Self::AlertRequest(_, _)
+            | Self::CompactForkAlert(_)
+            | Self::LegitUnitsRequest(_, _, _) => Vec::new(),
+        }
+    }
+
+    /// The node the message claims to come from.
+    pub fn sender(&self) -> NodeIndex {
         match self {
-            Self::ForkAlert(unchecked_alert) => unchecked_alert.as_signable().included_data(),
-            Self::RmcMessage(_, _) => Vec::new(),
-            Self::AlertRequest(_, _) => Vec::new(),
+            Self::ForkAlert(unchecked_alert) => unchecked_alert.as_signable().sender,
+            Self::CompactForkAlert(unchecked_alert) => unchecked_alert.as_signable().sender,
+            Self::RmcMessage(sender, _)
+            | Self::AlertRequest(sender, _)
+            | Self::LegitUnitsRequest(sender, _, _)
+            | Self::LegitUnits(sender, _, _) => *sender,
         }
     }
 }
@@ -0,0 +1,169 @@
+use crate::{
+    alerts::ForkProof,
+    decoding::MAX_DECODE_DEPTH,
+    units::{SignatureVerifiedUnit, Unit},
+    Data, Hasher, Keychain, NodeIndex, SessionId, Signature,
+};
+use codec::DecodeLimit;
+use thiserror::Error;
+
+/// Why a fork proof does not prove a fork, see [`verify_fork_proof`].
+#[derive(Clone, Copy, Debug, Error, Eq, PartialEq)]
+pub enum ForkProofError {
+    #[error("some unit of the proof is not signed by its creator")]
+    BadSignature,
+    #[error("the units of the proof have different creators")]
+    DifferentCreators,
+    #[error("the units of the proof are of different rounds")]
+    DifferentRounds,
+    #[error("two copies of a single unit do not constitute a fork")]
+    SameUnit,
+    #[error("some unit of the proof is of another session")]
+    WrongSession,
+}
+
+/// Checks that the proof shows two distinct units of the same creator and round, both signed by
+/// that creator, in the given session, and returns the forker. This is exactly the check the
+/// alerter runs on the proofs in the alerts it receives, so a proof accepted here would be
+/// accepted by the committee of the session, and the other way round. It needs no running
+/// session, only a keychain able to verify the signatures of the committee, e.g. to verify
+/// [`crate::MisconductReport`]s long after the session ended.
+pub fn verify_fork_proof<H: Hasher, D: Data, K: Keychain>(
+    proof: &ForkProof<H, D, K::Signature>,
+    keychain: &K,
+    session_id: SessionId,
+) -> Result<NodeIndex, ForkProofError> {
+    let (u1, u2) = proof;
+    let (u1, u2) = match (
+        SignatureVerifiedUnit::verify(u1.clone(), keychain),
+        SignatureVerifiedUnit::verify(u2.clone(), keychain),
+    ) {
+        (Ok(u1), Ok(u2)) => (u1, u2),
+        _ => return Err(ForkProofError::BadSignature),
+    };
+    let full_unit1 = u1.as_signable();
+    let full_unit2 = u2.as_signable();
+    if full_unit1.session_id() != session_id || full_unit2.session_id() != session_id {
+        return Err(ForkProofError::WrongSession);
+    }
+    if full_unit1 == full_unit2 {
+        return Err(ForkProofError::SameUnit);
+    }
+    if full_unit1.creator() != full_unit2.creator() {
+        return Err(ForkProofError::DifferentCreators);
+    }
+    if full_unit1.round() != full_unit2.round() {
+        return Err(ForkProofError::DifferentRounds);
+    }
+    Ok(full_unit1.creator())
+}
+
+/// Decodes a fork proof, e.g. the one of a [`crate::MisconductReport`], with the limits of
+/// decoding messages, see [`crate::MAX_DECODE_DEPTH`]. The whole input has to be the proof. The
+/// proof is not checked, see [`verify_fork_proof`].
+pub fn decode_fork_proof<H: Hasher, D: Data, S: Signature>(
+    encoded: &[u8],
+) -> Result<ForkProof<H, D, S>, codec::Error> {
+    ForkProof::decode_all_with_depth_limit(MAX_DECODE_DEPTH, &mut &encoded[..])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        alerts::{
+            proof::{decode_fork_proof, verify_fork_proof, ForkProofError},
+            ForkProof,
+        },
+        units::{ControlHash, FullUnit, PreUnit},
+        NodeCount, NodeIndex, NodeMap, Round, SessionId, Signed,
+    };
+    use aleph_bft_mock::{Data, Hasher64, Keychain, Signature};
+    use codec::{Decode, Encode};
+
+    const N_MEMBERS: NodeCount = NodeCount(4);
+    const FORKER: NodeIndex = NodeIndex(3);
+    const SESSION: SessionId = 7;
+
+    type TestForkProof = ForkProof<Hasher64, Data, Signature>;
+    type TestUnit = crate::units::UncheckedSignedUnit<Hasher64, Data, Signature>;
+
+    fn unit(creator: NodeIndex, round: Round, data: Data, session_id: SessionId) -> TestUnit {
+        let control_hash = ControlHash::new(&NodeMap::with_size(N_MEMBERS));
+        let full_unit = FullUnit::new(
+            PreUnit::new(creator, round, control_hash),
+            Some(data),
+            session_id,
+        );
+        Signed::sign(full_unit, &Keychain::new(N_MEMBERS, creator))
+            .expect("the keychain never fails")
+            .into_unchecked()
+    }
+
+    fn verify(proof: &TestForkProof) -> Result<NodeIndex, ForkProofError> {
+        verify_fork_proof(proof, &Keychain::new(N_MEMBERS, NodeIndex(0)), SESSION)
+    }
+
+    fn valid_proof() -> TestForkProof {
+        (unit(FORKER, 2, 0, SESSION), unit(FORKER, 2, 1, SESSION))
+    }
+
+    #[test]
+    fn accepts_valid_proof_after_encoding_round_trip() {
+        let encoded = valid_proof().encode();
+        let proof = decode_fork_proof(&encoded).expect("the proof should decode");
+        assert_eq!(proof, valid_proof());
+        assert_eq!(verify(&proof), Ok(FORKER));
+    }
+
+    #[test]
+    fn refuses_trailing_bytes() {
+        let mut encoded = valid_proof().encode();
+        encoded.push(0);
+        assert!(decode_fork_proof::<Hasher64, Data, Signature>(&encoded).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_signature() {
+        let (u1, _) = valid_proof();
+        let control_hash = ControlHash::new(&NodeMap::with_size(N_MEMBERS));
+        let full_unit: FullUnit<Hasher64, Data> =
+            FullUnit::new(PreUnit::new(FORKER, 2, control_hash), Some(1), SESSION);
+        // Claims to be signed by the forker, but the signature covers something else.
+        let forged =
+            TestUnit::decode(&mut &(full_unit, Signature::new(vec![], FORKER)).encode()[..])
+                .expect("the encoding is correct");
+        assert_eq!(verify(&(u1, forged)), Err(ForkProofError::BadSignature));
+    }
+
+    #[test]
+    fn rejects_different_creators() {
+        let proof = (
+            unit(FORKER, 2, 0, SESSION),
+            unit(NodeIndex(2), 2, 1, SESSION),
+        );
+        assert_eq!(verify(&proof), Err(ForkProofError::DifferentCreators));
+    }
+
+    #[test]
+    fn rejects_different_rounds() {
+        let proof = (unit(FORKER, 2, 0, SESSION), unit(FORKER, 3, 1, SESSION));
+        assert_eq!(verify(&proof), Err(ForkProofError::DifferentRounds));
+    }
+
+    #[test]
+    fn rejects_same_unit() {
+        let proof = (unit(FORKER, 2, 0, SESSION), unit(FORKER, 2, 0, SESSION));
+        assert_eq!(verify(&proof), Err(ForkProofError::SameUnit));
+    }
+
+    #[test]
+    fn rejects_wrong_session() {
+        let proof = (unit(FORKER, 2, 0, SESSION), unit(FORKER, 2, 1, SESSION + 1));
+        assert_eq!(verify(&proof), Err(ForkProofError::WrongSession));
+        let proof = (
+            unit(FORKER, 2, 0, SESSION + 1),
+            unit(FORKER, 2, 1, SESSION + 1),
+        );
+        assert_eq!(verify(&proof), Err(ForkProofError::WrongSession));
+    }
+}
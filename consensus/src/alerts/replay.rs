@@ -0,0 +1,237 @@
+use crate::{
+    alerts::{AlertMessage, Handler},
+    network::{NetworkDataInner, MAX_DECOMPRESSED_SIZE},
+    Data, Hasher, Index, MultiKeychain, Multisigned, NetworkData, NodeIndex, SessionId,
+};
+use aleph_bft_rmc::Message as RmcMessage;
+use log::{debug, trace};
+use std::collections::HashMap;
+
+const LOG_TARGET: &str = "AlephBFT-alert-replay";
+
+/// An alert found on a recorded tape of network traffic, see [`replay_alerts`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReplayedAlert<H: Hasher> {
+    /// The hash of the alert, under which the live node reports it.
+    pub hash: H::Hash,
+    /// The node that raised the alert.
+    pub sender: NodeIndex,
+    /// The nodes the alert proves to be forkers.
+    pub forkers: Vec<NodeIndex>,
+    /// Whether a correct multisignature of the alert is on the tape, i.e. whether a node that
+    /// received this traffic confirms the alert. Unconfirmed alerts never completed their
+    /// reliable broadcast as far as the tape shows.
+    pub confirmed: bool,
+}
+
+/// Re-derives the alerts a node confirmed from the network traffic it recorded, without trusting
+/// anything but the signatures. The tape should contain the messages the node received, and the
+/// ones it sent if its own alerts should be replayed too, in the order they were recorded.
+///
+/// The alerts are checked exactly like a live node checks them, and an alert is confirmed only
+/// when a correct multisignature of it appears on the tape, before or after the alert itself.
+/// Alerts are returned in the order of their first appearance on the tape. Any difference from
+/// the alerts the live node reported points at a tampered tape or a bug.
+pub fn replay_alerts<H: Hasher, D: Data, MK: MultiKeychain>(
+    tape: impl IntoIterator<Item = NetworkData<H, D, MK::Signature, MK::PartialMultisignature>>,
+    keychain: MK,
+    session_id: SessionId,
+) -> Vec<ReplayedAlert<H>> {
+    let mut handler = Handler::new(keychain.clone(), session_id);
+    let mut alerts: Vec<ReplayedAlert<H>> = Vec::new();
+    let mut positions = HashMap::new();
+    let mut multisigned_first: HashMap<H::Hash, Multisigned<H::Hash, MK>> = HashMap::new();
+    for NetworkData(data, _) in tape {
+        let message = match data.decompressed(MAX_DECOMPRESSED_SIZE) {
+            Ok(NetworkDataInner::Alert(message)) => message,
+            Ok(NetworkDataInner::Units(_))
+            | Ok(NetworkDataInner::Compressed(_))
+            | Ok(NetworkDataInner::Unsupported) => continue,
+            Err(e) => {
+                debug!(target: LOG_TARGET, "Skipping a compressed message: {}.", e);
+                continue;
+            }
+        };
+        let (sender, forkers, checked) = match message {
+            AlertMessage::ForkAlert(unchecked) => {
+                let sender = unchecked.as_signable().index();
+                let forkers = unchecked.as_signable().forkers();
+                let checked = handler.on_network_alert(unchecked).map(|(_, hash)| hash);
+                (sender, forkers, checked)
+            }
+            // The multisignature of a compact alert confirms it just as well, whether or not its
+            // units are on the tape.
+            AlertMessage::CompactForkAlert(unchecked) => {
+                let sender = unchecked.as_signable().index();
+                let forkers = unchecked.as_signable().forkers();
+                let checked = handler
+                    .on_network_compact_alert(unchecked)
+                    .map(|(_, hash, _)| hash);
+                (sender, forkers, checked)
+            }
+            AlertMessage::RmcMessage(_, RmcMessage::MultisignedHash(unchecked)) => {
+                let multisigned = match unchecked.check_multi(&keychain) {
+                    Ok(multisigned) => multisigned,
+                    Err(_) => {
+                        debug!(target: LOG_TARGET, "Skipping an incorrect multisignature.");
+                        continue;
+                    }
+                };
+                let hash = *multisigned.as_signable();
+                match positions.contains_key(&hash) {
+                    true => confirm(&mut handler, &mut alerts, &positions, multisigned),
+                    false => {
+                        multisigned_first.entry(hash).or_insert(multisigned);
+                    }
+                }
+                continue;
+            }
+            AlertMessage::RmcMessage(_, RmcMessage::SignedHash(_))
+            | AlertMessage::AlertRequest(_, _)
+            | AlertMessage::LegitUnitsRequest(_, _, _)
+            | AlertMessage::LegitUnits(_, _, _) => continue,
+        };
+        let hash = match checked {
+            Ok(hash) => hash,
+            Err(e) => {
+                trace!(target: LOG_TARGET, "Skipping an alert: {}.", e);
+                continue;
+            }
+        };
+        positions.insert(hash, alerts.len());
+        alerts.push(ReplayedAlert {
+            hash,
+            sender,
+            forkers,
+            confirmed: false,
+        });
+        if let Some(multisigned) = multisigned_first.remove(&hash) {
+            confirm(&mut handler, &mut alerts, &positions, multisigned);
+        }
+    }
+    alerts
+}
+
+fn confirm<H: Hasher, D: Data, MK: MultiKeychain>(
+    handler: &mut Handler<H, D, MK>,
+    alerts: &mut [ReplayedAlert<H>],
+    positions: &HashMap<H::Hash, usize>,
+    multisigned: Multisigned<H::Hash, MK>,
+) {
+    let hash = *multisigned.as_signable();
+    match handler.alert_confirmed(multisigned) {
+        Ok(_) => {
+            if let Some(position) = positions.get(&hash) {
+                alerts[*position].confirmed = true;
+            }
+        }
+        Err(e) => debug!(target: LOG_TARGET, "Not confirming alert {:?}: {}.", hash, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        alerts::{replay::replay_alerts, Alert, AlertMessage, ForkProof},
+        network::NetworkDataInner,
+        units::{ControlHash, FullUnit, PreUnit},
+        NetworkData, NodeCount, NodeIndex, NodeMap, Signable, Signed,
+    };
+    use aleph_bft_mock::{Data, Hash64, Hasher64, Keychain, PartialMultisignature, Signature};
+    use aleph_bft_rmc::Message;
+
+    type TestNetworkData = NetworkData<Hasher64, Data, Signature, PartialMultisignature>;
+
+    const N_MEMBERS: NodeCount = NodeCount(4);
+    const SENDER: NodeIndex = NodeIndex(1);
+    const FORKER: NodeIndex = NodeIndex(3);
+
+    fn keychains() -> Vec<Keychain> {
+        N_MEMBERS
+            .into_iterator()
+            .map(|node_id| Keychain::new(N_MEMBERS, node_id))
+            .collect()
+    }
+
+    fn fork_proof(keychain: &Keychain) -> ForkProof<Hasher64, Data, Signature> {
+        let unit = |data| {
+            let control_hash = ControlHash::new(&NodeMap::with_size(N_MEMBERS));
+            let full_unit = FullUnit::new(PreUnit::new(FORKER, 0, control_hash), Some(data), 0);
+            Signed::sign(full_unit, keychain)
+                .expect("the keychain never fails")
+                .into_unchecked()
+        };
+        (unit(0), unit(1))
+    }
+
+    /// The alert of [`SENDER`] about [`FORKER`], together with its hash multisigned by the
+    /// given signers.
+    fn alert_and_multisignature(
+        signers: &[NodeIndex],
+    ) -> (TestNetworkData, TestNetworkData, Hash64) {
+        let keychains = keychains();
+        let alert = Alert::new(SENDER, fork_proof(&keychains[FORKER.0]), vec![]);
+        let hash = Signable::hash(&alert);
+        let signed_alert = Signed::sign(alert, &keychains[SENDER.0])
+            .expect("the keychain never fails")
+            .into_unchecked();
+        let sign = |node_id: &NodeIndex| {
+            Signed::sign_with_index(hash, &keychains[node_id.0]).expect("the keychain never fails")
+        };
+        let mut multisigned = sign(&signers[0]).into_partially_multisigned(&keychains[0]);
+        for signer in &signers[1..] {
+            multisigned = multisigned.add_signature(sign(signer), &keychains[0]);
+        }
+        let alert_data = NetworkData(
+            NetworkDataInner::Alert(AlertMessage::ForkAlert(signed_alert)),
+            0,
+        );
+        let multisigned_data = NetworkData(
+            NetworkDataInner::Alert(AlertMessage::RmcMessage(
+                signers[0],
+                Message::MultisignedHash(multisigned.into_unchecked()),
+            )),
+            0,
+        );
+        (alert_data, multisigned_data, hash)
+    }
+
+    fn quorum() -> Vec<NodeIndex> {
+        vec![NodeIndex(0), NodeIndex(1), NodeIndex(2)]
+    }
+
+    #[test]
+    fn confirms_multisigned_alert() {
+        let (alert, multisigned, hash) = alert_and_multisignature(&quorum());
+        let replayed = replay_alerts(vec![alert, multisigned], keychains()[0].clone(), 0);
+        assert_eq!(replayed.len(), 1);
+        assert_eq!(replayed[0].hash, hash);
+        assert_eq!(replayed[0].sender, SENDER);
+        assert_eq!(replayed[0].forkers, vec![FORKER]);
+        assert!(replayed[0].confirmed);
+    }
+
+    #[test]
+    fn confirms_alert_multisigned_before_it_arrived() {
+        let (alert, multisigned, _) = alert_and_multisignature(&quorum());
+        let replayed = replay_alerts(vec![multisigned, alert], keychains()[0].clone(), 0);
+        assert_eq!(replayed.len(), 1);
+        assert!(replayed[0].confirmed);
+    }
+
+    #[test]
+    fn flags_alert_without_multisignature() {
+        let (alert, _, _) = alert_and_multisignature(&quorum());
+        let replayed = replay_alerts(vec![alert], keychains()[0].clone(), 0);
+        assert_eq!(replayed.len(), 1);
+        assert!(!replayed[0].confirmed);
+    }
+
+    #[test]
+    fn does_not_confirm_with_too_few_signatures() {
+        let (alert, multisigned, _) = alert_and_multisignature(&quorum()[..2]);
+        let replayed = replay_alerts(vec![alert, multisigned], keychains()[0].clone(), 0);
+        assert_eq!(replayed.len(), 1);
+        assert!(!replayed[0].confirmed);
+    }
+}
@@ -1,28 +1,65 @@
 use crate::{
     alerts::{
-        handler::{Handler, RmcResponse},
+        completed::{CompletedRmcs, CompletionCheck, COMPLETED_RMCS_KEPT},
+        handler::{Error, Handler, RmcResponse},
+        throttle::AlertThrottle,
         Alert, AlertMessage, ForkingNotification, NetworkMessage,
     },
-    Data, Hasher, MultiKeychain, Multisigned, NodeIndex, Receiver, Recipient, Sender, Terminator,
+    drops::{DropMonitor, DropReason},
+    events::{AlertState, EventBus, InternalEvent, SigningTarget},
+    misconduct::{MisconductMonitor, MisconductReport},
+    protocol::RMC_REBROADCAST_BASE_DELAY,
+    units::Unit,
+    AlertRateLimit, ClockSource, Data, Hasher, MultiKeychain, Multisigned, NodeIndex, Receiver,
+    Recipient, Role, Sender, Terminator,
 };
 use aleph_bft_rmc::{DoublingDelayScheduler, Message as RmcMessage};
-use futures::{FutureExt, StreamExt};
+use codec::Encode;
+use futures::{
+    future::{pending, BoxFuture},
+    FutureExt, StreamExt,
+};
 use log::{debug, error, trace, warn};
-use std::time::Duration;
+use std::{cmp::min, collections::HashSet, mem, time::Duration};
 
 const LOG_TARGET: &str = "AlephBFT-alerter";
+/// The delay before the first retry of a failed signing, doubled with each failed retry.
+const INITIAL_SIGNING_RETRY_DELAY: Duration = Duration::from_millis(100);
+const MAX_SIGNING_RETRY_DELAY: Duration = Duration::from_secs(10);
+/// How often the legit units of compact alerts are requested again while some are missing.
+const LEGIT_UNITS_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
 type RmcService<H, MK, S, M> =
     aleph_bft_rmc::Service<H, MK, DoublingDelayScheduler<RmcMessage<H, S, M>>>;
 
+/// Something we failed to sign. Alerts are crucial for the safety of the protocol, so they
+/// have to be signed eventually.
+enum PendingSignature<H: Hasher, D: Data, MK: MultiKeychain> {
+    OwnAlert(Alert<H, D, MK::Signature>),
+    RmcHash(H::Hash),
+}
+
 pub struct Service<H: Hasher, D: Data, MK: MultiKeychain> {
     messages_for_network: Sender<(NetworkMessage<H, D, MK>, Recipient)>,
     messages_from_network: Receiver<NetworkMessage<H, D, MK>>,
     notifications_for_units: Sender<ForkingNotification<H, D, MK::Signature>>,
     alerts_from_units: Receiver<Alert<H, D, MK::Signature>>,
+    events: EventBus<H, D, MK::Signature>,
     node_index: NodeIndex,
+    role: Role,
     exiting: bool,
     handler: Handler<H, D, MK>,
     rmc_service: RmcService<H::Hash, MK, MK::Signature, MK::PartialMultisignature>,
+    pending_signatures: Vec<PendingSignature<H, D, MK>>,
+    signing_retry_delay: Duration,
+    signing_retry: Option<BoxFuture<'static, ()>>,
+    throttle: Option<AlertThrottle<Alert<H, D, MK::Signature>>>,
+    throttle_release: Option<BoxFuture<'static, ()>>,
+    legit_units_retry: Option<BoxFuture<'static, ()>>,
+    clock: ClockSource,
+    drops: DropMonitor,
+    completed_rmcs: CompletedRmcs<H::Hash, Multisigned<H::Hash, MK>>,
+    misconduct: MisconductMonitor<H>,
+    reported_forkers: HashSet<NodeIndex>,
 }
 
 pub struct IO<H: Hasher, D: Data, MK: MultiKeychain> {
@@ -30,6 +67,20 @@ pub struct IO<H: Hasher, D: Data, MK: MultiKeychain> {
     pub messages_from_network: Receiver<NetworkMessage<H, D, MK>>,
     pub notifications_for_units: Sender<ForkingNotification<H, D, MK::Signature>>,
     pub alerts_from_units: Receiver<Alert<H, D, MK::Signature>>,
+    pub events: EventBus<H, D, MK::Signature>,
+    pub clock: ClockSource,
+    pub alert_rate_limit: Option<AlertRateLimit>,
+    pub drops: DropMonitor,
+    pub role: Role,
+    pub rmc_completion_replies: bool,
+    pub misconduct: MisconductMonitor<H>,
+}
+
+async fn wait_for(timer: &mut Option<BoxFuture<'static, ()>>) {
+    match timer {
+        Some(delay) => delay.await,
+        None => pending().await,
+    }
 }
 
 impl<H: Hasher, D: Data, MK: MultiKeychain> Service<H, D, MK> {
@@ -39,12 +90,25 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Service<H, D, MK> {
             messages_from_network,
             notifications_for_units,
             alerts_from_units,
+            events,
+            clock,
+            alert_rate_limit,
+            drops,
+            role,
+            rmc_completion_replies,
+            misconduct,
         } = io;
 
         let node_index = keychain.index();
+        // Observers are not part of the multicast, so they have nothing to answer with.
+        let completed_rmcs = CompletedRmcs::new(
+            COMPLETED_RMCS_KEPT,
+            keychain.node_count(),
+            rmc_completion_replies && role == Role::Member,
+        );
         let rmc_handler = aleph_bft_rmc::Handler::new(keychain);
         let rmc_service = aleph_bft_rmc::Service::new(
-            DoublingDelayScheduler::new(Duration::from_millis(500)),
+            DoublingDelayScheduler::with_clock(RMC_REBROADCAST_BASE_DELAY, clock.clone()),
             rmc_handler,
         );
 
@@ -53,10 +117,23 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Service<H, D, MK> {
             messages_from_network,
             notifications_for_units,
             alerts_from_units,
+            events,
             node_index,
+            role,
             exiting: false,
             handler,
             rmc_service,
+            pending_signatures: Vec::new(),
+            signing_retry_delay: INITIAL_SIGNING_RETRY_DELAY,
+            signing_retry: None,
+            throttle: alert_rate_limit.map(AlertThrottle::new),
+            throttle_release: None,
+            legit_units_retry: None,
+            clock,
+            drops,
+            completed_rmcs,
+            misconduct,
+            reported_forkers: HashSet::new(),
         }
     }
 
@@ -64,6 +141,12 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Service<H, D, MK> {
         &mut self,
         message: RmcMessage<H::Hash, MK::Signature, MK::PartialMultisignature>,
     ) {
+        if self.role == Role::Observer {
+            trace!(target: LOG_TARGET, "Not multicasting {:?}, as we are an observer.", message);
+            self.drops
+                .record_drop(DropReason::Observing, None, || message.encode());
+            return;
+        }
         self.send_message_for_network(
             AlertMessage::RmcMessage(self.node_index, message),
             Recipient::Everyone,
@@ -87,6 +170,11 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Service<H, D, MK> {
         }
     }
 
+    fn publish_alert_state(&self, hash: H::Hash, state: AlertState) {
+        self.events
+            .publish(InternalEvent::AlertStateChanged(hash, state));
+    }
+
     fn send_message_for_network(
         &mut self,
         message: AlertMessage<H, D, MK::Signature, MK::PartialMultisignature>,
@@ -110,18 +198,101 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Service<H, D, MK> {
         message: AlertMessage<H, D, MK::Signature, MK::PartialMultisignature>,
     ) {
         match message {
-            AlertMessage::ForkAlert(alert) => match self.handler.on_network_alert(alert.clone()) {
-                Ok((maybe_notification, hash)) => {
-                    if let Some(multisigned) = self.rmc_service.start_rmc(hash) {
-                        self.handle_multisigned(multisigned);
+            AlertMessage::ForkAlert(alert) => {
+                match self.handler.on_network_alert(alert.clone()) {
+                    Ok((notifications, hash)) => {
+                        self.publish_alert_state(hash, AlertState::Received);
+                        // Observers only learn the outcome of the multicast of the committee.
+                        if self.role == Role::Member {
+                            self.start_rmc(hash);
+                        }
+                        for notification in notifications {
+                            self.send_notification_for_units(notification);
+                        }
+                    }
+                    Err(error) => {
+                        let sender = alert.as_signable().sender;
+                        self.on_invalid_alert(error, sender, || alert.encode());
+                    }
+                }
+                // The units of the alert might be the ones a compact alert is missing.
+                self.complete_alerts();
+            }
+            AlertMessage::CompactForkAlert(alert) => {
+                match self.handler.on_network_compact_alert(alert.clone()) {
+                    Ok((notifications, hash, request)) => {
+                        self.publish_alert_state(hash, AlertState::Received);
+                        match request {
+                            Some((request, recipient)) => {
+                                debug!(target: LOG_TARGET, "Requesting the units of alert {:?}.", hash);
+                                self.send_message_for_network(request, recipient);
+                                self.schedule_legit_units_retry();
+                            }
+                            None if self.role == Role::Member => self.start_rmc(hash),
+                            None => {}
+                        }
+                        for notification in notifications {
+                            self.send_notification_for_units(notification);
+                        }
                     }
-                    if let Some(notification) = maybe_notification {
-                        self.send_notification_for_units(notification);
+                    Err(error) => {
+                        let sender = alert.as_signable().sender;
+                        self.on_invalid_alert(error, sender, || alert.encode());
                     }
                 }
-                Err(error) => debug!(target: LOG_TARGET, "{}", error),
-            },
+            }
+            AlertMessage::LegitUnitsRequest(node, hash, units) => {
+                match self.handler.on_legit_units_request(node, hash, units) {
+                    Ok((message, recipient)) => self.send_message_for_network(message, recipient),
+                    Err(error) => {
+                        debug!(target: LOG_TARGET, "{}", error);
+                        self.drops
+                            .record_drop(DropReason::UnknownAlertRequest, Some(node), || {
+                                hash.encode()
+                            });
+                    }
+                }
+            }
+            AlertMessage::LegitUnits(sender, hash, units) => {
+                if let Err(error) = self.handler.on_legit_units(sender, hash, &units) {
+                    debug!(target: LOG_TARGET, "{}", error);
+                    let reason = match error {
+                        Error::UnknownLegitUnits(_) => DropReason::StaleResponse,
+                        _ => DropReason::InvalidUnit,
+                    };
+                    self.drops
+                        .record_drop(reason, Some(sender), || units.encode());
+                }
+                self.complete_alerts();
+            }
             AlertMessage::RmcMessage(sender, message) => {
+                // Peers keep multicasting until they see the completion, so these are common
+                // and have to be dropped before the costly verification.
+                match self
+                    .completed_rmcs
+                    .check(sender, message.hash(), message.is_complete())
+                {
+                    CompletionCheck::Pending => {}
+                    CompletionCheck::Suppressed => {
+                        self.drops
+                            .record_drop(DropReason::CompletedRmc, Some(sender), || {
+                                message.encode()
+                            });
+                        return;
+                    }
+                    CompletionCheck::Reply(multisigned) => {
+                        self.drops
+                            .record_drop(DropReason::CompletedRmc, Some(sender), || {
+                                message.encode()
+                            });
+                        let message = RmcMessage::MultisignedHash(multisigned.into_unchecked());
+                        self.send_message_for_network(
+                            AlertMessage::RmcMessage(self.node_index, message),
+                            Recipient::Node(sender),
+                        );
+                        return;
+                    }
+                }
                 match self.handler.on_rmc_message(sender, message) {
                     RmcResponse::RmcMessage(message) => {
                         if let Some(multisigned) = self.rmc_service.process_message(message) {
@@ -132,40 +303,303 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Service<H, D, MK> {
                         let message = AlertMessage::AlertRequest(self.node_index, hash);
                         self.send_message_for_network(message, recipient);
                     }
-                    RmcResponse::Noop => {}
+                    RmcResponse::Noop => {
+                        self.drops.record_drop(
+                            DropReason::UnsolicitedRmcMessage,
+                            Some(sender),
+                            Vec::new,
+                        );
+                    }
                 }
             }
             AlertMessage::AlertRequest(node, hash) => {
                 match self.handler.on_alert_request(node, hash) {
-                    Ok((alert, recipient)) => {
-                        self.send_message_for_network(AlertMessage::ForkAlert(alert), recipient);
+                    Ok((message, recipient)) => {
+                        self.send_message_for_network(message, recipient);
+                    }
+                    Err(error) => {
+                        debug!(target: LOG_TARGET, "{}", error);
+                        self.drops
+                            .record_drop(DropReason::UnknownAlertRequest, Some(node), || {
+                                hash.encode()
+                            });
                     }
-                    Err(error) => debug!(target: LOG_TARGET, "{}", error),
                 }
             }
         }
     }
 
-    fn handle_alert_from_runway(&mut self, alert: Alert<H, D, MK::Signature>) {
+    fn on_invalid_alert(
+        &mut self,
+        error: Error,
+        sender: NodeIndex,
+        encode: impl FnOnce() -> Vec<u8>,
+    ) {
+        debug!(target: LOG_TARGET, "{}", error);
+        let reason = match error {
+            Error::WrongSession(_) => DropReason::AlertWrongSession,
+            Error::RepeatedAlert(..) => DropReason::RepeatedAlert,
+            _ => DropReason::InvalidAlert,
+        };
+        self.drops.record_drop(reason, Some(sender), encode);
+    }
+
+    /// Passes on the compact alerts that got all their legit units, like the ones that arrived
+    /// in full.
+    fn complete_alerts(&mut self) {
+        if !self.handler.is_awaiting_units() {
+            return;
+        }
+        for (hash, confirmation) in self.handler.take_completed_alerts() {
+            debug!(target: LOG_TARGET, "Received all the units of alert {:?}.", hash);
+            if self.role == Role::Member {
+                self.start_rmc(hash);
+            }
+            match confirmation {
+                Some(Ok(notification)) => {
+                    self.publish_alert_state(hash, AlertState::Confirmed);
+                    self.send_notification_for_units(notification);
+                }
+                Some(Err(error)) => warn!(target: LOG_TARGET, "{}", error),
+                None => {}
+            }
+        }
+    }
+
+    fn schedule_legit_units_retry(&mut self) {
+        if self.legit_units_retry.is_none() {
+            self.legit_units_retry = Some(self.clock.sleep(LEGIT_UNITS_REQUEST_INTERVAL));
+        }
+    }
+
+    fn retry_legit_units_requests(&mut self) {
+        self.legit_units_retry = None;
+        for (request, recipient) in self.handler.legit_units_requests() {
+            self.send_message_for_network(request, recipient);
+        }
+        if self.handler.is_awaiting_units() {
+            self.schedule_legit_units_retry();
+        }
+    }
+
+    /// Raises the alerts the runway produced at once, as a single alert if we aggregate them, so
+    /// that all of them take part in one RMC.
+    fn handle_alerts_from_runway(&mut self, alerts: Vec<Alert<H, D, MK::Signature>>) {
+        let alerts: Vec<_> = alerts
+            .into_iter()
+            .filter_map(|alert| self.admit_alert_from_runway(alert))
+            .collect();
+        self.raise_own_alerts(alerts);
+    }
+
+    fn raise_own_alerts(&mut self, alerts: Vec<Alert<H, D, MK::Signature>>) {
+        match self.handler.aggregates_alerts() {
+            true => {
+                if let Some(alert) = Alert::aggregate(alerts) {
+                    self.raise_own_alert(alert);
+                }
+            }
+            false => {
+                for alert in alerts {
+                    self.raise_own_alert(alert);
+                }
+            }
+        }
+    }
+
+    /// Returns the alert if it should be raised right away.
+    fn admit_alert_from_runway(
+        &mut self,
+        alert: Alert<H, D, MK::Signature>,
+    ) -> Option<Alert<H, D, MK::Signature>> {
         trace!(target: LOG_TARGET, "Handling alert {:?}.", alert);
-        let (message, recipient, hash) = self.handler.on_own_alert(alert.clone());
+        if self.role == Role::Observer {
+            debug!(target: LOG_TARGET, "Not raising an alert about {:?}, as we are an observer.", alert.forkers());
+            self.drops
+                .record_drop(DropReason::Observing, None, || alert.encode());
+            return None;
+        }
+        // The runway raises alerts about a single forker each.
+        let (Some(throttle), &[forker]) = (self.throttle.as_mut(), &alert.forkers()[..]) else {
+            return Some(alert);
+        };
+        let already_queued = throttle.is_queued(forker);
+        let was_throttling = throttle.is_throttling();
+        match throttle.submit(forker, alert.clone(), self.clock.now()) {
+            Some(alert) => Some(alert),
+            None if already_queued => {
+                debug!(target: LOG_TARGET, "Dropping a repeated alert about {:?}, we are already holding one back.", forker);
+                self.drops
+                    .record_drop(DropReason::ThrottledAlert, None, || alert.encode());
+                None
+            }
+            None => {
+                self.on_alert_queued(alert, was_throttling);
+                None
+            }
+        }
+    }
+
+    fn on_alert_queued(&mut self, alert: Alert<H, D, MK::Signature>, was_throttling: bool) {
+        warn!(target: LOG_TARGET, "Holding back our alert about {:?}, as we raised too many alerts recently.", alert.forkers());
+        self.handler.on_own_alert_queued(&alert);
+        for proof in alert.proofs() {
+            self.events
+                .publish(InternalEvent::AlertQueued(proof.clone()));
+        }
+        if !was_throttling {
+            error!(target: LOG_TARGET, "Started holding back our own alerts. Detecting this many forks suggests our units are corrupted locally.");
+            self.events
+                .publish(InternalEvent::AlertThrottlingChanged(true));
+        }
+        self.schedule_throttle_release();
+    }
+
+    fn schedule_throttle_release(&mut self) {
+        let now = self.clock.now();
+        self.throttle_release = self
+            .throttle
+            .as_ref()
+            .and_then(|throttle| throttle.next_release(now))
+            .map(|release| self.clock.sleep_until(release));
+    }
+
+    fn release_throttled_alerts(&mut self) {
+        let released = match self.throttle.as_mut() {
+            Some(throttle) => throttle.release(self.clock.now()),
+            None => return,
+        };
+        self.raise_own_alerts(released);
+        if !self
+            .throttle
+            .as_ref()
+            .is_some_and(|throttle| throttle.is_throttling())
+        {
+            debug!(target: LOG_TARGET, "Stopped holding back our own alerts.");
+            self.events
+                .publish(InternalEvent::AlertThrottlingChanged(false));
+        }
+        self.schedule_throttle_release();
+    }
+
+    fn raise_own_alert(&mut self, alert: Alert<H, D, MK::Signature>) {
+        let (message, recipient, hash) = match self.handler.on_own_alert(alert.clone()) {
+            Ok(response) => response,
+            Err(e) => {
+                let target = SigningTarget::Alert(alert.forkers());
+                self.on_signing_failed(target, e, PendingSignature::OwnAlert(alert));
+                return;
+            }
+        };
+        for forker in alert.forkers() {
+            self.publish_alert_state(hash, AlertState::Raised(forker));
+        }
         self.send_message_for_network(message, recipient);
-        if let Some(multisigned) = self.rmc_service.start_rmc(hash) {
-            self.handle_multisigned(multisigned);
+        self.start_rmc(hash);
+        // Our alert might hold the units missing from the compact alerts of others.
+        self.complete_alerts();
+    }
+
+    fn start_rmc(&mut self, hash: H::Hash) {
+        match self.rmc_service.start_rmc(hash) {
+            Ok(Some(multisigned)) => self.handle_multisigned(multisigned),
+            Ok(None) => {}
+            Err(e) => {
+                let target = SigningTarget::RmcHash(hash);
+                self.on_signing_failed(target, e, PendingSignature::RmcHash(hash));
+            }
+        }
+    }
+
+    fn on_signing_failed(
+        &mut self,
+        target: SigningTarget<H>,
+        error: MK::SignError,
+        pending: PendingSignature<H, D, MK>,
+    ) {
+        warn!(target: LOG_TARGET, "Failed to sign {}: {}. Retrying in {:?}.", target, error, self.signing_retry_delay);
+        self.events.publish(InternalEvent::SigningFailed(target));
+        self.pending_signatures.push(pending);
+        if self.signing_retry.is_none() {
+            self.signing_retry = Some(self.clock.sleep(self.signing_retry_delay));
+        }
+    }
+
+    fn retry_signing(&mut self) {
+        self.signing_retry = None;
+        self.signing_retry_delay = min(2 * self.signing_retry_delay, MAX_SIGNING_RETRY_DELAY);
+        for pending in mem::take(&mut self.pending_signatures) {
+            match pending {
+                PendingSignature::OwnAlert(alert) => self.raise_own_alert(alert),
+                PendingSignature::RmcHash(hash) => self.start_rmc(hash),
+            }
+        }
+        if self.pending_signatures.is_empty() {
+            self.signing_retry_delay = INITIAL_SIGNING_RETRY_DELAY;
         }
     }
 
     fn handle_multisigned(&mut self, multisigned: Multisigned<H::Hash, MK>) {
+        self.completed_rmcs
+            .complete(*multisigned.as_signable(), multisigned.clone());
+        let hash = *multisigned.as_signable();
         match self.handler.alert_confirmed(multisigned.clone()) {
-            Ok(notification) => {
+            Ok(Some(notification)) => {
+                self.publish_alert_state(hash, AlertState::Confirmed);
+                self.report_misconduct(multisigned);
                 self.send_notification_for_units(notification);
             }
+            Ok(None) => {
+                debug!(target: LOG_TARGET, "Alert {:?} confirmed, still missing some of its units.", hash);
+                self.report_misconduct(multisigned);
+                // Now some honest node other than the sender holds the units as well.
+                if let Some((request, recipient)) = self.handler.legit_units_request(&hash) {
+                    self.send_message_for_network(request, recipient);
+                }
+            }
             Err(error) => warn!(target: LOG_TARGET, "{}", error),
         }
     }
 
+    /// Reports the forkers of the confirmed alert, except the ones an earlier alert was reported
+    /// about. Our own alerts get here as well, once their multicast completes.
+    fn report_misconduct(&mut self, multisigned: Multisigned<H::Hash, MK>) {
+        if !self.misconduct.is_active() {
+            return;
+        }
+        let hash = *multisigned.as_signable();
+        let Some((sender, proofs)) = self.handler.alert_proofs(&hash) else {
+            return;
+        };
+        let encoded_multisignature = multisigned.into_unchecked().encode();
+        for proof in proofs {
+            let forker = proof.0.as_signable().creator();
+            if !self.reported_forkers.insert(forker) {
+                continue;
+            }
+            debug!(target: LOG_TARGET, "Reporting the misconduct of {:?}.", forker);
+            self.misconduct.report(MisconductReport {
+                forker,
+                sender,
+                alert: hash,
+                encoded_fork_proof: proof.encode(),
+                encoded_multisignature: encoded_multisignature.clone(),
+            });
+        }
+    }
+
     pub async fn run(&mut self, mut terminator: Terminator) {
         loop {
+            if !self.exiting && terminator.exit_requested() {
+                debug!(target: LOG_TARGET, "Received exit signal.");
+                self.exiting = true;
+            }
+            if self.exiting {
+                debug!(target: LOG_TARGET, "Alerter decided to exit.");
+                terminator.terminate_sync().await;
+                break;
+            }
+
             futures::select! {
                 message = self.messages_from_network.next() => match message {
                     Some(message) => self.handle_message_from_network(message),
@@ -175,7 +609,13 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Service<H, D, MK> {
                     }
                 },
                 alert = self.alerts_from_units.next() => match alert {
-                    Some(alert) => self.handle_alert_from_runway(alert),
+                    Some(alert) => {
+                        let mut alerts = vec![alert];
+                        while let Ok(Some(alert)) = self.alerts_from_units.try_next() {
+                            alerts.push(alert);
+                        }
+                        self.handle_alerts_from_runway(alerts);
+                    }
                     None => {
                         error!(target: LOG_TARGET, "Alert stream closed.");
                         break;
@@ -184,16 +624,252 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Service<H, D, MK> {
                 message = self.rmc_service.next_message().fuse() => {
                     self.rmc_message_to_network(message);
                 },
+                _ = wait_for(&mut self.signing_retry).fuse() => {
+                    self.retry_signing();
+                },
+                _ = wait_for(&mut self.throttle_release).fuse() => {
+                    self.release_throttled_alerts();
+                },
+                _ = wait_for(&mut self.legit_units_retry).fuse() => {
+                    self.retry_legit_units_requests();
+                },
                 _ = terminator.get_exit().fuse() => {
                     debug!(target: LOG_TARGET, "Received exit signal.");
                     self.exiting = true;
                 },
             }
-            if self.exiting {
-                debug!(target: LOG_TARGET, "Alerter decided to exit.");
-                terminator.terminate_sync().await;
-                break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        alerts::{handler::Handler, service::Service, Alert, AlertMessage, IO},
+        drops::{drop_monitor, DropMonitor, DropReason},
+        events::EventBus,
+        misconduct::MisconductMonitor,
+        units::{ControlHash, FullUnit, PreUnit},
+        ClockSource, Hasher, NodeCount, NodeIndex, NodeMap, Recipient, Role, Signable, Signed,
+        Terminator,
+    };
+    use aleph_bft_mock::{CountingVerification, Data, Hasher64, Keychain};
+    use aleph_bft_rmc::Message as RmcMessage;
+    use futures::channel::{mpsc, oneshot};
+    use std::{collections::HashMap, time::Duration};
+    use tokio::time::timeout;
+
+    const QUEUED_MESSAGES: usize = 100_000;
+    const EXIT_BOUND: Duration = Duration::from_millis(100);
+    const STALE_MESSAGES: usize = 500;
+
+    #[tokio::test]
+    async fn exits_without_draining_the_queue() {
+        let keychain = Keychain::new(NodeCount(4), NodeIndex(0));
+        let (messages_for_network, _messages) = mpsc::unbounded();
+        let (messages_for_service, messages_from_network) = mpsc::unbounded();
+        let (notifications_for_units, _notifications) = mpsc::unbounded();
+        let (_alerts, alerts_from_units) = mpsc::unbounded();
+        let io = IO {
+            messages_for_network,
+            messages_from_network,
+            notifications_for_units,
+            alerts_from_units,
+            events: EventBus::new(),
+            clock: ClockSource::default(),
+            alert_rate_limit: None,
+            drops: DropMonitor::default(),
+            role: Role::Member,
+            rmc_completion_replies: false,
+            misconduct: MisconductMonitor::default(),
+        };
+        let mut service: Service<Hasher64, Data, _> =
+            Service::new(keychain.clone(), io, Handler::new(keychain, 0));
+
+        let hash = Hasher64::hash(b"unknown alert");
+        for _ in 0..QUEUED_MESSAGES {
+            messages_for_service
+                .unbounded_send(AlertMessage::AlertRequest(NodeIndex(1), hash))
+                .expect("the service is not running yet");
+        }
+        let (exit_tx, exit_rx) = oneshot::channel();
+        exit_tx.send(()).expect("the receiver is alive");
+
+        timeout(
+            EXIT_BOUND,
+            service.run(Terminator::create_root(exit_rx, "alerter")),
+        )
+        .await
+        .expect("the service should exit right away");
+        let mut unhandled = 0;
+        while let Ok(Some(_)) = service.messages_from_network.try_next() {
+            unhandled += 1;
+        }
+        assert!(unhandled >= QUEUED_MESSAGES - 1);
+    }
+
+    fn stale_signatures_of_completed_rmc(replies: bool) {
+        let n_members = NodeCount(4);
+        let own_index = NodeIndex(0);
+        let forker_index = NodeIndex(3);
+        let keychains: Vec<_> = n_members
+            .into_iterator()
+            .map(|node_ix| Keychain::new(n_members, node_ix))
+            .collect();
+        let keychain = CountingVerification::new(keychains[own_index.0].clone());
+        let (messages_for_network, mut messages) = mpsc::unbounded();
+        let (_messages_for_service, messages_from_network) = mpsc::unbounded();
+        let (notifications_for_units, _notifications) = mpsc::unbounded();
+        let (_alerts, alerts_from_units) = mpsc::unbounded();
+        let (drop_stats, drops) = drop_monitor();
+        let io = IO {
+            messages_for_network,
+            messages_from_network,
+            notifications_for_units,
+            alerts_from_units,
+            events: EventBus::new(),
+            clock: ClockSource::default(),
+            alert_rate_limit: None,
+            drops,
+            role: Role::Member,
+            rmc_completion_replies: replies,
+            misconduct: MisconductMonitor::default(),
+        };
+        let mut service: Service<Hasher64, Data, _> =
+            Service::new(keychain.clone(), io, Handler::new(keychain.clone(), 0));
+
+        let fork_proof = [0, 1].map(|variant| {
+            let control_hash = ControlHash::new(&NodeMap::with_size(n_members));
+            let unit = FullUnit::new(
+                PreUnit::new(forker_index, 0, control_hash),
+                Some(variant),
+                0,
+            );
+            Signed::sign(unit, &keychains[forker_index.0])
+                .expect("the keychain never fails")
+                .into_unchecked()
+        });
+        let alert = Alert::new(NodeIndex(1), fork_proof.into(), vec![]);
+        let hash = Signable::hash(&alert);
+        let alert = Signed::sign(alert, &keychains[1])
+            .expect("the keychain never fails")
+            .into_unchecked();
+        service.handle_message_from_network(AlertMessage::ForkAlert(alert));
+        let signature = |node_ix: NodeIndex| {
+            let signed = Signed::sign_with_index(hash, &keychains[node_ix.0])
+                .expect("the keychain never fails");
+            RmcMessage::SignedHash(signed.into_unchecked())
+        };
+        for node_ix in [NodeIndex(1), NodeIndex(2)] {
+            service
+                .handle_message_from_network(AlertMessage::RmcMessage(node_ix, signature(node_ix)));
+        }
+        while let Ok(Some(_)) = messages.try_next() {}
+
+        let verifications = keychain.verifications();
+        // Peers keep sending their signatures, some claiming to be nodes outside the committee.
+        for i in 0..STALE_MESSAGES {
+            let sender = NodeIndex(1 + i % 6);
+            let message = signature(NodeIndex(1 + i % 3));
+            service.handle_message_from_network(AlertMessage::RmcMessage(sender, message));
+        }
+        assert_eq!(keychain.verifications(), verifications);
+        assert_eq!(
+            drop_stats.stats().count(DropReason::CompletedRmc),
+            STALE_MESSAGES
+        );
+        let mut replies_to = HashMap::new();
+        while let Ok(Some((message, recipient))) = messages.try_next() {
+            match (message, recipient) {
+                (
+                    AlertMessage::RmcMessage(_, RmcMessage::MultisignedHash(multisigned)),
+                    Recipient::Node(node_ix),
+                ) => {
+                    assert_eq!(multisigned.as_signable(), &hash);
+                    *replies_to.entry(node_ix).or_insert(0) += 1;
+                }
+                (message, recipient) => panic!("unexpected {:?} to {:?}", message, recipient),
             }
         }
+        let expected = match replies {
+            true => (1..n_members.0)
+                .map(|node_ix| (NodeIndex(node_ix), 1))
+                .collect(),
+            false => HashMap::new(),
+        };
+        assert_eq!(replies_to, expected);
+    }
+
+    #[test]
+    fn drops_stale_signatures_of_completed_rmc() {
+        stale_signatures_of_completed_rmc(false);
+    }
+
+    #[test]
+    fn answers_stale_signatures_of_completed_rmc_once() {
+        stale_signatures_of_completed_rmc(true);
+    }
+
+    /// The forkers of every alert raised about two forkers detected at once.
+    fn raised_alerts(aggregated_alerts: bool) -> Vec<Vec<NodeIndex>> {
+        let n_members = NodeCount(4);
+        let own_index = NodeIndex(0);
+        let forkers = [NodeIndex(2), NodeIndex(3)];
+        let keychains: Vec<_> = n_members
+            .into_iterator()
+            .map(|node_ix| Keychain::new(n_members, node_ix))
+            .collect();
+        let (messages_for_network, mut messages) = mpsc::unbounded();
+        let (_messages_for_service, messages_from_network) = mpsc::unbounded();
+        let (notifications_for_units, _notifications) = mpsc::unbounded();
+        let (_alerts, alerts_from_units) = mpsc::unbounded();
+        let io = IO {
+            messages_for_network,
+            messages_from_network,
+            notifications_for_units,
+            alerts_from_units,
+            events: EventBus::new(),
+            clock: ClockSource::default(),
+            alert_rate_limit: None,
+            drops: DropMonitor::default(),
+            role: Role::Member,
+            rmc_completion_replies: false,
+            misconduct: MisconductMonitor::default(),
+        };
+        let keychain = keychains[own_index.0].clone();
+        let handler = Handler::new(keychain.clone(), 0).with_aggregated_alerts(aggregated_alerts);
+        let mut service: Service<Hasher64, Data, _> = Service::new(keychain, io, handler);
+
+        let alerts = forkers
+            .iter()
+            .map(|forker| {
+                let fork_proof = [0, 1].map(|variant| {
+                    let control_hash = ControlHash::new(&NodeMap::with_size(n_members));
+                    let unit =
+                        FullUnit::new(PreUnit::new(*forker, 0, control_hash), Some(variant), 0);
+                    Signed::sign(unit, &keychains[forker.0])
+                        .expect("the keychain never fails")
+                        .into_unchecked()
+                });
+                Alert::new(own_index, fork_proof.into(), vec![])
+            })
+            .collect();
+        service.handle_alerts_from_runway(alerts);
+        let mut raised = Vec::new();
+        while let Ok(Some((message, _))) = messages.try_next() {
+            if let AlertMessage::ForkAlert(alert) = message {
+                raised.push(alert.as_signable().forkers());
+            }
+        }
+        raised
+    }
+
+    #[test]
+    fn raises_alerts_separately_unless_aggregating() {
+        assert_eq!(
+            raised_alerts(false),
+            vec![vec![NodeIndex(2)], vec![NodeIndex(3)]]
+        );
+        assert_eq!(raised_alerts(true), vec![vec![NodeIndex(2), NodeIndex(3)]]);
     }
 }
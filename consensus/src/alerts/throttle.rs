@@ -0,0 +1,171 @@
+use crate::{AlertRateLimit, NodeIndex};
+use std::{
+    cmp::max,
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// The window the per minute cap of the [`AlertRateLimit`] applies to.
+pub(crate) const ALERT_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Holds back our own alerts raised over the [`AlertRateLimit`]. The queue keeps at most one
+/// alert per forker and releases them in the order they came in, so below the session cap the
+/// throttle only limits how fast we alert, never about whom.
+pub struct AlertThrottle<A> {
+    limit: AlertRateLimit,
+    raised_at: VecDeque<Instant>,
+    raised: usize,
+    queue: VecDeque<(NodeIndex, A)>,
+}
+
+impl<A> AlertThrottle<A> {
+    pub fn new(limit: AlertRateLimit) -> Self {
+        AlertThrottle {
+            limit,
+            raised_at: VecDeque::new(),
+            raised: 0,
+            queue: VecDeque::new(),
+        }
+    }
+
+    fn forget_old(&mut self, now: Instant) {
+        while let Some(raised_at) = self.raised_at.front() {
+            if now.saturating_duration_since(*raised_at) < ALERT_RATE_WINDOW {
+                break;
+            }
+            self.raised_at.pop_front();
+        }
+    }
+
+    fn can_raise(&self) -> bool {
+        self.raised < self.limit.per_session && self.raised_at.len() < self.limit.per_minute
+    }
+
+    fn on_raised(&mut self, now: Instant) {
+        self.raised += 1;
+        self.raised_at.push_back(now);
+    }
+
+    /// Returns the alert about the forker if it can be raised at the given time, counting it as
+    /// raised. Otherwise the alert is queued, unless one about the same forker already is.
+    pub fn submit(&mut self, forker: NodeIndex, alert: A, now: Instant) -> Option<A> {
+        self.forget_old(now);
+        if self.queue.is_empty() && self.can_raise() {
+            self.on_raised(now);
+            return Some(alert);
+        }
+        if !self.is_queued(forker) {
+            self.queue.push_back((forker, alert));
+        }
+        None
+    }
+
+    /// Whether an alert about the forker is waiting in the queue.
+    pub fn is_queued(&self, forker: NodeIndex) -> bool {
+        self.queue.iter().any(|(queued, _)| *queued == forker)
+    }
+
+    /// Takes the queued alerts that can be raised at the given time, counting them as raised.
+    pub fn release(&mut self, now: Instant) -> Vec<A> {
+        self.forget_old(now);
+        let mut released = Vec::new();
+        while self.can_raise() {
+            match self.queue.pop_front() {
+                Some((_, alert)) => {
+                    self.on_raised(now);
+                    released.push(alert);
+                }
+                None => break,
+            }
+        }
+        released
+    }
+
+    /// Whether some alerts are being held back.
+    pub fn is_throttling(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    /// When the next queued alert can be released, if ever in this session.
+    pub fn next_release(&self, now: Instant) -> Option<Instant> {
+        if self.queue.is_empty() || self.raised >= self.limit.per_session {
+            return None;
+        }
+        let release = match self.raised_at.len().checked_sub(self.limit.per_minute) {
+            Some(expiring) => self.raised_at[expiring] + ALERT_RATE_WINDOW,
+            None => now,
+        };
+        Some(max(release, now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        alerts::throttle::{AlertThrottle, ALERT_RATE_WINDOW},
+        AlertRateLimit, NodeIndex,
+    };
+    use std::time::{Duration, Instant};
+
+    const LIMIT: AlertRateLimit = AlertRateLimit {
+        per_minute: 3,
+        per_session: 5,
+    };
+
+    #[test]
+    fn queues_alerts_over_the_minute_cap() {
+        let mut throttle = AlertThrottle::new(LIMIT);
+        let start = Instant::now();
+        for forker in 0..3 {
+            assert_eq!(
+                throttle.submit(NodeIndex(forker), forker, start),
+                Some(forker)
+            );
+        }
+        assert!(!throttle.is_throttling());
+        for forker in 3..6 {
+            assert_eq!(throttle.submit(NodeIndex(forker), forker, start), None);
+        }
+        assert!(throttle.is_throttling());
+        assert!(throttle.release(start + ALERT_RATE_WINDOW / 2).is_empty());
+        assert_eq!(
+            throttle.next_release(start),
+            Some(start + ALERT_RATE_WINDOW)
+        );
+        assert_eq!(throttle.release(start + ALERT_RATE_WINDOW), vec![3, 4]);
+        assert!(throttle.is_throttling());
+    }
+
+    #[test]
+    fn keeps_one_queued_alert_per_forker() {
+        let mut throttle = AlertThrottle::new(LIMIT);
+        let start = Instant::now();
+        for forker in 0..3 {
+            throttle.submit(NodeIndex(forker), forker, start);
+        }
+        assert_eq!(throttle.submit(NodeIndex(7), 7, start), None);
+        assert_eq!(throttle.submit(NodeIndex(7), 8, start), None);
+        assert_eq!(throttle.submit(NodeIndex(9), 9, start), None);
+        assert!(throttle.is_queued(NodeIndex(7)));
+        assert_eq!(throttle.release(start + ALERT_RATE_WINDOW), vec![7, 9]);
+        assert!(!throttle.is_throttling());
+    }
+
+    #[test]
+    fn holds_alerts_over_the_session_cap() {
+        let mut throttle = AlertThrottle::new(LIMIT);
+        let mut now = Instant::now();
+        for forker in 0..LIMIT.per_session {
+            if throttle.submit(NodeIndex(forker), forker, now).is_none() {
+                now += ALERT_RATE_WINDOW;
+                assert_eq!(throttle.release(now), vec![forker]);
+            }
+        }
+        assert_eq!(throttle.submit(NodeIndex(10), 10, now), None);
+        assert_eq!(throttle.next_release(now), None);
+        assert!(throttle
+            .release(now + 10 * ALERT_RATE_WINDOW + Duration::from_secs(1))
+            .is_empty());
+        assert!(throttle.is_throttling());
+    }
+}
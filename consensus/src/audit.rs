@@ -0,0 +1,266 @@
+use crate::{
+    creation::InclusionChange,
+    events::{InternalEvent, NetworkState},
+    units::Unit,
+    Data, Hasher, NodeIndex, Round, Signature,
+};
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+    time::Instant,
+};
+
+/// How many entries the audit log keeps, older ones are only counted.
+const AUDIT_LOG_CAPACITY: usize = 1000;
+
+/// A decision that affected how the session behaves.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AuditRecord {
+    /// The effective configuration the session started with, as rendered by
+    /// [`crate::Config::describe`].
+    Config(String),
+    /// The creator stopped attaching data to our units starting with the given round, as they
+    /// finalized the given number of rounds late.
+    DataHeldBack { round: Round, latency: Round },
+    /// The creator attached data to our units again starting with the given round, as they
+    /// finalized only the given number of rounds late.
+    DataResumed { round: Round, latency: Round },
+    /// Our alert about the given forker is held back, as we raised too many alerts recently.
+    AlertQueued(NodeIndex),
+    /// We started or stopped holding back our own alerts.
+    AlertThrottling(bool),
+    /// The network temporarily yields no events.
+    NetworkInterrupted,
+    /// The network yields events again.
+    NetworkRecovered,
+    /// The network got closed or did not recover in time.
+    NetworkClosed,
+    /// The session got frozen for a migration.
+    SessionFrozen,
+}
+
+impl AuditRecord {
+    /// A short name of the kind of the record, used to summarize the dropped entries.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AuditRecord::Config(_) => "config",
+            AuditRecord::DataHeldBack { .. } => "data held back",
+            AuditRecord::DataResumed { .. } => "data resumed",
+            AuditRecord::AlertQueued(_) => "alert queued",
+            AuditRecord::AlertThrottling(_) => "alert throttling",
+            AuditRecord::NetworkInterrupted => "network interrupted",
+            AuditRecord::NetworkRecovered => "network recovered",
+            AuditRecord::NetworkClosed => "network closed",
+            AuditRecord::SessionFrozen => "session frozen",
+        }
+    }
+}
+
+/// A decision together with the moment it was made.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuditEntry {
+    pub at: Instant,
+    pub record: AuditRecord,
+}
+
+#[derive(Default)]
+struct AuditLog {
+    config: Option<AuditEntry>,
+    entries: VecDeque<AuditEntry>,
+    dropped: BTreeMap<&'static str, usize>,
+}
+
+/// Allows the application to review the decisions that affected the behavior of the session,
+/// e.g. after an incident.
+#[derive(Clone)]
+pub struct AuditLogHandle {
+    log: Arc<Mutex<AuditLog>>,
+}
+
+impl AuditLogHandle {
+    /// The entries in the order they were made, starting with the configuration. Only the
+    /// latest entries are kept, apart from the configuration, see [`AuditLogHandle::dropped`].
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        let log = self.log.lock();
+        log.config
+            .iter()
+            .chain(log.entries.iter())
+            .cloned()
+            .collect()
+    }
+
+    /// How many entries of each kind were dropped to keep the log bounded.
+    pub fn dropped(&self) -> BTreeMap<&'static str, usize> {
+        self.log.lock().dropped.clone()
+    }
+}
+
+/// The part of the audit log passed to the session, see [`audit_log`].
+#[derive(Clone, Default)]
+pub struct AuditLogMonitor {
+    log: Arc<Mutex<AuditLog>>,
+}
+
+impl AuditLogMonitor {
+    pub(crate) fn record(&self, at: Instant, record: AuditRecord) {
+        let entry = AuditEntry { at, record };
+        let mut log = self.log.lock();
+        if let AuditRecord::Config(_) = entry.record {
+            log.config = Some(entry);
+            return;
+        }
+        if log.entries.len() == AUDIT_LOG_CAPACITY {
+            if let Some(oldest) = log.entries.pop_front() {
+                *log.dropped.entry(oldest.record.kind()).or_default() += 1;
+            }
+        }
+        log.entries.push_back(entry);
+    }
+
+    /// Records the decision the event reports, if it reports any.
+    pub(crate) fn on_event<H: Hasher, D: Data, S: Signature>(
+        &self,
+        at: Instant,
+        event: &InternalEvent<H, D, S>,
+    ) {
+        let record = match event {
+            InternalEvent::DataInclusionChanged(round, InclusionChange::Paused(latency)) => {
+                AuditRecord::DataHeldBack {
+                    round: *round,
+                    latency: *latency,
+                }
+            }
+            InternalEvent::DataInclusionChanged(round, InclusionChange::Resumed(latency)) => {
+                AuditRecord::DataResumed {
+                    round: *round,
+                    latency: *latency,
+                }
+            }
+            InternalEvent::AlertQueued((unit, _)) => {
+                AuditRecord::AlertQueued(unit.as_signable().creator())
+            }
+            InternalEvent::AlertThrottlingChanged(throttled) => {
+                AuditRecord::AlertThrottling(*throttled)
+            }
+            InternalEvent::NetworkStateChanged(NetworkState::Interrupted) => {
+                AuditRecord::NetworkInterrupted
+            }
+            InternalEvent::NetworkStateChanged(NetworkState::Recovered) => {
+                AuditRecord::NetworkRecovered
+            }
+            InternalEvent::NetworkStateChanged(NetworkState::Closed) => AuditRecord::NetworkClosed,
+            InternalEvent::SessionFrozen => AuditRecord::SessionFrozen,
+            _ => return,
+        };
+        self.record(at, record);
+    }
+}
+
+/// Creates a handle for reviewing the decisions made in a session together with the monitor
+/// that should be passed to the session with [`crate::LocalIO::with_audit_log`].
+pub fn audit_log() -> (AuditLogHandle, AuditLogMonitor) {
+    let log = Arc::new(Mutex::new(AuditLog::default()));
+    (AuditLogHandle { log: log.clone() }, AuditLogMonitor { log })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        audit::{audit_log, AuditRecord, AUDIT_LOG_CAPACITY},
+        creation::InclusionChange,
+        events::{InternalEvent, NetworkState},
+        units::{ControlHash, FullUnit, PreUnit, UnitCoord},
+        NodeCount, NodeIndex, NodeMap, Signed,
+    };
+    use aleph_bft_mock::{Data, Hasher64, Keychain, Signature};
+    use std::time::{Duration, Instant};
+
+    type TestEvent = InternalEvent<Hasher64, Data, Signature>;
+
+    fn fork_proof_against(forker: NodeIndex) -> TestEvent {
+        let n_members = NodeCount(4);
+        let keychain = Keychain::new(n_members, forker);
+        let unit = |data| {
+            let control_hash = ControlHash::new(&NodeMap::with_size(n_members));
+            let full_unit = FullUnit::new(PreUnit::new(forker, 0, control_hash), Some(data), 0);
+            Signed::sign(full_unit, &keychain)
+                .expect("the keychain never fails")
+                .into_unchecked()
+        };
+        InternalEvent::AlertQueued((unit(1), unit(2)))
+    }
+
+    #[test]
+    fn records_decisions_in_order() {
+        let (handle, monitor) = audit_log();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        monitor.record(at(0), AuditRecord::Config("max round: 5000".to_string()));
+        let events = vec![
+            InternalEvent::DataInclusionChanged(7, InclusionChange::Paused(10)),
+            InternalEvent::UnitFinalized(Default::default(), UnitCoord::new(0, NodeIndex(0))),
+            fork_proof_against(NodeIndex(2)),
+            InternalEvent::AlertThrottlingChanged(true),
+            InternalEvent::NetworkStateChanged(NetworkState::Interrupted),
+            InternalEvent::NetworkStateChanged(NetworkState::Recovered),
+            InternalEvent::DataInclusionChanged(12, InclusionChange::Resumed(6)),
+        ];
+        for (secs, event) in events.iter().enumerate() {
+            monitor.on_event(at(secs as u64 + 1), event);
+        }
+
+        let entries = handle.entries();
+        let records: Vec<_> = entries.iter().map(|entry| entry.record.clone()).collect();
+        assert_eq!(
+            records,
+            vec![
+                AuditRecord::Config("max round: 5000".to_string()),
+                AuditRecord::DataHeldBack {
+                    round: 7,
+                    latency: 10
+                },
+                AuditRecord::AlertQueued(NodeIndex(2)),
+                AuditRecord::AlertThrottling(true),
+                AuditRecord::NetworkInterrupted,
+                AuditRecord::NetworkRecovered,
+                AuditRecord::DataResumed {
+                    round: 12,
+                    latency: 6
+                },
+            ]
+        );
+        let times: Vec<_> = entries.iter().map(|entry| entry.at).collect();
+        assert_eq!(times, vec![at(0), at(1), at(3), at(4), at(5), at(6), at(7)]);
+        assert!(handle.dropped().is_empty());
+    }
+
+    #[test]
+    fn summarizes_dropped_entries_but_keeps_config() {
+        let (handle, monitor) = audit_log();
+        let now = Instant::now();
+        monitor.record(now, AuditRecord::Config("session id: 0".to_string()));
+        for _ in 0..3 {
+            monitor.record(now, AuditRecord::NetworkInterrupted);
+        }
+        for _ in 0..AUDIT_LOG_CAPACITY {
+            monitor.record(now, AuditRecord::AlertThrottling(true));
+        }
+        monitor.record(now, AuditRecord::NetworkClosed);
+
+        let entries = handle.entries();
+        assert_eq!(entries.len(), AUDIT_LOG_CAPACITY + 1);
+        assert_eq!(
+            entries[0].record,
+            AuditRecord::Config("session id: 0".to_string())
+        );
+        assert_eq!(
+            entries.last().map(|entry| &entry.record),
+            Some(&AuditRecord::NetworkClosed)
+        );
+        let dropped = handle.dropped();
+        assert_eq!(dropped.get("network interrupted"), Some(&3));
+        assert_eq!(dropped.get("alert throttling"), Some(&1));
+        assert_eq!(dropped.len(), 2);
+    }
+}
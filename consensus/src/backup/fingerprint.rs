@@ -0,0 +1,123 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use codec::{Decode, Encode, Error as CodecError};
+
+use crate::{dag::RejectionReason, protocol::PROTOCOL_VERSION, Hasher, NodeCount, SessionId};
+
+/// Marks the header at the start of a backup. Encoded units start with the index of their
+/// creator, and no committee is large enough for an index encoded like this.
+const HEADER_MAGIC: [u8; 8] = *b"AlephBFT";
+
+/// Identifies the session and the committee a backup was written by. It is written as the first
+/// item of every new backup, so that a session run by a different committee does not load it.
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+pub struct BackupFingerprint<H: Hasher> {
+    session_id: SessionId,
+    n_members: u64,
+    committee: H::Hash,
+    protocol_version: u8,
+}
+
+impl<H: Hasher> BackupFingerprint<H> {
+    /// The fingerprint of the current session, see [`crate::Config::with_committee_id`].
+    pub fn new(session_id: SessionId, n_members: NodeCount, committee_id: &[u8]) -> Self {
+        BackupFingerprint {
+            session_id,
+            n_members: n_members.0 as u64,
+            committee: H::hash(committee_id),
+            protocol_version: PROTOCOL_VERSION,
+        }
+    }
+
+    /// The header to write at the start of a new backup.
+    pub fn header(&self) -> Vec<u8> {
+        (HEADER_MAGIC, self).encode()
+    }
+
+    /// Reads the header at the start of the backup, if there is one. Backups written by older
+    /// versions have none.
+    pub fn read_header(input: &mut &[u8]) -> Result<Option<Self>, CodecError> {
+        match input.strip_prefix(&HEADER_MAGIC[..]) {
+            Some(rest) => {
+                *input = rest;
+                Ok(Some(Self::decode(input)?))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl<H: Hasher> Display for BackupFingerprint<H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "session {}, {} members, committee {:?}, protocol version {}",
+            self.session_id, self.n_members, self.committee, self.protocol_version
+        )
+    }
+}
+
+/// Why a unit of a backup written by a different committee was not loaded.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DiscardReason {
+    /// The unit is not valid for the current committee.
+    Invalid(RejectionReason),
+    /// One of the parents of the unit was discarded.
+    ParentDiscarded,
+}
+
+impl Display for DiscardReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            DiscardReason::Invalid(reason) => write!(f, "invalid unit: {}", reason),
+            DiscardReason::ParentDiscarded => write!(f, "a parent was discarded"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{backup::fingerprint::BackupFingerprint, NodeCount};
+    use aleph_bft_mock::Hasher64;
+
+    #[test]
+    fn header_is_read_back() {
+        let fingerprint = BackupFingerprint::<Hasher64>::new(43, NodeCount(4), b"committee");
+        let mut backup = fingerprint.header();
+        backup.extend([1, 2, 3]);
+        let input = &mut &backup[..];
+        assert_eq!(
+            BackupFingerprint::read_header(input).expect("the header is correct"),
+            Some(fingerprint)
+        );
+        assert_eq!(*input, &[1, 2, 3]);
+    }
+
+    #[test]
+    fn backup_without_header_is_left_intact() {
+        let backup = [0; 16];
+        let input = &mut &backup[..];
+        assert_eq!(
+            BackupFingerprint::<Hasher64>::read_header(input).expect("there is no header"),
+            None
+        );
+        assert_eq!(input.len(), 16);
+    }
+
+    #[test]
+    fn fingerprints_differ_between_committees() {
+        let fingerprint = BackupFingerprint::<Hasher64>::new(43, NodeCount(4), b"committee");
+        assert_ne!(
+            fingerprint,
+            BackupFingerprint::new(43, NodeCount(4), b"reshuffled")
+        );
+        assert_ne!(
+            fingerprint,
+            BackupFingerprint::new(43, NodeCount(5), b"committee")
+        );
+        assert_ne!(
+            fingerprint,
+            BackupFingerprint::new(44, NodeCount(4), b"committee")
+        );
+    }
+}
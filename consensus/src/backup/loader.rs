@@ -5,27 +5,50 @@ use std::{
     pin::Pin,
 };
 
-use codec::{Decode, Error as CodecError};
+use codec::{DecodeLimit, Error as CodecError};
 use futures::{channel::oneshot, AsyncRead, AsyncReadExt};
 use log::{error, info, warn};
 
 use crate::{
-    units::{UncheckedSignedUnit, Unit, UnitCoord},
-    Data, Hasher, NodeIndex, Round, SessionId, Signature,
+    backup::{BackupFingerprint, DiscardReason},
+    callbacks::{CallbackGuard, CallbackPanicked, UserComponent},
+    dag::RejectionReason,
+    decoding::MAX_DECODE_DEPTH,
+    units::{UncheckedSignedUnit, Unit, UnitCoord, Validator},
+    Data, Hasher, Keychain, NodeIndex, Round, SessionId, Signature,
 };
 
 const LOG_TARGET: &str = "AlephBFT-backup-loader";
 
+/// The fingerprint and units of a backup, decoded. Decoding never panics, and the units are
+/// limited in depth like the messages from the network, see [`crate::MAX_DECODE_DEPTH`].
+pub(crate) fn decode_backup<H: Hasher, D: Data, S: Signature>(
+    backup: &[u8],
+) -> Result<DecodedBackup<H, D, S>, CodecError> {
+    let input = &mut &backup[..];
+    let fingerprint = BackupFingerprint::read_header(input)?;
+    let mut units = Vec::new();
+    while !input.is_empty() {
+        units.push(UncheckedSignedUnit::decode_with_depth_limit(
+            MAX_DECODE_DEPTH,
+            input,
+        )?);
+    }
+    Ok((fingerprint, units))
+}
+
 /// Backup read error. Could be either caused by io error from `BackupReader`, or by decoding.
 #[derive(Debug)]
-enum LoaderError {
+enum LoaderError<H: Hasher> {
     IO(std::io::Error),
     Codec(CodecError),
     InconsistentData(UnitCoord),
     WrongSession(UnitCoord, SessionId, SessionId),
+    WrongFingerprint(BackupFingerprint<H>, BackupFingerprint<H>),
+    ReaderPanicked,
 }
 
-impl fmt::Display for LoaderError {
+impl<H: Hasher> fmt::Display for LoaderError<H> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             LoaderError::IO(err) => {
@@ -52,51 +75,179 @@ impl fmt::Display for LoaderError {
                     coord.round(), coord.creator(), expected_session, actual_session
                 )
             }
+            LoaderError::WrongFingerprint(expected, found) => {
+                write!(
+                    f,
+                    "the backup was written by a different committee. Expected: {} got: {}. Enable backup migration to load the units still valid for this committee.",
+                    expected, found
+                )
+            }
+            LoaderError::ReaderPanicked => write!(f, "the backup reader panicked"),
         }
     }
 }
 
-impl From<std::io::Error> for LoaderError {
+impl<H: Hasher> From<std::io::Error> for LoaderError<H> {
     fn from(err: std::io::Error) -> Self {
         Self::IO(err)
     }
 }
 
-impl From<CodecError> for LoaderError {
+impl<H: Hasher> From<CallbackPanicked> for LoaderError<H> {
+    fn from(_: CallbackPanicked) -> Self {
+        Self::ReaderPanicked
+    }
+}
+
+impl<H: Hasher> From<CodecError> for LoaderError<H> {
     fn from(err: CodecError) -> Self {
         Self::Codec(err)
     }
 }
 
-pub struct BackupLoader<H: Hasher, D: Data, S: Signature, R: AsyncRead> {
+type LoadedUnits<H, D, K> = Vec<UncheckedSignedUnit<H, D, <K as Keychain>::Signature>>;
+type DecodedBackup<H, D, S> = (
+    Option<BackupFingerprint<H>>,
+    Vec<UncheckedSignedUnit<H, D, S>>,
+);
+
+pub struct BackupLoader<H: Hasher, D: Data, K: Keychain, R: AsyncRead> {
     backup: Pin<Box<R>>,
     index: NodeIndex,
     session_id: SessionId,
-    _phantom: PhantomData<(H, D, S)>,
+    fingerprint: BackupFingerprint<H>,
+    validator: Validator<K>,
+    migrate: bool,
+    callbacks: CallbackGuard,
+    _phantom: PhantomData<D>,
 }
 
-impl<H: Hasher, D: Data, S: Signature, R: AsyncRead> BackupLoader<H, D, S, R> {
-    pub fn new(backup: R, index: NodeIndex, session_id: SessionId) -> BackupLoader<H, D, S, R> {
+impl<H: Hasher, D: Data, K: Keychain, R: AsyncRead> BackupLoader<H, D, K, R> {
+    /// The loader of the backup of the session with the given fingerprint. The validator is only
+    /// used when migrating a backup written by a different committee.
+    pub fn new(
+        backup: R,
+        fingerprint: BackupFingerprint<H>,
+        validator: Validator<K>,
+        callbacks: CallbackGuard,
+    ) -> BackupLoader<H, D, K, R> {
         BackupLoader {
             backup: Box::pin(backup),
-            index,
-            session_id,
+            index: validator.index(),
+            session_id: validator.session_id(),
+            fingerprint,
+            validator,
+            migrate: false,
+            callbacks,
             _phantom: PhantomData,
         }
     }
 
-    async fn load(&mut self) -> Result<Vec<UncheckedSignedUnit<H, D, S>>, LoaderError> {
+    /// Makes the loader migrate a backup written by a different committee instead of refusing
+    /// it, see [`crate::Config::with_migrate_backup`].
+    pub fn with_migration(self, migrate: bool) -> Self {
+        BackupLoader { migrate, ..self }
+    }
+
+    async fn load(
+        &mut self,
+    ) -> Result<(Option<BackupFingerprint<H>>, LoadedUnits<H, D, K>), LoaderError<H>> {
         let mut buf = Vec::new();
-        self.backup.read_to_end(&mut buf).await?;
-        let input = &mut &buf[..];
-        let mut result = Vec::new();
-        while !input.is_empty() {
-            result.push(<UncheckedSignedUnit<H, D, S>>::decode(input)?);
+        self.callbacks
+            .call_async(
+                UserComponent::BackupReader,
+                self.backup.read_to_end(&mut buf),
+            )
+            .await??;
+        Ok(decode_backup(&buf)?)
+    }
+
+    /// Checks that the backup was written by the current committee. A backup written by a
+    /// different one is refused, unless migration is enabled, in which case only the units that
+    /// are still valid are kept.
+    fn check_fingerprint(
+        &self,
+        fingerprint: Option<BackupFingerprint<H>>,
+        units: LoadedUnits<H, D, K>,
+    ) -> Result<LoadedUnits<H, D, K>, LoaderError<H>> {
+        let fingerprint = match fingerprint {
+            Some(fingerprint) => fingerprint,
+            None => {
+                if !units.is_empty() {
+                    warn!(
+                        target: LOG_TARGET,
+                        "The backup has no fingerprint, it was written by an older version. Loading it unchecked."
+                    );
+                }
+                return Ok(units);
+            }
+        };
+        if fingerprint == self.fingerprint {
+            return Ok(units);
+        }
+        if !self.migrate {
+            return Err(LoaderError::WrongFingerprint(
+                self.fingerprint.clone(),
+                fingerprint,
+            ));
+        }
+        warn!(
+            target: LOG_TARGET,
+            "Migrating a backup written by a different committee. Expected: {} got: {}.",
+            self.fingerprint,
+            fingerprint
+        );
+        let (units, discarded) = self.migrate_units(units);
+        for (coord, reason) in &discarded {
+            warn!(
+                target: LOG_TARGET,
+                "Discarded the unit from round {:?} of creator {:?}: {}.",
+                coord.round(),
+                coord.creator(),
+                reason
+            );
+        }
+        info!(
+            target: LOG_TARGET,
+            "Migrated the backup, keeping {:?} units and discarding {:?}.",
+            units.len(),
+            discarded.len()
+        );
+        Ok(units)
+    }
+
+    /// Keeps the units that are valid for the current committee and all of whose parents are
+    /// kept, returning the rest together with the reason they were discarded.
+    fn migrate_units(
+        &self,
+        units: LoadedUnits<H, D, K>,
+    ) -> (LoadedUnits<H, D, K>, Vec<(UnitCoord, DiscardReason)>) {
+        let mut kept_coords = HashSet::new();
+        let mut kept = Vec::new();
+        let mut discarded = Vec::new();
+        for unit in units {
+            let coord = unit.as_signable().coord();
+            if let Err(e) = self.validator.validate_unit(unit.clone()) {
+                discarded.push((coord, DiscardReason::Invalid(RejectionReason::from(&e))));
+                continue;
+            }
+            if unit
+                .as_signable()
+                .as_pre_unit()
+                .control_hash()
+                .parents()
+                .any(|parent| !kept_coords.contains(&parent))
+            {
+                discarded.push((coord, DiscardReason::ParentDiscarded));
+                continue;
+            }
+            kept_coords.insert(coord);
+            kept.push(unit);
         }
-        Ok(result)
+        (kept, discarded)
     }
 
-    fn verify_units(&self, units: &Vec<UncheckedSignedUnit<H, D, S>>) -> Result<(), LoaderError> {
+    fn verify_units(&self, units: &LoadedUnits<H, D, K>) -> Result<(), LoaderError<H>> {
         let mut already_loaded_coords = HashSet::new();
 
         for unit in units {
@@ -159,14 +310,26 @@ impl<H: Hasher, D: Data, S: Signature, R: AsyncRead> BackupLoader<H, D, S, R> {
         Some(next_round_backup)
     }
 
+    /// Loads the backup, telling the saver whether it is new, i.e. empty, in which case the
+    /// saver starts it with the fingerprint.
     pub async fn run(
         &mut self,
-        loaded_data: oneshot::Sender<Vec<UncheckedSignedUnit<H, D, S>>>,
+        loaded_data: oneshot::Sender<LoadedUnits<H, D, K>>,
+        new_backup: oneshot::Sender<bool>,
         starting_round: oneshot::Sender<Option<Round>>,
         next_round_collection: oneshot::Receiver<Round>,
     ) {
-        let units = match self.load().await {
-            Ok(items) => items,
+        let (fingerprint, units) = match self.load().await {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                error!(target: LOG_TARGET, "unable to load backup data: {}", e);
+                self.on_shutdown(starting_round);
+                return;
+            }
+        };
+        let is_new = fingerprint.is_none() && units.is_empty();
+        let units = match self.check_fingerprint(fingerprint, units) {
+            Ok(units) => units,
             Err(e) => {
                 error!(target: LOG_TARGET, "unable to load backup data: {}", e);
                 self.on_shutdown(starting_round);
@@ -194,6 +357,9 @@ impl<H: Hasher, D: Data, S: Signature, R: AsyncRead> BackupLoader<H, D, S, R> {
             next_round_backup
         );
 
+        if new_backup.send(is_new).is_err() {
+            warn!(target: LOG_TARGET, "Could not tell the saver whether the backup is new.");
+        }
         if loaded_data.send(units).is_err() {
             error!(target: LOG_TARGET, "Could not send loaded items");
             self.on_shutdown(starting_round);
@@ -238,15 +404,17 @@ mod tests {
     use codec::Encode;
     use futures::channel::oneshot;
 
-    use aleph_bft_mock::{Data, Hasher64, Keychain, Loader, Signature};
+    use aleph_bft_mock::{BadSigning, Data, Hasher64, Keychain, Loader, Signature};
 
     use crate::{
-        backup::BackupLoader,
+        backup::{loader::LoaderError, BackupFingerprint, BackupLoader, DiscardReason},
+        callbacks::CallbackGuard,
+        dag::RejectionReason,
         units::{
             create_preunits, creator_set, preunit_to_full_unit, preunit_to_unchecked_signed_unit,
-            UncheckedSignedUnit as GenericUncheckedSignedUnit,
+            UncheckedSignedUnit as GenericUncheckedSignedUnit, Unit, UnitCoord, Validator,
         },
-        NodeCount, NodeIndex, Round, SessionId,
+        NodeCount, NodeIndex, Round, SessionId, Signed,
     };
 
     type UncheckedSignedUnit = GenericUncheckedSignedUnit<Hasher64, Data, Signature>;
@@ -260,6 +428,9 @@ mod tests {
     const SESSION_ID: SessionId = 43;
     const NODE_ID: NodeIndex = NodeIndex(0);
     const N_MEMBERS: NodeCount = NodeCount(4);
+    const CURRENT_COMMITTEE: &[u8] = b"current";
+    const OLD_COMMITTEE: &[u8] = b"old";
+    const REPLACED: NodeIndex = NodeIndex(2);
 
     fn produce_units(rounds: usize, session_id: SessionId) -> Vec<Vec<UncheckedSignedUnit>> {
         let mut creators = creator_set(N_MEMBERS);
@@ -308,28 +479,75 @@ mod tests {
         items.iter().map(|u| u.encode()).collect()
     }
 
+    fn fingerprint(committee_id: &[u8]) -> BackupFingerprint<Hasher64> {
+        BackupFingerprint::new(SESSION_ID, N_MEMBERS, committee_id)
+    }
+
+    /// The units of the current committee, except that the member with the given index was
+    /// replaced, so the units it created are not signed with the current key.
+    fn with_replaced_member(
+        units: Vec<Vec<UncheckedSignedUnit>>,
+        replaced: NodeIndex,
+    ) -> Vec<Vec<UncheckedSignedUnit>> {
+        let old_keychain: BadSigning<Keychain> = Keychain::new(N_MEMBERS, replaced).into();
+        units
+            .into_iter()
+            .map(|mut units_per_round| {
+                let unit = units_per_round[replaced.0].clone().into_signable();
+                units_per_round[replaced.0] = Signed::sign(unit, &old_keychain)
+                    .expect("the keychain never fails")
+                    .into();
+                units_per_round
+            })
+            .collect()
+    }
+
     fn prepare_test(encoded_items: Vec<u8>) -> PrepareTestResponse<impl futures::Future> {
+        prepare_test_with(encoded_items, false).0
+    }
+
+    fn prepare_test_with(
+        encoded_items: Vec<u8>,
+        migrate: bool,
+    ) -> (
+        PrepareTestResponse<impl futures::Future>,
+        oneshot::Receiver<bool>,
+    ) {
         let (loaded_data_tx, loaded_data_rx) = oneshot::channel();
+        let (new_backup_tx, new_backup_rx) = oneshot::channel();
         let (starting_round_tx, starting_round_rx) = oneshot::channel();
         let (highest_response_tx, highest_response_rx) = oneshot::channel();
 
         let task = {
-            let mut backup_loader =
-                BackupLoader::new(Loader::new(encoded_items), NODE_ID, SESSION_ID);
+            let mut backup_loader = BackupLoader::new(
+                Loader::new(encoded_items),
+                fingerprint(CURRENT_COMMITTEE),
+                Validator::new(SESSION_ID, Keychain::new(N_MEMBERS, NODE_ID), 5000),
+                CallbackGuard::default(),
+            )
+            .with_migration(migrate);
 
             async move {
                 backup_loader
-                    .run(loaded_data_tx, starting_round_tx, highest_response_rx)
+                    .run(
+                        loaded_data_tx,
+                        new_backup_tx,
+                        starting_round_tx,
+                        highest_response_rx,
+                    )
                     .await
             }
         };
 
-        PrepareTestResponse {
-            task,
-            loaded_data_rx,
-            highest_response_tx,
-            starting_round_rx,
-        }
+        (
+            PrepareTestResponse {
+                task,
+                loaded_data_rx,
+                highest_response_tx,
+                starting_round_rx,
+            },
+            new_backup_rx,
+        )
     }
 
     #[tokio::test]
@@ -585,4 +803,171 @@ mod tests {
         assert_eq!(starting_round_rx.await, Ok(None));
         assert!(loaded_data_rx.await.is_err());
     }
+
+    #[tokio::test]
+    async fn only_empty_backup_is_new() {
+        let items: Vec<_> = produce_units(2, SESSION_ID).into_iter().flatten().collect();
+        let units: Vec<u8> = encode_all(items).into_iter().flatten().collect();
+        let header = fingerprint(CURRENT_COMMITTEE).header();
+        let mut with_header = header.clone();
+        with_header.extend(&units);
+        for (backup, is_new) in [
+            (Vec::new(), true),
+            (header, false),
+            (with_header, false),
+            (units, false),
+        ] {
+            let (
+                PrepareTestResponse {
+                    task,
+                    highest_response_tx,
+                    ..
+                },
+                new_backup_rx,
+            ) = prepare_test_with(backup, false);
+
+            let handle = tokio::spawn(async {
+                task.await;
+            });
+
+            let _ = highest_response_tx.send(0);
+            handle.await.unwrap();
+
+            assert_eq!(new_backup_rx.await, Ok(is_new));
+        }
+    }
+
+    #[tokio::test]
+    async fn backup_with_matching_fingerprint_succeeds() {
+        let items: Vec<_> = produce_units(5, SESSION_ID).into_iter().flatten().collect();
+        let mut backup = fingerprint(CURRENT_COMMITTEE).header();
+        backup.extend(encode_all(items.clone()).into_iter().flatten());
+
+        let PrepareTestResponse {
+            task,
+            loaded_data_rx,
+            highest_response_tx,
+            starting_round_rx,
+        } = prepare_test(backup);
+
+        let handle = tokio::spawn(async {
+            task.await;
+        });
+
+        highest_response_tx.send(5).unwrap();
+        handle.await.unwrap();
+
+        assert_eq!(starting_round_rx.await, Ok(Some(5)));
+        assert_eq!(loaded_data_rx.await, Ok(items));
+    }
+
+    #[tokio::test]
+    async fn backup_of_other_committee_fails() {
+        let items: Vec<_> = with_replaced_member(produce_units(5, SESSION_ID), REPLACED)
+            .into_iter()
+            .flatten()
+            .collect();
+        let mut backup = fingerprint(OLD_COMMITTEE).header();
+        backup.extend(encode_all(items).into_iter().flatten());
+
+        let (
+            PrepareTestResponse {
+                task,
+                loaded_data_rx,
+                highest_response_tx,
+                starting_round_rx,
+            },
+            new_backup_rx,
+        ) = prepare_test_with(backup, false);
+
+        let handle = tokio::spawn(async {
+            task.await;
+        });
+
+        let _ = highest_response_tx.send(5);
+        handle.await.unwrap();
+
+        assert_eq!(starting_round_rx.await, Ok(None));
+        assert!(loaded_data_rx.await.is_err());
+        assert!(new_backup_rx.await.is_err());
+    }
+
+    #[test]
+    fn refusal_names_both_fingerprints() {
+        let error = LoaderError::WrongFingerprint(
+            fingerprint(CURRENT_COMMITTEE),
+            BackupFingerprint::new(SESSION_ID, NodeCount(7), OLD_COMMITTEE),
+        );
+        let message = error.to_string();
+        assert!(message.contains(&format!(
+            "Expected: {} got: {}",
+            fingerprint(CURRENT_COMMITTEE),
+            BackupFingerprint::<Hasher64>::new(SESSION_ID, NodeCount(7), OLD_COMMITTEE),
+        )));
+        assert!(message.contains("4 members"));
+        assert!(message.contains("7 members"));
+    }
+
+    #[tokio::test]
+    async fn migration_keeps_units_still_valid() {
+        let units = with_replaced_member(produce_units(5, SESSION_ID), REPLACED);
+        let items: Vec<_> = units.clone().into_iter().flatten().collect();
+        let mut backup = fingerprint(OLD_COMMITTEE).header();
+        backup.extend(encode_all(items).into_iter().flatten());
+
+        let (
+            PrepareTestResponse {
+                task,
+                loaded_data_rx,
+                highest_response_tx,
+                starting_round_rx,
+            },
+            new_backup_rx,
+        ) = prepare_test_with(backup, true);
+
+        let handle = tokio::spawn(async {
+            task.await;
+        });
+
+        highest_response_tx.send(1).unwrap();
+        handle.await.unwrap();
+
+        // Only the initial units of the members that stayed do not depend on the replaced one.
+        let expected: Vec<_> = units[0]
+            .iter()
+            .filter(|unit| unit.as_signable().creator() != REPLACED)
+            .cloned()
+            .collect();
+        assert_eq!(loaded_data_rx.await, Ok(expected));
+        assert_eq!(starting_round_rx.await, Ok(Some(1)));
+        assert_eq!(new_backup_rx.await, Ok(false));
+    }
+
+    #[test]
+    fn migration_reports_discarded_units() {
+        let units = with_replaced_member(produce_units(3, SESSION_ID), REPLACED);
+        let loader = BackupLoader::<Hasher64, Data, Keychain, Loader>::new(
+            Loader::new(Vec::new()),
+            fingerprint(CURRENT_COMMITTEE),
+            Validator::new(SESSION_ID, Keychain::new(N_MEMBERS, NODE_ID), 5000),
+            CallbackGuard::default(),
+        )
+        .with_migration(true);
+
+        let (kept, discarded) = loader.migrate_units(units.into_iter().flatten().collect());
+
+        assert_eq!(kept.len(), 3);
+        let mut expected = Vec::new();
+        for round in 0..3 {
+            for creator in N_MEMBERS.into_iterator() {
+                let reason = match (round, creator) {
+                    (_, REPLACED) => DiscardReason::Invalid(RejectionReason::WrongSignature),
+                    (0, _) => continue,
+                    _ => DiscardReason::ParentDiscarded,
+                };
+                expected.push((UnitCoord::new(round, creator), reason));
+            }
+        }
+        assert_eq!(discarded, expected);
+    }
 }
@@ -1,5 +1,8 @@
+pub use fingerprint::{BackupFingerprint, DiscardReason};
+pub(crate) use loader::decode_backup;
 pub use loader::BackupLoader;
 pub use saver::BackupSaver;
 
+mod fingerprint;
 mod loader;
 mod saver;
@@ -1,23 +1,30 @@
 use std::pin::Pin;
 
 use crate::{
+    callbacks::{CallbackGuard, UserComponent},
     dag::DagUnit,
+    standby::BackupReplication,
     units::{UncheckedSignedUnit, WrappedUnit},
     Data, Hasher, MultiKeychain, Receiver, Sender, Terminator,
 };
 use codec::Encode;
-use futures::{AsyncWrite, AsyncWriteExt, FutureExt, StreamExt};
+use futures::{channel::oneshot, AsyncWrite, AsyncWriteExt, FutureExt, StreamExt};
 use log::{debug, error};
 
 const LOG_TARGET: &str = "AlephBFT-backup-saver";
 
 /// Component responsible for saving units into backup.
 /// It waits for items to appear on its receivers, and writes them to backup.
-/// It announces a successful write through an appropriate response sender.
+/// It announces a successful write through an appropriate response sender, after passing the
+/// written unit on to the replication, if any.
+/// A new backup is started with a header, which is not replicated.
 pub struct BackupSaver<H: Hasher, D: Data, MK: MultiKeychain, W: AsyncWrite> {
     units_from_runway: Receiver<DagUnit<H, D, MK>>,
     responses_for_runway: Sender<DagUnit<H, D, MK>>,
     backup: Pin<Box<W>>,
+    replication: BackupReplication,
+    header: Option<(Vec<u8>, oneshot::Receiver<bool>)>,
+    callbacks: CallbackGuard,
 }
 
 impl<H: Hasher, D: Data, MK: MultiKeychain, W: AsyncWrite> BackupSaver<H, D, MK, W> {
@@ -25,23 +32,55 @@ impl<H: Hasher, D: Data, MK: MultiKeychain, W: AsyncWrite> BackupSaver<H, D, MK,
         units_from_runway: Receiver<DagUnit<H, D, MK>>,
         responses_for_runway: Sender<DagUnit<H, D, MK>>,
         backup: W,
+        replication: BackupReplication,
+        callbacks: CallbackGuard,
     ) -> BackupSaver<H, D, MK, W> {
         BackupSaver {
             units_from_runway,
             responses_for_runway,
             backup: Box::pin(backup),
+            replication,
+            header: None,
+            callbacks,
+        }
+    }
+
+    /// Makes the saver write the header before the first unit, if the loader finds the backup
+    /// to be new.
+    pub fn with_header(self, header: Vec<u8>, new_backup: oneshot::Receiver<bool>) -> Self {
+        BackupSaver {
+            header: Some((header, new_backup)),
+            ..self
         }
     }
 
     pub async fn save_unit(&mut self, unit: &DagUnit<H, D, MK>) -> Result<(), std::io::Error> {
+        if let Some((header, new_backup)) = self.header.take() {
+            if new_backup.await == Ok(true) {
+                self.backup.write_all(&header).await?;
+            }
+        }
         let unit: UncheckedSignedUnit<_, _, _> = unit.clone().unpack().into();
-        self.backup.write_all(&unit.encode()).await?;
-        self.backup.flush().await
+        let encoded = unit.encode();
+        self.backup.write_all(&encoded).await?;
+        self.backup.flush().await?;
+        self.replication.replicate(encoded);
+        Ok(())
     }
 
     pub async fn run(&mut self, mut terminator: Terminator) {
         let mut terminator_exit = false;
         loop {
+            if !terminator_exit && terminator.exit_requested() {
+                debug!(target: LOG_TARGET, "backup saver received exit signal.");
+                terminator_exit = true;
+            }
+            if terminator_exit {
+                debug!(target: LOG_TARGET, "backup saver decided to exit.");
+                terminator.terminate_sync().await;
+                break;
+            }
+
             futures::select! {
                 unit = self.units_from_runway.next() => {
                     let item = match unit {
@@ -51,9 +90,14 @@ impl<H: Hasher, D: Data, MK: MultiKeychain, W: AsyncWrite> BackupSaver<H, D, MK,
                             break;
                         },
                     };
-                    if let Err(e) = self.save_unit(&item).await {
-                        error!(target: LOG_TARGET, "couldn't save item to backup: {:?}", e);
-                        break;
+                    let callbacks = self.callbacks.clone();
+                    match callbacks.call_async(UserComponent::BackupWriter, self.save_unit(&item)).await {
+                        Ok(Ok(())) => {},
+                        Ok(Err(e)) => {
+                            error!(target: LOG_TARGET, "couldn't save item to backup: {:?}", e);
+                            break;
+                        },
+                        Err(_) => break,
                     }
                     if self.responses_for_runway.unbounded_send(item).is_err() {
                         error!(target: LOG_TARGET, "couldn't respond with saved unit to runway");
@@ -65,12 +109,6 @@ impl<H: Hasher, D: Data, MK: MultiKeychain, W: AsyncWrite> BackupSaver<H, D, MK,
                     terminator_exit = true;
                 }
             }
-
-            if terminator_exit {
-                debug!(target: LOG_TARGET, "backup saver decided to exit.");
-                terminator.terminate_sync().await;
-                break;
-            }
         }
     }
 }
@@ -82,14 +120,26 @@ mod tests {
         StreamExt,
     };
 
-    use aleph_bft_mock::{Data, Hasher64, Keychain, Saver};
+    use aleph_bft_mock::{Data, FailingSaver, Hasher64, Keychain, Saver, Signature};
 
     use crate::{
         backup::BackupSaver,
+        callbacks::CallbackGuard,
         dag::ReconstructedUnit,
-        units::{creator_set, preunit_to_signed_unit, TestingSignedUnit},
-        NodeCount, Terminator,
+        standby::BackupReplication,
+        units::{
+            creator_set, preunit_to_signed_unit, TestingSignedUnit, UncheckedSignedUnit,
+            WrappedUnit,
+        },
+        NodeCount, NodeIndex, Terminator,
     };
+    use codec::Encode;
+    use parking_lot::Mutex;
+    use std::{sync::Arc, time::Duration};
+    use tokio::time::timeout;
+
+    const QUEUED_UNITS: usize = 100_000;
+    const EXIT_BOUND: Duration = Duration::from_millis(100);
 
     type TestUnit = ReconstructedUnit<TestingSignedUnit>;
     type TestBackupSaver = BackupSaver<Hasher64, Data, Keychain, Saver>;
@@ -107,8 +157,13 @@ mod tests {
         let backup = Saver::new();
 
         let task = {
-            let mut saver: TestBackupSaver =
-                BackupSaver::new(units_from_runway, units_for_runway, backup);
+            let mut saver: TestBackupSaver = BackupSaver::new(
+                units_from_runway,
+                units_for_runway,
+                backup,
+                BackupReplication::default(),
+                CallbackGuard::default(),
+            );
 
             async move {
                 saver.run(Terminator::create_root(exit_rx, "saver")).await;
@@ -165,4 +220,118 @@ mod tests {
         exit_tx.send(()).unwrap();
         handle.await.unwrap();
     }
+
+    #[tokio::test]
+    async fn exits_without_draining_the_queue() {
+        let node_count = NodeCount(5);
+        let PrepareSaverResponse {
+            task,
+            units_for_saver,
+            units_from_saver,
+            exit_tx,
+        } = prepare_saver();
+
+        let creator = &creator_set(node_count)[0];
+        let unit = ReconstructedUnit::initial(preunit_to_signed_unit(
+            creator.create_unit(0).unwrap(),
+            0,
+            &Keychain::new(node_count, NodeIndex(0)),
+        ));
+        for _ in 0..QUEUED_UNITS {
+            units_for_saver.unbounded_send(unit.clone()).unwrap();
+        }
+        exit_tx.send(()).unwrap();
+
+        timeout(EXIT_BOUND, task)
+            .await
+            .expect("the saver should exit right away");
+        let saved: Vec<_> = units_from_saver.collect().await;
+        assert!(saved.is_empty());
+    }
+
+    #[tokio::test]
+    async fn header_starts_only_new_backups() {
+        let node_count = NodeCount(5);
+        let creator = &creator_set(node_count)[0];
+        let unit = ReconstructedUnit::initial(preunit_to_signed_unit(
+            creator.create_unit(0).unwrap(),
+            0,
+            &Keychain::new(node_count, NodeIndex(0)),
+        ));
+        let unchecked: UncheckedSignedUnit<Hasher64, Data, Signature> =
+            unit.clone().unpack().into();
+        let encoded = unchecked.encode();
+
+        for new_backup in [true, false] {
+            let saved = Arc::new(Mutex::new(vec![]));
+            let (new_backup_tx, new_backup_rx) = oneshot::channel();
+            let (_units_for_saver, units_from_runway) = mpsc::unbounded();
+            let (units_for_runway, _units_from_saver) = mpsc::unbounded();
+            let mut saver: TestBackupSaver = BackupSaver::new(
+                units_from_runway,
+                units_for_runway,
+                Saver::from(saved.clone()),
+                BackupReplication::default(),
+                CallbackGuard::default(),
+            )
+            .with_header(b"header".to_vec(), new_backup_rx);
+            new_backup_tx.send(new_backup).unwrap();
+
+            saver.save_unit(&unit).await.unwrap();
+            saver.save_unit(&unit).await.unwrap();
+
+            let mut expected = match new_backup {
+                true => b"header".to_vec(),
+                false => Vec::new(),
+            };
+            expected.extend(&encoded);
+            expected.extend(&encoded);
+            assert_eq!(*saved.lock(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn halts_when_an_async_write_fails() {
+        let node_count = NodeCount(5);
+        let creators = creator_set(node_count);
+        let units: Vec<TestUnit> = node_count
+            .into_iterator()
+            .map(|id| {
+                ReconstructedUnit::initial(preunit_to_signed_unit(
+                    creators[id.0].create_unit(0).unwrap(),
+                    0,
+                    &Keychain::new(node_count, id),
+                ))
+            })
+            .collect();
+        let saved = Arc::new(Mutex::new(vec![]));
+        let backup = FailingSaver::new(Saver::from(saved.clone()), |write| write >= 1);
+        let (units_for_saver, units_from_runway) = mpsc::unbounded();
+        let (units_for_runway, units_from_saver) = mpsc::unbounded();
+        let (_exit_tx, exit_rx) = oneshot::channel();
+        let mut saver = BackupSaver::<Hasher64, Data, Keychain, _>::new(
+            units_from_runway,
+            units_for_runway,
+            backup.clone(),
+            BackupReplication::default(),
+            CallbackGuard::default(),
+        );
+        for unit in &units {
+            units_for_saver.unbounded_send(unit.clone()).unwrap();
+        }
+
+        timeout(
+            EXIT_BOUND,
+            saver.run(Terminator::create_root(exit_rx, "saver")),
+        )
+        .await
+        .expect("the saver should stop after the failed write");
+        assert_eq!(backup.writes(), 2);
+        drop(saver);
+        let saved_units: Vec<_> = units_from_saver.collect().await;
+        assert_eq!(saved_units, units[..1]);
+        let first: UncheckedSignedUnit<Hasher64, Data, Signature> =
+            units[0].clone().unpack().into();
+        assert_eq!(*saved.lock(), first.encode());
+    }
 }
@@ -0,0 +1,248 @@
+use futures::{channel::oneshot, FutureExt};
+use log::error;
+use parking_lot::Mutex;
+use std::{
+    any::Any,
+    collections::HashSet,
+    fmt::{Display, Formatter, Result as FmtResult},
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+};
+use thiserror::Error;
+
+const LOG_TARGET: &str = "AlephBFT-callbacks";
+
+/// A component of the session supplied by the application.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum UserComponent {
+    DataProvider,
+    BroadcastGate,
+    DataAvailabilityChecker,
+    UnitMetadataProvider,
+    FinalizationHandler,
+    FinalizationStateStore,
+    LeaseStore,
+    Network,
+    BackupWriter,
+    BackupReader,
+}
+
+impl Display for UserComponent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let name = match self {
+            UserComponent::DataProvider => "data provider",
+            UserComponent::BroadcastGate => "broadcast gate",
+            UserComponent::DataAvailabilityChecker => "data availability checker",
+            UserComponent::UnitMetadataProvider => "unit metadata provider",
+            UserComponent::FinalizationHandler => "finalization handler",
+            UserComponent::FinalizationStateStore => "finalization state store",
+            UserComponent::LeaseStore => "lease store",
+            UserComponent::Network => "network",
+            UserComponent::BackupWriter => "backup writer",
+            UserComponent::BackupReader => "backup reader",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Why a session ended on its own, rather than being stopped with its terminator.
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+pub enum SessionError {
+    #[error("the config is invalid")]
+    InvalidConfig,
+    #[error("the {component} panicked: {payload_description}")]
+    UserCallbackPanicked {
+        component: UserComponent,
+        payload_description: String,
+    },
+    /// One of the tasks of the session, named like in [`crate::SessionComponents`], ended before
+    /// the session did, e.g. because it panicked or its backup writer failed.
+    #[error("the {component} task terminated early")]
+    ComponentTerminated { component: &'static str },
+}
+
+/// A callback of the given component panicked, which is already recorded as the error of the
+/// session. The component must not be called again.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct CallbackPanicked(pub UserComponent);
+
+/// Calls the components supplied by the application, turning their panics into the error of
+/// the session instead of unwinding through its tasks. It also records the tasks of the session
+/// ending early, the first of all these errors is the one reported.
+///
+/// The callbacks are treated as unwind safe. The session ends after any of them panics and
+/// never calls that component again, so whatever broken state the panic left behind can only
+/// be observed by the application itself.
+#[derive(Clone, Default)]
+pub(crate) struct CallbackGuard {
+    state: Arc<Mutex<GuardState>>,
+}
+
+#[derive(Default)]
+struct GuardState {
+    error: Option<SessionError>,
+    panicked: HashSet<UserComponent>,
+    failure_listener: Option<oneshot::Sender<()>>,
+}
+
+impl CallbackGuard {
+    /// Creates a guard together with a receiver notified about the first error.
+    pub fn new() -> (Self, oneshot::Receiver<()>) {
+        let (failure_listener, failures) = oneshot::channel();
+        let guard = CallbackGuard::default();
+        guard.state.lock().failure_listener = Some(failure_listener);
+        (guard, failures)
+    }
+
+    /// Calls the component, unless it already panicked before.
+    pub fn call<T>(
+        &self,
+        component: UserComponent,
+        callback: impl FnOnce() -> T,
+    ) -> Result<T, CallbackPanicked> {
+        self.check(component)?;
+        panic::catch_unwind(AssertUnwindSafe(callback))
+            .map_err(|payload| self.on_panic(component, payload))
+    }
+
+    /// Polls the future of the component, unless it already panicked before.
+    pub async fn call_async<F: Future>(
+        &self,
+        component: UserComponent,
+        callback: F,
+    ) -> Result<F::Output, CallbackPanicked> {
+        self.check(component)?;
+        AssertUnwindSafe(callback)
+            .catch_unwind()
+            .await
+            .map_err(|payload| self.on_panic(component, payload))
+    }
+
+    fn check(&self, component: UserComponent) -> Result<(), CallbackPanicked> {
+        match self.state.lock().panicked.contains(&component) {
+            true => Err(CallbackPanicked(component)),
+            false => Ok(()),
+        }
+    }
+
+    fn on_panic(&self, component: UserComponent, payload: Box<dyn Any + Send>) -> CallbackPanicked {
+        let payload_description = describe(payload.as_ref());
+        error!(target: LOG_TARGET, "The {} panicked: {}, ending the session.", component, payload_description);
+        self.state.lock().panicked.insert(component);
+        self.fail(SessionError::UserCallbackPanicked {
+            component,
+            payload_description,
+        });
+        CallbackPanicked(component)
+    }
+
+    /// The task of the given component ended before the session did.
+    pub fn on_component_terminated(&self, component: &'static str) {
+        self.fail(SessionError::ComponentTerminated { component });
+    }
+
+    fn fail(&self, error: SessionError) {
+        let mut state = self.state.lock();
+        state.error.get_or_insert(error);
+        if let Some(failure_listener) = state.failure_listener.take() {
+            // The session might be ending already.
+            let _ = failure_listener.send(());
+        }
+    }
+
+    /// The error the session should end with, if any callback panicked or task ended early.
+    pub fn error(&self) -> Option<SessionError> {
+        self.state.lock().error.clone()
+    }
+}
+
+/// The message of the panic, if it has one.
+fn describe(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        return message.to_string();
+    }
+    match payload.downcast_ref::<String>() {
+        Some(message) => message.clone(),
+        None => "a panic without a message".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::callbacks::{CallbackGuard, CallbackPanicked, SessionError, UserComponent};
+
+    #[test]
+    fn reports_the_first_panic() {
+        let (guard, mut panics) = CallbackGuard::new();
+        assert_eq!(guard.call(UserComponent::Network, || 7), Ok(7));
+        assert_eq!(guard.error(), None);
+        assert_eq!(panics.try_recv(), Ok(None));
+        assert_eq!(
+            guard.call(UserComponent::FinalizationHandler, || panic!("item {}", 10)),
+            Err::<(), _>(CallbackPanicked(UserComponent::FinalizationHandler))
+        );
+        assert_eq!(panics.try_recv(), Ok(Some(())));
+        let _ = guard.call(UserComponent::Network, || panic!("later"));
+        assert_eq!(
+            guard.error(),
+            Some(SessionError::UserCallbackPanicked {
+                component: UserComponent::FinalizationHandler,
+                payload_description: "item 10".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn does_not_call_components_that_panicked() {
+        let guard = CallbackGuard::default();
+        let _ = guard.call(UserComponent::Network, || panic!("broken"));
+        let mut called = false;
+        assert_eq!(
+            guard.call(UserComponent::Network, || called = true),
+            Err(CallbackPanicked(UserComponent::Network))
+        );
+        assert!(!called);
+        assert_eq!(guard.call(UserComponent::DataProvider, || 3), Ok(3));
+    }
+
+    #[test]
+    fn reports_terminated_components_unless_something_panicked_first() {
+        let (guard, mut failures) = CallbackGuard::new();
+        guard.on_component_terminated("runway/alerter");
+        assert_eq!(failures.try_recv(), Ok(Some(())));
+        assert_eq!(
+            guard.error(),
+            Some(SessionError::ComponentTerminated {
+                component: "runway/alerter"
+            })
+        );
+
+        let guard = CallbackGuard::default();
+        let _ = guard.call(UserComponent::BackupWriter, || panic!("disk full"));
+        guard.on_component_terminated("runway/backup_saver");
+        assert_eq!(
+            guard.error().map(|error| error.to_string()),
+            Some("the backup writer panicked: disk full".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn catches_panics_of_futures() {
+        let guard = CallbackGuard::default();
+        let result = guard
+            .call_async(UserComponent::DataProvider, async {
+                tokio::task::yield_now().await;
+                panic!("no data");
+            })
+            .await;
+        assert_eq!(
+            result,
+            Err::<(), _>(CallbackPanicked(UserComponent::DataProvider))
+        );
+        assert_eq!(
+            guard.error().map(|error| error.to_string()),
+            Some("the data provider panicked: no data".to_string())
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{
+    events::EventBus, member::start_session, Config, DataProvider, LocalIO, MultiKeychain, Network,
+    NetworkData, SessionError, SpawnHandle, TaskHandle, Terminator, UnitFinalizationHandler,
+};
+use futures::{channel::oneshot, AsyncRead, AsyncWrite, Future, FutureExt};
+use parking_lot::Mutex;
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// A long-lived component of a session, see [`SessionComponents`].
+pub struct SessionComponent {
+    name: &'static str,
+    task: Pin<Box<dyn Future<Output = ()> + Send>>,
+}
+
+impl SessionComponent {
+    /// The name [`crate::run_session`] spawns the component with.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl Future for SessionComponent {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.task.as_mut().poll(cx)
+    }
+}
+
+/// Collects the components instead of spawning them. The handle of a component resolves once the
+/// component completes, and fails if the component gets dropped before that.
+#[derive(Clone, Default)]
+struct Collector {
+    components: Arc<Mutex<Vec<SessionComponent>>>,
+}
+
+impl Collector {
+    fn take(&self) -> Vec<SessionComponent> {
+        std::mem::take(&mut *self.components.lock())
+    }
+}
+
+impl SpawnHandle for Collector {
+    fn spawn(&self, name: &'static str, task: impl Future<Output = ()> + Send + 'static) {
+        self.components.lock().push(SessionComponent {
+            name,
+            task: Box::pin(task),
+        });
+    }
+
+    fn spawn_essential(
+        &self,
+        name: &'static str,
+        task: impl Future<Output = ()> + Send + 'static,
+    ) -> TaskHandle {
+        let (completed_tx, completed_rx) = oneshot::channel();
+        self.spawn(name, async move {
+            task.await;
+            let _ = completed_tx.send(());
+        });
+        Box::pin(completed_rx.map(|completed| completed.map_err(|_| ())))
+    }
+}
+
+/// A session split into its long-lived components, for applications that cannot let the session
+/// spawn tasks, e.g. because of a strict thread budget or pinning to cores. The components are
+/// already connected with each other, so the application only has to poll all of them, together
+/// with the [`SessionComponents::session`], on whatever threads and executors it chooses, until
+/// they complete. Nothing else gets spawned. The session ends exactly as with
+/// [`crate::run_session`], in particular the terminator stops all the components regardless of
+/// who polls them. A component dropped before completing counts as a panicked task, and ends the
+/// session.
+///
+/// The components, by name:
+/// * `member/network` moves messages between the network and the rest of the session,
+///   latency-sensitive.
+/// * `member` schedules the requests and broadcasts of units, latency-sensitive.
+/// * `runway` verifies the incoming units, reconstructs the dag, orders it and calls the
+///   finalization handler. The most latency-sensitive one, and the one doing the most work.
+/// * `runway/creation` creates our units, calling the data provider, latency-sensitive. It is
+///   not there for observers, which create no units.
+/// * `runway/backup_saver` writes units to the backup before they are sent to anyone,
+///   latency-sensitive, and might block on the writer.
+/// * `runway/alerter` handles alerts about forks, idle as long as nobody forks.
+/// * `runway/loading` reads the backup at the start of the session and completes.
+/// * `member/runway` supervises the components of the runway, it only does anything when
+///   they end.
+pub struct SessionComponents {
+    /// Supervises all the components, ending with the result of the session.
+    pub session: Pin<Box<dyn Future<Output = Result<(), SessionError>> + Send>>,
+    /// All the components, in the order they are spawned by [`crate::run_session`].
+    pub components: Vec<SessionComponent>,
+}
+
+impl SessionComponents {
+    /// Prepares the components of a session with the same arguments as [`crate::run_session`],
+    /// apart from the spawn handle. Fails if the config is invalid.
+    pub fn new<
+        DP: DataProvider,
+        UFH: UnitFinalizationHandler<Data = DP::Output>,
+        US: AsyncWrite + Send + Sync + 'static,
+        UL: AsyncRead + Send + Sync + 'static,
+        N: Network<NetworkData<UFH::Hasher, DP::Output, MK::Signature, MK::PartialMultisignature>>,
+        MK: MultiKeychain,
+    >(
+        config: Config,
+        local_io: LocalIO<DP, UFH, US, UL>,
+        network: N,
+        keychain: MK,
+        terminator: Terminator,
+    ) -> Result<Self, SessionError> {
+        Self::with_events(
+            config,
+            local_io,
+            network,
+            keychain,
+            terminator,
+            EventBus::new(),
+        )
+    }
+
+    /// Prepares the components exactly like [`SessionComponents::new`], publishing internal
+    /// events on the given bus.
+    pub(crate) fn with_events<
+        DP: DataProvider,
+        UFH: UnitFinalizationHandler<Data = DP::Output>,
+        US: AsyncWrite + Send + Sync + 'static,
+        UL: AsyncRead + Send + Sync + 'static,
+        N: Network<NetworkData<UFH::Hasher, DP::Output, MK::Signature, MK::PartialMultisignature>>,
+        MK: MultiKeychain,
+    >(
+        config: Config,
+        local_io: LocalIO<DP, UFH, US, UL>,
+        network: N,
+        keychain: MK,
+        terminator: Terminator,
+        events: EventBus<UFH::Hasher, DP::Output, MK::Signature>,
+    ) -> Result<Self, SessionError> {
+        let collector = Collector::default();
+        let session = start_session(
+            config,
+            local_io,
+            network,
+            keychain,
+            collector.clone(),
+            terminator,
+            events,
+        )?;
+        Ok(SessionComponents {
+            session: Box::pin(session),
+            components: collector.take(),
+        })
+    }
+
+    /// Spawns all the components and waits for the session to end, which is all
+    /// [`crate::run_session`] does. The session supervises the components itself, so they are
+    /// not spawned as essential tasks.
+    pub async fn run<SH: SpawnHandle>(self, spawn_handle: SH) -> Result<(), SessionError> {
+        let SessionComponents {
+            session,
+            components,
+        } = self;
+        for component in components {
+            spawn_handle.spawn(component.name(), component);
+        }
+        session.await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{components::Collector, SpawnHandle};
+    use futures::executor::block_on;
+
+    #[test]
+    fn handles_report_dropped_components() {
+        let collector = Collector::default();
+        let completed = collector.spawn_essential("completed", async {});
+        let dropped = collector.spawn_essential("dropped", async {});
+        let mut components = collector.take();
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[1].name(), "dropped");
+        drop(components.pop());
+        block_on(components.pop().expect("one is left"));
+        assert_eq!(block_on(completed), Ok(()));
+        assert_eq!(block_on(dropped), Err(()));
+    }
+}
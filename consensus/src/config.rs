@@ -1,6 +1,12 @@
-use crate::{NodeCount, NodeIndex, Round, SessionId};
+use crate::{
+    protocol::PROTOCOL_VERSION, ClockSource, Compression, DelayControl, GateDecision, NodeCount,
+    NodeIndex, NodeWeights, Round, SessionId, Weight, WireVersion, EXTENDED_MESSAGES_VERSION,
+    MAX_SUPPORTED_VERSION,
+};
 use log::error;
 use std::{
+    cmp::max,
+    collections::HashSet,
     fmt::{Debug, Formatter},
     sync::Arc,
     time::Duration,
@@ -15,8 +21,11 @@ pub type DelaySchedule = Arc<dyn Fn(usize) -> Duration + Sync + Send + 'static>;
 /// A function answering the question of how many nodes to query on the n-th (0-based) try.
 pub type RecipientCountSchedule = Arc<dyn Fn(usize) -> usize + Sync + Send + 'static>;
 
-/// Configuration of several parameters related to delaying various tasks.
+/// Configuration of several parameters related to delaying various tasks. With the `serde`
+/// feature it can be serialized, e.g. for diagnostics, but not deserialized, as the schedules are
+/// functions and are left out.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DelayConfig {
     /// Tick frequency of the Member. Governs internal task queue of the Member.
     pub tick_interval: Duration,
@@ -26,22 +35,32 @@ pub struct DelayConfig {
     /// Maximum frequency of broadcast of top known units.
     pub unit_rebroadcast_interval_max: Duration,
     /// unit_creation_delay(k) represents the delay between creating the (k-1)th and kth unit.
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub unit_creation_delay: DelaySchedule,
     /// coord_request_delay(k) represents the delay between the kth and (k+1)st try when requesting
     /// a unit by coords.
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub coord_request_delay: DelaySchedule,
     /// coord_request_recipients(k) represents the number of nodes to ask at the kth try when
     /// requesting a unit by coords.
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub coord_request_recipients: RecipientCountSchedule,
     /// parent_request_delay(k) represents the delay between the kth and (k+1)st try when requesting
     /// unknown parents of a unit.
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub parent_request_delay: DelaySchedule,
     /// parent_request_recipients(k) represents the number of nodes to ask at the kth try when
     /// requesting unknown parents of a unit.
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub parent_request_recipients: RecipientCountSchedule,
     /// newest_request_delay(k) represents the delay between the kth and (k+1)st try when sending
     /// a broadcast request for newest units
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub newest_request_delay: DelaySchedule,
+    /// How long the creator waits for the [`crate::DataProvider`] before creating the unit
+    /// without data. The pending request is not dropped, its answer goes into a later unit.
+    /// Without a timeout the creator waits as long as it takes.
+    pub data_fetch_timeout: Option<Duration>,
 }
 
 impl Debug for DelayConfig {
@@ -56,44 +75,1209 @@ impl Debug for DelayConfig {
                 "max unit rebroadcast interval",
                 &self.unit_rebroadcast_interval_max,
             )
+            .field("data fetch timeout", &self.data_fetch_timeout)
+            .finish()
+    }
+}
+
+/// Decides locally which data should be flagged when finalized, based on the creator of the unit
+/// containing it. Flagged data is still ordered in exactly the same way, it is only marked when
+/// passed to [`crate::FinalizationHandler::flagged_data_finalized`], so that the application can
+/// e.g. treat data from members in a probation period differently.
+#[derive(Clone)]
+pub struct DataPolicy {
+    flagged: Option<Arc<dyn Fn(NodeIndex) -> bool + Sync + Send + 'static>>,
+}
+
+impl DataPolicy {
+    /// Flags the data of all the creators for which `flagged` returns true.
+    pub fn new(flagged: impl Fn(NodeIndex) -> bool + Sync + Send + 'static) -> Self {
+        DataPolicy {
+            flagged: Some(Arc::new(flagged)),
+        }
+    }
+
+    /// Flags the data of all the creators except the allowed ones.
+    pub fn allow_list(allowed: impl IntoIterator<Item = NodeIndex>) -> Self {
+        let allowed: HashSet<_> = allowed.into_iter().collect();
+        Self::new(move |creator| !allowed.contains(&creator))
+    }
+
+    /// Whether the data in units created by `creator` should be flagged.
+    pub fn is_flagged(&self, creator: NodeIndex) -> bool {
+        self.flagged
+            .as_ref()
+            .map_or(false, |flagged| flagged(creator))
+    }
+}
+
+impl Default for DataPolicy {
+    /// A policy that flags nothing.
+    fn default() -> Self {
+        DataPolicy { flagged: None }
+    }
+}
+
+impl Debug for DataPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DataPolicy")
+            .field("flagging", &self.flagged.is_some())
             .finish()
     }
 }
 
+/// Makes the creator stop attaching data to our units while they take too long to finalize, so
+/// that the application data waiting for finalization stays bounded. The latency of our unit is
+/// the number of rounds we created in the meantime, counted until it is finalized, or until now
+/// if it is not finalized yet. The provider is not polled at all while the data is held back.
+/// Units waiting to be ordered are not finalized yet, so a lagging ordering holds the data back
+/// just like a lagging committee, see [`ExtenderFlowControl`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdaptiveInclusion {
+    /// Data is held back once the rolling latency reaches this many rounds. At most this many
+    /// of our units with data wait for finalization at any time.
+    pub pause_latency: Round,
+    /// Data is attached again once the rolling latency drops to this many rounds, has to be
+    /// lower than `pause_latency`.
+    pub resume_latency: Round,
+    /// The number of our most recently finalized units the rolling latency is computed over.
+    pub window: usize,
+}
+
+/// A cap on how many alerts we raise ourselves. Honest nodes raise at most one alert per forker,
+/// so a burst of our own alerts rather points at a local problem, e.g. corrupted units coming
+/// from our storage. Alerts over the cap are queued, at most one per forker, and raised once the
+/// cap allows it, the forkers are known locally in the meantime.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlertRateLimit {
+    /// How many alerts we raise within any minute at most.
+    pub per_minute: usize,
+    /// How many alerts we raise in the whole session at most, the ones over it stay queued
+    /// until the session ends.
+    pub per_session: usize,
+}
+
+/// How the network hub deals with a [`crate::Network`] that temporarily yields no events. It
+/// polls the network again after a backoff, starting at `initial_backoff` and doubling up to
+/// `max_backoff`, and gives up after `max_consecutive_failures` retries in a row yield nothing.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NetworkRetry {
+    /// How many times in a row the network may yield no events before the session ends, zero
+    /// ends it on the first one.
+    pub max_consecutive_failures: usize,
+    /// The backoff before the first retry, has to be positive.
+    pub initial_backoff: Duration,
+    /// The cap on the backoff, at least `initial_backoff`.
+    pub max_backoff: Duration,
+}
+
+/// Bounds the units that wait in the reconstruction for parents, so that units with control
+/// hashes referencing parents nobody has cannot pile up. A unit becomes a suspect once all the
+/// requests for its missing parents were answered negatively by every node other than its
+/// creator, and it is evicted if it is still waiting `suspect_timeout` later, which counts as
+/// misbehavior of its creator. Independently, at most `max_pending_per_creator` units of every
+/// creator wait for parents, over that the unit of the highest round is dropped, as it would be
+/// needed last. An evicted unit arriving again is only admitted if its parents are already there.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReconstructionLimits {
+    /// How many units of a single creator can wait for their parents at most, has to be positive.
+    pub max_pending_per_creator: usize,
+    /// How long a suspect unit can wait for its parents, has to be positive. Parents that are
+    /// just slow to arrive only have to arrive within it after every node denied having them.
+    pub suspect_timeout: Duration,
+}
+
+/// Bounds the responses to requests for units by coord and for parents of units, which every
+/// peer can solicit and which are costly to send, as they carry whole units. A request repeated
+/// by the same peer within `dedup_window` of being answered is dropped, the peer got the response
+/// already. Over that, every peer gets at most `max_per_second` responses per second, and the
+/// requests over the limit are dropped. The responses sent last are cached, at most `cache_size`
+/// of them, so that many peers asking for the same units don't make us build them again. The
+/// requests are not authenticated, so the budgets are per index the requester claims, and
+/// requests claiming an index outside the committee or our own are dropped.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResponseLimits {
+    /// How long a request answered for a peer is not answered for it again, no requests are
+    /// dropped as repeated if zero.
+    pub dedup_window: Duration,
+    /// How many responses a single peer gets per second at most, has to be positive.
+    pub max_per_second: usize,
+    /// How many responses are cached at most, none if zero.
+    pub cache_size: usize,
+}
+
+/// Makes the runway put off the units far ahead of the ordering while the ordering falls behind,
+/// so that the units waiting to be ordered stay bounded. The backlog of the ordering consists of
+/// the units added to it, but not ordered yet, whether they wait for a decision or for the
+/// delivery to resume. Once it exceeds `max_backlog`, units received from the network of rounds
+/// more than `horizon` above the round being decided are kept aside, unvalidated, and processed
+/// lowest rounds first as the backlog shrinks or the decided round catches up. At most
+/// `max_deferred` units are kept aside, units over it are processed as usual. No unit is dropped,
+/// and units of the rounds that are being decided are processed as usual.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtenderFlowControl {
+    /// How many units may wait to be ordered before units far ahead are put off, has to be
+    /// positive.
+    pub max_backlog: usize,
+    /// How many rounds above the round being decided are still processed as usual, has to be
+    /// positive.
+    pub horizon: Round,
+    /// How many units can be kept aside at most, as they are kept before validating them.
+    pub max_deferred: usize,
+}
+
+/// The part a node takes in the session.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Role {
+    /// A member of the committee, creating units and taking part in alerts.
+    Member,
+    /// A node outside of the committee following the session, e.g. an RPC node or a replica. It
+    /// validates the units of the committee, requesting the ones it misses, and finalizes the same
+    /// data, but it never creates units, signs anything or takes part in alerts.
+    Observer,
+}
+
+/// How the units are spread over the committee.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BroadcastStrategy {
+    /// Units are sent to everyone by their creator, and by every node rebroadcasting them.
+    #[default]
+    Direct,
+    /// Units are sent to `fanout` random peers only, and every node passes on the units it sees
+    /// for the first time to `fanout` random peers of its own, has to be positive.
+    Gossip { fanout: usize },
+}
+
 /// Main configuration of the consensus. We refer to [the documentation](https://cardinal-cryptography.github.io/AlephBFT/aleph_bft_api.html#34-alephbft-sessions)
-/// Section 3.4 for a discussion of some of these parameters and their significance.
+/// Section 3.4 for a discussion of some of these parameters and their significance. With the
+/// `serde` feature it can be serialized, e.g. for diagnostics, leaving out the data policy, the
+/// clock, the delay control and the schedules of the delay config, which are all functions, so
+/// it cannot be deserialized.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Config {
-    /// Identification number of the Member=0,..,(n_members-1).
+    /// Identification number of the Member=0,..,(n_members-1), or of an observer outside of it.
     node_ix: NodeIndex,
+    /// Whether we are a member of the committee or just observe the session.
+    role: Role,
     /// Id of the session for which this instance is run.
     session_id: SessionId,
     /// The size of the committee running the consensus.
     n_members: NodeCount,
+    /// The voting weights of the members, every member weighs one if absent.
+    node_weights: Option<NodeWeights>,
     /// Configuration of several parameters related to delaying various tasks.
     delay_config: DelayConfig,
-    /// Maximum allowable round of a unit.
+    /// The delays replaced while the session runs.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    delay_control: DelayControl,
+    /// Maximum allowable round of a unit, the session ends after reaching it.
     max_round: Round,
+    /// Local policy for flagging finalized data.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    data_policy: DataPolicy,
+    /// The source of time for all the delays and timeouts in the session.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    clock: ClockSource,
+    /// Broadcasts of the same unit within this window are sent only once.
+    broadcast_dedup_window: Duration,
+    /// Whether units are sent to everyone or gossiped.
+    broadcast_strategy: BroadcastStrategy,
+    /// Holding back the data of our units while they finalize late, disabled if absent.
+    adaptive_inclusion: Option<AdaptiveInclusion>,
+    /// Whether committees not tolerating any faulty node are accepted.
+    allow_small_committee: bool,
+    /// How long our unit waits at the broadcast gate at most.
+    broadcast_gate_timeout: Duration,
+    /// What happens to our unit once the broadcast gate times out.
+    on_broadcast_gate_timeout: GateDecision,
+    /// How many units held back until their data is available there can be at most.
+    max_unavailable_units: usize,
+    /// How often the availability of data that was not available is checked again.
+    availability_recheck_interval: Duration,
+    /// The cap on raising our own alerts, unlimited if absent.
+    alert_rate_limit: Option<AlertRateLimit>,
+    /// Whether we send the multisignature of a completed alert RMC to the nodes still sending
+    /// us their signatures of it.
+    rmc_completion_replies: bool,
+    /// Whether our parents responses refer to the units the requester most likely holds by hash.
+    compact_unit_refs: bool,
+    /// Whether our fork alerts carry the hashes of the legit units instead of the units.
+    compact_alerts: bool,
+    /// How a network temporarily yielding no events is retried.
+    network_retry: NetworkRetry,
+    /// The bounds on the units waiting for parents in the reconstruction.
+    reconstruction_limits: ReconstructionLimits,
+    /// The bounds on the responses to requests for units by coord and for parents.
+    response_limits: ResponseLimits,
+    /// Putting off units far ahead of the ordering while it falls behind, disabled if absent.
+    extender_flow_control: Option<ExtenderFlowControl>,
+    /// The largest metadata, in bytes, attached to units we create or accept.
+    max_unit_metadata_size: usize,
+    /// The largest encoded data, in bytes, in units we create or accept.
+    max_data_size: usize,
+    /// How many rounds above the top round of our DAG units from the network can be at most.
+    max_round_lead: Round,
+    /// How many rounds above the last finalized one our units can be, unlimited if absent.
+    max_unfinalized_rounds: Option<Round>,
+    /// How many rounds below the top of our DAG our next unit can be before it is created without
+    /// data, to catch up quicker, never if absent.
+    fast_forward_lag: Option<Round>,
+    /// How many rounds below the last finalized one units are kept in the store, all if absent.
+    kept_rounds: Option<Round>,
+    /// How many messages from the network wait for processing in any queue at most.
+    max_pending_messages: usize,
+    /// How many units, or references to units, a unit message from the network holds at most.
+    max_units_per_message: usize,
+    /// The largest encoded message, in bytes, accepted from the network, unlimited if absent.
+    max_message_size: Option<usize>,
+    /// How the messages we send are compressed.
+    compression: Compression,
+    /// The version of the encoding of the messages we send.
+    wire_version: WireVersion,
+    /// Identifies the composition of the committee, recorded in the backup.
+    committee_id: Vec<u8>,
+    /// Whether a backup written by a different committee is migrated instead of refused.
+    migrate_backup: bool,
+    /// How often the lease of our seat in the committee is renewed, if there is one.
+    lease_renewal_interval: Duration,
+    /// How long the session keeps answering requests for its units after finalizing its last
+    /// head, before it ends on its own.
+    session_end_grace_period: Duration,
 }
 
 impl Config {
+    /// Checks whether the configuration can be used to run a session, i.e. whether it is
+    /// internally consistent and the committee tolerates a faulty node, unless small committees
+    /// are explicitly allowed. Sessions with an invalid configuration end right away.
+    pub fn validate(&self) -> Result<(), InvalidConfigError> {
+        self.check_consistency()?;
+        if self.n_members < MIN_FAULT_TOLERANT_COMMITTEE && !self.allow_small_committee {
+            error!(
+                target: "AlephBFT-config",
+                "A committee of {} members does not tolerate any faulty node, use with_allow_small_committee to run it anyway.", self.n_members.0
+            );
+            return Err(InvalidConfigError);
+        }
+        Ok(())
+    }
+
+    /// Checks the internal consistency of the configuration, i.e. whether it describes a member
+    /// of the committee, or an observer outside of it, and whether the delays can actually be used
+    /// for scheduling.
+    fn check_consistency(&self) -> Result<(), InvalidConfigError> {
+        if self.n_members == NodeCount(0) {
+            error!(target: "AlephBFT-config", "The committee has to contain at least one member.");
+            return Err(InvalidConfigError);
+        }
+        if let Some(node_weights) = &self.node_weights {
+            if node_weights.node_count() != self.n_members {
+                error!(
+                    target: "AlephBFT-config",
+                    "There are {} node weights for a committee of {} members.", node_weights.node_count().0, self.n_members.0
+                );
+                return Err(InvalidConfigError);
+            }
+            if self
+                .n_members
+                .into_iterator()
+                .any(|node_id| node_weights.weight(node_id) == 0)
+            {
+                error!(target: "AlephBFT-config", "Every member has to weigh something.");
+                return Err(InvalidConfigError);
+            }
+            if self
+                .n_members
+                .into_iterator()
+                .try_fold(0 as Weight, |total, node_id| {
+                    total.checked_add(node_weights.weight(node_id))
+                })
+                .is_none()
+            {
+                error!(
+                    target: "AlephBFT-config",
+                    "The node weights have to add up to at most {}.", Weight::MAX
+                );
+                return Err(InvalidConfigError);
+            }
+        }
+        let in_committee = self.node_ix.0 < self.n_members.0;
+        if in_committee != (self.role == Role::Member) {
+            error!(
+                target: "AlephBFT-config",
+                "Node index {:?} does not fit the {:?} role in a committee of size {:?}.", self.node_ix, self.role, self.n_members
+            );
+            return Err(InvalidConfigError);
+        }
+        let delay_config = &self.delay_config;
+        if delay_config.tick_interval.is_zero() {
+            error!(target: "AlephBFT-config", "Tick interval has to be positive.");
+            return Err(InvalidConfigError);
+        }
+        if delay_config.unit_rebroadcast_interval_min.as_millis()
+            >= delay_config.unit_rebroadcast_interval_max.as_millis()
+        {
+            error!(
+                target: "AlephBFT-config",
+                "Minimal unit rebroadcast interval has to be lower than the maximal one (in milliseconds)."
+            );
+            return Err(InvalidConfigError);
+        }
+        if let Some(inclusion) = &self.adaptive_inclusion {
+            if inclusion.resume_latency >= inclusion.pause_latency || inclusion.window == 0 {
+                error!(
+                    target: "AlephBFT-config",
+                    "Adaptive inclusion has to resume below the pause latency and use a nonempty window."
+                );
+                return Err(InvalidConfigError);
+            }
+        }
+        if let Some(limit) = &self.alert_rate_limit {
+            if limit.per_minute == 0 || limit.per_session == 0 {
+                error!(target: "AlephBFT-config", "The alert rate limit has to allow raising some alerts.");
+                return Err(InvalidConfigError);
+            }
+        }
+        let retry = &self.network_retry;
+        if retry.initial_backoff.is_zero() || retry.initial_backoff > retry.max_backoff {
+            error!(target: "AlephBFT-config", "The network retry backoff has to be positive and within its cap.");
+            return Err(InvalidConfigError);
+        }
+        let limits = &self.reconstruction_limits;
+        if limits.max_pending_per_creator == 0 || limits.suspect_timeout.is_zero() {
+            error!(target: "AlephBFT-config", "The reconstruction limits have to allow some units to wait for some time.");
+            return Err(InvalidConfigError);
+        }
+        if self.response_limits.max_per_second == 0 {
+            error!(target: "AlephBFT-config", "The response limits have to allow answering some requests.");
+            return Err(InvalidConfigError);
+        }
+        if let Some(flow_control) = &self.extender_flow_control {
+            if flow_control.max_backlog == 0
+                || flow_control.horizon == 0
+                || flow_control.max_deferred == 0
+            {
+                error!(target: "AlephBFT-config", "The extender flow control has to allow some backlog, a positive horizon and some units kept aside.");
+                return Err(InvalidConfigError);
+            }
+        }
+        if self.max_round_lead == 0 {
+            error!(target: "AlephBFT-config", "The max round lead has to allow units of the next round.");
+            return Err(InvalidConfigError);
+        }
+        if self.max_unfinalized_rounds == Some(0) {
+            error!(target: "AlephBFT-config", "The max unfinalized rounds have to allow units above the last finalized round.");
+            return Err(InvalidConfigError);
+        }
+        if self
+            .kept_rounds
+            .is_some_and(|rounds| rounds < MIN_KEPT_ROUNDS)
+        {
+            error!(target: "AlephBFT-config", "At least {} rounds below the finalized one have to be kept.", MIN_KEPT_ROUNDS);
+            return Err(InvalidConfigError);
+        }
+        if self.fast_forward_lag == Some(0) {
+            error!(target: "AlephBFT-config", "The fast forward lag has to allow the DAG to be ahead of our next unit.");
+            return Err(InvalidConfigError);
+        }
+        if self.max_unavailable_units == 0 {
+            error!(target: "AlephBFT-config", "Some units have to be able to wait for their data.");
+            return Err(InvalidConfigError);
+        }
+        if self.max_pending_messages == 0 {
+            error!(target: "AlephBFT-config", "The queues of messages from the network have to hold some messages.");
+            return Err(InvalidConfigError);
+        }
+        if self.max_units_per_message < self.n_members.0 {
+            error!(target: "AlephBFT-config", "Unit messages have to be allowed to hold all the parents of a unit.");
+            return Err(InvalidConfigError);
+        }
+        if self.max_message_size == Some(0) {
+            error!(target: "AlephBFT-config", "The message size limit has to allow some messages.");
+            return Err(InvalidConfigError);
+        }
+        if self.wire_version > MAX_SUPPORTED_VERSION {
+            error!(
+                target: "AlephBFT-config",
+                "Wire version {} is newer than the supported {}.", self.wire_version, MAX_SUPPORTED_VERSION
+            );
+            return Err(InvalidConfigError);
+        }
+        if self.compression != Compression::None && self.wire_version < EXTENDED_MESSAGES_VERSION {
+            error!(
+                target: "AlephBFT-config",
+                "Compression needs wire version at least {}.", EXTENDED_MESSAGES_VERSION
+            );
+            return Err(InvalidConfigError);
+        }
+        if self.compact_alerts && self.wire_version < EXTENDED_MESSAGES_VERSION {
+            error!(
+                target: "AlephBFT-config",
+                "Compact alerts need wire version at least {}.", EXTENDED_MESSAGES_VERSION
+            );
+            return Err(InvalidConfigError);
+        }
+        if self.lease_renewal_interval.is_zero() {
+            error!(target: "AlephBFT-config", "Lease renewal interval has to be positive.");
+            return Err(InvalidConfigError);
+        }
+        if self.broadcast_strategy == (BroadcastStrategy::Gossip { fanout: 0 }) {
+            error!(target: "AlephBFT-config", "Units have to be gossiped to some peers.");
+            return Err(InvalidConfigError);
+        }
+        Ok(())
+    }
+
+    /// Renders the effective parameters of this configuration in a human readable form, for
+    /// operators to review.
+    pub fn describe(&self) -> String {
+        let delay_config = &self.delay_config;
+        let delays = |schedule: &DelaySchedule| {
+            (0..DESCRIBED_SCHEDULE_STEPS)
+                .map(|t| format!("{}ms", schedule(t).as_millis()))
+                .chain(std::iter::once("...".to_string()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let counts = |schedule: &RecipientCountSchedule| {
+            (0..DESCRIBED_SCHEDULE_STEPS)
+                .map(|t| schedule(t).to_string())
+                .chain(std::iter::once("...".to_string()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        [
+            format!("protocol version: {}", PROTOCOL_VERSION),
+            format!("node index: {}", self.node_ix.0),
+            format!("role: {:?}", self.role),
+            format!("session id: {}", self.session_id),
+            format!("committee size: {}", self.n_members.0),
+            match &self.node_weights {
+                Some(node_weights) => format!(
+                    "node weights: {} (quorum weight {} of {})",
+                    node_weights
+                        .node_count()
+                        .into_iterator()
+                        .map(|node_id| node_weights.weight(node_id).to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    node_weights.consensus_threshold(),
+                    node_weights.total()
+                ),
+                None => "node weights: uniform".to_string(),
+            },
+            format!(
+                "tolerated faulty members: {}",
+                self.n_members.tolerated_faults().0
+            ),
+            format!("max round: {}", self.max_round),
+            format!(
+                "minimal time to reach max round: {}s",
+                time_to_reach_round(self.max_round, &delay_config.unit_creation_delay).as_secs()
+            ),
+            format!(
+                "broadcast dedup window: {}ms",
+                self.broadcast_dedup_window.as_millis()
+            ),
+            match self.broadcast_strategy {
+                BroadcastStrategy::Direct => "broadcast strategy: direct".to_string(),
+                BroadcastStrategy::Gossip { fanout } => {
+                    format!("broadcast strategy: gossip to {} peers", fanout)
+                }
+            },
+            format!(
+                "broadcast gate timeout: {}ms, then {:?}",
+                self.broadcast_gate_timeout.as_millis(),
+                self.on_broadcast_gate_timeout
+            ),
+            format!(
+                "availability checks: up to {} units held, rechecked every {}ms",
+                self.max_unavailable_units,
+                self.availability_recheck_interval.as_millis()
+            ),
+            match &self.adaptive_inclusion {
+                Some(inclusion) => format!(
+                    "adaptive inclusion: pause at {} rounds, resume at {} rounds, window of {} units",
+                    inclusion.pause_latency, inclusion.resume_latency, inclusion.window
+                ),
+                None => "adaptive inclusion: disabled".to_string(),
+            },
+            match &self.alert_rate_limit {
+                Some(limit) => format!(
+                    "alert rate limit: {} per minute, {} per session",
+                    limit.per_minute, limit.per_session
+                ),
+                None => "alert rate limit: disabled".to_string(),
+            },
+            format!("rmc completion replies: {}", self.rmc_completion_replies),
+            format!("compact unit references: {}", self.compact_unit_refs),
+            format!("compact alerts: {}", self.compact_alerts),
+            format!(
+                "network retry: {} attempts, backoff {}ms - {}ms",
+                self.network_retry.max_consecutive_failures,
+                self.network_retry.initial_backoff.as_millis(),
+                self.network_retry.max_backoff.as_millis()
+            ),
+            format!(
+                "reconstruction limits: {} pending units per creator, suspects evicted after {}ms",
+                self.reconstruction_limits.max_pending_per_creator,
+                self.reconstruction_limits.suspect_timeout.as_millis()
+            ),
+            format!(
+                "response limits: repeated requests dropped for {}ms, {} responses per peer per second, {} cached",
+                self.response_limits.dedup_window.as_millis(),
+                self.response_limits.max_per_second,
+                self.response_limits.cache_size
+            ),
+            match &self.extender_flow_control {
+                Some(flow_control) => format!(
+                    "extender flow control: backlog of {} units, horizon of {} rounds, up to {} units kept aside",
+                    flow_control.max_backlog, flow_control.horizon, flow_control.max_deferred
+                ),
+                None => "extender flow control: disabled".to_string(),
+            },
+            format!(
+                "max unit metadata size: {} bytes",
+                self.max_unit_metadata_size
+            ),
+            match self.max_data_size {
+                usize::MAX => "max data size: unlimited".to_string(),
+                max_data_size => format!("max data size: {} bytes", max_data_size),
+            },
+            format!("max round lead: {}", self.max_round_lead),
+            match self.max_unfinalized_rounds {
+                Some(rounds) => format!("max unfinalized rounds: {}", rounds),
+                None => "max unfinalized rounds: unlimited".to_string(),
+            },
+            match self.fast_forward_lag {
+                Some(lag) => format!("fast forward lag: {}", lag),
+                None => "fast forward: disabled".to_string(),
+            },
+            match self.kept_rounds {
+                Some(rounds) => format!("kept rounds: {}", rounds),
+                None => "kept rounds: all".to_string(),
+            },
+            format!("max pending messages: {}", self.max_pending_messages),
+            format!("max units per message: {}", self.max_units_per_message),
+            match self.max_message_size {
+                Some(size) => format!("max message size: {} bytes", size),
+                None => "max message size: unlimited".to_string(),
+            },
+            format!("compression: {:?}", self.compression),
+            format!("wire version: {}", self.wire_version),
+            format!(
+                "committee id: {}",
+                self.committee_id
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect::<Vec<_>>()
+                    .join("")
+            ),
+            format!("migrate backup: {}", self.migrate_backup),
+            format!(
+                "lease renewal interval: {}ms",
+                self.lease_renewal_interval.as_millis()
+            ),
+            format!(
+                "session end grace period: {}ms",
+                self.session_end_grace_period.as_millis()
+            ),
+            format!(
+                "tick interval: {}ms",
+                delay_config.tick_interval.as_millis()
+            ),
+            format!(
+                "unit rebroadcast interval: {}ms - {}ms",
+                delay_config.unit_rebroadcast_interval_min.as_millis(),
+                delay_config.unit_rebroadcast_interval_max.as_millis()
+            ),
+            format!(
+                "unit creation delay: {}",
+                delays(&delay_config.unit_creation_delay)
+            ),
+            format!(
+                "coord request delay: {}",
+                delays(&delay_config.coord_request_delay)
+            ),
+            format!(
+                "coord request recipients: {}",
+                counts(&delay_config.coord_request_recipients)
+            ),
+            format!(
+                "parent request delay: {}",
+                delays(&delay_config.parent_request_delay)
+            ),
+            format!(
+                "parent request recipients: {}",
+                counts(&delay_config.parent_request_recipients)
+            ),
+            format!(
+                "newest request delay: {}",
+                delays(&delay_config.newest_request_delay)
+            ),
+            match delay_config.data_fetch_timeout {
+                Some(timeout) => format!("data fetch timeout: {}ms", timeout.as_millis()),
+                None => "data fetch timeout: none".to_string(),
+            },
+        ]
+        .join("\n")
+    }
+
     pub fn node_ix(&self) -> NodeIndex {
         self.node_ix
     }
+    pub fn role(&self) -> Role {
+        self.role
+    }
     pub fn session_id(&self) -> SessionId {
         self.session_id
     }
     pub fn n_members(&self) -> NodeCount {
         self.n_members
     }
+    /// The voting weights of the members, all equal to one unless set with
+    /// [`Config::with_node_weights`].
+    pub fn node_weights(&self) -> NodeWeights {
+        self.node_weights
+            .clone()
+            .unwrap_or_else(|| NodeWeights::uniform(self.n_members))
+    }
     pub fn delay_config(&self) -> &DelayConfig {
         &self.delay_config
     }
+    /// The delays to use right now, i.e. the ones of the config with the schedules replaced
+    /// through the handle of the delay control, see [`crate::delay_control`].
+    pub fn current_delay_config(&self) -> DelayConfig {
+        self.delay_control.apply(&self.delay_config)
+    }
+    /// The round of the last unit of every member in the session. Once it is reached no more
+    /// units are created, and the session ends on its own after the last round that can be
+    /// decided with such units is finalized, see [`crate::SessionEnd`].
     pub fn max_round(&self) -> Round {
         self.max_round
     }
+    pub fn data_policy(&self) -> &DataPolicy {
+        &self.data_policy
+    }
+
+    pub fn clock(&self) -> &ClockSource {
+        &self.clock
+    }
+
+    pub fn broadcast_dedup_window(&self) -> Duration {
+        self.broadcast_dedup_window
+    }
+
+    pub fn broadcast_strategy(&self) -> BroadcastStrategy {
+        self.broadcast_strategy
+    }
+
+    pub fn adaptive_inclusion(&self) -> Option<AdaptiveInclusion> {
+        self.adaptive_inclusion
+    }
+
+    pub fn alert_rate_limit(&self) -> Option<AlertRateLimit> {
+        self.alert_rate_limit
+    }
+
+    pub fn rmc_completion_replies(&self) -> bool {
+        self.rmc_completion_replies
+    }
+
+    pub fn allow_small_committee(&self) -> bool {
+        self.allow_small_committee
+    }
+
+    pub fn broadcast_gate_timeout(&self) -> Duration {
+        self.broadcast_gate_timeout
+    }
+
+    pub fn on_broadcast_gate_timeout(&self) -> GateDecision {
+        self.on_broadcast_gate_timeout
+    }
+
+    pub fn max_unavailable_units(&self) -> usize {
+        self.max_unavailable_units
+    }
+
+    pub fn availability_recheck_interval(&self) -> Duration {
+        self.availability_recheck_interval
+    }
+
+    pub fn compact_unit_refs(&self) -> bool {
+        self.compact_unit_refs
+    }
+
+    pub fn compact_alerts(&self) -> bool {
+        self.compact_alerts
+    }
+
+    pub fn network_retry(&self) -> NetworkRetry {
+        self.network_retry
+    }
+
+    pub fn reconstruction_limits(&self) -> ReconstructionLimits {
+        self.reconstruction_limits
+    }
+
+    pub fn response_limits(&self) -> ResponseLimits {
+        self.response_limits
+    }
+
+    pub fn extender_flow_control(&self) -> Option<ExtenderFlowControl> {
+        self.extender_flow_control
+    }
+
+    pub fn max_unit_metadata_size(&self) -> usize {
+        self.max_unit_metadata_size
+    }
+
+    pub fn max_data_size(&self) -> usize {
+        self.max_data_size
+    }
+
+    pub fn max_round_lead(&self) -> Round {
+        self.max_round_lead
+    }
+
+    pub fn max_unfinalized_rounds(&self) -> Option<Round> {
+        self.max_unfinalized_rounds
+    }
+
+    pub fn fast_forward_lag(&self) -> Option<Round> {
+        self.fast_forward_lag
+    }
+
+    pub fn kept_rounds(&self) -> Option<Round> {
+        self.kept_rounds
+    }
+
+    pub fn max_pending_messages(&self) -> usize {
+        self.max_pending_messages
+    }
+
+    pub fn max_units_per_message(&self) -> usize {
+        self.max_units_per_message
+    }
+
+    pub fn max_message_size(&self) -> Option<usize> {
+        self.max_message_size
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    pub fn wire_version(&self) -> WireVersion {
+        self.wire_version
+    }
+
+    pub fn committee_id(&self) -> &[u8] {
+        &self.committee_id
+    }
+
+    pub fn lease_renewal_interval(&self) -> Duration {
+        self.lease_renewal_interval
+    }
+
+    pub fn migrate_backup(&self) -> bool {
+        self.migrate_backup
+    }
+
+    pub fn session_end_grace_period(&self) -> Duration {
+        self.session_end_grace_period
+    }
+
+    /// Makes us an observer of the session instead of a member of the committee, see
+    /// [`Role::Observer`]. Observers are known in the network under their own index outside of
+    /// the committee, i.e. at least `n_members`, which replaces the index of the configuration.
+    pub fn with_observer_index(self, node_ix: NodeIndex) -> Self {
+        Config {
+            node_ix,
+            role: Role::Observer,
+            ..self
+        }
+    }
+
+    /// Sets the local policy for flagging finalized data. The policy can differ between the nodes.
+    pub fn with_data_policy(self, data_policy: DataPolicy) -> Self {
+        Config {
+            data_policy,
+            ..self
+        }
+    }
+
+    /// Sets the clock used for all the delays and timeouts in the session, e.g. to run it in
+    /// simulated time. The real time is used by default.
+    pub fn with_clock(self, clock: ClockSource) -> Self {
+        Config { clock, ..self }
+    }
+
+    /// Allows changing the delays of the running session with the handle corresponding to the
+    /// given control, see [`crate::delay_control`].
+    pub fn with_delay_control(self, delay_control: DelayControl) -> Self {
+        Config {
+            delay_control,
+            ..self
+        }
+    }
+
+    /// Sets the window in which repeated broadcasts of the same unit are sent only once, a zero
+    /// window disables the deduplication. Defaults to [`DEFAULT_BROADCAST_DEDUP_WINDOW`].
+    pub fn with_broadcast_dedup_window(self, broadcast_dedup_window: Duration) -> Self {
+        Config {
+            broadcast_dedup_window,
+            ..self
+        }
+    }
+
+    /// Sets how units are spread, see [`BroadcastStrategy`]. Gossiping spares the creators
+    /// sending every unit to everyone, at the cost of units taking a few hops to reach everyone,
+    /// and of every node passing on every unit once. Requests, responses and alerts are sent as
+    /// usual. Nodes with different strategies understand each other. Defaults to
+    /// [`BroadcastStrategy::Direct`].
+    pub fn with_broadcast_strategy(self, broadcast_strategy: BroadcastStrategy) -> Self {
+        Config {
+            broadcast_strategy,
+            ..self
+        }
+    }
+
+    /// Sets how long our unit waits for the decision of the [`crate::BroadcastGate`] at most, and
+    /// what happens to it afterwards. Defaults to [`DEFAULT_BROADCAST_GATE_TIMEOUT`], after which
+    /// the data is replaced.
+    pub fn with_broadcast_gate_timeout(
+        self,
+        broadcast_gate_timeout: Duration,
+        on_broadcast_gate_timeout: GateDecision,
+    ) -> Self {
+        Config {
+            broadcast_gate_timeout,
+            on_broadcast_gate_timeout,
+            ..self
+        }
+    }
+
+    /// Sets how many units of other nodes can be held back at most while the
+    /// [`crate::DataAvailabilityChecker`] does not find their data available, together with the
+    /// units above them, and how often the data is checked again. New units from the network are
+    /// dropped while that many are held, they come again once requested. Defaults to
+    /// [`DEFAULT_MAX_UNAVAILABLE_UNITS`] and [`DEFAULT_AVAILABILITY_RECHECK_INTERVAL`].
+    pub fn with_availability_checks(
+        self,
+        max_unavailable_units: usize,
+        availability_recheck_interval: Duration,
+    ) -> Self {
+        Config {
+            max_unavailable_units,
+            availability_recheck_interval,
+            ..self
+        }
+    }
+
+    /// Allows running committees smaller than [`MIN_FAULT_TOLERANT_COMMITTEE`], e.g. for
+    /// development. Such committees need all of their members to make progress: a single
+    /// silent member stalls the session, and a single malicious one breaks its guarantees.
+    pub fn with_allow_small_committee(self, allow_small_committee: bool) -> Self {
+        Config {
+            allow_small_committee,
+            ..self
+        }
+    }
+
+    /// Makes the creator hold back the data of our units while they finalize late, see
+    /// [`AdaptiveInclusion`]. Disabled by default.
+    pub fn with_adaptive_inclusion(self, adaptive_inclusion: AdaptiveInclusion) -> Self {
+        Config {
+            adaptive_inclusion: Some(adaptive_inclusion),
+            ..self
+        }
+    }
+
+    /// Caps the rate at which we raise our own alerts, see [`AlertRateLimit`]. Unlimited by
+    /// default.
+    pub fn with_alert_rate_limit(self, alert_rate_limit: AlertRateLimit) -> Self {
+        Config {
+            alert_rate_limit: Some(alert_rate_limit),
+            ..self
+        }
+    }
+
+    /// Makes us answer the signatures still arriving for an alert RMC that already completed
+    /// with its multisignature, so that the sender completes it sooner. Every node gets at most
+    /// one such answer per RMC. Disabled by default.
+    pub fn with_rmc_completion_replies(self, rmc_completion_replies: bool) -> Self {
+        Config {
+            rmc_completion_replies,
+            ..self
+        }
+    }
+
+    /// Makes our parents responses refer to the parents the requester most likely holds, judging
+    /// by its DAG digest, by hash instead of sending them in full. The requester fetches the
+    /// referenced units it doesn't hold with a follow-up request. Every node understands such
+    /// responses, but older versions don't, so this should only be enabled once the whole
+    /// committee is upgraded. Disabled by default.
+    pub fn with_compact_unit_refs(self, compact_unit_refs: bool) -> Self {
+        Config {
+            compact_unit_refs,
+            ..self
+        }
+    }
+
+    /// Makes our fork alerts carry the rounds and hashes of the legit units of the forker instead
+    /// of the units themselves. The recipients fetch the units they don't hold with a follow-up
+    /// request before signing the alert. Such alerts are signed with a different hash than full
+    /// ones, and the versions predating them can neither decode nor sign them, so this requires
+    /// wire version at least [`crate::EXTENDED_MESSAGES_VERSION`]. Disabled by default.
+    pub fn with_compact_alerts(self, compact_alerts: bool) -> Self {
+        Config {
+            compact_alerts,
+            ..self
+        }
+    }
+
+    /// Sets how a network temporarily yielding no events is retried, see [`NetworkRetry`].
+    /// Defaults to [`DEFAULT_NETWORK_RETRY`].
+    pub fn with_network_retry(self, network_retry: NetworkRetry) -> Self {
+        Config {
+            network_retry,
+            ..self
+        }
+    }
+
+    /// Sets the bounds on the units waiting for parents, see [`ReconstructionLimits`]. Defaults
+    /// to [`DEFAULT_RECONSTRUCTION_LIMITS`].
+    pub fn with_reconstruction_limits(self, reconstruction_limits: ReconstructionLimits) -> Self {
+        Config {
+            reconstruction_limits,
+            ..self
+        }
+    }
+
+    /// Sets the bounds on the responses to requests for units by coord and for parents, see
+    /// [`ResponseLimits`]. Defaults to [`DEFAULT_RESPONSE_LIMITS`].
+    pub fn with_response_limits(self, response_limits: ResponseLimits) -> Self {
+        Config {
+            response_limits,
+            ..self
+        }
+    }
+
+    /// Makes the runway put off units far ahead of the ordering while it falls behind, see
+    /// [`ExtenderFlowControl`]. Disabled by default.
+    pub fn with_extender_flow_control(self, extender_flow_control: ExtenderFlowControl) -> Self {
+        Config {
+            extender_flow_control: Some(extender_flow_control),
+            ..self
+        }
+    }
+
+    /// Sets the largest metadata, in bytes, attached to units, see
+    /// [`crate::UnitMetadataProvider`]. Units of other nodes with larger metadata are rejected,
+    /// so the bound should be the same for the whole committee. Defaults to
+    /// [`DEFAULT_MAX_UNIT_METADATA_SIZE`].
+    pub fn with_max_unit_metadata_size(self, max_unit_metadata_size: usize) -> Self {
+        Config {
+            max_unit_metadata_size,
+            ..self
+        }
+    }
+
+    /// Sets the largest encoded data, in bytes, put in a unit. Data from the
+    /// [`crate::DataProvider`] above the bound is dropped and our unit is created without data,
+    /// while units of other nodes with larger data are rejected, so the bound should be the same
+    /// for the whole committee. Defaults to [`DEFAULT_MAX_DATA_SIZE`], i.e. no bound.
+    pub fn with_max_data_size(self, max_data_size: usize) -> Self {
+        Config {
+            max_data_size,
+            ..self
+        }
+    }
+
+    /// Sets how many rounds above the top round of our DAG a unit sent to us unprompted can be.
+    /// Units further ahead are dropped, as honest nodes are never that far ahead of us, unless
+    /// we fell behind, in which case we catch up by requesting their units instead. Our own
+    /// units and units of fork alerts are only bounded by the max round. Defaults to
+    /// [`DEFAULT_MAX_ROUND_LEAD`].
+    pub fn with_max_round_lead(self, max_round_lead: Round) -> Self {
+        Config {
+            max_round_lead,
+            ..self
+        }
+    }
+
+    /// Sets the voting weights of the members, e.g. their stakes, in the order of their indices.
+    /// Quorums are then sets of members weighing more than two thirds of the total weight rather
+    /// than sets of more than two thirds of the members, both among the parents of units and
+    /// among the signers of multisignatures, so the keychain has to use the same weights in
+    /// [`crate::MultiKeychain::is_complete`]. All the members have to agree on the weights, and
+    /// every member has to weigh something. Every member weighs one by default.
+    pub fn with_node_weights(self, node_weights: NodeWeights) -> Self {
+        Config {
+            node_weights: Some(node_weights),
+            ..self
+        }
+    }
+
+    /// Sets how many rounds above the round of the last finalized batch head our units can be.
+    /// Once finalization stalls, e.g. with a third of the nodes down, the creator pauses at that
+    /// round instead of growing the unfinalized part of the DAG, and resumes as finalization
+    /// catches up. The limit engages only after the first batch is finalized in the session, as
+    /// the last finalized round is unknown before, e.g. after starting from the backup. Unlimited
+    /// by default.
+    pub fn with_max_unfinalized_rounds(self, max_unfinalized_rounds: Round) -> Self {
+        Config {
+            max_unfinalized_rounds: Some(max_unfinalized_rounds),
+            ..self
+        }
+    }
+
+    /// Sets how many rounds below the top of our DAG our next unit can be before we fast-forward,
+    /// e.g. after starting from an old backup while the rest of the committee went far ahead.
+    /// We cannot skip any round, as every unit of ours needs our unit of the previous round as a
+    /// parent, but the units of rounds that far behind are created right away, without data and
+    /// without passing the broadcast gate, and the data goes into our first unit close enough to
+    /// the top. Only units admitted to the DAG count, so nobody can make us hold back our data by
+    /// sending units of rounds the committee did not reach. A lag of a few rounds is never seen
+    /// while we keep up. Disabled if absent, defaults to [`DEFAULT_FAST_FORWARD_LAG`].
+    pub fn with_fast_forward_lag(self, fast_forward_lag: Option<Round>) -> Self {
+        Config {
+            fast_forward_lag,
+            ..self
+        }
+    }
+
+    /// Sets how many rounds below the last finalized one units are kept in memory. Units of older
+    /// rounds are pruned as finalization advances, except for the newest unit of every creator,
+    /// and requests for them are answered as if we did not have them. Units of pruned rounds
+    /// coming later are ignored, so forks there go unnoticed, and nodes lagging further behind
+    /// cannot catch up with us by requesting units one by one. They learn that we pruned them
+    /// and catch up with our newest units instead. Units are only ever pruned once saved to the
+    /// backup. Has to be at least [`MIN_KEPT_ROUNDS`], all units are kept by default.
+    pub fn with_kept_rounds(self, kept_rounds: Round) -> Self {
+        Config {
+            kept_rounds: Some(kept_rounds),
+            ..self
+        }
+    }
+
+    /// Sets how many unit messages from the network wait for processing in any queue at most.
+    /// When a queue is full, requests for units and responses to them are dropped first, as they
+    /// are repeated if lost, and otherwise the incoming message is dropped. Our own units and
+    /// alerts never go through these queues. Defaults to [`DEFAULT_MAX_PENDING_MESSAGES`].
+    pub fn with_max_pending_messages(self, max_pending_messages: usize) -> Self {
+        Config {
+            max_pending_messages,
+            ..self
+        }
+    }
+
+    /// Sets how many units, or references to units, a unit message from the network may hold,
+    /// e.g. in a response to a request for parents. Messages holding more are dropped right after
+    /// they arrive. Honest messages never hold more units than there are members, which is both
+    /// the default and the minimum.
+    pub fn with_max_units_per_message(self, max_units_per_message: usize) -> Self {
+        Config {
+            max_units_per_message,
+            ..self
+        }
+    }
+
+    /// Sets the size, in bytes, of the largest encoded message accepted from the network. Larger
+    /// messages are dropped right after they arrive, so the bound has to accommodate the largest
+    /// units of the committee, including their data. Unlimited by default.
+    pub fn with_max_message_size(self, max_message_size: usize) -> Self {
+        Config {
+            max_message_size: Some(max_message_size),
+            ..self
+        }
+    }
+
+    /// Sets how the messages we send are compressed, after they are encoded. Compressed messages
+    /// from others are decompressed whatever the setting, so it can differ between nodes, and
+    /// they cannot decompress to more than the max message size, nor to more than
+    /// [`crate::MAX_DECOMPRESSED_SIZE`] bytes. The releases predating compression cannot decode
+    /// compressed messages, so it requires wire version at least
+    /// [`crate::EXTENDED_MESSAGES_VERSION`], and every node has to be built with the `zstd`
+    /// feature. Messages are not compressed by default.
+    pub fn with_compression(self, compression: Compression) -> Self {
+        Config {
+            compression,
+            ..self
+        }
+    }
+
+    /// Sets the version of the encoding of the messages we send, at most
+    /// [`crate::MAX_SUPPORTED_VERSION`]. Messages of every supported version are decoded whatever
+    /// the setting, so during an upgrade all the nodes first get a version that supports the new
+    /// encoding, and only then start sending it. Version 0, the encoding of the versions that
+    /// predate this setting, is used by default, at which our units carry no metadata and each of
+    /// our alerts is about a single forker, see [`crate::EXTENDED_MESSAGES_VERSION`].
+    pub fn with_wire_version(self, wire_version: WireVersion) -> Self {
+        Config {
+            wire_version,
+            ..self
+        }
+    }
+
+    /// Sets the identifier of the composition of the committee, e.g. the hash of the public keys
+    /// of its members. It is recorded in every new backup, and a backup recorded with a
+    /// different identifier is refused, see [`Config::with_migrate_backup`]. Empty by default.
+    pub fn with_committee_id(self, committee_id: Vec<u8>) -> Self {
+        Config {
+            committee_id,
+            ..self
+        }
+    }
+
+    /// Makes the session load a backup written by a different committee, keeping only the units
+    /// that are still valid for this committee, instead of refusing to run. The discarded units
+    /// are reported in the logs. Meant for recovering from a committee change reusing the session
+    /// id by mistake. Disabled by default.
+    pub fn with_migrate_backup(self, migrate_backup: bool) -> Self {
+        Config {
+            migrate_backup,
+            ..self
+        }
+    }
+
+    /// Sets how often the lease of our seat in the committee is renewed, see
+    /// [`crate::lease_control`]. The lease of a stopped instance is taken over after
+    /// [`crate::LEASE_EXPIRY_CHECKS`] intervals, so a shorter one replaces it faster, at the cost
+    /// of more frequent accesses to the store. Defaults to [`DEFAULT_LEASE_RENEWAL_INTERVAL`].
+    pub fn with_lease_renewal_interval(self, lease_renewal_interval: Duration) -> Self {
+        Config {
+            lease_renewal_interval,
+            ..self
+        }
+    }
+
+    /// Sets how long the session keeps running after the head of its last round is finalized,
+    /// see [`crate::SessionEnd::FinalizationEnded`]. In the meantime nothing is created or
+    /// finalized anymore, but requests for our units are still answered, so that nodes lagging
+    /// slightly behind can finalize the last rounds too. Afterwards the session ends on its own,
+    /// just as if it was stopped with its terminator. Defaults to
+    /// [`DEFAULT_SESSION_END_GRACE_PERIOD`].
+    pub fn with_session_end_grace_period(self, session_end_grace_period: Duration) -> Self {
+        Config {
+            session_end_grace_period,
+            ..self
+        }
+    }
 }
 
+/// A delay of `base_delay` milliseconds up to the step `start_exp_delay`, growing `exp_base`
+/// times with every step after it. The base can change while the session runs, e.g. through a
+/// [`crate::DelayControlHandle`], while the growth still depends on the step only.
 pub fn exponential_slowdown(
     t: usize,
     base_delay: f64,
@@ -116,6 +1300,8 @@ pub fn exponential_slowdown(
 
 /// Creates a [`Config`] which wraps the passed arguments. `time_to_reach_max_round` is a lower bound
 /// on the time needed to reach the maximum round expected by the user and is only used for verification.
+/// Committees smaller than [`MIN_FAULT_TOLERANT_COMMITTEE`] additionally need
+/// [`Config::with_allow_small_committee`], otherwise the session refuses to run.
 pub fn create_config(
     n_members: NodeCount,
     node_ix: NodeIndex,
@@ -132,13 +1318,51 @@ pub fn create_config(
         return Err(InvalidConfigError);
     }
 
-    Ok(Config {
+    let config = Config {
         node_ix,
+        role: Role::Member,
         session_id,
         n_members,
+        node_weights: None,
         delay_config,
+        delay_control: DelayControl::default(),
         max_round,
-    })
+        data_policy: DataPolicy::default(),
+        clock: ClockSource::default(),
+        broadcast_dedup_window: DEFAULT_BROADCAST_DEDUP_WINDOW,
+        broadcast_strategy: BroadcastStrategy::Direct,
+        adaptive_inclusion: None,
+        allow_small_committee: false,
+        broadcast_gate_timeout: DEFAULT_BROADCAST_GATE_TIMEOUT,
+        on_broadcast_gate_timeout: GateDecision::ReplaceWithEmpty,
+        max_unavailable_units: DEFAULT_MAX_UNAVAILABLE_UNITS,
+        availability_recheck_interval: DEFAULT_AVAILABILITY_RECHECK_INTERVAL,
+        alert_rate_limit: None,
+        rmc_completion_replies: false,
+        compact_unit_refs: false,
+        compact_alerts: false,
+        network_retry: DEFAULT_NETWORK_RETRY,
+        reconstruction_limits: DEFAULT_RECONSTRUCTION_LIMITS,
+        response_limits: DEFAULT_RESPONSE_LIMITS,
+        extender_flow_control: None,
+        max_unit_metadata_size: DEFAULT_MAX_UNIT_METADATA_SIZE,
+        max_data_size: DEFAULT_MAX_DATA_SIZE,
+        max_round_lead: DEFAULT_MAX_ROUND_LEAD,
+        max_unfinalized_rounds: None,
+        fast_forward_lag: Some(DEFAULT_FAST_FORWARD_LAG),
+        kept_rounds: None,
+        max_pending_messages: DEFAULT_MAX_PENDING_MESSAGES,
+        max_units_per_message: n_members.0,
+        max_message_size: None,
+        compression: Compression::None,
+        wire_version: 0,
+        committee_id: Vec::new(),
+        migrate_backup: false,
+        lease_renewal_interval: DEFAULT_LEASE_RENEWAL_INTERVAL,
+        session_end_grace_period: DEFAULT_SESSION_END_GRACE_PERIOD,
+    };
+    config.check_consistency()?;
+    Ok(config)
 }
 
 /// Creates a [`Config`], allowing the user to omit specifying the `delay_config` in which case it will be
@@ -174,6 +1398,7 @@ pub fn default_delay_config() -> DelayConfig {
         parent_request_delay: Arc::new(|_| Duration::from_millis(3000)),
         parent_request_recipients: Arc::new(|_| 1),
         newest_request_delay: Arc::new(|_| Duration::from_millis(3000)),
+        data_fetch_timeout: None,
     }
 }
 
@@ -200,29 +1425,298 @@ fn default_coord_request_recipients() -> RecipientCountSchedule {
     Arc::new(|t| if t <= 2 { 3 } else { 1 })
 }
 
-fn time_to_reach_round(round: Round, delay_schedule: &DelaySchedule) -> Duration {
-    let mut total_time = Duration::from_millis(0);
-    for r in 0..round {
-        total_time += delay_schedule(r as usize);
-    }
-    total_time
-}
+/// The default window in which repeated broadcasts of the same unit are sent only once. It is
+/// much shorter than the unit rebroadcast intervals, so deliberate rebroadcasts are not affected.
+pub const DEFAULT_BROADCAST_DEDUP_WINDOW: Duration = Duration::from_millis(500);
 
-#[cfg(test)]
-mod tests {
-    use crate::{
-        config::{
-            default_coord_request_delay, default_coord_request_recipients, time_to_reach_round,
-            DelaySchedule,
-        },
-        create_config, exponential_slowdown, DelayConfig, NodeCount, NodeIndex,
-    };
-    use std::{sync::Arc, time::Duration};
+/// The default time our unit waits for the decision of the [`crate::BroadcastGate`], a few
+/// times the default unit creation delay.
+pub const DEFAULT_BROADCAST_GATE_TIMEOUT: Duration = Duration::from_secs(2);
 
-    const MILLIS_IN_WEEK: u64 = 1000 * 60 * 60 * 24 * 7;
+/// The default bound on the units held back until their data is available, a few hundred rounds
+/// of a large committee.
+pub const DEFAULT_MAX_UNAVAILABLE_UNITS: usize = 10_000;
 
-    fn delay_config_for_tests() -> DelayConfig {
-        DelayConfig {
+/// The default interval of checking again whether data is available, a few times the default
+/// unit creation delay.
+pub const DEFAULT_AVAILABILITY_RECHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The default retrying of a network temporarily yielding no events, riding out gaps of over
+/// ten seconds.
+pub const DEFAULT_NETWORK_RETRY: NetworkRetry = NetworkRetry {
+    max_consecutive_failures: 16,
+    initial_backoff: Duration::from_millis(50),
+    max_backoff: Duration::from_secs(1),
+};
+
+/// The default bounds on the units waiting for parents. Honest nodes hardly ever have more than
+/// a few units waiting, unless we are catching up, and the timeout leaves plenty of time for
+/// parents that are merely slow.
+pub const DEFAULT_RECONSTRUCTION_LIMITS: ReconstructionLimits = ReconstructionLimits {
+    max_pending_per_creator: 256,
+    suspect_timeout: Duration::from_secs(30),
+};
+
+/// The default bounds on the responses to requests. Honest nodes repeat a request only after
+/// waiting for the response for a while, see [`DelayConfig::coord_request_delay`], and ask for
+/// a few units per second of every member at most, unless they are catching up.
+pub const DEFAULT_RESPONSE_LIMITS: ResponseLimits = ResponseLimits {
+    dedup_window: Duration::from_millis(500),
+    max_per_second: 512,
+    cache_size: 256,
+};
+
+/// The default bound on the metadata attached to units, enough for a few short identifiers, e.g.
+/// a software version.
+pub const DEFAULT_MAX_UNIT_METADATA_SIZE: usize = 128;
+
+/// The default bound on the encoded data in units, none at all, as only the application knows
+/// how large its data gets.
+pub const DEFAULT_MAX_DATA_SIZE: usize = usize::MAX;
+
+/// The default bound on how far ahead of our DAG units from the network can be, far more rounds
+/// than honest nodes get ahead of each other while we keep up.
+pub const DEFAULT_MAX_ROUND_LEAD: Round = 500;
+
+/// The default lag behind the top of our DAG from which our units are created without data, more
+/// rounds than we fall behind while we keep up.
+pub const DEFAULT_FAST_FORWARD_LAG: Round = 4;
+
+/// The default bound on the unit messages from the network waiting for processing, many times
+/// more than honest committees send while the session keeps up.
+pub const DEFAULT_MAX_PENDING_MESSAGES: usize = 20_000;
+
+/// The default interval of renewing the lease, see [`crate::lease_control`]. A stopped instance
+/// is replaced after a few intervals.
+pub const DEFAULT_LEASE_RENEWAL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The default time the session keeps answering requests after finalizing its last head, long
+/// enough for the nodes a few rounds behind to fetch the units they miss.
+pub const DEFAULT_SESSION_END_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// The fewest rounds below the last finalized one that can be kept, see
+/// [`Config::with_kept_rounds`], so that the nodes a few rounds behind still get the units they
+/// miss one by one.
+pub const MIN_KEPT_ROUNDS: Round = 10;
+
+/// The smallest committee tolerating a faulty member, smaller ones have to be allowed with
+/// [`Config::with_allow_small_committee`].
+pub const MIN_FAULT_TOLERANT_COMMITTEE: NodeCount = NodeCount(4);
+
+/// How many initial steps of every schedule are rendered by [`Config::describe`].
+const DESCRIBED_SCHEDULE_STEPS: usize = 5;
+
+/// Every preset keeps creating units at its base pace for at least this long, after which the
+/// creation slows down exponentially, just like with [`default_delay_config`].
+const PRESET_FULL_SPEED_DURATION: Duration = Duration::from_secs(25 * 60);
+
+/// The growth rate of the unit creation delay after `PRESET_FULL_SPEED_DURATION` passes.
+const PRESET_SLOWDOWN_BASE: f64 = 1.005;
+
+/// The one-way message latency assumed by the LAN presets.
+const LAN_EXPECTED_LATENCY: Duration = Duration::from_millis(5);
+
+/// The one-way message latency assumed by the WAN presets.
+const WAN_EXPECTED_LATENCY: Duration = Duration::from_millis(150);
+
+/// Requests are never retried more often than this, even on fast networks, to avoid flooding
+/// peers that are just slow to respond.
+const PRESET_MIN_REQUEST_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// How many of the first tries of requesting a unit by coords go to multiple peers at once.
+const PRESET_FIRST_TRIES: usize = 3;
+
+/// The largest committee the small presets are tuned for. Bigger committees should use the large
+/// presets, which trade some latency for lower bandwidth.
+const SMALL_COMMITTEE_MAX_SIZE: usize = 10;
+
+/// Ready-made delay configurations for common deployments. They differ in how fast units are
+/// created and how aggressively missing units are requested:
+/// - the unit creation delay is a few round trips, so that a unit usually gathers all the parents
+///   that were created around the same time, and grows with the committee size, since larger
+///   committees send quadratically more data per round,
+/// - requests are first retried after roughly one round trip and then increasingly slowly,
+///   large committees ask more peers at once in the first tries, since it is cheap compared to
+///   a broadcast,
+/// - rebroadcasts are only a fallback for lost messages, so they happen rarely, especially in
+///   large committees.
+///
+/// These are starting points, the numbers can be reviewed with [`Config::describe`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ConfigPreset {
+    /// At most 10 nodes in a single datacenter or on a local network.
+    LanSmall,
+    /// Up to around a hundred nodes in a single datacenter or on a local network.
+    LanLarge,
+    /// At most 10 nodes connected over the internet.
+    WanSmall,
+    /// Up to around a hundred nodes connected over the internet.
+    WanLarge,
+}
+
+impl ConfigPreset {
+    /// All the available presets.
+    pub const ALL: [ConfigPreset; 4] = [
+        ConfigPreset::LanSmall,
+        ConfigPreset::LanLarge,
+        ConfigPreset::WanSmall,
+        ConfigPreset::WanLarge,
+    ];
+
+    /// The preset suggested for a committee of the given size.
+    pub fn for_committee(n_members: NodeCount, over_wan: bool) -> Self {
+        use ConfigPreset::*;
+        match (n_members.0 <= SMALL_COMMITTEE_MAX_SIZE, over_wan) {
+            (true, false) => LanSmall,
+            (false, false) => LanLarge,
+            (true, true) => WanSmall,
+            (false, true) => WanLarge,
+        }
+    }
+
+    /// The one-way message latency this preset is tuned for.
+    pub fn expected_latency(&self) -> Duration {
+        use ConfigPreset::*;
+        match self {
+            LanSmall | LanLarge => LAN_EXPECTED_LATENCY,
+            WanSmall | WanLarge => WAN_EXPECTED_LATENCY,
+        }
+    }
+
+    fn is_large(&self) -> bool {
+        use ConfigPreset::*;
+        matches!(self, LanLarge | WanLarge)
+    }
+
+    /// How often the member checks for scheduled tasks, well below the expected latency.
+    pub fn tick_interval(&self) -> Duration {
+        use ConfigPreset::*;
+        match self {
+            LanSmall | LanLarge => Duration::from_millis(5),
+            WanSmall | WanLarge => Duration::from_millis(10),
+        }
+    }
+
+    /// The delay between creating subsequent units when running at full speed.
+    pub fn unit_creation_delay(&self) -> Duration {
+        use ConfigPreset::*;
+        match self {
+            LanSmall => Duration::from_millis(100),
+            LanLarge => Duration::from_millis(200),
+            WanSmall => Duration::from_millis(500),
+            WanLarge => Duration::from_millis(1000),
+        }
+    }
+
+    /// The delay before creating the very first unit, giving other members time to start.
+    pub fn initial_unit_creation_delay(&self) -> Duration {
+        use ConfigPreset::*;
+        match self {
+            LanSmall | LanLarge => Duration::from_millis(1000),
+            WanSmall | WanLarge => Duration::from_millis(3000),
+        }
+    }
+
+    /// The number of rounds created at full speed, before the exponential slowdown starts.
+    pub fn full_speed_rounds(&self) -> usize {
+        (PRESET_FULL_SPEED_DURATION.as_millis() / self.unit_creation_delay().as_millis()) as usize
+    }
+
+    /// Creates the [`DelayConfig`] described by this preset.
+    pub fn delay_config(&self) -> DelayConfig {
+        let round_trip = self.expected_latency() * 2;
+        let initial_unit_creation_delay = self.initial_unit_creation_delay();
+        let unit_creation_delay = self.unit_creation_delay().as_millis() as f64;
+        let full_speed_rounds = self.full_speed_rounds();
+        let request_retry_delay = max(round_trip * 10, PRESET_MIN_REQUEST_RETRY_DELAY);
+        let (unit_rebroadcast_interval_min, unit_rebroadcast_interval_max) = match self.is_large() {
+            true => (Duration::from_secs(20), Duration::from_secs(30)),
+            false => (Duration::from_secs(10), Duration::from_secs(15)),
+        };
+        let first_tries_recipients = match self.is_large() {
+            true => 5,
+            false => 3,
+        };
+        DelayConfig {
+            tick_interval: self.tick_interval(),
+            unit_rebroadcast_interval_min,
+            unit_rebroadcast_interval_max,
+            unit_creation_delay: Arc::new(move |t| match t {
+                0 => initial_unit_creation_delay,
+                _ => exponential_slowdown(
+                    t,
+                    unit_creation_delay,
+                    full_speed_rounds,
+                    PRESET_SLOWDOWN_BASE,
+                ),
+            }),
+            coord_request_delay: Arc::new(move |t| match t {
+                0 => Duration::ZERO,
+                1 => round_trip,
+                _ => request_retry_delay.saturating_mul(u32::try_from(t - 1).unwrap_or(u32::MAX)),
+            }),
+            coord_request_recipients: Arc::new(move |t| match t < PRESET_FIRST_TRIES {
+                true => first_tries_recipients,
+                false => 1,
+            }),
+            parent_request_delay: Arc::new(move |_| request_retry_delay),
+            parent_request_recipients: Arc::new(|_| 1),
+            newest_request_delay: Arc::new(move |_| request_retry_delay),
+            data_fetch_timeout: None,
+        }
+    }
+
+    /// Creates a [`Config`] using the delays described by this preset, see [`create_config`].
+    pub fn config(
+        &self,
+        n_members: NodeCount,
+        node_ix: NodeIndex,
+        session_id: SessionId,
+        max_round: Round,
+        time_to_reach_max_round: Duration,
+    ) -> Result<Config, InvalidConfigError> {
+        create_config(
+            n_members,
+            node_ix,
+            session_id,
+            max_round,
+            self.delay_config(),
+            time_to_reach_max_round,
+        )
+    }
+}
+
+fn time_to_reach_round(round: Round, delay_schedule: &DelaySchedule) -> Duration {
+    let mut total_time = Duration::from_millis(0);
+    for r in 0..round {
+        total_time = total_time.saturating_add(delay_schedule(r as usize));
+    }
+    total_time
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::{
+            default_coord_request_delay, default_coord_request_recipients, time_to_reach_round,
+            DelaySchedule,
+        },
+        create_config, exponential_slowdown,
+        protocol::PROTOCOL_VERSION,
+        AdaptiveInclusion, AlertRateLimit, BroadcastStrategy, ConfigPreset, DataPolicy,
+        DelayConfig, ExtenderFlowControl, NetworkRetry, NodeCount, NodeIndex, NodeWeights,
+        ReconstructionLimits, ResponseLimits, Role, Weight, DEFAULT_AVAILABILITY_RECHECK_INTERVAL,
+        DEFAULT_FAST_FORWARD_LAG, DEFAULT_LEASE_RENEWAL_INTERVAL, DEFAULT_MAX_DATA_SIZE,
+        DEFAULT_MAX_ROUND_LEAD, DEFAULT_MAX_UNAVAILABLE_UNITS, DEFAULT_MAX_UNIT_METADATA_SIZE,
+        EXTENDED_MESSAGES_VERSION, MAX_SUPPORTED_VERSION, MIN_FAULT_TOLERANT_COMMITTEE,
+        MIN_KEPT_ROUNDS,
+    };
+    use std::{sync::Arc, time::Duration};
+
+    const MILLIS_IN_WEEK: u64 = 1000 * 60 * 60 * 24 * 7;
+
+    fn delay_config_for_tests() -> DelayConfig {
+        DelayConfig {
             tick_interval: Duration::from_millis(10),
             unit_rebroadcast_interval_min: Duration::from_millis(15000),
             unit_rebroadcast_interval_max: Duration::from_millis(20000),
@@ -235,6 +1729,7 @@ mod tests {
             parent_request_delay: Arc::new(|_| Duration::from_millis(3000)),
             parent_request_recipients: Arc::new(|_| 1),
             newest_request_delay: Arc::new(|_| Duration::from_millis(3000)),
+            data_fetch_timeout: None,
         }
     }
 
@@ -296,4 +1791,807 @@ mod tests {
 
         assert!(config.is_ok());
     }
+
+    #[test]
+    fn presets_pass_validation() {
+        for preset in ConfigPreset::ALL {
+            for n_members in [1, 4, 10, 50, 100] {
+                let n_members = NodeCount(n_members);
+                let config = preset
+                    .config(
+                        n_members,
+                        NodeIndex(n_members.0 - 1),
+                        0,
+                        5000,
+                        Duration::ZERO,
+                    )
+                    .expect("preset should produce a valid config")
+                    .with_allow_small_committee(n_members < MIN_FAULT_TOLERANT_COMMITTEE);
+                assert!(config.validate().is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn presets_last_at_least_a_week_with_high_max_round() {
+        for preset in ConfigPreset::ALL {
+            let config = preset.config(
+                NodeCount(10),
+                NodeIndex(0),
+                0,
+                20000,
+                Duration::from_millis(MILLIS_IN_WEEK),
+            );
+            assert!(config.is_ok(), "{:?} reaches max round too fast", preset);
+        }
+    }
+
+    #[test]
+    fn presets_chosen_by_committee_size() {
+        assert_eq!(
+            ConfigPreset::for_committee(NodeCount(4), false),
+            ConfigPreset::LanSmall
+        );
+        assert_eq!(
+            ConfigPreset::for_committee(NodeCount(40), false),
+            ConfigPreset::LanLarge
+        );
+        assert_eq!(
+            ConfigPreset::for_committee(NodeCount(10), true),
+            ConfigPreset::WanSmall
+        );
+        assert_eq!(
+            ConfigPreset::for_committee(NodeCount(100), true),
+            ConfigPreset::WanLarge
+        );
+    }
+
+    #[test]
+    fn node_outside_committee_fails_validation() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(5),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        );
+
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn empty_rebroadcast_interval_fails_validation() {
+        let mut delay_config = delay_config_for_tests();
+        delay_config.unit_rebroadcast_interval_max = delay_config.unit_rebroadcast_interval_min;
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config,
+            Duration::ZERO,
+        );
+
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn describes_effective_delays() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        let description = config.describe();
+
+        assert!(description.contains(&format!("protocol version: {}", PROTOCOL_VERSION)));
+        assert!(description.contains("committee size: 5"));
+        assert!(description.contains("max round: 7000"));
+        assert!(
+            description.contains("unit creation delay: 2000ms, 300ms, 300ms, 300ms, 300ms, ...")
+        );
+        assert!(description.contains("coord request delay: 0ms, 50ms, 1000ms, 3000ms, 6000ms, ..."));
+        assert!(description.contains("coord request recipients: 3, 3, 3, 1, 1, ..."));
+        assert!(description.contains("unit rebroadcast interval: 15000ms - 20000ms"));
+        assert!(description.contains("broadcast dedup window: 500ms"));
+        assert!(description.contains("broadcast gate timeout: 2000ms, then ReplaceWithEmpty"));
+    }
+
+    #[test]
+    fn small_committees_have_to_be_allowed() {
+        for n_members in 1..=4 {
+            let n_members = NodeCount(n_members);
+            let config = create_config(
+                n_members,
+                NodeIndex(0),
+                0,
+                5000,
+                delay_config_for_tests(),
+                Duration::ZERO,
+            )
+            .expect("config should be consistent");
+            let fault_tolerant = n_members >= MIN_FAULT_TOLERANT_COMMITTEE;
+            assert_eq!(config.validate().is_ok(), fault_tolerant);
+            let config = config.with_allow_small_committee(true);
+            assert!(config.validate().is_ok());
+            let faults = match fault_tolerant {
+                true => 1,
+                false => 0,
+            };
+            assert!(config
+                .describe()
+                .contains(&format!("tolerated faulty members: {}", faults)));
+        }
+    }
+
+    #[test]
+    fn adaptive_inclusion_has_to_resume_below_pause() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        let inclusion = AdaptiveInclusion {
+            pause_latency: 10,
+            resume_latency: 4,
+            window: 8,
+        };
+        let config = config.with_adaptive_inclusion(inclusion);
+        assert!(config.validate().is_ok());
+        assert!(config
+            .describe()
+            .contains("adaptive inclusion: pause at 10 rounds, resume at 4 rounds"));
+        for inclusion in [
+            AdaptiveInclusion {
+                resume_latency: 10,
+                ..inclusion
+            },
+            AdaptiveInclusion {
+                window: 0,
+                ..inclusion
+            },
+        ] {
+            assert!(config
+                .clone()
+                .with_adaptive_inclusion(inclusion)
+                .validate()
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn extender_flow_control_has_to_allow_a_backlog() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert!(config
+            .describe()
+            .contains("extender flow control: disabled"));
+        let flow_control = ExtenderFlowControl {
+            max_backlog: 100,
+            horizon: 4,
+            max_deferred: 1000,
+        };
+        let config = config.with_extender_flow_control(flow_control);
+        assert!(config.validate().is_ok());
+        assert!(config
+            .describe()
+            .contains("extender flow control: backlog of 100 units, horizon of 4 rounds"));
+        assert!(config.describe().contains("up to 1000 units kept aside"));
+        for flow_control in [
+            ExtenderFlowControl {
+                max_backlog: 0,
+                ..flow_control
+            },
+            ExtenderFlowControl {
+                horizon: 0,
+                ..flow_control
+            },
+            ExtenderFlowControl {
+                max_deferred: 0,
+                ..flow_control
+            },
+        ] {
+            assert!(config
+                .clone()
+                .with_extender_flow_control(flow_control)
+                .validate()
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn alert_rate_limit_has_to_allow_alerts() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert!(config.describe().contains("alert rate limit: disabled"));
+        let limit = AlertRateLimit {
+            per_minute: 2,
+            per_session: 5,
+        };
+        let limited = config.clone().with_alert_rate_limit(limit);
+        assert!(limited.validate().is_ok());
+        assert!(limited
+            .describe()
+            .contains("alert rate limit: 2 per minute, 5 per session"));
+        for limit in [
+            AlertRateLimit {
+                per_minute: 0,
+                ..limit
+            },
+            AlertRateLimit {
+                per_session: 0,
+                ..limit
+            },
+        ] {
+            assert!(config
+                .clone()
+                .with_alert_rate_limit(limit)
+                .validate()
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn network_retry_backoff_has_to_be_positive_and_capped() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert!(config
+            .describe()
+            .contains("network retry: 16 attempts, backoff 50ms - 1000ms"));
+        let retry = NetworkRetry {
+            max_consecutive_failures: 0,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(10),
+        };
+        assert!(config.clone().with_network_retry(retry).validate().is_ok());
+        for retry in [
+            NetworkRetry {
+                initial_backoff: Duration::ZERO,
+                ..retry
+            },
+            NetworkRetry {
+                max_backoff: Duration::from_millis(5),
+                ..retry
+            },
+        ] {
+            assert!(config.clone().with_network_retry(retry).validate().is_err());
+        }
+    }
+
+    #[test]
+    fn reconstruction_limits_have_to_be_positive() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert!(config.describe().contains(
+            "reconstruction limits: 256 pending units per creator, suspects evicted after 30000ms"
+        ));
+        let limits = ReconstructionLimits {
+            max_pending_per_creator: 1,
+            suspect_timeout: Duration::from_millis(1),
+        };
+        assert!(config
+            .clone()
+            .with_reconstruction_limits(limits)
+            .validate()
+            .is_ok());
+        for limits in [
+            ReconstructionLimits {
+                max_pending_per_creator: 0,
+                ..limits
+            },
+            ReconstructionLimits {
+                suspect_timeout: Duration::ZERO,
+                ..limits
+            },
+        ] {
+            assert!(config
+                .clone()
+                .with_reconstruction_limits(limits)
+                .validate()
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn response_limits_have_to_allow_some_responses() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert!(config.describe().contains(
+            "response limits: repeated requests dropped for 500ms, 512 responses per peer per second, 256 cached"
+        ));
+        let limits = ResponseLimits {
+            dedup_window: Duration::ZERO,
+            max_per_second: 1,
+            cache_size: 0,
+        };
+        assert!(config
+            .clone()
+            .with_response_limits(limits)
+            .validate()
+            .is_ok());
+        assert!(config
+            .with_response_limits(ResponseLimits {
+                max_per_second: 0,
+                ..limits
+            })
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn max_unit_metadata_size_is_described() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert_eq!(
+            config.max_unit_metadata_size(),
+            DEFAULT_MAX_UNIT_METADATA_SIZE
+        );
+        assert!(config
+            .describe()
+            .contains("max unit metadata size: 128 bytes"));
+        let config = config.with_max_unit_metadata_size(0);
+        assert!(config.validate().is_ok());
+        assert!(config
+            .describe()
+            .contains("max unit metadata size: 0 bytes"));
+    }
+
+    #[test]
+    fn max_data_size_is_described() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert_eq!(config.max_data_size(), DEFAULT_MAX_DATA_SIZE);
+        assert!(config.describe().contains("max data size: unlimited"));
+        let config = config.with_max_data_size(1 << 16);
+        assert!(config.validate().is_ok());
+        assert!(config.describe().contains("max data size: 65536 bytes"));
+    }
+
+    #[test]
+    fn max_round_lead_has_to_be_positive() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert_eq!(config.max_round_lead(), DEFAULT_MAX_ROUND_LEAD);
+        assert!(config.describe().contains("max round lead: 500"));
+        assert!(config.clone().with_max_round_lead(0).validate().is_err());
+        let config = config.with_max_round_lead(20);
+        assert!(config.validate().is_ok());
+        assert!(config.describe().contains("max round lead: 20"));
+    }
+
+    #[test]
+    fn max_unfinalized_rounds_have_to_be_positive() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert_eq!(config.max_unfinalized_rounds(), None);
+        assert!(config
+            .describe()
+            .contains("max unfinalized rounds: unlimited"));
+        assert!(config
+            .clone()
+            .with_max_unfinalized_rounds(0)
+            .validate()
+            .is_err());
+        let config = config.with_max_unfinalized_rounds(30);
+        assert!(config.validate().is_ok());
+        assert!(config.describe().contains("max unfinalized rounds: 30"));
+    }
+
+    #[test]
+    fn fast_forward_lag_has_to_be_positive() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert_eq!(config.fast_forward_lag(), Some(DEFAULT_FAST_FORWARD_LAG));
+        assert!(config
+            .clone()
+            .with_fast_forward_lag(Some(0))
+            .validate()
+            .is_err());
+        let config = config.with_fast_forward_lag(None);
+        assert!(config.validate().is_ok());
+        assert!(config.describe().contains("fast forward: disabled"));
+    }
+
+    #[test]
+    fn availability_checks_have_to_hold_some_units() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert_eq!(
+            config.max_unavailable_units(),
+            DEFAULT_MAX_UNAVAILABLE_UNITS
+        );
+        assert_eq!(
+            config.availability_recheck_interval(),
+            DEFAULT_AVAILABILITY_RECHECK_INTERVAL
+        );
+        assert!(config
+            .clone()
+            .with_availability_checks(0, Duration::from_millis(100))
+            .validate()
+            .is_err());
+        let config = config.with_availability_checks(50, Duration::from_millis(100));
+        assert!(config.validate().is_ok());
+        assert!(config
+            .describe()
+            .contains("availability checks: up to 50 units held, rechecked every 100ms"));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn compression_needs_extended_messages() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid")
+        .with_compression(crate::Compression::Zstd);
+        assert!(config.validate().is_err());
+        let config = config.with_wire_version(EXTENDED_MESSAGES_VERSION);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn compact_alerts_need_extended_messages() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid")
+        .with_compact_alerts(true);
+        assert!(config.validate().is_err());
+        let config = config.with_wire_version(EXTENDED_MESSAGES_VERSION);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn wire_version_has_to_be_supported() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert_eq!(config.wire_version(), 0);
+        assert!(config
+            .clone()
+            .with_wire_version(MAX_SUPPORTED_VERSION + 1)
+            .validate()
+            .is_err());
+        let config = config.with_wire_version(MAX_SUPPORTED_VERSION);
+        assert!(config.validate().is_ok());
+        assert!(config
+            .describe()
+            .contains(&format!("wire version: {}", MAX_SUPPORTED_VERSION)));
+    }
+
+    #[test]
+    fn keeps_all_rounds_by_default() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert_eq!(config.kept_rounds(), None);
+        assert!(config.describe().contains("kept rounds: all"));
+        assert!(config
+            .clone()
+            .with_kept_rounds(MIN_KEPT_ROUNDS - 1)
+            .validate()
+            .is_err());
+        let config = config.with_kept_rounds(50);
+        assert!(config.validate().is_ok());
+        assert_eq!(config.kept_rounds(), Some(50));
+        assert!(config.describe().contains("kept rounds: 50"));
+    }
+
+    #[test]
+    fn node_weights_have_to_cover_the_committee() {
+        let config = create_config(
+            NodeCount(4),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert_eq!(config.node_weights(), NodeWeights::uniform(NodeCount(4)));
+        assert!(config.describe().contains("node weights: uniform"));
+        for weights in [
+            vec![4, 1, 1],
+            vec![4, 1, 1, 1, 1],
+            vec![4, 1, 0, 1],
+            vec![Weight::MAX / 2, Weight::MAX / 2, 1, 1],
+        ] {
+            assert!(config
+                .clone()
+                .with_node_weights(NodeWeights::new(weights))
+                .validate()
+                .is_err());
+        }
+        let config = config.with_node_weights(NodeWeights::new(vec![4, 1, 1, 1]));
+        assert!(config.validate().is_ok());
+        assert_eq!(config.node_weights().consensus_threshold(), 5);
+        assert!(config
+            .describe()
+            .contains("node weights: 4, 1, 1, 1 (quorum weight 5 of 7)"));
+    }
+
+    #[test]
+    fn message_limits_have_to_allow_honest_messages() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert_eq!(config.max_units_per_message(), 5);
+        assert_eq!(config.max_message_size(), None);
+        assert!(config.describe().contains("max units per message: 5"));
+        assert!(config.describe().contains("max message size: unlimited"));
+        let config = config
+            .with_max_units_per_message(100)
+            .with_max_message_size(1 << 20);
+        assert!(config.validate().is_ok());
+        assert!(config
+            .describe()
+            .contains("max message size: 1048576 bytes"));
+        assert!(config
+            .clone()
+            .with_max_units_per_message(4)
+            .validate()
+            .is_err());
+        assert!(config.with_max_message_size(0).validate().is_err());
+    }
+
+    #[test]
+    fn backup_fingerprint_parameters_are_described() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert!(config.committee_id().is_empty());
+        assert!(!config.migrate_backup());
+        assert!(config.describe().contains("migrate backup: false"));
+        let config = config
+            .with_committee_id(vec![0xab, 0x01])
+            .with_migrate_backup(true);
+        assert!(config.validate().is_ok());
+        assert!(config.describe().contains("committee id: ab01"));
+        assert!(config.describe().contains("migrate backup: true"));
+    }
+
+    #[test]
+    fn lease_renewal_interval_has_to_be_positive() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert_eq!(
+            config.lease_renewal_interval(),
+            DEFAULT_LEASE_RENEWAL_INTERVAL
+        );
+        assert!(config.describe().contains("lease renewal interval: 1000ms"));
+        assert!(config
+            .clone()
+            .with_lease_renewal_interval(Duration::ZERO)
+            .validate()
+            .is_err());
+        assert!(config
+            .with_lease_renewal_interval(Duration::from_millis(200))
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn observers_have_an_index_outside_of_the_committee() {
+        let config = create_config(
+            NodeCount(4),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert_eq!(config.role(), Role::Member);
+        assert!(config.describe().contains("role: Member"));
+        assert!(config
+            .clone()
+            .with_observer_index(NodeIndex(3))
+            .validate()
+            .is_err());
+        let observer = config.with_observer_index(NodeIndex(4));
+        assert!(observer.validate().is_ok());
+        assert_eq!(observer.role(), Role::Observer);
+        assert_eq!(observer.node_ix(), NodeIndex(4));
+        assert!(observer.describe().contains("role: Observer"));
+    }
+
+    #[test]
+    fn data_policy_flags_creators_outside_allow_list() {
+        let policy = DataPolicy::allow_list([NodeIndex(0), NodeIndex(2)]);
+        assert!(!policy.is_flagged(NodeIndex(0)));
+        assert!(policy.is_flagged(NodeIndex(1)));
+        assert!(!policy.is_flagged(NodeIndex(2)));
+        assert!(!DataPolicy::default().is_flagged(NodeIndex(1)));
+    }
+
+    #[test]
+    fn gossip_has_to_reach_some_peers() {
+        let config = create_config(
+            NodeCount(5),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid");
+        assert_eq!(config.broadcast_strategy(), BroadcastStrategy::Direct);
+        assert!(config.describe().contains("broadcast strategy: direct"));
+        assert!(config
+            .clone()
+            .with_broadcast_strategy(BroadcastStrategy::Gossip { fanout: 0 })
+            .validate()
+            .is_err());
+        let config = config.with_broadcast_strategy(BroadcastStrategy::Gossip { fanout: 2 });
+        assert!(config.validate().is_ok());
+        assert!(config
+            .describe()
+            .contains("broadcast strategy: gossip to 2 peers"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_to_json_without_functions() {
+        let config = create_config(
+            NodeCount(4),
+            NodeIndex(1),
+            3,
+            7000,
+            delay_config_for_tests(),
+            Duration::ZERO,
+        )
+        .expect("config should be valid")
+        .with_node_weights(NodeWeights::new(vec![4, 1, 1, 1]))
+        .with_broadcast_strategy(BroadcastStrategy::Gossip { fanout: 2 });
+        let json = serde_json::to_value(&config).expect("serializing should work");
+        assert_eq!(json["node_ix"], 1);
+        assert_eq!(json["n_members"], 4);
+        assert_eq!(json["role"], "Member");
+        assert_eq!(json["node_weights"]["items"]["0"], 4);
+        assert_eq!(json["fast_forward_lag"], DEFAULT_FAST_FORWARD_LAG);
+        assert_eq!(json["max_unfinalized_rounds"], serde_json::Value::Null);
+        assert_eq!(
+            json["delay_config"]["tick_interval"],
+            serde_json::json!({ "secs": 0, "nanos": 10_000_000 })
+        );
+        for function in ["data_policy", "clock", "delay_control"] {
+            assert!(json.get(function).is_none(), "{} serialized", function);
+        }
+        assert!(json["delay_config"].get("unit_creation_delay").is_none());
+        // The plain parts read back as they were.
+        let strategy: BroadcastStrategy =
+            serde_json::from_value(json["broadcast_strategy"].clone())
+                .expect("deserializing should work");
+        assert_eq!(strategy, config.broadcast_strategy());
+        let network_retry: NetworkRetry = serde_json::from_value(json["network_retry"].clone())
+            .expect("deserializing should work");
+        assert_eq!(network_retry, config.network_retry());
+    }
 }
@@ -0,0 +1,415 @@
+use crate::{
+    backup::decode_backup,
+    config::DelaySchedule,
+    creation::{Creator, Packer},
+    dag::{Dag, DagResult, DagUnit, Request as ReconstructionRequest},
+    dissemination::{Request, RequestId, Responder, Response},
+    extension::Extender,
+    member::UnitMessage,
+    network::NetworkDataInner,
+    units::{UncheckedSignedUnit, Unit, UnitCoord, UnitStore, Validator, WrappedUnit},
+    Config, Data, Hasher, InvalidConfigError, Keychain, MultiKeychain, NetworkData, NodeIndex,
+    OrderedUnit, PartialMultisignature, Recipient, Round, Signature, WireVersion,
+};
+use codec::{Encode, Error as CodecError};
+use log::{debug, error, info, trace, warn};
+use std::{
+    collections::VecDeque,
+    fmt::{Display, Formatter, Result as FmtResult},
+    time::{Duration, Instant},
+};
+
+const LOG_TARGET: &str = "AlephBFT-consensus-handler";
+
+/// Something the application driving a [`ConsensusHandler`] has to do. The actions returned by
+/// a single call have to be done in the order they are returned in, in particular a unit has to
+/// be saved to the backup before any message sent after it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Action<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> {
+    /// Send the message to the recipient, the same messages [`crate::run_session`] sends.
+    Send(NetworkData<H, D, S, MS>, Recipient),
+    /// The data was finalized, in the order of finalization.
+    Finalize(D),
+    /// Append the encoding of a unit to the backup, the way [`crate::run_session`] saves units.
+    SaveToBackup(Vec<u8>),
+    /// Call [`ConsensusHandler::on_timer`] once this time comes. Only the latest scheduled time
+    /// matters.
+    ScheduleTimer(Instant),
+}
+
+/// Why a [`ConsensusHandler`] could not be started.
+#[derive(Debug)]
+pub enum ConsensusHandlerError {
+    /// The configuration is not valid, see [`Config::validate`].
+    InvalidConfig(InvalidConfigError),
+    /// The backup does not decode to units.
+    CorruptedBackup(CodecError),
+    /// The unit of the backup at the coord is invalid or comes before its parents, so the backup
+    /// was not written by a handler of this session.
+    InconsistentBackup(UnitCoord),
+}
+
+impl Display for ConsensusHandlerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            ConsensusHandlerError::InvalidConfig(_) => write!(f, "the configuration is invalid"),
+            ConsensusHandlerError::CorruptedBackup(err) => {
+                write!(f, "the backup does not decode: {}", err)
+            }
+            ConsensusHandlerError::InconsistentBackup(coord) => write!(
+                f,
+                "the unit at {} of the backup is invalid or misses its parents",
+                coord
+            ),
+        }
+    }
+}
+
+impl From<InvalidConfigError> for ConsensusHandlerError {
+    fn from(err: InvalidConfigError) -> Self {
+        ConsensusHandlerError::InvalidConfig(err)
+    }
+}
+
+type Message<H, D, MK> =
+    NetworkData<H, D, <MK as Keychain>::Signature, <MK as MultiKeychain>::PartialMultisignature>;
+type Actions<H, D, MK> =
+    Vec<Action<H, D, <MK as Keychain>::Signature, <MK as MultiKeychain>::PartialMultisignature>>;
+
+/// A request for units we sent and have not got the answer to yet.
+struct MissingUnits<H: Hasher> {
+    request: RequestId<H>,
+    /// Sent before we knew the time, if absent, so it is repeated on the next timer.
+    retry_at: Option<Instant>,
+}
+
+/// The consensus of a single member as a state machine without any IO, for applications
+/// driving AlephBFT from their own event loop instead of spawning tasks with
+/// [`crate::run_session`]. Every call returns the [`Action`]s the application has to carry out:
+/// sending messages, delivering finalized data, saving units to the backup and calling
+/// [`ConsensusHandler::on_timer`] at the scheduled time. Time only passes through the calls to
+/// `on_timer`, so that the handler is deterministic given the order of the calls.
+///
+/// The handler creates units, adds the units of the peers to its dag, fetching the missing ones,
+/// answers the requests of the peers and orders the dag, exchanging the same unit messages as
+/// the members running [`crate::run_session`]. It does not take part in the alerts about forks,
+/// so it never adds units of forkers to its dag. With a forker in the committee it can then stall
+/// or finalize other data than the members running [`crate::run_session`], so it is not a
+/// replacement for it. A restarted handler has to be given the backup written so far, so that
+/// it never creates a unit of a round it already created one for.
+pub struct ConsensusHandler<H: Hasher, D: Data, MK: MultiKeychain> {
+    keychain: MK,
+    max_round: Round,
+    wire_version: WireVersion,
+    unit_creation_delay: DelaySchedule,
+    request_retry_delay: Duration,
+    creator: Creator<H>,
+    packer: Packer<MK>,
+    dag: Dag<H, D, MK>,
+    store: UnitStore<DagUnit<H, D, MK>>,
+    extender: Extender<DagUnit<H, D, MK>>,
+    responder: Responder<H, D, MK>,
+    data: VecDeque<D>,
+    next_round: Round,
+    next_creation: Option<Instant>,
+    missing: Vec<MissingUnits<H>>,
+    now: Option<Instant>,
+    scheduled: Option<Instant>,
+    /// The data finalized while restoring, returned by the next call.
+    pending: Actions<H, D, MK>,
+}
+
+impl<H: Hasher, D: Data, MK: MultiKeychain> ConsensusHandler<H, D, MK> {
+    /// Creates the handler of a session, if the configuration is valid, see [`Config::validate`],
+    /// restoring it from the backup, i.e. all the units of the [`Action::SaveToBackup`]s done so
+    /// far, concatenated, which is empty for a fresh session. The data of the restored units is
+    /// finalized again, with the actions returned by the first call. Nothing else happens until
+    /// the first call to [`ConsensusHandler::on_timer`], which creates our next unit once its
+    /// delay passes.
+    pub fn new(config: Config, keychain: MK, backup: &[u8]) -> Result<Self, ConsensusHandlerError> {
+        config.validate()?;
+        let node_weights = config.node_weights();
+        let validator = Validator::new(config.session_id(), keychain.clone(), config.max_round())
+            .with_max_metadata_size(config.max_unit_metadata_size())
+            .with_max_data_size(config.max_data_size())
+            .with_node_weights(node_weights.clone());
+        let delay_config = config.delay_config();
+        let mut handler = ConsensusHandler {
+            max_round: config.max_round(),
+            wire_version: config.wire_version(),
+            unit_creation_delay: delay_config.unit_creation_delay.clone(),
+            request_retry_delay: delay_config.unit_rebroadcast_interval_min,
+            creator: Creator::new(keychain.index(), config.n_members())
+                .with_node_weights(node_weights.clone()),
+            packer: Packer::new(keychain.clone(), config.session_id()),
+            dag: Dag::new(validator)
+                .with_reconstruction_limits(config.reconstruction_limits())
+                .with_clock(config.clock().clone()),
+            store: UnitStore::new(config.n_members()),
+            extender: Extender::new().with_node_weights(node_weights),
+            responder: Responder::new(keychain.clone())
+                .with_max_units_per_message(config.max_units_per_message()),
+            keychain,
+            data: VecDeque::new(),
+            next_round: 0,
+            next_creation: None,
+            missing: Vec::new(),
+            now: None,
+            scheduled: None,
+            pending: Vec::new(),
+        };
+        handler.restore(backup)?;
+        Ok(handler)
+    }
+
+    fn restore(&mut self, backup: &[u8]) -> Result<(), ConsensusHandlerError> {
+        let (_, units) = decode_backup::<H, D, MK::Signature>(backup)
+            .map_err(ConsensusHandlerError::CorruptedBackup)?;
+        let mut actions = Vec::new();
+        let restored = units.len();
+        for unit in units {
+            let coord = unit.as_signable().coord();
+            let u_hash = unit.as_signable().hash();
+            if self.store.unit(&u_hash).is_some() {
+                continue;
+            }
+            for unit in self.dag.add_unit(unit, &self.store).units {
+                if unit.creator() == self.index() {
+                    self.next_round = self.next_round.max(unit.round().saturating_add(1));
+                }
+                self.add_to_store(unit, &mut actions);
+            }
+            if self.store.unit(&u_hash).is_none() {
+                return Err(ConsensusHandlerError::InconsistentBackup(coord));
+            }
+        }
+        if restored > 0 {
+            info!(target: LOG_TARGET, "{:?} Restored {} units from the backup, continuing from round {}.", self.index(), restored, self.next_round);
+        }
+        self.pending = actions;
+        Ok(())
+    }
+
+    fn index(&self) -> NodeIndex {
+        self.keychain.index()
+    }
+
+    /// Processes a message from a peer.
+    pub fn on_network_message(&mut self, message: Message<H, D, MK>) -> Actions<H, D, MK> {
+        let mut actions = std::mem::take(&mut self.pending);
+        let message = match message.0 {
+            NetworkDataInner::Units(message) => message,
+            _ => {
+                trace!(target: LOG_TARGET, "{:?} Ignoring a message that is not about units.", self.index());
+                return actions;
+            }
+        };
+        match message {
+            UnitMessage::NewUnit(unit) | UnitMessage::ResponseCoord(unit) => {
+                let result = self.dag.add_unit(unit, &self.store);
+                self.on_dag_result(result, &mut actions);
+            }
+            UnitMessage::ResponseParents(u_hash, parents) => {
+                let result = self.dag.add_parents(u_hash, parents, &self.store);
+                self.on_dag_result(result, &mut actions);
+            }
+            UnitMessage::RequestCoord(node_id, coord) => {
+                self.on_request(Request::Coord(coord), node_id, &mut actions)
+            }
+            UnitMessage::RequestParents(node_id, u_hash) => {
+                self.on_request(Request::Parents(u_hash), node_id, &mut actions)
+            }
+            _ => {
+                trace!(target: LOG_TARGET, "{:?} Ignoring a unit message we never request.", self.index())
+            }
+        }
+        self.try_create_unit(&mut actions);
+        self.schedule_timer(&mut actions);
+        actions
+    }
+
+    /// Lets the handler know the time, creating our unit if it is due and repeating the requests
+    /// that were not answered in time.
+    pub fn on_timer(&mut self, now: Instant) -> Actions<H, D, MK> {
+        let mut actions = std::mem::take(&mut self.pending);
+        self.now = Some(now);
+        if self.next_creation.is_none() {
+            self.next_creation = Some(now + (self.unit_creation_delay)(0));
+        }
+        self.try_create_unit(&mut actions);
+        self.retry_requests(now, &mut actions);
+        self.schedule_timer(&mut actions);
+        actions
+    }
+
+    /// Queues the data to be included in our next units, one item per unit.
+    pub fn on_data(&mut self, data: D) -> Actions<H, D, MK> {
+        self.data.push_back(data);
+        std::mem::take(&mut self.pending)
+    }
+
+    fn message(&self, message: UnitMessage<H, D, MK::Signature>) -> Message<H, D, MK> {
+        NetworkData(NetworkDataInner::Units(message), self.wire_version)
+    }
+
+    /// Asks for a call to [`ConsensusHandler::on_timer`] when we have to create our next unit or
+    /// repeat a request, unless it was asked for already.
+    fn schedule_timer(&mut self, actions: &mut Actions<H, D, MK>) {
+        let next_timer = self
+            .next_creation
+            // A unit overdue waits for its parents, not for the time.
+            .filter(|at| {
+                self.next_round <= self.max_round && self.now.map_or(true, |now| *at > now)
+            })
+            .into_iter()
+            .chain(self.missing.iter().filter_map(|missing| missing.retry_at))
+            .min();
+        if next_timer.is_some() && next_timer != self.scheduled {
+            self.scheduled = next_timer;
+            actions.extend(next_timer.map(Action::ScheduleTimer));
+        }
+    }
+
+    fn try_create_unit(&mut self, actions: &mut Actions<H, D, MK>) {
+        let (Some(now), Some(next_creation)) = (self.now, self.next_creation) else {
+            return;
+        };
+        if now < next_creation || self.next_round > self.max_round {
+            return;
+        }
+        let preunit = match self.creator.create_unit(self.next_round) {
+            Ok(preunit) => preunit,
+            Err(e) => {
+                trace!(target: LOG_TARGET, "{:?} Unable to create a unit of round {} yet: {}.", self.index(), self.next_round, e);
+                return;
+            }
+        };
+        let unit = match self.packer.pack(preunit, self.data.pop_front(), Vec::new()) {
+            Ok(unit) => unit,
+            Err(e) => {
+                error!(target: LOG_TARGET, "{:?} Failed to sign our unit of round {}: {}.", self.index(), self.next_round, e);
+                return;
+            }
+        };
+        debug!(target: LOG_TARGET, "{:?} Created a unit of round {}.", self.index(), self.next_round);
+        self.next_round += 1;
+        self.next_creation = Some(now + (self.unit_creation_delay)(self.next_round.into()));
+        let result = self.dag.add_unit(unit.into(), &self.store);
+        self.on_dag_result(result, actions);
+    }
+
+    fn on_dag_result(&mut self, result: DagResult<H, D, MK>, actions: &mut Actions<H, D, MK>) {
+        let DagResult {
+            units,
+            requests,
+            alerts,
+            ..
+        } = result;
+        for unit in units {
+            self.on_unit_reconstructed(unit, actions);
+        }
+        for request in requests {
+            let request = match request {
+                ReconstructionRequest::Coord(coord) => RequestId::Coord(coord),
+                ReconstructionRequest::ParentsOf(u_hash) => RequestId::Parents(u_hash),
+            };
+            if self
+                .missing
+                .iter()
+                .any(|missing| missing.request == request)
+            {
+                continue;
+            }
+            self.send_request(&request, actions);
+            let retry_at = self.now.map(|now| now + self.request_retry_delay);
+            self.missing.push(MissingUnits { request, retry_at });
+        }
+        if !alerts.is_empty() {
+            warn!(target: LOG_TARGET, "{:?} Detected {} forks, the units of the forkers are not added.", self.index(), alerts.len());
+        }
+    }
+
+    fn on_unit_reconstructed(&mut self, unit: DagUnit<H, D, MK>, actions: &mut Actions<H, D, MK>) {
+        let unit_hash = unit.hash();
+        trace!(target: LOG_TARGET, "{:?} Unit {:?} {} reconstructed.", self.index(), unit_hash, unit.coord());
+        let unpacked: UncheckedSignedUnit<_, _, _> = unit.clone().unpack().into();
+        actions.push(Action::SaveToBackup(unpacked.encode()));
+        if unit.creator() == self.index() {
+            actions.push(Action::Send(
+                self.message(UnitMessage::NewUnit(unpacked)),
+                Recipient::Everyone,
+            ));
+        }
+        self.add_to_store(unit, actions);
+    }
+
+    /// Adds the reconstructed unit to the store, the creator and the ordering, finalizing the
+    /// data of the units it orders.
+    fn add_to_store(&mut self, unit: DagUnit<H, D, MK>, actions: &mut Actions<H, D, MK>) {
+        let unit_hash = unit.hash();
+        self.store.insert(unit.clone());
+        self.dag.finished_processing(&unit_hash);
+        self.creator.add_unit(&unit);
+        self.missing
+            .retain(|missing| !Self::is_resolved(&self.store, &missing.request));
+        for batch in self.extender.add_unit(unit) {
+            for unit in batch.units {
+                if let Some(data) = OrderedUnit::from(unit).data {
+                    actions.push(Action::Finalize(data));
+                }
+            }
+        }
+    }
+
+    fn is_resolved(store: &UnitStore<DagUnit<H, D, MK>>, request: &RequestId<H>) -> bool {
+        match request {
+            RequestId::Coord(coord) => store.canonical_unit(*coord).is_some(),
+            RequestId::Parents(u_hash) => store.unit(u_hash).is_some(),
+        }
+    }
+
+    fn send_request(&self, request: &RequestId<H>, actions: &mut Actions<H, D, MK>) {
+        let message = match request {
+            RequestId::Coord(coord) => UnitMessage::RequestCoord(self.index(), *coord),
+            RequestId::Parents(u_hash) => UnitMessage::RequestParents(self.index(), *u_hash),
+        };
+        actions.push(Action::Send(self.message(message), Recipient::Everyone));
+    }
+
+    fn retry_requests(&mut self, now: Instant, actions: &mut Actions<H, D, MK>) {
+        let retry_delay = self.request_retry_delay;
+        let mut due = Vec::new();
+        for missing in self
+            .missing
+            .iter_mut()
+            .filter(|missing| missing.retry_at.map_or(true, |at| at <= now))
+        {
+            missing.retry_at = Some(now + retry_delay);
+            due.push(missing.request.clone());
+        }
+        for request in due {
+            self.send_request(&request, actions);
+        }
+    }
+
+    fn on_request(
+        &mut self,
+        request: Request<H>,
+        node_id: NodeIndex,
+        actions: &mut Actions<H, D, MK>,
+    ) {
+        let message = match self.responder.handle_request(request, &self.store) {
+            Ok(Response::Coord(unit)) => UnitMessage::ResponseCoord(unit),
+            Ok(Response::Parents(u_hash, parents)) => UnitMessage::ResponseParents(u_hash, parents),
+            Ok(_) => return,
+            Err(e) => {
+                trace!(target: LOG_TARGET, "{:?} Not answering a request of {:?}: {:?}.", self.index(), node_id, e);
+                return;
+            }
+        };
+        actions.push(Action::Send(
+            self.message(message),
+            Recipient::Node(node_id),
+        ));
+    }
+}
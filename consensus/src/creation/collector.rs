@@ -1,4 +1,7 @@
-use crate::{units::Unit, Hasher, NodeCount, NodeIndex, NodeMap, Round};
+use crate::{
+    units::{parent_eligibility, ControlHashError, Unit, UnitCoord},
+    Hasher, NodeCount, NodeIndex, NodeMap, NodeWeights, Round,
+};
 use anyhow::Result;
 use thiserror::Error;
 
@@ -14,7 +17,6 @@ pub enum ConstraintError {
 pub struct UnitsCollector<H: Hasher> {
     candidates: NodeMap<(H::Hash, Round)>,
     for_round: Round,
-    direct_parents: NodeCount,
 }
 
 impl<H: Hasher> UnitsCollector<H> {
@@ -22,7 +24,6 @@ impl<H: Hasher> UnitsCollector<H> {
         UnitsCollector {
             candidates: NodeMap::with_size(n_members),
             for_round: 1,
-            direct_parents: NodeCount(0),
         }
     }
 
@@ -30,43 +31,36 @@ impl<H: Hasher> UnitsCollector<H> {
         UnitsCollector {
             candidates: previous.candidates.clone(),
             for_round: previous.for_round + 1,
-            direct_parents: NodeCount(0),
         }
     }
 
     pub fn add_unit<U: Unit<Hasher = H>>(&mut self, unit: &U) {
-        let node_id = unit.creator();
-        let hash = unit.hash();
         let round = unit.round();
-
-        if round >= self.for_round {
+        if !parent_eligibility::may_be_parent(round, self.for_round) {
             return;
         }
-
-        let to_insert = match self.candidates.get(node_id) {
-            None => Some((hash, round)),
-            Some((_, r)) if *r < round => Some((hash, round)),
-            _ => None,
-        };
-
-        if let Some(data) = to_insert {
-            self.candidates.insert(node_id, data);
-            if round == self.for_round - 1 {
-                self.direct_parents += NodeCount(1);
-            }
+        let candidate_round = self.candidates.get(unit.creator()).map(|(_, r)| *r);
+        if parent_eligibility::replaces_candidate(candidate_round, round) {
+            self.candidates.insert(unit.creator(), (unit.hash(), round));
         }
     }
 
     pub fn prospective_parents(
         &self,
         node_id: NodeIndex,
+        node_weights: &NodeWeights,
     ) -> Result<&NodeMap<(H::Hash, Round)>, ConstraintError> {
-        if self.direct_parents < self.candidates.size().consensus_threshold() {
-            return Err(ConstraintError::NotEnoughParents);
+        let mut parent_rounds = NodeMap::with_size(self.candidates.size());
+        for (creator, (_, round)) in self.candidates.iter() {
+            parent_rounds.insert(creator, *round);
         }
-        match self.candidates.get(node_id) {
-            Some((_, r)) if *r == self.for_round - 1 => Ok(&self.candidates),
-            _ => Err(ConstraintError::MissingOwnParent),
+        let coord = UnitCoord::new(self.for_round, node_id);
+        match parent_eligibility::check_parents::<H>(&parent_rounds, coord, node_weights) {
+            Ok(()) => Ok(&self.candidates),
+            Err(ControlHashError::NotEnoughParentsForRound(_)) => {
+                Err(ConstraintError::NotEnoughParents)
+            }
+            Err(_) => Err(ConstraintError::MissingOwnParent),
         }
     }
 }
@@ -76,7 +70,7 @@ mod tests {
     use crate::{
         creation::collector::{ConstraintError, UnitsCollector},
         units::{random_full_parent_units_up_to, Unit},
-        NodeCount, NodeIndex,
+        NodeCount, NodeIndex, NodeWeights,
     };
     use aleph_bft_mock::Hasher64;
 
@@ -86,7 +80,7 @@ mod tests {
         let units_collector = UnitsCollector::<Hasher64>::new_initial(n_members);
 
         let err = units_collector
-            .prospective_parents(NodeIndex(0))
+            .prospective_parents(NodeIndex(0), &NodeWeights::uniform(n_members))
             .expect_err("should fail without parents");
         assert_eq!(err, ConstraintError::NotEnoughParents);
     }
@@ -99,7 +93,7 @@ mod tests {
         units_collector.add_unit(&units[0][0]);
 
         let err = units_collector
-            .prospective_parents(NodeIndex(0))
+            .prospective_parents(NodeIndex(0), &NodeWeights::uniform(n_members))
             .expect_err("should fail without parents");
         assert_eq!(err, ConstraintError::NotEnoughParents);
     }
@@ -114,7 +108,7 @@ mod tests {
         }
 
         let err = units_collector
-            .prospective_parents(NodeIndex(0))
+            .prospective_parents(NodeIndex(0), &NodeWeights::uniform(n_members))
             .expect_err("should fail without parents");
         assert_eq!(err, ConstraintError::MissingOwnParent);
     }
@@ -129,7 +123,7 @@ mod tests {
         }
 
         let parents = units_collector
-            .prospective_parents(NodeIndex(0))
+            .prospective_parents(NodeIndex(0), &NodeWeights::uniform(n_members))
             .expect("we should be able to retrieve parents");
         assert_eq!(parents.item_count(), 3);
 
@@ -152,7 +146,7 @@ mod tests {
         }
 
         let parents = units_collector
-            .prospective_parents(NodeIndex(0))
+            .prospective_parents(NodeIndex(0), &NodeWeights::uniform(n_members))
             .expect("we should be able to retrieve parents");
         assert_eq!(parents.item_count(), 4);
 
@@ -176,7 +170,7 @@ mod tests {
         }
 
         let parents = units_collector
-            .prospective_parents(NodeIndex(0))
+            .prospective_parents(NodeIndex(0), &NodeWeights::uniform(n_members))
             .expect("we should be able to retrieve parents");
         assert_eq!(parents.item_count(), 4);
 
@@ -195,7 +189,7 @@ mod tests {
         let units_collector = UnitsCollector::from_previous(&initial_units_collector);
 
         let err = units_collector
-            .prospective_parents(NodeIndex(0))
+            .prospective_parents(NodeIndex(0), &NodeWeights::uniform(n_members))
             .expect_err("should fail without parents");
         assert_eq!(err, ConstraintError::NotEnoughParents);
     }
@@ -209,7 +203,7 @@ mod tests {
         units_collector.add_unit(&units[1][0]);
 
         let err = units_collector
-            .prospective_parents(NodeIndex(0))
+            .prospective_parents(NodeIndex(0), &NodeWeights::uniform(n_members))
             .expect_err("should fail without parents");
         assert_eq!(err, ConstraintError::NotEnoughParents);
     }
@@ -225,7 +219,7 @@ mod tests {
         }
 
         let err = units_collector
-            .prospective_parents(NodeIndex(0))
+            .prospective_parents(NodeIndex(0), &NodeWeights::uniform(n_members))
             .expect_err("should fail without parents");
         assert_eq!(err, ConstraintError::NotEnoughParents);
     }
@@ -241,7 +235,7 @@ mod tests {
         }
 
         let err = units_collector
-            .prospective_parents(NodeIndex(0))
+            .prospective_parents(NodeIndex(0), &NodeWeights::uniform(n_members))
             .expect_err("should fail without parents");
         assert_eq!(err, ConstraintError::MissingOwnParent);
     }
@@ -258,7 +252,7 @@ mod tests {
         units_collector.add_unit(&units[0][0]);
 
         let err = units_collector
-            .prospective_parents(NodeIndex(0))
+            .prospective_parents(NodeIndex(0), &NodeWeights::uniform(n_members))
             .expect_err("should fail without parents");
         assert_eq!(err, ConstraintError::MissingOwnParent);
     }
@@ -274,7 +268,7 @@ mod tests {
         }
 
         let parents = units_collector
-            .prospective_parents(NodeIndex(0))
+            .prospective_parents(NodeIndex(0), &NodeWeights::uniform(n_members))
             .expect("we should be able to retrieve parents");
         assert_eq!(parents.item_count(), 3);
 
@@ -299,7 +293,7 @@ mod tests {
         units_collector.add_unit(&units[0][3]);
 
         let parents = units_collector
-            .prospective_parents(NodeIndex(0))
+            .prospective_parents(NodeIndex(0), &NodeWeights::uniform(n_members))
             .expect("we should be able to retrieve parents");
         assert_eq!(parents.item_count(), 4);
 
@@ -324,7 +318,7 @@ mod tests {
         }
 
         let parents = units_collector
-            .prospective_parents(NodeIndex(0))
+            .prospective_parents(NodeIndex(0), &NodeWeights::uniform(n_members))
             .expect("we should be able to retrieve parents");
         assert_eq!(parents.item_count(), 4);
 
@@ -350,7 +344,7 @@ mod tests {
         }
 
         let parents = units_collector
-            .prospective_parents(NodeIndex(0))
+            .prospective_parents(NodeIndex(0), &NodeWeights::uniform(n_members))
             .expect("we should be able to retrieve parents");
         assert_eq!(parents.item_count(), 4);
 
@@ -1,7 +1,7 @@
 use crate::{
     creation::collector::{ConstraintError, UnitsCollector},
     units::{ControlHash, PreUnit, Unit},
-    Hasher, NodeCount, NodeIndex, NodeMap, Round,
+    Hasher, NodeCount, NodeIndex, NodeMap, NodeWeights, Round,
 };
 use anyhow::Result;
 use std::cmp;
@@ -10,6 +10,7 @@ pub struct Creator<H: Hasher> {
     round_collectors: Vec<UnitsCollector<H>>,
     node_id: NodeIndex,
     n_members: NodeCount,
+    node_weights: NodeWeights,
 }
 
 impl<H: Hasher> Creator<H> {
@@ -17,10 +18,20 @@ impl<H: Hasher> Creator<H> {
         Creator {
             node_id,
             n_members,
+            node_weights: NodeWeights::uniform(n_members),
             round_collectors: vec![UnitsCollector::new_initial(n_members)],
         }
     }
 
+    /// Sets the voting weights of the nodes, our units need parents weighing enough. Every node
+    /// weighs one by default.
+    pub fn with_node_weights(self, node_weights: NodeWeights) -> Self {
+        Creator {
+            node_weights,
+            ..self
+        }
+    }
+
     pub fn current_round(&self) -> Round {
         (self.round_collectors.len() - 1) as Round
     }
@@ -38,7 +49,7 @@ impl<H: Hasher> Creator<H> {
         &mut self.round_collectors[round as usize]
     }
 
-    /// To create a new unit, we need to have at least the consensus threshold of parents available in previous round.
+    /// To create a new unit, we need parents weighing at least the consensus threshold available in previous round.
     /// Additionally, our unit from previous round must be available.
     pub fn create_unit(&self, round: Round) -> Result<PreUnit<H>> {
         let control_hash = match round.checked_sub(1) {
@@ -47,7 +58,7 @@ impl<H: Hasher> Creator<H> {
                 self.round_collectors
                     .get(usize::from(prev_round))
                     .ok_or(ConstraintError::NotEnoughParents)?
-                    .prospective_parents(self.node_id)?,
+                    .prospective_parents(self.node_id, &self.node_weights)?,
             ),
         };
 
@@ -69,11 +80,13 @@ mod tests {
     use crate::{
         creation::creator::Creator as GenericCreator,
         units::{
-            create_preunits, creator_set, preunit_to_full_unit, random_full_parent_units_up_to,
+            create_preunits, creator_set, preunit_to_full_unit, preunit_to_unchecked_signed_unit,
+            random_full_parent_units_up_to, Unit, Validator,
         },
-        NodeCount, NodeIndex,
+        NodeCount, NodeIndex, NodeWeights,
     };
-    use aleph_bft_mock::Hasher64;
+    use aleph_bft_mock::{Hasher64, Keychain};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
 
     type Creator = GenericCreator<Hasher64>;
 
@@ -126,6 +139,21 @@ mod tests {
         assert_eq!(preunit.round(), round);
     }
 
+    #[test]
+    fn creates_unit_with_minimal_parents_1() {
+        create_unit_with_minimal_parents(NodeCount(1));
+    }
+
+    #[test]
+    fn creates_unit_with_minimal_parents_2() {
+        create_unit_with_minimal_parents(NodeCount(2));
+    }
+
+    #[test]
+    fn creates_unit_with_minimal_parents_3() {
+        create_unit_with_minimal_parents(NodeCount(3));
+    }
+
     #[test]
     fn creates_unit_with_minimal_parents_4() {
         create_unit_with_minimal_parents(NodeCount(4));
@@ -161,6 +189,16 @@ mod tests {
         assert!(creator.create_unit(round).is_err())
     }
 
+    #[test]
+    fn cannot_create_unit_below_parents_threshold_2() {
+        dont_create_unit_below_parents_threshold(NodeCount(2));
+    }
+
+    #[test]
+    fn cannot_create_unit_below_parents_threshold_3() {
+        dont_create_unit_below_parents_threshold(NodeCount(3));
+    }
+
     #[test]
     fn cannot_create_unit_below_parents_threshold_4() {
         dont_create_unit_below_parents_threshold(NodeCount(4));
@@ -181,6 +219,25 @@ mod tests {
         dont_create_unit_below_parents_threshold(NodeCount(7));
     }
 
+    #[test]
+    fn parents_have_to_weigh_enough() {
+        let n_members = NodeCount(4);
+        let node_weights = NodeWeights::new(vec![4, 1, 1, 1]);
+        let creators = creator_set(n_members);
+        let new_units: Vec<_> = create_preunits(creators.iter(), 0)
+            .into_iter()
+            .map(|pu| preunit_to_full_unit(pu, 0))
+            .collect();
+        // The heavy node and any other one are enough, all the light ones are not.
+        let mut creator =
+            Creator::new(NodeIndex(1), n_members).with_node_weights(node_weights.clone());
+        creator.add_units(&new_units[..2]);
+        assert!(creator.create_unit(1).is_ok());
+        let mut creator = Creator::new(NodeIndex(1), n_members).with_node_weights(node_weights);
+        creator.add_units(&new_units[1..]);
+        assert!(creator.create_unit(1).is_err());
+    }
+
     #[test]
     fn creates_two_units_when_possible() {
         let n_members = NodeCount(7);
@@ -299,4 +356,42 @@ mod tests {
         }
         assert!(preunit.control_hash().parents().nth(5).is_some());
     }
+
+    #[test]
+    fn created_units_always_validate() {
+        let n_members = NodeCount(7);
+        let session_id = 43;
+        let max_round = 5;
+        let node_id = NodeIndex(0);
+        let keychain = Keychain::new(n_members, node_id);
+        for seed in 0..200 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut creator = Creator::new(node_id, n_members);
+            let units = random_full_parent_units_up_to(max_round, n_members, session_id);
+            // Forks of other nodes' units, as if the alerter legitimized them.
+            let forks = random_full_parent_units_up_to(max_round, n_members, session_id);
+            for (round_units, round_forks) in units.iter().zip(forks.iter()) {
+                for (unit, fork) in round_units.iter().zip(round_forks.iter()) {
+                    if rng.gen_bool(0.7) {
+                        creator.add_unit(unit);
+                    }
+                    if unit.creator() != node_id && rng.gen_bool(0.1) {
+                        creator.add_unit(fork);
+                    }
+                }
+            }
+            for round in 0..=max_round {
+                if let Ok(preunit) = creator.create_unit(round) {
+                    let unit = preunit_to_unchecked_signed_unit(preunit, session_id, &keychain);
+                    let validator = Validator::new(session_id, keychain.clone(), max_round);
+                    assert!(
+                        validator.validate_unit(unit).is_ok(),
+                        "unit of round {} created with seed {} should validate",
+                        round,
+                        seed,
+                    );
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,97 @@
+use crate::{
+    callbacks::{CallbackGuard, CallbackPanicked, UserComponent},
+    DataProvider,
+};
+use futures::{
+    future::{pending, BoxFuture},
+    FutureExt,
+};
+
+type Answer<D> = Result<Option<D>, CallbackPanicked>;
+/// A request to the provider, giving the provider back together with the answer.
+type Request<'a, DP> = BoxFuture<'a, (&'a mut DP, Answer<<DP as DataProvider>::Output>)>;
+
+/// Asks the provider for the data of our units without letting a slow provider hold up the
+/// rounds. A request not answered within the timeout keeps running while we create the next
+/// units, and its answer goes into the first unit created after it arrives, instead of being
+/// dropped.
+pub struct DataFetcher<'a, DP: DataProvider> {
+    /// Absent while a request is pending, as the request holds the provider.
+    provider: Option<&'a mut DP>,
+    request: Option<Request<'a, DP>>,
+    late_answer: Option<DP::Output>,
+    callbacks: CallbackGuard,
+}
+
+impl<'a, DP: DataProvider> DataFetcher<'a, DP> {
+    pub fn new(provider: &'a mut DP, callbacks: CallbackGuard) -> Self {
+        DataFetcher {
+            provider: Some(provider),
+            request: None,
+            late_answer: None,
+            callbacks,
+        }
+    }
+
+    /// Whether a request that did not make it for a previous unit is still pending.
+    pub fn is_pending(&self) -> bool {
+        self.request.is_some()
+    }
+
+    /// Completes once the pending request is answered, keeping the answer for the next unit.
+    /// Never completes while no request is pending, and the request stays pending if this is
+    /// dropped before completing.
+    pub async fn progress(&mut self) -> Result<(), CallbackPanicked> {
+        let request = match self.request.as_mut() {
+            Some(request) => request,
+            None => return pending().await,
+        };
+        let (provider, answer) = request.await;
+        self.request = None;
+        self.provider = Some(provider);
+        self.late_answer = answer?;
+        Ok(())
+    }
+
+    /// The data for our next unit. An answer that came too late for a previous unit is used
+    /// first, otherwise a new request is sent and awaited until the timeout, if any. Returns no
+    /// data if the request times out, it stays pending then.
+    pub async fn get_data(
+        &mut self,
+        timeout: Option<BoxFuture<'static, ()>>,
+    ) -> Result<Option<DP::Output>, CallbackPanicked> {
+        if let Some(data) = self.late_answer.take() {
+            return Ok(Some(data));
+        }
+        if let Some(provider) = self.provider.take() {
+            let callbacks = self.callbacks.clone();
+            self.request = Some(
+                async move {
+                    let answer = callbacks
+                        .call_async(UserComponent::DataProvider, provider.get_data())
+                        .await;
+                    (provider, answer)
+                }
+                .boxed(),
+            );
+        }
+        match timeout {
+            Some(timeout) => futures::select! {
+                result = self.progress().fuse() => result?,
+                _ = timeout.fuse() => return Ok(None),
+            },
+            None => self.progress().await?,
+        }
+        Ok(self.late_answer.take())
+    }
+
+    /// Hands back data that will not be ordered after all, see [`DataProvider::return_unused`].
+    pub fn return_unused(&mut self, data: DP::Output) -> Result<(), CallbackPanicked> {
+        let provider = self
+            .provider
+            .as_mut()
+            .expect("the data comes from an answered request, so none is pending");
+        self.callbacks
+            .call(UserComponent::DataProvider, || provider.return_unused(data))
+    }
+}
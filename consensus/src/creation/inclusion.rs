@@ -0,0 +1,175 @@
+use crate::{AdaptiveInclusion, Round};
+use std::{
+    collections::VecDeque,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// A change of the decision whether our units carry data, with the rolling latency causing it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum InclusionChange {
+    Paused(Round),
+    Resumed(Round),
+}
+
+impl Display for InclusionChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            InclusionChange::Paused(latency) => write!(
+                f,
+                "holding back data, our units finalize {} rounds late",
+                latency
+            ),
+            InclusionChange::Resumed(latency) => write!(
+                f,
+                "attaching data again, our units finalize {} rounds late",
+                latency
+            ),
+        }
+    }
+}
+
+/// Tracks how late our units get finalized and decides whether the next one should carry data,
+/// according to the [`AdaptiveInclusion`] policy.
+///
+/// Our units get finalized in the order of their rounds, as each of them is a parent of the
+/// next one, so it is enough to remember the oldest one still waiting.
+pub(crate) struct InclusionTracker {
+    policy: AdaptiveInclusion,
+    latencies: VecDeque<Round>,
+    oldest_pending: Option<Round>,
+    newest: Option<Round>,
+    paused: bool,
+}
+
+impl InclusionTracker {
+    pub fn new(policy: AdaptiveInclusion) -> Self {
+        InclusionTracker {
+            policy,
+            latencies: VecDeque::with_capacity(policy.window),
+            oldest_pending: None,
+            newest: None,
+            paused: false,
+        }
+    }
+
+    /// We created our unit of the given round.
+    pub fn on_created(&mut self, round: Round) {
+        self.oldest_pending.get_or_insert(round);
+        self.newest = Some(round);
+    }
+
+    /// Our unit of the given round got finalized, while we were about to create a unit of
+    /// `current_round`. Units we did not create in this run, e.g. ones from the backup, are ignored.
+    pub fn on_finalized(&mut self, round: Round, current_round: Round) {
+        let oldest = match self.oldest_pending {
+            Some(oldest) if round >= oldest => oldest,
+            _ => return,
+        };
+        if self.latencies.len() == self.policy.window {
+            self.latencies.pop_front();
+        }
+        // All our units up to this one got finalized, the oldest of them waited the longest.
+        self.latencies
+            .push_back(current_round.saturating_sub(oldest));
+        self.oldest_pending = match self.newest {
+            Some(newest) if newest > round => Some(round + 1),
+            _ => None,
+        };
+    }
+
+    /// The highest latency among the recently finalized units and the one still waiting the longest.
+    fn latency(&self, current_round: Round) -> Round {
+        let waiting = self
+            .oldest_pending
+            .map_or(0, |oldest| current_round.saturating_sub(oldest));
+        self.latencies.iter().copied().fold(waiting, std::cmp::max)
+    }
+
+    /// Decides whether our unit of the given round should carry data, returning the change of
+    /// the decision, if any.
+    pub fn update(&mut self, current_round: Round) -> Option<InclusionChange> {
+        let latency = self.latency(current_round);
+        match self.paused {
+            false if latency >= self.policy.pause_latency => {
+                self.paused = true;
+                Some(InclusionChange::Paused(latency))
+            }
+            true if latency <= self.policy.resume_latency => {
+                self.paused = false;
+                Some(InclusionChange::Resumed(latency))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether our units should carry data, as of the last update.
+    pub fn includes_data(&self) -> bool {
+        !self.paused
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        creation::inclusion::{InclusionChange, InclusionTracker},
+        AdaptiveInclusion,
+    };
+
+    const POLICY: AdaptiveInclusion = AdaptiveInclusion {
+        pause_latency: 5,
+        resume_latency: 2,
+        window: 3,
+    };
+
+    #[test]
+    fn keeps_data_while_units_finalize_promptly() {
+        let mut tracker = InclusionTracker::new(POLICY);
+        for round in 0..100 {
+            if round >= 2 {
+                tracker.on_finalized(round - 2, round);
+            }
+            assert_eq!(tracker.update(round), None);
+            assert!(tracker.includes_data());
+            tracker.on_created(round);
+        }
+    }
+
+    #[test]
+    fn pauses_when_units_wait_too_long_and_resumes_after_recovery() {
+        let mut tracker = InclusionTracker::new(POLICY);
+        let mut data_units = 0;
+        for round in 0..10 {
+            match tracker.update(round) {
+                Some(change) => assert_eq!(change, InclusionChange::Paused(5)),
+                None => assert!(round < 5 || !tracker.includes_data()),
+            }
+            if tracker.includes_data() {
+                data_units += 1;
+            }
+            tracker.on_created(round);
+        }
+        assert_eq!(data_units, POLICY.pause_latency);
+        // Everything gets finalized at once, but the rolling latency remembers how late.
+        tracker.on_finalized(9, 10);
+        assert_eq!(tracker.update(10), None);
+        tracker.on_created(10);
+        let mut round = 11;
+        while tracker.update(round).is_none() {
+            assert!(round < 20, "the tracker should recover");
+            tracker.on_finalized(round - 1, round);
+            tracker.on_created(round);
+            round += 1;
+        }
+        assert!(tracker.includes_data());
+    }
+
+    #[test]
+    fn ignores_units_from_before_the_start() {
+        let mut tracker = InclusionTracker::new(POLICY);
+        tracker.on_finalized(3, 20);
+        tracker.on_created(20);
+        tracker.on_finalized(7, 21);
+        assert_eq!(tracker.update(21), None);
+        assert!(tracker.includes_data());
+    }
+}
@@ -1,30 +1,46 @@
 use crate::{
+    callbacks::{CallbackGuard, CallbackPanicked, UserComponent},
     config::Config,
+    events::{EventBus, InternalEvent, SigningTarget},
+    lease::CreationPermit,
+    session_end::{SessionEnd, SessionEndMonitor},
     units::{PreUnit, SignedUnit, Unit},
-    DataProvider, MultiKeychain, Receiver, Round, Sender, Terminator,
+    BroadcastGate, DataProvider, GateDecision, MultiKeychain, Receiver, Round, Sender, Terminator,
+    UnitMetadataProvider, EXTENDED_MESSAGES_VERSION,
 };
+use codec::Encode;
 use futures::{
     channel::{
         mpsc::{SendError, TrySendError},
         oneshot,
     },
-    FutureExt, StreamExt,
+    future::BoxFuture,
+    pin_mut, FutureExt, StreamExt,
 };
-use futures_timer::Delay;
-use log::{debug, error, trace, warn};
+use log::{debug, error, info, trace, warn};
+use std::sync::Arc;
 
 mod collector;
 mod creator;
+mod fetcher;
+mod inclusion;
+mod pacing;
 mod packer;
 
 pub use creator::Creator;
-use packer::Packer;
+use fetcher::DataFetcher;
+pub(crate) use inclusion::InclusionChange;
+use inclusion::InclusionTracker;
+use pacing::FinalizationPacing;
+pub(crate) use packer::Packer;
 
 const LOG_TARGET: &str = "AlephBFT-creator";
 
 enum CreatorError {
     OutChannelClosed(SendError),
     ParentsChannelClosed,
+    FinalizedHeadsChannelClosed,
+    CallbackPanicked(CallbackPanicked),
 }
 
 impl<T> From<TrySendError<T>> for CreatorError {
@@ -33,10 +49,28 @@ impl<T> From<TrySendError<T>> for CreatorError {
     }
 }
 
+impl From<CallbackPanicked> for CreatorError {
+    fn from(e: CallbackPanicked) -> Self {
+        Self::CallbackPanicked(e)
+    }
+}
+
 pub struct IO<U: Unit, MK: MultiKeychain, DP: DataProvider> {
     pub incoming_parents: Receiver<U>,
     pub outgoing_units: Sender<SignedUnit<U::Hasher, DP::Output, MK>>,
     pub data_provider: DP,
+    pub broadcast_gate: Option<Arc<dyn BroadcastGate<DP::Output>>>,
+    pub metadata_provider: Option<Arc<dyn UnitMetadataProvider>>,
+    /// Whether we hold the lease of our seat, so that no other instance creates units too.
+    pub lease: CreationPermit,
+    /// The rounds of our units as they get finalized, for the adaptive inclusion policy.
+    pub finalized_rounds: Receiver<Round>,
+    /// The rounds of the heads of the finalized batches, for the unfinalized rounds limit.
+    pub finalized_heads: Receiver<Round>,
+    pub events: EventBus<U::Hasher, DP::Output, MK::Signature>,
+    pub callbacks: CallbackGuard,
+    /// Notified once we created our unit of the max round.
+    pub session_end: SessionEndMonitor,
 }
 
 async fn create_unit<U: Unit>(
@@ -81,7 +115,7 @@ async fn keep_processing_units<U: Unit>(
 async fn keep_processing_units_until<U: Unit>(
     creator: &mut Creator<U::Hasher>,
     incoming_parents: &mut Receiver<U>,
-    until: Delay,
+    until: BoxFuture<'static, ()>,
 ) -> anyhow::Result<(), CreatorError> {
     futures::select! {
         result = keep_processing_units(creator, incoming_parents).fuse() => {
@@ -94,6 +128,98 @@ async fn keep_processing_units_until<U: Unit>(
     Ok(())
 }
 
+/// Like [`keep_processing_units_until`], but also lets a data request that timed out for one of our
+/// previous units get answered in the meantime.
+async fn keep_processing_units_and_data_until<U: Unit, DP: DataProvider>(
+    creator: &mut Creator<U::Hasher>,
+    incoming_parents: &mut Receiver<U>,
+    data_fetcher: &mut DataFetcher<'_, DP>,
+    until: BoxFuture<'static, ()>,
+) -> anyhow::Result<(), CreatorError> {
+    let mut until = until.fuse();
+    loop {
+        futures::select! {
+            result = keep_processing_units(creator, incoming_parents).fuse() => result?,
+            result = data_fetcher.progress().fuse() => result?,
+            _ = until => {
+                debug!(target: LOG_TARGET, "Delay passed.");
+                return Ok(());
+            },
+        }
+    }
+}
+
+/// Waits until finalization catches up enough for us to create a unit of the given round,
+/// processing incoming parents in the meantime.
+async fn wait_for_finalization<U: Unit>(
+    round: Round,
+    pacing: &mut FinalizationPacing,
+    creator: &mut Creator<U::Hasher>,
+    incoming_parents: &mut Receiver<U>,
+    finalized_heads: &mut Receiver<Round>,
+) -> Result<(), CreatorError> {
+    while let Ok(Some(head)) = finalized_heads.try_next() {
+        pacing.on_finalized(head);
+    }
+    if pacing.allows(round) {
+        return Ok(());
+    }
+    info!(target: LOG_TARGET, "Finalization is behind, pausing before our unit of round {} until a batch with the head of round {} is finalized.", round, round - pacing.max_unfinalized_rounds());
+    while !pacing.allows(round) {
+        futures::select! {
+            head = finalized_heads.next() => match head {
+                Some(head) => pacing.on_finalized(head),
+                None => return Err(CreatorError::FinalizedHeadsChannelClosed),
+            },
+            result = process_unit(creator, incoming_parents).fuse() => result?,
+        }
+    }
+    info!(target: LOG_TARGET, "Finalization caught up, resuming with our unit of round {}.", round);
+    Ok(())
+}
+
+/// Waits for the decision of the gate about the data of our unit of the given round, processing
+/// incoming parents in the meantime. Returns the data the unit should contain, rejected data is
+/// returned to the provider.
+#[allow(clippy::too_many_arguments)]
+async fn pass_gate<U: Unit, DP: DataProvider>(
+    conf: &Config,
+    round: Round,
+    data: DP::Output,
+    gate: &dyn BroadcastGate<DP::Output>,
+    data_fetcher: &mut DataFetcher<'_, DP>,
+    callbacks: &CallbackGuard,
+    creator: &mut Creator<U::Hasher>,
+    incoming_parents: &mut Receiver<U>,
+) -> Result<Option<DP::Output>, CreatorError> {
+    let decision = {
+        let check = callbacks
+            .call_async(UserComponent::BroadcastGate, gate.check(&data))
+            .fuse();
+        pin_mut!(check);
+        let mut timeout = conf.clock().sleep(conf.broadcast_gate_timeout()).fuse();
+        loop {
+            futures::select! {
+                decision = check => break decision?,
+                _ = timeout => {
+                    let decision = conf.on_broadcast_gate_timeout();
+                    warn!(target: LOG_TARGET, "Broadcast gate timed out for our unit of round {}, deciding {:?}.", round, decision);
+                    break decision;
+                },
+                result = process_unit(creator, incoming_parents).fuse() => result?,
+            }
+        }
+    };
+    match decision {
+        GateDecision::Release => Ok(Some(data)),
+        GateDecision::ReplaceWithEmpty => {
+            debug!(target: LOG_TARGET, "Creating our unit of round {} without data, as the broadcast gate rejected it.", round);
+            data_fetcher.return_unused(data)?;
+            Ok(None)
+        }
+    }
+}
+
 /// A process responsible for creating new units. It receives all the units added locally to the Dag
 /// via the `incoming_parents` channel. It creates units according to an internal strategy respecting
 /// always the following constraints: if round is equal to 0, U has no parents, otherwise for a unit U of round r > 0
@@ -105,6 +231,22 @@ async fn keep_processing_units_until<U: Unit>(
 /// The currently implemented strategy creates the unit U according to a delay schedule and when enough
 /// candidates for parents are available for all the above constraints to be satisfied.
 ///
+/// If there is a broadcast gate, the data of our unit is checked right after it is obtained from
+/// the provider, so before the unit is signed, saved to the backup and broadcast. Replacing the
+/// data after saving the unit would make us a forker. The time spent at the gate counts towards
+/// the delay before our next unit, so a slow gate does not slow down the rounds even further.
+///
+/// With a data fetch timeout configured, a provider not answering in time gets our unit created
+/// without data. Its answer still arrives while we create the next units and goes into the first
+/// unit created after it, so a slow provider neither delays the rounds nor loses data.
+///
+/// With the unfinalized rounds limit configured, we do not create units too far above the head of
+/// the last finalized batch, so a stalled finalization does not let the DAG grow without bound.
+///
+/// With the fast forward lag configured, our units of rounds far below the top of the DAG, e.g.
+/// after starting from an old backup, are created without data as soon as their parents are
+/// there, as we cannot skip these rounds without breaking the chain of our units.
+///
 /// We refer to the documentation https://cardinal-cryptography.github.io/AlephBFT/internals.html
 /// Section 5.1 for a discussion of this component.
 pub async fn run<U: Unit, MK: MultiKeychain, DP: DataProvider>(
@@ -151,6 +293,12 @@ async fn read_starting_round_and_run_creator<U: Unit, MK: MultiKeychain, DP: Dat
             CreatorError::ParentsChannelClosed => {
                 debug!(target: LOG_TARGET, "Incoming parent channel closed, exiting.")
             }
+            CreatorError::FinalizedHeadsChannelClosed => {
+                debug!(target: LOG_TARGET, "Finalized heads channel closed, exiting.")
+            }
+            CreatorError::CallbackPanicked(CallbackPanicked(component)) => {
+                error!(target: LOG_TARGET, "The {} panicked, exiting.", component)
+            }
         }
     }
 }
@@ -163,36 +311,191 @@ async fn run_creator<U: Unit, MK: MultiKeychain, DP: DataProvider>(
 ) -> anyhow::Result<(), CreatorError> {
     let node_id = conf.node_ix();
     let n_members = conf.n_members();
-    let create_delay = conf.delay_config().unit_creation_delay.clone();
+    let clock = conf.clock().clone();
     let max_round = conf.max_round();
     let session_id = conf.session_id();
-    let mut creator = Creator::new(node_id, n_members);
+    let mut creator = Creator::new(node_id, n_members).with_node_weights(conf.node_weights());
     let packer = Packer::new(keychain, session_id);
     let incoming_parents = &mut io.incoming_parents;
     let outgoing_units = &io.outgoing_units;
-    let data_provider = &mut io.data_provider;
+    let mut data_fetcher = DataFetcher::new(&mut io.data_provider, io.callbacks.clone());
+    let broadcast_gate = io.broadcast_gate.clone();
+    let metadata_provider = match &io.metadata_provider {
+        Some(_) if conf.wire_version() < EXTENDED_MESSAGES_VERSION => {
+            warn!(target: LOG_TARGET, "Leaving the metadata out of our units, as the nodes running releases predating wire version {} could not read them.", EXTENDED_MESSAGES_VERSION);
+            None
+        }
+        metadata_provider => metadata_provider.clone(),
+    };
+    let lease = &io.lease;
+    let finalized_rounds = &mut io.finalized_rounds;
+    let finalized_heads = &mut io.finalized_heads;
+    let events = &io.events;
+    let callbacks = &io.callbacks;
+    let session_end = &io.session_end;
+    let mut inclusion = conf.adaptive_inclusion().map(InclusionTracker::new);
+    let mut pacing = conf.max_unfinalized_rounds().map(FinalizationPacing::new);
+    let mut gated_since = None;
+    let mut fast_forwarding = false;
 
     debug!(target: LOG_TARGET, "Creator starting from round {}", starting_round);
-    for round in starting_round..max_round {
+    for round in starting_round..=max_round {
         // Skip waiting if someone created a unit of a higher round.
         // In such a case at least 2/3 nodes created units from this round so we aren't skipping a
         // delay we should observe.
         let skip_delay = creator.current_round() > round;
+        // Read every round, as the schedule can be replaced while the session runs.
+        let create_delay = conf.current_delay_config().unit_creation_delay;
         if !skip_delay {
-            let delay = Delay::new(create_delay(round.into()));
+            let delay = match gated_since.take() {
+                Some(since) => clock.sleep_until(since + create_delay(round.into())),
+                None => clock.sleep(create_delay(round.into())),
+            };
 
-            keep_processing_units_until(&mut creator, incoming_parents, delay).await?;
+            keep_processing_units_and_data_until(
+                &mut creator,
+                incoming_parents,
+                &mut data_fetcher,
+                delay,
+            )
+            .await?;
+        }
+        if let Some(pacing) = &mut pacing {
+            wait_for_finalization(
+                round,
+                pacing,
+                &mut creator,
+                incoming_parents,
+                finalized_heads,
+            )
+            .await?;
         }
 
-        let preunit = create_unit(round, &mut creator, incoming_parents).await?;
+        let mut preunit = create_unit(round, &mut creator, incoming_parents).await?;
         trace!(target: LOG_TARGET, "Created a new preunit {:?} at round {:?}.", preunit, round);
-        let data = data_provider.get_data().await;
+        // Our units far below the top of the DAG only link our later units to our earlier ones,
+        // so they don't wait for data, which goes into our first unit close to the top instead.
+        let stale = conf
+            .fast_forward_lag()
+            .is_some_and(|lag| creator.current_round() > round.saturating_add(lag));
+        if stale != fast_forwarding {
+            fast_forwarding = stale;
+            match stale {
+                true => {
+                    info!(target: LOG_TARGET, "Fast-forwarding from round {} towards the top of the DAG at round {}, creating units without data.", round, creator.current_round())
+                }
+                false => {
+                    info!(target: LOG_TARGET, "Fast-forwarded to round {}, creating units with data again.", round)
+                }
+            }
+        }
+        let include_data = match &mut inclusion {
+            Some(inclusion) => {
+                while let Ok(Some(finalized)) = finalized_rounds.try_next() {
+                    inclusion.on_finalized(finalized, round);
+                }
+                if let Some(change) = inclusion.update(round) {
+                    events.publish(InternalEvent::DataInclusionChanged(round, change));
+                }
+                inclusion.includes_data()
+            }
+            None => true,
+        } && !stale;
+        // No data is requested while the data is held back, so nothing gets lost.
+        let data = match include_data {
+            true => {
+                let timeout = conf
+                    .current_delay_config()
+                    .data_fetch_timeout
+                    .map(|timeout| clock.sleep(timeout));
+                let data = data_fetcher.get_data(timeout).await?;
+                if data_fetcher.is_pending() {
+                    warn!(target: LOG_TARGET, "Data provider did not answer in time, creating our unit of round {} without data.", round);
+                }
+                data
+            }
+            false => None,
+        };
         trace!(target: LOG_TARGET, "Received data: {:?}.", data);
-        let unit = packer.pack(preunit, data);
+        let data = match (data, &broadcast_gate) {
+            (Some(data), Some(gate)) => {
+                gated_since = Some(clock.now());
+                pass_gate(
+                    &conf,
+                    round,
+                    data,
+                    gate.as_ref(),
+                    &mut data_fetcher,
+                    callbacks,
+                    &mut creator,
+                    incoming_parents,
+                )
+                .await?
+            }
+            (data, _) => data,
+        };
+        // The data cannot be cut down, so it is dropped, as other nodes would reject our unit.
+        let data = match data {
+            Some(data) if data.encoded_size() > conf.max_data_size() => {
+                warn!(target: LOG_TARGET, "Data for our unit of round {} has {} bytes, more than the allowed {}, creating the unit without it.", round, data.encoded_size(), conf.max_data_size());
+                None
+            }
+            data => data,
+        };
+        let metadata = match &metadata_provider {
+            Some(provider) => {
+                let metadata = callbacks.call(UserComponent::UnitMetadataProvider, || {
+                    provider.metadata(round)
+                })?;
+                match metadata.len() > conf.max_unit_metadata_size() {
+                    true => {
+                        warn!(target: LOG_TARGET, "Metadata of our unit of round {} has {} bytes, more than the allowed {}, dropping it.", round, metadata.len(), conf.max_unit_metadata_size());
+                        Vec::new()
+                    }
+                    false => metadata,
+                }
+            }
+            None => Vec::new(),
+        };
+        let unit = loop {
+            // Signing a unit while another instance with our keys creates units would fork, so
+            // right before every signature we check that the lease is still ours, and wait until
+            // it is ours again otherwise. The preunit is recreated, as we might know more parents
+            // by then.
+            if !lease.allows_creation()? {
+                info!(target: LOG_TARGET, "Not holding the lease, waiting with our unit of round {}.", round);
+                while !lease.allows_creation()? {
+                    let delay = clock.sleep(conf.lease_renewal_interval());
+                    keep_processing_units_until(&mut creator, incoming_parents, delay).await?;
+                }
+                preunit = create_unit(round, &mut creator, incoming_parents).await?;
+                continue;
+            }
+            // We cannot skip a round, as our next unit needs this one as a parent, so all we can
+            // do is to wait and try again. The preunit is recreated, as we might know more parents
+            // by then.
+            match packer.pack(preunit, data.clone(), metadata.clone()) {
+                Ok(unit) => break unit,
+                Err(e) => {
+                    warn!(target: LOG_TARGET, "Failed to sign our unit of round {}: {}. Retrying after a delay.", round, e);
+                    events.publish(InternalEvent::SigningFailed(SigningTarget::Unit(round)));
+                    let delay = clock.sleep(create_delay(round.into()));
+                    keep_processing_units_until(&mut creator, incoming_parents, delay).await?;
+                    preunit = create_unit(round, &mut creator, incoming_parents).await?;
+                }
+            }
+        };
 
         outgoing_units.unbounded_send(unit)?;
+        events.publish(InternalEvent::UnitCreated(round));
+        if let Some(inclusion) = &mut inclusion {
+            inclusion.on_created(round);
+        }
     }
 
-    warn!(target: LOG_TARGET, "Maximum round reached. Not creating another unit.");
-    Ok(())
+    info!(target: LOG_TARGET, "Maximum round {} reached, not creating any more units in this session.", max_round);
+    session_end.report(SessionEnd::CreationEnded(max_round));
+    // The runway treats us going away as a failure, so we stay until the session ends, just
+    // without any timers.
+    keep_processing_units(&mut creator, incoming_parents).await
 }
@@ -0,0 +1,68 @@
+use crate::Round;
+
+/// Decides whether we can create a unit of a given round, so that the rounds above the last
+/// finalized one stay under the limit of [`crate::Config::with_max_unfinalized_rounds`].
+///
+/// Until the first batch is finalized in this run, e.g. after starting from the backup, the last
+/// finalized round is unknown, so nothing is held back.
+pub(crate) struct FinalizationPacing {
+    max_unfinalized_rounds: Round,
+    finalized_round: Option<Round>,
+}
+
+impl FinalizationPacing {
+    pub fn new(max_unfinalized_rounds: Round) -> Self {
+        FinalizationPacing {
+            max_unfinalized_rounds,
+            finalized_round: None,
+        }
+    }
+
+    pub fn max_unfinalized_rounds(&self) -> Round {
+        self.max_unfinalized_rounds
+    }
+
+    /// A batch with the head of the given round got finalized.
+    pub fn on_finalized(&mut self, round: Round) {
+        self.finalized_round = Some(self.finalized_round.map_or(round, |last| last.max(round)));
+    }
+
+    /// The highest round above the last finalized one we can create a unit of, if the limit
+    /// engaged already.
+    pub fn round_cap(&self) -> Option<Round> {
+        self.finalized_round
+            .map(|finalized| finalized.saturating_add(self.max_unfinalized_rounds))
+    }
+
+    /// Whether we can create a unit of the given round.
+    pub fn allows(&self, round: Round) -> bool {
+        self.round_cap().map_or(true, |cap| round <= cap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::creation::pacing::FinalizationPacing;
+
+    #[test]
+    fn holds_nothing_back_before_the_first_finalization() {
+        let pacing = FinalizationPacing::new(3);
+        assert_eq!(pacing.round_cap(), None);
+        assert!(pacing.allows(0));
+        assert!(pacing.allows(1000));
+    }
+
+    #[test]
+    fn holds_back_rounds_over_the_cap_until_finalization_catches_up() {
+        let mut pacing = FinalizationPacing::new(3);
+        pacing.on_finalized(10);
+        assert!(pacing.allows(13));
+        assert!(!pacing.allows(14));
+        // Heads come in the order of finalization, an older one changes nothing.
+        pacing.on_finalized(8);
+        assert!(!pacing.allows(14));
+        pacing.on_finalized(11);
+        assert!(pacing.allows(14));
+        assert_eq!(pacing.round_cap(), Some(14));
+    }
+}
@@ -22,9 +22,10 @@ impl<MK: MultiKeychain> Packer<MK> {
         &self,
         preunit: PreUnit<H>,
         data: Option<D>,
-    ) -> SignedUnit<H, D, MK> {
+        metadata: Vec<u8>,
+    ) -> Result<SignedUnit<H, D, MK>, MK::SignError> {
         Signed::sign(
-            FullUnit::new(preunit, data, self.session_id),
+            FullUnit::new(preunit, data, self.session_id).with_metadata(metadata),
             &self.keychain,
         )
     }
@@ -1,22 +1,31 @@
 //! Converts units from the network into ones that are in the Dag, in the correct order.
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Instant};
 
 use crate::{
+    admission::{mark_stage, AdmissionStage, AdmissionTrace},
     alerts::{Alert, ForkingNotification},
+    dissemination::RequestId,
+    drops::{DropMonitor, DropReason},
     units::{
-        SignedUnit, UncheckedSignedUnit, Unit, UnitStore, Validator as UnitValidator, WrappedUnit,
+        SignedUnit, UncheckedSignedUnit, Unit, UnitCoord, UnitStore, Validator as UnitValidator,
+        WrappedUnit,
     },
-    Data, Hasher, MultiKeychain,
+    ClockSource, Data, Hasher, MultiKeychain, NodeIndex, ReconstructionLimits, Round,
 };
+use codec::Encode;
 use log::{debug, trace, warn};
 
 mod reconstruction;
+mod rejections;
 mod validation;
 
-pub use reconstruction::{ReconstructedUnit, Request};
+pub use reconstruction::{EvictedUnit, EvictionCause, ReconstructedUnit, Request};
 use reconstruction::{Reconstruction, ReconstructionResult};
-pub use validation::ValidatorStatus as DagStatus;
+use rejections::InconsistentResponses;
+pub use rejections::RejectionReason;
+pub use validation::ValidatorStatus;
 use validation::{Error as ValidationError, Validator};
+pub(crate) use validation::{UnverifiedUnit, VerifiedUnit};
 
 const LOG_TARGET: &str = "AlephBFT-dag";
 
@@ -30,6 +39,14 @@ pub struct DagResult<H: Hasher, D: Data, MK: MultiKeychain> {
     pub requests: Vec<Request<H>>,
     /// Alerts raised due to encountered forks.
     pub alerts: Vec<Alert<H, D, MK::Signature>>,
+    /// Hashes of units for which we received parents inconsistent with their control hashes,
+    /// reported once for every distinct response.
+    pub inconsistent_parents: Vec<H::Hash>,
+    /// Units dropped while waiting for their parents.
+    pub evicted: Vec<EvictedUnit<H>>,
+    /// Correctly signed units that failed validation, with their creators, reported only the
+    /// first time they arrive.
+    pub invalid_units: Vec<(NodeIndex, H::Hash, RejectionReason)>,
 }
 
 impl<H: Hasher, D: Data, MK: MultiKeychain> DagResult<H, D, MK> {
@@ -38,6 +55,9 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> DagResult<H, D, MK> {
             units: Vec::new(),
             requests: Vec::new(),
             alerts: Vec::new(),
+            inconsistent_parents: Vec::new(),
+            evicted: Vec::new(),
+            invalid_units: Vec::new(),
         }
     }
 
@@ -46,6 +66,9 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> DagResult<H, D, MK> {
             units: Vec::new(),
             requests: Vec::new(),
             alerts: vec![alert],
+            inconsistent_parents: Vec::new(),
+            evicted: Vec::new(),
+            invalid_units: Vec::new(),
         }
     }
 
@@ -58,10 +81,16 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> DagResult<H, D, MK> {
             mut units,
             mut requests,
             mut alerts,
+            mut inconsistent_parents,
+            mut evicted,
+            mut invalid_units,
         } = other;
         self.units.append(&mut units);
         self.requests.append(&mut requests);
         self.alerts.append(&mut alerts);
+        self.inconsistent_parents.append(&mut inconsistent_parents);
+        self.evicted.append(&mut evicted);
+        self.invalid_units.append(&mut invalid_units);
     }
 }
 
@@ -69,11 +98,18 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> From<ReconstructionResult<SignedUnit
     for DagResult<H, D, MK>
 {
     fn from(other: ReconstructionResult<SignedUnit<H, D, MK>>) -> Self {
-        let ReconstructionResult { units, requests } = other;
+        let ReconstructionResult {
+            units,
+            requests,
+            evicted,
+        } = other;
         DagResult {
             units,
             requests,
+            evicted,
             alerts: Vec::new(),
+            inconsistent_parents: Vec::new(),
+            invalid_units: Vec::new(),
         }
     }
 }
@@ -82,6 +118,8 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> From<ReconstructionResult<SignedUnit
 pub struct Dag<H: Hasher, D: Data, MK: MultiKeychain> {
     validator: Validator<H, D, MK>,
     reconstruction: Reconstruction<SignedUnit<H, D, MK>>,
+    inconsistent_responses: InconsistentResponses<H>,
+    drops: DropMonitor,
 }
 
 impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
@@ -90,14 +128,61 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
         Dag {
             validator: Validator::new(unit_validator),
             reconstruction: Reconstruction::new(),
+            inconsistent_responses: InconsistentResponses::default(),
+            drops: DropMonitor::default(),
+        }
+    }
+
+    /// Bounds the units waiting for their parents, see [`ReconstructionLimits`].
+    pub fn with_reconstruction_limits(self, limits: ReconstructionLimits) -> Self {
+        Dag {
+            reconstruction: self.reconstruction.with_limits(limits),
+            ..self
+        }
+    }
+
+    /// Uses the given clock to expire the remembered rejected units.
+    pub fn with_clock(self, clock: ClockSource) -> Self {
+        Dag {
+            validator: self.validator.with_clock(clock),
+            ..self
+        }
+    }
+
+    /// Counts the units dropped on validation with the given monitor.
+    pub fn with_drop_monitor(self, drops: DropMonitor) -> Self {
+        Dag { drops, ..self }
+    }
+
+    /// Evicted units will not finish processing, so the validator should forget them, in case
+    /// they come again.
+    fn handle_reconstruction_result(
+        &mut self,
+        result: ReconstructionResult<SignedUnit<H, D, MK>>,
+    ) -> DagResult<H, D, MK> {
+        for evicted in &result.evicted {
+            self.validator.finished_processing(&evicted.hash);
         }
+        result.into()
     }
 
-    fn handle_validation_error(error: ValidationError<H, D, MK>) -> DagResult<H, D, MK> {
+    fn handle_validation_error(
+        &self,
+        error: ValidationError<H, D, MK>,
+        creator: Option<NodeIndex>,
+    ) -> DagResult<H, D, MK> {
         use ValidationError::*;
         match error {
             Invalid(e) => {
                 warn!(target: LOG_TARGET, "Received unit failing validation: {}", e);
+                self.drops
+                    .record_drop(DropReason::InvalidUnit, creator, || e.encoded_unit());
+                DagResult::empty()
+            }
+            KnownInvalid(reason) => {
+                trace!(target: LOG_TARGET, "Received a unit that recently failed validation again: {}.", reason);
+                self.drops
+                    .record_drop(DropReason::KnownInvalidUnit, creator, Vec::new);
                 DagResult::empty()
             }
             Duplicate(unit) => {
@@ -106,6 +191,10 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
             }
             Uncommitted(unit) => {
                 debug!(target: LOG_TARGET, "Received unit with hash {:?} created by known forker {:?} for which we don't have a commitment, discarding.", unit.hash(), unit.creator());
+                self.drops
+                    .record_drop(DropReason::UncommittedUnit, Some(unit.creator()), || {
+                        unit.as_signable().encode()
+                    });
                 DagResult::empty()
             }
             NewForker(alert) => {
@@ -113,6 +202,13 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
                 trace!(target: LOG_TARGET, "Created alert: {:?}.", alert);
                 DagResult::alert(*alert)
             }
+            Pruned(unit) => {
+                debug!(target: LOG_TARGET, "Received unit with hash {:?} of pruned round {}, discarding.", unit.hash(), unit.round());
+                self.drops.record_drop(DropReason::PrunedUnit, creator, || {
+                    unit.as_signable().encode()
+                });
+                DagResult::empty()
+            }
         }
     }
 
@@ -122,13 +218,98 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
         unit: UncheckedSignedUnit<H, D, MK::Signature>,
         store: &UnitStore<U>,
     ) -> DagResult<H, D, MK> {
-        match self.validator.validate(unit, store) {
-            Ok(unit) => self.reconstruction.add_unit(unit).into(),
-            Err(e) => Self::handle_validation_error(e),
+        self.add_unit_traced(unit, store, &mut None)
+    }
+
+    /// Add a unit to the Dag, marking the ends of the stages it goes through in the trace.
+    pub(crate) fn add_unit_traced<U: WrappedUnit<H, Wrapped = SignedUnit<H, D, MK>>>(
+        &mut self,
+        unit: UncheckedSignedUnit<H, D, MK::Signature>,
+        store: &UnitStore<U>,
+        trace: &mut Option<AdmissionTrace>,
+    ) -> DagResult<H, D, MK> {
+        match self.admit_unit(unit) {
+            Some(unit) => {
+                let unit = unit.verify(self.validator.unit_validator(), trace);
+                self.add_verified_unit_traced(unit, store, trace)
+            }
+            None => DagResult::empty(),
+        }
+    }
+
+    /// The first part of [`Self::add_unit_traced`], returning the unit if its signature has to
+    /// be checked, and nothing if it recently failed validation, which adds nothing to the dag.
+    pub(crate) fn admit_unit(
+        &mut self,
+        unit: UncheckedSignedUnit<H, D, MK::Signature>,
+    ) -> Option<UnverifiedUnit<H, D, MK>> {
+        let creator = unit.as_signable().creator();
+        match self.validator.admit(unit) {
+            Ok(unit) => Some(unit),
+            Err(e) => {
+                self.handle_validation_error(e, Some(creator));
+                None
+            }
+        }
+    }
+
+    /// The rest of [`Self::add_unit_traced`], for units the signatures of which were checked,
+    /// possibly in another task.
+    pub(crate) fn add_verified_unit_traced<U: WrappedUnit<H, Wrapped = SignedUnit<H, D, MK>>>(
+        &mut self,
+        unit: VerifiedUnit<H, D, MK>,
+        store: &UnitStore<U>,
+        trace: &mut Option<AdmissionTrace>,
+    ) -> DagResult<H, D, MK> {
+        let creator = unit.creator();
+        let unit_hash = unit.hash();
+        match self.validator.validate_verified(unit, store, trace) {
+            Ok(unit) => {
+                let result = self.reconstruction.add_unit(unit);
+                let result = self.handle_reconstruction_result(result);
+                mark_stage(trace, AdmissionStage::Reconstruction);
+                result
+            }
+            Err(ValidationError::Invalid(e)) => {
+                let reason = RejectionReason::from(&e);
+                let mut result =
+                    self.handle_validation_error(ValidationError::Invalid(e), Some(creator));
+                if reason.is_attributable() {
+                    result.invalid_units.push((creator, unit_hash, reason));
+                }
+                result
+            }
+            Err(e) => self.handle_validation_error(e, Some(creator)),
+        }
+    }
+
+    /// Drops a parents response not matching the control hash of the unit. Only the first copy
+    /// of the response is reported, so that repeating it does not cause any more requests.
+    fn reject_inconsistent_parents(
+        &mut self,
+        unit_hash: H::Hash,
+        claimed_parents: &[(UnitCoord, H::Hash)],
+        parents: Vec<UncheckedSignedUnit<H, D, MK::Signature>>,
+    ) -> DagResult<H, D, MK> {
+        let mut result = DagResult::empty();
+        let key = InconsistentResponses::<H>::key(&unit_hash, claimed_parents);
+        if self.inconsistent_responses.insert(key) {
+            warn!(target: LOG_TARGET, "Received parents inconsistent with the control hash of unit {:?}, dropping them before validation.", unit_hash);
+            result.inconsistent_parents.push(unit_hash);
+        } else {
+            trace!(target: LOG_TARGET, "Received the same parents inconsistent with the control hash of unit {:?} again.", unit_hash);
         }
+        self.drops
+            .record_drop(DropReason::InconsistentParents, None, || {
+                (unit_hash, parents).encode()
+            });
+        result
     }
 
-    /// Add parents of a unit to the Dag.
+    /// Add parents of a unit to the Dag. If the unit waits for its parents, they are first
+    /// checked against its control hash, and the whole list is dropped before validation if it
+    /// does not match. Otherwise the parents that pass validation are kept even if the list
+    /// turns out to be inconsistent with the control hash later.
     pub fn add_parents<U: WrappedUnit<H, Wrapped = SignedUnit<H, D, MK>>>(
         &mut self,
         unit_hash: H::Hash,
@@ -136,17 +317,41 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
         store: &UnitStore<U>,
     ) -> DagResult<H, D, MK> {
         use ValidationError::*;
+        let claimed_parents: Vec<_> = parents
+            .iter()
+            .map(|parent| {
+                let parent = parent.as_signable();
+                (parent.coord(), parent.hash())
+            })
+            .collect();
+        if self
+            .reconstruction
+            .control_hash(&unit_hash)
+            .is_some_and(|control_hash| !control_hash.matches_parents(claimed_parents.clone()))
+        {
+            return self.reject_inconsistent_parents(unit_hash, &claimed_parents, parents);
+        }
         let mut result = DagResult::empty();
         let mut parent_hashes = HashMap::new();
         for unit in parents {
             let unit = match self.validator.validate(unit, store) {
                 Ok(unit) => {
-                    result.accumulate(self.reconstruction.add_unit(unit.clone()).into());
+                    let reconstruction_result = self.reconstruction.add_unit(unit.clone());
+                    result.accumulate(self.handle_reconstruction_result(reconstruction_result));
                     unit
                 }
                 Err(Invalid(e)) => {
                     warn!(target: LOG_TARGET, "Received parent failing validation: {}", e);
-                    return result;
+                    self.drops
+                        .record_drop(DropReason::InvalidUnit, None, || e.encoded_unit());
+                    // the list of parents cannot match the control hash anymore
+                    continue;
+                }
+                Err(KnownInvalid(reason)) => {
+                    trace!(target: LOG_TARGET, "Received a parent that recently failed validation again: {}.", reason);
+                    self.drops
+                        .record_drop(DropReason::KnownInvalidUnit, None, Vec::new);
+                    continue;
                 }
                 Err(Duplicate(unit)) => {
                     trace!(target: LOG_TARGET, "Received parent with hash {:?} again.", unit.hash());
@@ -156,6 +361,12 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
                     debug!(target: LOG_TARGET, "Received uncommitted parent {:?}, we should get the commitment soon.", unit.hash());
                     unit
                 }
+                Err(Pruned(unit)) => {
+                    debug!(target: LOG_TARGET, "Received parent with hash {:?} of pruned round {}, discarding.", unit.hash(), unit.round());
+                    self.drops
+                        .record_drop(DropReason::PrunedUnit, None, || unit.as_signable().encode());
+                    continue;
+                }
                 Err(NewForker(alert)) => {
                     warn!(target: LOG_TARGET, "New forker detected.");
                     trace!(target: LOG_TARGET, "Created alert: {:?}.", alert);
@@ -168,11 +379,15 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
             };
             parent_hashes.insert(unit.coord(), unit.hash());
         }
-        result.accumulate(
-            self.reconstruction
-                .add_parents(unit_hash, parent_hashes)
-                .into(),
-        );
+        match self.reconstruction.add_parents(unit_hash, parent_hashes) {
+            Ok(reconstruction_result) => {
+                result.accumulate(self.handle_reconstruction_result(reconstruction_result))
+            }
+            Err(_) => {
+                warn!(target: LOG_TARGET, "Received parents inconsistent with the control hash of unit {:?}.", unit_hash);
+                result.inconsistent_parents.push(unit_hash);
+            }
+        }
         result
     }
 
@@ -194,8 +409,11 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
             Units(units) => {
                 for unit in units {
                     result.accumulate(match self.validator.validate_committed(unit, store) {
-                        Ok(unit) => self.reconstruction.add_unit(unit).into(),
-                        Err(e) => Self::handle_validation_error(e),
+                        Ok(unit) => {
+                            let reconstruction_result = self.reconstruction.add_unit(unit);
+                            self.handle_reconstruction_result(reconstruction_result)
+                        }
+                        Err(e) => self.handle_validation_error(e, None),
                     })
                 }
             }
@@ -203,12 +421,50 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
         result
     }
 
+    /// Forgets the units of rounds up to the given one that failed validation, as the round got
+    /// finalized.
+    pub fn prune_rejections_up_to(&mut self, round: Round) {
+        self.validator.prune_rejections_up_to(round)
+    }
+
     /// Notify the dag that a unit has finished processing and can be cleared from the cache.
     pub fn finished_processing(&mut self, hash: &H::Hash) {
         self.validator.finished_processing(hash);
     }
 
-    pub fn status(&self) -> DagStatus {
+    /// Whether the unit with the given hash was added, but did not finish processing yet. Such
+    /// units might still end up in the store, so we cannot claim we don't have them.
+    pub fn is_processing(&self, hash: &H::Hash) -> bool {
+        self.validator.is_processing(hash)
+    }
+
+    /// Whether some unit at the given coord was added, but did not finish processing yet.
+    pub fn is_processing_coord(&self, coord: UnitCoord) -> bool {
+        self.validator.is_processing_coord(coord)
+    }
+
+    /// Whether some unit we received cannot be added before we get the unit at the given coord.
+    pub fn is_blocked_on(&self, coord: UnitCoord) -> bool {
+        self.reconstruction.is_waiting_for(coord)
+    }
+
+    /// Notes that the peer definitively doesn't have what we requested.
+    pub fn on_not_found(&mut self, request: RequestId<H>, peer: NodeIndex, now: Instant) {
+        self.reconstruction.on_not_found(request, peer, now)
+    }
+
+    /// Evicts the units whose parents nobody but their creators had for the suspect timeout.
+    pub fn evict_unsatisfiable(&mut self, now: Instant) -> DagResult<H, D, MK> {
+        let result = self.reconstruction.evict_unsatisfiable(now);
+        self.handle_reconstruction_result(result)
+    }
+
+    /// How many units wait for their parents.
+    pub fn pending_units(&self) -> usize {
+        self.reconstruction.pending_units()
+    }
+
+    pub fn status(&self) -> ValidatorStatus {
         self.validator.status()
     }
 }
@@ -217,14 +473,16 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
 mod test {
     use crate::{
         alerts::ForkingNotification,
-        dag::{Dag, DagResult, Request},
+        dag::{rejections::REJECTION_TTL, Dag, DagResult, RejectionReason, Request},
+        drops::{drop_monitor, DropReason},
         units::{
-            random_full_parent_units_up_to, random_unit_with_parents, Unit, UnitStore,
-            Validator as UnitValidator, WrappedSignedUnit,
+            random_full_parent_units_up_to, random_unit_with_parents, FullUnit,
+            UncheckedSignedUnit, Unit, UnitStore, Validator as UnitValidator, WrappedSignedUnit,
         },
-        NodeCount, NodeIndex, Signed,
+        ClockSource, NodeCount, NodeIndex, Signed,
     };
-    use aleph_bft_mock::Keychain;
+    use aleph_bft_mock::{Data, Hash64, Hasher64, Keychain, Signature, TokioClock};
+    use codec::{Decode, Encode};
 
     #[test]
     fn accepts_initial_units() {
@@ -237,7 +495,7 @@ mod test {
             .map(|node_id| Keychain::new(node_count, node_id))
             .collect();
         let store = UnitStore::<WrappedSignedUnit>::new(node_count);
-        let validator = UnitValidator::new(session_id, keychains[node_id.0], max_round);
+        let validator = UnitValidator::new(session_id, keychains[node_id.0].clone(), max_round);
         let mut dag = Dag::new(validator);
         for unit in random_full_parent_units_up_to(0, node_count, session_id)
             .into_iter()
@@ -246,13 +504,14 @@ mod test {
                 let keychain = keychains
                     .get(unit.creator().0)
                     .expect("we have the keychains");
-                Signed::sign(unit, keychain)
+                Signed::sign(unit, keychain).expect("the keychain never fails")
             })
         {
             let DagResult {
                 units,
                 requests,
                 alerts,
+                ..
             } = dag.add_unit(unit.into(), &store);
             assert_eq!(units.len(), 1);
             assert!(requests.is_empty());
@@ -271,7 +530,7 @@ mod test {
             .map(|node_id| Keychain::new(node_count, node_id))
             .collect();
         let store = UnitStore::<WrappedSignedUnit>::new(node_count);
-        let validator = UnitValidator::new(session_id, keychains[node_id.0], max_round);
+        let validator = UnitValidator::new(session_id, keychains[node_id.0].clone(), max_round);
         let mut dag = Dag::new(validator);
         for unit in random_full_parent_units_up_to(13, node_count, session_id)
             .into_iter()
@@ -280,13 +539,14 @@ mod test {
                 let keychain = keychains
                     .get(unit.creator().0)
                     .expect("we have the keychains");
-                Signed::sign(unit, keychain)
+                Signed::sign(unit, keychain).expect("the keychain never fails")
             })
         {
             let DagResult {
                 units,
                 requests,
                 alerts,
+                ..
             } = dag.add_unit(unit.into(), &store);
             assert_eq!(units.len(), 1);
             assert!(requests.is_empty());
@@ -306,7 +566,7 @@ mod test {
             .map(|node_id| Keychain::new(node_count, node_id))
             .collect();
         let store = UnitStore::<WrappedSignedUnit>::new(node_count);
-        let validator = UnitValidator::new(session_id, keychains[node_id.0], max_round);
+        let validator = UnitValidator::new(session_id, keychains[node_id.0].clone(), max_round);
         let mut dag = Dag::new(validator);
         for unit in random_full_parent_units_up_to(total_rounds, node_count, session_id)
             .into_iter()
@@ -316,7 +576,7 @@ mod test {
                 let keychain = keychains
                     .get(unit.creator().0)
                     .expect("we have the keychains");
-                Signed::sign(unit, keychain)
+                Signed::sign(unit, keychain).expect("the keychain never fails")
             })
         {
             let unit_round = unit.round();
@@ -325,12 +585,13 @@ mod test {
                 units,
                 requests,
                 alerts,
+                ..
             } = dag.add_unit(unit.into(), &store);
             assert!(alerts.is_empty());
             match unit_round {
                 0 => match unit_creator {
                     NodeIndex(0) => {
-                        assert_eq!(units.len(), (total_rounds * 4 + 1).into());
+                        assert_eq!(units.len(), usize::from(total_rounds * 4 + 1));
                         assert!(requests.is_empty());
                     }
                     _ => {
@@ -346,6 +607,36 @@ mod test {
         }
     }
 
+    #[test]
+    fn reports_processing_units() {
+        let node_count = NodeCount(4);
+        let node_id = NodeIndex(0);
+        let session_id = 43;
+        let max_round = 2137;
+        let keychains: Vec<_> = node_count
+            .into_iterator()
+            .map(|node_id| Keychain::new(node_count, node_id))
+            .collect();
+        let store = UnitStore::<WrappedSignedUnit>::new(node_count);
+        let validator = UnitValidator::new(session_id, keychains[node_id.0].clone(), max_round);
+        let mut dag = Dag::new(validator);
+        let units = random_full_parent_units_up_to(1, node_count, session_id);
+        let unit = units[1][0].clone();
+        let parent_coord = units[0][0].coord();
+        let unit = Signed::sign(unit, &keychains[0]).expect("the keychain never fails");
+        let (unit_coord, unit_hash) = (unit.coord(), unit.hash());
+        assert!(!dag.is_processing_coord(unit_coord));
+        let DagResult { units, .. } = dag.add_unit(unit.into(), &store);
+        assert!(units.is_empty());
+        // The unit waits for its parents, so it is neither in the store nor forgotten.
+        assert!(dag.is_processing_coord(unit_coord));
+        assert!(dag.is_processing(&unit_hash));
+        assert!(!dag.is_processing_coord(parent_coord));
+        dag.finished_processing(&unit_hash);
+        assert!(!dag.is_processing_coord(unit_coord));
+        assert!(!dag.is_processing(&unit_hash));
+    }
+
     #[test]
     fn alerts_on_fork() {
         let node_count = NodeCount(4);
@@ -357,7 +648,7 @@ mod test {
             .map(|node_id| Keychain::new(node_count, node_id))
             .collect();
         let mut store = UnitStore::new(node_count);
-        let validator = UnitValidator::new(session_id, keychains[node_id.0], max_round);
+        let validator = UnitValidator::new(session_id, keychains[node_id.0].clone(), max_round);
         let mut dag = Dag::new(validator);
         let forker_id = NodeIndex(3);
         let keychain = keychains.get(forker_id.0).expect("we have the keychain");
@@ -367,7 +658,7 @@ mod test {
             .get(forker_id.0)
             .expect("We have the forker")
             .clone();
-        let unit = Signed::sign(unit, keychain);
+        let unit = Signed::sign(unit, keychain).expect("the keychain never fails");
         let mut fork = random_full_parent_units_up_to(0, node_count, session_id)
             .first()
             .expect("we have initial units")
@@ -383,11 +674,12 @@ mod test {
                 .expect("We have the forker")
                 .clone();
         }
-        let fork = Signed::sign(fork, keychain);
+        let fork = Signed::sign(fork, keychain).expect("the keychain never fails");
         let DagResult {
             mut units,
             requests,
             alerts,
+            ..
         } = dag.add_unit(unit.into(), &store);
         assert_eq!(units.len(), 1);
         assert!(requests.is_empty());
@@ -397,12 +689,35 @@ mod test {
             units,
             requests,
             alerts,
+            ..
         } = dag.add_unit(fork.into(), &store);
         assert!(units.is_empty());
         assert!(requests.is_empty());
         assert_eq!(alerts.len(), 1);
     }
 
+    #[test]
+    fn alerts_on_units_differing_in_metadata() {
+        let node_count = NodeCount(4);
+        let session_id = 43;
+        let forker_id = NodeIndex(3);
+        let keychain = Keychain::new(node_count, forker_id);
+        let mut store = UnitStore::new(node_count);
+        let validator = UnitValidator::new(session_id, Keychain::new(node_count, 0.into()), 2137);
+        let mut dag = Dag::new(validator);
+        let unit = random_full_parent_units_up_to(0, node_count, session_id)[0][forker_id.0]
+            .clone()
+            .with_metadata(vec![1]);
+        let fork = unit.clone().with_metadata(vec![2]);
+        let unit = Signed::sign(unit, &keychain).expect("the keychain never fails");
+        let fork = Signed::sign(fork, &keychain).expect("the keychain never fails");
+        let DagResult { mut units, .. } = dag.add_unit(unit.into(), &store);
+        store.insert(units.pop().expect("the unit is added"));
+        let DagResult { units, alerts, .. } = dag.add_unit(fork.into(), &store);
+        assert!(units.is_empty());
+        assert_eq!(alerts.len(), 1);
+    }
+
     #[test]
     fn detects_fork_through_notification() {
         let node_count = NodeCount(7);
@@ -415,7 +730,7 @@ mod test {
             .map(|node_id| Keychain::new(node_count, node_id))
             .collect();
         let store = UnitStore::<WrappedSignedUnit>::new(node_count);
-        let validator = UnitValidator::new(session_id, keychains[node_id.0], max_round);
+        let validator = UnitValidator::new(session_id, keychains[node_id.0].clone(), max_round);
         let mut dag = Dag::new(validator);
         let unit = random_full_parent_units_up_to(2, node_count, session_id)
             .get(2)
@@ -423,18 +738,19 @@ mod test {
             .get(forker_id.0)
             .expect("we have the unit for the forker")
             .clone();
-        let unit = Signed::sign(unit, &keychains[forker_id.0]);
+        let unit = Signed::sign(unit, &keychains[forker_id.0]).expect("the keychain never fails");
         let fork = random_full_parent_units_up_to(2, node_count, session_id)
             .get(2)
             .expect("we have the requested round")
             .get(forker_id.0)
             .expect("we have the unit for the forker")
             .clone();
-        let fork = Signed::sign(fork, &keychains[forker_id.0]);
+        let fork = Signed::sign(fork, &keychains[forker_id.0]).expect("the keychain never fails");
         let DagResult {
             units,
             requests,
             alerts,
+            ..
         } = dag.process_forking_notification(
             ForkingNotification::Forker((unit.clone().into(), fork.into())),
             &store,
@@ -458,7 +774,7 @@ mod test {
             .map(|node_id| Keychain::new(node_count, node_id))
             .collect();
         let store = UnitStore::<WrappedSignedUnit>::new(node_count);
-        let validator = UnitValidator::new(session_id, keychains[node_id.0], max_round);
+        let validator = UnitValidator::new(session_id, keychains[node_id.0].clone(), max_round);
         let mut dag = Dag::new(validator);
         let units = random_full_parent_units_up_to(produced_round, node_count, session_id);
         let fork_parents = units
@@ -469,18 +785,19 @@ mod test {
             .cloned()
             .collect();
         let fork = random_unit_with_parents(forker_id, &fork_parents, 3);
-        let fork = Signed::sign(fork, &keychains[forker_id.0]);
+        let fork = Signed::sign(fork, &keychains[forker_id.0]).expect("the keychain never fails");
         let unit = units
             .get(3)
             .expect("we have the requested round")
             .get(forker_id.0)
             .expect("we have the forker's unit")
             .clone();
-        let unit = Signed::sign(unit, &keychains[forker_id.0]);
+        let unit = Signed::sign(unit, &keychains[forker_id.0]).expect("the keychain never fails");
         let DagResult {
             units: reconstructed_units,
             requests,
             alerts,
+            ..
         } = dag.process_forking_notification(
             ForkingNotification::Forker((unit.clone().into(), fork.clone().into())),
             &store,
@@ -494,12 +811,13 @@ mod test {
             let keychain = keychains
                 .get(unit.creator().0)
                 .expect("we have the keychains");
-            Signed::sign(unit.clone(), keychain)
+            Signed::sign(unit.clone(), keychain).expect("the keychain never fails")
         }) {
             let DagResult {
                 units,
                 requests: _,
                 alerts,
+                ..
             } = dag.add_unit(unit.into(), &store);
             units_added += units.len();
             assert!(alerts.is_empty());
@@ -514,7 +832,9 @@ mod test {
                     .expect("we have the forker's unit")
                     .clone()
             })
-            .map(|unit| Signed::sign(unit, &keychains[forker_id.0]))
+            .map(|unit| {
+                Signed::sign(unit, &keychains[forker_id.0]).expect("the keychain never fails")
+            })
             .chain(Some(fork))
             .map(|unit| unit.into())
             .collect();
@@ -522,6 +842,7 @@ mod test {
             units: reconstructed_units,
             requests,
             alerts,
+            ..
         } = dag.process_forking_notification(ForkingNotification::Units(committed_units), &store);
         assert!(alerts.is_empty());
         // the non-fork unit was added first in the forking notif, so all units reconstruct successfully
@@ -529,8 +850,18 @@ mod test {
         assert_eq!(reconstructed_units.len(), node_count.0 * 4 + 1);
     }
 
-    #[test]
-    fn handles_explicit_parents() {
+    type TestDag = Dag<Hasher64, Data, Keychain>;
+    type TestFullUnit = FullUnit<Hasher64, Data>;
+
+    /// Creates a dag in which a unit cannot be reconstructed without explicit parents, returning
+    /// it together with the hash of that unit and the units generated for it.
+    fn dag_with_confused_unit() -> (
+        TestDag,
+        UnitStore<WrappedSignedUnit>,
+        Vec<Keychain>,
+        Vec<Vec<TestFullUnit>>,
+        Hash64,
+    ) {
         let node_count = NodeCount(7);
         let node_id = NodeIndex(0);
         let forker_id = NodeIndex(3);
@@ -542,7 +873,7 @@ mod test {
             .map(|node_id| Keychain::new(node_count, node_id))
             .collect();
         let store = UnitStore::<WrappedSignedUnit>::new(node_count);
-        let validator = UnitValidator::new(session_id, keychains[node_id.0], max_round);
+        let validator = UnitValidator::new(session_id, keychains[node_id.0].clone(), max_round);
         let mut dag = Dag::new(validator);
         let units = random_full_parent_units_up_to(produced_round, node_count, session_id);
         let fork_parents = units
@@ -553,18 +884,19 @@ mod test {
             .cloned()
             .collect();
         let fork = random_unit_with_parents(forker_id, &fork_parents, 3);
-        let fork = Signed::sign(fork, &keychains[forker_id.0]);
+        let fork = Signed::sign(fork, &keychains[forker_id.0]).expect("the keychain never fails");
         let unit = units
             .get(3)
             .expect("we have the requested round")
             .get(forker_id.0)
             .expect("we have the forker's unit")
             .clone();
-        let unit = Signed::sign(unit, &keychains[forker_id.0]);
+        let unit = Signed::sign(unit, &keychains[forker_id.0]).expect("the keychain never fails");
         let DagResult {
             units: reconstructed_units,
             requests,
             alerts,
+            ..
         } = dag.process_forking_notification(
             // note the reverse order, to create parent requests later
             ForkingNotification::Forker((fork.clone().into(), unit.clone().into())),
@@ -580,12 +912,13 @@ mod test {
             let keychain = keychains
                 .get(unit.creator().0)
                 .expect("we have the keychains");
-            Signed::sign(unit.clone(), keychain)
+            Signed::sign(unit.clone(), keychain).expect("the keychain never fails")
         }) {
             let DagResult {
                 units,
                 mut requests,
                 alerts,
+                ..
             } = dag.add_unit(unit.into(), &store);
             units_added += units.len();
             all_requests.append(&mut requests);
@@ -610,7 +943,9 @@ mod test {
                     .expect("we have the forker's unit")
                     .clone()
             })
-            .map(|unit| Signed::sign(unit, &keychains[forker_id.0]))
+            .map(|unit| {
+                Signed::sign(unit, &keychains[forker_id.0]).expect("the keychain never fails")
+            })
             .chain(Some(fork))
             .map(|unit| unit.into())
             .collect();
@@ -618,6 +953,7 @@ mod test {
             units: reconstructed_units,
             requests,
             alerts,
+            ..
         } = dag.process_forking_notification(ForkingNotification::Units(committed_units), &store);
         assert!(alerts.is_empty());
         // we already got the requests earlier, in parent_requests
@@ -633,32 +969,259 @@ mod test {
                     .expect("we have the forker's unit")
                     .clone()
             })
-            .map(|unit| Signed::sign(unit, &keychains[forker_id.0]).into())
+            .map(|unit| {
+                Signed::sign(unit, &keychains[forker_id.0])
+                    .expect("the keychain never fails")
+                    .into()
+            })
             .collect();
         let DagResult {
             units: reconstructed_units,
             requests,
             alerts,
+            ..
         } = dag.process_forking_notification(ForkingNotification::Units(committed_units), &store);
         assert!(alerts.is_empty());
         assert!(requests.is_empty());
         assert_eq!(reconstructed_units.len(), 1);
         let confused_unit = parent_requests.pop().expect("we chacked it's not empty");
+        (dag, store, keychains, units, confused_unit)
+    }
+
+    #[test]
+    fn handles_explicit_parents() {
+        let (mut dag, store, keychains, units, confused_unit) = dag_with_confused_unit();
         let parents = units
             .get(3)
             .expect("we have round 3 units")
             .iter()
-            .map(|unit| Signed::sign(unit.clone(), &keychains[unit.creator().0]))
+            .map(|unit| {
+                Signed::sign(unit.clone(), &keychains[unit.creator().0])
+                    .expect("the keychain never fails")
+            })
             .map(|unit| unit.into())
             .collect();
         let DagResult {
             units: reconstructed_units,
             requests,
             alerts,
+            ..
         } = dag.add_parents(confused_unit, parents, &store);
         assert!(alerts.is_empty());
         assert!(requests.is_empty());
         assert_eq!(reconstructed_units.len(), 1);
         assert_eq!(reconstructed_units[0].hash(), confused_unit);
     }
+
+    #[test]
+    fn reports_inconsistent_explicit_parents() {
+        let (mut dag, store, keychains, units, confused_unit) = dag_with_confused_unit();
+        let parents: Vec<UncheckedSignedUnit<Hasher64, Data, Signature>> = units
+            .get(3)
+            .expect("we have round 3 units")
+            .iter()
+            .map(|unit| {
+                Signed::sign(unit.clone(), &keychains[unit.creator().0])
+                    .expect("the keychain never fails")
+                    .into()
+            })
+            .collect();
+        // a valid unit is sent in place of one of the parents
+        let mut corrupted_parents = parents.clone();
+        corrupted_parents[1] = corrupted_parents[0].clone();
+        let DagResult {
+            units: reconstructed_units,
+            requests,
+            alerts,
+            inconsistent_parents,
+            ..
+        } = dag.add_parents(confused_unit, corrupted_parents, &store);
+        assert!(alerts.is_empty());
+        assert!(requests.is_empty());
+        assert!(reconstructed_units.is_empty());
+        assert_eq!(inconsistent_parents, vec![confused_unit]);
+        let DagResult {
+            units: reconstructed_units,
+            inconsistent_parents,
+            ..
+        } = dag.add_parents(confused_unit, parents, &store);
+        assert!(inconsistent_parents.is_empty());
+        assert_eq!(reconstructed_units.len(), 1);
+        assert_eq!(reconstructed_units[0].hash(), confused_unit);
+    }
+
+    fn signed_parents(
+        units: &[Vec<TestFullUnit>],
+        keychains: &[Keychain],
+    ) -> Vec<UncheckedSignedUnit<Hasher64, Data, Signature>> {
+        units
+            .get(3)
+            .expect("we have round 3 units")
+            .iter()
+            .map(|unit| {
+                Signed::sign(unit.clone(), &keychains[unit.creator().0])
+                    .expect("the keychain never fails")
+                    .into()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn drops_parents_with_a_substituted_hash_before_validation() {
+        let (dag, store, keychains, units, confused_unit) = dag_with_confused_unit();
+        let (handle, monitor) = drop_monitor();
+        let mut dag = dag.with_drop_monitor(monitor);
+        let parents = signed_parents(&units, &keychains);
+        // a fork of one of the parents, so the count and coords match the control hash
+        let parent = &units[3][1];
+        let data = parent.data().map_or(0, |data| data + 1);
+        let substitute = FullUnit::new(
+            parent.as_pre_unit().clone(),
+            Some(data),
+            parent.session_id(),
+        );
+        let substitute = Signed::sign(substitute, &keychains[1]).expect("the keychain never fails");
+        let mut substituted_parents = parents.clone();
+        substituted_parents[1] = substitute.into();
+        let DagResult {
+            units: reconstructed_units,
+            requests,
+            alerts,
+            inconsistent_parents,
+            ..
+        } = dag.add_parents(confused_unit, substituted_parents.clone(), &store);
+        // the fork was not even validated, so no alert
+        assert!(alerts.is_empty());
+        assert!(requests.is_empty());
+        assert!(reconstructed_units.is_empty());
+        assert_eq!(inconsistent_parents, vec![confused_unit]);
+        let stats = handle.stats();
+        assert_eq!(stats.count(DropReason::InconsistentParents), 1);
+        assert_eq!(stats.count(DropReason::InvalidUnit), 0);
+
+        let DagResult {
+            units: reconstructed_units,
+            alerts,
+            inconsistent_parents,
+            ..
+        } = dag.add_parents(confused_unit, substituted_parents, &store);
+        assert!(alerts.is_empty());
+        assert!(reconstructed_units.is_empty());
+        assert!(inconsistent_parents.is_empty());
+        assert_eq!(handle.stats().count(DropReason::InconsistentParents), 2);
+
+        let DagResult {
+            units: reconstructed_units,
+            inconsistent_parents,
+            ..
+        } = dag.add_parents(confused_unit, parents, &store);
+        assert!(inconsistent_parents.is_empty());
+        assert_eq!(reconstructed_units.len(), 1);
+        assert_eq!(reconstructed_units[0].hash(), confused_unit);
+    }
+
+    #[test]
+    fn reports_differently_inconsistent_parents_again() {
+        let (mut dag, store, keychains, units, confused_unit) = dag_with_confused_unit();
+        let parents = signed_parents(&units, &keychains);
+        let missing_one = parents[1..].to_vec();
+        let missing_another = [&parents[..1], &parents[2..]].concat();
+        let expected_reports = [
+            (missing_one.clone(), vec![confused_unit]),
+            (missing_another, vec![confused_unit]),
+            (missing_one, vec![]),
+        ];
+        for (inconsistent, expected) in expected_reports {
+            let DagResult {
+                inconsistent_parents,
+                ..
+            } = dag.add_parents(confused_unit, inconsistent, &store);
+            assert_eq!(inconsistent_parents, expected);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn validates_copies_of_rejected_unit_once() {
+        let node_count = NodeCount(4);
+        let node_id = NodeIndex(0);
+        let session_id = 43;
+        let max_round = 2137;
+        let keychains = Keychain::new_vec(node_count);
+        let store = UnitStore::<WrappedSignedUnit>::new(node_count);
+        let validator = UnitValidator::new(session_id, keychains[node_id.0].clone(), max_round);
+        let mut dag = Dag::new(validator).with_clock(ClockSource::new(TokioClock));
+        let unit = random_full_parent_units_up_to(0, node_count, session_id + 1)[0][1].clone();
+        let unit_hash = unit.hash();
+        let unit: UncheckedSignedUnit<Hasher64, Data, Signature> =
+            Signed::sign(unit, &keychains[1])
+                .expect("the keychain never fails")
+                .into();
+
+        let mut reports = Vec::new();
+        for _ in 0..10 {
+            let DagResult {
+                units,
+                invalid_units,
+                ..
+            } = dag.add_unit(unit.clone(), &store);
+            assert!(units.is_empty());
+            reports.extend(invalid_units);
+        }
+        assert_eq!(
+            reports,
+            vec![(NodeIndex(1), unit_hash, RejectionReason::WrongSession)]
+        );
+        assert!(dag
+            .status()
+            .to_string()
+            .ends_with("suppressed copies of rejected units: 9"));
+
+        tokio::time::advance(REJECTION_TTL).await;
+        let DagResult { invalid_units, .. } = dag.add_unit(unit.clone(), &store);
+        assert_eq!(invalid_units.len(), 1);
+        assert!(dag
+            .status()
+            .to_string()
+            .ends_with("suppressed copies of rejected units: 9"));
+
+        dag.prune_rejections_up_to(0);
+        let DagResult { invalid_units, .. } = dag.add_unit(unit, &store);
+        assert_eq!(invalid_units.len(), 1);
+    }
+
+    #[test]
+    fn wrongly_signed_copy_does_not_block_unit() {
+        let node_count = NodeCount(4);
+        let node_id = NodeIndex(0);
+        let session_id = 43;
+        let max_round = 2137;
+        let keychains = Keychain::new_vec(node_count);
+        let store = UnitStore::<WrappedSignedUnit>::new(node_count);
+        let validator = UnitValidator::new(session_id, keychains[node_id.0].clone(), max_round);
+        let mut dag = Dag::new(validator);
+        let unit = random_full_parent_units_up_to(0, node_count, session_id)[0][1].clone();
+        // Claims to be signed by the creator, but the signature covers something else.
+        let forged = UncheckedSignedUnit::<Hasher64, Data, Signature>::decode(
+            &mut &(unit.clone(), Signature::new(vec![], NodeIndex(1))).encode()[..],
+        )
+        .expect("the encoding is correct");
+        for _ in 0..2 {
+            let DagResult {
+                units,
+                invalid_units,
+                ..
+            } = dag.add_unit(forged.clone(), &store);
+            assert!(units.is_empty());
+            // Anyone could have broken the signature, so nobody is blamed.
+            assert!(invalid_units.is_empty());
+        }
+        assert!(dag
+            .status()
+            .to_string()
+            .ends_with("suppressed copies of rejected units: 1"));
+
+        let unit = Signed::sign(unit, &keychains[1]).expect("the keychain never fails");
+        let DagResult { units, .. } = dag.add_unit(unit.into(), &store);
+        assert_eq!(units.len(), 1);
+    }
 }
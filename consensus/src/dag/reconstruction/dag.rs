@@ -111,6 +111,27 @@ impl<U: UnitWithParents> Dag<U> {
             Err(unit) => self.move_to_dag(unit),
         }
     }
+
+    /// Removes the units waiting, directly or not, for the unit with the given hash, which will
+    /// not be added after all. Returns the removed units.
+    pub fn remove_descendants(&mut self, unit_hash: &HashFor<U>) -> Vec<U> {
+        let mut result = Vec::new();
+        let mut removed_units = VecDeque::from([*unit_hash]);
+        while let Some(unit_hash) = removed_units.pop_front() {
+            for child in self.waiting_for.remove(&unit_hash).into_iter().flatten() {
+                if let Some(orphan) = self.orphaned_units.remove(&child) {
+                    for parent in orphan.missing_parents() {
+                        if let Some(children) = self.waiting_for.get_mut(parent) {
+                            children.retain(|other_child| *other_child != child);
+                        }
+                    }
+                    removed_units.push_back(child);
+                    result.push(orphan.unit);
+                }
+            }
+        }
+        result
+    }
 }
 
 #[cfg(test)]
@@ -227,4 +248,26 @@ mod test {
         }
         assert!(hash_batches.is_empty());
     }
+
+    #[test]
+    fn removes_descendants_of_missing_unit() {
+        let mut unit_dag = reconstructed(random_full_parent_units_up_to(2, NodeCount(4), 43));
+        let missing_unit = unit_dag[0].remove(0);
+        let mut dag = Dag::new();
+        for unit in unit_dag.iter().flatten() {
+            dag.add_unit(unit.clone());
+        }
+        let removed: HashSet<_> = dag
+            .remove_descendants(&missing_unit.hash())
+            .iter()
+            .map(|unit| unit.hash())
+            .collect();
+        let descendants: HashSet<_> = unit_dag[1..]
+            .iter()
+            .flatten()
+            .map(|unit| unit.hash())
+            .collect();
+        assert_eq!(removed, descendants);
+        assert_eq!(dag.add_unit(missing_unit.clone()), vec![missing_unit]);
+    }
 }
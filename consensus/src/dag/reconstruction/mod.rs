@@ -1,9 +1,13 @@
 use crate::{
-    units::{ControlHash, FullUnit, HashFor, Unit, UnitCoord, UnitWithParents, WrappedUnit},
-    Hasher, NodeMap, SessionId,
+    dissemination::RequestId,
+    units::{
+        parent_eligibility, ControlHash, FullUnit, HashFor, Unit, UnitCoord, UnitWithParents,
+        WrappedUnit,
+    },
+    Hasher, NodeMap, ReconstructionLimits, SessionId,
 };
 use aleph_bft_rmc::NodeCount;
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Instant};
 
 mod dag;
 mod parents;
@@ -22,9 +26,7 @@ pub struct ReconstructedUnit<U: Unit> {
 impl<U: Unit> ReconstructedUnit<U> {
     /// Returns a reconstructed unit if the parents agree with the hash, errors out otherwise.
     pub fn with_parents(unit: U, parents: NodeMap<(HashFor<U>, Round)>) -> Result<Self, U> {
-        match unit.control_hash().combined_hash()
-            == ControlHash::<U::Hasher>::create_control_hash(&parents)
-        {
+        match parent_eligibility::control_hash_matches(unit.control_hash(), &parents) {
             true => Ok(ReconstructedUnit { unit, parents }),
             false => Err(unit),
         }
@@ -61,6 +63,10 @@ impl<U: Unit> Unit for ReconstructedUnit<U> {
     fn session_id(&self) -> SessionId {
         self.unit.session_id()
     }
+
+    fn has_data(&self) -> bool {
+        self.unit.has_data()
+    }
 }
 
 impl<U: Unit> WrappedUnit<U::Hasher> for ReconstructedUnit<U> {
@@ -126,6 +132,7 @@ impl<D: Data, H: Hasher, K: MultiKeychain> From<ReconstructedUnit<Signed<FullUni
             round,
             hash,
             data,
+            flagged: false,
         }
     }
 }
@@ -140,6 +147,36 @@ pub enum Request<H: Hasher> {
     ParentsOf(H::Hash),
 }
 
+/// Why a unit was evicted from the reconstruction before getting its parents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EvictionCause {
+    /// Every node other than its creator denied having the parents it was waiting for, and they
+    /// did not arrive within the suspect timeout.
+    Unsatisfiable,
+    /// Its creator had too many units waiting for parents, and it had the highest round of them.
+    OverCap,
+    /// It was evicted before and arrived again without the parents needed to reconstruct it.
+    EvictedBefore,
+    /// It was waiting in the Dag for a parent that got evicted.
+    ParentEvicted,
+}
+
+/// A unit evicted from the reconstruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvictedUnit<H: Hasher> {
+    pub hash: H::Hash,
+    pub coord: UnitCoord,
+    pub cause: EvictionCause,
+    /// The coords of the parents the unit was still waiting for.
+    pub awaited_coords: Vec<UnitCoord>,
+    /// How many units of the same creator still wait for parents.
+    pub pending: usize,
+}
+
+/// The explicit parents received for a unit do not match its control hash.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InconsistentParents;
+
 /// The result of a reconstruction attempt. Might contain multiple reconstructed units,
 /// as well as requests for some data that is needed for further reconstruction.
 #[derive(Debug, PartialEq, Eq)]
@@ -148,11 +185,17 @@ pub struct ReconstructionResult<U: Unit> {
     pub units: Vec<ReconstructedUnit<U>>,
     /// Any requests that now should be made.
     pub requests: Vec<Request<U::Hasher>>,
+    /// All the units that got evicted.
+    pub evicted: Vec<EvictedUnit<U::Hasher>>,
 }
 
 impl<U: Unit> ReconstructionResult<U> {
     fn new(units: Vec<ReconstructedUnit<U>>, requests: Vec<Request<U::Hasher>>) -> Self {
-        ReconstructionResult { units, requests }
+        ReconstructionResult {
+            units,
+            requests,
+            evicted: Vec::new(),
+        }
     }
 
     fn empty() -> Self {
@@ -160,16 +203,17 @@ impl<U: Unit> ReconstructionResult<U> {
     }
 
     fn reconstructed(unit: ReconstructedUnit<U>) -> Self {
-        ReconstructionResult {
-            units: vec![unit],
-            requests: Vec::new(),
-        }
+        ReconstructionResult::new(vec![unit], Vec::new())
     }
 
     fn request(request: Request<U::Hasher>) -> Self {
+        ReconstructionResult::new(Vec::new(), vec![request])
+    }
+
+    fn evicted(evicted: Vec<EvictedUnit<U::Hasher>>) -> Self {
         ReconstructionResult {
-            units: Vec::new(),
-            requests: vec![request],
+            evicted,
+            ..ReconstructionResult::empty()
         }
     }
 
@@ -181,13 +225,19 @@ impl<U: Unit> ReconstructionResult<U> {
         self.requests.push(request);
     }
 
+    fn add_evicted(&mut self, evicted: EvictedUnit<U::Hasher>) {
+        self.evicted.push(evicted);
+    }
+
     fn accumulate(&mut self, other: ReconstructionResult<U>) {
         let ReconstructionResult {
             mut units,
             mut requests,
+            mut evicted,
         } = other;
         self.units.append(&mut units);
         self.requests.append(&mut requests);
+        self.evicted.append(&mut evicted);
     }
 }
 
@@ -207,16 +257,48 @@ impl<U: Unit> Reconstruction<U> {
         Reconstruction { parents, dag }
     }
 
+    /// Bounds the units waiting for parents, see [`ReconstructionLimits`].
+    pub fn with_limits(self, limits: ReconstructionLimits) -> Self {
+        Reconstruction {
+            parents: self.parents.with_limits(limits),
+            ..self
+        }
+    }
+
     fn handle_parents_reconstruction_result(
         &mut self,
         reconstruction_result: ReconstructionResult<U>,
     ) -> ReconstructionResult<U> {
-        let ReconstructionResult { units, requests } = reconstruction_result;
+        let ReconstructionResult {
+            units,
+            requests,
+            mut evicted,
+        } = reconstruction_result;
         let units = units
             .into_iter()
             .flat_map(|unit| self.dag.add_unit(unit))
             .collect();
-        ReconstructionResult::new(units, requests)
+        // Units that got their parents reconstructed, but wait in the dag for an evicted one,
+        // would never leave it.
+        let orphans: Vec<_> = evicted
+            .iter()
+            .flat_map(|evicted| self.dag.remove_descendants(&evicted.hash))
+            .collect();
+        for orphan in orphans {
+            self.parents.forget_unit(orphan.hash(), orphan.coord());
+            evicted.push(EvictedUnit {
+                hash: orphan.hash(),
+                coord: orphan.coord(),
+                cause: EvictionCause::ParentEvicted,
+                awaited_coords: Vec::new(),
+                pending: self.parents.pending(orphan.creator()),
+            });
+        }
+        ReconstructionResult {
+            units,
+            requests,
+            evicted,
+        }
     }
 
     /// Add a unit to the reconstruction.
@@ -226,13 +308,41 @@ impl<U: Unit> Reconstruction<U> {
     }
 
     /// Add an explicit list of parents to the reconstruction.
+    /// Errors out if the parents do not match the control hash of the unit.
     pub fn add_parents(
         &mut self,
         unit: HashFor<U>,
         parents: HashMap<UnitCoord, HashFor<U>>,
-    ) -> ReconstructionResult<U> {
-        let parent_reconstruction_result = self.parents.add_parents(unit, parents);
-        self.handle_parents_reconstruction_result(parent_reconstruction_result)
+    ) -> Result<ReconstructionResult<U>, InconsistentParents> {
+        let parent_reconstruction_result = self.parents.add_parents(unit, parents)?;
+        Ok(self.handle_parents_reconstruction_result(parent_reconstruction_result))
+    }
+
+    /// Whether some unit waits for the unit at the given coord to get its parents reconstructed.
+    pub fn is_waiting_for(&self, coord: UnitCoord) -> bool {
+        self.parents.is_waiting_for(coord)
+    }
+
+    /// The control hash of the unit with the given hash, if it waits for its parents.
+    pub fn control_hash(&self, unit: &HashFor<U>) -> Option<&ControlHash<U::Hasher>> {
+        self.parents.control_hash(unit)
+    }
+
+    /// Notes that the peer definitively doesn't have what we requested, which might make the
+    /// units waiting for it suspect.
+    pub fn on_not_found(&mut self, request: RequestId<U::Hasher>, peer: NodeIndex, now: Instant) {
+        self.parents.on_not_found(request, peer, now)
+    }
+
+    /// Evicts the units that stayed suspect for longer than the suspect timeout.
+    pub fn evict_unsatisfiable(&mut self, now: Instant) -> ReconstructionResult<U> {
+        let evicted = self.parents.evict_unsatisfiable(now);
+        self.handle_parents_reconstruction_result(ReconstructionResult::evicted(evicted))
+    }
+
+    /// How many units wait for their parents.
+    pub fn pending_units(&self) -> usize {
+        self.parents.pending_units()
     }
 }
 
@@ -254,6 +364,7 @@ mod test {
             let ReconstructionResult {
                 mut units,
                 requests,
+                ..
             } = reconstruction.add_unit(unit.clone());
             assert!(requests.is_empty());
             assert_eq!(units.len(), 1);
@@ -273,6 +384,7 @@ mod test {
                 let ReconstructionResult {
                     mut units,
                     requests,
+                    ..
                 } = reconstruction.add_unit(unit.clone());
                 assert!(requests.is_empty());
                 assert_eq!(units.len(), 1);
@@ -307,7 +419,9 @@ mod test {
             .expect("just created")
             .last()
             .expect("we have a unit");
-        let ReconstructionResult { units, requests } = reconstruction.add_unit(unit.clone());
+        let ReconstructionResult {
+            units, requests, ..
+        } = reconstruction.add_unit(unit.clone());
         assert!(units.is_empty());
         assert_eq!(requests.len(), 4);
     }
@@ -324,7 +438,9 @@ mod test {
             .expect("just created")
             .last()
             .expect("we have a unit");
-        let ReconstructionResult { units, requests } = reconstruction.add_unit(unit.clone());
+        let ReconstructionResult {
+            units, requests, ..
+        } = reconstruction.add_unit(unit.clone());
         assert!(units.is_empty());
         assert_eq!(requests.len(), 1);
         assert_eq!(
@@ -340,18 +456,23 @@ mod test {
         dag.reverse();
         for units in dag.iter().take(7) {
             for unit in units {
-                let ReconstructionResult { units, requests } =
-                    reconstruction.add_unit(unit.clone());
+                let ReconstructionResult {
+                    units, requests, ..
+                } = reconstruction.add_unit(unit.clone());
                 assert!(units.is_empty());
                 assert_eq!(requests.len(), 4);
             }
         }
         for unit in dag[7].iter().take(3) {
-            let ReconstructionResult { units, requests } = reconstruction.add_unit(unit.clone());
+            let ReconstructionResult {
+                units, requests, ..
+            } = reconstruction.add_unit(unit.clone());
             assert!(requests.is_empty());
             assert_eq!(units.len(), 1);
         }
-        let ReconstructionResult { units, requests } = reconstruction.add_unit(dag[7][3].clone());
+        let ReconstructionResult {
+            units, requests, ..
+        } = reconstruction.add_unit(dag[7][3].clone());
         assert!(requests.is_empty());
         assert_eq!(units.len(), 4 * 8 - 3);
     }
@@ -371,7 +492,9 @@ mod test {
             .last()
             .expect("we have a unit");
         let unit_hash = unit.hash();
-        let ReconstructionResult { units, requests } = reconstruction.add_unit(unit.clone());
+        let ReconstructionResult {
+            units, requests, ..
+        } = reconstruction.add_unit(unit.clone());
         assert!(units.is_empty());
         assert_eq!(requests.len(), 1);
         assert_eq!(
@@ -384,8 +507,11 @@ mod test {
             .iter()
             .map(|unit| (unit.coord(), unit.hash()))
             .collect();
-        let ReconstructionResult { units, requests } =
-            reconstruction.add_parents(unit_hash, parent_hashes.clone());
+        let ReconstructionResult {
+            units, requests, ..
+        } = reconstruction
+            .add_parents(unit_hash, parent_hashes.clone())
+            .expect("the parents are consistent");
         assert!(requests.is_empty());
         assert!(units.is_empty());
         let mut all_reconstructed = Vec::new();
@@ -393,6 +519,7 @@ mod test {
             let ReconstructionResult {
                 mut units,
                 requests,
+                ..
             } = reconstruction.add_unit(other_initial.clone());
             assert!(requests.is_empty());
             all_reconstructed.append(&mut units);
@@ -417,8 +544,9 @@ mod test {
         for units in &dag {
             for unit in units {
                 let round = unit.round();
-                let ReconstructionResult { units, requests } =
-                    reconstruction.add_unit(unit.clone());
+                let ReconstructionResult {
+                    units, requests, ..
+                } = reconstruction.add_unit(unit.clone());
                 assert!(requests.is_empty());
                 assert_eq!(units.len(), 1);
                 match round {
@@ -1,10 +1,21 @@
 use crate::{
-    dag::reconstruction::{ReconstructedUnit, ReconstructionResult, Request},
+    dag::reconstruction::{
+        EvictedUnit, EvictionCause, InconsistentParents, ReconstructedUnit, ReconstructionResult,
+        Request,
+    },
+    dissemination::RequestId,
     units::{ControlHash, HashFor, Unit, UnitCoord},
-    NodeIndex, NodeMap,
+    NodeIndex, NodeMap, ReconstructionLimits, DEFAULT_RECONSTRUCTION_LIMITS,
 };
 use aleph_bft_types::Round;
-use std::collections::{hash_map::Entry, HashMap};
+use std::{
+    collections::{hash_map::Entry, BTreeSet, HashMap, HashSet, VecDeque},
+    time::Instant,
+};
+
+/// How many evicted units we remember, so that they are only added again together with their
+/// parents. Forgotten ones are just treated like new units.
+const REMEMBERED_EVICTIONS: usize = 4096;
 
 /// A unit in the process of reconstructing its parents.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -67,6 +78,20 @@ impl<U: Unit> ReconstructingUnit<U> {
         self.as_unit().control_hash()
     }
 
+    /// The requests that have to be answered for the unit to get its parents.
+    fn awaited_requests(&self) -> Vec<RequestId<U::Hasher>> {
+        use ReconstructingUnit::*;
+        match self {
+            Reconstructing(unit, parents) => unit
+                .control_hash()
+                .parents()
+                .filter(|coord| parents.get(coord.creator()).is_none())
+                .map(RequestId::Coord)
+                .collect(),
+            WaitingForParents(unit) => vec![RequestId::Parents(unit.hash())],
+        }
+    }
+
     fn as_unit(&self) -> &U {
         use ReconstructingUnit::*;
         match self {
@@ -101,6 +126,12 @@ pub struct Reconstruction<U: Unit> {
     reconstructing_units: HashMap<HashFor<U>, ReconstructingUnit<U>>,
     units_by_coord: HashMap<UnitCoord, HashFor<U>>,
     waiting_for_coord: HashMap<UnitCoord, Vec<HashFor<U>>>,
+    limits: ReconstructionLimits,
+    pending_by_creator: HashMap<NodeIndex, BTreeSet<(Round, HashFor<U>)>>,
+    not_found_from: HashMap<RequestId<U::Hasher>, HashSet<NodeIndex>>,
+    suspect_since: HashMap<HashFor<U>, Instant>,
+    evicted: HashSet<HashFor<U>>,
+    eviction_order: VecDeque<HashFor<U>>,
 }
 
 impl<U: Unit> Reconstruction<U> {
@@ -110,7 +141,180 @@ impl<U: Unit> Reconstruction<U> {
             reconstructing_units: HashMap::new(),
             units_by_coord: HashMap::new(),
             waiting_for_coord: HashMap::new(),
+            limits: DEFAULT_RECONSTRUCTION_LIMITS,
+            pending_by_creator: HashMap::new(),
+            not_found_from: HashMap::new(),
+            suspect_since: HashMap::new(),
+            evicted: HashSet::new(),
+            eviction_order: VecDeque::new(),
+        }
+    }
+
+    /// Bounds the units waiting for parents, see [`ReconstructionLimits`].
+    pub fn with_limits(self, limits: ReconstructionLimits) -> Self {
+        Reconstruction { limits, ..self }
+    }
+
+    /// How many units of the creator wait for their parents.
+    pub fn pending(&self, creator: NodeIndex) -> usize {
+        self.pending_by_creator
+            .get(&creator)
+            .map_or(0, |pending| pending.len())
+    }
+
+    /// How many units wait for their parents.
+    pub fn pending_units(&self) -> usize {
+        self.reconstructing_units.len()
+    }
+
+    /// The control hash of the unit with the given hash, if it waits for its parents.
+    pub fn control_hash(&self, unit_hash: &HashFor<U>) -> Option<&ControlHash<U::Hasher>> {
+        self.reconstructing_units
+            .get(unit_hash)
+            .map(|unit| unit.control_hash())
+    }
+
+    /// Drops the accounting of a unit that stopped waiting for parents.
+    fn forget_pending(&mut self, unit_hash: HashFor<U>, coord: UnitCoord) {
+        if let Some(pending) = self.pending_by_creator.get_mut(&coord.creator()) {
+            pending.remove(&(coord.round(), unit_hash));
+        }
+        self.suspect_since.remove(&unit_hash);
+        self.not_found_from.remove(&RequestId::Parents(unit_hash));
+        self.evicted.remove(&unit_hash);
+    }
+
+    fn evict(
+        &mut self,
+        unit_hash: HashFor<U>,
+        cause: EvictionCause,
+    ) -> Option<EvictedUnit<U::Hasher>> {
+        let unit = self.reconstructing_units.remove(&unit_hash)?;
+        let awaited_coords: Vec<_> = unit
+            .awaited_requests()
+            .into_iter()
+            .filter_map(|request| match request {
+                RequestId::Coord(coord) => Some(coord),
+                RequestId::Parents(_) => None,
+            })
+            .collect();
+        for coord in &awaited_coords {
+            if let Entry::Occupied(mut children) = self.waiting_for_coord.entry(*coord) {
+                children.get_mut().retain(|child| *child != unit_hash);
+                if children.get().is_empty() {
+                    children.remove();
+                    self.not_found_from.remove(&RequestId::Coord(*coord));
+                }
+            }
+        }
+        let coord = unit.as_unit().coord();
+        self.forget_unit(unit_hash, coord);
+        self.forget_pending(unit_hash, coord);
+        if self.evicted.insert(unit_hash) {
+            self.eviction_order.push_back(unit_hash);
+            if self.eviction_order.len() > REMEMBERED_EVICTIONS {
+                if let Some(oldest) = self.eviction_order.pop_front() {
+                    self.evicted.remove(&oldest);
+                }
+            }
         }
+        Some(EvictedUnit {
+            hash: unit_hash,
+            coord,
+            cause,
+            awaited_coords,
+            pending: self.pending(coord.creator()),
+        })
+    }
+
+    /// Stops using the unit as a parent, as it will not be added after all.
+    pub fn forget_unit(&mut self, unit_hash: HashFor<U>, coord: UnitCoord) {
+        if self.units_by_coord.get(&coord) == Some(&unit_hash) {
+            self.units_by_coord.remove(&coord);
+        }
+    }
+
+    /// Evicts the unit of the highest round of the creator, if it has too many units waiting.
+    fn enforce_cap(&mut self, creator: NodeIndex) -> Option<EvictedUnit<U::Hasher>> {
+        let pending = self.pending_by_creator.get(&creator)?;
+        if pending.len() <= self.limits.max_pending_per_creator {
+            return None;
+        }
+        let (_, highest) = *pending.last()?;
+        self.evict(highest, EvictionCause::OverCap)
+    }
+
+    /// Whether the request is still needed by any of the units waiting for parents.
+    fn is_needed(&self, request: &Request<U::Hasher>) -> bool {
+        match request {
+            Request::Coord(coord) => self.is_waiting_for(*coord),
+            Request::ParentsOf(unit_hash) => self.reconstructing_units.contains_key(unit_hash),
+        }
+    }
+
+    /// Whether every node other than us and the creator of the unit denied having anything the
+    /// unit is waiting for.
+    fn is_unsatisfiable(&self, unit_hash: &HashFor<U>) -> bool {
+        let unit = match self.reconstructing_units.get(unit_hash) {
+            Some(unit) => unit,
+            None => return false,
+        };
+        let creator = unit.as_unit().creator();
+        let other_nodes = unit.control_hash().n_members().0.saturating_sub(2);
+        unit.awaited_requests().iter().all(|request| {
+            self.not_found_from.get(request).is_some_and(|peers| {
+                peers.iter().filter(|peer| **peer != creator).count() >= other_nodes
+            })
+        })
+    }
+
+    /// Notes that the peer definitively doesn't have what we requested. The units waiting for it
+    /// become suspect once nobody but their creators has anything they are waiting for.
+    pub fn on_not_found(&mut self, request: RequestId<U::Hasher>, peer: NodeIndex, now: Instant) {
+        let waiting: Vec<_> = match &request {
+            RequestId::Coord(coord) => self
+                .waiting_for_coord
+                .get(coord)
+                .into_iter()
+                .flatten()
+                .filter(|child| self.reconstructing_units.contains_key(child))
+                .copied()
+                .collect(),
+            RequestId::Parents(unit_hash) => match self.reconstructing_units.get(unit_hash) {
+                Some(ReconstructingUnit::WaitingForParents(_)) => vec![*unit_hash],
+                _ => Vec::new(),
+            },
+        };
+        if waiting.is_empty() {
+            return;
+        }
+        self.not_found_from.entry(request).or_default().insert(peer);
+        for unit_hash in waiting {
+            if self.is_unsatisfiable(&unit_hash) {
+                self.suspect_since.entry(unit_hash).or_insert(now);
+            }
+        }
+    }
+
+    /// Evicts the units that stayed suspect for at least the suspect timeout.
+    pub fn evict_unsatisfiable(&mut self, now: Instant) -> Vec<EvictedUnit<U::Hasher>> {
+        let timeout = self.limits.suspect_timeout;
+        let expired: Vec<_> = self
+            .suspect_since
+            .iter()
+            .filter(|(_, since)| now.saturating_duration_since(**since) >= timeout)
+            .map(|(unit_hash, _)| *unit_hash)
+            .collect();
+        let mut evicted = Vec::new();
+        for unit_hash in expired {
+            match self.is_unsatisfiable(&unit_hash) {
+                true => evicted.extend(self.evict(unit_hash, EvictionCause::Unsatisfiable)),
+                false => {
+                    self.suspect_since.remove(&unit_hash);
+                }
+            }
+        }
+        evicted
     }
 
     fn reconstruct_parent(
@@ -123,7 +327,10 @@ impl<U: Unit> Reconstruction<U> {
         use SingleParentReconstructionResult::*;
         match self.reconstructing_units.remove(&child_hash) {
             Some(child) => match child.reconstruct_parent(parent_id, parent_hash, parent_round) {
-                Reconstructed(unit) => ReconstructionResult::reconstructed(unit),
+                Reconstructed(unit) => {
+                    self.forget_pending(child_hash, unit.coord());
+                    ReconstructionResult::reconstructed(unit)
+                }
                 InProgress(unit) => {
                     self.reconstructing_units.insert(child_hash, unit);
                     ReconstructionResult::empty()
@@ -131,6 +338,8 @@ impl<U: Unit> Reconstruction<U> {
                 RequestParents(unit) => {
                     let hash = unit.as_unit().hash();
                     self.reconstructing_units.insert(child_hash, unit);
+                    // It waits for something new, which has to be denied first.
+                    self.suspect_since.remove(&child_hash);
                     ReconstructionResult::request(Request::ParentsOf(hash))
                 }
             },
@@ -140,7 +349,8 @@ impl<U: Unit> Reconstruction<U> {
         }
     }
 
-    /// Add a unit and start reconstructing its parents.
+    /// Add a unit and start reconstructing its parents. A unit that was evicted before is only
+    /// added if it can be reconstructed right away.
     pub fn add_unit(&mut self, unit: U) -> ReconstructionResult<U> {
         let mut result = ReconstructionResult::empty();
         let unit_hash = unit.hash();
@@ -149,6 +359,22 @@ impl<U: Unit> Reconstruction<U> {
             return result;
         }
         let unit_coord = UnitCoord::new(unit.round(), unit.creator());
+        let evicted_before = self.evicted.contains(&unit_hash);
+        if evicted_before
+            && unit
+                .control_hash()
+                .parents()
+                .any(|coord| !self.units_by_coord.contains_key(&coord))
+        {
+            return ReconstructionResult::evicted(vec![EvictedUnit {
+                hash: unit_hash,
+                coord: unit_coord,
+                cause: EvictionCause::EvictedBefore,
+                awaited_coords: Vec::new(),
+                pending: self.pending(unit_coord.creator()),
+            }]);
+        }
+        self.not_found_from.remove(&RequestId::Coord(unit_coord));
         // We place the unit in the coord map only if this is the first variant ever received.
         // This is not crucial for correctness, but helps in clarity.
         if let Entry::Vacant(entry) = self.units_by_coord.entry(unit_coord) {
@@ -191,43 +417,86 @@ impl<U: Unit> Reconstruction<U> {
                         }
                     }
                 }
+                if self.reconstructing_units.contains_key(&unit_hash) {
+                    let evicted = match evicted_before {
+                        true => self.evict(unit_hash, EvictionCause::EvictedBefore),
+                        false => {
+                            self.pending_by_creator
+                                .entry(unit_coord.creator())
+                                .or_default()
+                                .insert((unit_coord.round(), unit_hash));
+                            self.enforce_cap(unit_coord.creator())
+                        }
+                    };
+                    if let Some(evicted) = evicted {
+                        result.add_evicted(evicted);
+                        result.requests.retain(|request| self.is_needed(request));
+                    }
+                }
             }
         }
         result
     }
 
+    /// Whether some unit we are still reconstructing needs the unit at the given coord as a parent.
+    pub fn is_waiting_for(&self, coord: UnitCoord) -> bool {
+        self.waiting_for_coord.get(&coord).is_some_and(|children| {
+            children
+                .iter()
+                .any(|child| self.reconstructing_units.contains_key(child))
+        })
+    }
+
     /// Add an explicit list of a units' parents, perhaps reconstructing it.
+    /// Errors out if the parents do not match the control hash of the unit.
     pub fn add_parents(
         &mut self,
         unit_hash: HashFor<U>,
         parents: HashMap<UnitCoord, HashFor<U>>,
-    ) -> ReconstructionResult<U> {
+    ) -> Result<ReconstructionResult<U>, InconsistentParents> {
         // If we don't have the unit, just ignore this response.
         match self.reconstructing_units.remove(&unit_hash) {
             Some(unit) => match unit.with_parents(parents) {
-                Ok(unit) => ReconstructionResult::reconstructed(unit),
+                Ok(unit) => {
+                    self.forget_pending(unit_hash, unit.coord());
+                    Ok(ReconstructionResult::reconstructed(unit))
+                }
                 Err(unit) => {
                     self.reconstructing_units.insert(unit_hash, unit);
-                    ReconstructionResult::empty()
+                    Err(InconsistentParents)
                 }
             },
-            None => ReconstructionResult::empty(),
+            None => Ok(ReconstructionResult::empty()),
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use std::collections::HashMap;
+    use std::{
+        collections::HashMap,
+        time::{Duration, Instant},
+    };
 
     use crate::{
         dag::reconstruction::{
-            parents::Reconstruction, ReconstructedUnit, ReconstructionResult, Request,
+            parents::Reconstruction, EvictionCause, ReconstructedUnit, ReconstructionResult,
+            Request,
         },
+        dissemination::RequestId,
         units::{random_full_parent_units_up_to, Unit, UnitCoord, UnitWithParents},
-        NodeCount, NodeIndex,
+        NodeCount, NodeIndex, ReconstructionLimits,
     };
 
+    const SUSPECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn limits(max_pending_per_creator: usize) -> ReconstructionLimits {
+        ReconstructionLimits {
+            max_pending_per_creator,
+            suspect_timeout: SUSPECT_TIMEOUT,
+        }
+    }
+
     #[test]
     fn reconstructs_initial_units() {
         let mut reconstruction = Reconstruction::new();
@@ -235,6 +504,7 @@ mod test {
             let ReconstructionResult {
                 mut units,
                 requests,
+                ..
             } = reconstruction.add_unit(unit.clone());
             assert!(requests.is_empty());
             assert_eq!(units.len(), 1);
@@ -254,6 +524,7 @@ mod test {
                 let ReconstructionResult {
                     mut units,
                     requests,
+                    ..
                 } = reconstruction.add_unit(unit.clone());
                 assert!(requests.is_empty());
                 assert_eq!(units.len(), 1);
@@ -288,7 +559,9 @@ mod test {
             .expect("just created")
             .last()
             .expect("we have a unit");
-        let ReconstructionResult { units, requests } = reconstruction.add_unit(unit.clone());
+        let ReconstructionResult {
+            units, requests, ..
+        } = reconstruction.add_unit(unit.clone());
         assert!(units.is_empty());
         assert_eq!(requests.len(), 4);
     }
@@ -305,7 +578,9 @@ mod test {
             .expect("just created")
             .last()
             .expect("we have a unit");
-        let ReconstructionResult { units, requests } = reconstruction.add_unit(unit.clone());
+        let ReconstructionResult {
+            units, requests, ..
+        } = reconstruction.add_unit(unit.clone());
         assert!(units.is_empty());
         assert_eq!(requests.len(), 1);
         assert_eq!(
@@ -314,13 +589,38 @@ mod test {
         );
     }
 
+    #[test]
+    fn waits_only_for_missing_parents() {
+        let mut reconstruction = Reconstruction::new();
+        let dag = random_full_parent_units_up_to(1, NodeCount(4), 43);
+        let initial_units = dag.first().expect("just created");
+        for unit in initial_units.iter().skip(1) {
+            reconstruction.add_unit(unit.clone());
+        }
+        let missing = UnitCoord::new(0, NodeIndex(0));
+        assert!(!reconstruction.is_waiting_for(missing));
+        let unit = dag
+            .get(1)
+            .expect("just created")
+            .last()
+            .expect("we have a unit");
+        reconstruction.add_unit(unit.clone());
+        assert!(reconstruction.is_waiting_for(missing));
+        assert!(!reconstruction.is_waiting_for(UnitCoord::new(0, NodeIndex(1))));
+        let ReconstructionResult { units, .. } = reconstruction.add_unit(initial_units[0].clone());
+        assert_eq!(units.len(), 2);
+        assert!(!reconstruction.is_waiting_for(missing));
+    }
+
     #[test]
     fn reconstructs_units_coming_in_reverse_order() {
         let mut reconstruction = Reconstruction::new();
         let mut dag = random_full_parent_units_up_to(7, NodeCount(4), 43);
         dag.reverse();
         for unit in dag.first().expect("we have the top units") {
-            let ReconstructionResult { units, requests } = reconstruction.add_unit(unit.clone());
+            let ReconstructionResult {
+                units, requests, ..
+            } = reconstruction.add_unit(unit.clone());
             assert!(units.is_empty());
             assert_eq!(requests.len(), 4);
         }
@@ -328,12 +628,10 @@ mod test {
         for mut units in dag.into_iter().skip(1) {
             let last_unit = units.pop().expect("we have the unit");
             for unit in units {
-                let ReconstructionResult { units, requests: _ } =
-                    reconstruction.add_unit(unit.clone());
+                let ReconstructionResult { units, .. } = reconstruction.add_unit(unit.clone());
                 total_reconstructed += units.len();
             }
-            let ReconstructionResult { units, requests: _ } =
-                reconstruction.add_unit(last_unit.clone());
+            let ReconstructionResult { units, .. } = reconstruction.add_unit(last_unit.clone());
             total_reconstructed += units.len();
             assert!(units.len() >= 4);
         }
@@ -354,7 +652,9 @@ mod test {
             .last()
             .expect("we have a unit");
         let unit_hash = unit.hash();
-        let ReconstructionResult { units, requests } = reconstruction.add_unit(unit.clone());
+        let ReconstructionResult {
+            units, requests, ..
+        } = reconstruction.add_unit(unit.clone());
         assert!(units.is_empty());
         assert_eq!(requests.len(), 1);
         assert_eq!(
@@ -370,7 +670,10 @@ mod test {
         let ReconstructionResult {
             mut units,
             requests,
-        } = reconstruction.add_parents(unit_hash, parent_hashes.clone());
+            ..
+        } = reconstruction
+            .add_parents(unit_hash, parent_hashes.clone())
+            .expect("the parents are consistent");
         assert!(requests.is_empty());
         assert_eq!(units.len(), 1);
         let reconstructed_unit = units.pop().expect("just checked its there");
@@ -382,4 +685,95 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn evicts_units_nobody_has_parents_for() {
+        let mut reconstruction = Reconstruction::new().with_limits(limits(16));
+        let dag = random_full_parent_units_up_to(1, NodeCount(4), 43);
+        let unit = dag[1][3].clone();
+        reconstruction.add_unit(unit.clone());
+        let now = Instant::now();
+        for parent in &dag[0] {
+            reconstruction.on_not_found(RequestId::Coord(parent.coord()), NodeIndex(1), now);
+        }
+        // The creator could still have them, but anyone else could too.
+        assert!(reconstruction
+            .evict_unsatisfiable(now + 2 * SUSPECT_TIMEOUT)
+            .is_empty());
+        for parent in &dag[0] {
+            reconstruction.on_not_found(RequestId::Coord(parent.coord()), NodeIndex(2), now);
+        }
+        assert!(reconstruction
+            .evict_unsatisfiable(now + SUSPECT_TIMEOUT / 2)
+            .is_empty());
+        let evicted = reconstruction.evict_unsatisfiable(now + SUSPECT_TIMEOUT);
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].hash, unit.hash());
+        assert_eq!(evicted[0].cause, EvictionCause::Unsatisfiable);
+        assert_eq!(evicted[0].awaited_coords.len(), 4);
+        assert_eq!(reconstruction.pending_units(), 0);
+        for parent in &dag[0] {
+            assert!(!reconstruction.is_waiting_for(parent.coord()));
+        }
+
+        let ReconstructionResult {
+            units,
+            requests,
+            evicted,
+        } = reconstruction.add_unit(unit.clone());
+        assert!(units.is_empty());
+        assert!(requests.is_empty());
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].cause, EvictionCause::EvictedBefore);
+        for parent in &dag[0] {
+            reconstruction.add_unit(parent.clone());
+        }
+        let ReconstructionResult { units, .. } = reconstruction.add_unit(unit);
+        assert_eq!(units.len(), 1);
+    }
+
+    #[test]
+    fn keeps_suspects_whose_parents_arrive() {
+        let mut reconstruction = Reconstruction::new().with_limits(limits(16));
+        let dag = random_full_parent_units_up_to(1, NodeCount(4), 43);
+        reconstruction.add_unit(dag[1][3].clone());
+        let now = Instant::now();
+        for peer in [NodeIndex(1), NodeIndex(2)] {
+            for parent in &dag[0] {
+                reconstruction.on_not_found(RequestId::Coord(parent.coord()), peer, now);
+            }
+        }
+        let mut reconstructed = 0;
+        for parent in &dag[0] {
+            let ReconstructionResult { units, .. } = reconstruction.add_unit(parent.clone());
+            reconstructed += units.len();
+        }
+        assert_eq!(reconstructed, 5);
+        assert!(reconstruction
+            .evict_unsatisfiable(now + SUSPECT_TIMEOUT)
+            .is_empty());
+    }
+
+    #[test]
+    fn bounds_pending_units_per_creator() {
+        let mut reconstruction = Reconstruction::new().with_limits(limits(2));
+        let dag = random_full_parent_units_up_to(3, NodeCount(4), 43);
+        for units in &dag[1..=2] {
+            let ReconstructionResult { evicted, .. } = reconstruction.add_unit(units[0].clone());
+            assert!(evicted.is_empty());
+        }
+        let ReconstructionResult {
+            requests, evicted, ..
+        } = reconstruction.add_unit(dag[3][0].clone());
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].hash, dag[3][0].hash());
+        assert_eq!(evicted[0].cause, EvictionCause::OverCap);
+        assert_eq!(evicted[0].pending, 2);
+        // Nothing else waits for the other units of round 2, so they are not requested.
+        assert!(requests.is_empty());
+        assert_eq!(reconstruction.pending(NodeIndex(0)), 2);
+        let ReconstructionResult { evicted, .. } = reconstruction.add_unit(dag[3][1].clone());
+        assert!(evicted.is_empty());
+        assert_eq!(reconstruction.pending(NodeIndex(1)), 1);
+    }
 }
@@ -0,0 +1,296 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::{Display, Formatter, Result as FmtResult},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    units::{UncheckedSignedUnit, UnitCoord, ValidationError},
+    ClockSource, Data, Hasher, Round, Signature,
+};
+use codec::Encode;
+
+/// How many rejected units are remembered at most, the oldest ones are forgotten first.
+const REJECTIONS_KEPT: usize = 1000;
+
+/// How many parents responses inconsistent with the control hash are remembered at most, the
+/// oldest ones are forgotten first.
+const INCONSISTENT_RESPONSES_KEPT: usize = 1000;
+
+/// How long a rejected unit is remembered. Copies of a unit arrive within a few rebroadcast
+/// intervals, later ones are validated again.
+pub const REJECTION_TTL: Duration = Duration::from_secs(60);
+
+/// Why a unit failed validation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RejectionReason {
+    WrongSignature,
+    WrongSession,
+    RoundTooHigh,
+    MetadataTooLarge,
+    DataTooLarge,
+    WrongNumberOfMembers,
+    ParentValidationFailed,
+}
+
+impl RejectionReason {
+    /// Whether the unit was correctly signed, so its creator is responsible for it, rather than
+    /// whoever sent it to us.
+    pub fn is_attributable(&self) -> bool {
+        *self != RejectionReason::WrongSignature
+    }
+}
+
+impl<H: Hasher, D: Data, S: Signature> From<&ValidationError<H, D, S>> for RejectionReason {
+    fn from(error: &ValidationError<H, D, S>) -> Self {
+        use ValidationError::*;
+        match error {
+            WrongSignature(_) => RejectionReason::WrongSignature,
+            WrongSession(_) => RejectionReason::WrongSession,
+            RoundTooHigh(_) => RejectionReason::RoundTooHigh,
+            MetadataTooLarge(_) => RejectionReason::MetadataTooLarge,
+            DataTooLarge(_) => RejectionReason::DataTooLarge,
+            WrongNumberOfMembers(_) => RejectionReason::WrongNumberOfMembers,
+            ParentValidationFailed(_, _) => RejectionReason::ParentValidationFailed,
+        }
+    }
+}
+
+impl Display for RejectionReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let reason = match self {
+            RejectionReason::WrongSignature => "wrong signature",
+            RejectionReason::WrongSession => "wrong session",
+            RejectionReason::RoundTooHigh => "round too high",
+            RejectionReason::MetadataTooLarge => "metadata too large",
+            RejectionReason::DataTooLarge => "data too large",
+            RejectionReason::WrongNumberOfMembers => "wrong number of members",
+            RejectionReason::ParentValidationFailed => "parent validation failed",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+struct Rejection {
+    reason: RejectionReason,
+    round: Round,
+    rejected_at: Instant,
+}
+
+/// The units that recently failed validation, so that copies of them arriving again are dropped
+/// without repeating the validation.
+///
+/// The units are identified by the hash of their whole encoding, signature included. The hash of
+/// a unit does not cover its signature, so identifying them by it would let anyone block a
+/// correct unit by sending it first with a broken signature.
+pub struct RejectionCache<H: Hasher> {
+    rejections: HashMap<H::Hash, Rejection>,
+    order: VecDeque<H::Hash>,
+    suppressed: usize,
+    clock: ClockSource,
+}
+
+impl<H: Hasher> RejectionCache<H> {
+    pub fn new(clock: ClockSource) -> Self {
+        RejectionCache {
+            rejections: HashMap::new(),
+            order: VecDeque::new(),
+            suppressed: 0,
+            clock,
+        }
+    }
+
+    /// The identifier of the unit in the cache.
+    pub fn key<D: Data, S: Signature>(unit: &UncheckedSignedUnit<H, D, S>) -> H::Hash {
+        unit.using_encoded(H::hash)
+    }
+
+    /// Forgets the rejections that are too old. They are ordered by time, as that is how they
+    /// get inserted.
+    fn expire(&mut self) {
+        let now = self.clock.now();
+        while let Some(key) = self.order.front() {
+            match self.rejections.get(key) {
+                Some(rejection)
+                    if now.saturating_duration_since(rejection.rejected_at) < REJECTION_TTL =>
+                {
+                    break
+                }
+                _ => {
+                    let key = self.order.pop_front().expect("just checked it is there");
+                    self.rejections.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// The reason the unit with the given key was rejected, if it was rejected recently. Counts
+    /// the unit as suppressed if so.
+    pub fn check(&mut self, key: &H::Hash) -> Option<RejectionReason> {
+        self.expire();
+        let reason = self.rejections.get(key).map(|rejection| rejection.reason)?;
+        self.suppressed += 1;
+        Some(reason)
+    }
+
+    /// Remembers that the unit with the given key and claimed round was rejected.
+    pub fn insert(&mut self, key: H::Hash, reason: RejectionReason, round: Round) {
+        self.expire();
+        if self.order.len() == REJECTIONS_KEPT {
+            if let Some(oldest) = self.order.pop_front() {
+                self.rejections.remove(&oldest);
+            }
+        }
+        self.rejections.insert(
+            key,
+            Rejection {
+                reason,
+                round,
+                rejected_at: self.clock.now(),
+            },
+        );
+        self.order.push_back(key);
+    }
+
+    /// Forgets the rejections of units of rounds that got finalized, copies of them cannot
+    /// matter anymore, and validating them again is not any more expensive than receiving them.
+    pub fn prune_up_to(&mut self, round: Round) {
+        self.rejections
+            .retain(|_, rejection| rejection.round > round);
+        let rejections = &self.rejections;
+        self.order.retain(|key| rejections.contains_key(key));
+    }
+
+    /// How many units are remembered as rejected.
+    pub fn len(&self) -> usize {
+        self.rejections.len()
+    }
+
+    /// How many copies of rejected units were dropped without validation.
+    pub fn suppressed(&self) -> usize {
+        self.suppressed
+    }
+}
+
+/// The parents responses that were recently found inconsistent with the control hash of the unit
+/// they claim to be the parents of, so that copies of them are dropped without causing more
+/// requests or reports.
+///
+/// The responses are identified by the hash of the unit together with the coords and hashes of
+/// the parents, so a different response about the same unit is still checked.
+pub struct InconsistentResponses<H: Hasher> {
+    responses: HashSet<H::Hash>,
+    order: VecDeque<H::Hash>,
+}
+
+impl<H: Hasher> Default for InconsistentResponses<H> {
+    fn default() -> Self {
+        InconsistentResponses {
+            responses: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+}
+
+impl<H: Hasher> InconsistentResponses<H> {
+    /// The identifier of the response in the cache.
+    pub fn key(unit_hash: &H::Hash, parents: &[(UnitCoord, H::Hash)]) -> H::Hash {
+        (unit_hash, parents).using_encoded(H::hash)
+    }
+
+    /// Remembers the response with the given key, returns whether it was not remembered yet.
+    pub fn insert(&mut self, key: H::Hash) -> bool {
+        if !self.responses.insert(key) {
+            return false;
+        }
+        if self.order.len() == INCONSISTENT_RESPONSES_KEPT {
+            if let Some(oldest) = self.order.pop_front() {
+                self.responses.remove(&oldest);
+            }
+        }
+        self.order.push_back(key);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dag::rejections::{
+            InconsistentResponses, RejectionCache, RejectionReason, INCONSISTENT_RESPONSES_KEPT,
+            REJECTIONS_KEPT, REJECTION_TTL,
+        },
+        units::UnitCoord,
+        ClockSource, NodeIndex,
+    };
+    use aleph_bft_mock::{Hasher64, TokioClock};
+    use std::time::Duration;
+
+    fn cache() -> RejectionCache<Hasher64> {
+        RejectionCache::new(ClockSource::new(TokioClock))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn remembers_rejections_for_a_while() {
+        let mut cache = cache();
+        cache.insert([1; 8], RejectionReason::WrongSignature, 3);
+        assert_eq!(cache.check(&[2; 8]), None);
+        tokio::time::advance(REJECTION_TTL - Duration::from_millis(1)).await;
+        assert_eq!(cache.check(&[1; 8]), Some(RejectionReason::WrongSignature));
+        assert_eq!(cache.suppressed(), 1);
+        tokio::time::advance(Duration::from_millis(1)).await;
+        assert_eq!(cache.check(&[1; 8]), None);
+        assert_eq!(cache.len(), 0);
+        assert_eq!(cache.suppressed(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn forgets_finalized_rounds() {
+        let mut cache = cache();
+        cache.insert([1; 8], RejectionReason::RoundTooHigh, 3);
+        cache.insert([2; 8], RejectionReason::WrongSession, 4);
+        cache.prune_up_to(3);
+        assert_eq!(cache.check(&[1; 8]), None);
+        assert_eq!(cache.check(&[2; 8]), Some(RejectionReason::WrongSession));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn keeps_only_the_latest_rejections() {
+        let mut cache = cache();
+        for i in 0..(REJECTIONS_KEPT as u64 + 1) {
+            cache.insert(i.to_le_bytes(), RejectionReason::WrongSignature, 0);
+        }
+        assert_eq!(cache.len(), REJECTIONS_KEPT);
+        assert_eq!(cache.check(&0u64.to_le_bytes()), None);
+        assert_eq!(
+            cache.check(&1u64.to_le_bytes()),
+            Some(RejectionReason::WrongSignature)
+        );
+    }
+
+    #[test]
+    fn tells_inconsistent_responses_apart_by_their_parents() {
+        let mut responses = InconsistentResponses::<Hasher64>::default();
+        let parents = vec![(UnitCoord::new(1, NodeIndex(0)), [1; 8])];
+        let other_parents = vec![(UnitCoord::new(1, NodeIndex(0)), [2; 8])];
+        let key = InconsistentResponses::<Hasher64>::key(&[0; 8], &parents);
+        assert!(responses.insert(key));
+        assert!(!responses.insert(key));
+        assert!(responses.insert(InconsistentResponses::<Hasher64>::key(
+            &[0; 8],
+            &other_parents
+        )));
+        assert!(responses.insert(InconsistentResponses::<Hasher64>::key(&[3; 8], &parents)));
+    }
+
+    #[test]
+    fn keeps_only_the_latest_inconsistent_responses() {
+        let mut responses = InconsistentResponses::<Hasher64>::default();
+        for i in 0..(INCONSISTENT_RESPONSES_KEPT as u64 + 1) {
+            assert!(responses.insert(i.to_le_bytes()));
+        }
+        assert!(responses.insert(0u64.to_le_bytes()));
+        assert!(!responses.insert(2u64.to_le_bytes()));
+    }
+}
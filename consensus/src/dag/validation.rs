@@ -1,21 +1,30 @@
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
 
 use crate::{
+    admission::{mark_stage, AdmissionStage, AdmissionTrace},
     alerts::Alert,
+    dag::rejections::{RejectionCache, RejectionReason},
     units::{
-        SignedUnit, UncheckedSignedUnit, Unit, UnitStore, UnitStoreStatus, ValidationError,
-        Validator as UnitValidator, WrappedUnit,
+        SessionVerifiedUnit, SignatureVerifiedUnit, SignedUnit, UncheckedSignedUnit, Unit,
+        UnitCoord, UnitStore, UnitStoreStatus, ValidationError, Validator as UnitValidator,
+        WrappedUnit,
     },
-    Data, Hasher, MultiKeychain, NodeIndex, NodeSubset, Round,
+    ClockSource, Data, Hasher, Keychain, MultiKeychain, NodeIndex, NodeSubset, Round,
 };
 
 /// What can go wrong when validating a unit.
 #[derive(Eq, PartialEq)]
 pub enum Error<H: Hasher, D: Data, MK: MultiKeychain> {
     Invalid(ValidationError<H, D, MK::Signature>),
+    /// The same unit failed validation recently for the given reason, so it was not validated
+    /// again.
+    KnownInvalid(RejectionReason),
     Duplicate(SignedUnit<H, D, MK>),
     Uncommitted(SignedUnit<H, D, MK>),
     NewForker(Box<Alert<H, D, MK::Signature>>),
+    /// A unit of a round pruned from the store, other than the one we had there, if any. It
+    /// might be a fork, but we cannot prove it anymore.
+    Pruned(SignedUnit<H, D, MK>),
 }
 
 impl<H: Hasher, D: Data, MK: MultiKeychain> Debug for Error<H, D, MK> {
@@ -23,9 +32,11 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Debug for Error<H, D, MK> {
         use Error::*;
         match self {
             Invalid(e) => write!(f, "Invalid({:?})", e),
+            KnownInvalid(reason) => write!(f, "KnownInvalid({:?})", reason),
             Duplicate(u) => write!(f, "Duplicate({:?})", u.clone().into_unchecked()),
             Uncommitted(u) => write!(f, "Uncommitted({:?})", u.clone().into_unchecked()),
             NewForker(a) => write!(f, "NewForker({:?})", a),
+            Pruned(u) => write!(f, "Pruned({:?})", u.clone().into_unchecked()),
         }
     }
 }
@@ -42,6 +53,8 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> From<ValidationError<H, D, MK::Signa
 pub struct ValidatorStatus {
     processing_units: UnitStoreStatus,
     known_forkers: NodeSubset,
+    rejected_units: usize,
+    suppressed_units: usize,
 }
 
 impl ValidatorStatus {
@@ -55,19 +68,79 @@ impl Display for ValidatorStatus {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(
             f,
-            "processing units: ({}), forkers: {}",
-            self.processing_units, self.known_forkers
+            "processing units: ({}), forkers: {}, recently rejected units: {}, suppressed copies of rejected units: {}",
+            self.processing_units, self.known_forkers, self.rejected_units, self.suppressed_units
         )
     }
 }
 
+/// An incoming unit that is not known to be invalid, so its signature has to be checked.
+pub struct UnverifiedUnit<H: Hasher, D: Data, MK: MultiKeychain> {
+    key: H::Hash,
+    unit: UncheckedSignedUnit<H, D, MK::Signature>,
+}
+
+impl<H: Hasher, D: Data, MK: MultiKeychain> UnverifiedUnit<H, D, MK> {
+    pub fn creator(&self) -> NodeIndex {
+        self.unit.as_signable().creator()
+    }
+
+    /// Checks the signature of the unit, which does not need the validator, so it can happen
+    /// in another task. Marks the end of the check in the trace if the signature is correct.
+    pub fn verify(
+        self,
+        unit_validator: &UnitValidator<MK>,
+        trace: &mut Option<AdmissionTrace>,
+    ) -> VerifiedUnit<H, D, MK> {
+        let UnverifiedUnit { key, unit } = self;
+        let creator = unit.as_signable().creator();
+        let round = unit.as_signable().round();
+        let hash = unit.as_signable().hash();
+        let result = unit_validator.verify_signature(unit);
+        if result.is_ok() {
+            mark_stage(trace, AdmissionStage::Verification);
+        }
+        VerifiedUnit {
+            key,
+            creator,
+            round,
+            hash,
+            result,
+        }
+    }
+}
+
+/// An incoming unit after the check of its signature, the rest of the validation is up to
+/// the validator.
+pub struct VerifiedUnit<H: Hasher, D: Data, MK: MultiKeychain> {
+    key: H::Hash,
+    creator: NodeIndex,
+    round: Round,
+    hash: H::Hash,
+    result: VerificationResult<H, D, MK>,
+}
+
+impl<H: Hasher, D: Data, MK: MultiKeychain> VerifiedUnit<H, D, MK> {
+    pub fn creator(&self) -> NodeIndex {
+        self.creator
+    }
+
+    pub fn hash(&self) -> H::Hash {
+        self.hash
+    }
+}
+
+type VerificationResult<H, D, MK> =
+    Result<SignatureVerifiedUnit<H, D, MK>, ValidationError<H, D, <MK as Keychain>::Signature>>;
 type ValidatorResult<H, D, MK> = Result<SignedUnit<H, D, MK>, Error<H, D, MK>>;
+type PreValidatorResult<H, D, MK> = Result<SessionVerifiedUnit<H, D, MK>, Error<H, D, MK>>;
 
 /// A validator that checks basic properties of units and catches forks.
 pub struct Validator<H: Hasher, D: Data, MK: MultiKeychain> {
     unit_validator: UnitValidator<MK>,
     processing_units: UnitStore<SignedUnit<H, D, MK>>,
     known_forkers: NodeSubset,
+    rejections: RejectionCache<H>,
 }
 
 impl<H: Hasher, D: Data, MK: MultiKeychain> Validator<H, D, MK> {
@@ -78,6 +151,15 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Validator<H, D, MK> {
             unit_validator,
             processing_units: UnitStore::new(node_count),
             known_forkers: NodeSubset::with_size(node_count),
+            rejections: RejectionCache::new(ClockSource::default()),
+        }
+    }
+
+    /// Uses the given clock to expire the remembered rejections.
+    pub fn with_clock(self, clock: ClockSource) -> Self {
+        Validator {
+            rejections: RejectionCache::new(clock),
+            ..self
         }
     }
 
@@ -109,11 +191,27 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Validator<H, D, MK> {
         &mut self,
         unit: UncheckedSignedUnit<H, D, MK::Signature>,
         store: &UnitStore<U>,
-    ) -> ValidatorResult<H, D, MK> {
-        let unit = self.unit_validator.validate_unit(unit)?;
+    ) -> PreValidatorResult<H, D, MK> {
+        let unit = self.unit_validator.verify_signature(unit)?;
+        self.pre_validate_signed(unit, store)
+    }
+
+    fn pre_validate_signed<U: WrappedUnit<H, Wrapped = SignedUnit<H, D, MK>>>(
+        &mut self,
+        unit: SignatureVerifiedUnit<H, D, MK>,
+        store: &UnitStore<U>,
+    ) -> PreValidatorResult<H, D, MK> {
+        let unit = self.unit_validator.verify_session(unit)?;
         let unit_hash = unit.as_signable().hash();
         if store.unit(&unit_hash).is_some() || self.processing_units.unit(&unit_hash).is_some() {
-            return Err(Error::Duplicate(unit));
+            return Err(Error::Duplicate(unit.into_signed()));
+        }
+        let unit_coord = unit.as_signable().coord();
+        if store.is_pruned(unit_coord) {
+            return Err(match store.canonical_hash(unit_coord) == Some(&unit_hash) {
+                true => Error::Duplicate(unit.into_signed()),
+                false => Error::Pruned(unit.into_signed()),
+            });
         }
         Ok(unit)
     }
@@ -123,9 +221,64 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Validator<H, D, MK> {
         &mut self,
         unit: UncheckedSignedUnit<H, D, MK::Signature>,
         store: &UnitStore<U>,
+    ) -> ValidatorResult<H, D, MK> {
+        self.validate_traced(unit, store, &mut None)
+    }
+
+    /// Validate an incoming unit, marking the ends of the verification of its signature and of
+    /// the rest of the validation in the trace.
+    pub(crate) fn validate_traced<U: WrappedUnit<H, Wrapped = SignedUnit<H, D, MK>>>(
+        &mut self,
+        unit: UncheckedSignedUnit<H, D, MK::Signature>,
+        store: &UnitStore<U>,
+        trace: &mut Option<AdmissionTrace>,
+    ) -> ValidatorResult<H, D, MK> {
+        let unit = self.admit(unit)?.verify(&self.unit_validator, trace);
+        self.validate_verified(unit, store, trace)
+    }
+
+    /// The first part of [`Self::validate_traced`], refusing units that failed validation
+    /// recently, so that their signatures are not checked again.
+    pub(crate) fn admit(
+        &mut self,
+        unit: UncheckedSignedUnit<H, D, MK::Signature>,
+    ) -> Result<UnverifiedUnit<H, D, MK>, Error<H, D, MK>> {
+        let key = RejectionCache::<H>::key(&unit);
+        if let Some(reason) = self.rejections.check(&key) {
+            return Err(Error::KnownInvalid(reason));
+        }
+        Ok(UnverifiedUnit { key, unit })
+    }
+
+    /// The rest of [`Self::validate_traced`], for units the signatures of which were checked,
+    /// possibly in another task.
+    pub(crate) fn validate_verified<U: WrappedUnit<H, Wrapped = SignedUnit<H, D, MK>>>(
+        &mut self,
+        unit: VerifiedUnit<H, D, MK>,
+        store: &UnitStore<U>,
+        trace: &mut Option<AdmissionTrace>,
+    ) -> ValidatorResult<H, D, MK> {
+        let VerifiedUnit {
+            key, round, result, ..
+        } = unit;
+        let result = result.map_err(Error::from).and_then(|unit| {
+            let result = self.validate_signed(unit, store);
+            mark_stage(trace, AdmissionStage::Validation);
+            result
+        });
+        if let Err(Error::Invalid(e)) = &result {
+            self.rejections.insert(key, e.into(), round);
+        }
+        result
+    }
+
+    fn validate_signed<U: WrappedUnit<H, Wrapped = SignedUnit<H, D, MK>>>(
+        &mut self,
+        unit: SignatureVerifiedUnit<H, D, MK>,
+        store: &UnitStore<U>,
     ) -> ValidatorResult<H, D, MK> {
         use Error::*;
-        let unit = self.pre_validate(unit, store)?;
+        let unit = self.pre_validate_signed(unit, store)?.into_signed();
         let unit_coord = unit.as_signable().coord();
         if self.is_forker(unit_coord.creator()) {
             return Err(Uncommitted(unit));
@@ -153,7 +306,7 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Validator<H, D, MK> {
         unit: UncheckedSignedUnit<H, D, MK::Signature>,
         store: &UnitStore<U>,
     ) -> ValidatorResult<H, D, MK> {
-        let unit = self.pre_validate(unit, store)?;
+        let unit = self.pre_validate(unit, store)?.into_signed();
         assert!(
             self.is_forker(unit.creator()),
             "We should only receive committed units for known forkers."
@@ -162,17 +315,39 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Validator<H, D, MK> {
         Ok(unit)
     }
 
+    /// The validator of single units, all that is needed to check the signatures of units.
+    pub fn unit_validator(&self) -> &UnitValidator<MK> {
+        &self.unit_validator
+    }
+
     /// Signal that a unit finished processing and thus it's copy no longer has to be kept for fork detection.
     /// NOTE: This is only a memory optimization, if the units stay there forever everything still works.
     pub fn finished_processing(&mut self, unit: &H::Hash) {
         self.processing_units.remove(unit)
     }
 
+    /// Whether the unit with the given hash passed validation, but did not finish processing yet.
+    pub fn is_processing(&self, unit: &H::Hash) -> bool {
+        self.processing_units.unit(unit).is_some()
+    }
+
+    /// Whether some unit at the given coord passed validation, but did not finish processing yet.
+    pub fn is_processing_coord(&self, coord: UnitCoord) -> bool {
+        self.processing_units.canonical_unit(coord).is_some()
+    }
+
+    /// Forgets the rejected units of rounds up to the given one, as it got finalized.
+    pub fn prune_rejections_up_to(&mut self, round: Round) {
+        self.rejections.prune_up_to(round)
+    }
+
     /// The status summary of this validator.
     pub fn status(&self) -> ValidatorStatus {
         ValidatorStatus {
             processing_units: self.processing_units.status(),
             known_forkers: self.known_forkers.clone(),
+            rejected_units: self.rejections.len(),
+            suppressed_units: self.rejections.suppressed(),
         }
     }
 }
@@ -199,11 +374,18 @@ mod test {
             .map(|node_id| Keychain::new(node_count, node_id))
             .collect();
         let store = UnitStore::<WrappedSignedUnit>::new(node_count);
-        let mut validator = Validator::new(UnitValidator::new(session_id, keychains[0], max_round));
+        let mut validator = Validator::new(UnitValidator::new(
+            session_id,
+            keychains[0].clone(),
+            max_round,
+        ));
         for unit in random_full_parent_units_up_to(4, node_count, session_id)
             .iter()
             .flatten()
-            .map(|unit| Signed::sign(unit.clone(), &keychains[unit.creator().0]))
+            .map(|unit| {
+                Signed::sign(unit.clone(), &keychains[unit.creator().0])
+                    .expect("the keychain never fails")
+            })
         {
             assert_eq!(
                 validator.validate(unit.clone().into(), &store),
@@ -222,14 +404,18 @@ mod test {
             .map(|node_id| Keychain::new(node_count, node_id))
             .collect();
         let store = UnitStore::<WrappedSignedUnit>::new(node_count);
-        let mut validator = Validator::new(UnitValidator::new(session_id, keychains[0], max_round));
+        let mut validator = Validator::new(UnitValidator::new(
+            session_id,
+            keychains[0].clone(),
+            max_round,
+        ));
         let unit = random_full_parent_units_up_to(0, node_count, session_id)
             .first()
             .expect("we have the first round")
             .first()
             .expect("we have the initial unit for the zeroth creator")
             .clone();
-        let unit = Signed::sign(unit, &keychains[0]);
+        let unit = Signed::sign(unit, &keychains[0]).expect("the keychain never fails");
         assert_eq!(
             validator.validate(unit.clone().into(), &store),
             Ok(unit.clone())
@@ -250,14 +436,18 @@ mod test {
             .map(|node_id| Keychain::new(node_count, node_id))
             .collect();
         let mut store = UnitStore::new(node_count);
-        let mut validator = Validator::new(UnitValidator::new(session_id, keychains[0], max_round));
+        let mut validator = Validator::new(UnitValidator::new(
+            session_id,
+            keychains[0].clone(),
+            max_round,
+        ));
         let unit = random_full_parent_units_up_to(0, node_count, session_id)
             .first()
             .expect("we have the first round")
             .first()
             .expect("we have the initial unit for the zeroth creator")
             .clone();
-        let unit = Signed::sign(unit, &keychains[0]);
+        let unit = Signed::sign(unit, &keychains[0]).expect("the keychain never fails");
         store.insert(WrappedSignedUnit(unit.clone()));
         assert_eq!(
             validator.validate(unit.clone().into(), &store),
@@ -265,6 +455,52 @@ mod test {
         );
     }
 
+    #[test]
+    fn ignores_units_of_pruned_rounds() {
+        let node_count = NodeCount(7);
+        let session_id = 0;
+        let max_round = 2137;
+        let keychains: Vec<_> = node_count
+            .into_iterator()
+            .map(|node_id| Keychain::new(node_count, node_id))
+            .collect();
+        let mut store = UnitStore::new(node_count);
+        let mut validator = Validator::new(UnitValidator::new(
+            session_id,
+            keychains[0].clone(),
+            max_round,
+        ));
+        let units: Vec<_> = random_full_parent_units_up_to(3, node_count, session_id)
+            .iter()
+            .flatten()
+            .map(|unit| {
+                Signed::sign(unit.clone(), &keychains[unit.creator().0])
+                    .expect("the keychain never fails")
+            })
+            .collect();
+        for unit in &units {
+            store.insert(WrappedSignedUnit(unit.clone()));
+        }
+        store.prune_below(2);
+        let unit = units.first().expect("we have the initial unit").clone();
+        assert_eq!(
+            validator.validate(unit.clone().into(), &store),
+            Err(Error::Duplicate(unit))
+        );
+        let fork = random_full_parent_units_up_to(1, node_count, session_id)
+            .get(1)
+            .expect("we have the requested round")
+            .first()
+            .expect("we have the unit for the zeroth creator")
+            .clone();
+        let fork = Signed::sign(fork, &keychains[0]).expect("the keychain never fails");
+        // We cannot prove the fork without the pruned unit.
+        assert_eq!(
+            validator.validate(fork.clone().into(), &store),
+            Err(Error::Pruned(fork))
+        );
+    }
+
     #[test]
     fn detects_processing_fork() {
         let node_count = NodeCount(7);
@@ -276,11 +512,18 @@ mod test {
             .map(|node_id| Keychain::new(node_count, node_id))
             .collect();
         let store = UnitStore::<WrappedSignedUnit>::new(node_count);
-        let mut validator = Validator::new(UnitValidator::new(session_id, keychains[0], max_round));
+        let mut validator = Validator::new(UnitValidator::new(
+            session_id,
+            keychains[0].clone(),
+            max_round,
+        ));
         for unit in random_full_parent_units_up_to(produced_round, node_count, session_id)
             .iter()
             .flatten()
-            .map(|unit| Signed::sign(unit.clone(), &keychains[unit.creator().0]))
+            .map(|unit| {
+                Signed::sign(unit.clone(), &keychains[unit.creator().0])
+                    .expect("the keychain never fails")
+            })
         {
             assert_eq!(
                 validator.validate(unit.clone().into(), &store),
@@ -293,7 +536,7 @@ mod test {
             .first()
             .expect("we have the unit for the zeroth creator")
             .clone();
-        let fork = Signed::sign(fork, &keychains[0]);
+        let fork = Signed::sign(fork, &keychains[0]).expect("the keychain never fails");
         assert!(matches!(
             validator.validate(fork.clone().into(), &store),
             Err(Error::NewForker(_))
@@ -311,11 +554,18 @@ mod test {
             .map(|node_id| Keychain::new(node_count, node_id))
             .collect();
         let mut store = UnitStore::new(node_count);
-        let mut validator = Validator::new(UnitValidator::new(session_id, keychains[0], max_round));
+        let mut validator = Validator::new(UnitValidator::new(
+            session_id,
+            keychains[0].clone(),
+            max_round,
+        ));
         for unit in random_full_parent_units_up_to(produced_round, node_count, session_id)
             .iter()
             .flatten()
-            .map(|unit| Signed::sign(unit.clone(), &keychains[unit.creator().0]))
+            .map(|unit| {
+                Signed::sign(unit.clone(), &keychains[unit.creator().0])
+                    .expect("the keychain never fails")
+            })
         {
             store.insert(WrappedSignedUnit(unit));
         }
@@ -325,7 +575,7 @@ mod test {
             .first()
             .expect("we have the unit for the zeroth creator")
             .clone();
-        let fork = Signed::sign(fork, &keychains[0]);
+        let fork = Signed::sign(fork, &keychains[0]).expect("the keychain never fails");
         assert!(matches!(
             validator.validate(fork.clone().into(), &store),
             Err(Error::NewForker(_))
@@ -343,19 +593,26 @@ mod test {
             .map(|node_id| Keychain::new(node_count, node_id))
             .collect();
         let store = UnitStore::<WrappedSignedUnit>::new(node_count);
-        let mut validator = Validator::new(UnitValidator::new(session_id, keychains[0], max_round));
+        let mut validator = Validator::new(UnitValidator::new(
+            session_id,
+            keychains[0].clone(),
+            max_round,
+        ));
         let fork = random_full_parent_units_up_to(2, node_count, session_id)
             .get(2)
             .expect("we have the requested round")
             .first()
             .expect("we have the unit for the zeroth creator")
             .clone();
-        let fork = Signed::sign(fork, &keychains[0]);
+        let fork = Signed::sign(fork, &keychains[0]).expect("the keychain never fails");
         for unit in random_full_parent_units_up_to(produced_round, node_count, session_id)
             .iter()
             .flatten()
             .filter(|unit| unit.creator() == NodeIndex(0))
-            .map(|unit| Signed::sign(unit.clone(), &keychains[unit.creator().0]))
+            .map(|unit| {
+                Signed::sign(unit.clone(), &keychains[unit.creator().0])
+                    .expect("the keychain never fails")
+            })
         {
             match unit.round() {
                 0..=1 => assert_eq!(
@@ -391,19 +648,26 @@ mod test {
             .map(|node_id| Keychain::new(node_count, node_id))
             .collect();
         let store = UnitStore::<WrappedSignedUnit>::new(node_count);
-        let mut validator = Validator::new(UnitValidator::new(session_id, keychains[0], max_round));
+        let mut validator = Validator::new(UnitValidator::new(
+            session_id,
+            keychains[0].clone(),
+            max_round,
+        ));
         let fork = random_full_parent_units_up_to(2, node_count, session_id)
             .get(2)
             .expect("we have the requested round")
             .first()
             .expect("we have the unit for the zeroth creator")
             .clone();
-        let fork = Signed::sign(fork, &keychains[0]);
+        let fork = Signed::sign(fork, &keychains[0]).expect("the keychain never fails");
         let units: Vec<_> = random_full_parent_units_up_to(produced_round, node_count, session_id)
             .iter()
             .flatten()
             .filter(|unit| unit.creator() == NodeIndex(0))
-            .map(|unit| Signed::sign(unit.clone(), &keychains[unit.creator().0]))
+            .map(|unit| {
+                Signed::sign(unit.clone(), &keychains[unit.creator().0])
+                    .expect("the keychain never fails")
+            })
             .collect();
         for unit in units.iter().take(3) {
             assert_eq!(
@@ -0,0 +1,76 @@
+use codec::{Compact, Decode, Error as CodecError, Input};
+
+/// How many items a list in a message from the network or in a backup may claim to hold at most,
+/// e.g. units in a response or hashes in a request. Honest lists are far shorter, as they hold
+/// at most a few units of every member, so longer ones are refused before anything is decoded.
+pub const MAX_DECODED_ITEMS: usize = 1 << 20;
+
+/// How deeply the lists in a message from the network or in a backup may be nested, including
+/// the ones in the data of the application. Our messages nest a few levels deep at most.
+pub const MAX_DECODE_DEPTH: u32 = 32;
+
+/// Space is reserved for at most this many items before they decode, so that a claimed length
+/// never makes us allocate more than the input is worth.
+const PREALLOCATED_ITEMS: usize = 64;
+
+/// Decodes a list of at most [`MAX_DECODED_ITEMS`] items, encoded like a `Vec`.
+pub(crate) fn decode_bounded_vec<T: Decode, I: Input>(input: &mut I) -> Result<Vec<T>, CodecError> {
+    let Compact(len) = Compact::<u32>::decode(input)?;
+    let len = len as usize;
+    if len > MAX_DECODED_ITEMS {
+        return Err("a list claims more items than are ever decoded".into());
+    }
+    input.descend_ref()?;
+    let mut items = Vec::with_capacity(len.min(PREALLOCATED_ITEMS));
+    for _ in 0..len {
+        items.push(T::decode(input)?);
+    }
+    input.ascend_ref();
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decoding::{decode_bounded_vec, MAX_DECODED_ITEMS};
+    use codec::{Compact, DecodeLimit, Encode};
+
+    #[test]
+    fn decodes_like_a_vec() {
+        let items: Vec<u64> = (0..1000).collect();
+        let encoded = items.encode();
+        assert_eq!(decode_bounded_vec::<u64, _>(&mut &encoded[..]), Ok(items));
+    }
+
+    #[test]
+    fn refuses_too_many_items() {
+        let mut encoded = Compact(MAX_DECODED_ITEMS as u32 + 1).encode();
+        encoded.extend(vec![0; 8 * 16]);
+        assert!(decode_bounded_vec::<u64, _>(&mut &encoded[..]).is_err());
+    }
+
+    #[test]
+    fn refuses_huge_claimed_length_without_the_items() {
+        let mut encoded = Compact(u32::MAX).encode();
+        encoded.extend(7u64.encode());
+        assert!(decode_bounded_vec::<u64, _>(&mut &encoded[..]).is_err());
+        let mut encoded = Compact(MAX_DECODED_ITEMS as u32).encode();
+        encoded.extend(7u64.encode());
+        assert!(decode_bounded_vec::<u64, _>(&mut &encoded[..]).is_err());
+    }
+
+    #[test]
+    fn counts_towards_the_depth_limit() {
+        struct Nested(Vec<u64>);
+        impl codec::Decode for Nested {
+            fn decode<I: codec::Input>(input: &mut I) -> Result<Self, codec::Error> {
+                Ok(Nested(decode_bounded_vec(input)?))
+            }
+        }
+        let encoded = vec![1u64, 2, 3].encode();
+        assert_eq!(
+            Nested::decode_with_depth_limit(1, &mut &encoded[..]).map(|Nested(items)| items),
+            Ok(vec![1, 2, 3])
+        );
+        assert!(Nested::decode_with_depth_limit(0, &mut &encoded[..]).is_err());
+    }
+}
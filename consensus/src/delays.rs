@@ -0,0 +1,149 @@
+use crate::{config::DelaySchedule, DelayConfig};
+use parking_lot::Mutex;
+use std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    sync::Arc,
+};
+
+/// The schedules replaced while the session runs, the ones of the config are used otherwise.
+#[derive(Clone, Default)]
+struct Overrides {
+    unit_creation_delay: Option<DelaySchedule>,
+    coord_request_delay: Option<DelaySchedule>,
+    parent_request_delay: Option<DelaySchedule>,
+    newest_request_delay: Option<DelaySchedule>,
+}
+
+/// Allows the application to change the pace of a running session, e.g. to slow down the
+/// creation of units while it is under load and speed it up again later, see [`delay_control`].
+///
+/// The schedules are still called with the same arguments, e.g. the round of the unit being
+/// created, so a schedule using [`crate::exponential_slowdown`] keeps slowing down from the same
+/// round regardless of when it was set.
+#[derive(Clone)]
+pub struct DelayControlHandle {
+    overrides: Arc<Mutex<Overrides>>,
+}
+
+impl DelayControlHandle {
+    /// Replaces the schedule of creating units, starting with the delay before the next unit.
+    pub fn set_unit_creation_delay(&self, schedule: DelaySchedule) {
+        self.overrides.lock().unit_creation_delay = Some(schedule);
+    }
+
+    /// Replaces the schedule of retrying requests for units by coords, for all the retries
+    /// scheduled from now on.
+    pub fn set_coord_request_delay(&self, schedule: DelaySchedule) {
+        self.overrides.lock().coord_request_delay = Some(schedule);
+    }
+
+    /// Replaces the schedule of retrying requests for the parents of units, for all the retries
+    /// scheduled from now on.
+    pub fn set_parent_request_delay(&self, schedule: DelaySchedule) {
+        self.overrides.lock().parent_request_delay = Some(schedule);
+    }
+
+    /// Replaces the schedule of retrying requests for the newest units, for all the retries
+    /// scheduled from now on.
+    pub fn set_newest_request_delay(&self, schedule: DelaySchedule) {
+        self.overrides.lock().newest_request_delay = Some(schedule);
+    }
+
+    /// Goes back to the schedules of the [`DelayConfig`] the session was started with.
+    pub fn reset(&self) {
+        *self.overrides.lock() = Overrides::default();
+    }
+}
+
+/// The part of the delay control passed to the session, see [`delay_control`].
+#[derive(Clone, Default)]
+pub struct DelayControl {
+    overrides: Arc<Mutex<Overrides>>,
+}
+
+impl DelayControl {
+    /// The given delays, with the schedules replaced through the handle.
+    pub(crate) fn apply(&self, delay_config: &DelayConfig) -> DelayConfig {
+        let Overrides {
+            unit_creation_delay,
+            coord_request_delay,
+            parent_request_delay,
+            newest_request_delay,
+        } = self.overrides.lock().clone();
+        let delay_config = delay_config.clone();
+        DelayConfig {
+            unit_creation_delay: unit_creation_delay.unwrap_or(delay_config.unit_creation_delay),
+            coord_request_delay: coord_request_delay.unwrap_or(delay_config.coord_request_delay),
+            parent_request_delay: parent_request_delay.unwrap_or(delay_config.parent_request_delay),
+            newest_request_delay: newest_request_delay.unwrap_or(delay_config.newest_request_delay),
+            ..delay_config
+        }
+    }
+}
+
+impl Debug for DelayControl {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let overrides = self.overrides.lock();
+        f.debug_struct("DelayControl")
+            .field(
+                "unit creation delay replaced",
+                &overrides.unit_creation_delay.is_some(),
+            )
+            .field(
+                "coord request delay replaced",
+                &overrides.coord_request_delay.is_some(),
+            )
+            .field(
+                "parent request delay replaced",
+                &overrides.parent_request_delay.is_some(),
+            )
+            .field(
+                "newest request delay replaced",
+                &overrides.newest_request_delay.is_some(),
+            )
+            .finish()
+    }
+}
+
+/// Creates a handle for changing the delays of a running session together with the control
+/// that should be passed to it with [`crate::Config::with_delay_control`]. Without the control
+/// the session keeps the delays it was started with.
+///
+/// Note that the config is only checked against the delays it was created with, e.g. when
+/// estimating the time needed to reach the maximum round.
+pub fn delay_control() -> (DelayControlHandle, DelayControl) {
+    let overrides = Arc::new(Mutex::new(Overrides::default()));
+    (
+        DelayControlHandle {
+            overrides: overrides.clone(),
+        },
+        DelayControl { overrides },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{delays::delay_control, testing::gen_delay_config};
+    use std::{sync::Arc, time::Duration};
+
+    #[test]
+    fn replaces_schedules_until_reset() {
+        let delay_config = gen_delay_config();
+        let (handle, control) = delay_control();
+        let current = control.apply(&delay_config);
+        assert_eq!((current.unit_creation_delay)(7), Duration::from_millis(50));
+
+        handle.set_unit_creation_delay(Arc::new(|t| Duration::from_millis(t as u64)));
+        handle.set_parent_request_delay(Arc::new(|_| Duration::from_secs(1)));
+        let current = control.apply(&delay_config);
+        assert_eq!((current.unit_creation_delay)(7), Duration::from_millis(7));
+        assert_eq!((current.parent_request_delay)(0), Duration::from_secs(1));
+        assert_eq!((current.coord_request_delay)(0), Duration::from_millis(100));
+        assert_eq!(current.tick_interval, delay_config.tick_interval);
+
+        handle.reset();
+        let current = control.apply(&delay_config);
+        assert_eq!((current.unit_creation_delay)(7), Duration::from_millis(50));
+        assert_eq!((current.parent_request_delay)(0), Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,510 @@
+use crate::{
+    callbacks::{CallbackGuard, CallbackPanicked, UserComponent},
+    finalization_state::{FinalizationState, RestoreError},
+    protocol::BatchId,
+    DeliveryCheckpoint, Hasher, OrderedUnit, Receiver, Sender, UnitFinalizationHandler,
+};
+use futures::channel::mpsc;
+use log::{debug, error, warn};
+use parking_lot::Mutex;
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+};
+
+const LOG_TARGET: &str = "AlephBFT-delivery";
+
+/// The number of batches the delivery buffer holds by default before applying the
+/// [`OverflowPolicy`].
+pub const DEFAULT_DELIVERY_BUFFER_LIMIT: usize = 1000;
+
+/// A finalized batch together with its identifier.
+type Batch<UFH> = (
+    BatchId<<UFH as UnitFinalizationHandler>::Hasher>,
+    Vec<
+        OrderedUnit<
+            <UFH as UnitFinalizationHandler>::Data,
+            <UFH as UnitFinalizationHandler>::Hasher,
+        >,
+    >,
+);
+
+/// What happens when the delivery buffer reaches its limit while the delivery is paused.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OverflowPolicy {
+    /// Stop finalizing further batches until the delivery is resumed. Units keep being created
+    /// and added to the DAG, they are only ordered later.
+    #[default]
+    Block,
+    /// Terminate the session.
+    Abort,
+}
+
+/// The state of the delivery of finalized batches to the finalization handler.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DeliveryStatus {
+    /// Whether the delivery is paused.
+    pub paused: bool,
+    /// The number of finalized batches waiting for the delivery to be resumed.
+    pub buffered_batches: usize,
+}
+
+struct SharedState {
+    status: DeliveryStatus,
+    resumption_listeners: Vec<Sender<()>>,
+}
+
+/// Allows the application to pause and resume the delivery of finalized batches, e.g. for
+/// the duration of a maintenance window, without stopping the participation in consensus.
+///
+/// While the delivery is paused the finalized batches are buffered, and resuming delivers
+/// them in order. No batch is ever dropped.
+#[derive(Clone)]
+pub struct DeliveryControlHandle {
+    shared: Arc<Mutex<SharedState>>,
+}
+
+impl DeliveryControlHandle {
+    /// Stops calling the finalization handler until [`Self::resume_delivery`] is called.
+    /// The delivery stays paused even if all the handles get dropped.
+    pub fn pause_delivery(&self) {
+        self.shared.lock().status.paused = true;
+    }
+
+    /// Delivers all the buffered batches in order and resumes the normal delivery.
+    pub fn resume_delivery(&self) {
+        let mut shared = self.shared.lock();
+        shared.status.paused = false;
+        // Listeners of sessions that already ended are forgotten.
+        shared
+            .resumption_listeners
+            .retain(|listener| listener.unbounded_send(()).is_ok());
+    }
+
+    /// The current state of the delivery.
+    pub fn status(&self) -> DeliveryStatus {
+        self.shared.lock().status
+    }
+}
+
+/// The part of the delivery control passed to the session, see [`delivery_control`].
+#[derive(Clone)]
+pub struct DeliveryControl {
+    shared: Arc<Mutex<SharedState>>,
+    buffer_limit: usize,
+    overflow_policy: OverflowPolicy,
+}
+
+impl Default for DeliveryControl {
+    /// A delivery control that is never paused.
+    fn default() -> Self {
+        delivery_control(DEFAULT_DELIVERY_BUFFER_LIMIT, OverflowPolicy::default()).1
+    }
+}
+
+impl DeliveryControl {
+    /// Returns the session side of the control, together with a stream notifying about the
+    /// delivery being resumed.
+    pub(crate) fn split(self) -> (Delivery, Receiver<()>) {
+        let (resumptions_for_session, resumptions) = mpsc::unbounded();
+        self.shared
+            .lock()
+            .resumption_listeners
+            .push(resumptions_for_session);
+        let DeliveryControl {
+            shared,
+            buffer_limit,
+            overflow_policy,
+        } = self;
+        (
+            Delivery {
+                shared,
+                buffer_limit,
+                overflow_policy,
+            },
+            resumptions,
+        )
+    }
+}
+
+/// Creates a handle for pausing the delivery of finalized batches together with the control
+/// that should be passed to the session with [`crate::LocalIO::with_delivery_control`].
+/// At most `buffer_limit` batches are buffered before applying the `overflow_policy`.
+pub fn delivery_control(
+    buffer_limit: usize,
+    overflow_policy: OverflowPolicy,
+) -> (DeliveryControlHandle, DeliveryControl) {
+    let shared = Arc::new(Mutex::new(SharedState {
+        status: DeliveryStatus::default(),
+        resumption_listeners: Vec::new(),
+    }));
+    (
+        DeliveryControlHandle {
+            shared: shared.clone(),
+        },
+        DeliveryControl {
+            shared,
+            buffer_limit,
+            overflow_policy,
+        },
+    )
+}
+
+/// Why the finalized batches cannot be delivered anymore.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum DeliveryError {
+    /// The delivery buffer reached its limit and the [`OverflowPolicy`] is to abort.
+    BufferOverflow,
+    CallbackPanicked(CallbackPanicked),
+}
+
+impl Display for DeliveryError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            DeliveryError::BufferOverflow => write!(f, "delivery buffer overflowed"),
+            DeliveryError::CallbackPanicked(CallbackPanicked(component)) => {
+                write!(f, "the {} panicked", component)
+            }
+        }
+    }
+}
+
+impl From<CallbackPanicked> for DeliveryError {
+    fn from(e: CallbackPanicked) -> Self {
+        DeliveryError::CallbackPanicked(e)
+    }
+}
+
+/// The session side of the delivery control.
+pub(crate) struct Delivery {
+    shared: Arc<Mutex<SharedState>>,
+    buffer_limit: usize,
+    overflow_policy: OverflowPolicy,
+}
+
+impl Delivery {
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+}
+
+/// Passes finalized batches to the finalization handler, buffering them while the delivery
+/// is paused. With a finalization state the batches delivered before a restart are skipped.
+pub(crate) struct DeliveryBuffer<UFH: UnitFinalizationHandler> {
+    finalization_handler: UFH,
+    delivery: Delivery,
+    buffer: VecDeque<Batch<UFH>>,
+    finalization_state: Option<FinalizationState<UFH::Hasher>>,
+    finalized_batches: u64,
+    delivered_before_restart: u64,
+    checkpoint: Option<DeliveryCheckpoint<<UFH::Hasher as Hasher>::Hash>>,
+    callbacks: CallbackGuard,
+}
+
+impl<UFH: UnitFinalizationHandler> DeliveryBuffer<UFH> {
+    pub fn new(
+        finalization_handler: UFH,
+        delivery: Delivery,
+        finalization_state: Option<FinalizationState<UFH::Hasher>>,
+        callbacks: CallbackGuard,
+    ) -> Self {
+        DeliveryBuffer {
+            finalization_handler,
+            delivery,
+            buffer: VecDeque::new(),
+            finalization_state,
+            finalized_batches: 0,
+            delivered_before_restart: 0,
+            checkpoint: None,
+            callbacks,
+        }
+    }
+
+    /// Reads the checkpoint of the finalization state, if there is one, given the hashes of
+    /// the units loaded from the backup. Has to be called before any batch is finalized.
+    pub fn restore(
+        &mut self,
+        backup: &HashSet<<UFH::Hasher as Hasher>::Hash>,
+    ) -> Result<(), RestoreError<<UFH::Hasher as Hasher>::Hash>> {
+        if let Some(finalization_state) = &self.finalization_state {
+            let (delivered_before_restart, checkpoint) = self
+                .callbacks
+                .call(UserComponent::FinalizationStateStore, || {
+                    (
+                        finalization_state.restore(backup),
+                        finalization_state.checkpoint(),
+                    )
+                })
+                .map_err(|_| RestoreError::StorePanicked)?;
+            self.delivered_before_restart = delivered_before_restart?;
+            self.checkpoint = checkpoint.map_err(RestoreError::Store)?;
+            if self.delivered_before_restart > 0 {
+                debug!(target: LOG_TARGET, "Skipping {} batches delivered before the restart.", self.delivered_before_restart);
+            }
+        }
+        Ok(())
+    }
+
+    /// The point up to which the batches were passed to the finalization handler, also before
+    /// a restart if there is a finalization state.
+    pub fn checkpoint(&self) -> Option<DeliveryCheckpoint<<UFH::Hasher as Hasher>::Hash>> {
+        self.checkpoint
+    }
+
+    /// Whether the delivery is recorded in a finalization state.
+    pub fn has_finalization_state(&self) -> bool {
+        self.finalization_state.is_some()
+    }
+
+    pub fn delivery(&self) -> &Delivery {
+        &self.delivery
+    }
+
+    /// Whether the buffer reached its limit, so no more batches should be finalized for now.
+    pub fn is_full(&self) -> bool {
+        self.delivery.shared.lock().status.paused && self.buffer.len() >= self.delivery.buffer_limit
+    }
+
+    /// Whether all the finalized batches were delivered.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn deliver(&mut self, batch: Batch<UFH>) -> Result<(), CallbackPanicked> {
+        self.buffer.push_back(batch);
+        self.flush()
+    }
+
+    /// Delivers all the buffered batches, unless the delivery is paused. Nothing more gets
+    /// delivered after the finalization handler or the finalization state store panicked.
+    pub fn flush(&mut self) -> Result<(), CallbackPanicked> {
+        let mut shared = self.delivery.shared.lock();
+        let status = &mut shared.status;
+        if status.paused {
+            status.buffered_batches = self.buffer.len();
+            drop(shared);
+            self.warn_on_watermark();
+            return Ok(());
+        }
+        if !self.buffer.is_empty() && status.buffered_batches > 0 {
+            debug!(target: LOG_TARGET, "Delivering {} buffered batches.", self.buffer.len());
+        }
+        status.buffered_batches = 0;
+        drop(shared);
+        while let Some((id, batch)) = self.buffer.pop_front() {
+            let number = self.finalized_batches;
+            self.finalized_batches += 1;
+            if number < self.delivered_before_restart {
+                continue;
+            }
+            if let Some(head) = batch.last() {
+                self.checkpoint = Some(DeliveryCheckpoint {
+                    batches: number + 1,
+                    head: head.hash,
+                    batch_id: id,
+                });
+            }
+            let finalization_handler = &mut self.finalization_handler;
+            let finalization_state = match &self.finalization_state {
+                Some(finalization_state) => finalization_state,
+                None => {
+                    self.callbacks
+                        .call(UserComponent::FinalizationHandler, || {
+                            finalization_handler.identified_batch_finalized(id, batch)
+                        })?;
+                    continue;
+                }
+            };
+            let units = batch.iter().map(|unit| unit.hash).collect();
+            self.callbacks
+                .call(UserComponent::FinalizationHandler, || {
+                    finalization_handler.identified_batch_finalized(id, batch)
+                })?;
+            let recorded = self
+                .callbacks
+                .call(UserComponent::FinalizationStateStore, || {
+                    finalization_state.record(number, id, units)
+                })?;
+            if let Err(e) = recorded {
+                error!(target: LOG_TARGET, "Failed to record delivered batch {} in the finalization state: {}.", number, e);
+            }
+        }
+        Ok(())
+    }
+
+    fn warn_on_watermark(&self) {
+        let buffered = self.buffer.len();
+        let limit = self.delivery.buffer_limit;
+        if buffered == limit {
+            warn!(target: LOG_TARGET, "Delivery buffer is full with {} batches, applying the {:?} overflow policy.", buffered, self.delivery.overflow_policy);
+        } else if buffered == limit / 2 || buffered == limit - limit / 4 {
+            warn!(target: LOG_TARGET, "Delivery buffer is filling up, {} out of {} batches buffered.", buffered, limit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        callbacks::CallbackGuard,
+        delivery::{delivery_control, DeliveryBuffer, DeliveryStatus, OverflowPolicy},
+        FinalizationState, NodeIndex, OrderedUnit, UnitFinalizationHandler,
+    };
+    use aleph_bft_mock::{Data, FinalizationStateStore, Hash64, Hasher64};
+    use std::collections::HashSet;
+
+    #[derive(Default)]
+    struct RecordingHandler {
+        rounds: Vec<u16>,
+    }
+
+    impl UnitFinalizationHandler for RecordingHandler {
+        type Data = Data;
+        type Hasher = Hasher64;
+
+        fn batch_finalized(&mut self, batch: Vec<OrderedUnit<Data, Hasher64>>) {
+            self.rounds.extend(batch.into_iter().map(|unit| unit.round));
+        }
+    }
+
+    /// A batch of a single unit, its identifier being the hash of the unit negated.
+    fn batch(round: u16) -> (Hash64, Vec<OrderedUnit<Data, Hasher64>>) {
+        let unit = OrderedUnit {
+            data: None,
+            parents: Vec::new(),
+            hash: [round as u8; 8],
+            creator: NodeIndex(0),
+            round,
+            flagged: false,
+        };
+        ([!(round as u8); 8], vec![unit])
+    }
+
+    #[test]
+    fn delivers_immediately_when_not_paused() {
+        let (handle, control) = delivery_control(2, OverflowPolicy::Block);
+        let (delivery, _) = control.split();
+        let mut buffer = DeliveryBuffer::new(
+            RecordingHandler::default(),
+            delivery,
+            None,
+            CallbackGuard::default(),
+        );
+        buffer
+            .deliver(batch(0))
+            .expect("the handler does not panic");
+        buffer
+            .deliver(batch(1))
+            .expect("the handler does not panic");
+        assert_eq!(buffer.finalization_handler.rounds, vec![0, 1]);
+        assert_eq!(handle.status(), DeliveryStatus::default());
+    }
+
+    #[test]
+    fn buffers_while_paused_and_flushes_in_order() {
+        let (handle, control) = delivery_control(2, OverflowPolicy::Block);
+        let (delivery, _) = control.split();
+        let mut buffer = DeliveryBuffer::new(
+            RecordingHandler::default(),
+            delivery,
+            None,
+            CallbackGuard::default(),
+        );
+        buffer
+            .deliver(batch(0))
+            .expect("the handler does not panic");
+        handle.pause_delivery();
+        buffer
+            .deliver(batch(1))
+            .expect("the handler does not panic");
+        assert!(!buffer.is_full());
+        buffer
+            .deliver(batch(2))
+            .expect("the handler does not panic");
+        assert!(buffer.is_full());
+        assert_eq!(buffer.finalization_handler.rounds, vec![0]);
+        assert_eq!(
+            handle.status(),
+            DeliveryStatus {
+                paused: true,
+                buffered_batches: 2,
+            }
+        );
+        handle.resume_delivery();
+        assert!(!buffer.is_full());
+        buffer.flush().expect("the handler does not panic");
+        assert_eq!(buffer.finalization_handler.rounds, vec![0, 1, 2]);
+        assert_eq!(handle.status(), DeliveryStatus::default());
+    }
+
+    #[test]
+    fn new_batch_after_resume_goes_after_buffered_ones() {
+        let (handle, control) = delivery_control(10, OverflowPolicy::Block);
+        let (delivery, _) = control.split();
+        let mut buffer = DeliveryBuffer::new(
+            RecordingHandler::default(),
+            delivery,
+            None,
+            CallbackGuard::default(),
+        );
+        handle.pause_delivery();
+        buffer
+            .deliver(batch(0))
+            .expect("the handler does not panic");
+        handle.resume_delivery();
+        buffer
+            .deliver(batch(1))
+            .expect("the handler does not panic");
+        assert_eq!(buffer.finalization_handler.rounds, vec![0, 1]);
+    }
+
+    #[test]
+    fn skips_batches_delivered_before_restart() {
+        let store = FinalizationStateStore::new();
+        let (_, control) = delivery_control(10, OverflowPolicy::Block);
+        let (delivery, _) = control.split();
+        let state = FinalizationState::new(store.clone());
+        let mut buffer = DeliveryBuffer::new(
+            RecordingHandler::default(),
+            delivery,
+            Some(state),
+            CallbackGuard::default(),
+        );
+        buffer.restore(&HashSet::new()).expect("the store is empty");
+        buffer
+            .deliver(batch(0))
+            .expect("the handler does not panic");
+        buffer
+            .deliver(batch(1))
+            .expect("the handler does not panic");
+
+        let (_, control) = delivery_control(10, OverflowPolicy::Block);
+        let (delivery, _) = control.split();
+        let state = FinalizationState::new(store);
+        let mut buffer = DeliveryBuffer::new(
+            RecordingHandler::default(),
+            delivery,
+            Some(state.clone()),
+            CallbackGuard::default(),
+        );
+        let backup = [0, 1].into_iter().map(|round| [round; 8]).collect();
+        buffer.restore(&backup).expect("the head is in the backup");
+        for round in 0..3 {
+            buffer
+                .deliver(batch(round))
+                .expect("the handler does not panic");
+        }
+        assert_eq!(buffer.finalization_handler.rounds, vec![2]);
+        assert_eq!(state.finalized_in(&[1; 8]).unwrap(), Some(1));
+        assert_eq!(
+            state
+                .checkpoint()
+                .unwrap()
+                .map(|checkpoint| checkpoint.batches),
+            Some(3)
+        );
+        assert_eq!(state.batch_id(1).unwrap(), Some([!1; 8]));
+        assert_eq!(state.batch_id(2).unwrap(), Some([!2; 8]));
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{
+    decoding::decode_bounded_vec, runway::Salt, units::UncheckedSignedUnit, Data, Hasher, Index,
+    NodeIndex, Round, Signable, Signature,
+};
+use codec::{Decode, Encode, Error as CodecError, Input};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// How long we wait for the answer to a request for the newest units before we consider it
+/// lost, and how often we send such requests at most.
+pub(crate) const CATCH_UP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How often we answer the requests for the newest units of a single peer at most.
+pub(crate) const MIN_CATCH_UP_ANSWER_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How many rounds of units we send in answer to a single request for the newest units at
+/// most, a node lagging further behind catches up in a few requests.
+pub(crate) const MAX_CATCH_UP_ROUNDS: Round = 50;
+
+/// A part of the answer to a request for the newest units, signed by the responder. The units
+/// are sorted by rounds, so that they can be added to the DAG in order.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Encode)]
+pub struct NewestUnitsResponse<H: Hasher, D: Data, S: Signature> {
+    requester: NodeIndex,
+    responder: NodeIndex,
+    salt: Salt,
+    units: Vec<UncheckedSignedUnit<H, D, S>>,
+}
+
+impl<H: Hasher, D: Data, S: Signature> Decode for NewestUnitsResponse<H, D, S> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        Ok(NewestUnitsResponse {
+            requester: Decode::decode(input)?,
+            responder: Decode::decode(input)?,
+            salt: Decode::decode(input)?,
+            units: decode_bounded_vec(input)?,
+        })
+    }
+}
+
+impl<H: Hasher, D: Data, S: Signature> Signable for NewestUnitsResponse<H, D, S> {
+    type Hash = Vec<u8>;
+
+    fn hash(&self) -> Self::Hash {
+        self.encode()
+    }
+}
+
+impl<H: Hasher, D: Data, S: Signature> Index for NewestUnitsResponse<H, D, S> {
+    fn index(&self) -> NodeIndex {
+        self.responder
+    }
+}
+
+impl<H: Hasher, D: Data, S: Signature> NewestUnitsResponse<H, D, S> {
+    pub fn new(
+        requester: NodeIndex,
+        responder: NodeIndex,
+        salt: Salt,
+        units: Vec<UncheckedSignedUnit<H, D, S>>,
+    ) -> Self {
+        NewestUnitsResponse {
+            requester,
+            responder,
+            salt,
+            units,
+        }
+    }
+
+    pub fn requester(&self) -> NodeIndex {
+        self.requester
+    }
+
+    pub fn salt(&self) -> Salt {
+        self.salt
+    }
+
+    pub fn units(&self) -> &[UncheckedSignedUnit<H, D, S>] {
+        &self.units
+    }
+
+    pub fn into_units(self) -> Vec<UncheckedSignedUnit<H, D, S>> {
+        self.units
+    }
+}
+
+/// What to do about a peer being ahead of us.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CatchUpStep {
+    /// Our request for the newest units is still on its way, so nothing.
+    Wait,
+    /// Request the newest units from the peer with the given salt.
+    Request(Salt),
+    /// Our last request went unanswered, e.g. because the peer doesn't understand it, so request
+    /// the units by their coords instead.
+    FallBack,
+}
+
+struct PendingRequest {
+    salt: Salt,
+    peer: NodeIndex,
+    sent_at: Instant,
+    answered: bool,
+}
+
+/// Keeps track of the requests for the newest units, both ours and those of our peers.
+///
+/// We request all the units we lack from a single peer at a time, and accept only the signed
+/// answers of that peer with the salt of that request, so they cannot be replayed or spoofed by
+/// other nodes. The units themselves are signed by their creators and checked like any others.
+#[derive(Default)]
+pub struct CatchUp {
+    pending: Option<PendingRequest>,
+    answered_at: HashMap<NodeIndex, Instant>,
+}
+
+impl CatchUp {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The given peer is ahead of us, decides how to catch up with it.
+    pub fn on_lag(&mut self, peer: NodeIndex, now: Instant) -> CatchUpStep {
+        match &self.pending {
+            Some(pending) if now.saturating_duration_since(pending.sent_at) < CATCH_UP_TIMEOUT => {
+                CatchUpStep::Wait
+            }
+            Some(pending) if !pending.answered => {
+                self.pending = None;
+                CatchUpStep::FallBack
+            }
+            _ => {
+                let salt = rand::random();
+                self.pending = Some(PendingRequest {
+                    salt,
+                    peer,
+                    sent_at: now,
+                    answered: false,
+                });
+                CatchUpStep::Request(salt)
+            }
+        }
+    }
+
+    /// Whether the units from the peer answer our pending request. They can come in a few
+    /// messages, all of them are accepted.
+    pub fn accept(&mut self, peer: NodeIndex, salt: Salt) -> bool {
+        match &mut self.pending {
+            Some(pending) if pending.peer == peer && pending.salt == salt => {
+                pending.answered = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether we can answer another request of the peer at the given time. Counts the request
+    /// as answered if so.
+    pub fn try_answer(&mut self, peer: NodeIndex, now: Instant) -> bool {
+        match self.answered_at.get(&peer) {
+            Some(at) if now.saturating_duration_since(*at) < MIN_CATCH_UP_ANSWER_INTERVAL => false,
+            _ => {
+                self.answered_at.insert(peer, now);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dissemination::catch_up::{
+            CatchUp, CatchUpStep, CATCH_UP_TIMEOUT, MIN_CATCH_UP_ANSWER_INTERVAL,
+        },
+        NodeIndex,
+    };
+    use std::time::Instant;
+
+    fn request(catch_up: &mut CatchUp, peer: NodeIndex, now: Instant) -> u64 {
+        match catch_up.on_lag(peer, now) {
+            CatchUpStep::Request(salt) => salt,
+            step => panic!("expected a request, got {:?}", step),
+        }
+    }
+
+    #[test]
+    fn waits_for_answer_and_falls_back_without_one() {
+        let mut catch_up = CatchUp::new();
+        let now = Instant::now();
+        request(&mut catch_up, NodeIndex(1), now);
+        assert_eq!(catch_up.on_lag(NodeIndex(2), now), CatchUpStep::Wait);
+        let later = now + CATCH_UP_TIMEOUT;
+        assert_eq!(catch_up.on_lag(NodeIndex(2), later), CatchUpStep::FallBack);
+        request(&mut catch_up, NodeIndex(2), later);
+    }
+
+    #[test]
+    fn accepts_only_answers_to_pending_request() {
+        let mut catch_up = CatchUp::new();
+        let now = Instant::now();
+        assert!(!catch_up.accept(NodeIndex(1), 0));
+        let salt = request(&mut catch_up, NodeIndex(1), now);
+        assert!(!catch_up.accept(NodeIndex(2), salt));
+        assert!(!catch_up.accept(NodeIndex(1), salt.wrapping_add(1)));
+        assert!(catch_up.accept(NodeIndex(1), salt));
+        assert!(catch_up.accept(NodeIndex(1), salt));
+        // An answered request is followed by another one, not by the fallback.
+        let later = now + CATCH_UP_TIMEOUT;
+        let next_salt = request(&mut catch_up, NodeIndex(2), later);
+        assert!(!catch_up.accept(NodeIndex(1), salt));
+        assert!(catch_up.accept(NodeIndex(2), next_salt));
+    }
+
+    #[test]
+    fn limits_answers_per_peer() {
+        let mut catch_up = CatchUp::new();
+        let now = Instant::now();
+        assert!(catch_up.try_answer(NodeIndex(1), now));
+        assert!(!catch_up.try_answer(NodeIndex(1), now));
+        assert!(catch_up.try_answer(NodeIndex(2), now));
+        assert!(catch_up.try_answer(NodeIndex(1), now + MIN_CATCH_UP_ANSWER_INTERVAL));
+    }
+}
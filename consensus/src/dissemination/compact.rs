@@ -0,0 +1,291 @@
+use crate::{
+    units::{UncheckedSignedUnit, Unit, UnitCoord},
+    Data, Hasher, Signature,
+};
+use codec::{Decode, Encode};
+use std::collections::HashMap;
+
+/// A unit within a message, either in full or as a reference to a unit the receiver most likely
+/// already holds.
+#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
+pub enum CompactUnit<H: Hasher, D: Data, S: Signature> {
+    Full(UncheckedSignedUnit<H, D, S>),
+    HashRef(H::Hash),
+}
+
+impl<H: Hasher, D: Data, S: Signature> CompactUnit<H, D, S> {
+    /// Replaces the units the receiver holds with references, according to our belief about
+    /// which units they hold.
+    pub fn compact(
+        units: Vec<UncheckedSignedUnit<H, D, S>>,
+        holds: impl Fn(UnitCoord) -> bool,
+    ) -> Vec<Self> {
+        units
+            .into_iter()
+            .map(|unit| {
+                let full_unit = unit.as_signable();
+                match holds(full_unit.coord()) {
+                    true => CompactUnit::HashRef(full_unit.hash()),
+                    false => CompactUnit::Full(unit),
+                }
+            })
+            .collect()
+    }
+
+    /// The unit, if it is sent in full.
+    pub fn full(&self) -> Option<&UncheckedSignedUnit<H, D, S>> {
+        match self {
+            CompactUnit::Full(unit) => Some(unit),
+            CompactUnit::HashRef(_) => None,
+        }
+    }
+}
+
+/// The outcome of resolving the references in a compact list of parents.
+#[derive(Debug)]
+pub enum Resolution<H: Hasher, D: Data, S: Signature> {
+    /// All the parents, in the order they were sent in.
+    Complete(Vec<UncheckedSignedUnit<H, D, S>>),
+    /// The hashes of the referenced units we don't hold, which have to be requested.
+    Missing(Vec<H::Hash>),
+}
+
+/// A unit together with its complete parents.
+pub type ResolvedParents<H, D, S> = (<H as Hasher>::Hash, Vec<UncheckedSignedUnit<H, D, S>>);
+
+/// Keeps the compact parents responses with references we could not resolve yet, until the
+/// referenced units arrive or the parents are not needed anymore.
+pub struct CompactResolver<H: Hasher, D: Data, S: Signature> {
+    pending: HashMap<H::Hash, Vec<CompactUnit<H, D, S>>>,
+}
+
+impl<H: Hasher, D: Data, S: Signature> CompactResolver<H, D, S> {
+    pub fn new() -> Self {
+        CompactResolver {
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Resolves the references in the parents of the given unit with `lookup`, remembering the
+    /// parents if some of the references remain unresolved. A later response for the same unit
+    /// replaces the earlier one.
+    pub fn resolve(
+        &mut self,
+        u_hash: H::Hash,
+        parents: Vec<CompactUnit<H, D, S>>,
+        lookup: impl Fn(&H::Hash) -> Option<UncheckedSignedUnit<H, D, S>>,
+    ) -> Resolution<H, D, S> {
+        let parents: Vec<_> = parents
+            .into_iter()
+            .map(|parent| match parent {
+                CompactUnit::HashRef(hash) => lookup(&hash)
+                    .map(CompactUnit::Full)
+                    .unwrap_or(CompactUnit::HashRef(hash)),
+                full => full,
+            })
+            .collect();
+        let missing: Vec<_> = parents
+            .iter()
+            .filter_map(|parent| match parent {
+                CompactUnit::HashRef(hash) => Some(*hash),
+                CompactUnit::Full(_) => None,
+            })
+            .collect();
+        if !missing.is_empty() {
+            self.pending.insert(u_hash, parents);
+            return Resolution::Missing(missing);
+        }
+        self.pending.remove(&u_hash);
+        Resolution::Complete(
+            parents
+                .into_iter()
+                .filter_map(|parent| match parent {
+                    CompactUnit::Full(unit) => Some(unit),
+                    CompactUnit::HashRef(_) => None,
+                })
+                .collect(),
+        )
+    }
+
+    /// Uses the units we requested to resolve the remembered parents, returning the units whose
+    /// parents are now complete, together with the parents.
+    pub fn on_units(
+        &mut self,
+        units: Vec<UncheckedSignedUnit<H, D, S>>,
+        lookup: impl Fn(&H::Hash) -> Option<UncheckedSignedUnit<H, D, S>>,
+    ) -> Vec<ResolvedParents<H, D, S>> {
+        let units: HashMap<_, _> = units
+            .into_iter()
+            .map(|unit| (unit.as_signable().hash(), unit))
+            .collect();
+        let referencing: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_, parents)| {
+                parents.iter().any(
+                    |parent| matches!(parent, CompactUnit::HashRef(hash) if units.contains_key(hash)),
+                )
+            })
+            .map(|(u_hash, _)| *u_hash)
+            .collect();
+        let mut complete = Vec::new();
+        for u_hash in referencing {
+            let parents = self
+                .pending
+                .remove(&u_hash)
+                .expect("we just found the parents");
+            let lookup = |hash: &H::Hash| units.get(hash).cloned().or_else(|| lookup(hash));
+            if let Resolution::Complete(parents) = self.resolve(u_hash, parents, lookup) {
+                complete.push((u_hash, parents));
+            }
+        }
+        complete
+    }
+
+    /// The parents of the unit are not needed anymore.
+    pub fn forget(&mut self, u_hash: &H::Hash) {
+        self.pending.remove(u_hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dissemination::compact::{CompactResolver, CompactUnit, Resolution},
+        units::{
+            full_unit_to_unchecked_signed_unit, parent_eligibility::control_hash_matches,
+            random_full_parent_units_up_to, FullUnit, UncheckedSignedUnit, Unit,
+        },
+        NodeCount, NodeIndex, NodeMap,
+    };
+    use aleph_bft_mock::{Data, Hash64, Hasher64, Keychain, Signature};
+    use std::collections::HashMap;
+
+    type TestUnit = UncheckedSignedUnit<Hasher64, Data, Signature>;
+
+    const N_MEMBERS: NodeCount = NodeCount(20);
+    const SESSION_ID: u64 = 43;
+
+    /// A unit of round 2, together with its parents.
+    fn unit_with_parents() -> (FullUnit<Hasher64, Data>, Vec<TestUnit>) {
+        let mut units = random_full_parent_units_up_to(2, N_MEMBERS, SESSION_ID);
+        let child = units[2].remove(0);
+        let parents = units[1]
+            .iter()
+            .map(|unit| {
+                let keychain = Keychain::new(N_MEMBERS, unit.creator());
+                full_unit_to_unchecked_signed_unit(unit.clone(), &keychain)
+            })
+            .collect();
+        (child, parents)
+    }
+
+    fn lookup_in(units: &[TestUnit]) -> impl Fn(&Hash64) -> Option<TestUnit> + '_ {
+        |hash| {
+            units
+                .iter()
+                .find(|unit| unit.as_signable().hash() == *hash)
+                .cloned()
+        }
+    }
+
+    fn matches_control_hash(child: &FullUnit<Hasher64, Data>, parents: &[TestUnit]) -> bool {
+        let mut parent_map = NodeMap::with_size(N_MEMBERS);
+        for parent in parents {
+            let parent = parent.as_signable();
+            parent_map.insert(parent.creator(), (parent.hash(), parent.round()));
+        }
+        control_hash_matches(child.control_hash(), &parent_map)
+    }
+
+    #[test]
+    fn replaces_held_units_with_references() {
+        let (_, parents) = unit_with_parents();
+        let compact = CompactUnit::compact(parents.clone(), |coord| coord.creator().0 < 15);
+        assert_eq!(
+            compact.iter().filter(|unit| unit.full().is_some()).count(),
+            5
+        );
+        for (unit, parent) in compact.iter().zip(parents.iter()) {
+            match unit {
+                CompactUnit::HashRef(hash) => {
+                    assert!(parent.as_signable().creator().0 < 15);
+                    assert_eq!(*hash, parent.as_signable().hash());
+                }
+                CompactUnit::Full(unit) => assert_eq!(unit, parent),
+            }
+        }
+    }
+
+    #[test]
+    fn fetches_the_units_missing_locally() {
+        let (child, parents) = unit_with_parents();
+        // The sender believes we hold all the parents, but we only hold 15 of them.
+        let compact = CompactUnit::compact(parents.clone(), |_| true);
+        assert!(compact.iter().all(|unit| unit.full().is_none()));
+        let held = &parents[..15];
+        let mut resolver = CompactResolver::new();
+        let u_hash = child.hash();
+        let missing = match resolver.resolve(u_hash, compact, lookup_in(held)) {
+            Resolution::Missing(missing) => missing,
+            Resolution::Complete(_) => panic!("5 parents should be missing"),
+        };
+        let fetched: Vec<_> = parents[15..].to_vec();
+        let expected: Vec<_> = fetched
+            .iter()
+            .map(|unit| unit.as_signable().hash())
+            .collect();
+        assert_eq!(missing, expected);
+
+        // Unrelated units do not resolve anything.
+        assert!(resolver
+            .on_units(parents[..2].to_vec(), lookup_in(held))
+            .is_empty());
+        let mut complete = resolver.on_units(fetched, lookup_in(held));
+        assert_eq!(complete.len(), 1);
+        let (resolved_hash, resolved) = complete.pop().expect("there is a resolved unit");
+        assert_eq!(resolved_hash, u_hash);
+        assert_eq!(resolved, parents);
+        assert!(matches_control_hash(&child, &resolved));
+    }
+
+    #[test]
+    fn converges_when_missing_everything() {
+        let (child, parents) = unit_with_parents();
+        let compact =
+            CompactUnit::compact(parents.clone(), |coord| coord.creator() != NodeIndex(3));
+        let mut resolver = CompactResolver::new();
+        let u_hash = child.hash();
+        let missing = match resolver.resolve(u_hash, compact, lookup_in(&[])) {
+            Resolution::Missing(missing) => missing,
+            Resolution::Complete(_) => panic!("the references should be missing"),
+        };
+        assert_eq!(missing.len(), 19);
+        let available: HashMap<_, _> = parents
+            .iter()
+            .map(|unit| (unit.as_signable().hash(), unit.clone()))
+            .collect();
+        let fetched = missing
+            .iter()
+            .map(|hash| available.get(hash).expect("we requested a parent").clone())
+            .collect();
+        let complete = resolver.on_units(fetched, lookup_in(&[]));
+        assert_eq!(complete.len(), 1);
+        assert_eq!(complete[0].1, parents);
+        assert!(matches_control_hash(&child, &complete[0].1));
+    }
+
+    #[test]
+    fn forgets_parents_no_longer_needed() {
+        let (child, parents) = unit_with_parents();
+        let compact = CompactUnit::compact(parents.clone(), |_| true);
+        let mut resolver = CompactResolver::new();
+        let u_hash = child.hash();
+        assert!(matches!(
+            resolver.resolve(u_hash, compact, lookup_in(&[])),
+            Resolution::Missing(_)
+        ));
+        resolver.forget(&u_hash);
+        assert!(resolver.on_units(parents, lookup_in(&[])).is_empty());
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{Hasher, NodeCount, NodeIndex};
+use rand::seq::SliceRandom;
+use std::collections::{HashSet, VecDeque};
+
+/// How many units per member are remembered as seen, the oldest ones are forgotten first.
+pub(crate) const SEEN_UNITS_PER_MEMBER: usize = 64;
+
+/// Spreads units over random subsets of peers instead of sending them to everyone, see
+/// [`crate::BroadcastStrategy::Gossip`].
+///
+/// Every unit is passed on once, when it is seen for the first time, which is what stops the
+/// gossip from looping. The units seen are remembered up to a bound proportional to the size of
+/// the committee, a unit forgotten before it stops circulating is only passed on again.
+pub struct Gossip<H: Hasher> {
+    own_id: NodeIndex,
+    peers: Vec<NodeIndex>,
+    fanout: usize,
+    seen: HashSet<H::Hash>,
+    seen_order: VecDeque<H::Hash>,
+    capacity: usize,
+}
+
+impl<H: Hasher> Gossip<H> {
+    pub fn new(own_id: NodeIndex, n_members: NodeCount, fanout: usize) -> Self {
+        Gossip {
+            own_id,
+            peers: n_members
+                .into_iterator()
+                .filter(|peer| *peer != own_id)
+                .collect(),
+            fanout,
+            seen: HashSet::new(),
+            seen_order: VecDeque::new(),
+            capacity: SEEN_UNITS_PER_MEMBER * n_members.0,
+        }
+    }
+
+    /// Remembers the unit as seen, returns whether it was not seen before.
+    pub fn first_seen(&mut self, hash: H::Hash) -> bool {
+        if !self.seen.insert(hash) {
+            return false;
+        }
+        self.seen_order.push_back(hash);
+        if self.seen_order.len() > self.capacity {
+            if let Some(oldest) = self.seen_order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        true
+    }
+
+    /// Random peers to pass a unit of the given creator on to, never the creator itself.
+    pub fn peers(&self, creator: NodeIndex) -> Vec<NodeIndex> {
+        let candidates: Vec<_> = self
+            .peers
+            .iter()
+            .filter(|peer| **peer != creator || creator == self.own_id)
+            .copied()
+            .collect();
+        candidates
+            .choose_multiple(&mut rand::thread_rng(), self.fanout)
+            .copied()
+            .collect()
+    }
+
+    /// How many units are remembered as seen.
+    pub fn seen(&self) -> usize {
+        self.seen.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dissemination::gossip::{Gossip, SEEN_UNITS_PER_MEMBER},
+        NodeCount, NodeIndex,
+    };
+    use aleph_bft_mock::{Hash64, Hasher64};
+    use std::collections::HashSet;
+
+    const OWN_ID: NodeIndex = NodeIndex(0);
+    const N_MEMBERS: NodeCount = NodeCount(10);
+
+    fn hash(i: usize) -> Hash64 {
+        (i as u64).to_le_bytes()
+    }
+
+    #[test]
+    fn passes_units_on_once() {
+        let mut gossip = Gossip::<Hasher64>::new(OWN_ID, N_MEMBERS, 2);
+        assert!(gossip.first_seen(hash(1)));
+        assert!(!gossip.first_seen(hash(1)));
+        assert!(gossip.first_seen(hash(2)));
+        assert_eq!(gossip.seen(), 2);
+    }
+
+    #[test]
+    fn forgets_the_oldest_units() {
+        let mut gossip = Gossip::<Hasher64>::new(OWN_ID, N_MEMBERS, 2);
+        let capacity = SEEN_UNITS_PER_MEMBER * N_MEMBERS.0;
+        for i in 0..capacity + 1 {
+            assert!(gossip.first_seen(hash(i)));
+        }
+        assert_eq!(gossip.seen(), capacity);
+        assert!(!gossip.first_seen(hash(capacity)));
+        assert!(gossip.first_seen(hash(0)));
+    }
+
+    #[test]
+    fn picks_distinct_peers_other_than_us_and_the_creator() {
+        let gossip = Gossip::<Hasher64>::new(OWN_ID, N_MEMBERS, 3);
+        let creator = NodeIndex(4);
+        for _ in 0..100 {
+            let peers = gossip.peers(creator);
+            assert_eq!(peers.len(), 3);
+            assert_eq!(peers.iter().collect::<HashSet<_>>().len(), 3);
+            assert!(!peers.contains(&OWN_ID));
+            assert!(!peers.contains(&creator));
+        }
+        assert_eq!(gossip.peers(OWN_ID).len(), 3);
+    }
+
+    #[test]
+    fn fanout_beyond_the_committee_reaches_everyone() {
+        let gossip = Gossip::<Hasher64>::new(OWN_ID, N_MEMBERS, 20);
+        assert_eq!(gossip.peers(OWN_ID).len(), N_MEMBERS.0 - 1);
+        assert_eq!(gossip.peers(NodeIndex(4)).len(), N_MEMBERS.0 - 2);
+    }
+}
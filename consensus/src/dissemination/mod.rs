@@ -3,23 +3,72 @@ use crate::{
     units::{UncheckedSignedUnit, UnitCoord},
     Data, Hasher, NodeIndex, Signature, UncheckedSigned,
 };
+use codec::{Decode, Encode};
+use std::hash::{Hash as StdHash, Hasher as StdHasher};
 
+mod catch_up;
+mod compact;
+mod gossip;
+mod not_found;
+mod requests;
 mod responder;
+mod response_limiter;
 
-pub use responder::Responder;
+pub(crate) use catch_up::MAX_CATCH_UP_ROUNDS;
+pub use catch_up::{CatchUp, CatchUpStep, NewestUnitsResponse};
+pub use compact::{CompactResolver, CompactUnit, Resolution};
+pub use gossip::Gossip;
+pub use not_found::NotFoundLimiter;
+pub use requests::RequestManager;
+pub use responder::{Error as ResponderError, Responder};
+pub use response_limiter::{Refusal, ResponseLimiter, ResponseStats};
 
 /// Possible requests for information from other nodes.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Request<H: Hasher> {
     Coord(UnitCoord),
     Parents(H::Hash),
     NewestUnit(NodeIndex, Salt),
+    /// The units with the given hashes, referenced in a compact response of the peer.
+    Units(Vec<H::Hash>),
+}
+
+/// Identifies the requests that can be answered with a negative response, when we definitively
+/// don't have the requested units.
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+pub enum RequestId<H: Hasher> {
+    Coord(UnitCoord),
+    Parents(H::Hash),
+}
+
+impl<H: Hasher> RequestId<H> {
+    /// The identifier of the request, if it can be answered negatively.
+    pub fn of(request: &Request<H>) -> Option<Self> {
+        match request {
+            Request::Coord(coord) => Some(RequestId::Coord(*coord)),
+            Request::Parents(u_hash) => Some(RequestId::Parents(*u_hash)),
+            Request::NewestUnit(..) | Request::Units(_) => None,
+        }
+    }
+}
+
+impl<H: Hasher> StdHash for RequestId<H> {
+    fn hash<SH: StdHasher>(&self, state: &mut SH) {
+        match self {
+            RequestId::Coord(coord) => (0u8, coord).hash(state),
+            RequestId::Parents(u_hash) => (1u8, u_hash).hash(state),
+        }
+    }
 }
 
 /// Responses to requests.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Response<H: Hasher, D: Data, S: Signature> {
     Coord(UncheckedSignedUnit<H, D, S>),
     Parents(H::Hash, Vec<UncheckedSignedUnit<H, D, S>>),
     NewestUnit(UncheckedSigned<NewestUnitResponse<H, D, S>, S>),
+    /// Parents of the unit with the given hash, sent by the given node with the units the
+    /// requester most likely holds replaced by references.
+    CompactParents(NodeIndex, H::Hash, Vec<CompactUnit<H, D, S>>),
+    Units(Vec<UncheckedSignedUnit<H, D, S>>),
 }
@@ -0,0 +1,74 @@
+use crate::NodeIndex;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// The length of the window in which we limit the negative responses sent to a single peer.
+pub(crate) const NOT_FOUND_WINDOW: Duration = Duration::from_secs(1);
+
+/// How many negative responses we send to a single peer in a single window at most.
+pub(crate) const MAX_NOT_FOUND_PER_WINDOW: usize = 32;
+
+/// Limits the negative responses we send to every peer. They are cheap to solicit, so without
+/// a limit anyone could make us spam them by requesting units that don't exist.
+#[derive(Default)]
+pub struct NotFoundLimiter {
+    windows: HashMap<NodeIndex, (Instant, usize)>,
+}
+
+impl NotFoundLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether we can send another negative response to the peer at the given time. Counts the
+    /// response as sent if so.
+    pub fn try_send(&mut self, peer: NodeIndex, now: Instant) -> bool {
+        let (start, count) = self.windows.entry(peer).or_insert((now, 0));
+        if now.saturating_duration_since(*start) >= NOT_FOUND_WINDOW {
+            *start = now;
+            *count = 0;
+        }
+        if *count >= MAX_NOT_FOUND_PER_WINDOW {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dissemination::not_found::{NotFoundLimiter, MAX_NOT_FOUND_PER_WINDOW, NOT_FOUND_WINDOW},
+        NodeIndex,
+    };
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn limits_flood_from_single_peer() {
+        let mut limiter = NotFoundLimiter::new();
+        let now = Instant::now();
+        let sent = (0..10 * MAX_NOT_FOUND_PER_WINDOW)
+            .filter(|_| limiter.try_send(NodeIndex(1), now))
+            .count();
+        assert_eq!(sent, MAX_NOT_FOUND_PER_WINDOW);
+        assert!(limiter.try_send(NodeIndex(2), now));
+    }
+
+    #[test]
+    fn allows_more_in_next_window() {
+        let mut limiter = NotFoundLimiter::new();
+        let now = Instant::now();
+        for _ in 0..MAX_NOT_FOUND_PER_WINDOW {
+            assert!(limiter.try_send(NodeIndex(1), now));
+        }
+        assert!(!limiter.try_send(NodeIndex(1), now + NOT_FOUND_WINDOW / 2));
+        assert!(limiter.try_send(NodeIndex(1), now + NOT_FOUND_WINDOW));
+        assert!(limiter.try_send(
+            NodeIndex(1),
+            now + NOT_FOUND_WINDOW + Duration::from_millis(1)
+        ));
+    }
+}
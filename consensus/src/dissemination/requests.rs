@@ -0,0 +1,381 @@
+use crate::{dissemination::RequestId, Hasher, NodeCount, NodeIndex};
+use std::{
+    cmp::min,
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+/// How long a peer that left a request unanswered is asked only if no other peer can be, doubled
+/// with every further unanswered request.
+pub(crate) const INITIAL_PEER_BACKOFF: Duration = Duration::from_millis(500);
+
+/// The bound on the backoff of a peer, so that a peer that recovered is asked again eventually.
+pub(crate) const MAX_PEER_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+struct InFlight {
+    solicited: HashSet<NodeIndex>,
+    not_found: HashSet<NodeIndex>,
+    // The peers asked since the request was last repeated.
+    last_asked: Vec<NodeIndex>,
+}
+
+struct Backoff {
+    unanswered: u32,
+    until: Instant,
+}
+
+/// Keeps track of our requests for units by coord and for parents, from the moment they are
+/// needed until they get resolved or obsolete, whichever path the units arrive by.
+///
+/// A request that is already in flight is not started again. The recipients rotate through the
+/// peers, preferring the ones not asked about the request yet, and peers that did not answer are
+/// backed off exponentially: they are asked only when no other peer can be, until their backoff
+/// expires or they answer any request.
+pub struct RequestManager<H: Hasher> {
+    peers: Vec<NodeIndex>,
+    next_peer: usize,
+    in_flight: HashMap<RequestId<H>, InFlight>,
+    backoffs: HashMap<NodeIndex, Backoff>,
+    cancelled: usize,
+    suppressed: usize,
+}
+
+impl<H: Hasher> RequestManager<H> {
+    pub fn new(own_id: NodeIndex, n_members: NodeCount) -> Self {
+        RequestManager {
+            peers: n_members
+                .into_iterator()
+                .filter(|peer| *peer != own_id)
+                .collect(),
+            next_peer: 0,
+            in_flight: HashMap::new(),
+            backoffs: HashMap::new(),
+            cancelled: 0,
+            suppressed: 0,
+        }
+    }
+
+    /// Starts tracking the request, returns whether it was not in flight already.
+    pub fn start(&mut self, request_id: RequestId<H>) -> bool {
+        if self.in_flight.contains_key(&request_id) {
+            self.suppressed += 1;
+            return false;
+        }
+        self.in_flight.insert(request_id, InFlight::default());
+        true
+    }
+
+    pub fn is_in_flight(&self, request_id: &RequestId<H>) -> bool {
+        self.in_flight.contains_key(request_id)
+    }
+
+    /// How many requests are in flight.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// How many requests got cancelled as obsolete before being resolved.
+    pub fn cancelled(&self) -> usize {
+        self.cancelled
+    }
+
+    /// How many times a request was needed again while already in flight.
+    pub fn suppressed(&self) -> usize {
+        self.suppressed
+    }
+
+    /// The peers to send the request to this time, at most `count` of them. The peers asked the
+    /// previous time did not answer in time, unless they responded negatively, so they get backed
+    /// off.
+    pub fn recipients(
+        &mut self,
+        request_id: &RequestId<H>,
+        count: usize,
+        now: Instant,
+    ) -> Vec<NodeIndex> {
+        let unanswered: Vec<_> = match self.in_flight.get_mut(request_id) {
+            Some(in_flight) => in_flight
+                .last_asked
+                .drain(..)
+                .filter(|peer| !in_flight.not_found.contains(peer))
+                .collect(),
+            None => return Vec::new(),
+        };
+        for peer in unanswered {
+            self.back_off(peer, now);
+        }
+        self.pick(request_id, count, now)
+    }
+
+    /// A peer we did not ask yet, or at least one that did not respond negatively, to send the
+    /// request to right away.
+    pub fn next_peer(&mut self, request_id: &RequestId<H>, now: Instant) -> Option<NodeIndex> {
+        let in_flight = self.in_flight.get(request_id)?;
+        let exhausted = self
+            .peers
+            .iter()
+            .all(|peer| in_flight.not_found.contains(peer));
+        match exhausted {
+            true => None,
+            false => self.pick(request_id, 1, now).pop(),
+        }
+    }
+
+    /// Records that the request was sent to the peer.
+    pub fn on_sent(&mut self, request_id: &RequestId<H>, peer: NodeIndex) {
+        if let Some(in_flight) = self.in_flight.get_mut(request_id) {
+            in_flight.solicited.insert(peer);
+            in_flight.last_asked.push(peer);
+        }
+    }
+
+    /// The peer responded negatively, returns whether it was the first such response to a request
+    /// in flight that we sent to the peer. Only such responses count, otherwise anyone could make
+    /// us flood the other peers with requests.
+    pub fn on_not_found(&mut self, request_id: &RequestId<H>, peer: NodeIndex) -> bool {
+        let counts = match self.in_flight.get_mut(request_id) {
+            Some(in_flight) => {
+                in_flight.solicited.contains(&peer) && in_flight.not_found.insert(peer)
+            }
+            None => false,
+        };
+        if counts {
+            // The peer answers, even if it has nothing for us.
+            self.backoffs.remove(&peer);
+        }
+        counts
+    }
+
+    /// The peers we sent the request to that did not respond negatively, so one of them has to be
+    /// the sender of a response.
+    pub fn responders(&self, request_id: &RequestId<H>) -> HashSet<NodeIndex> {
+        match self.in_flight.get(request_id) {
+            Some(in_flight) => in_flight
+                .solicited
+                .difference(&in_flight.not_found)
+                .copied()
+                .collect(),
+            None => HashSet::new(),
+        }
+    }
+
+    /// The request got resolved, most likely by one of the peers asked the last time, so they are
+    /// no longer backed off.
+    pub fn on_resolved(&mut self, request_id: &RequestId<H>) {
+        if let Some(in_flight) = self.in_flight.remove(request_id) {
+            for peer in in_flight.last_asked {
+                self.backoffs.remove(&peer);
+            }
+        }
+    }
+
+    /// The request is no longer needed, so it is not repeated.
+    pub fn on_obsolete(&mut self, request_id: &RequestId<H>) {
+        if self.in_flight.remove(request_id).is_some() {
+            self.cancelled += 1;
+        }
+    }
+
+    fn back_off(&mut self, peer: NodeIndex, now: Instant) {
+        let backoff = self.backoffs.entry(peer).or_insert(Backoff {
+            unanswered: 0,
+            until: now,
+        });
+        let delay = INITIAL_PEER_BACKOFF.saturating_mul(2u32.saturating_pow(backoff.unanswered));
+        backoff.unanswered += 1;
+        backoff.until = now + min(delay, MAX_PEER_BACKOFF);
+    }
+
+    fn is_backed_off(&self, peer: &NodeIndex, now: Instant) -> bool {
+        self.backoffs
+            .get(peer)
+            .is_some_and(|backoff| backoff.until > now)
+    }
+
+    /// Picks the peers in the order of the rotation, preferring the peers that are not backed
+    /// off, then the ones not asked about the request yet, and then the ones that did not respond
+    /// negatively.
+    fn pick(&mut self, request_id: &RequestId<H>, count: usize, now: Instant) -> Vec<NodeIndex> {
+        let in_flight = match self.in_flight.get(request_id) {
+            Some(in_flight) => in_flight,
+            None => return Vec::new(),
+        };
+        let n_peers = self.peers.len();
+        let mut candidates: Vec<_> = (0..n_peers)
+            .map(|offset| (self.next_peer + offset) % n_peers)
+            .collect();
+        candidates.sort_by_key(|position| {
+            let peer = &self.peers[*position];
+            (
+                self.is_backed_off(peer, now),
+                in_flight.solicited.contains(peer),
+                in_flight.not_found.contains(peer),
+            )
+        });
+        candidates.truncate(count);
+        if let Some(last) = candidates.last() {
+            self.next_peer = (last + 1) % n_peers;
+        }
+        candidates
+            .into_iter()
+            .map(|position| self.peers[position])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dissemination::{
+            requests::{RequestManager, INITIAL_PEER_BACKOFF, MAX_PEER_BACKOFF},
+            RequestId,
+        },
+        units::UnitCoord,
+        NodeCount, NodeIndex,
+    };
+    use aleph_bft_mock::Hasher64;
+    use std::{collections::HashSet, time::Instant};
+
+    const OWN_ID: NodeIndex = NodeIndex(0);
+    const N_MEMBERS: NodeCount = NodeCount(4);
+
+    fn coord_request(round: u16) -> RequestId<Hasher64> {
+        RequestId::Coord(UnitCoord::new(round, NodeIndex(2)))
+    }
+
+    fn send(
+        manager: &mut RequestManager<Hasher64>,
+        request_id: &RequestId<Hasher64>,
+        count: usize,
+        now: Instant,
+    ) -> Vec<NodeIndex> {
+        let recipients = manager.recipients(request_id, count, now);
+        for peer in &recipients {
+            manager.on_sent(request_id, *peer);
+        }
+        recipients
+    }
+
+    #[test]
+    fn suppresses_requests_in_flight() {
+        let mut manager = RequestManager::new(OWN_ID, N_MEMBERS);
+        assert!(manager.start(coord_request(1)));
+        assert!(!manager.start(coord_request(1)));
+        assert!(manager.start(coord_request(2)));
+        assert_eq!(manager.in_flight(), 2);
+        assert_eq!(manager.suppressed(), 1);
+
+        manager.on_resolved(&coord_request(1));
+        manager.on_obsolete(&coord_request(2));
+        assert_eq!(manager.in_flight(), 0);
+        assert_eq!(manager.cancelled(), 1);
+        assert!(manager.start(coord_request(1)));
+        assert!(send(&mut manager, &coord_request(3), 1, Instant::now()).is_empty());
+    }
+
+    #[test]
+    fn rotates_through_peers() {
+        let mut manager = RequestManager::new(OWN_ID, N_MEMBERS);
+        let request_id = coord_request(1);
+        manager.start(request_id.clone());
+        let now = Instant::now();
+        let asked: Vec<_> = (0..3)
+            .flat_map(|_| send(&mut manager, &request_id, 1, now))
+            .collect();
+        let unique: HashSet<_> = asked.iter().collect();
+        assert_eq!(unique.len(), 3);
+        assert!(!unique.contains(&OWN_ID));
+    }
+
+    #[test]
+    fn backs_off_peers_that_do_not_answer() {
+        let mut manager = RequestManager::new(OWN_ID, N_MEMBERS);
+        let now = Instant::now();
+        let unanswered = coord_request(1);
+        manager.start(unanswered.clone());
+        let silent = send(&mut manager, &unanswered, 1, now)[0];
+        // Repeating the request backs off the peer that did not answer.
+        send(&mut manager, &unanswered, 1, now);
+
+        let request_id = coord_request(2);
+        manager.start(request_id.clone());
+        let recipients = manager.recipients(&request_id, 2, now);
+        assert_eq!(recipients.len(), 2);
+        assert!(!recipients.contains(&silent));
+        // Nobody else is left, so the backed off peer is asked after all.
+        assert!(manager.recipients(&request_id, 3, now).contains(&silent));
+        assert!(!manager.is_backed_off(&silent, now + INITIAL_PEER_BACKOFF));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_bound() {
+        let mut manager = RequestManager::new(OWN_ID, NodeCount(2));
+        let silent = NodeIndex(1);
+        let request_id = coord_request(1);
+        manager.start(request_id.clone());
+        let mut now = Instant::now();
+        for _ in 0..20 {
+            send(&mut manager, &request_id, 1, now);
+            now += INITIAL_PEER_BACKOFF;
+        }
+        send(&mut manager, &request_id, 1, now);
+        assert!(manager.is_backed_off(&silent, now + MAX_PEER_BACKOFF / 2));
+        assert!(!manager.is_backed_off(&silent, now + MAX_PEER_BACKOFF));
+    }
+
+    #[test]
+    fn answering_peers_are_not_backed_off() {
+        let mut manager = RequestManager::new(OWN_ID, N_MEMBERS);
+        let now = Instant::now();
+        let request_id = coord_request(1);
+        manager.start(request_id.clone());
+        let peer = send(&mut manager, &request_id, 1, now)[0];
+        assert!(manager.on_not_found(&request_id, peer));
+        send(&mut manager, &request_id, 1, now);
+        assert!(!manager.is_backed_off(&peer, now));
+
+        let unanswered = coord_request(2);
+        manager.start(unanswered.clone());
+        let silent = send(&mut manager, &unanswered, 1, now)[0];
+        send(&mut manager, &unanswered, 1, now);
+        assert!(manager.is_backed_off(&silent, now));
+        // A request sent to everyone got resolved, maybe by the backed off peer.
+        let resolved = coord_request(3);
+        manager.start(resolved.clone());
+        send(&mut manager, &resolved, 3, now);
+        manager.on_resolved(&resolved);
+        assert!(!manager.is_backed_off(&silent, now));
+    }
+
+    #[test]
+    fn counts_only_first_solicited_not_found() {
+        let mut manager = RequestManager::new(OWN_ID, N_MEMBERS);
+        let now = Instant::now();
+        let request_id = coord_request(1);
+        manager.start(request_id.clone());
+        let peer = send(&mut manager, &request_id, 1, now)[0];
+        let other = manager
+            .next_peer(&request_id, now)
+            .expect("there are other peers");
+        assert_ne!(other, peer);
+        assert!(!manager.on_not_found(&request_id, other));
+        assert!(!manager.on_not_found(&coord_request(2), peer));
+        assert!(manager.on_not_found(&request_id, peer));
+        assert!(!manager.on_not_found(&request_id, peer));
+        assert!(manager.responders(&request_id).is_empty());
+    }
+
+    #[test]
+    fn no_next_peer_once_everyone_responded_negatively() {
+        let mut manager = RequestManager::new(OWN_ID, N_MEMBERS);
+        let now = Instant::now();
+        let request_id = coord_request(1);
+        manager.start(request_id.clone());
+        for peer in send(&mut manager, &request_id, 3, now) {
+            assert!(manager.next_peer(&request_id, now).is_some());
+            manager.on_not_found(&request_id, peer);
+        }
+        assert_eq!(manager.next_peer(&request_id, now), None);
+    }
+}
@@ -1,9 +1,9 @@
 use crate::{
     dag::DagUnit,
-    dissemination::{Request, Response},
-    runway::{NewestUnitResponse, Salt},
+    dissemination::{NewestUnitsResponse, Request, Response, MAX_CATCH_UP_ROUNDS},
+    runway::{DagDigest, NewestUnitResponse, Salt, SignedNewestUnits},
     units::{UnitCoord, UnitStore, UnitWithParents, WrappedUnit},
-    Data, Hasher, MultiKeychain, NodeIndex, Signed,
+    Data, Hasher, Keychain, MultiKeychain, NodeIndex, Signed,
 };
 use std::marker::PhantomData;
 use thiserror::Error;
@@ -11,9 +11,13 @@ use thiserror::Error;
 /// A responder that is able to answer requests for data about units.
 pub struct Responder<H: Hasher, D: Data, MK: MultiKeychain> {
     keychain: MK,
+    max_units_per_message: usize,
     _phantom: PhantomData<(H, D)>,
 }
 
+/// The signed parts of a response to a request for the newest units.
+type NewestUnitsParts<H, D, MK> = Vec<SignedNewestUnits<H, D, <MK as Keychain>::Signature>>;
+
 /// Ways in which it can be impossible for us to respond to a request.
 #[derive(Eq, Error, Debug, PartialEq)]
 pub enum Error<H: Hasher> {
@@ -21,17 +25,35 @@ pub enum Error<H: Hasher> {
     NoCanonicalAt(UnitCoord),
     #[error("unit with hash {0:?} not known")]
     UnknownUnit(H::Hash),
+    #[error("failed to sign a response for {0:?}: {1}")]
+    SigningFailed(NodeIndex, String),
+    #[error("none of the requested units is known")]
+    NoRequestedUnits,
+    #[error("the round of {0} was pruned")]
+    PrunedCoord(UnitCoord),
+    #[error("some parents of unit with hash {0:?} were pruned")]
+    PrunedParents(H::Hash),
 }
 
 impl<H: Hasher, D: Data, MK: MultiKeychain> Responder<H, D, MK> {
     /// Create a new responder.
     pub fn new(keychain: MK) -> Self {
+        let max_units_per_message = keychain.node_count().0;
         Responder {
             keychain,
+            max_units_per_message,
             _phantom: PhantomData,
         }
     }
 
+    /// Sets how many units we send in a single message at most, the committee size by default.
+    pub fn with_max_units_per_message(self, max_units_per_message: usize) -> Self {
+        Responder {
+            max_units_per_message,
+            ..self
+        }
+    }
+
     fn index(&self) -> NodeIndex {
         self.keychain.index()
     }
@@ -41,6 +63,9 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Responder<H, D, MK> {
         coord: UnitCoord,
         units: &UnitStore<DagUnit<H, D, MK>>,
     ) -> Result<Response<H, D, MK::Signature>, Error<H>> {
+        if units.is_pruned(coord) {
+            return Err(Error::PrunedCoord(coord));
+        }
         units
             .canonical_unit(coord)
             .map(|unit| Response::Coord(unit.clone().unpack().into()))
@@ -52,23 +77,18 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Responder<H, D, MK> {
         hash: H::Hash,
         units: &UnitStore<DagUnit<H, D, MK>>,
     ) -> Result<Response<H, D, MK::Signature>, Error<H>> {
-        units
-            .unit(&hash)
-            .map(|unit| {
-                let parents = unit
-                    .parents()
-                    .map(|parent_hash| {
-                        units
-                            .unit(parent_hash)
-                            .expect("Units are added to the store in order.")
-                            .clone()
-                            .unpack()
-                            .into_unchecked()
-                    })
-                    .collect();
-                Response::Parents(hash, parents)
+        let unit = units.unit(&hash).ok_or(Error::UnknownUnit(hash))?;
+        // Units are added to the store in order, so only pruning can make parents absent.
+        let parents = unit
+            .parents()
+            .map(|parent_hash| {
+                units
+                    .unit(parent_hash)
+                    .map(|parent| parent.clone().unpack().into_unchecked())
             })
-            .ok_or(Error::UnknownUnit(hash))
+            .collect::<Option<_>>()
+            .ok_or(Error::PrunedParents(hash))?;
+        Ok(Response::Parents(hash, parents))
     }
 
     fn on_request_newest(
@@ -76,15 +96,81 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Responder<H, D, MK> {
         requester: NodeIndex,
         salt: Salt,
         units: &UnitStore<DagUnit<H, D, MK>>,
-    ) -> Response<H, D, MK::Signature> {
+    ) -> Result<Response<H, D, MK::Signature>, Error<H>> {
         let unit = units
             .canonical_units(requester)
             .last()
             .map(|unit| unit.clone().unpack().into_unchecked());
         let response = NewestUnitResponse::new(requester, self.index(), unit, salt);
 
-        let signed_response = Signed::sign(response, &self.keychain).into_unchecked();
-        Response::NewestUnit(signed_response)
+        let signed_response = Signed::sign(response, &self.keychain)
+            .map_err(|e| Error::SigningFailed(requester, e.to_string()))?
+            .into_unchecked();
+        Ok(Response::NewestUnit(signed_response))
+    }
+
+    fn on_request_units(
+        &self,
+        hashes: Vec<H::Hash>,
+        units: &UnitStore<DagUnit<H, D, MK>>,
+    ) -> Result<Response<H, D, MK::Signature>, Error<H>> {
+        // A unit has at most one parent per creator, so no honest request is longer.
+        let found: Vec<_> = hashes
+            .iter()
+            .take(self.keychain.node_count().0)
+            .filter_map(|hash| units.unit(hash))
+            .map(|unit| unit.clone().unpack().into_unchecked())
+            .collect();
+        match found.is_empty() {
+            true => Err(Error::NoRequestedUnits),
+            false => Ok(Response::Units(found)),
+        }
+    }
+
+    /// Answer a request for the newest units of a node with the given digest. These are the
+    /// canonical units it lacks, starting from the lowest round it lacks a unit of, or the lowest
+    /// one we did not prune, and spanning at most [`MAX_CATCH_UP_ROUNDS`] rounds, sorted by rounds. They are split into signed parts
+    /// fitting in single messages, there is always at least one part.
+    pub fn handle_newest_units_request(
+        &self,
+        requester: NodeIndex,
+        salt: Salt,
+        digest: &DagDigest,
+        units: &UnitStore<DagUnit<H, D, MK>>,
+    ) -> Result<NewestUnitsParts<H, D, MK>, Error<H>> {
+        let first_round = digest.lowest_missing_round().max(units.pruned_below());
+        let top_round = units.status().top_round();
+        let mut missing = Vec::new();
+        for round in first_round..first_round.saturating_add(MAX_CATCH_UP_ROUNDS) {
+            if round > top_round {
+                break;
+            }
+            for creator in self.keychain.node_count().into_iterator() {
+                let coord = UnitCoord::new(round, creator);
+                if digest.holds(coord) {
+                    continue;
+                }
+                if let Some(unit) = units.canonical_unit(coord) {
+                    missing.push(unit.clone().unpack().into_unchecked());
+                }
+            }
+        }
+        let mut parts: Vec<_> = missing
+            .chunks(self.max_units_per_message.max(1))
+            .map(<[_]>::to_vec)
+            .collect();
+        if parts.is_empty() {
+            parts.push(Vec::new());
+        }
+        parts
+            .into_iter()
+            .map(|part| {
+                let response = NewestUnitsResponse::new(requester, self.index(), salt, part);
+                Signed::sign(response, &self.keychain)
+                    .map(Signed::into_unchecked)
+                    .map_err(|e| Error::SigningFailed(requester, e.to_string()))
+            })
+            .collect()
     }
 
     /// Handle an incoming request returning either the appropriate response or an error if we
@@ -98,7 +184,8 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Responder<H, D, MK> {
         match request {
             Coord(coord) => self.on_request_coord(coord, units),
             Parents(hash) => self.on_request_parents(hash, units),
-            NewestUnit(node_id, salt) => Ok(self.on_request_newest(node_id, salt, units)),
+            NewestUnit(node_id, salt) => self.on_request_newest(node_id, salt, units),
+            Units(hashes) => self.on_request_units(hashes, units),
         }
     }
 }
@@ -110,6 +197,7 @@ mod test {
             responder::{Error, Responder},
             Request, Response,
         },
+        runway::DagDigest,
         units::{
             random_full_parent_reconstrusted_units_up_to, TestingDagUnit, Unit, UnitCoord,
             UnitStore, UnitWithParents, WrappedUnit,
@@ -129,7 +217,7 @@ mod test {
     ) {
         let keychains = Keychain::new_vec(NODE_COUNT);
         (
-            Responder::new(keychains[NODE_ID.0]),
+            Responder::new(keychains[NODE_ID.0].clone()),
             UnitStore::new(NODE_COUNT),
             keychains,
         )
@@ -183,7 +271,7 @@ mod test {
                 );
                 assert!(checked_newest_unit_response
                     .as_signable()
-                    .included_data()
+                    .included_data_with_creators()
                     .is_empty());
             }
             other => panic!("Unexpected response: {:?}.", other),
@@ -303,6 +391,67 @@ mod test {
         }
     }
 
+    #[test]
+    fn refuses_requests_reaching_pruned_rounds() {
+        let (responder, mut store, keychains) = setup();
+        let session_id = 2137;
+        let units =
+            random_full_parent_reconstrusted_units_up_to(5, NODE_COUNT, session_id, &keychains);
+        for round_units in &units {
+            for unit in round_units {
+                store.insert(unit.clone());
+            }
+        }
+        store.prune_below(3);
+        let coord = UnitCoord::new(2, NodeIndex(1));
+        match responder.handle_request(Request::Coord(coord), &store) {
+            Ok(response) => panic!("Unexpected response: {:?}.", response),
+            Err(err) => assert_eq!(err, Error::PrunedCoord(coord)),
+        }
+        let hash = units[3][1].hash();
+        match responder.handle_request(Request::Parents(hash), &store) {
+            Ok(response) => panic!("Unexpected response: {:?}.", response),
+            Err(err) => assert_eq!(err, Error::PrunedParents(hash)),
+        }
+        let request = Request::Parents(units[4][1].hash());
+        assert!(matches!(
+            responder.handle_request(request, &store),
+            Ok(Response::Parents(_, _))
+        ));
+    }
+
+    #[test]
+    fn responds_with_the_known_requested_units() {
+        let (responder, mut store, keychains) = setup();
+        let session_id = 2137;
+        let units =
+            random_full_parent_reconstrusted_units_up_to(2, NODE_COUNT, session_id, &keychains);
+        for unit in &units[1] {
+            store.insert(unit.clone());
+        }
+        let unknown = units[2][0].hash();
+        let requested: Vec<_> = units[1].iter().take(3).map(|unit| unit.hash()).collect();
+        let request = Request::Units(vec![requested[0], unknown, requested[1], requested[2]]);
+        let response = responder
+            .handle_request(request, &store)
+            .expect("should successfully respond");
+        match response {
+            Response::Units(response_units) => {
+                let hashes: Vec<_> = response_units
+                    .iter()
+                    .map(|unit| unit.as_signable().hash())
+                    .collect();
+                assert_eq!(hashes, requested);
+            }
+            other => panic!("Unexpected response: {:?}.", other),
+        }
+        let request = Request::Units(vec![unknown]);
+        match responder.handle_request(request, &store) {
+            Ok(response) => panic!("Unexpected response: {:?}.", response),
+            Err(err) => assert_eq!(err, Error::NoRequestedUnits),
+        }
+    }
+
     #[test]
     fn responds_to_existing_newest() {
         let (responder, mut store, keychains) = setup();
@@ -334,4 +483,61 @@ mod test {
             other => panic!("Unexpected response: {:?}.", other),
         }
     }
+
+    #[test]
+    fn responds_with_newest_units_in_parts() {
+        let (responder, mut store, keychains) = setup();
+        let responder = responder.with_max_units_per_message(4);
+        let session_id = 2137;
+        let units =
+            random_full_parent_reconstrusted_units_up_to(5, NODE_COUNT, session_id, &keychains);
+        let mut digest = DagDigest::new(NODE_COUNT);
+        for round_units in &units {
+            for unit in round_units {
+                store.insert(unit.clone());
+                if unit.round() < 2 || (unit.round() == 2 && unit.creator() != NodeIndex(3)) {
+                    digest.add_unit::<Hasher64>(unit.creator(), unit.round(), &unit.hash());
+                }
+            }
+        }
+        let requester = NodeIndex(1);
+        let salt = rand::random();
+        let parts = responder
+            .handle_newest_units_request(requester, salt, &digest, &store)
+            .expect("the keychain never fails");
+        assert_eq!(parts.len(), 6);
+        let mut received = Vec::new();
+        for part in parts {
+            let part = part
+                .check(&keychains[NODE_ID.0])
+                .expect("should sign correctly")
+                .into_signable();
+            assert_eq!(part.requester(), requester);
+            assert_eq!(part.salt(), salt);
+            assert!(part.units().len() <= 4);
+            received.extend(part.into_units());
+        }
+        assert_eq!(received.len(), 1 + 3 * NODE_COUNT.0);
+        assert_eq!(
+            received[0].as_signable().coord(),
+            UnitCoord::new(2, NodeIndex(3))
+        );
+        let rounds: Vec<_> = received
+            .iter()
+            .map(|unit| unit.as_signable().round())
+            .collect();
+        assert!(rounds.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        for unit in &units[2] {
+            digest.add_unit::<Hasher64>(unit.creator(), unit.round(), &unit.hash());
+        }
+        for unit in units[3..].iter().flatten() {
+            digest.add_unit::<Hasher64>(unit.creator(), unit.round(), &unit.hash());
+        }
+        let parts = responder
+            .handle_newest_units_request(requester, salt, &digest, &store)
+            .expect("the keychain never fails");
+        assert_eq!(parts.len(), 1);
+        assert!(parts[0].as_signable().units().is_empty());
+    }
 }
@@ -0,0 +1,258 @@
+use crate::{
+    dissemination::{RequestId, Response},
+    Data, Hasher, NodeCount, NodeIndex, NodeMap, ResponseLimits, Signature,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{Display, Formatter, Result as FmtResult},
+    time::{Duration, Instant},
+};
+
+/// The length of the window in which we limit the responses sent to a single peer.
+pub(crate) const RESPONSE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Why a request of a peer is not answered.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Refusal {
+    /// We answered the same request of the peer within the dedup window.
+    Repeated,
+    /// The peer got all the responses it can get in the current window.
+    RateLimited,
+}
+
+/// How many requests by coord and for parents were answered and how many were refused.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ResponseStats {
+    /// The requests answered positively.
+    pub answered: usize,
+    /// The answered requests for which the response was cached.
+    pub from_cache: usize,
+    /// The requests dropped as repeated.
+    pub repeated: usize,
+    /// The requests dropped as over the rate limit.
+    pub rate_limited: usize,
+}
+
+impl Display for ResponseStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{} answered, {} of them from the cache, {} dropped as repeated, {} over the rate limit",
+            self.answered, self.from_cache, self.repeated, self.rate_limited
+        )
+    }
+}
+
+#[derive(Clone)]
+struct PeerWindow<H: Hasher> {
+    start: Instant,
+    sent: usize,
+    answered: HashMap<RequestId<H>, Instant>,
+}
+
+/// Limits the positive responses to requests by coord and for parents we send to every peer, see
+/// [`ResponseLimits`], and caches the responses sent last. They carry whole units, so without
+/// a limit anyone could make us spam them by repeating requests for units we have.
+///
+/// Requests are not authenticated, so the budget is per index the requester claims, not per
+/// peer. Only the indices of the committee other than our own have a budget, the caller has to
+/// drop the requests claiming any other index before they get here.
+pub struct ResponseLimiter<H: Hasher, D: Data, S: Signature> {
+    limits: ResponseLimits,
+    peers: NodeMap<PeerWindow<H>>,
+    cache: HashMap<RequestId<H>, Response<H, D, S>>,
+    cached_order: VecDeque<RequestId<H>>,
+    stats: ResponseStats,
+}
+
+impl<H: Hasher, D: Data, S: Signature> ResponseLimiter<H, D, S> {
+    pub fn new(n_members: NodeCount, limits: ResponseLimits) -> Self {
+        ResponseLimiter {
+            limits,
+            peers: NodeMap::with_size(n_members),
+            cache: HashMap::new(),
+            cached_order: VecDeque::new(),
+            stats: ResponseStats::default(),
+        }
+    }
+
+    /// Whether we can answer the request of the peer at the given time. Only responses recorded
+    /// with [`ResponseLimiter::on_answered`] count, so requests we could not answer positively
+    /// can be repeated at will, negative responses are limited separately.
+    pub fn check(
+        &mut self,
+        peer: NodeIndex,
+        request: &RequestId<H>,
+        now: Instant,
+    ) -> Result<(), Refusal> {
+        let Some(window) = self.peers.get_mut(peer) else {
+            return Ok(());
+        };
+        if now.saturating_duration_since(window.start) >= RESPONSE_WINDOW {
+            let dedup_window = self.limits.dedup_window;
+            window.start = now;
+            window.sent = 0;
+            window
+                .answered
+                .retain(|_, at| now.saturating_duration_since(*at) < dedup_window);
+        }
+        if window
+            .answered
+            .get(request)
+            .is_some_and(|at| now.saturating_duration_since(*at) < self.limits.dedup_window)
+        {
+            self.stats.repeated += 1;
+            return Err(Refusal::Repeated);
+        }
+        if window.sent >= self.limits.max_per_second {
+            self.stats.rate_limited += 1;
+            return Err(Refusal::RateLimited);
+        }
+        Ok(())
+    }
+
+    /// The cached response to the request, if any.
+    pub fn cached(&mut self, request: &RequestId<H>) -> Option<Response<H, D, S>> {
+        let response = self.cache.get(request).cloned();
+        if response.is_some() {
+            self.stats.from_cache += 1;
+        }
+        response
+    }
+
+    /// Counts the response to the request of the peer as sent at the given time, and caches it,
+    /// evicting the response cached first if the cache is full.
+    pub fn on_answered(
+        &mut self,
+        peer: NodeIndex,
+        request: RequestId<H>,
+        response: &Response<H, D, S>,
+        now: Instant,
+    ) {
+        self.stats.answered += 1;
+        if self.peers.get(peer).is_none() {
+            self.peers.insert(
+                peer,
+                PeerWindow {
+                    start: now,
+                    sent: 0,
+                    answered: HashMap::new(),
+                },
+            );
+        }
+        let window = self.peers.get_mut(peer).expect("just inserted");
+        window.sent += 1;
+        if !self.limits.dedup_window.is_zero() {
+            window.answered.insert(request.clone(), now);
+        }
+        if self.limits.cache_size == 0 || self.cache.contains_key(&request) {
+            return;
+        }
+        if self.cache.len() >= self.limits.cache_size {
+            if let Some(evicted) = self.cached_order.pop_front() {
+                self.cache.remove(&evicted);
+            }
+        }
+        self.cached_order.push_back(request.clone());
+        self.cache.insert(request, response.clone());
+    }
+
+    pub fn stats(&self) -> ResponseStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dissemination::{
+            response_limiter::{Refusal, ResponseLimiter, RESPONSE_WINDOW},
+            RequestId, Response,
+        },
+        units::{full_unit_to_unchecked_signed_unit, random_full_parent_units_up_to, UnitCoord},
+        NodeCount, NodeIndex, ResponseLimits,
+    };
+    use aleph_bft_mock::{Data, Hasher64, Keychain, Signature};
+    use std::time::{Duration, Instant};
+
+    const LIMITS: ResponseLimits = ResponseLimits {
+        dedup_window: Duration::from_millis(500),
+        max_per_second: 8,
+        cache_size: 2,
+    };
+
+    fn request(round: u16) -> RequestId<Hasher64> {
+        RequestId::Coord(UnitCoord::new(round, NodeIndex(0)))
+    }
+
+    fn response() -> Response<Hasher64, Data, Signature> {
+        let keychain = Keychain::new(NodeCount(4), NodeIndex(0));
+        let unit = random_full_parent_units_up_to(0, NodeCount(4), 0)[0][0].clone();
+        Response::Coord(full_unit_to_unchecked_signed_unit(unit, &keychain))
+    }
+
+    fn answer(
+        limiter: &mut ResponseLimiter<Hasher64, Data, Signature>,
+        peer: NodeIndex,
+        request: RequestId<Hasher64>,
+        now: Instant,
+    ) -> Result<(), Refusal> {
+        limiter.check(peer, &request, now)?;
+        limiter.on_answered(peer, request, &response(), now);
+        Ok(())
+    }
+
+    #[test]
+    fn drops_repeated_requests_within_dedup_window() {
+        let mut limiter = ResponseLimiter::new(NodeCount(4), LIMITS);
+        let now = Instant::now();
+        assert_eq!(answer(&mut limiter, NodeIndex(1), request(0), now), Ok(()));
+        assert_eq!(
+            answer(&mut limiter, NodeIndex(1), request(0), now),
+            Err(Refusal::Repeated)
+        );
+        assert_eq!(answer(&mut limiter, NodeIndex(2), request(0), now), Ok(()));
+        assert_eq!(
+            answer(
+                &mut limiter,
+                NodeIndex(1),
+                request(0),
+                now + LIMITS.dedup_window
+            ),
+            Ok(())
+        );
+        assert_eq!(limiter.stats().repeated, 1);
+    }
+
+    #[test]
+    fn limits_flood_of_distinct_requests_from_single_peer() {
+        let mut limiter = ResponseLimiter::new(NodeCount(4), LIMITS);
+        let now = Instant::now();
+        let answered = (0..10 * LIMITS.max_per_second as u16)
+            .filter(|round| answer(&mut limiter, NodeIndex(1), request(*round), now).is_ok())
+            .count();
+        assert_eq!(answered, LIMITS.max_per_second);
+        assert_eq!(
+            limiter.check(NodeIndex(1), &request(1000), now),
+            Err(Refusal::RateLimited)
+        );
+        assert_eq!(limiter.check(NodeIndex(2), &request(1000), now), Ok(()));
+        assert_eq!(
+            limiter.check(NodeIndex(1), &request(1000), now + RESPONSE_WINDOW),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn caches_last_responses() {
+        let mut limiter = ResponseLimiter::new(NodeCount(4), LIMITS);
+        let now = Instant::now();
+        for round in 0..3 {
+            answer(&mut limiter, NodeIndex(1), request(round), now).expect("under the limit");
+        }
+        assert!(limiter.cached(&request(0)).is_none());
+        assert!(limiter.cached(&request(1)).is_some());
+        assert!(limiter.cached(&request(2)).is_some());
+        assert_eq!(limiter.stats().from_cache, 2);
+    }
+}
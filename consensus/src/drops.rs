@@ -0,0 +1,396 @@
+use crate::{ClockSource, NodeIndex};
+use log::{info, warn};
+use parking_lot::Mutex;
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+const LOG_TARGET: &str = "AlephBFT-drops";
+
+/// How many bytes of the encoding of a sampled message are logged at most.
+pub const DROP_SAMPLE_PREFIX: usize = 64;
+
+/// Why a message was intentionally dropped.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum DropReason {
+    /// A unit failing validation, e.g. of another session or with a wrong signature.
+    InvalidUnit,
+    /// A copy of a unit that recently failed validation.
+    KnownInvalidUnit,
+    /// A unit of a known forker we have no commitment to.
+    UncommittedUnit,
+    /// A response that came after we no longer needed it.
+    StaleResponse,
+    /// A unit message the session does not expect from the network, e.g. a request for units
+    /// claiming to come from outside the committee or from us.
+    UnexpectedMessage,
+    /// A negative response to a request we did not send to that peer.
+    UnsolicitedNotFound,
+    /// A negative response of ours not sent, as the requester got too many recently.
+    NotFoundRateLimited,
+    /// A digest that does not describe the committee.
+    MalformedDigest,
+    /// A digest sent too soon after the previous one.
+    DigestTooSoon,
+    /// A unit message received or created while the session is frozen.
+    Frozen,
+    /// A broadcast of ours repeating one sent just before.
+    RepeatedBroadcast,
+    /// A fork alert failing validation.
+    InvalidAlert,
+    /// A fork alert of another session.
+    AlertWrongSession,
+    /// A fork alert we already know.
+    RepeatedAlert,
+    /// A request for a fork alert we do not know.
+    UnknownAlertRequest,
+    /// A reliable multicast message about an alert whose multicast we do not take part in.
+    UnsolicitedRmcMessage,
+    /// An alert of ours about a forker we are already holding an alert back for.
+    ThrottledAlert,
+    /// A message that would make an observer sign something or take part in alerts.
+    Observing,
+    /// A message from the network that did not fit in a full queue, see
+    /// [`crate::Config::with_max_pending_messages`].
+    QueueFull,
+    /// A message of an alert RMC that already completed.
+    CompletedRmc,
+    /// A message from the network over the limits of the configuration, see
+    /// [`crate::Config::with_max_units_per_message`] and [`crate::Config::with_max_message_size`],
+    /// also once decompressed.
+    OversizedMessage,
+    /// A unit from the network too many rounds above our own DAG, see
+    /// [`crate::Config::with_max_round_lead`].
+    TooFarAhead,
+    /// A compressed message from the network that does not decompress to a message, or that we
+    /// cannot decompress, being built without the `zstd` feature.
+    MalformedCompression,
+    /// A response to a request for the newest units we did not send to that peer, or with a
+    /// wrong signature.
+    UnsolicitedNewestUnits,
+    /// A request for the newest units not answered, as the requester sent another one recently.
+    NewestUnitsRateLimited,
+    /// A unit of a round pruned from our store, see [`crate::Config::with_kept_rounds`].
+    PrunedUnit,
+    /// A unit from the network that came while too many units wait for their data, see
+    /// [`crate::Config::with_availability_checks`].
+    TooManyUnavailable,
+    /// A message from the network encoded at a wire version newer than we support, see
+    /// [`crate::MAX_SUPPORTED_VERSION`].
+    UnsupportedVersion,
+    /// A response with parents that do not match the control hash of the unit they were
+    /// requested for.
+    InconsistentParents,
+    /// A request for units by coord or for parents we answered for that peer just before, see
+    /// [`crate::Config::with_response_limits`].
+    RepeatedRequest,
+    /// A request for units by coord or for parents not answered, as the requester got too many
+    /// responses recently.
+    RequestRateLimited,
+}
+
+impl DropReason {
+    /// All the reasons.
+    pub const ALL: [DropReason; 31] = [
+        DropReason::InvalidUnit,
+        DropReason::KnownInvalidUnit,
+        DropReason::UncommittedUnit,
+        DropReason::StaleResponse,
+        DropReason::UnexpectedMessage,
+        DropReason::UnsolicitedNotFound,
+        DropReason::NotFoundRateLimited,
+        DropReason::MalformedDigest,
+        DropReason::DigestTooSoon,
+        DropReason::Frozen,
+        DropReason::RepeatedBroadcast,
+        DropReason::InvalidAlert,
+        DropReason::AlertWrongSession,
+        DropReason::RepeatedAlert,
+        DropReason::UnknownAlertRequest,
+        DropReason::UnsolicitedRmcMessage,
+        DropReason::ThrottledAlert,
+        DropReason::Observing,
+        DropReason::QueueFull,
+        DropReason::CompletedRmc,
+        DropReason::OversizedMessage,
+        DropReason::TooFarAhead,
+        DropReason::MalformedCompression,
+        DropReason::UnsolicitedNewestUnits,
+        DropReason::NewestUnitsRateLimited,
+        DropReason::PrunedUnit,
+        DropReason::TooManyUnavailable,
+        DropReason::UnsupportedVersion,
+        DropReason::InconsistentParents,
+        DropReason::RepeatedRequest,
+        DropReason::RequestRateLimited,
+    ];
+
+    fn position(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl Display for DropReason {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let name = match self {
+            DropReason::InvalidUnit => "invalid unit",
+            DropReason::KnownInvalidUnit => "known invalid unit",
+            DropReason::UncommittedUnit => "uncommitted unit",
+            DropReason::StaleResponse => "stale response",
+            DropReason::UnexpectedMessage => "unexpected message",
+            DropReason::UnsolicitedNotFound => "unsolicited not found",
+            DropReason::NotFoundRateLimited => "rate limited not found",
+            DropReason::MalformedDigest => "malformed digest",
+            DropReason::DigestTooSoon => "digest too soon",
+            DropReason::Frozen => "frozen",
+            DropReason::RepeatedBroadcast => "repeated broadcast",
+            DropReason::InvalidAlert => "invalid alert",
+            DropReason::AlertWrongSession => "alert of wrong session",
+            DropReason::RepeatedAlert => "repeated alert",
+            DropReason::UnknownAlertRequest => "unknown alert request",
+            DropReason::UnsolicitedRmcMessage => "unsolicited multicast message",
+            DropReason::ThrottledAlert => "throttled alert",
+            DropReason::Observing => "observing",
+            DropReason::QueueFull => "queue full",
+            DropReason::CompletedRmc => "completed rmc",
+            DropReason::OversizedMessage => "oversized message",
+            DropReason::TooFarAhead => "too far ahead",
+            DropReason::MalformedCompression => "malformed compression",
+            DropReason::UnsolicitedNewestUnits => "unsolicited newest units",
+            DropReason::NewestUnitsRateLimited => "rate limited newest units request",
+            DropReason::PrunedUnit => "unit of pruned round",
+            DropReason::TooManyUnavailable => "too many unavailable units",
+            DropReason::UnsupportedVersion => "unsupported wire version",
+            DropReason::InconsistentParents => "inconsistent parents",
+            DropReason::RepeatedRequest => "repeated request",
+            DropReason::RequestRateLimited => "rate limited request",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The numbers of messages dropped for every reason.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DropStats {
+    counts: [usize; DropReason::ALL.len()],
+    samples_logged: usize,
+}
+
+impl DropStats {
+    /// The number of messages dropped for the given reason.
+    pub fn count(&self, reason: DropReason) -> usize {
+        self.counts[reason.position()]
+    }
+
+    /// The number of messages dropped for any reason.
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// The number of dropped messages logged as samples, see [`DropStatsHandle::sample`].
+    pub fn samples_logged(&self) -> usize {
+        self.samples_logged
+    }
+}
+
+impl Display for DropStats {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{} dropped", self.total())?;
+        for reason in DropReason::ALL {
+            let count = self.count(reason);
+            if count > 0 {
+                write!(f, "; {} - {}", reason, count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Sampling {
+    every: usize,
+    until: Instant,
+}
+
+struct SharedState {
+    stats: DropStats,
+    sampling: Option<Sampling>,
+    clock: ClockSource,
+}
+
+/// Allows the application to see how many messages the session drops intentionally, and why,
+/// e.g. to find out why some data never gets finalized.
+#[derive(Clone)]
+pub struct DropStatsHandle {
+    shared: Arc<Mutex<SharedState>>,
+}
+
+impl DropStatsHandle {
+    /// The numbers of dropped messages so far.
+    pub fn stats(&self) -> DropStats {
+        self.shared.lock().stats.clone()
+    }
+
+    /// Logs every `every`-th dropped message of each reason at the warn level, together with
+    /// its sender, if known, and a prefix of its encoding, for the given time. Sampling stops on
+    /// its own afterwards, so that it cannot flood the logs when forgotten.
+    pub fn sample(&self, every: usize, budget: Duration) {
+        let mut shared = self.shared.lock();
+        let until = shared.clock.now() + budget;
+        shared.sampling = Some(Sampling {
+            every: every.max(1),
+            until,
+        });
+    }
+
+    /// Stops logging samples of dropped messages.
+    pub fn stop_sampling(&self) {
+        self.shared.lock().sampling = None;
+    }
+}
+
+/// The part of the drop accounting passed to the session, see [`drop_monitor`]. Drops are not
+/// counted at all unless the session got a monitor created with the handle.
+#[derive(Clone, Default)]
+pub struct DropMonitor {
+    shared: Option<Arc<Mutex<SharedState>>>,
+}
+
+impl DropMonitor {
+    /// Makes the sampling budget use the clock of the session.
+    pub(crate) fn use_clock(&self, clock: ClockSource) {
+        if let Some(shared) = &self.shared {
+            shared.lock().clock = clock;
+        }
+    }
+
+    /// Counts a message dropped for the given reason. The encoding of the message, provided by
+    /// `sample`, is only computed when the drop gets logged as a sample.
+    pub(crate) fn record_drop(
+        &self,
+        reason: DropReason,
+        peer_hint: Option<NodeIndex>,
+        sample: impl FnOnce() -> Vec<u8>,
+    ) {
+        let Some(shared) = &self.shared else {
+            return;
+        };
+        let mut shared = shared.lock();
+        let shared = &mut *shared;
+        shared.stats.counts[reason.position()] += 1;
+        let Some(sampling) = &shared.sampling else {
+            return;
+        };
+        if shared.clock.now() >= sampling.until {
+            info!(target: LOG_TARGET, "Stopped sampling dropped messages, the time budget ran out.");
+            shared.sampling = None;
+            return;
+        }
+        if shared.stats.count(reason) % sampling.every != 0 {
+            return;
+        }
+        shared.stats.samples_logged += 1;
+        let encoded = sample();
+        let prefix: String = encoded
+            .iter()
+            .take(DROP_SAMPLE_PREFIX)
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<Vec<_>>()
+            .join("");
+        let ellipsis = match encoded.len() > DROP_SAMPLE_PREFIX {
+            true => "...",
+            false => "",
+        };
+        warn!(
+            target: LOG_TARGET,
+            "Dropped a message ({}) from {:?}, {} bytes: {}{}.",
+            reason,
+            peer_hint,
+            encoded.len(),
+            prefix,
+            ellipsis
+        );
+    }
+
+    pub(crate) fn stats(&self) -> Option<DropStats> {
+        self.shared
+            .as_ref()
+            .map(|shared| shared.lock().stats.clone())
+    }
+}
+
+/// Creates a handle for inspecting the messages dropped by the session together with the
+/// monitor that should be passed to the session with [`crate::LocalIO::with_drop_monitor`].
+pub fn drop_monitor() -> (DropStatsHandle, DropMonitor) {
+    let shared = Arc::new(Mutex::new(SharedState {
+        stats: DropStats::default(),
+        sampling: None,
+        clock: ClockSource::default(),
+    }));
+    (
+        DropStatsHandle {
+            shared: shared.clone(),
+        },
+        DropMonitor {
+            shared: Some(shared),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        drops::{drop_monitor, DropMonitor, DropReason},
+        ClockSource, NodeIndex,
+    };
+    use aleph_bft_mock::TokioClock;
+    use std::time::Duration;
+
+    #[test]
+    fn counts_drops_per_reason() {
+        let (handle, monitor) = drop_monitor();
+        for _ in 0..3 {
+            monitor.record_drop(DropReason::MalformedDigest, Some(NodeIndex(1)), Vec::new);
+        }
+        monitor.record_drop(DropReason::Frozen, None, Vec::new);
+        let stats = handle.stats();
+        assert_eq!(stats.count(DropReason::MalformedDigest), 3);
+        assert_eq!(stats.count(DropReason::Frozen), 1);
+        assert_eq!(stats.count(DropReason::InvalidUnit), 0);
+        assert_eq!(stats.total(), 4);
+        assert_eq!(stats.samples_logged(), 0);
+        assert_eq!(
+            stats.to_string(),
+            "4 dropped; malformed digest - 3; frozen - 1"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn samples_only_within_the_budget() {
+        let (handle, monitor) = drop_monitor();
+        monitor.use_clock(ClockSource::new(TokioClock));
+        handle.sample(4, Duration::from_secs(10));
+        for _ in 0..20 {
+            monitor.record_drop(DropReason::InvalidUnit, Some(NodeIndex(2)), || vec![7; 100]);
+        }
+        assert_eq!(handle.stats().samples_logged(), 5);
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        for _ in 0..20 {
+            monitor.record_drop(DropReason::InvalidUnit, Some(NodeIndex(2)), || {
+                panic!("nothing is sampled after the budget")
+            });
+        }
+        let stats = handle.stats();
+        assert_eq!(stats.samples_logged(), 5);
+        assert_eq!(stats.count(DropReason::InvalidUnit), 40);
+    }
+
+    #[test]
+    fn default_monitor_does_not_count() {
+        let monitor = DropMonitor::default();
+        monitor.record_drop(DropReason::Frozen, None, Vec::new);
+        assert!(monitor.stats().is_none());
+    }
+}
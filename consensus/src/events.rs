@@ -0,0 +1,273 @@
+use crate::{
+    alerts::ForkProof,
+    creation::InclusionChange,
+    dag::{EvictedUnit, RejectionReason},
+    dissemination::Request,
+    lateness::LateUnits,
+    receipts::QuorumReceipt,
+    units::{UncheckedSignedUnit, UnitCoord},
+    Data, Hasher, NodeIndex, NodeSubset, Round, Signature,
+};
+use futures::channel::mpsc::{self, Receiver as BoundedReceiver, Sender as BoundedSender};
+use log::trace;
+use parking_lot::Mutex;
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+};
+
+const LOG_TARGET: &str = "AlephBFT-events";
+
+/// How many events can wait for a single subscriber before further events get dropped.
+/// Generous enough that a subscriber processing events as they come never loses any.
+const SUBSCRIBER_QUEUE_SIZE: usize = 4096;
+
+type Subscriber<H, D, S> = BoundedSender<InternalEvent<H, D, S>>;
+
+/// The stage an alert reached in the alerter.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum AlertState {
+    /// We raised the alert about the given forker ourselves, after noticing the fork.
+    Raised(NodeIndex),
+    /// We received a correct alert from another node.
+    Received,
+    /// The alert was confirmed by enough nodes through RMC.
+    Confirmed,
+}
+
+/// The availability of the network, as seen by the network hub.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum NetworkState {
+    /// The network yields events again after an interruption.
+    Recovered,
+    /// The network temporarily yields no events, so it is polled again after a backoff.
+    Interrupted,
+    /// The network is closed for good, or did not recover within the retry budget, which ends
+    /// the session.
+    Closed,
+}
+
+/// Provable misbehavior of a peer.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Misbehavior<H: Hasher> {
+    /// The peer answered our request for the parents of the unit with the given hash with
+    /// parents inconsistent with its control hash.
+    InconsistentParents(H::Hash),
+    /// The peer created the unit with the given hash, whose parents nobody else had for the
+    /// whole suspect timeout.
+    UnsatisfiableUnit(H::Hash),
+    /// The peer signed the unit with the given hash, which failed validation for the given
+    /// reason.
+    InvalidUnit(H::Hash, RejectionReason),
+}
+
+impl<H: Hasher> Display for Misbehavior<H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Misbehavior::InconsistentParents(hash) => {
+                write!(f, "sent parents inconsistent with unit {:?}", hash)
+            }
+            Misbehavior::UnsatisfiableUnit(hash) => {
+                write!(f, "created unit {:?} whose parents nobody else has", hash)
+            }
+            Misbehavior::InvalidUnit(hash, reason) => {
+                write!(f, "created invalid unit {:?}: {}", hash, reason)
+            }
+        }
+    }
+}
+
+/// An observability-grade notification about something that happened inside a session.
+#[derive(Clone, Debug)]
+pub(crate) enum InternalEvent<H: Hasher, D: Data, S: Signature> {
+    /// We created our unit of the given round.
+    UnitCreated(Round),
+    /// A unit was added to the DAG after being saved in the backup.
+    UnitAdmitted(UncheckedSignedUnit<H, D, S>),
+    /// The backup saver confirmed saving the unit with the given hash.
+    BackupAcked(H::Hash),
+    /// The unit with the given hash and coord was included in a finalized batch.
+    UnitFinalized(H::Hash, UnitCoord),
+    /// The alert with the given hash changed its state.
+    AlertStateChanged(H::Hash, AlertState),
+    /// We detected the fork with the given proof, but our alert about it is held back, as we
+    /// raised too many alerts recently.
+    AlertQueued(ForkProof<H, D, S>),
+    /// We started or stopped holding back our own alerts.
+    AlertThrottlingChanged(bool),
+    /// We started asking other nodes for the given information.
+    RequestIssued(Request<H>),
+    /// The given request no longer needs to be sent.
+    RequestResolved(Request<H>),
+    /// The given request got cancelled, as finalization went past it without needing it, or the
+    /// units needing it got evicted.
+    RequestObsolete(Request<H>),
+    /// We sent the given request to the given peer.
+    RequestSent(Request<H>, NodeIndex),
+    /// The given peer misbehaved.
+    PeerMisbehaved(NodeIndex, Misbehavior<H>),
+    /// The given unit was dropped while waiting for its parents.
+    UnitEvicted(EvictedUnit<H>),
+    /// We failed to sign the given object.
+    SigningFailed(SigningTarget<H>),
+    /// Summary of a round two rounds after we created our unit on top of it.
+    LateUnits(LateUnits),
+    /// The digest of the given peer shows that it has different units than us at the top of
+    /// the DAG for the given creators.
+    DagDivergence(NodeIndex, NodeSubset),
+    /// The creator changed whether our units carry data, starting with our unit of the given round.
+    DataInclusionChanged(Round, InclusionChange),
+    /// A quorum of the committee holds our unit, as evidenced by the parents of their units.
+    QuorumReceipt(QuorumReceipt<D>),
+    /// The session got frozen for a migration, so it only answers requests from now on.
+    SessionFrozen,
+    /// The availability of the network changed.
+    NetworkStateChanged(NetworkState),
+}
+
+/// Something we failed to sign, e.g. because the keychain was temporarily unavailable.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum SigningTarget<H: Hasher> {
+    /// Our unit of the given round, the creator tries again after another creation delay.
+    Unit(Round),
+    /// Our alert about the given forkers, retried with a backoff.
+    Alert(Vec<NodeIndex>),
+    /// Our signature of the given hash in RMC, retried with a backoff.
+    RmcHash(H::Hash),
+    /// Our response to a request for the newest unit from the given node, which will ask again.
+    NewestUnitResponse(NodeIndex),
+    /// Our response to a request for the newest units from the given node, which will ask again
+    /// or fall back to requesting the units one by one.
+    NewestUnitsResponse(NodeIndex),
+}
+
+impl<H: Hasher> Display for SigningTarget<H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            SigningTarget::Unit(round) => write!(f, "our unit of round {}", round),
+            SigningTarget::Alert(forkers) => write!(f, "our alert about forkers {:?}", forkers),
+            SigningTarget::RmcHash(hash) => write!(f, "the RMC hash {:?}", hash),
+            SigningTarget::NewestUnitResponse(requester) => {
+                write!(f, "the newest unit response for {:?}", requester)
+            }
+            SigningTarget::NewestUnitsResponse(requester) => {
+                write!(f, "the newest units response for {:?}", requester)
+            }
+        }
+    }
+}
+
+/// A dispatcher of [`InternalEvent`]s shared by all the components of a session.
+///
+/// Components publish events without knowing who listens, and consumers subscribe without
+/// any changes to the components. The bus is lossy under pressure: every subscriber has a
+/// bounded queue and events that do not fit are dropped, so it must never carry anything
+/// whose loss would affect the correctness of the protocol.
+pub(crate) struct EventBus<H: Hasher, D: Data, S: Signature> {
+    subscribers: Arc<Mutex<Vec<Subscriber<H, D, S>>>>,
+}
+
+impl<H: Hasher, D: Data, S: Signature> Clone for EventBus<H, D, S> {
+    fn clone(&self) -> Self {
+        EventBus {
+            subscribers: self.subscribers.clone(),
+        }
+    }
+}
+
+impl<H: Hasher, D: Data, S: Signature> EventBus<H, D, S> {
+    pub fn new() -> Self {
+        EventBus {
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Returns a stream of all the events published from now on. Dropping it unsubscribes.
+    pub fn subscribe(&self) -> BoundedReceiver<InternalEvent<H, D, S>> {
+        let (events_for_subscriber, events) = mpsc::channel(SUBSCRIBER_QUEUE_SIZE);
+        self.subscribers.lock().push(events_for_subscriber);
+        events
+    }
+
+    /// Passes the event to all subscribers, dropping it for those that are lagging behind.
+    pub fn publish(&self, event: InternalEvent<H, D, S>) {
+        let mut subscribers = self.subscribers.lock();
+        subscribers.retain(|subscriber| !subscriber.is_closed());
+        for subscriber in subscribers.iter_mut() {
+            if let Err(e) = subscriber.try_send(event.clone()) {
+                trace!(target: LOG_TARGET, "Dropping an event for a lagging subscriber: {:?}.", e.into_inner());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dissemination::Request,
+        events::{EventBus, InternalEvent, SUBSCRIBER_QUEUE_SIZE},
+        units::UnitCoord,
+    };
+    use aleph_bft_mock::{Data, Hasher64, Signature};
+    use futures::StreamExt;
+
+    type TestEventBus = EventBus<Hasher64, Data, Signature>;
+
+    fn resolved(round: u16) -> InternalEvent<Hasher64, Data, Signature> {
+        InternalEvent::RequestResolved(Request::Coord(UnitCoord::new(round, 0.into())))
+    }
+
+    fn resolved_round(event: Option<InternalEvent<Hasher64, Data, Signature>>) -> u16 {
+        match event {
+            Some(InternalEvent::RequestResolved(Request::Coord(coord))) => coord.round(),
+            event => panic!("unexpected event {:?}", event),
+        }
+    }
+
+    #[tokio::test]
+    async fn delivers_events_to_all_subscribers() {
+        let bus = TestEventBus::new();
+        let mut first = bus.subscribe();
+        let mut second = bus.subscribe();
+        bus.publish(resolved(0));
+        bus.publish(resolved(1));
+        for events in [&mut first, &mut second] {
+            assert_eq!(resolved_round(events.next().await), 0);
+            assert_eq!(resolved_round(events.next().await), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn late_subscriber_gets_only_new_events() {
+        let bus = TestEventBus::new();
+        bus.publish(resolved(0));
+        let mut events = bus.subscribe();
+        bus.publish(resolved(1));
+        assert_eq!(resolved_round(events.next().await), 1);
+    }
+
+    #[tokio::test]
+    async fn drops_events_for_lagging_subscriber() {
+        let bus = TestEventBus::new();
+        let lagging = bus.subscribe();
+        let mut eager = bus.subscribe();
+        let n_events = 2 * SUBSCRIBER_QUEUE_SIZE as u16;
+        for round in 0..n_events {
+            bus.publish(resolved(round));
+            assert_eq!(resolved_round(eager.next().await), round);
+        }
+        drop(bus);
+        let received = lagging.collect::<Vec<_>>().await.len();
+        assert!(received >= SUBSCRIBER_QUEUE_SIZE);
+        assert!(received < n_events as usize);
+    }
+
+    #[test]
+    fn forgets_dropped_subscribers() {
+        let bus = TestEventBus::new();
+        let events = bus.subscribe();
+        drop(events);
+        bus.publish(resolved(0));
+        assert!(bus.subscribers.lock().is_empty());
+    }
+}
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use crate::{
     extension::units::Units,
     units::{HashFor, UnitWithParents},
-    Hasher, NodeCount, NodeIndex, Round,
+    Hasher, NodeIndex, NodeWeights, Round, Weight,
 };
 
 fn common_vote(relative_round: Round) -> bool {
@@ -27,7 +27,8 @@ struct CandidateElection<U: UnitWithParents> {
     round: Round,
     candidate_creator: NodeIndex,
     candidate_hash: HashFor<U>,
-    votes: HashMap<HashFor<U>, bool>,
+    // The votes of the voters, together with their creators.
+    votes: HashMap<HashFor<U>, (NodeIndex, bool)>,
 }
 
 impl<U: UnitWithParents> CandidateElection<U> {
@@ -37,6 +38,7 @@ impl<U: UnitWithParents> CandidateElection<U> {
     pub fn for_candidate(
         candidate: &U,
         units: &Units<U>,
+        node_weights: &NodeWeights,
     ) -> Result<Self, CandidateOutcome<U::Hasher>> {
         CandidateElection {
             round: candidate.round(),
@@ -44,18 +46,24 @@ impl<U: UnitWithParents> CandidateElection<U> {
             candidate_hash: candidate.hash(),
             votes: HashMap::new(),
         }
-        .compute_votes(units)
+        .compute_votes(units, node_weights)
     }
 
+    /// The weights of the parents voting for and against the candidate.
     fn parent_votes(
         &mut self,
         parents: Vec<HashFor<U>>,
-    ) -> Result<(NodeCount, NodeCount), CandidateOutcome<U::Hasher>> {
-        let (mut votes_for, mut votes_against) = (NodeCount(0), NodeCount(0));
+        node_weights: &NodeWeights,
+    ) -> Result<(Weight, Weight), CandidateOutcome<U::Hasher>> {
+        let (mut votes_for, mut votes_against): (Weight, Weight) = (0, 0);
         for parent in parents {
             match self.votes.get(&parent).expect("units are added in order") {
-                true => votes_for += NodeCount(1),
-                false => votes_against += NodeCount(1),
+                (creator, true) => {
+                    votes_for = votes_for.saturating_add(node_weights.weight(*creator))
+                }
+                (creator, false) => {
+                    votes_against = votes_against.saturating_add(node_weights.weight(*creator))
+                }
             }
         }
         Ok((votes_for, votes_against))
@@ -64,13 +72,14 @@ impl<U: UnitWithParents> CandidateElection<U> {
     fn vote_from_parents(
         &mut self,
         parents: Vec<HashFor<U>>,
-        threshold: NodeCount,
+        node_weights: &NodeWeights,
         relative_round: Round,
     ) -> Result<bool, CandidateOutcome<U::Hasher>> {
         use CandidateOutcome::*;
+        let threshold = node_weights.consensus_threshold();
         // Gather parents' votes.
-        let (votes_for, votes_against) = self.parent_votes(parents)?;
-        assert!(votes_for + votes_against >= threshold);
+        let (votes_for, votes_against) = self.parent_votes(parents, node_weights)?;
+        assert!(votes_for.saturating_add(votes_against) >= threshold);
         let common_vote = common_vote(relative_round);
         // If the round is sufficiently high we are done voting for the candidate if
         if relative_round >= 3 {
@@ -86,13 +95,17 @@ impl<U: UnitWithParents> CandidateElection<U> {
 
         // The vote is either identical to all the votes of the parents, or the default vote if that is not possible.
         Ok(match (votes_for, votes_against) {
-            (NodeCount(0), _) => false,
-            (_, NodeCount(0)) => true,
+            (0, _) => false,
+            (_, 0) => true,
             _ => common_vote,
         })
     }
 
-    fn vote(&mut self, voter: &U) -> Result<(), CandidateOutcome<U::Hasher>> {
+    fn vote(
+        &mut self,
+        voter: &U,
+        node_weights: &NodeWeights,
+    ) -> Result<(), CandidateOutcome<U::Hasher>> {
         // If the vote is already computed we are done.
         if self.votes.contains_key(&voter.hash()) {
             return Ok(());
@@ -108,19 +121,22 @@ impl<U: UnitWithParents> CandidateElection<U> {
             1 => voter.parent_for(self.candidate_creator) == Some(&self.candidate_hash),
             // Otherwise we compute the vote based on the parents' votes.
             _ => {
-                let threshold = voter.node_count().consensus_threshold();
                 let direct_parents = voter.direct_parents().cloned().collect();
-                self.vote_from_parents(direct_parents, threshold, relative_round)?
+                self.vote_from_parents(direct_parents, node_weights, relative_round)?
             }
         };
-        self.votes.insert(voter.hash(), vote);
+        self.votes.insert(voter.hash(), (voter.creator(), vote));
         Ok(())
     }
 
-    fn compute_votes(mut self, units: &Units<U>) -> Result<Self, CandidateOutcome<U::Hasher>> {
+    fn compute_votes(
+        mut self,
+        units: &Units<U>,
+        node_weights: &NodeWeights,
+    ) -> Result<Self, CandidateOutcome<U::Hasher>> {
         for round in self.round + 1..=units.highest_round() {
             for voter in units.in_round(round).expect("units are added in order") {
-                self.vote(voter)?;
+                self.vote(voter, node_weights)?;
             }
         }
         Ok(self)
@@ -128,8 +144,12 @@ impl<U: UnitWithParents> CandidateElection<U> {
 
     /// Add a single voter and compute their vote. This might end up electing or eliminating the candidate.
     /// Might panic if called for a unit before its parents.
-    pub fn add_voter(mut self, voter: &U) -> Result<Self, CandidateOutcome<U::Hasher>> {
-        self.vote(voter).map(|()| self)
+    pub fn add_voter(
+        mut self,
+        voter: &U,
+        node_weights: &NodeWeights,
+    ) -> Result<Self, CandidateOutcome<U::Hasher>> {
+        self.vote(voter, node_weights).map(|()| self)
     }
 }
 
@@ -153,7 +173,11 @@ impl<U: UnitWithParents> RoundElection<U> {
     /// Returns an error when it's too early to finalize the candidate list, i.e. we are not at least 3 rounds ahead of the election round.
     ///
     /// Note: it is crucial that units are added to `Units` only when all their parents are there, otherwise this might panic.
-    pub fn for_round(round: Round, units: &Units<U>) -> Result<ElectionResult<U>, ()> {
+    pub fn for_round(
+        round: Round,
+        units: &Units<U>,
+        node_weights: &NodeWeights,
+    ) -> Result<ElectionResult<U>, ()> {
         // If we don't yet have a unit of round + 3 we might not know about the winning candidate, so we cannot start the election.
         if units.highest_round() < round + 3 {
             return Err(());
@@ -173,9 +197,10 @@ impl<U: UnitWithParents> RoundElection<U> {
             .get(&candidates.pop().expect("there is a candidate"))
             .expect("we have all the units we work with");
         Ok(Self::handle_candidate_election_result(
-            CandidateElection::for_candidate(candidate, units),
+            CandidateElection::for_candidate(candidate, units, node_weights),
             candidates,
             units,
+            node_weights,
         ))
     }
 
@@ -183,6 +208,7 @@ impl<U: UnitWithParents> RoundElection<U> {
         result: Result<CandidateElection<U>, CandidateOutcome<U::Hasher>>,
         mut candidates: Vec<HashFor<U>>,
         units: &Units<U>,
+        node_weights: &NodeWeights,
     ) -> ElectionResult<U> {
         use CandidateOutcome::*;
         use ElectionResult::*;
@@ -195,9 +221,10 @@ impl<U: UnitWithParents> RoundElection<U> {
                     .get(&candidates.pop().expect("there is a candidate"))
                     .expect("we have all the units we work with");
                 Self::handle_candidate_election_result(
-                    CandidateElection::for_candidate(candidate, units),
+                    CandidateElection::for_candidate(candidate, units, node_weights),
                     candidates,
                     units,
+                    node_weights,
                 )
             }
             // Yay, we picked a head.
@@ -207,9 +234,19 @@ impl<U: UnitWithParents> RoundElection<U> {
 
     /// Add a single voter to the election.
     /// Might panic if not all parents were added previously.
-    pub fn add_voter(self, voter: &U, units: &Units<U>) -> ElectionResult<U> {
+    pub fn add_voter(
+        self,
+        voter: &U,
+        units: &Units<U>,
+        node_weights: &NodeWeights,
+    ) -> ElectionResult<U> {
         let RoundElection { candidates, voting } = self;
-        Self::handle_candidate_election_result(voting.add_voter(voter), candidates, units)
+        Self::handle_candidate_election_result(
+            voting.add_voter(voter, node_weights),
+            candidates,
+            units,
+            node_weights,
+        )
     }
 }
 
@@ -224,14 +261,15 @@ mod test {
             minimal_reconstructed_dag_units_up_to, random_full_parent_reconstrusted_units_up_to,
             random_reconstructed_unit_with_parents, TestingDagUnit, Unit,
         },
-        NodeCount,
+        NodeCount, NodeWeights,
     };
     use aleph_bft_mock::Keychain;
 
     #[test]
     fn refuses_to_elect_without_units() {
         let units = Units::<TestingDagUnit>::new();
-        assert!(RoundElection::for_round(0, &units).is_err());
+        let node_weights = NodeWeights::uniform(NodeCount(4));
+        assert!(RoundElection::for_round(0, &units, &node_weights).is_err());
     }
 
     #[test]
@@ -248,7 +286,7 @@ mod test {
                 units.add_unit(unit);
             }
         }
-        assert!(RoundElection::for_round(0, &units).is_err());
+        assert!(RoundElection::for_round(0, &units, &NodeWeights::uniform(n_members)).is_err());
     }
 
     #[test]
@@ -267,14 +305,15 @@ mod test {
                 units.add_unit(unit.clone());
             }
         }
-        let election = RoundElection::for_round(0, &units).expect("we have enough rounds");
+        let election = RoundElection::for_round(0, &units, &NodeWeights::uniform(n_members))
+            .expect("we have enough rounds");
         let election = match election {
             Pending(election) => election,
             Elected(_) => panic!("elected head without units of round + 4"),
         };
         let last_voter = dag[4].last().expect("created all units").clone();
         units.add_unit(last_voter.clone());
-        match election.add_voter(&last_voter, &units) {
+        match election.add_voter(&last_voter, &units, &NodeWeights::uniform(n_members)) {
             Pending(_) => panic!("failed to elect obvious head"),
             Elected(head) => {
                 assert_eq!(units.get(&head).expect("we have the head").round(), 0);
@@ -297,7 +336,8 @@ mod test {
                 units.add_unit(unit.clone());
             }
         }
-        let election = RoundElection::for_round(0, &units).expect("we have enough rounds");
+        let election = RoundElection::for_round(0, &units, &NodeWeights::uniform(n_members))
+            .expect("we have enough rounds");
         match election {
             Pending(_) => panic!("should have elected"),
             Elected(head) => {
@@ -352,7 +392,8 @@ mod test {
                 ));
             }
         }
-        let election = RoundElection::for_round(0, &units).expect("we have enough rounds");
+        let election = RoundElection::for_round(0, &units, &NodeWeights::uniform(n_members))
+            .expect("we have enough rounds");
         match election {
             Pending(_) => panic!("should have elected"),
             Elected(head) => {
@@ -378,7 +419,8 @@ mod test {
                 units.add_unit(unit);
             }
         }
-        let election = RoundElection::for_round(0, &units).expect("we have enough rounds");
+        let election = RoundElection::for_round(0, &units, &NodeWeights::uniform(n_members))
+            .expect("we have enough rounds");
         match election {
             Pending(_) => panic!("should have elected"),
             Elected(head) => {
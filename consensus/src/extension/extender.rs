@@ -3,14 +3,36 @@ use crate::{
         election::{ElectionResult, RoundElection},
         units::Units,
     },
-    units::UnitWithParents,
-    Round,
+    protocol::{batch_id, BatchId},
+    units::{HashFor, UnitWithParents},
+    NodeWeights, Round,
 };
 
+/// A batch of ordered units together with its identifier.
+pub struct Batch<U: UnitWithParents> {
+    pub id: BatchId<U::Hasher>,
+    pub units: Vec<U>,
+}
+
+impl<U: UnitWithParents> Batch<U> {
+    /// The batch with the given number, its last unit being the head.
+    fn new(number: Round, units: Vec<U>) -> Self {
+        let head = units.last().expect("a batch contains at least its head");
+        let data_units: Vec<HashFor<U>> = units
+            .iter()
+            .filter(|unit| unit.has_data())
+            .map(|unit| unit.hash())
+            .collect();
+        let id = batch_id::<U::Hasher>(head.session_id(), number.into(), &head.hash(), &data_units);
+        Batch { id, units }
+    }
+}
+
 pub struct Extender<U: UnitWithParents> {
     election: Option<RoundElection<U>>,
     units: Units<U>,
     round: Round,
+    node_weights: Option<NodeWeights>,
 }
 
 impl<U: UnitWithParents> Extender<U> {
@@ -20,10 +42,26 @@ impl<U: UnitWithParents> Extender<U> {
             election: None,
             units: Units::new(),
             round: 0,
+            node_weights: None,
         }
     }
 
-    fn handle_election_result(&mut self, result: ElectionResult<U>) -> Option<Vec<U>> {
+    /// Sets the voting weights of the nodes, which decide the elections of heads. Every node
+    /// weighs one by default.
+    pub fn with_node_weights(self, node_weights: NodeWeights) -> Self {
+        Extender {
+            node_weights: Some(node_weights),
+            ..self
+        }
+    }
+
+    fn node_weights(&self) -> &NodeWeights {
+        self.node_weights
+            .as_ref()
+            .expect("the weights are set before any unit is added")
+    }
+
+    fn handle_election_result(&mut self, result: ElectionResult<U>) -> Option<Batch<U>> {
         use ElectionResult::*;
         match result {
             // Wait for more voters for this election.
@@ -31,30 +69,47 @@ impl<U: UnitWithParents> Extender<U> {
                 self.election = Some(election);
                 None
             }
-            // Advance to the next round and return the ordered batch.
+            // Advance to the next round and return the ordered batch, there is one per round.
             Elected(head) => {
+                let batch = Batch::new(self.round, self.units.remove_batch(&head));
                 self.round += 1;
-                Some(self.units.remove_batch(&head))
+                Some(batch)
             }
         }
     }
 
+    /// The round whose head is being elected.
+    pub fn round(&self) -> Round {
+        self.round
+    }
+
+    /// The number of units waiting to be ordered.
+    pub fn pending_units(&self) -> usize {
+        self.units.len()
+    }
+
     /// Add a unit to the extender. Might return several batches of ordered units as a result.
-    pub fn add_unit(&mut self, u: U) -> Vec<Vec<U>> {
+    pub fn add_unit(&mut self, u: U) -> Vec<Batch<U>> {
+        if self.node_weights.is_none() {
+            self.node_weights = Some(NodeWeights::uniform(u.node_count()));
+        }
         let hash = u.hash();
         self.units.add_unit(u);
         let unit = self.units.get(&hash).expect("just added");
         let mut result = Vec::new();
         // If we have an ongoing election try to finish it.
         if let Some(election) = self.election.take() {
-            if let Some(batch) = self.handle_election_result(election.add_voter(unit, &self.units))
-            {
+            if let Some(batch) = self.handle_election_result(election.add_voter(
+                unit,
+                &self.units,
+                self.node_weights(),
+            )) {
                 result.push(batch);
             }
         }
         // Try finding another election to be working on.
         while self.election.is_none() {
-            match RoundElection::for_round(self.round, &self.units) {
+            match RoundElection::for_round(self.round, &self.units, self.node_weights()) {
                 Ok(election_result) => {
                     if let Some(batch) = self.handle_election_result(election_result) {
                         result.push(batch);
@@ -70,12 +125,16 @@ impl<U: UnitWithParents> Extender<U> {
 
 #[cfg(test)]
 mod test {
-    use crate::units::{minimal_reconstructed_dag_units_up_to, Unit, UnitWithParents};
     use crate::{
-        extension::extender::Extender, units::random_full_parent_reconstrusted_units_up_to,
+        extension::extender::Extender,
+        units::{
+            minimal_reconstructed_dag_units_up_to, random_full_parent_reconstrusted_units_up_to,
+            Unit, UnitWithParents,
+        },
         NodeCount, Round,
     };
     use aleph_bft_mock::Keychain;
+    use std::collections::HashSet;
 
     #[test]
     fn easy_elections() {
@@ -92,11 +151,15 @@ mod test {
                 batches.append(&mut extender.add_unit(unit));
             }
         }
-        assert_eq!(batches.len(), (max_round - 3).into());
-        assert_eq!(batches[0].len(), 1);
+        assert_eq!(batches.len(), usize::from(max_round - 3));
+        assert_eq!(batches[0].units.len(), 1);
         for batch in batches.iter().skip(1) {
-            assert_eq!(batch.len(), n_members.0);
+            assert_eq!(batch.units.len(), n_members.0);
         }
+        // The last rounds wait for the election of the next head, together with the units of
+        // the last decided round other than its head.
+        assert_eq!(extender.round(), max_round - 3);
+        assert_eq!(extender.pending_units(), 5 * n_members.0 - 1);
     }
 
     #[test]
@@ -115,15 +178,45 @@ mod test {
                 batches.append(&mut extender.add_unit(unit));
             }
         }
-        assert_eq!(batches.len(), (max_round - 3).into());
-        assert_eq!(batches[0].len(), 1);
-        assert_eq!(batches[0][0].round(), 0);
+        assert_eq!(batches.len(), usize::from(max_round - 3));
+        assert_eq!(batches[0].units.len(), 1);
+        assert_eq!(batches[0].units[0].round(), 0);
         for batch in batches.iter().skip(1) {
-            assert!(batch.len() == threshold.0 || batch.len() == n_members.0);
-            if batch.len() == n_members.0 {
+            assert!(batch.units.len() == threshold.0 || batch.units.len() == n_members.0);
+            if batch.units.len() == n_members.0 {
                 // the batch that should have ancient unit
-                assert!(batch.iter().any(|unit| unit.parents().count() == 0));
+                assert!(batch.units.iter().any(|unit| unit.parents().count() == 0));
             }
         }
     }
+
+    #[test]
+    fn batch_ids_do_not_depend_on_insertion_order() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 11;
+        let session_id = 2137;
+        let keychains = Keychain::new_vec(n_members);
+        let units: Vec<_> = random_full_parent_reconstrusted_units_up_to(
+            max_round, n_members, session_id, &keychains,
+        )
+        .into_iter()
+        .flatten()
+        .collect();
+        let batch_ids = |units: Vec<_>| {
+            let mut extender = Extender::new();
+            let mut ids = Vec::new();
+            for unit in units {
+                ids.extend(extender.add_unit(unit).into_iter().map(|batch| batch.id));
+            }
+            ids
+        };
+        let mut reversed_rounds = units.clone();
+        reversed_rounds.reverse();
+        reversed_rounds.sort_by_key(|unit| unit.round());
+        let ids = batch_ids(units);
+        assert_eq!(ids.len(), usize::from(max_round - 3));
+        assert_eq!(ids, batch_ids(reversed_rounds));
+        let distinct: HashSet<_> = ids.iter().collect();
+        assert_eq!(distinct.len(), ids.len());
+    }
 }
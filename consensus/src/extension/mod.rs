@@ -1,10 +1,42 @@
-use crate::{dag::DagUnit, MultiKeychain, UnitFinalizationHandler};
+use crate::{
+    callbacks::CallbackGuard,
+    dag::DagUnit,
+    delivery::{Delivery, DeliveryBuffer, DeliveryError, OverflowPolicy},
+    events::{EventBus, InternalEvent},
+    finalization_state::{FinalizationState, RestoreError},
+    units::Unit,
+    DataPolicy, DeliveryCheckpoint, Hasher, MultiKeychain, NodeIndex, NodeWeights, OrderedUnit,
+    Round, Sender, UnitFinalizationHandler,
+};
+use std::collections::{HashSet, VecDeque};
 
 mod election;
 mod extender;
 mod units;
 
-use extender::Extender;
+use extender::Batch;
+pub(crate) use extender::Extender;
+
+/// How many rounds of units above a head it takes at least to elect it.
+const ELECTION_ROUNDS: Round = 4;
+
+/// The last round whose head can be elected in a session with the given max round, at least
+/// when all the votes agree, so nothing is finalized after its head.
+pub fn last_head_round(max_round: Round) -> Round {
+    max_round.saturating_sub(ELECTION_ROUNDS)
+}
+
+/// How far behind the ordering is, see [`crate::ExtenderFlowControl`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Backlog {
+    /// The round whose head is being elected.
+    pub working_round: Round,
+    /// The units added, but not ordered yet, whether they wait for the election or for the
+    /// delivery to resume.
+    pub depth: usize,
+    /// Whether some of these units wait for the delivery to resume.
+    pub waiting_for_delivery: bool,
+}
 
 /// A struct responsible for executing the Consensus protocol on a local copy of the Dag.
 /// It receives units which are guaranteed to eventually appear in the Dags
@@ -17,22 +49,175 @@ use extender::Extender;
 /// Section 5.4 for a discussion of this component.
 pub struct Ordering<MK: MultiKeychain, UFH: UnitFinalizationHandler> {
     extender: Extender<DagUnit<UFH::Hasher, UFH::Data, MK>>,
-    finalization_handler: UFH,
+    delivery_buffer: DeliveryBuffer<UFH>,
+    blocked_units: VecDeque<DagUnit<UFH::Hasher, UFH::Data, MK>>,
+    data_policy: DataPolicy,
+    flagged_units: HashSet<<UFH::Hasher as Hasher>::Hash>,
+    events: EventBus<UFH::Hasher, UFH::Data, MK::Signature>,
+    finalization_feedback: Option<(NodeIndex, Sender<Round>)>,
+    head_feedback: Option<Sender<Round>>,
+    finalized_round: Option<Round>,
 }
 
 impl<MK: MultiKeychain, UFH: UnitFinalizationHandler> Ordering<MK, UFH> {
-    pub fn new(finalization_handler: UFH) -> Self {
+    pub fn new(
+        finalization_handler: UFH,
+        delivery: Delivery,
+        finalization_state: Option<FinalizationState<UFH::Hasher>>,
+        data_policy: DataPolicy,
+        events: EventBus<UFH::Hasher, UFH::Data, MK::Signature>,
+        callbacks: CallbackGuard,
+    ) -> Self {
         let extender = Extender::new();
         Ordering {
             extender,
-            finalization_handler,
+            delivery_buffer: DeliveryBuffer::new(
+                finalization_handler,
+                delivery,
+                finalization_state,
+                callbacks,
+            ),
+            blocked_units: VecDeque::new(),
+            data_policy,
+            flagged_units: HashSet::new(),
+            events,
+            finalization_feedback: None,
+            head_feedback: None,
+            finalized_round: None,
+        }
+    }
+
+    /// Sets the voting weights of the nodes, which decide the elections of heads. Every node
+    /// weighs one by default.
+    pub fn with_node_weights(self, node_weights: NodeWeights) -> Self {
+        Ordering {
+            extender: self.extender.with_node_weights(node_weights),
+            ..self
+        }
+    }
+
+    /// Reports the rounds of the finalized units of the given creator, in the order of
+    /// finalization.
+    pub fn with_finalization_feedback(self, creator: NodeIndex, rounds: Sender<Round>) -> Self {
+        Ordering {
+            finalization_feedback: Some((creator, rounds)),
+            ..self
+        }
+    }
+
+    /// Reports the rounds of the heads of the finalized batches, in the order of finalization.
+    pub fn with_head_feedback(self, rounds: Sender<Round>) -> Self {
+        Ordering {
+            head_feedback: Some(rounds),
+            ..self
+        }
+    }
+
+    /// Adds the unit to the local copy of the Dag, finalizing whatever becomes possible. While
+    /// the delivery buffer is full the unit waits for the delivery to be resumed, unless the
+    /// overflow policy is to abort. This is where the unit gets admitted, so the data policy is
+    /// consulted here. Nothing gets finalized anymore once the finalization handler panicked.
+    pub fn add_unit(
+        &mut self,
+        unit: DagUnit<UFH::Hasher, UFH::Data, MK>,
+    ) -> Result<(), DeliveryError> {
+        if self.data_policy.is_flagged(unit.creator()) {
+            self.flagged_units.insert(unit.hash());
+        }
+        self.blocked_units.push_back(unit);
+        self.process_blocked_units()
+    }
+
+    /// The round of the most recent head, i.e. the last unit of the most recent batch.
+    pub fn finalized_round(&self) -> Option<Round> {
+        self.finalized_round
+    }
+
+    /// Whether the head of the given round, or of a later one, was finalized and all the batches
+    /// up to it were delivered.
+    pub fn delivered_head_of(&self, round: Round) -> bool {
+        self.finalized_round
+            .is_some_and(|finalized| finalized >= round)
+            && self.delivery_buffer.is_empty()
+    }
+
+    /// How far behind the ordering is.
+    pub fn backlog(&self) -> Backlog {
+        Backlog {
+            working_round: self.extender.round(),
+            depth: self.blocked_units.len() + self.extender.pending_units(),
+            waiting_for_delivery: !self.blocked_units.is_empty(),
         }
     }
 
-    pub fn add_unit(&mut self, unit: DagUnit<UFH::Hasher, UFH::Data, MK>) {
-        for batch in self.extender.add_unit(unit) {
-            self.finalization_handler
-                .batch_finalized(batch.into_iter().map(|unit| unit.into()).collect());
+    /// The point up to which the batches were delivered, see [`DeliveryBuffer::checkpoint`].
+    pub fn delivery_checkpoint(&self) -> Option<DeliveryCheckpoint<<UFH::Hasher as Hasher>::Hash>> {
+        self.delivery_buffer.checkpoint()
+    }
+
+    /// Whether the delivery is recorded in a finalization state.
+    pub fn has_finalization_state(&self) -> bool {
+        self.delivery_buffer.has_finalization_state()
+    }
+
+    /// Prepares the finalization state for the session, given the hashes of the units loaded
+    /// from the backup. Has to be called before any unit is added.
+    pub fn restore_finalization_state(
+        &mut self,
+        backup: &HashSet<<UFH::Hasher as Hasher>::Hash>,
+    ) -> Result<(), RestoreError<<UFH::Hasher as Hasher>::Hash>> {
+        self.delivery_buffer.restore(backup)
+    }
+
+    /// Delivers the buffered batches and finalizes the units that were waiting for that.
+    pub fn on_delivery_resumed(&mut self) -> Result<(), DeliveryError> {
+        self.delivery_buffer.flush()?;
+        self.process_blocked_units()
+    }
+
+    fn process_blocked_units(&mut self) -> Result<(), DeliveryError> {
+        while !self.delivery_buffer.is_full() {
+            let unit = match self.blocked_units.pop_front() {
+                Some(unit) => unit,
+                None => return Ok(()),
+            };
+            for Batch { id, units: batch } in self.extender.add_unit(unit) {
+                if let Some(head) = batch.last() {
+                    self.finalized_round = Some(head.round());
+                    if let Some(rounds) = &self.head_feedback {
+                        // The creator only stops needing this once it is done.
+                        let _ = rounds.unbounded_send(head.round());
+                    }
+                }
+                for unit in &batch {
+                    self.events
+                        .publish(InternalEvent::UnitFinalized(unit.hash(), unit.coord()));
+                    if let Some((creator, rounds)) = &self.finalization_feedback {
+                        if unit.creator() == *creator {
+                            // The creator only stops needing this once it is done.
+                            let _ = rounds.unbounded_send(unit.round());
+                        }
+                    }
+                }
+                let batch = batch
+                    .into_iter()
+                    .map(|unit| {
+                        let flagged = self.flagged_units.remove(&unit.hash());
+                        OrderedUnit {
+                            flagged,
+                            ..unit.into()
+                        }
+                    })
+                    .collect();
+                self.delivery_buffer.deliver((id, batch))?;
+            }
+        }
+        match (
+            self.blocked_units.is_empty(),
+            self.delivery_buffer.delivery().overflow_policy(),
+        ) {
+            (false, OverflowPolicy::Abort) => Err(DeliveryError::BufferOverflow),
+            _ => Ok(()),
         }
     }
 }
@@ -49,6 +49,11 @@ impl<U: UnitWithParents> Units<U> {
         })
     }
 
+    /// The number of units that were added, but not removed in a batch yet.
+    pub fn len(&self) -> usize {
+        self.units.len()
+    }
+
     /// The highest round among all added units, or 0 if there are none.
     pub fn highest_round(&self) -> Round {
         self.highest_round
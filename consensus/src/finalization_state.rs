@@ -0,0 +1,180 @@
+use crate::{DeliveryCheckpoint, FinalizationStateStore, Hasher};
+use parking_lot::Mutex;
+use std::{
+    collections::HashSet,
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    io,
+    sync::Arc,
+};
+
+/// The number of most recent batches for which the finalization index is kept. The index is
+/// pruned once every that many batches, so it never holds entries of more than twice as many.
+pub const FINALIZATION_INDEX_RETENTION: u64 = 10_000;
+
+/// The reasons for which the finalization state cannot be used by a session.
+#[derive(Debug)]
+pub(crate) enum RestoreError<Hash> {
+    Store(io::Error),
+    /// The checkpoint is ahead of the backup, e.g. the store belongs to other session or the
+    /// backup was lost.
+    UnknownHead(DeliveryCheckpoint<Hash>),
+    StorePanicked,
+}
+
+impl<Hash: Debug> Display for RestoreError<Hash> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            RestoreError::Store(e) => write!(f, "reading the checkpoint failed: {}", e),
+            RestoreError::UnknownHead(checkpoint) => write!(
+                f,
+                "the head {:?} of the checkpoint after {} batches is not in the backup",
+                checkpoint.head, checkpoint.batches
+            ),
+            RestoreError::StorePanicked => write!(f, "the finalization state store panicked"),
+        }
+    }
+}
+
+/// Gives access to the delivery checkpoint and the finalization index persisted in the store
+/// provided by the application, see [`crate::LocalIO::with_finalization_state`].
+///
+/// The session keeps the state up to date, while the application can query a clone of it at
+/// any time, also after the session ended.
+#[derive(Clone)]
+pub struct FinalizationState<H: Hasher> {
+    store: Arc<Mutex<Box<dyn FinalizationStateStore<H::Hash>>>>,
+}
+
+impl<H: Hasher> FinalizationState<H> {
+    pub fn new(store: impl FinalizationStateStore<H::Hash>) -> Self {
+        FinalizationState {
+            store: Arc::new(Mutex::new(Box::new(store))),
+        }
+    }
+
+    /// The number of the batch the unit was delivered in, counting from the beginning of the
+    /// session. Units of batches older than [`FINALIZATION_INDEX_RETENTION`] might be forgotten.
+    pub fn finalized_in(&self, unit: &H::Hash) -> io::Result<Option<u64>> {
+        self.store.lock().get_index_entry(unit)
+    }
+
+    /// Whether the unit was delivered to the finalization handler.
+    pub fn is_finalized(&self, unit: &H::Hash) -> io::Result<bool> {
+        Ok(self.finalized_in(unit)?.is_some())
+    }
+
+    /// The identifier of the batch with the given number, see [`crate::protocol::batch_id`].
+    /// Identifiers of batches older than [`FINALIZATION_INDEX_RETENTION`] might be forgotten.
+    pub fn batch_id(&self, number: u64) -> io::Result<Option<H::Hash>> {
+        self.store.lock().get_batch_id(number)
+    }
+
+    /// The point up to which the batches were passed to the finalization handler.
+    pub fn checkpoint(&self) -> io::Result<Option<DeliveryCheckpoint<H::Hash>>> {
+        self.store.lock().get_checkpoint()
+    }
+
+    /// Returns the number of batches delivered before the restart. Every delivered unit was
+    /// saved in the backup before, so the head of the checkpoint has to be among the units
+    /// loaded from it.
+    pub(crate) fn restore(&self, backup: &HashSet<H::Hash>) -> Result<u64, RestoreError<H::Hash>> {
+        match self.checkpoint().map_err(RestoreError::Store)? {
+            Some(checkpoint) if !backup.contains(&checkpoint.head) => {
+                Err(RestoreError::UnknownHead(checkpoint))
+            }
+            Some(checkpoint) => Ok(checkpoint.batches),
+            None => Ok(0),
+        }
+    }
+
+    /// Records a batch that was just passed to the finalization handler. The checkpoint is
+    /// written last, so it is never ahead of the index or of the handler.
+    pub(crate) fn record(&self, number: u64, id: H::Hash, units: Vec<H::Hash>) -> io::Result<()> {
+        let head = match units.last() {
+            Some(head) => *head,
+            None => return Ok(()),
+        };
+        let mut store = self.store.lock();
+        store.put_index_entries(units.into_iter().map(|unit| (unit, number)).collect())?;
+        store.put_batch_id(number, id)?;
+        if number > 0 && number % FINALIZATION_INDEX_RETENTION == 0 {
+            store.prune_below(number + 1 - FINALIZATION_INDEX_RETENTION)?;
+        }
+        store.put_checkpoint(DeliveryCheckpoint {
+            batches: number + 1,
+            head,
+            batch_id: id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        finalization_state::{FinalizationState, RestoreError, FINALIZATION_INDEX_RETENTION},
+        DeliveryCheckpoint, Hasher,
+    };
+    use aleph_bft_mock::{FinalizationStateStore, Hasher64};
+    use std::collections::HashSet;
+
+    fn hash(seed: u64) -> [u8; 8] {
+        Hasher64::hash(&seed.to_le_bytes())
+    }
+
+    #[test]
+    fn records_index_and_checkpoint() {
+        let state = FinalizationState::<Hasher64>::new(FinalizationStateStore::new());
+        state
+            .record(0, hash(100), vec![hash(0), hash(1)])
+            .expect("mock store works");
+        state
+            .record(1, hash(101), vec![hash(2)])
+            .expect("mock store works");
+        assert_eq!(state.finalized_in(&hash(1)).unwrap(), Some(0));
+        assert_eq!(state.finalized_in(&hash(2)).unwrap(), Some(1));
+        assert!(!state.is_finalized(&hash(3)).unwrap());
+        assert_eq!(state.batch_id(0).unwrap(), Some(hash(100)));
+        assert_eq!(state.batch_id(2).unwrap(), None);
+        assert_eq!(
+            state.checkpoint().unwrap(),
+            Some(DeliveryCheckpoint {
+                batches: 2,
+                head: hash(2),
+                batch_id: hash(101),
+            })
+        );
+    }
+
+    #[test]
+    fn prunes_old_batches() {
+        let state = FinalizationState::<Hasher64>::new(FinalizationStateStore::new());
+        for number in 0..=FINALIZATION_INDEX_RETENTION + 1 {
+            state
+                .record(number, hash(number), vec![hash(number)])
+                .expect("mock store works");
+        }
+        assert!(!state.is_finalized(&hash(0)).unwrap());
+        assert!(state.is_finalized(&hash(1)).unwrap());
+        assert_eq!(state.batch_id(0).unwrap(), None);
+        assert_eq!(state.batch_id(1).unwrap(), Some(hash(1)));
+        assert!(state
+            .is_finalized(&hash(FINALIZATION_INDEX_RETENTION + 1))
+            .unwrap());
+    }
+
+    #[test]
+    fn rejects_checkpoint_ahead_of_backup() {
+        let state = FinalizationState::<Hasher64>::new(FinalizationStateStore::new());
+        assert_eq!(state.restore(&HashSet::new()).unwrap(), 0);
+        state
+            .record(0, hash(100), vec![hash(0), hash(1)])
+            .expect("mock store works");
+        let backup: HashSet<_> = [hash(0), hash(1)].into_iter().collect();
+        assert_eq!(state.restore(&backup).unwrap(), 1);
+        let backup: HashSet<_> = [hash(0)].into_iter().collect();
+        assert!(matches!(
+            state.restore(&backup),
+            Err(RestoreError::UnknownHead(_))
+        ));
+    }
+}
@@ -0,0 +1,379 @@
+//! Entry points for fuzzing the decoding of the bytes that other nodes and the backup control,
+//! e.g. with `cargo fuzz`, available with the `fuzz` feature. The types of `aleph-bft-mock` take
+//! the place of the ones provided by the application.
+//!
+//! The entry points accept any bytes and return an error for the ones that do not decode. A panic
+//! is a bug, as is a decoded value that does not decode to itself after encoding it again. The
+//! corpus functions return the encodings of valid values of every kind, to seed the fuzzer with.
+
+use crate::{
+    alerts::{Alert, AlertMessage},
+    backup::{decode_backup, BackupFingerprint},
+    decoding::MAX_DECODE_DEPTH,
+    dissemination::{CompactUnit, NewestUnitsResponse, RequestId},
+    member::UnitMessage,
+    network::{Compression, NetworkData, NetworkDataInner, MAX_DECOMPRESSED_SIZE},
+    runway::{DagDigest, NewestUnitResponse},
+    units::{ControlHash, FullUnit, PreUnit, UncheckedSignedUnit, Unit, UnitCoord},
+    Hasher, Indexed, NodeCount, NodeIndex, NodeMap, Round, Signable, Signed, UncheckedSigned,
+    MAX_SUPPORTED_VERSION,
+};
+use aleph_bft_mock::{Data, Hasher64, Keychain, PartialMultisignature, Signature};
+use aleph_bft_rmc::Message as RmcMessage;
+use codec::{Decode, DecodeAll, DecodeLimit, Encode, Error as CodecError};
+use std::fmt::Debug;
+
+type FuzzUnit = UncheckedSignedUnit<Hasher64, Data, Signature>;
+type FuzzUnitMessage = UnitMessage<Hasher64, Data, Signature>;
+type FuzzAlertMessage = AlertMessage<Hasher64, Data, Signature, PartialMultisignature>;
+type FuzzNetworkData = NetworkData<Hasher64, Data, Signature, PartialMultisignature>;
+type FuzzNetworkDataInner = NetworkDataInner<Hasher64, Data, Signature, PartialMultisignature>;
+
+const N_MEMBERS: NodeCount = NodeCount(7);
+
+fn check_round_trip<T: Decode + Encode + Debug + PartialEq>(decoded: &T) {
+    let encoded = decoded.encode();
+    let again = T::decode_all(&mut &encoded[..]).expect("an encoded value should decode");
+    assert_eq!(&again, decoded, "a value should decode to itself");
+}
+
+/// Decodes the bytes as a message from the network, as well as its decompressed content, if it
+/// is compressed.
+pub fn fuzz_decode_network_data(bytes: &[u8]) -> Result<(), CodecError> {
+    let data = FuzzNetworkData::decode_all(&mut &bytes[..])?;
+    data.included_data_with_creators();
+    match &data.0 {
+        // The content of messages of newer versions is never read, nor encoded again.
+        NetworkDataInner::Unsupported => {}
+        NetworkDataInner::Compressed(message) => {
+            check_round_trip(&data);
+            if let Ok(inner) = FuzzNetworkDataInner::decompress(message, MAX_DECOMPRESSED_SIZE) {
+                check_round_trip(&NetworkData(inner, MAX_SUPPORTED_VERSION));
+            }
+        }
+        _ => check_round_trip(&data),
+    }
+    Ok(())
+}
+
+/// Decodes the bytes as a message concerning units.
+pub fn fuzz_decode_unit_message(bytes: &[u8]) -> Result<(), CodecError> {
+    let message = FuzzUnitMessage::decode_all_with_depth_limit(MAX_DECODE_DEPTH, &mut &bytes[..])?;
+    message.included_data_with_creators();
+    message.unit_count();
+    check_round_trip(&message);
+    Ok(())
+}
+
+/// Decodes the bytes as a message concerning alerts.
+pub fn fuzz_decode_alert_message(bytes: &[u8]) -> Result<(), CodecError> {
+    let message = FuzzAlertMessage::decode_all_with_depth_limit(MAX_DECODE_DEPTH, &mut &bytes[..])?;
+    message.included_data_with_creators();
+    message.sender();
+    check_round_trip(&message);
+    Ok(())
+}
+
+/// Decodes the bytes as the content of a backup, i.e. the optional header followed by units.
+pub fn fuzz_decode_backup(bytes: &[u8]) -> Result<(), CodecError> {
+    let (fingerprint, units) = decode_backup::<Hasher64, Data, Signature>(bytes)?;
+    if let Some(fingerprint) = fingerprint {
+        check_round_trip(&fingerprint);
+    }
+    for unit in &units {
+        check_round_trip(unit);
+    }
+    Ok(())
+}
+
+fn unit(creator: NodeIndex, round: Round, data: Data) -> FuzzUnit {
+    let mut parents = NodeMap::with_size(N_MEMBERS);
+    if round > 0 {
+        for parent in 0..N_MEMBERS.0 {
+            let hash = (parent as u64, round).using_encoded(Hasher64::hash);
+            parents.insert(parent.into(), (hash, round - 1));
+        }
+    }
+    let pre_unit = PreUnit::new(creator, round, ControlHash::new(&parents));
+    let full_unit = FullUnit::new(pre_unit, Some(data), 0);
+    Signed::sign(full_unit, &Keychain::new(N_MEMBERS, creator))
+        .expect("the mock keychain never fails")
+        .into_unchecked()
+}
+
+fn hash_of(unit: &FuzzUnit) -> <Hasher64 as Hasher>::Hash {
+    Signable::hash(unit.as_signable())
+}
+
+fn unit_messages() -> Vec<FuzzUnitMessage> {
+    let sender = NodeIndex(3);
+    let parents: Vec<_> = (0..N_MEMBERS.0).map(|i| unit(i.into(), 6, 43)).collect();
+    let child = unit(1.into(), 7, 1729);
+    let keychain = Keychain::new(N_MEMBERS, sender);
+    let newest = NewestUnitResponse::new(1.into(), sender, Some(child.clone()), 2137);
+    let newest = Signed::sign(newest, &keychain)
+        .expect("the mock keychain never fails")
+        .into_unchecked();
+    let newest_units = NewestUnitsResponse::new(1.into(), sender, 2137, parents.clone());
+    let newest_units = Signed::sign(newest_units, &keychain)
+        .expect("the mock keychain never fails")
+        .into_unchecked();
+    let mut digest = DagDigest::new(N_MEMBERS);
+    for parent in &parents {
+        let full_unit = parent.as_signable();
+        digest.add_unit::<Hasher64>(full_unit.creator(), full_unit.round(), &hash_of(parent));
+    }
+    let compact = parents
+        .iter()
+        .enumerate()
+        .map(|(i, parent)| match i % 2 {
+            0 => CompactUnit::Full(parent.clone()),
+            _ => CompactUnit::HashRef(hash_of(parent)),
+        })
+        .collect();
+    vec![
+        UnitMessage::NewUnit(child.clone()),
+        UnitMessage::RequestCoord(sender, UnitCoord::new(6, 2.into())),
+        UnitMessage::ResponseCoord(parents[2].clone()),
+        UnitMessage::RequestParents(sender, hash_of(&child)),
+        UnitMessage::ResponseParents(hash_of(&child), parents.clone()),
+        UnitMessage::RequestNewest(sender, 2137),
+        UnitMessage::ResponseNewest(newest),
+        UnitMessage::DagDigest(sender, digest.clone()),
+        UnitMessage::NotFound(sender, RequestId::Parents(hash_of(&child))),
+        UnitMessage::RequestUnits(sender, parents.iter().map(hash_of).collect()),
+        UnitMessage::ResponseUnits(parents),
+        UnitMessage::ResponseParentsCompact(sender, hash_of(&child), compact),
+        UnitMessage::RequestNewestUnits(sender, 2137, digest),
+        UnitMessage::ResponseNewestUnits(newest_units),
+        UnitMessage::Pruned(sender, RequestId::Coord(UnitCoord::new(1, 2.into()))),
+    ]
+}
+
+fn alert_messages() -> Vec<FuzzAlertMessage> {
+    let sender = NodeIndex(3);
+    let keychain = Keychain::new(N_MEMBERS, sender);
+    let proof = |forker: usize| (unit(forker.into(), 5, 0), unit(forker.into(), 5, 1));
+    let legit_units: Vec<_> = (6..9).map(|round| unit(4.into(), round, 0)).collect();
+    let alert = Alert::new(sender, proof(4), legit_units.clone());
+    let aggregated = Alert::aggregated(sender, vec![proof(4), proof(5)], legit_units.clone());
+    let alert_hash = Signable::hash(&alert);
+    let signed_hash =
+        Signed::sign_with_index(alert_hash, &keychain).expect("the mock keychain never fails");
+    let multisigned = signed_hash.clone().into_partially_multisigned(&keychain);
+    let signed_hash: UncheckedSigned<Indexed<_>, _> = signed_hash.into_unchecked();
+    let sign = |alert| {
+        Signed::sign(alert, &keychain)
+            .expect("the mock keychain never fails")
+            .into_unchecked()
+    };
+    vec![
+        AlertMessage::ForkAlert(sign(alert.clone())),
+        AlertMessage::ForkAlert(sign(aggregated)),
+        AlertMessage::RmcMessage(sender, RmcMessage::SignedHash(signed_hash)),
+        AlertMessage::RmcMessage(
+            sender,
+            RmcMessage::MultisignedHash(multisigned.into_unchecked()),
+        ),
+        AlertMessage::AlertRequest(sender, alert_hash),
+        AlertMessage::CompactForkAlert(
+            Signed::sign(alert.compact(), &keychain)
+                .expect("the mock keychain never fails")
+                .into_unchecked(),
+        ),
+        AlertMessage::LegitUnitsRequest(
+            sender,
+            alert_hash,
+            legit_units.iter().map(hash_of).collect(),
+        ),
+        AlertMessage::LegitUnits(sender, alert_hash, legit_units),
+    ]
+}
+
+/// Encodings of every kind of message concerning units, for [`fuzz_decode_unit_message`].
+pub fn unit_message_corpus() -> Vec<Vec<u8>> {
+    unit_messages().iter().map(Encode::encode).collect()
+}
+
+/// Encodings of every kind of message concerning alerts, for [`fuzz_decode_alert_message`].
+pub fn alert_message_corpus() -> Vec<Vec<u8>> {
+    alert_messages().iter().map(Encode::encode).collect()
+}
+
+/// How the messages of the corpus are compressed, only when built with the `zstd` feature, which
+/// the `fuzz` feature enables.
+#[cfg(feature = "zstd")]
+const CORPUS_COMPRESSION: Compression = Compression::Zstd;
+#[cfg(not(feature = "zstd"))]
+const CORPUS_COMPRESSION: Compression = Compression::None;
+
+/// Encodings of every kind of message from the network, at every supported version, compressed
+/// and not, for [`fuzz_decode_network_data`].
+pub fn network_data_corpus() -> Vec<Vec<u8>> {
+    let units = unit_messages().into_iter().map(NetworkDataInner::Units);
+    let alerts = alert_messages().into_iter().map(NetworkDataInner::Alert);
+    units
+        .chain(alerts)
+        .flat_map(|inner| {
+            let compressed = inner.clone().compressed(CORPUS_COMPRESSION);
+            (0..=MAX_SUPPORTED_VERSION).flat_map(move |version| {
+                [
+                    NetworkData(inner.clone(), version).encode(),
+                    NetworkData(compressed.clone(), version).encode(),
+                ]
+            })
+        })
+        .collect()
+}
+
+/// Backups with and without the header, for [`fuzz_decode_backup`].
+pub fn backup_corpus() -> Vec<Vec<u8>> {
+    let units: Vec<u8> = (0..4)
+        .flat_map(|round| (0..N_MEMBERS.0).map(move |creator| unit(creator.into(), round, 0)))
+        .flat_map(|unit| unit.encode())
+        .collect();
+    let header = BackupFingerprint::<Hasher64>::new(0, N_MEMBERS, b"committee").header();
+    vec![
+        Vec::new(),
+        header.clone(),
+        units.clone(),
+        header.into_iter().chain(units).collect(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        decoding::MAX_DECODED_ITEMS,
+        fuzz::{
+            alert_message_corpus, backup_corpus, fuzz_decode_alert_message, fuzz_decode_backup,
+            fuzz_decode_network_data, fuzz_decode_unit_message, network_data_corpus, unit,
+            unit_message_corpus, FuzzNetworkData, FuzzUnitMessage, N_MEMBERS,
+        },
+        units::{ValidationError, Validator},
+        NodeIndex, Round,
+    };
+    use aleph_bft_mock::Keychain;
+    use codec::{Compact, Decode, Encode, Error as CodecError};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    type EntryPoint = fn(&[u8]) -> Result<(), CodecError>;
+
+    fn message_corpora() -> Vec<(Vec<Vec<u8>>, EntryPoint)> {
+        vec![
+            (unit_message_corpus(), fuzz_decode_unit_message),
+            (alert_message_corpus(), fuzz_decode_alert_message),
+            (network_data_corpus(), fuzz_decode_network_data),
+        ]
+    }
+
+    fn corpora() -> Vec<(Vec<Vec<u8>>, EntryPoint)> {
+        let mut corpora = message_corpora();
+        corpora.push((backup_corpus(), fuzz_decode_backup));
+        corpora
+    }
+
+    #[test]
+    fn corpus_round_trips() {
+        for (corpus, entry_point) in corpora() {
+            assert!(!corpus.is_empty());
+            for encoded in corpus {
+                assert_eq!(entry_point(&encoded), Ok(()));
+            }
+        }
+        for encoded in network_data_corpus() {
+            let decoded = FuzzNetworkData::decode(&mut &encoded[..]).expect("should decode");
+            assert_eq!(decoded.encode(), encoded);
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn corpus_has_compressed_messages() {
+        let compressed = network_data_corpus()
+            .into_iter()
+            .map(|encoded| FuzzNetworkData::decode(&mut &encoded[..]).expect("should decode"))
+            .filter(|decoded| matches!(decoded.0, crate::network::NetworkDataInner::Compressed(_)))
+            .count();
+        assert!(compressed > 0);
+    }
+
+    #[test]
+    fn refuses_truncated_messages() {
+        // A backup cut right after a unit is a valid, shorter one, so only messages are checked.
+        for (corpus, entry_point) in message_corpora() {
+            for encoded in corpus {
+                for len in [encoded.len() - 1, encoded.len() - 5, encoded.len() / 2] {
+                    assert!(entry_point(&encoded[..len]).is_err());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn refuses_truncated_signature_of_a_backup_unit() {
+        let encoded = unit(0.into(), 0, 0).encode();
+        assert_eq!(fuzz_decode_backup(&encoded), Ok(()));
+        assert!(fuzz_decode_backup(&encoded[..encoded.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn refuses_huge_claimed_list_lengths() {
+        // Variant of `ResponseUnits`, followed by the length of the list of units.
+        for len in [
+            u32::MAX,
+            MAX_DECODED_ITEMS as u32 + 1,
+            MAX_DECODED_ITEMS as u32,
+        ] {
+            let mut encoded = vec![10];
+            encoded.extend(Compact(len).encode());
+            encoded.extend(unit(0.into(), 0, 0).encode());
+            assert!(fuzz_decode_unit_message(&encoded).is_err());
+        }
+        // Variant of `LegitUnitsRequest`, the sender, the alert hash and the list of hashes.
+        let mut encoded = vec![4];
+        encoded.extend(NodeIndex(0).encode());
+        encoded.extend([0; 8]);
+        encoded.extend(Compact(u32::MAX).encode());
+        assert!(fuzz_decode_alert_message(&encoded).is_err());
+    }
+
+    #[test]
+    fn decodes_absurd_rounds_for_validation_to_refuse() {
+        let unit = unit(2.into(), Round::MAX, 0);
+        let message = FuzzUnitMessage::NewUnit(unit.clone());
+        assert_eq!(fuzz_decode_unit_message(&message.encode()), Ok(()));
+        let validator = Validator::new(0, Keychain::new(N_MEMBERS, 0.into()), 5000);
+        assert!(matches!(
+            validator.validate_unit(unit),
+            Err(ValidationError::RoundTooHigh(_))
+        ));
+    }
+
+    #[test]
+    fn never_panics_on_mutated_corpus() {
+        let mut rng = StdRng::seed_from_u64(2137);
+        for (corpus, entry_point) in corpora() {
+            for encoded in corpus {
+                for _ in 0..50 {
+                    let mut mutated = encoded.clone();
+                    for _ in 0..rng.gen_range(1..4) {
+                        match (rng.gen_range(0..3), mutated.len()) {
+                            (_, 0) | (0, _) => mutated.push(rng.gen()),
+                            (1, len) => mutated[rng.gen_range(0..len)] = rng.gen(),
+                            (_, len) => mutated.truncate(rng.gen_range(0..len)),
+                        }
+                    }
+                    let _ = entry_point(&mutated);
+                }
+            }
+        }
+        for _ in 0..1000 {
+            let len = rng.gen_range(0..200);
+            let bytes: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            for (_, entry_point) in corpora() {
+                let _ = entry_point(&bytes);
+            }
+        }
+    }
+}
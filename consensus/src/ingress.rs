@@ -0,0 +1,266 @@
+use futures::{
+    stream::FusedStream,
+    task::{Context, Poll, Waker},
+    Stream,
+};
+use parking_lot::Mutex;
+use std::{
+    collections::VecDeque,
+    fmt::{Debug, Formatter, Result as FmtResult},
+    pin::Pin,
+    sync::Arc,
+};
+
+/// How important a message from the network is, for deciding what to drop when a queue is full.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Priority {
+    /// Requests for units and the responses to them, which are repeated if lost.
+    Low,
+    /// Everything else.
+    Normal,
+}
+
+/// Why a message could not be queued, together with the message that was dropped.
+#[derive(Eq, PartialEq)]
+pub(crate) enum IngressError<T> {
+    /// The queue is full, so either the message or an older one of lower priority was dropped.
+    Full(T),
+    /// The receiver is gone.
+    Closed(T),
+}
+
+impl<T> Debug for IngressError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            IngressError::Full(_) => write!(f, "Full(..)"),
+            IngressError::Closed(_) => write!(f, "Closed(..)"),
+        }
+    }
+}
+
+struct State<T> {
+    low: VecDeque<(u64, T)>,
+    normal: VecDeque<(u64, T)>,
+    next_seq: u64,
+    senders: usize,
+    receiver_alive: bool,
+    waker: Option<Waker>,
+}
+
+impl<T> State<T> {
+    fn len(&self) -> usize {
+        self.low.len() + self.normal.len()
+    }
+
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Pops the oldest message, regardless of its priority.
+    fn pop(&mut self) -> Option<T> {
+        let queue = match (self.low.front(), self.normal.front()) {
+            (Some((low, _)), Some((normal, _))) if low < normal => &mut self.low,
+            (_, Some(_)) => &mut self.normal,
+            (Some(_), None) => &mut self.low,
+            (None, None) => return None,
+        };
+        queue.pop_front().map(|(_, message)| message)
+    }
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    capacity: usize,
+}
+
+/// Creates a queue for messages from the network holding at most `capacity` messages. When it
+/// is full, the oldest message of low priority is dropped to make room, or the incoming one if
+/// there is none. Messages are received in the order they were sent.
+pub(crate) fn ingress_queue<T>(capacity: usize) -> (IngressSender<T>, IngressReceiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            low: VecDeque::new(),
+            normal: VecDeque::new(),
+            next_seq: 0,
+            senders: 1,
+            receiver_alive: true,
+            waker: None,
+        }),
+        capacity,
+    });
+    (
+        IngressSender {
+            shared: shared.clone(),
+        },
+        IngressReceiver {
+            shared,
+            terminated: false,
+        },
+    )
+}
+
+/// The sending side of [`ingress_queue`].
+pub(crate) struct IngressSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> IngressSender<T> {
+    /// Queues the message, dropping a message if the queue is full.
+    pub fn send(&self, message: T, priority: Priority) -> Result<(), IngressError<T>> {
+        let mut state = self.shared.state.lock();
+        if !state.receiver_alive {
+            return Err(IngressError::Closed(message));
+        }
+        let mut dropped = None;
+        if state.len() >= self.shared.capacity {
+            match state.low.pop_front() {
+                Some((_, oldest)) => dropped = Some(oldest),
+                None => return Err(IngressError::Full(message)),
+            }
+        }
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        match priority {
+            Priority::Low => state.low.push_back((seq, message)),
+            Priority::Normal => state.normal.push_back((seq, message)),
+        }
+        state.wake();
+        match dropped {
+            Some(oldest) => Err(IngressError::Full(oldest)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T> Clone for IngressSender<T> {
+    fn clone(&self) -> Self {
+        self.shared.state.lock().senders += 1;
+        IngressSender {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for IngressSender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            state.wake();
+        }
+    }
+}
+
+/// The receiving side of [`ingress_queue`], ending once all the senders are gone and the queued
+/// messages are received.
+pub(crate) struct IngressReceiver<T> {
+    shared: Arc<Shared<T>>,
+    terminated: bool,
+}
+
+impl<T> IngressReceiver<T> {
+    /// The number of queued messages.
+    pub fn len(&self) -> usize {
+        self.shared.state.lock().len()
+    }
+}
+
+impl<T> Stream for IngressReceiver<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        if self.terminated {
+            return Poll::Ready(None);
+        }
+        let mut state = self.shared.state.lock();
+        if let Some(message) = state.pop() {
+            return Poll::Ready(Some(message));
+        }
+        if state.senders == 0 {
+            drop(state);
+            self.terminated = true;
+            return Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<T> FusedStream for IngressReceiver<T> {
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+impl<T> Drop for IngressReceiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock();
+        state.receiver_alive = false;
+        state.low.clear();
+        state.normal.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ingress::{ingress_queue, IngressError, Priority};
+    use futures::StreamExt;
+
+    #[test]
+    fn stays_within_capacity() {
+        let (sender, receiver) = ingress_queue(10);
+        let mut rejected = Vec::new();
+        for message in 0..100_000 {
+            if let Err(IngressError::Full(message)) = sender.send(message, Priority::Normal) {
+                rejected.push(message);
+            }
+        }
+        assert_eq!(receiver.len(), 10);
+        assert_eq!(rejected, (10..100_000).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn drops_low_priority_messages_first() {
+        let (sender, receiver) = ingress_queue(3);
+        sender.send(0, Priority::Low).expect("there is room");
+        sender.send(1, Priority::Normal).expect("there is room");
+        sender.send(2, Priority::Low).expect("there is room");
+        assert_eq!(sender.send(3, Priority::Normal), Err(IngressError::Full(0)));
+        assert_eq!(sender.send(4, Priority::Low), Err(IngressError::Full(2)));
+        assert_eq!(sender.send(5, Priority::Normal), Err(IngressError::Full(4)));
+        assert_eq!(sender.send(6, Priority::Normal), Err(IngressError::Full(6)));
+        drop(sender);
+        assert_eq!(receiver.collect::<Vec<_>>().await, vec![1, 3, 5]);
+    }
+
+    #[tokio::test]
+    async fn keeps_the_order_across_priorities() {
+        let (sender, receiver) = ingress_queue(10);
+        for (message, priority) in [
+            (0, Priority::Normal),
+            (1, Priority::Low),
+            (2, Priority::Low),
+            (3, Priority::Normal),
+            (4, Priority::Low),
+        ] {
+            sender.send(message, priority).expect("there is room");
+        }
+        drop(sender);
+        assert_eq!(
+            receiver.collect::<Vec<_>>().await,
+            (0..5).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn sending_fails_without_the_receiver() {
+        let (sender, receiver) = ingress_queue(10);
+        drop(receiver);
+        assert_eq!(
+            sender.send(0, Priority::Normal),
+            Err(IngressError::Closed(0))
+        );
+    }
+}
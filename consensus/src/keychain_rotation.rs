@@ -0,0 +1,208 @@
+use crate::{Index, Keychain, MultiKeychain, NodeCount, NodeIndex};
+use log::info;
+use parking_lot::RwLock;
+use std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    sync::Arc,
+};
+use thiserror::Error;
+
+const LOG_TARGET: &str = "AlephBFT-keychain";
+
+/// Why rotating the keychain failed, see [`KeychainRotationHandle::rotate`].
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum RotationError {
+    #[error("the new keychain is of {new:?} instead of {current:?}")]
+    WrongIndex { current: NodeIndex, new: NodeIndex },
+    #[error("the new keychain knows {new:?} nodes instead of {current:?}")]
+    WrongNodeCount { current: NodeCount, new: NodeCount },
+}
+
+/// Allows the application to replace the keychain of a running session, see
+/// [`keychain_rotation`].
+#[derive(Clone)]
+pub struct KeychainRotationHandle<MK: MultiKeychain> {
+    current: Arc<RwLock<MK>>,
+}
+
+impl<MK: MultiKeychain> KeychainRotationHandle<MK> {
+    /// Makes everything signed from now on, i.e. units, responses to requests for our newest
+    /// unit and alerts together with their multisignatures, use the given keychain. The keychain
+    /// has to be of the same node and committee, and has to keep verifying the signatures made
+    /// with any key used earlier in the session, as they keep arriving from the network.
+    pub fn rotate(&self, keychain: MK) -> Result<(), RotationError> {
+        let mut current = self.current.write();
+        if keychain.index() != current.index() {
+            return Err(RotationError::WrongIndex {
+                current: current.index(),
+                new: keychain.index(),
+            });
+        }
+        if keychain.node_count() != current.node_count() {
+            return Err(RotationError::WrongNodeCount {
+                current: current.node_count(),
+                new: keychain.node_count(),
+            });
+        }
+        *current = keychain;
+        info!(target: LOG_TARGET, "{:?} Rotated the keychain.", current.index());
+        Ok(())
+    }
+}
+
+/// A keychain that can be replaced while the session runs, to be passed to
+/// [`crate::run_session`] instead of the keychain it starts with, see [`keychain_rotation`].
+/// Clones share the replaced keychain.
+#[derive(Clone)]
+pub struct RotatingKeychain<MK: MultiKeychain> {
+    current: Arc<RwLock<MK>>,
+}
+
+impl<MK: MultiKeychain> Debug for RotatingKeychain<MK> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("RotatingKeychain")
+            .field("index", &self.index())
+            .finish()
+    }
+}
+
+impl<MK: MultiKeychain> Index for RotatingKeychain<MK> {
+    fn index(&self) -> NodeIndex {
+        self.current.read().index()
+    }
+}
+
+impl<MK: MultiKeychain> Keychain for RotatingKeychain<MK> {
+    type Signature = MK::Signature;
+    type SignError = MK::SignError;
+
+    fn node_count(&self) -> NodeCount {
+        self.current.read().node_count()
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Self::Signature, Self::SignError> {
+        self.current.read().sign(msg)
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+        self.current.read().verify(msg, sgn, index)
+    }
+
+    fn is_verification_cheap(&self) -> bool {
+        self.current.read().is_verification_cheap()
+    }
+}
+
+impl<MK: MultiKeychain> MultiKeychain for RotatingKeychain<MK> {
+    type PartialMultisignature = MK::PartialMultisignature;
+
+    fn bootstrap_multi(
+        &self,
+        signature: &Self::Signature,
+        index: NodeIndex,
+    ) -> Self::PartialMultisignature {
+        self.current.read().bootstrap_multi(signature, index)
+    }
+
+    fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool {
+        self.current.read().is_complete(msg, partial)
+    }
+}
+
+/// Creates a handle for rotating the keys of a session together with the keychain that should
+/// be passed to [`crate::run_session`], starting with the given keychain. Everything in the
+/// session signing or verifying holds a clone of the keychain, so a rotation takes effect
+/// everywhere at once, without restarting the session.
+pub fn keychain_rotation<MK: MultiKeychain>(
+    keychain: MK,
+) -> (KeychainRotationHandle<MK>, RotatingKeychain<MK>) {
+    let current = Arc::new(RwLock::new(keychain));
+    (
+        KeychainRotationHandle {
+            current: current.clone(),
+        },
+        RotatingKeychain { current },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        keychain_rotation::{keychain_rotation, RotationError},
+        Keychain as _, NodeCount, NodeIndex,
+    };
+    use aleph_bft_mock::Keychain;
+
+    const N_MEMBERS: NodeCount = NodeCount(4);
+    const MSG: &[u8] = b"hello";
+
+    #[test]
+    fn signatures_from_before_and_after_rotation_verify() {
+        let node_ix = NodeIndex(1);
+        let peer = Keychain::new(N_MEMBERS, NodeIndex(2)).with_key_count(2);
+        let (handle, keychain) =
+            keychain_rotation(Keychain::new(N_MEMBERS, node_ix).with_key_count(2));
+        let before = keychain.sign(MSG).expect("the keychain never fails");
+        handle
+            .rotate(
+                Keychain::new(N_MEMBERS, node_ix)
+                    .with_key_count(2)
+                    .with_key(1),
+            )
+            .expect("the keychain is of the same node");
+        let after = keychain.sign(MSG).expect("the keychain never fails");
+        assert_ne!(before, after);
+        for signature in [&before, &after] {
+            assert!(keychain.verify(MSG, signature, node_ix));
+            assert!(peer.verify(MSG, signature, node_ix));
+            assert!(!peer.verify(b"other", signature, node_ix));
+        }
+        // A peer that does not know the new key rejects what we sign with it.
+        let outdated = Keychain::new(N_MEMBERS, NodeIndex(2));
+        assert!(outdated.verify(MSG, &before, node_ix));
+        assert!(!outdated.verify(MSG, &after, node_ix));
+    }
+
+    #[test]
+    fn clones_use_the_rotated_keychain() {
+        let node_ix = NodeIndex(0);
+        let (handle, keychain) =
+            keychain_rotation(Keychain::new(N_MEMBERS, node_ix).with_key_count(2));
+        let clone = keychain.clone();
+        handle
+            .rotate(
+                Keychain::new(N_MEMBERS, node_ix)
+                    .with_key_count(2)
+                    .with_key(1),
+            )
+            .expect("the keychain is of the same node");
+        assert_eq!(
+            clone.sign(MSG).expect("the keychain never fails"),
+            keychain.sign(MSG).expect("the keychain never fails")
+        );
+    }
+
+    #[test]
+    fn keychains_of_other_nodes_are_refused() {
+        let (handle, keychain) = keychain_rotation(Keychain::new(N_MEMBERS, NodeIndex(0)));
+        let before = keychain.sign(MSG).expect("the keychain never fails");
+        assert_eq!(
+            handle.rotate(Keychain::new(N_MEMBERS, NodeIndex(3))),
+            Err(RotationError::WrongIndex {
+                current: NodeIndex(0),
+                new: NodeIndex(3),
+            })
+        );
+        assert_eq!(
+            handle.rotate(Keychain::new(NodeCount(7), NodeIndex(0))),
+            Err(RotationError::WrongNodeCount {
+                current: N_MEMBERS,
+                new: NodeCount(7),
+            })
+        );
+        assert_eq!(
+            keychain.sign(MSG).expect("the keychain never fails"),
+            before
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{NodeCount, NodeIndex, NodeMap, NodeSubset, Round};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// How much a single round influences the lateness score of a peer.
+const LATENESS_SCORE_WEIGHT: f64 = 0.1;
+
+/// The peers whose units of the given round arrived only after we created our unit of the next
+/// round, so they could not be its parents.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct LateUnits {
+    pub round: Round,
+    pub late: NodeSubset,
+    /// How long after our unit of the next round the latest of the late units arrived. For units
+    /// that did not arrive at all this is measured until the summary was made.
+    pub max_lateness: Duration,
+}
+
+impl Display for LateUnits {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{} unit(s) of round {} too late to be our parents, the latest by {}ms",
+            self.late.len(),
+            self.round,
+            self.max_lateness.as_millis()
+        )
+    }
+}
+
+/// Allows the application to see which peers tend to be too slow for their units to become our
+/// parents, e.g. to tune the delay schedules.
+#[derive(Clone)]
+pub struct LatenessHandle {
+    scores: Arc<Mutex<NodeMap<f64>>>,
+}
+
+impl LatenessHandle {
+    /// A moving average of how often the units of each peer arrived too late to become our
+    /// parents, between 0 (never) and 1 (always). Empty until the session starts.
+    pub fn scores(&self) -> NodeMap<f64> {
+        self.scores.lock().clone()
+    }
+}
+
+/// The part of the lateness tracking passed to the session, see [`lateness_monitor`].
+#[derive(Clone, Default)]
+pub struct LatenessMonitor {
+    scores: Arc<Mutex<NodeMap<f64>>>,
+}
+
+/// Creates a handle for inspecting the lateness of peers together with the monitor that should be
+/// passed to the session with [`crate::LocalIO::with_lateness_monitor`].
+pub fn lateness_monitor() -> (LatenessHandle, LatenessMonitor) {
+    let scores = Arc::new(Mutex::new(NodeMap::default()));
+    (
+        LatenessHandle {
+            scores: scores.clone(),
+        },
+        LatenessMonitor { scores },
+    )
+}
+
+/// Correlates the admission times of units with the moments we create our own units.
+pub(crate) struct LatenessTracker {
+    own_id: NodeIndex,
+    n_members: NodeCount,
+    arrivals: HashMap<Round, HashMap<NodeIndex, Instant>>,
+    /// The moment we created our unit of the next round, for every round not summarized yet.
+    cutoffs: HashMap<Round, Instant>,
+    last_summarized: Option<Round>,
+    monitor: LatenessMonitor,
+}
+
+impl LatenessTracker {
+    pub fn new(own_id: NodeIndex, n_members: NodeCount, monitor: LatenessMonitor) -> Self {
+        let mut scores = NodeMap::with_size(n_members);
+        for node_id in n_members.into_iterator() {
+            scores.insert(node_id, 0.0);
+        }
+        *monitor.scores.lock() = scores;
+        LatenessTracker {
+            own_id,
+            n_members,
+            arrivals: HashMap::new(),
+            cutoffs: HashMap::new(),
+            last_summarized: None,
+            monitor,
+        }
+    }
+
+    pub fn on_unit_admitted(&mut self, creator: NodeIndex, round: Round, now: Instant) {
+        if self.last_summarized.map_or(true, |last| round > last) {
+            self.arrivals
+                .entry(round)
+                .or_default()
+                .entry(creator)
+                .or_insert(now);
+        }
+    }
+
+    /// Notes the creation of our unit of the given round. Summarizes the round two rounds
+    /// earlier, giving the late units of that round one more round to arrive.
+    pub fn on_own_unit_created(&mut self, round: Round, now: Instant) -> Option<LateUnits> {
+        let parent_round = round.checked_sub(1)?;
+        self.cutoffs.insert(parent_round, now);
+        let summarized_round = parent_round.checked_sub(1)?;
+        let cutoff = self.cutoffs.remove(&summarized_round)?;
+        self.last_summarized = Some(summarized_round);
+        let arrivals = self.arrivals.remove(&summarized_round).unwrap_or_default();
+        self.arrivals.retain(|round, _| *round > summarized_round);
+        self.cutoffs.retain(|round, _| *round > summarized_round);
+
+        let mut late = NodeSubset::with_size(self.n_members);
+        let mut max_lateness = Duration::ZERO;
+        for node_id in self.n_members.into_iterator() {
+            if node_id == self.own_id {
+                continue;
+            }
+            let arrival = arrivals.get(&node_id).copied().unwrap_or(now);
+            if arrival > cutoff {
+                late.insert(node_id);
+                max_lateness = max_lateness.max(arrival - cutoff);
+            }
+        }
+        self.update_scores(&late);
+        Some(LateUnits {
+            round: summarized_round,
+            late,
+            max_lateness,
+        })
+    }
+
+    fn update_scores(&self, late: &NodeSubset) {
+        let mut scores = self.monitor.scores.lock();
+        let late: Vec<_> = late.elements().collect();
+        for (node_id, score) in scores.iter_mut() {
+            let sample = match late.contains(&node_id) {
+                true => 1.0,
+                false => 0.0,
+            };
+            *score += LATENESS_SCORE_WEIGHT * (sample - *score);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lateness::{lateness_monitor, LatenessTracker},
+        NodeCount, NodeIndex,
+    };
+    use std::time::{Duration, Instant};
+
+    fn tracker() -> (crate::lateness::LatenessHandle, LatenessTracker) {
+        let (handle, monitor) = lateness_monitor();
+        (
+            handle,
+            LatenessTracker::new(NodeIndex(0), NodeCount(4), monitor),
+        )
+    }
+
+    #[test]
+    fn summarizes_a_round_after_two_own_units() {
+        let (_, mut tracker) = tracker();
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        for creator in 0..4 {
+            tracker.on_unit_admitted(NodeIndex(creator), 0, at(0));
+        }
+        assert_eq!(tracker.on_own_unit_created(0, at(10)), None);
+        assert_eq!(tracker.on_own_unit_created(1, at(20)), None);
+        let summary = tracker
+            .on_own_unit_created(2, at(40))
+            .expect("round 0 should be summarized");
+        assert_eq!(summary.round, 0);
+        assert!(summary.late.is_empty());
+        assert_eq!(summary.max_lateness, Duration::ZERO);
+    }
+
+    #[test]
+    fn reports_late_and_missing_units() {
+        let (handle, mut tracker) = tracker();
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        tracker.on_own_unit_created(0, at(0));
+        tracker.on_unit_admitted(NodeIndex(0), 0, at(0));
+        tracker.on_unit_admitted(NodeIndex(1), 0, at(5));
+        tracker.on_own_unit_created(1, at(10));
+        tracker.on_unit_admitted(NodeIndex(2), 0, at(25));
+        let summary = tracker
+            .on_own_unit_created(2, at(50))
+            .expect("round 0 should be summarized");
+        let late: Vec<_> = summary.late.elements().collect();
+        assert_eq!(late, vec![NodeIndex(2), NodeIndex(3)]);
+        // The unit of node 3 never arrived, so it is late by the time of the summary.
+        assert_eq!(summary.max_lateness, Duration::from_millis(40));
+
+        let scores = handle.scores();
+        assert_eq!(scores.get(NodeIndex(1)), Some(&0.0));
+        assert!(scores.get(NodeIndex(2)) > Some(&0.0));
+        assert_eq!(scores.get(NodeIndex(2)), scores.get(NodeIndex(3)));
+    }
+
+    #[test]
+    fn forgets_arrivals_of_summarized_rounds() {
+        let (_, mut tracker) = tracker();
+        let start = Instant::now();
+        for round in 0..10 {
+            tracker.on_own_unit_created(round, start);
+            tracker.on_unit_admitted(NodeIndex(1), round, start);
+        }
+        tracker.on_unit_admitted(NodeIndex(1), 2, start);
+        assert!(tracker.arrivals.len() <= 3);
+        assert!(tracker.cutoffs.len() <= 2);
+    }
+}
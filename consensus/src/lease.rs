@@ -0,0 +1,441 @@
+use crate::{
+    callbacks::{CallbackGuard, CallbackPanicked, UserComponent},
+    ClockSource, Lease, LeaseStore, Terminator,
+};
+use futures::FutureExt;
+use log::{debug, error, info, warn};
+use parking_lot::Mutex;
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const LOG_TARGET: &str = "AlephBFT-lease";
+
+/// How many renewals in a row the lease of another instance has to miss before we take it over.
+pub const LEASE_EXPIRY_CHECKS: usize = 3;
+
+/// Whether the session creates units, see [`lease_control`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LeaseStatus {
+    /// We do not hold the lease yet, e.g. because we wait for the lease of a previous instance
+    /// to expire. No units are created.
+    Acquiring,
+    /// We hold the lease and create units.
+    Held,
+    /// Another instance renewed the lease since we last checked it, or the lease could not be
+    /// checked at all. No units are created, the units of others are still relayed, until the
+    /// lease expires or we are told to take it over.
+    DuplicateInstanceSuspected,
+}
+
+struct SharedState {
+    status: LeaseStatus,
+    take_over: bool,
+}
+
+/// Allows the application to see whether the session creates units, and to make it take the
+/// lease over, see [`lease_control`].
+#[derive(Clone)]
+pub struct LeaseHandle {
+    shared: Arc<Mutex<SharedState>>,
+}
+
+impl LeaseHandle {
+    /// The status of the lease as of its last renewal.
+    pub fn status(&self) -> LeaseStatus {
+        self.shared.lock().status
+    }
+
+    /// Makes the session take the lease over at its next renewal, even if another instance
+    /// keeps renewing it, e.g. once the other instance is known to be stopped. The other
+    /// instance reads the lease back before signing any unit, so it signs none from then on.
+    pub fn take_over(&self) {
+        self.shared.lock().take_over = true;
+    }
+}
+
+/// The part of the lease control passed to the session, see [`lease_control`].
+#[derive(Clone)]
+pub struct LeaseControl {
+    store: Arc<Mutex<Box<dyn LeaseStore>>>,
+    shared: Arc<Mutex<SharedState>>,
+}
+
+impl LeaseControl {
+    /// Returns the keeper renewing the lease in the given store, together with the permit
+    /// telling the creation whether it may create units.
+    pub(crate) fn split(self, callbacks: CallbackGuard) -> (LeaseKeeper, CreationPermit) {
+        let instance_id = rand::random();
+        let permit = CreationPermit {
+            check: Some(LeaseCheck {
+                store: self.store.clone(),
+                shared: self.shared.clone(),
+                callbacks: callbacks.clone(),
+                instance_id,
+            }),
+        };
+        (
+            LeaseKeeper::new(self.store, self.shared, callbacks, instance_id),
+            permit,
+        )
+    }
+}
+
+/// Creates a handle for inspecting the lease of our seat in the committee, kept in the given
+/// store, together with the control that should be passed to the session with
+/// [`crate::LocalIO::with_lease_control`]. Without it the session creates units regardless.
+pub fn lease_control(store: impl LeaseStore) -> (LeaseHandle, LeaseControl) {
+    let shared = Arc::new(Mutex::new(SharedState {
+        status: LeaseStatus::Acquiring,
+        take_over: false,
+    }));
+    (
+        LeaseHandle {
+            shared: shared.clone(),
+        },
+        LeaseControl {
+            store: Arc::new(Mutex::new(Box::new(store))),
+            shared,
+        },
+    )
+}
+
+fn set_status(shared: &Mutex<SharedState>, status: LeaseStatus) {
+    let previous = std::mem::replace(&mut shared.lock().status, status);
+    if previous == status {
+        return;
+    }
+    match status {
+        LeaseStatus::Held => info!(target: LOG_TARGET, "Holding the lease, creating units."),
+        LeaseStatus::Acquiring => {
+            info!(target: LOG_TARGET, "Waiting for the lease, not creating units.")
+        }
+        LeaseStatus::DuplicateInstanceSuspected => warn!(
+            target: LOG_TARGET,
+            "Another instance with our keys seems to be running, not creating units."
+        ),
+    }
+}
+
+#[derive(Clone)]
+struct LeaseCheck {
+    store: Arc<Mutex<Box<dyn LeaseStore>>>,
+    shared: Arc<Mutex<SharedState>>,
+    callbacks: CallbackGuard,
+    instance_id: u64,
+}
+
+/// Tells the creation whether it may create units, always if there is no lease.
+#[derive(Clone, Default)]
+pub(crate) struct CreationPermit {
+    check: Option<LeaseCheck>,
+}
+
+impl CreationPermit {
+    /// Whether we may sign a unit right now. Holding the lease as of its last renewal is not
+    /// enough, another instance might have taken it over since, so the latest lease is read
+    /// back and has to still be ours.
+    pub fn allows_creation(&self) -> Result<bool, CallbackPanicked> {
+        let check = match &self.check {
+            Some(check) => check,
+            None => return Ok(true),
+        };
+        if check.shared.lock().status != LeaseStatus::Held {
+            return Ok(false);
+        }
+        match check
+            .callbacks
+            .call(UserComponent::LeaseStore, || check.store.lock().get_lease())?
+        {
+            Ok(Some(lease)) if lease.instance_id == check.instance_id => Ok(true),
+            Ok(_) => {
+                set_status(&check.shared, LeaseStatus::DuplicateInstanceSuspected);
+                Ok(false)
+            }
+            Err(e) => {
+                error!(target: LOG_TARGET, "Could not read the lease: {}.", e);
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// Renews the lease periodically, stopping the creation of units whenever another instance
+/// renews it too.
+///
+/// Every renewal reads the latest lease first. A lease of another instance that changed since
+/// the previous renewal means that instance is alive, so we step back. One that stayed the same
+/// for [`LEASE_EXPIRY_CHECKS`] renewals in a row is taken over. Otherwise we write our lease with
+/// a higher counter and read it back, holding the lease only if nobody overwrote it meanwhile.
+pub(crate) struct LeaseKeeper {
+    store: Arc<Mutex<Box<dyn LeaseStore>>>,
+    shared: Arc<Mutex<SharedState>>,
+    callbacks: CallbackGuard,
+    instance_id: u64,
+    /// The latest lease we saw, if we checked at all.
+    observed: Option<Lease>,
+    /// For how many renewals in a row the lease of another instance did not change.
+    missed_renewals: usize,
+}
+
+impl LeaseKeeper {
+    fn new(
+        store: Arc<Mutex<Box<dyn LeaseStore>>>,
+        shared: Arc<Mutex<SharedState>>,
+        callbacks: CallbackGuard,
+        instance_id: u64,
+    ) -> Self {
+        LeaseKeeper {
+            store,
+            shared,
+            callbacks,
+            instance_id,
+            observed: None,
+            missed_renewals: 0,
+        }
+    }
+
+    fn get_lease(&self) -> Result<Option<Option<Lease>>, CallbackPanicked> {
+        match self
+            .callbacks
+            .call(UserComponent::LeaseStore, || self.store.lock().get_lease())?
+        {
+            Ok(lease) => Ok(Some(lease)),
+            Err(e) => {
+                error!(target: LOG_TARGET, "Could not read the lease: {}.", e);
+                Ok(None)
+            }
+        }
+    }
+
+    fn put_lease(&self, lease: Lease) -> Result<bool, CallbackPanicked> {
+        match self.callbacks.call(UserComponent::LeaseStore, || {
+            self.store.lock().put_lease(lease)
+        })? {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                error!(target: LOG_TARGET, "Could not write the lease: {}.", e);
+                Ok(false)
+            }
+        }
+    }
+
+    /// The status after a renewal that found the given lease of another instance, if we should
+    /// not take the lease.
+    fn step_back(&mut self, lease: Lease) -> Option<LeaseStatus> {
+        let previous = self.observed.replace(lease);
+        match previous {
+            None => {
+                self.missed_renewals = 0;
+                Some(LeaseStatus::Acquiring)
+            }
+            Some(previous) if previous == lease => {
+                self.missed_renewals += 1;
+                match self.missed_renewals < LEASE_EXPIRY_CHECKS {
+                    true => Some(self.shared.lock().status),
+                    false => None,
+                }
+            }
+            Some(_) => {
+                self.missed_renewals = 0;
+                Some(LeaseStatus::DuplicateInstanceSuspected)
+            }
+        }
+    }
+
+    /// Renews the lease once, returning our status afterwards.
+    pub fn renew(&mut self) -> Result<LeaseStatus, CallbackPanicked> {
+        let take_over = std::mem::take(&mut self.shared.lock().take_over);
+        let latest = match self.get_lease()? {
+            Some(latest) => latest,
+            None => return Ok(LeaseStatus::DuplicateInstanceSuspected),
+        };
+        if let Some(lease) = latest.filter(|lease| lease.instance_id != self.instance_id) {
+            if take_over {
+                info!(target: LOG_TARGET, "Taking the lease over from instance {}.", lease.instance_id);
+            } else if let Some(status) = self.step_back(lease) {
+                return Ok(status);
+            }
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis() as u64);
+        let ours = Lease {
+            instance_id: self.instance_id,
+            counter: latest.map_or(0, |lease| lease.counter) + 1,
+            timestamp,
+        };
+        if !self.put_lease(ours)? {
+            return Ok(LeaseStatus::DuplicateInstanceSuspected);
+        }
+        let read_back = match self.get_lease()? {
+            Some(read_back) => read_back,
+            None => return Ok(LeaseStatus::DuplicateInstanceSuspected),
+        };
+        self.observed = read_back;
+        self.missed_renewals = 0;
+        match read_back == Some(ours) {
+            true => Ok(LeaseStatus::Held),
+            false => Ok(LeaseStatus::DuplicateInstanceSuspected),
+        }
+    }
+
+    fn set_status(&self, status: LeaseStatus) {
+        set_status(&self.shared, status)
+    }
+
+    /// Renews the lease every interval, until the session ends or the store panics.
+    pub async fn run(mut self, clock: ClockSource, interval: Duration, mut terminator: Terminator) {
+        loop {
+            match self.renew() {
+                Ok(status) => self.set_status(status),
+                Err(CallbackPanicked(component)) => {
+                    error!(target: LOG_TARGET, "The {} panicked, exiting.", component);
+                    break;
+                }
+            }
+            futures::select! {
+                _ = clock.sleep(interval).fuse() => {},
+                _ = terminator.get_exit().fuse() => {
+                    debug!(target: LOG_TARGET, "Lease keeper received exit signal.");
+                    break;
+                },
+            }
+        }
+        terminator.terminate_sync().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        callbacks::CallbackGuard,
+        lease::{lease_control, LeaseKeeper, LeaseStatus, LEASE_EXPIRY_CHECKS},
+    };
+    use aleph_bft_mock::LeaseStore;
+
+    /// An instance of the member, sharing the lease store with the others.
+    fn instance(store: &LeaseStore) -> LeaseKeeper {
+        lease_control(store.clone())
+            .1
+            .split(CallbackGuard::default())
+            .0
+    }
+
+    /// Renews the leases of the running instances one after another, checking after every
+    /// renewal that at most one of them holds the lease.
+    fn renew_all(instances: &mut [&mut LeaseKeeper]) -> Vec<LeaseStatus> {
+        let mut statuses = vec![LeaseStatus::Acquiring; instances.len()];
+        for i in 0..instances.len() {
+            let status = instances[i].renew().expect("the store does not panic");
+            instances[i].set_status(status);
+            statuses[i] = status;
+            let held = instances
+                .iter()
+                .filter(|instance| instance.shared.lock().status == LeaseStatus::Held)
+                .count();
+            assert!(held <= 1, "{} instances hold the lease at once", held);
+        }
+        statuses
+    }
+
+    #[test]
+    fn acquires_free_lease() {
+        let store = LeaseStore::new();
+        let mut first = instance(&store);
+        assert_eq!(renew_all(&mut [&mut first]), vec![LeaseStatus::Held]);
+        assert_eq!(renew_all(&mut [&mut first]), vec![LeaseStatus::Held]);
+    }
+
+    #[test]
+    fn only_one_of_alternating_instances_creates() {
+        let store = LeaseStore::new();
+        let mut active = instance(&store);
+        assert_eq!(renew_all(&mut [&mut active]), vec![LeaseStatus::Held]);
+        for _ in 0..4 {
+            // The orchestrator starts a second instance while the first one is still running.
+            let mut duplicate = instance(&store);
+            assert_eq!(
+                renew_all(&mut [&mut duplicate, &mut active]),
+                vec![LeaseStatus::Acquiring, LeaseStatus::Held]
+            );
+            for _ in 0..2 * LEASE_EXPIRY_CHECKS {
+                assert_eq!(
+                    renew_all(&mut [&mut active, &mut duplicate]),
+                    vec![LeaseStatus::Held, LeaseStatus::DuplicateInstanceSuspected]
+                );
+            }
+            // Only once the first instance stops does the second one take over.
+            for _ in 1..LEASE_EXPIRY_CHECKS {
+                assert_eq!(
+                    renew_all(&mut [&mut duplicate]),
+                    vec![LeaseStatus::DuplicateInstanceSuspected]
+                );
+            }
+            assert_eq!(renew_all(&mut [&mut duplicate]), vec![LeaseStatus::Held]);
+            active = duplicate;
+        }
+    }
+
+    #[test]
+    fn takes_over_when_told() {
+        let store = LeaseStore::new();
+        let (mut first, first_permit) = lease_control(store.clone())
+            .1
+            .split(CallbackGuard::default());
+        let (handle, control) = lease_control(store.clone());
+        let (mut second, permit) = control.split(CallbackGuard::default());
+        renew_all(&mut [&mut first, &mut second]);
+        assert_eq!(first_permit.allows_creation(), Ok(true));
+        assert_eq!(permit.allows_creation(), Ok(false));
+
+        handle.take_over();
+        let status = second.renew().expect("the store does not panic");
+        second.set_status(status);
+        assert_eq!(handle.status(), LeaseStatus::Held);
+        assert_eq!(permit.allows_creation(), Ok(true));
+        // The first instance stops creating before its next renewal.
+        assert_eq!(first_permit.allows_creation(), Ok(false));
+        assert_eq!(
+            first.shared.lock().status,
+            LeaseStatus::DuplicateInstanceSuspected
+        );
+        assert_eq!(
+            first.renew().expect("the store does not panic"),
+            LeaseStatus::DuplicateInstanceSuspected
+        );
+    }
+
+    #[test]
+    fn stops_creating_once_lease_taken_over_after_expiry() {
+        let store = LeaseStore::new();
+        let (mut first, first_permit) = lease_control(store.clone())
+            .1
+            .split(CallbackGuard::default());
+        renew_all(&mut [&mut first]);
+        assert_eq!(first_permit.allows_creation(), Ok(true));
+
+        // The first instance stalls, so its lease expires and the second one takes it over
+        // between the renewals of the first.
+        let (mut second, permit) = lease_control(store.clone())
+            .1
+            .split(CallbackGuard::default());
+        for _ in 0..LEASE_EXPIRY_CHECKS {
+            let status = second.renew().expect("the store does not panic");
+            assert_eq!(status, LeaseStatus::Acquiring);
+            second.set_status(status);
+            assert_eq!(permit.allows_creation(), Ok(false));
+        }
+        let status = second.renew().expect("the store does not panic");
+        assert_eq!(status, LeaseStatus::Held);
+        second.set_status(status);
+        assert_eq!(permit.allows_creation(), Ok(true));
+        assert_eq!(first_permit.allows_creation(), Ok(false));
+        assert_eq!(
+            renew_all(&mut [&mut second, &mut first]),
+            vec![LeaseStatus::Held, LeaseStatus::DuplicateInstanceSuspected]
+        );
+        assert_eq!(first_permit.allows_creation(), Ok(false));
+    }
+}
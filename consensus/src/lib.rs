@@ -2,36 +2,131 @@
 //! requires access to a network layer, a cryptographic primitive, and a data provider that
 //! gives appropriate access to the set of available data that we need to make consensus on.
 
+mod admission;
 mod alerts;
+mod audit;
+mod callbacks;
+mod components;
 mod config;
+mod consensus_handler;
 mod creation;
 mod dag;
+mod decoding;
+mod delays;
+mod delivery;
 mod dissemination;
+mod drops;
+mod events;
 mod extension;
+mod finalization_state;
+mod ingress;
+mod keychain_rotation;
+mod lateness;
+mod lease;
 mod member;
+mod metrics;
+mod migration;
+mod misconduct;
 mod network;
+mod receipts;
+mod reception;
 mod runway;
+mod session_end;
+mod standby;
+mod status;
 mod terminator;
+mod unit_metadata;
+mod unit_sizes;
 mod units;
 
 mod backup;
+#[cfg(any(test, feature = "fuzz"))]
+pub mod fuzz;
 mod task_queue;
 #[cfg(test)]
 mod testing;
 
+pub use admission::{
+    admission_monitor, AdmissionHistogram, AdmissionMonitor, AdmissionStage, AdmissionStats,
+    AdmissionStatsHandle, ADMISSION_LATENCY_BUCKETS,
+};
+#[cfg(feature = "async-std")]
+pub use aleph_bft_types::AsyncStdClock;
+#[cfg(feature = "tokio")]
+pub use aleph_bft_types::TokioClock;
 pub use aleph_bft_types::{
-    Data, DataProvider, FinalizationHandler, Hasher, IncompleteMultisignatureError, Index, Indexed,
-    Keychain, MultiKeychain, Multisigned, Network, NodeCount, NodeIndex, NodeMap, NodeSubset,
-    OrderedUnit, PartialMultisignature, PartiallyMultisigned, Recipient, Round, SessionId,
-    Signable, Signature, SignatureError, SignatureSet, Signed, SpawnHandle, TaskHandle,
-    UncheckedSigned, UnitFinalizationHandler,
+    protocol, BroadcastGate, Clock, ClockSource, Data, DataAvailabilityChecker, DataProvider,
+    DeliveryCheckpoint, FinalizationHandler, FinalizationInfo, FinalizationStateStore,
+    FinalizedUnitInfo, Flagged, GateDecision, Hasher, IncompleteMultisignatureError, Index,
+    Indexed, Keychain, Lease, LeaseStore, MultiKeychain, Multisigned, Network, NodeCount,
+    NodeIndex, NodeMap, NodeSubset, NodeWeights, OrderedUnit, PartialMultisignature,
+    PartiallyMultisigned, RealClock, Recipient, Round, SessionId, Signable, Signature,
+    SignatureError, SignatureSet, Signed, SpawnHandle, TaskHandle, UncheckedSigned,
+    UnitFinalizationHandler, UnitMetadataProvider, Weight,
+};
+pub use alerts::{
+    decode_fork_proof, replay_alerts, verify_fork_proof, ForkProof, ForkProofError, ReplayedAlert,
 };
+pub use audit::{audit_log, AuditEntry, AuditLogHandle, AuditLogMonitor, AuditRecord};
+pub use callbacks::{SessionError, UserComponent};
+pub use components::{SessionComponent, SessionComponents};
 pub use config::{
-    create_config, default_config, default_delay_config, exponential_slowdown, Config, DelayConfig,
+    create_config, default_config, default_delay_config, exponential_slowdown, AdaptiveInclusion,
+    AlertRateLimit, BroadcastStrategy, Config, ConfigPreset, DataPolicy, DelayConfig,
+    ExtenderFlowControl, InvalidConfigError, NetworkRetry, ReconstructionLimits, ResponseLimits,
+    Role, DEFAULT_AVAILABILITY_RECHECK_INTERVAL, DEFAULT_BROADCAST_DEDUP_WINDOW,
+    DEFAULT_BROADCAST_GATE_TIMEOUT, DEFAULT_FAST_FORWARD_LAG, DEFAULT_LEASE_RENEWAL_INTERVAL,
+    DEFAULT_MAX_DATA_SIZE, DEFAULT_MAX_PENDING_MESSAGES, DEFAULT_MAX_ROUND_LEAD,
+    DEFAULT_MAX_UNAVAILABLE_UNITS, DEFAULT_MAX_UNIT_METADATA_SIZE, DEFAULT_NETWORK_RETRY,
+    DEFAULT_RECONSTRUCTION_LIMITS, DEFAULT_RESPONSE_LIMITS, DEFAULT_SESSION_END_GRACE_PERIOD,
+    MIN_FAULT_TOLERANT_COMMITTEE, MIN_KEPT_ROUNDS,
+};
+pub use consensus_handler::{Action, ConsensusHandler, ConsensusHandlerError};
+pub use decoding::{MAX_DECODED_ITEMS, MAX_DECODE_DEPTH};
+pub use delays::{delay_control, DelayControl, DelayControlHandle};
+pub use delivery::{
+    delivery_control, DeliveryControl, DeliveryControlHandle, DeliveryStatus, OverflowPolicy,
+    DEFAULT_DELIVERY_BUFFER_LIMIT,
 };
+pub use drops::{
+    drop_monitor, DropMonitor, DropReason, DropStats, DropStatsHandle, DROP_SAMPLE_PREFIX,
+};
+pub use finalization_state::{FinalizationState, FINALIZATION_INDEX_RETENTION};
+pub use keychain_rotation::{
+    keychain_rotation, KeychainRotationHandle, RotatingKeychain, RotationError,
+};
+pub use lateness::{lateness_monitor, LatenessHandle, LatenessMonitor};
+pub use lease::{lease_control, LeaseControl, LeaseHandle, LeaseStatus, LEASE_EXPIRY_CHECKS};
 pub use member::{run_session, LocalIO};
-pub use network::NetworkData;
+pub use metrics::{metrics_monitor, MetricsEvent, MetricsMonitor, METRICS_QUEUE_SIZE};
+pub use migration::{
+    migration_control, BackupPosition, MigrationControl, MigrationError, MigrationHandle,
+    SessionStateExport,
+};
+pub use misconduct::{misconduct_monitor, MisconductMonitor, MisconductReport};
+pub use network::{
+    broadcast_dedup_monitor, BroadcastDedupHandle, BroadcastDedupMonitor, Compression, NetworkData,
+    SessionNetwork, SessionRouter, SessionRoutingStats, WireVersion, EXTENDED_MESSAGES_VERSION,
+    MAX_DECOMPRESSED_SIZE, MAX_SUPPORTED_VERSION,
+};
+pub use receipts::{
+    quorum_receipt_monitor, QuorumReceipt, QuorumReceiptHandle, QuorumReceiptMonitor,
+};
+pub use reception::{CreatorReception, RECEPTION_GRACE_ROUNDS, RECEPTION_WINDOW};
+pub use session_end::{session_end_monitor, SessionEnd, SessionEndMonitor};
+pub use standby::{
+    backup_replication, BackupReplica, BackupReplication, StandbyError, WarmStandby,
+};
+pub use status::{status_query, DagStatus, StatusQuery, StatusQueryError, StatusQueryHandle};
 pub use terminator::{handle_task_termination, Terminator};
+pub use unit_metadata::{
+    unit_metadata_monitor, UnitMetadata, UnitMetadataHandle, UnitMetadataMonitor,
+};
+pub use unit_sizes::{
+    unit_size_monitor, UnitSizeHistogram, UnitSizeMonitor, UnitSizeStats, UnitSizeStatsHandle,
+    UnitSizeSummary, UNIT_SIZE_BUCKETS,
+};
+pub use units::{SessionVerifiedUnit, SignatureVerifiedUnit};
 
 type Receiver<T> = futures::channel::mpsc::UnboundedReceiver<T>;
 type Sender<T> = futures::channel::mpsc::UnboundedSender<T>;
@@ -1,34 +1,72 @@
 use crate::{
-    dissemination::{Request, Response},
+    admission::{mark_stage, AdmissionMonitor, AdmissionStage, AdmissionTrace},
+    audit::{AuditLogMonitor, AuditRecord},
+    callbacks::{CallbackGuard, SessionError},
+    components::SessionComponents,
+    decoding::decode_bounded_vec,
+    delivery::DeliveryControl,
+    dissemination::{
+        CompactUnit, Gossip, NewestUnitsResponse, Request, RequestId, RequestManager, Response,
+    },
+    drops::{DropMonitor, DropReason},
+    events::{EventBus, InternalEvent, Misbehavior},
+    finalization_state::FinalizationState,
     handle_task_termination,
+    ingress::{ingress_queue, IngressError, IngressReceiver, IngressSender, Priority},
+    lateness::LatenessMonitor,
+    lease::LeaseControl,
     member::Task::{CoordRequest, ParentsRequest, RequestNewest, UnitBroadcast},
-    network::{Hub as NetworkHub, NetworkData},
+    metrics::{MetricsEvent, MetricsMonitor},
+    migration::{MigrationControl, SessionStateExport},
+    misconduct::MisconductMonitor,
+    network::{
+        BroadcastDedupMonitor, BroadcastDeduplicator, Hub as NetworkHub, MessageLimits, NetworkData,
+    },
+    receipts::QuorumReceiptMonitor,
     runway::{
-        self, NetworkIO, NewestUnitResponse, RunwayIO, RunwayNotificationIn, RunwayNotificationOut,
+        self, DagDigest, NetworkIO, NewestUnitResponse, RunwayIO, RunwayNotificationIn,
+        RunwayNotificationOut, Salt, SizedNotificationIn,
     },
+    session_end::SessionEndMonitor,
+    standby::BackupReplication,
+    status::StatusQuery,
     task_queue::TaskQueue,
+    unit_metadata::UnitMetadataMonitor,
+    unit_sizes::UnitSizeMonitor,
     units::{UncheckedSignedUnit, Unit, UnitCoord},
-    Config, Data, DataProvider, FinalizationHandler, Hasher, MultiKeychain, Network, NodeIndex,
-    OrderedUnit, Receiver, Recipient, Round, Sender, Signature, SpawnHandle, Terminator,
-    UncheckedSigned, UnitFinalizationHandler,
+    BroadcastGate, BroadcastStrategy, Config, Data, DataAvailabilityChecker, DataProvider,
+    FinalizationHandler, FinalizationInfo, FinalizedUnitInfo, Hasher, Index, MultiKeychain,
+    Network, NodeIndex, OrderedUnit, Receiver, Recipient, Role, Round, Sender, Signature,
+    SpawnHandle, Terminator, UncheckedSigned, UnitFinalizationHandler, UnitMetadataProvider,
 };
 use aleph_bft_types::NodeMap;
-use codec::{Decode, Encode};
-use futures::{channel::mpsc, pin_mut, AsyncRead, AsyncWrite, FutureExt, StreamExt};
-use futures_timer::Delay;
+use codec::{Decode, Encode, Error as CodecError, Input};
+use futures::{
+    channel::{
+        mpsc::{self, Receiver as BoundedReceiver},
+        oneshot,
+    },
+    future::pending,
+    pin_mut, AsyncRead, AsyncWrite, Future, FutureExt, StreamExt,
+};
 use itertools::Itertools;
 use log::{debug, error, info, trace, warn};
-use rand::{prelude::SliceRandom, Rng};
+use rand::Rng;
 use std::{
-    collections::HashSet,
     convert::TryInto,
     fmt::{self, Debug},
     marker::PhantomData,
+    sync::Arc,
     time::Duration,
 };
 
+/// A unit message from the network together with the encoded sizes of the units it contains and,
+/// if it is traced, the trace of its admission.
+pub(crate) type ReceivedUnitMessage<H, D, S> =
+    (UnitMessage<H, D, S>, Vec<usize>, Option<AdmissionTrace>);
+
 /// A message concerning units, either about new units or some requests for them.
-#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
+#[derive(Clone, Eq, PartialEq, Debug, Encode)]
 pub(crate) enum UnitMessage<H: Hasher, D: Data, S: Signature> {
     /// For disseminating newly created units.
     NewUnit(UncheckedSignedUnit<H, D, S>),
@@ -44,21 +82,203 @@ pub(crate) enum UnitMessage<H: Hasher, D: Data, S: Signature> {
     RequestNewest(NodeIndex, u64),
     /// Response to RequestNewest: (our index, maybe unit, salt) signed by us
     ResponseNewest(UncheckedSigned<NewestUnitResponse<H, D, S>, S>),
+    /// A digest of the DAG frontier of the given node, gossiped periodically.
+    DagDigest(NodeIndex, DagDigest),
+    /// Negative response of the given node to a request by coord or for parents, sent when the
+    /// node definitively doesn't have the requested units.
+    NotFound(NodeIndex, RequestId<H>),
+    /// Request by the given node for the units with the given hashes, which it could not resolve
+    /// in a compact response.
+    RequestUnits(NodeIndex, Vec<H::Hash>),
+    /// Response to a request for units by their hashes.
+    ResponseUnits(Vec<UncheckedSignedUnit<H, D, S>>),
+    /// Response of the given node to a request for parents, in which the parents the requester
+    /// most likely holds are only referenced by their hashes.
+    ResponseParentsCompact(NodeIndex, H::Hash, Vec<CompactUnit<H, D, S>>),
+    /// Request by the given node for the newest units it lacks according to its digest, with a
+    /// salt identifying the request.
+    RequestNewestUnits(NodeIndex, Salt, DagDigest),
+    /// A part of the response to a request for the newest units, signed by the responder.
+    ResponseNewestUnits(UncheckedSigned<NewestUnitsResponse<H, D, S>, S>),
+    /// Negative response of the given node to a request by coord or for parents, sent when the
+    /// node pruned the requested units, see [`crate::Config::with_kept_rounds`].
+    Pruned(NodeIndex, RequestId<H>),
+}
+
+// Decoded like the derive would, but with the lists bounded, see [`crate::MAX_DECODED_ITEMS`].
+impl<H: Hasher, D: Data, S: Signature> Decode for UnitMessage<H, D, S> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        Ok(match input.read_byte()? {
+            0 => Self::NewUnit(Decode::decode(input)?),
+            1 => Self::RequestCoord(Decode::decode(input)?, Decode::decode(input)?),
+            2 => Self::ResponseCoord(Decode::decode(input)?),
+            3 => Self::RequestParents(Decode::decode(input)?, Decode::decode(input)?),
+            4 => Self::ResponseParents(Decode::decode(input)?, decode_bounded_vec(input)?),
+            5 => Self::RequestNewest(Decode::decode(input)?, Decode::decode(input)?),
+            6 => Self::ResponseNewest(Decode::decode(input)?),
+            7 => Self::DagDigest(Decode::decode(input)?, Decode::decode(input)?),
+            8 => Self::NotFound(Decode::decode(input)?, Decode::decode(input)?),
+            9 => Self::RequestUnits(Decode::decode(input)?, decode_bounded_vec(input)?),
+            10 => Self::ResponseUnits(decode_bounded_vec(input)?),
+            11 => Self::ResponseParentsCompact(
+                Decode::decode(input)?,
+                Decode::decode(input)?,
+                decode_bounded_vec(input)?,
+            ),
+            12 => Self::RequestNewestUnits(
+                Decode::decode(input)?,
+                Decode::decode(input)?,
+                Decode::decode(input)?,
+            ),
+            13 => Self::ResponseNewestUnits(Decode::decode(input)?),
+            14 => Self::Pruned(Decode::decode(input)?, Decode::decode(input)?),
+            _ => return Err("unknown variant of a unit message".into()),
+        })
+    }
 }
 
 impl<H: Hasher, D: Data, S: Signature> UnitMessage<H, D, S> {
-    pub(crate) fn included_data(&self) -> Vec<D> {
+    pub(crate) fn included_data_with_creators(&self) -> Vec<(D, NodeIndex)> {
         match self {
-            Self::NewUnit(uu) => uu.as_signable().included_data(),
+            Self::NewUnit(uu) => uu.as_signable().included_data_with_creator(),
             Self::RequestCoord(_, _) => Vec::new(),
-            Self::ResponseCoord(uu) => uu.as_signable().included_data(),
+            Self::ResponseCoord(uu) => uu.as_signable().included_data_with_creator(),
             Self::RequestParents(_, _) => Vec::new(),
             Self::ResponseParents(_, units) => units
                 .iter()
-                .flat_map(|uu| uu.as_signable().included_data())
+                .flat_map(|uu| uu.as_signable().included_data_with_creator())
                 .collect(),
             UnitMessage::RequestNewest(_, _) => Vec::new(),
-            UnitMessage::ResponseNewest(response) => response.as_signable().included_data(),
+            UnitMessage::ResponseNewest(response) => {
+                response.as_signable().included_data_with_creators()
+            }
+            UnitMessage::DagDigest(_, _) => Vec::new(),
+            UnitMessage::NotFound(_, _) | UnitMessage::Pruned(_, _) => Vec::new(),
+            UnitMessage::RequestUnits(_, _) => Vec::new(),
+            UnitMessage::ResponseUnits(units) => units
+                .iter()
+                .flat_map(|uu| uu.as_signable().included_data_with_creator())
+                .collect(),
+            UnitMessage::ResponseParentsCompact(_, _, units) => units
+                .iter()
+                .filter_map(CompactUnit::full)
+                .flat_map(|uu| uu.as_signable().included_data_with_creator())
+                .collect(),
+            UnitMessage::RequestNewestUnits(_, _, _) => Vec::new(),
+            UnitMessage::ResponseNewestUnits(response) => response
+                .as_signable()
+                .units()
+                .iter()
+                .flat_map(|uu| uu.as_signable().included_data_with_creator())
+                .collect(),
+        }
+    }
+
+    /// How many units, or references to units, the message holds.
+    pub(crate) fn unit_count(&self) -> usize {
+        match self {
+            Self::NewUnit(_) | Self::ResponseCoord(_) => 1,
+            Self::ResponseParents(_, units) | Self::ResponseUnits(units) => units.len(),
+            Self::ResponseParentsCompact(_, _, units) => units.len(),
+            Self::RequestUnits(_, hashes) => hashes.len(),
+            Self::ResponseNewest(response) => response.as_signable().unit().into_iter().count(),
+            Self::ResponseNewestUnits(response) => response.as_signable().units().len(),
+            Self::RequestCoord(_, _)
+            | Self::RequestParents(_, _)
+            | Self::RequestNewest(_, _)
+            | Self::RequestNewestUnits(_, _, _)
+            | Self::DagDigest(_, _)
+            | Self::NotFound(_, _)
+            | Self::Pruned(_, _) => 0,
+        }
+    }
+
+    /// The node the message claims to come from, if it names one.
+    pub(crate) fn sender(&self) -> Option<NodeIndex> {
+        match self {
+            Self::RequestCoord(node_ix, _)
+            | Self::RequestParents(node_ix, _)
+            | Self::RequestNewest(node_ix, _)
+            | Self::DagDigest(node_ix, _)
+            | Self::NotFound(node_ix, _)
+            | Self::Pruned(node_ix, _)
+            | Self::RequestUnits(node_ix, _)
+            | Self::ResponseParentsCompact(node_ix, _, _)
+            | Self::RequestNewestUnits(node_ix, _, _) => Some(*node_ix),
+            Self::ResponseNewest(response) => Some(response.as_signable().index()),
+            Self::ResponseNewestUnits(response) => Some(response.as_signable().index()),
+            Self::NewUnit(_)
+            | Self::ResponseCoord(_)
+            | Self::ResponseParents(_, _)
+            | Self::ResponseUnits(_) => None,
+        }
+    }
+
+    /// Identifies broadcasts that are pointless to repeat shortly after each other, i.e. the
+    /// broadcasts of the same unit.
+    pub(crate) fn broadcast_identity(&self) -> Option<H::Hash> {
+        match self {
+            Self::NewUnit(uu) => Some(uu.as_signable().hash()),
+            _ => None,
+        }
+    }
+
+    /// The encoded sizes of all the units contained in the message. The network passes us
+    /// messages that are already decoded, but the SCALE encoding is canonical, so these are
+    /// exactly the lengths the units were decoded from.
+    pub(crate) fn unit_sizes(&self) -> Vec<usize> {
+        match self {
+            Self::NewUnit(uu) | Self::ResponseCoord(uu) => vec![uu.encoded_size()],
+            Self::ResponseParents(_, units) | Self::ResponseUnits(units) => {
+                units.iter().map(Encode::encoded_size).collect()
+            }
+            Self::ResponseParentsCompact(_, _, units) => units
+                .iter()
+                .filter_map(CompactUnit::full)
+                .map(Encode::encoded_size)
+                .collect(),
+            Self::ResponseNewest(response) => response
+                .as_signable()
+                .unit()
+                .into_iter()
+                .map(Encode::encoded_size)
+                .collect(),
+            Self::ResponseNewestUnits(response) => response
+                .as_signable()
+                .units()
+                .iter()
+                .map(Encode::encoded_size)
+                .collect(),
+            Self::RequestCoord(_, _)
+            | Self::RequestParents(_, _)
+            | Self::RequestNewest(_, _)
+            | Self::RequestNewestUnits(_, _, _)
+            | Self::DagDigest(_, _)
+            | Self::NotFound(_, _)
+            | Self::Pruned(_, _)
+            | Self::RequestUnits(_, _) => Vec::new(),
+        }
+    }
+
+    /// Requests for units and responses to them are repeated if lost, so they are the first to
+    /// go when the queue of messages from the network is full.
+    pub(crate) fn priority(&self) -> Priority {
+        match self {
+            Self::RequestCoord(_, _)
+            | Self::RequestParents(_, _)
+            | Self::RequestUnits(_, _)
+            | Self::ResponseCoord(_)
+            | Self::ResponseParents(_, _)
+            | Self::ResponseUnits(_)
+            | Self::ResponseParentsCompact(_, _, _)
+            | Self::RequestNewestUnits(_, _, _)
+            | Self::ResponseNewestUnits(_) => Priority::Low,
+            Self::NewUnit(_)
+            | Self::RequestNewest(_, _)
+            | Self::ResponseNewest(_)
+            | Self::DagDigest(_, _)
+            | Self::NotFound(_, _)
+            | Self::Pruned(_, _) => Priority::Normal,
         }
     }
 }
@@ -128,11 +348,23 @@ impl<D: Data, H: Hasher, FH: FinalizationHandler<D>> UnitFinalizationHandler
     type Hasher = H;
 
     fn batch_finalized(&mut self, batch: Vec<OrderedUnit<Self::Data, Self::Hasher>>) {
-        for unit in batch {
-            if let Some(data) = unit.data {
-                self.finalization_handler.data_finalized(data)
-            }
-        }
+        let head = match batch.last() {
+            Some(head) => FinalizationInfo {
+                creator: head.creator,
+                round: head.round,
+            },
+            None => return,
+        };
+        let batch = batch
+            .into_iter()
+            .map(|unit| FinalizedUnitInfo {
+                creator: unit.creator,
+                round: unit.round,
+                data: unit.data,
+                flagged: unit.flagged,
+            })
+            .collect();
+        self.finalization_handler.batch_finalized(batch, head);
     }
 }
 
@@ -142,6 +374,27 @@ pub struct LocalIO<DP: DataProvider, UFH: UnitFinalizationHandler, US: AsyncWrit
     finalization_handler: UFH,
     unit_saver: US,
     unit_loader: UL,
+    delivery_control: DeliveryControl,
+    unit_size_monitor: UnitSizeMonitor,
+    lateness_monitor: LatenessMonitor,
+    broadcast_dedup_monitor: BroadcastDedupMonitor,
+    finalization_state: Option<FinalizationState<UFH::Hasher>>,
+    broadcast_gate: Option<Arc<dyn BroadcastGate<DP::Output>>>,
+    data_availability_checker: Option<Arc<dyn DataAvailabilityChecker<DP::Output>>>,
+    quorum_receipt_monitor: QuorumReceiptMonitor<DP::Output>,
+    migration_control: MigrationControl<UFH::Hasher>,
+    state_import: Option<SessionStateExport<UFH::Hasher>>,
+    audit_log: AuditLogMonitor,
+    backup_replication: BackupReplication,
+    admission_monitor: AdmissionMonitor,
+    unit_metadata_provider: Option<Arc<dyn UnitMetadataProvider>>,
+    unit_metadata_monitor: UnitMetadataMonitor<UFH::Hasher>,
+    lease_control: Option<LeaseControl>,
+    drop_monitor: DropMonitor,
+    metrics_monitor: MetricsMonitor,
+    status_query: StatusQuery,
+    misconduct_monitor: MisconductMonitor<UFH::Hasher>,
+    session_end_monitor: SessionEndMonitor,
 }
 
 impl<
@@ -163,6 +416,27 @@ impl<
             finalization_handler: finalization_handler.into(),
             unit_saver,
             unit_loader,
+            delivery_control: DeliveryControl::default(),
+            unit_size_monitor: UnitSizeMonitor::default(),
+            lateness_monitor: LatenessMonitor::default(),
+            broadcast_dedup_monitor: BroadcastDedupMonitor::default(),
+            finalization_state: None,
+            broadcast_gate: None,
+            data_availability_checker: None,
+            quorum_receipt_monitor: QuorumReceiptMonitor::default(),
+            migration_control: MigrationControl::default(),
+            state_import: None,
+            audit_log: AuditLogMonitor::default(),
+            backup_replication: BackupReplication::default(),
+            admission_monitor: AdmissionMonitor::default(),
+            unit_metadata_provider: None,
+            unit_metadata_monitor: UnitMetadataMonitor::default(),
+            lease_control: None,
+            drop_monitor: DropMonitor::default(),
+            metrics_monitor: MetricsMonitor::default(),
+            status_query: StatusQuery::default(),
+            misconduct_monitor: MisconductMonitor::default(),
+            session_end_monitor: SessionEndMonitor::default(),
         }
     }
 }
@@ -181,26 +455,268 @@ impl<DP: DataProvider, UFH: UnitFinalizationHandler, US: AsyncWrite, UL: AsyncRe
             finalization_handler,
             unit_saver,
             unit_loader,
+            delivery_control: DeliveryControl::default(),
+            unit_size_monitor: UnitSizeMonitor::default(),
+            lateness_monitor: LatenessMonitor::default(),
+            broadcast_dedup_monitor: BroadcastDedupMonitor::default(),
+            finalization_state: None,
+            broadcast_gate: None,
+            data_availability_checker: None,
+            quorum_receipt_monitor: QuorumReceiptMonitor::default(),
+            migration_control: MigrationControl::default(),
+            state_import: None,
+            audit_log: AuditLogMonitor::default(),
+            backup_replication: BackupReplication::default(),
+            admission_monitor: AdmissionMonitor::default(),
+            unit_metadata_provider: None,
+            unit_metadata_monitor: UnitMetadataMonitor::default(),
+            lease_control: None,
+            drop_monitor: DropMonitor::default(),
+            metrics_monitor: MetricsMonitor::default(),
+            status_query: StatusQuery::default(),
+            misconduct_monitor: MisconductMonitor::default(),
+            session_end_monitor: SessionEndMonitor::default(),
+        }
+    }
+
+    /// Allows pausing the delivery of finalized data with the handle corresponding to the given
+    /// control, see [`crate::delivery_control`].
+    pub fn with_delivery_control(self, delivery_control: DeliveryControl) -> Self {
+        Self {
+            delivery_control,
+            ..self
+        }
+    }
+
+    /// Tracks which peers' units arrive too late to become our parents, so that it can be
+    /// inspected with the handle corresponding to the given monitor, see
+    /// [`crate::lateness_monitor`].
+    pub fn with_lateness_monitor(self, lateness_monitor: LatenessMonitor) -> Self {
+        Self {
+            lateness_monitor,
+            ..self
+        }
+    }
+
+    /// Counts the repeated broadcasts suppressed in the session, so that they can be inspected
+    /// with the handle corresponding to the given monitor, see [`crate::broadcast_dedup_monitor`].
+    pub fn with_broadcast_dedup_monitor(
+        self,
+        broadcast_dedup_monitor: BroadcastDedupMonitor,
+    ) -> Self {
+        Self {
+            broadcast_dedup_monitor,
+            ..self
+        }
+    }
+
+    /// Persists the delivery checkpoint and the finalization index in the store of the given
+    /// state, so that a restarted session does not deliver the same batches again. The state
+    /// can be queried by the application, see [`crate::FinalizationState`].
+    pub fn with_finalization_state(
+        self,
+        finalization_state: FinalizationState<UFH::Hasher>,
+    ) -> Self {
+        Self {
+            finalization_state: Some(finalization_state),
+            ..self
+        }
+    }
+
+    /// Makes every unit of ours with data wait for the decision of the given gate, before it is
+    /// saved or sent to anyone, see [`BroadcastGate`].
+    pub fn with_broadcast_gate(self, broadcast_gate: impl BroadcastGate<DP::Output>) -> Self {
+        Self {
+            broadcast_gate: Some(Arc::new(broadcast_gate)),
+            ..self
+        }
+    }
+
+    /// Holds every unit of other nodes with data back from the ordering until the given checker
+    /// finds the data available, see [`DataAvailabilityChecker`].
+    pub fn with_data_availability_checker(
+        self,
+        data_availability_checker: impl DataAvailabilityChecker<DP::Output>,
+    ) -> Self {
+        Self {
+            data_availability_checker: Some(Arc::new(data_availability_checker)),
+            ..self
+        }
+    }
+
+    /// Reports when a quorum of the committee holds each of our units, so that it can be read
+    /// with the handle corresponding to the given monitor, see [`crate::quorum_receipt_monitor`].
+    pub fn with_quorum_receipt_monitor(
+        self,
+        quorum_receipt_monitor: QuorumReceiptMonitor<DP::Output>,
+    ) -> Self {
+        Self {
+            quorum_receipt_monitor,
+            ..self
+        }
+    }
+
+    /// Allows freezing the session with the handle corresponding to the given control, to move
+    /// it to another host, see [`crate::migration_control`].
+    pub fn with_migration_control(self, migration_control: MigrationControl<UFH::Hasher>) -> Self {
+        Self {
+            migration_control,
+            ..self
+        }
+    }
+
+    /// Continues a session frozen on another host from the given export. The unit loader has to
+    /// provide the complete backup of the frozen session, otherwise the session ends right away.
+    pub fn with_state_import(self, state_import: SessionStateExport<UFH::Hasher>) -> Self {
+        Self {
+            state_import: Some(state_import),
+            ..self
+        }
+    }
+
+    /// Records the sizes of the units in the session, so that they can be inspected with the
+    /// handle corresponding to the given monitor, see [`crate::unit_size_monitor`].
+    pub fn with_unit_size_monitor(self, unit_size_monitor: UnitSizeMonitor) -> Self {
+        Self {
+            unit_size_monitor,
+            ..self
+        }
+    }
+
+    /// Records the configuration and the decisions affecting the behavior of the session, so
+    /// that they can be reviewed with the handle corresponding to the given monitor, see
+    /// [`crate::audit_log`].
+    pub fn with_audit_log(self, audit_log: AuditLogMonitor) -> Self {
+        Self { audit_log, ..self }
+    }
+
+    /// Passes every unit saved to the backup on to the given replication, so that a warm
+    /// standby can mirror the backup, see [`crate::backup_replication`].
+    pub fn with_backup_replication(self, backup_replication: BackupReplication) -> Self {
+        Self {
+            backup_replication,
+            ..self
+        }
+    }
+
+    /// Traces the admission of the units broadcast by other nodes, so that the time spent in
+    /// every stage can be read with the handle corresponding to the given monitor, see
+    /// [`crate::admission_monitor`].
+    pub fn with_admission_monitor(self, admission_monitor: AdmissionMonitor) -> Self {
+        Self {
+            admission_monitor,
+            ..self
+        }
+    }
+
+    /// Attaches the metadata from the given provider to every unit we create, for the
+    /// applications of our peers to read, see [`UnitMetadataProvider`].
+    pub fn with_unit_metadata_provider(
+        self,
+        unit_metadata_provider: impl UnitMetadataProvider,
+    ) -> Self {
+        Self {
+            unit_metadata_provider: Some(Arc::new(unit_metadata_provider)),
+            ..self
+        }
+    }
+
+    /// Records the metadata of the units admitted to the dag, so that it can be read with the
+    /// handle corresponding to the given monitor, see [`crate::unit_metadata_monitor`].
+    pub fn with_unit_metadata_monitor(
+        self,
+        unit_metadata_monitor: UnitMetadataMonitor<UFH::Hasher>,
+    ) -> Self {
+        Self {
+            unit_metadata_monitor,
+            ..self
+        }
+    }
+
+    /// Creates units only while holding the lease of our seat in the committee, so that an
+    /// accidentally duplicated instance of this member cannot fork it, see
+    /// [`crate::lease_control`].
+    pub fn with_lease_control(self, lease_control: LeaseControl) -> Self {
+        Self {
+            lease_control: Some(lease_control),
+            ..self
+        }
+    }
+
+    /// Counts the messages the session drops intentionally, per reason, so that they can be
+    /// read, and sampled into the logs, with the handle corresponding to the given monitor, see
+    /// [`crate::drop_monitor`].
+    pub fn with_drop_monitor(self, drop_monitor: DropMonitor) -> Self {
+        Self {
+            drop_monitor,
+            ..self
+        }
+    }
+
+    /// Reports what happens in the session, e.g. units being created, received, added and
+    /// finalized, to the stream corresponding to the given monitor, see
+    /// [`crate::metrics_monitor`].
+    pub fn with_metrics_monitor(self, metrics_monitor: MetricsMonitor) -> Self {
+        Self {
+            metrics_monitor,
+            ..self
+        }
+    }
+
+    /// Answers the queries about the state of the dag made with the handle corresponding to the
+    /// given query, e.g. to diagnose a stalled session, see [`crate::status_query`].
+    pub fn with_status_query(self, status_query: StatusQuery) -> Self {
+        Self {
+            status_query,
+            ..self
+        }
+    }
+
+    /// Reports the forkers confirmed in the session, together with the evidence of their
+    /// misconduct, to the stream corresponding to the given monitor, see
+    /// [`crate::misconduct_monitor`].
+    pub fn with_misconduct_monitor(
+        self,
+        misconduct_monitor: MisconductMonitor<UFH::Hasher>,
+    ) -> Self {
+        Self {
+            misconduct_monitor,
+            ..self
+        }
+    }
+
+    /// Reports the session reaching its end, see [`crate::SessionEnd`], to the stream
+    /// corresponding to the given monitor, see [`crate::session_end_monitor`].
+    pub fn with_session_end_monitor(self, session_end_monitor: SessionEndMonitor) -> Self {
+        Self {
+            session_end_monitor,
+            ..self
         }
     }
 }
 
 struct MemberStatus<'a, H: Hasher, D: Data, S: Signature> {
     task_queue: &'a TaskQueue<RepeatableTask<H, D, S>>,
-    not_resolved_parents: &'a HashSet<H::Hash>,
-    not_resolved_coords: &'a HashSet<UnitCoord>,
+    requests: &'a RequestManager<H>,
+    gossip: Option<&'a Gossip<H>>,
+    alerts_throttled: bool,
+    pending_messages: usize,
 }
 
 impl<'a, H: Hasher, D: Data, S: Signature> MemberStatus<'a, H, D, S> {
     fn new(
         task_queue: &'a TaskQueue<RepeatableTask<H, D, S>>,
-        not_resolved_parents: &'a HashSet<H::Hash>,
-        not_resolved_coords: &'a HashSet<UnitCoord>,
+        requests: &'a RequestManager<H>,
+        gossip: Option<&'a Gossip<H>>,
+        alerts_throttled: bool,
+        pending_messages: usize,
     ) -> Self {
         Self {
             task_queue,
-            not_resolved_parents,
-            not_resolved_coords,
+            requests,
+            gossip,
+            alerts_throttled,
+            pending_messages,
         }
     }
 }
@@ -234,18 +750,34 @@ where
             "CoordRequest - {}, ParentsRequest - {}, UnitBroadcast - {}, RequestNewest - {}",
             count_coord_request, count_parents_request, count_rebroadcast, count_request_newest,
         )?;
-        if !self.not_resolved_coords.is_empty() {
+        if self.requests.in_flight() > 0 {
+            write!(f, "; requests in flight - {}", self.requests.in_flight())?;
+        }
+        if self.requests.cancelled() > 0 {
             write!(
                 f,
-                "; not_resolved_coords.len() - {}",
-                self.not_resolved_coords.len()
+                "; obsolete requests cancelled - {}",
+                self.requests.cancelled()
             )?;
         }
-        if !self.not_resolved_parents.is_empty() {
+        if self.requests.suppressed() > 0 {
+            write!(
+                f,
+                "; repeated requests suppressed - {}",
+                self.requests.suppressed()
+            )?;
+        }
+        if let Some(gossip) = self.gossip {
+            write!(f, "; units seen by gossip - {}", gossip.seen())?;
+        }
+        if self.alerts_throttled {
+            write!(f, "; own alerts throttled")?;
+        }
+        if self.pending_messages > 0 {
             write!(
                 f,
-                "; not_resolved_parents.len() - {}",
-                self.not_resolved_parents.len()
+                "; messages from the network pending - {}",
+                self.pending_messages
             )?;
         }
 
@@ -277,15 +809,21 @@ where
 {
     config: Config,
     task_queue: TaskQueue<RepeatableTask<H, D, S>>,
-    not_resolved_parents: HashSet<H::Hash>,
-    not_resolved_coords: HashSet<UnitCoord>,
+    requests: RequestManager<H>,
+    // Present if we gossip units instead of sending them to everyone.
+    gossip: Option<Gossip<H>>,
+    alerts_throttled: bool,
+    frozen: bool,
     newest_unit_resolved: bool,
-    peers: Vec<Recipient>,
     unit_messages_for_network: Sender<(UnitMessage<H, D, S>, Recipient)>,
-    unit_messages_from_network: Receiver<UnitMessage<H, D, S>>,
-    notifications_for_runway: Sender<RunwayNotificationIn<H, D, S>>,
+    unit_messages_from_network: IngressReceiver<ReceivedUnitMessage<H, D, S>>,
+    notifications_for_runway: IngressSender<SizedNotificationIn<H, D, S>>,
     notifications_from_runway: Receiver<RunwayNotificationOut<H, D, S>>,
-    resolved_requests: Receiver<Request<H>>,
+    event_bus: EventBus<H, D, S>,
+    events: BoundedReceiver<InternalEvent<H, D, S>>,
+    audit_log: AuditLogMonitor,
+    drops: DropMonitor,
+    metrics: MetricsMonitor,
     exiting: bool,
     top_units: NodeMap<Round>,
 }
@@ -296,43 +834,91 @@ where
     D: Data,
     S: Signature,
 {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         config: Config,
         unit_messages_for_network: Sender<(UnitMessage<H, D, S>, Recipient)>,
-        unit_messages_from_network: Receiver<UnitMessage<H, D, S>>,
-        notifications_for_runway: Sender<RunwayNotificationIn<H, D, S>>,
+        unit_messages_from_network: IngressReceiver<ReceivedUnitMessage<H, D, S>>,
+        notifications_for_runway: IngressSender<SizedNotificationIn<H, D, S>>,
         notifications_from_runway: Receiver<RunwayNotificationOut<H, D, S>>,
-        resolved_requests: Receiver<Request<H>>,
+        events: &EventBus<H, D, S>,
+        audit_log: AuditLogMonitor,
+        drops: DropMonitor,
+        metrics: MetricsMonitor,
     ) -> Self {
         let n_members = config.n_members();
-        let peers = (0..n_members.0)
-            .map(NodeIndex)
-            .filter(|x| *x != config.node_ix())
-            .map(Recipient::Node)
-            .collect();
+        let task_queue = TaskQueue::new(config.clock().clone());
+        let requests = RequestManager::new(config.node_ix(), n_members);
+        // Observers never pass units on, the committee spreads them.
+        let gossip = match (config.broadcast_strategy(), config.role()) {
+            (BroadcastStrategy::Gossip { fanout }, Role::Member) => {
+                Some(Gossip::new(config.node_ix(), n_members, fanout))
+            }
+            _ => None,
+        };
 
         Self {
             config,
-            task_queue: TaskQueue::new(),
-            not_resolved_parents: HashSet::new(),
-            not_resolved_coords: HashSet::new(),
+            task_queue,
+            requests,
+            gossip,
+            alerts_throttled: false,
+            frozen: false,
             newest_unit_resolved: false,
-            peers,
             unit_messages_for_network,
             unit_messages_from_network,
             notifications_for_runway,
             notifications_from_runway,
-            resolved_requests,
+            event_bus: events.clone(),
+            events: events.subscribe(),
+            audit_log,
+            drops,
+            metrics,
             exiting: false,
             top_units: NodeMap::with_size(n_members),
         }
     }
 
     fn on_create(&mut self, u: UncheckedSignedUnit<H, D, S>) {
-        self.send_unit_message(UnitMessage::NewUnit(u), Recipient::Everyone);
+        if let Some(gossip) = self.gossip.as_mut() {
+            gossip.first_seen(u.as_signable().hash());
+        }
+        for recipient in self.unit_recipients(&u) {
+            self.send_unit_message(UnitMessage::NewUnit(u.clone()), recipient);
+        }
+    }
+
+    /// Everyone, unless we gossip, in which case a few random peers.
+    fn unit_recipients(&self, unit: &UncheckedSignedUnit<H, D, S>) -> Vec<Recipient> {
+        match &self.gossip {
+            Some(gossip) => gossip
+                .peers(unit.as_signable().creator())
+                .into_iter()
+                .map(Recipient::Node)
+                .collect(),
+            None => vec![Recipient::Everyone],
+        }
+    }
+
+    /// Passes a unit broadcast by someone else on, if we gossip and see it for the first time.
+    /// Units in responses to requests are never passed on, as only the requester needs them.
+    fn on_unit_message_from_network(&mut self, message: &UnitMessage<H, D, S>) {
+        let (UnitMessage::NewUnit(unit), Some(gossip)) = (message, self.gossip.as_mut()) else {
+            return;
+        };
+        if !gossip.first_seen(unit.as_signable().hash()) || self.frozen {
+            return;
+        }
+        for recipient in self.unit_recipients(unit) {
+            self.send_unit_message(UnitMessage::NewUnit(unit.clone()), recipient);
+        }
     }
 
     fn on_unit_discovered(&mut self, new_unit: UncheckedSignedUnit<H, D, S>) {
+        // Observers never broadcast units, the committee spreads them.
+        if self.config.role() == Role::Observer {
+            return;
+        }
         let unit_creator = new_unit.as_signable().creator();
         let unit_round = new_unit.as_signable().round();
         if self
@@ -350,7 +936,7 @@ where
 
     fn on_request_coord(&mut self, coord: UnitCoord) {
         trace!(target: "AlephBFT-member", "{:?} Dealing with missing coord notification {:?}.", self.index(), coord);
-        if !self.not_resolved_coords.insert(coord) {
+        if !self.requests.start(RequestId::Coord(coord)) {
             return;
         }
 
@@ -360,7 +946,7 @@ where
     }
 
     fn on_request_parents(&mut self, u_hash: H::Hash) {
-        if !self.not_resolved_parents.insert(u_hash) {
+        if !self.requests.start(RequestId::Parents(u_hash)) {
             return;
         }
 
@@ -376,6 +962,10 @@ where
     }
 
     fn trigger_tasks(&mut self) {
+        // A frozen session only answers requests, it sends nothing of its own.
+        if self.frozen {
+            return;
+        }
         while let Some(mut task) = self.task_queue.pop_due_task() {
             match self.task_details(&task.task, task.counter) {
                 TaskDetails::Cancel => (),
@@ -385,6 +975,11 @@ where
                     reschedule,
                 } => {
                     for recipient in recipients.into_iter() {
+                        if let (Some(request), Recipient::Node(peer)) =
+                            (self.request(&task.task), &recipient)
+                        {
+                            self.on_request_sent(request, *peer);
+                        }
                         self.send_unit_message(message.clone(), recipient);
                     }
 
@@ -395,13 +990,6 @@ where
         }
     }
 
-    fn random_peers(&self, n: usize) -> Vec<Recipient> {
-        self.peers
-            .choose_multiple(&mut rand::thread_rng(), n)
-            .cloned()
-            .collect()
-    }
-
     fn index(&self) -> NodeIndex {
         self.config.node_ix()
     }
@@ -441,27 +1029,138 @@ where
         }
     }
 
-    fn recipients(&self, task: &Task<H, D, S>, counter: usize) -> Vec<Recipient> {
+    fn request(&self, task: &Task<H, D, S>) -> Option<Request<H>> {
         match task {
-            CoordRequest(_) => {
-                self.random_peers((self.config.delay_config().coord_request_recipients)(
-                    counter,
-                ))
+            CoordRequest(coord) => Some(Request::Coord(*coord)),
+            ParentsRequest(hash) => Some(Request::Parents(*hash)),
+            RequestNewest(salt) => Some(Request::NewestUnit(self.index(), *salt)),
+            UnitBroadcast(_) => None,
+        }
+    }
+
+    fn on_request_sent(&mut self, request: Request<H>, peer: NodeIndex) {
+        if let Some(request_id) = RequestId::of(&request) {
+            self.requests.on_sent(&request_id, peer);
+        }
+        self.event_bus
+            .publish(InternalEvent::RequestSent(request, peer));
+    }
+
+    /// A parents response for the unit was inconsistent with its control hash. It is only
+    /// attributed to a peer if only one of the peers we asked could have responded, as responses
+    /// do not carry their sender.
+    fn on_inconsistent_parents(&mut self, u_hash: H::Hash) {
+        let request_id = RequestId::Parents(u_hash);
+        let responders = self.requests.responders(&request_id);
+        match responders.iter().exactly_one() {
+            Ok(peer) => self.event_bus.publish(InternalEvent::PeerMisbehaved(
+                *peer,
+                Misbehavior::InconsistentParents(u_hash),
+            )),
+            Err(_) => {
+                debug!(target: "AlephBFT-member", "{:?} Cannot attribute inconsistent parents of {:?} to a single peer out of {:?}.", self.index(), u_hash, responders)
             }
-            ParentsRequest(_) => {
-                self.random_peers((self.config.delay_config().parent_request_recipients)(
-                    counter,
-                ))
+        }
+        let now = self.config.clock().now();
+        if let Some(peer) = self.requests.next_peer(&request_id, now) {
+            self.on_request_sent(Request::Parents(u_hash), peer);
+            self.send_unit_message(
+                UnitMessage::RequestParents(self.index(), u_hash),
+                Recipient::Node(peer),
+            );
+        }
+    }
+
+    /// The peer definitively doesn't have what we requested from them, so instead of waiting for
+    /// the request to be repeated we immediately ask a peer we didn't ask yet.
+    fn on_not_found(&mut self, peer: NodeIndex, request_id: RequestId<H>) {
+        if self.frozen {
+            self.drops
+                .record_drop(DropReason::Frozen, Some(peer), || request_id.encode());
+            return;
+        }
+        let task = match request_id.clone() {
+            RequestId::Coord(coord) => CoordRequest(coord),
+            RequestId::Parents(u_hash) => ParentsRequest(u_hash),
+        };
+        if !self.requests.on_not_found(&request_id, peer) {
+            trace!(target: "AlephBFT-member", "{:?} Ignoring a negative response for {:?} from {:?}.", self.index(), request_id, peer);
+            self.drops
+                .record_drop(DropReason::UnsolicitedNotFound, Some(peer), || {
+                    request_id.encode()
+                });
+            return;
+        }
+        // The reconstruction needs to know which requests nobody can answer.
+        self.send_notification_to_runway(
+            RunwayNotificationIn::NotFound(request_id.clone(), peer),
+            Vec::new(),
+            None,
+        );
+        let now = self.config.clock().now();
+        match self.requests.next_peer(&request_id, now) {
+            Some(next_peer) => {
+                if let Some(request) = self.request(&task) {
+                    self.on_request_sent(request, next_peer);
+                }
+                self.send_unit_message(self.message(&task), Recipient::Node(next_peer));
+            }
+            None => {
+                debug!(target: "AlephBFT-member", "{:?} No peer has the units of {:?}, waiting for the repeated requests.", self.index(), request_id)
             }
-            UnitBroadcast(_) => vec![Recipient::Everyone],
-            RequestNewest(_) => vec![Recipient::Everyone],
         }
     }
 
+    /// The peer pruned what we requested, so the other peers most likely did as well, and we are
+    /// too far behind to get the units one by one. Instead of asking the next peer, the runway
+    /// catches up with the newest units of this one.
+    fn on_pruned(&mut self, peer: NodeIndex, request_id: RequestId<H>) {
+        if self.frozen {
+            self.drops
+                .record_drop(DropReason::Frozen, Some(peer), || request_id.encode());
+            return;
+        }
+        if !self.requests.on_not_found(&request_id, peer) {
+            trace!(target: "AlephBFT-member", "{:?} Ignoring a pruned response for {:?} from {:?}.", self.index(), request_id, peer);
+            self.drops
+                .record_drop(DropReason::UnsolicitedNotFound, Some(peer), || {
+                    request_id.encode()
+                });
+            return;
+        }
+        debug!(target: "AlephBFT-member", "{:?} {:?} pruned the units of {:?}, catching up with its newest units.", self.index(), peer, request_id);
+        self.send_notification_to_runway(
+            RunwayNotificationIn::Pruned(request_id, peer),
+            Vec::new(),
+            None,
+        );
+    }
+
+    fn recipients(&mut self, task: &Task<H, D, S>, counter: usize) -> Vec<Recipient> {
+        let (request_id, count) = match task {
+            CoordRequest(coord) => (
+                RequestId::Coord(*coord),
+                (self.config.delay_config().coord_request_recipients)(counter),
+            ),
+            ParentsRequest(u_hash) => (
+                RequestId::Parents(*u_hash),
+                (self.config.delay_config().parent_request_recipients)(counter),
+            ),
+            UnitBroadcast(unit) => return self.unit_recipients(unit),
+            RequestNewest(_) => return vec![Recipient::Everyone],
+        };
+        let now = self.config.clock().now();
+        self.requests
+            .recipients(&request_id, count, now)
+            .into_iter()
+            .map(Recipient::Node)
+            .collect()
+    }
+
     fn still_valid(&self, task: &Task<H, D, S>) -> bool {
         match task {
-            CoordRequest(coord) => self.not_resolved_coords.contains(coord),
-            ParentsRequest(hash) => self.not_resolved_parents.contains(hash),
+            CoordRequest(coord) => self.requests.is_in_flight(&RequestId::Coord(*coord)),
+            ParentsRequest(hash) => self.requests.is_in_flight(&RequestId::Parents(*hash)),
             RequestNewest(_) => !self.newest_unit_resolved,
             UnitBroadcast(unit) => {
                 Some(&unit.as_signable().round())
@@ -477,6 +1176,9 @@ where
     ///
     /// The other exception is [Task::CoordRequest] - this one uses the configurable
     /// `coord_request_delay` schedule.
+    ///
+    /// The request schedules are read anew every time, as they can be replaced while the session
+    /// runs, see [crate::delay_control].
     fn delay(&self, task: &Task<H, D, S>, counter: usize) -> Duration {
         match task {
             UnitBroadcast(_) => {
@@ -485,9 +1187,9 @@ where
                 let millis = rand::thread_rng().gen_range(low.as_millis()..high.as_millis());
                 Duration::from_millis(millis as u64)
             }
-            CoordRequest(_) => (self.config.delay_config().coord_request_delay)(counter),
-            ParentsRequest(_) => (self.config.delay_config().parent_request_delay)(counter),
-            RequestNewest(_) => (self.config.delay_config().newest_request_delay)(counter),
+            CoordRequest(_) => (self.config.current_delay_config().coord_request_delay)(counter),
+            ParentsRequest(_) => (self.config.current_delay_config().parent_request_delay)(counter),
+            RequestNewest(_) => (self.config.current_delay_config().newest_request_delay)(counter),
         }
     }
 
@@ -499,7 +1201,51 @@ where
                 Request::Coord(coord) => self.on_request_coord(coord),
                 Request::Parents(u_hash) => self.on_request_parents(u_hash),
                 Request::NewestUnit(_, salt) => self.on_request_newest(salt),
+                // Units referenced in compact responses are requested once, not scheduled.
+                Request::Units(_) => {}
             },
+            RunwayNotificationOut::RequestResolved(request) => self.on_request_resolved(request),
+            RunwayNotificationOut::RequestObsolete(request) => {
+                if let Some(request_id) = RequestId::of(&request) {
+                    self.requests.on_obsolete(&request_id);
+                }
+            }
+            RunwayNotificationOut::SessionFrozen => {
+                info!(target: "AlephBFT-member", "{:?} Session frozen, only answering requests from now on.", self.index());
+                self.frozen = true;
+            }
+            RunwayNotificationOut::InconsistentParents(u_hash) => {
+                self.on_inconsistent_parents(u_hash)
+            }
+            RunwayNotificationOut::Digest(digest) => self.send_unit_message(
+                UnitMessage::DagDigest(self.index(), digest),
+                Recipient::Everyone,
+            ),
+            RunwayNotificationOut::NotFound(request_id, recipient) => self.send_unit_message(
+                UnitMessage::NotFound(self.index(), request_id),
+                Recipient::Node(recipient),
+            ),
+            RunwayNotificationOut::Pruned(request_id, recipient) => self.send_unit_message(
+                UnitMessage::Pruned(self.index(), request_id),
+                Recipient::Node(recipient),
+            ),
+            RunwayNotificationOut::UnitsRequest(hashes, peer) => {
+                self.on_request_sent(Request::Units(hashes.clone()), peer);
+                self.send_unit_message(
+                    UnitMessage::RequestUnits(self.index(), hashes),
+                    Recipient::Node(peer),
+                )
+            }
+            RunwayNotificationOut::NewestUnitsRequest(salt, digest, peer) => self
+                .send_unit_message(
+                    UnitMessage::RequestNewestUnits(self.index(), salt, digest),
+                    Recipient::Node(peer),
+                ),
+            RunwayNotificationOut::NewestUnitsResponse(response, requester) => self
+                .send_unit_message(
+                    UnitMessage::ResponseNewestUnits(response),
+                    Recipient::Node(requester),
+                ),
             RunwayNotificationOut::Response(response, recipient) => match response {
                 Response::Coord(u) => {
                     let message = UnitMessage::ResponseCoord(u);
@@ -514,26 +1260,103 @@ where
                     let message = UnitMessage::ResponseNewest(response);
                     self.send_unit_message(message, Recipient::Node(requester))
                 }
+                Response::CompactParents(responder, u_hash, parents) => {
+                    let message = UnitMessage::ResponseParentsCompact(responder, u_hash, parents);
+                    self.send_unit_message(message, Recipient::Node(recipient))
+                }
+                Response::Units(units) => {
+                    let message = UnitMessage::ResponseUnits(units);
+                    self.send_unit_message(message, Recipient::Node(recipient))
+                }
             },
         }
     }
 
+    fn on_request_resolved(&mut self, request: Request<H>) {
+        if let Request::NewestUnit(..) = request {
+            self.newest_unit_resolved = true;
+        }
+        if let Some(request_id) = RequestId::of(&request) {
+            self.requests.on_resolved(&request_id);
+        }
+    }
+
+    fn on_internal_event(&mut self, event: InternalEvent<H, D, S>) {
+        self.audit_log.on_event(self.config.clock().now(), &event);
+        self.metrics.on_event(&event);
+        match event {
+            InternalEvent::AlertStateChanged(hash, state) => {
+                debug!(target: "AlephBFT-member", "{:?} Alert {:?} changed state to {:?}.", self.index(), hash, state)
+            }
+            InternalEvent::AlertQueued(proof) => {
+                warn!(target: "AlephBFT-member", "{:?} Holding back our alert with fork proof {:?}.", self.index(), proof)
+            }
+            InternalEvent::AlertThrottlingChanged(throttled) => {
+                if throttled {
+                    warn!(target: "AlephBFT-member", "{:?} Our own alerts are being throttled.", self.index());
+                }
+                self.alerts_throttled = throttled;
+            }
+            InternalEvent::QuorumReceipt(receipt) => {
+                trace!(target: "AlephBFT-member", "{:?} {}.", self.index(), receipt)
+            }
+            InternalEvent::NetworkStateChanged(state) => {
+                debug!(target: "AlephBFT-member", "{:?} Network state changed to {:?}.", self.index(), state)
+            }
+            InternalEvent::RequestSent(request, peer) => {
+                trace!(target: "AlephBFT-member", "{:?} Sent request {:?} to {:?}.", self.index(), request, peer)
+            }
+            InternalEvent::PeerMisbehaved(peer, misbehavior) => {
+                warn!(target: "AlephBFT-member", "{:?} Peer {:?} {}.", self.index(), peer, misbehavior)
+            }
+            InternalEvent::UnitEvicted(evicted) => {
+                debug!(target: "AlephBFT-member", "{:?} Evicted unit {:?} at {:?} waiting for parents: {:?}.", self.index(), evicted.hash, evicted.coord, evicted.cause)
+            }
+            InternalEvent::SigningFailed(target) => {
+                debug!(target: "AlephBFT-member", "{:?} Failed to sign {}.", self.index(), target)
+            }
+            InternalEvent::DagDivergence(peer, creators) => {
+                warn!(target: "AlephBFT-member", "{:?} The DAG of {:?} diverges from ours for creators {:?}.", self.index(), peer, creators)
+            }
+            InternalEvent::DataInclusionChanged(round, change) => {
+                info!(target: "AlephBFT-member", "{:?} From round {}: {}.", self.index(), round, change)
+            }
+            InternalEvent::LateUnits(late_units) if !late_units.late.is_empty() => {
+                debug!(target: "AlephBFT-member", "{:?} {}: {:?}.", self.index(), late_units, late_units.late)
+            }
+            _ => {}
+        }
+    }
+
     fn status_report(&self) {
         let status = MemberStatus::new(
             &self.task_queue,
-            &self.not_resolved_parents,
-            &self.not_resolved_coords,
+            &self.requests,
+            self.gossip.as_ref(),
+            self.alerts_throttled,
+            self.unit_messages_from_network.len(),
         );
         info!(target: "AlephBFT-member", "{}", status);
     }
 
     async fn run(mut self, mut terminator: Terminator) {
+        let clock = self.config.clock().clone();
         let ticker_delay = self.config.delay_config().tick_interval;
-        let mut ticker = Delay::new(ticker_delay).fuse();
+        let mut ticker = clock.sleep(ticker_delay).fuse();
         let status_ticker_delay = Duration::from_secs(10);
-        let mut status_ticker = Delay::new(status_ticker_delay).fuse();
+        let mut status_ticker = clock.sleep(status_ticker_delay).fuse();
 
         loop {
+            if !self.exiting && terminator.exit_requested() {
+                debug!(target: "AlephBFT-member", "{:?} received exit signal", self.index());
+                self.exiting = true;
+            }
+            if self.exiting {
+                debug!(target: "AlephBFT-member", "{:?} Member decided to exit.", self.index());
+                terminator.terminate_sync().await;
+                break;
+            }
+
             futures::select! {
                 event = self.notifications_from_runway.next() => match event {
                     Some(message) => {
@@ -545,30 +1368,29 @@ where
                     },
                 },
 
-                event = self.resolved_requests.next() => match event {
-                    Some(request) => match request {
-                        Request::Coord(coord) => {
-                            self.not_resolved_coords.remove(&coord);
-                        },
-                        Request::Parents(u_hash) => {
-                            self.not_resolved_parents.remove(&u_hash);
-                        },
-                        Request::NewestUnit(..) => {
-                            self.newest_unit_resolved = true;
-                        }
-                    },
+                event = self.events.next() => match event {
+                    Some(event) => self.on_internal_event(event),
                     None => {
-                        error!(target: "AlephBFT-member", "{:?} Resolved-requests stream from Runway closed.", self.index());
+                        error!(target: "AlephBFT-member", "{:?} Internal event stream closed.", self.index());
                         break;
                     }
                 },
 
                 event = self.unit_messages_from_network.next() => match event {
-                    Some(message) => match message.try_into() {
-                        Ok(notification) => {
-                            self.send_notification_to_runway(notification)
-                        },
-                        Err(_) => error!(target: "AlephBFT-member", "{:?} Unable to convert a UnitMessage into an instance of RunwayNotificationIn.", self.index()),
+                    Some((UnitMessage::NotFound(peer, request_id), _, _)) => self.on_not_found(peer, request_id),
+                    Some((UnitMessage::Pruned(peer, request_id), _, _)) => self.on_pruned(peer, request_id),
+                    Some((message, unit_sizes, mut trace)) => {
+                        self.on_unit_message_from_network(&message);
+                        match message.try_into() {
+                            Ok(notification) => {
+                                mark_stage(&mut trace, AdmissionStage::NetworkQueue);
+                                self.send_notification_to_runway(notification, unit_sizes, trace)
+                            },
+                            Err(_) => {
+                                self.drops.record_drop(DropReason::UnexpectedMessage, None, Vec::new);
+                                error!(target: "AlephBFT-member", "{:?} Unable to convert a UnitMessage into an instance of RunwayNotificationIn.", self.index());
+                            },
+                        }
                     },
                     None => {
                         error!(target: "AlephBFT-member", "{:?} Unit message stream from network closed.", self.index());
@@ -578,12 +1400,12 @@ where
 
                 _ = &mut ticker => {
                     self.trigger_tasks();
-                    ticker = Delay::new(ticker_delay).fuse();
+                    ticker = clock.sleep(ticker_delay).fuse();
                 },
 
                 _ = &mut status_ticker => {
                     self.status_report();
-                    status_ticker = Delay::new(status_ticker_delay).fuse();
+                    status_ticker = clock.sleep(status_ticker_delay).fuse();
                 },
 
                 _ = terminator.get_exit().fuse() => {
@@ -591,24 +1413,39 @@ where
                     self.exiting = true;
                 },
             }
-            if self.exiting {
-                debug!(target: "AlephBFT-member", "{:?} Member decided to exit.", self.index());
-                terminator.terminate_sync().await;
-                break;
-            }
         }
 
         debug!(target: "AlephBFT-member", "{:?} Member stopped.", self.index());
     }
 
-    fn send_notification_to_runway(&mut self, notification: RunwayNotificationIn<H, D, S>) {
-        if self
+    fn send_notification_to_runway(
+        &mut self,
+        notification: RunwayNotificationIn<H, D, S>,
+        unit_sizes: Vec<usize>,
+        trace: Option<AdmissionTrace>,
+    ) {
+        if let RunwayNotificationIn::NewUnit(unit) = &notification {
+            let coord = unit.as_signable().coord();
+            self.metrics.report(MetricsEvent::UnitReceived {
+                creator: coord.creator(),
+                round: coord.round(),
+            });
+        }
+        let priority = notification.priority();
+        match self
             .notifications_for_runway
-            .unbounded_send(notification)
-            .is_err()
+            .send((notification, unit_sizes, trace), priority)
         {
-            warn!(target: "AlephBFT-member", "{:?} Sender to runway with RunwayNotificationIn messages should be open", self.index());
-            self.exiting = true;
+            Ok(()) => {}
+            Err(IngressError::Full((notification, _, _))) => {
+                trace!(target: "AlephBFT-member", "{:?} Queue to runway full, dropping a notification.", self.index());
+                self.drops
+                    .record_drop(DropReason::QueueFull, None, || notification.encode_sample());
+            }
+            Err(IngressError::Closed(_)) => {
+                warn!(target: "AlephBFT-member", "{:?} Sender to runway with RunwayNotificationIn messages should be open", self.index());
+                self.exiting = true;
+            }
         }
     }
 }
@@ -626,6 +1463,16 @@ where
 /// [`UnitFinalizationHandler`] directly is considered less stable since it exposes intrisics which might be
 /// subject to change. Implement [`FinalizationHandler<DP::Output>`] instead, unless you absolutely know
 /// what you are doing.
+///
+/// A panic in any of the components supplied in `local_io` or in the `network` does not bring down
+/// the tasks of the session. Instead the session shuts down in an orderly way and ends with
+/// [`SessionError::UserCallbackPanicked`] describing the first such panic, after which the component
+/// is not called anymore. Similarly, a task of the session ending before the session does, e.g.
+/// because the backup writer failed, ends the session with [`SessionError::ComponentTerminated`]
+/// naming that task.
+///
+/// All the tasks of the session are spawned with `spawn_handle`. Applications that need to decide
+/// where every task runs can use [`SessionComponents`] instead, this function only spawns those.
 pub async fn run_session<
     DP: DataProvider,
     UFH: UnitFinalizationHandler<Data = DP::Output>,
@@ -634,6 +1481,59 @@ pub async fn run_session<
     N: Network<NetworkData<UFH::Hasher, DP::Output, MK::Signature, MK::PartialMultisignature>>,
     SH: SpawnHandle,
     MK: MultiKeychain,
+>(
+    config: Config,
+    local_io: LocalIO<DP, UFH, US, UL>,
+    network: N,
+    keychain: MK,
+    spawn_handle: SH,
+    terminator: Terminator,
+) -> Result<(), SessionError> {
+    run_session_with_events(
+        config,
+        local_io,
+        network,
+        keychain,
+        spawn_handle,
+        terminator,
+        EventBus::new(),
+    )
+    .await
+}
+
+/// Runs the session exactly like [`run_session`], publishing internal events on the given bus.
+pub(crate) async fn run_session_with_events<
+    DP: DataProvider,
+    UFH: UnitFinalizationHandler<Data = DP::Output>,
+    US: AsyncWrite + Send + Sync + 'static,
+    UL: AsyncRead + Send + Sync + 'static,
+    N: Network<NetworkData<UFH::Hasher, DP::Output, MK::Signature, MK::PartialMultisignature>>,
+    SH: SpawnHandle,
+    MK: MultiKeychain,
+>(
+    config: Config,
+    local_io: LocalIO<DP, UFH, US, UL>,
+    network: N,
+    keychain: MK,
+    spawn_handle: SH,
+    terminator: Terminator,
+    events: EventBus<UFH::Hasher, DP::Output, MK::Signature>,
+) -> Result<(), SessionError> {
+    SessionComponents::with_events(config, local_io, network, keychain, terminator, events)?
+        .run(spawn_handle)
+        .await
+}
+
+/// Spawns all the components of the session, returning the future supervising them. Nothing gets
+/// spawned later, so the spawn handle can be dropped right away.
+pub(crate) fn start_session<
+    DP: DataProvider,
+    UFH: UnitFinalizationHandler<Data = DP::Output>,
+    US: AsyncWrite + Send + Sync + 'static,
+    UL: AsyncRead + Send + Sync + 'static,
+    N: Network<NetworkData<UFH::Hasher, DP::Output, MK::Signature, MK::PartialMultisignature>>,
+    SH: SpawnHandle,
+    MK: MultiKeychain,
 >(
     config: Config,
     local_io: LocalIO<DP, UFH, US, UL>,
@@ -641,22 +1541,49 @@ pub async fn run_session<
     keychain: MK,
     spawn_handle: SH,
     mut terminator: Terminator,
-) {
+    events: EventBus<UFH::Hasher, DP::Output, MK::Signature>,
+) -> Result<impl Future<Output = Result<(), SessionError>> + Send, SessionError> {
     let index = config.node_ix();
+    if config.validate().is_err() {
+        error!(target: "AlephBFT-member", "{:?} Refusing to start a session with an invalid config.", index);
+        return Err(SessionError::InvalidConfig);
+    }
+    let (callbacks, failures) = CallbackGuard::new();
     info!(target: "AlephBFT-member", "{:?} Starting a new session.", index);
+    local_io
+        .audit_log
+        .record(config.clock().now(), AuditRecord::Config(config.describe()));
     debug!(target: "AlephBFT-member", "{:?} Spawning party for a session.", index);
 
     let (alert_messages_for_alerter, alert_messages_from_network) = mpsc::unbounded();
     let (alert_messages_for_network, alert_messages_from_alerter) = mpsc::unbounded();
-    let (unit_messages_for_units, unit_messages_from_network) = mpsc::unbounded();
+    let (unit_messages_for_units, unit_messages_from_network) =
+        ingress_queue(config.max_pending_messages());
     let (unit_messages_for_network, unit_messages_from_units) = mpsc::unbounded();
-    let (runway_messages_for_runway, runway_messages_from_network) = mpsc::unbounded();
+    let (runway_messages_for_runway, runway_messages_from_network) =
+        ingress_queue(config.max_pending_messages());
     let (runway_messages_for_network, runway_messages_from_runway) = mpsc::unbounded();
-    let (resolved_requests_tx, resolved_requests_rx) = mpsc::unbounded();
 
     debug!(target: "AlephBFT-member", "{:?} Spawning network.", index);
     let network_terminator = terminator.add_offspring_connection("AlephBFT-network");
 
+    let broadcasts = BroadcastDeduplicator::new(
+        config.broadcast_dedup_window(),
+        local_io.broadcast_dedup_monitor,
+    );
+    local_io.drop_monitor.use_clock(config.clock().clone());
+    let network_admission = local_io.admission_monitor.clone();
+    let network_drops = local_io.drop_monitor.clone();
+    let network_clock = config.clock().clone();
+    let network_callbacks = callbacks.clone();
+    let network_retry = config.network_retry();
+    let network_limits = MessageLimits {
+        max_units_per_message: config.max_units_per_message(),
+        max_message_size: config.max_message_size(),
+    };
+    let network_compression = config.compression();
+    let network_wire_version = config.wire_version();
+    let network_events = events.clone();
     let network_handle = spawn_handle
         .spawn_essential("member/network", async move {
             NetworkHub::new(
@@ -665,12 +1592,21 @@ pub async fn run_session<
                 unit_messages_for_units,
                 alert_messages_from_alerter,
                 alert_messages_for_alerter,
+                broadcasts,
+                network_limits,
+                network_compression,
+                network_wire_version,
+                network_admission,
+                network_drops,
+                network_retry,
+                network_clock,
+                network_callbacks,
+                network_events,
             )
             .run(network_terminator)
             .await
         })
         .fuse();
-    pin_mut!(network_handle);
     debug!(target: "AlephBFT-member", "{:?} Network spawned.", index);
 
     debug!(target: "AlephBFT-member", "{:?} Initializing Runway.", index);
@@ -680,30 +1616,47 @@ pub async fn run_session<
         alert_messages_from_network,
         unit_messages_from_network: runway_messages_from_network,
         unit_messages_for_network: runway_messages_for_network,
-        resolved_requests: resolved_requests_tx,
     };
     let runway_io = RunwayIO::new(
         local_io.data_provider,
         local_io.finalization_handler,
         local_io.unit_saver,
         local_io.unit_loader,
+        local_io.delivery_control,
+        local_io.unit_size_monitor,
+        local_io.lateness_monitor,
+    )
+    .with_finalization_state(local_io.finalization_state)
+    .with_broadcast_gate(local_io.broadcast_gate)
+    .with_data_availability_checker(local_io.data_availability_checker)
+    .with_quorum_receipt_monitor(local_io.quorum_receipt_monitor)
+    .with_migration(local_io.migration_control, local_io.state_import)
+    .with_backup_replication(local_io.backup_replication)
+    .with_admission_monitor(local_io.admission_monitor)
+    .with_unit_metadata(
+        local_io.unit_metadata_provider,
+        local_io.unit_metadata_monitor,
+    )
+    .with_lease_control(local_io.lease_control)
+    .with_drop_monitor(local_io.drop_monitor.clone())
+    .with_status_query(local_io.status_query)
+    .with_misconduct_monitor(local_io.misconduct_monitor)
+    .with_session_end_monitor(local_io.session_end_monitor);
+    let (exit_flushes_for_runway, exit_flushes) = mpsc::unbounded();
+    let (finished_for_member, finished) = oneshot::channel();
+    let runway = runway::start(
+        config.clone(),
+        runway_io,
+        keychain,
+        spawn_handle.clone(),
+        network_io,
+        exit_flushes,
+        finished_for_member,
+        events.clone(),
+        callbacks.clone(),
+        runway_terminator,
     );
-    let spawn_copy = spawn_handle.clone();
-    let config_copy = config.clone();
-    let runway_handle = spawn_handle
-        .spawn_essential("member/runway", async move {
-            runway::run(
-                config_copy,
-                runway_io,
-                keychain.clone(),
-                spawn_copy,
-                network_io,
-                runway_terminator,
-            )
-            .await
-        })
-        .fuse();
-    pin_mut!(runway_handle);
+    let runway_handle = spawn_handle.spawn_essential("member/runway", runway).fuse();
     debug!(target: "AlephBFT-member", "{:?} Runway spawned.", index);
 
     debug!(target: "AlephBFT-member", "{:?} Initializing Member.", index);
@@ -713,7 +1666,10 @@ pub async fn run_session<
         unit_messages_from_network,
         runway_messages_for_runway,
         runway_messages_from_runway,
-        resolved_requests_rx,
+        &events,
+        local_io.audit_log,
+        local_io.drop_monitor,
+        local_io.metrics_monitor,
     );
     let member_terminator = terminator.add_offspring_connection("AlephBFT-member");
     let member_handle = spawn_handle
@@ -721,36 +1677,92 @@ pub async fn run_session<
             member.run(member_terminator).await;
         })
         .fuse();
-    pin_mut!(member_handle);
     debug!(target: "AlephBFT-member", "{:?} Member initialized.", index);
 
-    futures::select! {
-        _ = network_handle => {
-            error!(target: "AlephBFT-member", "{:?} Network-hub terminated early.", index);
-        },
+    Ok(async move {
+        pin_mut!(network_handle);
+        pin_mut!(runway_handle);
+        pin_mut!(member_handle);
+        let mut failures = failures.fuse();
+        // The runway drops the sender only when it ends, which is noticed through its handle.
+        let finished = async move {
+            if finished.await.is_err() {
+                pending::<()>().await
+            }
+        }
+        .fuse();
+        pin_mut!(finished);
+        let stopping = futures::select! {
+            _ = network_handle => {
+                error!(target: "AlephBFT-member", "{:?} Network-hub terminated early.", index);
+                callbacks.on_component_terminated("member/network");
+                false
+            },
 
-        _ = runway_handle => {
-            error!(target: "AlephBFT-member", "{:?} Runway terminated early.", index);
-        },
+            _ = runway_handle => {
+                error!(target: "AlephBFT-member", "{:?} Runway terminated early.", index);
+                // The runway records which of its components ended first, if any.
+                callbacks.on_component_terminated("member/runway");
+                false
+            },
 
-        _ = member_handle => {
-            error!(target: "AlephBFT-member", "{:?} Member terminated early.", index);
-        },
+            _ = member_handle => {
+                error!(target: "AlephBFT-member", "{:?} Member terminated early.", index);
+                callbacks.on_component_terminated("member");
+                false
+            },
 
-        _ = terminator.get_exit().fuse() => {
-            debug!(target: "AlephBFT-member", "{:?} exit channel was called.", index);
-        },
-    }
+            _ = failures => {
+                error!(target: "AlephBFT-member", "{:?} A user callback panicked or a task terminated early.", index);
+                false
+            },
 
-    debug!(target: "AlephBFT-member", "{:?} Run ending.", index);
+            _ = finished => {
+                debug!(target: "AlephBFT-member", "{:?} The session reached its end.", index);
+                true
+            },
 
-    terminator.terminate_sync().await;
+            _ = terminator.get_exit().fuse() => {
+                debug!(target: "AlephBFT-member", "{:?} exit channel was called.", index);
+                true
+            },
+        };
+
+        // Everything keeps running until our units are saved, only then the components get the
+        // exit signal.
+        if stopping {
+            let (flushed_tx, flushed_rx) = oneshot::channel();
+            if exit_flushes_for_runway.unbounded_send(flushed_tx).is_ok() {
+                debug!(target: "AlephBFT-member", "{:?} Waiting for our units to be saved.", index);
+                futures::select! {
+                    _ = flushed_rx.fuse() => {},
+                    _ = runway_handle => {
+                        error!(target: "AlephBFT-member", "{:?} Runway terminated before saving our units.", index);
+                        callbacks.on_component_terminated("member/runway");
+                    },
+                }
+            }
+        }
 
-    handle_task_termination(network_handle, "AlephBFT-member", "Network", index).await;
-    handle_task_termination(runway_handle, "AlephBFT-member", "Runway", index).await;
-    handle_task_termination(member_handle, "AlephBFT-member", "Member", index).await;
+        debug!(target: "AlephBFT-member", "{:?} Run ending.", index);
 
-    info!(target: "AlephBFT-member", "{:?} Session ended.", index);
+        terminator.terminate_sync().await;
+
+        handle_task_termination(network_handle, "AlephBFT-member", "Network", index).await;
+        handle_task_termination(runway_handle, "AlephBFT-member", "Runway", index).await;
+        handle_task_termination(member_handle, "AlephBFT-member", "Member", index).await;
+
+        match callbacks.error() {
+            Some(error) => {
+                error!(target: "AlephBFT-member", "{:?} Session ended with an error: {}.", index, error);
+                Err(error)
+            }
+            None => {
+                info!(target: "AlephBFT-member", "{:?} Session ended.", index);
+                Ok(())
+            }
+        }
+    })
 }
 
 #[cfg(test)]
@@ -766,26 +1778,59 @@ mod tests {
     use itertools::Itertools;
     use std::sync::Arc;
 
+    type MockMember = Member<Hasher64, u32, Signature>;
+    type SentMessages = Receiver<(UnitMessage<Hasher64, u32, Signature>, Recipient)>;
+
     fn mock_member(
         node_ix: NodeIndex,
         node_count: NodeCount,
         delay_config: DelayConfig,
-    ) -> Member<Hasher64, u32, Signature> {
+    ) -> MockMember {
+        mock_member_with_network(node_ix, node_count, delay_config).0
+    }
+
+    fn mock_member_with_network(
+        node_ix: NodeIndex,
+        node_count: NodeCount,
+        delay_config: DelayConfig,
+    ) -> (MockMember, SentMessages) {
         let config = gen_config(node_ix, node_count, delay_config);
-        let (unit_messages_for_network_sx, _) = unbounded();
-        let (_, unit_messages_from_network_rx) = unbounded();
-        let (notifications_for_runway_sx, _) = unbounded();
+        let (unit_messages_for_network_sx, unit_messages_for_network_rx) = unbounded();
+        let (_, unit_messages_from_network_rx) = ingress_queue(config.max_pending_messages());
+        let (notifications_for_runway_sx, _) = ingress_queue(config.max_pending_messages());
         let (_, notifications_from_runway_rx) = unbounded();
-        let (_, resolved_requests_rx) = unbounded();
 
-        Member::new(
+        let member = Member::new(
             config,
             unit_messages_for_network_sx,
             unit_messages_from_network_rx,
             notifications_for_runway_sx,
             notifications_from_runway_rx,
-            resolved_requests_rx,
-        )
+            &EventBus::new(),
+            AuditLogMonitor::default(),
+            DropMonitor::default(),
+            MetricsMonitor::default(),
+        );
+        (member, unit_messages_for_network_rx)
+    }
+
+    fn sent_coord_requests(sent: &mut SentMessages) -> Vec<(UnitCoord, NodeIndex)> {
+        let mut result = Vec::new();
+        while let Ok(Some(message)) = sent.try_next() {
+            if let (UnitMessage::RequestCoord(_, coord), Recipient::Node(peer)) = message {
+                result.push((coord, peer));
+            }
+        }
+        result
+    }
+
+    fn single_recipient_delay_config() -> DelayConfig {
+        let mut delay_config = gen_delay_config();
+        delay_config.coord_request_recipients = Arc::new(|_| 1);
+        delay_config.coord_request_delay = Arc::new(|_| Duration::from_secs(3600));
+        delay_config.parent_request_recipients = Arc::new(|_| 1);
+        delay_config.parent_request_delay = Arc::new(|_| Duration::from_secs(3600));
+        delay_config
     }
 
     #[test]
@@ -830,10 +1875,11 @@ mod tests {
         let mut delay_config = gen_delay_config();
         delay_config.coord_request_recipients = Arc::new(|t| 10 - t);
 
-        let member = mock_member(node_ix, NodeCount(20), delay_config);
+        let mut member = mock_member(node_ix, NodeCount(20), delay_config);
 
-        let request = CoordRequest(UnitCoord::new(1, NodeIndex(3)));
-        let recipients = member.recipients(&request, 3);
+        let coord = UnitCoord::new(1, NodeIndex(3));
+        member.requests.start(RequestId::Coord(coord));
+        let recipients = member.recipients(&CoordRequest(coord), 3);
 
         assert_eq!(recipients.len(), 7);
         assert_eq!(
@@ -849,10 +1895,11 @@ mod tests {
         let mut delay_config = gen_delay_config();
         delay_config.parent_request_recipients = Arc::new(|t| 10 - t);
 
-        let member = mock_member(node_ix, NodeCount(20), delay_config);
+        let mut member = mock_member(node_ix, NodeCount(20), delay_config);
 
-        let request = ParentsRequest(Hasher64::hash(&[0x0]));
-        let recipients = member.recipients(&request, 3);
+        let u_hash = Hasher64::hash(&[0x0]);
+        member.requests.start(RequestId::Parents(u_hash));
+        let recipients = member.recipients(&ParentsRequest(u_hash), 3);
 
         assert_eq!(recipients.len(), 7);
         assert_eq!(
@@ -867,10 +1914,11 @@ mod tests {
         let mut delay_config = gen_delay_config();
         delay_config.coord_request_recipients = Arc::new(move |_| 30);
 
-        let member = mock_member(NodeIndex(7), NodeCount(20), delay_config);
+        let mut member = mock_member(NodeIndex(7), NodeCount(20), delay_config);
 
-        let request = CoordRequest(UnitCoord::new(1, NodeIndex(3)));
-        let recipients = member.recipients(&request, 10);
+        let coord = UnitCoord::new(1, NodeIndex(3));
+        member.requests.start(RequestId::Coord(coord));
+        let recipients = member.recipients(&CoordRequest(coord), 10);
 
         assert_eq!(recipients.len(), member.config.n_members().0 - 1);
     }
@@ -880,11 +1928,145 @@ mod tests {
         let mut delay_config = gen_delay_config();
         delay_config.coord_request_recipients = Arc::new(move |_| 30);
 
-        let member = mock_member(NodeIndex(0), NodeCount(1), delay_config);
+        let mut member = mock_member(NodeIndex(0), NodeCount(1), delay_config);
 
-        let request = CoordRequest(UnitCoord::new(1, NodeIndex(3)));
-        let recipients = member.recipients(&request, 10);
+        let coord = UnitCoord::new(1, NodeIndex(3));
+        member.requests.start(RequestId::Coord(coord));
+        let recipients = member.recipients(&CoordRequest(coord), 10);
 
         assert_eq!(recipients, vec![]);
     }
+
+    #[test]
+    fn asks_next_peer_after_not_found() {
+        let (mut member, mut sent) =
+            mock_member_with_network(NodeIndex(0), NodeCount(4), single_recipient_delay_config());
+        let coord = UnitCoord::new(3, NodeIndex(2));
+
+        member.on_request_coord(coord);
+        let first_peer = match sent_coord_requests(&mut sent)[..] {
+            [(requested, peer)] if requested == coord => peer,
+            ref requests => panic!("expected a single request, got {:?}", requests),
+        };
+
+        member.on_not_found(first_peer, RequestId::Coord(coord));
+        match sent_coord_requests(&mut sent)[..] {
+            [(requested, peer)] => {
+                assert_eq!(requested, coord);
+                assert_ne!(peer, first_peer);
+                assert_ne!(peer, NodeIndex(0));
+            }
+            ref requests => panic!("expected a single request, got {:?}", requests),
+        }
+    }
+
+    #[test]
+    fn does_not_repeat_requests_in_flight() {
+        let (mut member, mut sent) =
+            mock_member_with_network(NodeIndex(0), NodeCount(4), single_recipient_delay_config());
+        let coord = UnitCoord::new(3, NodeIndex(2));
+
+        member.on_request_coord(coord);
+        member.on_request_coord(coord);
+        assert_eq!(sent_coord_requests(&mut sent).len(), 1);
+        assert_eq!(member.requests.in_flight(), 1);
+        assert_eq!(member.requests.suppressed(), 1);
+
+        member.on_request_resolved(Request::Coord(coord));
+        assert_eq!(member.requests.in_flight(), 0);
+        assert!(!member.still_valid(&CoordRequest(coord)));
+    }
+
+    #[test]
+    fn runway_notifications_stop_requests() {
+        let (mut member, mut sent) =
+            mock_member_with_network(NodeIndex(0), NodeCount(4), single_recipient_delay_config());
+        let resolved = UnitCoord::new(3, NodeIndex(2));
+        let obsolete = UnitCoord::new(3, NodeIndex(3));
+
+        // Goes over the same channel as the request itself, never over the lossy event bus.
+        member.on_unit_message_from_units(RunwayNotificationOut::Request(Request::Coord(resolved)));
+        member.on_unit_message_from_units(RunwayNotificationOut::Request(Request::Coord(obsolete)));
+        assert_eq!(sent_coord_requests(&mut sent).len(), 2);
+        assert_eq!(member.requests.in_flight(), 2);
+
+        member.on_unit_message_from_units(RunwayNotificationOut::RequestResolved(Request::Coord(
+            resolved,
+        )));
+        member.on_unit_message_from_units(RunwayNotificationOut::RequestObsolete(Request::Coord(
+            obsolete,
+        )));
+        assert_eq!(member.requests.in_flight(), 0);
+        assert!(!member.still_valid(&CoordRequest(resolved)));
+        assert!(!member.still_valid(&CoordRequest(obsolete)));
+    }
+
+    #[test]
+    fn does_not_ask_next_peer_after_pruned() {
+        let (mut member, mut sent) =
+            mock_member_with_network(NodeIndex(0), NodeCount(4), single_recipient_delay_config());
+        let coord = UnitCoord::new(3, NodeIndex(2));
+
+        member.on_request_coord(coord);
+        let (_, first_peer) = sent_coord_requests(&mut sent)[0];
+        member.on_pruned(first_peer, RequestId::Coord(coord));
+        assert!(sent_coord_requests(&mut sent).is_empty());
+        // The request stays, it is repeated as usual until the catching up resolves it.
+        assert_eq!(member.requests.in_flight(), 1);
+    }
+
+    #[test]
+    fn ignores_not_found_flood() {
+        let (mut member, mut sent) =
+            mock_member_with_network(NodeIndex(0), NodeCount(4), single_recipient_delay_config());
+        let coord = UnitCoord::new(3, NodeIndex(2));
+        let unrequested = UnitCoord::new(4, NodeIndex(2));
+
+        member.on_request_coord(coord);
+        let (_, first_peer) = sent_coord_requests(&mut sent)[0];
+        for _ in 0..100 {
+            member.on_not_found(first_peer, RequestId::Coord(coord));
+            member.on_not_found(first_peer, RequestId::Coord(unrequested));
+        }
+        assert_eq!(sent_coord_requests(&mut sent).len(), 1);
+
+        // Negative responses from peers we didn't ask are ignored as well.
+        let mut asked = member.requests.responders(&RequestId::Coord(coord));
+        asked.insert(first_peer);
+        for peer in (1..4).map(NodeIndex).filter(|peer| !asked.contains(peer)) {
+            member.on_not_found(peer, RequestId::Coord(coord));
+        }
+        assert!(sent_coord_requests(&mut sent).is_empty());
+    }
+
+    #[test]
+    fn attributes_inconsistent_parents_to_peer_that_responded() {
+        let (mut member, mut sent) =
+            mock_member_with_network(NodeIndex(0), NodeCount(4), single_recipient_delay_config());
+        let mut events = member.event_bus.subscribe();
+        let u_hash = Hasher64::hash(&[0x0]);
+        let parents_requests = |sent: &mut SentMessages| {
+            let mut result = Vec::new();
+            while let Ok(Some(message)) = sent.try_next() {
+                if let (UnitMessage::RequestParents(_, _), Recipient::Node(peer)) = message {
+                    result.push(peer);
+                }
+            }
+            result
+        };
+
+        member.on_request_parents(u_hash);
+        let first_peer = parents_requests(&mut sent)[0];
+        member.on_not_found(first_peer, RequestId::Parents(u_hash));
+        let second_peer = parents_requests(&mut sent)[0];
+        member.on_inconsistent_parents(u_hash);
+
+        let mut reported = Vec::new();
+        while let Ok(Some(event)) = events.try_next() {
+            if let InternalEvent::PeerMisbehaved(peer, _) = event {
+                reported.push(peer);
+            }
+        }
+        assert_eq!(reported, vec![second_peer]);
+    }
 }
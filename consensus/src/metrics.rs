@@ -0,0 +1,185 @@
+use crate::{
+    dissemination::Request,
+    events::{AlertState, InternalEvent},
+    units::Unit,
+    Data, Hasher, NodeIndex, Round, Signature,
+};
+use futures::channel::mpsc::{self, Receiver, Sender};
+use log::trace;
+
+const LOG_TARGET: &str = "AlephBFT-metrics";
+
+/// How many events can wait for the collector before further events get dropped. Generous
+/// enough that a collector processing events as they come never loses any.
+pub const METRICS_QUEUE_SIZE: usize = 4096;
+
+/// Something that happened in the session, for collecting the metrics of the node.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MetricsEvent {
+    /// We created our unit of the given round, which is the round the node is at.
+    UnitCreated { round: Round },
+    /// The given creator broadcast its unit of the given round to us.
+    UnitReceived { creator: NodeIndex, round: Round },
+    /// The unit of the given creator and round was added to the dag, after being validated and
+    /// saved in the backup.
+    UnitAdded { creator: NodeIndex, round: Round },
+    /// We sent a request for a unit by its coord.
+    CoordRequestSent,
+    /// We sent a request for the parents of a unit.
+    ParentsRequestSent,
+    /// The unit of the given creator and round was included in a finalized batch.
+    UnitFinalized { creator: NodeIndex, round: Round },
+    /// We raised an alert about the given forker.
+    AlertRaised { forker: NodeIndex },
+    /// The given peer misbehaved.
+    PeerMisbehaved { peer: NodeIndex },
+}
+
+/// The part of the metrics passed to the session, see [`metrics_monitor`].
+#[derive(Clone, Default)]
+pub struct MetricsMonitor {
+    events: Option<Sender<MetricsEvent>>,
+}
+
+impl MetricsMonitor {
+    /// Passes the event to the collector, dropping it if the collector is lagging behind.
+    pub(crate) fn report(&mut self, event: MetricsEvent) {
+        let Some(events) = &mut self.events else {
+            return;
+        };
+        if let Err(e) = events.try_send(event) {
+            trace!(target: LOG_TARGET, "Dropping a metrics event for a lagging collector: {:?}.", e.into_inner());
+        }
+    }
+
+    /// Reports the metrics event corresponding to the internal event, if there is one.
+    pub(crate) fn on_event<H: Hasher, D: Data, S: Signature>(
+        &mut self,
+        event: &InternalEvent<H, D, S>,
+    ) {
+        let event = match event {
+            InternalEvent::UnitCreated(round) => MetricsEvent::UnitCreated { round: *round },
+            InternalEvent::UnitAdmitted(unit) => {
+                let coord = unit.as_signable().coord();
+                MetricsEvent::UnitAdded {
+                    creator: coord.creator(),
+                    round: coord.round(),
+                }
+            }
+            InternalEvent::RequestSent(Request::Coord(_), _) => MetricsEvent::CoordRequestSent,
+            InternalEvent::RequestSent(Request::Parents(_), _) => MetricsEvent::ParentsRequestSent,
+            InternalEvent::UnitFinalized(_, coord) => MetricsEvent::UnitFinalized {
+                creator: coord.creator(),
+                round: coord.round(),
+            },
+            InternalEvent::AlertStateChanged(_, AlertState::Raised(forker)) => {
+                MetricsEvent::AlertRaised { forker: *forker }
+            }
+            InternalEvent::PeerMisbehaved(peer, _) => MetricsEvent::PeerMisbehaved { peer: *peer },
+            _ => return,
+        };
+        self.report(event);
+    }
+}
+
+/// Creates a stream of the metrics events of a session together with the monitor that should
+/// be passed to the session with [`crate::LocalIO::with_metrics_monitor`]. The stream is lossy:
+/// at most [`METRICS_QUEUE_SIZE`] events wait for the collector and further ones are dropped.
+pub fn metrics_monitor() -> (Receiver<MetricsEvent>, MetricsMonitor) {
+    let (events_for_collector, events) = mpsc::channel(METRICS_QUEUE_SIZE);
+    (
+        events,
+        MetricsMonitor {
+            events: Some(events_for_collector),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dissemination::Request,
+        events::{AlertState, InternalEvent},
+        metrics::{metrics_monitor, MetricsEvent, METRICS_QUEUE_SIZE},
+        units::{ControlHash, FullUnit, PreUnit, UnitCoord},
+        NodeCount, NodeIndex, NodeMap, Signed,
+    };
+    use aleph_bft_mock::{Data, Hasher64, Keychain, Signature};
+    use futures::StreamExt;
+
+    type TestEvent = InternalEvent<Hasher64, Data, Signature>;
+
+    fn admitted_unit(creator: NodeIndex, round: u16) -> TestEvent {
+        let n_members = NodeCount(4);
+        let control_hash = ControlHash::new(&NodeMap::with_size(n_members));
+        let full_unit = FullUnit::new(PreUnit::new(creator, round, control_hash), Some(0), 0);
+        let unit = Signed::sign(full_unit, &Keychain::new(n_members, creator))
+            .expect("the keychain never fails")
+            .into_unchecked();
+        InternalEvent::UnitAdmitted(unit)
+    }
+
+    #[tokio::test]
+    async fn reports_events_of_the_session() {
+        let (mut events, mut monitor) = metrics_monitor();
+        let coord = UnitCoord::new(3, NodeIndex(2));
+        for event in [
+            InternalEvent::UnitCreated(4),
+            admitted_unit(NodeIndex(1), 3),
+            InternalEvent::RequestSent(Request::Coord(coord), NodeIndex(1)),
+            InternalEvent::RequestSent(Request::Parents(Default::default()), NodeIndex(1)),
+            InternalEvent::RequestResolved(Request::Coord(coord)),
+            InternalEvent::UnitFinalized(Default::default(), coord),
+            InternalEvent::AlertStateChanged(Default::default(), AlertState::Received),
+            InternalEvent::AlertStateChanged(Default::default(), AlertState::Raised(NodeIndex(3))),
+            InternalEvent::SessionFrozen,
+        ] {
+            monitor.on_event(&event);
+        }
+        monitor.report(MetricsEvent::UnitReceived {
+            creator: NodeIndex(1),
+            round: 5,
+        });
+        drop(monitor);
+
+        assert_eq!(
+            events.by_ref().collect::<Vec<_>>().await,
+            vec![
+                MetricsEvent::UnitCreated { round: 4 },
+                MetricsEvent::UnitAdded {
+                    creator: NodeIndex(1),
+                    round: 3
+                },
+                MetricsEvent::CoordRequestSent,
+                MetricsEvent::ParentsRequestSent,
+                MetricsEvent::UnitFinalized {
+                    creator: NodeIndex(2),
+                    round: 3
+                },
+                MetricsEvent::AlertRaised {
+                    forker: NodeIndex(3)
+                },
+                MetricsEvent::UnitReceived {
+                    creator: NodeIndex(1),
+                    round: 5
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_events_for_a_lagging_collector() {
+        let (mut events, mut monitor) = metrics_monitor();
+        for round in 0..2 * METRICS_QUEUE_SIZE {
+            monitor.report(MetricsEvent::UnitCreated {
+                round: round as u16,
+            });
+        }
+        let mut received = 0;
+        while let Ok(Some(_)) = events.try_next() {
+            received += 1;
+        }
+        // The sender has a guaranteed slot of its own.
+        assert_eq!(received, METRICS_QUEUE_SIZE + 1);
+    }
+}
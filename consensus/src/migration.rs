@@ -0,0 +1,293 @@
+use crate::{
+    units::UnitCoord, DeliveryCheckpoint, Hasher, NodeIndex, Receiver, Round, Sender, SessionId,
+};
+use codec::{Decode, Encode};
+use futures::channel::{mpsc, oneshot};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// How much was written to the backup in the session, in units and in bytes.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Encode, Decode)]
+pub struct BackupPosition {
+    pub units: u64,
+    pub bytes: u64,
+}
+
+impl BackupPosition {
+    pub(crate) fn advance(&mut self, unit_size: usize) {
+        self.units += 1;
+        self.bytes += unit_size as u64;
+    }
+}
+
+/// The state of a frozen session needed to continue it on another host, see
+/// [`MigrationHandle::freeze`]. It is meant to be moved as a whole, encoded with `codec`, and
+/// passed to the new session with [`crate::LocalIO::with_state_import`].
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+pub struct SessionStateExport<H: Hasher> {
+    session_id: SessionId,
+    node_ix: NodeIndex,
+    backup_position: BackupPosition,
+    next_round: Round,
+    last_own_unit: Option<H::Hash>,
+    /// Encoded, as the export does not depend on the data and signature types.
+    fork_proofs: Vec<Vec<u8>>,
+    missing_coords: Vec<UnitCoord>,
+    missing_parents: Vec<H::Hash>,
+    delivery_checkpoint: Option<(u64, H::Hash, H::Hash)>,
+}
+
+impl<H: Hasher> SessionStateExport<H> {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        session_id: SessionId,
+        node_ix: NodeIndex,
+        backup_position: BackupPosition,
+        next_round: Round,
+        last_own_unit: Option<H::Hash>,
+        fork_proofs: Vec<Vec<u8>>,
+        missing_coords: Vec<UnitCoord>,
+        missing_parents: Vec<H::Hash>,
+        delivery_checkpoint: Option<DeliveryCheckpoint<H::Hash>>,
+    ) -> Self {
+        SessionStateExport {
+            session_id,
+            node_ix,
+            backup_position,
+            next_round,
+            last_own_unit,
+            fork_proofs,
+            missing_coords,
+            missing_parents,
+            delivery_checkpoint: delivery_checkpoint
+                .map(|checkpoint| (checkpoint.batches, checkpoint.head, checkpoint.batch_id)),
+        }
+    }
+
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+
+    pub fn node_ix(&self) -> NodeIndex {
+        self.node_ix
+    }
+
+    /// How much the frozen session wrote to the backup. The copy of the backup used by the new
+    /// session has to contain exactly that.
+    pub fn backup_position(&self) -> BackupPosition {
+        self.backup_position
+    }
+
+    /// The round of the next unit to create, the new session continues from it.
+    pub fn next_round(&self) -> Round {
+        self.next_round
+    }
+
+    /// The point up to which the frozen session delivered the finalized batches.
+    pub fn delivery_checkpoint(&self) -> Option<DeliveryCheckpoint<H::Hash>> {
+        self.delivery_checkpoint
+            .map(|(batches, head, batch_id)| DeliveryCheckpoint {
+                batches,
+                head,
+                batch_id,
+            })
+    }
+
+    pub(crate) fn last_own_unit(&self) -> Option<H::Hash> {
+        self.last_own_unit
+    }
+
+    pub(crate) fn fork_proofs(&self) -> &[Vec<u8>] {
+        &self.fork_proofs
+    }
+
+    pub(crate) fn missing_coords(&self) -> &[UnitCoord] {
+        &self.missing_coords
+    }
+
+    pub(crate) fn missing_parents(&self) -> &[H::Hash] {
+        &self.missing_parents
+    }
+}
+
+/// The reasons for which a session cannot continue from an export.
+#[derive(Debug, Error, Eq, PartialEq)]
+pub(crate) enum ImportError<Hash> {
+    #[error("the export is of node {0:?} in session {1}")]
+    OtherSession(NodeIndex, SessionId),
+    #[error("the backup holds {found:?}, while the export expects {expected:?}")]
+    BackupPosition {
+        expected: BackupPosition,
+        found: BackupPosition,
+    },
+    #[error("the backup continues from round {found}, while the export from round {expected}")]
+    NextRound { expected: Round, found: Round },
+    #[error("our last unit in the backup is {found:?}, while in the export it is {expected:?}")]
+    LastOwnUnit {
+        expected: Option<Hash>,
+        found: Option<Hash>,
+    },
+    #[error("the finalization state is at {found:?}, while the export is at {expected:?}")]
+    DeliveryCheckpoint {
+        expected: Option<DeliveryCheckpoint<Hash>>,
+        found: Option<DeliveryCheckpoint<Hash>>,
+    },
+    #[error("a fork proof in the export cannot be decoded")]
+    ForkProof,
+}
+
+/// Why freezing the session failed.
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum MigrationError {
+    #[error("no session is running with the migration control")]
+    NotRunning,
+    #[error("the session ended before it got frozen")]
+    SessionEnded,
+}
+
+type FreezeRequest<H> = oneshot::Sender<SessionStateExport<H>>;
+
+struct SharedState<H: Hasher> {
+    freeze_listeners: Vec<Sender<FreezeRequest<H>>>,
+}
+
+/// Allows the application to move a running session to another host, see [`migration_control`].
+#[derive(Clone)]
+pub struct MigrationHandle<H: Hasher> {
+    shared: Arc<Mutex<SharedState<H>>>,
+}
+
+impl<H: Hasher> MigrationHandle<H> {
+    /// Stops creating units, waits until all the units sent to the backup are saved and returns
+    /// the state needed to continue the session elsewhere. The session stays alive, answering
+    /// the requests of other nodes, but neither creating, nor accepting, nor sending anything
+    /// new, until it is stopped with its terminator. Freezing a frozen session returns the same
+    /// export again.
+    pub async fn freeze(&self) -> Result<SessionStateExport<H>, MigrationError> {
+        let (export_for_handle, export) = oneshot::channel();
+        {
+            let mut shared = self.shared.lock();
+            // Listeners of sessions that already ended are forgotten.
+            let mut request = Some(export_for_handle);
+            while let Some(listener) = shared.freeze_listeners.last() {
+                match listener.unbounded_send(request.take().expect("only taken once")) {
+                    Ok(()) => break,
+                    Err(e) => {
+                        request = Some(e.into_inner());
+                        shared.freeze_listeners.pop();
+                    }
+                }
+            }
+            if request.is_some() {
+                return Err(MigrationError::NotRunning);
+            }
+        }
+        export.await.map_err(|_| MigrationError::SessionEnded)
+    }
+}
+
+/// The part of the migration control passed to the session, see [`migration_control`].
+pub struct MigrationControl<H: Hasher> {
+    shared: Arc<Mutex<SharedState<H>>>,
+}
+
+impl<H: Hasher> Clone for MigrationControl<H> {
+    fn clone(&self) -> Self {
+        MigrationControl {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<H: Hasher> Default for MigrationControl<H> {
+    /// A control nobody can use to freeze the session.
+    fn default() -> Self {
+        migration_control().1
+    }
+}
+
+impl<H: Hasher> MigrationControl<H> {
+    /// Returns the stream of the freeze requests for the session. A handle freezes the session
+    /// that started most recently with its control.
+    pub(crate) fn split(self) -> Receiver<FreezeRequest<H>> {
+        let (requests_for_session, requests) = mpsc::unbounded();
+        self.shared
+            .lock()
+            .freeze_listeners
+            .push(requests_for_session);
+        requests
+    }
+}
+
+/// Creates a handle for freezing a running session together with the control that should be
+/// passed to the session with [`crate::LocalIO::with_migration_control`].
+pub fn migration_control<H: Hasher>() -> (MigrationHandle<H>, MigrationControl<H>) {
+    let shared = Arc::new(Mutex::new(SharedState {
+        freeze_listeners: Vec::new(),
+    }));
+    (
+        MigrationHandle {
+            shared: shared.clone(),
+        },
+        MigrationControl { shared },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        migration::{migration_control, BackupPosition, MigrationError, SessionStateExport},
+        units::UnitCoord,
+        DeliveryCheckpoint, NodeIndex,
+    };
+    use aleph_bft_mock::Hasher64;
+    use codec::{Decode, Encode};
+    use futures::StreamExt;
+
+    fn export() -> SessionStateExport<Hasher64> {
+        SessionStateExport::new(
+            7,
+            NodeIndex(3),
+            BackupPosition {
+                units: 40,
+                bytes: 4000,
+            },
+            9,
+            Some([8; 8]),
+            vec![vec![1, 2, 3]],
+            vec![UnitCoord::new(8, NodeIndex(1))],
+            vec![[2; 8]],
+            Some(DeliveryCheckpoint {
+                batches: 5,
+                head: [5; 8],
+                batch_id: [6; 8],
+            }),
+        )
+    }
+
+    #[test]
+    fn export_survives_encoding() {
+        let export = export();
+        let encoded = export.encode();
+        assert_eq!(
+            SessionStateExport::<Hasher64>::decode(&mut &encoded[..]),
+            Ok(export)
+        );
+    }
+
+    #[tokio::test]
+    async fn freezes_the_latest_running_session() {
+        let (handle, control) = migration_control::<Hasher64>();
+        assert_eq!(handle.freeze().await, Err(MigrationError::NotRunning));
+        let mut running = control.clone().split();
+        let ended = control.split();
+        drop(ended);
+        let session = tokio::spawn(async move {
+            let request = running.next().await.expect("the handle asks");
+            request.send(export()).expect("the handle waits");
+        });
+        assert_eq!(handle.freeze().await, Ok(export()));
+        session.await.expect("the session answers");
+    }
+}
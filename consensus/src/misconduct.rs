@@ -0,0 +1,104 @@
+use crate::{
+    alerts::{decode_fork_proof, ForkProof},
+    Data, Hasher, NodeIndex, PartialMultisignature, Signature, UncheckedSigned,
+};
+use codec::Decode;
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use log::debug;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+const LOG_TARGET: &str = "AlephBFT-misconduct";
+
+/// Evidence that the given node forked, confirmed by a quorum of the committee. The evidence is
+/// encoded, exactly as it travels in the network, so that it can be submitted elsewhere, e.g.
+/// on-chain for slashing, and verified independently of the session.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MisconductReport<H: Hasher> {
+    pub forker: NodeIndex,
+    /// The node that raised the confirmed alert, possibly us.
+    pub sender: NodeIndex,
+    /// The hash of the confirmed alert, which is what the multisignature signs.
+    pub alert: H::Hash,
+    /// The encoded fork proof, i.e. the two signed variants of the unit of the forker, see
+    /// [`Self::fork_proof`].
+    pub encoded_fork_proof: Vec<u8>,
+    /// The encoded multisignature of the alert hash, see [`Self::multisignature`].
+    pub encoded_multisignature: Vec<u8>,
+}
+
+impl<H: Hasher> MisconductReport<H> {
+    /// The fork proof, unchecked, so it should be checked with [`crate::verify_fork_proof`]
+    /// before relying on it.
+    pub fn fork_proof<D: Data, S: Signature>(&self) -> Result<ForkProof<H, D, S>, codec::Error> {
+        decode_fork_proof(&self.encoded_fork_proof)
+    }
+
+    /// The multisignature of the alert hash, unchecked, so it should be checked against the
+    /// keychain of the committee before relying on it.
+    pub fn multisignature<MS: PartialMultisignature>(
+        &self,
+    ) -> Result<UncheckedSigned<H::Hash, MS>, codec::Error> {
+        UncheckedSigned::decode(&mut &self.encoded_multisignature[..])
+    }
+}
+
+impl<H: Hasher> Display for MisconductReport<H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{:?} forked, as confirmed by the alert {:?} of {:?}",
+            self.forker, self.alert, self.sender
+        )
+    }
+}
+
+/// The part of the misconduct reporting passed to the session, see [`misconduct_monitor`].
+pub struct MisconductMonitor<H: Hasher> {
+    reports: Option<UnboundedSender<MisconductReport<H>>>,
+}
+
+impl<H: Hasher> Clone for MisconductMonitor<H> {
+    fn clone(&self) -> Self {
+        MisconductMonitor {
+            reports: self.reports.clone(),
+        }
+    }
+}
+
+impl<H: Hasher> Default for MisconductMonitor<H> {
+    fn default() -> Self {
+        MisconductMonitor { reports: None }
+    }
+}
+
+impl<H: Hasher> MisconductMonitor<H> {
+    /// Whether anybody receives the reports, so that they are worth preparing.
+    pub(crate) fn is_active(&self) -> bool {
+        self.reports.is_some()
+    }
+
+    pub(crate) fn report(&self, report: MisconductReport<H>) {
+        let Some(reports) = &self.reports else {
+            return;
+        };
+        if let Err(e) = reports.unbounded_send(report) {
+            debug!(target: LOG_TARGET, "Nobody receives the misconduct report: {}.", e.into_inner());
+        }
+    }
+}
+
+/// Creates a stream of the reports about the forkers confirmed in a session together with the
+/// monitor that should be passed to the session with [`crate::LocalIO::with_misconduct_monitor`].
+/// Every forker is reported at most once per session, as soon as the first alert about it gets
+/// confirmed, whether we raised the alert or someone else did, so the stream holds at most as
+/// many reports as there are nodes.
+pub fn misconduct_monitor<H: Hasher>(
+) -> (UnboundedReceiver<MisconductReport<H>>, MisconductMonitor<H>) {
+    let (reports_for_application, reports) = mpsc::unbounded();
+    (
+        reports,
+        MisconductMonitor {
+            reports: Some(reports_for_application),
+        },
+    )
+}
@@ -0,0 +1,227 @@
+use codec::{Decode, Encode};
+use thiserror::Error;
+
+/// How the messages we send over the network are compressed, see
+/// [`crate::Config::with_compression`]. Compressed messages from others are decompressed
+/// whatever the setting, so nodes with different settings understand each other, as long as they
+/// are all built with the `zstd` feature.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Compression {
+    /// Messages are sent as they are.
+    #[default]
+    None,
+    /// Messages are compressed with zstd. Messages that would not shrink are sent as they are.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+/// Messages smaller than this are sent as they are, as they hardly ever shrink.
+#[cfg(feature = "zstd")]
+const MIN_COMPRESSED_SIZE: usize = 128;
+
+/// The zstd compression level, its default one.
+#[cfg(feature = "zstd")]
+const ZSTD_LEVEL: i32 = 3;
+
+/// The largest window, as a power of two, of the compressed messages we decompress. We never
+/// compress with a larger one, and it bounds the memory of the decompression.
+#[cfg(feature = "zstd")]
+const ZSTD_MAX_WINDOW_LOG: u32 = 23;
+
+/// How large a decompressed message can be at most, unless the max message size of the
+/// configuration is lower.
+pub const MAX_DECOMPRESSED_SIZE: usize = 1 << 26;
+
+/// Why a compressed message could not be decompressed.
+#[derive(Debug, Eq, PartialEq, Error)]
+pub(crate) enum DecompressionError {
+    #[error("the message decompresses to {0} bytes, over the limit")]
+    TooLarge(usize),
+    #[error("the compressed message is malformed")]
+    Malformed,
+    #[error("we are built without the zstd feature, so we cannot decompress messages")]
+    Unsupported,
+}
+
+/// The encoding of a message, compressed.
+#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
+pub(crate) struct CompressedMessage {
+    decompressed_size: u32,
+    bytes: Vec<u8>,
+}
+
+impl Compression {
+    /// Compresses the encoding of a message, unless there is no point in doing so.
+    #[cfg_attr(not(feature = "zstd"), allow(unused_variables))]
+    pub(crate) fn compress(&self, encoded: &[u8]) -> Option<CompressedMessage> {
+        match self {
+            Compression::None => None,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                if encoded.len() < MIN_COMPRESSED_SIZE {
+                    return None;
+                }
+                let decompressed_size = u32::try_from(encoded.len()).ok()?;
+                let bytes = zstd::bulk::compress(encoded, ZSTD_LEVEL).ok()?;
+                // The header of the message takes a few bytes too.
+                match bytes.len() + 8 < encoded.len() {
+                    true => Some(CompressedMessage {
+                        decompressed_size,
+                        bytes,
+                    }),
+                    false => None,
+                }
+            }
+        }
+    }
+}
+
+impl CompressedMessage {
+    /// Decompresses the message, failing without allocating anything if it claims to be
+    /// larger than `max_size` bytes.
+    pub(crate) fn decompress(&self, max_size: usize) -> Result<Vec<u8>, DecompressionError> {
+        let size = self.decompressed_size as usize;
+        if size > max_size {
+            return Err(DecompressionError::TooLarge(size));
+        }
+        decompress(&self.bytes, size)
+    }
+}
+
+/// Decompresses the input, which has to decompress to exactly `size` bytes. The output grows
+/// with what the input actually produces, so a message lying about its size cannot make us
+/// allocate it.
+#[cfg(feature = "zstd")]
+fn decompress(input: &[u8], size: usize) -> Result<Vec<u8>, DecompressionError> {
+    use std::io::Read;
+
+    let mut decoder = zstd::stream::read::Decoder::with_buffer(input)
+        .map_err(|_| DecompressionError::Malformed)?;
+    decoder
+        .window_log_max(ZSTD_MAX_WINDOW_LOG)
+        .map_err(|_| DecompressionError::Malformed)?;
+    let mut output = Vec::new();
+    decoder
+        .take(size as u64 + 1)
+        .read_to_end(&mut output)
+        .map_err(|_| DecompressionError::Malformed)?;
+    match output.len() == size {
+        true => Ok(output),
+        false => Err(DecompressionError::Malformed),
+    }
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress(_input: &[u8], _size: usize) -> Result<Vec<u8>, DecompressionError> {
+    Err(DecompressionError::Unsupported)
+}
+
+#[cfg(all(test, feature = "zstd"))]
+mod tests {
+    use crate::network::compression::{
+        decompress, CompressedMessage, Compression, DecompressionError, MIN_COMPRESSED_SIZE,
+    };
+    use codec::{Decode, Encode};
+
+    fn repetitive(size: usize) -> Vec<u8> {
+        (0..size).map(|i| (i % 7 + i / 100 % 3) as u8).collect()
+    }
+
+    fn round_trip(input: &[u8]) -> Option<Vec<u8>> {
+        let message = Compression::Zstd.compress(input)?;
+        let message = CompressedMessage::decode(&mut &message.encode()[..])
+            .expect("the message should decode");
+        Some(
+            message
+                .decompress(input.len())
+                .expect("the message should decompress"),
+        )
+    }
+
+    #[test]
+    fn repetitive_data_round_trips() {
+        for size in [MIN_COMPRESSED_SIZE, 1000, 100_000, 1 << 20] {
+            let input = repetitive(size);
+            let message = Compression::Zstd
+                .compress(&input)
+                .expect("repetitive data should shrink");
+            assert!(message.bytes.len() < size / 4);
+            assert_eq!(round_trip(&input), Some(input));
+        }
+    }
+
+    #[test]
+    fn incompressible_and_small_messages_are_not_compressed() {
+        // A sequence without repeated four byte windows.
+        let input: Vec<u8> = (0..1000u32)
+            .flat_map(|i| i.wrapping_mul(2_654_435_761).to_le_bytes())
+            .collect();
+        assert_eq!(Compression::Zstd.compress(&input), None);
+        assert_eq!(
+            Compression::Zstd.compress(&repetitive(MIN_COMPRESSED_SIZE - 1)),
+            None
+        );
+        assert_eq!(Compression::None.compress(&repetitive(1000)), None);
+    }
+
+    #[test]
+    fn decompression_is_bounded() {
+        let input = repetitive(100_000);
+        let message = Compression::Zstd
+            .compress(&input)
+            .expect("repetitive data should shrink");
+        assert_eq!(
+            message.decompress(99_999),
+            Err(DecompressionError::TooLarge(100_000))
+        );
+        // A message lying about its size cannot produce more or less than it claims.
+        for decompressed_size in [1000, 200_000] {
+            let lying = CompressedMessage {
+                decompressed_size,
+                ..message.clone()
+            };
+            assert_eq!(
+                lying.decompress(usize::MAX),
+                Err(DecompressionError::Malformed)
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        let compressed =
+            zstd::bulk::compress(&repetitive(1000), 3).expect("compression never fails");
+        assert_eq!(decompress(&compressed, 1000), Ok(repetitive(1000)));
+        // Truncated.
+        assert_eq!(
+            decompress(&compressed[..compressed.len() - 1], 1000),
+            Err(DecompressionError::Malformed)
+        );
+        // Not zstd at all.
+        assert_eq!(
+            decompress(&[0x80, 1, 0], 1000),
+            Err(DecompressionError::Malformed)
+        );
+    }
+
+    #[test]
+    fn windows_over_the_limit_are_rejected() {
+        // Large enough for the window not to be shrunk to fit it.
+        let input = repetitive(1 << 24);
+        let mut compressor =
+            zstd::bulk::Compressor::new(3).expect("the compressor should be created");
+        compressor
+            .set_parameter(zstd::zstd_safe::CParameter::WindowLog(
+                super::ZSTD_MAX_WINDOW_LOG + 1,
+            ))
+            .expect("the window is valid");
+        let compressed = compressor
+            .compress(&input)
+            .expect("compression never fails");
+        assert_eq!(
+            decompress(&compressed, input.len()),
+            Err(DecompressionError::Malformed)
+        );
+    }
+}
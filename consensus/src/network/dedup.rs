@@ -0,0 +1,112 @@
+use crate::Hasher;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Allows the application to see how many repeated broadcasts were suppressed in the session.
+#[derive(Clone)]
+pub struct BroadcastDedupHandle {
+    suppressed: Arc<AtomicUsize>,
+}
+
+impl BroadcastDedupHandle {
+    /// The number of broadcasts that were not sent, because the same unit was broadcast shortly
+    /// before, see [`crate::Config::with_broadcast_dedup_window`].
+    pub fn suppressed(&self) -> usize {
+        self.suppressed.load(Ordering::Relaxed)
+    }
+}
+
+/// The part of the broadcast deduplication statistics passed to the session, see
+/// [`broadcast_dedup_monitor`].
+#[derive(Clone, Default)]
+pub struct BroadcastDedupMonitor {
+    suppressed: Arc<AtomicUsize>,
+}
+
+/// Creates a handle for inspecting the suppressed broadcasts together with the monitor that
+/// should be passed to the session with [`crate::LocalIO::with_broadcast_dedup_monitor`].
+pub fn broadcast_dedup_monitor() -> (BroadcastDedupHandle, BroadcastDedupMonitor) {
+    let suppressed = Arc::new(AtomicUsize::new(0));
+    (
+        BroadcastDedupHandle {
+            suppressed: suppressed.clone(),
+        },
+        BroadcastDedupMonitor { suppressed },
+    )
+}
+
+/// Remembers the recently broadcast units, so that the same unit is not broadcast twice within
+/// the window.
+pub(crate) struct BroadcastDeduplicator<H: Hasher> {
+    window: Duration,
+    recent: HashMap<H::Hash, Instant>,
+    monitor: BroadcastDedupMonitor,
+}
+
+impl<H: Hasher> BroadcastDeduplicator<H> {
+    pub fn new(window: Duration, monitor: BroadcastDedupMonitor) -> Self {
+        BroadcastDeduplicator {
+            window,
+            recent: HashMap::new(),
+            monitor,
+        }
+    }
+
+    /// Whether a broadcast with the given identity at the given time should be suppressed, as
+    /// one was already sent within the window. Otherwise it is recorded as sent.
+    pub fn is_duplicate(&mut self, identity: H::Hash, now: Instant) -> bool {
+        let window = self.window;
+        self.recent
+            .retain(|_, sent_at| now.saturating_duration_since(*sent_at) < window);
+        if self.recent.contains_key(&identity) {
+            self.monitor.suppressed.fetch_add(1, Ordering::Relaxed);
+            return true;
+        }
+        if !window.is_zero() {
+            self.recent.insert(identity, now);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        network::dedup::{broadcast_dedup_monitor, BroadcastDeduplicator},
+        Hasher,
+    };
+    use aleph_bft_mock::Hasher64;
+    use std::time::{Duration, Instant};
+
+    const WINDOW: Duration = Duration::from_millis(500);
+
+    #[test]
+    fn suppresses_repeats_within_window() {
+        let (handle, monitor) = broadcast_dedup_monitor();
+        let mut deduplicator = BroadcastDeduplicator::<Hasher64>::new(WINDOW, monitor);
+        let (first, second) = (Hasher64::hash(b"first"), Hasher64::hash(b"second"));
+        let now = Instant::now();
+        assert!(!deduplicator.is_duplicate(first, now));
+        assert!(!deduplicator.is_duplicate(second, now));
+        assert!(deduplicator.is_duplicate(first, now + WINDOW / 2));
+        assert!(!deduplicator.is_duplicate(first, now + WINDOW));
+        assert_eq!(handle.suppressed(), 1);
+    }
+
+    #[test]
+    fn zero_window_disables_deduplication() {
+        let (handle, monitor) = broadcast_dedup_monitor();
+        let mut deduplicator = BroadcastDeduplicator::<Hasher64>::new(Duration::ZERO, monitor);
+        let unit = Hasher64::hash(b"unit");
+        let now = Instant::now();
+        assert!(!deduplicator.is_duplicate(unit, now));
+        assert!(!deduplicator.is_duplicate(unit, now));
+        assert_eq!(handle.suppressed(), 0);
+    }
+}
@@ -1,12 +1,49 @@
 use crate::{
+    admission::AdmissionMonitor,
     alerts::AlertMessage,
-    member::UnitMessage,
-    network::{NetworkData, NetworkDataInner},
-    Data, Hasher, Network, PartialMultisignature, Receiver, Recipient, Sender, Signature,
-    Terminator,
+    callbacks::{CallbackGuard, CallbackPanicked, UserComponent},
+    drops::{DropMonitor, DropReason},
+    events::{EventBus, InternalEvent, NetworkState},
+    ingress::{IngressError, IngressSender},
+    member::{ReceivedUnitMessage, UnitMessage},
+    network::{
+        dedup::BroadcastDeduplicator, Compression, DecompressionError, NetworkData,
+        NetworkDataInner, WireVersion, MAX_DECOMPRESSED_SIZE, MAX_SUPPORTED_VERSION,
+    },
+    ClockSource, Data, Hasher, Network, NetworkRetry, PartialMultisignature, Receiver, Recipient,
+    Sender, Signature, Terminator,
 };
+use codec::Encode;
 use futures::{FutureExt, StreamExt};
-use log::{debug, error, warn};
+use log::{debug, error, info, trace, warn};
+use std::time::{Duration, Instant};
+
+/// How often we warn about dropping messages of unsupported wire versions at most, the messages
+/// dropped in between are only counted.
+const UNSUPPORTED_VERSION_WARNING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Waits until the retry deadline, if any, and then for the next event of the network.
+async fn next_event<D, N: Network<D>>(
+    network: &mut N,
+    retry_at: Option<Instant>,
+    clock: &ClockSource,
+    callbacks: &CallbackGuard,
+) -> Result<Option<D>, CallbackPanicked> {
+    if let Some(retry_at) = retry_at {
+        clock.sleep_until(retry_at).await;
+    }
+    callbacks
+        .call_async(UserComponent::Network, network.next_event())
+        .await
+}
+
+/// The bounds on the messages accepted from the network, see
+/// [`crate::Config::with_max_units_per_message`] and [`crate::Config::with_max_message_size`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MessageLimits {
+    pub max_units_per_message: usize,
+    pub max_message_size: Option<usize>,
+}
 
 pub struct Hub<
     H: Hasher,
@@ -17,9 +54,23 @@ pub struct Hub<
 > {
     network: N,
     units_to_send: Receiver<(UnitMessage<H, D, S>, Recipient)>,
-    units_received: Sender<UnitMessage<H, D, S>>,
+    units_received: IngressSender<ReceivedUnitMessage<H, D, S>>,
     alerts_to_send: Receiver<(AlertMessage<H, D, S, MS>, Recipient)>,
     alerts_received: Sender<AlertMessage<H, D, S, MS>>,
+    broadcasts: BroadcastDeduplicator<H>,
+    limits: MessageLimits,
+    compression: Compression,
+    wire_version: WireVersion,
+    admission: AdmissionMonitor,
+    drops: DropMonitor,
+    retry: NetworkRetry,
+    consecutive_failures: usize,
+    retry_at: Option<Instant>,
+    unsupported_dropped: usize,
+    unsupported_warned_at: Option<Instant>,
+    clock: ClockSource,
+    callbacks: CallbackGuard,
+    events: EventBus<H, D, S>,
 }
 
 impl<
@@ -30,12 +81,23 @@ impl<
         N: Network<NetworkData<H, D, S, MS>>,
     > Hub<H, D, S, MS, N>
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         network: N,
         units_to_send: Receiver<(UnitMessage<H, D, S>, Recipient)>,
-        units_received: Sender<UnitMessage<H, D, S>>,
+        units_received: IngressSender<ReceivedUnitMessage<H, D, S>>,
         alerts_to_send: Receiver<(AlertMessage<H, D, S, MS>, Recipient)>,
         alerts_received: Sender<AlertMessage<H, D, S, MS>>,
+        broadcasts: BroadcastDeduplicator<H>,
+        limits: MessageLimits,
+        compression: Compression,
+        wire_version: WireVersion,
+        admission: AdmissionMonitor,
+        drops: DropMonitor,
+        retry: NetworkRetry,
+        clock: ClockSource,
+        callbacks: CallbackGuard,
+        events: EventBus<H, D, S>,
     ) -> Self {
         Hub {
             network,
@@ -43,20 +105,159 @@ impl<
             units_received,
             alerts_to_send,
             alerts_received,
+            broadcasts,
+            limits,
+            compression,
+            wire_version,
+            admission,
+            drops,
+            retry,
+            consecutive_failures: 0,
+            retry_at: None,
+            unsupported_dropped: 0,
+            unsupported_warned_at: None,
+            clock,
+            callbacks,
+            events,
         }
     }
 
-    fn send(&self, data: NetworkData<H, D, S, MS>, recipient: Recipient) {
-        self.network.send(data, recipient);
+    /// Sends the message at the configured wire version, compressed if the configuration says so.
+    fn send(
+        &self,
+        data: NetworkDataInner<H, D, S, MS>,
+        recipient: Recipient,
+    ) -> Result<(), CallbackPanicked> {
+        let data = NetworkData(data.compressed(self.compression), self.wire_version);
+        self.callbacks.call(UserComponent::Network, || {
+            self.network.send(data, recipient)
+        })
     }
 
-    fn handle_incoming(&self, network_data: NetworkData<H, D, S, MS>) {
-        let NetworkData(network_data) = network_data;
+    /// Sends the unit message, unless it is a broadcast of a unit that was just broadcast.
+    /// Messages to specific nodes are always sent, as they answer their requests.
+    fn send_units(
+        &mut self,
+        unit_message: UnitMessage<H, D, S>,
+        recipient: Recipient,
+    ) -> Result<(), CallbackPanicked> {
+        if let (Some(identity), Recipient::Everyone) =
+            (unit_message.broadcast_identity(), &recipient)
+        {
+            if self.broadcasts.is_duplicate(identity, self.clock.now()) {
+                trace!(target: "AlephBFT-network-hub", "Suppressing a repeated broadcast of unit {:?}.", identity);
+                self.drops
+                    .record_drop(DropReason::RepeatedBroadcast, None, || {
+                        unit_message.encode()
+                    });
+                return Ok(());
+            }
+        }
+        self.send(NetworkDataInner::Units(unit_message), recipient)
+    }
+
+    /// Checks the message against the limits of the configuration, before it is queued anywhere.
+    fn within_limits(&self, network_data: &NetworkData<H, D, S, MS>) -> bool {
+        let NetworkData(inner, _) = network_data;
+        let sender = match inner {
+            NetworkDataInner::Units(unit_message) => unit_message.sender(),
+            NetworkDataInner::Alert(alert_message) => Some(alert_message.sender()),
+            NetworkDataInner::Compressed(_) | NetworkDataInner::Unsupported => None,
+        };
+        if let Some(max_size) = self.limits.max_message_size {
+            let size = network_data.encoded_size();
+            if size > max_size {
+                warn!(target: "AlephBFT-network-hub", "Dropping a message of {} bytes from {:?}, over the limit of {} bytes.", size, sender, max_size);
+                self.drops
+                    .record_drop(DropReason::OversizedMessage, sender, || {
+                        network_data.encode()
+                    });
+                return false;
+            }
+        }
+        if let NetworkDataInner::Units(unit_message) = inner {
+            let units = unit_message.unit_count();
+            if units > self.limits.max_units_per_message {
+                warn!(target: "AlephBFT-network-hub", "Dropping a message with {} units from {:?}, over the limit of {} units.", units, sender, self.limits.max_units_per_message);
+                self.drops
+                    .record_drop(DropReason::OversizedMessage, sender, || {
+                        network_data.encode()
+                    });
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Decompresses the message if it is compressed, never beyond the max message size.
+    fn decompressed(
+        &self,
+        NetworkData(network_data, version): NetworkData<H, D, S, MS>,
+    ) -> Option<NetworkData<H, D, S, MS>> {
+        let message = match network_data {
+            NetworkDataInner::Compressed(message) => message,
+            network_data => return Some(NetworkData(network_data, version)),
+        };
+        let max_size = self
+            .limits
+            .max_message_size
+            .map_or(MAX_DECOMPRESSED_SIZE, |size| {
+                size.min(MAX_DECOMPRESSED_SIZE)
+            });
+        let reason = match NetworkDataInner::decompress(&message, max_size) {
+            Ok(inner) => return Some(NetworkData(inner, version)),
+            Err(DecompressionError::TooLarge(size)) => {
+                warn!(target: "AlephBFT-network-hub", "Dropping a compressed message of {} bytes, over the limit of {} bytes.", size, max_size);
+                DropReason::OversizedMessage
+            }
+            Err(DecompressionError::Malformed) => {
+                warn!(target: "AlephBFT-network-hub", "Dropping a malformed compressed message.");
+                DropReason::MalformedCompression
+            }
+            Err(DecompressionError::Unsupported) => {
+                warn!(target: "AlephBFT-network-hub", "Dropping a compressed message, as we are built without the zstd feature.");
+                DropReason::MalformedCompression
+            }
+        };
+        self.drops.record_drop(reason, None, || message.encode());
+        None
+    }
+
+    fn handle_incoming(&mut self, network_data: NetworkData<H, D, S, MS>) {
+        if let NetworkData(NetworkDataInner::Unsupported, version) = network_data {
+            self.on_unsupported_version(version);
+            return;
+        }
+        let Some(network_data) = self.decompressed(network_data) else {
+            return;
+        };
+        if !self.within_limits(&network_data) {
+            return;
+        }
+        let NetworkData(network_data, _) = network_data;
         use NetworkDataInner::*;
         match network_data {
             Units(unit_message) => {
-                if let Err(e) = self.units_received.unbounded_send(unit_message) {
-                    warn!(target: "AlephBFT-network-hub", "Error when sending units to consensus {:?}", e);
+                let unit_sizes = unit_message.unit_sizes();
+                // Only the admission of units broadcast by their creators is traced.
+                let trace = match unit_message {
+                    UnitMessage::NewUnit(_) => self.admission.start_trace(&self.clock),
+                    _ => None,
+                };
+                let priority = unit_message.priority();
+                match self
+                    .units_received
+                    .send((unit_message, unit_sizes, trace), priority)
+                {
+                    Ok(()) => {}
+                    Err(IngressError::Full((unit_message, _, _))) => {
+                        trace!(target: "AlephBFT-network-hub", "Queue of units to consensus full, dropping a message.");
+                        self.drops
+                            .record_drop(DropReason::QueueFull, None, || unit_message.encode());
+                    }
+                    Err(IngressError::Closed(_)) => {
+                        warn!(target: "AlephBFT-network-hub", "Error when sending units to consensus, the queue is closed.");
+                    }
                 }
             }
 
@@ -65,33 +266,107 @@ impl<
                     warn!(target: "AlephBFT-network-hub", "Error when sending alerts to consensus {:?}", e);
                 }
             }
+
+            // Compressed messages were decompressed and unsupported ones dropped above.
+            Compressed(_) | Unsupported => {}
+        }
+    }
+
+    fn on_unsupported_version(&mut self, version: WireVersion) {
+        self.drops
+            .record_drop(DropReason::UnsupportedVersion, None, || vec![version]);
+        self.unsupported_dropped += 1;
+        let now = self.clock.now();
+        if let Some(warned_at) = self.unsupported_warned_at {
+            if now.duration_since(warned_at) < UNSUPPORTED_VERSION_WARNING_INTERVAL {
+                return;
+            }
+        }
+        warn!(target: "AlephBFT-network-hub", "Dropped {} message(s) of wire versions newer than the supported {}, the latest of version {}.", self.unsupported_dropped, MAX_SUPPORTED_VERSION, version);
+        self.unsupported_warned_at = Some(now);
+        self.unsupported_dropped = 0;
+    }
+
+    fn on_incoming(&mut self, network_data: NetworkData<H, D, S, MS>) {
+        self.retry_at = None;
+        if self.consecutive_failures > 0 {
+            info!(target: "AlephBFT-network-hub", "Network recovered after {} retries.", self.consecutive_failures);
+            self.consecutive_failures = 0;
+            self.events
+                .publish(InternalEvent::NetworkStateChanged(NetworkState::Recovered));
         }
+        self.handle_incoming(network_data);
+    }
+
+    /// Decides what to do after the network yielded no event, scheduling a retry if the end of
+    /// events is transient and the retry budget allows it. Returns whether the hub should stop.
+    fn on_no_event(&mut self) -> Result<bool, CallbackPanicked> {
+        let terminated = self
+            .callbacks
+            .call(UserComponent::Network, || self.network.is_terminated())?;
+        if terminated {
+            error!(target: "AlephBFT-network-hub", "Network stopped working.");
+            self.events
+                .publish(InternalEvent::NetworkStateChanged(NetworkState::Closed));
+            return Ok(true);
+        }
+        if self.consecutive_failures >= self.retry.max_consecutive_failures {
+            error!(target: "AlephBFT-network-hub", "Network did not recover after {} retries.", self.consecutive_failures);
+            self.events
+                .publish(InternalEvent::NetworkStateChanged(NetworkState::Closed));
+            return Ok(true);
+        }
+        if self.consecutive_failures == 0 {
+            warn!(target: "AlephBFT-network-hub", "Network interrupted, retrying.");
+            self.events.publish(InternalEvent::NetworkStateChanged(
+                NetworkState::Interrupted,
+            ));
+        }
+        self.retry_at = Some(self.clock.now() + self.backoff());
+        self.consecutive_failures += 1;
+        Ok(false)
+    }
+
+    /// The backoff before the next retry, doubling with every failure up to the cap.
+    fn backoff(&self) -> Duration {
+        self.retry
+            .initial_backoff
+            .saturating_mul(1 << self.consecutive_failures.min(16))
+            .min(self.retry.max_backoff)
     }
 
     pub async fn run(mut self, mut terminator: Terminator) {
         loop {
             use NetworkDataInner::*;
+            if terminator.exit_requested() {
+                terminator.terminate_sync().await;
+                break;
+            }
             futures::select! {
                 unit_message = self.units_to_send.next() => match unit_message {
-                    Some((unit_message, recipient)) => self.send(NetworkData(Units(unit_message)), recipient),
+                    Some((unit_message, recipient)) => if self.send_units(unit_message, recipient).is_err() {
+                        break;
+                    },
                     None => {
                         error!(target: "AlephBFT-network-hub", "Outgoing units stream closed.");
                         break;
                     }
                 },
                 alert_message = self.alerts_to_send.next() => match alert_message {
-                    Some((alert_message, recipient)) => self.send(NetworkData(Alert(alert_message)), recipient),
+                    Some((alert_message, recipient)) => if self.send(Alert(alert_message), recipient).is_err() {
+                        break;
+                    },
                     None => {
                         error!(target: "AlephBFT-network-hub", "Outgoing alerts stream closed.");
                         break;
                     }
                 },
-                incoming_message = self.network.next_event().fuse() => match incoming_message {
-                    Some(incoming_message) => self.handle_incoming(incoming_message),
-                    None => {
-                        error!(target: "AlephBFT-network-hub", "Network stopped working.");
+                incoming_message = next_event(&mut self.network, self.retry_at, &self.clock, &self.callbacks).fuse() => match incoming_message {
+                    Ok(Some(incoming_message)) => self.on_incoming(incoming_message),
+                    Ok(None) => if !matches!(self.on_no_event(), Ok(false)) {
                         break;
-                    }
+                    },
+                    Err(_) => break,
                 },
                 _ = terminator.get_exit().fuse() => {
                     terminator.terminate_sync().await;
@@ -103,3 +378,418 @@ impl<
         debug!(target: "AlephBFT-network-hub", "Network ended.");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        admission::AdmissionMonitor,
+        alerts::{Alert, AlertMessage},
+        callbacks::CallbackGuard,
+        drops::{drop_monitor, DropMonitor, DropReason},
+        events::{EventBus, InternalEvent, NetworkState},
+        ingress::ingress_queue,
+        member::UnitMessage,
+        network::{
+            broadcast_dedup_monitor, dedup::BroadcastDeduplicator, BroadcastDedupMonitor,
+            CompressedMessage, Compression, Hub, MessageLimits, NetworkData, NetworkDataInner,
+            MAX_SUPPORTED_VERSION,
+        },
+        units::{ControlHash, FullUnit, PreUnit, UncheckedSignedUnit},
+        ClockSource, Network, NodeIndex, Recipient, Round, Signed, Terminator,
+        DEFAULT_NETWORK_RETRY,
+    };
+    use aleph_bft_mock::{Data, Hasher64, Keychain, PartialMultisignature, Signature};
+    use aleph_bft_types::NodeMap;
+    use codec::{Decode, Encode};
+    use futures::{
+        channel::{mpsc::unbounded, oneshot},
+        StreamExt,
+    };
+    use parking_lot::Mutex;
+    use std::{sync::Arc, time::Duration};
+    use tokio::time::timeout;
+
+    const QUEUED_MESSAGES: usize = 100_000;
+    const SPAM_CAPACITY: usize = 1000;
+    const EXIT_BOUND: Duration = Duration::from_millis(100);
+    const NO_LIMITS: MessageLimits = MessageLimits {
+        max_units_per_message: usize::MAX,
+        max_message_size: None,
+    };
+
+    type TestNetworkData = NetworkData<Hasher64, Data, Signature, PartialMultisignature>;
+
+    /// Records everything sent through it, and yields no events, temporarily unless closed.
+    #[derive(Clone, Default)]
+    struct RecordingNetwork {
+        sent: Arc<Mutex<Vec<(TestNetworkData, Recipient)>>>,
+        closed: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl Network<TestNetworkData> for RecordingNetwork {
+        fn send(&self, data: TestNetworkData, recipient: Recipient) {
+            self.sent.lock().push((data, recipient));
+        }
+
+        async fn next_event(&mut self) -> Option<TestNetworkData> {
+            futures::future::pending().await
+        }
+
+        fn is_terminated(&self) -> bool {
+            self.closed
+        }
+    }
+
+    fn test_unit(round: Round) -> UncheckedSignedUnit<Hasher64, Data, Signature> {
+        let creator = NodeIndex(0);
+        let control_hash = ControlHash::new(&NodeMap::with_size(4.into()));
+        let pu = PreUnit::new(creator, round, control_hash);
+        let signable = FullUnit::new(pu, Some(0), 0);
+        Signed::sign(signable, &Keychain::new(4.into(), creator))
+            .expect("the keychain never fails")
+            .into_unchecked()
+    }
+
+    fn test_hub(
+        network: RecordingNetwork,
+        monitor: BroadcastDedupMonitor,
+    ) -> Hub<Hasher64, Data, Signature, PartialMultisignature, RecordingNetwork> {
+        let (_, units_to_send) = unbounded();
+        let (units_received, _) = ingress_queue(QUEUED_MESSAGES);
+        let (_, alerts_to_send) = unbounded();
+        let (alerts_received, _) = unbounded();
+        Hub::new(
+            network,
+            units_to_send,
+            units_received,
+            alerts_to_send,
+            alerts_received,
+            BroadcastDeduplicator::new(Duration::from_secs(3600), monitor),
+            NO_LIMITS,
+            Compression::None,
+            0,
+            AdmissionMonitor::default(),
+            DropMonitor::default(),
+            DEFAULT_NETWORK_RETRY,
+            ClockSource::default(),
+            CallbackGuard::default(),
+            EventBus::new(),
+        )
+    }
+
+    fn broadcast_units(
+        network: &RecordingNetwork,
+    ) -> Vec<UncheckedSignedUnit<Hasher64, Data, Signature>> {
+        network
+            .sent
+            .lock()
+            .iter()
+            .filter_map(|(data, recipient)| match (data, recipient) {
+                (
+                    NetworkData(NetworkDataInner::Units(UnitMessage::NewUnit(unit)), _),
+                    Recipient::Everyone,
+                ) => Some(unit.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn repeated_broadcast_is_sent_once() {
+        let network = RecordingNetwork::default();
+        let (handle, monitor) = broadcast_dedup_monitor();
+        let mut hub = test_hub(network.clone(), monitor);
+        let unit = test_unit(0);
+
+        // Once when created and once when rebroadcast right away.
+        hub.send_units(UnitMessage::NewUnit(unit.clone()), Recipient::Everyone)
+            .expect("the network does not panic");
+        hub.send_units(UnitMessage::NewUnit(unit.clone()), Recipient::Everyone)
+            .expect("the network does not panic");
+
+        assert_eq!(broadcast_units(&network), vec![unit]);
+        assert_eq!(handle.suppressed(), 1);
+    }
+
+    #[test]
+    fn different_units_are_broadcast() {
+        let network = RecordingNetwork::default();
+        let mut hub = test_hub(network.clone(), BroadcastDedupMonitor::default());
+        let units = vec![test_unit(0), test_unit(1)];
+
+        for unit in &units {
+            hub.send_units(UnitMessage::NewUnit(unit.clone()), Recipient::Everyone)
+                .expect("the network does not panic");
+        }
+
+        assert_eq!(broadcast_units(&network), units);
+    }
+
+    #[test]
+    fn targeted_sends_are_not_deduplicated() {
+        let network = RecordingNetwork::default();
+        let mut hub = test_hub(network.clone(), BroadcastDedupMonitor::default());
+        let unit = test_unit(0);
+
+        hub.send_units(UnitMessage::NewUnit(unit.clone()), Recipient::Everyone)
+            .expect("the network does not panic");
+        for _ in 0..2 {
+            hub.send_units(
+                UnitMessage::NewUnit(unit.clone()),
+                Recipient::Node(NodeIndex(1)),
+            )
+            .expect("the network does not panic");
+        }
+
+        assert_eq!(network.sent.lock().len(), 3);
+    }
+
+    #[test]
+    fn transient_end_of_events_is_retried_within_budget() {
+        let mut hub = test_hub(
+            RecordingNetwork::default(),
+            BroadcastDedupMonitor::default(),
+        );
+        let mut events = hub.events.subscribe();
+        let mut backoffs = Vec::new();
+        for _ in 0..DEFAULT_NETWORK_RETRY.max_consecutive_failures {
+            backoffs.push(hub.backoff());
+            let now = hub.clock.now();
+            assert!(!hub.on_no_event().expect("the network does not panic"));
+            assert!(
+                hub.retry_at.expect("a retry is scheduled") >= now + backoffs[backoffs.len() - 1]
+            );
+        }
+        assert_eq!(backoffs[0], DEFAULT_NETWORK_RETRY.initial_backoff);
+        assert!(backoffs.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(
+            backoffs.last().copied(),
+            Some(DEFAULT_NETWORK_RETRY.max_backoff)
+        );
+        assert!(hub.on_no_event().expect("the network does not panic"));
+        let states: Vec<_> = std::iter::from_fn(|| events.try_next().ok().flatten())
+            .filter_map(|event| match event {
+                InternalEvent::NetworkStateChanged(state) => Some(state),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            states,
+            vec![NetworkState::Interrupted, NetworkState::Closed]
+        );
+    }
+
+    #[test]
+    fn closed_network_is_not_retried() {
+        let network = RecordingNetwork {
+            closed: true,
+            ..RecordingNetwork::default()
+        };
+        let mut hub = test_hub(network, BroadcastDedupMonitor::default());
+        assert!(hub.on_no_event().expect("the network does not panic"));
+        assert_eq!(hub.retry_at, None);
+    }
+
+    #[test]
+    fn spam_from_the_network_stays_within_the_queue() {
+        let mut hub = test_hub(
+            RecordingNetwork::default(),
+            BroadcastDedupMonitor::default(),
+        );
+        let (units_received, units_from_hub) = ingress_queue(SPAM_CAPACITY);
+        hub.units_received = units_received;
+        let (handle, drops) = drop_monitor();
+        hub.drops = drops;
+        for round in 0..QUEUED_MESSAGES {
+            let unit = test_unit((round % 1000) as Round);
+            hub.handle_incoming(NetworkData(
+                NetworkDataInner::Units(UnitMessage::NewUnit(unit)),
+                0,
+            ));
+        }
+
+        assert_eq!(units_from_hub.len(), SPAM_CAPACITY);
+        assert_eq!(
+            handle.stats().count(DropReason::QueueFull),
+            QUEUED_MESSAGES - SPAM_CAPACITY
+        );
+    }
+
+    #[tokio::test]
+    async fn oversized_messages_are_dropped() {
+        let mut hub = test_hub(
+            RecordingNetwork::default(),
+            BroadcastDedupMonitor::default(),
+        );
+        let (units_received, mut units_from_hub) = ingress_queue(QUEUED_MESSAGES);
+        hub.units_received = units_received;
+        let (alerts_received, mut alerts_from_hub) = unbounded();
+        hub.alerts_received = alerts_received;
+        let (handle, drops) = drop_monitor();
+        hub.drops = drops;
+        let units = |count: usize| (0..count).map(|round| test_unit(round as Round)).collect();
+        let parents = |count| {
+            NetworkData(
+                NetworkDataInner::Units(UnitMessage::ResponseParents(
+                    Default::default(),
+                    units(count),
+                )),
+                0,
+            )
+        };
+        let keychain = Keychain::new(4.into(), NodeIndex(1));
+        let alert = |count| {
+            let alert = Alert::new(NodeIndex(1), (test_unit(0), test_unit(0)), units(count));
+            let alert = Signed::sign(alert, &keychain)
+                .expect("the keychain never fails")
+                .into_unchecked();
+            NetworkData(NetworkDataInner::Alert(AlertMessage::ForkAlert(alert)), 0)
+        };
+        hub.limits = MessageLimits {
+            max_units_per_message: 4,
+            max_message_size: Some(alert(4).encoded_size()),
+        };
+
+        for message in [parents(4), parents(1000), alert(4), alert(1000)] {
+            hub.handle_incoming(message);
+        }
+
+        assert_eq!(handle.stats().count(DropReason::OversizedMessage), 2);
+        assert_eq!(units_from_hub.len(), 1);
+        match units_from_hub.next().await {
+            Some((UnitMessage::ResponseParents(_, units), _, _)) => assert_eq!(units.len(), 4),
+            _ => panic!("the small response should be queued"),
+        }
+        match alerts_from_hub.try_next() {
+            Ok(Some(AlertMessage::ForkAlert(alert))) => {
+                assert_eq!(alert.as_signable().included_data_with_creators().len(), 4)
+            }
+            _ => panic!("the small alert should be passed on"),
+        }
+        assert!(alerts_from_hub.try_next().is_err());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[tokio::test]
+    async fn compressed_messages_are_decompressed_within_limits() {
+        let network = RecordingNetwork::default();
+        let mut sending_hub = test_hub(network.clone(), BroadcastDedupMonitor::default());
+        sending_hub.compression = Compression::Zstd;
+        let parents = NetworkData(
+            NetworkDataInner::Units(UnitMessage::ResponseParents(
+                Default::default(),
+                (0..100).map(|round| test_unit(round as Round)).collect(),
+            )),
+            0,
+        );
+        let size = parents.encoded_size();
+        sending_hub
+            .send(parents.0.clone(), Recipient::Node(NodeIndex(1)))
+            .expect("the network does not panic");
+        let (compressed, _) = network.sent.lock().pop().expect("the message was sent");
+        assert!(matches!(compressed.0, NetworkDataInner::Compressed(_)));
+        assert!(compressed.encoded_size() < size);
+        assert_eq!(compressed.included_data(), parents.included_data());
+
+        let mut hub = test_hub(
+            RecordingNetwork::default(),
+            BroadcastDedupMonitor::default(),
+        );
+        let (units_received, mut units_from_hub) = ingress_queue(QUEUED_MESSAGES);
+        hub.units_received = units_received;
+        let (handle, drops) = drop_monitor();
+        hub.drops = drops;
+        hub.handle_incoming(compressed.clone());
+        match units_from_hub.next().await {
+            Some((UnitMessage::ResponseParents(_, units), _, _)) => assert_eq!(units.len(), 100),
+            _ => panic!("the decompressed response should be queued"),
+        }
+
+        // The compressed message is small, but it decompresses over the limit.
+        hub.limits = MessageLimits {
+            max_units_per_message: usize::MAX,
+            max_message_size: Some(size - 1),
+        };
+        hub.handle_incoming(compressed);
+        assert_eq!(handle.stats().count(DropReason::OversizedMessage), 1);
+        assert_eq!(units_from_hub.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn malformed_compressed_messages_are_dropped() {
+        let mut hub = test_hub(
+            RecordingNetwork::default(),
+            BroadcastDedupMonitor::default(),
+        );
+        let (units_received, units_from_hub) = ingress_queue(QUEUED_MESSAGES);
+        hub.units_received = units_received;
+        let (handle, drops) = drop_monitor();
+        hub.drops = drops;
+        let malformed = CompressedMessage::decode(&mut &(1000u32, vec![0x80u8, 1, 0]).encode()[..])
+            .expect("the message decodes");
+        hub.handle_incoming(NetworkData(NetworkDataInner::Compressed(malformed), 0));
+        assert_eq!(handle.stats().count(DropReason::MalformedCompression), 1);
+        assert_eq!(units_from_hub.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn sends_at_the_wire_version_and_drops_unsupported_ones() {
+        let network = RecordingNetwork::default();
+        let mut hub = test_hub(network.clone(), BroadcastDedupMonitor::default());
+        hub.wire_version = MAX_SUPPORTED_VERSION;
+        let parents = NetworkDataInner::Units(UnitMessage::ResponseParents(
+            Default::default(),
+            vec![test_unit(0)],
+        ));
+        hub.send(parents.clone(), Recipient::Node(NodeIndex(1)))
+            .expect("the network does not panic");
+        let (sent, _) = network.sent.lock().pop().expect("the message was sent");
+        assert_eq!(sent, NetworkData(parents.clone(), MAX_SUPPORTED_VERSION));
+
+        let (units_received, mut units_from_hub) = ingress_queue(QUEUED_MESSAGES);
+        hub.units_received = units_received;
+        let (handle, drops) = drop_monitor();
+        hub.drops = drops;
+        for _ in 0..3 {
+            hub.handle_incoming(NetworkData(
+                NetworkDataInner::Unsupported,
+                MAX_SUPPORTED_VERSION + 1,
+            ));
+        }
+        assert_eq!(handle.stats().count(DropReason::UnsupportedVersion), 3);
+        // Only the first drop was warned about, the others wait for the next warning.
+        assert_eq!(hub.unsupported_dropped, 2);
+        // Messages of older supported versions still get through.
+        hub.handle_incoming(NetworkData(parents, 0));
+        match units_from_hub.next().await {
+            Some((UnitMessage::ResponseParents(_, units), _, _)) => assert_eq!(units.len(), 1),
+            _ => panic!("the response should be queued"),
+        }
+        assert_eq!(units_from_hub.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn exits_without_draining_the_queue() {
+        let network = RecordingNetwork::default();
+        let mut hub = test_hub(network.clone(), BroadcastDedupMonitor::default());
+        let (units_for_hub, units_to_send) = unbounded();
+        hub.units_to_send = units_to_send;
+        let message = UnitMessage::NewUnit(test_unit(0));
+        for _ in 0..QUEUED_MESSAGES {
+            units_for_hub
+                .unbounded_send((message.clone(), Recipient::Node(NodeIndex(1))))
+                .expect("the hub is not running yet");
+        }
+        let (exit_tx, exit_rx) = oneshot::channel();
+        exit_tx.send(()).expect("the receiver is alive");
+
+        timeout(
+            EXIT_BOUND,
+            hub.run(Terminator::create_root(exit_rx, "network-hub")),
+        )
+        .await
+        .expect("the hub should exit right away");
+        assert!(network.sent.lock().is_empty());
+    }
+}
@@ -1,41 +1,205 @@
 use crate::{
-    alerts::AlertMessage, member::UnitMessage, Data, Hasher, PartialMultisignature, Signature,
+    alerts::AlertMessage, decoding::MAX_DECODE_DEPTH, member::UnitMessage, Data, Hasher, NodeIndex,
+    PartialMultisignature, Signature,
 };
-use codec::{Decode, Encode};
+use codec::{Decode, DecodeLimit, Encode, Error as CodecError, Input, Output};
 use std::fmt::Debug;
 
+mod compression;
+mod dedup;
 mod hub;
+mod sessions;
 
-pub use hub::Hub;
+pub(crate) use compression::{CompressedMessage, DecompressionError};
+pub use compression::{Compression, MAX_DECOMPRESSED_SIZE};
+pub(crate) use dedup::BroadcastDeduplicator;
+pub use dedup::{broadcast_dedup_monitor, BroadcastDedupHandle, BroadcastDedupMonitor};
+pub use hub::{Hub, MessageLimits};
+pub use sessions::{SessionNetwork, SessionRouter, SessionRoutingStats};
+
+/// The version of the encoding of the messages exchanged by the nodes, i.e. of how they are laid
+/// out in bytes. It is independent of [`crate::protocol::PROTOCOL_VERSION`], which versions what
+/// the nodes have to agree on, including the contents of the messages, and is recorded in the
+/// backups. The messages we send at version 0 are understood by the releases predating wire
+/// versions, while from [`EXTENDED_MESSAGES_VERSION`] on they might carry what those cannot read.
+pub type WireVersion = u8;
+
+/// The newest version of the encoding of [`NetworkData`] we decode. Version 0 is the encoding
+/// without a version, version 1 adds the version in front of it.
+pub const MAX_SUPPORTED_VERSION: WireVersion = 1;
+
+/// The first version at which we send what the releases predating wire versions cannot read: the
+/// metadata of our units, compact alerts, alerts about several forkers and compressed messages.
+/// At older versions we leave the metadata out, compact alerts and compression cannot be enabled,
+/// and we raise one alert per forker.
+pub const EXTENDED_MESSAGES_VERSION: WireVersion = 1;
+
+/// Precedes the version in messages of any version but 0. It is not the variant index of any
+/// message, so messages of version 0 are told apart by their first byte.
+const VERSIONED: u8 = u8::MAX;
 
 #[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
 pub(crate) enum NetworkDataInner<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> {
     Units(UnitMessage<H, D, S>),
     Alert(AlertMessage<H, D, S, MS>),
+    /// The encoding of one of the above, compressed. The variant index is the header telling
+    /// compressed messages apart.
+    Compressed(CompressedMessage),
+    /// A message of a version newer than [`MAX_SUPPORTED_VERSION`], the content of which we
+    /// cannot read. It is only ever decoded from such messages, never sent.
+    #[codec(skip)]
+    Unsupported,
 }
 
 impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> NetworkDataInner<H, D, S, MS> {
-    pub(crate) fn included_data(&self) -> Vec<D> {
+    pub(crate) fn included_data_with_creators(&self) -> Vec<(D, NodeIndex)> {
         match self {
-            Self::Units(message) => message.included_data(),
-            Self::Alert(message) => message.included_data(),
+            Self::Units(message) => message.included_data_with_creators(),
+            Self::Alert(message) => message.included_data_with_creators(),
+            Self::Compressed(message) => match Self::decompress(message, MAX_DECOMPRESSED_SIZE) {
+                Ok(inner) => inner.included_data_with_creators(),
+                Err(_) => Vec::new(),
+            },
+            Self::Unsupported => Vec::new(),
+        }
+    }
+
+    /// Decodes a message of version 0, the variant index of which was already read.
+    fn decode_unversioned<I: Input>(variant: u8, input: &mut I) -> Result<Self, CodecError> {
+        match variant {
+            0 => Ok(Self::Units(UnitMessage::decode_with_depth_limit(
+                MAX_DECODE_DEPTH,
+                input,
+            )?)),
+            1 => Ok(Self::Alert(AlertMessage::decode_with_depth_limit(
+                MAX_DECODE_DEPTH,
+                input,
+            )?)),
+            2 => Ok(Self::Compressed(CompressedMessage::decode(input)?)),
+            _ => Err("unknown variant of a network message".into()),
+        }
+    }
+
+    /// Compresses the message, unless the compression is disabled or would not shrink it.
+    pub(crate) fn compressed(self, compression: Compression) -> Self {
+        match compression.compress(&self.encode()) {
+            Some(message) => Self::Compressed(message),
+            None => self,
+        }
+    }
+
+    /// Decompresses the message, if it is compressed, failing if it would be over `max_size`
+    /// bytes.
+    pub(crate) fn decompressed(self, max_size: usize) -> Result<Self, DecompressionError> {
+        match self {
+            Self::Compressed(message) => Self::decompress(&message, max_size),
+            inner => Ok(inner),
+        }
+    }
+
+    /// Decompresses the message, failing if it would be over `max_size` bytes. A compressed
+    /// message cannot contain another one.
+    pub(crate) fn decompress(
+        message: &CompressedMessage,
+        max_size: usize,
+    ) -> Result<Self, DecompressionError> {
+        let encoded = message.decompress(max_size)?;
+        match Self::decode_all_with_depth_limit(MAX_DECODE_DEPTH, &mut &encoded[..]) {
+            Ok(Self::Compressed(_)) | Err(_) => Err(DecompressionError::Malformed),
+            Ok(inner) => Ok(inner),
         }
     }
 }
 
 /// NetworkData is the opaque format for all data that a committee member needs to send to other nodes.
-#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
+///
+/// It is encoded at the version it carries, the one set with [`crate::Config::with_wire_version`]
+/// for the messages we send, and decodes from any version up to [`MAX_SUPPORTED_VERSION`], so
+/// that nodes can be upgraded one at a time. Messages of newer versions still decode, but are
+/// dropped unread. Decoding never panics and refuses lists longer than
+/// [`crate::MAX_DECODED_ITEMS`] or nested deeper than [`crate::MAX_DECODE_DEPTH`], so arbitrary
+/// bytes from the network can be decoded safely.
+#[derive(Clone, Eq, PartialEq, Debug)]
 pub struct NetworkData<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature>(
     pub(crate) NetworkDataInner<H, D, S, MS>,
+    pub(crate) WireVersion,
 );
 
+impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> Encode
+    for NetworkData<H, D, S, MS>
+{
+    fn size_hint(&self) -> usize {
+        let header = match self.1 {
+            0 => 0,
+            _ => 2,
+        };
+        header + self.0.size_hint()
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        if self.1 != 0 {
+            dest.push_byte(VERSIONED);
+            dest.push_byte(self.1);
+        }
+        self.0.encode_to(dest);
+    }
+}
+
+impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> Decode
+    for NetworkData<H, D, S, MS>
+{
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        let first = input.read_byte()?;
+        if first != VERSIONED {
+            return Ok(NetworkData(
+                NetworkDataInner::decode_unversioned(first, input)?,
+                0,
+            ));
+        }
+        match input.read_byte()? {
+            // Version 0 is never written down, so that every message has one encoding.
+            0 => Err("version 0 of a network message written down".into()),
+            version if version > MAX_SUPPORTED_VERSION => {
+                skip_rest(input)?;
+                Ok(NetworkData(NetworkDataInner::Unsupported, version))
+            }
+            version => Ok(NetworkData(
+                NetworkDataInner::decode_with_depth_limit(MAX_DECODE_DEPTH, input)?,
+                version,
+            )),
+        }
+    }
+}
+
+/// Reads the rest of the input, if its length is known, so that a message we cannot read still
+/// decodes whole.
+fn skip_rest<I: Input>(input: &mut I) -> Result<(), CodecError> {
+    let mut remaining = input.remaining_len()?.unwrap_or(0);
+    let mut buffer = [0; 256];
+    while remaining > 0 {
+        let chunk = remaining.min(buffer.len());
+        input.read(&mut buffer[..chunk])?;
+        remaining -= chunk;
+    }
+    Ok(())
+}
+
 impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> NetworkData<H, D, S, MS> {
     /// Returns all the Data in the network message that might end up in the ordering as a result
     /// of accepting this message. Useful for ensuring data availability, if Data only represents
     /// the objects the user wants to order, and facilitates access to the Data before it is
     /// ordered for optimization purposes.
     pub fn included_data(&self) -> Vec<D> {
-        self.0.included_data()
+        self.included_data_with_creators()
+            .into_iter()
+            .map(|(data, _)| data)
+            .collect()
+    }
+
+    /// Like [`Self::included_data`], but every item is paired with the creator of the unit
+    /// containing it. Useful for treating data differently depending on who proposed it.
+    pub fn included_data_with_creators(&self) -> Vec<(D, NodeIndex)> {
+        self.0.included_data_with_creators()
     }
 }
 
@@ -43,14 +207,18 @@ impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> NetworkData<H,
 mod tests {
     use crate::{
         alerts::AlertMessage,
+        dissemination::{CompactUnit, NewestUnitsResponse},
         member::UnitMessage,
-        network::NetworkDataInner::{Alert, Units},
-        units::{ControlHash, FullUnit, PreUnit, UncheckedSignedUnit, UnitCoord},
+        network::{
+            NetworkDataInner::{Alert, Units, Unsupported},
+            MAX_SUPPORTED_VERSION,
+        },
+        units::{ControlHash, FullUnit, PreUnit, UncheckedSignedUnit, Unit, UnitCoord},
         Hasher, NodeIndex, Round, Signed,
     };
     use aleph_bft_mock::{Data, Hasher64, Keychain, PartialMultisignature, Signature};
     use aleph_bft_types::NodeMap;
-    use codec::{Decode, Encode};
+    use codec::{Decode, DecodeAll, Encode};
 
     fn test_unchecked_unit(
         creator: NodeIndex,
@@ -60,7 +228,9 @@ mod tests {
         let control_hash = ControlHash::new(&NodeMap::with_size(7.into()));
         let pu = PreUnit::new(creator, round, control_hash);
         let signable = FullUnit::new(pu, Some(data), 0);
-        Signed::sign(signable, &Keychain::new(0.into(), creator)).into_unchecked()
+        Signed::sign(signable, &Keychain::new(0.into(), creator))
+            .expect("the keychain never fails")
+            .into_unchecked()
     }
 
     type TestNetworkData = super::NetworkData<Hasher64, Data, Signature, PartialMultisignature>;
@@ -68,10 +238,68 @@ mod tests {
         fn new(
             inner: super::NetworkDataInner<Hasher64, Data, Signature, PartialMultisignature>,
         ) -> Self {
-            super::NetworkData::<Hasher64, Data, Signature, PartialMultisignature>(inner)
+            super::NetworkData::<Hasher64, Data, Signature, PartialMultisignature>(inner, 0)
         }
     }
 
+    // The encodings of `test_messages` sent by the last release predating wire versions,
+    // captured from it rather than produced by this code. Every later release has to decode them
+    // and, at wire version 0, encode them the same.
+    const UNVERSIONED_NEW_UNIT: [u8; 58] = [
+        0, 0, 43, 0, 5, 0, 0, 0, 0, 0, 0, 0, 28, 0, 0, 0, 0, 0, 0, 0, 1, 12, 26, 170, 170, 201,
+        247, 76, 1, 193, 6, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 32, 110, 245, 114, 15, 168, 22, 199, 138,
+        5, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    const UNVERSIONED_REQUEST_COORD: [u8; 20] =
+        [0, 1, 7, 0, 0, 0, 0, 0, 0, 0, 3, 0, 13, 0, 0, 0, 0, 0, 0, 0];
+    const UNVERSIONED_FORK_ALERT: [u8; 140] = [
+        1, 0, 7, 0, 0, 0, 0, 0, 0, 0, 10, 0, 9, 0, 0, 0, 0, 0, 0, 0, 28, 0, 0, 0, 0, 0, 0, 0, 1,
+        12, 26, 170, 170, 201, 247, 76, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 32, 115, 104, 151,
+        175, 178, 22, 159, 139, 9, 0, 0, 0, 0, 0, 0, 0, 10, 0, 9, 0, 0, 0, 0, 0, 0, 0, 28, 0, 0, 0,
+        0, 0, 0, 0, 1, 12, 26, 170, 170, 201, 247, 76, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 32,
+        137, 131, 59, 81, 243, 76, 17, 15, 9, 0, 0, 0, 0, 0, 0, 0, 0, 32, 84, 227, 4, 26, 211, 180,
+        245, 46, 7, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    const UNVERSIONED_RESPONSE_PARENTS: [u8; 67] = [
+        0, 4, 110, 245, 114, 15, 168, 22, 199, 138, 4, 42, 0, 2, 0, 0, 0, 0, 0, 0, 0, 28, 0, 0, 0,
+        0, 0, 0, 0, 1, 12, 26, 170, 170, 201, 247, 76, 1, 17, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 32,
+        249, 185, 56, 10, 80, 136, 218, 89, 2, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    const UNVERSIONED_ALERT_REQUEST: [u8; 18] = [
+        1, 2, 3, 0, 0, 0, 0, 0, 0, 0, 84, 227, 4, 26, 211, 180, 245, 46,
+    ];
+
+    fn test_messages() -> Vec<TestNetworkData> {
+        let forker = 9.into();
+        let alert = crate::alerts::Alert::new(
+            7.into(),
+            (
+                test_unchecked_unit(forker, 10, 0),
+                test_unchecked_unit(forker, 10, 1),
+            ),
+            Vec::new(),
+        );
+        let alert_hash = crate::Signable::hash(&alert);
+        let alert = Signed::sign(alert, &Keychain::new(0.into(), 7.into()))
+            .expect("the keychain never fails")
+            .into_unchecked();
+        let unit = test_unchecked_unit(5.into(), 43, 1729);
+        let unit_hash = unit.as_signable().hash();
+        vec![
+            TestNetworkData::new(Units(UnitMessage::NewUnit(unit))),
+            TestNetworkData::new(Units(UnitMessage::RequestCoord(
+                7.into(),
+                UnitCoord::new(3, 13.into()),
+            ))),
+            TestNetworkData::new(Units(UnitMessage::ResponseParents(
+                unit_hash,
+                vec![test_unchecked_unit(2.into(), 42, 17)],
+            ))),
+            TestNetworkData::new(Alert(AlertMessage::ForkAlert(alert))),
+            TestNetworkData::new(Alert(AlertMessage::AlertRequest(3.into(), alert_hash))),
+        ]
+    }
+
     #[test]
     fn decoding_network_data_units_new_unit() {
         use UnitMessage::NewUnit;
@@ -211,6 +439,87 @@ mod tests {
         }
     }
 
+    #[test]
+    fn included_data_is_paired_with_creators() {
+        use UnitMessage::ResponseParents;
+
+        let h = 43.using_encoded(Hasher64::hash);
+        let parents = vec![
+            test_unchecked_unit(5.into(), 43, 1729),
+            test_unchecked_unit(13.into(), 43, 1730),
+        ];
+        let nd = TestNetworkData::new(Units(ResponseParents(h, parents)));
+        assert_eq!(
+            nd.included_data_with_creators(),
+            vec![(1729, 5.into()), (1730, 13.into())]
+        );
+    }
+
+    #[test]
+    fn decoding_network_data_units_response_parents_compact() {
+        use UnitMessage::ResponseParentsCompact;
+
+        let ni = 7.into();
+        let h = 43.using_encoded(Hasher64::hash);
+        let full = test_unchecked_unit(5.into(), 43, 1729);
+        let referenced = test_unchecked_unit(13.into(), 43, 1730);
+        let parents = vec![
+            CompactUnit::Full(full),
+            CompactUnit::HashRef(referenced.as_signable().hash()),
+        ];
+
+        let nd = TestNetworkData::new(Units(ResponseParentsCompact(ni, h, parents.clone())));
+        let decoded = TestNetworkData::decode(&mut &nd.encode()[..]);
+        assert!(
+            decoded.is_ok(),
+            "Bug in encode/decode for ResponseParentsCompact"
+        );
+        let decoded = decoded.unwrap();
+        assert_eq!(
+            decoded.included_data_with_creators(),
+            vec![(1729, 5.into())],
+            "only the units sent in full carry data"
+        );
+        if let Units(ResponseParentsCompact(dni, dh, dparents)) = decoded.0 {
+            assert!(ni == dni && h == dh, "decoded should equal encoded");
+            assert_eq!(parents, dparents, "decoded should equal encoded");
+        } else {
+            panic!("Decoded ResponseParentsCompact as something else");
+        }
+    }
+
+    #[test]
+    fn decoding_network_data_units_response_newest_units() {
+        use UnitMessage::ResponseNewestUnits;
+
+        let units = vec![
+            test_unchecked_unit(5.into(), 43, 1729),
+            test_unchecked_unit(13.into(), 44, 1730),
+        ];
+        let response = NewestUnitsResponse::new(1.into(), 7.into(), 2137, units.clone());
+        let response = Signed::sign(response, &Keychain::new(0.into(), 7.into()))
+            .expect("the keychain never fails")
+            .into_unchecked();
+
+        let nd = TestNetworkData::new(Units(ResponseNewestUnits(response.clone())));
+        let decoded = TestNetworkData::decode(&mut &nd.encode()[..]);
+        assert!(
+            decoded.is_ok(),
+            "Bug in encode/decode for ResponseNewestUnits"
+        );
+        let decoded = decoded.unwrap();
+        assert_eq!(
+            decoded.included_data_with_creators(),
+            vec![(1729, 5.into()), (1730, 13.into())],
+            "data decoded incorrectly"
+        );
+        if let Units(ResponseNewestUnits(dresponse)) = decoded.0 {
+            assert_eq!(response, dresponse, "decoded should equal encoded");
+        } else {
+            panic!("Decoded ResponseNewestUnits as something else");
+        }
+    }
+
     #[test]
     fn decoding_network_data_alert_fork_alert() {
         use AlertMessage::ForkAlert;
@@ -226,7 +535,9 @@ mod tests {
         let alert = crate::alerts::Alert::new(sender, (f1, f2), vec![lu1, lu2]);
 
         let nd = TestNetworkData::new(Alert(ForkAlert(
-            Signed::sign(alert.clone(), &Keychain::new(0.into(), sender)).into_unchecked(),
+            Signed::sign(alert.clone(), &Keychain::new(0.into(), sender))
+                .expect("the keychain never fails")
+                .into_unchecked(),
         )));
         let decoded = TestNetworkData::decode(&mut &nd.encode()[..]);
         assert!(decoded.is_ok(), "Bug in encode/decode for ForkAlert");
@@ -246,4 +557,61 @@ mod tests {
             panic!("Decoded ForkAlert as something else");
         }
     }
+
+    #[test]
+    fn decodes_unversioned_messages() {
+        let blobs: [&[u8]; 5] = [
+            &UNVERSIONED_NEW_UNIT,
+            &UNVERSIONED_REQUEST_COORD,
+            &UNVERSIONED_RESPONSE_PARENTS,
+            &UNVERSIONED_FORK_ALERT,
+            &UNVERSIONED_ALERT_REQUEST,
+        ];
+        assert_eq!(blobs.len(), test_messages().len());
+        for (blob, message) in blobs.into_iter().zip(test_messages()) {
+            let decoded =
+                TestNetworkData::decode_all(&mut &blob[..]).expect("the message should decode");
+            assert_eq!(decoded, message);
+            assert_eq!(decoded.1, 0);
+            assert_eq!(decoded.encode(), blob, "version 0 should encode as before");
+        }
+    }
+
+    #[test]
+    fn round_trips_at_the_newest_version() {
+        for super::NetworkData(inner, _) in test_messages() {
+            let unversioned = TestNetworkData::new(inner.clone()).encode();
+            let message = super::NetworkData(inner, MAX_SUPPORTED_VERSION);
+            let encoded = message.encode();
+            assert_eq!(encoded[..2], [u8::MAX, MAX_SUPPORTED_VERSION]);
+            assert_eq!(encoded[2..], unversioned[..]);
+            assert_eq!(TestNetworkData::decode_all(&mut &encoded[..]), Ok(message));
+        }
+    }
+
+    #[test]
+    fn decodes_messages_of_unsupported_versions_unread() {
+        let newer = MAX_SUPPORTED_VERSION + 1;
+        let mut encoded = vec![u8::MAX, newer];
+        encoded.extend(vec![0xab; 1000]);
+        assert_eq!(
+            TestNetworkData::decode_all(&mut &encoded[..]),
+            Ok(super::NetworkData(Unsupported, newer))
+        );
+        assert!(
+            super::NetworkData::<Hasher64, Data, Signature, PartialMultisignature>(
+                Unsupported,
+                newer
+            )
+            .included_data()
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn refuses_version_zero_written_down() {
+        let mut encoded = vec![u8::MAX, 0];
+        encoded.extend(UNVERSIONED_REQUEST_COORD);
+        assert!(TestNetworkData::decode(&mut &encoded[..]).is_err());
+    }
 }
@@ -0,0 +1,214 @@
+use crate::{ClockSource, Network, Receiver, Recipient, Sender, SessionId};
+use futures::{channel::mpsc, future::Either, pin_mut, FutureExt, StreamExt};
+use log::{debug, trace};
+use parking_lot::Mutex;
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    sync::Arc,
+    time::Duration,
+};
+
+const LOG_TARGET: &str = "AlephBFT-session-router";
+
+/// How long to wait before polling the wrapped network again, after it yielded no event without
+/// being terminated.
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// What happened to the messages passed through a [`SessionRouter`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SessionRoutingStats {
+    /// How many messages wait for their sessions to be opened.
+    pub buffered: usize,
+    /// How many messages were dropped, as their sessions were already closed.
+    pub dropped_closed: usize,
+    /// How many messages for sessions not opened yet were dropped, as the buffer was full.
+    pub dropped_buffer_full: usize,
+}
+
+struct Sessions<M> {
+    open: HashMap<SessionId, Sender<M>>,
+    pending: HashMap<SessionId, Vec<M>>,
+    closed: HashSet<SessionId>,
+    buffer_limit: usize,
+    stats: SessionRoutingStats,
+}
+
+impl<M> Sessions<M> {
+    fn route(&mut self, session_id: SessionId, message: M) {
+        if self.closed.contains(&session_id) {
+            self.stats.dropped_closed += 1;
+            return;
+        }
+        // A session whose network was dropped without closing it might be started again, e.g.
+        // after a crash, so its messages are buffered like the ones of sessions not opened yet.
+        let message = match self.open.get(&session_id) {
+            Some(session) => match session.unbounded_send(message) {
+                Ok(()) => return,
+                Err(e) => {
+                    self.open.remove(&session_id);
+                    e.into_inner()
+                }
+            },
+            None => message,
+        };
+        if self.stats.buffered >= self.buffer_limit {
+            trace!(target: LOG_TARGET, "Buffer full, dropping a message of session {}.", session_id);
+            self.stats.dropped_buffer_full += 1;
+            return;
+        }
+        self.pending.entry(session_id).or_default().push(message);
+        self.stats.buffered += 1;
+    }
+
+    fn take_pending(&mut self, session_id: SessionId) -> Vec<M> {
+        let pending = self.pending.remove(&session_id).unwrap_or_default();
+        self.stats.buffered -= pending.len();
+        pending
+    }
+}
+
+/// Multiplexes the networks of concurrent sessions, e.g. overlapping during a transition
+/// between eras, over a single network carrying messages tagged with their session.
+///
+/// Messages for sessions that were not opened yet are buffered, up to a limit shared by all the
+/// sessions, and passed on once the session is opened. Messages for closed sessions are
+/// dropped, both incoming and outgoing ones.
+pub struct SessionRouter<M> {
+    sessions: Arc<Mutex<Sessions<M>>>,
+    outgoing: Sender<(SessionId, M, Recipient)>,
+}
+
+impl<M: Send + 'static> SessionRouter<M> {
+    /// Wraps the network, buffering at most `buffer_limit` messages for sessions not opened yet.
+    /// Returns the router together with the future passing the messages, which has to be polled,
+    /// e.g. spawned, for as long as the sessions run. It finishes when the wrapped network is
+    /// terminated, or when the router and all the session networks are dropped.
+    pub fn new<N: Network<(SessionId, M)>>(
+        network: N,
+        buffer_limit: usize,
+    ) -> (Self, impl Future<Output = ()> + Send) {
+        let sessions = Arc::new(Mutex::new(Sessions {
+            open: HashMap::new(),
+            pending: HashMap::new(),
+            closed: HashSet::new(),
+            buffer_limit,
+            stats: SessionRoutingStats::default(),
+        }));
+        let (outgoing, outgoing_rx) = mpsc::unbounded();
+        let router = SessionRouter {
+            sessions: sessions.clone(),
+            outgoing,
+        };
+        (router, run(network, sessions, outgoing_rx))
+    }
+
+    /// The network of the session, to be passed to [`crate::run_session`], receiving the
+    /// messages buffered for it so far. `None` if the session is closed, or its network is in
+    /// use. Opening a session again after its network was dropped is allowed.
+    pub fn open(&self, session_id: SessionId) -> Option<SessionNetwork<M>> {
+        let mut sessions = self.sessions.lock();
+        if sessions.closed.contains(&session_id)
+            || sessions
+                .open
+                .get(&session_id)
+                .is_some_and(|session| !session.is_closed())
+        {
+            return None;
+        }
+        let (for_session, incoming) = mpsc::unbounded();
+        for message in sessions.take_pending(session_id) {
+            let _ = for_session.unbounded_send(message);
+        }
+        sessions.open.insert(session_id, for_session);
+        debug!(target: LOG_TARGET, "Opened session {}.", session_id);
+        Some(SessionNetwork {
+            session_id,
+            outgoing: self.outgoing.clone(),
+            incoming,
+        })
+    }
+
+    /// Drops all the messages of the session from now on, including the buffered ones. The
+    /// network of the session yields no more events, so a session still running on it ends.
+    /// A closed session cannot be opened again.
+    pub fn close(&self, session_id: SessionId) {
+        let mut sessions = self.sessions.lock();
+        sessions.take_pending(session_id);
+        sessions.open.remove(&session_id);
+        sessions.closed.insert(session_id);
+        debug!(target: LOG_TARGET, "Closed session {}.", session_id);
+    }
+
+    /// What happened to the messages so far.
+    pub fn stats(&self) -> SessionRoutingStats {
+        self.sessions.lock().stats
+    }
+}
+
+async fn run<M: Send, N: Network<(SessionId, M)>>(
+    mut network: N,
+    sessions: Arc<Mutex<Sessions<M>>>,
+    mut outgoing: Receiver<(SessionId, M, Recipient)>,
+) {
+    let clock = ClockSource::default();
+    loop {
+        let event = {
+            let next_event = network.next_event().fuse();
+            pin_mut!(next_event);
+            futures::select! {
+                event = next_event => Either::Left(event),
+                message = outgoing.next() => Either::Right(message),
+            }
+        };
+        match event {
+            Either::Left(Some((session_id, message))) => sessions.lock().route(session_id, message),
+            Either::Left(None) if network.is_terminated() => {
+                debug!(target: LOG_TARGET, "Network terminated, stopping.");
+                break;
+            }
+            Either::Left(None) => clock.sleep(RETRY_DELAY).await,
+            Either::Right(Some((session_id, message, recipient))) => {
+                if !sessions.lock().closed.contains(&session_id) {
+                    network.send((session_id, message), recipient);
+                }
+            }
+            Either::Right(None) => {
+                debug!(target: LOG_TARGET, "Router and all the sessions gone, stopping.");
+                break;
+            }
+        }
+    }
+    // The sessions see the end of their networks.
+    sessions.lock().open.clear();
+}
+
+/// The network of a single session, see [`SessionRouter::open`].
+pub struct SessionNetwork<M> {
+    session_id: SessionId,
+    outgoing: Sender<(SessionId, M, Recipient)>,
+    incoming: Receiver<M>,
+}
+
+impl<M> SessionNetwork<M> {
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: Send + 'static> Network<M> for SessionNetwork<M> {
+    fn send(&self, data: M, recipient: Recipient) {
+        if self
+            .outgoing
+            .unbounded_send((self.session_id, data, recipient))
+            .is_err()
+        {
+            debug!(target: LOG_TARGET, "Router stopped, dropping a message of session {}.", self.session_id);
+        }
+    }
+
+    async fn next_event(&mut self) -> Option<M> {
+        self.incoming.next().await
+    }
+}
@@ -0,0 +1,277 @@
+use crate::{Data, Hasher, NodeIndex, NodeSubset, NodeWeights, Round};
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// How many receipts wait for the application before the oldest ones get dropped.
+const RECEIPTS_KEPT: usize = 1000;
+
+/// How many of our units can wait for a quorum at once, older ones are forgotten without a
+/// receipt, as some peers might never evidence holding them.
+const PENDING_RECEIPTS_LIMIT: usize = 100;
+
+/// The moment a quorum of the committee demonstrably held our unit of the given round.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QuorumReceipt<D> {
+    pub round: Round,
+    /// The data our unit carried, if any.
+    pub data: Option<D>,
+    /// How long after we broadcast the unit the last evidence completing the quorum arrived.
+    pub latency_from_broadcast: Duration,
+}
+
+impl<D> Display for QuorumReceipt<D> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "our unit of round {} held by a quorum {}ms after the broadcast",
+            self.round,
+            self.latency_from_broadcast.as_millis()
+        )
+    }
+}
+
+/// Allows the application to learn when the data it provided reached a quorum of the
+/// committee, a leading indicator of its finalization.
+#[derive(Clone)]
+pub struct QuorumReceiptHandle<D> {
+    receipts: Arc<Mutex<VecDeque<QuorumReceipt<D>>>>,
+}
+
+impl<D> QuorumReceiptHandle<D> {
+    /// The receipts issued since the last call, oldest first. Only the latest receipts are kept
+    /// when the handle is not asked for a long time.
+    pub fn take(&self) -> Vec<QuorumReceipt<D>> {
+        self.receipts.lock().drain(..).collect()
+    }
+}
+
+/// The part of the receipt tracking passed to the session, see [`quorum_receipt_monitor`].
+pub struct QuorumReceiptMonitor<D> {
+    receipts: Arc<Mutex<VecDeque<QuorumReceipt<D>>>>,
+}
+
+impl<D> Clone for QuorumReceiptMonitor<D> {
+    fn clone(&self) -> Self {
+        QuorumReceiptMonitor {
+            receipts: self.receipts.clone(),
+        }
+    }
+}
+
+impl<D> Default for QuorumReceiptMonitor<D> {
+    fn default() -> Self {
+        QuorumReceiptMonitor {
+            receipts: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+}
+
+impl<D> QuorumReceiptMonitor<D> {
+    fn record(&self, receipt: QuorumReceipt<D>) {
+        let mut receipts = self.receipts.lock();
+        if receipts.len() == RECEIPTS_KEPT {
+            receipts.pop_front();
+        }
+        receipts.push_back(receipt);
+    }
+}
+
+/// Creates a handle for reading the quorum receipts of our units together with the monitor that
+/// should be passed to the session with [`crate::LocalIO::with_quorum_receipt_monitor`].
+pub fn quorum_receipt_monitor<D>() -> (QuorumReceiptHandle<D>, QuorumReceiptMonitor<D>) {
+    let monitor = QuorumReceiptMonitor::default();
+    (
+        QuorumReceiptHandle {
+            receipts: monitor.receipts.clone(),
+        },
+        monitor,
+    )
+}
+
+struct PendingReceipt<H: Hasher, D: Data> {
+    hash: H::Hash,
+    data: Option<D>,
+    broadcast_at: Instant,
+    holders: NodeSubset,
+}
+
+/// Infers which peers hold our units from the parents of their units. A peer holding a unit of
+/// ours as a parent holds all of its ancestors too, so it evidences all our earlier units. We
+/// count ourselves among the holders, so with `n = 3f + 1` nodes a receipt needs evidence from
+/// `2f` peers, or from peers weighing enough to make a quorum with us if the nodes have weights.
+pub(crate) struct QuorumReceiptTracker<H: Hasher, D: Data> {
+    own_id: NodeIndex,
+    node_weights: NodeWeights,
+    pending: BTreeMap<Round, PendingReceipt<H, D>>,
+    monitor: QuorumReceiptMonitor<D>,
+}
+
+impl<H: Hasher, D: Data> QuorumReceiptTracker<H, D> {
+    pub fn new(
+        own_id: NodeIndex,
+        node_weights: NodeWeights,
+        monitor: QuorumReceiptMonitor<D>,
+    ) -> Self {
+        QuorumReceiptTracker {
+            own_id,
+            node_weights,
+            pending: BTreeMap::new(),
+            monitor,
+        }
+    }
+
+    /// Starts waiting for the evidence about our unit, which we just broadcast.
+    pub fn on_own_unit_broadcast(
+        &mut self,
+        round: Round,
+        hash: H::Hash,
+        data: Option<D>,
+        now: Instant,
+    ) {
+        let mut holders = NodeSubset::with_size(self.node_weights.node_count());
+        holders.insert(self.own_id);
+        self.pending.insert(
+            round,
+            PendingReceipt {
+                hash,
+                data,
+                broadcast_at: now,
+                holders,
+            },
+        );
+        while self.pending.len() > PENDING_RECEIPTS_LIMIT {
+            self.pending.pop_first();
+        }
+    }
+
+    /// Notes that the peer holds the unit with the given hash, as its unit has it as a parent.
+    /// Returns the receipts of our units that got held by a quorum because of that.
+    pub fn on_parent_of_peer_unit(
+        &mut self,
+        peer: NodeIndex,
+        parent: &H::Hash,
+        now: Instant,
+    ) -> Vec<QuorumReceipt<D>> {
+        let evidenced = match self
+            .pending
+            .iter()
+            .find(|(_, pending)| pending.hash == *parent)
+        {
+            Some((round, _)) => *round,
+            None => return Vec::new(),
+        };
+        let mut completed = Vec::new();
+        for (round, pending) in self.pending.range_mut(..=evidenced) {
+            pending.holders.insert(peer);
+            if self.node_weights.is_quorum(pending.holders.elements()) {
+                completed.push(*round);
+            }
+        }
+        let mut receipts = Vec::new();
+        for round in completed {
+            let pending = self.pending.remove(&round).expect("just found");
+            let receipt = QuorumReceipt {
+                round,
+                data: pending.data,
+                latency_from_broadcast: now.saturating_duration_since(pending.broadcast_at),
+            };
+            self.monitor.record(receipt.clone());
+            receipts.push(receipt);
+        }
+        receipts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        receipts::{quorum_receipt_monitor, QuorumReceipt, QuorumReceiptTracker},
+        NodeCount, NodeIndex, NodeWeights,
+    };
+    use aleph_bft_mock::{Data, Hasher64};
+    use std::time::{Duration, Instant};
+
+    fn tracker() -> (
+        crate::receipts::QuorumReceiptHandle<Data>,
+        QuorumReceiptTracker<Hasher64, Data>,
+    ) {
+        let (handle, monitor) = quorum_receipt_monitor();
+        (
+            handle,
+            QuorumReceiptTracker::new(NodeIndex(0), NodeWeights::uniform(NodeCount(7)), monitor),
+        )
+    }
+
+    #[test]
+    fn issues_a_receipt_once_a_quorum_holds_the_unit() {
+        let (handle, mut tracker) = tracker();
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        tracker.on_own_unit_broadcast(3, [3; 8], Some(33), start);
+        for peer in 1..4 {
+            assert!(tracker
+                .on_parent_of_peer_unit(NodeIndex(peer), &[3; 8], at(10 * peer as u64))
+                .is_empty());
+        }
+        // Repeated evidence from the same peer does not count.
+        assert!(tracker
+            .on_parent_of_peer_unit(NodeIndex(3), &[3; 8], at(40))
+            .is_empty());
+        let expected = QuorumReceipt {
+            round: 3,
+            data: Some(33),
+            latency_from_broadcast: Duration::from_millis(50),
+        };
+        assert_eq!(
+            tracker.on_parent_of_peer_unit(NodeIndex(5), &[3; 8], at(50)),
+            vec![expected.clone()]
+        );
+        assert!(tracker
+            .on_parent_of_peer_unit(NodeIndex(6), &[3; 8], at(60))
+            .is_empty());
+        assert_eq!(handle.take(), vec![expected]);
+        assert!(handle.take().is_empty());
+    }
+
+    #[test]
+    fn evidence_covers_earlier_units() {
+        let (_, mut tracker) = tracker();
+        let start = Instant::now();
+        tracker.on_own_unit_broadcast(0, [0; 8], Some(0), start);
+        tracker.on_own_unit_broadcast(1, [1; 8], None, start);
+        tracker.on_own_unit_broadcast(2, [2; 8], Some(2), start);
+        for peer in 1..4 {
+            tracker.on_parent_of_peer_unit(NodeIndex(peer), &[0; 8], start);
+        }
+        let receipts = tracker.on_parent_of_peer_unit(NodeIndex(4), &[1; 8], start);
+        let rounds: Vec<_> = receipts.iter().map(|receipt| receipt.round).collect();
+        assert_eq!(rounds, vec![0]);
+        for peer in 1..3 {
+            tracker.on_parent_of_peer_unit(NodeIndex(peer), &[2; 8], start);
+        }
+        let receipts = tracker.on_parent_of_peer_unit(NodeIndex(3), &[2; 8], start);
+        let rounds: Vec<_> = receipts.iter().map(|receipt| receipt.round).collect();
+        assert_eq!(rounds, vec![1]);
+        let receipts = tracker.on_parent_of_peer_unit(NodeIndex(4), &[2; 8], start);
+        let rounds: Vec<_> = receipts.iter().map(|receipt| receipt.round).collect();
+        assert_eq!(rounds, vec![2]);
+    }
+
+    #[test]
+    fn ignores_units_of_others() {
+        let (handle, mut tracker) = tracker();
+        let start = Instant::now();
+        tracker.on_own_unit_broadcast(0, [0; 8], Some(0), start);
+        for peer in 1..7 {
+            assert!(tracker
+                .on_parent_of_peer_unit(NodeIndex(peer), &[7; 8], start)
+                .is_empty());
+        }
+        assert!(handle.take().is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+use crate::{NodeCount, NodeIndex, NodeMap, Round};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{Display, Formatter, Result as FmtResult},
+    time::{Duration, Instant},
+};
+
+/// How many of our latest rounds the reception of the units of every creator is tracked for.
+pub const RECEPTION_WINDOW: usize = 100;
+
+/// How many rounds after creating our unit of a round the units of others of that round are
+/// still waited for, later units count as missed.
+pub const RECEPTION_GRACE_ROUNDS: Round = 10;
+
+/// How the units of a single creator reached us in the latest rounds, see [`RECEPTION_WINDOW`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CreatorReception {
+    /// How many of our rounds are in the window.
+    pub rounds: usize,
+    /// In how many of them the unit of the creator did not arrive within
+    /// [`RECEPTION_GRACE_ROUNDS`].
+    pub missed: usize,
+    /// The median time from the creation of our unit of a round to the arrival of the unit of
+    /// the creator of the same round, zero for units arriving earlier. Absent if none arrived.
+    pub median_latency: Option<Duration>,
+    /// The longest such time, absent if no unit arrived.
+    pub max_latency: Option<Duration>,
+}
+
+impl CreatorReception {
+    /// The part of the rounds in which the unit of the creator was missed, between 0 and 1.
+    pub fn miss_rate(&self) -> f64 {
+        match self.rounds {
+            0 => 0.0,
+            rounds => self.missed as f64 / rounds as f64,
+        }
+    }
+}
+
+impl Display for CreatorReception {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self.median_latency {
+            Some(median) => write!(f, "median {}ms", median.as_millis())?,
+            None => write!(f, "none arrived")?,
+        }
+        write!(f, ", missed {}/{}", self.missed, self.rounds)
+    }
+}
+
+/// The latencies of the units of every creator of a single round, absent for the missed units.
+struct RoundReception {
+    latencies: Vec<Option<Duration>>,
+}
+
+/// Measures how long after our units the units of the other creators of the same round arrive.
+/// Holds at most [`RECEPTION_WINDOW`] summarized rounds and [`RECEPTION_GRACE_ROUNDS`] rounds
+/// waiting to be summarized, of a single entry per creator.
+pub(crate) struct ReceptionTracker {
+    own_id: NodeIndex,
+    n_members: NodeCount,
+    created: HashMap<Round, Instant>,
+    arrivals: HashMap<Round, NodeMap<Instant>>,
+    last_created: Option<Round>,
+    last_summarized: Option<Round>,
+    window: VecDeque<RoundReception>,
+}
+
+impl ReceptionTracker {
+    pub fn new(own_id: NodeIndex, n_members: NodeCount) -> Self {
+        ReceptionTracker {
+            own_id,
+            n_members,
+            created: HashMap::new(),
+            arrivals: HashMap::new(),
+            last_created: None,
+            last_summarized: None,
+            window: VecDeque::new(),
+        }
+    }
+
+    fn is_tracked(&self, round: Round) -> bool {
+        let grace = RECEPTION_GRACE_ROUNDS as usize;
+        self.last_summarized.map_or(true, |last| round > last)
+            && (round as usize) <= self.last_created.unwrap_or(0) as usize + grace
+    }
+
+    pub fn on_unit_admitted(&mut self, creator: NodeIndex, round: Round, now: Instant) {
+        if creator == self.own_id || !self.is_tracked(round) {
+            return;
+        }
+        let n_members = self.n_members;
+        let arrivals = self
+            .arrivals
+            .entry(round)
+            .or_insert_with(|| NodeMap::with_size(n_members));
+        if arrivals.get(creator).is_none() {
+            arrivals.insert(creator, now);
+        }
+    }
+
+    /// Notes the creation of our unit of the given round, summarizing the round
+    /// [`RECEPTION_GRACE_ROUNDS`] earlier.
+    pub fn on_own_unit_created(&mut self, round: Round, now: Instant) {
+        self.created.insert(round, now);
+        self.last_created = Some(self.last_created.map_or(round, |last| last.max(round)));
+        let Some(summarized_round) = round.checked_sub(RECEPTION_GRACE_ROUNDS) else {
+            return;
+        };
+        if self
+            .last_summarized
+            .is_some_and(|last| summarized_round <= last)
+        {
+            return;
+        }
+        self.last_summarized = Some(summarized_round);
+        let created = self.created.remove(&summarized_round);
+        let arrivals = self.arrivals.remove(&summarized_round);
+        self.created.retain(|round, _| *round > summarized_round);
+        self.arrivals.retain(|round, _| *round > summarized_round);
+        // Without our unit of the round there is nothing to measure against.
+        let Some(created) = created else {
+            return;
+        };
+        let arrivals = arrivals.unwrap_or_else(|| NodeMap::with_size(self.n_members));
+        let latencies = self
+            .n_members
+            .into_iterator()
+            .map(|node_id| {
+                arrivals
+                    .get(node_id)
+                    .map(|arrival| arrival.saturating_duration_since(created))
+            })
+            .collect();
+        self.window.push_back(RoundReception { latencies });
+        if self.window.len() > RECEPTION_WINDOW {
+            self.window.pop_front();
+        }
+    }
+
+    /// The reception of the units of every creator other than us in the window, empty before
+    /// the first round is summarized.
+    pub fn reception(&self) -> NodeMap<CreatorReception> {
+        let mut reception = NodeMap::with_size(self.n_members);
+        if self.window.is_empty() {
+            return reception;
+        }
+        for node_id in self.n_members.into_iterator() {
+            if node_id == self.own_id {
+                continue;
+            }
+            let mut latencies: Vec<_> = self
+                .window
+                .iter()
+                .filter_map(|round| round.latencies[node_id.0])
+                .collect();
+            latencies.sort();
+            reception.insert(
+                node_id,
+                CreatorReception {
+                    rounds: self.window.len(),
+                    missed: self.window.len() - latencies.len(),
+                    median_latency: latencies.get(latencies.len() / 2).copied(),
+                    max_latency: latencies.last().copied(),
+                },
+            );
+        }
+        reception
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        reception::{ReceptionTracker, RECEPTION_GRACE_ROUNDS, RECEPTION_WINDOW},
+        NodeCount, NodeIndex, Round,
+    };
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn measures_latency_against_our_unit_of_the_round() {
+        let mut tracker = ReceptionTracker::new(NodeIndex(0), NodeCount(4));
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        for round in 0..=RECEPTION_GRACE_ROUNDS {
+            let created = 100 * round as u64;
+            tracker.on_unit_admitted(NodeIndex(1), round, at(created.saturating_sub(10)));
+            tracker.on_own_unit_created(round, at(created));
+            tracker.on_unit_admitted(NodeIndex(2), round, at(created + 30));
+        }
+        let reception = tracker.reception();
+        assert!(reception.get(NodeIndex(0)).is_none());
+        let early = reception.get(NodeIndex(1)).expect("tracked");
+        assert_eq!(early.rounds, 1);
+        assert_eq!(early.median_latency, Some(Duration::ZERO));
+        let late = reception.get(NodeIndex(2)).expect("tracked");
+        assert_eq!(late.median_latency, Some(Duration::from_millis(30)));
+        assert_eq!(late.missed, 0);
+        let missing = reception.get(NodeIndex(3)).expect("tracked");
+        assert_eq!(missing.missed, 1);
+        assert_eq!(missing.median_latency, None);
+        assert_eq!(missing.miss_rate(), 1.0);
+    }
+
+    #[test]
+    fn stays_bounded() {
+        let mut tracker = ReceptionTracker::new(NodeIndex(0), NodeCount(4));
+        let start = Instant::now();
+        let rounds = 3 * RECEPTION_WINDOW as Round;
+        for round in 0..rounds {
+            tracker.on_own_unit_created(round, start);
+            tracker.on_unit_admitted(NodeIndex(1), round, start);
+            // Units far ahead of us are not tracked.
+            tracker.on_unit_admitted(NodeIndex(2), round + 1000, start);
+        }
+        assert_eq!(tracker.window.len(), RECEPTION_WINDOW);
+        assert!(tracker.created.len() <= RECEPTION_GRACE_ROUNDS as usize);
+        assert!(tracker.arrivals.len() <= 2 * RECEPTION_GRACE_ROUNDS as usize);
+        let reception = tracker.reception();
+        let tracked = reception.get(NodeIndex(1)).expect("tracked");
+        assert_eq!(tracked.rounds, RECEPTION_WINDOW);
+        assert_eq!(tracked.missed, 0);
+    }
+}
@@ -0,0 +1,331 @@
+use crate::{
+    callbacks::{CallbackGuard, UserComponent},
+    dag::DagUnit,
+    units::{Unit, UnitWithParents, WrappedUnit},
+    ClockSource, Data, DataAvailabilityChecker, Hasher, MultiKeychain, NodeIndex, Receiver, Sender,
+    SpawnHandle,
+};
+use futures::{channel::mpsc, future::pending, stream::FuturesUnordered, StreamExt};
+use log::error;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+/// The hashes of the units the data of which became available.
+pub type AvailableUnits<H> = Receiver<<H as Hasher>::Hash>;
+
+type Check<H, D> = (<H as Hasher>::Hash, D);
+
+struct HeldUnit<H: Hasher, D: Data, MK: MultiKeychain> {
+    unit: DagUnit<H, D, MK>,
+    awaits_data: bool,
+    held_parents: usize,
+}
+
+/// Holds back reconstructed units of other nodes until their data is available, see
+/// [`crate::DataAvailabilityChecker`], together with the units above them, so that the units
+/// leave in an order respecting the dag. The held units are still processing in the dag, so
+/// their copies are recognized and their forks detected as usual.
+pub struct Availability<H: Hasher, D: Data, MK: MultiKeychain> {
+    checks: Option<Sender<Check<H, D>>>,
+    own_id: NodeIndex,
+    max_held: usize,
+    held: HashMap<H::Hash, HeldUnit<H, D, MK>>,
+    held_children: HashMap<H::Hash, Vec<H::Hash>>,
+    // Kept, so that the stream of available units does not end when nothing is checked.
+    _available_for_checker: Sender<H::Hash>,
+}
+
+impl<H: Hasher, D: Data, MK: MultiKeychain> Availability<H, D, MK> {
+    /// Spawns a task running the checks, if there is a checker, returning the stream of units
+    /// found available. Without a checker, no unit is ever held.
+    pub fn new<SH: SpawnHandle>(
+        checker: Option<Arc<dyn DataAvailabilityChecker<D>>>,
+        own_id: NodeIndex,
+        max_held: usize,
+        recheck_interval: Duration,
+        clock: ClockSource,
+        callbacks: CallbackGuard,
+        spawn_handle: &SH,
+    ) -> (Self, AvailableUnits<H>) {
+        let (available_for_checker, available) = mpsc::unbounded();
+        let checks = checker.map(|checker| {
+            let (checks_for_checker, checks) = mpsc::unbounded();
+            spawn_handle.spawn(
+                "runway/availability",
+                run_checker::<H, D>(
+                    checker,
+                    checks,
+                    available_for_checker.clone(),
+                    recheck_interval,
+                    clock,
+                    callbacks,
+                ),
+            );
+            checks_for_checker
+        });
+        let availability = Availability {
+            checks,
+            own_id,
+            max_held,
+            held: HashMap::new(),
+            held_children: HashMap::new(),
+            _available_for_checker: available_for_checker,
+        };
+        (availability, available)
+    }
+
+    /// How many units are held.
+    pub fn len(&self) -> usize {
+        self.held.len()
+    }
+
+    /// Whether as many units are held as allowed, in which case new units should not come.
+    pub fn is_full(&self) -> bool {
+        self.held.len() >= self.max_held
+    }
+
+    /// Holds the unit if its data has to be checked or any of its parents is held, otherwise
+    /// returns it right away.
+    pub fn hold(&mut self, unit: DagUnit<H, D, MK>) -> Option<DagUnit<H, D, MK>> {
+        let checks = match &self.checks {
+            Some(checks) => checks,
+            None => return Some(unit),
+        };
+        let hash = unit.hash();
+        // Our own data needs no checking.
+        let data = match unit.creator() != self.own_id && unit.has_data() {
+            true => unit.clone().unpack().into_signable().data().clone(),
+            false => None,
+        };
+        let awaits_data = match data {
+            Some(data) => match checks.unbounded_send((hash, data)) {
+                Ok(()) => true,
+                Err(_) => {
+                    error!(target: "AlephBFT-runway", "The data availability checker is gone.");
+                    true
+                }
+            },
+            None => false,
+        };
+        let held_parents: Vec<_> = unit
+            .parents()
+            .filter(|parent| self.held.contains_key(parent))
+            .copied()
+            .collect();
+        if !awaits_data && held_parents.is_empty() {
+            return Some(unit);
+        }
+        for parent in &held_parents {
+            self.held_children.entry(*parent).or_default().push(hash);
+        }
+        self.held.insert(
+            hash,
+            HeldUnit {
+                unit,
+                awaits_data,
+                held_parents: held_parents.len(),
+            },
+        );
+        None
+    }
+
+    /// The data of the unit is available, returns the units released thanks to that, parents
+    /// before their children.
+    pub fn on_available(&mut self, hash: &H::Hash) -> Vec<DagUnit<H, D, MK>> {
+        let mut released = Vec::new();
+        match self.held.get_mut(hash) {
+            Some(held) => held.awaits_data = false,
+            None => return released,
+        }
+        let mut candidates = vec![*hash];
+        while let Some(hash) = candidates.pop() {
+            let ready = self
+                .held
+                .get(&hash)
+                .is_some_and(|held| !held.awaits_data && held.held_parents == 0);
+            if !ready {
+                continue;
+            }
+            let held = self.held.remove(&hash).expect("just checked");
+            released.push(held.unit);
+            for child in self.held_children.remove(&hash).unwrap_or_default() {
+                if let Some(held_child) = self.held.get_mut(&child) {
+                    held_child.held_parents -= 1;
+                    candidates.push(child);
+                }
+            }
+        }
+        released
+    }
+}
+
+/// Checks the availability of the data, checking again after the interval until it is
+/// available. A checker that panicked ends the session, so its checks never finish.
+async fn check<H: Hasher, D: Data>(
+    checker: Arc<dyn DataAvailabilityChecker<D>>,
+    (hash, data): Check<H, D>,
+    recheck_interval: Duration,
+    clock: ClockSource,
+    callbacks: CallbackGuard,
+) -> H::Hash {
+    loop {
+        match callbacks
+            .call_async(
+                UserComponent::DataAvailabilityChecker,
+                checker.available(&data),
+            )
+            .await
+        {
+            Ok(true) => return hash,
+            Ok(false) => clock.sleep(recheck_interval).await,
+            Err(_) => pending().await,
+        }
+    }
+}
+
+async fn run_checker<H: Hasher, D: Data>(
+    checker: Arc<dyn DataAvailabilityChecker<D>>,
+    mut checks: Receiver<Check<H, D>>,
+    available: Sender<H::Hash>,
+    recheck_interval: Duration,
+    clock: ClockSource,
+    callbacks: CallbackGuard,
+) {
+    let mut running = FuturesUnordered::new();
+    loop {
+        futures::select! {
+            next = checks.next() => match next {
+                Some(next) => running.push(check::<H, D>(
+                    checker.clone(),
+                    next,
+                    recheck_interval,
+                    clock.clone(),
+                    callbacks.clone(),
+                )),
+                None => return,
+            },
+            hash = running.select_next_some() => {
+                if available.unbounded_send(hash).is_err() {
+                    return;
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        callbacks::CallbackGuard,
+        runway::availability::Availability,
+        units::{random_full_parent_reconstrusted_units_up_to, Unit, UnitWithParents},
+        ClockSource, DataAvailabilityChecker, NodeCount, NodeIndex,
+    };
+    use aleph_bft_mock::{Data, Hasher64, Keychain, Spawner};
+    use async_trait::async_trait;
+    use std::{collections::HashSet, sync::Arc, time::Duration};
+
+    const N_MEMBERS: NodeCount = NodeCount(4);
+    const OWN_ID: NodeIndex = NodeIndex(0);
+    const MAX_HELD: usize = 1000;
+
+    /// Never finds anything available, so that the test decides what becomes available.
+    struct NeverAvailable;
+
+    #[async_trait]
+    impl DataAvailabilityChecker<Data> for NeverAvailable {
+        async fn available(&self, _data: &Data) -> bool {
+            false
+        }
+    }
+
+    fn availability(
+        checker: Option<Arc<dyn DataAvailabilityChecker<Data>>>,
+    ) -> Availability<Hasher64, Data, Keychain> {
+        Availability::new(
+            checker,
+            OWN_ID,
+            MAX_HELD,
+            Duration::from_secs(3600),
+            ClockSource::default(),
+            CallbackGuard::default(),
+            &Spawner::new(),
+        )
+        .0
+    }
+
+    #[tokio::test]
+    async fn passes_units_without_a_checker() {
+        let keychains = Keychain::new_vec(N_MEMBERS);
+        let mut availability = availability(None);
+        for unit in random_full_parent_reconstrusted_units_up_to(3, N_MEMBERS, 0, &keychains)
+            .into_iter()
+            .flatten()
+        {
+            assert!(availability.hold(unit).is_some());
+        }
+        assert_eq!(availability.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn releases_parents_before_children() {
+        let keychains = Keychain::new_vec(N_MEMBERS);
+        let mut availability = availability(Some(Arc::new(NeverAvailable)));
+        let mut saved = HashSet::new();
+        let mut held = Vec::new();
+        for unit in random_full_parent_reconstrusted_units_up_to(3, N_MEMBERS, 0, &keychains)
+            .into_iter()
+            .flatten()
+        {
+            let hash = unit.hash();
+            let awaits_data = unit.creator() != OWN_ID && unit.has_data();
+            let held_parents = unit.parents().any(|parent| !saved.contains(parent));
+            match availability.hold(unit) {
+                Some(unit) => {
+                    assert!(!awaits_data && !held_parents);
+                    saved.insert(unit.hash());
+                }
+                None => {
+                    assert!(awaits_data || held_parents);
+                    held.push(hash);
+                }
+            }
+        }
+        assert_eq!(availability.len(), held.len());
+
+        // The newest units become available first, so they wait for the ones below them.
+        for hash in held.iter().rev() {
+            for unit in availability.on_available(hash) {
+                assert!(unit.parents().all(|parent| saved.contains(parent)));
+                saved.insert(unit.hash());
+            }
+        }
+        assert_eq!(availability.len(), 0);
+        assert!(held.iter().all(|hash| saved.contains(hash)));
+    }
+
+    #[tokio::test]
+    async fn is_full_when_holding_enough_units() {
+        let keychains = Keychain::new_vec(N_MEMBERS);
+        let mut availability = Availability::<Hasher64, Data, Keychain>::new(
+            Some(Arc::new(NeverAvailable)),
+            OWN_ID,
+            1,
+            Duration::from_secs(3600),
+            ClockSource::default(),
+            CallbackGuard::default(),
+            &Spawner::new(),
+        )
+        .0;
+        assert!(!availability.is_full());
+        let unit = random_full_parent_reconstrusted_units_up_to(5, N_MEMBERS, 0, &keychains)
+            .into_iter()
+            .flatten()
+            .find(|unit| unit.creator() != OWN_ID && unit.has_data())
+            .expect("some unit has data");
+        let hash = unit.hash();
+        assert!(availability.hold(unit).is_none());
+        assert!(availability.is_full());
+        assert_eq!(availability.on_available(&hash).len(), 1);
+        assert!(!availability.is_full());
+    }
+}
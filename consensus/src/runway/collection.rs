@@ -1,12 +1,12 @@
 use crate::{
-    runway::Request,
+    events::{EventBus, InternalEvent},
+    runway::{Request, RunwayNotificationOut},
     units::{UncheckedSignedUnit, Unit, ValidationError, Validator},
-    Data, Hasher, Keychain, NodeCount, NodeIndex, NodeMap, Receiver, Round, Sender, Signable,
-    Signature, SignatureError, UncheckedSigned,
+    ClockSource, Data, Hasher, Keychain, NodeCount, NodeIndex, NodeMap, Receiver, Round, Sender,
+    Signable, Signature, SignatureError, UncheckedSigned,
 };
 use codec::{Decode, Encode};
 use futures::{channel::oneshot, FutureExt, StreamExt};
-use futures_timer::Delay;
 use log::{debug, error, info, warn};
 use std::{
     cmp::max,
@@ -64,10 +64,11 @@ impl<H: Hasher, D: Data, S: Signature> NewestUnitResponse<H, D, S> {
         }
     }
 
-    /// The data included in this message, i.e. contents of the unit if any.
-    pub fn included_data(&self) -> Vec<D> {
+    /// The data included in this message, i.e. contents of the unit if any, together with the
+    /// creator of the unit.
+    pub fn included_data_with_creators(&self) -> Vec<(D, NodeIndex)> {
         match &self.unit {
-            Some(u) => u.as_signable().included_data(),
+            Some(u) => u.as_signable().included_data_with_creator(),
             None => Vec::new(),
         }
     }
@@ -76,6 +77,11 @@ impl<H: Hasher, D: Data, S: Signature> NewestUnitResponse<H, D, S> {
     pub fn requester(&self) -> NodeIndex {
         self.requester
     }
+
+    /// The newest unit of the requester, if the responder knows any.
+    pub fn unit(&self) -> Option<&UncheckedSignedUnit<H, D, S>> {
+        self.unit.as_ref()
+    }
 }
 
 /// Ways in which a newest unit response might be wrong.
@@ -195,8 +201,10 @@ impl<'a, MK: Keychain> Collection<'a, MK> {
         self.salt
     }
 
-    fn threshold(&self) -> NodeCount {
-        self.collected_starting_rounds.size().consensus_threshold()
+    fn has_quorum(&self) -> bool {
+        self.validator
+            .node_weights()
+            .is_quorum(self.collected_starting_rounds.iter().map(|(node, _)| node))
     }
 
     /// The current status of the collection.
@@ -207,7 +215,7 @@ impl<'a, MK: Keychain> Collection<'a, MK> {
         if responders == self.keychain.node_count() {
             return Finished(starting_round);
         }
-        if responders >= self.threshold() {
+        if self.has_quorum() {
             return Ready(starting_round);
         }
         Pending
@@ -223,8 +231,10 @@ type ResponsesFromNetwork<H, D, MK> = UncheckedSigned<
 pub struct IO<'a, H: Hasher, D: Data, MK: Keychain> {
     round_for_creator: oneshot::Sender<Round>,
     responses_from_network: Receiver<ResponsesFromNetwork<H, D, MK>>,
-    resolved_requests: Sender<Request<H>>,
+    notifications_for_member: Sender<RunwayNotificationOut<H, D, MK::Signature>>,
+    events: EventBus<H, D, MK::Signature>,
     collection: Collection<'a, MK>,
+    clock: ClockSource,
 }
 
 impl<'a, H: Hasher, D: Data, MK: Keychain> IO<'a, H, D, MK> {
@@ -232,14 +242,18 @@ impl<'a, H: Hasher, D: Data, MK: Keychain> IO<'a, H, D, MK> {
     pub fn new(
         round_for_creator: oneshot::Sender<Round>,
         responses_from_network: Receiver<ResponsesFromNetwork<H, D, MK>>,
-        resolved_requests: Sender<Request<H>>,
+        notifications_for_member: Sender<RunwayNotificationOut<H, D, MK::Signature>>,
+        events: EventBus<H, D, MK::Signature>,
         collection: Collection<'a, MK>,
+        clock: ClockSource,
     ) -> Self {
         IO {
             round_for_creator,
             responses_from_network,
-            resolved_requests,
+            notifications_for_member,
+            events,
             collection,
+            clock,
         }
     }
 
@@ -247,11 +261,14 @@ impl<'a, H: Hasher, D: Data, MK: Keychain> IO<'a, H, D, MK> {
         if self.round_for_creator.send(round).is_err() {
             error!(target: "AlephBFT-runway", "unable to send starting round to creator");
         }
-        if let Err(e) = self.resolved_requests.unbounded_send(Request::NewestUnit(
-            self.collection.index(),
-            self.collection.salt(),
-        )) {
-            warn!(target: "AlephBFT-runway", "unable to send resolved request:  {}", e);
+        let request = Request::NewestUnit(self.collection.index(), self.collection.salt());
+        self.events
+            .publish(InternalEvent::RequestResolved(request.clone()));
+        if let Err(e) = self
+            .notifications_for_member
+            .unbounded_send(RunwayNotificationOut::RequestResolved(request))
+        {
+            warn!(target: "AlephBFT-runway", "unable to send resolved request: {}", e);
         }
         info!(target: "AlephBFT-runway", "Finished initial unit collection with status: {:?}", self.collection.status());
     }
@@ -263,11 +280,12 @@ impl<'a, H: Hasher, D: Data, MK: Keychain> IO<'a, H, D, MK> {
     /// Run the initial unit collection until it sends the initial round.
     pub async fn run(mut self) {
         use Status::*;
-        let mut catch_up_delay = futures_timer::Delay::new(Duration::from_secs(5)).fuse();
+        let clock = self.clock.clone();
+        let mut catch_up_delay = clock.sleep(Duration::from_secs(5)).fuse();
         let mut delay_passed = false;
 
         let status_ticker_delay = Duration::from_secs(10);
-        let mut status_ticker = Delay::new(status_ticker_delay).fuse();
+        let mut status_ticker = clock.sleep(status_ticker_delay).fuse();
 
         loop {
             futures::select! {
@@ -306,7 +324,7 @@ impl<'a, H: Hasher, D: Data, MK: Keychain> IO<'a, H, D, MK> {
                 },
                 _ = &mut status_ticker => {
                     self.status_report();
-                    status_ticker = Delay::new(status_ticker_delay).fuse();
+                    status_ticker = clock.sleep(status_ticker_delay).fuse();
                 },
             }
         }
@@ -355,7 +373,11 @@ mod tests {
         let mut result = Vec::new();
         for (keychain, maybe_unit) in presponses {
             let response = NewestUnitResponse::new(requester, keychain.index(), maybe_unit, salt);
-            result.push(Signed::sign(response, keychain).into_unchecked());
+            result.push(
+                Signed::sign(response, keychain)
+                    .expect("the keychain never fails")
+                    .into_unchecked(),
+            );
         }
         result
     }
@@ -366,7 +388,7 @@ mod tests {
         keychain: &Keychain,
     ) -> UncheckedSignedUnit {
         let full_unit = FullUnit::new(pu, Some(0), session_id);
-        let signed_unit = Signed::sign(full_unit, keychain);
+        let signed_unit = Signed::sign(full_unit, keychain).expect("the keychain never fails");
         signed_unit.into()
     }
 
@@ -377,7 +399,7 @@ mod tests {
         let session_id = 0;
         let max_round = 2;
         let keychain = Keychain::new(n_members, creator_id);
-        let validator = Validator::new(session_id, keychain, max_round);
+        let validator = Validator::new(session_id, keychain.clone(), max_round);
         let (collection, _) = Collection::new(&keychain, &validator);
         assert_eq!(collection.status(), Pending);
     }
@@ -390,7 +412,7 @@ mod tests {
         let max_round = 2;
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
-        let validator = Validator::new(session_id, *keychain, max_round);
+        let validator = Validator::new(session_id, keychain.clone(), max_round);
         let (mut collection, salt) = Collection::new(keychain, &validator);
         let responses = create_responses(
             keychains.iter().skip(1).take(3).zip(repeat(None)),
@@ -411,7 +433,7 @@ mod tests {
         let max_round = 2;
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
-        let validator = Validator::new(session_id, *keychain, max_round);
+        let validator = Validator::new(session_id, keychain.clone(), max_round);
         let (mut collection, salt) = Collection::new(keychain, &validator);
         let responses = create_responses(
             repeat(&keychains[1]).take(43).zip(repeat(None)),
@@ -432,7 +454,7 @@ mod tests {
         let max_round = 2;
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
-        let validator = Validator::new(session_id, *keychain, max_round);
+        let validator = Validator::new(session_id, keychain.clone(), max_round);
         let (mut collection, salt) = Collection::new(keychain, &validator);
         let responses = create_responses(
             keychains.iter().skip(1).take(4).zip(repeat(None)),
@@ -458,7 +480,7 @@ mod tests {
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
         let creator = Creator::new(creator_id, n_members);
-        let validator = Validator::new(session_id, *keychain, max_round);
+        let validator = Validator::new(session_id, keychain.clone(), max_round);
         let (mut collection, salt) = Collection::new(keychain, &validator);
         let preunit = creator.create_unit(0).expect("Creation should succeed.");
         let unit = preunit_to_unchecked_signed_unit(preunit, session_id, keychain);
@@ -494,7 +516,7 @@ mod tests {
         let max_round = 2;
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
-        let validator = Validator::new(session_id, *keychain, max_round);
+        let validator = Validator::new(session_id, keychain.clone(), max_round);
         let (mut collection, salt) = Collection::new(keychain, &validator);
         let other_salt = salt + 1;
         let responses = create_responses(
@@ -521,7 +543,7 @@ mod tests {
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
         let creator = Creator::new(creator_id, n_members);
-        let validator = Validator::new(session_id, *keychain, max_round);
+        let validator = Validator::new(session_id, keychain.clone(), max_round);
         let (mut collection, salt) = Collection::new(keychain, &validator);
         let preunit = creator.create_unit(0).expect("Creation should succeed.");
         let unit = preunit_to_unchecked_signed_unit(preunit, wrong_session_id, keychain);
@@ -549,7 +571,7 @@ mod tests {
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
         let creator = Creator::new(other_creator_id, n_members);
-        let validator = Validator::new(session_id, *keychain, max_round);
+        let validator = Validator::new(session_id, keychain.clone(), max_round);
         let (mut collection, salt) = Collection::new(keychain, &validator);
         let preunit = creator.create_unit(0).expect("Creation should succeed.");
         let unit = preunit_to_unchecked_signed_unit(preunit, session_id, &keychains[1]);
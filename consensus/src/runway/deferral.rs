@@ -0,0 +1,103 @@
+use crate::{
+    admission::AdmissionTrace,
+    units::{UncheckedSignedUnit, Unit},
+    Data, Hasher, Round, Signable, Signature,
+};
+use std::collections::{hash_map::Entry, BTreeMap, HashMap};
+
+type DeferredUnit<H, D, S> = (UncheckedSignedUnit<H, D, S>, Option<AdmissionTrace>);
+type RoundUnits<H, D, S> = HashMap<<H as Hasher>::Hash, DeferredUnit<H, D, S>>;
+
+/// Units of rounds far ahead of the ordering, put off while it falls behind, see
+/// [`crate::ExtenderFlowControl`]. They are kept unvalidated, by round, and every unit is kept
+/// at most once, however many times it arrives.
+pub struct DeferredUnits<H: Hasher, D: Data, S: Signature> {
+    by_round: BTreeMap<Round, RoundUnits<H, D, S>>,
+    len: usize,
+}
+
+impl<H: Hasher, D: Data, S: Signature> DeferredUnits<H, D, S> {
+    pub fn new() -> Self {
+        DeferredUnits {
+            by_round: BTreeMap::new(),
+            len: 0,
+        }
+    }
+
+    /// How many units are put off.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Puts off the unit, unless it is put off already.
+    pub fn defer(&mut self, unit: UncheckedSignedUnit<H, D, S>, trace: Option<AdmissionTrace>) {
+        let full_unit = unit.as_signable();
+        let round = full_unit.round();
+        let hash = Signable::hash(full_unit);
+        if let Entry::Vacant(entry) = self.by_round.entry(round).or_default().entry(hash) {
+            entry.insert((unit, trace));
+            self.len += 1;
+        }
+    }
+
+    /// The lowest round of the units put off, if there are any.
+    pub fn lowest_round(&self) -> Option<Round> {
+        self.by_round.keys().next().copied()
+    }
+
+    /// Takes all the units of the lowest round.
+    pub fn take_lowest_round(&mut self) -> Vec<DeferredUnit<H, D, S>> {
+        match self.by_round.pop_first() {
+            Some((_, units)) => {
+                self.len -= units.len();
+                units.into_values().collect()
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        runway::deferral::DeferredUnits,
+        units::{full_unit_to_unchecked_signed_unit, random_full_parent_units_up_to, Unit},
+        NodeCount, Round,
+    };
+    use aleph_bft_mock::{Data, Hasher64, Keychain, Signature};
+
+    #[test]
+    fn takes_units_lowest_rounds_first_without_duplicates() {
+        let n_members = NodeCount(4);
+        let keychains: Vec<_> = n_members
+            .into_iterator()
+            .map(|node_id| Keychain::new(n_members, node_id))
+            .collect();
+        let dag = random_full_parent_units_up_to(5, n_members, 43);
+        let mut deferred = DeferredUnits::<Hasher64, Data, Signature>::new();
+        assert!(deferred.is_empty());
+        assert_eq!(deferred.lowest_round(), None);
+        for round in [5, 3, 4, 3] {
+            for unit in &dag[round] {
+                let unit =
+                    full_unit_to_unchecked_signed_unit(unit.clone(), &keychains[unit.creator().0]);
+                deferred.defer(unit, None);
+            }
+        }
+        assert_eq!(deferred.len(), 3 * n_members.0);
+        for round in [3, 4, 5] {
+            assert_eq!(deferred.lowest_round(), Some(round as Round));
+            let units = deferred.take_lowest_round();
+            assert_eq!(units.len(), n_members.0);
+            assert!(units
+                .iter()
+                .all(|(unit, _)| unit.as_signable().round() == round as Round));
+        }
+        assert!(deferred.is_empty());
+        assert!(deferred.take_lowest_round().is_empty());
+    }
+}
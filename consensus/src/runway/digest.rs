@@ -0,0 +1,215 @@
+use crate::{units::UnitCoord, Hasher, NodeCount, NodeIndex, NodeMap, NodeSubset, Round};
+use codec::{Decode, Encode};
+use std::time::Duration;
+
+/// How often we send our digest to all the other nodes.
+pub(crate) const DIGEST_GOSSIP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Digests from a single peer arriving more often than this are ignored.
+pub(crate) const MIN_DIGEST_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How many rounds ahead of us a peer has to be for some creator for us to start catching up.
+pub(crate) const DIGEST_LAG_THRESHOLD: Round = 3;
+
+/// The units of a single creator at the frontier of the DAG.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Encode, Decode)]
+struct CreatorFrontier {
+    round: Round,
+    /// Combines the hashes of all the units of the creator of this round, forks included,
+    /// independently of the order in which they were added.
+    fingerprint: u64,
+}
+
+/// A small summary of the DAG frontier, i.e. the highest round we have a unit of for every
+/// creator, together with a fingerprint of the units of that round. Nodes that admitted the same
+/// units have equal digests, so the digests can be gossiped to detect lag and divergence early.
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+pub(crate) struct DagDigest {
+    frontier: NodeMap<CreatorFrontier>,
+}
+
+/// The result of comparing the digest of a peer with ours.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct DigestComparison {
+    /// The highest units of the creators for which the peer is far enough ahead of us.
+    pub ahead: Vec<UnitCoord>,
+    /// The creators for which we have the same highest round as the peer, but different units.
+    pub diverged: NodeSubset,
+}
+
+fn fingerprint<H: Hasher>(hash: &H::Hash) -> u64 {
+    hash.as_ref().chunks(8).fold(0, |fingerprint, chunk| {
+        let mut bytes = [0; 8];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        fingerprint ^ u64::from_le_bytes(bytes)
+    })
+}
+
+impl DagDigest {
+    pub fn new(n_members: NodeCount) -> Self {
+        DagDigest {
+            frontier: NodeMap::with_size(n_members),
+        }
+    }
+
+    /// The number of creators the digest is for.
+    pub fn size(&self) -> NodeCount {
+        self.frontier.size()
+    }
+
+    /// The highest round we have a unit of, for any creator.
+    pub fn top_round(&self) -> Round {
+        self.frontier
+            .values()
+            .map(|entry| entry.round)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Updates the digest with a newly admitted unit, should be called once per unit.
+    pub fn add_unit<H: Hasher>(&mut self, creator: NodeIndex, round: Round, hash: &H::Hash) {
+        let fingerprint = fingerprint::<H>(hash);
+        match self.frontier.get_mut(creator) {
+            Some(entry) if entry.round > round => {}
+            Some(entry) if entry.round == round => {
+                entry.fingerprint = entry.fingerprint.wrapping_add(fingerprint)
+            }
+            _ => self
+                .frontier
+                .insert(creator, CreatorFrontier { round, fingerprint }),
+        }
+    }
+
+    /// Compares the digest of a peer with ours. The digests have to be of the same size.
+    pub fn compare(&self, peers: &DagDigest) -> DigestComparison {
+        let mut ahead = Vec::new();
+        let mut diverged = NodeSubset::with_size(self.size());
+        for (creator, theirs) in peers.frontier.iter() {
+            match self.frontier.get(creator) {
+                Some(ours) if ours.round == theirs.round => {
+                    if ours.fingerprint != theirs.fingerprint {
+                        diverged.insert(creator);
+                    }
+                }
+                Some(ours) if ours.round > theirs.round => {}
+                ours => {
+                    let lag = match ours {
+                        Some(ours) => theirs.round - ours.round,
+                        None => theirs.round.saturating_add(1),
+                    };
+                    if lag >= DIGEST_LAG_THRESHOLD {
+                        ahead.push(UnitCoord::new(theirs.round, creator));
+                    }
+                }
+            }
+        }
+        DigestComparison { ahead, diverged }
+    }
+
+    /// Whether a node with this digest most likely holds the unit at the given coord. Units are
+    /// only admitted after their parents, so holding a unit of a creator means holding all the
+    /// earlier units of that creator, unless the creator forked.
+    pub fn holds(&self, coord: UnitCoord) -> bool {
+        self.frontier
+            .get(coord.creator())
+            .is_some_and(|entry| entry.round >= coord.round())
+    }
+
+    /// The lowest round some creator has no unit of, i.e. the round a node with this digest
+    /// has to catch up from.
+    pub fn lowest_missing_round(&self) -> Round {
+        match self.frontier.item_count() == self.size().0 {
+            true => self
+                .frontier
+                .values()
+                .map(|entry| entry.round.saturating_add(1))
+                .min()
+                .unwrap_or(0),
+            false => 0,
+        }
+    }
+
+    /// Breaks the fingerprint of the given creator, as if we had different units of theirs.
+    #[cfg(test)]
+    pub(crate) fn corrupt(&mut self, creator: NodeIndex) {
+        if let Some(entry) = self.frontier.get_mut(creator) {
+            entry.fingerprint = !entry.fingerprint;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        runway::digest::{DagDigest, DIGEST_LAG_THRESHOLD},
+        units::UnitCoord,
+        Hasher, NodeCount, NodeIndex,
+    };
+    use aleph_bft_mock::Hasher64;
+
+    fn digest(units: &[(usize, u16, &[u8])]) -> DagDigest {
+        let mut digest = DagDigest::new(NodeCount(4));
+        for (creator, round, seed) in units {
+            digest.add_unit::<Hasher64>(NodeIndex(*creator), *round, &Hasher64::hash(seed));
+        }
+        digest
+    }
+
+    #[test]
+    fn equal_units_give_equal_digests() {
+        let first = digest(&[(0, 0, b"a"), (0, 1, b"b"), (1, 0, b"c"), (1, 0, b"d")]);
+        let second = digest(&[(1, 0, b"d"), (1, 0, b"c"), (0, 1, b"b"), (0, 0, b"a")]);
+        assert_eq!(first, second);
+        let comparison = first.compare(&second);
+        assert!(comparison.ahead.is_empty());
+        assert!(comparison.diverged.is_empty());
+    }
+
+    #[test]
+    fn detects_divergence_at_equal_rounds() {
+        let ours = digest(&[(0, 2, b"a"), (1, 2, b"b"), (2, 1, b"c")]);
+        let theirs = digest(&[(0, 2, b"a"), (1, 2, b"x"), (2, 2, b"c")]);
+        let comparison = ours.compare(&theirs);
+        let diverged: Vec<_> = comparison.diverged.elements().collect();
+        assert_eq!(diverged, vec![NodeIndex(1)]);
+        assert!(comparison.ahead.is_empty());
+    }
+
+    #[test]
+    fn detects_large_lag_only() {
+        let far = DIGEST_LAG_THRESHOLD + 5;
+        let ours = digest(&[(0, 2, b"a"), (1, 2, b"b")]);
+        let theirs = digest(&[(0, 3, b"a"), (1, far, b"b"), (2, far, b"c")]);
+        let comparison = ours.compare(&theirs);
+        assert_eq!(
+            comparison.ahead,
+            vec![
+                UnitCoord::new(far, NodeIndex(1)),
+                UnitCoord::new(far, NodeIndex(2))
+            ]
+        );
+        assert!(ours.compare(&ours).ahead.is_empty());
+        assert!(theirs.compare(&ours).ahead.is_empty());
+    }
+
+    #[test]
+    fn holds_units_up_to_the_frontier() {
+        let digest = digest(&[(0, 2, b"a"), (1, 0, b"b")]);
+        assert!(digest.holds(UnitCoord::new(0, NodeIndex(0))));
+        assert!(digest.holds(UnitCoord::new(2, NodeIndex(0))));
+        assert!(!digest.holds(UnitCoord::new(3, NodeIndex(0))));
+        assert!(digest.holds(UnitCoord::new(0, NodeIndex(1))));
+        assert!(!digest.holds(UnitCoord::new(1, NodeIndex(1))));
+        assert!(!digest.holds(UnitCoord::new(0, NodeIndex(2))));
+    }
+
+    #[test]
+    fn lowest_missing_round_covers_all_creators() {
+        assert_eq!(
+            digest(&[(0, 2, b"a"), (1, 0, b"b")]).lowest_missing_round(),
+            0
+        );
+        let digest = digest(&[(0, 2, b"a"), (1, 4, b"b"), (2, 3, b"c"), (3, 5, b"d")]);
+        assert_eq!(digest.lowest_missing_round(), 3);
+    }
+}
@@ -1,58 +1,146 @@
 use crate::{
-    alerts::{Alert, ForkingNotification, NetworkMessage},
+    admission::{mark_stage, AdmissionMonitor, AdmissionStage, AdmissionTrace},
+    alerts::{Alert, ForkProof, ForkingNotification, NetworkMessage},
+    callbacks::CallbackGuard,
     creation,
-    dag::{Dag, DagResult, DagStatus, DagUnit, Request as ReconstructionRequest},
-    dissemination::{Request, Responder, Response},
-    extension::Ordering,
+    dag::{
+        Dag, DagResult, DagUnit, EvictedUnit, EvictionCause, Request as ReconstructionRequest,
+        ValidatorStatus,
+    },
+    delivery::DeliveryControl,
+    dissemination::{
+        CatchUp, CatchUpStep, CompactResolver, CompactUnit, NewestUnitsResponse, NotFoundLimiter,
+        Refusal, Request, RequestId, Resolution, Responder, ResponderError, Response,
+        ResponseLimiter, ResponseStats,
+    },
+    drops::{DropMonitor, DropReason},
+    events::{EventBus, InternalEvent, Misbehavior, SigningTarget},
+    extension::{last_head_round, Ordering},
+    finalization_state::FinalizationState,
     handle_task_termination,
+    ingress::{IngressReceiver, Priority},
+    lateness::{LatenessMonitor, LatenessTracker},
+    lease::{CreationPermit, LeaseControl},
     member::UnitMessage,
+    migration::{BackupPosition, ImportError, MigrationControl, SessionStateExport},
+    misconduct::MisconductMonitor,
+    receipts::{QuorumReceiptMonitor, QuorumReceiptTracker},
+    reception::ReceptionTracker,
+    session_end::{SessionEnd, SessionEndMonitor},
+    standby::BackupReplication,
+    status::{DagStatus, StatusQuery, StatusRequest},
+    unit_metadata::{UnitMetadata, UnitMetadataMonitor},
+    unit_sizes::{UnitSizeMonitor, UNIT_SIZE_SUMMARY_INTERVAL},
     units::{
-        SignedUnit, UncheckedSignedUnit, Unit, UnitCoord, UnitStore, UnitStoreStatus, Validator,
-        WrappedUnit,
+        SignedUnit, UncheckedSignedUnit, Unit, UnitCoord, UnitStore, UnitStoreStatus,
+        UnitWithParents, Validator, WrappedUnit,
     },
-    Config, Data, DataProvider, Hasher, Index, Keychain, MultiKeychain, NodeIndex, Receiver,
-    Recipient, Round, Sender, Signature, SpawnHandle, Terminator, UncheckedSigned,
-    UnitFinalizationHandler,
+    BroadcastGate, ClockSource, Config, Data, DataAvailabilityChecker, DataPolicy, DataProvider,
+    ExtenderFlowControl, Hasher, Index, Keychain, MultiKeychain, NodeIndex, Receiver, Recipient,
+    ReconstructionLimits, ResponseLimits, Role, Round, Sender, SessionId, Signature, SpawnHandle,
+    Terminator, UncheckedSigned, UnitFinalizationHandler, UnitMetadataProvider,
+    EXTENDED_MESSAGES_VERSION,
 };
+use codec::{Decode, Encode};
 use futures::{
     channel::{mpsc, oneshot},
-    future::pending,
+    future::{pending, Fuse, FusedFuture},
     pin_mut, AsyncRead, AsyncWrite, Future, FutureExt, StreamExt,
 };
-use futures_timer::Delay;
 use itertools::Itertools;
 use log::{debug, error, info, trace, warn};
 use std::{
     cmp::max,
-    collections::HashSet,
+    collections::{HashMap, HashSet, VecDeque},
     convert::TryFrom,
     fmt::{Display, Formatter, Result as FmtResult},
     marker::PhantomData,
-    time::Duration,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
 };
 
+mod availability;
 mod collection;
+mod deferral;
+mod digest;
+mod verification;
 
-use crate::backup::{BackupLoader, BackupSaver};
+use crate::backup::{BackupFingerprint, BackupLoader, BackupSaver};
+use availability::{Availability, AvailableUnits};
 #[cfg(feature = "initial_unit_collection")]
 use collection::{Collection, IO as CollectionIO};
 pub use collection::{NewestUnitResponse, Salt};
+use deferral::DeferredUnits;
+pub(crate) use digest::DagDigest;
+use digest::{DIGEST_GOSSIP_INTERVAL, MIN_DIGEST_INTERVAL};
+use verification::{Verification, Verified, VerifiedUnits, VERIFIERS};
+
+/// How often we look for units that stayed suspect for long enough to be evicted.
+const EVICTION_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often we check whether the ordering got stuck waiting for units we put off.
+const DEFERRAL_CHECK_INTERVAL: Duration = Duration::from_secs(1);
 
+/// How often we warn about dropping units too far ahead at most, the units dropped in between
+/// are only counted.
+const FAR_UNITS_WARNING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Everything the runway passes to the member. Whatever the member needs for the protocol to
+/// work goes here rather than over the lossy [`EventBus`], which only gets copies for observers.
 pub(crate) enum RunwayNotificationOut<H: Hasher, D: Data, S: Signature> {
     /// A new unit was generated by this runway
     NewSelfUnit(UncheckedSignedUnit<H, D, S>),
-    /// A new unit was generated by this runway or imported from outside and added to the DAG
+    /// A unit was added to the DAG after being saved in the backup.
     NewAnyUnit(UncheckedSignedUnit<H, D, S>),
     Request(Request<H>),
+    /// The given request no longer needs to be sent, as we got what it asked for.
+    RequestResolved(Request<H>),
+    /// The given request got cancelled, as nothing needs it anymore.
+    RequestObsolete(Request<H>),
+    /// The session got frozen for a migration, so it only answers requests from now on.
+    SessionFrozen,
     Response(Response<H, D, S>, NodeIndex),
+    /// The parents received for the unit with the given hash did not match its control hash.
+    InconsistentParents(H::Hash),
+    /// Our current DAG digest, to be gossiped to the other nodes.
+    Digest(DagDigest),
+    /// We definitively don't have the units the given node requested.
+    NotFound(RequestId<H>, NodeIndex),
+    /// We pruned the units the given node requested.
+    Pruned(RequestId<H>, NodeIndex),
+    /// Units the given node referenced in a compact response, but we don't hold.
+    UnitsRequest(Vec<H::Hash>, NodeIndex),
+    /// A request to the given node for the units we lack according to our digest.
+    NewestUnitsRequest(Salt, DagDigest, NodeIndex),
+    /// A part of our response to a request for the newest units of the given node.
+    NewestUnitsResponse(SignedNewestUnits<H, D, S>, NodeIndex),
 }
 
 pub(crate) enum RunwayNotificationIn<H: Hasher, D: Data, S: Signature> {
     NewUnit(UncheckedSignedUnit<H, D, S>),
     Request(Request<H>, NodeIndex),
     Response(Response<H, D, S>),
+    Digest(DagDigest, NodeIndex),
+    /// The given node definitively doesn't have what we requested from it.
+    NotFound(RequestId<H>, NodeIndex),
+    /// The given node pruned what we requested from it.
+    Pruned(RequestId<H>, NodeIndex),
+    NewestUnitsRequest(Salt, DagDigest, NodeIndex),
+    NewestUnitsResponse(SignedNewestUnits<H, D, S>),
 }
 
+/// A part of a response to a request for the newest units, signed by the responder.
+pub(crate) type SignedNewestUnits<H, D, S> = UncheckedSigned<NewestUnitsResponse<H, D, S>, S>;
+
+/// A notification from the network together with the encoded sizes of the units it contains
+/// and, if it is traced, the trace of its admission.
+pub(crate) type SizedNotificationIn<H, D, S> = (
+    RunwayNotificationIn<H, D, S>,
+    Vec<usize>,
+    Option<AdmissionTrace>,
+);
+
 impl<H: Hasher, D: Data, S: Signature> TryFrom<UnitMessage<H, D, S>>
     for RunwayNotificationIn<H, D, S>
 {
@@ -77,11 +165,75 @@ impl<H: Hasher, D: Data, S: Signature> TryFrom<UnitMessage<H, D, S>>
             UnitMessage::ResponseNewest(response) => {
                 RunwayNotificationIn::Response(Response::NewestUnit(response))
             }
+            UnitMessage::DagDigest(node_id, digest) => {
+                RunwayNotificationIn::Digest(digest, node_id)
+            }
+            UnitMessage::RequestUnits(node_id, hashes) => {
+                RunwayNotificationIn::Request(Request::Units(hashes), node_id)
+            }
+            UnitMessage::ResponseUnits(units) => {
+                RunwayNotificationIn::Response(Response::Units(units))
+            }
+            UnitMessage::ResponseParentsCompact(node_id, u_hash, parents) => {
+                RunwayNotificationIn::Response(Response::CompactParents(node_id, u_hash, parents))
+            }
+            UnitMessage::RequestNewestUnits(node_id, salt, digest) => {
+                RunwayNotificationIn::NewestUnitsRequest(salt, digest, node_id)
+            }
+            UnitMessage::ResponseNewestUnits(response) => {
+                RunwayNotificationIn::NewestUnitsResponse(response)
+            }
+            // Negative responses go to the member first, which schedules the requests and only
+            // passes on the ones answering requests it sent.
+            UnitMessage::NotFound(_, _) | UnitMessage::Pruned(_, _) => return Err(()),
         };
         Ok(result)
     }
 }
 
+impl<H: Hasher, D: Data, S: Signature> RunwayNotificationIn<H, D, S> {
+    /// The encoding of the contents of the notification, for logging dropped messages.
+    pub(crate) fn encode_sample(&self) -> Vec<u8> {
+        match self {
+            RunwayNotificationIn::NewUnit(u) => u.encode(),
+            RunwayNotificationIn::Request(_, node_id) => node_id.encode(),
+            RunwayNotificationIn::Response(response) => match response {
+                Response::Coord(u) => u.encode(),
+                Response::Parents(u_hash, parents) => (u_hash, parents).encode(),
+                Response::NewestUnit(response) => response.encode(),
+                Response::CompactParents(node_id, u_hash, parents) => {
+                    (node_id, u_hash, parents).encode()
+                }
+                Response::Units(units) => units.encode(),
+            },
+            RunwayNotificationIn::Digest(digest, node_id) => (digest, node_id).encode(),
+            RunwayNotificationIn::NotFound(request, node_id)
+            | RunwayNotificationIn::Pruned(request, node_id) => (request, node_id).encode(),
+            RunwayNotificationIn::NewestUnitsRequest(salt, digest, node_id) => {
+                (salt, digest, node_id).encode()
+            }
+            RunwayNotificationIn::NewestUnitsResponse(response) => response.encode(),
+        }
+    }
+
+    /// The priority of the notification in the queue to the runway, see
+    /// [`UnitMessage::priority`].
+    pub(crate) fn priority(&self) -> Priority {
+        match self {
+            RunwayNotificationIn::Request(Request::NewestUnit(..), _)
+            | RunwayNotificationIn::Response(Response::NewestUnit(_))
+            | RunwayNotificationIn::NewUnit(_)
+            | RunwayNotificationIn::Digest(_, _)
+            | RunwayNotificationIn::NotFound(_, _)
+            | RunwayNotificationIn::Pruned(_, _) => Priority::Normal,
+            RunwayNotificationIn::Request(_, _)
+            | RunwayNotificationIn::Response(_)
+            | RunwayNotificationIn::NewestUnitsRequest(_, _, _)
+            | RunwayNotificationIn::NewestUnitsResponse(_) => Priority::Low,
+        }
+    }
+}
+
 type CollectionResponse<H, D, MK> = UncheckedSigned<
     NewestUnitResponse<H, D, <MK as Keychain>::Signature>,
     <MK as Keychain>::Signature,
@@ -93,29 +245,111 @@ where
     MK: MultiKeychain,
 {
     own_id: NodeIndex,
+    role: Role,
     missing_coords: HashSet<UnitCoord>,
     missing_parents: HashSet<<FH::Hasher as Hasher>::Hash>,
     store: UnitStore<DagUnit<FH::Hasher, FH::Data, MK>>,
     dag: Dag<FH::Hasher, FH::Data, MK>,
     ordering: Ordering<MK, FH>,
     responder: Responder<FH::Hasher, FH::Data, MK>,
+    keychain: MK,
+    catch_up: CatchUp,
     alerts_for_alerter: Sender<Alert<FH::Hasher, FH::Data, MK::Signature>>,
     notifications_from_alerter: Receiver<ForkingNotification<FH::Hasher, FH::Data, MK::Signature>>,
-    unit_messages_from_network: Receiver<RunwayNotificationIn<FH::Hasher, FH::Data, MK::Signature>>,
+    held_notifications: VecDeque<HeldNotification<FH, MK>>,
+    verification: Verification<FH::Hasher, FH::Data, MK>,
+    verified_units: VerifiedUnits<FH::Hasher, FH::Data, MK>,
+    availability: Availability<FH::Hasher, FH::Data, MK>,
+    available_units: AvailableUnits<FH::Hasher>,
+    unit_messages_from_network:
+        IngressReceiver<SizedNotificationIn<FH::Hasher, FH::Data, MK::Signature>>,
     unit_messages_for_network: Sender<RunwayNotificationOut<FH::Hasher, FH::Data, MK::Signature>>,
     responses_for_collection: Sender<CollectionResponse<FH::Hasher, FH::Data, MK>>,
-    resolved_requests: Sender<Request<FH::Hasher>>,
+    events: EventBus<FH::Hasher, FH::Data, MK::Signature>,
     parents_for_creator: Sender<DagUnit<FH::Hasher, FH::Data, MK>>,
     backup_units_for_saver: Sender<DagUnit<FH::Hasher, FH::Data, MK>>,
     backup_units_from_saver: Receiver<DagUnit<FH::Hasher, FH::Data, MK>>,
     new_units_from_creation: Receiver<SignedUnit<FH::Hasher, FH::Data, MK>>,
+    delivery_resumptions: Receiver<()>,
+    unit_size_monitor: UnitSizeMonitor,
+    admission_monitor: AdmissionMonitor,
+    drops: DropMonitor,
+    lateness: LatenessTracker,
+    reception: ReceptionTracker,
+    receipts: QuorumReceiptTracker<FH::Hasher, FH::Data>,
+    unit_metadata_monitor: UnitMetadataMonitor<FH::Hasher>,
+    digest: DagDigest,
+    digests_received_at: HashMap<NodeIndex, Instant>,
+    peer_digests: HashMap<NodeIndex, DagDigest>,
+    compact_unit_refs: bool,
+    compact_parents: CompactResolver<FH::Hasher, FH::Data, MK::Signature>,
+    not_found_limiter: NotFoundLimiter,
+    response_limiter: ResponseLimiter<FH::Hasher, FH::Data, MK::Signature>,
+    pruned_round: Option<Round>,
+    kept_rounds: Option<Round>,
+    session_id: SessionId,
+    backup_position: BackupPosition,
+    units_being_saved: usize,
+    flow_control: Option<ExtenderFlowControl>,
+    deferred_units: DeferredUnits<FH::Hasher, FH::Data, MK::Signature>,
+    deferral_checked_round: Round,
+    max_round_lead: Round,
+    far_units_warned_at: Option<Instant>,
+    far_units_dropped: usize,
+    next_round: Round,
+    last_own_unit: Option<<FH::Hasher as Hasher>::Hash>,
+    fork_proofs: HashMap<NodeIndex, RunwayForkProof<FH, MK>>,
+    freeze_requests: Receiver<FreezeRequest<FH::Hasher>>,
+    pending_freezes: Vec<FreezeRequest<FH::Hasher>>,
+    frozen: bool,
+    exit_flushes: Receiver<ExitFlush>,
+    pending_exit_flushes: Vec<ExitFlush>,
+    status_requests: Receiver<StatusRequest>,
+    state_import: Option<SessionStateExport<FH::Hasher>>,
+    last_head_round: Round,
+    session_end: SessionEndMonitor,
+    session_end_grace_period: Duration,
+    session_ended: bool,
+    finished: Option<oneshot::Sender<()>>,
+    clock: ClockSource,
     exiting: bool,
 }
 
+type FreezeRequest<H> = oneshot::Sender<SessionStateExport<H>>;
+
+/// Asks the runway to save all our units before the session exits, answered once they are saved.
+pub(crate) type ExitFlush = oneshot::Sender<()>;
+
+/// A forking notification waiting for the units that arrived before it to be verified.
+type HeldNotification<UFH, MK> = (
+    u64,
+    ForkingNotification<
+        <UFH as UnitFinalizationHandler>::Hasher,
+        <UFH as UnitFinalizationHandler>::Data,
+        <MK as Keychain>::Signature,
+    >,
+);
+
+type RunwayForkProof<UFH, MK> = ForkProof<
+    <UFH as UnitFinalizationHandler>::Hasher,
+    <UFH as UnitFinalizationHandler>::Data,
+    <MK as Keychain>::Signature,
+>;
+
+/// The fork proofs to continue with, if the session can continue from the export.
+type ImportResult<UFH, MK> = Result<
+    Vec<RunwayForkProof<UFH, MK>>,
+    ImportError<<<UFH as UnitFinalizationHandler>::Hasher as Hasher>::Hash>,
+>;
+
 struct RunwayStatus<'a, H: Hasher> {
     missing_coords: &'a HashSet<UnitCoord>,
     missing_parents: &'a HashSet<H::Hash>,
-    dag_status: DagStatus,
+    pending_units: usize,
+    deferred_units: usize,
+    verified_units: usize,
+    unavailable_units: usize,
+    dag_status: ValidatorStatus,
     store_status: UnitStoreStatus,
 }
 
@@ -187,6 +421,26 @@ impl<'a, H: Hasher> Display for RunwayStatus<'a, H> {
         if !self.missing_parents.is_empty() {
             write!(f, "; missing parents - {:?}", self.missing_parents)?;
         }
+        if self.pending_units > 0 {
+            write!(f, "; units waiting for parents - {}", self.pending_units)?;
+        }
+        if self.deferred_units > 0 {
+            write!(
+                f,
+                "; units put off for the ordering - {}",
+                self.deferred_units
+            )?;
+        }
+        if self.verified_units > 0 {
+            write!(f, "; units being verified - {}", self.verified_units)?;
+        }
+        if self.unavailable_units > 0 {
+            write!(
+                f,
+                "; units waiting for their data - {}",
+                self.unavailable_units
+            )?;
+        }
         write!(f, ";reconstructed DAG: {}", self.store_status)?;
         write!(f, ";additional information: {}", self.dag_status)?;
         write!(f, ".")?;
@@ -196,18 +450,52 @@ impl<'a, H: Hasher> Display for RunwayStatus<'a, H> {
 
 struct RunwayConfig<UFH: UnitFinalizationHandler, MK: MultiKeychain> {
     finalization_handler: UFH,
+    delivery_control: DeliveryControl,
+    finalization_state: Option<FinalizationState<UFH::Hasher>>,
+    data_policy: DataPolicy,
+    session_id: SessionId,
+    role: Role,
+    clock: ClockSource,
+    compact_unit_refs: bool,
+    reconstruction_limits: ReconstructionLimits,
+    response_limits: ResponseLimits,
+    extender_flow_control: Option<ExtenderFlowControl>,
+    max_round_lead: Round,
+    max_units_per_message: usize,
+    kept_rounds: Option<Round>,
+    unit_size_monitor: UnitSizeMonitor,
+    admission_monitor: AdmissionMonitor,
+    drop_monitor: DropMonitor,
+    lateness_monitor: LatenessMonitor,
+    quorum_receipt_monitor: QuorumReceiptMonitor<UFH::Data>,
+    unit_metadata_monitor: UnitMetadataMonitor<UFH::Hasher>,
+    migration_control: MigrationControl<UFH::Hasher>,
+    state_import: Option<SessionStateExport<UFH::Hasher>>,
+    status_query: StatusQuery,
+    exit_flushes: Receiver<ExitFlush>,
+    max_round: Round,
+    session_end_monitor: SessionEndMonitor,
+    session_end_grace_period: Duration,
+    finished: oneshot::Sender<()>,
     backup_units_for_saver: Sender<DagUnit<UFH::Hasher, UFH::Data, MK>>,
     backup_units_from_saver: Receiver<DagUnit<UFH::Hasher, UFH::Data, MK>>,
     alerts_for_alerter: Sender<Alert<UFH::Hasher, UFH::Data, MK::Signature>>,
     notifications_from_alerter:
         Receiver<ForkingNotification<UFH::Hasher, UFH::Data, MK::Signature>>,
+    verification: Verification<UFH::Hasher, UFH::Data, MK>,
+    verified_units: VerifiedUnits<UFH::Hasher, UFH::Data, MK>,
+    availability: Availability<UFH::Hasher, UFH::Data, MK>,
+    available_units: AvailableUnits<UFH::Hasher>,
     unit_messages_from_network:
-        Receiver<RunwayNotificationIn<UFH::Hasher, UFH::Data, MK::Signature>>,
+        IngressReceiver<SizedNotificationIn<UFH::Hasher, UFH::Data, MK::Signature>>,
     unit_messages_for_network: Sender<RunwayNotificationOut<UFH::Hasher, UFH::Data, MK::Signature>>,
     responses_for_collection: Sender<CollectionResponse<UFH::Hasher, UFH::Data, MK>>,
     parents_for_creator: Sender<DagUnit<UFH::Hasher, UFH::Data, MK>>,
-    resolved_requests: Sender<Request<UFH::Hasher>>,
+    finalized_rounds_for_creator: Option<Sender<Round>>,
+    finalized_heads_for_creator: Option<Sender<Round>>,
+    events: EventBus<UFH::Hasher, UFH::Data, MK::Signature>,
     new_units_from_creation: Receiver<SignedUnit<UFH::Hasher, UFH::Data, MK>>,
+    callbacks: CallbackGuard,
 }
 
 type BackupUnits<UFH, MK> = Vec<
@@ -228,32 +516,96 @@ where
         let own_id = keychain.index();
         let RunwayConfig {
             finalization_handler,
+            delivery_control,
+            finalization_state,
+            data_policy,
+            session_id,
+            role,
+            clock,
+            compact_unit_refs,
+            reconstruction_limits,
+            response_limits,
+            extender_flow_control,
+            max_round_lead,
+            max_units_per_message,
+            kept_rounds,
+            unit_size_monitor,
+            admission_monitor,
+            drop_monitor,
+            lateness_monitor,
+            quorum_receipt_monitor,
+            unit_metadata_monitor,
+            migration_control,
+            state_import,
+            status_query,
+            exit_flushes,
+            max_round,
+            session_end_monitor,
+            session_end_grace_period,
+            finished,
             backup_units_for_saver,
             backup_units_from_saver,
             alerts_for_alerter,
             notifications_from_alerter,
+            verification,
+            verified_units,
+            availability,
+            available_units,
             unit_messages_from_network,
             unit_messages_for_network,
             responses_for_collection,
             parents_for_creator,
-            resolved_requests,
+            finalized_rounds_for_creator,
+            finalized_heads_for_creator,
+            events,
             new_units_from_creation,
+            callbacks,
         } = config;
         let store = UnitStore::new(n_members);
-        let dag = Dag::new(validator);
-        let ordering = Ordering::new(finalization_handler);
+        let node_weights = validator.node_weights().clone();
+        let dag = Dag::new(validator)
+            .with_reconstruction_limits(reconstruction_limits)
+            .with_clock(clock.clone())
+            .with_drop_monitor(drop_monitor.clone());
+        let (delivery, delivery_resumptions) = delivery_control.split();
+        let ordering = Ordering::new(
+            finalization_handler,
+            delivery,
+            finalization_state,
+            data_policy,
+            events.clone(),
+            callbacks,
+        )
+        .with_node_weights(node_weights.clone());
+        let ordering = match finalized_rounds_for_creator {
+            Some(rounds) => ordering.with_finalization_feedback(own_id, rounds),
+            None => ordering,
+        };
+        let ordering = match finalized_heads_for_creator {
+            Some(rounds) => ordering.with_head_feedback(rounds),
+            None => ordering,
+        };
 
         Runway {
             own_id,
+            role,
             store,
             dag,
             ordering,
             missing_coords: HashSet::new(),
             missing_parents: HashSet::new(),
-            responder: Responder::new(keychain),
-            resolved_requests,
+            responder: Responder::new(keychain.clone())
+                .with_max_units_per_message(max_units_per_message),
+            keychain,
+            catch_up: CatchUp::new(),
+            events,
             alerts_for_alerter,
             notifications_from_alerter,
+            held_notifications: VecDeque::new(),
+            verification,
+            verified_units,
+            availability,
+            available_units,
             unit_messages_from_network,
             unit_messages_for_network,
             parents_for_creator,
@@ -261,6 +613,48 @@ where
             backup_units_from_saver,
             responses_for_collection,
             new_units_from_creation,
+            delivery_resumptions,
+            unit_size_monitor,
+            admission_monitor,
+            drops: drop_monitor,
+            lateness: LatenessTracker::new(own_id, n_members, lateness_monitor),
+            reception: ReceptionTracker::new(own_id, n_members),
+            receipts: QuorumReceiptTracker::new(own_id, node_weights, quorum_receipt_monitor),
+            unit_metadata_monitor,
+            digest: DagDigest::new(n_members),
+            digests_received_at: HashMap::new(),
+            peer_digests: HashMap::new(),
+            compact_unit_refs,
+            compact_parents: CompactResolver::new(),
+            not_found_limiter: NotFoundLimiter::new(),
+            response_limiter: ResponseLimiter::new(n_members, response_limits),
+            pruned_round: None,
+            kept_rounds,
+            session_id,
+            backup_position: BackupPosition::default(),
+            units_being_saved: 0,
+            flow_control: extender_flow_control,
+            deferred_units: DeferredUnits::new(),
+            deferral_checked_round: 0,
+            max_round_lead,
+            far_units_warned_at: None,
+            far_units_dropped: 0,
+            next_round: 0,
+            last_own_unit: None,
+            fork_proofs: HashMap::new(),
+            freeze_requests: migration_control.split(),
+            pending_freezes: Vec::new(),
+            frozen: false,
+            exit_flushes,
+            pending_exit_flushes: Vec::new(),
+            status_requests: status_query.split(),
+            state_import,
+            last_head_round: last_head_round(max_round),
+            session_end: session_end_monitor,
+            session_end_grace_period,
+            session_ended: false,
+            finished: Some(finished),
+            clock,
             exiting: false,
         }
     }
@@ -274,6 +668,9 @@ where
             units,
             requests,
             alerts,
+            inconsistent_parents,
+            evicted,
+            invalid_units,
         } = result;
         for unit in units {
             self.on_unit_reconstructed(unit);
@@ -281,12 +678,49 @@ where
         for request in requests {
             self.on_reconstruction_request(request);
         }
+        for u_hash in inconsistent_parents {
+            self.send_message_for_network(RunwayNotificationOut::InconsistentParents(u_hash));
+        }
         for alert in alerts {
+            for (forker, proof) in alert.forkers().into_iter().zip(alert.proofs()) {
+                self.fork_proofs
+                    .entry(forker)
+                    .or_insert_with(|| proof.clone());
+            }
             if self.alerts_for_alerter.unbounded_send(alert).is_err() {
                 warn!(target: "AlephBFT-runway", "{:?} Channel to alerter should be open", self.index());
                 self.exiting = true;
             }
         }
+        for evicted in evicted {
+            self.on_unit_evicted(evicted);
+        }
+        for (creator, u_hash, reason) in invalid_units {
+            self.events.publish(InternalEvent::PeerMisbehaved(
+                creator,
+                Misbehavior::InvalidUnit(u_hash, reason),
+            ));
+        }
+    }
+
+    fn on_unit_evicted(&mut self, evicted: EvictedUnit<UFH::Hasher>) {
+        debug!(target: "AlephBFT-runway", "{:?} Evicted unit {:?} of {:?} waiting for parents: {:?}, {} more of its creator waiting.", self.index(), evicted.hash, evicted.coord, evicted.cause, evicted.pending);
+        if evicted.cause == EvictionCause::Unsatisfiable {
+            self.events.publish(InternalEvent::PeerMisbehaved(
+                evicted.coord.creator(),
+                Misbehavior::UnsatisfiableUnit(evicted.hash),
+            ));
+        }
+        self.compact_parents.forget(&evicted.hash);
+        if self.missing_parents.remove(&evicted.hash) {
+            self.cancel_request(Request::Parents(evicted.hash));
+        }
+        for coord in &evicted.awaited_coords {
+            if !self.dag.is_blocked_on(*coord) && self.missing_coords.remove(coord) {
+                self.cancel_request(Request::Coord(*coord));
+            }
+        }
+        self.events.publish(InternalEvent::UnitEvicted(evicted));
     }
 
     fn on_unit_received(
@@ -297,31 +731,178 @@ where
         self.handle_dag_result(result);
     }
 
+    /// Units from the network might get verified by separate tasks, see [`Verification`].
+    fn on_unit_received_traced(
+        &mut self,
+        unit: UncheckedSignedUnit<UFH::Hasher, UFH::Data, MK::Signature>,
+        mut trace: Option<AdmissionTrace>,
+    ) {
+        if !self.verification.is_offloaded() {
+            let result = self.dag.add_unit_traced(unit, &self.store, &mut trace);
+            self.admission_monitor.record(trace);
+            self.handle_dag_result(result);
+            return;
+        }
+        match self.dag.admit_unit(unit) {
+            Some(unit) => self.verification.submit(unit, trace),
+            None => self.admission_monitor.record(trace),
+        }
+    }
+
+    fn on_units_verified(&mut self, units: Vec<Verified<UFH::Hasher, UFH::Data, MK>>) {
+        for (unit, mut trace) in units {
+            self.verification.on_verified(unit.creator());
+            if self.frozen {
+                trace!(target: "AlephBFT-runway", "{:?} Ignoring a verified unit, as we are frozen.", self.index());
+                self.drops.record_drop(DropReason::Frozen, None, Vec::new);
+                continue;
+            }
+            let result = self
+                .dag
+                .add_verified_unit_traced(unit, &self.store, &mut trace);
+            self.admission_monitor.record(trace);
+            self.handle_dag_result(result);
+        }
+        self.release_held_notifications();
+    }
+
+    fn on_unit_created(&mut self, unit: SignedUnit<UFH::Hasher, UFH::Data, MK>) {
+        self.reception
+            .on_own_unit_created(unit.round(), self.clock.now());
+        if let Some(late_units) = self
+            .lateness
+            .on_own_unit_created(unit.round(), self.clock.now())
+        {
+            self.events.publish(InternalEvent::LateUnits(late_units));
+        }
+        let unit: UncheckedSignedUnit<_, _, _> = unit.into();
+        self.unit_size_monitor.record_created(unit.encoded_size());
+        self.on_unit_received(unit)
+    }
+
     fn on_unit_message(
         &mut self,
         message: RunwayNotificationIn<UFH::Hasher, UFH::Data, MK::Signature>,
+        trace: Option<AdmissionTrace>,
     ) {
+        if self.frozen
+            && !matches!(
+                message,
+                RunwayNotificationIn::Request(..) | RunwayNotificationIn::NewestUnitsRequest(..)
+            )
+        {
+            trace!(target: "AlephBFT-runway", "{:?} Ignoring a unit message, as we are frozen.", self.index());
+            self.drops
+                .record_drop(DropReason::Frozen, None, || message.encode_sample());
+            return;
+        }
         match message {
+            RunwayNotificationIn::NewUnit(u) if self.is_beyond_lead(&u) => {
+                trace!(target: "AlephBFT-runway", "{:?} Dropping a new unit {:?}, it is too far ahead.", self.index(), &u);
+                self.on_unit_too_far_ahead(u)
+            }
+            RunwayNotificationIn::NewUnit(u) if self.availability.is_full() => {
+                trace!(target: "AlephBFT-runway", "{:?} Dropping a new unit {:?}, too many units wait for their data.", self.index(), &u);
+                self.drops
+                    .record_drop(DropReason::TooManyUnavailable, None, || u.encode());
+            }
+            RunwayNotificationIn::NewUnit(u) if self.should_defer(&u) => {
+                trace!(target: "AlephBFT-runway", "{:?} Putting off a new unit {:?}, the ordering is behind.", self.index(), &u);
+                self.deferred_units.defer(u, trace)
+            }
             RunwayNotificationIn::NewUnit(u) => {
                 trace!(target: "AlephBFT-runway", "{:?} New unit received {:?}.", self.index(), &u);
-                self.on_unit_received(u)
+                self.on_unit_received_traced(u, trace)
+            }
+
+            // Answering would mean signing the response.
+            RunwayNotificationIn::Request(Request::NewestUnit(_, salt), node_id)
+                if self.role == Role::Observer =>
+            {
+                trace!(target: "AlephBFT-runway", "{:?} Not answering the newest unit request of {:?}, as we are an observer.", self.index(), node_id);
+                self.drops
+                    .record_drop(DropReason::Observing, Some(node_id), || salt.encode());
             }
 
             RunwayNotificationIn::Request(request, node_id) => {
-                match self.responder.handle_request(request, &self.store) {
-                    Ok(response) => self.send_message_for_network(RunwayNotificationOut::Response(
-                        response, node_id,
-                    )),
-                    Err(err) => {
-                        trace!(target: "AlephBFT-runway", "Not answering request from node {:?}: {}.", node_id, err)
+                let request_id = RequestId::of(&request);
+                let mut response = None;
+                if let Some(request_id) = &request_id {
+                    if node_id.0 >= self.digest.size().0 || node_id == self.index() {
+                        debug!(target: "AlephBFT-runway", "{:?} Ignoring request {:?} claiming to come from {:?}.", self.index(), request_id, node_id);
+                        self.drops.record_drop(
+                            DropReason::UnexpectedMessage,
+                            Some(node_id),
+                            || request_id.encode(),
+                        );
+                        return;
+                    }
+                    if let Err(refusal) =
+                        self.response_limiter
+                            .check(node_id, request_id, self.clock.now())
+                    {
+                        self.on_request_refused(request_id, node_id, refusal);
+                        return;
+                    }
+                    response = self.response_limiter.cached(request_id);
+                }
+                let response = match response {
+                    Some(response) => Ok(response),
+                    None => self.responder.handle_request(request, &self.store),
+                };
+                match response {
+                    Ok(response) => {
+                        if let Some(request_id) = request_id {
+                            self.response_limiter.on_answered(
+                                node_id,
+                                request_id,
+                                &response,
+                                self.clock.now(),
+                            );
+                        }
+                        let response = self.compact_response(response, node_id);
+                        self.send_message_for_network(RunwayNotificationOut::Response(
+                            response, node_id,
+                        ))
+                    }
+                    Err(ResponderError::SigningFailed(requester, err)) => {
+                        warn!(target: "AlephBFT-runway", "{:?} Failed to sign the newest unit response for {:?}: {}.", self.index(), requester, err);
+                        self.events.publish(InternalEvent::SigningFailed(
+                            SigningTarget::NewestUnitResponse(requester),
+                        ));
+                    }
+                    Err(ResponderError::NoCanonicalAt(coord)) => {
+                        if !self.dag.is_processing_coord(coord) {
+                            self.on_not_found(RequestId::Coord(coord), node_id)
+                        }
+                    }
+                    Err(ResponderError::UnknownUnit(u_hash)) => {
+                        if !self.dag.is_processing(&u_hash) {
+                            self.on_not_found(RequestId::Parents(u_hash), node_id)
+                        }
+                    }
+                    Err(ResponderError::PrunedCoord(coord)) => {
+                        trace!(target: "AlephBFT-runway", "{:?} The unit at {} requested by {:?} was pruned.", self.index(), coord, node_id);
+                        self.on_pruned(RequestId::Coord(coord), node_id)
+                    }
+                    Err(ResponderError::PrunedParents(u_hash)) => {
+                        trace!(target: "AlephBFT-runway", "{:?} The parents of {:?} requested by {:?} were pruned.", self.index(), u_hash, node_id);
+                        self.on_pruned(RequestId::Parents(u_hash), node_id)
+                    }
+                    Err(ResponderError::NoRequestedUnits) => {
+                        trace!(target: "AlephBFT-runway", "{:?} We hold none of the units requested by {:?}.", self.index(), node_id)
                     }
                 }
             }
 
             RunwayNotificationIn::Response(res) => match res {
+                Response::Coord(u) if self.should_defer(&u) => {
+                    trace!(target: "AlephBFT-runway", "{:?} Putting off a fetched unit {:?}, the ordering is behind.", self.index(), &u);
+                    self.deferred_units.defer(u, None)
+                }
                 Response::Coord(u) => {
                     trace!(target: "AlephBFT-runway", "{:?} Fetch response received {:?}.", self.index(), &u);
-                    self.on_unit_received(u)
+                    self.on_unit_received_traced(u, None)
                 }
                 Response::Parents(u_hash, parents) => {
                     trace!(target: "AlephBFT-runway", "{:?} Response parents received {:?}.", self.index(), u_hash);
@@ -329,18 +910,241 @@ where
                 }
                 Response::NewestUnit(response) => {
                     trace!(target: "AlephBFT-runway", "{:?} Response newest unit received from {:?}.", self.index(), response.index());
-                    let res = self.responses_for_collection.unbounded_send(response);
-                    if res.is_err() {
-                        debug!(target: "AlephBFT-runway", "{:?} Could not send response to collection ({:?}).", self.index(), res)
+                    let peer = response.index();
+                    if let Err(e) = self.responses_for_collection.unbounded_send(response) {
+                        debug!(target: "AlephBFT-runway", "{:?} Could not send response to collection.", self.index());
+                        self.drops
+                            .record_drop(DropReason::StaleResponse, Some(peer), || {
+                                e.into_inner().encode()
+                            });
                     }
                 }
+                Response::CompactParents(node_id, u_hash, parents) => {
+                    trace!(target: "AlephBFT-runway", "{:?} Compact response parents received from {:?} {:?}.", self.index(), node_id, u_hash);
+                    self.on_compact_parents_response(node_id, u_hash, parents)
+                }
+                Response::Units(units) => {
+                    trace!(target: "AlephBFT-runway", "{:?} Response with {} units received.", self.index(), units.len());
+                    self.on_units_response(units)
+                }
             },
+
+            RunwayNotificationIn::Digest(digest, node_id) => self.on_digest(node_id, digest),
+
+            RunwayNotificationIn::NotFound(request, node_id) => {
+                self.dag.on_not_found(request, node_id, self.clock.now())
+            }
+            RunwayNotificationIn::Pruned(request, node_id) => {
+                self.dag.on_not_found(request, node_id, self.clock.now());
+                self.catch_up_with(node_id);
+            }
+
+            // Answering would mean signing the response.
+            RunwayNotificationIn::NewestUnitsRequest(salt, _, node_id)
+                if self.role == Role::Observer =>
+            {
+                trace!(target: "AlephBFT-runway", "{:?} Not answering the newest units request of {:?}, as we are an observer.", self.index(), node_id);
+                self.drops
+                    .record_drop(DropReason::Observing, Some(node_id), || salt.encode());
+            }
+            RunwayNotificationIn::NewestUnitsRequest(salt, digest, node_id) => {
+                self.on_newest_units_request(node_id, salt, digest)
+            }
+            RunwayNotificationIn::NewestUnitsResponse(response) => {
+                self.on_newest_units_response(response)
+            }
+        }
+    }
+
+    fn on_request_refused(
+        &self,
+        request: &RequestId<UFH::Hasher>,
+        node_id: NodeIndex,
+        refusal: Refusal,
+    ) {
+        trace!(target: "AlephBFT-runway", "{:?} Not answering request {:?} from node {:?}: {:?}.", self.index(), request, node_id, refusal);
+        let reason = match refusal {
+            Refusal::Repeated => DropReason::RepeatedRequest,
+            Refusal::RateLimited => DropReason::RequestRateLimited,
+        };
+        self.drops
+            .record_drop(reason, Some(node_id), || request.encode());
+    }
+
+    /// We definitively don't have what the node requested, units that are still processing
+    /// are not considered absent.
+    fn on_not_found(&mut self, request: RequestId<UFH::Hasher>, node_id: NodeIndex) {
+        match self.not_found_limiter.try_send(node_id, self.clock.now()) {
+            true => {
+                self.send_message_for_network(RunwayNotificationOut::NotFound(request, node_id))
+            }
+            false => {
+                trace!(target: "AlephBFT-runway", "{:?} Not answering request {:?} from node {:?}, too many negative responses.", self.index(), request, node_id);
+                self.drops
+                    .record_drop(DropReason::NotFoundRateLimited, Some(node_id), || {
+                        request.encode()
+                    });
+            }
+        }
+    }
+
+    /// We pruned what the node requested, it is too far behind to get the units one by one.
+    fn on_pruned(&mut self, request: RequestId<UFH::Hasher>, node_id: NodeIndex) {
+        match self.not_found_limiter.try_send(node_id, self.clock.now()) {
+            true => self.send_message_for_network(RunwayNotificationOut::Pruned(request, node_id)),
+            false => {
+                trace!(target: "AlephBFT-runway", "{:?} Not answering request {:?} from node {:?}, too many negative responses.", self.index(), request, node_id);
+                self.drops
+                    .record_drop(DropReason::NotFoundRateLimited, Some(node_id), || {
+                        request.encode()
+                    });
+            }
+        }
+    }
+
+    /// Requests the newest units of the node, unless we are waiting for some already.
+    fn catch_up_with(&mut self, node_id: NodeIndex) {
+        match self.catch_up.on_lag(node_id, self.clock.now()) {
+            CatchUpStep::Request(salt) => self.send_message_for_network(
+                RunwayNotificationOut::NewestUnitsRequest(salt, self.digest.clone(), node_id),
+            ),
+            // The requests by coord go on regardless.
+            CatchUpStep::Wait | CatchUpStep::FallBack => {}
+        }
+    }
+
+    fn on_digest(&mut self, node_id: NodeIndex, digest: DagDigest) {
+        let n_members = self.digest.size();
+        if node_id.0 >= n_members.0 || node_id == self.index() || digest.size() != n_members {
+            debug!(target: "AlephBFT-runway", "{:?} Ignoring a malformed digest from {:?}.", self.index(), node_id);
+            self.drops
+                .record_drop(DropReason::MalformedDigest, Some(node_id), || {
+                    digest.encode()
+                });
+            return;
+        }
+        let now = self.clock.now();
+        if let Some(previous) = self.digests_received_at.get(&node_id) {
+            if now.duration_since(*previous) < MIN_DIGEST_INTERVAL {
+                trace!(target: "AlephBFT-runway", "{:?} Ignoring a digest from {:?} sent too soon.", self.index(), node_id);
+                self.drops
+                    .record_drop(DropReason::DigestTooSoon, Some(node_id), || digest.encode());
+                return;
+            }
+        }
+        self.digests_received_at.insert(node_id, now);
+        let comparison = self.digest.compare(&digest);
+        self.peer_digests.insert(node_id, digest);
+        if !comparison.ahead.is_empty() {
+            debug!(target: "AlephBFT-runway", "{:?} {:?} is ahead of us for {} creators, catching up.", self.index(), node_id, comparison.ahead.len());
+            match self.catch_up.on_lag(node_id, now) {
+                CatchUpStep::Wait => {}
+                CatchUpStep::Request(salt) => self.send_message_for_network(
+                    RunwayNotificationOut::NewestUnitsRequest(salt, self.digest.clone(), node_id),
+                ),
+                CatchUpStep::FallBack => {
+                    debug!(target: "AlephBFT-runway", "{:?} No newest units came in time, requesting the units ahead by coords.", self.index());
+                    // Units far ahead of the ordering would only be put off.
+                    for coord in comparison.ahead {
+                        if !self.is_far_ahead(coord.round()) {
+                            self.on_missing_coord(coord);
+                        }
+                    }
+                }
+            }
+        }
+        if !comparison.diverged.is_empty() {
+            self.events
+                .publish(InternalEvent::DagDivergence(node_id, comparison.diverged));
+        }
+    }
+
+    /// Sends the units the node lacks according to its digest, unless it asked recently.
+    fn on_newest_units_request(&mut self, node_id: NodeIndex, salt: Salt, digest: DagDigest) {
+        let n_members = self.digest.size();
+        if node_id.0 >= n_members.0 || node_id == self.index() || digest.size() != n_members {
+            debug!(target: "AlephBFT-runway", "{:?} Ignoring a newest units request with a malformed digest from {:?}.", self.index(), node_id);
+            self.drops
+                .record_drop(DropReason::MalformedDigest, Some(node_id), || {
+                    digest.encode()
+                });
+            return;
+        }
+        if !self.catch_up.try_answer(node_id, self.clock.now()) {
+            trace!(target: "AlephBFT-runway", "{:?} Not answering the newest units request of {:?}, it asked recently.", self.index(), node_id);
+            self.drops
+                .record_drop(DropReason::NewestUnitsRateLimited, Some(node_id), || {
+                    salt.encode()
+                });
+            return;
+        }
+        match self
+            .responder
+            .handle_newest_units_request(node_id, salt, &digest, &self.store)
+        {
+            Ok(parts) => {
+                trace!(target: "AlephBFT-runway", "{:?} Answering the newest units request of {:?} in {} parts.", self.index(), node_id, parts.len());
+                for part in parts {
+                    self.send_message_for_network(RunwayNotificationOut::NewestUnitsResponse(
+                        part, node_id,
+                    ));
+                }
+            }
+            Err(err) => {
+                warn!(target: "AlephBFT-runway", "{:?} Failed to answer the newest units request of {:?}: {}.", self.index(), node_id, err);
+                self.events.publish(InternalEvent::SigningFailed(
+                    SigningTarget::NewestUnitsResponse(node_id),
+                ));
+            }
+        }
+    }
+
+    /// Adds the units answering our pending request for the newest units. Only signed answers of
+    /// the peer we asked, with the salt of our request, are accepted.
+    fn on_newest_units_response(
+        &mut self,
+        response: SignedNewestUnits<UFH::Hasher, UFH::Data, MK::Signature>,
+    ) {
+        let responder = response.as_signable().index();
+        let salt = response.as_signable().salt();
+        let addressed_to_us = responder.0 < self.digest.size().0
+            && response.as_signable().requester() == self.index();
+        let response = match addressed_to_us {
+            true => response.check(&self.keychain).map_err(|e| e.unchecked),
+            false => Err(response),
+        };
+        let response = match response {
+            Ok(response) if self.catch_up.accept(responder, salt) => Ok(response.into_signable()),
+            Ok(response) => Err(response.into_unchecked()),
+            Err(response) => Err(response),
+        };
+        let units = match response {
+            Ok(response) => response.into_units(),
+            Err(response) => {
+                debug!(target: "AlephBFT-runway", "{:?} Dropping newest units from {:?} we did not ask for.", self.index(), responder);
+                self.drops
+                    .record_drop(DropReason::UnsolicitedNewestUnits, Some(responder), || {
+                        response.encode()
+                    });
+                return;
+            }
+        };
+        debug!(target: "AlephBFT-runway", "{:?} Received {} newest units from {:?}.", self.index(), units.len(), responder);
+        for unit in units {
+            match self.should_defer(&unit) {
+                true => self.deferred_units.defer(unit, None),
+                false => self.on_unit_received_traced(unit, None),
+            }
         }
     }
 
+    fn send_digest(&mut self) {
+        self.send_message_for_network(RunwayNotificationOut::Digest(self.digest.clone()));
+    }
+
     fn resolve_missing_coord(&mut self, coord: &UnitCoord) {
         if self.missing_coords.remove(coord) {
-            self.send_resolved_request_notification(Request::Coord(*coord));
+            self.resolve_request(Request::Coord(*coord));
         }
     }
 
@@ -351,16 +1155,130 @@ where
     ) {
         if self.store.unit(&u_hash).is_some() {
             trace!(target: "AlephBFT-runway", "{:?} We got parents response but already imported the unit.", self.index());
+            self.drops.record_drop(DropReason::StaleResponse, None, || {
+                (u_hash, parents).encode()
+            });
             return;
         }
         let result = self.dag.add_parents(u_hash, parents, &self.store);
         self.handle_dag_result(result);
     }
 
+    /// Refers to the parents the requester most likely holds by hash, if enabled and we know
+    /// the digest of the requester.
+    fn compact_response(
+        &self,
+        response: Response<UFH::Hasher, UFH::Data, MK::Signature>,
+        node_id: NodeIndex,
+    ) -> Response<UFH::Hasher, UFH::Data, MK::Signature> {
+        let digest = match (self.compact_unit_refs, self.peer_digests.get(&node_id)) {
+            (true, Some(digest)) => digest,
+            _ => return response,
+        };
+        match response {
+            Response::Parents(u_hash, parents)
+                if parents
+                    .iter()
+                    .any(|parent| digest.holds(parent.as_signable().coord())) =>
+            {
+                let parents = CompactUnit::compact(parents, |coord| digest.holds(coord));
+                Response::CompactParents(self.index(), u_hash, parents)
+            }
+            response => response,
+        }
+    }
+
+    fn on_compact_parents_response(
+        &mut self,
+        node_id: NodeIndex,
+        u_hash: <UFH::Hasher as Hasher>::Hash,
+        parents: Vec<CompactUnit<UFH::Hasher, UFH::Data, MK::Signature>>,
+    ) {
+        // Only the responses to our requests are kept until the referenced units arrive.
+        if !self.missing_parents.contains(&u_hash) {
+            trace!(target: "AlephBFT-runway", "{:?} We got compact parents response but don't need the parents.", self.index());
+            return;
+        }
+        let store = &self.store;
+        let lookup = |hash: &_| store.unit(hash).map(|unit| unit.clone().unpack().into());
+        match self.compact_parents.resolve(u_hash, parents, lookup) {
+            Resolution::Complete(parents) => self.on_parents_response(u_hash, parents),
+            Resolution::Missing(hashes) => {
+                debug!(target: "AlephBFT-runway", "{:?} Requesting {} units referenced by {:?} in the parents of {:?}.", self.index(), hashes.len(), node_id, u_hash);
+                self.send_message_for_network(RunwayNotificationOut::UnitsRequest(hashes, node_id))
+            }
+        }
+    }
+
+    fn on_units_response(
+        &mut self,
+        units: Vec<UncheckedSignedUnit<UFH::Hasher, UFH::Data, MK::Signature>>,
+    ) {
+        let store = &self.store;
+        let lookup = |hash: &_| store.unit(hash).map(|unit| unit.clone().unpack().into());
+        for (u_hash, parents) in self.compact_parents.on_units(units, lookup) {
+            self.on_parents_response(u_hash, parents);
+        }
+    }
+
+    /// A notification cannot overtake the units of the forker that arrived before it, so it
+    /// waits while they are verified, and so do the notifications after it.
     fn on_forking_notification(
         &mut self,
         notification: ForkingNotification<UFH::Hasher, UFH::Data, MK::Signature>,
     ) {
+        let checkpoint = self.verification.checkpoint();
+        if self.held_notifications.is_empty() && self.is_verified_up_to(&notification, checkpoint) {
+            self.process_forking_notification(notification);
+        } else {
+            trace!(target: "AlephBFT-runway", "{:?} Holding an alerter notification until the units before it are verified.", self.index());
+            self.held_notifications
+                .push_back((checkpoint, notification));
+        }
+    }
+
+    fn is_verified_up_to(
+        &self,
+        notification: &ForkingNotification<UFH::Hasher, UFH::Data, MK::Signature>,
+        checkpoint: u64,
+    ) -> bool {
+        match notification {
+            ForkingNotification::Forker((unit, _)) => self
+                .verification
+                .is_verified_up_to(unit.as_signable().creator(), checkpoint),
+            ForkingNotification::Units(units) => units.iter().all(|unit| {
+                self.verification
+                    .is_verified_up_to(unit.as_signable().creator(), checkpoint)
+            }),
+        }
+    }
+
+    fn release_held_notifications(&mut self) {
+        while let Some((checkpoint, notification)) = self.held_notifications.front() {
+            if !self.is_verified_up_to(notification, *checkpoint) {
+                return;
+            }
+            let (_, notification) = self
+                .held_notifications
+                .pop_front()
+                .expect("just checked it is there");
+            if self.frozen {
+                trace!(target: "AlephBFT-runway", "{:?} Ignoring a held alerter notification, as we are frozen.", self.index());
+                continue;
+            }
+            self.process_forking_notification(notification);
+        }
+    }
+
+    fn process_forking_notification(
+        &mut self,
+        notification: ForkingNotification<UFH::Hasher, UFH::Data, MK::Signature>,
+    ) {
+        if let ForkingNotification::Forker(proof) = &notification {
+            self.fork_proofs
+                .entry(proof.0.as_signable().creator())
+                .or_insert_with(|| proof.clone());
+        }
         let result = self
             .dag
             .process_forking_notification(notification, &self.store);
@@ -368,8 +1286,9 @@ where
     }
 
     fn resolve_missing_parents(&mut self, u_hash: &<UFH::Hasher as Hasher>::Hash) {
+        self.compact_parents.forget(u_hash);
         if self.missing_parents.remove(u_hash) {
-            self.send_resolved_request_notification(Request::Parents(*u_hash));
+            self.resolve_request(Request::Parents(*u_hash));
         }
     }
 
@@ -386,109 +1305,565 @@ where
     }
 
     fn on_unit_reconstructed(&mut self, unit: DagUnit<UFH::Hasher, UFH::Data, MK>) {
+        trace!(target: "AlephBFT-runway", "Unit {:?} {} reconstructed.", unit.hash(), unit.coord());
+        if let Some(unit) = self.availability.hold(unit) {
+            self.save_unit(unit);
+        }
+    }
+
+    /// The data of the unit is available, so it and the units waiting for it can be saved.
+    fn on_unit_available(&mut self, unit_hash: &<UFH::Hasher as Hasher>::Hash) {
+        if self.frozen {
+            trace!(target: "AlephBFT-runway", "{:?} Ignoring an available unit, as we are frozen.", self.index());
+            return;
+        }
+        for unit in self.availability.on_available(unit_hash) {
+            self.save_unit(unit);
+        }
+    }
+
+    fn save_unit(&mut self, unit: DagUnit<UFH::Hasher, UFH::Data, MK>) {
         let unit_hash = unit.hash();
-        trace!(target: "AlephBFT-runway", "Unit {:?} {} reconstructed.", unit_hash, unit.coord());
-        if self.backup_units_for_saver.unbounded_send(unit).is_err() {
-            error!(target: "AlephBFT-runway", "{:?} A unit couldn't be sent to backup: {:?}.", self.index(), unit_hash);
+        match self.backup_units_for_saver.unbounded_send(unit) {
+            Ok(()) => self.units_being_saved += 1,
+            Err(_) => {
+                error!(target: "AlephBFT-runway", "{:?} A unit couldn't be sent to backup: {:?}.", self.index(), unit_hash)
+            }
         }
     }
 
     fn on_unit_backup_saved(&mut self, unit: DagUnit<UFH::Hasher, UFH::Data, MK>) {
         let unit_hash = unit.hash();
+        let is_member = self.role == Role::Member;
+        // Lateness is only summarized when creating our units.
+        if is_member {
+            self.lateness
+                .on_unit_admitted(unit.creator(), unit.round(), self.clock.now());
+            self.reception
+                .on_unit_admitted(unit.creator(), unit.round(), self.clock.now());
+        }
+        self.digest
+            .add_unit::<UFH::Hasher>(unit.creator(), unit.round(), &unit_hash);
+        self.events.publish(InternalEvent::BackupAcked(unit_hash));
+        self.units_being_saved = self.units_being_saved.saturating_sub(1);
         self.store.insert(unit.clone());
         self.dag.finished_processing(&unit_hash);
         self.resolve_missing_parents(&unit_hash);
         self.resolve_missing_coord(&unit.coord());
-        if self
-            .parents_for_creator
-            .unbounded_send(unit.clone())
-            .is_err()
+        // The creator is gone once we are frozen, and observers have none.
+        if is_member
+            && !self.frozen
+            && self
+                .parents_for_creator
+                .unbounded_send(unit.clone())
+                .is_err()
         {
             warn!(target: "AlephBFT-runway", "Creator channel should be open.");
             self.exiting = true;
         }
-        let unpacked_unit = unit.clone().unpack();
-        self.send_message_for_network(RunwayNotificationOut::NewAnyUnit(
-            unpacked_unit.clone().into(),
-        ));
+        let unpacked_unit: UncheckedSignedUnit<_, _, _> = unit.clone().unpack().into();
+        self.events
+            .publish(InternalEvent::UnitAdmitted(unpacked_unit.clone()));
+        self.send_message_for_network(RunwayNotificationOut::NewAnyUnit(unpacked_unit.clone()));
+        self.unit_metadata_monitor.record(UnitMetadata {
+            hash: unit_hash,
+            creator: unit.creator(),
+            round: unit.round(),
+            metadata: unpacked_unit.as_signable().metadata().to_vec(),
+        });
+        self.backup_position.advance(unpacked_unit.encoded_size());
 
         if unit.creator() == self.index() {
+            self.receipts.on_own_unit_broadcast(
+                unit.round(),
+                unit_hash,
+                unpacked_unit.as_signable().data().clone(),
+                self.clock.now(),
+            );
+            if unit.round() >= self.next_round {
+                self.next_round = unit.round() + 1;
+                self.last_own_unit = Some(unit_hash);
+            }
+            if unit.round() % UNIT_SIZE_SUMMARY_INTERVAL == 0 {
+                info!(target: "AlephBFT-runway", "{:?} Unit sizes at round {}: {}.", self.index(), unit.round(), self.unit_size_monitor.stats());
+            }
             trace!(target: "AlephBFT-runway", "{:?} Sending a unit {:?}.", self.index(), unit.hash());
-            self.send_message_for_network(RunwayNotificationOut::NewSelfUnit(unpacked_unit.into()));
-        }
-        self.ordering.add_unit(unit.clone());
-    }
-
-    fn on_missing_coord(&mut self, coord: UnitCoord) {
-        trace!(target: "AlephBFT-runway", "{:?} Dealing with missing coord notification {:?}.", self.index(), coord);
-        if self.store.canonical_unit(coord).is_none() {
-            let new_request = self.missing_coords.insert(coord);
-            if new_request {
-                self.send_message_for_network(RunwayNotificationOut::Request(Request::Coord(
-                    coord,
-                )));
+            self.send_message_for_network(RunwayNotificationOut::NewSelfUnit(unpacked_unit));
+        } else if is_member {
+            if let Some(parent) = unit.parent_for(self.index()) {
+                for receipt in
+                    self.receipts
+                        .on_parent_of_peer_unit(unit.creator(), parent, self.clock.now())
+                {
+                    self.events.publish(InternalEvent::QuorumReceipt(receipt));
+                }
             }
         }
-    }
-
-    fn on_wrong_control_hash(&mut self, u_hash: <UFH::Hasher as Hasher>::Hash) {
-        trace!(target: "AlephBFT-runway", "{:?} Dealing with wrong control hash notification {:?}.", self.index(), u_hash);
-        if self.missing_parents.insert(u_hash) {
-            self.send_message_for_network(RunwayNotificationOut::Request(Request::Parents(u_hash)));
+        if let Err(e) = self.ordering.add_unit(unit.clone()) {
+            error!(target: "AlephBFT-runway", "{:?} Cannot finalize anymore: {}, aborting.", self.index(), e);
+            self.exiting = true;
         }
+        self.check_session_end();
+        self.prune_obsolete_requests();
+        self.answer_freeze_requests();
+        self.answer_exit_flushes();
+        self.release_deferred_units();
     }
 
-    fn send_message_for_network(
-        &mut self,
-        notification: RunwayNotificationOut<UFH::Hasher, UFH::Data, MK::Signature>,
-    ) {
-        if self
-            .unit_messages_for_network
-            .unbounded_send(notification)
-            .is_err()
-        {
-            warn!(target: "AlephBFT-runway", "{:?} unit_messages_for_network channel should be open", self.index());
+    fn on_delivery_resumed(&mut self) {
+        debug!(target: "AlephBFT-runway", "{:?} Resuming the delivery of finalized batches.", self.index());
+        if let Err(e) = self.ordering.on_delivery_resumed() {
+            error!(target: "AlephBFT-runway", "{:?} Cannot finalize anymore: {}, aborting.", self.index(), e);
             self.exiting = true;
         }
+        self.check_session_end();
+        self.prune_obsolete_requests();
+        self.release_deferred_units();
     }
 
-    fn send_resolved_request_notification(&mut self, notification: Request<UFH::Hasher>) {
-        if self.resolved_requests.unbounded_send(notification).is_err() {
-            warn!(target: "AlephBFT-runway", "{:?} resolved_requests channel should be open", self.index());
-            self.exiting = true;
+    /// Notices the end of the session, i.e. the head of its last round being finalized and
+    /// delivered, as no unit above the max round can ever elect the heads of later rounds.
+    fn check_session_end(&mut self) {
+        if self.session_ended || !self.ordering.delivered_head_of(self.last_head_round) {
+            return;
         }
+        self.session_ended = true;
+        let round = self
+            .ordering
+            .finalized_round()
+            .unwrap_or(self.last_head_round);
+        info!(target: "AlephBFT-runway", "{:?} Session {} reached its end with the head of round {} finalized, answering requests for {}ms more.", self.index(), self.session_id, round, self.session_end_grace_period.as_millis());
+        self.session_end
+            .report(SessionEnd::FinalizationEnded(round));
     }
 
-    fn status(&self) -> RunwayStatus<'_, UFH::Hasher> {
-        RunwayStatus {
-            missing_coords: &self.missing_coords,
-            missing_parents: &self.missing_parents,
-            dag_status: self.dag.status(),
-            store_status: self.store.status(),
+    fn on_session_finished(&mut self) {
+        info!(target: "AlephBFT-runway", "{:?} Session {} finished.", self.index(), self.session_id);
+        if let Some(finished) = self.finished.take() {
+            // The member is gone only if the session is ending anyway.
+            let _ = finished.send(());
         }
     }
 
-    fn status_report(&self) {
-        info!(target: "AlephBFT-runway", "{}", self.status());
+    /// Whether units of the given round are far enough ahead of the ordering to be put off, see
+    /// [`ExtenderFlowControl`]. The units waiting for parents or being saved count towards the
+    /// backlog, as they are about to be ordered.
+    fn is_far_ahead(&self, round: Round) -> bool {
+        let flow_control = match &self.flow_control {
+            Some(flow_control) => flow_control,
+            None => return false,
+        };
+        let backlog = self.ordering.backlog();
+        let depth = backlog.depth + self.units_being_saved + self.dag.pending_units();
+        depth > flow_control.max_backlog && round > backlog.working_round + flow_control.horizon
     }
 
-    async fn run(
-        mut self,
-        data_from_backup: oneshot::Receiver<BackupUnits<UFH, MK>>,
-        mut terminator: Terminator,
+    /// Whether the unit is further ahead of our DAG than honest nodes get. Only applied to units
+    /// sent to us unprompted, responses to our requests are needed to catch up.
+    fn is_beyond_lead(
+        &self,
+        unit: &UncheckedSignedUnit<UFH::Hasher, UFH::Data, MK::Signature>,
+    ) -> bool {
+        let top_round = self.digest.top_round();
+        unit.as_signable().round() > top_round.saturating_add(self.max_round_lead)
+    }
+
+    fn on_unit_too_far_ahead(
+        &mut self,
+        unit: UncheckedSignedUnit<UFH::Hasher, UFH::Data, MK::Signature>,
+    ) {
+        let round = unit.as_signable().round();
+        self.drops
+            .record_drop(DropReason::TooFarAhead, None, || unit.encode());
+        self.far_units_dropped += 1;
+        let now = self.clock.now();
+        if let Some(warned_at) = self.far_units_warned_at {
+            if now.duration_since(warned_at) < FAR_UNITS_WARNING_INTERVAL {
+                return;
+            }
+        }
+        warn!(target: "AlephBFT-runway", "{:?} Dropped {} unit(s) too far ahead of our top round {}, the latest of round {}.", self.index(), self.far_units_dropped, self.digest.top_round(), round);
+        self.far_units_warned_at = Some(now);
+        self.far_units_dropped = 0;
+    }
+
+    fn should_defer(
+        &self,
+        unit: &UncheckedSignedUnit<UFH::Hasher, UFH::Data, MK::Signature>,
+    ) -> bool {
+        match &self.flow_control {
+            Some(flow_control) => {
+                self.deferred_units.len() < flow_control.max_deferred
+                    && self.is_far_ahead(unit.as_signable().round())
+            }
+            None => false,
+        }
+    }
+
+    /// Processes the units we put off, lowest rounds first, as long as the ordering is not too
+    /// far behind for them.
+    fn release_deferred_units(&mut self) {
+        while let Some(round) = self.deferred_units.lowest_round() {
+            if self.frozen || self.is_far_ahead(round) {
+                return;
+            }
+            self.release_lowest_deferred_round();
+        }
+    }
+
+    fn release_lowest_deferred_round(&mut self) {
+        for (unit, trace) in self.deferred_units.take_lowest_round() {
+            self.on_unit_received_traced(unit, trace);
+        }
+    }
+
+    /// The election of the head of a round might need units of rounds above the horizon, so if
+    /// the ordering does not advance while it is not waiting for the delivery, we release the
+    /// lowest round we put off, even though the backlog is still over the limit.
+    fn check_deferred_units(&mut self) {
+        let backlog = self.ordering.backlog();
+        let stuck =
+            backlog.working_round == self.deferral_checked_round && !backlog.waiting_for_delivery;
+        self.deferral_checked_round = backlog.working_round;
+        if self.frozen || !stuck || self.deferred_units.is_empty() {
+            return;
+        }
+        debug!(target: "AlephBFT-runway", "{:?} Ordering stuck at round {} with {} unit(s) put off, releasing some.", self.index(), backlog.working_round, self.deferred_units.len());
+        self.release_lowest_deferred_round();
+    }
+
+    /// Cancels the requests for units of rounds that got finalized without them. Such units
+    /// cannot affect the ordering anymore, unless some unit we hold needs them as parents, in
+    /// which case we keep asking. Should a unit needing them arrive later, the reconstruction
+    /// requests them again. Also prunes the store of units far enough below the finalized round,
+    /// if configured to. These are all saved to the backup, as only such units enter the store.
+    fn prune_obsolete_requests(&mut self) {
+        let finalized_round = match self.ordering.finalized_round() {
+            Some(round) if Some(round) != self.pruned_round => round,
+            _ => return,
+        };
+        self.pruned_round = Some(finalized_round);
+        self.dag.prune_rejections_up_to(finalized_round);
+        if let Some(kept_rounds) = self.kept_rounds {
+            let pruned = self
+                .store
+                .prune_below(finalized_round.saturating_sub(kept_rounds));
+            if pruned > 0 {
+                trace!(target: "AlephBFT-runway", "{:?} Pruned {} unit(s) below round {}.", self.index(), pruned, self.store.pruned_below());
+            }
+        }
+        let obsolete: Vec<_> = self
+            .missing_coords
+            .iter()
+            .filter(|coord| coord.round() <= finalized_round && !self.dag.is_blocked_on(**coord))
+            .copied()
+            .collect();
+        if obsolete.is_empty() {
+            return;
+        }
+        debug!(target: "AlephBFT-runway", "{:?} Cancelling {} request(s) made obsolete by finalizing round {}.", self.index(), obsolete.len(), finalized_round);
+        for coord in obsolete {
+            self.missing_coords.remove(&coord);
+            self.cancel_request(Request::Coord(coord));
+        }
+    }
+
+    fn on_missing_coord(&mut self, coord: UnitCoord) {
+        trace!(target: "AlephBFT-runway", "{:?} Dealing with missing coord notification {:?}.", self.index(), coord);
+        if self.store.canonical_unit(coord).is_none() {
+            let new_request = self.missing_coords.insert(coord);
+            if new_request {
+                self.issue_request(Request::Coord(coord));
+            }
+        }
+    }
+
+    fn on_wrong_control_hash(&mut self, u_hash: <UFH::Hasher as Hasher>::Hash) {
+        trace!(target: "AlephBFT-runway", "{:?} Dealing with wrong control hash notification {:?}.", self.index(), u_hash);
+        if self.missing_parents.insert(u_hash) {
+            self.issue_request(Request::Parents(u_hash));
+        }
+    }
+
+    fn issue_request(&mut self, request: Request<UFH::Hasher>) {
+        self.events
+            .publish(InternalEvent::RequestIssued(request.clone()));
+        self.send_message_for_network(RunwayNotificationOut::Request(request));
+    }
+
+    fn resolve_request(&mut self, request: Request<UFH::Hasher>) {
+        self.events
+            .publish(InternalEvent::RequestResolved(request.clone()));
+        self.send_message_for_network(RunwayNotificationOut::RequestResolved(request));
+    }
+
+    fn cancel_request(&mut self, request: Request<UFH::Hasher>) {
+        self.events
+            .publish(InternalEvent::RequestObsolete(request.clone()));
+        self.send_message_for_network(RunwayNotificationOut::RequestObsolete(request));
+    }
+
+    fn send_message_for_network(
+        &mut self,
+        notification: RunwayNotificationOut<UFH::Hasher, UFH::Data, MK::Signature>,
+    ) {
+        if self
+            .unit_messages_for_network
+            .unbounded_send(notification)
+            .is_err()
+        {
+            warn!(target: "AlephBFT-runway", "{:?} unit_messages_for_network channel should be open", self.index());
+            self.exiting = true;
+        }
+    }
+
+    /// Stops creating and accepting units, from now on we only answer requests. The export is
+    /// ready once all the units sent to the backup are saved.
+    fn on_freeze_request(&mut self, request: FreezeRequest<UFH::Hasher>) {
+        if !self.frozen {
+            info!(target: "AlephBFT-runway", "{:?} Freezing the session for a migration, {} unit(s) still being saved.", self.index(), self.units_being_saved);
+            self.freeze();
+            self.events.publish(InternalEvent::SessionFrozen);
+            self.send_message_for_network(RunwayNotificationOut::SessionFrozen);
+        }
+        self.pending_freezes.push(request);
+        self.answer_freeze_requests();
+    }
+
+    fn freeze(&mut self) {
+        self.frozen = true;
+        // Closing the channel stops the creator.
+        let (closed, _) = mpsc::unbounded();
+        self.parents_for_creator = closed;
+    }
+
+    /// Freezes the session before it exits, so that our units are all in the backup when it
+    /// does. Our units the creator sent already are saved as well, they might be the only copy
+    /// of our newest unit, and creating another unit of the same round after a restart would
+    /// make us fork.
+    fn on_exit_flush(&mut self, request: ExitFlush) {
+        if !self.frozen {
+            while let Ok(Some(unit)) = self.new_units_from_creation.try_next() {
+                self.on_unit_created(unit);
+            }
+            debug!(target: "AlephBFT-runway", "{:?} Freezing the session before exiting, {} unit(s) still being saved.", self.index(), self.units_being_saved);
+            self.freeze();
+        }
+        self.pending_exit_flushes.push(request);
+        self.answer_exit_flushes();
+    }
+
+    fn answer_exit_flushes(&mut self) {
+        if self.units_being_saved > 0 || self.pending_exit_flushes.is_empty() {
+            return;
+        }
+        debug!(target: "AlephBFT-runway", "{:?} All our units are saved, ready to exit.", self.index());
+        for request in self.pending_exit_flushes.drain(..) {
+            let _ = request.send(());
+        }
+    }
+
+    fn answer_freeze_requests(&mut self) {
+        if !self.frozen || self.units_being_saved > 0 || self.pending_freezes.is_empty() {
+            return;
+        }
+        let export = self.export();
+        info!(target: "AlephBFT-runway", "{:?} Session frozen with {:?} in the backup, continuing from round {}.", self.index(), export.backup_position(), export.next_round());
+        for request in self.pending_freezes.drain(..) {
+            let _ = request.send(export.clone());
+        }
+    }
+
+    fn export(&self) -> SessionStateExport<UFH::Hasher> {
+        SessionStateExport::new(
+            self.session_id,
+            self.index(),
+            self.backup_position,
+            self.next_round,
+            self.last_own_unit,
+            self.fork_proofs.values().map(Encode::encode).collect(),
+            self.missing_coords.iter().copied().collect(),
+            self.missing_parents.iter().copied().collect(),
+            self.ordering.delivery_checkpoint(),
+        )
+    }
+
+    /// Checks that the units loaded from the backup are exactly the ones the frozen session
+    /// saved, as continuing from a stale copy of the backup would make us fork. The finalization
+    /// state has to be restored already.
+    fn verify_import(
+        &self,
+        export: &SessionStateExport<UFH::Hasher>,
+        units: &BackupUnits<UFH, MK>,
+    ) -> ImportResult<UFH, MK> {
+        if export.session_id() != self.session_id || export.node_ix() != self.index() {
+            return Err(ImportError::OtherSession(
+                export.node_ix(),
+                export.session_id(),
+            ));
+        }
+        let mut found = BackupPosition::default();
+        let mut last_own_unit = None;
+        for unit in units {
+            found.advance(unit.encoded_size());
+            let full_unit = unit.as_signable();
+            if full_unit.creator() == self.index()
+                && last_own_unit.map_or(true, |(round, _)| full_unit.round() >= round)
+            {
+                last_own_unit = Some((full_unit.round(), full_unit.hash()));
+            }
+        }
+        if found != export.backup_position() {
+            return Err(ImportError::BackupPosition {
+                expected: export.backup_position(),
+                found,
+            });
+        }
+        let next_round = last_own_unit.map_or(0, |(round, _)| round + 1);
+        if next_round != export.next_round() {
+            return Err(ImportError::NextRound {
+                expected: export.next_round(),
+                found: next_round,
+            });
+        }
+        let last_own_unit = last_own_unit.map(|(_, hash)| hash);
+        if last_own_unit != export.last_own_unit() {
+            return Err(ImportError::LastOwnUnit {
+                expected: export.last_own_unit(),
+                found: last_own_unit,
+            });
+        }
+        if self.ordering.has_finalization_state()
+            && self.ordering.delivery_checkpoint() != export.delivery_checkpoint()
+        {
+            return Err(ImportError::DeliveryCheckpoint {
+                expected: export.delivery_checkpoint(),
+                found: self.ordering.delivery_checkpoint(),
+            });
+        }
+        export
+            .fork_proofs()
+            .iter()
+            .map(|proof| ForkProof::decode(&mut &proof[..]).map_err(|_| ImportError::ForkProof))
+            .collect()
+    }
+
+    /// Picks up where the frozen session left, after the units from the backup were added.
+    fn apply_import(
+        &mut self,
+        export: &SessionStateExport<UFH::Hasher>,
+        fork_proofs: Vec<RunwayForkProof<UFH, MK>>,
+    ) {
+        for proof in fork_proofs {
+            self.on_forking_notification(ForkingNotification::Forker(proof));
+        }
+        for coord in export.missing_coords() {
+            if !self.dag.is_processing_coord(*coord) {
+                self.on_missing_coord(*coord);
+            }
+        }
+        for u_hash in export.missing_parents() {
+            if self.dag.is_processing(u_hash) {
+                self.on_wrong_control_hash(*u_hash);
+            }
+        }
+    }
+
+    fn status(&self) -> RunwayStatus<'_, UFH::Hasher> {
+        RunwayStatus {
+            missing_coords: &self.missing_coords,
+            missing_parents: &self.missing_parents,
+            pending_units: self.dag.pending_units(),
+            deferred_units: self.deferred_units.len(),
+            verified_units: self.verification.in_flight(),
+            unavailable_units: self.availability.len(),
+            dag_status: self.dag.status(),
+            store_status: self.store.status(),
+        }
+    }
+
+    fn dag_status(&self) -> DagStatus {
+        let mut missing_coords: Vec<_> = self
+            .missing_coords
+            .iter()
+            .map(|coord| (coord.creator(), coord.round()))
+            .collect();
+        missing_coords.sort();
+        DagStatus {
+            top_rounds: self.store.status().top_row().clone(),
+            missing_coords,
+            pending_requests: self.missing_coords.len() + self.missing_parents.len(),
+            known_forkers: self.fork_proofs.len(),
+            finalized_round: self.ordering.finalized_round(),
+            stored_units: self.store.status().size(),
+            reception: self.reception.reception(),
+        }
+    }
+
+    fn status_report(&self) {
+        info!(target: "AlephBFT-runway", "{}", self.status());
+        if let Some(admission) = self.admission_monitor.stats() {
+            info!(target: "AlephBFT-runway", "{:?} Unit admission: {}.", self.index(), admission);
+        }
+        if let Some(drops) = self.drops.stats() {
+            info!(target: "AlephBFT-runway", "{:?} Messages {}.", self.index(), drops);
+        }
+        let reception: Vec<_> = self
+            .reception
+            .reception()
+            .iter()
+            .map(|(node_id, reception)| format!("{:?}: {}", node_id, reception))
+            .collect();
+        if !reception.is_empty() {
+            info!(target: "AlephBFT-runway", "{:?} Reception of units: {}.", self.index(), reception.join("; "));
+        }
+        let responses = self.response_limiter.stats();
+        if responses != ResponseStats::default() {
+            info!(target: "AlephBFT-runway", "{:?} Requests for units: {}.", self.index(), responses);
+        }
+    }
+
+    async fn run(
+        mut self,
+        data_from_backup: oneshot::Receiver<BackupUnits<UFH, MK>>,
+        mut terminator: Terminator,
     ) {
         let index = self.index();
         let data_from_backup = data_from_backup.fuse();
         pin_mut!(data_from_backup);
 
         let status_ticker_delay = Duration::from_secs(10);
-        let mut status_ticker = Delay::new(status_ticker_delay).fuse();
+        let clock = self.clock.clone();
+        let mut status_ticker = clock.sleep(status_ticker_delay).fuse();
+        let mut digest_ticker = clock.sleep(DIGEST_GOSSIP_INTERVAL).fuse();
+        let mut eviction_ticker = clock.sleep(EVICTION_CHECK_INTERVAL).fuse();
+        let mut deferral_ticker = clock.sleep(DEFERRAL_CHECK_INTERVAL).fuse();
+        let mut grace_period = Fuse::terminated();
 
         match data_from_backup.await {
             Ok(units) => {
+                let backup = units.iter().map(|unit| unit.as_signable().hash()).collect();
+                if let Err(e) = self.ordering.restore_finalization_state(&backup) {
+                    error!(target: "AlephBFT-runway", "{:?} Finalization state cannot be used: {}.", index, e);
+                    return;
+                }
+                let import = match self.state_import.take() {
+                    Some(export) => match self.verify_import(&export, &units) {
+                        Ok(fork_proofs) => Some((export, fork_proofs)),
+                        Err(e) => {
+                            error!(target: "AlephBFT-runway", "{:?} Cannot continue the migrated session: {}.", index, e);
+                            return;
+                        }
+                    },
+                    None => None,
+                };
                 for unit in units {
                     self.on_unit_received(unit);
                 }
+                if let Some((export, fork_proofs)) = import {
+                    info!(target: "AlephBFT-runway", "{:?} Continuing the migrated session from round {}.", index, export.next_round());
+                    self.apply_import(&export, fork_proofs);
+                }
             }
             Err(e) => {
                 error!(target: "AlephBFT-runway", "{:?} Units message from backup channel closed: {:?}", index, e);
@@ -498,9 +1873,38 @@ where
 
         debug!(target: "AlephBFT-runway", "{:?} Runway started.", index);
         loop {
+            if !self.exiting && terminator.exit_requested() {
+                debug!(target: "AlephBFT-runway", "{:?} received exit signal", index);
+                self.exiting = true;
+            }
+            if self.exiting {
+                debug!(target: "AlephBFT-runway", "{:?} Runway decided to exit.", index);
+                terminator.terminate_sync().await;
+                break;
+            }
+            // Nothing is admitted above the max round, so neither suspects nor units far ahead
+            // matter anymore.
+            if self.session_ended && self.finished.is_some() && grace_period.is_terminated() {
+                grace_period = clock.sleep(self.session_end_grace_period).fuse();
+                eviction_ticker = Fuse::terminated();
+                deferral_ticker = Fuse::terminated();
+            }
+
             futures::select! {
                 signed_unit = self.new_units_from_creation.next() => match signed_unit {
-                    Some(signed_unit) => self.on_unit_received(signed_unit.into()),
+                    Some(signed_unit) if self.frozen => {
+                        debug!(target: "AlephBFT-runway", "{:?} Dropping our unit of round {} created while freezing.", index, signed_unit.round());
+                        self.drops.record_drop(DropReason::Frozen, Some(index), || {
+                            UncheckedSignedUnit::from(signed_unit).encode()
+                        });
+                    },
+                    Some(signed_unit) => self.on_unit_created(signed_unit),
+                    None if self.frozen => {
+                        debug!(target: "AlephBFT-runway", "{:?} Creation stopped, as we are frozen.", index);
+                    },
+                    None if self.role == Role::Observer => {
+                        debug!(target: "AlephBFT-runway", "{:?} Not creating units, as we are an observer.", index);
+                    },
                     None => {
                         error!(target: "AlephBFT-runway", "{:?} Creation stream closed.", index);
                         break;
@@ -508,6 +1912,9 @@ where
                 },
 
                 notification = self.notifications_from_alerter.next() => match notification {
+                    Some(_) if self.frozen => {
+                        trace!(target: "AlephBFT-runway", "{:?} Ignoring an alerter notification, as we are frozen.", index);
+                    },
                     Some(notification) => {
                         trace!(target: "AlephBFT-runway", "Received alerter notification: {:?}.", notification);
                         self.on_forking_notification(notification);
@@ -518,37 +1925,102 @@ where
                     }
                 },
 
+                verified = self.verified_units.next() => match verified {
+                    Some(units) => self.on_units_verified(units),
+                    None => {
+                        error!(target: "AlephBFT-runway", "{:?} Verified units stream closed.", index);
+                        break;
+                    }
+                },
+
                 event = self.unit_messages_from_network.next() => match event {
-                    Some(event) => self.on_unit_message(event),
+                    Some((event, unit_sizes, mut trace)) => {
+                        mark_stage(&mut trace, AdmissionStage::RunwayQueue);
+                        self.unit_size_monitor.record_received(&unit_sizes);
+                        self.on_unit_message(event, trace)
+                    },
                     None => {
                         error!(target: "AlephBFT-runway", "{:?} Unit message stream closed.", index);
                         break;
                     }
                 },
 
+                available = self.available_units.next() => match available {
+                    Some(unit_hash) => self.on_unit_available(&unit_hash),
+                    None => {
+                        error!(target: "AlephBFT-runway", "{:?} Available units stream closed.", index);
+                        break;
+                    }
+                },
+
                 message = self.backup_units_from_saver.next() => match message {
                     Some(unit) => self.on_unit_backup_saved(unit),
                     None => {
                         error!(target: "AlephBFT-runway", "{:?} Saved units receiver closed.", index);
+                        // Nothing gets saved anymore, so there is no point in waiting.
+                        self.pending_exit_flushes.clear();
+                    }
+                },
+
+                resumption = self.delivery_resumptions.next() => match resumption {
+                    Some(()) => self.on_delivery_resumed(),
+                    None => {
+                        error!(target: "AlephBFT-runway", "{:?} Delivery resumption stream closed.", index);
+                        break;
                     }
                 },
 
                 _ = &mut status_ticker => {
                     self.status_report();
-                    status_ticker = Delay::new(status_ticker_delay).fuse();
+                    status_ticker = clock.sleep(status_ticker_delay).fuse();
+                },
+
+                request = self.freeze_requests.next() => {
+                    if let Some(request) = request {
+                        self.on_freeze_request(request);
+                    }
                 },
 
+                request = self.exit_flushes.next() => {
+                    if let Some(request) = request {
+                        self.on_exit_flush(request);
+                    }
+                },
+
+                request = self.status_requests.next() => {
+                    if let Some(request) = request {
+                        let _ = request.send(self.dag_status());
+                    }
+                },
+
+                _ = &mut digest_ticker => {
+                    // Digests of observers don't describe a member, so the committee ignores them.
+                    if !self.frozen && self.role == Role::Member {
+                        self.send_digest();
+                    }
+                    digest_ticker = clock.sleep(DIGEST_GOSSIP_INTERVAL).fuse();
+                },
+
+                _ = &mut eviction_ticker => {
+                    if !self.frozen {
+                        let result = self.dag.evict_unsatisfiable(self.clock.now());
+                        self.handle_dag_result(result);
+                    }
+                    eviction_ticker = clock.sleep(EVICTION_CHECK_INTERVAL).fuse();
+                },
+
+                _ = &mut deferral_ticker => {
+                    self.check_deferred_units();
+                    deferral_ticker = clock.sleep(DEFERRAL_CHECK_INTERVAL).fuse();
+                },
+
+                _ = &mut grace_period => self.on_session_finished(),
+
                 _ = terminator.get_exit().fuse() => {
                     debug!(target: "AlephBFT-runway", "{:?} received exit signal", index);
                     self.exiting = true;
                 }
             }
-
-            if self.exiting {
-                debug!(target: "AlephBFT-runway", "{:?} Runway decided to exit.", index);
-                terminator.terminate_sync().await;
-                break;
-            }
         }
 
         debug!(target: "AlephBFT-runway", "{:?} Run ended.", index);
@@ -559,8 +2031,8 @@ pub(crate) struct NetworkIO<H: Hasher, D: Data, MK: MultiKeychain> {
     pub(crate) alert_messages_for_network: Sender<(NetworkMessage<H, D, MK>, Recipient)>,
     pub(crate) alert_messages_from_network: Receiver<NetworkMessage<H, D, MK>>,
     pub(crate) unit_messages_for_network: Sender<RunwayNotificationOut<H, D, MK::Signature>>,
-    pub(crate) unit_messages_from_network: Receiver<RunwayNotificationIn<H, D, MK::Signature>>,
-    pub(crate) resolved_requests: Sender<Request<H>>,
+    pub(crate) unit_messages_from_network:
+        IngressReceiver<SizedNotificationIn<H, D, MK::Signature>>,
 }
 
 #[cfg(feature = "initial_unit_collection")]
@@ -570,10 +2042,13 @@ fn initial_unit_collection<'a, H: Hasher, D: Data, MK: MultiKeychain>(
     unit_messages_for_network: &Sender<RunwayNotificationOut<H, D, MK::Signature>>,
     unit_collection_sender: oneshot::Sender<Round>,
     responses_from_runway: Receiver<CollectionResponse<H, D, MK>>,
-    resolved_requests: Sender<Request<H>>,
+    events: EventBus<H, D, MK::Signature>,
+    clock: ClockSource,
 ) -> Result<impl Future<Output = ()> + 'a, ()> {
     let (collection, salt) = Collection::new(keychain, validator);
-    let notification = RunwayNotificationOut::Request(Request::NewestUnit(keychain.index(), salt));
+    let request = Request::NewestUnit(keychain.index(), salt);
+    events.publish(InternalEvent::RequestIssued(request.clone()));
+    let notification = RunwayNotificationOut::Request(request);
 
     if let Err(e) = unit_messages_for_network.unbounded_send(notification) {
         error!(target: "AlephBFT-runway", "Unable to send the newest unit request: {}", e);
@@ -583,13 +2058,14 @@ fn initial_unit_collection<'a, H: Hasher, D: Data, MK: MultiKeychain>(
     let collection = CollectionIO::new(
         unit_collection_sender,
         responses_from_runway,
-        resolved_requests,
+        unit_messages_for_network.clone(),
+        events,
         collection,
+        clock,
     );
     Ok(collection.run())
 }
 
-#[cfg(not(feature = "initial_unit_collection"))]
 fn trivial_start(
     starting_round_sender: oneshot::Sender<Round>,
 ) -> Result<impl Future<Output = ()>, ()> {
@@ -611,6 +2087,24 @@ pub struct RunwayIO<
     pub finalization_handler: UFH,
     pub backup_write: W,
     pub backup_read: R,
+    pub delivery_control: DeliveryControl,
+    pub unit_size_monitor: UnitSizeMonitor,
+    pub lateness_monitor: LatenessMonitor,
+    pub finalization_state: Option<FinalizationState<UFH::Hasher>>,
+    pub broadcast_gate: Option<Arc<dyn BroadcastGate<DP::Output>>>,
+    pub data_availability_checker: Option<Arc<dyn DataAvailabilityChecker<DP::Output>>>,
+    pub quorum_receipt_monitor: QuorumReceiptMonitor<DP::Output>,
+    pub migration_control: MigrationControl<UFH::Hasher>,
+    pub state_import: Option<SessionStateExport<UFH::Hasher>>,
+    pub backup_replication: BackupReplication,
+    pub admission_monitor: AdmissionMonitor,
+    pub unit_metadata_provider: Option<Arc<dyn UnitMetadataProvider>>,
+    pub unit_metadata_monitor: UnitMetadataMonitor<UFH::Hasher>,
+    pub lease_control: Option<LeaseControl>,
+    pub drop_monitor: DropMonitor,
+    pub status_query: StatusQuery,
+    pub misconduct_monitor: MisconductMonitor<UFH::Hasher>,
+    pub session_end_monitor: SessionEndMonitor,
     _phantom: PhantomData<MK::Signature>,
 }
 
@@ -627,25 +2121,170 @@ impl<
         finalization_handler: UFH,
         backup_write: W,
         backup_read: R,
+        delivery_control: DeliveryControl,
+        unit_size_monitor: UnitSizeMonitor,
+        lateness_monitor: LatenessMonitor,
     ) -> Self {
         RunwayIO {
             data_provider,
             finalization_handler,
             backup_write,
             backup_read,
+            delivery_control,
+            unit_size_monitor,
+            lateness_monitor,
+            finalization_state: None,
+            broadcast_gate: None,
+            data_availability_checker: None,
+            quorum_receipt_monitor: QuorumReceiptMonitor::default(),
+            migration_control: MigrationControl::default(),
+            state_import: None,
+            backup_replication: BackupReplication::default(),
+            admission_monitor: AdmissionMonitor::default(),
+            unit_metadata_provider: None,
+            unit_metadata_monitor: UnitMetadataMonitor::default(),
+            lease_control: None,
+            drop_monitor: DropMonitor::default(),
+            status_query: StatusQuery::default(),
+            misconduct_monitor: MisconductMonitor::default(),
+            session_end_monitor: SessionEndMonitor::default(),
             _phantom: PhantomData,
         }
     }
+
+    pub fn with_finalization_state(
+        self,
+        finalization_state: Option<FinalizationState<UFH::Hasher>>,
+    ) -> Self {
+        RunwayIO {
+            finalization_state,
+            ..self
+        }
+    }
+
+    pub fn with_broadcast_gate(
+        self,
+        broadcast_gate: Option<Arc<dyn BroadcastGate<DP::Output>>>,
+    ) -> Self {
+        RunwayIO {
+            broadcast_gate,
+            ..self
+        }
+    }
+
+    pub fn with_data_availability_checker(
+        self,
+        data_availability_checker: Option<Arc<dyn DataAvailabilityChecker<DP::Output>>>,
+    ) -> Self {
+        RunwayIO {
+            data_availability_checker,
+            ..self
+        }
+    }
+
+    pub fn with_quorum_receipt_monitor(
+        self,
+        quorum_receipt_monitor: QuorumReceiptMonitor<DP::Output>,
+    ) -> Self {
+        RunwayIO {
+            quorum_receipt_monitor,
+            ..self
+        }
+    }
+
+    pub fn with_migration(
+        self,
+        migration_control: MigrationControl<UFH::Hasher>,
+        state_import: Option<SessionStateExport<UFH::Hasher>>,
+    ) -> Self {
+        RunwayIO {
+            migration_control,
+            state_import,
+            ..self
+        }
+    }
+
+    pub fn with_backup_replication(self, backup_replication: BackupReplication) -> Self {
+        RunwayIO {
+            backup_replication,
+            ..self
+        }
+    }
+
+    pub fn with_admission_monitor(self, admission_monitor: AdmissionMonitor) -> Self {
+        RunwayIO {
+            admission_monitor,
+            ..self
+        }
+    }
+
+    pub fn with_unit_metadata(
+        self,
+        unit_metadata_provider: Option<Arc<dyn UnitMetadataProvider>>,
+        unit_metadata_monitor: UnitMetadataMonitor<UFH::Hasher>,
+    ) -> Self {
+        RunwayIO {
+            unit_metadata_provider,
+            unit_metadata_monitor,
+            ..self
+        }
+    }
+
+    pub fn with_lease_control(self, lease_control: Option<LeaseControl>) -> Self {
+        RunwayIO {
+            lease_control,
+            ..self
+        }
+    }
+
+    pub fn with_drop_monitor(self, drop_monitor: DropMonitor) -> Self {
+        RunwayIO {
+            drop_monitor,
+            ..self
+        }
+    }
+
+    pub fn with_status_query(self, status_query: StatusQuery) -> Self {
+        RunwayIO {
+            status_query,
+            ..self
+        }
+    }
+
+    pub fn with_misconduct_monitor(
+        self,
+        misconduct_monitor: MisconductMonitor<UFH::Hasher>,
+    ) -> Self {
+        RunwayIO {
+            misconduct_monitor,
+            ..self
+        }
+    }
+
+    pub fn with_session_end_monitor(self, session_end_monitor: SessionEndMonitor) -> Self {
+        RunwayIO {
+            session_end_monitor,
+            ..self
+        }
+    }
 }
 
-pub(crate) async fn run<US, UL, MK, DP, UFH, SH>(
+/// Spawns all the components of the runway, returning the future supervising them. Nothing gets
+/// spawned later, so the spawn handle can be dropped right away.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn start<US, UL, MK, DP, UFH, SH>(
     config: Config,
     runway_io: RunwayIO<MK, US, UL, DP, UFH>,
     keychain: MK,
     spawn_handle: SH,
     network_io: NetworkIO<UFH::Hasher, DP::Output, MK>,
+    exit_flushes: Receiver<ExitFlush>,
+    finished: oneshot::Sender<()>,
+    events: EventBus<UFH::Hasher, DP::Output, MK::Signature>,
+    callbacks: CallbackGuard,
     mut terminator: Terminator,
-) where
+) -> impl Future<Output = ()> + Send
+where
     US: AsyncWrite + Send + Sync + 'static,
     UL: AsyncRead + Send + Sync + 'static,
     DP: DataProvider,
@@ -658,46 +2297,100 @@ pub(crate) async fn run<US, UL, MK, DP, UFH, SH>(
         finalization_handler,
         backup_write,
         backup_read,
+        delivery_control,
+        unit_size_monitor,
+        lateness_monitor,
+        finalization_state,
+        broadcast_gate,
+        data_availability_checker,
+        quorum_receipt_monitor,
+        migration_control,
+        state_import,
+        backup_replication,
+        admission_monitor,
+        unit_metadata_provider,
+        unit_metadata_monitor,
+        lease_control,
+        drop_monitor,
+        status_query,
+        misconduct_monitor,
+        session_end_monitor,
         _phantom: _,
     } = runway_io;
 
+    let (lease_keeper, lease_permit) = match lease_control {
+        Some(lease_control) => {
+            let (lease_keeper, lease_permit) = lease_control.split(callbacks.clone());
+            (Some(lease_keeper), lease_permit)
+        }
+        None => (None, CreationPermit::default()),
+    };
+
     let (new_units_for_runway, new_units_from_creation) = mpsc::unbounded();
 
     let (parents_for_creator, parents_from_runway) = mpsc::unbounded();
-    let creation_terminator = terminator.add_offspring_connection("creator");
-    let creation_config = config.clone();
+    let (finalized_rounds_for_creator, finalized_rounds) = mpsc::unbounded();
+    let role = config.role();
+    // Nobody reads the finalized rounds without the adaptive inclusion policy, or as an observer.
+    let finalized_rounds_for_creator = config
+        .adaptive_inclusion()
+        .filter(|_| role == Role::Member)
+        .map(|_| finalized_rounds_for_creator);
+    let (finalized_heads_for_creator, finalized_heads) = mpsc::unbounded();
+    // Likewise, nobody reads the rounds of the finalized heads without the unfinalized rounds
+    // limit.
+    let finalized_heads_for_creator = config
+        .max_unfinalized_rounds()
+        .filter(|_| role == Role::Member)
+        .map(|_| finalized_heads_for_creator);
     let (starting_round_sender, starting_round) = oneshot::channel();
 
-    let creation_keychain = keychain.clone();
-    let creation_handle = spawn_handle
-        .spawn_essential("runway/creation", async move {
-            creation::run(
-                creation_config,
-                creation::IO {
-                    outgoing_units: new_units_for_runway,
-                    incoming_parents: parents_from_runway,
-                    data_provider,
-                },
-                creation_keychain,
-                starting_round,
-                creation_terminator,
-            )
-            .await
-        })
-        .shared();
-    let creator_handle_for_panic = creation_handle.clone();
-    let creator_panic_handle = async move {
-        if creator_handle_for_panic.await.is_err() {
-            return;
+    // Observers create no units, they only keep the starting round for the loader to send it.
+    let (creation_handle, unused_starting_round) = match role {
+        Role::Member => {
+            let creation_terminator = terminator.add_offspring_connection("creator");
+            let creation_config = config.clone();
+            let creation_keychain = keychain.clone();
+            let creation_events = events.clone();
+            let creation_callbacks = callbacks.clone();
+            let creation_session_end = session_end_monitor.clone();
+            let creation_handle = spawn_handle
+                .spawn_essential("runway/creation", async move {
+                    creation::run(
+                        creation_config,
+                        creation::IO {
+                            outgoing_units: new_units_for_runway,
+                            incoming_parents: parents_from_runway,
+                            data_provider,
+                            broadcast_gate,
+                            metadata_provider: unit_metadata_provider,
+                            lease: lease_permit,
+                            finalized_rounds,
+                            finalized_heads,
+                            events: creation_events,
+                            callbacks: creation_callbacks,
+                            session_end: creation_session_end,
+                        },
+                        creation_keychain,
+                        starting_round,
+                        creation_terminator,
+                    )
+                    .await
+                })
+                .shared();
+            (Some(creation_handle), None)
         }
-        pending().await
-    }
-    .fuse();
-    pin_mut!(creator_panic_handle);
-    let creation_handle = creation_handle.fuse();
+        Role::Observer => (None, Some(starting_round)),
+    };
 
     let (backup_units_for_saver, backup_units_from_runway) = mpsc::unbounded();
     let (backup_units_for_runway, backup_units_from_saver) = mpsc::unbounded();
+    let (new_backup_for_saver, new_backup_from_loader) = oneshot::channel();
+    let backup_fingerprint = BackupFingerprint::<UFH::Hasher>::new(
+        config.session_id(),
+        config.n_members(),
+        config.committee_id(),
+    );
 
     let backup_saver_terminator = terminator.add_offspring_connection("AlephBFT-backup-saver");
     let backup_saver_handle = spawn_handle.spawn_essential("runway/backup_saver", {
@@ -705,13 +2398,33 @@ pub(crate) async fn run<US, UL, MK, DP, UFH, SH>(
             backup_units_from_runway,
             backup_units_for_runway,
             backup_write,
-        );
+            backup_replication,
+            callbacks.clone(),
+        )
+        .with_header(backup_fingerprint.header(), new_backup_from_loader);
         async move {
             backup_saver.run(backup_saver_terminator).await;
         }
     });
     let mut backup_saver_handle = backup_saver_handle.fuse();
 
+    // Without a lease there is nothing to renew, and the terminated handle is never selected.
+    let mut lease_handle = match lease_keeper {
+        Some(lease_keeper) => {
+            let lease_terminator = terminator.add_offspring_connection("AlephBFT-lease");
+            let lease_clock = config.clock().clone();
+            let lease_renewal_interval = config.lease_renewal_interval();
+            spawn_handle
+                .spawn_essential("runway/lease", async move {
+                    lease_keeper
+                        .run(lease_clock, lease_renewal_interval, lease_terminator)
+                        .await
+                })
+                .fuse()
+        }
+        None => Fuse::terminated(),
+    };
+
     let (alert_notifications_for_units, notifications_from_alerter) = mpsc::unbounded();
     let (alerts_for_alerter, alerts_from_units) = mpsc::unbounded();
 
@@ -720,7 +2433,11 @@ pub(crate) async fn run<US, UL, MK, DP, UFH, SH>(
     let alert_messages_for_network = network_io.alert_messages_for_network;
     let alert_messages_from_network = network_io.alert_messages_from_network;
     let alerter_handler =
-        crate::alerts::Handler::new(alerter_keychain.clone(), config.session_id());
+        crate::alerts::Handler::new(alerter_keychain.clone(), config.session_id())
+            .with_max_legit_units(config.max_round() as usize + 1)
+            .with_max_round(config.max_round())
+            .with_compact_alerts(config.compact_alerts())
+            .with_aggregated_alerts(config.wire_version() >= EXTENDED_MESSAGES_VERSION);
 
     let mut alerter_service = crate::alerts::Service::new(
         alerter_keychain,
@@ -729,6 +2446,13 @@ pub(crate) async fn run<US, UL, MK, DP, UFH, SH>(
             messages_from_network: alert_messages_from_network,
             notifications_for_units: alert_notifications_for_units,
             alerts_from_units,
+            events: events.clone(),
+            clock: config.clock().clone(),
+            alert_rate_limit: config.alert_rate_limit(),
+            rmc_completion_replies: config.rmc_completion_replies(),
+            drops: drop_monitor.clone(),
+            role,
+            misconduct: misconduct_monitor,
         },
         alerter_handler,
     );
@@ -740,19 +2464,45 @@ pub(crate) async fn run<US, UL, MK, DP, UFH, SH>(
         .fuse();
 
     let index = keychain.index();
-    let validator = Validator::new(config.session_id(), keychain.clone(), config.max_round());
+    let validator = Validator::new(config.session_id(), keychain.clone(), config.max_round())
+        .with_max_metadata_size(config.max_unit_metadata_size())
+        .with_max_data_size(config.max_data_size())
+        .with_node_weights(config.node_weights());
+    // The verifiers block the threads they run on, so there are never more of them than threads.
+    let verifiers = match keychain.is_verification_cheap() {
+        true => 0,
+        false => {
+            thread::available_parallelism().map_or(1, |threads| threads.get().clamp(1, VERIFIERS))
+        }
+    };
+    let (verification, verified_units) = Verification::new(&validator, verifiers, &spawn_handle);
+    let (availability, available_units) = Availability::new(
+        data_availability_checker,
+        index,
+        config.max_unavailable_units(),
+        config.availability_recheck_interval(),
+        config.clock().clone(),
+        callbacks.clone(),
+        &spawn_handle,
+    );
     let (responses_for_collection, responses_from_runway) = mpsc::unbounded();
     let (unit_collections_sender, unit_collection_result) = oneshot::channel();
     let (loaded_data_tx, loaded_data_rx) = oneshot::channel();
-    let session_id = config.session_id();
 
     let backup_loading_handle = spawn_handle
         .spawn_essential("runway/loading", {
-            let mut backup_loader = BackupLoader::new(backup_read, index, session_id);
+            let mut backup_loader = BackupLoader::new(
+                backup_read,
+                backup_fingerprint,
+                validator.clone(),
+                callbacks.clone(),
+            )
+            .with_migration(config.migrate_backup());
             async move {
                 backup_loader
                     .run(
                         loaded_data_tx,
+                        new_backup_for_saver,
                         starting_round_sender,
                         unit_collection_result,
                     )
@@ -760,43 +2510,64 @@ pub(crate) async fn run<US, UL, MK, DP, UFH, SH>(
             }
         })
         .fuse();
-    pin_mut!(backup_loading_handle);
 
     #[cfg(feature = "initial_unit_collection")]
-    let starting_round_handle = match initial_unit_collection(
-        &keychain,
-        &validator,
-        &network_io.unit_messages_for_network,
-        unit_collections_sender,
-        responses_from_runway,
-        network_io.resolved_requests.clone(),
-    ) {
-        Ok(handle) => handle.fuse(),
-        Err(_) => return,
-    };
-    #[cfg(not(feature = "initial_unit_collection"))]
-    let starting_round_handle = match trivial_start(unit_collections_sender) {
-        Ok(handle) => handle.fuse(),
-        Err(_) => return,
-    };
-    pin_mut!(starting_round_handle);
+    let collection_io = (
+        network_io.unit_messages_for_network.clone(),
+        events.clone(),
+        config.clock().clone(),
+    );
 
+    let runway_terminator = terminator.add_offspring_connection("AlephBFT-runway");
     let runway_handle = spawn_handle
         .spawn_essential("runway", {
             let runway_config = RunwayConfig {
                 finalization_handler,
+                delivery_control,
+                finalization_state,
+                data_policy: config.data_policy().clone(),
+                session_id: config.session_id(),
+                role,
+                clock: config.clock().clone(),
+                compact_unit_refs: config.compact_unit_refs(),
+                reconstruction_limits: config.reconstruction_limits(),
+                response_limits: config.response_limits(),
+                max_round_lead: config.max_round_lead(),
+                max_units_per_message: config.max_units_per_message(),
+                kept_rounds: config.kept_rounds(),
+                extender_flow_control: config.extender_flow_control(),
+                unit_size_monitor,
+                admission_monitor,
+                drop_monitor,
+                lateness_monitor,
+                quorum_receipt_monitor,
+                unit_metadata_monitor,
+                migration_control,
+                state_import,
+                status_query,
+                exit_flushes,
+                max_round: config.max_round(),
+                session_end_monitor,
+                session_end_grace_period: config.session_end_grace_period(),
+                finished,
                 backup_units_for_saver,
                 backup_units_from_saver,
                 alerts_for_alerter,
                 notifications_from_alerter,
+                verification,
+                verified_units,
+                availability,
+                available_units,
                 unit_messages_from_network: network_io.unit_messages_from_network,
                 unit_messages_for_network: network_io.unit_messages_for_network,
                 parents_for_creator,
+                finalized_rounds_for_creator,
+                finalized_heads_for_creator,
                 responses_for_collection,
-                resolved_requests: network_io.resolved_requests,
+                events,
                 new_units_from_creation,
+                callbacks: callbacks.clone(),
             };
-            let runway_terminator = terminator.add_offspring_connection("AlephBFT-runway");
             let validator = validator.clone();
             let keychain = keychain.clone();
             let runway = Runway::new(runway_config, keychain, validator);
@@ -804,53 +2575,146 @@ pub(crate) async fn run<US, UL, MK, DP, UFH, SH>(
             async move { runway.run(loaded_data_rx, runway_terminator).await }
         })
         .fuse();
-    pin_mut!(runway_handle);
 
-    loop {
-        futures::select! {
-            _ = runway_handle => {
-                debug!(target: "AlephBFT-runway", "{:?} Runway task terminated early.", index);
-                break;
-            },
-            _ = alerter_handle => {
-                debug!(target: "AlephBFT-runway", "{:?} Alerter task terminated early.", index);
-                break;
-            },
-            _ = creator_panic_handle => {
-                debug!(target: "AlephBFT-runway", "{:?} creator task terminated early with its task being dropped.", index);
-                break;
-            },
-            _ = backup_saver_handle => {
-                debug!(target: "AlephBFT-runway", "{:?} Backup saving task terminated early.", index);
-                break;
-            },
-            _ = starting_round_handle => {
-                debug!(target: "AlephBFT-runway", "{:?} Starting round task terminated.", index);
-            },
-            _ = backup_loading_handle => {
-                debug!(target: "AlephBFT-runway", "{:?} Backup loading task terminated.", index);
+    async move {
+        let _unused_starting_round = unused_starting_round;
+        let creator_handle_for_panic = creation_handle.clone();
+        let creator_panic_handle = async move {
+            if let Some(handle) = creator_handle_for_panic {
+                if handle.await.is_err() {
+                    return;
+                }
+            }
+            pending().await
+        }
+        .fuse();
+        pin_mut!(creator_panic_handle);
+        let creation_handle = match creation_handle {
+            Some(handle) => handle.fuse(),
+            None => Fuse::terminated(),
+        };
+        pin_mut!(backup_loading_handle);
+        pin_mut!(runway_handle);
+
+        // Observers have no units of their own to collect.
+        #[cfg(feature = "initial_unit_collection")]
+        let starting_round_handle = match role {
+            Role::Member => {
+                let (unit_messages_for_network, events, clock) = collection_io;
+                match initial_unit_collection(
+                    &keychain,
+                    &validator,
+                    &unit_messages_for_network,
+                    unit_collections_sender,
+                    responses_from_runway,
+                    events,
+                    clock,
+                ) {
+                    Ok(handle) => futures::future::Either::Left(handle).fuse(),
+                    Err(_) => return,
+                }
+            }
+            Role::Observer => match trivial_start(unit_collections_sender) {
+                Ok(handle) => futures::future::Either::Right(handle).fuse(),
+                Err(_) => return,
             },
-            _ = terminator.get_exit().fuse() => {
-                break;
+        };
+        #[cfg(not(feature = "initial_unit_collection"))]
+        let starting_round_handle = match trivial_start(unit_collections_sender) {
+            Ok(handle) => handle.fuse(),
+            Err(_) => return,
+        };
+        pin_mut!(starting_round_handle);
+
+        loop {
+            futures::select! {
+                _ = runway_handle => {
+                    debug!(target: "AlephBFT-runway", "{:?} Runway task terminated early.", index);
+                    callbacks.on_component_terminated("runway");
+                    break;
+                },
+                _ = alerter_handle => {
+                    debug!(target: "AlephBFT-runway", "{:?} Alerter task terminated early.", index);
+                    callbacks.on_component_terminated("runway/alerter");
+                    break;
+                },
+                _ = creator_panic_handle => {
+                    debug!(target: "AlephBFT-runway", "{:?} creator task terminated early with its task being dropped.", index);
+                    callbacks.on_component_terminated("runway/creation");
+                    break;
+                },
+                _ = backup_saver_handle => {
+                    debug!(target: "AlephBFT-runway", "{:?} Backup saving task terminated early.", index);
+                    callbacks.on_component_terminated("runway/backup_saver");
+                    break;
+                },
+                _ = lease_handle => {
+                    debug!(target: "AlephBFT-runway", "{:?} Lease task terminated early.", index);
+                    callbacks.on_component_terminated("runway/lease");
+                    break;
+                },
+                _ = starting_round_handle => {
+                    debug!(target: "AlephBFT-runway", "{:?} Starting round task terminated.", index);
+                },
+                _ = backup_loading_handle => {
+                    debug!(target: "AlephBFT-runway", "{:?} Backup loading task terminated.", index);
+                },
+                _ = terminator.get_exit().fuse() => {
+                    break;
+                }
             }
         }
-    }
 
-    debug!(target: "AlephBFT-runway", "{:?} Ending run.", index);
-    terminator.terminate_sync().await;
+        debug!(target: "AlephBFT-runway", "{:?} Ending run.", index);
+        terminator.terminate_sync().await;
 
-    handle_task_termination(creation_handle, "AlephBFT-runway", "Creator", index).await;
-    handle_task_termination(alerter_handle, "AlephBFT-runway", "Alerter", index).await;
-    handle_task_termination(runway_handle, "AlephBFT-runway", "Runway", index).await;
-    handle_task_termination(backup_saver_handle, "AlephBFT-runway", "BackupSaver", index).await;
+        handle_task_termination(creation_handle, "AlephBFT-runway", "Creator", index).await;
+        handle_task_termination(alerter_handle, "AlephBFT-runway", "Alerter", index).await;
+        handle_task_termination(runway_handle, "AlephBFT-runway", "Runway", index).await;
+        handle_task_termination(backup_saver_handle, "AlephBFT-runway", "BackupSaver", index).await;
+        handle_task_termination(lease_handle, "AlephBFT-runway", "Lease", index).await;
 
-    debug!(target: "AlephBFT-runway", "{:?} Runway ended.", index);
+        debug!(target: "AlephBFT-runway", "{:?} Runway ended.", index);
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::runway::RunwayStatus;
-    use aleph_bft_mock::Hasher64;
+    use crate::{
+        alerts::{Alert, ForkingNotification},
+        callbacks::CallbackGuard,
+        dag::DagUnit,
+        dissemination::Request,
+        events::EventBus,
+        ingress::{ingress_queue, IngressSender, Priority},
+        member::FinalizationHandlerAdapter,
+        runway::{
+            availability::Availability,
+            digest::DIGEST_GOSSIP_INTERVAL,
+            verification::{Verification, VERIFIERS},
+            CollectionResponse, Runway, RunwayConfig, RunwayNotificationIn, RunwayNotificationOut,
+            RunwayStatus, SizedNotificationIn,
+        },
+        units::{random_full_parent_units_up_to, SignedUnit, Unit, UnitCoord, Validator},
+        ClockSource, DataPolicy, Index, NodeCount, NodeIndex, Role, Round, Signed, Terminator,
+        DEFAULT_AVAILABILITY_RECHECK_INTERVAL, DEFAULT_MAX_ROUND_LEAD,
+        DEFAULT_MAX_UNAVAILABLE_UNITS, DEFAULT_RECONSTRUCTION_LIMITS, DEFAULT_RESPONSE_LIMITS,
+    };
+    use aleph_bft_mock::{Data, FinalizationHandler, Hasher64, Keychain, Signature, Spawner};
+    use futures::{
+        channel::{mpsc, oneshot},
+        StreamExt,
+    };
+    use std::{iter, time::Duration};
+    use tokio::time::timeout;
+
+    const QUEUED_MESSAGES: usize = 100_000;
+    const EXIT_BOUND: Duration = Duration::from_millis(100);
+    const VERIFICATION_DELAY: Duration = Duration::from_millis(100);
+    const SLOW_ROUNDS: Round = 31;
+
+    type TestRunway =
+        Runway<FinalizationHandlerAdapter<FinalizationHandler, Data, Hasher64>, Keychain>;
 
     #[test]
     pub fn formats_missing_coords() {
@@ -883,4 +2747,222 @@ mod tests {
             "{Creator 0: 1}, {Creator 1: 1, 3}, {Creator 3: 0}"
         );
     }
+
+    /// The ends of the channels of a runway used in tests, the ones nobody reads are kept so
+    /// that the runway does not stop because they are gone.
+    struct TestChannels {
+        messages_for_runway: IngressSender<SizedNotificationIn<Hasher64, Data, Signature>>,
+        messages_from_runway:
+            mpsc::UnboundedReceiver<RunwayNotificationOut<Hasher64, Data, Signature>>,
+        backup_units: mpsc::UnboundedReceiver<DagUnit<Hasher64, Data, Keychain>>,
+        _saved_units: mpsc::UnboundedSender<DagUnit<Hasher64, Data, Keychain>>,
+        _notifications: mpsc::UnboundedSender<ForkingNotification<Hasher64, Data, Signature>>,
+        _new_units: mpsc::UnboundedSender<SignedUnit<Hasher64, Data, Keychain>>,
+        _finalized: mpsc::UnboundedReceiver<Data>,
+        _alerts: mpsc::UnboundedReceiver<Alert<Hasher64, Data, Signature>>,
+        _responses: mpsc::UnboundedReceiver<CollectionResponse<Hasher64, Data, Keychain>>,
+        _parents: mpsc::UnboundedReceiver<DagUnit<Hasher64, Data, Keychain>>,
+    }
+
+    fn test_runway(keychain: Keychain, verifiers: usize) -> (TestRunway, TestChannels) {
+        let (finalization_handler, _finalized) = FinalizationHandler::new();
+        let (backup_units_for_saver, backup_units) = mpsc::unbounded();
+        let (_saved_units, backup_units_from_saver) = mpsc::unbounded();
+        let (alerts_for_alerter, _alerts) = mpsc::unbounded();
+        let (_notifications, notifications_from_alerter) = mpsc::unbounded();
+        let (messages_for_runway, unit_messages_from_network) = ingress_queue(QUEUED_MESSAGES);
+        let (unit_messages_for_network, messages_from_runway) = mpsc::unbounded();
+        let (responses_for_collection, _responses) = mpsc::unbounded();
+        let (parents_for_creator, _parents) = mpsc::unbounded();
+        let (_new_units, new_units_from_creation) = mpsc::unbounded();
+        let validator = Validator::new(0, keychain.clone(), 5000);
+        let (verification, verified_units) =
+            Verification::new(&validator, verifiers, &Spawner::new());
+        let (availability, available_units) = Availability::new(
+            None,
+            Index::index(&keychain),
+            DEFAULT_MAX_UNAVAILABLE_UNITS,
+            DEFAULT_AVAILABILITY_RECHECK_INTERVAL,
+            ClockSource::default(),
+            CallbackGuard::default(),
+            &Spawner::new(),
+        );
+        let config = RunwayConfig {
+            finalization_handler: finalization_handler.into(),
+            delivery_control: Default::default(),
+            finalization_state: None,
+            data_policy: DataPolicy::default(),
+            session_id: 0,
+            role: Role::Member,
+            clock: ClockSource::default(),
+            compact_unit_refs: false,
+            reconstruction_limits: DEFAULT_RECONSTRUCTION_LIMITS,
+            response_limits: DEFAULT_RESPONSE_LIMITS,
+            max_round_lead: DEFAULT_MAX_ROUND_LEAD,
+            max_units_per_message: usize::MAX,
+            kept_rounds: None,
+            extender_flow_control: None,
+            unit_size_monitor: Default::default(),
+            admission_monitor: Default::default(),
+            drop_monitor: Default::default(),
+            lateness_monitor: Default::default(),
+            quorum_receipt_monitor: Default::default(),
+            unit_metadata_monitor: Default::default(),
+            migration_control: Default::default(),
+            state_import: None,
+            status_query: Default::default(),
+            exit_flushes: mpsc::unbounded().1,
+            max_round: 5000,
+            session_end_monitor: Default::default(),
+            session_end_grace_period: Duration::ZERO,
+            finished: oneshot::channel().0,
+            backup_units_for_saver,
+            backup_units_from_saver,
+            alerts_for_alerter,
+            notifications_from_alerter,
+            verification,
+            verified_units,
+            availability,
+            available_units,
+            unit_messages_from_network,
+            unit_messages_for_network,
+            responses_for_collection,
+            parents_for_creator,
+            finalized_rounds_for_creator: None,
+            finalized_heads_for_creator: None,
+            events: EventBus::new(),
+            new_units_from_creation,
+            callbacks: CallbackGuard::default(),
+        };
+        let runway = Runway::new(config, keychain, validator);
+        let channels = TestChannels {
+            messages_for_runway,
+            messages_from_runway,
+            backup_units,
+            _saved_units,
+            _notifications,
+            _new_units,
+            _finalized,
+            _alerts,
+            _responses,
+            _parents,
+        };
+        (runway, channels)
+    }
+
+    #[tokio::test]
+    async fn exits_without_draining_the_queue() {
+        let n_members = NodeCount(4);
+        let keychain = Keychain::new(n_members, NodeIndex(0));
+        let (runway, channels) = test_runway(keychain, 0);
+
+        // Requests for units we do not have, answered cheaply with a negative response.
+        let request = Request::Coord(UnitCoord::new(0, NodeIndex(1)));
+        for _ in 0..QUEUED_MESSAGES {
+            channels
+                .messages_for_runway
+                .send(
+                    (
+                        RunwayNotificationIn::Request(request.clone(), NodeIndex(1)),
+                        Vec::new(),
+                        None,
+                    ),
+                    Priority::Low,
+                )
+                .expect("the runway is not running yet");
+        }
+        let (backup_tx, backup_rx) = oneshot::channel();
+        backup_tx.send(Vec::new()).expect("the receiver is alive");
+        let (exit_tx, exit_rx) = oneshot::channel();
+        exit_tx.send(()).expect("the receiver is alive");
+
+        timeout(
+            EXIT_BOUND,
+            runway.run(backup_rx, Terminator::create_root(exit_rx, "runway")),
+        )
+        .await
+        .expect("the runway should exit right away");
+        let responses = channels
+            .messages_from_runway
+            .filter(|message| {
+                futures::future::ready(matches!(message, RunwayNotificationOut::NotFound(..)))
+            })
+            .count()
+            .await;
+        assert_eq!(responses, 0);
+    }
+    #[tokio::test(flavor = "multi_thread", worker_threads = 6)]
+    async fn keeps_ticking_while_verification_lags() {
+        let n_members = NodeCount(4);
+        let keychains = Keychain::new_vec(n_members);
+        let keychain = keychains[0]
+            .clone()
+            .with_verification_delay(VERIFICATION_DELAY);
+        let (runway, mut channels) = test_runway(keychain, VERIFIERS);
+
+        let units: Vec<_> = random_full_parent_units_up_to(SLOW_ROUNDS, n_members, 0)
+            .into_iter()
+            .flatten()
+            .map(|unit| {
+                let keychain = &keychains[unit.creator().0];
+                Signed::sign(unit, keychain).expect("the keychain never fails")
+            })
+            .collect();
+        let n_units = units.len();
+        for unit in units {
+            channels
+                .messages_for_runway
+                .send(
+                    (RunwayNotificationIn::NewUnit(unit.into()), Vec::new(), None),
+                    Priority::Normal,
+                )
+                .expect("the runway is not running yet");
+        }
+        // Waits behind all the units, as requests have lower priority.
+        let request = Request::Coord(UnitCoord::new(SLOW_ROUNDS + 1, NodeIndex(1)));
+        channels
+            .messages_for_runway
+            .send(
+                (
+                    RunwayNotificationIn::Request(request, NodeIndex(1)),
+                    Vec::new(),
+                    None,
+                ),
+                Priority::Low,
+            )
+            .expect("the runway is not running yet");
+        let (backup_tx, backup_rx) = oneshot::channel();
+        backup_tx.send(Vec::new()).expect("the receiver is alive");
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle =
+            tokio::spawn(runway.run(backup_rx, Terminator::create_root(exit_rx, "runway")));
+
+        // Verifying the units of a single creator takes over three seconds, in the meantime the
+        // runway answers the request and gossips its digest, which it does on a timer.
+        let (mut answered, mut ticked) = (false, false);
+        timeout(DIGEST_GOSSIP_INTERVAL * 2, async {
+            while !(answered && ticked) {
+                match channels.messages_from_runway.next().await {
+                    Some(RunwayNotificationOut::NotFound(..)) => answered = true,
+                    Some(RunwayNotificationOut::Digest(_)) => ticked = true,
+                    Some(_) => {}
+                    None => panic!("the runway is running"),
+                }
+            }
+        })
+        .await
+        .expect("the runway should keep going while the units are verified");
+        let added = iter::from_fn(|| channels.backup_units.try_next().ok().flatten()).count();
+        assert!(added < n_units, "all {} units verified already", added);
+
+        let added_later = timeout(
+            VERIFICATION_DELAY * n_units as u32,
+            (&mut channels.backup_units).take(n_units - added).count(),
+        )
+        .await
+        .expect("all the units should get verified");
+        assert_eq!(added + added_later, n_units);
+        exit_tx.send(()).expect("the runway is running");
+        handle.await.expect("the runway should not panic");
+    }
 }
@@ -0,0 +1,244 @@
+use crate::{
+    admission::AdmissionTrace,
+    dag::{UnverifiedUnit, VerifiedUnit},
+    units::Validator,
+    Data, Hasher, MultiKeychain, NodeIndex, Receiver, Sender, SpawnHandle,
+};
+use futures::{channel::mpsc, future::poll_fn, StreamExt};
+use log::error;
+use std::{
+    collections::{HashMap, VecDeque},
+    task::Poll,
+};
+
+/// How many tasks check the signatures of units, when the keychain says it is expensive.
+pub const VERIFIERS: usize = 4;
+
+/// How many waiting units a verifier checks before sending them back at once.
+const BATCH_SIZE: usize = 8;
+
+type Job<H, D, MK> = (UnverifiedUnit<H, D, MK>, Option<AdmissionTrace>);
+
+/// A unit that went through the check of its signature, with its trace.
+pub type Verified<H, D, MK> = (VerifiedUnit<H, D, MK>, Option<AdmissionTrace>);
+
+/// The batches of units coming back from the verifiers.
+pub type VerifiedUnits<H, D, MK> = Receiver<Vec<Verified<H, D, MK>>>;
+
+/// Checks the signatures of incoming units in separate tasks, so that the runway keeps going
+/// while they are checked. Every creator is assigned one of the tasks, which checks units in
+/// the order it gets them, so the units of a creator come back in the order they arrived and
+/// forks get detected as usual.
+///
+/// The units are numbered as they are submitted, so that whatever has to wait for the units
+/// of a creator that arrived before it can wait for them only, see [`Self::checkpoint`].
+pub struct Verification<H: Hasher, D: Data, MK: MultiKeychain> {
+    verifiers: Vec<Sender<Job<H, D, MK>>>,
+    next_job: u64,
+    in_flight: HashMap<NodeIndex, VecDeque<u64>>,
+    // Kept, so that the stream of results does not end when there are no verifiers.
+    _results_for_verifiers: Sender<Vec<Verified<H, D, MK>>>,
+}
+
+impl<H: Hasher, D: Data, MK: MultiKeychain> Verification<H, D, MK> {
+    /// Spawns the given number of verifiers, returning the stream of their results. Without
+    /// verifiers, the signatures are meant to be checked right where the units arrive.
+    pub fn new<SH: SpawnHandle>(
+        validator: &Validator<MK>,
+        verifiers: usize,
+        spawn_handle: &SH,
+    ) -> (Self, VerifiedUnits<H, D, MK>) {
+        let (results_for_verifiers, results) = mpsc::unbounded();
+        let verifiers = (0..verifiers)
+            .map(|_| {
+                let (jobs_for_verifier, jobs) = mpsc::unbounded();
+                spawn_handle.spawn(
+                    "runway/verifier",
+                    run_verifier(validator.clone(), jobs, results_for_verifiers.clone()),
+                );
+                jobs_for_verifier
+            })
+            .collect();
+        let verification = Verification {
+            verifiers,
+            next_job: 0,
+            in_flight: HashMap::new(),
+            _results_for_verifiers: results_for_verifiers,
+        };
+        (verification, results)
+    }
+
+    /// Whether the signatures are checked by the verifiers.
+    pub fn is_offloaded(&self) -> bool {
+        !self.verifiers.is_empty()
+    }
+
+    /// Passes the unit to the verifier of its creator.
+    pub fn submit(&mut self, unit: UnverifiedUnit<H, D, MK>, trace: Option<AdmissionTrace>) {
+        let creator = unit.creator();
+        let verifier = &self.verifiers[creator.0 % self.verifiers.len()];
+        match verifier.unbounded_send((unit, trace)) {
+            Ok(()) => {
+                self.in_flight
+                    .entry(creator)
+                    .or_default()
+                    .push_back(self.next_job);
+                self.next_job += 1;
+            }
+            Err(_) => {
+                error!(target: "AlephBFT-runway", "Verifier of the units of {:?} is gone.", creator)
+            }
+        }
+    }
+
+    /// Notes that a unit of the creator came back from its verifier.
+    pub fn on_verified(&mut self, creator: NodeIndex) {
+        if let Some(jobs) = self.in_flight.get_mut(&creator) {
+            jobs.pop_front();
+            if jobs.is_empty() {
+                self.in_flight.remove(&creator);
+            }
+        }
+    }
+
+    /// Marks the units submitted so far, see [`Self::is_verified_up_to`].
+    pub fn checkpoint(&self) -> u64 {
+        self.next_job
+    }
+
+    /// Whether all the units of the creator submitted before the checkpoint came back.
+    pub fn is_verified_up_to(&self, creator: NodeIndex, checkpoint: u64) -> bool {
+        match self.in_flight.get(&creator).and_then(|jobs| jobs.front()) {
+            Some(oldest) => *oldest >= checkpoint,
+            None => true,
+        }
+    }
+
+    /// How many units are being verified.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.values().map(VecDeque::len).sum()
+    }
+}
+
+async fn run_verifier<H: Hasher, D: Data, MK: MultiKeychain>(
+    validator: Validator<MK>,
+    mut jobs: Receiver<Job<H, D, MK>>,
+    results: Sender<Vec<Verified<H, D, MK>>>,
+) {
+    while let Some(job) = jobs.next().await {
+        let mut batch = vec![job];
+        while batch.len() < BATCH_SIZE {
+            match jobs.try_next() {
+                Ok(Some(job)) => batch.push(job),
+                _ => break,
+            }
+        }
+        let verified = batch
+            .into_iter()
+            .map(|(unit, mut trace)| (unit.verify(&validator, &mut trace), trace))
+            .collect();
+        if results.unbounded_send(verified).is_err() {
+            return;
+        }
+        yield_now().await;
+    }
+}
+
+/// Lets other tasks run before checking the next batch. The verifiers block the thread they
+/// run on, and the runway woken up by the results might be waiting for this very thread.
+async fn yield_now() {
+    let mut yielded = false;
+    poll_fn(|cx| match yielded {
+        true => Poll::Ready(()),
+        false => {
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dag::Dag,
+        runway::verification::Verification,
+        units::{random_full_parent_units_up_to, Unit, UnitStore, Validator, WrappedSignedUnit},
+        NodeCount, NodeIndex, Signed,
+    };
+    use aleph_bft_mock::{Data, Hash64, Hasher64, Keychain, Spawner};
+    use futures::StreamExt;
+    use std::time::Duration;
+
+    const N_MEMBERS: NodeCount = NodeCount(4);
+    const SESSION_ID: u64 = 0;
+    const MAX_ROUND: u16 = 2137;
+
+    #[tokio::test]
+    async fn returns_units_of_a_creator_in_order() {
+        let keychains = Keychain::new_vec(N_MEMBERS);
+        let slow_keychain = keychains[0]
+            .clone()
+            .with_verification_delay(Duration::from_millis(1));
+        let validator = Validator::new(SESSION_ID, slow_keychain, MAX_ROUND);
+        let store = UnitStore::<WrappedSignedUnit>::new(N_MEMBERS);
+        let mut dag = Dag::new(validator.clone());
+        let (mut verification, mut results) = Verification::new(&validator, 3, &Spawner::new());
+        assert!(verification.is_offloaded());
+
+        let mut submitted = Vec::new();
+        for unit in random_full_parent_units_up_to(5, N_MEMBERS, SESSION_ID)
+            .into_iter()
+            .flatten()
+        {
+            let creator = unit.creator();
+            let unit = Signed::sign(unit, &keychains[creator.0]).expect("the keychain never fails");
+            submitted.push((creator, unit.hash()));
+            let unit = dag
+                .admit_unit(unit.into())
+                .expect("the unit is not known to be invalid");
+            verification.submit(unit, None);
+        }
+        assert_eq!(verification.in_flight(), submitted.len());
+
+        let checkpoint = verification.checkpoint();
+        let mut returned = Vec::new();
+        let mut added = 0;
+        while returned.len() < submitted.len() {
+            for (unit, mut trace) in results.next().await.expect("the verifiers are running") {
+                assert!(!verification.is_verified_up_to(unit.creator(), checkpoint));
+                verification.on_verified(unit.creator());
+                returned.push((unit.creator(), unit.hash()));
+                added += dag
+                    .add_verified_unit_traced(unit, &store, &mut trace)
+                    .units
+                    .len();
+            }
+        }
+        assert_eq!(verification.in_flight(), 0);
+        assert!(N_MEMBERS
+            .into_iterator()
+            .all(|creator| verification.is_verified_up_to(creator, checkpoint)));
+        assert_eq!(added, submitted.len());
+        for creator in N_MEMBERS.into_iterator() {
+            let of_creator = |units: &[(NodeIndex, Hash64)]| -> Vec<_> {
+                units
+                    .iter()
+                    .filter(|(unit_creator, _)| *unit_creator == creator)
+                    .cloned()
+                    .collect()
+            };
+            assert_eq!(of_creator(&returned), of_creator(&submitted));
+        }
+    }
+
+    #[test]
+    fn checks_nothing_without_verifiers() {
+        let keychain = Keychain::new(N_MEMBERS, NodeIndex(0));
+        let validator = Validator::new(SESSION_ID, keychain, MAX_ROUND);
+        let (verification, _results) =
+            Verification::<Hasher64, Data, Keychain>::new(&validator, 0, &Spawner::new());
+        assert!(!verification.is_offloaded());
+    }
+}
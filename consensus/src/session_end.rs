@@ -0,0 +1,64 @@
+use crate::Round;
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use log::debug;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+const LOG_TARGET: &str = "AlephBFT-session-end";
+
+/// A notification that the session reached its end, as it is bounded by [`crate::Config::max_round`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SessionEnd {
+    /// We created our unit of the max round, and refuse to create any further ones. The session
+    /// goes on, as the last rounds still have to be finalized.
+    CreationEnded(Round),
+    /// The head of the given round, the last one that can be elected with units up to the max
+    /// round, was finalized and delivered, so nothing is ever finalized in the session anymore.
+    /// The session keeps answering requests for its units for the configured grace period, see
+    /// [`crate::Config::with_session_end_grace_period`], and then ends on its own.
+    FinalizationEnded(Round),
+}
+
+impl Display for SessionEnd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            SessionEnd::CreationEnded(round) => {
+                write!(f, "created our last unit, of round {}", round)
+            }
+            SessionEnd::FinalizationEnded(round) => {
+                write!(f, "finalized the last head, of round {}", round)
+            }
+        }
+    }
+}
+
+/// The part of the session end reporting passed to the session, see [`session_end_monitor`].
+#[derive(Clone, Default)]
+pub struct SessionEndMonitor {
+    ends: Option<UnboundedSender<SessionEnd>>,
+}
+
+impl SessionEndMonitor {
+    pub(crate) fn report(&self, end: SessionEnd) {
+        let Some(ends) = &self.ends else {
+            return;
+        };
+        if let Err(e) = ends.unbounded_send(end) {
+            debug!(target: LOG_TARGET, "Nobody receives the session end notification: {}.", e.into_inner());
+        }
+    }
+}
+
+/// Creates a stream of the notifications about the session reaching its end together with the
+/// monitor that should be passed to the session with
+/// [`crate::LocalIO::with_session_end_monitor`]. A member reports [`SessionEnd::CreationEnded`]
+/// and then [`SessionEnd::FinalizationEnded`], an observer only the latter, each at most once,
+/// so the application knows when to start the next session.
+pub fn session_end_monitor() -> (UnboundedReceiver<SessionEnd>, SessionEndMonitor) {
+    let (ends_for_application, ends) = mpsc::unbounded();
+    (
+        ends,
+        SessionEndMonitor {
+            ends: Some(ends_for_application),
+        },
+    )
+}
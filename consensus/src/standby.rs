@@ -0,0 +1,228 @@
+use crate::{
+    units::{UncheckedSignedUnit, Unit},
+    BackupPosition, ClockSource, Data, Hasher, NodeIndex, Receiver, Round, Sender, Signature,
+};
+use codec::Decode;
+use futures::channel::mpsc;
+use log::{info, warn};
+use std::{marker::PhantomData, time::Duration};
+use thiserror::Error;
+
+const LOG_TARGET: &str = "AlephBFT-standby";
+
+/// The part of the backup replication passed to the primary's session, see
+/// [`backup_replication`]. Every unit is replicated right after it is saved to the backup, so
+/// before the session sends it to anyone.
+#[derive(Clone, Default)]
+pub struct BackupReplication {
+    sink: Option<Sender<Vec<u8>>>,
+}
+
+impl BackupReplication {
+    pub(crate) fn replicate(&self, encoded_unit: Vec<u8>) {
+        if let Some(sink) = &self.sink {
+            if sink.unbounded_send(encoded_unit).is_err() {
+                warn!(target: LOG_TARGET, "The replica of the backup is gone.");
+            }
+        }
+    }
+}
+
+/// The encoded units saved to the backup of the primary, in the order they were saved. The
+/// application moves them to the standby host and passes them to [`WarmStandby::new`], see
+/// [`backup_replication`].
+pub struct BackupReplica {
+    units: Receiver<Vec<u8>>,
+}
+
+impl BackupReplica {
+    /// The replica fed with units that were replicated some other way, e.g. over the network
+    /// from the primary's host.
+    pub fn from_receiver(units: Receiver<Vec<u8>>) -> Self {
+        BackupReplica { units }
+    }
+}
+
+/// Creates the replica of the backup of a session together with the replication that should be
+/// passed to the session with [`crate::LocalIO::with_backup_replication`].
+pub fn backup_replication() -> (BackupReplica, BackupReplication) {
+    let (sink, units) = mpsc::unbounded();
+    (
+        BackupReplica { units },
+        BackupReplication { sink: Some(sink) },
+    )
+}
+
+/// Why the standby cannot be promoted.
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum StandbyError {
+    #[error("a replicated unit cannot be decoded")]
+    Codec,
+    #[error("the primary saved {0} more units during the fencing delay")]
+    PrimaryActive(usize),
+}
+
+/// A standby for a committee seat that buffers a hot copy of the primary's backup, so that it
+/// can take over the seat without restoring the backup from elsewhere. It does not run a member:
+/// until it is promoted it only decodes and buffers the replicated units, and the promotion
+/// hands the buffer over to a new session, which replays it like the backup after a crash.
+pub struct WarmStandby<H: Hasher, D: Data, S: Signature> {
+    replica: BackupReplica,
+    node_ix: NodeIndex,
+    clock: ClockSource,
+    backup: Vec<u8>,
+    position: BackupPosition,
+    next_round: Round,
+    _phantom: PhantomData<(H, D, S)>,
+}
+
+impl<H: Hasher, D: Data, S: Signature> WarmStandby<H, D, S> {
+    pub fn new(replica: BackupReplica, node_ix: NodeIndex, clock: ClockSource) -> Self {
+        WarmStandby {
+            replica,
+            node_ix,
+            clock,
+            backup: Vec::new(),
+            position: BackupPosition::default(),
+            next_round: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Adds the units replicated so far to the hot copy of the backup, returning how many were
+    /// added. Meant to be called regularly, so that the promotion has little left to do.
+    pub fn ingest(&mut self) -> Result<usize, StandbyError> {
+        let mut ingested = 0;
+        while let Ok(Some(encoded)) = self.replica.units.try_next() {
+            let unit = UncheckedSignedUnit::<H, D, S>::decode(&mut &encoded[..])
+                .map_err(|_| StandbyError::Codec)?;
+            let full_unit = unit.as_signable();
+            if full_unit.creator() == self.node_ix {
+                self.next_round = self.next_round.max(full_unit.round() + 1);
+            }
+            self.position.advance(encoded.len());
+            self.backup.extend(encoded);
+            ingested += 1;
+        }
+        Ok(ingested)
+    }
+
+    /// How much of the primary's backup the hot copy holds.
+    pub fn backup_position(&self) -> BackupPosition {
+        self.position
+    }
+
+    /// The round of the next unit the node would create, as far as the hot copy shows.
+    pub fn next_round(&self) -> Round {
+        self.next_round
+    }
+
+    /// Ends the buffering once the primary is stopped. Ingests everything replicated so far,
+    /// waits the fencing delay and fails if the primary saved anything in the meantime.
+    /// Otherwise returns the hot copy of the backup, which the application passes as the backup
+    /// to read of a new session for the seat. The copy is also what the backup written by that
+    /// session should start with.
+    ///
+    /// The new session replays the copy from scratch and, before creating anything, checks with
+    /// the rest of the committee that none of its units is missing from it, exactly like after a
+    /// crash.
+    pub async fn promote(mut self, fencing_delay: Duration) -> Result<Vec<u8>, StandbyError> {
+        self.ingest()?;
+        self.clock.sleep(fencing_delay).await;
+        match self.ingest()? {
+            0 => {
+                info!(
+                    target: LOG_TARGET,
+                    "Promoting the standby of {:?}, holding {:?} and continuing from round {}.",
+                    self.node_ix,
+                    self.position,
+                    self.next_round
+                );
+                Ok(self.backup)
+            }
+            saved => Err(StandbyError::PrimaryActive(saved)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        standby::{backup_replication, StandbyError, WarmStandby},
+        units::{full_unit_to_unchecked_signed_unit, random_full_parent_units_up_to},
+        BackupPosition, ClockSource, NodeCount, NodeIndex, Round,
+    };
+    use aleph_bft_mock::{Data, Hasher64, Keychain, Signature};
+    use codec::Encode;
+    use std::time::Duration;
+
+    type TestStandby = WarmStandby<Hasher64, Data, Signature>;
+
+    const N_MEMBERS: NodeCount = NodeCount(4);
+    const MIRRORED: NodeIndex = NodeIndex(1);
+
+    /// The encoded units of the creator from all the rounds up to the given one.
+    fn encoded_units_of(creator: NodeIndex, last_round: Round) -> Vec<Vec<u8>> {
+        let keychain = Keychain::new(N_MEMBERS, creator);
+        random_full_parent_units_up_to(last_round, N_MEMBERS, 0)
+            .into_iter()
+            .map(|round_units| {
+                full_unit_to_unchecked_signed_unit(round_units[creator.0].clone(), &keychain)
+                    .encode()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn promotes_with_the_replicated_backup() {
+        let (replica, replication) = backup_replication();
+        let mut standby = TestStandby::new(replica, MIRRORED, ClockSource::default());
+        let units = encoded_units_of(MIRRORED, 2);
+        for unit in &units[..2] {
+            replication.replicate(unit.clone());
+        }
+        assert_eq!(standby.ingest(), Ok(2));
+        assert_eq!(standby.next_round(), 2);
+        replication.replicate(units[2].clone());
+        drop(replication);
+
+        let backup = standby
+            .promote(Duration::from_millis(10))
+            .await
+            .expect("the primary is stopped");
+        assert_eq!(backup, units.concat());
+    }
+
+    #[tokio::test]
+    async fn refuses_promotion_while_the_primary_saves_units() {
+        let (replica, replication) = backup_replication();
+        let standby = TestStandby::new(replica, MIRRORED, ClockSource::default());
+        let units = encoded_units_of(MIRRORED, 1);
+        replication.replicate(units[0].clone());
+        let still_saving = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            replication.replicate(units[1].clone());
+        };
+        let (promotion, _) =
+            futures::join!(standby.promote(Duration::from_millis(100)), still_saving);
+        assert_eq!(promotion, Err(StandbyError::PrimaryActive(1)));
+    }
+
+    #[test]
+    fn counts_rounds_of_own_units_only() {
+        let (replica, replication) = backup_replication();
+        let mut standby = TestStandby::new(replica, MIRRORED, ClockSource::default());
+        let own_units = encoded_units_of(MIRRORED, 0);
+        let other_units = encoded_units_of(NodeIndex(2), 1);
+        for unit in own_units.iter().chain(other_units.iter()) {
+            replication.replicate(unit.clone());
+        }
+        assert_eq!(standby.ingest(), Ok(3));
+        assert_eq!(standby.next_round(), 1);
+        let bytes = (own_units.concat().len() + other_units.concat().len()) as u64;
+        assert_eq!(
+            standby.backup_position(),
+            BackupPosition { units: 3, bytes }
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{CreatorReception, NodeIndex, NodeMap, Receiver, Round, Sender};
+use futures::channel::{mpsc, oneshot};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// A snapshot of the state of the dag of a running session, e.g. to tell whether a stalled
+/// session is missing units, stuck on a forker or just slow, see [`status_query`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DagStatus {
+    /// The highest round of the units of every creator we hold, absent for the creators we hold
+    /// no units of.
+    pub top_rounds: NodeMap<Round>,
+    /// The units we don't hold but are requesting, by creator and round.
+    pub missing_coords: Vec<(NodeIndex, Round)>,
+    /// How many of our requests for units, by their coords or as the parents of other units,
+    /// are not answered yet.
+    pub pending_requests: usize,
+    /// How many creators we know to have forked.
+    pub known_forkers: usize,
+    /// The highest round of the units ordered so far, absent before the first batch.
+    pub finalized_round: Option<Round>,
+    /// How many units we hold in memory, see [`crate::Config::with_kept_rounds`].
+    pub stored_units: usize,
+    /// How the units of every other creator reached us in our latest rounds, empty for
+    /// observers and before the first rounds, see [`crate::RECEPTION_WINDOW`].
+    pub reception: NodeMap<CreatorReception>,
+}
+
+/// Why querying the status failed.
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum StatusQueryError {
+    #[error("no session is running with the status query")]
+    NotRunning,
+    #[error("the session ended before answering the query")]
+    SessionEnded,
+}
+
+pub(crate) type StatusRequest = oneshot::Sender<DagStatus>;
+
+struct SharedState {
+    listeners: Vec<Sender<StatusRequest>>,
+}
+
+/// Allows the application to look into the dag of a running session, see [`status_query`].
+#[derive(Clone)]
+pub struct StatusQueryHandle {
+    shared: Arc<Mutex<SharedState>>,
+}
+
+impl StatusQueryHandle {
+    /// The current state of the dag of the session that started most recently with the query.
+    pub async fn query(&self) -> Result<DagStatus, StatusQueryError> {
+        let (status_for_handle, status) = oneshot::channel();
+        {
+            let mut shared = self.shared.lock();
+            // Listeners of sessions that already ended are forgotten.
+            let mut request = Some(status_for_handle);
+            while let Some(listener) = shared.listeners.last() {
+                match listener.unbounded_send(request.take().expect("only taken once")) {
+                    Ok(()) => break,
+                    Err(e) => {
+                        request = Some(e.into_inner());
+                        shared.listeners.pop();
+                    }
+                }
+            }
+            if request.is_some() {
+                return Err(StatusQueryError::NotRunning);
+            }
+        }
+        status.await.map_err(|_| StatusQueryError::SessionEnded)
+    }
+}
+
+/// The part of the status query passed to the session, see [`status_query`].
+#[derive(Clone)]
+pub struct StatusQuery {
+    shared: Arc<Mutex<SharedState>>,
+}
+
+impl Default for StatusQuery {
+    /// A query nobody can use to look into the session.
+    fn default() -> Self {
+        status_query().1
+    }
+}
+
+impl StatusQuery {
+    /// Returns the stream of the status requests for the session.
+    pub(crate) fn split(self) -> Receiver<StatusRequest> {
+        let (requests_for_session, requests) = mpsc::unbounded();
+        self.shared.lock().listeners.push(requests_for_session);
+        requests
+    }
+}
+
+/// Creates a handle for querying the state of the dag of a running session together with the
+/// query that should be passed to the session with [`crate::LocalIO::with_status_query`].
+pub fn status_query() -> (StatusQueryHandle, StatusQuery) {
+    let shared = Arc::new(Mutex::new(SharedState {
+        listeners: Vec::new(),
+    }));
+    (
+        StatusQueryHandle {
+            shared: shared.clone(),
+        },
+        StatusQuery { shared },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        status::{status_query, DagStatus, StatusQueryError},
+        NodeIndex,
+    };
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn queries_the_latest_running_session() {
+        let (handle, query) = status_query();
+        assert_eq!(handle.query().await, Err(StatusQueryError::NotRunning));
+        let status = DagStatus {
+            missing_coords: vec![(NodeIndex(2), 7)],
+            pending_requests: 1,
+            ..DagStatus::default()
+        };
+        let mut running = query.clone().split();
+        let ended = query.split();
+        drop(ended);
+        let answered = status.clone();
+        let session = tokio::spawn(async move {
+            let request = running.next().await.expect("the handle asks");
+            request.send(answered).expect("the handle waits");
+        });
+        assert_eq!(handle.query().await, Ok(status));
+        session.await.expect("the session answers");
+    }
+}
@@ -1,3 +1,4 @@
+use crate::ClockSource;
 use std::{
     cmp::Ordering,
     collections::{binary_heap::PeekMut, BinaryHeap},
@@ -28,6 +29,7 @@ impl<T: Eq> Ord for ScheduledTask<T> {
 #[derive(Clone, Default)]
 pub struct TaskQueue<T: Eq + PartialEq> {
     queue: BinaryHeap<ScheduledTask<T>>,
+    clock: ClockSource,
 }
 
 impl<T: Eq + PartialEq> Debug for TaskQueue<T> {
@@ -42,21 +44,22 @@ impl<T: Eq + PartialEq> Debug for TaskQueue<T> {
 ///
 /// Note that this queue is passive - nothing will happen until you call `pop_due_task`.
 impl<T: Eq> TaskQueue<T> {
-    /// Creates an empty queue.
-    pub fn new() -> Self {
+    /// Creates an empty queue telling the time with the given clock.
+    pub fn new(clock: ClockSource) -> Self {
         Self {
             queue: BinaryHeap::new(),
+            clock,
         }
     }
 
     /// Schedules `task` for as soon as possible.
     pub fn schedule_now(&mut self, task: T) {
-        self.schedule(task, time::Instant::now());
+        self.schedule(task, self.clock.now());
     }
 
     /// Schedules `task` for execution after `delay`.
     pub fn schedule_in(&mut self, task: T, delay: Duration) {
-        self.schedule(task, time::Instant::now() + delay)
+        self.schedule(task, self.clock.now() + delay)
     }
 
     /// Schedules `task` for execution at `scheduled_time`.
@@ -72,7 +75,7 @@ impl<T: Eq> TaskQueue<T> {
     pub fn pop_due_task(&mut self) -> Option<T> {
         let scheduled_task = self.queue.peek_mut()?;
 
-        if scheduled_task.scheduled_time <= time::Instant::now() {
+        if scheduled_task.scheduled_time <= self.clock.now() {
             Some(PeekMut::pop(scheduled_task).task)
         } else {
             None
@@ -92,7 +95,7 @@ mod tests {
 
     #[test]
     fn test_scheduling() {
-        let mut q = TaskQueue::new();
+        let mut q = TaskQueue::new(ClockSource::default());
         q.schedule_now(1);
         q.schedule_in(2, Duration::from_millis(5));
         q.schedule_in(3, Duration::from_millis(30));
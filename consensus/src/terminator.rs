@@ -1,6 +1,7 @@
 use futures::{
     channel::oneshot::{channel, Receiver, Sender},
     future::FusedFuture,
+    FutureExt,
 };
 use log::{debug, warn};
 use std::fmt::{Debug, Formatter};
@@ -61,6 +62,13 @@ impl Terminator {
             .to_owned()
     }
 
+    /// Whether we should exit already, without waiting for it. Components check it before
+    /// handling every message, so that however many messages are waiting, they exit after handling
+    /// at most one of them.
+    pub fn exit_requested(&mut self) -> bool {
+        self.get_exit().now_or_never().is_some()
+    }
+
     /// Add a connection to an offspring component/task
     pub fn add_offspring_connection(&mut self, name: &'static str) -> Terminator {
         let (exit_send, exit_recv) = channel();
@@ -292,4 +300,14 @@ mod tests {
         let terminator = Terminator::create_root(exit_rx, "root");
         root_component(terminator, true).await;
     }
+
+    #[test]
+    fn exit_requested_does_not_wait() {
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let mut terminator = Terminator::create_root(exit_rx, "root");
+        assert!(!terminator.exit_requested());
+        exit_tx.send(()).expect("should send");
+        assert!(terminator.exit_requested());
+        assert!(terminator.exit_requested());
+    }
 }
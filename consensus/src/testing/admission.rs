@@ -0,0 +1,75 @@
+use crate::{
+    admission_monitor,
+    testing::{init_log, spawn_honest_member, HonestMemberBuilder},
+    AdmissionStage, NodeCount, NodeIndex, SpawnHandle,
+};
+use aleph_bft_mock::{DataProvider, Router, Spawner};
+use futures::StreamExt;
+use serial_test::serial;
+use std::time::Duration;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const TRACED: NodeIndex = NodeIndex(0);
+const VERIFICATION_DELAY: Duration = Duration::from_millis(5);
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn slow_verification_shows_in_its_stage_only() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut others = Vec::new();
+    let mut traced = None;
+    for (network, _) in networks {
+        match network.index() {
+            TRACED => traced = Some(network),
+            node_ix => others.push(spawn_honest_member(
+                spawner,
+                node_ix,
+                N_MEMBERS,
+                vec![],
+                DataProvider::new(),
+                network,
+            )),
+        }
+    }
+    let network = traced.expect("the traced node is a member");
+    let (admission_handle, admission_monitor) = admission_monitor();
+    let mut traced = HonestMemberBuilder::new(TRACED, N_MEMBERS)
+        .with_keychain(|keychain| keychain.with_verification_delay(VERIFICATION_DELAY))
+        .with_local_io(|local_io| local_io.with_admission_monitor(admission_monitor))
+        .spawn(spawner, network);
+
+    let finalized: Vec<_> = (&mut traced.finalization_rx).take(20).collect().await;
+    assert_eq!(finalized.len(), 20);
+    traced.stop().await;
+    for member in others {
+        member.stop().await;
+    }
+
+    let stats = admission_handle.stats();
+    let median = |stage| {
+        stats
+            .stage(stage)
+            .quantile(0.5)
+            .expect("units went through every stage")
+    };
+    assert!(median(AdmissionStage::Verification) >= VERIFICATION_DELAY);
+    // Units wait in the runway queue while the ones before them are verified, so that stage
+    // grows too, as it should.
+    for stage in [
+        AdmissionStage::NetworkQueue,
+        AdmissionStage::Validation,
+        AdmissionStage::Reconstruction,
+    ] {
+        assert!(
+            median(stage) < VERIFICATION_DELAY,
+            "{} takes {:?}",
+            stage,
+            median(stage)
+        );
+    }
+    assert!(stats.total().count() >= stats.stage(AdmissionStage::Reconstruction).count());
+}
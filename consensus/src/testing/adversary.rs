@@ -0,0 +1,495 @@
+use crate::{
+    alerts::{Alert, AlertMessage::ForkAlert},
+    events::{AlertState, InternalEvent},
+    member::UnitMessage::{NewUnit, RequestParents, ResponseParents},
+    misconduct_monitor,
+    network::NetworkDataInner::{Alert as AlertData, Units},
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member_with_events, HonestMember,
+        HonestMemberBuilder, Network, NetworkData, TestEventBus,
+    },
+    units::{ControlHash, FullUnit, PreUnit, SignedUnit, UncheckedSignedUnit, Unit, UnitCoord},
+    Network as NetworkT, NetworkData as NetworkDataT, NodeCount, NodeIndex, NodeMap, Recipient,
+    Round, SessionId, Signed, SpawnHandle, TaskHandle,
+};
+use aleph_bft_mock::{
+    Data, DataProvider, Hash64, Hasher64, Keychain, PartialMultisignature, Router, Signature,
+    Spawner,
+};
+use futures::{channel::oneshot, StreamExt};
+use log::{debug, error, trace};
+use parking_lot::Mutex;
+use serial_test::serial;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::time::{sleep, timeout};
+
+type AdversaryUnit = SignedUnit<Hasher64, Data, Keychain>;
+
+/// Who gets which variant of the forked unit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ForkDelivery {
+    /// Everyone gets both variants, so the fork is evident right away.
+    Everyone,
+    /// Every peer gets only one of the variants, depending on the parity of its index.
+    Split,
+}
+
+/// A scripted malicious member, misbehaving in the ways it is built with. Otherwise it creates
+/// its units like an honest member would, on top of all the units of the previous round it
+/// holds, and ignores all the messages but new units and requests for parents.
+#[derive(Clone, Debug)]
+pub(crate) struct Adversary {
+    node_ix: NodeIndex,
+    n_members: NodeCount,
+    session_id: SessionId,
+    fork: Option<(Round, ForkDelivery)>,
+    wrong_alert: bool,
+    mismatched_parents: bool,
+}
+
+impl Adversary {
+    /// An adversary that behaves, until built otherwise.
+    pub fn new(node_ix: NodeIndex, n_members: NodeCount) -> Self {
+        Adversary {
+            node_ix,
+            n_members,
+            session_id: 0,
+            fork: None,
+            wrong_alert: false,
+            mismatched_parents: false,
+        }
+    }
+
+    /// Creates two variants of its unit of the given round, sending both to everyone.
+    pub fn with_fork(self, round: Round) -> Self {
+        Adversary {
+            fork: Some((round, ForkDelivery::Everyone)),
+            ..self
+        }
+    }
+
+    /// Creates two variants of its unit of the given round, sending different variants to
+    /// different peers.
+    pub fn with_split_fork(self, round: Round) -> Self {
+        Adversary {
+            fork: Some((round, ForkDelivery::Split)),
+            ..self
+        }
+    }
+
+    /// Raises an alert about its own fork, committing to both variants as its legit units,
+    /// which no correct commitment can contain.
+    pub fn with_wrong_alert(self, wrong_alert: bool) -> Self {
+        Adversary {
+            wrong_alert,
+            ..self
+        }
+    }
+
+    /// Answers the requests for the parents of units with parents that do not match their
+    /// control hashes.
+    pub fn with_mismatched_parents(self, mismatched_parents: bool) -> Self {
+        Adversary {
+            mismatched_parents,
+            ..self
+        }
+    }
+
+    pub fn spawn(self, spawner: Spawner, network: Network) -> (oneshot::Sender<()>, TaskHandle) {
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let keychain = Keychain::new(self.n_members, self.node_ix);
+        let member = AdversaryMember {
+            adversary: self,
+            keychain,
+            network,
+            units: HashMap::new(),
+            units_by_hash: HashMap::new(),
+        };
+        let handle = spawner.spawn_essential("adversary", member.run(exit_rx));
+        (exit_tx, handle)
+    }
+}
+
+struct AdversaryMember {
+    adversary: Adversary,
+    keychain: Keychain,
+    network: Network,
+    units: HashMap<UnitCoord, AdversaryUnit>,
+    units_by_hash: HashMap<Hash64, AdversaryUnit>,
+}
+
+impl AdversaryMember {
+    fn index(&self) -> NodeIndex {
+        self.adversary.node_ix
+    }
+
+    fn n_members(&self) -> NodeCount {
+        self.adversary.n_members
+    }
+
+    /// The parents for our unit of the given round, if we hold our previous unit and enough of
+    /// the others.
+    fn pick_parents(&self, round: Round) -> Option<NodeMap<(Hash64, Round)>> {
+        let mut parents = NodeMap::with_size(self.n_members());
+        if round == 0 {
+            return Some(parents);
+        }
+        if !self
+            .units
+            .contains_key(&UnitCoord::new(round - 1, self.index()))
+        {
+            return None;
+        }
+        for creator in self.n_members().into_iterator() {
+            if let Some(unit) = self.units.get(&UnitCoord::new(round - 1, creator)) {
+                parents.insert(creator, (unit.as_signable().hash(), round - 1));
+            }
+        }
+        match NodeCount(parents.item_count()) >= self.n_members().consensus_threshold() {
+            true => Some(parents),
+            false => None,
+        }
+    }
+
+    fn sign(&self, pre_unit: PreUnit<Hasher64>, data: Data) -> AdversaryUnit {
+        let full_unit = FullUnit::new(pre_unit, Some(data), self.adversary.session_id);
+        Signed::sign(full_unit, &self.keychain).expect("the keychain never fails")
+    }
+
+    fn send_unit(&self, unit: &AdversaryUnit, recipient: Recipient) {
+        let message = NetworkDataT(Units(NewUnit(unit.clone().into())), 0);
+        self.network.send(message, recipient);
+    }
+
+    fn create_if_possible(&mut self, round: Round) -> bool {
+        let parents = match self.pick_parents(round) {
+            Some(parents) => parents,
+            None => return false,
+        };
+        let pre_unit = PreUnit::new(self.index(), round, ControlHash::new(&parents));
+        match self.adversary.fork {
+            Some((forking_round, delivery)) if forking_round == round => {
+                debug!(target: "adversary", "Creating forks for round {}.", round);
+                let variants = [self.sign(pre_unit.clone(), 0), self.sign(pre_unit, 1)];
+                self.on_unit_received(variants[0].clone());
+                for peer in self.n_members().into_iterator() {
+                    if peer == self.index() {
+                        continue;
+                    }
+                    match delivery {
+                        ForkDelivery::Everyone => {
+                            for variant in &variants {
+                                self.send_unit(variant, Recipient::Node(peer));
+                            }
+                        }
+                        ForkDelivery::Split => {
+                            self.send_unit(&variants[peer.0 % 2], Recipient::Node(peer))
+                        }
+                    }
+                }
+                if self.adversary.wrong_alert {
+                    self.raise_wrong_alert(variants);
+                }
+            }
+            _ => {
+                debug!(target: "adversary", "Creating a unit for round {}.", round);
+                let unit = self.sign(pre_unit, 0);
+                self.on_unit_received(unit.clone());
+                self.send_unit(&unit, Recipient::Everyone);
+            }
+        }
+        true
+    }
+
+    fn raise_wrong_alert(&self, variants: [AdversaryUnit; 2]) {
+        let [first, second] = variants.map(UncheckedSignedUnit::from);
+        let alert = Alert::new(
+            self.index(),
+            (first.clone(), second.clone()),
+            vec![first, second],
+        );
+        debug!(target: "adversary", "Raising an alert with a wrong commitment.");
+        let alert = Signed::sign(alert, &self.keychain).expect("the keychain never fails");
+        let message = NetworkDataT(AlertData(ForkAlert(alert.into_unchecked())), 0);
+        self.network.send(message, Recipient::Everyone);
+    }
+
+    /// Answers with the units of the previous round at the coords of the parents, but with the
+    /// last of them replaced by the first one.
+    fn answer_parents_request(&self, requester: NodeIndex, hash: Hash64) {
+        let unit = match self.units_by_hash.get(&hash) {
+            Some(unit) => unit,
+            None => return,
+        };
+        let mut parents: Vec<UncheckedSignedUnit<_, _, _>> = Vec::new();
+        for coord in unit.as_signable().control_hash().parents() {
+            match self.units.get(&coord) {
+                Some(parent) => parents.push(parent.clone().into()),
+                None => return,
+            }
+        }
+        if parents.len() < 2 {
+            return;
+        }
+        debug!(target: "adversary", "Sending mismatched parents of {:?} to {:?}.", hash, requester);
+        let last = parents.len() - 1;
+        parents[last] = parents[0].clone();
+        let message = NetworkDataT(Units(ResponseParents(hash, parents)), 0);
+        self.network.send(message, Recipient::Node(requester));
+    }
+
+    fn on_unit_received(&mut self, unit: AdversaryUnit) {
+        // Keeping a single version of a unit at every coord is enough to create units.
+        self.units_by_hash
+            .insert(unit.as_signable().hash(), unit.clone());
+        self.units.insert(unit.as_signable().coord(), unit);
+    }
+
+    fn on_network_data(&mut self, data: NetworkData) {
+        match data {
+            NetworkDataT(Units(NewUnit(unchecked)), _) => {
+                trace!(target: "adversary", "New unit received {:?}.", &unchecked);
+                match unchecked.check(&self.keychain) {
+                    Ok(unit) => self.on_unit_received(unit),
+                    Err(unchecked) => panic!("Wrong signature received {:?}.", &unchecked),
+                }
+            }
+            NetworkDataT(Units(RequestParents(requester, hash)), _)
+                if self.adversary.mismatched_parents =>
+            {
+                self.answer_parents_request(requester, hash)
+            }
+            _ => {}
+        }
+    }
+
+    async fn run(mut self, mut exit: oneshot::Receiver<()>) {
+        let mut round: Round = 0;
+        loop {
+            if self.create_if_possible(round) {
+                round += 1;
+            }
+            tokio::select! {
+                event = self.network.next_event() => match event {
+                    Some(data) => self.on_network_data(data),
+                    None => {
+                        error!(target: "adversary", "Network message stream closed.");
+                        break;
+                    }
+                },
+                _ = &mut exit => break,
+            }
+        }
+    }
+}
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const ADVERSARY: NodeIndex = NodeIndex(3);
+const FORKING_ROUND: Round = 2;
+/// Enough batches for finalization to get well past the fork.
+const N_BATCHES: usize = 10;
+
+/// What the honest members observed, accumulated from their events.
+#[derive(Default)]
+struct Observed {
+    confirmed: HashSet<NodeIndex>,
+    reported: Vec<(NodeIndex, NodeIndex)>,
+}
+
+/// Runs the adversary against three honest members, checking that they all confirm an alert,
+/// agree on the batches finalized well past the fork and never report each other.
+async fn honest_majority_catches(adversary: Adversary) {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let observed = Arc::new(Mutex::new(Observed::default()));
+    let mut finalization_rxs = Vec::new();
+    let mut exits = Vec::new();
+    let mut handles = Vec::new();
+    let mut adversary = Some(adversary);
+    for (network, _) in networks {
+        let ix = network.index();
+        if ix == ADVERSARY {
+            let adversary = adversary.take().expect("there is a single adversary");
+            let (exit_tx, handle) = adversary.spawn(spawner, network);
+            exits.push(exit_tx);
+            handles.push(handle);
+            continue;
+        }
+        let events = TestEventBus::new();
+        let mut consumer = events.subscribe();
+        let observed = observed.clone();
+        tokio::spawn(async move {
+            while let Some(event) = consumer.next().await {
+                match event {
+                    InternalEvent::AlertStateChanged(_, AlertState::Confirmed) => {
+                        observed.lock().confirmed.insert(ix);
+                    }
+                    InternalEvent::PeerMisbehaved(peer, _) => {
+                        observed.lock().reported.push((ix, peer));
+                    }
+                    _ => {}
+                }
+            }
+        });
+        let HonestMember {
+            finalization_rx,
+            exit_tx,
+            handle,
+            ..
+        } = spawn_honest_member_with_events(
+            spawner,
+            gen_config(ix, N_MEMBERS, gen_delay_config()),
+            vec![],
+            DataProvider::new(),
+            network,
+            events,
+        );
+        finalization_rxs.push(finalization_rx);
+        exits.push(exit_tx);
+        handles.push(handle);
+    }
+
+    let mut batches = Vec::new();
+    for rx in &mut finalization_rxs {
+        let finalized: Vec<_> = timeout(Duration::from_secs(30), rx.take(N_BATCHES).collect())
+            .await
+            .expect("the honest members should keep finalizing");
+        batches.push(finalized);
+    }
+    timeout(Duration::from_secs(30), async {
+        while observed.lock().confirmed.len() < batches.len() {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("every honest member should confirm an alert about the adversary");
+    for exit in exits {
+        let _ = exit.send(());
+    }
+    for handle in handles {
+        let _ = handle.await;
+    }
+
+    for finalized in &batches[1..] {
+        assert_eq!(finalized, &batches[0]);
+    }
+    for (reporter, peer) in &observed.lock().reported {
+        assert_eq!(*peer, ADVERSARY, "{:?} reported an honest peer", reporter);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn forker_is_caught() {
+    honest_majority_catches(Adversary::new(ADVERSARY, N_MEMBERS).with_fork(FORKING_ROUND)).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn equivocating_forker_is_caught() {
+    honest_majority_catches(Adversary::new(ADVERSARY, N_MEMBERS).with_split_fork(FORKING_ROUND))
+        .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn forker_committing_to_its_forks_is_caught() {
+    honest_majority_catches(
+        Adversary::new(ADVERSARY, N_MEMBERS)
+            .with_split_fork(FORKING_ROUND)
+            .with_wrong_alert(true),
+    )
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn forker_sending_mismatched_parents_is_caught() {
+    honest_majority_catches(
+        Adversary::new(ADVERSARY, N_MEMBERS)
+            .with_split_fork(FORKING_ROUND)
+            .with_mismatched_parents(true),
+    )
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn forker_is_reported_once() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut reports = Vec::new();
+    let mut finalization_rxs = Vec::new();
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let ix = network.index();
+        if ix == ADVERSARY {
+            // Everyone gets both variants, so every honest member raises an alert of its own.
+            let adversary = Adversary::new(ADVERSARY, N_MEMBERS).with_fork(FORKING_ROUND);
+            members.push(adversary.spawn(spawner, network));
+            continue;
+        }
+        let (misconduct, misconduct_monitor) = misconduct_monitor();
+        let member = HonestMemberBuilder::new(ix, N_MEMBERS)
+            .with_local_io(|local_io| local_io.with_misconduct_monitor(misconduct_monitor))
+            .spawn(spawner, network);
+        reports.push(misconduct);
+        finalization_rxs.push(member.finalization_rx);
+        members.push((member.exit_tx, member.handle));
+    }
+
+    // By then all the alerts about the fork got confirmed.
+    for rx in &mut finalization_rxs {
+        let _: Vec<_> = timeout(Duration::from_secs(30), rx.take(N_BATCHES).collect())
+            .await
+            .expect("the honest members should keep finalizing");
+    }
+    let mut first_reports = Vec::new();
+    for misconduct in &mut reports {
+        let report = timeout(Duration::from_secs(30), misconduct.next())
+            .await
+            .expect("every honest member should report the forker")
+            .expect("the session is still running");
+        first_reports.push(report);
+    }
+    for (exit_tx, handle) in members {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    let keychain = Keychain::new(N_MEMBERS, NodeIndex(0));
+    for (report, mut misconduct) in first_reports.into_iter().zip(reports) {
+        assert_eq!(report.forker, ADVERSARY);
+        let (first, second) = report
+            .fork_proof::<Data, Signature>()
+            .expect("the fork proof should decode");
+        let first = first.check(&keychain).expect("the forker signed the unit");
+        let second = second.check(&keychain).expect("the forker signed the unit");
+        for unit in [&first, &second] {
+            assert_eq!(unit.as_signable().creator(), ADVERSARY);
+            assert_eq!(unit.as_signable().round(), FORKING_ROUND);
+        }
+        assert_ne!(first.as_signable().hash(), second.as_signable().hash());
+        let multisigned = report
+            .multisignature::<PartialMultisignature>()
+            .expect("the multisignature should decode")
+            .check_multi(&keychain)
+            .expect("a quorum signed the alert");
+        assert_eq!(*multisigned.as_signable(), report.alert);
+        assert_eq!(
+            misconduct.next().await,
+            None,
+            "the forker should be reported only once"
+        );
+    }
+}
@@ -1,11 +1,17 @@
 use crate::{
     alerts::{Alert, AlertMessage, ForkProof, ForkingNotification, Handler, Service},
-    units::{ControlHash, FullUnit, PreUnit},
-    Index, Indexed, Keychain as _, NodeCount, NodeIndex, NodeMap, Recipient, Round, Signable,
-    Signed, Terminator, UncheckedSigned,
+    drops::DropMonitor,
+    events::{EventBus, InternalEvent},
+    misconduct::MisconductMonitor,
+    units::{ControlHash, FullUnit, PreUnit, Unit},
+    AlertRateLimit, ClockSource, Index, Indexed, Keychain as _, MultiKeychain, NodeCount,
+    NodeIndex, NodeMap, Recipient, Role, Round, Signable, Signed, Terminator, UncheckedSigned,
+};
+use aleph_bft_mock::{
+    Data, FailingSigning, Hasher64, Keychain, PartialMultisignature, Signature, TokioClock,
 };
-use aleph_bft_mock::{Data, Hasher64, Keychain, PartialMultisignature, Signature};
 use aleph_bft_rmc::Message as RmcMessage;
+use codec::Encode;
 use futures::{
     channel::{mpsc, oneshot},
     FutureExt, StreamExt,
@@ -17,6 +23,7 @@ use std::{
     hash::Hash,
     time::Duration,
 };
+use tokio::time::sleep;
 
 type TestMessage = AlertMessage<Hasher64, Data, Signature, PartialMultisignature>;
 type TestAlert = Alert<Hasher64, Data, Signature>;
@@ -92,7 +99,9 @@ impl TestCase {
         to_sign: T,
         signer: NodeIndex,
     ) -> UncheckedSigned<T, Signature> {
-        Signed::sign(to_sign, self.keychain(signer)).into()
+        Signed::sign(to_sign, self.keychain(signer))
+            .expect("the keychain never fails")
+            .into()
     }
 
     fn indexed_unchecked_signed<T: Signable>(
@@ -100,7 +109,9 @@ impl TestCase {
         to_sign: T,
         signer: NodeIndex,
     ) -> UncheckedSigned<Indexed<T>, Signature> {
-        Signed::sign_with_index(to_sign, self.keychain(signer)).into()
+        Signed::sign_with_index(to_sign, self.keychain(signer))
+            .expect("the keychain never fails")
+            .into()
     }
 
     fn full_unit(&self, forker: NodeIndex, round: Round, variant: u32) -> TestFullUnit {
@@ -208,14 +219,19 @@ impl TestCase {
         self
     }
 
-    async fn test(self, keychain: Keychain) {
+    async fn test<
+        MK: MultiKeychain<Signature = Signature, PartialMultisignature = PartialMultisignature>,
+    >(
+        self,
+        keychain: MK,
+    ) {
         let (messages_for_network, mut messages_from_alerter) = mpsc::unbounded();
         let (messages_for_alerter, messages_from_network) = mpsc::unbounded();
         let (notifications_for_units, mut notifications_from_alerter) = mpsc::unbounded();
         let (alerts_for_alerter, alerts_from_units) = mpsc::unbounded();
         let (exit_alerter_tx, exit_alerter_rx) = oneshot::channel();
 
-        let alerter_handler = Handler::new(keychain, 0);
+        let alerter_handler = Handler::new(keychain.clone(), 0);
         let mut alerter_service = Service::new(
             keychain,
             crate::alerts::IO {
@@ -223,6 +239,13 @@ impl TestCase {
                 messages_from_network,
                 notifications_for_units,
                 alerts_from_units,
+                events: EventBus::new(),
+                clock: ClockSource::default(),
+                alert_rate_limit: None,
+                rmc_completion_replies: false,
+                misconduct: MisconductMonitor::default(),
+                drops: DropMonitor::default(),
+                role: Role::Member,
             },
             alerter_handler,
         );
@@ -267,8 +290,19 @@ impl TestCase {
     }
 
     async fn run(self, run_as: NodeIndex) {
-        let keychain = *self.keychain(run_as);
-        let mut timeout = Delay::new(Duration::from_millis(500)).fuse();
+        let keychain = self.keychain(run_as).clone();
+        self.run_with_keychain(keychain, Duration::from_millis(500))
+            .await;
+    }
+
+    async fn run_with_keychain<
+        MK: MultiKeychain<Signature = Signature, PartialMultisignature = PartialMultisignature>,
+    >(
+        self,
+        keychain: MK,
+        timeout: Duration,
+    ) {
+        let mut timeout = Delay::new(timeout).fuse();
         futures::select! {
             _ = self.test(keychain).fuse() => {},
             _ = timeout => {
@@ -292,6 +326,25 @@ async fn distributes_alert_from_units() {
     test_case.run(own_index).await;
 }
 
+#[tokio::test]
+async fn retries_signing_own_alert() {
+    let n_members = NodeCount(7);
+    let own_index = NodeIndex(0);
+    let forker = NodeIndex(6);
+    let mut test_case = TestCase::new(n_members);
+    let alert = test_case.alert(own_index, test_case.fork_proof(forker, 0));
+    let signed_alert = test_case.unchecked_signed(alert.clone(), own_index);
+    let keychain =
+        FailingSigning::new(test_case.keychain(own_index).clone(), |attempt| attempt < 3);
+    test_case
+        .incoming_alert(alert.clone())
+        .outgoing_message(AlertMessage::ForkAlert(signed_alert), Recipient::Everyone);
+    test_case
+        .run_with_keychain(keychain.clone(), Duration::from_secs(5))
+        .await;
+    assert!(keychain.attempts() > 3);
+}
+
 #[tokio::test]
 async fn reacts_to_correctly_incoming_alert() {
     let n_members = NodeCount(7);
@@ -499,3 +552,378 @@ async fn notifies_only_about_multisigned_alert() {
         .unexpected_notification(ForkingNotification::Units(Vec::new()));
     test_case.run(own_index).await;
 }
+
+/// Drains the alerts we sent out so far, returning their forkers.
+fn sent_alerts_about(
+    messages: &mut mpsc::UnboundedReceiver<(TestMessage, Recipient)>,
+) -> HashSet<NodeIndex> {
+    let mut forkers = HashSet::new();
+    while let Ok(Some((message, _))) = messages.try_next() {
+        if let AlertMessage::ForkAlert(alert) = message {
+            forkers.extend(alert.as_signable().forkers());
+        }
+    }
+    forkers
+}
+
+// The runtime time is paused, so the rate window passes without actually waiting.
+#[tokio::test(start_paused = true)]
+async fn throttles_own_alerts() {
+    let n_members = NodeCount(21);
+    let own_index = NodeIndex(0);
+    let rate_window = Duration::from_secs(60);
+    let test_case = TestCase::new(n_members);
+    let (messages_for_network, mut messages_from_alerter) = mpsc::unbounded();
+    let (_messages_for_alerter, messages_from_network) = mpsc::unbounded();
+    let (notifications_for_units, _notifications_from_alerter) = mpsc::unbounded();
+    let (alerts_for_alerter, alerts_from_units) = mpsc::unbounded();
+    let (exit_alerter_tx, exit_alerter_rx) = oneshot::channel();
+    let events = EventBus::new();
+    let mut observed_events = events.subscribe();
+    let mut alerter_service = Service::new(
+        test_case.keychain(own_index).clone(),
+        crate::alerts::IO {
+            messages_for_network,
+            messages_from_network,
+            notifications_for_units,
+            alerts_from_units,
+            events,
+            clock: ClockSource::new(TokioClock),
+            alert_rate_limit: Some(AlertRateLimit {
+                per_minute: 3,
+                per_session: 10,
+            }),
+            rmc_completion_replies: false,
+            misconduct: MisconductMonitor::default(),
+            drops: DropMonitor::default(),
+            role: Role::Member,
+        },
+        Handler::new(test_case.keychain(own_index).clone(), 0),
+    );
+    let alerter = tokio::spawn(async move {
+        alerter_service
+            .run(Terminator::create_root(exit_alerter_rx, "AlephBFT-alerter"))
+            .await
+    });
+
+    for forker in 1..n_members.0 {
+        let alert = test_case.alert(own_index, test_case.fork_proof(NodeIndex(forker), 0));
+        alerts_for_alerter
+            .unbounded_send(alert)
+            .expect("the alert channel works");
+    }
+    let forkers = |range: std::ops::Range<usize>| range.map(NodeIndex).collect::<HashSet<_>>();
+
+    sleep(rate_window / 2).await;
+    assert_eq!(sent_alerts_about(&mut messages_from_alerter), forkers(1..4));
+    let mut queued = HashSet::new();
+    let mut throttling = false;
+    while let Ok(Some(event)) = observed_events.try_next() {
+        match event {
+            InternalEvent::AlertQueued((unit, _)) => {
+                queued.insert(unit.as_signable().creator());
+            }
+            InternalEvent::AlertThrottlingChanged(changed) => throttling = changed,
+            _ => {}
+        }
+    }
+    assert_eq!(queued, forkers(4..21));
+    assert!(throttling);
+
+    // The queue drains as the rate window moves, until the session cap is reached.
+    sleep(rate_window / 2 + Duration::from_secs(1)).await;
+    assert_eq!(sent_alerts_about(&mut messages_from_alerter), forkers(4..7));
+    sleep(rate_window).await;
+    assert_eq!(
+        sent_alerts_about(&mut messages_from_alerter),
+        forkers(7..10)
+    );
+    sleep(rate_window).await;
+    assert_eq!(
+        sent_alerts_about(&mut messages_from_alerter),
+        forkers(10..11)
+    );
+    sleep(10 * rate_window).await;
+    assert!(sent_alerts_about(&mut messages_from_alerter).is_empty());
+    while let Ok(Some(event)) = observed_events.try_next() {
+        assert!(!matches!(
+            event,
+            InternalEvent::AlertThrottlingChanged(false)
+        ));
+    }
+
+    exit_alerter_tx
+        .send(())
+        .expect("exit channel shouldn't be closed");
+    alerter.await.expect("the alerter should exit cleanly");
+}
+
+type LargeData = Vec<u8>;
+type LargeSignedUnit = UncheckedSigned<FullUnit<Hasher64, LargeData>, Signature>;
+
+const LARGE_DATA_SIZE: usize = 10_000;
+const FORK_ROUND: Round = 20;
+const N_ALERTERS: usize = 3;
+
+fn large_unit(keychain: &Keychain, round: Round, variant: u8) -> LargeSignedUnit {
+    let unit = FullUnit::new(
+        PreUnit::new(
+            keychain.index(),
+            round,
+            ControlHash::new(&NodeMap::with_size(keychain.node_count())),
+        ),
+        Some(vec![variant; LARGE_DATA_SIZE]),
+        0,
+    );
+    Signed::sign(unit, keychain)
+        .expect("the keychain never fails")
+        .into()
+}
+
+/// Passes the message on to the honest nodes, returning how many bytes got sent.
+fn deliver<M: Clone + Encode>(
+    nodes: &[mpsc::UnboundedSender<M>],
+    sender: NodeIndex,
+    message: M,
+    recipient: Recipient,
+) -> usize {
+    let recipients: Vec<_> = match recipient {
+        Recipient::Everyone => (0..nodes.len()).filter(|node| *node != sender.0).collect(),
+        Recipient::Node(node) if node.0 < nodes.len() => vec![node.0],
+        Recipient::Node(_) => Vec::new(),
+    };
+    for node in &recipients {
+        let _ = nodes[*node].unbounded_send(message.clone());
+    }
+    recipients.len() * message.encoded_size()
+}
+
+/// The bytes sent between the honest nodes until each of them passed on the units of every
+/// alert. Most of the honest nodes raise an alert about the same forker committing to the same
+/// units, the remaining one didn't see the fork and holds none of them.
+async fn alert_storm_bytes(compact_alerts: bool) -> usize {
+    let n_members = NodeCount(5);
+    let forker = NodeIndex(4);
+    let keychains: Vec<_> = (0..n_members.0)
+        .map(|node| Keychain::new(n_members, NodeIndex(node)))
+        .collect();
+    let legit_units: Vec<_> = (0..FORK_ROUND)
+        .map(|round| large_unit(&keychains[forker.0], round, 0))
+        .collect();
+    let proof = (
+        large_unit(&keychains[forker.0], FORK_ROUND, 0),
+        large_unit(&keychains[forker.0], FORK_ROUND, 1),
+    );
+
+    let mut incoming = Vec::new();
+    let mut outgoing = Vec::new();
+    let mut notifications = Vec::new();
+    let mut exits = Vec::new();
+    for keychain in &keychains[..forker.0] {
+        let (messages_for_network, messages_from_alerter) = mpsc::unbounded();
+        let (messages_for_alerter, messages_from_network) = mpsc::unbounded();
+        let (notifications_for_units, notifications_from_alerter) = mpsc::unbounded();
+        let (alerts_for_alerter, alerts_from_units) = mpsc::unbounded();
+        let (exit_alerter_tx, exit_alerter_rx) = oneshot::channel();
+        let handler = Handler::new(keychain.clone(), 0)
+            .with_max_legit_units(FORK_ROUND as usize)
+            .with_compact_alerts(compact_alerts);
+        let mut alerter_service = Service::new(
+            keychain.clone(),
+            crate::alerts::IO {
+                messages_for_network,
+                messages_from_network,
+                notifications_for_units,
+                alerts_from_units,
+                events: EventBus::new(),
+                clock: ClockSource::default(),
+                alert_rate_limit: None,
+                rmc_completion_replies: false,
+                misconduct: MisconductMonitor::default(),
+                drops: DropMonitor::default(),
+                role: Role::Member,
+            },
+            handler,
+        );
+        tokio::spawn(async move {
+            alerter_service
+                .run(Terminator::create_root(exit_alerter_rx, "AlephBFT-alerter"))
+                .await
+        });
+        let node = keychain.index();
+        if node.0 < N_ALERTERS {
+            alerts_for_alerter
+                .unbounded_send(Alert::new(node, proof.clone(), legit_units.clone()))
+                .expect("the alert channel works");
+        }
+        incoming.push(messages_for_alerter);
+        outgoing.push(messages_from_alerter.map(move |message| (node, message)));
+        notifications.push(notifications_from_alerter);
+        exits.push((exit_alerter_tx, alerts_for_alerter));
+    }
+    let mut outgoing = futures::stream::select_all(outgoing);
+    let mut notifications = futures::stream::select_all(notifications);
+
+    // The alerts are only passed on once all of them were raised, so that no alerter learns about
+    // the fork from the others first.
+    let mut held = Vec::new();
+    let mut raised = HashSet::new();
+    let mut bytes = 0;
+    let mut units_notifications = 0;
+    let expected_notifications = N_ALERTERS * forker.0;
+    let storm = async {
+        while units_notifications < expected_notifications {
+            tokio::select! {
+                Some((sender, (message, recipient))) = outgoing.next() => {
+                    if matches!(
+                        message,
+                        AlertMessage::ForkAlert(_) | AlertMessage::CompactForkAlert(_)
+                    ) {
+                        raised.insert(sender);
+                    }
+                    held.push((sender, message, recipient));
+                    if raised.len() == N_ALERTERS {
+                        for (sender, message, recipient) in held.drain(..) {
+                            bytes += deliver(&incoming, sender, message, recipient);
+                        }
+                    }
+                }
+                Some(notification) = notifications.next() => {
+                    if let ForkingNotification::Units(units) = notification {
+                        assert_eq!(units, legit_units);
+                        units_notifications += 1;
+                    }
+                }
+            }
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(30), storm)
+        .await
+        .expect("every node should pass on the units of every alert");
+    for (exit_alerter_tx, _) in exits {
+        let _ = exit_alerter_tx.send(());
+    }
+    bytes
+}
+
+#[tokio::test]
+async fn compact_alerts_send_less() {
+    let full = alert_storm_bytes(false).await;
+    let compact = alert_storm_bytes(true).await;
+    assert!(
+        2 * compact < full,
+        "compact alerts took {} bytes, full ones {}",
+        compact,
+        full
+    );
+}
+
+/// Several nodes fork in the same round, every honest node that noticed raises a single alert
+/// about all of them, so each honest node takes part in one RMC per alerter.
+#[tokio::test]
+async fn aggregates_alerts_about_simultaneous_forkers() {
+    let n_members = NodeCount(10);
+    let forkers: Vec<_> = (7..10).map(NodeIndex).collect();
+    let n_honest = forkers[0].0;
+    let test_case = TestCase::new(n_members);
+
+    let mut incoming = Vec::new();
+    let mut outgoing = Vec::new();
+    let mut notifications = Vec::new();
+    let mut exits = Vec::new();
+    for node in (0..n_honest).map(NodeIndex) {
+        let (messages_for_network, messages_from_alerter) = mpsc::unbounded();
+        let (messages_for_alerter, messages_from_network) = mpsc::unbounded();
+        let (notifications_for_units, notifications_from_alerter) = mpsc::unbounded();
+        let (alerts_for_alerter, alerts_from_units) = mpsc::unbounded();
+        let (exit_alerter_tx, exit_alerter_rx) = oneshot::channel();
+        // The alerts are all there before the service starts, like when one unit reveals them.
+        if node.0 < N_ALERTERS {
+            for forker in &forkers {
+                alerts_for_alerter
+                    .unbounded_send(test_case.alert(node, test_case.fork_proof(*forker, 0)))
+                    .expect("the alert channel works");
+            }
+        }
+        let mut alerter_service = Service::new(
+            test_case.keychain(node).clone(),
+            crate::alerts::IO {
+                messages_for_network,
+                messages_from_network,
+                notifications_for_units,
+                alerts_from_units,
+                events: EventBus::new(),
+                clock: ClockSource::default(),
+                alert_rate_limit: None,
+                rmc_completion_replies: false,
+                misconduct: MisconductMonitor::default(),
+                drops: DropMonitor::default(),
+                role: Role::Member,
+            },
+            Handler::new(test_case.keychain(node).clone(), 0).with_aggregated_alerts(true),
+        );
+        tokio::spawn(async move {
+            alerter_service
+                .run(Terminator::create_root(exit_alerter_rx, "AlephBFT-alerter"))
+                .await
+        });
+        incoming.push(messages_for_alerter);
+        outgoing.push(messages_from_alerter.map(move |message| (node, message)));
+        notifications.push(notifications_from_alerter);
+        exits.push((exit_alerter_tx, alerts_for_alerter));
+    }
+    let mut outgoing = futures::stream::select_all(outgoing);
+    let mut notifications = futures::stream::select_all(notifications);
+
+    let mut held = Vec::new();
+    let mut raised = HashMap::new();
+    let mut rmcs_by_node: HashMap<NodeIndex, HashSet<_>> = HashMap::new();
+    let mut units_notifications = 0;
+    let storm = async {
+        while units_notifications < N_ALERTERS * n_honest {
+            tokio::select! {
+                Some((sender, (message, recipient))) = outgoing.next() => {
+                    match &message {
+                        AlertMessage::ForkAlert(alert) => {
+                            *raised.entry(sender).or_insert(0) += 1;
+                            assert_eq!(alert.as_signable().forkers(), forkers);
+                        }
+                        AlertMessage::RmcMessage(_, rmc_message @ RmcMessage::SignedHash(_)) => {
+                            rmcs_by_node.entry(sender).or_default().insert(*rmc_message.hash());
+                        }
+                        _ => {}
+                    }
+                    held.push((sender, message, recipient));
+                    if raised.len() == N_ALERTERS {
+                        for (sender, message, recipient) in held.drain(..) {
+                            deliver(&incoming, sender, message, recipient);
+                        }
+                    }
+                }
+                Some(notification) = notifications.next() => {
+                    if let ForkingNotification::Units(_) = notification {
+                        units_notifications += 1;
+                    }
+                }
+            }
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(30), storm)
+        .await
+        .expect("every node should pass on the units of every alert");
+    for (exit_alerter_tx, _) in exits {
+        let _ = exit_alerter_tx.send(());
+    }
+
+    assert!(raised.values().all(|alerts| *alerts == 1));
+    assert_eq!(rmcs_by_node.len(), n_honest);
+    for (node, rmcs) in rmcs_by_node {
+        assert_eq!(
+            rmcs.len(),
+            N_ALERTERS,
+            "node {:?} took part in {:?}",
+            node,
+            rmcs
+        );
+    }
+}
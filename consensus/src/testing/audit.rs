@@ -0,0 +1,80 @@
+use crate::{
+    audit_log,
+    testing::{gen_config, gen_delay_config, init_log, spawn_honest_member, HonestMemberBuilder},
+    AlertRateLimit, AuditRecord, NodeCount, NodeIndex, SpawnHandle,
+};
+use aleph_bft_mock::{DataProvider, Router, Spawner};
+use futures::StreamExt;
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::timeout;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const AUDITED: NodeIndex = NodeIndex(0);
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn audit_log_records_config_and_network_gap() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut others = Vec::new();
+    let mut audited = None;
+    for (network, _) in networks {
+        match network.index() {
+            AUDITED => audited = Some(network),
+            node_ix => others.push(spawn_honest_member(
+                spawner,
+                node_ix,
+                N_MEMBERS,
+                vec![],
+                DataProvider::new(),
+                network,
+            )),
+        }
+    }
+    let network = audited.expect("the audited node is a member");
+    let gaps = network.gaps();
+    let config =
+        gen_config(AUDITED, N_MEMBERS, gen_delay_config()).with_alert_rate_limit(AlertRateLimit {
+            per_minute: 2,
+            per_session: 10,
+        });
+    let described = config.describe();
+    let (audit_handle, audit_monitor) = audit_log();
+    let mut audited = HonestMemberBuilder::from_config(config)
+        .with_local_io(|local_io| local_io.with_audit_log(audit_monitor))
+        .spawn(spawner, network);
+
+    let before_gap: Vec<_> = (&mut audited.finalization_rx).take(5).collect().await;
+    assert_eq!(before_gap.len(), 5);
+    gaps.gap_for(Duration::from_secs(1));
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let after_gap: Vec<_> = timeout(
+        Duration::from_secs(30),
+        (&mut audited.finalization_rx).take(5).collect::<Vec<_>>(),
+    )
+    .await
+    .expect("the session should keep finalizing after the gap");
+    assert_eq!(after_gap.len(), 5);
+
+    audited.stop().await;
+    for member in others {
+        member.stop().await;
+    }
+
+    let entries = audit_handle.entries();
+    let records: Vec<_> = entries.iter().map(|entry| entry.record.clone()).collect();
+    assert_eq!(
+        records,
+        vec![
+            AuditRecord::Config(described),
+            AuditRecord::NetworkInterrupted,
+            AuditRecord::NetworkRecovered,
+        ]
+    );
+    assert!(entries.windows(2).all(|pair| pair[0].at <= pair[1].at));
+    assert!(audit_handle.dropped().is_empty());
+}
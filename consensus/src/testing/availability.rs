@@ -0,0 +1,129 @@
+use crate::{
+    testing::{init_log, HonestMemberBuilder, Network},
+    DataAvailabilityChecker, NodeCount, NodeIndex, SpawnHandle, DEFAULT_MAX_UNAVAILABLE_UNITS,
+};
+use aleph_bft_mock::{Data, DataProvider, Router, Spawner};
+use async_trait::async_trait;
+use futures::{channel::mpsc::UnboundedReceiver, StreamExt};
+use parking_lot::Mutex;
+use serial_test::serial;
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::time::timeout;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const CHECKING: NodeIndex = NodeIndex(0);
+const DELAYED: NodeIndex = NodeIndex(1);
+const DATA_PER_NODE: u32 = 1_000_000;
+const UNAVAILABLE_FOR: Duration = Duration::from_millis(300);
+const RECHECK_INTERVAL: Duration = Duration::from_millis(20);
+const FINALIZED: usize = 200;
+
+fn data_of(node_ix: NodeIndex) -> Range<u32> {
+    let start = node_ix.0 as u32 * DATA_PER_NODE;
+    start..start + DATA_PER_NODE
+}
+
+/// Finds the data of the delayed node available only a while after first asked about it, and
+/// any other data right away, recording what it found available.
+#[derive(Clone, Default)]
+struct DelayingChecker {
+    first_asked: Arc<Mutex<HashMap<Data, Instant>>>,
+    confirmed: Arc<Mutex<HashSet<Data>>>,
+}
+
+#[async_trait]
+impl DataAvailabilityChecker<Data> for DelayingChecker {
+    async fn available(&self, data: &Data) -> bool {
+        let first_asked = *self
+            .first_asked
+            .lock()
+            .entry(*data)
+            .or_insert_with(Instant::now);
+        if data_of(DELAYED).contains(data) && first_asked.elapsed() < UNAVAILABLE_FOR {
+            return false;
+        }
+        self.confirmed.lock().insert(*data);
+        true
+    }
+}
+
+/// The finalized data, each with whether the checker found it available before it was finalized.
+async fn finalized(
+    finalization_rx: &mut UnboundedReceiver<Data>,
+    checker: &DelayingChecker,
+) -> Vec<(Data, bool)> {
+    let mut finalized = Vec::new();
+    while finalized.len() < FINALIZED {
+        let data = finalization_rx
+            .next()
+            .await
+            .expect("the session should keep finalizing");
+        finalized.push((data, checker.confirmed.lock().contains(&data)));
+    }
+    finalized
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn holds_units_back_until_their_data_is_available() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let checker = DelayingChecker::default();
+    let mut members = HashMap::new();
+    for (network, _) in networks {
+        let network: Network = network;
+        let node_ix = network.index();
+        let data = data_of(node_ix);
+        let member = HonestMemberBuilder::new(node_ix, N_MEMBERS).with_data_provider(
+            DataProvider::new_range(data.start as usize, data.end as usize),
+        );
+        let member = match node_ix {
+            CHECKING => member
+                .with_config(|config| {
+                    config.with_availability_checks(DEFAULT_MAX_UNAVAILABLE_UNITS, RECHECK_INTERVAL)
+                })
+                .with_local_io(|local_io| local_io.with_data_availability_checker(checker.clone())),
+            _ => member,
+        };
+        members.insert(node_ix, member.spawn(spawner, network));
+    }
+
+    let mut checking = members.remove(&CHECKING).expect("a member");
+    let mut delayed = members.remove(&DELAYED).expect("a member");
+    let (checked, other) = timeout(Duration::from_secs(60), async {
+        let checked = finalized(&mut checking.finalization_rx, &checker).await;
+        let other = finalized(&mut delayed.finalization_rx, &checker).await;
+        (checked, other)
+    })
+    .await
+    .expect("the sessions should keep finalizing");
+
+    for member in [checking, delayed].into_iter().chain(members.into_values()) {
+        member.stop().await;
+    }
+
+    for &(data, confirmed) in &checked {
+        assert!(
+            confirmed || data_of(CHECKING).contains(&data),
+            "finalized {} before it was available",
+            data
+        );
+    }
+    assert!(
+        checked
+            .iter()
+            .any(|(data, _)| data_of(DELAYED).contains(data)),
+        "the delayed data should get finalized eventually"
+    );
+    let checked: Vec<_> = checked.into_iter().map(|(data, _)| data).collect();
+    let other: Vec<_> = other.into_iter().map(|(data, _)| data).collect();
+    assert_eq!(checked, other, "the checks should not change the order");
+}
@@ -0,0 +1,103 @@
+use crate::{
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_session, HonestMemberBuilder, Network,
+    },
+    FinalizationHandler, FinalizationInfo, FinalizedUnitInfo, LocalIO, NodeCount, NodeIndex,
+    SpawnHandle,
+};
+use aleph_bft_mock::{Data, DataProvider, Loader, Router, Saver, Spawner};
+use futures::{channel::mpsc, StreamExt};
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::timeout;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+/// The members receiving the data one item at a time, the others receive it in batches.
+const PER_ITEM: NodeIndex = NodeIndex(3);
+const BATCHES: usize = 20;
+
+type Batch = (FinalizationInfo, Vec<FinalizedUnitInfo<Data>>);
+
+/// Passes the finalized batches to a channel.
+struct BatchingHandler {
+    tx: mpsc::UnboundedSender<Batch>,
+}
+
+impl FinalizationHandler<Data> for BatchingHandler {
+    fn data_finalized(&mut self, _data: Data) {
+        unreachable!("the data is passed in batches");
+    }
+
+    fn batch_finalized(&mut self, batch: Vec<FinalizedUnitInfo<Data>>, head: FinalizationInfo) {
+        let _ = self.tx.unbounded_send((head, batch));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn batches_have_the_same_boundaries_on_all_members() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut batch_rxs = Vec::new();
+    let mut per_item = None;
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let network: Network = network;
+        let node_ix = network.index();
+        match node_ix {
+            PER_ITEM => {
+                per_item =
+                    Some(HonestMemberBuilder::new(node_ix, N_MEMBERS).spawn(spawner, network))
+            }
+            _ => {
+                let (tx, rx) = mpsc::unbounded();
+                batch_rxs.push(rx);
+                let local_io = LocalIO::new(
+                    DataProvider::new(),
+                    BatchingHandler { tx },
+                    Saver::new(),
+                    Loader::new(vec![]),
+                );
+                let config = gen_config(node_ix, N_MEMBERS, gen_delay_config());
+                members.push(spawn_session(spawner, config, local_io, network));
+            }
+        }
+    }
+    let mut per_item = per_item.expect("the per item member is there");
+
+    let mut all_batches = Vec::new();
+    for batch_rx in batch_rxs {
+        let batches: Vec<_> = timeout(Duration::from_secs(30), batch_rx.take(BATCHES).collect())
+            .await
+            .expect("the members should keep finalizing");
+        all_batches.push(batches);
+    }
+    let data: Vec<Data> = all_batches[0]
+        .iter()
+        .flat_map(|(_, batch)| batch.iter().filter_map(|unit| unit.data))
+        .collect();
+    let items: Vec<_> = timeout(
+        Duration::from_secs(30),
+        (&mut per_item.finalization_rx).take(data.len()).collect(),
+    )
+    .await
+    .expect("the per item member should keep finalizing");
+    per_item.stop().await;
+    for (exit_tx, handle) in members {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    for batches in &all_batches {
+        assert_eq!(batches, &all_batches[0]);
+    }
+    // The head ends the batch.
+    for (head, batch) in &all_batches[0] {
+        let last = batch.last().expect("the batch holds at least its head");
+        assert_eq!((head.creator, head.round), (last.creator, last.round));
+    }
+    assert_eq!(items, data);
+}
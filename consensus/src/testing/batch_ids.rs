@@ -0,0 +1,99 @@
+use crate::{
+    create_config,
+    protocol::{ordered_batch_id, BatchId},
+    testing::{gen_delay_config, init_log, spawn_session},
+    LocalIO, NodeCount, OrderedUnit, SessionId, SpawnHandle, UnitFinalizationHandler,
+};
+use aleph_bft_mock::{Data, DataProvider, Hasher64, Loader, Router, Saver, Spawner};
+use futures::{channel::mpsc, StreamExt};
+use serial_test::serial;
+use std::{collections::HashSet, time::Duration};
+use tokio::time::timeout;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const SESSION_ID: SessionId = 7;
+const BATCHES: usize = 20;
+
+type IdentifiedBatch = (BatchId<Hasher64>, Vec<OrderedUnit<Data, Hasher64>>);
+
+/// Passes the finalized batches together with their identifiers to a channel.
+struct RecordingHandler {
+    tx: mpsc::UnboundedSender<IdentifiedBatch>,
+}
+
+impl UnitFinalizationHandler for RecordingHandler {
+    type Data = Data;
+    type Hasher = Hasher64;
+
+    fn batch_finalized(&mut self, _batch: Vec<OrderedUnit<Data, Hasher64>>) {
+        unreachable!("the batches are passed together with their identifiers");
+    }
+
+    fn identified_batch_finalized(
+        &mut self,
+        id: BatchId<Hasher64>,
+        batch: Vec<OrderedUnit<Data, Hasher64>>,
+    ) {
+        let _ = self.tx.unbounded_send((id, batch));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn batch_ids_agree_between_members() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut batch_rxs = Vec::new();
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let node_ix = network.index();
+        let (tx, batch_rx) = mpsc::unbounded();
+        let local_io = LocalIO::new_with_unit_finalization_handler(
+            DataProvider::new(),
+            RecordingHandler { tx },
+            Saver::new(),
+            Loader::new(vec![]),
+        );
+        let config = create_config(
+            N_MEMBERS,
+            node_ix,
+            SESSION_ID,
+            5000,
+            gen_delay_config(),
+            Duration::ZERO,
+        )
+        .expect("the config is valid");
+        members.push(spawn_session(spawner, config, local_io, network));
+        batch_rxs.push(batch_rx);
+    }
+
+    let mut batch_ids = Vec::new();
+    for batch_rx in batch_rxs {
+        let batches: Vec<_> = timeout(Duration::from_secs(30), batch_rx.take(BATCHES).collect())
+            .await
+            .expect("the members should keep finalizing");
+        let mut ids = Vec::new();
+        for (number, (id, batch)) in batches.into_iter().enumerate() {
+            // Anyone holding the batch can compute the identifier on their own.
+            assert_eq!(
+                ordered_batch_id(SESSION_ID, number as u64, &batch),
+                Some(id)
+            );
+            ids.push(id);
+        }
+        batch_ids.push(ids);
+    }
+    for (exit_tx, handle) in members {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    for ids in &batch_ids {
+        assert_eq!(ids, &batch_ids[0]);
+    }
+    let distinct: HashSet<_> = batch_ids[0].iter().collect();
+    assert_eq!(distinct.len(), BATCHES);
+}
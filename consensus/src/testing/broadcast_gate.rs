@@ -0,0 +1,288 @@
+use crate::{
+    backup::BackupFingerprint,
+    events::InternalEvent,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member_with_config,
+        spawn_session_with_events, Network, TestEventBus,
+    },
+    units::{UncheckedSignedUnit, Unit},
+    BroadcastGate, DataProvider as DataProviderT, GateDecision, LocalIO, NodeCount, NodeIndex,
+    SpawnHandle, TaskHandle,
+};
+use aleph_bft_mock::{
+    Data, DataProvider, FinalizationHandler, Hasher64, Keychain, Loader, Router, Saver, Signature,
+    Spawner,
+};
+use async_trait::async_trait;
+use codec::Decode;
+use futures::{
+    channel::{mpsc::UnboundedReceiver, oneshot},
+    StreamExt,
+};
+use parking_lot::Mutex;
+use serial_test::serial;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::time::{sleep, timeout};
+
+const DATA_PER_NODE: usize = 1_000_000;
+const GATED: NodeIndex = NodeIndex(0);
+
+type TestUnit = UncheckedSignedUnit<Hasher64, Data, Signature>;
+type Others = Vec<(oneshot::Sender<()>, TaskHandle)>;
+
+/// Offers the returned data again, before any new data.
+#[derive(Default)]
+struct ReturningProvider {
+    next: Data,
+    returned: VecDeque<Data>,
+    returns: Arc<Mutex<Vec<Data>>>,
+}
+
+#[async_trait]
+impl DataProviderT for ReturningProvider {
+    type Output = Data;
+
+    async fn get_data(&mut self) -> Option<Data> {
+        if let Some(data) = self.returned.pop_front() {
+            return Some(data);
+        }
+        self.next += 1;
+        Some(self.next - 1)
+    }
+
+    fn return_unused(&mut self, data: Data) {
+        self.returns.lock().push(data);
+        self.returned.push_back(data);
+    }
+}
+
+/// Releases every item after a delay, recording when it was asked about it.
+#[derive(Clone)]
+struct DelayingGate {
+    delay: Duration,
+    checked: Arc<Mutex<HashMap<Data, Instant>>>,
+}
+
+#[async_trait]
+impl BroadcastGate<Data> for DelayingGate {
+    async fn check(&self, data: &Data) -> GateDecision {
+        self.checked.lock().insert(*data, Instant::now());
+        sleep(self.delay).await;
+        GateDecision::Release
+    }
+}
+
+/// Rejects every item the first time it is asked about it.
+#[derive(Default)]
+struct RejectingGate {
+    seen: Mutex<HashSet<Data>>,
+}
+
+#[async_trait]
+impl BroadcastGate<Data> for RejectingGate {
+    async fn check(&self, data: &Data) -> GateDecision {
+        match self.seen.lock().insert(*data) {
+            true => GateDecision::ReplaceWithEmpty,
+            false => GateDecision::Release,
+        }
+    }
+}
+
+struct GatedMember {
+    finalization_rx: UnboundedReceiver<Data>,
+    saved_state: Arc<Mutex<Vec<u8>>>,
+    exit_tx: oneshot::Sender<()>,
+    handle: TaskHandle,
+}
+
+fn spawn_gated_member<DP: DataProviderT<Output = Data>>(
+    spawner: Spawner,
+    network: Network,
+    n_members: NodeCount,
+    data_provider: DP,
+    gate: impl BroadcastGate<Data>,
+    events: TestEventBus,
+) -> GatedMember {
+    let node_index = network.index();
+    let (finalization_handler, finalization_rx) = FinalizationHandler::new();
+    let saved_state = Arc::new(Mutex::new(vec![]));
+    let local_io = LocalIO::new(
+        data_provider,
+        finalization_handler,
+        Saver::from(saved_state.clone()),
+        Loader::new(vec![]),
+    )
+    .with_broadcast_gate(gate);
+    let (exit_tx, handle) = spawn_session_with_events(
+        spawner,
+        gen_config(node_index, n_members, gen_delay_config()),
+        local_io,
+        network,
+        Keychain::new(n_members, node_index),
+        events,
+    );
+    GatedMember {
+        finalization_rx,
+        saved_state,
+        exit_tx,
+        handle,
+    }
+}
+
+fn own_units(mut backup: &[u8]) -> Vec<TestUnit> {
+    BackupFingerprint::<Hasher64>::read_header(&mut backup).expect("the header is correct");
+    let mut units = Vec::new();
+    while !backup.is_empty() {
+        let unit = TestUnit::decode(&mut backup).expect("backup should decode");
+        if unit.as_signable().creator() == GATED {
+            units.push(unit);
+        }
+    }
+    units.sort_by_key(|unit| unit.as_signable().round());
+    units
+}
+
+/// Spawns the committee with the gated node first, returning the exits and handles of the others.
+fn spawn_committee(
+    n_members: NodeCount,
+    data_provider: impl DataProviderT<Output = Data>,
+    gate: impl BroadcastGate<Data>,
+    events: TestEventBus,
+) -> (GatedMember, Vec<UnboundedReceiver<Data>>, Others) {
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+    let mut networks = networks.into_iter().map(|(network, _)| network);
+    let gated = spawn_gated_member(
+        spawner,
+        networks.next().expect("there are networks"),
+        n_members,
+        data_provider,
+        gate,
+        events,
+    );
+    let (finalization_rxs, others) = networks
+        .map(|network| {
+            let ix = network.index();
+            let member = spawn_honest_member_with_config(
+                spawner,
+                gen_config(ix, n_members, gen_delay_config()),
+                vec![],
+                DataProvider::new_range(ix.0 * DATA_PER_NODE, (ix.0 + 1) * DATA_PER_NODE),
+                network,
+            );
+            (member.finalization_rx, (member.exit_tx, member.handle))
+        })
+        .unzip();
+    (gated, finalization_rxs, others)
+}
+
+async fn finalize_gated_data(
+    finalization_rx: &mut UnboundedReceiver<Data>,
+    n_data: usize,
+) -> Vec<Data> {
+    timeout(
+        Duration::from_secs(60),
+        finalization_rx
+            .by_ref()
+            .filter(|data| futures::future::ready((*data as usize) < DATA_PER_NODE))
+            .take(n_data)
+            .collect(),
+    )
+    .await
+    .expect("the data of the gated node should get finalized")
+}
+
+async fn stop(gated: GatedMember, others: Others) -> Vec<TestUnit> {
+    let _ = gated.exit_tx.send(());
+    let _ = gated.handle.await;
+    for (exit_tx, handle) in others {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+    let backup = gated.saved_state.lock().clone();
+    own_units(&backup)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn delaying_gate_shifts_broadcasts() {
+    init_log();
+    let n_members = NodeCount(4);
+    let n_data = 8;
+    let gate = DelayingGate {
+        delay: Duration::from_millis(300),
+        checked: Arc::new(Mutex::new(HashMap::new())),
+    };
+    let events = TestEventBus::new();
+    let mut observed_events = events.subscribe();
+    let (mut gated, mut finalization_rxs, others) =
+        spawn_committee(n_members, DataProvider::new(), gate.clone(), events);
+
+    let mut admitted = Vec::new();
+    timeout(Duration::from_secs(60), async {
+        while admitted.len() < n_data {
+            if let Some(InternalEvent::UnitAdmitted(unit)) = observed_events.next().await {
+                let unit = unit.as_signable();
+                if let (GATED, Some(data)) = (unit.creator(), unit.data()) {
+                    admitted.push((*data, Instant::now()));
+                }
+            }
+        }
+    })
+    .await
+    .expect("the gated node should keep broadcasting units");
+    for (data, admitted_at) in &admitted {
+        let checked_at = gate.checked.lock()[data];
+        assert!(*admitted_at >= checked_at + gate.delay);
+    }
+    // The committee keeps finalizing, including the gated data.
+    let finalized = finalize_gated_data(&mut finalization_rxs[0], n_data).await;
+    assert!(finalized.iter().copied().eq(0..n_data as Data));
+    let own_finalized = finalize_gated_data(&mut gated.finalization_rx, n_data).await;
+    assert_eq!(own_finalized, finalized);
+
+    stop(gated, others).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn rejected_data_is_returned_to_provider() {
+    init_log();
+    let n_members = NodeCount(4);
+    let n_data = 5;
+    let provider = ReturningProvider::default();
+    let returns = provider.returns.clone();
+    let (gated, mut finalization_rxs, others) = spawn_committee(
+        n_members,
+        provider,
+        RejectingGate::default(),
+        TestEventBus::new(),
+    );
+
+    // Every item is rejected once and included in the next unit, so nothing is lost or repeated.
+    let finalized = finalize_gated_data(&mut finalization_rxs[0], n_data).await;
+    assert!(finalized.iter().copied().eq(0..n_data as Data));
+    let units = stop(gated, others).await;
+
+    assert!(units.len() > 2 * n_data);
+    for unit in &units {
+        let round = unit.as_signable().round();
+        let expected = (round % 2 == 1).then_some((round / 2) as Data);
+        assert_eq!(
+            unit.as_signable().data(),
+            &expected,
+            "unit of round {}",
+            round
+        );
+    }
+    // The rejection of the last item might have happened after its unit was saved.
+    let returns = returns.lock().clone();
+    let n_rejections = (units.len() + 1) / 2;
+    assert!(returns.len() == n_rejections || returns.len() == n_rejections + 1);
+    assert!(returns.iter().copied().eq(0..returns.len() as Data));
+}
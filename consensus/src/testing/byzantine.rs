@@ -1,211 +1,31 @@
 use crate::{
-    member::UnitMessage::NewUnit,
-    network::NetworkDataInner::Units,
-    testing::{init_log, spawn_honest_member, HonestMember, Network, NetworkData},
-    units::{ControlHash, FullUnit, PreUnit, SignedUnit, Unit, UnitCoord},
-    Hasher, Network as NetworkT, NetworkData as NetworkDataT, NodeCount, NodeIndex, NodeMap,
-    Recipient, Round, SessionId, Signed, SpawnHandle, TaskHandle,
+    alerts::AlertMessage::RmcMessage,
+    dissemination::Request,
+    events::{AlertState, InternalEvent, Misbehavior},
+    member::UnitMessage::{ResponseParents, ResponseParentsCompact, ResponseUnits},
+    network::NetworkDataInner::{Alert, Units},
+    replay_alerts,
+    testing::{
+        adversary::Adversary, gen_config, gen_delay_config, init_log, spawn_honest_member,
+        spawn_honest_member_with_events, HonestMember, NetworkData, TestEventBus,
+    },
+    units::Unit,
+    NetworkData as NetworkDataT, NodeCount, NodeIndex, SpawnHandle,
 };
 use aleph_bft_mock::{
-    Data, DataProvider, Hash64, Hasher64, Keychain, NetworkHook, Router, Spawner,
+    Data, DataProvider, Hash64, Hasher64, Keychain, NetworkHook, Router, Signature, Spawner,
 };
-use futures::{channel::oneshot, StreamExt};
-use log::{debug, error, trace};
+use aleph_bft_rmc::Message;
+use futures::StreamExt;
+use log::debug;
 use parking_lot::Mutex;
 use serial_test::serial;
-use std::{collections::HashMap, sync::Arc};
-
-struct MaliciousMember<'a> {
-    node_ix: NodeIndex,
-    n_members: NodeCount,
-    session_id: SessionId,
-    forking_round: Round,
-    keychain: &'a Keychain,
-    network: Network,
-    unit_store: HashMap<UnitCoord, SignedUnit<Hasher64, Data, Keychain>>,
-}
-
-impl<'a> MaliciousMember<'a> {
-    fn new(
-        keychain: &'a Keychain,
-        network: Network,
-        node_ix: NodeIndex,
-        n_members: NodeCount,
-        session_id: SessionId,
-        forking_round: Round,
-    ) -> Self {
-        MaliciousMember {
-            node_ix,
-            n_members,
-            session_id,
-            forking_round,
-            keychain,
-            network,
-            unit_store: HashMap::new(),
-        }
-    }
-
-    fn unit_to_data(su: SignedUnit<Hasher64, Data, Keychain>) -> NetworkData {
-        NetworkDataT(Units(NewUnit(su.into())))
-    }
-
-    fn threshold(&self) -> NodeCount {
-        self.n_members.consensus_threshold()
-    }
-
-    fn pick_parents(&self, round: Round) -> Option<NodeMap<Hash64>> {
-        // Outputs a parent map if there are enough of them to create a new unit.
-        let mut parents = NodeMap::with_size(self.n_members);
-        if round == 0 {
-            return Some(parents);
-        }
-        let mut count = 0;
-        let our_prev_coord = UnitCoord::new(round - 1, self.node_ix);
-        if !self.unit_store.contains_key(&our_prev_coord) {
-            return None;
-        }
-        for i in 0..self.n_members.0 {
-            let ix = NodeIndex(i);
-            let coord = UnitCoord::new(round - 1, ix);
-            if let Some(su) = self.unit_store.get(&coord) {
-                let hash: <Hasher64 as Hasher>::Hash = su.as_signable().hash();
-                parents.insert(ix, hash);
-                count += 1;
-            }
-        }
-        if NodeCount(count) >= self.threshold() {
-            Some(parents)
-        } else {
-            None
-        }
-    }
-
-    fn send_legit_unit(&mut self, su: SignedUnit<Hasher64, Data, Keychain>) {
-        let message = Self::unit_to_data(su);
-        self.network.send(message, Recipient::Everyone);
-    }
-
-    fn send_two_variants(
-        &mut self,
-        su0: SignedUnit<Hasher64, Data, Keychain>,
-        su1: SignedUnit<Hasher64, Data, Keychain>,
-    ) {
-        // We send variant k \in {0,1} to each node with index = k (mod 2)
-        // We also send to ourselves, it does not matter much.
-        let message0 = Self::unit_to_data(su0);
-        let message1 = Self::unit_to_data(su1);
-        for ix in 0..self.n_members.0 {
-            let node_ix = NodeIndex(ix);
-            if ix % 2 == 0 {
-                self.network
-                    .send(message0.clone(), Recipient::Node(node_ix));
-            } else {
-                self.network
-                    .send(message1.clone(), Recipient::Node(node_ix));
-            }
-        }
-    }
-
-    fn create_if_possible(&mut self, round: Round) -> bool {
-        if let Some(parents) = self.pick_parents(round) {
-            debug!(target: "malicious-member", "Creating a legit unit for round {}.", round);
-            let mut node_with_parents = NodeMap::with_size(parents.size());
-            if round > 0 {
-                for (node_index, &hash) in parents.iter() {
-                    node_with_parents.insert(node_index, (hash, round - 1));
-                }
-            }
-            let control_hash = ControlHash::<Hasher64>::new(&node_with_parents);
-            let new_preunit = PreUnit::<Hasher64>::new(self.node_ix, round, control_hash);
-            if round != self.forking_round {
-                let full_unit = FullUnit::new(new_preunit, Some(0), self.session_id);
-                let signed_unit = Signed::sign(full_unit, self.keychain);
-                self.on_unit_received(signed_unit.clone());
-                self.send_legit_unit(signed_unit);
-            } else {
-                // FORKING HAPPENS HERE!
-                debug!(target: "malicious-member", "Creating forks for round {}.", round);
-                let mut variants = Vec::new();
-                for data in 0u32..2u32 {
-                    let full_unit = FullUnit::new(new_preunit.clone(), Some(data), self.session_id);
-                    let signed = Signed::sign(full_unit, self.keychain);
-                    variants.push(signed);
-                }
-                self.send_two_variants(variants[0].clone(), variants[1].clone());
-            }
-            return true;
-        }
-        false
-    }
-
-    fn on_unit_received(&mut self, su: SignedUnit<Hasher64, Data, Keychain>) {
-        let full_unit = su.as_signable();
-        let coord: UnitCoord = full_unit.coord();
-        // We don't care if we overwrite something as long as we keep at least one version of a unit
-        // at a given coord.
-        self.unit_store.insert(coord, su);
-    }
-
-    fn on_network_data(&mut self, data: NetworkData) {
-        // We ignore all messages except those carrying new units.
-        if let NetworkDataT(Units(NewUnit(unchecked))) = data {
-            trace!(target: "malicious-member", "New unit received {:?}.", &unchecked);
-            match unchecked.check(self.keychain) {
-                Ok(su) => self.on_unit_received(su),
-                Err(unchecked) => {
-                    panic!("Wrong signature received {:?}.", &unchecked);
-                }
-            }
-        }
-        // else we stay silent
-    }
-
-    pub async fn run_session(mut self, mut exit: oneshot::Receiver<()>) {
-        let mut round: Round = 0;
-        loop {
-            if self.create_if_possible(round) {
-                round += 1;
-            }
-            tokio::select! {
-                event = self.network.next_event() => match event {
-                    Some(data) => {
-                        self.on_network_data(data);
-                    },
-                    None => {
-                        error!(target: "malicious-member", "Network message stream closed.");
-                        break;
-                    }
-                },
-                _ = &mut exit => break,
-            }
-        }
-    }
-}
-
-fn spawn_malicious_member(
-    spawner: Spawner,
-    node_index: NodeIndex,
-    n_members: NodeCount,
-    round_to_fork: Round,
-    network: Network,
-) -> (oneshot::Sender<()>, TaskHandle) {
-    let (exit_tx, exit_rx) = oneshot::channel();
-    let member_task = async move {
-        let keychain = Keychain::new(n_members, node_index);
-        let session_id = 0u64;
-        let lesniak = MaliciousMember::new(
-            &keychain,
-            network,
-            node_index,
-            n_members,
-            session_id,
-            round_to_fork,
-        );
-        lesniak.run_session(exit_rx).await;
-    };
-    let task_handle = spawner.spawn_essential("malicious-member", member_task);
-    (exit_tx, task_handle)
-}
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::time::{sleep, timeout};
 
 #[derive(Clone)]
 pub(crate) struct AlertHook {
@@ -239,12 +59,13 @@ impl NetworkHook<NetworkData> for AlertHook {
         recipient: NodeIndex,
     ) -> Vec<(NetworkData, NodeIndex, NodeIndex)> {
         use crate::{alerts::AlertMessage::*, network::NetworkDataInner::*};
-        if let crate::NetworkData(Alert(ForkAlert(_))) = data {
+        // An alert about several forkers counts once for each of them.
+        if let crate::NetworkData(Alert(ForkAlert(alert)), _) = &data {
             *self
                 .alerts_sent_by_connection
                 .lock()
                 .entry((sender, recipient))
-                .or_insert(0) += 1;
+                .or_insert(0) += alert.as_signable().forkers().len();
         }
         vec![(data, sender, recipient)]
     }
@@ -270,7 +91,9 @@ async fn honest_members_agree_on_batches_byzantine(
     for (network, _) in networks {
         let ix = network.index();
         let (exit_tx, handle) = if !n_honest.into_range().contains(&ix) {
-            spawn_malicious_member(spawner, ix, n_members, 2, network)
+            Adversary::new(ix, n_members)
+                .with_split_fork(2)
+                .spawn(spawner, network)
         } else {
             let HonestMember {
                 finalization_rx,
@@ -338,3 +161,446 @@ async fn small_byzantine_two_forkers() {
 async fn medium_byzantine_ten_forkers() {
     honest_members_agree_on_batches_byzantine(31.into(), 21.into(), 5).await;
 }
+
+/// Replaces the last unit of the first parents response sent to an honest node with a copy
+/// of the first one.
+#[derive(Clone)]
+struct CorruptParentsHook {
+    forkers: Vec<NodeIndex>,
+    corrupted: Arc<Mutex<Option<(NodeIndex, NodeIndex, Hash64)>>>,
+}
+
+impl CorruptParentsHook {
+    fn new(forkers: Vec<NodeIndex>) -> Self {
+        CorruptParentsHook {
+            forkers,
+            corrupted: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// The responder, requester and unit hash of the corrupted response, if any.
+    fn corrupted(&self) -> Option<(NodeIndex, NodeIndex, Hash64)> {
+        *self.corrupted.lock()
+    }
+}
+
+impl NetworkHook<NetworkData> for CorruptParentsHook {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<(NetworkData, NodeIndex, NodeIndex)> {
+        let mut corrupted = self.corrupted.lock();
+        match data {
+            NetworkDataT(Units(ResponseParents(hash, mut parents)), _)
+                if corrupted.is_none()
+                    && parents.len() > 1
+                    && !self.forkers.contains(&recipient) =>
+            {
+                debug!(target: "byzantine-test", "Corrupting parents of {:?} sent by {:?} to {:?}.", hash, sender, recipient);
+                let last = parents.len() - 1;
+                parents[last] = parents[0].clone();
+                *corrupted = Some((sender, recipient, hash));
+                vec![(
+                    NetworkDataT(Units(ResponseParents(hash, parents)), 0),
+                    sender,
+                    recipient,
+                )]
+            }
+            data => vec![(data, sender, recipient)],
+        }
+    }
+}
+
+/// Whether the responder got reported for the corrupted response, and afterwards the parents were
+/// requested from someone else and the unit got admitted.
+fn corruption_handled(
+    events: &[InternalEvent<Hasher64, Data, Signature>],
+    responder: NodeIndex,
+    unit_hash: Hash64,
+) -> bool {
+    let mut reports = events
+        .iter()
+        .enumerate()
+        .filter_map(|(i, event)| match event {
+            InternalEvent::PeerMisbehaved(peer, misbehavior) => Some((i, *peer, misbehavior)),
+            _ => None,
+        });
+    let report = match reports.next() {
+        Some((i, peer, Misbehavior::InconsistentParents(hash))) => {
+            assert_eq!(
+                peer, responder,
+                "only the corrupting peer should be reported"
+            );
+            assert_eq!(*hash, unit_hash);
+            i
+        }
+        Some((_, peer, misbehavior)) => panic!("{:?} reported for {}", peer, misbehavior),
+        None => return false,
+    };
+    assert!(reports.next().is_none(), "the peer should be reported once");
+    let after_report = &events[report..];
+    let rerequested = after_report.iter().any(|event| {
+        matches!(event, InternalEvent::RequestSent(Request::Parents(hash), peer)
+            if *hash == unit_hash && *peer != responder)
+    });
+    let admitted = after_report.iter().any(|event| {
+        matches!(event, InternalEvent::UnitAdmitted(unit) if unit.as_signable().hash() == unit_hash)
+    });
+    rerequested && admitted
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn corrupted_parents_response_is_reported() {
+    init_log();
+    let n_members = NodeCount(4);
+    let forker = NodeIndex(3);
+    let spawner = Spawner::new();
+    let (mut net_hub, networks) = Router::new(n_members);
+    let hook = CorruptParentsHook::new(vec![forker]);
+    net_hub.add_hook(hook.clone());
+    spawner.spawn("network-hub", net_hub);
+
+    let mut observed_events = HashMap::new();
+    let mut exits = Vec::new();
+    let mut handles = Vec::new();
+    for (network, _) in networks {
+        let ix = network.index();
+        let (exit_tx, handle) = match ix == forker {
+            true => Adversary::new(ix, n_members)
+                .with_split_fork(2)
+                .spawn(spawner, network),
+            false => {
+                let mut delay_config = gen_delay_config();
+                // Retries should not interfere with attributing the corrupted response.
+                delay_config.parent_request_delay = Arc::new(|_| Duration::from_secs(2));
+                let events = TestEventBus::new();
+                let observed = Arc::new(Mutex::new(Vec::new()));
+                let mut consumer = events.subscribe();
+                let observed_for_consumer = observed.clone();
+                tokio::spawn(async move {
+                    while let Some(event) = consumer.next().await {
+                        observed_for_consumer.lock().push(event);
+                    }
+                });
+                observed_events.insert(ix, observed);
+                let HonestMember {
+                    exit_tx, handle, ..
+                } = spawn_honest_member_with_events(
+                    spawner,
+                    gen_config(ix, n_members, delay_config),
+                    vec![],
+                    DataProvider::new(),
+                    network,
+                    events,
+                );
+                (exit_tx, handle)
+            }
+        };
+        exits.push(exit_tx);
+        handles.push(handle);
+    }
+
+    let (responder, requester, unit_hash) = timeout(Duration::from_secs(30), async {
+        loop {
+            if let Some(corrupted) = hook.corrupted() {
+                return corrupted;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("some parents response should be sent");
+    timeout(Duration::from_secs(30), async {
+        loop {
+            if corruption_handled(&observed_events[&requester].lock(), responder, unit_hash) {
+                return;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("the corrupted response should be reported and the unit eventually admitted");
+
+    for exit in exits {
+        let _ = exit.send(());
+    }
+    for handle in handles {
+        let _ = handle.await;
+    }
+}
+
+/// Records the first compact parents response sent to an honest node, and whether the units
+/// referenced in it were requested from the responder afterwards.
+#[derive(Clone)]
+struct CompactParentsHook {
+    forkers: Vec<NodeIndex>,
+    compact: Arc<Mutex<Option<(NodeIndex, NodeIndex, Hash64)>>>,
+    followed_up: Arc<Mutex<bool>>,
+}
+
+impl CompactParentsHook {
+    fn new(forkers: Vec<NodeIndex>) -> Self {
+        CompactParentsHook {
+            forkers,
+            compact: Arc::new(Mutex::new(None)),
+            followed_up: Arc::new(Mutex::new(false)),
+        }
+    }
+
+    /// The responder, requester and unit hash of the compact response, if any.
+    fn compact(&self) -> Option<(NodeIndex, NodeIndex, Hash64)> {
+        *self.compact.lock()
+    }
+}
+
+impl NetworkHook<NetworkData> for CompactParentsHook {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<(NetworkData, NodeIndex, NodeIndex)> {
+        let mut compact = self.compact.lock();
+        match &data {
+            NetworkDataT(Units(ResponseParentsCompact(_, hash, _)), _)
+                if compact.is_none() && !self.forkers.contains(&recipient) =>
+            {
+                *compact = Some((sender, recipient, *hash));
+            }
+            NetworkDataT(Units(ResponseUnits(_)), _)
+                if compact.is_some_and(|(responder, requester, _)| {
+                    responder == sender && requester == recipient
+                }) =>
+            {
+                *self.followed_up.lock() = true;
+            }
+            _ => {}
+        }
+        vec![(data, sender, recipient)]
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn compact_parents_response_is_resolved() {
+    init_log();
+    let n_members = NodeCount(4);
+    let forker = NodeIndex(3);
+    let spawner = Spawner::new();
+    let (mut net_hub, networks) = Router::new(n_members);
+    let hook = CompactParentsHook::new(vec![forker]);
+    net_hub.add_hook(hook.clone());
+    spawner.spawn("network-hub", net_hub);
+    // Compact responses rely on the digest of the requester, so the rounds have to be slower
+    // than the digest gossip and the forks come after a few gossips.
+    let forking_round = 4;
+
+    let mut observed_events = HashMap::new();
+    let mut exits = Vec::new();
+    let mut handles = Vec::new();
+    for (network, _) in networks {
+        let ix = network.index();
+        let (exit_tx, handle) = match ix == forker {
+            true => Adversary::new(ix, n_members)
+                .with_split_fork(forking_round)
+                .spawn(spawner, network),
+            false => {
+                let events = TestEventBus::new();
+                let observed = Arc::new(Mutex::new(Vec::new()));
+                let mut consumer = events.subscribe();
+                let observed_for_consumer = observed.clone();
+                tokio::spawn(async move {
+                    while let Some(event) = consumer.next().await {
+                        observed_for_consumer.lock().push(event);
+                    }
+                });
+                observed_events.insert(ix, observed);
+                let mut delay_config = gen_delay_config();
+                delay_config.unit_creation_delay = Arc::new(|_| Duration::from_secs(1));
+                let config = gen_config(ix, n_members, delay_config).with_compact_unit_refs(true);
+                let HonestMember {
+                    exit_tx, handle, ..
+                } = spawn_honest_member_with_events(
+                    spawner,
+                    config,
+                    vec![],
+                    DataProvider::new(),
+                    network,
+                    events,
+                );
+                (exit_tx, handle)
+            }
+        };
+        exits.push(exit_tx);
+        handles.push(handle);
+    }
+
+    let (_, requester, unit_hash) = timeout(Duration::from_secs(30), async {
+        loop {
+            if let Some(compact) = hook.compact() {
+                return compact;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("some compact parents response should be sent");
+    timeout(Duration::from_secs(30), async {
+        loop {
+            let admitted = observed_events[&requester].lock().iter().any(|event| {
+                matches!(event, InternalEvent::UnitAdmitted(unit) if unit.as_signable().hash() == unit_hash)
+            });
+            if admitted {
+                return;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("the unit should be admitted with the resolved parents");
+    debug!(target: "byzantine-test", "Parents of {:?} resolved, follow-up needed: {}.", unit_hash, hook.followed_up.lock());
+
+    for exit in exits {
+        let _ = exit.send(());
+    }
+    for handle in handles {
+        let _ = handle.await;
+    }
+}
+
+/// Records the traffic from and to the observed node, in the order it passes the network.
+#[derive(Clone)]
+struct TapeHook {
+    observed: NodeIndex,
+    tape: Arc<Mutex<Vec<NetworkData>>>,
+}
+
+impl TapeHook {
+    fn new(observed: NodeIndex) -> Self {
+        TapeHook {
+            observed,
+            tape: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    fn tape(&self) -> Vec<NetworkData> {
+        self.tape.lock().clone()
+    }
+}
+
+impl NetworkHook<NetworkData> for TapeHook {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<(NetworkData, NodeIndex, NodeIndex)> {
+        if sender == self.observed || recipient == self.observed {
+            self.tape.lock().push(data.clone());
+        }
+        vec![(data, sender, recipient)]
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn replayed_alerts_match_live_ones() {
+    init_log();
+    let n_members = NodeCount(4);
+    let forker = NodeIndex(3);
+    let observed = NodeIndex(0);
+    let spawner = Spawner::new();
+    let (mut net_hub, networks) = Router::new(n_members);
+    let hook = TapeHook::new(observed);
+    net_hub.add_hook(hook.clone());
+    spawner.spawn("network-hub", net_hub);
+
+    let confirmed = Arc::new(Mutex::new(HashSet::new()));
+    let mut exits = Vec::new();
+    let mut handles = Vec::new();
+    for (network, _) in networks {
+        let ix = network.index();
+        let events = TestEventBus::new();
+        if ix == observed {
+            let mut consumer = events.subscribe();
+            let confirmed = confirmed.clone();
+            tokio::spawn(async move {
+                while let Some(event) = consumer.next().await {
+                    if let InternalEvent::AlertStateChanged(hash, AlertState::Confirmed) = event {
+                        confirmed.lock().insert(hash);
+                    }
+                }
+            });
+        }
+        let (exit_tx, handle) = match ix == forker {
+            true => Adversary::new(ix, n_members)
+                .with_split_fork(2)
+                .spawn(spawner, network),
+            false => {
+                let HonestMember {
+                    exit_tx, handle, ..
+                } = spawn_honest_member_with_events(
+                    spawner,
+                    gen_config(ix, n_members, gen_delay_config()),
+                    vec![],
+                    DataProvider::new(),
+                    network,
+                    events,
+                );
+                (exit_tx, handle)
+            }
+        };
+        exits.push(exit_tx);
+        handles.push(handle);
+    }
+
+    // The tape is recorded before the messages reach the node, so it can be ahead of it.
+    let (tape, replayed) = timeout(Duration::from_secs(30), async {
+        loop {
+            let tape = hook.tape();
+            let replayed = replay_alerts(tape.clone(), Keychain::new(n_members, observed), 0);
+            let replayed_confirmed: HashSet<_> = replayed
+                .iter()
+                .filter(|alert| alert.confirmed)
+                .map(|alert| alert.hash)
+                .collect();
+            let live_confirmed = confirmed.lock().clone();
+            if !live_confirmed.is_empty() && replayed_confirmed == live_confirmed {
+                return (tape, replayed);
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("the replayed alerts should match the live ones");
+    for exit in exits {
+        let _ = exit.send(());
+    }
+    for handle in handles {
+        let _ = handle.await;
+    }
+    assert!(replayed.iter().all(|alert| alert.forkers == vec![forker]));
+
+    // Cut the tape right before the first multisignature of a confirmed alert.
+    let (cut, cut_hash) = tape
+        .iter()
+        .enumerate()
+        .find_map(|(position, data)| match data {
+            NetworkDataT(Alert(RmcMessage(_, Message::MultisignedHash(unchecked))), _)
+                if confirmed.lock().contains(unchecked.as_signable()) =>
+            {
+                Some((position, *unchecked.as_signable()))
+            }
+            _ => None,
+        })
+        .expect("the confirmed alerts are multisigned on the tape");
+    let truncated = replay_alerts(tape[..cut].to_vec(), Keychain::new(n_members, observed), 0);
+    let incomplete = truncated
+        .iter()
+        .find(|alert| alert.hash == cut_hash)
+        .expect("the alert precedes its multisignature on the tape");
+    assert!(!incomplete.confirmed);
+}
@@ -0,0 +1,206 @@
+use crate::{
+    backup::BackupFingerprint,
+    run_session,
+    testing::{gen_config, gen_delay_config, init_log, spawn_honest_member, HonestMember, Network},
+    units::{UncheckedSignedUnit, Unit},
+    LocalIO, NodeCount, NodeIndex, OrderedUnit, SessionError, SpawnHandle, Terminator,
+    UnitFinalizationHandler, UserComponent,
+};
+use aleph_bft_mock::{
+    Data, DataProvider, FailingSaver, FinalizationHandler, Hash64, Hasher64, Keychain, Loader,
+    PanickingSaver, Router, Saver, Signature, Spawner,
+};
+use codec::Decode;
+use futures::{channel::oneshot, AsyncWrite};
+use parking_lot::Mutex;
+use serial_test::serial;
+use std::{collections::HashSet, sync::Arc, time::Duration};
+use tokio::time::timeout;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const FAILING: NodeIndex = NodeIndex(0);
+const HANDLED_UNITS: usize = 9;
+/// The number of the backup write that goes wrong.
+const FAILING_WRITE: usize = 5;
+
+/// Records the hashes of the finalized units, panicking on the one after [`HANDLED_UNITS`].
+struct PanickingHandler {
+    handled: Arc<Mutex<Vec<Hash64>>>,
+}
+
+impl UnitFinalizationHandler for PanickingHandler {
+    type Data = Data;
+    type Hasher = Hasher64;
+
+    fn batch_finalized(&mut self, batch: Vec<OrderedUnit<Data, Hasher64>>) {
+        for unit in batch {
+            let mut handled = self.handled.lock();
+            if handled.len() == HANDLED_UNITS {
+                panic!("cannot handle unit number {}", HANDLED_UNITS + 1);
+            }
+            handled.push(unit.hash);
+        }
+    }
+}
+
+/// Spawns all the members but the failing one, returning them with its network.
+fn spawn_others(spawner: Spawner) -> (Vec<HonestMember>, Network) {
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+    let mut others = Vec::new();
+    let mut failing = None;
+    for (network, _) in networks {
+        match network.index() {
+            FAILING => failing = Some(network),
+            node_ix => others.push(spawn_honest_member(
+                spawner,
+                node_ix,
+                N_MEMBERS,
+                vec![],
+                DataProvider::new(),
+                network,
+            )),
+        }
+    }
+    (others, failing.expect("the failing node is a member"))
+}
+
+async fn stop(others: Vec<HonestMember>) {
+    for member in others {
+        member.stop().await;
+    }
+}
+
+/// Runs the session of the failing node with the given backup writer, until it ends.
+async fn run_with_backup_writer<US: AsyncWrite + Send + Sync + 'static>(
+    spawner: Spawner,
+    network: Network,
+    backup_writer: US,
+) -> Result<(), SessionError> {
+    let (finalization_handler, _batches) = FinalizationHandler::new();
+    let local_io = LocalIO::new(
+        DataProvider::new(),
+        finalization_handler,
+        backup_writer,
+        Loader::new(vec![]),
+    );
+    let (_exit_tx, exit_rx) = oneshot::channel();
+    timeout(
+        Duration::from_secs(30),
+        run_session(
+            gen_config(FAILING, N_MEMBERS, gen_delay_config()),
+            local_io,
+            network,
+            Keychain::new(N_MEMBERS, FAILING),
+            spawner,
+            Terminator::create_root(exit_rx, "AlephBFT-member"),
+        ),
+    )
+    .await
+    .expect("the session should shut down after the failure")
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn panicking_finalization_handler_ends_the_session() {
+    init_log();
+    let spawner = Spawner::new();
+    let (others, network) = spawn_others(spawner);
+
+    let handled = Arc::new(Mutex::new(Vec::new()));
+    let saved_units = Arc::new(Mutex::new(Vec::new()));
+    let local_io = LocalIO::new_with_unit_finalization_handler(
+        DataProvider::new(),
+        PanickingHandler {
+            handled: handled.clone(),
+        },
+        Saver::from(saved_units.clone()),
+        Loader::new(vec![]),
+    );
+    let (_exit_tx, exit_rx) = oneshot::channel();
+    let result = timeout(
+        Duration::from_secs(30),
+        run_session(
+            gen_config(FAILING, N_MEMBERS, gen_delay_config()),
+            local_io,
+            network,
+            Keychain::new(N_MEMBERS, FAILING),
+            spawner,
+            Terminator::create_root(exit_rx, "AlephBFT-member"),
+        ),
+    )
+    .await
+    .expect("the session should shut down after the panic");
+
+    assert_eq!(
+        result,
+        Err(SessionError::UserCallbackPanicked {
+            component: UserComponent::FinalizationHandler,
+            payload_description: format!("cannot handle unit number {}", HANDLED_UNITS + 1),
+        })
+    );
+    let handled = handled.lock().clone();
+    assert_eq!(handled.len(), HANDLED_UNITS);
+    // Every finalized unit was admitted, so saved to the backup, before the panic.
+    let saved_units = saved_units.lock().clone();
+    let mut saved_units = &saved_units[..];
+    BackupFingerprint::<Hasher64>::read_header(&mut saved_units).expect("the header is correct");
+    let mut backup = HashSet::new();
+    while !saved_units.is_empty() {
+        let unit = UncheckedSignedUnit::<Hasher64, Data, Signature>::decode(&mut saved_units)
+            .expect("the backup is not corrupted by the panic");
+        backup.insert(unit.as_signable().hash());
+    }
+    for hash in &handled {
+        assert!(backup.contains(hash));
+    }
+
+    stop(others).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn panicking_backup_writer_ends_the_session() {
+    init_log();
+    let spawner = Spawner::new();
+    let (others, network) = spawn_others(spawner);
+
+    let result = run_with_backup_writer(
+        spawner,
+        network,
+        PanickingSaver::new(Saver::new(), FAILING_WRITE),
+    )
+    .await;
+
+    assert_eq!(
+        result,
+        Err(SessionError::UserCallbackPanicked {
+            component: UserComponent::BackupWriter,
+            payload_description: format!("backup write number {} panicked", FAILING_WRITE),
+        })
+    );
+    stop(others).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn failing_backup_writer_ends_the_session() {
+    init_log();
+    let spawner = Spawner::new();
+    let (others, network) = spawn_others(spawner);
+
+    let result = run_with_backup_writer(
+        spawner,
+        network,
+        FailingSaver::new(Saver::new(), |write| write == FAILING_WRITE),
+    )
+    .await;
+
+    assert_eq!(
+        result,
+        Err(SessionError::ComponentTerminated {
+            component: "runway/backup_saver"
+        })
+    );
+    stop(others).await;
+}
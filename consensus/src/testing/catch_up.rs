@@ -0,0 +1,156 @@
+use crate::{
+    member::UnitMessage,
+    network::NetworkDataInner::Units,
+    testing::{init_log, spawn_honest_member, HonestMember, NetworkData},
+    units::{Unit, UnitCoord},
+    NetworkData as NetworkDataT, NodeCount, NodeIndex, Round, SpawnHandle,
+};
+use aleph_bft_mock::{DataProvider, NetworkHook, Router, Spawner};
+use parking_lot::Mutex;
+use serial_test::serial;
+use std::{sync::Arc, time::Duration};
+use tokio::time::{sleep, timeout};
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const LATE: NodeIndex = NodeIndex(3);
+/// How far the others get before the late node starts.
+const HEAD_START: Round = 40;
+/// Requests the late node may send before it reaches the round the others were at when it
+/// started, independent of how far behind it was.
+const MAX_CATCH_UP_REQUESTS: usize = 4;
+
+#[derive(Default)]
+struct CatchUpState {
+    online: bool,
+    /// The highest round of the units broadcast by the others.
+    frontier: Round,
+    /// The round the others were at when the late node started.
+    start_frontier: Option<Round>,
+    requests: usize,
+    /// How many requests the late node sent before it caught up.
+    caught_up_after: Option<usize>,
+}
+
+/// Keeps the late node offline until it starts, and then only lets it learn about units by
+/// requesting them, as it gets no broadcasts of new units.
+struct LateNodeHook {
+    state: Arc<Mutex<CatchUpState>>,
+}
+
+impl NetworkHook<NetworkData> for LateNodeHook {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<(NetworkData, NodeIndex, NodeIndex)> {
+        let mut state = self.state.lock();
+        let NetworkDataT(Units(message), _) = &data else {
+            return vec![(data, sender, recipient)];
+        };
+        if sender != LATE {
+            if let UnitMessage::NewUnit(unit) = message {
+                state.frontier = state.frontier.max(unit.as_signable().round());
+            }
+        }
+        if sender != LATE && recipient != LATE {
+            return vec![(data, sender, recipient)];
+        }
+        if !state.online || matches!(message, UnitMessage::NewUnit(_)) {
+            return Vec::new();
+        }
+        if sender == LATE {
+            match message {
+                UnitMessage::RequestCoord(_, _)
+                | UnitMessage::RequestParents(_, _)
+                | UnitMessage::RequestUnits(_, _)
+                | UnitMessage::RequestNewestUnits(_, _, _) => state.requests += 1,
+                UnitMessage::DagDigest(_, digest) => {
+                    let start_frontier = state.start_frontier.expect("the late node is online");
+                    let caught_up = N_MEMBERS
+                        .into_iterator()
+                        .filter(|creator| *creator != LATE)
+                        .all(|creator| digest.holds(UnitCoord::new(start_frontier, creator)));
+                    if caught_up && state.caught_up_after.is_none() {
+                        state.caught_up_after = Some(state.requests);
+                    }
+                }
+                _ => {}
+            }
+        }
+        vec![(data, sender, recipient)]
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn late_node_catches_up_in_few_requests() {
+    init_log();
+    let spawner = Spawner::new();
+    let state = Arc::new(Mutex::new(CatchUpState::default()));
+    let (mut net_hub, networks) = Router::new(N_MEMBERS);
+    net_hub.add_hook(LateNodeHook {
+        state: state.clone(),
+    });
+    spawner.spawn("network-hub", net_hub);
+
+    let mut members: Vec<HonestMember> = Vec::new();
+    let mut late_network = None;
+    for (network, _) in networks {
+        let node_ix = network.index();
+        match node_ix == LATE {
+            true => late_network = Some(network),
+            false => members.push(spawn_honest_member(
+                spawner,
+                node_ix,
+                N_MEMBERS,
+                vec![],
+                DataProvider::new(),
+                network,
+            )),
+        }
+    }
+
+    timeout(Duration::from_secs(30), async {
+        while state.lock().frontier < HEAD_START {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("the others should make progress without the late node");
+    {
+        let mut state = state.lock();
+        state.online = true;
+        state.start_frontier = Some(state.frontier);
+    }
+    members.push(spawn_honest_member(
+        spawner,
+        LATE,
+        N_MEMBERS,
+        vec![],
+        DataProvider::new(),
+        late_network.expect("the late node has a network"),
+    ));
+
+    let requests = timeout(Duration::from_secs(30), async {
+        loop {
+            if let Some(requests) = state.lock().caught_up_after {
+                return requests;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("the late node should catch up");
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+
+    assert!(
+        requests <= MAX_CATCH_UP_REQUESTS,
+        "caught up with {} units of each creator after {} requests",
+        HEAD_START,
+        requests
+    );
+}
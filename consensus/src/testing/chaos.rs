@@ -0,0 +1,335 @@
+use crate::{
+    alerts::AlertMessage::ForkAlert,
+    backup::BackupFingerprint,
+    member::UnitMessage::NewUnit,
+    network::NetworkDataInner::{Alert, Units},
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member_with_config, HonestMember,
+        NetworkData, ReconnectSender,
+    },
+    units::{UncheckedSignedUnit, Unit, UnitCoord},
+    NetworkData as NetworkDataT, NodeCount, NodeIndex, Round, SpawnHandle, TaskHandle,
+};
+use aleph_bft_mock::{
+    Data, DataProvider, Hash64, Hasher64, KillableSpawner, NetworkHook, Router, Signature, Spawner,
+};
+use codec::Decode;
+use futures::channel::{mpsc::UnboundedReceiver, oneshot};
+use log::info;
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serial_test::serial;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::time::{sleep, timeout};
+
+const SEED: u64 = 0xa1e9;
+const N_MEMBERS: usize = 7;
+const TARGET_ROUND: Round = 200;
+const LATENCY: Duration = Duration::from_millis(20);
+
+type TestUnit = UncheckedSignedUnit<Hasher64, Data, Signature>;
+type RoutedMessage = (NetworkData, NodeIndex, NodeIndex);
+
+#[derive(Default)]
+struct Observations {
+    broadcast_units: HashMap<UnitCoord, HashSet<Hash64>>,
+    fork_alerts: usize,
+    max_round: Round,
+}
+
+/// Delays every message by [`LATENCY`] and keeps track of the broadcast units and fork alerts.
+#[derive(Clone, Default)]
+struct ChaosHook {
+    buffer: VecDeque<(Instant, RoutedMessage)>,
+    observations: Arc<Mutex<Observations>>,
+}
+
+impl ChaosHook {
+    fn observe(&self, data: &NetworkData) {
+        let mut observations = self.observations.lock();
+        match data {
+            NetworkDataT(Units(NewUnit(unit)), _) => {
+                let full_unit = unit.as_signable();
+                observations.max_round = observations.max_round.max(full_unit.round());
+                observations
+                    .broadcast_units
+                    .entry(full_unit.coord())
+                    .or_default()
+                    .insert(full_unit.hash());
+            }
+            NetworkDataT(Alert(ForkAlert(_)), _) => observations.fork_alerts += 1,
+            _ => {}
+        }
+    }
+
+    fn max_round(&self) -> Round {
+        self.observations.lock().max_round
+    }
+}
+
+impl NetworkHook<NetworkData> for ChaosHook {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<RoutedMessage> {
+        self.observe(&data);
+        let buffer = &mut self.buffer;
+        buffer.push_back((Instant::now(), (data, sender, recipient)));
+        let mut result = Vec::new();
+        while let Some((when, _)) = buffer.front() {
+            if when.elapsed() < LATENCY {
+                break;
+            }
+            let (_, message) = buffer.pop_front().expect("just checked it is not empty");
+            result.push(message);
+        }
+        result
+    }
+}
+
+fn decode_backup(mut buf: &[u8]) -> Vec<TestUnit> {
+    BackupFingerprint::<Hasher64>::read_header(&mut buf).expect("the header is correct");
+    let mut units = Vec::new();
+    while !buf.is_empty() {
+        units.push(TestUnit::decode(&mut buf).expect("backup should decode"));
+    }
+    units
+}
+
+fn unit_hashes(units: &[TestUnit]) -> HashSet<Hash64> {
+    units.iter().map(|unit| unit.as_signable().hash()).collect()
+}
+
+/// The round the loader should start creating units from, given the backup.
+fn next_round_from_backup(ix: NodeIndex, units: &[TestUnit]) -> Round {
+    units
+        .iter()
+        .map(|unit| unit.as_signable())
+        .filter(|unit| unit.creator() == ix)
+        .map(|unit| unit.round() + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+/// A single run of a member, from a start to a crash.
+struct Incarnation {
+    loaded_backup: Vec<u8>,
+    saved_state: Arc<Mutex<Vec<u8>>>,
+    finalization_rx: UnboundedReceiver<Data>,
+    finalized: Vec<Data>,
+    exit_tx: Option<oneshot::Sender<()>>,
+    handle: TaskHandle,
+}
+
+impl Incarnation {
+    fn receive_finalized(&mut self) {
+        while let Ok(Some(data)) = self.finalization_rx.try_next() {
+            self.finalized.push(data);
+        }
+    }
+
+    /// The units saved by this incarnation on top of the ones it loaded.
+    fn newly_saved_units(&self) -> Vec<TestUnit> {
+        let loaded = unit_hashes(&decode_backup(&self.loaded_backup));
+        decode_backup(&self.saved_state.lock())
+            .into_iter()
+            .filter(|unit| !loaded.contains(&unit.as_signable().hash()))
+            .collect()
+    }
+
+    /// Whether this incarnation created any units of its own.
+    fn created_units(&self, ix: NodeIndex) -> bool {
+        self.newly_saved_units()
+            .iter()
+            .any(|unit| unit.as_signable().creator() == ix)
+    }
+}
+
+/// A member that can be crashed and then restarted from its backup.
+struct RestartableMember {
+    index: NodeIndex,
+    n_members: NodeCount,
+    spawner: KillableSpawner,
+    reconnect_tx: ReconnectSender,
+    current: Option<Incarnation>,
+    past: Vec<Incarnation>,
+}
+
+impl RestartableMember {
+    fn new(index: NodeIndex, n_members: NodeCount, reconnect_tx: ReconnectSender) -> Self {
+        RestartableMember {
+            index,
+            n_members,
+            spawner: KillableSpawner::new(),
+            reconnect_tx,
+            current: None,
+            past: Vec::new(),
+        }
+    }
+
+    fn start(&mut self, backup: Vec<u8>, network: crate::testing::Network) {
+        let HonestMember {
+            finalization_rx,
+            saved_state,
+            exit_tx,
+            handle,
+        } = spawn_honest_member_with_config(
+            self.spawner.clone(),
+            gen_config(self.index, self.n_members, gen_delay_config()),
+            backup.clone(),
+            DataProvider::new(),
+            network,
+        );
+        self.current = Some(Incarnation {
+            loaded_backup: backup,
+            saved_state,
+            finalization_rx,
+            finalized: Vec::new(),
+            exit_tx: Some(exit_tx),
+            handle,
+        });
+    }
+
+    /// Abruptly stops all the tasks of the member, without letting it exit cleanly.
+    async fn crash(&mut self) {
+        self.spawner.kill().await;
+        let mut incarnation = self.current.take().expect("should be running");
+        incarnation.receive_finalized();
+        self.past.push(incarnation);
+    }
+
+    /// Restarts the member from the backup of its last incarnation.
+    async fn restart(&mut self) {
+        let last = self.past.last().expect("should have crashed");
+        let loaded = decode_backup(&last.loaded_backup);
+        let saved = last.saved_state.lock().clone();
+        // The loaded units get saved again before anything new, but the member might have crashed
+        // before it was done. A real application would keep the old backup in such a case.
+        let backup = match unit_hashes(&decode_backup(&saved)).is_superset(&unit_hashes(&loaded)) {
+            true => saved,
+            false => last.loaded_backup.clone(),
+        };
+        let (network_tx, network_rx) = oneshot::channel();
+        self.reconnect_tx
+            .unbounded_send((self.index, network_tx))
+            .expect("router should be running");
+        let network = network_rx.await.expect("router should reconnect us");
+        self.start(backup, network);
+    }
+
+    async fn shutdown(&mut self) {
+        let mut incarnation = self.current.take().expect("should be running");
+        if let Some(exit_tx) = incarnation.exit_tx.take() {
+            let _ = exit_tx.send(());
+        }
+        let _ = (&mut incarnation.handle).await;
+        incarnation.receive_finalized();
+        self.past.push(incarnation);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn members_restarted_from_backup_do_not_fork() {
+    init_log();
+    let n_members = NodeCount(N_MEMBERS);
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let spawner = Spawner::new();
+    let (mut net_hub, networks) = Router::new(n_members);
+    let hook = ChaosHook::default();
+    net_hub.add_hook(hook.clone());
+    spawner.spawn("network-hub", net_hub);
+
+    let mut members: Vec<_> = networks
+        .into_iter()
+        .map(|(network, reconnect_tx)| {
+            let mut member = RestartableMember::new(network.index(), n_members, reconnect_tx);
+            member.start(Vec::new(), network);
+            member
+        })
+        .collect();
+
+    let mut n_restarts = 0;
+    timeout(Duration::from_secs(120), async {
+        while hook.max_round() < TARGET_ROUND {
+            sleep(Duration::from_millis(rng.gen_range(1000..2000))).await;
+            let victim = rng.gen_range(0..N_MEMBERS);
+            info!(target: "chaos-test", "Crashing {:?} at round {}.", NodeIndex(victim), hook.max_round());
+            members[victim].crash().await;
+            sleep(Duration::from_millis(rng.gen_range(100..500))).await;
+            members[victim].restart().await;
+            n_restarts += 1;
+        }
+    })
+    .await
+    .expect("the session should progress despite the crashes");
+    assert!(n_restarts > 0);
+
+    timeout(Duration::from_secs(30), async {
+        while members.iter().any(|member| {
+            !member
+                .current
+                .as_ref()
+                .expect("should be running")
+                .created_units(member.index)
+        }) {
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("every member should resume creating units after its last restart");
+
+    for member in members.iter_mut() {
+        member.shutdown().await;
+    }
+
+    let observations = hook.observations.lock();
+    assert_eq!(
+        observations.fork_alerts, 0,
+        "no fork alert should be raised"
+    );
+    for (coord, hashes) in observations.broadcast_units.iter() {
+        assert_eq!(hashes.len(), 1, "{} got forked", coord);
+    }
+
+    let mut finalized: Vec<_> = members
+        .iter()
+        .flat_map(|member| member.past.iter().map(|incarnation| &incarnation.finalized))
+        .collect();
+    finalized.sort_by_key(|finalized| finalized.len());
+    let longest = finalized.last().expect("there are members");
+    assert!(!longest.is_empty());
+    for finalized in finalized.iter() {
+        assert!(
+            longest.starts_with(finalized),
+            "finalized sequences diverged"
+        );
+    }
+
+    for member in members.iter() {
+        for incarnation in member.past.iter() {
+            let loaded = decode_backup(&incarnation.loaded_backup);
+            let starting_round = incarnation
+                .newly_saved_units()
+                .iter()
+                .map(|unit| unit.as_signable())
+                .filter(|unit| unit.creator() == member.index)
+                .map(|unit| unit.round())
+                .min();
+            if let Some(starting_round) = starting_round {
+                assert_eq!(
+                    starting_round,
+                    next_round_from_backup(member.index, &loaded),
+                    "{:?} restarted from a round inconsistent with its backup",
+                    member.index
+                );
+            }
+        }
+    }
+}
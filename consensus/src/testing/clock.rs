@@ -0,0 +1,68 @@
+use crate::{
+    testing::{gen_config, gen_delay_config, init_log, spawn_honest_member_with_config},
+    ClockSource, NodeCount, SpawnHandle,
+};
+use aleph_bft_mock::{Data, DataProvider, Router, Spawner, TokioClock};
+use futures::StreamExt;
+use serial_test::serial;
+use std::{sync::Arc, time::Duration};
+
+const N_MEMBERS: usize = 7;
+const UNIT_CREATION_DELAY: Duration = Duration::from_millis(200);
+/// Every member puts a data item in every unit, so this is about 100 rounds.
+const N_DATA: usize = 100 * N_MEMBERS;
+
+// The runtime time is paused, so it only moves forward when all the tasks wait for timers.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn session_runs_in_simulated_time() {
+    init_log();
+    let n_members = NodeCount(N_MEMBERS);
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+
+    let wall_clock_start = std::time::Instant::now();
+    let simulated_start = tokio::time::Instant::now();
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let mut delay_config = gen_delay_config();
+        delay_config.unit_creation_delay = Arc::new(|_| UNIT_CREATION_DELAY);
+        let config = gen_config(network.index(), n_members, delay_config)
+            .with_clock(ClockSource::new(TokioClock));
+        members.push(spawn_honest_member_with_config(
+            spawner,
+            config,
+            vec![],
+            DataProvider::new(),
+            network,
+        ));
+    }
+
+    let mut finalized: Vec<Vec<Data>> = Vec::new();
+    for member in members.iter_mut() {
+        finalized.push(member.finalization_rx.by_ref().take(N_DATA).collect().await);
+    }
+    let simulated = simulated_start.elapsed();
+    let wall_clock = wall_clock_start.elapsed();
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+
+    for other in finalized.iter().skip(1) {
+        assert_eq!(other, &finalized[0]);
+    }
+    // The rounds are never created faster than the creation delay, as all the members use it.
+    assert!(
+        simulated >= UNIT_CREATION_DELAY * 90,
+        "simulated time {:?} is too short for 100 rounds",
+        simulated
+    );
+    assert!(
+        wall_clock * 10 < simulated,
+        "the session took {:?} of wall clock time and {:?} of simulated time",
+        wall_clock,
+        simulated
+    );
+}
@@ -0,0 +1,99 @@
+use crate::{
+    testing::{gen_config, gen_delay_config, init_log, spawn_honest_member},
+    LocalIO, NodeCount, NodeIndex, SessionComponents, SpawnHandle, Terminator,
+};
+use aleph_bft_mock::{DataProvider, FinalizationHandler, Keychain, Loader, Router, Saver, Spawner};
+use futures::{channel::oneshot, executor::LocalPool, task::LocalSpawnExt, StreamExt};
+use serial_test::serial;
+use std::thread;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const DRIVEN: NodeIndex = NodeIndex(0);
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn components_driven_on_a_single_thread_finalize() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut others = Vec::new();
+    let mut driven = None;
+    for (network, _) in networks {
+        match network.index() {
+            DRIVEN => driven = Some(network),
+            node_ix => others.push(spawn_honest_member(
+                spawner,
+                node_ix,
+                N_MEMBERS,
+                vec![],
+                DataProvider::new(),
+                network,
+            )),
+        }
+    }
+    let network = driven.expect("the driven node is a member");
+    let (finalization_handler, mut finalization_rx) = FinalizationHandler::new();
+    let local_io = LocalIO::new(
+        DataProvider::new(),
+        finalization_handler,
+        Saver::new(),
+        Loader::new(vec![]),
+    );
+    let (exit_tx, exit_rx) = oneshot::channel();
+    let SessionComponents {
+        session,
+        components,
+    } = SessionComponents::new(
+        gen_config(DRIVEN, N_MEMBERS, gen_delay_config()),
+        local_io,
+        network,
+        Keychain::new(N_MEMBERS, DRIVEN),
+        Terminator::create_root(exit_rx, "AlephBFT-member"),
+    )
+    .expect("the config is valid");
+    let mut names: Vec<_> = components
+        .iter()
+        .map(|component| component.name())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "member",
+            "member/network",
+            "member/runway",
+            "runway",
+            "runway/alerter",
+            "runway/backup_saver",
+            "runway/creation",
+            "runway/loading",
+        ]
+    );
+
+    let executor = thread::spawn(move || {
+        let mut pool = LocalPool::new();
+        for component in components {
+            pool.spawner()
+                .spawn_local(component)
+                .expect("the pool is running");
+        }
+        let result = pool.run_until(session);
+        // All the components should be done once the session is, so this returns right away.
+        pool.run();
+        result
+    });
+
+    let finalized: Vec<_> = (&mut finalization_rx).take(20).collect().await;
+    assert_eq!(finalized.len(), 20);
+    let _ = exit_tx.send(());
+    let result = tokio::task::spawn_blocking(move || executor.join())
+        .await
+        .expect("the joining task does not panic")
+        .expect("the executor thread does not panic");
+    assert!(result.is_ok(), "the session should end cleanly");
+    for member in others {
+        member.stop().await;
+    }
+}
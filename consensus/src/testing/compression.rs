@@ -0,0 +1,108 @@
+use crate::{
+    network::NetworkDataInner::Compressed,
+    testing::{init_log, HonestMemberBuilder, Network, NetworkData},
+    Compression, NetworkData as NetworkDataT, NodeCount, NodeIndex, NodeSubset, Round, SpawnHandle,
+    UnitMetadataProvider, EXTENDED_MESSAGES_VERSION,
+};
+use aleph_bft_mock::{NetworkHook, Router, Spawner};
+use codec::{Decode, Encode};
+use futures::StreamExt;
+use parking_lot::Mutex;
+use serial_test::serial;
+use std::{sync::Arc, time::Duration};
+use tokio::time::timeout;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const COMPRESSING: [NodeIndex; 2] = [NodeIndex(0), NodeIndex(1)];
+const METADATA_SIZE: usize = 512;
+const FINALIZED: usize = 50;
+
+/// Makes the units large and compressible, like units carrying a few kilobytes of data.
+struct PaddingProvider;
+
+impl UnitMetadataProvider for PaddingProvider {
+    fn metadata(&self, round: Round) -> Vec<u8> {
+        format!("round {} ", round)
+            .into_bytes()
+            .into_iter()
+            .cycle()
+            .take(METADATA_SIZE)
+            .collect()
+    }
+}
+
+/// Passes the messages through their encoding, as a real network would, and remembers the
+/// senders of compressed ones.
+#[derive(Clone)]
+struct WireHook {
+    compressing: Arc<Mutex<NodeSubset>>,
+}
+
+impl NetworkHook<NetworkData> for WireHook {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<(NetworkData, NodeIndex, NodeIndex)> {
+        let data = NetworkData::decode(&mut &data.encode()[..]).expect("the message decodes");
+        if let NetworkDataT(Compressed(_), _) = &data {
+            self.compressing.lock().insert(sender);
+        }
+        vec![(data, sender, recipient)]
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn nodes_with_and_without_compression_agree() {
+    init_log();
+    let spawner = Spawner::new();
+    let (mut net_hub, networks) = Router::new(N_MEMBERS);
+    let hook = WireHook {
+        compressing: Arc::new(Mutex::new(NodeSubset::with_size(N_MEMBERS))),
+    };
+    net_hub.add_hook(hook.clone());
+    spawner.spawn("network-hub", net_hub);
+
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let network: Network = network;
+        let node_ix = network.index();
+        let member = HonestMemberBuilder::new(node_ix, N_MEMBERS)
+            .with_config(|config| {
+                let config = config
+                    .with_wire_version(EXTENDED_MESSAGES_VERSION)
+                    .with_max_unit_metadata_size(METADATA_SIZE);
+                match COMPRESSING.contains(&node_ix) {
+                    true => config.with_compression(Compression::Zstd),
+                    false => config,
+                }
+            })
+            .with_local_io(|local_io| local_io.with_unit_metadata_provider(PaddingProvider));
+        members.push(member.spawn(spawner, network));
+    }
+
+    let mut finalized = Vec::new();
+    for member in &mut members {
+        let batch: Vec<_> = timeout(
+            Duration::from_secs(30),
+            member.finalization_rx.by_ref().take(FINALIZED).collect(),
+        )
+        .await
+        .expect("the members should finalize");
+        finalized.push(batch);
+    }
+    for member in members {
+        member.stop().await;
+    }
+
+    for node_finalized in &finalized {
+        assert_eq!(node_finalized, &finalized[0]);
+    }
+    let compressing = hook.compressing.lock();
+    assert_eq!(
+        compressing.elements().collect::<Vec<_>>(),
+        COMPRESSING.to_vec()
+    );
+}
@@ -0,0 +1,177 @@
+use crate::{
+    member::UnitMessage,
+    network::NetworkDataInner,
+    testing::{gen_config, gen_delay_config, init_log, NetworkData},
+    units::Unit,
+    Action, ConsensusHandler, ConsensusHandlerError, NetworkData as NetworkDataT, NodeCount,
+    NodeIndex, Recipient, Round,
+};
+use aleph_bft_mock::{Data, Hasher64, Keychain, PartialMultisignature, Signature};
+use codec::{Decode, Encode};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+const N_MEMBERS: NodeCount = NodeCount(2);
+const N_DATA: u32 = 10;
+const MAX_STEPS: usize = 100_000;
+
+type Handler = ConsensusHandler<Hasher64, Data, Keychain>;
+
+struct Node {
+    handler: Handler,
+    timer: Option<Instant>,
+    finalized: Vec<Data>,
+    backup: Vec<u8>,
+    created: Vec<Round>,
+}
+
+/// Carries out the actions of the node, queueing the messages for the other nodes after an
+/// encoding round trip, as they would go through a real network.
+fn carry_out(
+    node_ix: usize,
+    actions: Vec<Action<Hasher64, Data, Signature, PartialMultisignature>>,
+    nodes: &mut [Node],
+    messages: &mut VecDeque<(usize, NetworkData)>,
+) {
+    for action in actions {
+        match action {
+            Action::Send(message, recipient) => {
+                if let NetworkDataT(NetworkDataInner::Units(UnitMessage::NewUnit(unit)), _) =
+                    &message
+                {
+                    nodes[node_ix].created.push(unit.as_signable().round());
+                }
+                let message = NetworkData::decode(&mut &message.encode()[..])
+                    .expect("our messages should decode");
+                for recipient_ix in 0..nodes.len() {
+                    let addressed = match &recipient {
+                        Recipient::Everyone => recipient_ix != node_ix,
+                        Recipient::Node(ix) => ix.0 == recipient_ix,
+                    };
+                    if addressed {
+                        messages.push_back((recipient_ix, message.clone()));
+                    }
+                }
+            }
+            Action::Finalize(data) => nodes[node_ix].finalized.push(data),
+            Action::SaveToBackup(unit) => nodes[node_ix].backup.extend(unit),
+            Action::ScheduleTimer(at) => nodes[node_ix].timer = Some(at),
+        }
+    }
+}
+
+fn config(node_ix: usize) -> crate::Config {
+    gen_config(NodeIndex(node_ix), N_MEMBERS, gen_delay_config()).with_allow_small_committee(true)
+}
+
+fn keychain(node_ix: usize) -> Keychain {
+    Keychain::new(N_MEMBERS, NodeIndex(node_ix))
+}
+
+/// Runs the handlers until every one of them finalizes all the data, returning the time reached.
+fn run_until_finalized(nodes: &mut [Node], mut now: Instant) -> Instant {
+    // The time only passes when nothing else is left to do.
+    let mut messages = VecDeque::new();
+    for ix in 0..nodes.len() {
+        let actions = nodes[ix].handler.on_timer(now);
+        carry_out(ix, actions, nodes, &mut messages);
+    }
+    let expected = 2 * N_DATA as usize;
+    let mut steps = 0;
+    while nodes.iter().any(|node| node.finalized.len() < expected) {
+        steps += 1;
+        assert!(
+            steps < MAX_STEPS,
+            "the handlers should finalize all the data"
+        );
+        if let Some((ix, message)) = messages.pop_front() {
+            let actions = nodes[ix].handler.on_network_message(message);
+            carry_out(ix, actions, nodes, &mut messages);
+            continue;
+        }
+        let (ix, at) = nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, node)| node.timer.map(|at| (ix, at)))
+            .min_by_key(|(_, at)| *at)
+            .expect("a handler with nothing to do should wait for a timer");
+        now = now.max(at);
+        nodes[ix].timer = None;
+        let actions = nodes[ix].handler.on_timer(now);
+        carry_out(ix, actions, nodes, &mut messages);
+    }
+    now
+}
+
+fn fresh_nodes() -> Vec<Node> {
+    (0..N_MEMBERS.0)
+        .map(|ix| {
+            let mut handler =
+                Handler::new(config(ix), keychain(ix), &[]).expect("the config should be valid");
+            for data in 0..N_DATA {
+                assert!(handler.on_data(100 * ix as u32 + data).is_empty());
+            }
+            Node {
+                handler,
+                timer: None,
+                finalized: Vec::new(),
+                backup: Vec::new(),
+                created: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn two_handlers_finalize_without_any_task() {
+    init_log();
+    let mut nodes = fresh_nodes();
+    run_until_finalized(&mut nodes, Instant::now());
+
+    assert_eq!(nodes[0].finalized, nodes[1].finalized);
+    let mut finalized = nodes[0].finalized.clone();
+    finalized.sort();
+    let mut all_data: Vec<_> = (0..N_DATA).chain(100..100 + N_DATA).collect();
+    all_data.sort();
+    assert_eq!(finalized, all_data);
+    assert!(nodes.iter().all(|node| !node.backup.is_empty()));
+}
+
+#[test]
+fn restarted_handler_continues_from_its_backup() {
+    init_log();
+    let mut nodes = fresh_nodes();
+    let now = run_until_finalized(&mut nodes, Instant::now());
+    let crashed = nodes.remove(0);
+
+    assert!(matches!(
+        Handler::new(
+            config(0),
+            keychain(0),
+            &crashed.backup[..crashed.backup.len() - 1]
+        ),
+        Err(ConsensusHandlerError::CorruptedBackup(_))
+    ));
+    let handler =
+        Handler::new(config(0), keychain(0), &crashed.backup).expect("the backup should restore");
+    let mut restarted = vec![Node {
+        handler,
+        timer: None,
+        finalized: Vec::new(),
+        backup: Vec::new(),
+        created: Vec::new(),
+    }];
+    let mut messages = VecDeque::new();
+    let later = now + Duration::from_secs(3600);
+    let actions = restarted[0].handler.on_timer(later);
+    carry_out(0, actions, &mut restarted, &mut messages);
+
+    assert_eq!(restarted[0].finalized, crashed.finalized);
+    let last_created = crashed.created.iter().max().expect("units were created");
+    assert!(restarted[0]
+        .created
+        .iter()
+        .all(|round| round > last_created));
+}
@@ -1,12 +1,24 @@
 use crate::{
-    testing::{init_log, spawn_honest_member, HonestMember, Network, ReconnectSender},
+    backup::BackupFingerprint,
+    events::InternalEvent,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member,
+        spawn_honest_member_with_events, spawn_session_with_events, HonestMember, Network,
+        ReconnectSender, TestEventBus,
+    },
     units::{UncheckedSignedUnit, Unit, UnitCoord},
-    NodeCount, NodeIndex, SpawnHandle, TaskHandle,
+    LocalIO, NodeCount, NodeIndex, Round, SpawnHandle, TaskHandle,
+};
+use aleph_bft_mock::{
+    Data, DataProvider, FinalizationHandler, Hasher64, Keychain, Loader, Router, Signature,
+    SlowSaver, Spawner,
 };
-use aleph_bft_mock::{Data, DataProvider, Hasher64, Router, Signature, Spawner};
 use codec::Decode;
 use futures::{
-    channel::{mpsc, oneshot},
+    channel::{
+        mpsc::{self, Receiver as BoundedReceiver},
+        oneshot,
+    },
     StreamExt,
 };
 use parking_lot::Mutex;
@@ -16,6 +28,9 @@ use std::{
     sync::Arc,
     time::Duration,
 };
+use tokio::time::timeout;
+
+const BACKUP_WRITE_DELAY: Duration = Duration::from_millis(20);
 
 struct NodeData {
     batch_rx: mpsc::UnboundedReceiver<Data>,
@@ -140,6 +155,7 @@ async fn reconnect_nodes(
 }
 
 fn verify_backup(buf: &mut &[u8]) -> HashSet<UnitCoord> {
+    BackupFingerprint::<Hasher64>::read_header(buf).expect("the header is correct");
     let mut already_saved = HashSet::new();
 
     while !buf.is_empty() {
@@ -294,3 +310,150 @@ async fn small_node_crash_recovery_medium() {
 async fn medium_node_crash_recovery_large() {
     crashed_nodes_recover(28.into(), 2).await;
 }
+
+/// The round of the newest unit of the given creator in the backup.
+fn top_round_in_backup(mut buf: &[u8], creator: NodeIndex) -> Option<Round> {
+    BackupFingerprint::<Hasher64>::read_header(&mut buf).expect("the header is correct");
+    let mut top_round = None;
+    while !buf.is_empty() {
+        let unit = <UncheckedSignedUnit<Hasher64, Data, Signature>>::decode(&mut buf).unwrap();
+        let full_unit = unit.as_signable();
+        if full_unit.creator() == creator {
+            top_round = top_round.max(Some(full_unit.round()));
+        }
+    }
+    top_round
+}
+
+struct SlowBackupMember {
+    saved_units: Arc<Mutex<Vec<u8>>>,
+    created: BoundedReceiver<InternalEvent<Hasher64, Data, Signature>>,
+    exit_tx: oneshot::Sender<()>,
+    handle: TaskHandle,
+}
+
+/// Spawns a member whose backup takes a while to write every unit.
+fn spawn_member_with_slow_backup(
+    spawner: Spawner,
+    n_members: NodeCount,
+    network: Network,
+) -> SlowBackupMember {
+    let node_index = network.index();
+    let saved_units = Arc::new(Mutex::new(vec![]));
+    let local_io = LocalIO::new(
+        DataProvider::new(),
+        FinalizationHandler::new().0,
+        SlowSaver::new(saved_units.clone().into(), BACKUP_WRITE_DELAY),
+        Loader::new(vec![]),
+    );
+    let events = TestEventBus::new();
+    let created = events.subscribe();
+    let (exit_tx, handle) = spawn_session_with_events(
+        spawner,
+        gen_config(node_index, n_members, gen_delay_config()),
+        local_io,
+        network,
+        Keychain::new(n_members, node_index),
+        events,
+    );
+    SlowBackupMember {
+        saved_units,
+        created,
+        exit_tx,
+        handle,
+    }
+}
+
+async fn next_created_round(
+    events: &mut (impl StreamExt<Item = InternalEvent<Hasher64, Data, Signature>> + Unpin),
+) -> Round {
+    loop {
+        match events.next().await {
+            Some(InternalEvent::UnitCreated(round)) => return round,
+            Some(_) => {}
+            None => panic!("the session ended without creating a unit"),
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn unit_created_right_before_exit_is_saved() {
+    init_log();
+    let n_members = NodeCount(4);
+    let crashing = NodeIndex(0);
+    let crash_round = 5;
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut members = Vec::new();
+    let mut crashing_member = None;
+    for (network, reconnect_tx) in networks {
+        let ix = network.index();
+        if ix == crashing {
+            crashing_member = Some((
+                spawn_member_with_slow_backup(spawner, n_members, network),
+                reconnect_tx,
+            ));
+            continue;
+        }
+        members.push(spawn_honest_member(
+            spawner,
+            ix,
+            n_members,
+            vec![],
+            DataProvider::new(),
+            network,
+        ));
+    }
+    let (
+        SlowBackupMember {
+            saved_units,
+            mut created,
+            exit_tx,
+            handle,
+        },
+        reconnect_tx,
+    ) = crashing_member.expect("the crashing node is there");
+
+    // The backup is slow, so the exit comes while the unit is still on its way there.
+    timeout(Duration::from_secs(60), async {
+        while next_created_round(&mut created).await < crash_round {}
+    })
+    .await
+    .expect("the crashing node should create units");
+    let _ = exit_tx.send(());
+    let _ = handle.await;
+    let backup = saved_units.lock().clone();
+    let top_round = top_round_in_backup(&backup, crashing).expect("our units should be saved");
+    assert!(top_round >= crash_round);
+
+    let (network_tx, network_rx) = oneshot::channel();
+    reconnect_tx
+        .unbounded_send((crashing, network_tx))
+        .expect("receiver should exist");
+    let network = network_rx.await.expect("channel should be open");
+    let events = TestEventBus::new();
+    let mut created = events.subscribe();
+    let HonestMember {
+        exit_tx, handle, ..
+    } = spawn_honest_member_with_events(
+        spawner,
+        gen_config(crashing, n_members, gen_delay_config()),
+        backup,
+        DataProvider::new(),
+        network,
+        events,
+    );
+    let first_round = timeout(Duration::from_secs(60), next_created_round(&mut created))
+        .await
+        .expect("the restarted node should create units");
+    assert_eq!(first_round, top_round + 1);
+
+    let _ = exit_tx.send(());
+    let _ = handle.await;
+    for member in members {
+        member.stop().await;
+    }
+}
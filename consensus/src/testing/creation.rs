@@ -1,5 +1,7 @@
 use crate::{
+    callbacks::CallbackGuard,
     creation::{run, IO},
+    events::EventBus,
     testing::{gen_config, gen_delay_config},
     units::{SignedUnit as GenericSignedUnit, Unit as GenericUnit},
     NodeCount, Receiver, Round, Sender, Terminator,
@@ -75,6 +77,14 @@ fn setup_test(n_members: NodeCount) -> TestSetup {
             incoming_parents: parents_from_controller,
             outgoing_units: units_for_controller.clone(),
             data_provider: DataProvider::new(),
+            broadcast_gate: None,
+            metadata_provider: None,
+            lease: Default::default(),
+            finalized_rounds: mpsc::unbounded().1,
+            finalized_heads: mpsc::unbounded().1,
+            events: EventBus::new(),
+            callbacks: CallbackGuard::default(),
+            session_end: Default::default(),
         };
         let config = gen_config(node_ix, n_members, gen_delay_config());
         let (starting_round_for_consensus, starting_round) = oneshot::channel();
@@ -1,15 +1,19 @@
 use crate::{
     alerts::ForkingNotification,
+    callbacks::CallbackGuard,
     dag::{
         Dag as GenericDag, DagResult, ReconstructedUnit as GenericReconstructedUnit,
         Request as GenericRequest,
     },
+    delivery::DeliveryControl,
+    events::EventBus,
     extension::Ordering,
     units::{
         ControlHash, FullUnit, PreUnit, SignedUnit as GenericSignedUnit, Unit, UnitStore,
         UnitWithParents as _, Validator,
     },
-    NodeCount, NodeIndex, NodeMap, NodeSubset, OrderedUnit, Round, Signed, UnitFinalizationHandler,
+    DataPolicy, NodeCount, NodeIndex, NodeMap, NodeSubset, OrderedUnit, Round, Signed,
+    UnitFinalizationHandler,
 };
 use aleph_bft_mock::{Data, Hash64, Hasher64, Keychain};
 use log::debug;
@@ -41,7 +45,8 @@ impl UnitWithParents {
         let keychain = Keychain::new(parent_hashes.size(), creator);
         let control_hash = ControlHash::new(&parent_hashes);
         let pre_unit = PreUnit::new(creator, round, control_hash);
-        let unit = Signed::sign(FullUnit::new(pre_unit, Some(variant), 0), &keychain);
+        let unit = Signed::sign(FullUnit::new(pre_unit, Some(variant), 0), &keychain)
+            .expect("the keychain never fails");
         UnitWithParents {
             unit,
             parent_hashes,
@@ -122,12 +127,15 @@ impl DagFeeder {
                     units,
                     requests,
                     alerts,
+                    ..
                 } = self.dag.add_parents(h, parents, &self.store);
                 for unit in units {
                     self.on_reconstructed_unit(unit);
                 }
                 for alert in alerts {
-                    self.on_alert(alert.forker());
+                    for forker in alert.forkers() {
+                        self.on_alert(forker);
+                    }
                     // have to repeat it, as it wasn't properly accepted because of the alert
                     self.on_request(ParentsOf(h));
                 }
@@ -169,6 +177,7 @@ impl DagFeeder {
             units,
             requests,
             alerts,
+            ..
         } = self
             .dag
             .process_forking_notification(ForkingNotification::Units(committed_units), &self.store);
@@ -188,12 +197,15 @@ impl DagFeeder {
                 units,
                 requests,
                 alerts,
+                ..
             } = self.dag.add_unit(unit.unit.into(), &self.store);
             for unit in units {
                 self.on_reconstructed_unit(unit);
             }
             for alert in alerts {
-                self.on_alert(alert.forker());
+                for forker in alert.forkers() {
+                    self.on_alert(forker);
+                }
             }
             for request in requests {
                 self.on_request(request);
@@ -236,9 +248,16 @@ fn run_consensus_on_dag(
     let node_id = NodeIndex(0);
     let feeder = DagFeeder::new(node_id, units, forker_units);
     let (recording_handler, finalized) = RecordingHandler::new();
-    let mut ordering = Ordering::new(recording_handler);
+    let mut ordering = Ordering::new(
+        recording_handler,
+        DeliveryControl::default().split().0,
+        None,
+        DataPolicy::default(),
+        EventBus::new(),
+        CallbackGuard::default(),
+    );
     for unit in feeder.feed() {
-        ordering.add_unit(unit);
+        ordering.add_unit(unit).expect("delivery is never paused");
     }
     let finalized = finalized.lock().clone();
     finalized
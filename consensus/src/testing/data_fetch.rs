@@ -0,0 +1,146 @@
+use crate::{
+    status_query,
+    testing::{gen_config, gen_delay_config, init_log, spawn_session, Network},
+    ClockSource, Config, DataProvider as DataProviderT, LocalIO, NodeCount, NodeIndex, SpawnHandle,
+    StatusQuery, TaskHandle,
+};
+use aleph_bft_mock::{
+    Data, DataProvider, FinalizationHandler, Loader, Router, Saver, Spawner, StalledDataProvider,
+    StallingDataProvider, TokioClock,
+};
+use futures::{channel::oneshot, StreamExt};
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const DATA_FETCH_TIMEOUT: Duration = Duration::from_millis(20);
+/// The unit creation delay of the test configuration.
+const UNIT_CREATION_DELAY: Duration = Duration::from_millis(50);
+const DATA_PER_CREATOR: usize = 1000;
+
+fn config(node_index: NodeIndex) -> Config {
+    let mut delay_config = gen_delay_config();
+    delay_config.data_fetch_timeout = Some(DATA_FETCH_TIMEOUT);
+    gen_config(node_index, N_MEMBERS, delay_config).with_clock(ClockSource::new(TokioClock))
+}
+
+fn spawn_member<DP: DataProviderT<Output = Data>>(
+    spawner: Spawner,
+    network: Network,
+    data_provider: DP,
+    finalization_handler: FinalizationHandler,
+    status_query: StatusQuery,
+) -> (oneshot::Sender<()>, TaskHandle) {
+    let node_index = network.index();
+    let local_io = LocalIO::new(
+        data_provider,
+        finalization_handler,
+        Saver::new(),
+        Loader::new(vec![]),
+    )
+    .with_status_query(status_query);
+    spawn_session(spawner, config(node_index), local_io, network)
+}
+
+// The runtime time is paused, so it only moves forward when all the tasks wait for timers.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn rounds_advance_while_providers_stall_forever() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let (status, query) = status_query();
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let status_query = match network.index() {
+            NodeIndex(0) => query.clone(),
+            _ => StatusQuery::default(),
+        };
+        members.push(spawn_member(
+            spawner,
+            network,
+            StalledDataProvider::new(),
+            FinalizationHandler::new().0,
+            status_query,
+        ));
+    }
+
+    let running_time = Duration::from_secs(10);
+    sleep(running_time).await;
+    let status = status.query().await.expect("the session should run");
+    for (exit_tx, handle) in members {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    // Every round takes the creation delay and the timeout at most, with plenty of slack.
+    let min_rounds =
+        running_time.as_millis() / (UNIT_CREATION_DELAY + DATA_FETCH_TIMEOUT).as_millis() / 2;
+    for creator in N_MEMBERS.into_iterator() {
+        let top_round = status
+            .top_rounds
+            .get(creator)
+            .expect("the units of every creator should arrive");
+        assert!(
+            u128::from(*top_round) >= min_rounds,
+            "the units of {:?} only reached round {}",
+            creator,
+            top_round
+        );
+    }
+}
+
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn late_data_goes_into_later_units() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut members = Vec::new();
+    let mut finalized = None;
+    for (network, _) in networks {
+        let node_index = network.index();
+        let start = node_index.0 * DATA_PER_CREATOR;
+        let data_provider =
+            StallingDataProvider::new(DataProvider::new_range(start, start + DATA_PER_CREATOR));
+        // Every request of the slow node takes a few timeouts to answer.
+        if node_index == NodeIndex(0) {
+            data_provider.stall_for(4 * DATA_FETCH_TIMEOUT);
+        }
+        let (finalization_handler, finalized_rx) = FinalizationHandler::new();
+        if node_index == NodeIndex(1) {
+            finalized = Some(finalized_rx);
+        }
+        members.push(spawn_member(
+            spawner,
+            network,
+            data_provider,
+            finalization_handler,
+            StatusQuery::default(),
+        ));
+    }
+
+    let n_late = 20;
+    let late_data: Vec<Data> = timeout(
+        Duration::from_secs(60),
+        finalized
+            .expect("the second node is there")
+            .filter(|data| futures::future::ready((*data as usize) < DATA_PER_CREATOR))
+            .take(n_late)
+            .collect(),
+    )
+    .await
+    .expect("the data of the slow node should get finalized");
+    for (exit_tx, handle) in members {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    // None of the late answers got lost.
+    assert_eq!(late_data, (0..n_late as Data).collect::<Vec<_>>());
+}
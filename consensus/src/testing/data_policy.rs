@@ -0,0 +1,119 @@
+use crate::{
+    testing::{gen_config, gen_delay_config, init_log, spawn_session, Network},
+    DataPolicy, FinalizationHandler, Flagged, LocalIO, NodeCount, NodeIndex, SpawnHandle,
+    TaskHandle,
+};
+use aleph_bft_mock::{Data, DataProvider, Loader, Router, Saver, Spawner};
+use futures::{
+    channel::{
+        mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
+        oneshot,
+    },
+    StreamExt,
+};
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::timeout;
+
+const DATA_PER_CREATOR: usize = 1000;
+
+type Finalized = (Data, Option<NodeIndex>);
+
+/// Records the finalized data together with the creator, if it was flagged.
+struct RecordingHandler {
+    finalized: UnboundedSender<Finalized>,
+}
+
+impl FinalizationHandler<Data> for RecordingHandler {
+    fn data_finalized(&mut self, data: Data) {
+        let _ = self.finalized.unbounded_send((data, None));
+    }
+
+    fn flagged_data_finalized(&mut self, flagged: Flagged<Data>) {
+        let _ = self
+            .finalized
+            .unbounded_send((flagged.data, Some(flagged.creator)));
+    }
+}
+
+fn creator_of(data: Data) -> NodeIndex {
+    NodeIndex(data as usize / DATA_PER_CREATOR)
+}
+
+fn spawn_member(
+    spawner: Spawner,
+    network: Network,
+    n_members: NodeCount,
+    data_policy: DataPolicy,
+) -> (
+    UnboundedReceiver<Finalized>,
+    oneshot::Sender<()>,
+    TaskHandle,
+) {
+    let node_index = network.index();
+    let start = node_index.0 * DATA_PER_CREATOR;
+    let (finalized, finalized_rx) = unbounded();
+    let local_io = LocalIO::new(
+        DataProvider::new_range(start, start + DATA_PER_CREATOR - 1),
+        RecordingHandler { finalized },
+        Saver::new(),
+        Loader::new(vec![]),
+    );
+    let config =
+        gen_config(node_index, n_members, gen_delay_config()).with_data_policy(data_policy);
+    let (exit_tx, handle) = spawn_session(spawner, config, local_io, network);
+    (finalized_rx, exit_tx, handle)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn flagged_data_is_marked_only_locally() {
+    init_log();
+    let n_members = NodeCount(4);
+    let n_data = 60;
+    let probation = NodeIndex(3);
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut members: Vec<_> = networks
+        .into_iter()
+        .map(|(network, _)| {
+            let data_policy = match network.index() {
+                NodeIndex(0) => DataPolicy::new(move |creator| creator == probation),
+                _ => DataPolicy::default(),
+            };
+            spawn_member(spawner, network, n_members, data_policy)
+        })
+        .collect();
+
+    let mut finalized = Vec::new();
+    for (finalized_rx, _, _) in members.iter_mut() {
+        let data: Vec<Finalized> = timeout(
+            Duration::from_secs(60),
+            finalized_rx.by_ref().take(n_data).collect(),
+        )
+        .await
+        .expect("the session should finalize data");
+        finalized.push(data);
+    }
+    for (_, exit_tx, handle) in members {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    let ordering = |finalized: &[Finalized]| -> Vec<Data> {
+        finalized.iter().map(|(data, _)| *data).collect()
+    };
+    for other in finalized.iter().skip(1) {
+        assert_eq!(ordering(other), ordering(&finalized[0]));
+        assert!(other.iter().all(|(_, flag)| flag.is_none()));
+    }
+    assert!(finalized[0]
+        .iter()
+        .any(|(data, _)| creator_of(*data) == probation));
+    for (data, flag) in finalized[0].iter() {
+        let expected = Some(creator_of(*data)).filter(|creator| *creator == probation);
+        assert_eq!(*flag, expected, "wrong flag for {}", data);
+    }
+}
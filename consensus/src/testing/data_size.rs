@@ -0,0 +1,118 @@
+use crate::{
+    testing::{gen_config, gen_delay_config, init_log, spawn_session},
+    DataProvider, FinalizationHandler, LocalIO, NetworkData, NodeCount, NodeIndex, SpawnHandle,
+};
+use aleph_bft_mock::{
+    Hasher64, Loader, Network as MockNetwork, PartialMultisignature, Router, Saver, Signature,
+    Spawner,
+};
+use async_trait::async_trait;
+use futures::{
+    channel::mpsc::{unbounded, UnboundedSender},
+    StreamExt,
+};
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::timeout;
+
+const MAX_DATA_SIZE: usize = 1024;
+const STUFFED_DATA: usize = 4 * MAX_DATA_SIZE;
+const STUFFER: NodeIndex = NodeIndex(3);
+const FINALIZED_PER_MEMBER: usize = 60;
+
+type PayloadNetwork = MockNetwork<NetworkData<Hasher64, Vec<u8>, Signature, PartialMultisignature>>;
+
+/// Provides data starting with the index of the node, stuffed to more than the limit for the
+/// stuffer.
+struct PayloadProvider {
+    node_index: NodeIndex,
+    counter: u8,
+}
+
+#[async_trait]
+impl DataProvider for PayloadProvider {
+    type Output = Vec<u8>;
+
+    async fn get_data(&mut self) -> Option<Vec<u8>> {
+        self.counter = self.counter.wrapping_add(1);
+        let size = match self.node_index {
+            STUFFER => STUFFED_DATA,
+            _ => 2,
+        };
+        let mut data = vec![self.counter; size];
+        data[0] = self.node_index.0 as u8;
+        Some(data)
+    }
+}
+
+struct ForwardingHandler {
+    finalized: UnboundedSender<Vec<u8>>,
+}
+
+impl FinalizationHandler<Vec<u8>> for ForwardingHandler {
+    fn data_finalized(&mut self, data: Vec<u8>) {
+        let _ = self.finalized.unbounded_send(data);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn units_with_oversized_data_are_ignored() {
+    init_log();
+    let n_members = NodeCount(4);
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut members = Vec::new();
+    let mut finalized_streams = Vec::new();
+    for (network, _) in networks {
+        let network: PayloadNetwork = network;
+        let node_index = network.index();
+        let (finalized_tx, finalized_rx) = unbounded();
+        let local_io = LocalIO::new(
+            PayloadProvider {
+                node_index,
+                counter: 0,
+            },
+            ForwardingHandler {
+                finalized: finalized_tx,
+            },
+            Saver::new(),
+            Loader::new(vec![]),
+        );
+        let config = gen_config(node_index, n_members, gen_delay_config());
+        // The stuffer does not hold itself to the limit of the committee.
+        let config = match node_index {
+            STUFFER => config,
+            _ => config.with_max_data_size(MAX_DATA_SIZE),
+        };
+        members.push(spawn_session(spawner, config, local_io, network));
+        if node_index != STUFFER {
+            finalized_streams.push(finalized_rx);
+        }
+    }
+
+    let mut finalized: Vec<Vec<Vec<u8>>> = Vec::new();
+    for stream in finalized_streams.iter_mut() {
+        finalized.push(
+            timeout(
+                Duration::from_secs(60),
+                stream.take(FINALIZED_PER_MEMBER).collect(),
+            )
+            .await
+            .expect("the honest members should keep finalizing data"),
+        );
+    }
+    for (exit_tx, handle) in members {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    for other in finalized.iter().skip(1) {
+        assert_eq!(other, &finalized[0]);
+    }
+    assert!(finalized[0]
+        .iter()
+        .all(|data| data.len() <= MAX_DATA_SIZE && data[0] != STUFFER.0 as u8));
+}
@@ -0,0 +1,80 @@
+use crate::{
+    delay_control,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member_with_config, HonestMember,
+    },
+    ClockSource, NodeCount, SpawnHandle,
+};
+use aleph_bft_mock::{DataProvider, Router, Spawner, TokioClock};
+use futures::StreamExt;
+use serial_test::serial;
+use std::{sync::Arc, time::Duration};
+use tokio::time::Instant;
+
+const N_MEMBERS: usize = 4;
+const FAST_CREATION_DELAY: Duration = Duration::from_millis(10);
+const SLOW_CREATION_DELAY: Duration = Duration::from_millis(500);
+const ROUNDS: u32 = 20;
+/// The rounds created before the delay changed, but finalized after it.
+const ROUNDS_IN_FLIGHT: u32 = 5;
+
+/// How long it takes every member to finalize about the given number of rounds, as every
+/// member puts a data item in every unit.
+async fn time_to_finalize(members: &mut [HonestMember], rounds: u32) -> Duration {
+    let start = Instant::now();
+    for member in members.iter_mut() {
+        let n_data = rounds as usize * N_MEMBERS;
+        let finalized: Vec<_> = member.finalization_rx.by_ref().take(n_data).collect().await;
+        assert_eq!(finalized.len(), n_data);
+    }
+    start.elapsed()
+}
+
+// The runtime time is paused, so it only moves forward when all the tasks wait for timers.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn raising_the_creation_delay_slows_down_rounds() {
+    init_log();
+    let n_members = NodeCount(N_MEMBERS);
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+
+    let (delay_handle, delay_control) = delay_control();
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let mut delay_config = gen_delay_config();
+        delay_config.unit_creation_delay = Arc::new(|_| FAST_CREATION_DELAY);
+        let config = gen_config(network.index(), n_members, delay_config)
+            .with_clock(ClockSource::new(TokioClock))
+            .with_delay_control(delay_control.clone());
+        members.push(spawn_honest_member_with_config(
+            spawner,
+            config,
+            vec![],
+            DataProvider::new(),
+            network,
+        ));
+    }
+
+    let fast = time_to_finalize(&mut members, ROUNDS).await;
+    delay_handle.set_unit_creation_delay(Arc::new(|_| SLOW_CREATION_DELAY));
+    let slow = time_to_finalize(&mut members, ROUNDS).await;
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+
+    assert!(
+        fast < SLOW_CREATION_DELAY * ROUNDS / 4,
+        "{} rounds with the fast delay took {:?}",
+        ROUNDS,
+        fast
+    );
+    assert!(
+        slow >= SLOW_CREATION_DELAY * (ROUNDS - ROUNDS_IN_FLIGHT),
+        "{} rounds with the slow delay took only {:?}",
+        ROUNDS,
+        slow
+    );
+}
@@ -0,0 +1,136 @@
+use crate::{
+    delivery_control,
+    testing::{init_log, HonestMember, HonestMemberBuilder},
+    DeliveryControl, DeliveryControlHandle, NodeCount, OverflowPolicy, SpawnHandle,
+};
+use aleph_bft_mock::{Data, Router, Spawner};
+use futures::StreamExt;
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+/// Spawns the committee, the first members of which get the delivery controls. Their sessions
+/// may only end on their own if they are aborting.
+fn spawn_committee(
+    n_members: NodeCount,
+    delivery_controls: Vec<DeliveryControl>,
+    aborting: bool,
+) -> Vec<HonestMember> {
+    let mut delivery_controls = delivery_controls.into_iter();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+    networks
+        .into_iter()
+        .map(|(network, _)| {
+            let ix = network.index();
+            match delivery_controls.next() {
+                Some(delivery_control) => {
+                    let member = HonestMemberBuilder::new(ix, n_members)
+                        .with_local_io(|local_io| local_io.with_delivery_control(delivery_control));
+                    match aborting {
+                        true => member.with_early_termination_allowed(),
+                        false => member,
+                    }
+                    .spawn(spawner, network)
+                }
+                None => HonestMemberBuilder::new(ix, n_members).spawn(spawner, network),
+            }
+        })
+        .collect()
+}
+
+async fn wait_for_buffered_batches(handle: &DeliveryControlHandle, n_batches: usize) {
+    timeout(Duration::from_secs(60), async {
+        while handle.status().buffered_batches < n_batches {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("batches should get finalized while paused");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn paused_delivery_does_not_stop_consensus() {
+    init_log();
+    let n_members = NodeCount(4);
+    let n_paused_rounds = 20;
+    let n_data = 100;
+    // Pausing two out of four nodes, so the others could not progress without them.
+    let (handles, controls): (Vec<_>, Vec<_>) = (0..2)
+        .map(|_| delivery_control(1000, OverflowPolicy::Block))
+        .unzip();
+    for handle in &handles {
+        handle.pause_delivery();
+    }
+    let mut members = spawn_committee(n_members, controls, false);
+
+    for handle in &handles {
+        wait_for_buffered_batches(handle, n_paused_rounds).await;
+        assert!(handle.status().paused);
+    }
+    for member in members.iter_mut().take(handles.len()) {
+        assert!(member.finalization_rx.try_next().is_err());
+    }
+    let unpaused_data: Vec<Data> = timeout(
+        Duration::from_secs(10),
+        members[2]
+            .finalization_rx
+            .by_ref()
+            .take(n_paused_rounds)
+            .collect(),
+    )
+    .await
+    .expect("other nodes should not stall");
+    assert_eq!(unpaused_data.len(), n_paused_rounds);
+
+    for handle in &handles {
+        handle.resume_delivery();
+    }
+    let mut data = vec![Vec::new(); n_members.0];
+    data[2] = unpaused_data;
+    for (member, node_data) in members.iter_mut().zip(data.iter_mut()) {
+        let n_missing = n_data - node_data.len();
+        let missing_data: Vec<Data> = timeout(
+            Duration::from_secs(30),
+            member.finalization_rx.by_ref().take(n_missing).collect(),
+        )
+        .await
+        .expect("data should be delivered after resuming");
+        node_data.extend(missing_data);
+    }
+    for node_data in data.iter().skip(1) {
+        assert_eq!(&data[0], node_data);
+    }
+    for handle in &handles {
+        assert!(!handle.status().paused);
+    }
+
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn overflowing_delivery_buffer_aborts() {
+    init_log();
+    let n_members = NodeCount(4);
+    let (handle, control) = delivery_control(5, OverflowPolicy::Abort);
+    handle.pause_delivery();
+    let mut members = spawn_committee(n_members, vec![control], true);
+
+    let aborting = members.remove(0);
+    timeout(Duration::from_secs(30), aborting.handle)
+        .await
+        .expect("session should abort")
+        .expect("session should end cleanly");
+    assert!(handle.status().buffered_batches >= 5);
+
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+}
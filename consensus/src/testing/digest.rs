@@ -0,0 +1,176 @@
+use crate::{
+    dissemination::Request,
+    events::InternalEvent,
+    member::UnitMessage,
+    network::NetworkDataInner,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member_with_events, HonestMember,
+        NetworkData, TestEventBus,
+    },
+    NodeCount, NodeIndex, SpawnHandle,
+};
+use aleph_bft_mock::{Data, DataProvider, Hasher64, NetworkHook, Router, Signature, Spawner};
+use futures::{Stream, StreamExt};
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::timeout;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+
+fn is_digest(data: &NetworkData) -> bool {
+    matches!(
+        data,
+        crate::NetworkData(NetworkDataInner::Units(UnitMessage::DagDigest(_, _)), _)
+    )
+}
+
+/// Only lets digests through to the isolated node.
+struct IsolatingHook {
+    isolated: NodeIndex,
+}
+
+impl NetworkHook<NetworkData> for IsolatingHook {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<(NetworkData, NodeIndex, NodeIndex)> {
+        match recipient == self.isolated && !is_digest(&data) {
+            true => Vec::new(),
+            false => vec![(data, sender, recipient)],
+        }
+    }
+}
+
+/// Corrupts the digests sent by the given node, as if its store contained different units of
+/// the given creator.
+struct CorruptingHook {
+    corrupted: NodeIndex,
+    creator: NodeIndex,
+}
+
+impl NetworkHook<NetworkData> for CorruptingHook {
+    fn process_message(
+        &mut self,
+        mut data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<(NetworkData, NodeIndex, NodeIndex)> {
+        if let crate::NetworkData(NetworkDataInner::Units(UnitMessage::DagDigest(_, digest)), _) =
+            &mut data
+        {
+            if sender == self.corrupted {
+                digest.corrupt(self.creator);
+            }
+        }
+        vec![(data, sender, recipient)]
+    }
+}
+
+/// Spawns a committee with the given hook and returns the events of the observed node.
+fn spawn_committee(
+    spawner: Spawner,
+    hook: impl NetworkHook<NetworkData> + 'static,
+    observed: NodeIndex,
+) -> (
+    Vec<HonestMember>,
+    impl Stream<Item = InternalEvent<Hasher64, Data, Signature>>,
+) {
+    let (mut net_hub, networks) = Router::new(N_MEMBERS);
+    net_hub.add_hook(hook);
+    spawner.spawn("network-hub", net_hub);
+    let mut observed_events = None;
+    let members = networks
+        .into_iter()
+        .map(|(network, _)| {
+            let node_ix = network.index();
+            let events = TestEventBus::new();
+            if node_ix == observed {
+                observed_events = Some(events.subscribe());
+            }
+            spawn_honest_member_with_events(
+                spawner,
+                gen_config(node_ix, N_MEMBERS, gen_delay_config()),
+                vec![],
+                DataProvider::new(),
+                network,
+                events,
+            )
+        })
+        .collect();
+    (
+        members,
+        observed_events.expect("the observed node is a member"),
+    )
+}
+
+async fn stop(members: Vec<HonestMember>) {
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn lagging_node_catches_up_after_digest() {
+    init_log();
+    let isolated = NodeIndex(3);
+    let spawner = Spawner::new();
+    let (members, events) = spawn_committee(spawner, IsolatingHook { isolated }, isolated);
+
+    // The isolated node receives no units at all, so the only way for it to learn about
+    // units of higher rounds is from the digests of its peers.
+    let mut events = Box::pin(events);
+    let catch_up_request = async {
+        while let Some(event) = events.next().await {
+            match event {
+                InternalEvent::RequestIssued(Request::Coord(coord))
+                    if coord.creator() != isolated =>
+                {
+                    return coord
+                }
+                _ => {}
+            }
+        }
+        panic!("the event stream should be open");
+    };
+    let coord = timeout(Duration::from_secs(20), catch_up_request)
+        .await
+        .expect("the isolated node should start catching up");
+    assert!(coord.round() > 0, "requested {}", coord);
+    stop(members).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn corrupted_digest_is_reported_as_divergence() {
+    init_log();
+    let corrupted = NodeIndex(1);
+    let creator = NodeIndex(2);
+    let observer = NodeIndex(0);
+    let spawner = Spawner::new();
+    let (members, events) =
+        spawn_committee(spawner, CorruptingHook { corrupted, creator }, observer);
+
+    let divergences: Vec<_> = timeout(
+        Duration::from_secs(20),
+        events
+            .filter_map(|event| async move {
+                match event {
+                    InternalEvent::DagDivergence(peer, creators) => Some((peer, creators)),
+                    _ => None,
+                }
+            })
+            .take(2)
+            .collect(),
+    )
+    .await
+    .expect("the observer should notice the divergence");
+    for (peer, creators) in divergences {
+        assert_eq!(peer, corrupted);
+        assert_eq!(creators.elements().collect::<Vec<_>>(), vec![creator]);
+    }
+    stop(members).await;
+}
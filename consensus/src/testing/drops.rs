@@ -0,0 +1,105 @@
+use crate::{
+    drop_monitor,
+    member::UnitMessage::{DagDigest as DigestMessage, NewUnit},
+    network::NetworkDataInner::Units,
+    runway::DagDigest,
+    testing::{init_log, HonestMemberBuilder, NetworkData},
+    units::{ControlHash, FullUnit, PreUnit},
+    DropReason, DropStatsHandle, Network as _, NetworkData as NetworkDataT, NodeCount, NodeIndex,
+    NodeMap, Recipient, Signed, SpawnHandle,
+};
+use aleph_bft_mock::{Keychain, Router, Spawner};
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const OBSERVED: NodeIndex = NodeIndex(0);
+const BYZANTINE: NodeIndex = NodeIndex(1);
+const MALFORMED_DIGESTS: usize = 10;
+const UNIT_COPIES: usize = 5;
+
+fn malformed_digest() -> NetworkData {
+    NetworkDataT(
+        Units(DigestMessage(
+            BYZANTINE,
+            DagDigest::new(NodeCount(N_MEMBERS.0 - 1)),
+        )),
+        0,
+    )
+}
+
+fn wrong_session_unit() -> NetworkData {
+    let control_hash = ControlHash::new(&NodeMap::with_size(N_MEMBERS));
+    let full_unit = FullUnit::new(PreUnit::new(BYZANTINE, 0, control_hash), Some(0), 1);
+    let unit = Signed::sign(full_unit, &Keychain::new(N_MEMBERS, BYZANTINE))
+        .expect("the keychain never fails");
+    NetworkDataT(Units(NewUnit(unit.into())), 0)
+}
+
+async fn wait_for_count(handle: &DropStatsHandle, reason: DropReason, count: usize) {
+    timeout(Duration::from_secs(30), async {
+        while handle.stats().count(reason) < count {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("the messages should get dropped");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn dropped_messages_are_counted_and_sampled() {
+    init_log();
+    let spawner = Spawner::new();
+    let (drops, monitor) = drop_monitor();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut byzantine_network = None;
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let node_index = network.index();
+        if node_index == BYZANTINE {
+            byzantine_network = Some(network);
+            continue;
+        }
+        let member = HonestMemberBuilder::new(node_index, N_MEMBERS);
+        let member = match node_index {
+            OBSERVED => {
+                member.with_local_io(|local_io| local_io.with_drop_monitor(monitor.clone()))
+            }
+            _ => member,
+        };
+        members.push(member.spawn(spawner, network));
+    }
+    let byzantine_network = byzantine_network.expect("the byzantine node is a member");
+
+    drops.sample(1, Duration::from_secs(3600));
+    for _ in 0..MALFORMED_DIGESTS {
+        byzantine_network.send(malformed_digest(), Recipient::Node(OBSERVED));
+    }
+    for _ in 0..UNIT_COPIES {
+        byzantine_network.send(wrong_session_unit(), Recipient::Node(OBSERVED));
+    }
+    wait_for_count(&drops, DropReason::MalformedDigest, MALFORMED_DIGESTS).await;
+    wait_for_count(&drops, DropReason::KnownInvalidUnit, UNIT_COPIES - 1).await;
+    let stats = drops.stats();
+    assert_eq!(stats.count(DropReason::MalformedDigest), MALFORMED_DIGESTS);
+    assert_eq!(stats.count(DropReason::InvalidUnit), 1);
+    assert_eq!(stats.count(DropReason::KnownInvalidUnit), UNIT_COPIES - 1);
+    assert!(stats.samples_logged() >= MALFORMED_DIGESTS + UNIT_COPIES);
+
+    // Without sampling the drops are still counted, but no longer logged.
+    drops.stop_sampling();
+    let samples_logged = drops.stats().samples_logged();
+    for _ in 0..MALFORMED_DIGESTS {
+        byzantine_network.send(malformed_digest(), Recipient::Node(OBSERVED));
+    }
+    wait_for_count(&drops, DropReason::MalformedDigest, 2 * MALFORMED_DIGESTS).await;
+    assert_eq!(drops.stats().samples_logged(), samples_logged);
+
+    for member in members {
+        member.stop().await;
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{
+    testing::{gen_config, gen_delay_config, init_log, spawn_session, Network},
+    DataProvider as DataProviderT, LocalIO, NodeCount, SpawnHandle, TaskHandle,
+};
+use aleph_bft_mock::{Data, FinalizationHandler, Loader, Router, Saver, Spawner};
+use async_trait::async_trait;
+use futures::{
+    channel::{mpsc::UnboundedReceiver, oneshot},
+    StreamExt,
+};
+use serial_test::serial;
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::time::timeout;
+
+const DATA_PER_NODE: usize = 1_000_000;
+const FINALIZED_DATA: usize = 100;
+
+/// Has data ready only for every other unit, counting the units it had none for.
+struct IntermittentProvider {
+    start: usize,
+    calls: usize,
+    empty: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl DataProviderT for IntermittentProvider {
+    type Output = Data;
+
+    async fn get_data(&mut self) -> Option<Data> {
+        let call = self.calls;
+        self.calls += 1;
+        if call % 2 == 1 {
+            self.empty.fetch_add(1, Ordering::SeqCst);
+            return None;
+        }
+        Some((self.start + call) as Data)
+    }
+}
+
+fn spawn_member(
+    spawner: Spawner,
+    network: Network,
+    n_members: NodeCount,
+    empty: Arc<AtomicUsize>,
+) -> (UnboundedReceiver<Data>, oneshot::Sender<()>, TaskHandle) {
+    let node_index = network.index();
+    let (finalization_handler, finalization_rx) = FinalizationHandler::new();
+    let local_io = LocalIO::new(
+        IntermittentProvider {
+            start: node_index.0 * DATA_PER_NODE,
+            calls: 0,
+            empty,
+        },
+        finalization_handler,
+        Saver::new(),
+        Loader::new(vec![]),
+    );
+    let config = gen_config(node_index, n_members, gen_delay_config());
+    let (exit_tx, handle) = spawn_session(spawner, config, local_io, network);
+    (finalization_rx, exit_tx, handle)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn finalizes_only_data_of_units_that_have_some() {
+    init_log();
+    let n_members = NodeCount(4);
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+
+    let empty = Arc::new(AtomicUsize::new(0));
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        members.push(spawn_member(spawner, network, n_members, empty.clone()));
+    }
+
+    let mut batches = Vec::new();
+    for (finalization_rx, _, _) in members.iter_mut() {
+        let finalized: Vec<_> = timeout(
+            Duration::from_secs(60),
+            finalization_rx.take(FINALIZED_DATA).collect(),
+        )
+        .await
+        .expect("the session should keep finalizing");
+        batches.push(finalized);
+    }
+    for (_, exit_tx, handle) in members {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    assert!(empty.load(Ordering::SeqCst) > 0);
+    let finalized = &batches[0];
+    assert_eq!(finalized.len(), FINALIZED_DATA);
+    assert!(batches.iter().all(|batch| batch == finalized));
+    let distinct: HashSet<_> = finalized.iter().collect();
+    assert_eq!(distinct.len(), FINALIZED_DATA);
+    for data in finalized {
+        let call = *data as usize % DATA_PER_NODE;
+        assert_eq!(call % 2, 0, "{} was not provided", data);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{
+    events::InternalEvent,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member_with_events, HonestMember,
+        TestEventBus,
+    },
+    NodeCount, SpawnHandle,
+};
+use aleph_bft_mock::{DataProvider, Router, Spawner};
+use futures::StreamExt;
+use serial_test::serial;
+use std::collections::HashSet;
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn added_consumer_observes_session() {
+    init_log();
+    let n_members = NodeCount(4);
+    let n_batches = 10;
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut observed_events = None;
+    let mut batch_rxs = Vec::new();
+    let mut exits = Vec::new();
+    let mut handles = Vec::new();
+    for (network, _) in networks {
+        let ix = network.index();
+        let events = TestEventBus::new();
+        if observed_events.is_none() {
+            let mut consumer = events.subscribe();
+            observed_events = Some(tokio::spawn(async move {
+                let mut observed = Vec::new();
+                while let Some(event) = consumer.next().await {
+                    observed.push(event);
+                }
+                observed
+            }));
+        }
+        let HonestMember {
+            finalization_rx,
+            exit_tx,
+            handle,
+            ..
+        } = spawn_honest_member_with_events(
+            spawner,
+            gen_config(ix, n_members, gen_delay_config()),
+            vec![],
+            DataProvider::new(),
+            network,
+            events,
+        );
+        batch_rxs.push(finalization_rx);
+        exits.push(exit_tx);
+        handles.push(handle);
+    }
+
+    let finalized_data: Vec<_> = batch_rxs.remove(0).take(n_batches).collect().await;
+    assert_eq!(finalized_data.len(), n_batches);
+    for exit in exits {
+        let _ = exit.send(());
+    }
+    for handle in handles {
+        let _ = handle.await;
+    }
+
+    let observed_events = observed_events
+        .expect("consumer should be spawned")
+        .await
+        .expect("consumer should not panic");
+    let mut backed_up = HashSet::new();
+    let mut n_finalized = 0;
+    for event in observed_events {
+        match event {
+            InternalEvent::BackupAcked(hash) => {
+                backed_up.insert(hash);
+            }
+            InternalEvent::UnitFinalized(hash, _) => {
+                assert!(backed_up.contains(&hash), "finalized before backup");
+                n_finalized += 1;
+            }
+            InternalEvent::AlertStateChanged(..) => panic!("no alerts expected"),
+            _ => {}
+        }
+    }
+    assert!(n_finalized >= n_batches);
+}
@@ -0,0 +1,151 @@
+use crate::{
+    delivery_control,
+    events::InternalEvent,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member_with_delivery_control,
+        HonestMember, TestEventBus,
+    },
+    DeliveryControlHandle, ExtenderFlowControl, NodeCount, OverflowPolicy, SpawnHandle,
+};
+use aleph_bft_mock::{Data, DataProvider, Router, Spawner};
+use futures::StreamExt;
+use parking_lot::Mutex;
+use serial_test::serial;
+use std::{sync::Arc, time::Duration};
+use tokio::time::{sleep, timeout};
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const FLOW_CONTROL: ExtenderFlowControl = ExtenderFlowControl {
+    max_backlog: 40,
+    horizon: 3,
+    max_deferred: 10_000,
+};
+/// At 50ms per round the committee creates over two hundred units in the meantime.
+const INITIAL_PAUSE: Duration = Duration::from_secs(3);
+const THROTTLED_PAUSE: Duration = Duration::from_millis(300);
+const THROTTLED_RESUME: Duration = Duration::from_millis(20);
+const FINALIZED: usize = 200;
+
+/// The largest number of units the first node admitted, but did not finalize yet.
+#[derive(Clone, Default)]
+struct Backlog(Arc<Mutex<(usize, usize)>>);
+
+impl Backlog {
+    fn observe(&self, events: &TestEventBus) {
+        let backlog = self.0.clone();
+        let mut events = events.subscribe();
+        tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                let mut backlog = backlog.lock();
+                match event {
+                    InternalEvent::UnitAdmitted(_) => backlog.0 += 1,
+                    InternalEvent::UnitFinalized(..) => backlog.0 = backlog.0.saturating_sub(1),
+                    _ => continue,
+                }
+                backlog.1 = backlog.1.max(backlog.0);
+            }
+        });
+    }
+
+    fn max(&self) -> usize {
+        self.0.lock().1
+    }
+}
+
+/// Throttles the delivery of the first node, so that its ordering keeps falling behind.
+async fn throttle(handle: DeliveryControlHandle) {
+    sleep(INITIAL_PAUSE).await;
+    loop {
+        handle.resume_delivery();
+        sleep(THROTTLED_RESUME).await;
+        handle.pause_delivery();
+        sleep(THROTTLED_PAUSE).await;
+    }
+}
+
+/// Runs a committee with the first node throttled, until it finalizes enough data. Returns the
+/// largest backlog of its ordering.
+async fn run_throttled(flow_control: Option<ExtenderFlowControl>) -> usize {
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+    let (handle, control) = delivery_control(1, OverflowPolicy::Block);
+    handle.pause_delivery();
+    let mut control = Some(control);
+    let backlog = Backlog::default();
+    let mut members: Vec<HonestMember> = networks
+        .into_iter()
+        .map(|(network, _)| {
+            let ix = network.index();
+            let mut config = gen_config(ix, N_MEMBERS, gen_delay_config());
+            let events = TestEventBus::new();
+            let control = match ix.0 {
+                0 => {
+                    if let Some(flow_control) = flow_control {
+                        config = config.with_extender_flow_control(flow_control);
+                    }
+                    backlog.observe(&events);
+                    control.take().expect("there is one throttled node")
+                }
+                _ => Default::default(),
+            };
+            spawn_honest_member_with_delivery_control(
+                spawner,
+                config,
+                vec![],
+                DataProvider::new(),
+                network,
+                events,
+                control,
+            )
+        })
+        .collect();
+    let throttling = tokio::spawn(throttle(handle));
+
+    let throttled_data: Vec<Data> = timeout(
+        Duration::from_secs(60),
+        members[0]
+            .finalization_rx
+            .by_ref()
+            .take(FINALIZED)
+            .collect(),
+    )
+    .await
+    .expect("the throttled node should keep finalizing");
+    let data: Vec<Data> = members[1]
+        .finalization_rx
+        .by_ref()
+        .take(FINALIZED)
+        .collect()
+        .await;
+    assert_eq!(throttled_data, data);
+
+    throttling.abort();
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+    backlog.max()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn flow_control_bounds_the_backlog_of_a_throttled_ordering() {
+    init_log();
+    // The units of the rounds within the horizon and the ones about to be ordered come on top
+    // of the limit, but they are just a few rounds worth of units.
+    let bound = 2 * FLOW_CONTROL.max_backlog;
+    let controlled = run_throttled(Some(FLOW_CONTROL)).await;
+    assert!(
+        controlled <= bound,
+        "backlog of {} over the bound of {}",
+        controlled,
+        bound
+    );
+    let uncontrolled = run_throttled(None).await;
+    assert!(
+        uncontrolled > 2 * bound,
+        "backlog of only {} without flow control",
+        uncontrolled
+    );
+}
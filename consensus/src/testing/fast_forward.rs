@@ -0,0 +1,212 @@
+use crate::{
+    member::UnitMessage,
+    network::NetworkDataInner::Units,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member,
+        spawn_honest_member_with_config, HonestMember, NetworkData,
+    },
+    units::Unit,
+    NetworkData as NetworkDataT, NodeCount, NodeIndex, Round, SpawnHandle,
+    DEFAULT_FAST_FORWARD_LAG,
+};
+use aleph_bft_mock::{DataProvider, NetworkHook, Router, Spawner};
+use futures::channel::oneshot;
+use parking_lot::Mutex;
+use serial_test::serial;
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+use tokio::time::{sleep, timeout};
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const LATE: NodeIndex = NodeIndex(3);
+/// The round of the units of the late node after which it crashes.
+const BACKUP_ROUND: Round = 3;
+/// How far the others get while the late node is down.
+const HEAD_START: Round = 40;
+
+/// A new unit of the late node created after it restarted.
+struct LateUnit {
+    round: Round,
+    has_data: bool,
+    /// The highest round of the units of the others when the unit was sent.
+    frontier: Round,
+}
+
+#[derive(Default)]
+struct FastForwardState {
+    frontier: Round,
+    late_round: Option<Round>,
+    /// The highest round of the units of the late node before it crashed, once it restarted.
+    restarted_after: Option<Round>,
+    late_units: Vec<LateUnit>,
+}
+
+/// Watches the units broadcast by all the nodes, recording every unit of the late node only once.
+struct ObservingHook {
+    state: Arc<Mutex<FastForwardState>>,
+}
+
+impl NetworkHook<NetworkData> for ObservingHook {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<(NetworkData, NodeIndex, NodeIndex)> {
+        if let NetworkDataT(Units(UnitMessage::NewUnit(unit)), _) = &data {
+            let unit = unit.as_signable();
+            let mut state = self.state.lock();
+            match sender {
+                LATE if recipient == NodeIndex(0) => {
+                    state.late_round = state.late_round.max(Some(unit.round()));
+                    // The units from before the crash are sent again as well.
+                    if state
+                        .restarted_after
+                        .is_some_and(|round| unit.round() > round)
+                    {
+                        let frontier = state.frontier;
+                        state.late_units.push(LateUnit {
+                            round: unit.round(),
+                            has_data: unit.data().is_some(),
+                            frontier,
+                        });
+                    }
+                }
+                LATE => {}
+                _ => state.frontier = state.frontier.max(unit.round()),
+            }
+        }
+        vec![(data, sender, recipient)]
+    }
+}
+
+async fn wait_until(
+    state: &Arc<Mutex<FastForwardState>>,
+    done: impl Fn(&FastForwardState) -> bool,
+) {
+    timeout(Duration::from_secs(60), async {
+        while !done(&state.lock()) {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("the session should make progress");
+}
+
+/// Crashes the late node early, lets the others get far ahead and restarts the late node from
+/// its backup, returning its units until it reaches the round the others were at.
+async fn restart_late_node(fast_forward_lag: Option<Round>) -> (Vec<LateUnit>, Round) {
+    let spawner = Spawner::new();
+    let state = Arc::new(Mutex::new(FastForwardState::default()));
+    let (mut net_hub, networks) = Router::new(N_MEMBERS);
+    net_hub.add_hook(ObservingHook {
+        state: state.clone(),
+    });
+    spawner.spawn("network-hub", net_hub);
+
+    let mut members: Vec<HonestMember> = Vec::new();
+    let mut late = None;
+    for (network, reconnect_tx) in networks {
+        let node_ix = network.index();
+        let member = spawn_honest_member(
+            spawner,
+            node_ix,
+            N_MEMBERS,
+            vec![],
+            DataProvider::new(),
+            network,
+        );
+        match node_ix {
+            LATE => late = Some((member, reconnect_tx)),
+            _ => members.push(member),
+        }
+    }
+    let (late, reconnect_tx) = late.expect("the late node is a member");
+
+    wait_until(&state, |state| {
+        state.late_round.is_some_and(|round| round >= BACKUP_ROUND)
+    })
+    .await;
+    let _ = late.exit_tx.send(());
+    let _ = late.handle.await;
+    let backup = late.saved_state.lock().clone();
+
+    wait_until(&state, |state| state.frontier >= HEAD_START).await;
+    let start_frontier = {
+        let mut state = state.lock();
+        state.restarted_after = state.late_round;
+        state.frontier
+    };
+    let (network_tx, network_rx) = oneshot::channel();
+    reconnect_tx
+        .unbounded_send((LATE, network_tx))
+        .expect("the router should be running");
+    let network = network_rx.await.expect("the router should reconnect us");
+    let config =
+        gen_config(LATE, N_MEMBERS, gen_delay_config()).with_fast_forward_lag(fast_forward_lag);
+    members.push(spawn_honest_member_with_config(
+        spawner,
+        config,
+        backup,
+        DataProvider::new(),
+        network,
+    ));
+
+    wait_until(&state, |state| {
+        state
+            .late_units
+            .iter()
+            .any(|unit| unit.round >= start_frontier)
+    })
+    .await;
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+    let late_units = std::mem::take(&mut state.lock().late_units);
+    (late_units, start_frontier)
+}
+
+/// Units with data of rounds so far below the others that they were created only to catch up.
+fn stale_units_with_data(late_units: &[LateUnit]) -> usize {
+    late_units
+        .iter()
+        .filter(|unit| unit.has_data && unit.round + DEFAULT_FAST_FORWARD_LAG + 2 < unit.frontier)
+        .count()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn restarted_node_fast_forwards_to_the_frontier() {
+    init_log();
+    let (late_units, start_frontier) = restart_late_node(Some(DEFAULT_FAST_FORWARD_LAG)).await;
+    // No round is skipped, as each of our units needs the previous one as a parent.
+    let rounds: BTreeSet<_> = late_units.iter().map(|unit| unit.round).collect();
+    let lowest = *rounds.first().expect("the late node created units");
+    let highest = *rounds.last().expect("the late node created units");
+    assert_eq!(rounds.len(), (highest - lowest) as usize + 1);
+    // The first unit after the restart might be created from the backup alone, before hearing
+    // from the others how far behind we are.
+    let late_units: Vec<_> = late_units
+        .into_iter()
+        .filter(|unit| unit.round > lowest)
+        .collect();
+    let first_with_data = late_units
+        .iter()
+        .find(|unit| unit.has_data)
+        .expect("the late node should include data once it catches up");
+    assert!(
+        first_with_data.round + DEFAULT_FAST_FORWARD_LAG + 2 >= start_frontier,
+        "the first data of the late node is in round {}, the others were at round {}",
+        first_with_data.round,
+        start_frontier
+    );
+    assert_eq!(stale_units_with_data(&late_units), 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn restarted_node_without_fast_forward_puts_data_in_stale_units() {
+    init_log();
+    let (late_units, _) = restart_late_node(None).await;
+    assert!(stale_units_with_data(&late_units) > (HEAD_START / 2) as usize);
+}
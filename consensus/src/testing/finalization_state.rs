@@ -0,0 +1,195 @@
+use crate::{
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member, spawn_session,
+        spawn_session_allowing_early_termination, HonestMember, Network,
+    },
+    DeliveryCheckpoint, FinalizationState, FinalizationStateStore as _, Hasher, LocalIO, NodeCount,
+    NodeIndex, OrderedUnit, SpawnHandle, TaskHandle, UnitFinalizationHandler,
+};
+use aleph_bft_mock::{
+    Data, DataProvider, FinalizationStateStore, Hash64, Hasher64, Loader, Router, Saver, Spawner,
+};
+use futures::{
+    channel::{mpsc, oneshot},
+    StreamExt,
+};
+use parking_lot::Mutex;
+use serial_test::serial;
+use std::{collections::HashSet, sync::Arc, time::Duration};
+use tokio::time::timeout;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const RESTARTED: NodeIndex = NodeIndex(0);
+
+/// Passes the hashes of all the finalized units to a channel.
+struct RecordingHandler {
+    tx: mpsc::UnboundedSender<Hash64>,
+}
+
+impl UnitFinalizationHandler for RecordingHandler {
+    type Data = Data;
+    type Hasher = Hasher64;
+
+    fn batch_finalized(&mut self, batch: Vec<OrderedUnit<Data, Hasher64>>) {
+        for unit in batch {
+            let _ = self.tx.unbounded_send(unit.hash);
+        }
+    }
+}
+
+struct RecordingMember {
+    finalized_rx: mpsc::UnboundedReceiver<Hash64>,
+    saved_units: Arc<Mutex<Vec<u8>>>,
+    exit_tx: oneshot::Sender<()>,
+    handle: TaskHandle,
+}
+
+impl RecordingMember {
+    async fn receive(&mut self, n_units: usize) -> Vec<Hash64> {
+        timeout(
+            Duration::from_secs(30),
+            self.finalized_rx.by_ref().take(n_units).collect(),
+        )
+        .await
+        .expect("the member should keep finalizing units")
+    }
+
+    /// Stops the member, returning everything it finalized but was not received yet.
+    async fn kill(mut self) -> Vec<Hash64> {
+        let _ = self.exit_tx.send(());
+        let _ = self.handle.await;
+        let mut remaining = Vec::new();
+        while let Ok(Some(hash)) = self.finalized_rx.try_next() {
+            remaining.push(hash);
+        }
+        remaining
+    }
+}
+
+fn spawn_recording_member(
+    spawner: Spawner,
+    network: Network,
+    units: Vec<u8>,
+    finalization_state: FinalizationState<Hasher64>,
+    early_termination_allowed: bool,
+) -> RecordingMember {
+    let node_index = network.index();
+    let (tx, finalized_rx) = mpsc::unbounded();
+    let saved_units = Arc::new(Mutex::new(vec![]));
+    let unit_saver: Saver = saved_units.clone().into();
+    let local_io = LocalIO::new_with_unit_finalization_handler(
+        DataProvider::new(),
+        RecordingHandler { tx },
+        unit_saver,
+        Loader::new(units),
+    )
+    .with_finalization_state(finalization_state);
+    let config = gen_config(node_index, N_MEMBERS, gen_delay_config());
+    let (exit_tx, handle) = match early_termination_allowed {
+        true => spawn_session_allowing_early_termination(spawner, config, local_io, network),
+        false => spawn_session(spawner, config, local_io, network),
+    };
+    RecordingMember {
+        finalized_rx,
+        saved_units,
+        exit_tx,
+        handle,
+    }
+}
+
+fn spawn_other_members(spawner: Spawner, networks: Vec<Network>) -> Vec<HonestMember> {
+    networks
+        .into_iter()
+        .map(|network| {
+            spawn_honest_member(
+                spawner,
+                network.index(),
+                N_MEMBERS,
+                vec![],
+                DataProvider::new(),
+                network,
+            )
+        })
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn restarted_member_does_not_redeliver() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+    let mut networks = networks.into_iter();
+    let (network, reconnect_tx) = networks.next().expect("there are members");
+    let others = spawn_other_members(spawner, networks.map(|(network, _)| network).collect());
+
+    let store = FinalizationStateStore::new();
+    let finalization_state = FinalizationState::new(store.clone());
+    let mut member =
+        spawn_recording_member(spawner, network, vec![], finalization_state.clone(), false);
+    let mut before_restart = member.receive(20).await;
+    let saved_units = member.saved_units.lock().clone();
+    before_restart.extend(member.kill().await);
+
+    let (tx, rx) = oneshot::channel();
+    reconnect_tx
+        .unbounded_send((RESTARTED, tx))
+        .expect("the router should be running");
+    let network = rx.await.expect("the router should reconnect");
+    let mut member = spawn_recording_member(
+        spawner,
+        network,
+        saved_units,
+        FinalizationState::new(store),
+        false,
+    );
+    let after_restart = member.receive(20).await;
+    member.kill().await;
+    for other in others {
+        let _ = other.exit_tx.send(());
+        let _ = other.handle.await;
+    }
+
+    let before_restart: HashSet<_> = before_restart.into_iter().collect();
+    for hash in &after_restart {
+        assert!(!before_restart.contains(hash), "{:?} delivered twice", hash);
+    }
+    for hash in &before_restart {
+        assert!(finalization_state
+            .is_finalized(hash)
+            .expect("the mock store works"));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn store_ahead_of_backup_is_rejected() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, mut networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+    let (network, _) = networks.remove(RESTARTED.0);
+
+    // A checkpoint of a unit that is not in the backup, e.g. because the backup was lost.
+    let mut store = FinalizationStateStore::new();
+    store
+        .put_checkpoint(DeliveryCheckpoint {
+            batches: 3,
+            head: Hasher64::hash(b"unknown"),
+            batch_id: Hasher64::hash(b"unknown batch"),
+        })
+        .expect("the mock store works");
+    let mut member = spawn_recording_member(
+        spawner,
+        network,
+        vec![],
+        FinalizationState::new(store),
+        true,
+    );
+    timeout(Duration::from_secs(10), &mut member.handle)
+        .await
+        .expect("the session should end on its own")
+        .expect("the session should end properly");
+    assert!(member.finalized_rx.try_next().ok().flatten().is_none());
+}
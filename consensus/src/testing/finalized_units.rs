@@ -0,0 +1,122 @@
+use crate::{
+    testing::{gen_config, gen_delay_config, init_log, spawn_session},
+    FinalizationHandler, FinalizedUnitInfo, LocalIO, NodeCount, NodeIndex, OrderedUnit, Round,
+    SpawnHandle, UnitFinalizationHandler,
+};
+use aleph_bft_mock::{Data, DataProvider, Hasher64, Loader, Router, Saver, Spawner};
+use futures::{channel::mpsc, StreamExt};
+use serial_test::serial;
+use std::{collections::HashSet, time::Duration};
+use tokio::time::timeout;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const ATTRIBUTING: NodeIndex = NodeIndex(0);
+const UNITS: usize = 60;
+/// The data of every member comes from its own range, so it shows who created the unit.
+const DATA_RANGE: usize = 1_000_000;
+
+type UnitRecord = (NodeIndex, Round, Option<Data>);
+
+/// Passes the finalized units, as seen by a data-level handler, to a channel.
+struct AttributingHandler {
+    tx: mpsc::UnboundedSender<UnitRecord>,
+}
+
+impl FinalizationHandler<Data> for AttributingHandler {
+    fn data_finalized(&mut self, _data: Data) {
+        unreachable!("the units are passed in full");
+    }
+
+    fn unit_finalized(&mut self, unit: FinalizedUnitInfo<Data>) {
+        let _ = self
+            .tx
+            .unbounded_send((unit.creator, unit.round, unit.data));
+    }
+}
+
+/// Passes the ordered units to a channel.
+struct OrderingHandler {
+    tx: mpsc::UnboundedSender<UnitRecord>,
+}
+
+impl UnitFinalizationHandler for OrderingHandler {
+    type Data = Data;
+    type Hasher = Hasher64;
+
+    fn batch_finalized(&mut self, batch: Vec<OrderedUnit<Data, Hasher64>>) {
+        for unit in batch {
+            let _ = self
+                .tx
+                .unbounded_send((unit.creator, unit.round, unit.data));
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn finalized_units_are_attributed_to_their_creators() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut unit_rxs = Vec::new();
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let node_ix = network.index();
+        let (tx, unit_rx) = mpsc::unbounded();
+        let data_provider =
+            DataProvider::new_range(node_ix.0 * DATA_RANGE, (node_ix.0 + 1) * DATA_RANGE);
+        let config = gen_config(node_ix, N_MEMBERS, gen_delay_config());
+        let member = match node_ix {
+            ATTRIBUTING => {
+                let local_io = LocalIO::new(
+                    data_provider,
+                    AttributingHandler { tx },
+                    Saver::new(),
+                    Loader::new(vec![]),
+                );
+                spawn_session(spawner, config, local_io, network)
+            }
+            _ => {
+                let local_io = LocalIO::new_with_unit_finalization_handler(
+                    data_provider,
+                    OrderingHandler { tx },
+                    Saver::new(),
+                    Loader::new(vec![]),
+                );
+                spawn_session(spawner, config, local_io, network)
+            }
+        };
+        members.push(member);
+        unit_rxs.push(unit_rx);
+    }
+
+    let mut records = Vec::new();
+    for unit_rx in unit_rxs {
+        let units: Vec<_> = timeout(Duration::from_secs(30), unit_rx.take(UNITS).collect())
+            .await
+            .expect("the members should keep finalizing");
+        records.push(units);
+    }
+    for (exit_tx, handle) in members {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    // The attribution matches the units the other members actually ordered.
+    for units in &records {
+        assert_eq!(units, &records[ATTRIBUTING.0]);
+    }
+    let units = &records[ATTRIBUTING.0];
+    let distinct: HashSet<_> = units
+        .iter()
+        .map(|(creator, round, _)| (creator, round))
+        .collect();
+    assert_eq!(distinct.len(), UNITS);
+    for (creator, _, data) in units {
+        if let Some(data) = data {
+            assert_eq!(*data as usize / DATA_RANGE, creator.0);
+        }
+    }
+}
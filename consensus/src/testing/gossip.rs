@@ -0,0 +1,105 @@
+use crate::{
+    member::UnitMessage::NewUnit,
+    network::NetworkDataInner::Units,
+    testing::{init_log, HonestMemberBuilder, Network, NetworkData},
+    units::Unit,
+    BroadcastStrategy, NetworkData as NetworkDataT, NodeCount, NodeIndex, SpawnHandle,
+};
+use aleph_bft_mock::{Hash64, NetworkHook, Router, Spawner};
+use futures::StreamExt;
+use parking_lot::Mutex;
+use serial_test::serial;
+use std::{collections::HashSet, sync::Arc, time::Duration};
+use tokio::time::timeout;
+
+const N_MEMBERS: NodeCount = NodeCount(10);
+const FANOUT: usize = 2;
+const FINALIZED: usize = 30;
+
+#[derive(Default)]
+struct UnitTraffic {
+    units: HashSet<Hash64>,
+    sent_by_creators: usize,
+}
+
+impl UnitTraffic {
+    /// How many messages a creator sends on average to spread one of its units.
+    fn sent_by_creators_per_unit(&self) -> f64 {
+        self.sent_by_creators as f64 / self.units.len() as f64
+    }
+}
+
+/// Counts the new units sent by their creators.
+#[derive(Clone, Default)]
+struct UnitTrafficHook {
+    traffic: Arc<Mutex<UnitTraffic>>,
+}
+
+impl NetworkHook<NetworkData> for UnitTrafficHook {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<(NetworkData, NodeIndex, NodeIndex)> {
+        if let NetworkDataT(Units(NewUnit(unit)), _) = &data {
+            let full_unit = unit.as_signable();
+            let mut traffic = self.traffic.lock();
+            traffic.units.insert(full_unit.hash());
+            if full_unit.creator() == sender {
+                traffic.sent_by_creators += 1;
+            }
+        }
+        vec![(data, sender, recipient)]
+    }
+}
+
+async fn run_with(broadcast_strategy: BroadcastStrategy) -> UnitTraffic {
+    let spawner = Spawner::new();
+    let (mut net_hub, networks) = Router::new(N_MEMBERS);
+    let hook = UnitTrafficHook::default();
+    net_hub.add_hook(hook.clone());
+    spawner.spawn("network-hub", net_hub);
+
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let network: Network = network;
+        let member = HonestMemberBuilder::new(network.index(), N_MEMBERS)
+            .with_config(|config| config.with_broadcast_strategy(broadcast_strategy));
+        members.push(member.spawn(spawner, network));
+    }
+
+    let mut finalized = Vec::new();
+    for member in &mut members {
+        let batch: Vec<_> = timeout(
+            Duration::from_secs(60),
+            member.finalization_rx.by_ref().take(FINALIZED).collect(),
+        )
+        .await
+        .expect("the members should finalize");
+        finalized.push(batch);
+    }
+    for member in members {
+        member.stop().await;
+    }
+
+    for node_finalized in &finalized {
+        assert_eq!(node_finalized, &finalized[0]);
+    }
+    let traffic = std::mem::take(&mut *hook.traffic.lock());
+    traffic
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn gossiping_units_finalizes_with_fewer_messages_from_creators() {
+    init_log();
+    let direct = run_with(BroadcastStrategy::Direct).await;
+    let gossip = run_with(BroadcastStrategy::Gossip { fanout: FANOUT }).await;
+    assert!(
+        gossip.sent_by_creators_per_unit() < direct.sent_by_creators_per_unit(),
+        "gossip: {} messages per unit, direct: {}",
+        gossip.sent_by_creators_per_unit(),
+        direct.sent_by_creators_per_unit(),
+    );
+}
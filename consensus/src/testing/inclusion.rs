@@ -0,0 +1,164 @@
+use crate::{
+    backup::BackupFingerprint,
+    creation::InclusionChange,
+    delivery_control,
+    events::InternalEvent,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member_with_delivery_control,
+        HonestMember, TestEventBus,
+    },
+    units::{UncheckedSignedUnit, Unit},
+    AdaptiveInclusion, DeliveryControl, NodeCount, NodeIndex, OverflowPolicy, Round, SpawnHandle,
+};
+use aleph_bft_mock::{Data, DataProvider, Hasher64, Router, Signature, Spawner};
+use codec::Decode;
+use futures::{channel::mpsc::Receiver, StreamExt};
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+/// The data of each node comes from a separate range, so that it can be told apart.
+const DATA_PER_NODE: usize = 1_000_000;
+
+const POLICY: AdaptiveInclusion = AdaptiveInclusion {
+    pause_latency: 10,
+    resume_latency: 6,
+    window: 4,
+};
+
+type TestUnit = UncheckedSignedUnit<Hasher64, Data, Signature>;
+type TestEvent = InternalEvent<Hasher64, Data, Signature>;
+
+async fn next_inclusion_change(events: &mut Receiver<TestEvent>) -> (Round, InclusionChange) {
+    timeout(Duration::from_secs(60), async {
+        loop {
+            if let Some(InternalEvent::DataInclusionChanged(round, change)) = events.next().await {
+                return (round, change);
+            }
+        }
+    })
+    .await
+    .expect("the inclusion should change")
+}
+
+fn own_units(mut backup: &[u8], own_id: NodeIndex) -> Vec<TestUnit> {
+    BackupFingerprint::<Hasher64>::read_header(&mut backup).expect("the header is correct");
+    let mut units = Vec::new();
+    while !backup.is_empty() {
+        let unit = TestUnit::decode(&mut backup).expect("backup should decode");
+        if unit.as_signable().creator() == own_id {
+            units.push(unit);
+        }
+    }
+    units.sort_by_key(|unit| unit.as_signable().round());
+    units
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn late_finalization_holds_back_data() {
+    init_log();
+    let n_members = NodeCount(4);
+    let own_id = NodeIndex(0);
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+    let (delivery_handle, own_delivery_control) = delivery_control(2, OverflowPolicy::Block);
+    let events = TestEventBus::new();
+    let mut observed_events = events.subscribe();
+    let mut own_delivery_control = Some(own_delivery_control);
+    let mut members: Vec<_> = networks
+        .into_iter()
+        .map(|(network, _)| {
+            let ix = network.index();
+            let mut config = gen_config(ix, n_members, gen_delay_config());
+            let mut delivery_control = DeliveryControl::default();
+            let mut member_events = TestEventBus::new();
+            if ix == own_id {
+                config = config.with_adaptive_inclusion(POLICY);
+                delivery_control = own_delivery_control.take().expect("only one own node");
+                member_events = events.clone();
+            }
+            spawn_honest_member_with_delivery_control(
+                spawner,
+                config,
+                vec![],
+                DataProvider::new_range(ix.0 * DATA_PER_NODE, (ix.0 + 1) * DATA_PER_NODE),
+                network,
+                member_events,
+                delivery_control,
+            )
+        })
+        .collect();
+
+    let own_finalized = |data: &[Data]| {
+        data.iter()
+            .filter(|data| (**data as usize) < DATA_PER_NODE)
+            .count()
+    };
+    let mut finalized: Vec<Data> = timeout(
+        Duration::from_secs(30),
+        members[own_id.0]
+            .finalization_rx
+            .by_ref()
+            .take(20)
+            .collect(),
+    )
+    .await
+    .expect("the session should finalize data");
+
+    // Stalls finalization on our node, while the others keep building the DAG.
+    delivery_handle.pause_delivery();
+    let (pause_round, change) = next_inclusion_change(&mut observed_events).await;
+    assert!(matches!(change, InclusionChange::Paused(latency) if latency >= POLICY.pause_latency));
+    sleep(Duration::from_secs(1)).await;
+    delivery_handle.resume_delivery();
+    let (resume_round, change) = next_inclusion_change(&mut observed_events).await;
+    assert!(
+        matches!(change, InclusionChange::Resumed(latency) if latency <= POLICY.resume_latency)
+    );
+    assert!(resume_round > pause_round + POLICY.pause_latency);
+    sleep(Duration::from_secs(1)).await;
+
+    let others = members.split_off(own_id.0 + 1);
+    let HonestMember {
+        mut finalization_rx,
+        saved_state,
+        exit_tx,
+        handle,
+    } = members.pop().expect("our node is the first one");
+    let _ = exit_tx.send(());
+    let _ = handle.await;
+    while let Ok(Some(data)) = finalization_rx.try_next() {
+        finalized.push(data);
+    }
+    for member in others {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+
+    let units = own_units(&saved_state.lock(), own_id);
+    let mut consumed = Vec::new();
+    for unit in &units {
+        let round = unit.as_signable().round();
+        let data = unit.as_signable().data();
+        if round < pause_round {
+            assert!(data.is_some(), "unit of round {} should carry data", round);
+        } else if round < resume_round {
+            assert!(
+                data.is_none(),
+                "unit of round {} should be data-less",
+                round
+            );
+        } else if round == resume_round {
+            assert!(data.is_some(), "unit of round {} should carry data", round);
+        }
+        consumed.extend(data.iter().copied());
+    }
+    // Nothing got lost on the way, the provider was just not polled.
+    consumed.sort();
+    assert!(consumed.iter().copied().eq(0..consumed.len() as Data));
+    let n_finalized = own_finalized(&finalized);
+    assert!(n_finalized <= consumed.len());
+    assert!(consumed.len() <= n_finalized + POLICY.pause_latency as usize);
+}
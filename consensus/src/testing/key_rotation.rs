@@ -0,0 +1,129 @@
+use crate::{
+    events::EventBus,
+    keychain_rotation,
+    member::UnitMessage::NewUnit,
+    network::NetworkDataInner::Units,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_session_with_events, Network, NetworkData,
+    },
+    units::Unit,
+    LocalIO, NetworkData as NetworkDataT, NodeCount, NodeIndex, NodeSubset, SpawnHandle,
+};
+use aleph_bft_mock::{
+    DataProvider, FinalizationHandler, Keychain, Loader, NetworkHook, Router, Saver, Spawner,
+};
+use futures::StreamExt;
+use parking_lot::Mutex;
+use serial_test::serial;
+use std::{sync::Arc, time::Duration};
+use tokio::time::timeout;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const ROTATED: [NodeIndex; 2] = [NodeIndex(0), NodeIndex(1)];
+const KEY_COUNT: u8 = 2;
+const FINALIZED_PER_PHASE: usize = 20;
+
+/// Remembers the creators of the units sent over the network with a signature of a key other
+/// than the first one.
+#[derive(Clone)]
+struct RotatedKeyHook {
+    creators: Arc<Mutex<NodeSubset>>,
+}
+
+impl NetworkHook<NetworkData> for RotatedKeyHook {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<(NetworkData, NodeIndex, NodeIndex)> {
+        if let NetworkDataT(Units(NewUnit(unit)), _) = &data {
+            if unit.signature().msg().starts_with(b"KEY") {
+                self.creators.lock().insert(unit.as_signable().creator());
+            }
+        }
+        vec![(data, sender, recipient)]
+    }
+}
+
+fn keychain_with_key(node_ix: NodeIndex, key: u8) -> Keychain {
+    Keychain::new(N_MEMBERS, node_ix)
+        .with_key_count(KEY_COUNT)
+        .with_key(key)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn consensus_proceeds_across_key_rotation() {
+    init_log();
+    let spawner = Spawner::new();
+    let (mut net_hub, networks) = Router::new(N_MEMBERS);
+    let hook = RotatedKeyHook {
+        creators: Arc::new(Mutex::new(NodeSubset::with_size(N_MEMBERS))),
+    };
+    net_hub.add_hook(hook.clone());
+    spawner.spawn("network-hub", net_hub);
+
+    let mut finalized_rxs = Vec::new();
+    let mut rotation_handles = Vec::new();
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let network: Network = network;
+        let node_ix = network.index();
+        let (rotation_handle, keychain) = keychain_rotation(keychain_with_key(node_ix, 0));
+        rotation_handles.push(rotation_handle);
+        let (finalization_handler, finalized_rx) = FinalizationHandler::new();
+        finalized_rxs.push(finalized_rx);
+        let local_io = LocalIO::new(
+            DataProvider::new(),
+            finalization_handler,
+            Saver::new(),
+            Loader::new(vec![]),
+        );
+        let config = gen_config(node_ix, N_MEMBERS, gen_delay_config());
+        members.push(spawn_session_with_events(
+            spawner,
+            config,
+            local_io,
+            network,
+            keychain,
+            EventBus::new(),
+        ));
+    }
+
+    let mut finalized = Vec::new();
+    for finalized_rx in &mut finalized_rxs {
+        let batch: Vec<_> = timeout(
+            Duration::from_secs(30),
+            finalized_rx.take(FINALIZED_PER_PHASE).collect(),
+        )
+        .await
+        .expect("the members should finalize with the first keys");
+        finalized.push(batch);
+    }
+    assert!(hook.creators.lock().is_empty());
+    for node_ix in ROTATED {
+        rotation_handles[node_ix.0]
+            .rotate(keychain_with_key(node_ix, 1))
+            .expect("the keychain is of the same node");
+    }
+    for (node_finalized, finalized_rx) in finalized.iter_mut().zip(&mut finalized_rxs) {
+        let batch: Vec<_> = timeout(
+            Duration::from_secs(30),
+            finalized_rx.take(FINALIZED_PER_PHASE).collect(),
+        )
+        .await
+        .expect("the members should keep finalizing after the rotation");
+        node_finalized.extend(batch);
+    }
+    for (exit_tx, handle) in members {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    for node_finalized in &finalized {
+        assert_eq!(node_finalized, &finalized[0]);
+    }
+    let creators = hook.creators.lock();
+    assert_eq!(creators.elements().collect::<Vec<_>>(), ROTATED.to_vec());
+}
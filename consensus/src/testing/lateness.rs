@@ -0,0 +1,157 @@
+use crate::{
+    events::InternalEvent,
+    lateness::LateUnits,
+    lateness_monitor,
+    testing::{
+        gen_config, gen_delay_config, init_log, HonestMemberBuilder, NetworkData, TestEventBus,
+    },
+    DelayConfig, LatenessHandle, NodeCount, NodeIndex, Round, SpawnHandle,
+};
+use aleph_bft_mock::{NetworkHook, Router, Spawner};
+use futures::StreamExt;
+use serial_test::serial;
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::time::timeout;
+
+const N_MEMBERS: usize = 7;
+const SLOW_PEERS: [NodeIndex; 2] = [NodeIndex(2), NodeIndex(3)];
+const SLOW_PEER_DELAY: Duration = Duration::from_millis(300);
+const OBSERVER: NodeIndex = NodeIndex(0);
+
+type RoutedMessage = (NetworkData, NodeIndex, NodeIndex);
+
+/// Delays all the messages sent by the slow peers.
+#[derive(Default)]
+struct SlowPeersHook {
+    buffer: VecDeque<(Instant, RoutedMessage)>,
+}
+
+impl NetworkHook<NetworkData> for SlowPeersHook {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<RoutedMessage> {
+        if !SLOW_PEERS.contains(&sender) {
+            let mut result = vec![(data, sender, recipient)];
+            result.extend(self.release());
+            return result;
+        }
+        self.buffer
+            .push_back((Instant::now(), (data, sender, recipient)));
+        self.release()
+    }
+}
+
+impl SlowPeersHook {
+    fn release(&mut self) -> Vec<RoutedMessage> {
+        let mut result = Vec::new();
+        while let Some((when, _)) = self.buffer.front() {
+            if when.elapsed() < SLOW_PEER_DELAY {
+                break;
+            }
+            let (_, message) = self
+                .buffer
+                .pop_front()
+                .expect("just checked it is not empty");
+            result.push(message);
+        }
+        result
+    }
+}
+
+/// Runs a session in which the slow peers are delayed and returns the first `n_summaries` late
+/// units summaries of the observer, together with its lateness scores.
+async fn observe_lateness(
+    observer_delay_config: DelayConfig,
+    n_summaries: usize,
+) -> (Vec<LateUnits>, LatenessHandle) {
+    let n_members = NodeCount(N_MEMBERS);
+    let spawner = Spawner::new();
+    let (mut net_hub, networks) = Router::new(n_members);
+    net_hub.add_hook(SlowPeersHook::default());
+    spawner.spawn("network-hub", net_hub);
+
+    let mut members = Vec::new();
+    let events = TestEventBus::new();
+    let summaries = events.subscribe().filter_map(|event| async move {
+        match event {
+            InternalEvent::LateUnits(late_units) => Some(late_units),
+            _ => None,
+        }
+    });
+    let (lateness, lateness_monitor) = lateness_monitor();
+    for (network, _) in networks {
+        let node_index = network.index();
+        let member = match node_index {
+            OBSERVER => HonestMemberBuilder::from_config(gen_config(
+                node_index,
+                n_members,
+                observer_delay_config.clone(),
+            ))
+            .with_events(events.clone())
+            .with_local_io(|local_io| local_io.with_lateness_monitor(lateness_monitor.clone())),
+            _ => HonestMemberBuilder::new(node_index, n_members),
+        };
+        members.push(member.spawn(spawner, network));
+    }
+
+    let collected = timeout(
+        Duration::from_secs(60),
+        summaries.take(n_summaries).collect(),
+    )
+    .await
+    .expect("the observer should keep creating units");
+    for member in members {
+        member.stop().await;
+    }
+    (collected, lateness)
+}
+
+fn late_peers(late_units: &LateUnits) -> HashSet<NodeIndex> {
+    late_units.late.elements().collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn slow_peers_are_reported_late() {
+    init_log();
+    // The first rounds are skipped, as the members start at slightly different moments.
+    let skipped_rounds: Round = 3;
+    let (summaries, lateness) = observe_lateness(gen_delay_config(), 25).await;
+    let slow_peers: HashSet<_> = SLOW_PEERS.into_iter().collect();
+    let summaries: Vec<_> = summaries
+        .into_iter()
+        .filter(|late_units| late_units.round >= skipped_rounds)
+        .collect();
+    assert!(!summaries.is_empty());
+    for late_units in summaries.iter() {
+        assert_eq!(late_peers(late_units), slow_peers, "{}", late_units);
+        assert!(late_units.max_lateness > Duration::ZERO);
+    }
+    let scores = lateness.scores();
+    for (node_id, score) in scores.iter() {
+        match SLOW_PEERS.contains(&node_id) {
+            true => assert!(*score > 0.5, "{:?} should be late: {}", node_id, score),
+            false => assert!(*score < 0.1, "{:?} should be on time: {}", node_id, score),
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn slow_peers_are_not_late_for_a_patient_observer() {
+    init_log();
+    let mut delay_config = gen_delay_config();
+    delay_config.unit_creation_delay = Arc::new(|_| 2 * SLOW_PEER_DELAY);
+    let (summaries, lateness) = observe_lateness(delay_config, 5).await;
+    for late_units in summaries.iter() {
+        assert!(late_peers(late_units).is_empty(), "{}", late_units);
+    }
+    assert!(lateness.scores().values().all(|score| *score == 0.0));
+}
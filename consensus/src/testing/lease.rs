@@ -0,0 +1,102 @@
+use crate::{
+    lease_control,
+    testing::{init_log, HonestMemberBuilder},
+    unit_metadata_monitor, Lease, LeaseStatus, LeaseStore as _, NodeCount, NodeIndex, Round,
+    SpawnHandle, UnitMetadataProvider, EXTENDED_MESSAGES_VERSION,
+};
+use aleph_bft_mock::{Hasher64, LeaseStore, Router, Spawner};
+use futures::{channel::oneshot, StreamExt};
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const DUPLICATED: NodeIndex = NodeIndex(0);
+const READER: NodeIndex = NodeIndex(1);
+const RENEWAL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Tags every unit, so that the reader sees which members create units.
+struct CreatorTag;
+
+impl UnitMetadataProvider for CreatorTag {
+    fn metadata(&self, _round: Round) -> Vec<u8> {
+        vec![1]
+    }
+}
+
+/// Keeps renewing the lease like another running instance of the member would, until stopped.
+async fn other_instance(mut store: LeaseStore, mut stop: oneshot::Receiver<()>) {
+    for counter in 1.. {
+        store
+            .put_lease(Lease {
+                instance_id: u64::MAX,
+                counter,
+                timestamp: 0,
+            })
+            .expect("the mock store does not fail");
+        tokio::select! {
+            _ = sleep(RENEWAL_INTERVAL / 2) => {},
+            _ = &mut stop => return,
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn duplicated_member_creates_units_only_after_the_other_instance_stops() {
+    init_log();
+    let spawner = Spawner::new();
+    let store = LeaseStore::new();
+    let (stop_other_instance, stop) = oneshot::channel();
+    spawner.spawn("other-instance", other_instance(store.clone(), stop));
+    let (lease, control) = lease_control(store);
+    let (metadata, monitor) = unit_metadata_monitor::<Hasher64>();
+
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let node_index = network.index();
+        let member = HonestMemberBuilder::new(node_index, N_MEMBERS).with_config(|config| {
+            config
+                .with_wire_version(EXTENDED_MESSAGES_VERSION)
+                .with_lease_renewal_interval(RENEWAL_INTERVAL)
+        });
+        let member = match node_index {
+            DUPLICATED => member.with_local_io(|local_io| {
+                local_io
+                    .with_unit_metadata_provider(CreatorTag)
+                    .with_lease_control(control.clone())
+            }),
+            READER => member
+                .with_local_io(|local_io| local_io.with_unit_metadata_monitor(monitor.clone())),
+            _ => member,
+        };
+        members.push(member.spawn(spawner, network));
+    }
+    let finalized = &mut members[READER.0].finalization_rx;
+
+    // The rest of the committee keeps finalizing without the duplicated member.
+    let data: Vec<_> = timeout(Duration::from_secs(60), finalized.take(30).collect())
+        .await
+        .expect("the reader should keep finalizing");
+    assert_eq!(data.len(), 30);
+    assert_eq!(lease.status(), LeaseStatus::DuplicateInstanceSuspected);
+    assert!(metadata.take().is_empty());
+
+    stop_other_instance
+        .send(())
+        .expect("the other instance is running");
+    timeout(Duration::from_secs(60), async {
+        while metadata.take().is_empty() {
+            sleep(RENEWAL_INTERVAL).await;
+        }
+    })
+    .await
+    .expect("the member should take the lease over and create units");
+    assert_eq!(lease.status(), LeaseStatus::Held);
+
+    for member in members {
+        member.stop().await;
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{
+    metrics_monitor,
+    testing::{init_log, spawn_honest_member, HonestMemberBuilder},
+    MetricsEvent, NodeCount, NodeIndex, SpawnHandle,
+};
+use aleph_bft_mock::{DataProvider, Router, Spawner};
+use futures::StreamExt;
+use serial_test::serial;
+use std::{collections::HashSet, time::Duration};
+use tokio::time::timeout;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const MEASURED: NodeIndex = NodeIndex(0);
+const FINALIZED_UNITS: usize = 40;
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn metrics_follow_an_honest_run() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut others = Vec::new();
+    let mut measured = None;
+    for (network, _) in networks {
+        match network.index() {
+            MEASURED => measured = Some(network),
+            node_ix => others.push(spawn_honest_member(
+                spawner,
+                node_ix,
+                N_MEMBERS,
+                vec![],
+                DataProvider::new(),
+                network,
+            )),
+        }
+    }
+    let network = measured.expect("the measured node is a member");
+    let (mut metrics, metrics_monitor) = metrics_monitor();
+    let measured = HonestMemberBuilder::new(MEASURED, N_MEMBERS)
+        .with_local_io(|local_io| local_io.with_metrics_monitor(metrics_monitor))
+        .spawn(spawner, network);
+
+    let mut events = Vec::new();
+    let mut n_finalized = 0;
+    timeout(Duration::from_secs(30), async {
+        while n_finalized < FINALIZED_UNITS {
+            let event = metrics.next().await.expect("the session is running");
+            if let MetricsEvent::UnitFinalized { .. } = event {
+                n_finalized += 1;
+            }
+            events.push(event);
+        }
+    })
+    .await
+    .expect("the session should keep finalizing");
+
+    measured.stop().await;
+    for member in others {
+        member.stop().await;
+    }
+
+    let created: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            MetricsEvent::UnitCreated { round } => Some(*round),
+            _ => None,
+        })
+        .collect();
+    assert!(!created.is_empty());
+    assert_eq!(created, (0..created.len() as u16).collect::<Vec<_>>());
+
+    let mut added = HashSet::new();
+    let mut finalized = HashSet::new();
+    let mut received_from = HashSet::new();
+    for event in &events {
+        match *event {
+            MetricsEvent::UnitAdded { creator, round } => {
+                assert!(added.insert((creator, round)), "added twice");
+            }
+            MetricsEvent::UnitFinalized { creator, round } => {
+                assert!(added.contains(&(creator, round)), "finalized before added");
+                assert!(finalized.insert((creator, round)), "finalized twice");
+            }
+            MetricsEvent::UnitReceived { creator, .. } => {
+                received_from.insert(creator);
+            }
+            MetricsEvent::AlertRaised { .. } | MetricsEvent::PeerMisbehaved { .. } => {
+                panic!("no misbehavior expected, but {:?}", event)
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(finalized.len(), FINALIZED_UNITS);
+    let finalized_creators: HashSet<_> = finalized.iter().map(|(creator, _)| *creator).collect();
+    assert_eq!(finalized_creators.len(), N_MEMBERS.0);
+    for node_ix in 1..N_MEMBERS.0 {
+        assert!(received_from.contains(&NodeIndex(node_ix)));
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{
+    backup::BackupFingerprint,
+    events::InternalEvent,
+    migration_control,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member_with_events, HonestMember,
+        HonestMemberBuilder, Network, ReconnectSender, TestEventBus,
+    },
+    units::{UncheckedSignedUnit, Unit},
+    MigrationControl, NodeCount, NodeIndex, Round, SessionStateExport, SpawnHandle,
+};
+use aleph_bft_mock::{Data, DataProvider, Hasher64, Router, Signature, Spawner};
+use codec::{Decode, Encode};
+use futures::{channel::oneshot, StreamExt};
+use serial_test::serial;
+use std::{collections::BTreeSet, time::Duration};
+use tokio::time::{sleep, timeout};
+
+const N_MEMBERS: NodeCount = NodeCount(7);
+const OBSERVER: NodeIndex = NodeIndex(0);
+const MIGRATED: NodeIndex = NodeIndex(6);
+
+type TestUnit = UncheckedSignedUnit<Hasher64, Data, Signature>;
+
+fn spawn_migrating_member(
+    spawner: Spawner,
+    network: Network,
+    units: Vec<u8>,
+    migration_control: MigrationControl<Hasher64>,
+    state_import: Option<SessionStateExport<Hasher64>>,
+) -> HonestMember {
+    HonestMemberBuilder::new(network.index(), N_MEMBERS)
+        .with_units(units)
+        // Refusing to continue is checked through the session ending early.
+        .with_early_termination_allowed()
+        .with_local_io(|local_io| {
+            let local_io = local_io.with_migration_control(migration_control);
+            match state_import {
+                Some(export) => local_io.with_state_import(export),
+                None => local_io,
+            }
+        })
+        .spawn(spawner, network)
+}
+
+async fn reconnect(reconnect_tx: &ReconnectSender) -> Network {
+    let (network_tx, network_rx) = oneshot::channel();
+    reconnect_tx
+        .unbounded_send((MIGRATED, network_tx))
+        .expect("the router is running");
+    network_rx.await.expect("the router reconnects")
+}
+
+/// What the observer saw of the migrated node.
+#[derive(Default)]
+struct Observation {
+    rounds: BTreeSet<Round>,
+    alerts: usize,
+}
+
+impl Observation {
+    fn record(&mut self, event: InternalEvent<Hasher64, Data, Signature>) {
+        match event {
+            InternalEvent::UnitAdmitted(unit) if unit.as_signable().creator() == MIGRATED => {
+                self.rounds.insert(unit.as_signable().round());
+            }
+            InternalEvent::AlertStateChanged(..) | InternalEvent::AlertQueued(_) => {
+                self.alerts += 1
+            }
+            _ => {}
+        }
+    }
+
+    fn last_round(&self) -> Round {
+        self.rounds.last().copied().unwrap_or_default()
+    }
+}
+
+fn is_prefix_consistent(first: &[Data], second: &[Data]) -> bool {
+    first
+        .iter()
+        .zip(second)
+        .all(|(first, second)| first == second)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn migrated_member_continues_the_session() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+    let (handle, control) = migration_control::<Hasher64>();
+    let events = TestEventBus::new();
+    let mut observed = events.subscribe();
+    let mut others = Vec::new();
+    let mut migrated = None;
+    for (network, reconnect_tx) in networks {
+        match network.index() {
+            MIGRATED => {
+                let member =
+                    spawn_migrating_member(spawner, network, vec![], control.clone(), None);
+                migrated = Some((member, reconnect_tx));
+            }
+            node_ix => others.push(spawn_honest_member_with_events(
+                spawner,
+                gen_config(node_ix, N_MEMBERS, gen_delay_config()),
+                vec![],
+                DataProvider::new(),
+                network,
+                match node_ix {
+                    OBSERVER => events.clone(),
+                    _ => TestEventBus::new(),
+                },
+            )),
+        }
+    }
+    let (old_instance, reconnect_tx) = migrated.expect("the migrated node is a member");
+
+    let mut observation = Observation::default();
+    timeout(Duration::from_secs(30), async {
+        while let Some(event) = observed.next().await {
+            observation.record(event);
+            if observation.last_round() > 5 {
+                return;
+            }
+        }
+    })
+    .await
+    .expect("the migrated node should take part in the session");
+
+    let export = handle.freeze().await.expect("the session is running");
+    let export = SessionStateExport::<Hasher64>::decode(&mut &export.encode()[..])
+        .expect("the export survives encoding");
+    assert_eq!(export.node_ix(), MIGRATED);
+    assert!(export.next_round() > 5);
+    // The frozen instance keeps answering requests for a while, but creates nothing new.
+    sleep(Duration::from_secs(1)).await;
+    let backup = old_instance.saved_state.lock().clone();
+    old_instance.stop().await;
+    let network = reconnect(&reconnect_tx).await;
+    let mut new_instance = spawn_migrating_member(
+        spawner,
+        network,
+        backup,
+        MigrationControl::default(),
+        Some(export.clone()),
+    );
+
+    timeout(Duration::from_secs(60), async {
+        while let Some(event) = observed.next().await {
+            observation.record(event);
+            if observation.last_round() >= export.next_round() + 10 {
+                return;
+            }
+        }
+    })
+    .await
+    .expect("the migrated node should continue creating");
+    assert_eq!(observation.alerts, 0, "the migrated node forked");
+    assert_eq!(
+        observation.rounds,
+        (0..=observation.last_round()).collect(),
+        "the migrated node skipped some rounds"
+    );
+
+    let mut finalized: Vec<Vec<Data>> = Vec::new();
+    for member in &mut others {
+        let mut batches = Vec::new();
+        while let Ok(Some(batch)) = member.finalization_rx.try_next() {
+            batches.push(batch);
+        }
+        finalized.push(batches);
+    }
+    let mut migrated_batches = Vec::new();
+    while let Ok(Some(batch)) = new_instance.finalization_rx.try_next() {
+        migrated_batches.push(batch);
+    }
+    assert!(!migrated_batches.is_empty());
+    finalized.push(migrated_batches);
+    for batches in &finalized {
+        assert!(
+            is_prefix_consistent(&finalized[0], batches),
+            "the nodes finalized different data"
+        );
+    }
+
+    new_instance.stop().await;
+    for member in others {
+        member.stop().await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn stale_backup_is_not_imported() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+    let (handle, control) = migration_control::<Hasher64>();
+    let mut others = Vec::new();
+    let mut migrated = None;
+    for (network, reconnect_tx) in networks {
+        match network.index() {
+            MIGRATED => {
+                let member =
+                    spawn_migrating_member(spawner, network, vec![], control.clone(), None);
+                migrated = Some((member, reconnect_tx));
+            }
+            node_ix => others.push(spawn_honest_member_with_events(
+                spawner,
+                gen_config(node_ix, N_MEMBERS, gen_delay_config()),
+                vec![],
+                DataProvider::new(),
+                network,
+                TestEventBus::new(),
+            )),
+        }
+    }
+    let (old_instance, reconnect_tx) = migrated.expect("the migrated node is a member");
+
+    sleep(Duration::from_secs(2)).await;
+    let export = handle.freeze().await.expect("the session is running");
+    let saved = old_instance.saved_state.lock().clone();
+    old_instance.stop().await;
+    let mut units = Vec::new();
+    let mut saved = &saved[..];
+    BackupFingerprint::<Hasher64>::read_header(&mut saved).expect("the header is correct");
+    while !saved.is_empty() {
+        units.push(TestUnit::decode(&mut saved).expect("the backup is correct"));
+    }
+    assert_eq!(units.len() as u64, export.backup_position().units);
+    // A copy of the backup made before the last unit got saved.
+    units.pop();
+    let backup = units.iter().flat_map(Encode::encode).collect();
+    let network = reconnect(&reconnect_tx).await;
+    let new_instance = spawn_migrating_member(
+        spawner,
+        network,
+        backup,
+        MigrationControl::default(),
+        Some(export),
+    );
+    timeout(Duration::from_secs(5), new_instance.handle)
+        .await
+        .expect("the session should not continue from a stale backup")
+        .expect("the session ends cleanly");
+
+    for member in others {
+        member.stop().await;
+    }
+}
@@ -1,25 +1,90 @@
+mod admission;
+mod adversary;
 mod alerts;
+mod audit;
+mod availability;
+mod batch_boundaries;
+mod batch_ids;
 mod behind;
+mod broadcast_gate;
 mod byzantine;
+mod callbacks;
+mod catch_up;
+mod chaos;
+mod clock;
+mod components;
+#[cfg(feature = "zstd")]
+mod compression;
+mod consensus_handler;
 mod crash;
 mod crash_recovery;
 mod creation;
 mod dag;
+mod data_fetch;
+mod data_policy;
+mod data_size;
+mod delays;
+mod delivery;
+mod digest;
+mod drops;
+mod empty_units;
+mod events;
+mod extender_flow;
+mod fast_forward;
+mod finalization_state;
+mod finalized_units;
+mod gossip;
+mod inclusion;
+mod key_rotation;
+mod lateness;
+mod lease;
+mod metrics;
+mod migration;
+mod network_gaps;
+mod observer;
+mod pacing;
+mod presets;
+mod pruning;
+mod receipts;
+mod reception;
+mod reconstruction;
+mod requests;
+mod response_limits;
+mod round_lead;
+mod session_end;
+mod sessions;
+mod signing;
+mod simulation;
+mod small_committee;
+mod standby;
+mod status;
+mod unit_metadata;
+mod unit_sizes;
 mod unreliable;
+mod weights;
 
 use crate::{
-    create_config, run_session, Config, DelayConfig, LocalIO, Network as NetworkT, NodeCount,
-    NodeIndex, SpawnHandle, TaskHandle, Terminator,
+    create_config,
+    events::EventBus,
+    member::{run_session_with_events, FinalizationHandlerAdapter},
+    ClockSource, Config, DataProvider as DataProviderT, DelayConfig, DeliveryControl, LocalIO,
+    MultiKeychain, Network as NetworkT, NodeCount, NodeIndex, SessionError, SpawnHandle,
+    TaskHandle, Terminator, UnitFinalizationHandler,
 };
 use aleph_bft_mock::{
     Data, DataProvider, FinalizationHandler, Hasher64, Keychain, Loader, Network as MockNetwork,
-    PartialMultisignature, ReconnectSender as ReconnectSenderGeneric, Saver, Signature, Spawner,
+    PartialMultisignature, ReconnectSender as ReconnectSenderGeneric, Router, Saver, Signature,
+    Spawner, TokioClock,
+};
+use futures::{
+    channel::{mpsc::UnboundedReceiver, oneshot},
+    AsyncRead, AsyncWrite, StreamExt,
 };
-use futures::channel::{mpsc::UnboundedReceiver, oneshot};
 use parking_lot::Mutex;
 use std::{sync::Arc, time::Duration};
 
 pub type NetworkData = crate::NetworkData<Hasher64, Data, Signature, PartialMultisignature>;
+pub type TestEventBus = EventBus<Hasher64, Data, Signature>;
 
 pub type Network = MockNetwork<NetworkData>;
 pub type ReconnectSender = ReconnectSenderGeneric<NetworkData>;
@@ -48,12 +113,18 @@ pub fn gen_delay_config() -> DelayConfig {
         parent_request_recipients: Arc::new(|_| 1),
         // 50, 50, 50, 50, ...
         newest_request_delay: Arc::new(|_| Duration::from_millis(50)),
+        data_fetch_timeout: None,
     }
 }
 
+/// Sessions in the tests wait on the `async-std` timers instead when the feature is enabled, to
+/// check that nothing depends on the tokio runtime the tests run on.
 pub fn gen_config(node_ix: NodeIndex, n_members: NodeCount, delay_config: DelayConfig) -> Config {
-    create_config(n_members, node_ix, 0, 5000, delay_config, Duration::ZERO)
-        .expect("Should always succeed with Duration::ZERO")
+    let config = create_config(n_members, node_ix, 0, 5000, delay_config, Duration::ZERO)
+        .expect("Should always succeed with Duration::ZERO");
+    #[cfg(feature = "async-std")]
+    let config = config.with_clock(ClockSource::new(crate::AsyncStdClock));
+    config
 }
 
 pub struct HonestMember {
@@ -63,6 +134,137 @@ pub struct HonestMember {
     handle: TaskHandle,
 }
 
+impl HonestMember {
+    /// Ends the session of the member and waits for it to finish.
+    pub async fn stop(self) {
+        let _ = self.exit_tx.send(());
+        let _ = self.handle.await;
+    }
+}
+
+/// The local IO of the members spawned by [`HonestMemberBuilder`].
+pub type TestLocalIO = LocalIO<
+    DataProvider,
+    FinalizationHandlerAdapter<FinalizationHandler, Data, Hasher64>,
+    Saver,
+    Loader,
+>;
+
+type LocalIOHook<'a> = Box<dyn FnOnce(TestLocalIO) -> TestLocalIO + 'a>;
+
+/// Spawns an honest member like [`spawn_honest_member`], after the test adjusts its config, its
+/// keychain or its local IO.
+pub struct HonestMemberBuilder<'a> {
+    config: Config,
+    keychain: Keychain,
+    units: Vec<u8>,
+    data_provider: DataProvider,
+    events: TestEventBus,
+    local_io_hooks: Vec<LocalIOHook<'a>>,
+    early_termination_allowed: bool,
+}
+
+impl<'a> HonestMemberBuilder<'a> {
+    pub fn new(node_index: NodeIndex, n_members: NodeCount) -> Self {
+        Self::from_config(gen_config(node_index, n_members, gen_delay_config()))
+    }
+
+    /// A member running with the given config and the keychain of its index.
+    pub fn from_config(config: Config) -> Self {
+        let keychain = Keychain::new(config.n_members(), config.node_ix());
+        HonestMemberBuilder {
+            config,
+            keychain,
+            units: vec![],
+            data_provider: DataProvider::new(),
+            events: EventBus::new(),
+            local_io_hooks: Vec::new(),
+            early_termination_allowed: false,
+        }
+    }
+
+    pub fn with_config(self, adjust: impl FnOnce(Config) -> Config) -> Self {
+        HonestMemberBuilder {
+            config: adjust(self.config),
+            ..self
+        }
+    }
+
+    pub fn with_keychain(self, adjust: impl FnOnce(Keychain) -> Keychain) -> Self {
+        HonestMemberBuilder {
+            keychain: adjust(self.keychain),
+            ..self
+        }
+    }
+
+    /// Hands the member the given units to load, as if it was restarting.
+    pub fn with_units(self, units: Vec<u8>) -> Self {
+        HonestMemberBuilder { units, ..self }
+    }
+
+    pub fn with_data_provider(self, data_provider: DataProvider) -> Self {
+        HonestMemberBuilder {
+            data_provider,
+            ..self
+        }
+    }
+
+    pub fn with_events(self, events: TestEventBus) -> Self {
+        HonestMemberBuilder { events, ..self }
+    }
+
+    /// Adjusts the local IO once it is made, e.g. to hand it a monitor. The adjustments apply in
+    /// the order they were added.
+    pub fn with_local_io(mut self, adjust: impl FnOnce(TestLocalIO) -> TestLocalIO + 'a) -> Self {
+        self.local_io_hooks.push(Box::new(adjust));
+        self
+    }
+
+    /// Lets a component of the session end it early, for the tests checking that the session
+    /// refuses to continue. Otherwise the member fails the test once it is stopped.
+    pub fn with_early_termination_allowed(self) -> Self {
+        HonestMemberBuilder {
+            early_termination_allowed: true,
+            ..self
+        }
+    }
+
+    pub fn spawn(
+        self,
+        spawner: impl SpawnHandle,
+        network: impl 'static + NetworkT<NetworkData>,
+    ) -> HonestMember {
+        let (finalization_handler, finalization_rx) = FinalizationHandler::new();
+        let saved_state = Arc::new(Mutex::new(vec![]));
+        let unit_saver: Saver = saved_state.clone().into();
+        let local_io = LocalIO::new(
+            self.data_provider,
+            finalization_handler,
+            unit_saver,
+            Loader::new(self.units),
+        );
+        let local_io = self
+            .local_io_hooks
+            .into_iter()
+            .fold(local_io, |local_io, adjust| adjust(local_io));
+        let (exit_tx, handle) = spawn_checked_session(
+            spawner,
+            self.config,
+            local_io,
+            network,
+            self.keychain,
+            self.events,
+            self.early_termination_allowed,
+        );
+        HonestMember {
+            finalization_rx,
+            saved_state,
+            exit_tx,
+            handle,
+        }
+    }
+}
+
 pub fn spawn_honest_member(
     spawner: Spawner,
     node_index: NodeIndex,
@@ -71,31 +273,221 @@ pub fn spawn_honest_member(
     data_provider: DataProvider,
     network: impl 'static + NetworkT<NetworkData>,
 ) -> HonestMember {
-    let (finalization_handler, finalization_rx) = FinalizationHandler::new();
-    let config = gen_config(node_index, n_members, gen_delay_config());
+    HonestMemberBuilder::new(node_index, n_members)
+        .with_units(units)
+        .with_data_provider(data_provider)
+        .spawn(spawner, network)
+}
+
+pub fn spawn_honest_member_with_config<SH: SpawnHandle>(
+    spawner: SH,
+    config: Config,
+    units: Vec<u8>,
+    data_provider: DataProvider,
+    network: impl 'static + NetworkT<NetworkData>,
+) -> HonestMember {
+    HonestMemberBuilder::from_config(config)
+        .with_units(units)
+        .with_data_provider(data_provider)
+        .spawn(spawner, network)
+}
+
+pub fn spawn_honest_member_with_events<SH: SpawnHandle>(
+    spawner: SH,
+    config: Config,
+    units: Vec<u8>,
+    data_provider: DataProvider,
+    network: impl 'static + NetworkT<NetworkData>,
+    events: TestEventBus,
+) -> HonestMember {
+    HonestMemberBuilder::from_config(config)
+        .with_units(units)
+        .with_data_provider(data_provider)
+        .with_events(events)
+        .spawn(spawner, network)
+}
+
+pub fn spawn_honest_member_with_delivery_control<SH: SpawnHandle>(
+    spawner: SH,
+    config: Config,
+    units: Vec<u8>,
+    data_provider: DataProvider,
+    network: impl 'static + NetworkT<NetworkData>,
+    events: TestEventBus,
+    delivery_control: DeliveryControl,
+) -> HonestMember {
+    HonestMemberBuilder::from_config(config)
+        .with_units(units)
+        .with_data_provider(data_provider)
+        .with_events(events)
+        .with_local_io(|local_io| local_io.with_delivery_control(delivery_control))
+        .spawn(spawner, network)
+}
+
+/// Spawns the session of a member with any local IO, for the members [`HonestMemberBuilder`]
+/// cannot make, e.g. ones finalizing other data. The session ends once the returned sender is
+/// used or dropped.
+pub fn spawn_session<DP, UFH, US, UL>(
+    spawner: impl SpawnHandle,
+    config: Config,
+    local_io: LocalIO<DP, UFH, US, UL>,
+    network: impl 'static
+        + NetworkT<crate::NetworkData<Hasher64, DP::Output, Signature, PartialMultisignature>>,
+) -> (oneshot::Sender<()>, TaskHandle)
+where
+    DP: DataProviderT,
+    UFH: UnitFinalizationHandler<Data = DP::Output, Hasher = Hasher64>,
+    US: AsyncWrite + Send + Sync + 'static,
+    UL: AsyncRead + Send + Sync + 'static,
+{
+    let keychain = Keychain::new(config.n_members(), config.node_ix());
+    spawn_session_with_events(
+        spawner,
+        config,
+        local_io,
+        network,
+        keychain,
+        EventBus::new(),
+    )
+}
+
+/// Spawns the session of a member like [`spawn_session`], for the tests checking that the session
+/// refuses to continue, see [`HonestMemberBuilder::with_early_termination_allowed`].
+pub fn spawn_session_allowing_early_termination<DP, UFH, US, UL>(
+    spawner: impl SpawnHandle,
+    config: Config,
+    local_io: LocalIO<DP, UFH, US, UL>,
+    network: impl 'static
+        + NetworkT<crate::NetworkData<Hasher64, DP::Output, Signature, PartialMultisignature>>,
+) -> (oneshot::Sender<()>, TaskHandle)
+where
+    DP: DataProviderT,
+    UFH: UnitFinalizationHandler<Data = DP::Output, Hasher = Hasher64>,
+    US: AsyncWrite + Send + Sync + 'static,
+    UL: AsyncRead + Send + Sync + 'static,
+{
+    let keychain = Keychain::new(config.n_members(), config.node_ix());
+    spawn_checked_session(
+        spawner,
+        config,
+        local_io,
+        network,
+        keychain,
+        EventBus::new(),
+        true,
+    )
+}
+
+/// Spawns the session of a member like [`spawn_session`], with the given keychain and events.
+pub fn spawn_session_with_events<DP, UFH, US, UL>(
+    spawner: impl SpawnHandle,
+    config: Config,
+    local_io: LocalIO<DP, UFH, US, UL>,
+    network: impl 'static
+        + NetworkT<crate::NetworkData<Hasher64, DP::Output, Signature, PartialMultisignature>>,
+    keychain: impl MultiKeychain<Signature = Signature, PartialMultisignature = PartialMultisignature>,
+    events: EventBus<Hasher64, DP::Output, Signature>,
+) -> (oneshot::Sender<()>, TaskHandle)
+where
+    DP: DataProviderT,
+    UFH: UnitFinalizationHandler<Data = DP::Output, Hasher = Hasher64>,
+    US: AsyncWrite + Send + Sync + 'static,
+    UL: AsyncRead + Send + Sync + 'static,
+{
+    spawn_checked_session(spawner, config, local_io, network, keychain, events, false)
+}
+
+/// The returned handle panics once awaited if the session failed, so the tests waiting for their
+/// members to stop fail with it.
+fn spawn_checked_session<DP, UFH, US, UL>(
+    spawner: impl SpawnHandle,
+    config: Config,
+    local_io: LocalIO<DP, UFH, US, UL>,
+    network: impl 'static
+        + NetworkT<crate::NetworkData<Hasher64, DP::Output, Signature, PartialMultisignature>>,
+    keychain: impl MultiKeychain<Signature = Signature, PartialMultisignature = PartialMultisignature>,
+    events: EventBus<Hasher64, DP::Output, Signature>,
+    early_termination_allowed: bool,
+) -> (oneshot::Sender<()>, TaskHandle)
+where
+    DP: DataProviderT,
+    UFH: UnitFinalizationHandler<Data = DP::Output, Hasher = Hasher64>,
+    US: AsyncWrite + Send + Sync + 'static,
+    UL: AsyncRead + Send + Sync + 'static,
+{
     let (exit_tx, exit_rx) = oneshot::channel();
-    let spawner_inner = spawner;
-    let unit_loader = Loader::new(units);
-    let saved_state = Arc::new(Mutex::new(vec![]));
-    let unit_saver: Saver = saved_state.clone().into();
-    let local_io = LocalIO::new(data_provider, finalization_handler, unit_saver, unit_loader);
+    let spawner_inner = spawner.clone();
     let member_task = async move {
-        let keychain = Keychain::new(n_members, node_index);
-        run_session(
+        match run_session_with_events(
             config,
             local_io,
             network,
             keychain,
             spawner_inner,
             Terminator::create_root(exit_rx, "AlephBFT-member"),
+            events,
         )
         .await
+        {
+            // Refusing to run is checked by the tests through the missing finalizations.
+            Ok(()) | Err(SessionError::InvalidConfig) => {}
+            Err(SessionError::ComponentTerminated { .. }) if early_termination_allowed => {}
+            Err(e) => panic!("the session should end cleanly, but: {}", e),
+        }
     };
     let handle = spawner.spawn_essential("member", member_task);
-    HonestMember {
-        finalization_rx,
-        saved_state,
-        exit_tx,
-        handle,
-    }
+    let handle = async move {
+        handle.await.expect("the session should end cleanly");
+        Ok(())
+    };
+    (exit_tx, Box::pin(handle))
+}
+
+/// Runs a session of honest members until every one of them finalizes `n_rounds` rounds of data
+/// and returns what every member finalized. The network delivers the messages in an order drawn
+/// from the seed and the time is simulated on a single thread, so a failing seed can be rerun to
+/// look into the same schedule of deliveries and timers.
+pub fn simulate(seed: u64, n_nodes: usize, n_rounds: usize) -> Vec<Vec<Data>> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .start_paused(true)
+        .build()
+        .expect("the runtime should build");
+    runtime.block_on(async move {
+        let n_members = NodeCount(n_nodes);
+        let spawner = Spawner::new();
+        let (mut net_hub, networks) = Router::new(n_members);
+        net_hub.deliver_in_seeded_order(seed);
+        spawner.spawn("network-hub", net_hub);
+
+        let mut members = Vec::new();
+        for (network, _) in networks {
+            let config = gen_config(network.index(), n_members, gen_delay_config())
+                .with_clock(ClockSource::new(TokioClock));
+            members.push(spawn_honest_member_with_config(
+                spawner,
+                config,
+                vec![],
+                DataProvider::new(),
+                network,
+            ));
+        }
+
+        let mut finalized = Vec::new();
+        for member in members.iter_mut() {
+            finalized.push(
+                member
+                    .finalization_rx
+                    .by_ref()
+                    .take(n_rounds * n_nodes)
+                    .collect()
+                    .await,
+            );
+        }
+        for member in members {
+            let _ = member.exit_tx.send(());
+            let _ = member.handle.await;
+        }
+        finalized
+    })
 }
@@ -0,0 +1,138 @@
+use crate::{
+    events::{InternalEvent, NetworkState},
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member_with_events, HonestMember,
+        HonestMemberBuilder, TestEventBus,
+    },
+    NodeCount, NodeIndex, SpawnHandle,
+};
+use aleph_bft_mock::{Data, DataProvider, Hasher64, NetworkGaps, Router, Signature, Spawner};
+use futures::{channel::mpsc::Receiver, StreamExt};
+use serial_test::serial;
+use std::time::{Duration, Instant};
+use tokio::{task::JoinHandle, time::timeout};
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const OBSERVED: NodeIndex = NodeIndex(0);
+const GAP: Duration = Duration::from_secs(3);
+
+type EventConsumer = JoinHandle<Vec<NetworkState>>;
+
+fn collect_network_states(
+    mut events: Receiver<InternalEvent<Hasher64, Data, Signature>>,
+) -> EventConsumer {
+    tokio::spawn(async move {
+        let mut states = Vec::new();
+        while let Some(event) = events.next().await {
+            if let InternalEvent::NetworkStateChanged(state) = event {
+                states.push(state);
+            }
+        }
+        states
+    })
+}
+
+/// Spawns the committee, returning the observed member, the rest of it, the outage handle of
+/// the network of the observed member and the network states it goes through. The session of the
+/// observed member may only end on its own if its network is going to be closed.
+fn spawn_committee(
+    spawner: Spawner,
+    closed_early: bool,
+) -> (HonestMember, Vec<HonestMember>, NetworkGaps, EventConsumer) {
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut observed = None;
+    let mut others = Vec::new();
+    for (network, _) in networks {
+        let ix = network.index();
+        let events = TestEventBus::new();
+        let config = gen_config(ix, N_MEMBERS, gen_delay_config());
+        match ix {
+            OBSERVED => {
+                let gaps = network.gaps();
+                let states = collect_network_states(events.subscribe());
+                let member = HonestMemberBuilder::from_config(config).with_events(events);
+                let member = match closed_early {
+                    true => member.with_early_termination_allowed(),
+                    false => member,
+                }
+                .spawn(spawner, network);
+                observed = Some((member, gaps, states));
+            }
+            _ => others.push(spawn_honest_member_with_events(
+                spawner,
+                config,
+                vec![],
+                DataProvider::new(),
+                network,
+                events,
+            )),
+        }
+    }
+    let (member, gaps, states) = observed.expect("the observed node is a member");
+    (member, others, gaps, states)
+}
+
+async fn stop(members: Vec<HonestMember>) {
+    for member in members {
+        member.stop().await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn transient_gap_does_not_end_the_session() {
+    init_log();
+    let spawner = Spawner::new();
+    let (mut observed, others, gaps, states) = spawn_committee(spawner, false);
+
+    let before_gap: Vec<_> = (&mut observed.finalization_rx).take(5).collect().await;
+    assert_eq!(before_gap.len(), 5);
+    gaps.gap_for(GAP);
+    let gap_end = Instant::now() + GAP;
+
+    let mut after_gap = 0;
+    while after_gap < 5 {
+        timeout(Duration::from_secs(30), observed.finalization_rx.next())
+            .await
+            .expect("the member should keep finalizing after the gap")
+            .expect("the member should not end its session");
+        if Instant::now() > gap_end {
+            after_gap += 1;
+        }
+    }
+
+    stop(vec![observed]).await;
+    stop(others).await;
+    let states = states.await.expect("the consumer does not panic");
+    assert_eq!(
+        states,
+        vec![NetworkState::Interrupted, NetworkState::Recovered]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn closed_network_ends_the_session() {
+    init_log();
+    let spawner = Spawner::new();
+    let (mut observed, others, gaps, states) = spawn_committee(spawner, true);
+
+    let before_closing: Vec<_> = (&mut observed.finalization_rx).take(5).collect().await;
+    assert_eq!(before_closing.len(), 5);
+    gaps.close();
+
+    let HonestMember {
+        exit_tx, handle, ..
+    } = observed;
+    timeout(Duration::from_secs(10), handle)
+        .await
+        .expect("the session should end on its own")
+        .expect("the session should end cleanly");
+    drop(exit_tx);
+
+    stop(others).await;
+    let states = states.await.expect("the consumer does not panic");
+    assert_eq!(states, vec![NetworkState::Closed]);
+}
@@ -0,0 +1,86 @@
+use crate::{
+    member::UnitMessage::NewUnit,
+    network::NetworkDataInner::Units,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member_with_config, HonestMember,
+        NetworkData,
+    },
+    NetworkData as NetworkDataT, NodeCount, NodeIndex, SpawnHandle,
+};
+use aleph_bft_mock::{Data, DataProvider, NetworkHook, Router, Spawner};
+use futures::StreamExt;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const OBSERVER: NodeIndex = NodeIndex(4);
+const FINALIZED: usize = 100;
+
+/// Counts the units sent by the observer.
+#[derive(Clone, Default)]
+struct ObserverUnits(Arc<Mutex<usize>>);
+
+impl ObserverUnits {
+    fn count(&self) -> usize {
+        *self.0.lock()
+    }
+}
+
+impl NetworkHook<NetworkData> for ObserverUnits {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<(NetworkData, NodeIndex, NodeIndex)> {
+        if sender == OBSERVER && matches!(data, NetworkDataT(Units(NewUnit(_)), _)) {
+            *self.0.lock() += 1;
+        }
+        vec![(data, sender, recipient)]
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn observer_finalizes_the_same_data_without_sending_units() {
+    init_log();
+    let spawner = Spawner::new();
+    let (mut net_hub, networks) = Router::new(NodeCount(N_MEMBERS.0 + 1));
+    let observer_units = ObserverUnits::default();
+    net_hub.add_hook(observer_units.clone());
+    spawner.spawn("network-hub", net_hub);
+
+    let mut nodes = Vec::new();
+    for (network, _) in networks {
+        let node_index = network.index();
+        let config = match node_index {
+            OBSERVER => gen_config(NodeIndex(0), N_MEMBERS, gen_delay_config())
+                .with_observer_index(OBSERVER),
+            _ => gen_config(node_index, N_MEMBERS, gen_delay_config()),
+        };
+        nodes.push(spawn_honest_member_with_config(
+            spawner,
+            config,
+            vec![],
+            DataProvider::new_range(node_index.0 * 50, (node_index.0 + 1) * 50),
+            network,
+        ));
+    }
+
+    let mut sequences: Vec<Vec<Data>> = Vec::new();
+    for HonestMember {
+        finalization_rx, ..
+    } in nodes.iter_mut()
+    {
+        sequences.push(finalization_rx.take(FINALIZED).collect().await);
+    }
+    let observed = sequences.pop().expect("the observer runs");
+    assert_eq!(observed.len(), FINALIZED);
+    for sequence in sequences {
+        assert_eq!(sequence, observed);
+    }
+    assert_eq!(observer_units.count(), 0);
+
+    for member in nodes {
+        member.stop().await;
+    }
+}
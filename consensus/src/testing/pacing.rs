@@ -0,0 +1,127 @@
+use crate::{
+    delivery_control,
+    events::InternalEvent,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member_with_delivery_control,
+        TestEventBus,
+    },
+    DeliveryControl, NodeCount, NodeIndex, OverflowPolicy, Round, SpawnHandle,
+};
+use aleph_bft_mock::{Data, DataProvider, Hasher64, Router, Signature, Spawner};
+use futures::{channel::mpsc::Receiver, StreamExt};
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::timeout;
+
+const MAX_UNFINALIZED_ROUNDS: Round = 10;
+
+type TestEvent = InternalEvent<Hasher64, Data, Signature>;
+
+/// The top rounds of the units our node created and finalized so far.
+#[derive(Default)]
+struct Progress {
+    created: Option<Round>,
+    finalized: Option<Round>,
+}
+
+impl Progress {
+    /// Follows the events for the given time, they are dropped if we lag behind.
+    async fn observe(&mut self, events: &mut Receiver<TestEvent>, duration: Duration) {
+        let _ = timeout(duration, async {
+            while let Some(event) = events.next().await {
+                match event {
+                    InternalEvent::UnitCreated(round) => {
+                        self.created = self.created.max(Some(round));
+                    }
+                    InternalEvent::UnitFinalized(_, coord) => {
+                        self.finalized = self.finalized.max(Some(coord.round()));
+                    }
+                    _ => {}
+                }
+            }
+        })
+        .await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn creation_pauses_while_finalization_stalls() {
+    init_log();
+    let n_members = NodeCount(4);
+    let own_id = NodeIndex(0);
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+    let (delivery_handle, own_delivery_control) = delivery_control(2, OverflowPolicy::Block);
+    let events = TestEventBus::new();
+    let mut observed_events = events.subscribe();
+    let mut own_delivery_control = Some(own_delivery_control);
+    let mut members: Vec<_> = networks
+        .into_iter()
+        .map(|(network, _)| {
+            let ix = network.index();
+            let mut config = gen_config(ix, n_members, gen_delay_config());
+            let mut delivery_control = DeliveryControl::default();
+            let mut member_events = TestEventBus::new();
+            if ix == own_id {
+                config = config.with_max_unfinalized_rounds(MAX_UNFINALIZED_ROUNDS);
+                delivery_control = own_delivery_control.take().expect("only one own node");
+                member_events = events.clone();
+            }
+            spawn_honest_member_with_delivery_control(
+                spawner,
+                config,
+                vec![],
+                DataProvider::new(),
+                network,
+                member_events,
+                delivery_control,
+            )
+        })
+        .collect();
+
+    let mut progress = Progress::default();
+    progress
+        .observe(&mut observed_events, Duration::from_secs(2))
+        .await;
+    assert!(progress.finalized.is_some(), "the session should finalize");
+
+    // Stalls finalization on our node, the others can still finalize without us.
+    delivery_handle.pause_delivery();
+    progress
+        .observe(&mut observed_events, Duration::from_secs(3))
+        .await;
+    let paused_at = progress.created.expect("we should have created units");
+    let finalized = progress.finalized.expect("the session should finalize");
+    assert_eq!(paused_at, finalized + MAX_UNFINALIZED_ROUNDS);
+
+    let others = &mut members[own_id.0 + 1];
+    while let Ok(Some(_)) = others.finalization_rx.try_next() {}
+    progress
+        .observe(&mut observed_events, Duration::from_secs(2))
+        .await;
+    assert_eq!(progress.created, Some(paused_at));
+    assert_eq!(progress.finalized, Some(finalized));
+    assert!(
+        matches!(others.finalization_rx.try_next(), Ok(Some(_))),
+        "the others should keep finalizing"
+    );
+
+    delivery_handle.resume_delivery();
+    timeout(Duration::from_secs(30), async {
+        while progress.created == Some(paused_at) {
+            progress
+                .observe(&mut observed_events, Duration::from_millis(100))
+                .await;
+        }
+    })
+    .await
+    .expect("creation should resume once finalization catches up");
+    assert!(progress.finalized > Some(finalized));
+
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+}
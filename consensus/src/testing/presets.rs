@@ -0,0 +1,127 @@
+use crate::{
+    testing::{init_log, spawn_honest_member_with_config, HonestMember, Network, NetworkData},
+    ConfigPreset, Network as NetworkT, NodeCount, Recipient, SpawnHandle,
+};
+use aleph_bft_mock::{Data, DataProvider, Router, Spawner};
+use futures::{future::pending, FutureExt, StreamExt};
+use serial_test::serial;
+use std::{collections::VecDeque, time::Duration};
+use tokio::time::{sleep_until, timeout, Instant};
+
+/// Delays every incoming message by a constant latency, preserving their order.
+struct LatencyNetwork {
+    inner: Network,
+    latency: Duration,
+    in_flight: VecDeque<(Instant, NetworkData)>,
+}
+
+impl LatencyNetwork {
+    fn new(inner: Network, latency: Duration) -> Self {
+        LatencyNetwork {
+            inner,
+            latency,
+            in_flight: VecDeque::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl NetworkT<NetworkData> for LatencyNetwork {
+    fn send(&self, data: NetworkData, recipient: Recipient) {
+        self.inner.send(data, recipient)
+    }
+
+    async fn next_event(&mut self) -> Option<NetworkData> {
+        loop {
+            let delivery = match self.in_flight.front() {
+                Some((deadline, _)) => sleep_until(*deadline).boxed(),
+                None => pending().boxed(),
+            };
+            futures::select! {
+                data = self.inner.next_event().fuse() => {
+                    self.in_flight.push_back((Instant::now() + self.latency, data?));
+                },
+                _ = delivery.fuse() => return self.in_flight.pop_front().map(|(_, data)| data),
+            }
+        }
+    }
+}
+
+async fn preset_committee_finalizes_in_time(
+    preset: ConfigPreset,
+    n_members: NodeCount,
+    time_budget: Duration,
+) {
+    init_log();
+    let n_data = 2 * n_members.0;
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut batch_rxs = Vec::new();
+    let mut exits = Vec::new();
+    let mut handles = Vec::new();
+    for (network, _) in networks {
+        let config = preset
+            .config(n_members, network.index(), 0, 5000, Duration::ZERO)
+            .expect("presets should be valid");
+        let network = LatencyNetwork::new(network, preset.expected_latency());
+        let HonestMember {
+            finalization_rx,
+            exit_tx,
+            handle,
+            ..
+        } = spawn_honest_member_with_config(spawner, config, vec![], DataProvider::new(), network);
+        batch_rxs.push(finalization_rx);
+        exits.push(exit_tx);
+        handles.push(handle);
+    }
+
+    let batches: Vec<Vec<Data>> = timeout(time_budget, async {
+        let mut batches = Vec::new();
+        for rx in batch_rxs.iter_mut() {
+            batches.push(rx.take(n_data).collect().await);
+        }
+        batches
+    })
+    .await
+    .unwrap_or_else(|_| panic!("{:?} should finalize data within {:?}", preset, time_budget));
+
+    for node_batches in batches.iter().skip(1) {
+        assert_eq!(&batches[0], node_batches);
+    }
+    for exit in exits {
+        let _ = exit.send(());
+    }
+    for handle in handles {
+        let _ = handle.await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn lan_small_preset_finalizes_in_time() {
+    preset_committee_finalizes_in_time(ConfigPreset::LanSmall, 7.into(), Duration::from_secs(10))
+        .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn wan_small_preset_finalizes_in_time() {
+    preset_committee_finalizes_in_time(ConfigPreset::WanSmall, 7.into(), Duration::from_secs(20))
+        .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn medium_lan_large_preset_finalizes_in_time() {
+    preset_committee_finalizes_in_time(ConfigPreset::LanLarge, 31.into(), Duration::from_secs(20))
+        .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn medium_wan_large_preset_finalizes_in_time() {
+    preset_committee_finalizes_in_time(ConfigPreset::WanLarge, 31.into(), Duration::from_secs(40))
+        .await;
+}
@@ -0,0 +1,158 @@
+use crate::{
+    dissemination::RequestId,
+    member::UnitMessage::{Pruned, RequestCoord},
+    network::NetworkDataInner::Units,
+    status_query,
+    testing::{init_log, HonestMemberBuilder, Network},
+    units::UnitCoord,
+    DagStatus, Network as _, NetworkData as NetworkDataT, NodeCount, NodeIndex, Recipient, Round,
+    SpawnHandle, StatusQueryHandle, MIN_KEPT_ROUNDS,
+};
+use aleph_bft_mock::{Router, Spawner};
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const PRUNING: NodeIndex = NodeIndex(0);
+const KEEPING: NodeIndex = NodeIndex(1);
+const KEPT_ROUNDS: Round = 10;
+/// How many rounds the units in the store can reach above the finalized round.
+const UNFINALIZED_ROUNDS: Round = 10;
+const FINALIZED_ROUND: Round = 150;
+
+async fn query(status: &StatusQueryHandle) -> DagStatus {
+    loop {
+        // The query fails until the session starts.
+        if let Ok(status) = status.query().await {
+            return status;
+        }
+        sleep(Duration::from_millis(50)).await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn pruning_bounds_the_stored_units() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let (pruning_status, pruning_query) = status_query();
+    let (keeping_status, keeping_query) = status_query();
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let network: Network = network;
+        let node_ix = network.index();
+        let member = HonestMemberBuilder::new(node_ix, N_MEMBERS);
+        let member = match node_ix {
+            PRUNING => member
+                .with_config(|config| config.with_kept_rounds(KEPT_ROUNDS))
+                .with_local_io(|local_io| local_io.with_status_query(pruning_query.clone())),
+            KEEPING => {
+                member.with_local_io(|local_io| local_io.with_status_query(keeping_query.clone()))
+            }
+            _ => member,
+        };
+        members.push(member.spawn(spawner, network));
+    }
+
+    let most_stored = timeout(Duration::from_secs(120), async {
+        let mut most_stored = 0;
+        loop {
+            let status = query(&pruning_status).await;
+            let finalized_round = status.finalized_round.unwrap_or(0);
+            if finalized_round > KEPT_ROUNDS + UNFINALIZED_ROUNDS {
+                most_stored = most_stored.max(status.stored_units);
+            }
+            if finalized_round >= FINALIZED_ROUND {
+                return most_stored;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("the session should keep finalizing");
+    let kept = query(&keeping_status).await;
+
+    for member in members {
+        member.stop().await;
+    }
+
+    let bound = N_MEMBERS.0 * (KEPT_ROUNDS + UNFINALIZED_ROUNDS) as usize;
+    assert!(
+        most_stored <= bound,
+        "held {} units, more than {}",
+        most_stored,
+        bound
+    );
+    // Without pruning every unit up to about the finalized round stays.
+    assert!(kept.stored_units >= N_MEMBERS.0 * (FINALIZED_ROUND - UNFINALIZED_ROUNDS) as usize);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn requests_for_pruned_units_are_answered_as_pruned() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    // The last node only sends the request, the others are enough to finalize.
+    let requester = NodeIndex(N_MEMBERS.0 - 1);
+    let (pruning_status, pruning_query) = status_query();
+    let mut requester_network = None;
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let network: Network = network;
+        let node_ix = network.index();
+        if node_ix == requester {
+            requester_network = Some(network);
+            continue;
+        }
+        let member = HonestMemberBuilder::new(node_ix, N_MEMBERS)
+            .with_config(|config| config.with_kept_rounds(MIN_KEPT_ROUNDS));
+        let member = match node_ix {
+            PRUNING => {
+                member.with_local_io(|local_io| local_io.with_status_query(pruning_query.clone()))
+            }
+            _ => member,
+        };
+        members.push(member.spawn(spawner, network));
+    }
+    let mut requester_network = requester_network.expect("the requester is a member");
+
+    timeout(Duration::from_secs(120), async {
+        while query(&pruning_status).await.finalized_round.unwrap_or(0)
+            < 2 * (MIN_KEPT_ROUNDS + UNFINALIZED_ROUNDS)
+        {
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("the session should keep finalizing");
+
+    let coord = UnitCoord::new(0, NodeIndex(1));
+    requester_network.send(
+        NetworkDataT(Units(RequestCoord(requester, coord)), 0),
+        Recipient::Node(PRUNING),
+    );
+    timeout(Duration::from_secs(10), async {
+        loop {
+            if let Some(NetworkDataT(Units(Pruned(responder, request)), _)) =
+                requester_network.next_event().await
+            {
+                assert_eq!(responder, PRUNING);
+                assert_eq!(request, RequestId::Coord(coord));
+                return;
+            }
+        }
+    })
+    .await
+    .expect("the request should be answered as pruned");
+
+    for member in members {
+        member.stop().await;
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{
+    events::InternalEvent,
+    quorum_receipt_monitor,
+    testing::{init_log, HonestMemberBuilder, NetworkData, TestEventBus},
+    units::Unit,
+    NodeCount, NodeIndex, QuorumReceipt, Round, SpawnHandle,
+};
+use aleph_bft_mock::{Data, NetworkHook, Router, Spawner};
+use futures::StreamExt;
+use serial_test::serial;
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+use tokio::time::timeout;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const OBSERVER: NodeIndex = NodeIndex(0);
+const SLOW_PEERS: [NodeIndex; 2] = [NodeIndex(2), NodeIndex(3)];
+const SLOW_PEER_DELAY: Duration = Duration::from_millis(300);
+/// Creating the unit that evidences ours and releasing the delayed messages take some time too.
+const LATENCY_TOLERANCE: Duration = Duration::from_millis(500);
+const RECEIPTS: usize = 15;
+
+type RoutedMessage = (NetworkData, NodeIndex, NodeIndex);
+
+/// Delays all the messages sent by the slow peers.
+#[derive(Default)]
+struct SlowPeersHook {
+    buffer: VecDeque<(Instant, RoutedMessage)>,
+}
+
+impl NetworkHook<NetworkData> for SlowPeersHook {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<RoutedMessage> {
+        let mut result = Vec::new();
+        match SLOW_PEERS.contains(&sender) {
+            true => self
+                .buffer
+                .push_back((Instant::now(), (data, sender, recipient))),
+            false => result.push((data, sender, recipient)),
+        }
+        while let Some((when, _)) = self.buffer.front() {
+            if when.elapsed() < SLOW_PEER_DELAY {
+                break;
+            }
+            let (_, message) = self
+                .buffer
+                .pop_front()
+                .expect("just checked it is not empty");
+            result.push(message);
+        }
+        result
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn receipts_wait_for_the_quorum_of_peers() {
+    init_log();
+    let spawner = Spawner::new();
+    let (mut net_hub, networks) = Router::new(N_MEMBERS);
+    net_hub.add_hook(SlowPeersHook::default());
+    spawner.spawn("network-hub", net_hub);
+
+    let (receipts, monitor) = quorum_receipt_monitor();
+    let events = TestEventBus::new();
+    let mut observed = events.subscribe();
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let node_index = network.index();
+        let member = match node_index {
+            OBSERVER => HonestMemberBuilder::new(node_index, N_MEMBERS)
+                .with_events(events.clone())
+                .with_local_io(|local_io| local_io.with_quorum_receipt_monitor(monitor.clone())),
+            _ => HonestMemberBuilder::new(node_index, N_MEMBERS),
+        };
+        members.push(member.spawn(spawner, network));
+    }
+
+    // The latest unit of ours each peer evidenced holding, through the parents of its units.
+    let mut evidenced: HashMap<NodeIndex, Round> = HashMap::new();
+    let observed_receipts: Vec<QuorumReceipt<Data>> = timeout(Duration::from_secs(60), async {
+        let mut observed_receipts = Vec::new();
+        while let Some(event) = observed.next().await {
+            match event {
+                InternalEvent::UnitAdmitted(unit) => {
+                    let unit = unit.as_signable();
+                    if let Some(parent) = unit
+                        .control_hash()
+                        .parents()
+                        .find(|parent| parent.creator() == OBSERVER)
+                    {
+                        let latest = evidenced.entry(unit.creator()).or_insert(parent.round());
+                        *latest = parent.round().max(*latest);
+                    }
+                }
+                InternalEvent::QuorumReceipt(receipt) => {
+                    let holders: Vec<_> = evidenced
+                        .iter()
+                        .filter(|(peer, round)| **peer != OBSERVER && **round >= receipt.round)
+                        .map(|(peer, _)| *peer)
+                        .collect();
+                    // Together with us, the peers make the quorum, which the last of them just
+                    // completed.
+                    assert_eq!(
+                        holders.len() + 1,
+                        N_MEMBERS.consensus_threshold().0,
+                        "receipt for round {} with holders {:?}",
+                        receipt.round,
+                        holders
+                    );
+                    assert!(holders.iter().any(|peer| SLOW_PEERS.contains(peer)));
+                    observed_receipts.push(receipt);
+                    if observed_receipts.len() == RECEIPTS {
+                        return observed_receipts;
+                    }
+                }
+                _ => {}
+            }
+        }
+        panic!("the event stream should be open");
+    })
+    .await
+    .expect("our units should reach a quorum");
+
+    let rounds: Vec<_> = observed_receipts
+        .iter()
+        .map(|receipt| receipt.round)
+        .collect();
+    assert_eq!(rounds, (0..RECEIPTS as Round).collect::<Vec<_>>());
+    // The first rounds are skipped, as the members start at slightly different moments.
+    for receipt in &observed_receipts[3..] {
+        assert!(
+            receipt.latency_from_broadcast >= SLOW_PEER_DELAY,
+            "{}",
+            receipt
+        );
+        assert!(
+            receipt.latency_from_broadcast < SLOW_PEER_DELAY + LATENCY_TOLERANCE,
+            "{}",
+            receipt
+        );
+    }
+    let taken = receipts.take();
+    assert_eq!(taken[..RECEIPTS], observed_receipts[..]);
+
+    for member in members {
+        member.stop().await;
+    }
+}
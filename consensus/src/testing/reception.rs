@@ -0,0 +1,141 @@
+use crate::{
+    status_query,
+    testing::{init_log, HonestMemberBuilder, NetworkData},
+    CreatorReception, NodeCount, NodeIndex, SpawnHandle, StatusQueryHandle, RECEPTION_WINDOW,
+};
+use aleph_bft_mock::{NetworkHook, Router, Spawner};
+use serial_test::serial;
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+use tokio::time::{sleep, timeout};
+
+const N_MEMBERS: NodeCount = NodeCount(7);
+const OBSERVER: NodeIndex = NodeIndex(0);
+const DELAYED: NodeIndex = NodeIndex(2);
+const SILENCED: NodeIndex = NodeIndex(3);
+const DELAY: Duration = Duration::from_millis(300);
+const OBSERVED_ROUNDS: usize = 30;
+
+type RoutedMessage = (NetworkData, NodeIndex, NodeIndex);
+
+/// Delays all the messages sent by the delayed node and drops all the messages sent by the
+/// silenced one.
+#[derive(Default)]
+struct DelayingHook {
+    buffer: VecDeque<(Instant, RoutedMessage)>,
+}
+
+impl DelayingHook {
+    fn release(&mut self) -> Vec<RoutedMessage> {
+        let mut result = Vec::new();
+        while let Some((when, _)) = self.buffer.front() {
+            if when.elapsed() < DELAY {
+                break;
+            }
+            let (_, message) = self
+                .buffer
+                .pop_front()
+                .expect("just checked it is not empty");
+            result.push(message);
+        }
+        result
+    }
+}
+
+impl NetworkHook<NetworkData> for DelayingHook {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<RoutedMessage> {
+        let mut result = match sender {
+            SILENCED => Vec::new(),
+            DELAYED => {
+                self.buffer
+                    .push_back((Instant::now(), (data, sender, recipient)));
+                Vec::new()
+            }
+            _ => vec![(data, sender, recipient)],
+        };
+        result.extend(self.release());
+        result
+    }
+}
+
+async fn observed_reception(status: &StatusQueryHandle) -> Vec<(NodeIndex, CreatorReception)> {
+    loop {
+        // The query fails until the session of the observer starts.
+        if let Ok(status) = status.query().await {
+            let reception: Vec<_> = status
+                .reception
+                .iter()
+                .map(|(node_id, reception)| (node_id, reception.clone()))
+                .collect();
+            if reception
+                .iter()
+                .all(|(_, reception)| reception.rounds >= OBSERVED_ROUNDS)
+                && !reception.is_empty()
+            {
+                return reception;
+            }
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn reception_shows_delayed_and_silenced_creators() {
+    init_log();
+    let spawner = Spawner::new();
+    let (mut net_hub, networks) = Router::new(N_MEMBERS);
+    net_hub.add_hook(DelayingHook::default());
+    spawner.spawn("network-hub", net_hub);
+
+    let (status, query) = status_query();
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let node_index = network.index();
+        let member = HonestMemberBuilder::new(node_index, N_MEMBERS);
+        let member = match node_index {
+            OBSERVER => member.with_local_io(|local_io| local_io.with_status_query(query.clone())),
+            _ => member,
+        };
+        members.push(member.spawn(spawner, network));
+    }
+
+    let reception = timeout(Duration::from_secs(60), observed_reception(&status))
+        .await
+        .expect("the observer should keep creating units");
+    for member in members {
+        member.stop().await;
+    }
+
+    assert_eq!(reception.len(), N_MEMBERS.0 - 1);
+    for (node_id, reception) in reception {
+        assert!(reception.rounds <= RECEPTION_WINDOW);
+        match node_id {
+            SILENCED => {
+                assert_eq!(reception.miss_rate(), 1.0, "{:?}: {}", node_id, reception);
+                assert_eq!(reception.median_latency, None);
+            }
+            DELAYED => {
+                let median = reception.median_latency.expect("the units arrive");
+                assert!(
+                    median >= DELAY - Duration::from_millis(50) && median < 2 * DELAY,
+                    "{:?}: {}",
+                    node_id,
+                    reception
+                );
+            }
+            _ => {
+                let median = reception.median_latency.expect("the units arrive");
+                assert!(median < DELAY / 3, "{:?}: {}", node_id, reception);
+                assert!(reception.miss_rate() < 0.1, "{:?}: {}", node_id, reception);
+            }
+        }
+    }
+}
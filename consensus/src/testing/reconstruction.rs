@@ -0,0 +1,166 @@
+use crate::{
+    dag::EvictionCause,
+    events::{InternalEvent, Misbehavior},
+    member::UnitMessage::NewUnit,
+    network::NetworkDataInner::Units,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member,
+        spawn_honest_member_with_events, Network, NetworkData, TestEventBus,
+    },
+    units::{ControlHash, FullUnit, PreUnit, SignedUnit, Unit},
+    Hasher, Network as NetworkT, NetworkData as NetworkDataT, NodeCount, NodeIndex, NodeMap,
+    Recipient, ReconstructionLimits, Round, Signed, SpawnHandle,
+};
+use aleph_bft_mock::{Data, DataProvider, Hash64, Hasher64, Keychain, Router, Spawner};
+use futures::StreamExt;
+use serial_test::serial;
+use std::{collections::HashMap, time::Duration};
+use tokio::time::{sleep, timeout};
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const TARGET: NodeIndex = NodeIndex(0);
+const ADVERSARY: NodeIndex = NodeIndex(3);
+const FLOODED_UNITS: Round = 1000;
+const MAX_PENDING: usize = 64;
+const SUSPECT_TIMEOUT: Duration = Duration::from_secs(3);
+/// Longer than it takes the honest nodes to deny having the withheld unit, but well within the
+/// suspect timeout.
+const WITHHOLDING_DELAY: Duration = Duration::from_secs(1);
+const MIN_REPORTS: usize = 10;
+
+type TestSignedUnit = SignedUnit<Hasher64, Data, Keychain>;
+
+fn sign_unit(
+    keychain: &Keychain,
+    round: Round,
+    parents: &NodeMap<(Hash64, Round)>,
+) -> TestSignedUnit {
+    let control_hash = ControlHash::<Hasher64>::new(parents);
+    let preunit = PreUnit::<Hasher64>::new(ADVERSARY, round, control_hash);
+    let full_unit = FullUnit::new(preunit, Some(0), 0);
+    Signed::sign(full_unit, keychain).expect("the keychain never fails")
+}
+
+fn send_to_target(network: &Network, unit: TestSignedUnit) {
+    let message: NetworkData = NetworkDataT(Units(NewUnit(unit.into())), 0);
+    network.send(message, Recipient::Node(TARGET));
+}
+
+/// Collects the initial units of all the honest nodes.
+async fn honest_initial_units(network: &mut Network) -> NodeMap<(Hash64, Round)> {
+    let keychain = Keychain::new(N_MEMBERS, ADVERSARY);
+    let mut initial_units = HashMap::new();
+    while initial_units.len() < N_MEMBERS.0 - 1 {
+        let data = network.next_event().await.expect("the router is running");
+        if let NetworkDataT(Units(NewUnit(unchecked)), _) = data {
+            let unit = unchecked
+                .check(&keychain)
+                .expect("honest nodes sign correctly");
+            if unit.round() == 0 {
+                initial_units.insert(unit.creator(), unit.hash());
+            }
+        }
+    }
+    let mut parents = NodeMap::with_size(N_MEMBERS);
+    for (creator, hash) in initial_units {
+        parents.insert(creator, (hash, 0));
+    }
+    parents
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn unsatisfiable_units_are_bounded_and_evicted() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let events = TestEventBus::new();
+    let mut observed_events = events.subscribe();
+    let mut honest = Vec::new();
+    let mut adversary = None;
+    for (network, _) in networks {
+        match network.index() {
+            ADVERSARY => adversary = Some(network),
+            TARGET => honest.push(spawn_honest_member_with_events(
+                spawner,
+                gen_config(TARGET, N_MEMBERS, gen_delay_config()).with_reconstruction_limits(
+                    ReconstructionLimits {
+                        max_pending_per_creator: MAX_PENDING,
+                        suspect_timeout: SUSPECT_TIMEOUT,
+                    },
+                ),
+                vec![],
+                DataProvider::new(),
+                network,
+                events.clone(),
+            )),
+            node_ix => honest.push(spawn_honest_member(
+                spawner,
+                node_ix,
+                N_MEMBERS,
+                vec![],
+                DataProvider::new(),
+                network,
+            )),
+        }
+    }
+    let mut network = adversary.expect("the adversary is a member");
+    let keychain = Keychain::new(N_MEMBERS, ADVERSARY);
+
+    let mut parents = timeout(Duration::from_secs(10), honest_initial_units(&mut network))
+        .await
+        .expect("the honest nodes broadcast their initial units");
+    let withheld = sign_unit(&keychain, 0, &NodeMap::with_size(N_MEMBERS));
+    parents.insert(ADVERSARY, (withheld.hash(), 0));
+    let slow_unit = sign_unit(&keychain, 1, &parents);
+    let slow_unit_hash = slow_unit.hash();
+    send_to_target(&network, slow_unit);
+    for round in 2..FLOODED_UNITS + 2 {
+        let mut parents = NodeMap::with_size(N_MEMBERS);
+        for creator in N_MEMBERS.into_iterator() {
+            let nonexistent = Hasher64::hash(format!("{}-{}", round, creator.0).as_bytes());
+            parents.insert(creator, (nonexistent, round - 1));
+        }
+        send_to_target(&network, sign_unit(&keychain, round, &parents));
+    }
+    sleep(WITHHOLDING_DELAY).await;
+    send_to_target(&network, withheld);
+
+    let mut max_pending = 0;
+    let mut over_cap = 0;
+    let mut reports = 0;
+    let mut slow_unit_admitted = false;
+    timeout(Duration::from_secs(60), async {
+        while !slow_unit_admitted || reports < MIN_REPORTS {
+            match observed_events.next().await.expect("the member is running") {
+                InternalEvent::UnitEvicted(evicted) if evicted.coord.creator() == ADVERSARY => {
+                    assert_ne!(evicted.hash, slow_unit_hash);
+                    max_pending = max_pending.max(evicted.pending);
+                    if evicted.cause == EvictionCause::OverCap {
+                        over_cap += 1;
+                    }
+                }
+                InternalEvent::PeerMisbehaved(peer, Misbehavior::UnsatisfiableUnit(_)) => {
+                    assert_eq!(peer, ADVERSARY, "only the adversary should be reported");
+                    reports += 1;
+                }
+                InternalEvent::UnitAdmitted(unit)
+                    if unit.as_signable().hash() == slow_unit_hash =>
+                {
+                    slow_unit_admitted = true;
+                }
+                _ => {}
+            }
+        }
+    })
+    .await
+    .expect("the slow unit should be admitted and the flood reported");
+    assert_eq!(max_pending, MAX_PENDING);
+    assert!(over_cap > 0);
+
+    for member in honest {
+        member.stop().await;
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{
+    dissemination::{CompactUnit, Request},
+    events::InternalEvent,
+    member::UnitMessage,
+    network::NetworkDataInner,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member_with_events, HonestMember,
+        NetworkData, TestEventBus,
+    },
+    units::{UncheckedSignedUnit, Unit, UnitCoord},
+    NodeCount, NodeIndex, SpawnHandle,
+};
+use aleph_bft_mock::{Data, DataProvider, Hasher64, NetworkHook, Router, Signature, Spawner};
+use futures::StreamExt;
+use serial_test::serial;
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::time::{sleep, timeout};
+
+const N_MEMBERS: NodeCount = NodeCount(7);
+const OBSERVER: NodeIndex = NodeIndex(0);
+
+/// Keeps the units of the silenced node from round 2 on and the withheld unit away from
+/// everyone but their creators.
+struct WithholdingHook {
+    silenced: NodeIndex,
+    withheld: UnitCoord,
+}
+
+impl WithholdingHook {
+    fn is_withheld(&self, unit: &UncheckedSignedUnit<Hasher64, Data, Signature>) -> bool {
+        let unit = unit.as_signable();
+        (unit.creator() == self.silenced && unit.round() >= 2) || unit.coord() == self.withheld
+    }
+}
+
+impl NetworkHook<NetworkData> for WithholdingHook {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<(NetworkData, NodeIndex, NodeIndex)> {
+        let withheld =
+            match &data {
+                crate::NetworkData(
+                    NetworkDataInner::Units(
+                        UnitMessage::NewUnit(unit) | UnitMessage::ResponseCoord(unit),
+                    ),
+                    _,
+                ) => self.is_withheld(unit) && recipient != unit.as_signable().creator(),
+                crate::NetworkData(
+                    NetworkDataInner::Units(UnitMessage::ResponseParents(_, parents)),
+                    _,
+                ) => parents.iter().any(|unit| {
+                    self.is_withheld(unit) && recipient != unit.as_signable().creator()
+                }),
+                crate::NetworkData(
+                    NetworkDataInner::Units(UnitMessage::ResponseNewestUnits(response)),
+                    _,
+                ) => response.as_signable().units().iter().any(|unit| {
+                    self.is_withheld(unit) && recipient != unit.as_signable().creator()
+                }),
+                _ => false,
+            };
+        match withheld {
+            true => Vec::new(),
+            false => vec![(data, sender, recipient)],
+        }
+    }
+}
+
+/// Lets only the helpful peer deliver the withheld unit to the observer, the responses of all
+/// the other peers containing it get lost.
+struct SingleSourceHook {
+    withheld: UnitCoord,
+    helpful: NodeIndex,
+    delivered: Arc<AtomicBool>,
+}
+
+impl NetworkHook<NetworkData> for SingleSourceHook {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<(NetworkData, NodeIndex, NodeIndex)> {
+        let units = match &data {
+            crate::NetworkData(NetworkDataInner::Units(message), _) => match message {
+                UnitMessage::NewUnit(unit) | UnitMessage::ResponseCoord(unit) => vec![unit],
+                UnitMessage::ResponseParents(_, units) | UnitMessage::ResponseUnits(units) => {
+                    units.iter().collect()
+                }
+                UnitMessage::ResponseParentsCompact(_, _, units) => units
+                    .iter()
+                    .filter_map(|unit| match unit {
+                        CompactUnit::Full(unit) => Some(unit),
+                        CompactUnit::HashRef(_) => None,
+                    })
+                    .collect(),
+                UnitMessage::ResponseNewestUnits(response) => {
+                    response.as_signable().units().iter().collect()
+                }
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        };
+        let carries_withheld = recipient == OBSERVER
+            && units
+                .iter()
+                .any(|unit| unit.as_signable().coord() == self.withheld);
+        match (carries_withheld, sender == self.helpful) {
+            (true, false) => Vec::new(),
+            (true, true) => {
+                self.delivered.store(true, Ordering::SeqCst);
+                vec![(data, sender, recipient)]
+            }
+            (false, _) => vec![(data, sender, recipient)],
+        }
+    }
+}
+
+async fn stop(members: Vec<HonestMember>) {
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn requests_made_obsolete_by_finalization_stop() {
+    init_log();
+    let silenced = NodeIndex(6);
+    let withheld = UnitCoord::new(2, NodeIndex(5));
+    let spawner = Spawner::new();
+    let (mut net_hub, networks) = Router::new(N_MEMBERS);
+    net_hub.add_hook(WithholdingHook { silenced, withheld });
+    spawner.spawn("network-hub", net_hub);
+    let mut observed_events = None;
+    let members: Vec<_> = networks
+        .into_iter()
+        .map(|(network, _)| {
+            let node_ix = network.index();
+            let events = TestEventBus::new();
+            if node_ix == OBSERVER {
+                observed_events = Some(events.subscribe());
+            }
+            spawn_honest_member_with_events(
+                spawner,
+                gen_config(node_ix, N_MEMBERS, gen_delay_config()),
+                vec![],
+                DataProvider::new(),
+                network,
+                events,
+            )
+        })
+        .collect();
+    let mut events = observed_events.expect("the observer is a member");
+
+    // The observer only learns about the units of the silenced node from its digests, so
+    // nothing it holds needs them. The later units of the creator of the withheld unit do
+    // need it, so they stay buffered.
+    let obsolete = timeout(Duration::from_secs(30), async {
+        while let Some(event) = events.next().await {
+            match event {
+                InternalEvent::RequestObsolete(Request::Coord(coord)) if coord == withheld => {
+                    panic!("the request for a unit blocking a buffered unit got cancelled")
+                }
+                InternalEvent::RequestObsolete(Request::Coord(coord))
+                    if coord.creator() == silenced =>
+                {
+                    return coord;
+                }
+                _ => {}
+            }
+        }
+        panic!("the event stream should be open");
+    })
+    .await
+    .expect("finalization should make a request obsolete");
+
+    // Requests sent just before the member learned about the cancellation might still show up.
+    let _ = timeout(
+        Duration::from_millis(300),
+        (&mut events).for_each(|_| async {}),
+    )
+    .await;
+    let later: Vec<_> = (&mut events)
+        .take_until(sleep(Duration::from_secs(2)))
+        .collect()
+        .await;
+    let sent_for = |expected| {
+        later.iter().any(|event| {
+            matches!(event, InternalEvent::RequestSent(Request::Coord(coord), _) if *coord == expected)
+        })
+    };
+    assert!(!sent_for(obsolete), "still requesting {}", obsolete);
+    assert!(sent_for(withheld), "stopped requesting {}", withheld);
+    assert!(!later.iter().any(|event| matches!(
+        event,
+        InternalEvent::RequestObsolete(Request::Coord(coord)) if *coord == withheld
+    )));
+    stop(members).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn requests_for_a_lost_unit_succeed_through_another_peer() {
+    init_log();
+    let withheld = UnitCoord::new(2, NodeIndex(5));
+    let helpful = NodeIndex(6);
+    let delivered = Arc::new(AtomicBool::new(false));
+    let spawner = Spawner::new();
+    let (mut net_hub, networks) = Router::new(N_MEMBERS);
+    net_hub.add_hook(SingleSourceHook {
+        withheld,
+        helpful,
+        delivered: delivered.clone(),
+    });
+    spawner.spawn("network-hub", net_hub);
+    let mut observed_events = None;
+    let members: Vec<_> = networks
+        .into_iter()
+        .map(|(network, _)| {
+            let node_ix = network.index();
+            let events = TestEventBus::new();
+            if node_ix == OBSERVER {
+                observed_events = Some(events.subscribe());
+            }
+            spawn_honest_member_with_events(
+                spawner,
+                gen_config(node_ix, N_MEMBERS, gen_delay_config()),
+                vec![],
+                DataProvider::new(),
+                network,
+                events,
+            )
+        })
+        .collect();
+    let mut events = observed_events.expect("the observer is a member");
+
+    let sent = timeout(Duration::from_secs(30), async {
+        let mut sent = Vec::new();
+        while let Some(event) = events.next().await {
+            match event {
+                InternalEvent::RequestSent(Request::Coord(coord), peer) if coord == withheld => {
+                    sent.push(peer)
+                }
+                InternalEvent::RequestResolved(Request::Coord(coord)) if coord == withheld => {
+                    return sent;
+                }
+                _ => {}
+            }
+        }
+        panic!("the event stream should be open");
+    })
+    .await
+    .expect("the withheld unit should arrive eventually");
+
+    assert!(delivered.load(Ordering::SeqCst));
+    // Every peer is asked before anyone is asked again, and the peers that do not answer get
+    // backed off, so the lost responses cause only a bounded number of repeated requests.
+    let bound = 2 * (N_MEMBERS.0 - 1);
+    assert!(
+        sent.len() <= bound,
+        "sent {} requests for {}, expected at most {}",
+        sent.len(),
+        withheld,
+        bound
+    );
+    assert!(!sent.contains(&OBSERVER));
+    let unique: HashSet<_> = sent.iter().collect();
+    assert!(unique.len() >= sent.len().min(N_MEMBERS.0 - 1));
+    stop(members).await;
+}
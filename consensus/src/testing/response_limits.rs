@@ -0,0 +1,196 @@
+use crate::{
+    drop_monitor,
+    member::UnitMessage::{NewUnit, RequestCoord, RequestParents, ResponseCoord, ResponseParents},
+    network::NetworkDataInner::Units,
+    testing::{init_log, HonestMember, HonestMemberBuilder, Network},
+    units::{UncheckedSignedUnit, Unit},
+    DropMonitor, DropReason, DropStatsHandle, Network as _, NetworkData as NetworkDataT, NodeCount,
+    NodeIndex, Recipient, ResponseLimits, SpawnHandle, DEFAULT_RESPONSE_LIMITS,
+};
+use aleph_bft_mock::{Data, Hasher64, Router, Signature, Spawner};
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+const N_MEMBERS: NodeCount = NodeCount(7);
+const TARGET: NodeIndex = NodeIndex(0);
+const FLOODER: NodeIndex = NodeIndex(5);
+const PEER: NodeIndex = NodeIndex(6);
+const FLOOD: usize = 10_000;
+
+// Nothing is ever answered twice within the test.
+const LIMITS: ResponseLimits = ResponseLimits {
+    dedup_window: Duration::from_secs(3600),
+    ..DEFAULT_RESPONSE_LIMITS
+};
+
+async fn target_unit(network: &mut Network) -> UncheckedSignedUnit<Hasher64, Data, Signature> {
+    loop {
+        if let Some(NetworkDataT(Units(NewUnit(unit)), _)) = network.next_event().await {
+            if unit.as_signable().creator() == TARGET && unit.as_signable().round() == 1 {
+                return unit;
+            }
+        }
+    }
+}
+
+async fn count_responses(network: &mut Network, wait: Duration) -> usize {
+    let mut responses = 0;
+    let _ = timeout(wait, async {
+        loop {
+            if let Some(NetworkDataT(Units(ResponseCoord(_) | ResponseParents(..)), _)) =
+                network.next_event().await
+            {
+                responses += 1;
+            }
+        }
+    })
+    .await;
+    responses
+}
+
+async fn wait_for_flood(handle: &DropStatsHandle) {
+    timeout(Duration::from_secs(60), async {
+        loop {
+            let stats = handle.stats();
+            if stats.count(DropReason::RepeatedRequest) + stats.count(DropReason::QueueFull)
+                >= 2 * (FLOOD - 1)
+            {
+                return;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("the flood should get processed");
+}
+
+/// Spawns all the members other than the flooder and the peer, which are returned as networks.
+fn spawn_members(spawner: Spawner, monitor: DropMonitor) -> (Network, Network, Vec<HonestMember>) {
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut flooder_network = None;
+    let mut peer_network = None;
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let node_index = network.index();
+        match node_index {
+            FLOODER => {
+                flooder_network = Some(network);
+                continue;
+            }
+            PEER => {
+                peer_network = Some(network);
+                continue;
+            }
+            _ => {}
+        }
+        let member = HonestMemberBuilder::new(node_index, N_MEMBERS)
+            .with_config(|config| config.with_response_limits(LIMITS));
+        let member = match node_index {
+            TARGET => member.with_local_io(|local_io| local_io.with_drop_monitor(monitor.clone())),
+            _ => member,
+        };
+        members.push(member.spawn(spawner, network));
+    }
+    (
+        flooder_network.expect("the flooder is a member"),
+        peer_network.expect("the well-behaved peer is a member"),
+        members,
+    )
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn flood_of_repeated_requests_is_answered_once() {
+    init_log();
+    let spawner = Spawner::new();
+    let (drops, monitor) = drop_monitor();
+    let (mut flooder_network, mut peer_network, members) = spawn_members(spawner, monitor);
+
+    let unit = timeout(Duration::from_secs(30), target_unit(&mut flooder_network))
+        .await
+        .expect("the target should create units");
+    let coord = unit.as_signable().coord();
+    let u_hash = unit.as_signable().hash();
+    for _ in 0..FLOOD {
+        flooder_network.send(
+            NetworkDataT(Units(RequestCoord(FLOODER, coord)), 0),
+            Recipient::Node(TARGET),
+        );
+        flooder_network.send(
+            NetworkDataT(Units(RequestParents(FLOODER, u_hash)), 0),
+            Recipient::Node(TARGET),
+        );
+    }
+    wait_for_flood(&drops).await;
+
+    // The flood does not keep the target from answering others.
+    peer_network.send(
+        NetworkDataT(Units(RequestCoord(PEER, coord)), 0),
+        Recipient::Node(TARGET),
+    );
+    timeout(Duration::from_secs(2), async {
+        loop {
+            if let Some(NetworkDataT(Units(ResponseCoord(response)), _)) =
+                peer_network.next_event().await
+            {
+                if response.as_signable().coord() == coord {
+                    return;
+                }
+            }
+        }
+    })
+    .await
+    .expect("the well-behaved peer should get its response promptly");
+
+    let responses = count_responses(&mut flooder_network, Duration::from_millis(500)).await;
+    assert!(
+        (1..=2).contains(&responses),
+        "the flooder got {} responses",
+        responses
+    );
+    assert!(drops.stats().count(DropReason::RepeatedRequest) > 0);
+
+    for member in members {
+        member.stop().await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn requests_claiming_foreign_indices_are_dropped() {
+    init_log();
+    let spawner = Spawner::new();
+    let (drops, monitor) = drop_monitor();
+    let (mut flooder_network, _, members) = spawn_members(spawner, monitor);
+
+    let unit = timeout(Duration::from_secs(30), target_unit(&mut flooder_network))
+        .await
+        .expect("the target should create units");
+    let coord = unit.as_signable().coord();
+    let u_hash = unit.as_signable().hash();
+    for claimed in [TARGET, NodeIndex(N_MEMBERS.0), NodeIndex(usize::MAX)] {
+        flooder_network.send(
+            NetworkDataT(Units(RequestCoord(claimed, coord)), 0),
+            Recipient::Node(TARGET),
+        );
+        flooder_network.send(
+            NetworkDataT(Units(RequestParents(claimed, u_hash)), 0),
+            Recipient::Node(TARGET),
+        );
+    }
+    timeout(Duration::from_secs(10), async {
+        while drops.stats().count(DropReason::UnexpectedMessage) < 6 {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("the requests should get dropped");
+    assert_eq!(drops.stats().count(DropReason::RequestRateLimited), 0);
+
+    for member in members {
+        member.stop().await;
+    }
+}
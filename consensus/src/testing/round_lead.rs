@@ -0,0 +1,123 @@
+use crate::{
+    create_config, drop_monitor,
+    member::UnitMessage::NewUnit,
+    network::NetworkDataInner::Units,
+    status_query,
+    testing::{gen_delay_config, init_log, HonestMemberBuilder, Network, NetworkData},
+    units::{ControlHash, FullUnit, PreUnit},
+    Config, DropReason, Network as _, NetworkData as NetworkDataT, NodeCount, NodeIndex, NodeMap,
+    Recipient, Round, Signed, SpawnHandle,
+};
+use aleph_bft_mock::{Hasher64, Keychain, Router, Spawner};
+use futures::{FutureExt, StreamExt};
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const OBSERVED: NodeIndex = NodeIndex(0);
+const BYZANTINE: NodeIndex = NodeIndex(3);
+/// High enough for the rounds of the byzantine units to pass the validation.
+const MAX_ROUND: Round = 60_000;
+const FAR_ROUND: Round = 50_000;
+const FAR_UNITS_PER_BURST: Round = 10;
+const FINALIZED: usize = 50;
+
+fn config(node_ix: NodeIndex) -> Config {
+    create_config(
+        N_MEMBERS,
+        node_ix,
+        0,
+        MAX_ROUND,
+        gen_delay_config(),
+        Duration::ZERO,
+    )
+    .expect("the config is valid")
+}
+
+/// A correctly signed unit of the byzantine node of the given round, with parents nobody has.
+fn far_unit(round: Round) -> NetworkData {
+    let mut parents = NodeMap::with_size(N_MEMBERS);
+    for creator in N_MEMBERS.into_iterator() {
+        parents.insert(creator, ([round as u8; 8], round - 1));
+    }
+    let control_hash = ControlHash::<Hasher64>::new(&parents);
+    let pre_unit = PreUnit::<Hasher64>::new(BYZANTINE, round, control_hash);
+    let keychain = Keychain::new(N_MEMBERS, BYZANTINE);
+    let unit = Signed::sign(FullUnit::new(pre_unit, Some(0), 0), &keychain)
+        .expect("the keychain never fails");
+    NetworkDataT(Units(NewUnit(unit.into())), 0)
+}
+
+/// Keeps sending bursts of units far ahead of everyone, ignoring everything it receives.
+async fn flood_with_far_units(mut network: Network) {
+    loop {
+        for round in FAR_ROUND..FAR_ROUND + FAR_UNITS_PER_BURST {
+            network.send(far_unit(round), Recipient::Everyone);
+        }
+        let mut pause = sleep(Duration::from_millis(20)).boxed().fuse();
+        loop {
+            futures::select! {
+                _ = pause => break,
+                _ = network.next_event().fuse() => {},
+            }
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn units_far_ahead_are_dropped_while_consensus_proceeds() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let (drop_stats, monitor) = drop_monitor();
+    let (status, query) = status_query();
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let network: Network = network;
+        let node_ix = network.index();
+        if node_ix == BYZANTINE {
+            spawner.spawn("byzantine", flood_with_far_units(network));
+            continue;
+        }
+        let member = match node_ix {
+            OBSERVED => {
+                HonestMemberBuilder::from_config(config(node_ix)).with_local_io(|local_io| {
+                    local_io
+                        .with_drop_monitor(monitor.clone())
+                        .with_status_query(query.clone())
+                })
+            }
+            _ => HonestMemberBuilder::from_config(config(node_ix)),
+        };
+        members.push(member.spawn(spawner, network));
+    }
+
+    let finalized: Vec<_> = timeout(
+        Duration::from_secs(60),
+        (&mut members[OBSERVED.0].finalization_rx)
+            .take(FINALIZED)
+            .collect(),
+    )
+    .await
+    .expect("the honest nodes should keep finalizing");
+    let status = status.query().await.expect("the session should run");
+    for member in members {
+        member.stop().await;
+    }
+
+    assert_eq!(finalized.len(), FINALIZED);
+    // Nothing far ahead got in, so we ask nobody for its parents.
+    assert!(status
+        .top_rounds
+        .iter()
+        .all(|(_, round)| *round < FAR_ROUND));
+    assert!(status
+        .missing_coords
+        .iter()
+        .all(|(_, round)| *round < FAR_ROUND - 1));
+    assert!(drop_stats.stats().count(DropReason::TooFarAhead) > 0);
+}
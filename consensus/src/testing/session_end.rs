@@ -0,0 +1,93 @@
+use crate::{
+    create_config,
+    extension::last_head_round,
+    run_session, session_end_monitor,
+    testing::{gen_delay_config, init_log, NetworkData},
+    ClockSource, LocalIO, NodeCount, Round, SessionEnd, SpawnHandle, Terminator,
+};
+use aleph_bft_mock::{
+    DataProvider, FinalizationHandler, Keychain, Loader, Router, Saver, Spawner, TokioClock,
+};
+use futures::{channel::oneshot, StreamExt};
+use parking_lot::Mutex;
+use serial_test::serial;
+use std::{sync::Arc, time::Duration};
+
+const N_MEMBERS: usize = 4;
+const MAX_ROUND: Round = 20;
+
+// The runtime time is paused, so the grace period passes without waiting for it.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn session_ends_on_its_own_at_max_round() {
+    init_log();
+    let n_members = NodeCount(N_MEMBERS);
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::<NetworkData>::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let node_ix = network.index();
+        let config = create_config(
+            n_members,
+            node_ix,
+            0,
+            MAX_ROUND,
+            gen_delay_config(),
+            Duration::ZERO,
+        )
+        .expect("the config should be valid")
+        .with_clock(ClockSource::new(TokioClock));
+        let (finalization_handler, finalized) = FinalizationHandler::new();
+        let (session_ends, session_end_monitor) = session_end_monitor();
+        let saver: Saver = Arc::new(Mutex::new(vec![])).into();
+        let local_io = LocalIO::new(
+            DataProvider::new(),
+            finalization_handler,
+            saver,
+            Loader::new(vec![]),
+        )
+        .with_session_end_monitor(session_end_monitor);
+        // Nobody ever sends the exit signal, but the session has to see it is not dropped.
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let session = tokio::spawn(run_session(
+            config,
+            local_io,
+            network,
+            Keychain::new(n_members, node_ix),
+            spawner,
+            Terminator::create_root(exit_rx, "AlephBFT-member"),
+        ));
+        members.push((session, exit_tx, finalized, session_ends));
+    }
+
+    let mut final_rounds = Vec::new();
+    let mut finalized_data = Vec::new();
+    for (session, _exit_tx, finalized, session_ends) in members {
+        let result = tokio::time::timeout(Duration::from_secs(3600), session)
+            .await
+            .expect("the session should end on its own")
+            .expect("the session task should not panic");
+        assert!(result.is_ok(), "the session should end cleanly");
+        // The last head might get elected without our unit of the max round.
+        let mut session_ends: Vec<_> = session_ends.collect().await;
+        session_ends.sort_by_key(|end| matches!(end, SessionEnd::FinalizationEnded(_)));
+        match session_ends[..] {
+            [SessionEnd::CreationEnded(MAX_ROUND), SessionEnd::FinalizationEnded(round)] => {
+                final_rounds.push(round)
+            }
+            ref ends => panic!("unexpected session ends {:?}", ends),
+        }
+        finalized_data.push(finalized.collect::<Vec<_>>().await);
+    }
+
+    // Nobody gets stuck before the last head, and there are no heads above it.
+    assert!(final_rounds
+        .iter()
+        .all(|round| *round == last_head_round(MAX_ROUND)));
+    for other in finalized_data.iter().skip(1) {
+        assert_eq!(other, &finalized_data[0]);
+    }
+    assert!(!finalized_data[0].is_empty());
+}
@@ -0,0 +1,136 @@
+use crate::{
+    create_config,
+    testing::{gen_delay_config, init_log, HonestMember, HonestMemberBuilder},
+    NodeCount, NodeIndex, SessionId, SessionRouter, SessionRoutingStats, SpawnHandle,
+};
+use aleph_bft_mock::{Router, Spawner};
+use futures::StreamExt;
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+use super::NetworkData;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const BUFFER_LIMIT: usize = 10_000;
+const N_DATA: usize = 20;
+const LATE_NODE: usize = 3;
+
+type Routers = Vec<SessionRouter<NetworkData>>;
+
+fn spawn_session(
+    spawner: Spawner,
+    routers: &Routers,
+    nodes: impl Iterator<Item = usize>,
+    session_id: SessionId,
+    closed_early: bool,
+) -> Vec<HonestMember> {
+    nodes
+        .map(|node| {
+            let network = routers[node]
+                .open(session_id)
+                .expect("the session should not be opened yet");
+            let config = create_config(
+                N_MEMBERS,
+                NodeIndex(node),
+                session_id,
+                5000,
+                gen_delay_config(),
+                Duration::ZERO,
+            )
+            .expect("Should always succeed with Duration::ZERO");
+            let member = HonestMemberBuilder::from_config(config);
+            match closed_early {
+                true => member.with_early_termination_allowed(),
+                false => member,
+            }
+            .spawn(spawner, network)
+        })
+        .collect()
+}
+
+async fn check_finalization(members: &mut [HonestMember]) {
+    let mut batches = Vec::new();
+    for member in members.iter_mut() {
+        let batch: Vec<_> = member.finalization_rx.by_ref().take(N_DATA).collect().await;
+        assert_eq!(batch.len(), N_DATA);
+        batches.push(batch);
+    }
+    for batch in &batches {
+        assert_eq!(batch, &batches[0]);
+    }
+}
+
+async fn wait_for_stats(
+    router: &SessionRouter<NetworkData>,
+    check: impl Fn(SessionRoutingStats) -> bool,
+) {
+    timeout(Duration::from_secs(30), async {
+        while !check(router.stats()) {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("the messages should get routed");
+}
+
+async fn stop(members: Vec<HonestMember>) {
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn overlapping_sessions_share_one_network() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::<(SessionId, NetworkData)>::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+    let routers: Routers = networks
+        .into_iter()
+        .map(|(network, _)| {
+            let (router, routing) = SessionRouter::new(network, BUFFER_LIMIT);
+            spawner.spawn("session-router", routing);
+            router
+        })
+        .collect();
+
+    // The old session gets closed on one of the nodes while it runs.
+    let mut old_session = spawn_session(spawner, &routers, 0..N_MEMBERS.0, 0, true);
+    check_finalization(&mut old_session).await;
+
+    // The new session starts while the old one still runs, on one of the nodes only after the
+    // others already sent it messages.
+    let mut new_session = spawn_session(
+        spawner,
+        &routers,
+        (0..N_MEMBERS.0).filter(|node| *node != LATE_NODE),
+        1,
+        false,
+    );
+    wait_for_stats(&routers[LATE_NODE], |stats| stats.buffered > 0).await;
+    new_session.extend(spawn_session(
+        spawner,
+        &routers,
+        LATE_NODE..LATE_NODE + 1,
+        1,
+        false,
+    ));
+    check_finalization(&mut new_session).await;
+    check_finalization(&mut old_session).await;
+    assert_eq!(routers[LATE_NODE].stats().buffered, 0);
+
+    // The other nodes keep running the old session, so their messages keep coming.
+    routers[0].close(0);
+    assert!(routers[0].open(0).is_none());
+    wait_for_stats(&routers[0], |stats| stats.dropped_closed > 0).await;
+    check_finalization(&mut new_session).await;
+
+    stop(old_session).await;
+    stop(new_session).await;
+    for router in &routers {
+        assert_eq!(router.stats().dropped_buffer_full, 0);
+    }
+}
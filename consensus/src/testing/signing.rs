@@ -0,0 +1,107 @@
+use crate::{
+    events::{InternalEvent, SigningTarget},
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_session_with_events, Network, TestEventBus,
+    },
+    LocalIO, NodeCount, SpawnHandle,
+};
+use aleph_bft_mock::{
+    Data, DataProvider, FailingSigning, FinalizationHandler, Keychain, Loader, Router, Saver,
+    Spawner,
+};
+use futures::{
+    channel::{mpsc::UnboundedReceiver, oneshot},
+    StreamExt,
+};
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::timeout;
+
+struct FlakyMember {
+    finalization_rx: UnboundedReceiver<Data>,
+    unit_signing_failures: oneshot::Receiver<usize>,
+    exit_tx: oneshot::Sender<()>,
+    handle: crate::TaskHandle,
+}
+
+/// Spawns a member whose keychain fails every `failure_period`th signing attempt.
+fn spawn_flaky_member(
+    spawner: Spawner,
+    network: Network,
+    n_members: NodeCount,
+    failure_period: usize,
+) -> FlakyMember {
+    let node_index = network.index();
+    let config = gen_config(node_index, n_members, gen_delay_config());
+    let (finalization_handler, finalization_rx) = FinalizationHandler::new();
+    let local_io = LocalIO::new(
+        DataProvider::new(),
+        finalization_handler,
+        Saver::new(),
+        Loader::new(vec![]),
+    );
+    let keychain = FailingSigning::new(Keychain::new(n_members, node_index), move |attempt| {
+        attempt % failure_period == failure_period - 1
+    });
+    let events = TestEventBus::new();
+    let mut consumer = events.subscribe();
+    let (unit_signing_failures_tx, unit_signing_failures) = oneshot::channel();
+    tokio::spawn(async move {
+        let mut failures = 0;
+        while let Some(event) = consumer.next().await {
+            if let InternalEvent::SigningFailed(SigningTarget::Unit(_)) = event {
+                failures += 1;
+            }
+        }
+        let _ = unit_signing_failures_tx.send(failures);
+    });
+    let (exit_tx, handle) =
+        spawn_session_with_events(spawner, config, local_io, network, keychain, events);
+    FlakyMember {
+        finalization_rx,
+        unit_signing_failures,
+        exit_tx,
+        handle,
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn session_finalizes_despite_failing_signing() {
+    init_log();
+    let n_members = NodeCount(4);
+    let n_data = 50;
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut members: Vec<_> = networks
+        .into_iter()
+        .map(|(network, _)| spawn_flaky_member(spawner, network, n_members, 5))
+        .collect();
+
+    let mut finalized = Vec::new();
+    for member in members.iter_mut() {
+        let data: Vec<Data> = timeout(
+            Duration::from_secs(60),
+            member.finalization_rx.by_ref().take(n_data).collect(),
+        )
+        .await
+        .expect("signing failures should not stop finalization");
+        finalized.push(data);
+    }
+    for data in finalized.iter() {
+        assert_eq!(data, &finalized[0]);
+    }
+
+    let mut unit_signing_failures = 0;
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+        unit_signing_failures += member
+            .unit_signing_failures
+            .await
+            .expect("consumer should report");
+    }
+    assert!(unit_signing_failures > 0);
+}
@@ -0,0 +1,23 @@
+use crate::testing::{init_log, simulate};
+use serial_test::serial;
+
+const N_SEEDS: u64 = 20;
+const N_NODES: usize = 4;
+const N_ROUNDS: usize = 10;
+
+#[test]
+#[serial]
+fn honest_nodes_agree_whatever_the_delivery_order() {
+    init_log();
+    for seed in 0..N_SEEDS {
+        let finalized = simulate(seed, N_NODES, N_ROUNDS);
+        assert_eq!(finalized.len(), N_NODES);
+        for other in finalized.iter().skip(1) {
+            assert_eq!(
+                other, &finalized[0],
+                "the nodes disagree with the seed {}",
+                seed
+            );
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{
+    testing::{gen_config, gen_delay_config, init_log, spawn_honest_member_with_config},
+    NodeCount, NodeIndex, SpawnHandle,
+};
+use aleph_bft_mock::{Data, DataProvider, Router, Spawner};
+use futures::StreamExt;
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+async fn honest_small_committee_finalizes(n_members: NodeCount) {
+    init_log();
+    let n_batches = 20;
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let ix = network.index();
+        let config = gen_config(ix, n_members, gen_delay_config()).with_allow_small_committee(true);
+        members.push(spawn_honest_member_with_config(
+            spawner,
+            config,
+            vec![],
+            DataProvider::new(),
+            network,
+        ));
+    }
+
+    let mut finalized = Vec::new();
+    for member in members.iter_mut() {
+        let data: Vec<Data> = timeout(
+            Duration::from_secs(30),
+            member.finalization_rx.by_ref().take(n_batches).collect(),
+        )
+        .await
+        .expect("an honest small committee should finalize data");
+        finalized.push(data);
+    }
+    for data in &finalized {
+        assert_eq!(data, &finalized[0]);
+    }
+
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn single_node_finalizes() {
+    honest_small_committee_finalizes(NodeCount(1)).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn committee_of_two_finalizes() {
+    honest_small_committee_finalizes(NodeCount(2)).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn committee_of_three_finalizes() {
+    honest_small_committee_finalizes(NodeCount(3)).await;
+}
+
+/// A committee of three needs all of its members for every round, so with one of them silent
+/// nobody gets past the first round and nothing is ever finalized.
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn silent_node_stalls_committee_of_three() {
+    init_log();
+    let n_members = NodeCount(3);
+    let silent = NodeIndex(2);
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut members = Vec::new();
+    let mut silent_network = None;
+    for (network, _) in networks {
+        let ix = network.index();
+        if ix == silent {
+            silent_network = Some(network);
+            continue;
+        }
+        let config = gen_config(ix, n_members, gen_delay_config()).with_allow_small_committee(true);
+        members.push(spawn_honest_member_with_config(
+            spawner,
+            config,
+            vec![],
+            DataProvider::new(),
+            network,
+        ));
+    }
+
+    // Many times the creation delay, plenty for dozens of rounds.
+    sleep(Duration::from_secs(3)).await;
+    for member in members.iter_mut() {
+        assert!(member.finalization_rx.try_next().is_err());
+    }
+    drop(silent_network);
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn small_committee_has_to_be_allowed() {
+    init_log();
+    let n_members = NodeCount(3);
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+
+    for (network, _) in networks {
+        let ix = network.index();
+        let mut member = spawn_honest_member_with_config(
+            spawner,
+            gen_config(ix, n_members, gen_delay_config()),
+            vec![],
+            DataProvider::new(),
+            network,
+        );
+        timeout(Duration::from_secs(5), member.handle)
+            .await
+            .expect("the session should refuse to run")
+            .expect("the session should end gracefully");
+        assert_eq!(member.finalization_rx.next().await, None);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{
+    backup_replication,
+    events::InternalEvent,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member_with_events, HonestMember,
+        HonestMemberBuilder, Network, TestEventBus,
+    },
+    BackupReplication, ClockSource, NodeCount, NodeIndex, SpawnHandle, WarmStandby,
+};
+use aleph_bft_mock::{Data, DataProvider, Hasher64, Router, Signature, Spawner};
+use futures::{channel::oneshot, StreamExt};
+use serial_test::serial;
+use std::time::{Duration, Instant};
+use tokio::{task::JoinHandle, time::timeout};
+
+type TestStandby = WarmStandby<Hasher64, Data, Signature>;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const SEAT: NodeIndex = NodeIndex(0);
+const FENCING_DELAY: Duration = Duration::from_secs(1);
+/// How long the promoted session may take to collect the newest units of the committee and
+/// create its next unit, on top of the fencing delay.
+const STARTUP_ALLOWANCE: Duration = Duration::from_secs(2);
+const BATCHES_BEFORE_FAILOVER: usize = 20;
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Runs a session for the seat, continuing from the given backup and replicating it.
+fn spawn_seat(
+    spawner: Spawner,
+    network: Network,
+    backup: Vec<u8>,
+    replication: BackupReplication,
+) -> HonestMember {
+    HonestMemberBuilder::new(SEAT, N_MEMBERS)
+        .with_units(backup)
+        .with_local_io(|local_io| local_io.with_backup_replication(replication))
+        .spawn(spawner, network)
+}
+
+/// Counts the alerts the member raised or received.
+fn count_alerts(events: &TestEventBus) -> JoinHandle<usize> {
+    let mut events = events.subscribe();
+    tokio::spawn(async move {
+        let mut alerts = 0;
+        while let Some(event) = events.next().await {
+            if let InternalEvent::AlertQueued(_) | InternalEvent::AlertStateChanged(_, _) = event {
+                alerts += 1;
+            }
+        }
+        alerts
+    })
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn promoted_standby_takes_over_the_seat() {
+    init_log();
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut primary = None;
+    let mut others = Vec::new();
+    let mut alerts = Vec::new();
+    for (network, reconnect_tx) in networks {
+        match network.index() {
+            SEAT => primary = Some((network, reconnect_tx)),
+            ix => {
+                let events = TestEventBus::new();
+                alerts.push(count_alerts(&events));
+                others.push(spawn_honest_member_with_events(
+                    spawner,
+                    gen_config(ix, N_MEMBERS, gen_delay_config()),
+                    vec![],
+                    DataProvider::new(),
+                    network,
+                    events,
+                ));
+            }
+        }
+    }
+    let (network, reconnect_tx) = primary.expect("the seat is in the committee");
+    let (replica, replication) = backup_replication();
+    let mut standby = TestStandby::new(replica, SEAT, ClockSource::default());
+    let mut primary = spawn_seat(spawner, network, vec![], replication);
+
+    let mut last_unit_at = Instant::now();
+    let mut finalized_by_primary = Vec::new();
+    while finalized_by_primary.len() < BATCHES_BEFORE_FAILOVER {
+        if let Ok(Some(batch)) = primary.finalization_rx.try_next() {
+            finalized_by_primary.push(batch);
+        }
+        if standby
+            .ingest()
+            .expect("the primary replicates correct units")
+            > 0
+        {
+            last_unit_at = Instant::now();
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    primary.stop().await;
+    if standby
+        .ingest()
+        .expect("the primary replicates correct units")
+        > 0
+    {
+        last_unit_at = Instant::now();
+    }
+    let primary_next_round = standby.next_round();
+    assert!(primary_next_round > 0);
+
+    let (network_for_standby, network) = oneshot::channel();
+    reconnect_tx
+        .unbounded_send((SEAT, network_for_standby))
+        .expect("the router is running");
+    let network = network.await.expect("the router reconnects the seat");
+    let backup = standby
+        .promote(FENCING_DELAY)
+        .await
+        .expect("the primary is stopped");
+    let (replica, replication) = backup_replication();
+    let mut promoted_units = TestStandby::new(replica, SEAT, ClockSource::default());
+    let mut promoted = spawn_seat(spawner, network, backup, replication);
+
+    let first_new_unit_at = timeout(Duration::from_secs(30), async {
+        loop {
+            promoted_units
+                .ingest()
+                .expect("the promoted session replicates correct units");
+            if promoted_units.next_round() > primary_next_round {
+                return Instant::now();
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    })
+    .await
+    .expect("the promoted standby should create units");
+    let creation_gap = first_new_unit_at - last_unit_at;
+    assert!(
+        creation_gap <= FENCING_DELAY + STARTUP_ALLOWANCE,
+        "creation stopped for {:?}",
+        creation_gap
+    );
+
+    let finalized_by_promoted: Vec<_> = timeout(
+        Duration::from_secs(30),
+        (&mut promoted.finalization_rx)
+            .take(2 * BATCHES_BEFORE_FAILOVER)
+            .collect::<Vec<_>>(),
+    )
+    .await
+    .expect("the promoted standby should finalize");
+    assert_eq!(
+        finalized_by_promoted[..BATCHES_BEFORE_FAILOVER],
+        finalized_by_primary[..]
+    );
+    for member in others.iter_mut() {
+        let finalized: Vec<_> = (&mut member.finalization_rx)
+            .take(2 * BATCHES_BEFORE_FAILOVER)
+            .collect()
+            .await;
+        assert_eq!(finalized, finalized_by_promoted);
+    }
+
+    promoted.stop().await;
+    for member in others {
+        member.stop().await;
+    }
+    for alerts in alerts {
+        assert_eq!(alerts.await.expect("the consumer does not panic"), 0);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{
+    member::UnitMessage,
+    network::NetworkDataInner,
+    status_query,
+    testing::{init_log, HonestMemberBuilder, NetworkData},
+    units::{UncheckedSignedUnit, Unit},
+    NodeCount, NodeIndex, SpawnHandle,
+};
+use aleph_bft_mock::{Data, Hasher64, NetworkHook, Router, Signature, Spawner};
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const OBSERVER: NodeIndex = NodeIndex(0);
+const STALLED: NodeIndex = NodeIndex(3);
+
+/// Keeps the units of the stalled node from round 2 on away from the observer, so the observer
+/// keeps requesting them as the parents of the units of others.
+struct StallingHook;
+
+impl StallingHook {
+    fn is_withheld(unit: &UncheckedSignedUnit<Hasher64, Data, Signature>) -> bool {
+        let unit = unit.as_signable();
+        unit.creator() == STALLED && unit.round() >= 2
+    }
+}
+
+impl NetworkHook<NetworkData> for StallingHook {
+    fn process_message(
+        &mut self,
+        data: NetworkData,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) -> Vec<(NetworkData, NodeIndex, NodeIndex)> {
+        let withheld = recipient == OBSERVER
+            && match &data {
+                crate::NetworkData(
+                    NetworkDataInner::Units(
+                        UnitMessage::NewUnit(unit) | UnitMessage::ResponseCoord(unit),
+                    ),
+                    _,
+                ) => Self::is_withheld(unit),
+                crate::NetworkData(
+                    NetworkDataInner::Units(
+                        UnitMessage::ResponseParents(_, units) | UnitMessage::ResponseUnits(units),
+                    ),
+                    _,
+                ) => units.iter().any(Self::is_withheld),
+                _ => false,
+            };
+        match withheld {
+            true => Vec::new(),
+            false => vec![(data, sender, recipient)],
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn reports_units_missing_from_stalled_creator() {
+    init_log();
+    let spawner = Spawner::new();
+    let (mut net_hub, networks) = Router::new(N_MEMBERS);
+    net_hub.add_hook(StallingHook);
+    spawner.spawn("network-hub", net_hub);
+
+    let (status, query) = status_query();
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let node_index = network.index();
+        let member = match node_index {
+            OBSERVER => HonestMemberBuilder::new(node_index, N_MEMBERS)
+                .with_local_io(|local_io| local_io.with_status_query(query.clone())),
+            _ => HonestMemberBuilder::new(node_index, N_MEMBERS),
+        };
+        members.push(member.spawn(spawner, network));
+    }
+
+    let stalled = timeout(Duration::from_secs(30), async {
+        loop {
+            // The query fails until the session of the observer starts.
+            if let Ok(status) = status.query().await {
+                if status
+                    .missing_coords
+                    .iter()
+                    .any(|(creator, _)| *creator == STALLED)
+                {
+                    return status;
+                }
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("the observer should miss the units of the stalled node");
+
+    assert!(stalled.pending_requests >= stalled.missing_coords.len());
+    assert!(stalled
+        .top_rounds
+        .get(STALLED)
+        .is_some_and(|round| *round < 2));
+    assert!(stalled
+        .top_rounds
+        .get(NodeIndex(1))
+        .is_some_and(|round| *round >= 2));
+    assert_eq!(stalled.known_forkers, 0);
+
+    for member in members {
+        member.stop().await;
+    }
+    assert_eq!(
+        status.query().await,
+        Err(crate::StatusQueryError::NotRunning)
+    );
+}
@@ -0,0 +1,148 @@
+use crate::{
+    testing::{init_log, HonestMember, HonestMemberBuilder},
+    unit_metadata_monitor, NodeCount, NodeIndex, Round, SpawnHandle, UnitMetadata,
+    UnitMetadataHandle, UnitMetadataProvider, DEFAULT_MAX_UNIT_METADATA_SIZE,
+    EXTENDED_MESSAGES_VERSION,
+};
+use aleph_bft_mock::{Hasher64, Router, Spawner};
+use futures::StreamExt;
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const READER: NodeIndex = NodeIndex(1);
+const ROUNDS: Round = 10;
+
+/// Tags every unit with the version of the software and its round.
+struct VersionProvider;
+
+impl UnitMetadataProvider for VersionProvider {
+    fn metadata(&self, round: Round) -> Vec<u8> {
+        format!("v1.2.3/{}", round).into_bytes()
+    }
+}
+
+/// Attaches more metadata than the default configuration allows.
+struct OversizedProvider;
+
+impl UnitMetadataProvider for OversizedProvider {
+    fn metadata(&self, _round: Round) -> Vec<u8> {
+        vec![7; DEFAULT_MAX_UNIT_METADATA_SIZE + 1]
+    }
+}
+
+struct Committee {
+    metadata: UnitMetadataHandle<Hasher64>,
+    members: Vec<HonestMember>,
+}
+
+impl Committee {
+    /// Runs the committee, with the reader observing the metadata, until it finalizes some data.
+    async fn run_until_finalized(mut self, data_items: usize) -> Vec<UnitMetadata<Hasher64>> {
+        let finalized: Vec<_> = timeout(
+            Duration::from_secs(60),
+            (&mut self.members[READER.0].finalization_rx)
+                .take(data_items)
+                .collect(),
+        )
+        .await
+        .expect("the reader should keep finalizing");
+        assert_eq!(finalized.len(), data_items);
+        // The units carrying the finalized data might still be waiting for the backup elsewhere.
+        sleep(Duration::from_millis(500)).await;
+        let metadata = self.metadata.take();
+        for member in self.members {
+            member.stop().await;
+        }
+        metadata
+    }
+}
+
+fn spawn_committee(
+    spawner: Spawner,
+    setup: impl Fn(NodeIndex, HonestMemberBuilder) -> HonestMemberBuilder,
+) -> Committee {
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+    let (metadata, monitor) = unit_metadata_monitor();
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let node_index = network.index();
+        let member = HonestMemberBuilder::new(node_index, N_MEMBERS)
+            .with_config(|config| config.with_wire_version(EXTENDED_MESSAGES_VERSION));
+        let member = match node_index {
+            READER => member
+                .with_local_io(|local_io| local_io.with_unit_metadata_monitor(monitor.clone())),
+            _ => member,
+        };
+        members.push(setup(node_index, member).spawn(spawner, network));
+    }
+    Committee { metadata, members }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn peers_read_metadata_of_our_units() {
+    init_log();
+    let committee = spawn_committee(Spawner::new(), |node_index, member| match node_index.0 {
+        0 => member.with_local_io(|local_io| local_io.with_unit_metadata_provider(VersionProvider)),
+        // The creator drops the metadata exceeding the bound, but still creates the units.
+        2 => {
+            member.with_local_io(|local_io| local_io.with_unit_metadata_provider(OversizedProvider))
+        }
+        _ => member,
+    });
+    let metadata = committee
+        .run_until_finalized(4 * (ROUNDS as usize + 2))
+        .await;
+
+    assert!(metadata.iter().all(|record| record.creator == NodeIndex(0)));
+    let rounds: Vec<_> = metadata.iter().map(|record| record.round).collect();
+    for round in 0..ROUNDS {
+        assert!(rounds.contains(&round), "no metadata of round {}", round);
+    }
+    for record in metadata {
+        assert_eq!(
+            record.metadata,
+            format!("v1.2.3/{}", record.round).into_bytes()
+        );
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn units_with_oversized_metadata_are_rejected() {
+    init_log();
+    let committee = spawn_committee(Spawner::new(), |node_index, member| match node_index.0 {
+        // A member with a looser bound than the rest of the committee.
+        0 => member
+            .with_config(|config| {
+                config.with_max_unit_metadata_size(2 * DEFAULT_MAX_UNIT_METADATA_SIZE)
+            })
+            .with_local_io(|local_io| local_io.with_unit_metadata_provider(OversizedProvider)),
+        _ => member,
+    });
+    // The rest of the committee is enough to keep finalizing without the units of the member.
+    let metadata = committee.run_until_finalized(3 * ROUNDS as usize).await;
+
+    assert!(metadata.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn metadata_is_left_out_at_wire_version_0() {
+    init_log();
+    let committee = spawn_committee(Spawner::new(), |node_index, member| match node_index.0 {
+        // The releases predating wire versions cannot read units with metadata.
+        0 => member
+            .with_config(|config| config.with_wire_version(0))
+            .with_local_io(|local_io| local_io.with_unit_metadata_provider(VersionProvider)),
+        _ => member,
+    });
+    let metadata = committee
+        .run_until_finalized(4 * (ROUNDS as usize + 2))
+        .await;
+
+    assert!(metadata.is_empty());
+}
@@ -0,0 +1,155 @@
+use crate::{
+    testing::{gen_config, gen_delay_config, init_log, spawn_session},
+    unit_size_monitor, DataProvider, FinalizationHandler, LocalIO, NetworkData, NodeCount,
+    SpawnHandle, UnitSizeHistogram, UnitSizeStats, UnitSizeStatsHandle, UnitSizeSummary,
+};
+use aleph_bft_mock::{
+    Hasher64, Loader, Network as MockNetwork, PartialMultisignature, Router, Saver, Signature,
+    Spawner,
+};
+use async_trait::async_trait;
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+const SMALL_DATA: usize = 16;
+const LARGE_DATA: usize = 1500;
+const MIN_CREATED_UNITS: usize = 40;
+
+type BimodalNetwork = MockNetwork<NetworkData<Hasher64, Vec<u8>, Signature, PartialMultisignature>>;
+
+/// Provides small data items, and a large one every fourth time.
+#[derive(Default)]
+struct BimodalDataProvider {
+    counter: usize,
+}
+
+#[async_trait]
+impl DataProvider for BimodalDataProvider {
+    type Output = Vec<u8>;
+
+    async fn get_data(&mut self) -> Option<Vec<u8>> {
+        self.counter += 1;
+        let size = match self.counter % 4 {
+            0 => LARGE_DATA,
+            _ => SMALL_DATA,
+        };
+        Some(vec![self.counter as u8; size])
+    }
+}
+
+struct IgnoringFinalizationHandler;
+
+impl FinalizationHandler<Vec<u8>> for IgnoringFinalizationHandler {
+    fn data_finalized(&mut self, _: Vec<u8>) {}
+}
+
+fn parse_summary(summary: &str) -> UnitSizeSummary {
+    let parse = |field: Option<&str>, prefix: &str, suffix: &str| -> usize {
+        field
+            .and_then(|field| field.strip_prefix(prefix))
+            .and_then(|field| field.strip_suffix(suffix))
+            .and_then(|number| number.parse().ok())
+            .unwrap_or_else(|| panic!("unexpected summary format: {}", summary))
+    };
+    let mut fields = summary.split(", ");
+    UnitSizeSummary {
+        count: parse(fields.next(), "", " units"),
+        p50: parse(fields.next(), "p50 ", "B"),
+        p90: parse(fields.next(), "p90 ", "B"),
+        p99: parse(fields.next(), "p99 ", "B"),
+        max: parse(fields.next(), "max ", "B"),
+    }
+}
+
+/// Parses the unit size statistics in the format they are logged in.
+fn parse_stats_line(line: &str) -> (UnitSizeSummary, UnitSizeSummary) {
+    let (created, received) = line
+        .strip_prefix("created - ")
+        .and_then(|line| line.split_once("; received - "))
+        .expect("unexpected stats format");
+    (parse_summary(created), parse_summary(received))
+}
+
+/// The number of units in the buckets containing the small and the large units respectively,
+/// and in all the other buckets.
+fn modes(histogram: &UnitSizeHistogram) -> (usize, usize, usize) {
+    let (mut small, mut large, mut other) = (0, 0, 0);
+    for (bound, count) in histogram.buckets() {
+        match bound {
+            Some(bound) if bound <= 256 => small += count,
+            Some(2048) => large += count,
+            _ => other += count,
+        }
+    }
+    (small, large, other)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn histogram_reflects_bimodal_unit_sizes() {
+    init_log();
+    let n_members = NodeCount(4);
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut members = Vec::new();
+    let mut stats_handles: Vec<UnitSizeStatsHandle> = Vec::new();
+    for (network, _) in networks {
+        let network: BimodalNetwork = network;
+        let node_index = network.index();
+        let (stats_handle, monitor) = unit_size_monitor();
+        let local_io = LocalIO::new(
+            BimodalDataProvider::default(),
+            IgnoringFinalizationHandler,
+            Saver::new(),
+            Loader::new(vec![]),
+        )
+        .with_unit_size_monitor(monitor);
+        members.push(spawn_session(
+            spawner,
+            gen_config(node_index, n_members, gen_delay_config()),
+            local_io,
+            network,
+        ));
+        stats_handles.push(stats_handle);
+    }
+
+    timeout(Duration::from_secs(60), async {
+        while stats_handles
+            .iter()
+            .any(|handle| handle.stats().created.count() < MIN_CREATED_UNITS)
+        {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("members should keep creating units");
+    for (exit_tx, handle) in members {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    for handle in stats_handles {
+        let UnitSizeStats { created, received } = handle.stats();
+        let (small, large, other) = modes(&created);
+        assert_eq!(other, 0, "unexpected unit sizes: {:?}", created);
+        assert!(small > 2 * large, "too few small units: {:?}", created);
+        assert!(large > 0, "no large units: {:?}", created);
+        let (small, large, other) = modes(&received);
+        assert_eq!(other, 0, "unexpected unit sizes: {:?}", received);
+        assert!(small > 0 && large > 0, "missing a mode: {:?}", received);
+
+        let summary = created.summary();
+        assert!(summary.p50 <= 256);
+        assert!(summary.p90 > LARGE_DATA && summary.p90 <= 2048);
+        assert!(summary.max > LARGE_DATA && summary.max <= 2048);
+
+        let line = handle.stats().to_string();
+        assert_eq!(
+            parse_stats_line(&line),
+            (created.summary(), received.summary())
+        );
+    }
+}
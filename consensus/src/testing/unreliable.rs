@@ -27,12 +27,15 @@ impl NetworkHook<NetworkData> for CorruptPacket {
         if self.recipient != recipient || self.sender != sender {
             return vec![(data, sender, recipient)];
         }
-        if let crate::NetworkData(NetworkDataInner::Units(UnitMessage::NewUnit(us))) = &mut data {
+        if let crate::NetworkData(NetworkDataInner::Units(UnitMessage::NewUnit(us)), _) = &mut data
+        {
             let full_unit = us.clone().into_signable();
             let index = full_unit.index();
             if full_unit.round() == self.round && full_unit.creator() == self.creator {
                 let bad_keychain: BadSigning<Keychain> = Keychain::new(0.into(), index).into();
-                *us = Signed::sign(full_unit, &bad_keychain).into();
+                *us = Signed::sign(full_unit, &bad_keychain)
+                    .expect("the keychain never fails")
+                    .into();
             }
         }
         vec![(data, sender, recipient)]
@@ -56,7 +59,7 @@ impl NetworkHook<NetworkData> for NoteRequest {
         use NetworkDataInner::Units;
         use UnitMessage::RequestCoord;
         if sender == self.sender {
-            if let crate::NetworkData(Units(RequestCoord(_, co))) = &data {
+            if let crate::NetworkData(Units(RequestCoord(_, co)), _) = &data {
                 if co.round() == self.round && co.creator() == self.creator {
                     *self.requested.lock() = true;
                 }
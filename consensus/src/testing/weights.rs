@@ -0,0 +1,82 @@
+use crate::{
+    testing::{init_log, HonestMember, HonestMemberBuilder, Network},
+    NodeCount, NodeIndex, NodeWeights, SpawnHandle,
+};
+use aleph_bft_mock::{Router, Spawner};
+use futures::StreamExt;
+use serial_test::serial;
+use std::time::Duration;
+use tokio::time::timeout;
+
+const N_MEMBERS: NodeCount = NodeCount(4);
+const FINALIZED: usize = 20;
+
+/// A heavy node weighing more than all the others together, so that it makes a quorum with any
+/// other node, while the light nodes alone do not.
+fn node_weights() -> NodeWeights {
+    NodeWeights::new(vec![4, 1, 1, 1])
+}
+
+/// Runs the given members of the weighted committee, the others stay offline.
+fn spawn_members(spawner: Spawner, alive: &[NodeIndex]) -> Vec<HonestMember> {
+    let (net_hub, networks) = Router::new(N_MEMBERS);
+    spawner.spawn("network-hub", net_hub);
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let network: Network = network;
+        let node_ix = network.index();
+        if !alive.contains(&node_ix) {
+            continue;
+        }
+        members.push(
+            HonestMemberBuilder::new(node_ix, N_MEMBERS)
+                .with_config(|config| config.with_node_weights(node_weights()))
+                .with_keychain(|keychain| keychain.with_node_weights(node_weights()))
+                .spawn(spawner, network),
+        );
+    }
+    members
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn heavy_node_and_one_other_finalize() {
+    init_log();
+    let mut members = spawn_members(Spawner::new(), &[NodeIndex(0), NodeIndex(1)]);
+
+    let mut finalized = Vec::new();
+    for member in &mut members {
+        let batch: Vec<_> = timeout(
+            Duration::from_secs(30),
+            member.finalization_rx.by_ref().take(FINALIZED).collect(),
+        )
+        .await
+        .expect("a quorum of weight should finalize");
+        finalized.push(batch);
+    }
+    for member in members {
+        member.stop().await;
+    }
+
+    assert_eq!(finalized[0], finalized[1]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn light_nodes_alone_do_not_finalize() {
+    init_log();
+    let mut members = spawn_members(Spawner::new(), &[NodeIndex(1), NodeIndex(2), NodeIndex(3)]);
+
+    // Three out of four nodes would be a quorum without the weights.
+    for member in &mut members {
+        assert!(
+            timeout(Duration::from_secs(3), member.finalization_rx.next())
+                .await
+                .is_err(),
+            "the light nodes should not finalize anything"
+        );
+    }
+    for member in members {
+        member.stop().await;
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{Hasher, NodeIndex, Round};
+use parking_lot::Mutex;
+use std::{collections::VecDeque, sync::Arc};
+
+/// How many metadata records wait for the application before the oldest ones get dropped.
+const METADATA_KEPT: usize = 1000;
+
+/// The metadata carried by a unit admitted to the dag, see [`crate::UnitMetadataProvider`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnitMetadata<H: Hasher> {
+    pub hash: H::Hash,
+    pub creator: NodeIndex,
+    pub round: Round,
+    pub metadata: Vec<u8>,
+}
+
+/// Allows the application to read the metadata our peers attach to their units. Consensus never
+/// interprets the metadata, so it is only as trustworthy as its creator.
+#[derive(Clone)]
+pub struct UnitMetadataHandle<H: Hasher> {
+    records: Arc<Mutex<VecDeque<UnitMetadata<H>>>>,
+}
+
+impl<H: Hasher> UnitMetadataHandle<H> {
+    /// The metadata of the units admitted since the last call, in the order of admission. Only
+    /// the latest records are kept when the handle is not asked for a long time.
+    pub fn take(&self) -> Vec<UnitMetadata<H>> {
+        self.records.lock().drain(..).collect()
+    }
+}
+
+/// The part of the metadata observation passed to the session, see [`unit_metadata_monitor`].
+/// Units without metadata are not recorded.
+pub struct UnitMetadataMonitor<H: Hasher> {
+    records: Arc<Mutex<VecDeque<UnitMetadata<H>>>>,
+}
+
+impl<H: Hasher> Clone for UnitMetadataMonitor<H> {
+    fn clone(&self) -> Self {
+        UnitMetadataMonitor {
+            records: self.records.clone(),
+        }
+    }
+}
+
+impl<H: Hasher> Default for UnitMetadataMonitor<H> {
+    fn default() -> Self {
+        UnitMetadataMonitor {
+            records: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+}
+
+impl<H: Hasher> UnitMetadataMonitor<H> {
+    pub(crate) fn record(&self, record: UnitMetadata<H>) {
+        if record.metadata.is_empty() {
+            return;
+        }
+        let mut records = self.records.lock();
+        if records.len() == METADATA_KEPT {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+}
+
+/// Creates a handle for reading the metadata of the admitted units together with the monitor
+/// that should be passed to the session with [`crate::LocalIO::with_unit_metadata_monitor`].
+pub fn unit_metadata_monitor<H: Hasher>() -> (UnitMetadataHandle<H>, UnitMetadataMonitor<H>) {
+    let monitor = UnitMetadataMonitor::default();
+    (
+        UnitMetadataHandle {
+            records: monitor.records.clone(),
+        },
+        monitor,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        unit_metadata::{unit_metadata_monitor, UnitMetadata, METADATA_KEPT},
+        NodeIndex,
+    };
+    use aleph_bft_mock::Hasher64;
+
+    fn record(round: u16, metadata: Vec<u8>) -> UnitMetadata<Hasher64> {
+        UnitMetadata {
+            hash: [round as u8; 8],
+            creator: NodeIndex(1),
+            round,
+            metadata,
+        }
+    }
+
+    #[test]
+    fn skips_units_without_metadata() {
+        let (handle, monitor) = unit_metadata_monitor();
+        monitor.record(record(0, Vec::new()));
+        monitor.record(record(1, vec![1]));
+        assert_eq!(handle.take(), vec![record(1, vec![1])]);
+        assert!(handle.take().is_empty());
+    }
+
+    #[test]
+    fn keeps_only_the_latest_records() {
+        let (handle, monitor) = unit_metadata_monitor();
+        for round in 0..(METADATA_KEPT as u16 + 10) {
+            monitor.record(record(round, vec![1]));
+        }
+        let records = handle.take();
+        assert_eq!(records.len(), METADATA_KEPT);
+        assert_eq!(records[0].round, 10);
+    }
+}
@@ -0,0 +1,256 @@
+use crate::Round;
+use parking_lot::Mutex;
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+};
+
+/// The upper bounds, in bytes, of the histogram buckets. Units larger than the last bound end
+/// up in an additional overflow bucket.
+pub const UNIT_SIZE_BUCKETS: [usize; 16] = [
+    64,
+    128,
+    256,
+    512,
+    1 << 10,
+    2 << 10,
+    4 << 10,
+    8 << 10,
+    16 << 10,
+    32 << 10,
+    64 << 10,
+    128 << 10,
+    256 << 10,
+    512 << 10,
+    1 << 20,
+    2 << 20,
+];
+
+/// A summary of the unit sizes is logged whenever we create a unit of a round divisible by this.
+pub(crate) const UNIT_SIZE_SUMMARY_INTERVAL: Round = 50;
+
+/// A histogram of encoded unit sizes with fixed, exponentially growing buckets.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct UnitSizeHistogram {
+    counts: [usize; UNIT_SIZE_BUCKETS.len() + 1],
+    count: usize,
+    max: usize,
+}
+
+impl UnitSizeHistogram {
+    pub(crate) fn record(&mut self, size: usize) {
+        let bucket = UNIT_SIZE_BUCKETS.partition_point(|bound| *bound < size);
+        self.counts[bucket] += 1;
+        self.count += 1;
+        self.max = self.max.max(size);
+    }
+
+    /// The number of recorded units.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The size of the largest recorded unit.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// The number of units in every bucket, together with the upper bound of the bucket.
+    /// The bound of the overflow bucket is `None`.
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<usize>, usize)> + '_ {
+        UNIT_SIZE_BUCKETS
+            .iter()
+            .map(|bound| Some(*bound))
+            .chain(Some(None))
+            .zip(self.counts.iter().copied())
+    }
+
+    /// An upper estimate of the given quantile, i.e. the upper bound of the bucket containing it,
+    /// but never more than the largest recorded size. `None` if nothing was recorded.
+    pub fn quantile(&self, quantile: f64) -> Option<usize> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((quantile * self.count as f64).ceil() as usize).clamp(1, self.count);
+        let mut seen = 0;
+        for (bound, count) in self.buckets() {
+            seen += count;
+            if seen >= rank {
+                return Some(bound.map_or(self.max, |bound| bound.min(self.max)));
+            }
+        }
+        Some(self.max)
+    }
+
+    /// The most commonly needed quantiles.
+    pub fn summary(&self) -> UnitSizeSummary {
+        UnitSizeSummary {
+            count: self.count,
+            p50: self.quantile(0.5).unwrap_or(0),
+            p90: self.quantile(0.9).unwrap_or(0),
+            p99: self.quantile(0.99).unwrap_or(0),
+            max: self.max,
+        }
+    }
+}
+
+/// Estimated quantiles of the unit sizes, in bytes.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct UnitSizeSummary {
+    pub count: usize,
+    pub p50: usize,
+    pub p90: usize,
+    pub p99: usize,
+    pub max: usize,
+}
+
+impl Display for UnitSizeSummary {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "{} units, p50 {}B, p90 {}B, p99 {}B, max {}B",
+            self.count, self.p50, self.p90, self.p99, self.max
+        )
+    }
+}
+
+/// The sizes of the units seen during a session.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct UnitSizeStats {
+    /// The units created by us.
+    pub created: UnitSizeHistogram,
+    /// The units received from other nodes, as sent over the network. Includes duplicates.
+    pub received: UnitSizeHistogram,
+}
+
+impl Display for UnitSizeStats {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "created - {}; received - {}",
+            self.created.summary(),
+            self.received.summary()
+        )
+    }
+}
+
+/// Allows the application to inspect the sizes of the units in a session, e.g. to tune the amount
+/// of data put into units.
+#[derive(Clone)]
+pub struct UnitSizeStatsHandle {
+    stats: Arc<Mutex<UnitSizeStats>>,
+}
+
+impl UnitSizeStatsHandle {
+    /// The unit sizes recorded so far.
+    pub fn stats(&self) -> UnitSizeStats {
+        self.stats.lock().clone()
+    }
+}
+
+/// The part of the unit size monitoring passed to the session, see [`unit_size_monitor`].
+#[derive(Clone, Default)]
+pub struct UnitSizeMonitor {
+    stats: Arc<Mutex<UnitSizeStats>>,
+}
+
+impl UnitSizeMonitor {
+    pub(crate) fn record_created(&self, size: usize) {
+        self.stats.lock().created.record(size);
+    }
+
+    pub(crate) fn record_received(&self, sizes: &[usize]) {
+        let mut stats = self.stats.lock();
+        for size in sizes {
+            stats.received.record(*size);
+        }
+    }
+
+    pub(crate) fn stats(&self) -> UnitSizeStats {
+        self.stats.lock().clone()
+    }
+}
+
+/// Creates a handle for inspecting the unit sizes together with the monitor that should be
+/// passed to the session with [`crate::LocalIO::with_unit_size_monitor`].
+pub fn unit_size_monitor() -> (UnitSizeStatsHandle, UnitSizeMonitor) {
+    let stats = Arc::new(Mutex::new(UnitSizeStats::default()));
+    (
+        UnitSizeStatsHandle {
+            stats: stats.clone(),
+        },
+        UnitSizeMonitor { stats },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::unit_sizes::{unit_size_monitor, UnitSizeHistogram, UnitSizeSummary};
+
+    fn histogram(sizes: &[usize]) -> UnitSizeHistogram {
+        let mut histogram = UnitSizeHistogram::default();
+        for size in sizes {
+            histogram.record(*size);
+        }
+        histogram
+    }
+
+    #[test]
+    fn empty_histogram_has_no_quantiles() {
+        let histogram = UnitSizeHistogram::default();
+        assert_eq!(histogram.quantile(0.5), None);
+        assert_eq!(histogram.summary(), UnitSizeSummary::default());
+    }
+
+    #[test]
+    fn records_into_buckets_by_upper_bound() {
+        let histogram = histogram(&[1, 64, 65, 3000, 3 << 20]);
+        let counts: Vec<_> = histogram
+            .buckets()
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        assert_eq!(
+            counts,
+            vec![(Some(64), 2), (Some(128), 1), (Some(4096), 1), (None, 1)]
+        );
+        assert_eq!(histogram.count(), 5);
+        assert_eq!(histogram.max(), 3 << 20);
+    }
+
+    #[test]
+    fn quantiles_are_bucket_bounds_capped_by_max() {
+        let mut sizes = vec![100; 90];
+        sizes.extend([1500; 9]);
+        sizes.push(10_000);
+        let histogram = histogram(&sizes);
+        assert_eq!(histogram.quantile(0.5), Some(128));
+        assert_eq!(histogram.quantile(0.9), Some(128));
+        assert_eq!(histogram.quantile(0.91), Some(2048));
+        assert_eq!(histogram.quantile(0.99), Some(2048));
+        assert_eq!(histogram.quantile(1.0), Some(10_000));
+    }
+
+    #[test]
+    fn quantiles_of_a_single_unit_are_its_size() {
+        let histogram = histogram(&[5]);
+        assert_eq!(histogram.quantile(0.0), Some(5));
+        assert_eq!(histogram.quantile(0.99), Some(5));
+    }
+
+    #[test]
+    fn overflowing_sizes_use_max() {
+        let histogram = histogram(&[5 << 20, 6 << 20]);
+        assert_eq!(histogram.quantile(0.5), Some(6 << 20));
+    }
+
+    #[test]
+    fn handle_sees_recorded_sizes() {
+        let (handle, monitor) = unit_size_monitor();
+        monitor.record_created(100);
+        monitor.record_received(&[200, 300]);
+        let stats = handle.stats();
+        assert_eq!(stats.created.count(), 1);
+        assert_eq!(stats.received.count(), 2);
+        assert_eq!(stats.received.max(), 300);
+    }
+}
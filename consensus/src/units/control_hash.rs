@@ -1,4 +1,7 @@
-use crate::{units::UnitCoord, Hasher, NodeCount, NodeIndex, NodeMap, Round};
+use crate::{
+    units::{parent_eligibility, UnitCoord},
+    Hasher, NodeCount, NodeIndex, NodeMap, NodeWeights, Round,
+};
 use codec::{Decode, Encode};
 use std::{
     fmt::{Display, Formatter, Result as FmtResult},
@@ -82,6 +85,29 @@ impl<H: Hasher> ControlHash<H> {
         self.combined_hash
     }
 
+    /// Recalculates the combined hash from the coords and hashes of the parents, returns `None`
+    /// if they cannot be the parents of a single unit, i.e. two of them have the same creator or
+    /// a creator is not a member.
+    pub fn combined_hash_of(
+        n_members: NodeCount,
+        parents: impl IntoIterator<Item = (UnitCoord, H::Hash)>,
+    ) -> Option<H::Hash> {
+        let mut parent_map = NodeMap::with_size(n_members);
+        for (coord, hash) in parents {
+            let creator = coord.creator();
+            if creator.0 >= n_members.0 || parent_map.get(creator).is_some() {
+                return None;
+            }
+            parent_map.insert(creator, (hash, coord.round()));
+        }
+        Some(Self::create_control_hash(&parent_map))
+    }
+
+    /// Whether the parents with the given coords and hashes are exactly the ones committed to.
+    pub fn matches_parents(&self, parents: impl IntoIterator<Item = (UnitCoord, H::Hash)>) -> bool {
+        Self::combined_hash_of(self.n_members(), parents) == Some(self.combined_hash)
+    }
+
     /// Iterator over non-empty parents - returns [`UnitCoord`]s
     pub fn parents(&self) -> impl Iterator<Item = UnitCoord> + '_ {
         self.parents
@@ -94,71 +120,27 @@ impl<H: Hasher> ControlHash<H> {
         self.parents.size()
     }
 
-    pub fn validate(&self, unit_coord: UnitCoord) -> Result<(), Error<H>> {
-        match unit_coord.round {
-            0 => self.validate_initial_round(),
-            _ => self.validate_non_initial_round(unit_coord),
-        }
-    }
-
-    fn validate_initial_round(&self) -> Result<(), Error<H>> {
-        let parents_count = self.parents().count();
-        if parents_count > 0 {
-            return Err(Error::RoundZeroWithSomeParents(NodeCount(parents_count)));
-        }
-        let recalculated_control_hash =
-            ControlHash::<H>::create_control_hash(&NodeMap::with_size(self.n_members()));
-        if self.combined_hash != recalculated_control_hash {
-            return Err(Error::RoundZeroBadControlHash(
-                self.combined_hash,
-                recalculated_control_hash,
-            ));
-        }
-
-        Ok(())
-    }
-
-    fn validate_non_initial_round(&self, unit_coord: UnitCoord) -> Result<(), Error<H>> {
-        assert!(unit_coord.round > 0, "Round must be greater than 0");
-
-        self.unit_creator_is_descendant_of_previous_unit(unit_coord)?;
-        self.previous_round_have_enough_parents(unit_coord.round)?;
-        self.check_if_parents_greater_than_previous_round(unit_coord.round)?;
-
-        Ok(())
-    }
-
-    fn check_if_parents_greater_than_previous_round(&self, round: Round) -> Result<(), Error<H>> {
-        let parents_greater_than_previous_round = self
-            .parents()
-            .any(|unit_coord| unit_coord.round > round - 1);
-        if parents_greater_than_previous_round {
-            return Err(Error::ParentsHigherThanRound(round - 1));
-        }
-        Ok(())
+    /// Rounds of the parents, indexed by their creators.
+    pub fn parent_rounds(&self) -> &NodeMap<Round> {
+        &self.parents
     }
 
-    fn previous_round_have_enough_parents(&self, round: Round) -> Result<(), Error<H>> {
-        let previous_round_parents = self
-            .parents()
-            .filter(|&parent| parent.round == round - 1)
-            .count();
-        if previous_round_parents < self.n_members().consensus_threshold().0 {
-            return Err(Error::NotEnoughParentsForRound(round - 1));
-        }
-        Ok(())
-    }
-
-    fn unit_creator_is_descendant_of_previous_unit(
+    /// Checks the parents against the rules in [`parent_eligibility`], and, for round 0,
+    /// whether the combined hash is the hash of no parents.
+    pub fn validate(
         &self,
         unit_coord: UnitCoord,
+        node_weights: &NodeWeights,
     ) -> Result<(), Error<H>> {
-        match self.parents.get(unit_coord.creator) {
-            None => return Err(Error::NotDescendantOfPreviousUnit(unit_coord.creator)),
-            Some(&parent_round) => {
-                if unit_coord.round - 1 != parent_round {
-                    return Err(Error::DescendantOfPreviousUnitHasWrongRound(parent_round));
-                }
+        parent_eligibility::check_parents(&self.parents, unit_coord, node_weights)?;
+        if unit_coord.round == 0 {
+            let recalculated_control_hash =
+                ControlHash::<H>::create_control_hash(&NodeMap::with_size(self.n_members()));
+            if self.combined_hash != recalculated_control_hash {
+                return Err(Error::RoundZeroBadControlHash(
+                    self.combined_hash,
+                    recalculated_control_hash,
+                ));
             }
         }
         Ok(())
@@ -169,7 +151,7 @@ impl<H: Hasher> ControlHash<H> {
 pub mod tests {
     use crate::units::{control_hash::Error, ControlHash, NodeCount, NodeIndex, UnitCoord};
     use aleph_bft_mock::Hasher64;
-    use aleph_bft_types::{NodeMap, Round};
+    use aleph_bft_types::{NodeMap, NodeWeights, Round};
     use codec::{Decode, Encode};
 
     #[test]
@@ -229,8 +211,11 @@ pub mod tests {
         .into();
         let ch = ControlHash::<Hasher64>::new(&parent_map);
         assert_eq!(
-            ch.validate(UnitCoord::new(0, NodeIndex(1)))
-                .expect_err("validate() should return error, returned Ok(()) instead"),
+            ch.validate(
+                UnitCoord::new(0, NodeIndex(1)),
+                &NodeWeights::uniform(ch.n_members())
+            )
+            .expect_err("validate() should return error, returned Ok(()) instead"),
             Error::RoundZeroWithSomeParents(NodeCount(parent_map.item_count()))
         );
     }
@@ -254,7 +239,10 @@ pub mod tests {
 
         assert_eq!(
             borked_ch
-                .validate(UnitCoord::new(0, NodeIndex(4)))
+                .validate(
+                    UnitCoord::new(0, NodeIndex(4)),
+                    &NodeWeights::uniform(borked_ch.n_members())
+                )
                 .expect_err("validate() should return error, returned Ok(()) instead"),
             Error::RoundZeroBadControlHash(
                 borked_ch.combined_hash,
@@ -276,7 +264,12 @@ pub mod tests {
         ]
         .into();
         let ch = ControlHash::<Hasher64>::new(&parent_map);
-        assert!(ch.validate(UnitCoord::new(3, NodeIndex(2))).is_ok());
+        assert!(ch
+            .validate(
+                UnitCoord::new(3, NodeIndex(2)),
+                &NodeWeights::uniform(ch.n_members())
+            )
+            .is_ok());
     }
 
     #[test]
@@ -291,8 +284,11 @@ pub mod tests {
         .into();
         let ch = ControlHash::<Hasher64>::new(&parent_map);
         assert_eq!(
-            ch.validate(UnitCoord::new(3, NodeIndex(1)))
-                .expect_err("validate() should return error, returned Ok(()) instead"),
+            ch.validate(
+                UnitCoord::new(3, NodeIndex(1)),
+                &NodeWeights::uniform(ch.n_members())
+            )
+            .expect_err("validate() should return error, returned Ok(()) instead"),
             Error::NotDescendantOfPreviousUnit(NodeIndex(1))
         );
     }
@@ -309,8 +305,11 @@ pub mod tests {
         .into();
         let ch = ControlHash::<Hasher64>::new(&parent_map);
         assert_eq!(
-            ch.validate(UnitCoord::new(3, NodeIndex(1)))
-                .expect_err("validate() should return error, returned Ok(()) instead"),
+            ch.validate(
+                UnitCoord::new(3, NodeIndex(1)),
+                &NodeWeights::uniform(ch.n_members())
+            )
+            .expect_err("validate() should return error, returned Ok(()) instead"),
             Error::DescendantOfPreviousUnitHasWrongRound(1)
         );
     }
@@ -327,8 +326,11 @@ pub mod tests {
         .into();
         let ch = ControlHash::<Hasher64>::new(&parent_map);
         assert_eq!(
-            ch.validate(UnitCoord::new(3, NodeIndex(1)))
-                .expect_err("validate() should return error, returned Ok(()) instead"),
+            ch.validate(
+                UnitCoord::new(3, NodeIndex(1)),
+                &NodeWeights::uniform(ch.n_members())
+            )
+            .expect_err("validate() should return error, returned Ok(()) instead"),
             Error::NotEnoughParentsForRound(2)
         );
     }
@@ -345,8 +347,11 @@ pub mod tests {
         .into();
         let ch = ControlHash::<Hasher64>::new(&parent_map);
         assert_eq!(
-            ch.validate(UnitCoord::new(3, NodeIndex(1)))
-                .expect_err("validate() should return error, returned Ok(()) instead"),
+            ch.validate(
+                UnitCoord::new(3, NodeIndex(1)),
+                &NodeWeights::uniform(ch.n_members())
+            )
+            .expect_err("validate() should return error, returned Ok(()) instead"),
             Error::ParentsHigherThanRound(2)
         );
     }
@@ -392,4 +397,68 @@ pub mod tests {
             control_hash_of_fourth_round_unit
         );
     }
+
+    fn parents() -> Vec<(UnitCoord, [u8; 8])> {
+        vec![
+            (UnitCoord::new(2, NodeIndex(0)), [0; 8]),
+            (UnitCoord::new(2, NodeIndex(2)), [2; 8]),
+            (UnitCoord::new(1, NodeIndex(3)), [3; 8]),
+        ]
+    }
+
+    #[test]
+    fn matches_exactly_the_committed_parents() {
+        let ch = ControlHash::<Hasher64>::new(
+            &vec![
+                Some(([0; 8], 2)),
+                None,
+                Some(([2; 8], 2)),
+                Some(([3; 8], 1)),
+            ]
+            .into(),
+        );
+        assert_eq!(
+            ControlHash::<Hasher64>::combined_hash_of(NodeCount(4), parents()),
+            Some(ch.combined_hash())
+        );
+        assert!(ch.matches_parents(parents()));
+        assert!(ch.matches_parents(parents().into_iter().rev()));
+        assert!(!ch.matches_parents(parents().into_iter().take(2)));
+        let mut different_round = parents();
+        different_round[2].0 = UnitCoord::new(2, NodeIndex(3));
+        assert!(!ch.matches_parents(different_round));
+    }
+
+    #[test]
+    fn does_not_match_parents_with_a_substituted_hash() {
+        let ch = ControlHash::<Hasher64>::new(
+            &vec![
+                Some(([0; 8], 2)),
+                None,
+                Some(([2; 8], 2)),
+                Some(([3; 8], 1)),
+            ]
+            .into(),
+        );
+        let mut substituted = parents();
+        substituted[1].1 = [7; 8];
+        assert_eq!(substituted.len(), ch.parents().count());
+        assert!(!ch.matches_parents(substituted));
+    }
+
+    #[test]
+    fn parents_of_repeated_or_unknown_creators_have_no_combined_hash() {
+        let mut repeated = parents();
+        repeated[1].0 = UnitCoord::new(2, NodeIndex(0));
+        assert_eq!(
+            ControlHash::<Hasher64>::combined_hash_of(NodeCount(4), repeated),
+            None
+        );
+        let mut unknown = parents();
+        unknown[1].0 = UnitCoord::new(2, NodeIndex(4));
+        assert_eq!(
+            ControlHash::<Hasher64>::combined_hash_of(NodeCount(4), unknown),
+            None
+        );
+    }
 }
@@ -4,11 +4,12 @@ use crate::{
     Data, Hasher, Index, MultiKeychain, NodeCount, NodeIndex, Round, SessionId, Signable, Signed,
     UncheckedSigned,
 };
-use codec::{Decode, Encode};
+use codec::{Decode, Encode, Error as CodecError, Input, Output};
 use derivative::Derivative;
 use parking_lot::RwLock;
 
 mod control_hash;
+pub mod parent_eligibility;
 mod store;
 #[cfg(test)]
 mod testing;
@@ -25,7 +26,7 @@ pub use testing::{
     random_unit_with_parents, DagUnit as TestingDagUnit, FullUnit as TestingFullUnit,
     SignedUnit as TestingSignedUnit, WrappedSignedUnit,
 };
-pub use validator::{ValidationError, Validator};
+pub use validator::{SessionVerifiedUnit, SignatureVerifiedUnit, ValidationError, Validator};
 
 /// The coordinates of a unit, i.e. creator and round. In the absence of forks this uniquely
 /// determines a unit within a session.
@@ -87,17 +88,66 @@ impl<H: Hasher> PreUnit<H> {
     }
 }
 
-#[derive(Debug, Decode, Derivative, Encode)]
+/// Takes the place of the byte telling whether a unit has data, i.e. 0 or 1, in the encoding of
+/// a unit with metadata, increased by 1 if it has data. The metadata then follows the session id.
+/// Units without metadata are encoded like before they could carry it, so that nodes running
+/// older versions still understand them.
+const WITH_METADATA: u8 = 2;
+
+/// A unit as created by its creator. The metadata is opaque to consensus, but it is covered by the
+/// hash of the unit, so the same unit signed with different metadata is a fork.
+#[derive(Debug, Derivative)]
 #[derivative(Eq, PartialEq, Hash)]
 pub struct FullUnit<H: Hasher, D: Data> {
     pre_unit: PreUnit<H>,
     data: Option<D>,
     session_id: SessionId,
-    #[codec(skip)]
+    metadata: Vec<u8>,
     #[derivative(PartialEq = "ignore", Hash = "ignore")]
     hash: RwLock<Option<H::Hash>>,
 }
 
+impl<H: Hasher, D: Data> Encode for FullUnit<H, D> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        self.pre_unit.encode_to(dest);
+        match self.metadata.is_empty() {
+            true => self.data.encode_to(dest),
+            false => {
+                dest.push_byte(WITH_METADATA + self.data.is_some() as u8);
+                if let Some(data) = &self.data {
+                    data.encode_to(dest);
+                }
+            }
+        }
+        self.session_id.encode_to(dest);
+        if !self.metadata.is_empty() {
+            self.metadata.encode_to(dest);
+        }
+    }
+}
+
+impl<H: Hasher, D: Data> Decode for FullUnit<H, D> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        let pre_unit = PreUnit::decode(input)?;
+        let (data, with_metadata) = match input.read_byte()? {
+            0 => (None, false),
+            1 => (Some(D::decode(input)?), false),
+            WITH_METADATA => (None, true),
+            3 => (Some(D::decode(input)?), true),
+            _ => return Err("unknown encoding of the data of a unit".into()),
+        };
+        let session_id = SessionId::decode(input)?;
+        let metadata = match with_metadata {
+            true => Vec::decode(input)?,
+            false => Vec::new(),
+        };
+        if with_metadata && metadata.is_empty() {
+            return Err("a unit encoded with metadata carries none".into());
+        }
+        Ok(FullUnit::new(pre_unit, data, session_id).with_metadata(metadata))
+    }
+}
+
 impl<H: Hasher, D: Data> From<FullUnit<H, D>> for Option<D> {
     fn from(value: FullUnit<H, D>) -> Self {
         value.data
@@ -111,6 +161,7 @@ impl<H: Hasher, D: Data> Clone for FullUnit<H, D> {
             pre_unit: self.pre_unit.clone(),
             data: self.data.clone(),
             session_id: self.session_id,
+            metadata: self.metadata.clone(),
             hash: RwLock::new(hash),
         }
     }
@@ -122,7 +173,15 @@ impl<H: Hasher, D: Data> FullUnit<H, D> {
             pre_unit,
             data,
             session_id,
+            metadata: Vec::new(),
+            hash: RwLock::new(None),
+        }
+    }
+    pub(crate) fn with_metadata(self, metadata: Vec<u8>) -> Self {
+        FullUnit {
+            metadata,
             hash: RwLock::new(None),
+            ..self
         }
     }
     pub(crate) fn as_pre_unit(&self) -> &PreUnit<H> {
@@ -131,9 +190,19 @@ impl<H: Hasher, D: Data> FullUnit<H, D> {
     pub(crate) fn data(&self) -> &Option<D> {
         &self.data
     }
+    pub(crate) fn metadata(&self) -> &[u8] {
+        &self.metadata
+    }
     pub(crate) fn included_data(&self) -> Vec<D> {
         self.data.iter().cloned().collect()
     }
+    pub(crate) fn included_data_with_creator(&self) -> Vec<(D, NodeIndex)> {
+        let creator = self.pre_unit.creator();
+        self.included_data()
+            .into_iter()
+            .map(|data| (data, creator))
+            .collect()
+    }
 }
 
 impl<H: Hasher, D: Data> Signable for FullUnit<H, D> {
@@ -165,6 +234,9 @@ pub trait Unit: 'static + Send + Clone {
 
     fn session_id(&self) -> SessionId;
 
+    /// Whether the unit contains data, which makes it contribute to the identifier of its batch.
+    fn has_data(&self) -> bool;
+
     fn creator(&self) -> NodeIndex {
         self.coord().creator()
     }
@@ -191,12 +263,18 @@ pub trait UnitWithParents: Unit {
 impl<H: Hasher, D: Data> Unit for FullUnit<H, D> {
     type Hasher = H;
 
+    /// The hash of the unit is combined with the metadata if there is any, so that units without
+    /// it hash like before they could carry it.
     fn hash(&self) -> H::Hash {
         let hash = *self.hash.read();
         match hash {
             Some(hash) => hash,
             None => {
-                let hash = self.using_encoded(H::hash);
+                let hash = (&self.pre_unit, &self.data, self.session_id).using_encoded(H::hash);
+                let hash = match self.metadata.is_empty() {
+                    true => hash,
+                    false => (hash, &self.metadata).using_encoded(H::hash),
+                };
                 *self.hash.write() = Some(hash);
                 hash
             }
@@ -214,6 +292,10 @@ impl<H: Hasher, D: Data> Unit for FullUnit<H, D> {
     fn session_id(&self) -> SessionId {
         self.session_id
     }
+
+    fn has_data(&self) -> bool {
+        self.data.is_some()
+    }
 }
 
 impl<H: Hasher, D: Data, MK: MultiKeychain> Unit for SignedUnit<H, D, MK> {
@@ -234,6 +316,10 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Unit for SignedUnit<H, D, MK> {
     fn session_id(&self) -> SessionId {
         self.as_signable().session_id()
     }
+
+    fn has_data(&self) -> bool {
+        self.as_signable().has_data()
+    }
 }
 
 pub type HashFor<U> = <<U as Unit>::Hasher as Hasher>::Hash;
@@ -241,25 +327,96 @@ pub type HashFor<U> = <<U as Unit>::Hasher as Hasher>::Hash;
 #[cfg(test)]
 pub mod tests {
     use crate::{
-        units::{random_full_parent_units_up_to, FullUnit, Unit},
-        Hasher, NodeCount,
+        units::{random_full_parent_units_up_to, ControlHash, FullUnit, PreUnit, Unit},
+        Hasher, NodeCount, NodeIndex, NodeMap,
     };
     use aleph_bft_mock::{Data, Hasher64};
+    use aleph_bft_types::reference::ReferenceHasher;
     use codec::{Decode, Encode};
 
     pub type TestFullUnit = FullUnit<Hasher64, Data>;
 
+    fn signed_hash<H: Hasher>(full_unit: &FullUnit<H, Data>) -> H::Hash {
+        crate::Signable::hash(full_unit)
+    }
+
     #[test]
     fn test_full_unit_hash_is_correct() {
         for full_unit in random_full_parent_units_up_to(3, NodeCount(4), 43)
             .into_iter()
             .flatten()
         {
-            let hash = full_unit.using_encoded(Hasher64::hash);
+            let hash = (
+                full_unit.as_pre_unit(),
+                full_unit.data(),
+                full_unit.session_id(),
+            )
+                .using_encoded(Hasher64::hash);
             assert_eq!(full_unit.hash(), hash);
         }
     }
 
+    #[test]
+    fn metadata_is_hashed() {
+        for full_unit in random_full_parent_units_up_to(3, NodeCount(4), 43)
+            .into_iter()
+            .flatten()
+        {
+            let with_metadata = full_unit.clone().with_metadata(vec![1, 2, 3]);
+            assert_ne!(with_metadata.hash(), full_unit.hash());
+            assert_eq!(signed_hash(&full_unit), full_unit.hash());
+            assert_eq!(signed_hash(&with_metadata), with_metadata.hash());
+            assert_ne!(
+                with_metadata.hash(),
+                full_unit.with_metadata(vec![1, 2, 4]).hash()
+            );
+        }
+    }
+
+    // Any change to the unit encoding or the control hash computation changes this digest.
+    #[test]
+    fn full_unit_hash_is_pinned() {
+        let mut parents = NodeMap::with_size(NodeCount(4));
+        for parent_index in [0, 1, 3] {
+            let parent_index = NodeIndex(parent_index);
+            parents.insert(
+                parent_index,
+                (ReferenceHasher::hash(&[parent_index.0 as u8]), 2),
+            );
+        }
+        let pre_unit = PreUnit::new(
+            NodeIndex(1),
+            3,
+            ControlHash::<ReferenceHasher>::new(&parents),
+        );
+        let full_unit = FullUnit::new(pre_unit, Some(43u32), 7);
+        assert_eq!(
+            full_unit.hash(),
+            [
+                0x35, 0xa7, 0xfb, 0x4b, 0xb2, 0xce, 0xac, 0xd6, 0x60, 0xe8, 0x8d, 0xbd, 0x2e, 0xed,
+                0x43, 0xb9,
+            ]
+        );
+    }
+
+    // Any change to the way the metadata is hashed changes this digest.
+    #[test]
+    fn full_unit_hash_with_metadata_is_pinned() {
+        let pre_unit = PreUnit::new(
+            NodeIndex(1),
+            0,
+            ControlHash::<ReferenceHasher>::new(&NodeMap::with_size(NodeCount(4))),
+        );
+        let full_unit = FullUnit::new(pre_unit, Some(43u32), 7).with_metadata(b"v1.2.3".to_vec());
+        assert_eq!(
+            signed_hash(&full_unit),
+            [
+                0x26, 0xf1, 0xb4, 0x9b, 0xfa, 0x21, 0x3e, 0x2b, 0x24, 0x24, 0x37, 0xec, 0x0f, 0x7f,
+                0x16, 0x17,
+            ]
+        );
+    }
+
     #[test]
     fn test_full_unit_codec() {
         for full_unit in random_full_parent_units_up_to(3, NodeCount(4), 43)
@@ -270,6 +427,62 @@ pub mod tests {
             let decoded =
                 TestFullUnit::decode(&mut encoded.as_slice()).expect("should decode correctly");
             assert_eq!(decoded, full_unit);
+            let full_unit = full_unit.with_metadata(vec![7; 100]);
+            let encoded = full_unit.encode();
+            let decoded =
+                TestFullUnit::decode(&mut encoded.as_slice()).expect("should decode correctly");
+            assert_eq!(decoded.metadata(), full_unit.metadata());
+            assert_eq!(decoded, full_unit);
+        }
+    }
+
+    #[test]
+    fn encodes_units_without_metadata_like_before_it() {
+        for full_unit in random_full_parent_units_up_to(3, NodeCount(4), 43)
+            .into_iter()
+            .flatten()
+        {
+            assert_eq!(
+                full_unit.encode(),
+                (
+                    full_unit.as_pre_unit(),
+                    full_unit.data(),
+                    full_unit.session_id()
+                )
+                    .encode()
+            );
+        }
+    }
+
+    #[test]
+    fn refuses_units_encoded_with_empty_metadata() {
+        let full_unit = random_full_parent_units_up_to(0, NodeCount(4), 43)[0][0].clone();
+        let mut encoded = full_unit.as_pre_unit().encode();
+        encoded.push(2);
+        encoded.extend(full_unit.session_id().encode());
+        encoded.extend(Vec::<u8>::new().encode());
+        assert!(TestFullUnit::decode(&mut encoded.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_full_unit_without_data_codec() {
+        for full_unit in random_full_parent_units_up_to(3, NodeCount(4), 43)
+            .into_iter()
+            .flatten()
+        {
+            let pre_unit = full_unit.as_pre_unit().clone();
+            for (data, metadata) in [None, Some(7)]
+                .into_iter()
+                .flat_map(|data| [(data, Vec::new()), (data, vec![1, 2, 3])])
+            {
+                let full_unit = FullUnit::new(pre_unit.clone(), data, 43).with_metadata(metadata);
+                let encoded = full_unit.encode();
+                let decoded =
+                    TestFullUnit::decode(&mut encoded.as_slice()).expect("should decode correctly");
+                assert_eq!(decoded.data(), &data);
+                assert_eq!(decoded.included_data(), Vec::from_iter(data));
+                assert_eq!(decoded, full_unit);
+            }
         }
     }
 }
@@ -0,0 +1,266 @@
+//! The normative rules deciding which units may be parents of which.
+//!
+//! The creator uses them to pick parents for our units and the validator uses them to check
+//! the control hashes of units created by others, so a unit we create is always accepted by
+//! honest nodes. Changing any of these rules changes the protocol.
+//!
+//! The rules for the parents of a unit with coord `(round, creator)` are:
+//! 1. Round adjacency: every parent is from a round lower than `round`.
+//! 2. Forkers: at most one unit per creator is a parent, and a unit from some round never
+//!    replaces an already chosen parent from the same round, so forks of a chosen parent
+//!    are never used instead of it.
+//! 3. Quorum: if `round > 0`, the parents from `round - 1` weigh together at least the consensus
+//!    threshold of the [`NodeWeights`], i.e. there are at least that many of them if every node
+//!    weighs one.
+//! 4. Own previous unit: if `round > 0`, the parents contain the unit of `creator` from
+//!    `round - 1`.
+//! 5. Round 0: units of round 0 have no parents at all.
+//!
+//! Only units that were admitted to our store are ever proposed as parents, which in particular
+//! means that units of proven forkers are only used if they were legitimized by an alert.
+
+use crate::{
+    units::{ControlHash, ControlHashError, UnitCoord},
+    Hasher, NodeCount, NodeIndex, NodeMap, NodeWeights, Round,
+};
+
+/// Whether a unit from `parent_round` may be a parent of a unit from `round` (rule 1).
+pub fn may_be_parent(parent_round: Round, round: Round) -> bool {
+    parent_round < round
+}
+
+/// Whether a unit from `round` should replace the current parent candidate of its creator,
+/// which is from `candidate_round`, if there is any (rule 2).
+pub fn replaces_candidate(candidate_round: Option<Round>, round: Round) -> bool {
+    candidate_round.map_or(true, |candidate_round| candidate_round < round)
+}
+
+/// The creators of the parents from the round directly below `round`.
+pub fn direct_parents(
+    parent_rounds: &NodeMap<Round>,
+    round: Round,
+) -> impl Iterator<Item = NodeIndex> + '_ {
+    parent_rounds
+        .iter()
+        .filter(move |(_, parent_round)| round.checked_sub(1) == Some(**parent_round))
+        .map(|(creator, _)| creator)
+}
+
+/// Whether the parents from the round directly below `round` weigh enough (rule 3).
+pub fn has_quorum(
+    parent_rounds: &NodeMap<Round>,
+    round: Round,
+    node_weights: &NodeWeights,
+) -> bool {
+    node_weights.is_quorum(direct_parents(parent_rounds, round))
+}
+
+/// Checks rule 4, only ever for units above round 0.
+fn check_own_previous_unit<H: Hasher>(
+    parent_rounds: &NodeMap<Round>,
+    coord: UnitCoord,
+) -> Result<(), ControlHashError<H>> {
+    match parent_rounds.get(coord.creator()) {
+        None => Err(ControlHashError::NotDescendantOfPreviousUnit(
+            coord.creator(),
+        )),
+        Some(&parent_round) if coord.round() - 1 != parent_round => Err(
+            ControlHashError::DescendantOfPreviousUnitHasWrongRound(parent_round),
+        ),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Checks whether units with the given rounds may be the parents of a unit with `coord`,
+/// returning the first broken rule, if any. The rules are checked in the order 5, 3, 4, 1.
+pub fn check_parents<H: Hasher>(
+    parent_rounds: &NodeMap<Round>,
+    coord: UnitCoord,
+    node_weights: &NodeWeights,
+) -> Result<(), ControlHashError<H>> {
+    let round = coord.round();
+    if round == 0 {
+        return match parent_rounds.item_count() {
+            0 => Ok(()),
+            count => Err(ControlHashError::RoundZeroWithSomeParents(NodeCount(count))),
+        };
+    }
+    if !has_quorum(parent_rounds, round, node_weights) {
+        return Err(ControlHashError::NotEnoughParentsForRound(round - 1));
+    }
+    check_own_previous_unit(parent_rounds, coord)?;
+    if parent_rounds
+        .values()
+        .any(|parent_round| !may_be_parent(*parent_round, round))
+    {
+        return Err(ControlHashError::ParentsHigherThanRound(round - 1));
+    }
+    Ok(())
+}
+
+/// Whether the control hash commits to exactly the given parents.
+pub fn control_hash_matches<H: Hasher>(
+    control_hash: &ControlHash<H>,
+    parents: &NodeMap<(H::Hash, Round)>,
+) -> bool {
+    control_hash.combined_hash() == ControlHash::<H>::create_control_hash(parents)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        units::{
+            parent_eligibility::{
+                check_parents, control_hash_matches, direct_parents, has_quorum, may_be_parent,
+                replaces_candidate,
+            },
+            ControlHash, ControlHashError, UnitCoord,
+        },
+        NodeCount, NodeIndex, NodeMap, NodeWeights, Round,
+    };
+    use aleph_bft_mock::Hasher64;
+
+    fn parent_rounds(rounds: Vec<Option<Round>>) -> NodeMap<Round> {
+        rounds.into()
+    }
+
+    fn check(
+        rounds: Vec<Option<Round>>,
+        round: Round,
+        creator: usize,
+    ) -> Result<(), ControlHashError<Hasher64>> {
+        let rounds = parent_rounds(rounds);
+        let node_weights = NodeWeights::uniform(rounds.size());
+        check_parents(
+            &rounds,
+            UnitCoord::new(round, NodeIndex(creator)),
+            &node_weights,
+        )
+    }
+
+    #[test]
+    fn parents_must_be_from_lower_rounds() {
+        assert!(may_be_parent(0, 1));
+        assert!(may_be_parent(3, 7));
+        assert!(!may_be_parent(1, 1));
+        assert!(!may_be_parent(2, 1));
+        assert!(!may_be_parent(0, 0));
+    }
+
+    #[test]
+    fn only_strictly_newer_units_replace_candidates() {
+        assert!(replaces_candidate(None, 0));
+        assert!(replaces_candidate(Some(0), 1));
+        assert!(!replaces_candidate(Some(1), 1));
+        assert!(!replaces_candidate(Some(2), 1));
+    }
+
+    #[test]
+    fn quorum_counts_only_direct_parents() {
+        let node_weights = NodeWeights::uniform(NodeCount(4));
+        let rounds = parent_rounds(vec![Some(2), Some(2), Some(1), None]);
+        assert_eq!(direct_parents(&rounds, 3).count(), 2);
+        assert!(!has_quorum(&rounds, 3, &node_weights));
+        let rounds = parent_rounds(vec![Some(2), Some(2), Some(1), Some(2)]);
+        assert_eq!(direct_parents(&rounds, 3).count(), 3);
+        assert!(has_quorum(&rounds, 3, &node_weights));
+    }
+
+    #[test]
+    fn quorum_is_weighted() {
+        let node_weights = NodeWeights::new(vec![4, 1, 1, 1]);
+        let rounds = parent_rounds(vec![Some(2), Some(2), None, None]);
+        assert!(has_quorum(&rounds, 3, &node_weights));
+        let rounds = parent_rounds(vec![Some(1), Some(2), Some(2), Some(2)]);
+        assert!(!has_quorum(&rounds, 3, &node_weights));
+    }
+
+    #[test]
+    fn round_zero_has_no_direct_parents() {
+        let rounds = parent_rounds(vec![Some(0), Some(0), Some(0), Some(0)]);
+        assert_eq!(direct_parents(&rounds, 0).count(), 0);
+    }
+
+    #[test]
+    fn round_zero_accepts_no_parents() {
+        assert_eq!(check(vec![None; 4], 0, 0), Ok(()));
+    }
+
+    #[test]
+    fn round_zero_rejects_any_parents() {
+        assert_eq!(
+            check(vec![Some(0), None, None, None], 0, 0),
+            Err(ControlHashError::RoundZeroWithSomeParents(NodeCount(1)))
+        );
+    }
+
+    #[test]
+    fn round_one_requires_quorum_of_round_zero_units() {
+        assert_eq!(check(vec![Some(0), Some(0), Some(0), None], 1, 0), Ok(()));
+        assert_eq!(
+            check(vec![Some(0), Some(0), None, None], 1, 0),
+            Err(ControlHashError::NotEnoughParentsForRound(0))
+        );
+    }
+
+    #[test]
+    fn rejects_missing_own_previous_unit() {
+        assert_eq!(
+            check(vec![None, Some(2), Some(2), Some(2)], 3, 0),
+            Err(ControlHashError::NotDescendantOfPreviousUnit(NodeIndex(0)))
+        );
+    }
+
+    #[test]
+    fn rejects_own_unit_from_older_round() {
+        assert_eq!(
+            check(vec![Some(1), Some(2), Some(2), Some(2)], 3, 0),
+            Err(ControlHashError::DescendantOfPreviousUnitHasWrongRound(1))
+        );
+    }
+
+    #[test]
+    fn rejects_own_unit_from_highest_round() {
+        assert_eq!(
+            check(vec![Some(Round::MAX), Some(2), Some(2), Some(2)], 3, 0),
+            Err(ControlHashError::DescendantOfPreviousUnitHasWrongRound(
+                Round::MAX
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_parents_from_too_high_rounds() {
+        assert_eq!(
+            check(vec![Some(2), Some(2), Some(2), Some(3)], 3, 0),
+            Err(ControlHashError::ParentsHigherThanRound(2))
+        );
+    }
+
+    #[test]
+    fn accepts_older_non_own_parents() {
+        assert_eq!(
+            check(vec![Some(2), Some(2), Some(0), Some(2)], 3, 0),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn missing_quorum_is_reported_before_missing_own_unit() {
+        assert_eq!(
+            check(vec![None, Some(2), None, None], 3, 0),
+            Err(ControlHashError::NotEnoughParentsForRound(2))
+        );
+    }
+
+    #[test]
+    fn control_hash_matches_only_committed_parents() {
+        let parents: NodeMap<_> = vec![Some(([0; 8], 2)), Some(([1; 8], 2)), None].into();
+        let control_hash = ControlHash::<Hasher64>::new(&parents);
+        assert!(control_hash_matches(&control_hash, &parents));
+        let different_hash = vec![Some(([0; 8], 2)), Some(([2; 8], 2)), None].into();
+        assert!(!control_hash_matches(&control_hash, &different_hash));
+        let different_round = vec![Some(([0; 8], 2)), Some(([1; 8], 1)), None].into();
+        assert!(!control_hash_matches(&control_hash, &different_round));
+    }
+}
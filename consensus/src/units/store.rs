@@ -15,10 +15,20 @@ pub struct UnitStoreStatus {
 }
 
 impl UnitStoreStatus {
+    /// How many units the store holds.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
     /// Highest round among units in the store.
     pub fn top_round(&self) -> Round {
         self.top_row.values().max().cloned().unwrap_or(0)
     }
+
+    /// Highest round among units of every creator in the store.
+    pub fn top_row(&self) -> &NodeMap<Round> {
+        &self.top_row
+    }
 }
 
 impl Display for UnitStoreStatus {
@@ -29,9 +39,11 @@ impl Display for UnitStoreStatus {
 
 /// Stores units, and keeps track of which are canonical, i.e. the first ones inserted with a given coordinate.
 /// See `remove` for limitation on trusting canonical units, although they don't impact our usecases.
+/// Units of old rounds can be pruned, see `prune_below`, only the hashes of canonical ones remain.
 pub struct UnitStore<U: Unit> {
     by_hash: HashMap<HashFor<U>, U>,
     canonical_units: NodeMap<HashMap<Round, HashFor<U>>>,
+    pruned_below: Round,
 }
 
 impl<U: Unit> UnitStore<U> {
@@ -44,6 +56,7 @@ impl<U: Unit> UnitStore<U> {
         UnitStore {
             by_hash: HashMap::new(),
             canonical_units,
+            pruned_below: 0,
         }
     }
 
@@ -59,19 +72,13 @@ impl<U: Unit> UnitStore<U> {
             .expect("all hashmaps initialized")
     }
 
-    // only call this for canonical units
-    fn canonical_by_hash(&self, hash: &HashFor<U>) -> &U {
-        self.by_hash.get(hash).expect("we have all canonical units")
-    }
-
     /// Insert a unit. If no other unit with this coord is in the store it becomes canonical.
     pub fn insert(&mut self, unit: U) {
         let unit_hash = unit.hash();
         let unit_coord = unit.coord();
-        if self.canonical_unit(unit_coord).is_none() {
-            self.mut_hashes_by(unit_coord.creator())
-                .insert(unit.coord().round(), unit_hash);
-        }
+        self.mut_hashes_by(unit_coord.creator())
+            .entry(unit_coord.round())
+            .or_insert(unit_hash);
         self.by_hash.insert(unit_hash, unit);
     }
 
@@ -85,20 +92,56 @@ impl<U: Unit> UnitStore<U> {
         }
     }
 
-    /// The canonical unit for the given coord if it exists.
+    /// The canonical unit for the given coord if it exists and was not pruned.
     pub fn canonical_unit(&self, coord: UnitCoord) -> Option<&U> {
         self.hashes_by(coord.creator())
             .get(&coord.round())
-            .map(|hash| self.canonical_by_hash(hash))
+            .and_then(|hash| self.by_hash.get(hash))
     }
 
-    /// All the canonical units for the given creator, in order of rounds.
+    /// All the canonical units for the given creator that were not pruned, in order of rounds.
     pub fn canonical_units(&self, creator: NodeIndex) -> impl Iterator<Item = &U> {
         let canonical_hashes = self.hashes_by(creator);
         let max_round = canonical_hashes.keys().max().cloned().unwrap_or(0);
-        (0..=max_round)
+        (self.pruned_below.min(max_round)..=max_round)
             .filter_map(|round| canonical_hashes.get(&round))
-            .map(|hash| self.canonical_by_hash(hash))
+            .filter_map(|hash| self.by_hash.get(hash))
+    }
+
+    /// The hash of the canonical unit for the given coord, also if it was pruned.
+    pub fn canonical_hash(&self, coord: UnitCoord) -> Option<&HashFor<U>> {
+        self.hashes_by(coord.creator()).get(&coord.round())
+    }
+
+    /// The round below which units were pruned, zero if none were.
+    pub fn pruned_below(&self) -> Round {
+        self.pruned_below
+    }
+
+    /// Whether the unit at the given coord is gone with its round, if we ever had it. Units
+    /// inserted there later would be neither served nor checked for forks.
+    pub fn is_pruned(&self, coord: UnitCoord) -> bool {
+        coord.round() < self.pruned_below && self.canonical_unit(coord).is_none()
+    }
+
+    /// Drops the units of rounds below the given one, keeping only the hashes of the canonical
+    /// ones. The top unit of every creator stays, so that a node restarting can still learn its
+    /// newest unit from us. Returns how many units were dropped.
+    pub fn prune_below(&mut self, round: Round) -> usize {
+        if round <= self.pruned_below {
+            return 0;
+        }
+        self.pruned_below = round;
+        let kept: Vec<_> = self
+            .canonical_units
+            .values()
+            .filter_map(|hashes| hashes.iter().max_by_key(|(round, _)| **round))
+            .map(|(_, hash)| *hash)
+            .collect();
+        let size = self.by_hash.len();
+        self.by_hash
+            .retain(|hash, unit| unit.round() >= round || kept.contains(hash));
+        size - self.by_hash.len()
     }
 
     /// The unit for the given hash, if present.
@@ -271,4 +314,46 @@ mod test {
             assert_eq!(canonical_units.next(), None);
         }
     }
+
+    #[test]
+    fn prunes_old_rounds_keeping_hashes() {
+        let node_count = NodeCount(7);
+        let mut store = UnitStore::new(node_count);
+        let max_round = 15;
+        let units = random_full_parent_units_up_to(max_round, node_count, 43);
+        for round_units in &units {
+            for unit in round_units {
+                // the last creator stops early
+                if unit.creator().0 + 1 < node_count.0 || unit.round() <= 3 {
+                    store.insert(unit.clone());
+                }
+            }
+        }
+        let size = store.status().size();
+        let pruned = store.prune_below(10);
+        assert_eq!(store.status().size(), size - pruned);
+        assert_eq!(pruned, 6 * 10 + 3);
+        assert_eq!(store.prune_below(10), 0);
+        for round_units in &units {
+            for unit in round_units {
+                let coord = unit.coord();
+                let is_last = unit.creator().0 + 1 == node_count.0;
+                if is_last && unit.round() > 3 {
+                    continue;
+                }
+                let kept = unit.round() >= 10 || (is_last && unit.round() == 3);
+                assert_eq!(store.unit(&unit.hash()).is_some(), kept);
+                assert_eq!(store.canonical_unit(coord).is_some(), kept);
+                assert_eq!(store.is_pruned(coord), !kept);
+                assert_eq!(store.canonical_hash(coord), Some(&unit.hash()));
+            }
+        }
+        let last_creator = NodeIndex(node_count.0 - 1);
+        assert_eq!(
+            store.canonical_units(last_creator).collect::<Vec<_>>(),
+            vec![&units[3][last_creator.0]]
+        );
+        assert_eq!(store.canonical_units(NodeIndex(0)).count(), 6);
+        assert_eq!(store.status().top_row().get(last_creator), Some(&3));
+    }
 }
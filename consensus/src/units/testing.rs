@@ -1,11 +1,10 @@
-use crate::units::TestingDagUnit;
 use crate::{
     creation::Creator as GenericCreator,
     dag::ReconstructedUnit,
     units::{
         ControlHash as GenericControlHash, FullUnit as GenericFullUnit, PreUnit as GenericPreUnit,
-        SignedUnit as GenericSignedUnit, UncheckedSignedUnit as GenericUncheckedSignedUnit, Unit,
-        UnitCoord, WrappedUnit,
+        SignedUnit as GenericSignedUnit, TestingDagUnit,
+        UncheckedSignedUnit as GenericUncheckedSignedUnit, Unit, UnitCoord, WrappedUnit,
     },
     NodeCount, NodeIndex, NodeMap, Round, SessionId, Signed,
 };
@@ -41,6 +40,10 @@ impl Unit for WrappedSignedUnit {
     fn session_id(&self) -> SessionId {
         self.0.session_id()
     }
+
+    fn has_data(&self) -> bool {
+        self.0.has_data()
+    }
 }
 
 impl WrappedUnit<Hasher64> for WrappedSignedUnit {
@@ -79,7 +82,7 @@ impl Creator {
 }
 
 pub fn full_unit_to_signed_unit(full_unit: FullUnit, keychain: &Keychain) -> SignedUnit {
-    Signed::sign(full_unit, keychain)
+    Signed::sign(full_unit, keychain).expect("the keychain never fails")
 }
 
 pub fn preunit_to_signed_unit(
@@ -1,8 +1,11 @@
-use crate::units::{ControlHashError, UnitCoord};
 use crate::{
-    units::{FullUnit, PreUnit, SignedUnit, UncheckedSignedUnit, Unit},
-    Data, Hasher, Keychain, NodeCount, NodeIndex, Round, SessionId, Signature, SignatureError,
+    units::{
+        ControlHashError, FullUnit, PreUnit, SignedUnit, UncheckedSignedUnit, Unit, UnitCoord,
+    },
+    Data, Hasher, Keychain, NodeCount, NodeIndex, NodeWeights, Round, SessionId, Signature,
+    SignatureError, DEFAULT_MAX_DATA_SIZE, DEFAULT_MAX_UNIT_METADATA_SIZE,
 };
+use codec::Encode;
 use std::{
     fmt::{Display, Formatter, Result as FmtResult},
     result::Result as StdResult,
@@ -14,6 +17,8 @@ pub enum ValidationError<H: Hasher, D: Data, S: Signature> {
     WrongSignature(UncheckedSignedUnit<H, D, S>),
     WrongSession(FullUnit<H, D>),
     RoundTooHigh(FullUnit<H, D>),
+    MetadataTooLarge(FullUnit<H, D>),
+    DataTooLarge(FullUnit<H, D>),
     WrongNumberOfMembers(PreUnit<H>),
     ParentValidationFailed(PreUnit<H>, ControlHashError<H>),
 }
@@ -25,6 +30,20 @@ impl<H: Hasher, D: Data, S: Signature> Display for ValidationError<H, D, S> {
             WrongSignature(usu) => write!(f, "wrongly signed unit: {:?}", usu),
             WrongSession(fu) => write!(f, "unit from wrong session: {:?}", fu),
             RoundTooHigh(fu) => write!(f, "unit with too high round {}: {:?}", fu.round(), fu),
+            MetadataTooLarge(fu) => write!(
+                f,
+                "unit with too large metadata of {} bytes: {:?}",
+                fu.metadata().len(),
+                fu
+            ),
+            // The data is not printed, as it might be huge.
+            DataTooLarge(fu) => write!(
+                f,
+                "unit of round {} created by {:?} with too large data of {} bytes",
+                fu.round(),
+                fu.creator(),
+                fu.data().encoded_size()
+            ),
             WrongNumberOfMembers(pu) => write!(
                 f,
                 "wrong number of members implied by unit {:?}: {:?}",
@@ -40,6 +59,20 @@ impl<H: Hasher, D: Data, S: Signature> Display for ValidationError<H, D, S> {
     }
 }
 
+impl<H: Hasher, D: Data, S: Signature> ValidationError<H, D, S> {
+    /// The encoding of the offending unit, or of as much of it as we kept.
+    pub fn encoded_unit(&self) -> Vec<u8> {
+        use ValidationError::*;
+        match self {
+            WrongSignature(usu) => usu.encode(),
+            WrongSession(fu) | RoundTooHigh(fu) | MetadataTooLarge(fu) | DataTooLarge(fu) => {
+                fu.encode()
+            }
+            WrongNumberOfMembers(pu) | ParentValidationFailed(pu, _) => pu.encode(),
+        }
+    }
+}
+
 impl<H: Hasher, D: Data, S: Signature> From<SignatureError<FullUnit<H, D>, S>>
     for ValidationError<H, D, S>
 {
@@ -48,22 +81,109 @@ impl<H: Hasher, D: Data, S: Signature> From<SignatureError<FullUnit<H, D>, S>>
     }
 }
 
+/// A unit with a correct signature of its creator, the first stage of the validation of units
+/// arriving in their wire form, `UncheckedSignedUnit`. Nothing else about the unit is known,
+/// in particular it might come from another session.
+///
+/// The stages are distinct types, so a unit cannot skip any of them:
+///
+/// ```compile_fail,E0308
+/// use aleph_bft::{Data, Hasher, Keychain, SessionVerifiedUnit, SignatureVerifiedUnit};
+///
+/// fn admit<H: Hasher, D: Data, K: Keychain>(
+///     unit: SignatureVerifiedUnit<H, D, K>,
+/// ) -> SessionVerifiedUnit<H, D, K> {
+///     unit
+/// }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignatureVerifiedUnit<H: Hasher, D: Data, K: Keychain>(SignedUnit<H, D, K>);
+
+impl<H: Hasher, D: Data, K: Keychain> SignatureVerifiedUnit<H, D, K> {
+    /// The only place the signature of a unit is checked.
+    pub(crate) fn verify(
+        uu: UncheckedSignedUnit<H, D, K::Signature>,
+        keychain: &K,
+    ) -> StdResult<Self, SignatureError<FullUnit<H, D>, K::Signature>> {
+        Ok(SignatureVerifiedUnit(uu.check(keychain)?))
+    }
+
+    pub(crate) fn as_signable(&self) -> &FullUnit<H, D> {
+        self.0.as_signable()
+    }
+}
+
+/// A unit that passed all the checks not involving other units, i.e. it is correctly signed,
+/// belongs to our session, and its round, metadata and control hash are acceptable. The next
+/// stage is a unit admitted to the dag with its parents resolved, a `DagUnit`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SessionVerifiedUnit<H: Hasher, D: Data, K: Keychain>(SignedUnit<H, D, K>);
+
+impl<H: Hasher, D: Data, K: Keychain> SessionVerifiedUnit<H, D, K> {
+    pub(crate) fn as_signable(&self) -> &FullUnit<H, D> {
+        self.0.as_signable()
+    }
+
+    pub(crate) fn into_signed(self) -> SignedUnit<H, D, K> {
+        self.0
+    }
+}
+
+impl<H: Hasher, D: Data, K: Keychain> From<SessionVerifiedUnit<H, D, K>>
+    for UncheckedSignedUnit<H, D, K::Signature>
+{
+    fn from(unit: SessionVerifiedUnit<H, D, K>) -> Self {
+        unit.0.into()
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
 pub struct Validator<K: Keychain> {
     session_id: SessionId,
     keychain: K,
     max_round: Round,
+    max_metadata_size: usize,
+    max_data_size: usize,
+    node_weights: NodeWeights,
 }
 
-type Result<H, D, K> =
-    StdResult<SignedUnit<H, D, K>, ValidationError<H, D, <K as Keychain>::Signature>>;
+type Result<T, H, D, K> = StdResult<T, ValidationError<H, D, <K as Keychain>::Signature>>;
 
 impl<K: Keychain> Validator<K> {
     pub fn new(session_id: SessionId, keychain: K, max_round: Round) -> Self {
+        let node_weights = NodeWeights::uniform(keychain.node_count());
         Validator {
             session_id,
             keychain,
             max_round,
+            max_metadata_size: DEFAULT_MAX_UNIT_METADATA_SIZE,
+            max_data_size: DEFAULT_MAX_DATA_SIZE,
+            node_weights,
+        }
+    }
+
+    /// Sets the voting weights of the nodes, which decide whether the parents of units form a
+    /// quorum. Every node weighs one by default.
+    pub fn with_node_weights(self, node_weights: NodeWeights) -> Self {
+        Validator {
+            node_weights,
+            ..self
+        }
+    }
+
+    /// Sets the largest metadata, in bytes, accepted in units.
+    pub fn with_max_metadata_size(self, max_metadata_size: usize) -> Self {
+        Validator {
+            max_metadata_size,
+            ..self
+        }
+    }
+
+    /// Sets the largest encoded data, in bytes, accepted in units.
+    pub fn with_max_data_size(self, max_data_size: usize) -> Self {
+        Validator {
+            max_data_size,
+            ..self
         }
     }
 
@@ -71,15 +191,39 @@ impl<K: Keychain> Validator<K> {
         self.keychain.node_count()
     }
 
+    pub fn node_weights(&self) -> &NodeWeights {
+        &self.node_weights
+    }
+
     pub fn index(&self) -> NodeIndex {
         self.keychain.index()
     }
 
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+
     pub fn validate_unit<H: Hasher, D: Data>(
         &self,
         uu: UncheckedSignedUnit<H, D, K::Signature>,
-    ) -> Result<H, D, K> {
-        let su = uu.check(&self.keychain)?;
+    ) -> Result<SessionVerifiedUnit<H, D, K>, H, D, K> {
+        let su = self.verify_signature(uu)?;
+        self.verify_session(su)
+    }
+
+    /// The first part of [`Self::validate_unit`], checking only the signature.
+    pub fn verify_signature<H: Hasher, D: Data>(
+        &self,
+        uu: UncheckedSignedUnit<H, D, K::Signature>,
+    ) -> Result<SignatureVerifiedUnit<H, D, K>, H, D, K> {
+        Ok(SignatureVerifiedUnit::verify(uu, &self.keychain)?)
+    }
+
+    /// The rest of [`Self::validate_unit`], for units with a correct signature.
+    pub fn verify_session<H: Hasher, D: Data>(
+        &self,
+        su: SignatureVerifiedUnit<H, D, K>,
+    ) -> Result<SessionVerifiedUnit<H, D, K>, H, D, K> {
         let full_unit = su.as_signable();
         if full_unit.session_id() != self.session_id {
             // NOTE: this implies malicious behavior as the unit's session_id
@@ -89,13 +233,19 @@ impl<K: Keychain> Validator<K> {
         if full_unit.round() > self.max_round {
             return Err(ValidationError::RoundTooHigh(full_unit.clone()));
         }
-        self.validate_unit_parents(su)
+        if full_unit.metadata().len() > self.max_metadata_size {
+            return Err(ValidationError::MetadataTooLarge(full_unit.clone()));
+        }
+        if full_unit.data().encoded_size() > self.max_data_size {
+            return Err(ValidationError::DataTooLarge(full_unit.clone()));
+        }
+        self.validate_unit_parents(su.0)
     }
 
     fn validate_unit_parents<H: Hasher, D: Data>(
         &self,
         su: SignedUnit<H, D, K>,
-    ) -> Result<H, D, K> {
+    ) -> Result<SessionVerifiedUnit<H, D, K>, H, D, K> {
         let pre_unit = su.as_signable().as_pre_unit();
         let n_members = pre_unit.n_members();
         if n_members != self.keychain.node_count() {
@@ -104,9 +254,9 @@ impl<K: Keychain> Validator<K> {
         let unit_coord = UnitCoord::new(pre_unit.round(), pre_unit.creator());
         pre_unit
             .control_hash
-            .validate(unit_coord)
+            .validate(unit_coord, &self.node_weights)
             .map_err(|e| ValidationError::ParentValidationFailed(pre_unit.clone(), e))?;
-        Ok(su)
+        Ok(SessionVerifiedUnit(su))
     }
 }
 
@@ -116,8 +266,8 @@ mod tests {
     use crate::{
         units::{
             full_unit_to_unchecked_signed_unit, preunit_to_unchecked_signed_unit,
-            random_full_parent_units_up_to, random_unit_with_parents, PreUnit,
-            {ControlHash, ControlHashError},
+            random_full_parent_units_up_to, random_unit_with_parents, ControlHash,
+            ControlHashError, PreUnit,
         },
         NodeCount, NodeIndex,
     };
@@ -133,7 +283,7 @@ mod tests {
         let session_id = 0;
         let max_round = 2;
         let keychain = Keychain::new(n_members, creator_id);
-        let validator = Validator::new(session_id, keychain, max_round);
+        let validator = Validator::new(session_id, keychain.clone(), max_round);
         let full_unit = random_full_parent_units_up_to(0, n_members, session_id)[0][0].clone();
         let unchecked_unit = full_unit_to_unchecked_signed_unit(full_unit, &keychain);
         let checked_unit = validator
@@ -149,7 +299,7 @@ mod tests {
         let session_id = 0;
         let max_round = 2;
         let keychain = Keychain::new(n_members, creator_id);
-        let validator = Validator::new(session_id, keychain, max_round);
+        let validator = Validator::new(session_id, keychain.clone(), max_round);
         let preunit = random_full_parent_units_up_to(0, n_members, session_id)[0][0]
             .as_pre_unit()
             .clone();
@@ -181,7 +331,7 @@ mod tests {
         let wrong_session_id = 43;
         let max_round = 2;
         let keychain = Keychain::new(n_members, creator_id);
-        let validator = Validator::new(session_id, keychain, max_round);
+        let validator = Validator::new(session_id, keychain.clone(), max_round);
         let full_unit =
             random_full_parent_units_up_to(0, n_members, wrong_session_id)[0][0].clone();
         let unchecked_unit = full_unit_to_unchecked_signed_unit(full_unit, &keychain);
@@ -201,7 +351,7 @@ mod tests {
         let session_id = 0;
         let max_round = 2;
         let keychain = Keychain::new(n_plus_one_members, creator_id);
-        let validator = Validator::new(session_id, keychain, max_round);
+        let validator = Validator::new(session_id, keychain.clone(), max_round);
         let full_unit = random_full_parent_units_up_to(0, n_members, session_id)[0][0].clone();
         let preunit = full_unit.as_pre_unit().clone();
         let unchecked_unit = full_unit_to_unchecked_signed_unit(full_unit, &keychain);
@@ -247,7 +397,7 @@ mod tests {
         let session_id = 0;
         let max_round = 2;
         let keychain = Keychain::new(n_members, creator_id);
-        let validator = Validator::new(session_id, keychain, max_round);
+        let validator = Validator::new(session_id, keychain.clone(), max_round);
         let full_unit = random_full_parent_units_up_to(3, n_members, session_id)[3][0].clone();
         let unchecked_unit = full_unit_to_unchecked_signed_unit(full_unit, &keychain);
         let full_unit = match validator.validate_unit(unchecked_unit.clone()) {
@@ -257,4 +407,52 @@ mod tests {
         };
         assert_eq!(full_unit, unchecked_unit.into_signable());
     }
+
+    #[test]
+    fn detects_too_large_metadata() {
+        let n_members = NodeCount(7);
+        let creator_id = NodeIndex(0);
+        let session_id = 0;
+        let max_round = 2;
+        let keychain = Keychain::new(n_members, creator_id);
+        let validator =
+            Validator::new(session_id, keychain.clone(), max_round).with_max_metadata_size(4);
+        let full_unit = random_full_parent_units_up_to(0, n_members, session_id)[0][0].clone();
+        let fitting = full_unit_to_unchecked_signed_unit(
+            full_unit.clone().with_metadata(vec![1, 2, 3, 4]),
+            &keychain,
+        );
+        assert!(validator.validate_unit(fitting).is_ok());
+        let unchecked_unit =
+            full_unit_to_unchecked_signed_unit(full_unit.with_metadata(vec![0; 5]), &keychain);
+        let full_unit = match validator.validate_unit(unchecked_unit.clone()) {
+            Ok(_) => panic!("Validated bad unit."),
+            Err(MetadataTooLarge(full_unit)) => full_unit,
+            Err(e) => panic!("Unexpected error from validator: {:?}", e),
+        };
+        assert_eq!(full_unit, unchecked_unit.into_signable());
+    }
+
+    #[test]
+    fn detects_too_large_data() {
+        let n_members = NodeCount(7);
+        let creator_id = NodeIndex(0);
+        let session_id = 0;
+        let max_round = 2;
+        let keychain = Keychain::new(n_members, creator_id);
+        let full_unit = random_full_parent_units_up_to(0, n_members, session_id)[0][0].clone();
+        let data_size = full_unit.data().encoded_size();
+        let unchecked_unit = full_unit_to_unchecked_signed_unit(full_unit, &keychain);
+        let validator =
+            Validator::new(session_id, keychain.clone(), max_round).with_max_data_size(data_size);
+        assert!(validator.validate_unit(unchecked_unit.clone()).is_ok());
+        let validator =
+            Validator::new(session_id, keychain, max_round).with_max_data_size(data_size - 1);
+        let full_unit = match validator.validate_unit(unchecked_unit.clone()) {
+            Ok(_) => panic!("Validated bad unit."),
+            Err(DataTooLarge(full_unit)) => full_unit,
+            Err(e) => panic!("Unexpected error from validator: {:?}", e),
+        };
+        assert_eq!(full_unit, unchecked_unit.into_signable());
+    }
 }
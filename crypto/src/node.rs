@@ -10,6 +10,11 @@ use std::{
 
 /// The index of a node
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, From, Into)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct NodeIndex(pub usize);
 
 impl Encode for NodeIndex {
@@ -34,8 +39,7 @@ pub trait Index {
     fn index(&self) -> NodeIndex;
 }
 
-/// Node count. Right now it doubles as node weight in many places in the code, in the future we
-/// might need a new type for that.
+/// Node count. Every node weighs one unless the committee has [`NodeWeights`].
 #[derive(
     Copy,
     Clone,
@@ -54,6 +58,11 @@ pub trait Index {
     SubAssign,
     Sum,
 )]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct NodeCount(pub usize);
 
 // deriving Mul and Div is somehow cumbersome
@@ -84,13 +93,25 @@ impl NodeCount {
     }
 
     /// If this is the total node count, what number of nodes is required for secure consensus.
+    ///
+    /// This is more than two thirds of the nodes, so in committees of at most three nodes it is
+    /// all of them: 1 of 1, 2 of 2 and 3 of 3. Four nodes are the smallest committee tolerating
+    /// a faulty node, requiring 3 of 4.
     pub fn consensus_threshold(&self) -> NodeCount {
         (*self * 2) / 3 + NodeCount(1)
     }
+
+    /// If this is the total node count, how many nodes can be faulty without breaking the
+    /// guarantees of the protocol. None in committees of at most three nodes, where a single
+    /// silent node stalls everyone.
+    pub fn tolerated_faults(&self) -> NodeCount {
+        NodeCount(self.0.saturating_sub(self.consensus_threshold().0))
+    }
 }
 
-/// A container keeping items indexed by NodeIndex.
-#[derive(Clone, Eq, PartialEq, Hash, Debug, Default, Decode, Encode, From)]
+/// A container keeping items indexed by NodeIndex. With the `serde` feature it is represented
+/// by its size and a map from the indices of the items to the items.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Decode, Encode, From)]
 pub struct NodeMap<T>(Vec<Option<T>>);
 
 impl<T> NodeMap<T> {
@@ -214,6 +235,84 @@ impl<T: fmt::Display> fmt::Display for NodeMap<T> {
     }
 }
 
+/// The voting weight of a node, for instance its stake.
+pub type Weight = u64;
+
+/// The voting weights of the nodes of a committee. Quorums are sets of nodes weighing more than
+/// two thirds of the total weight, rather than sets of more than two thirds of the nodes, so with
+/// [`NodeWeights::uniform`] weights they are the usual quorums of [`NodeCount::consensus_threshold`]
+/// nodes.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Decode, Encode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct NodeWeights(NodeMap<Weight>);
+
+impl NodeWeights {
+    /// The given weights of the nodes, in the order of their indices.
+    pub fn new(weights: Vec<Weight>) -> Self {
+        NodeWeights(weights.into_iter().map(Some).collect::<Vec<_>>().into())
+    }
+
+    /// The weights of a committee where every node weighs one.
+    pub fn uniform(node_count: NodeCount) -> Self {
+        NodeWeights::new(vec![1; node_count.0])
+    }
+
+    pub fn node_count(&self) -> NodeCount {
+        self.0.size()
+    }
+
+    /// The weight of the given node, zero for nodes outside the committee.
+    pub fn weight(&self, node_id: NodeIndex) -> Weight {
+        match node_id.0 < self.0.size().0 {
+            true => self.0.get(node_id).copied().unwrap_or(0),
+            false => 0,
+        }
+    }
+
+    /// The weight of the given nodes together, each counted once however often it appears.
+    pub fn weight_of(&self, nodes: impl IntoIterator<Item = NodeIndex>) -> Weight {
+        let mut counted = NodeSubset::with_size(self.node_count());
+        let mut weight: Weight = 0;
+        for node_id in nodes {
+            if node_id.0 < counted.size() && !counted[node_id] {
+                counted.insert(node_id);
+                weight = weight.saturating_add(self.weight(node_id));
+            }
+        }
+        weight
+    }
+
+    pub fn total(&self) -> Weight {
+        self.weight_of(self.node_count().into_iterator())
+    }
+
+    /// How much nodes have to weigh together for secure consensus, more than two thirds of the
+    /// total weight.
+    pub fn consensus_threshold(&self) -> Weight {
+        (self.total() as u128 * 2 / 3 + 1) as Weight
+    }
+
+    /// Whether the given nodes together weigh at least the consensus threshold.
+    pub fn is_quorum(&self, nodes: impl IntoIterator<Item = NodeIndex>) -> bool {
+        self.weight_of(nodes) >= self.consensus_threshold()
+    }
+
+    /// Whether every node weighs the same, so quorums are just large enough sets of nodes.
+    pub fn is_uniform(&self) -> bool {
+        let mut weights = self.0.values();
+        match weights.next() {
+            Some(first) => weights.all(|weight| weight == first),
+            None => true,
+        }
+    }
+}
+
+/// A subset of the nodes of a committee. With the `serde` feature it is represented by the size
+/// of the committee and the sorted list of the indices in the subset.
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
 pub struct NodeSubset(bit_vec::BitVec<u32>);
 
@@ -256,14 +355,15 @@ impl Encode for NodeSubset {
 impl Decode for NodeSubset {
     fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
         let capacity = u32::decode(input)? as usize;
-        let bytes = Vec::decode(input)?;
-        let mut bv = bit_vec::BitVec::from_bytes(&bytes);
-        // Length should be capacity rounded up to the closest multiple of 8
-        if bv.len() != 8 * ((capacity + 7) / 8) {
+        let bytes: Vec<u8> = Vec::decode(input)?;
+        // Length should be capacity rounded up to the closest multiple of 8, checked in bytes so
+        // that no capacity overflows.
+        if bytes.len() != capacity.div_ceil(8) {
             return Err(Error::from(
                 "Length of bitvector inconsistent with encoded capacity.",
             ));
         }
+        let mut bv = bit_vec::BitVec::from_bytes(&bytes);
         while bv.len() > capacity {
             if bv.pop() != Some(false) {
                 return Err(Error::from(
@@ -292,11 +392,235 @@ impl fmt::Display for NodeSubset {
     }
 }
 
+#[cfg(feature = "serde")]
+mod serialization {
+    use crate::node::{NodeCount, NodeIndex, NodeMap, NodeSubset};
+    use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename = "NodeMap", deny_unknown_fields)]
+    struct NodeMapRepr<T> {
+        size: usize,
+        items: BTreeMap<usize, T>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename = "NodeSubset", deny_unknown_fields)]
+    struct NodeSubsetRepr {
+        size: usize,
+        elements: Vec<usize>,
+    }
+
+    impl<T: Serialize> Serialize for NodeMap<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            NodeMapRepr {
+                size: self.0.len(),
+                items: self
+                    .iter()
+                    .map(|(node_id, item)| (node_id.0, item))
+                    .collect(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for NodeMap<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let NodeMapRepr { size, items } = NodeMapRepr::<T>::deserialize(deserializer)?;
+            if let Some((index, _)) = items.last_key_value().filter(|(index, _)| **index >= size) {
+                return Err(D::Error::custom(format!(
+                    "node {} outside of a map of size {}",
+                    index, size
+                )));
+            }
+            let mut values: Vec<Option<T>> = (0..size).map(|_| None).collect();
+            for (index, item) in items {
+                values[index] = Some(item);
+            }
+            Ok(NodeMap(values))
+        }
+    }
+
+    impl Serialize for NodeSubset {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            NodeSubsetRepr {
+                size: self.size(),
+                elements: self.elements().map(|node_id| node_id.0).collect(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for NodeSubset {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let NodeSubsetRepr { size, elements } = NodeSubsetRepr::deserialize(deserializer)?;
+            // Like the SCALE encoding, every subset has a single representation.
+            if elements.windows(2).any(|pair| pair[0] >= pair[1]) {
+                return Err(D::Error::custom(
+                    "the elements of a node subset have to be sorted and distinct",
+                ));
+            }
+            if let Some(index) = elements.last().filter(|index| **index >= size) {
+                return Err(D::Error::custom(format!(
+                    "node {} outside of a subset of size {}",
+                    index, size
+                )));
+            }
+            let mut subset = NodeSubset::with_size(NodeCount(size));
+            for index in elements {
+                subset.insert(NodeIndex(index));
+            }
+            Ok(subset)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::node::{NodeCount, NodeIndex, NodeMap, NodeSubset, NodeWeights};
+        use codec::Encode;
+        use serde::{de::DeserializeOwned, Serialize};
+        use std::fmt::Debug;
+
+        /// Checks that the value goes through JSON unchanged, with an unchanged SCALE encoding,
+        /// and returns its JSON.
+        fn round_trip<T: Serialize + DeserializeOwned + Encode + Debug + Eq>(value: &T) -> String {
+            let json = serde_json::to_string(value).expect("serializing should work");
+            let decoded: T = serde_json::from_str(&json).expect("deserializing should work");
+            assert_eq!(&decoded, value);
+            assert_eq!(decoded.encode(), value.encode());
+            json
+        }
+
+        fn subset(size: usize, elements: &[usize]) -> NodeSubset {
+            let mut subset = NodeSubset::with_size(NodeCount(size));
+            for index in elements {
+                subset.insert(NodeIndex(*index));
+            }
+            subset
+        }
+
+        #[test]
+        fn indices_and_counts_are_plain_numbers() {
+            assert_eq!(round_trip(&NodeIndex(3)), "3");
+            // Counts have no SCALE encoding.
+            let count = serde_json::to_string(&NodeCount(7)).expect("serializing should work");
+            assert_eq!(count, "7");
+            assert_eq!(
+                serde_json::from_str::<NodeCount>(&count).ok(),
+                Some(NodeCount(7))
+            );
+            assert_eq!(round_trip(&NodeIndex(usize::MAX)), usize::MAX.to_string());
+            assert_eq!(NodeIndex(3).encode(), 3u64.to_le_bytes());
+        }
+
+        #[test]
+        fn node_map_is_a_map_from_indices() {
+            let mut map = NodeMap::with_size(NodeCount(5));
+            map.insert(NodeIndex(1), "one".to_string());
+            map.insert(NodeIndex(4), "four".to_string());
+            assert_eq!(
+                round_trip(&map),
+                r#"{"size":5,"items":{"1":"one","4":"four"}}"#
+            );
+        }
+
+        #[test]
+        fn empty_node_maps_keep_their_size() {
+            assert_eq!(
+                round_trip(&NodeMap::<u64>::with_size(NodeCount(0))),
+                r#"{"size":0,"items":{}}"#
+            );
+            assert_eq!(
+                round_trip(&NodeMap::<u64>::with_size(NodeCount(3))),
+                r#"{"size":3,"items":{}}"#
+            );
+        }
+
+        #[test]
+        fn node_subset_is_a_sorted_list_of_indices() {
+            assert_eq!(
+                round_trip(&subset(6, &[5, 0, 2])),
+                r#"{"size":6,"elements":[0,2,5]}"#
+            );
+            assert_eq!(round_trip(&subset(0, &[])), r#"{"size":0,"elements":[]}"#);
+            assert_eq!(round_trip(&subset(4, &[])), r#"{"size":4,"elements":[]}"#);
+            assert_eq!(
+                round_trip(&subset(100, &[99])),
+                r#"{"size":100,"elements":[99]}"#
+            );
+        }
+
+        #[test]
+        fn node_weights_are_a_node_map() {
+            assert_eq!(
+                round_trip(&NodeWeights::new(vec![4, 1])),
+                r#"{"size":2,"items":{"0":4,"1":1}}"#
+            );
+        }
+
+        #[test]
+        fn refuses_indices_outside_of_the_committee() {
+            assert!(serde_json::from_str::<NodeMap<u64>>(r#"{"size":2,"items":{"2":1}}"#).is_err());
+            assert!(serde_json::from_str::<NodeSubset>(r#"{"size":2,"elements":[2]}"#).is_err());
+        }
+
+        #[test]
+        fn refuses_non_canonical_subsets() {
+            assert!(serde_json::from_str::<NodeSubset>(r#"{"size":4,"elements":[2,1]}"#).is_err());
+            assert!(serde_json::from_str::<NodeSubset>(r#"{"size":4,"elements":[1,1]}"#).is_err());
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
-    use crate::node::{NodeIndex, NodeSubset};
+    use crate::node::{NodeCount, NodeIndex, NodeSubset, NodeWeights};
     use codec::{Decode, Encode};
+
+    #[test]
+    fn thresholds_of_small_committees() {
+        for (n_members, threshold, faults) in [
+            (1, 1, 0),
+            (2, 2, 0),
+            (3, 3, 0),
+            (4, 3, 1),
+            (6, 5, 1),
+            (7, 5, 2),
+        ] {
+            let n_members = NodeCount(n_members);
+            assert_eq!(n_members.consensus_threshold(), NodeCount(threshold));
+            assert_eq!(n_members.tolerated_faults(), NodeCount(faults));
+        }
+    }
+
+    #[test]
+    fn uniform_weights_agree_with_node_counts() {
+        for n_members in 1..20 {
+            let n_members = NodeCount(n_members);
+            let weights = NodeWeights::uniform(n_members);
+            assert!(weights.is_uniform());
+            assert_eq!(weights.total(), n_members.0 as u64);
+            assert_eq!(
+                weights.consensus_threshold(),
+                n_members.consensus_threshold().0 as u64
+            );
+        }
+    }
+
+    #[test]
+    fn quorums_are_weighted() {
+        let weights = NodeWeights::new(vec![4, 1, 1, 1]);
+        assert!(!weights.is_uniform());
+        assert_eq!(weights.total(), 7);
+        assert_eq!(weights.consensus_threshold(), 5);
+        assert!(weights.is_quorum([NodeIndex(0), NodeIndex(1)]));
+        assert!(!weights.is_quorum([NodeIndex(1), NodeIndex(2), NodeIndex(3)]));
+        assert!(!weights.is_quorum([NodeIndex(0), NodeIndex(0), NodeIndex(4)]));
+        assert_eq!(weights.weight(NodeIndex(4)), 0);
+    }
+
     #[test]
     fn decoding_node_index_works() {
         for i in 0..1000 {
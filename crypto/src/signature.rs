@@ -1,7 +1,10 @@
 use crate::{Index, NodeCount, NodeIndex, NodeMap};
 use codec::{Codec, Decode, Encode};
 use log::warn;
-use std::{fmt::Debug, hash::Hash};
+use std::{
+    fmt::{Debug, Display},
+    hash::Hash,
+};
 
 /// The type used as a signature.
 ///
@@ -17,16 +20,27 @@ impl<T: Debug + Clone + Codec + Send + Sync + Eq + 'static> Signature for T {}
 /// The meaning of sign is then to produce a signature `s` using the given private key,
 /// and `verify(msg, s, j)` is to verify whether the signature s under the message msg is
 /// correct with respect to the public key of the jth node.
+///
+/// Signing might fail, e.g. when the private key resides on an unavailable device. Keychains
+/// that always manage to sign should use [`std::convert::Infallible`] as [`Self::SignError`].
 pub trait Keychain: Index + Clone + Send + Sync + 'static {
     type Signature: Signature;
+    /// The reason for a failure to sign.
+    type SignError: Debug + Display + Send + Sync + 'static;
 
     /// Returns the total number of known public keys.
     fn node_count(&self) -> NodeCount;
     /// Signs a message `msg`.
-    fn sign(&self, msg: &[u8]) -> Self::Signature;
+    fn sign(&self, msg: &[u8]) -> Result<Self::Signature, Self::SignError>;
     /// Verifies whether a node with `index` correctly signed the message `msg`.
     /// Should always return false for indices outside the node range.
     fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool;
+    /// Whether [`Self::verify`] is cheap enough to call it right where a signature needs
+    /// checking. Otherwise the signatures of incoming units are checked by separate tasks,
+    /// so that a burst of units does not hold up everything else.
+    fn is_verification_cheap(&self) -> bool {
+        true
+    }
 }
 
 /// A type to which signatures can be aggregated.
@@ -55,7 +69,12 @@ pub trait MultiKeychain: Keychain {
         signature: &Self::Signature,
         index: NodeIndex,
     ) -> Self::PartialMultisignature;
-    /// Checks if enough signatures have beed added.
+    /// Checks if enough signatures have beed added, that is signatures of nodes weighing together
+    /// at least the [`NodeWeights::consensus_threshold`] of the committee. Committees without
+    /// weights are committees of nodes weighing one, so these are signatures of at least
+    /// [`NodeCount::consensus_threshold`] nodes.
+    ///
+    /// [`NodeWeights::consensus_threshold`]: crate::NodeWeights::consensus_threshold
     fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool;
 }
 
@@ -112,6 +131,16 @@ impl<T: Signable, S: Signature> UncheckedSigned<T, S> {
     pub fn signature(&self) -> S {
         self.signature.clone()
     }
+
+    /// Replaces the signed object, keeping the signature. The signature only stays correct if
+    /// the new object has the same hash, e.g. when it is a different representation of the
+    /// same content, which has to be checked again.
+    pub fn map_signable<U: Signable>(self, f: impl FnOnce(T) -> U) -> UncheckedSigned<U, S> {
+        UncheckedSigned {
+            signable: f(self.signable),
+            signature: self.signature,
+        }
+    }
 }
 
 impl<T: Signable, S: Signature> UncheckedSigned<Indexed<T>, S> {
@@ -190,15 +219,15 @@ impl<T: Signable + Clone + Index, K: Keychain> Clone for Signed<T, K> {
 
 impl<T: Signable + Index, K: Keychain> Signed<T, K> {
     /// Create a signed object from a signable. The index of `signable` must match the index of the `keychain`.
-    pub fn sign(signable: T, keychain: &K) -> Signed<T, K> {
+    pub fn sign(signable: T, keychain: &K) -> Result<Signed<T, K>, K::SignError> {
         assert_eq!(signable.index(), keychain.index());
-        let signature = keychain.sign(signable.hash().as_ref());
-        Signed {
+        let signature = keychain.sign(signable.hash().as_ref())?;
+        Ok(Signed {
             unchecked: UncheckedSigned {
                 signable,
                 signature,
             },
-        }
+        })
     }
 
     /// Get a reference to the signed object.
@@ -217,7 +246,10 @@ impl<T: Signable + Index, K: Keychain> Signed<T, K> {
 
 impl<T: Signable, K: Keychain> Signed<Indexed<T>, K> {
     /// Create a signed object from a signable. The index is added based on the index of the `keychain`.
-    pub fn sign_with_index(signable: T, keychain: &K) -> Signed<Indexed<T>, K> {
+    pub fn sign_with_index(
+        signable: T,
+        keychain: &K,
+    ) -> Result<Signed<Indexed<T>, K>, K::SignError> {
         Signed::sign(Indexed::new(signable, keychain.index()), keychain)
     }
 }
@@ -369,8 +401,8 @@ pub enum PartiallyMultisigned<T: Signable, MK: MultiKeychain> {
 
 impl<T: Signable, MK: MultiKeychain> PartiallyMultisigned<T, MK> {
     /// Create a partially multisigned object.
-    pub fn sign(signable: T, keychain: &MK) -> PartiallyMultisigned<T, MK> {
-        Signed::sign_with_index(signable, keychain).into_partially_multisigned(keychain)
+    pub fn sign(signable: T, keychain: &MK) -> Result<PartiallyMultisigned<T, MK>, MK::SignError> {
+        Ok(Signed::sign_with_index(signable, keychain)?.into_partially_multisigned(keychain))
     }
 
     /// Chceck if the partial multisignature is complete.
@@ -426,11 +458,11 @@ impl<T: Signable, MK: MultiKeychain> PartiallyMultisigned<T, MK> {
 mod tests {
 
     use crate::{
-        Index, Keychain, MultiKeychain, NodeCount, NodeIndex, PartialMultisignature,
+        Index, Indexed, Keychain, MultiKeychain, NodeCount, NodeIndex, PartialMultisignature,
         PartiallyMultisigned, Signable, SignatureSet, Signed,
     };
     use codec::{Decode, Encode};
-    use std::fmt::Debug;
+    use std::{convert::Infallible, fmt::Debug};
 
     /// Keychain wrapper which implements MultiKeychain such that a partial multisignature is a list of
     /// signatures and a partial multisignature is considered complete if it contains more than 2N/3 signatures.
@@ -456,18 +488,23 @@ mod tests {
 
     impl<K: Keychain> Keychain for DefaultMultiKeychain<K> {
         type Signature = K::Signature;
+        type SignError = K::SignError;
 
         fn node_count(&self) -> NodeCount {
             self.keychain.node_count()
         }
 
-        fn sign(&self, msg: &[u8]) -> Self::Signature {
+        fn sign(&self, msg: &[u8]) -> Result<Self::Signature, Self::SignError> {
             self.keychain.sign(msg)
         }
 
         fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
             self.keychain.verify(msg, sgn, index)
         }
+
+        fn is_verification_cheap(&self) -> bool {
+            self.keychain.is_verification_cheap()
+        }
     }
 
     impl<K: Keychain> MultiKeychain for DefaultMultiKeychain<K> {
@@ -540,16 +577,17 @@ mod tests {
 
     impl Keychain for TestKeychain {
         type Signature = TestSignature;
+        type SignError = Infallible;
 
         fn node_count(&self) -> NodeCount {
             self.count
         }
 
-        fn sign(&self, msg: &[u8]) -> Self::Signature {
-            TestSignature {
+        fn sign(&self, msg: &[u8]) -> Result<Self::Signature, Self::SignError> {
+            Ok(TestSignature {
                 msg: msg.to_vec(),
                 index: self.index,
-            }
+            })
         }
 
         fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
@@ -573,7 +611,8 @@ mod tests {
         for i in 0..node_count.0 {
             for j in 0..node_count.0 {
                 let msg = test_message();
-                let signed_msg = Signed::sign_with_index(msg.clone(), &keychains[i]);
+                let signed_msg = Signed::sign_with_index(msg.clone(), &keychains[i])
+                    .expect("the keychain never fails");
                 let unchecked_msg = signed_msg.into_unchecked();
                 assert!(
                     unchecked_msg.check(&keychains[j]).is_ok(),
@@ -589,7 +628,7 @@ mod tests {
         let index: NodeIndex = 0.into();
         let keychain = test_multi_keychain(node_count, index);
         let msg = test_message();
-        let signed_msg = Signed::sign_with_index(msg, &keychain);
+        let signed_msg = Signed::sign_with_index(msg, &keychain).expect("the keychain never fails");
         let mut unchecked_msg = signed_msg.into_unchecked();
         unchecked_msg.signature.index = 1.into();
 
@@ -599,6 +638,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_mapped_signatures() {
+        let node_count: NodeCount = 1.into();
+        let index: NodeIndex = 0.into();
+        let keychain = test_multi_keychain(node_count, index);
+        let signed_msg =
+            Signed::sign_with_index(test_message(), &keychain).expect("the keychain never fails");
+        let unchecked_msg = signed_msg.into_unchecked();
+
+        let same_hash = unchecked_msg
+            .clone()
+            .map_signable(|indexed| Indexed::new(indexed.strip_index(), index));
+        assert!(
+            same_hash.check(&keychain).is_ok(),
+            "the signature stays valid for an object of the same hash"
+        );
+        let other_hash = unchecked_msg.map_signable(|_| {
+            let msg = TestMessage {
+                msg: "Bye".as_bytes().to_vec(),
+            };
+            Indexed::new(msg, index)
+        });
+        assert!(
+            other_hash.check(&keychain).is_err(),
+            "the signature does not match an object of another hash"
+        );
+    }
+
     #[test]
     fn test_incomplete_multisignature() {
         let msg = test_message();
@@ -606,7 +673,7 @@ mod tests {
         let node_count: NodeCount = 2.into();
         let keychain = test_multi_keychain(node_count, index);
 
-        let partial = PartiallyMultisigned::sign(msg, &keychain);
+        let partial = PartiallyMultisigned::sign(msg, &keychain).expect("the keychain never fails");
         assert!(
             !partial.is_complete(),
             "One signature does not form a complete multisignature",
@@ -621,10 +688,12 @@ mod tests {
             .map(|i| test_multi_keychain(node_count, i.into()))
             .collect();
 
-        let mut partial = PartiallyMultisigned::sign(msg.clone(), &keychains[0]);
+        let mut partial = PartiallyMultisigned::sign(msg.clone(), &keychains[0])
+            .expect("the keychain never fails");
         for keychain in keychains.iter().skip(1).take(4) {
             assert!(!partial.is_complete());
-            let signed = Signed::sign_with_index(msg.clone(), keychain);
+            let signed =
+                Signed::sign_with_index(msg.clone(), keychain).expect("the keychain never fails");
             partial = partial.add_signature(signed, keychain);
         }
         assert!(
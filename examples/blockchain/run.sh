@@ -8,10 +8,10 @@ clear
 
 n_members="$1"
 
-cargo run --release -- --my-id 0 --n-members $n_members --n-finalized 50 --ip-addr 127.0.0.1:43000 --bootnodes-id 0 --bootnodes-ip-addr 127.0.0.1:43000 2> node0.log &
+addresses=$(seq -s , -f "127.0.0.1:%g" 43000 $(expr 43000 + $n_members - 1))
 
-for i in $(seq 1 $(expr $n_members - 1)); do
-    cargo run --release -- --my-id $i --n-members $n_members --n-finalized 50 --bootnodes-id 0 --bootnodes-ip-addr 127.0.0.1:43000 2> node$i.log &
+for i in $(seq 0 $(expr $n_members - 1)); do
+    cargo run --release -- --my-id $i --n-finalized 50 --addresses $addresses 2> node$i.log &
 done
 
 echo "Running blockchain example... (Ctrl+C to exit)"
@@ -1,7 +1,7 @@
 use std::{
     collections::HashMap,
     io::Write,
-    str::FromStr,
+    net::SocketAddr,
     time::{Duration, Instant},
 };
 
@@ -14,7 +14,7 @@ use aleph_bft::{run_session, NodeIndex, Terminator};
 use aleph_bft_mock::{FinalizationHandler, Keychain, Loader, Saver, Spawner};
 use chain::{run_blockchain, Block, BlockNum, ChainConfig};
 use data::{Data, DataProvider, DataStore};
-use network::{Address, NetworkData, NetworkManager};
+use network::{NetworkData, NetworkManager};
 
 mod chain;
 mod data;
@@ -33,21 +33,9 @@ struct Args {
     #[clap(long, value_parser)]
     my_id: usize,
 
-    /// IP address of the node
-    #[clap(default_value = "127.0.0.1:0", long, value_parser)]
-    ip_addr: String,
-
-    /// Bootnodes indices
-    #[clap(long, value_parser, value_delimiter = ',')]
-    bootnodes_id: Vec<usize>,
-
-    /// Bootnodes addresses
+    /// Addresses of all the nodes, in the order of their indices
     #[clap(long, value_parser, value_delimiter = ',')]
-    bootnodes_ip_addr: Vec<String>,
-
-    /// Size of the committee
-    #[clap(long, value_parser)]
-    n_members: usize,
+    addresses: Vec<SocketAddr>,
 
     /// Number of data to be finalized
     #[clap(long, value_parser)]
@@ -78,11 +66,12 @@ async fn main() {
     let args = Args::parse();
     let start_time = Instant::now();
     info!(target: "Blockchain-main", "Getting network up.");
-    let bootnodes: HashMap<NodeIndex, Address> = args
-        .bootnodes_id
+    let n_members = args.addresses.len();
+    let addresses: HashMap<NodeIndex, SocketAddr> = args
+        .addresses
         .into_iter()
-        .zip(args.bootnodes_ip_addr)
-        .map(|(id, addr)| (id.into(), Address::from_str(&addr).unwrap()))
+        .enumerate()
+        .map(|(id, addr)| (id.into(), addr))
         .collect();
     let (
         mut manager,
@@ -91,7 +80,7 @@ async fn main() {
         block_from_network_rx,
         message_for_network,
         message_from_network,
-    ) = NetworkManager::new(args.my_id.into(), args.ip_addr, args.n_members, bootnodes)
+    ) = NetworkManager::new(args.my_id.into(), addresses)
         .await
         .expect("Network set-up should succeed.");
     let (data_provider, current_block) = DataProvider::new();
@@ -106,7 +95,7 @@ async fn main() {
     let data_size: usize = TXS_PER_BLOCK * TX_SIZE;
     let chain_config = ChainConfig::new(
         args.my_id.into(),
-        args.n_members,
+        n_members,
         data_size,
         BLOCK_TIME,
         INITIAL_DELAY,
@@ -127,15 +116,10 @@ async fn main() {
 
     let member_terminator = terminator.add_offspring_connection("AlephBFT-member");
     let member_handle = tokio::spawn(async move {
-        let keychain = Keychain::new(args.n_members.into(), args.my_id.into());
-        let config = aleph_bft::default_config(
-            args.n_members.into(),
-            args.my_id.into(),
-            0,
-            5000,
-            Duration::ZERO,
-        )
-        .expect("Should always succeed with Duration::ZERO");
+        let keychain = Keychain::new(n_members.into(), args.my_id.into());
+        let config =
+            aleph_bft::default_config(n_members.into(), args.my_id.into(), 0, 5000, Duration::ZERO)
+                .expect("Should always succeed with Duration::ZERO");
         let backup_loader = Loader::new(vec![]);
         let backup_saver = Saver::new();
         let local_io = aleph_bft::LocalIO::new(
@@ -182,7 +166,9 @@ async fn main() {
     terminator.get_exit().await.expect("should receive");
     terminator.terminate_sync().await;
 
-    member_handle.await.unwrap();
+    if let Err(e) = member_handle.await.unwrap() {
+        error!(target: "Blockchain-main", "The session ended with an error: {}.", e);
+    }
     chain_handle.await.unwrap();
     network_handle.await.unwrap();
 }
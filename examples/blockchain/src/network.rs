@@ -1,75 +1,31 @@
 use crate::{Block, Data};
-use aleph_bft::{NodeIndex, Recipient, Terminator};
+use aleph_bft::{Network as _, NodeIndex, Recipient, Terminator};
 use aleph_bft_mock::{Hasher64, PartialMultisignature, Signature};
+use aleph_bft_net_tcp::{ConnectionStatus, TcpNetwork, TcpNetworkConfig};
 use codec::{Decode, Encode};
 use futures::{
     channel::mpsc::{self, UnboundedReceiver, UnboundedSender},
     FutureExt, StreamExt,
 };
-use log::{debug, error, warn};
+use log::{debug, info, warn};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     error::Error,
     fmt::{Debug, Formatter},
-    io::Write,
-    net::{SocketAddr, SocketAddrV4, TcpStream},
-    str::FromStr,
+    net::SocketAddr,
+    time::Duration,
 };
-use tokio::{io::AsyncReadExt, net::TcpListener};
 
 pub type NetworkData = aleph_bft::NetworkData<Hasher64, Data, Signature, PartialMultisignature>;
 
-#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
-pub struct Address {
-    octets: [u8; 4],
-    port: u16,
-}
-
-impl From<SocketAddrV4> for Address {
-    fn from(addr: SocketAddrV4) -> Self {
-        Self {
-            octets: addr.ip().octets(),
-            port: addr.port(),
-        }
-    }
-}
+/// Blocks are large, so the frames have to fit them.
+const MAX_FRAME_SIZE: usize = 64 * 1024 * 1024;
 
-impl From<SocketAddr> for Address {
-    fn from(addr: SocketAddr) -> Self {
-        match addr {
-            SocketAddr::V4(addr) => addr.into(),
-            SocketAddr::V6(_) => panic!(),
-        }
-    }
-}
-
-impl FromStr for Address {
-    type Err = ();
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(s.parse::<SocketAddr>().unwrap().into())
-    }
-}
-
-impl Address {
-    pub async fn new_bind(ip_addr: String) -> (TcpListener, Self) {
-        let listener = TcpListener::bind(ip_addr.parse::<SocketAddr>().unwrap())
-            .await
-            .unwrap();
-        let ip_addr = listener.local_addr().unwrap().to_string();
-        (listener, Self::from_str(&ip_addr).unwrap())
-    }
-
-    pub fn connect(&self) -> std::io::Result<TcpStream> {
-        TcpStream::connect(SocketAddr::from((self.octets, self.port)))
-    }
-}
+const STATUS_REPORT_INTERVAL: Duration = Duration::from_secs(5);
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Decode, Encode, Debug)]
 enum Message {
-    DNSHello(NodeIndex, Address),
-    DNSRequest(NodeIndex, Address),
-    DNSResponse(Vec<(NodeIndex, Address)>),
     Consensus(NetworkData),
     Block(Block),
 }
@@ -91,13 +47,11 @@ impl aleph_bft::Network<NetworkData> for Network {
     }
 }
 
+/// Passes the messages of consensus and the blocks over a single TCP network.
 pub struct NetworkManager {
     id: NodeIndex,
-    address: Address,
-    addresses: HashMap<NodeIndex, Address>,
-    bootnodes: HashSet<NodeIndex>,
-    n_nodes: usize,
-    listener: TcpListener,
+    network: TcpNetwork<Message>,
+    status: ConnectionStatus,
     consensus_tx: UnboundedSender<NetworkData>,
     consensus_rx: UnboundedReceiver<(NetworkData, Recipient)>,
     block_tx: UnboundedSender<Block>,
@@ -108,10 +62,7 @@ impl Debug for NetworkManager {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("NetworkManager")
             .field("id", &self.id)
-            .field("address", &self.address)
-            .field("address count", &self.addresses.len())
-            .field("bootnode count", &self.bootnodes.len())
-            .field("node count", &self.n_nodes)
+            .field("connected peers", &self.status.connected_peers())
             .finish()
     }
 }
@@ -119,9 +70,7 @@ impl Debug for NetworkManager {
 impl NetworkManager {
     pub async fn new(
         id: NodeIndex,
-        ip_addr: String,
-        n_nodes: usize,
-        bootnodes: HashMap<NodeIndex, Address>,
+        addresses: HashMap<NodeIndex, SocketAddr>,
     ) -> Result<
         (
             Self,
@@ -133,9 +82,9 @@ impl NetworkManager {
         ),
         Box<dyn Error>,
     > {
-        let mut addresses = bootnodes.clone();
-        let (listener, address) = Address::new_bind(ip_addr).await;
-        addresses.insert(id, address.clone());
+        let config = TcpNetworkConfig::default().with_max_frame_size(MAX_FRAME_SIZE);
+        let network = TcpNetwork::bind(id, addresses, config).await?;
+        let status = network.status();
 
         let (msg_to_manager_tx, msg_to_manager_rx) = mpsc::unbounded();
         let (msg_for_store, msg_from_manager) = mpsc::unbounded();
@@ -143,18 +92,15 @@ impl NetworkManager {
         let (block_to_data_io_tx, block_to_data_io_rx) = mpsc::unbounded();
         let (block_from_data_io_tx, block_from_data_io_rx) = mpsc::unbounded();
 
-        let network = Network {
+        let consensus_network = Network {
             msg_to_manager_tx,
             msg_from_manager_rx: msg_from_store,
         };
 
         let network_manager = NetworkManager {
             id,
-            address,
-            addresses,
-            bootnodes: bootnodes.into_keys().collect(),
-            n_nodes,
-            listener,
+            network,
+            status,
             consensus_tx: msg_for_store,
             consensus_rx: msg_to_manager_rx,
             block_tx: block_to_data_io_tx,
@@ -163,7 +109,7 @@ impl NetworkManager {
 
         Ok((
             network_manager,
-            network,
+            consensus_network,
             block_from_data_io_tx,
             block_to_data_io_rx,
             msg_for_network,
@@ -171,109 +117,31 @@ impl NetworkManager {
         ))
     }
 
-    fn reset_dns(&mut self, n: &NodeIndex) {
-        if !self.bootnodes.contains(n) {
-            error!("Reseting address of node {}", n.0);
-            self.addresses.remove(n);
-        }
-    }
-
-    fn send(&mut self, message: Message, recipient: Recipient) {
-        let mut to_reset = vec![];
-        match recipient {
-            Recipient::Node(n) => {
-                if let Some(addr) = self.addresses.get(&n) {
-                    if let Err(e) = self.try_send(&message, addr) {
-                        error!("Failed to send message {:?} to {:?}: {}", message, addr, e);
-                        to_reset.push(n)
-                    }
-                }
-            }
-            Recipient::Everyone => {
-                let my_id = self.id;
-                for (n, addr) in self.addresses.iter().filter(|(n, _)| n != &&my_id) {
-                    if let Err(e) = self.try_send(&message, addr) {
-                        error!("Failed to send message {:?} to {:?}: {}", message, addr, e);
-                        to_reset.push(*n)
-                    }
-                }
-            }
-        }
-        for n in to_reset {
-            self.reset_dns(&n);
-        }
-    }
-
-    fn try_send(&self, message: &Message, address: &Address) -> std::io::Result<()> {
-        debug!("Trying to send message {:?} to {:?}", message, address);
-        address.connect()?.write_all(&message.encode())
-    }
-
-    fn dns_response(&mut self, id: NodeIndex, address: Address) {
-        self.addresses.insert(id, address.clone());
-        self.try_send(
-            &Message::DNSResponse(self.addresses.clone().into_iter().collect()),
-            &address,
-        )
-        .unwrap_or(());
-    }
-
     pub async fn run(&mut self, mut terminator: Terminator) {
-        let mut dns_interval = tokio::time::interval(std::time::Duration::from_millis(1000));
-        let mut dns_hello_interval = tokio::time::interval(std::time::Duration::from_millis(5000));
+        let mut status_interval = tokio::time::interval(STATUS_REPORT_INTERVAL);
         loop {
-            let mut buffer = Vec::new();
             tokio::select! {
-
-                event = self.listener.accept() => match event {
-                    Ok((mut socket, _addr)) => {
-                        match socket.read_to_end(&mut buffer).await {
-                            Ok(_) => {
-                                let message = Message::decode(&mut &buffer[..]);
-                                debug!("Received message: {:?}", message);
-                                match message {
-                                    Ok(Message::Consensus(data)) => self.consensus_tx.unbounded_send(data).expect("Network must listen"),
-                                    Ok(Message::Block(block)) => {
-                                        debug!(target: "Blockchain-network", "Received block num {:?}", block.num);
-                                        self.block_tx
-                                            .unbounded_send(block)
-                                            .expect("Blockchain process must listen");
-                                    },
-                                    Ok(Message::DNSHello(id, address)) => { self.addresses.insert(id, address); },
-                                    Ok(Message::DNSRequest(id, address)) => self.dns_response(id, address),
-                                    Ok(Message::DNSResponse(addresses)) => self.addresses.extend(addresses.into_iter()),
-                                    Err(_) => (),
-                                };
-                            },
-                            Err(_) => {
-                                error!("Could not decode incoming data");
-                            },
-                        }
-                    },
-                    Err(e) => {
-                        error!("Couldn't accept connection: {:?}", e);
+                Some(message) = self.network.next_event() => match message {
+                    Message::Consensus(data) => self.consensus_tx.unbounded_send(data).expect("Network must listen"),
+                    Message::Block(block) => {
+                        debug!(target: "Blockchain-network", "Received block num {:?}", block.num);
+                        self.block_tx
+                            .unbounded_send(block)
+                            .expect("Blockchain process must listen");
                     },
                 },
 
-                _ = dns_interval.tick() => {
-                    if self.addresses.len() < self.n_nodes {
-                        self.send(Message::DNSRequest(self.id, self.address.clone()), Recipient::Everyone);
-                        debug!("Requesting IP addresses");
-                    }
-                },
-
-                _ = dns_hello_interval.tick() => {
-                    self.send(Message::DNSHello(self.id, self.address.clone()), Recipient::Everyone);
-                    debug!("Sending Hello!");
+                _ = status_interval.tick() => {
+                    info!(target: "Blockchain-network", "Connected to {:?}.", self.status.connected_peers());
                 },
 
                 Some((consensus_msg, recipient)) = self.consensus_rx.next() => {
-                    self.send(Message::Consensus(consensus_msg), recipient);
+                    self.network.send(Message::Consensus(consensus_msg), recipient);
                 }
 
                 Some(block) = self.block_rx.next() => {
                     debug!(target: "Blockchain-network", "Sending block message num {:?}.", block.num);
-                    self.send(Message::Block(block), Recipient::Everyone);
+                    self.network.send(Message::Block(block), Recipient::Everyone);
                 }
 
                _ = terminator.get_exit().fuse()  => {
@@ -11,7 +11,7 @@ Usage:
   integers from range [i * DATA_ITEMS; (i + 1) * DATA_ITEMS). where 0 <= i < N. At the end, each node makes
   sure that it receives all integers from range [0, N * DATA_ITEMS), each integer exactly once.
 
-  N nodes are started on your machine, and they communicate via UDP. Not all nodes behave correctly - some of them crash
+  N nodes are started on your machine, and they communicate via TCP. Not all nodes behave correctly - some of them crash
   or are stuck while providing data.
 
   This script is using aleph-bft-examples-ordering and assumes to be available in a relative folder from this script path
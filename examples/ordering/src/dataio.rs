@@ -1,10 +1,18 @@
+use aleph_bft_mock::Hash64;
 use aleph_bft_types::{
-    DataProvider as DataProviderT, FinalizationHandler as FinalizationHandlerT, NodeIndex,
+    DataProvider as DataProviderT, DeliveryCheckpoint, FinalizationHandler as FinalizationHandlerT,
+    FinalizationStateStore, NodeIndex,
 };
 use async_trait::async_trait;
 use codec::{Decode, Encode};
 use futures::{channel::mpsc::unbounded, future::pending};
 use log::{error, info};
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+};
 
 type Receiver<T> = futures::channel::mpsc::UnboundedReceiver<T>;
 type Sender<T> = futures::channel::mpsc::UnboundedSender<T>;
@@ -72,3 +80,132 @@ impl FinalizationHandler {
         (Self { tx }, rx)
     }
 }
+
+fn decoding_error(e: codec::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+/// An entry of the index log.
+#[derive(Decode, Encode)]
+enum IndexEntry {
+    Unit(Hash64, u64),
+    BatchId(u64, Hash64),
+}
+
+/// Keeps the finalization state in two files: the checkpoint, replaced atomically on every
+/// update, and an append-only log of the index entries, rewritten when pruned.
+pub struct FileFinalizationStore {
+    checkpoint_path: PathBuf,
+    index_path: PathBuf,
+    index_log: File,
+    index: HashMap<Hash64, u64>,
+    batch_ids: HashMap<u64, Hash64>,
+}
+
+impl FileFinalizationStore {
+    pub fn open(checkpoint_path: PathBuf, index_path: PathBuf) -> io::Result<Self> {
+        let mut index = HashMap::new();
+        let mut batch_ids = HashMap::new();
+        if index_path.exists() {
+            let bytes = fs::read(&index_path)?;
+            let mut buf = &bytes[..];
+            while !buf.is_empty() {
+                match IndexEntry::decode(&mut buf).map_err(decoding_error)? {
+                    IndexEntry::Unit(unit, batch) => {
+                        index.insert(unit, batch);
+                    }
+                    IndexEntry::BatchId(batch, id) => {
+                        batch_ids.insert(batch, id);
+                    }
+                }
+            }
+        }
+        let index_log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&index_path)?;
+        Ok(Self {
+            checkpoint_path,
+            index_path,
+            index_log,
+            index,
+            batch_ids,
+        })
+    }
+
+    fn append(&mut self, entries: Vec<IndexEntry>) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        for entry in &entries {
+            entry.encode_to(&mut bytes);
+        }
+        self.index_log.write_all(&bytes)?;
+        self.index_log.sync_data()
+    }
+}
+
+impl FinalizationStateStore<Hash64> for FileFinalizationStore {
+    fn put_checkpoint(&mut self, checkpoint: DeliveryCheckpoint<Hash64>) -> io::Result<()> {
+        let tmp_path = self.checkpoint_path.with_extension("tmp");
+        fs::write(
+            &tmp_path,
+            (checkpoint.batches, checkpoint.head, checkpoint.batch_id).encode(),
+        )?;
+        fs::rename(tmp_path, &self.checkpoint_path)
+    }
+
+    fn get_checkpoint(&self) -> io::Result<Option<DeliveryCheckpoint<Hash64>>> {
+        if !self.checkpoint_path.exists() {
+            return Ok(None);
+        }
+        let bytes = fs::read(&self.checkpoint_path)?;
+        let (batches, head, batch_id) =
+            <(u64, Hash64, Hash64)>::decode(&mut &bytes[..]).map_err(decoding_error)?;
+        Ok(Some(DeliveryCheckpoint {
+            batches,
+            head,
+            batch_id,
+        }))
+    }
+
+    fn put_index_entries(&mut self, entries: Vec<(Hash64, u64)>) -> io::Result<()> {
+        self.append(
+            entries
+                .iter()
+                .map(|(unit, batch)| IndexEntry::Unit(*unit, *batch))
+                .collect(),
+        )?;
+        self.index.extend(entries);
+        Ok(())
+    }
+
+    fn get_index_entry(&self, unit: &Hash64) -> io::Result<Option<u64>> {
+        Ok(self.index.get(unit).copied())
+    }
+
+    fn put_batch_id(&mut self, batch: u64, id: Hash64) -> io::Result<()> {
+        self.append(vec![IndexEntry::BatchId(batch, id)])?;
+        self.batch_ids.insert(batch, id);
+        Ok(())
+    }
+
+    fn get_batch_id(&self, batch: u64) -> io::Result<Option<Hash64>> {
+        Ok(self.batch_ids.get(&batch).copied())
+    }
+
+    fn prune_below(&mut self, batch: u64) -> io::Result<()> {
+        self.index.retain(|_, number| *number >= batch);
+        self.batch_ids.retain(|number, _| *number >= batch);
+        let mut bytes = Vec::new();
+        for (unit, number) in self.index.iter() {
+            IndexEntry::Unit(*unit, *number).encode_to(&mut bytes);
+        }
+        for (number, id) in self.batch_ids.iter() {
+            IndexEntry::BatchId(*number, *id).encode_to(&mut bytes);
+        }
+        let tmp_path = self.index_path.with_extension("tmp");
+        fs::write(&tmp_path, bytes)?;
+        fs::rename(tmp_path, &self.index_path)?;
+        self.index_log = OpenOptions::new().append(true).open(&self.index_path)?;
+        Ok(())
+    }
+}
@@ -2,13 +2,13 @@ use std::io::Write;
 mod dataio;
 mod network;
 
-use aleph_bft::{default_delay_config, run_session, NodeIndex, Terminator};
+use aleph_bft::{default_delay_config, run_session, FinalizationState, NodeIndex, Terminator};
 use aleph_bft_mock::{Keychain, Spawner};
 use clap::Parser;
-use dataio::{Data, DataProvider, FinalizationHandler};
+use dataio::{Data, DataProvider, FileFinalizationStore, FinalizationHandler};
 use futures::{channel::oneshot, io, StreamExt};
 use log::{debug, error, info};
-use network::Network;
+use network::create_network;
 use std::{path::Path, sync::Arc, time::Duration};
 use time::{macros::format_description, OffsetDateTime};
 use tokio::fs::{self, File};
@@ -46,12 +46,20 @@ struct Args {
     /// Unit creation delay (milliseconds)
     #[clap(long, default_value = "200", value_parser)]
     unit_creation_delay: u64,
+
+    /// Should the finalization state be persisted next to the backup, so that a restarted node
+    /// does not finalize the items it finalized before the crash again. The run script does not
+    /// use it, as it expects the restarted nodes to finalize all the items.
+    #[clap(long, value_parser)]
+    persist_finalization_state: bool,
 }
 
+const STASH_PATH: &str = "./aleph-bft-examples-ordering-backup";
+
 async fn create_backup(
     node_id: NodeIndex,
 ) -> Result<(Compat<File>, io::Cursor<Vec<u8>>), io::Error> {
-    let stash_path = Path::new("./aleph-bft-examples-ordering-backup");
+    let stash_path = Path::new(STASH_PATH);
     fs::create_dir_all(stash_path).await?;
     let file_path = stash_path.join(format!("{}.units", node_id.0));
     let loader = if file_path.exists() {
@@ -67,6 +75,17 @@ async fn create_backup(
     Ok((saver.compat_write(), loader))
 }
 
+/// The finalization state is kept next to the backup, so that a restarted node does not
+/// finalize the same items again.
+fn create_finalization_store(node_id: NodeIndex) -> std::io::Result<FileFinalizationStore> {
+    let stash_path = Path::new(STASH_PATH);
+    std::fs::create_dir_all(stash_path)?;
+    FileFinalizationStore::open(
+        stash_path.join(format!("{}.checkpoint", node_id.0)),
+        stash_path.join(format!("{}.index", node_id.0)),
+    )
+}
+
 #[tokio::main]
 async fn main() {
     let time_format =
@@ -96,12 +115,13 @@ async fn main() {
         should_stall,
         required_finalization_value,
         unit_creation_delay,
+        persist_finalization_state,
     } = Args::parse();
 
     let id: NodeIndex = id.into();
 
     info!("Getting network up.");
-    let network = Network::new(id, &ports)
+    let network = create_network(id, &ports)
         .await
         .expect("Could not create a Network instance.");
     let n_members = ports.len().into();
@@ -110,12 +130,16 @@ async fn main() {
     let (backup_saver, backup_loader) = create_backup(id)
         .await
         .expect("Error setting up unit saving");
-    let local_io = aleph_bft::LocalIO::new(
+    let mut local_io = aleph_bft::LocalIO::new(
         data_provider,
         finalization_handler,
         backup_saver,
         backup_loader,
     );
+    if persist_finalization_state {
+        let store = create_finalization_store(id).expect("Error setting up the finalization state");
+        local_io = local_io.with_finalization_state(FinalizationState::new(store));
+    }
 
     let (exit_tx, exit_rx) = oneshot::channel();
     let member_terminator = Terminator::create_root(exit_rx, "AlephBFT-member");
@@ -173,5 +197,7 @@ async fn main() {
     }
 
     exit_tx.send(()).expect("should send");
-    member_handle.await.unwrap();
+    if let Err(e) = member_handle.await.unwrap() {
+        error!("The session ended with an error: {}.", e);
+    }
 }
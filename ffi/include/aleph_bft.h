@@ -0,0 +1,118 @@
+/*
+ * A C ABI for embedding an AlephBFT member, see the documentation of the aleph-bft-ffi crate.
+ *
+ * Memory ownership:
+ * - Objects created by aleph_*_create are owned by the host, which has to release them with
+ *   the corresponding aleph_*_destroy, exactly once.
+ * - Buffers passed by the host are only read during the call, the session keeps copies.
+ * - Buffers passed to the callbacks are owned by the session and are only valid during the
+ *   call, the host has to copy whatever it needs later.
+ *
+ * The callbacks are only ever called from within aleph_session_* calls, on the thread making
+ * them, and must not unwind (e.g. throw C++ exceptions).
+ */
+
+#ifndef ALEPH_BFT_H
+#define ALEPH_BFT_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The call succeeded and the session is still running. */
+#define ALEPH_OK (0)
+/* The session finished. */
+#define ALEPH_FINISHED (1)
+/* The arguments were invalid, e.g. a message that cannot be decoded. */
+#define ALEPH_INVALID_ARGUMENT (-1)
+/* The session is broken and can only be destroyed. */
+#define ALEPH_ERROR (-2)
+
+/* The recipient passed to the send callback for messages to all the nodes. */
+#define ALEPH_RECIPIENT_EVERYONE (-1)
+/* The capacity of the buffer passed to the sign callback. */
+#define ALEPH_MAX_SIGNATURE_SIZE (256)
+/* The capacity of the buffer passed to the get data callback. */
+#define ALEPH_MAX_DATA_SIZE (65536)
+
+typedef struct AlephConfig AlephConfig;
+typedef struct AlephSession AlephSession;
+
+/* The signing and verification of the host. */
+typedef struct {
+    void *user_data;
+    /* Writes at most signature_capacity bytes of the signature, returns its length or a
+     * negative number if signing failed. */
+    intptr_t (*sign)(void *user_data, const uint8_t *message, size_t message_len,
+                     uint8_t *signature, size_t signature_capacity);
+    /* Checks whether the node with the given index signed the message. */
+    bool (*verify)(void *user_data, const uint8_t *message, size_t message_len,
+                   const uint8_t *signature, size_t signature_len, size_t signer);
+} AlephKeychainCallbacks;
+
+/* The network of the host. Received messages are passed to aleph_session_receive. */
+typedef struct {
+    void *user_data;
+    /* Sends the bytes to the node with the given index, or to everyone. */
+    void (*send)(void *user_data, const uint8_t *data, size_t data_len, intptr_t recipient);
+} AlephNetworkCallbacks;
+
+/* The source of the data to order and the destination of the finalized data. */
+typedef struct {
+    void *user_data;
+    /* Writes at most capacity bytes of the next data item, returns its length or a negative
+     * number if there is no data to order right now. */
+    intptr_t (*get_data)(void *user_data, uint8_t *data, size_t capacity);
+    /* The data item has been finalized, the calls follow the order of finalization. */
+    void (*data_finalized)(void *user_data, const uint8_t *data, size_t data_len);
+} AlephDataCallbacks;
+
+/* The storage of the host for the backup of the units, which lets a session recover from a
+ * crash. The backup of the previous run of the session is read once, when it starts. */
+typedef struct {
+    void *user_data;
+    /* Appends the bytes to the backup, they have to be stored durably once the call returns.
+     * Returns false if storing failed. */
+    bool (*write)(void *user_data, const uint8_t *data, size_t data_len);
+    /* Reads at most capacity bytes of the backup, continuing where the previous call stopped.
+     * Returns the number of bytes read, zero at the end of the backup or a negative number if
+     * reading failed. */
+    intptr_t (*read)(void *user_data, uint8_t *buffer, size_t capacity);
+} AlephBackupCallbacks;
+
+/* Creates the default configuration, returns NULL if the arguments are invalid, including
+ * committees smaller than four nodes, which do not tolerate any faulty node. */
+AlephConfig *aleph_config_create(size_t n_members, size_t node_index, uint64_t session_id);
+
+/* Releases a configuration. Sessions created with it are not affected. */
+void aleph_config_destroy(AlephConfig *config);
+
+/* Creates a session, returns NULL if the configuration is NULL or a callback is missing. The
+ * user data pointers have to stay valid until the session is destroyed. */
+AlephSession *aleph_session_create(const AlephConfig *config, AlephKeychainCallbacks keychain,
+                                   AlephNetworkCallbacks network, AlephDataCallbacks data,
+                                   AlephBackupCallbacks backup);
+
+/* Passes a message received from the network to the session. Returns ALEPH_INVALID_ARGUMENT if
+ * it cannot be decoded, has trailing bytes or is nested too deeply. */
+int32_t aleph_session_receive(AlephSession *session, const uint8_t *data, size_t data_len);
+
+/* Runs the session until it has to wait, returns ALEPH_OK while it is running, ALEPH_FINISHED
+ * once it ended and ALEPH_ERROR if it broke. */
+int32_t aleph_session_run_step(AlephSession *session);
+
+/* Asks the session to stop, run_step should be called until it returns ALEPH_FINISHED. */
+void aleph_session_stop(AlephSession *session);
+
+/* Releases the session, abruptly dropping all its tasks if it did not finish yet. */
+void aleph_session_destroy(AlephSession *session);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ALEPH_BFT_H */
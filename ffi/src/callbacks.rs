@@ -0,0 +1,408 @@
+use crate::{Hasher, ALEPH_MAX_DATA_SIZE, ALEPH_MAX_SIGNATURE_SIZE, ALEPH_RECIPIENT_EVERYONE};
+use aleph_bft::{
+    DataProvider, FinalizationHandler, Index, Keychain, MultiKeychain, Network, NetworkData,
+    NodeCount, NodeIndex, PartialMultisignature, Recipient, SignatureSet,
+};
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+use futures::{channel::mpsc, AsyncRead, AsyncWrite, StreamExt};
+use log::warn;
+use std::{
+    ffi::c_void,
+    fmt::{Display, Formatter, Result as FmtResult},
+    io::{Error as IoError, ErrorKind, Result as IoResult},
+    pin::Pin,
+    slice,
+    task::{Context, Poll},
+};
+
+const LOG_TARGET: &str = "AlephBFT-ffi";
+
+/// The data ordered through the C ABI, opaque bytes of the host.
+pub type Data = Vec<u8>;
+
+/// A signature produced by the host.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Encode, Decode)]
+pub struct Signature(Vec<u8>);
+
+pub type Multisignature = SignatureSet<Signature>;
+
+pub type FfiNetworkData = NetworkData<Hasher, Data, Signature, Multisignature>;
+
+/// The user data pointer of the host, passed back to its callbacks.
+#[derive(Clone, Copy)]
+struct UserData(*mut c_void);
+
+// The callbacks are only ever called from within the calls of the host to the session, on the
+// thread making them, so the pointer is never used concurrently.
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+/// Signs `message_len` bytes at `message` by writing at most `signature_capacity` bytes to
+/// `signature`. Returns the length of the signature, or a negative number if signing failed.
+pub type SignCallback = unsafe extern "C" fn(
+    user_data: *mut c_void,
+    message: *const u8,
+    message_len: usize,
+    signature: *mut u8,
+    signature_capacity: usize,
+) -> isize;
+
+/// Checks whether the node with the given index signed the message.
+pub type VerifyCallback = unsafe extern "C" fn(
+    user_data: *mut c_void,
+    message: *const u8,
+    message_len: usize,
+    signature: *const u8,
+    signature_len: usize,
+    signer: usize,
+) -> bool;
+
+/// Sends the bytes to the node with the given index, or to everyone if the recipient is
+/// `ALEPH_RECIPIENT_EVERYONE`.
+pub type SendCallback = unsafe extern "C" fn(
+    user_data: *mut c_void,
+    data: *const u8,
+    data_len: usize,
+    recipient: isize,
+);
+
+/// Writes at most `capacity` bytes of the next data item to `data`. Returns its length, or
+/// a negative number if there is no data to order right now.
+pub type GetDataCallback =
+    unsafe extern "C" fn(user_data: *mut c_void, data: *mut u8, capacity: usize) -> isize;
+
+/// The data item has been finalized, the calls follow the order of finalization.
+pub type DataFinalizedCallback =
+    unsafe extern "C" fn(user_data: *mut c_void, data: *const u8, data_len: usize);
+
+/// Appends `data_len` bytes at `data` to the backup. The bytes have to be stored durably once
+/// the call returns. Returns false if storing failed.
+pub type WriteBackupCallback =
+    unsafe extern "C" fn(user_data: *mut c_void, data: *const u8, data_len: usize) -> bool;
+
+/// Reads at most `capacity` bytes of the backup to `buffer`, continuing where the previous call
+/// stopped. Returns the number of bytes read, zero once the whole backup is read, or a negative
+/// number if reading failed.
+pub type ReadBackupCallback =
+    unsafe extern "C" fn(user_data: *mut c_void, buffer: *mut u8, capacity: usize) -> isize;
+
+/// The signing and verification of the host.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct AlephKeychainCallbacks {
+    pub user_data: *mut c_void,
+    pub sign: Option<SignCallback>,
+    pub verify: Option<VerifyCallback>,
+}
+
+/// The network of the host. Received messages are passed to the session with
+/// `aleph_session_receive`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct AlephNetworkCallbacks {
+    pub user_data: *mut c_void,
+    pub send: Option<SendCallback>,
+}
+
+/// The source of the data to order and the destination of the finalized data.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct AlephDataCallbacks {
+    pub user_data: *mut c_void,
+    pub get_data: Option<GetDataCallback>,
+    pub data_finalized: Option<DataFinalizedCallback>,
+}
+
+/// The storage of the host for the backup of the units, which lets a session recover from a
+/// crash. The backup of the previous run of the session is read once, when it starts.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct AlephBackupCallbacks {
+    pub user_data: *mut c_void,
+    pub write: Option<WriteBackupCallback>,
+    pub read: Option<ReadBackupCallback>,
+}
+
+/// Turns a pointer and a length into a slice, accepting a null pointer for empty slices.
+///
+/// # Safety
+/// Unless `len` is zero, `data` has to point to `len` bytes valid for the lifetime `'a`.
+pub(crate) unsafe fn bytes<'a>(data: *const u8, len: usize) -> &'a [u8] {
+    match len {
+        0 => &[],
+        len => slice::from_raw_parts(data, len),
+    }
+}
+
+/// Calls a callback that writes at most `capacity` bytes to a buffer, returning them if it
+/// reported success.
+fn read_from_host(
+    capacity: usize,
+    callback: impl FnOnce(*mut u8, usize) -> isize,
+) -> Option<Vec<u8>> {
+    let mut buffer = vec![0; capacity];
+    let len = callback(buffer.as_mut_ptr(), capacity);
+    let len = usize::try_from(len).ok()?;
+    if len > capacity {
+        warn!(target: LOG_TARGET, "Host reported writing {} bytes to a buffer of {}.", len, capacity);
+        return None;
+    }
+    buffer.truncate(len);
+    Some(buffer)
+}
+
+/// The host failed to produce a signature.
+#[derive(Debug)]
+pub struct SignError;
+
+impl Display for SignError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "the host failed to sign the message")
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct FfiKeychain {
+    index: NodeIndex,
+    n_members: NodeCount,
+    user_data: UserData,
+    sign: SignCallback,
+    verify: VerifyCallback,
+}
+
+impl FfiKeychain {
+    pub fn new(
+        index: NodeIndex,
+        n_members: NodeCount,
+        callbacks: AlephKeychainCallbacks,
+    ) -> Option<Self> {
+        Some(FfiKeychain {
+            index,
+            n_members,
+            user_data: UserData(callbacks.user_data),
+            sign: callbacks.sign?,
+            verify: callbacks.verify?,
+        })
+    }
+}
+
+impl Index for FfiKeychain {
+    fn index(&self) -> NodeIndex {
+        self.index
+    }
+}
+
+impl Keychain for FfiKeychain {
+    type Signature = Signature;
+    type SignError = SignError;
+
+    fn node_count(&self) -> NodeCount {
+        self.n_members
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Signature, SignError> {
+        // SAFETY: both buffers are valid for the whole call.
+        read_from_host(ALEPH_MAX_SIGNATURE_SIZE, |signature, capacity| unsafe {
+            (self.sign)(
+                self.user_data.0,
+                msg.as_ptr(),
+                msg.len(),
+                signature,
+                capacity,
+            )
+        })
+        .map(Signature)
+        .ok_or(SignError)
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Signature, index: NodeIndex) -> bool {
+        if index.0 >= self.n_members.0 {
+            return false;
+        }
+        // SAFETY: both buffers are valid for the whole call.
+        unsafe {
+            (self.verify)(
+                self.user_data.0,
+                msg.as_ptr(),
+                msg.len(),
+                sgn.0.as_ptr(),
+                sgn.0.len(),
+                index.0,
+            )
+        }
+    }
+}
+
+impl MultiKeychain for FfiKeychain {
+    type PartialMultisignature = Multisignature;
+
+    fn bootstrap_multi(&self, signature: &Signature, index: NodeIndex) -> Multisignature {
+        SignatureSet::add_signature(SignatureSet::with_size(self.n_members), signature, index)
+    }
+
+    fn is_complete(&self, msg: &[u8], partial: &Multisignature) -> bool {
+        let signature_count = partial.iter().count();
+        if signature_count < self.n_members.consensus_threshold().0 {
+            return false;
+        }
+        partial.iter().all(|(i, sgn)| self.verify(msg, sgn, i))
+    }
+}
+
+pub(crate) struct FfiNetwork {
+    user_data: UserData,
+    send: SendCallback,
+    incoming: mpsc::UnboundedReceiver<FfiNetworkData>,
+}
+
+impl FfiNetwork {
+    pub fn new(
+        callbacks: AlephNetworkCallbacks,
+        incoming: mpsc::UnboundedReceiver<FfiNetworkData>,
+    ) -> Option<Self> {
+        Some(FfiNetwork {
+            user_data: UserData(callbacks.user_data),
+            send: callbacks.send?,
+            incoming,
+        })
+    }
+}
+
+#[async_trait]
+impl Network<FfiNetworkData> for FfiNetwork {
+    fn send(&self, data: FfiNetworkData, recipient: Recipient) {
+        let recipient = match recipient {
+            Recipient::Everyone => ALEPH_RECIPIENT_EVERYONE,
+            Recipient::Node(node) => node.0 as isize,
+        };
+        let data = data.encode();
+        // SAFETY: the buffer is valid for the whole call.
+        unsafe { (self.send)(self.user_data.0, data.as_ptr(), data.len(), recipient) }
+    }
+
+    async fn next_event(&mut self) -> Option<FfiNetworkData> {
+        self.incoming.next().await
+    }
+}
+
+pub(crate) struct FfiDataProvider {
+    user_data: UserData,
+    get_data: GetDataCallback,
+}
+
+#[async_trait]
+impl DataProvider for FfiDataProvider {
+    type Output = Data;
+
+    async fn get_data(&mut self) -> Option<Data> {
+        // SAFETY: the buffer is valid for the whole call.
+        read_from_host(ALEPH_MAX_DATA_SIZE, |data, capacity| unsafe {
+            (self.get_data)(self.user_data.0, data, capacity)
+        })
+    }
+}
+
+pub(crate) struct FfiFinalizationHandler {
+    user_data: UserData,
+    data_finalized: DataFinalizedCallback,
+}
+
+impl FinalizationHandler<Data> for FfiFinalizationHandler {
+    fn data_finalized(&mut self, data: Data) {
+        // SAFETY: the buffer is valid for the whole call.
+        unsafe { (self.data_finalized)(self.user_data.0, data.as_ptr(), data.len()) }
+    }
+}
+
+pub(crate) fn data_io(
+    callbacks: AlephDataCallbacks,
+) -> Option<(FfiDataProvider, FfiFinalizationHandler)> {
+    let user_data = UserData(callbacks.user_data);
+    Some((
+        FfiDataProvider {
+            user_data,
+            get_data: callbacks.get_data?,
+        },
+        FfiFinalizationHandler {
+            user_data,
+            data_finalized: callbacks.data_finalized?,
+        },
+    ))
+}
+
+pub(crate) struct FfiBackupWriter {
+    user_data: UserData,
+    write: WriteBackupCallback,
+}
+
+impl AsyncWrite for FfiBackupWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<IoResult<usize>> {
+        // SAFETY: the buffer is valid for the whole call.
+        match unsafe { (self.write)(self.user_data.0, buf.as_ptr(), buf.len()) } {
+            true => Poll::Ready(Ok(buf.len())),
+            false => Poll::Ready(Err(IoError::other("the host failed to write the backup"))),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+        // Every write is durable once the callback returns.
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+pub(crate) struct FfiBackupReader {
+    user_data: UserData,
+    read: ReadBackupCallback,
+}
+
+impl AsyncRead for FfiBackupReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<IoResult<usize>> {
+        // SAFETY: the buffer is valid for the whole call.
+        let len = unsafe { (self.read)(self.user_data.0, buf.as_mut_ptr(), buf.len()) };
+        match usize::try_from(len) {
+            Ok(len) if len <= buf.len() => Poll::Ready(Ok(len)),
+            Ok(len) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Host reported reading {} bytes to a buffer of {}.",
+                    len,
+                    buf.len()
+                );
+                Poll::Ready(Err(IoError::new(
+                    ErrorKind::InvalidData,
+                    "the host read more than the buffer holds",
+                )))
+            }
+            Err(_) => Poll::Ready(Err(IoError::other("the host failed to read the backup"))),
+        }
+    }
+}
+
+pub(crate) fn backup_io(
+    callbacks: AlephBackupCallbacks,
+) -> Option<(FfiBackupWriter, FfiBackupReader)> {
+    let user_data = UserData(callbacks.user_data);
+    Some((
+        FfiBackupWriter {
+            user_data,
+            write: callbacks.write?,
+        },
+        FfiBackupReader {
+            user_data,
+            read: callbacks.read?,
+        },
+    ))
+}
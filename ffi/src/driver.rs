@@ -0,0 +1,107 @@
+use aleph_bft::{SpawnHandle, TaskHandle};
+use futures::{
+    channel::oneshot,
+    executor::LocalPool,
+    future::BoxFuture,
+    task::{LocalSpawnExt, SpawnError},
+    Future, FutureExt,
+};
+use parking_lot::Mutex;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+type Queue = Arc<Mutex<Vec<BoxFuture<'static, ()>>>>;
+
+/// Collects the spawned tasks, so that the driver can run them on the thread of the host.
+#[derive(Clone, Default)]
+pub(crate) struct Spawner {
+    spawned: Queue,
+}
+
+impl SpawnHandle for Spawner {
+    fn spawn(&self, _name: &'static str, task: impl Future<Output = ()> + Send + 'static) {
+        self.spawned.lock().push(task.boxed());
+    }
+
+    fn spawn_essential(
+        &self,
+        name: &'static str,
+        task: impl Future<Output = ()> + Send + 'static,
+    ) -> TaskHandle {
+        let (res_tx, res_rx) = oneshot::channel();
+        self.spawn(name, async move {
+            task.await;
+            let _ = res_tx.send(());
+        });
+        Box::pin(async move { res_rx.await.map_err(|_| ()) })
+    }
+}
+
+/// Runs a future with all the tasks it spawns on the thread calling [`Driver::run_step`], so
+/// that the host controls the event loop. Timers wake the tasks up from a background thread,
+/// but the tasks are only ever polled from within the steps.
+pub(crate) struct Driver {
+    pool: LocalPool,
+    spawner: Spawner,
+    finished: Arc<AtomicBool>,
+}
+
+impl Driver {
+    /// Creates a driver for the future built with the given spawner.
+    pub fn new<F: Future<Output = ()> + Send + 'static>(main: impl FnOnce(Spawner) -> F) -> Self {
+        let spawner = Spawner::default();
+        let finished = Arc::new(AtomicBool::new(false));
+        let main = main(spawner.clone());
+        let main_finished = finished.clone();
+        spawner.spawn("main", async move {
+            main.await;
+            main_finished.store(true, Ordering::SeqCst);
+        });
+        Driver {
+            pool: LocalPool::new(),
+            spawner,
+            finished,
+        }
+    }
+
+    /// Polls all the tasks that can make progress, until all of them wait. Returns whether the
+    /// main future finished.
+    pub fn run_step(&mut self) -> Result<bool, SpawnError> {
+        loop {
+            let spawned: Vec<_> = self.spawner.spawned.lock().drain(..).collect();
+            for task in spawned {
+                self.pool.spawner().spawn_local(task)?;
+            }
+            self.pool.run_until_stalled();
+            if self.spawner.spawned.lock().is_empty() {
+                return Ok(self.finished.load(Ordering::SeqCst));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::driver::Driver;
+    use aleph_bft::SpawnHandle;
+    use futures::channel::oneshot;
+
+    #[test]
+    fn runs_spawned_tasks_in_steps() {
+        let (tx, rx) = oneshot::channel();
+        let mut driver = Driver::new(|spawner| async move {
+            let (inner_tx, inner_rx) = oneshot::channel();
+            let handle = spawner.spawn_essential("inner", async move {
+                let _ = inner_tx.send(());
+            });
+            inner_rx.await.expect("the inner task sends");
+            handle.await.expect("the inner task finishes");
+            rx.await.expect("the test sends");
+        });
+        assert!(!driver.run_step().expect("the pool is running"));
+        tx.send(()).expect("the driver waits");
+        assert!(driver.run_step().expect("the pool is running"));
+    }
+}
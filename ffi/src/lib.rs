@@ -0,0 +1,368 @@
+//! A C ABI for embedding an AlephBFT member in applications not written in Rust. The header
+//! declaring it is `include/aleph_bft.h`.
+//!
+//! The host provides the keychain, the network and the data as callbacks, and drives the
+//! session itself by calling [`aleph_session_run_step`], e.g. from its event loop. The
+//! callbacks are only ever called from within the calls of the host to the session, on the
+//! thread making them, and must not unwind.
+//!
+//! Memory ownership:
+//! - Objects created by `aleph_*_create` are owned by the host, which has to release them with
+//!   the corresponding `aleph_*_destroy`, exactly once.
+//! - Buffers passed by the host are only read during the call, the session keeps copies.
+//! - Buffers passed to the callbacks are owned by the session and are only valid during the
+//!   call, the host has to copy whatever it needs later.
+//!
+//! Panics never cross the boundary: a session that panicked returns `ALEPH_ERROR` from then on
+//! and can only be destroyed.
+//!
+//! The units are backed up through the backup callbacks of the host. A session restarted after
+//! a crash reads the backup of its previous run, so it never signs conflicting units.
+
+use aleph_bft::{
+    create_config, default_delay_config, run_session, Config, LocalIO, Terminator, MAX_DECODE_DEPTH,
+};
+use codec::DecodeLimit;
+use futures::channel::{mpsc, oneshot};
+use log::{error, warn};
+use sha3::{Digest, Sha3_256};
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr,
+    time::Duration,
+};
+
+mod callbacks;
+mod driver;
+#[cfg(test)]
+mod testing;
+
+use callbacks::{backup_io, bytes, data_io, FfiKeychain, FfiNetwork, FfiNetworkData};
+pub use callbacks::{
+    AlephBackupCallbacks, AlephDataCallbacks, AlephKeychainCallbacks, AlephNetworkCallbacks,
+    DataFinalizedCallback, GetDataCallback, ReadBackupCallback, SendCallback, SignCallback,
+    VerifyCallback, WriteBackupCallback,
+};
+use driver::Driver;
+
+const LOG_TARGET: &str = "AlephBFT-ffi";
+
+/// The call succeeded and the session is still running.
+pub const ALEPH_OK: i32 = 0;
+/// The session finished.
+pub const ALEPH_FINISHED: i32 = 1;
+/// The arguments were invalid, e.g. a message that cannot be decoded.
+pub const ALEPH_INVALID_ARGUMENT: i32 = -1;
+/// The session is broken and can only be destroyed.
+pub const ALEPH_ERROR: i32 = -2;
+
+/// The recipient passed to the send callback for messages to all the nodes.
+pub const ALEPH_RECIPIENT_EVERYONE: isize = -1;
+/// The capacity of the buffer passed to the sign callback.
+pub const ALEPH_MAX_SIGNATURE_SIZE: usize = 256;
+/// The capacity of the buffer passed to the get data callback.
+pub const ALEPH_MAX_DATA_SIZE: usize = 65536;
+
+/// The number of rounds of a session created through the C ABI.
+const MAX_ROUND: u16 = 5000;
+
+/// The hasher of the units, SHA3-256.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Hasher;
+
+impl aleph_bft::Hasher for Hasher {
+    type Hash = [u8; 32];
+
+    fn hash(x: &[u8]) -> Self::Hash {
+        Sha3_256::digest(x).into()
+    }
+}
+
+/// The configuration of a session, see [`aleph_config_create`].
+pub struct AlephConfig {
+    config: Config,
+}
+
+/// A running session, see [`aleph_session_create`].
+pub struct AlephSession {
+    driver: Option<Driver>,
+    messages_for_network: mpsc::UnboundedSender<FfiNetworkData>,
+    exit: Option<oneshot::Sender<()>>,
+}
+
+/// Runs `f`, making sure no panic unwinds into the host.
+fn guarded<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        error!(target: LOG_TARGET, "Panic caught at the C ABI boundary.");
+        on_panic
+    })
+}
+
+/// Creates the default configuration for the node with the given index in a committee of
+/// `n_members`. Returns null if the arguments are invalid, including committees smaller than
+/// four nodes, which do not tolerate any faulty node.
+#[no_mangle]
+pub extern "C" fn aleph_config_create(
+    n_members: usize,
+    node_index: usize,
+    session_id: u64,
+) -> *mut AlephConfig {
+    guarded(ptr::null_mut(), || {
+        if node_index >= n_members {
+            return ptr::null_mut();
+        }
+        match create_config(
+            n_members.into(),
+            node_index.into(),
+            session_id,
+            MAX_ROUND,
+            default_delay_config(),
+            Duration::ZERO,
+        )
+        .and_then(|config| config.validate().map(|()| config))
+        {
+            Ok(config) => Box::into_raw(Box::new(AlephConfig { config })),
+            Err(e) => {
+                warn!(target: LOG_TARGET, "Invalid config: {:?}.", e);
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Releases a configuration. Sessions created with it are not affected.
+///
+/// # Safety
+/// `config` has to be null or returned by [`aleph_config_create`] and not destroyed before.
+#[no_mangle]
+pub unsafe extern "C" fn aleph_config_destroy(config: *mut AlephConfig) {
+    guarded((), || {
+        if !config.is_null() {
+            drop(Box::from_raw(config));
+        }
+    })
+}
+
+/// Creates a session with the given configuration and callbacks. Nothing happens until the
+/// first [`aleph_session_run_step`]. Returns null if the configuration is null or any of the
+/// callbacks is missing.
+///
+/// # Safety
+/// `config` has to be null or a valid configuration. The user data pointers have to stay valid
+/// for the callbacks until the session is destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn aleph_session_create(
+    config: *const AlephConfig,
+    keychain: AlephKeychainCallbacks,
+    network: AlephNetworkCallbacks,
+    data: AlephDataCallbacks,
+    backup: AlephBackupCallbacks,
+) -> *mut AlephSession {
+    guarded(ptr::null_mut(), || {
+        let config = match config.as_ref() {
+            Some(config) => config.config.clone(),
+            None => return ptr::null_mut(),
+        };
+        let (messages_for_network, messages_from_host) = mpsc::unbounded();
+        let parts = (
+            FfiKeychain::new(config.node_ix(), config.n_members(), keychain),
+            FfiNetwork::new(network, messages_from_host),
+            data_io(data),
+            backup_io(backup),
+        );
+        let (
+            keychain,
+            network,
+            (data_provider, finalization_handler),
+            (backup_writer, backup_reader),
+        ) = match parts {
+            (Some(keychain), Some(network), Some(data_io), Some(backup_io)) => {
+                (keychain, network, data_io, backup_io)
+            }
+            _ => return ptr::null_mut(),
+        };
+        let (exit, exit_rx) = oneshot::channel();
+        let local_io = LocalIO::new(
+            data_provider,
+            finalization_handler,
+            backup_writer,
+            backup_reader,
+        );
+        let driver = Driver::new(move |spawner| {
+            let session = run_session(
+                config,
+                local_io,
+                network,
+                keychain,
+                spawner,
+                Terminator::create_root(exit_rx, "AlephBFT-member"),
+            );
+            async move {
+                if let Err(e) = session.await {
+                    error!(target: LOG_TARGET, "The session ended with an error: {}.", e);
+                }
+            }
+        });
+        Box::into_raw(Box::new(AlephSession {
+            driver: Some(driver),
+            messages_for_network,
+            exit: Some(exit),
+        }))
+    })
+}
+
+/// Passes a message received from the network to the session. Returns `ALEPH_INVALID_ARGUMENT`
+/// if the message cannot be decoded, is followed by trailing bytes, or is nested deeper than
+/// [`MAX_DECODE_DEPTH`].
+///
+/// # Safety
+/// `session` has to be a valid session and `data` has to point to `data_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn aleph_session_receive(
+    session: *mut AlephSession,
+    data: *const u8,
+    data_len: usize,
+) -> i32 {
+    guarded(ALEPH_ERROR, || {
+        let session = match session.as_mut() {
+            Some(session) => session,
+            None => return ALEPH_INVALID_ARGUMENT,
+        };
+        let message = match FfiNetworkData::decode_all_with_depth_limit(
+            MAX_DECODE_DEPTH,
+            &mut bytes(data, data_len),
+        ) {
+            Ok(message) => message,
+            Err(_) => return ALEPH_INVALID_ARGUMENT,
+        };
+        match session.messages_for_network.unbounded_send(message) {
+            Ok(()) => ALEPH_OK,
+            Err(_) => ALEPH_FINISHED,
+        }
+    })
+}
+
+/// Runs the session until it has to wait for a message, a timer or data. Returns `ALEPH_OK` if
+/// the session is still running, `ALEPH_FINISHED` once it ended, and `ALEPH_ERROR` if it broke.
+///
+/// # Safety
+/// `session` has to be a valid session.
+#[no_mangle]
+pub unsafe extern "C" fn aleph_session_run_step(session: *mut AlephSession) -> i32 {
+    let session = match session.as_mut() {
+        Some(session) => session,
+        None => return ALEPH_INVALID_ARGUMENT,
+    };
+    let mut driver = match session.driver.take() {
+        Some(driver) => driver,
+        None => return ALEPH_ERROR,
+    };
+    guarded(ALEPH_ERROR, || match driver.run_step() {
+        Ok(finished) => {
+            session.driver = Some(driver);
+            match finished {
+                true => ALEPH_FINISHED,
+                false => ALEPH_OK,
+            }
+        }
+        Err(e) => {
+            error!(target: LOG_TARGET, "Failed to run the session tasks: {}.", e);
+            ALEPH_ERROR
+        }
+    })
+}
+
+/// Asks the session to stop. The host should keep calling [`aleph_session_run_step`] until it
+/// returns `ALEPH_FINISHED`, so that all the tasks end gracefully.
+///
+/// # Safety
+/// `session` has to be a valid session.
+#[no_mangle]
+pub unsafe extern "C" fn aleph_session_stop(session: *mut AlephSession) {
+    guarded((), || {
+        if let Some(exit) = session.as_mut().and_then(|session| session.exit.take()) {
+            let _ = exit.send(());
+        }
+    })
+}
+
+/// Releases the session, abruptly dropping all its tasks if it did not finish yet.
+///
+/// # Safety
+/// `session` has to be null or returned by [`aleph_session_create`] and not destroyed before.
+#[no_mangle]
+pub unsafe extern "C" fn aleph_session_destroy(session: *mut AlephSession) {
+    guarded((), || {
+        if !session.is_null() {
+            drop(Box::from_raw(session));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ALEPH_ERROR, ALEPH_FINISHED, ALEPH_INVALID_ARGUMENT, ALEPH_MAX_DATA_SIZE,
+        ALEPH_MAX_SIGNATURE_SIZE, ALEPH_OK, ALEPH_RECIPIENT_EVERYONE,
+    };
+    use std::collections::HashSet;
+
+    const HEADER: &str = include_str!("../include/aleph_bft.h");
+    const SOURCES: [&str; 2] = [include_str!("lib.rs"), include_str!("callbacks.rs")];
+
+    /// The identifiers following the prefix, and followed by the given character.
+    fn identifiers<'a>(source: &'a str, prefix: &str, followed_by: char) -> HashSet<&'a str> {
+        source
+            .match_indices(prefix)
+            .filter_map(|(i, _)| {
+                let rest = &source[i + prefix.len()..];
+                let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_'))?;
+                rest[end..].starts_with(followed_by).then_some(&rest[..end])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn header_declares_all_exported_functions() {
+        let exported = identifiers(SOURCES[0], "extern \"C\" fn aleph_", '(');
+        let declared = identifiers(HEADER, "aleph_", '(');
+        assert!(!exported.is_empty());
+        assert_eq!(exported, declared);
+    }
+
+    #[test]
+    fn header_declares_all_callback_fields() {
+        let fields = identifiers(SOURCES[1], "    pub ", ':');
+        assert!(!fields.is_empty());
+        for field in fields {
+            assert!(
+                HEADER.contains(&format!("{})(", field))
+                    || HEADER.contains(&format!("*{};", field)),
+                "{} is missing from the header",
+                field
+            );
+        }
+    }
+
+    #[test]
+    fn header_constants_match() {
+        for (name, value) in [
+            ("ALEPH_OK", ALEPH_OK.to_string()),
+            ("ALEPH_FINISHED", ALEPH_FINISHED.to_string()),
+            ("ALEPH_INVALID_ARGUMENT", ALEPH_INVALID_ARGUMENT.to_string()),
+            ("ALEPH_ERROR", ALEPH_ERROR.to_string()),
+            (
+                "ALEPH_RECIPIENT_EVERYONE",
+                ALEPH_RECIPIENT_EVERYONE.to_string(),
+            ),
+            (
+                "ALEPH_MAX_SIGNATURE_SIZE",
+                ALEPH_MAX_SIGNATURE_SIZE.to_string(),
+            ),
+            ("ALEPH_MAX_DATA_SIZE", ALEPH_MAX_DATA_SIZE.to_string()),
+        ] {
+            let definition = format!("#define {} ({})", name, value);
+            assert!(HEADER.contains(&definition), "missing {}", definition);
+        }
+    }
+}
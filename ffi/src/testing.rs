@@ -0,0 +1,251 @@
+use crate::{
+    aleph_config_create, aleph_config_destroy, aleph_session_create, aleph_session_destroy,
+    aleph_session_receive, aleph_session_run_step, aleph_session_stop, AlephBackupCallbacks,
+    AlephDataCallbacks, AlephKeychainCallbacks, AlephNetworkCallbacks, AlephSession,
+    ALEPH_FINISHED, ALEPH_INVALID_ARGUMENT, ALEPH_OK, ALEPH_RECIPIENT_EVERYONE,
+};
+use parking_lot::Mutex;
+use std::{
+    ffi::c_void,
+    ptr, slice,
+    time::{Duration, Instant},
+};
+
+const N_MEMBERS: usize = 4;
+const ITEMS_PER_NODE: u8 = 5;
+
+/// The state of a mock host of a single node, shared with its callbacks.
+#[derive(Default)]
+struct Host {
+    index: usize,
+    outbox: Vec<(Vec<u8>, isize)>,
+    provided: u8,
+    finalized: Vec<Vec<u8>>,
+    backup: Vec<u8>,
+    backup_read: usize,
+}
+
+fn host<'a>(user_data: *mut c_void) -> &'a Mutex<Host> {
+    // SAFETY: the user data always points to a host living longer than the session.
+    unsafe { &*(user_data as *const Mutex<Host>) }
+}
+
+/// A signature is the index of the signer followed by the message.
+unsafe extern "C" fn sign(
+    user_data: *mut c_void,
+    message: *const u8,
+    message_len: usize,
+    signature: *mut u8,
+    signature_capacity: usize,
+) -> isize {
+    if message_len + 1 > signature_capacity {
+        return -1;
+    }
+    *signature = host(user_data).lock().index as u8;
+    ptr::copy_nonoverlapping(message, signature.add(1), message_len);
+    (message_len + 1) as isize
+}
+
+unsafe extern "C" fn verify(
+    _: *mut c_void,
+    message: *const u8,
+    message_len: usize,
+    signature: *const u8,
+    signature_len: usize,
+    signer: usize,
+) -> bool {
+    let message = slice::from_raw_parts(message, message_len);
+    let signature = slice::from_raw_parts(signature, signature_len);
+    signature.first() == Some(&(signer as u8)) && &signature[1..] == message
+}
+
+unsafe extern "C" fn send(
+    user_data: *mut c_void,
+    data: *const u8,
+    data_len: usize,
+    recipient: isize,
+) {
+    let data = slice::from_raw_parts(data, data_len).to_vec();
+    host(user_data).lock().outbox.push((data, recipient));
+}
+
+unsafe extern "C" fn get_data(user_data: *mut c_void, data: *mut u8, capacity: usize) -> isize {
+    let mut host = host(user_data).lock();
+    if host.provided == ITEMS_PER_NODE || capacity < 2 {
+        return -1;
+    }
+    *data = host.index as u8;
+    *data.add(1) = host.provided;
+    host.provided += 1;
+    2
+}
+
+unsafe extern "C" fn data_finalized(user_data: *mut c_void, data: *const u8, data_len: usize) {
+    let data = slice::from_raw_parts(data, data_len).to_vec();
+    host(user_data).lock().finalized.push(data);
+}
+
+unsafe extern "C" fn write_backup(
+    user_data: *mut c_void,
+    data: *const u8,
+    data_len: usize,
+) -> bool {
+    let data = slice::from_raw_parts(data, data_len);
+    host(user_data).lock().backup.extend_from_slice(data);
+    true
+}
+
+unsafe extern "C" fn read_backup(
+    user_data: *mut c_void,
+    buffer: *mut u8,
+    capacity: usize,
+) -> isize {
+    let mut host = host(user_data).lock();
+    let len = capacity.min(host.backup.len() - host.backup_read);
+    ptr::copy_nonoverlapping(host.backup[host.backup_read..].as_ptr(), buffer, len);
+    host.backup_read += len;
+    len as isize
+}
+
+/// Creates a session of the node whose host is at `user_data`.
+///
+/// # Safety
+/// The host has to outlive the session.
+unsafe fn create_session(index: usize, user_data: *mut c_void) -> *mut AlephSession {
+    let config = aleph_config_create(N_MEMBERS, index, 0);
+    assert!(!config.is_null());
+    let session = aleph_session_create(
+        config,
+        AlephKeychainCallbacks {
+            user_data,
+            sign: Some(sign),
+            verify: Some(verify),
+        },
+        AlephNetworkCallbacks {
+            user_data,
+            send: Some(send),
+        },
+        AlephDataCallbacks {
+            user_data,
+            get_data: Some(get_data),
+            data_finalized: Some(data_finalized),
+        },
+        AlephBackupCallbacks {
+            user_data,
+            write: Some(write_backup),
+            read: Some(read_backup),
+        },
+    );
+    aleph_config_destroy(config);
+    assert!(!session.is_null());
+    session
+}
+
+/// Stops the session and waits until it finishes, then destroys it.
+///
+/// # Safety
+/// The session has to be valid, it is not afterwards.
+unsafe fn finish_session(session: *mut AlephSession, start: Instant) {
+    aleph_session_stop(session);
+    while aleph_session_run_step(session) != ALEPH_FINISHED {
+        assert!(start.elapsed() < Duration::from_secs(90));
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    aleph_session_destroy(session);
+}
+
+#[test]
+fn sessions_finalize_data_through_the_c_abi() {
+    let hosts: Vec<_> = (0..N_MEMBERS)
+        .map(|index| {
+            Box::new(Mutex::new(Host {
+                index,
+                ..Host::default()
+            }))
+        })
+        .collect();
+    let sessions: Vec<_> = hosts
+        .iter()
+        .enumerate()
+        .map(|(index, host)| {
+            let user_data = host.as_ref() as *const Mutex<Host> as *mut c_void;
+            // SAFETY: the hosts outlive the sessions.
+            unsafe { create_session(index, user_data) }
+        })
+        .collect();
+
+    let all_items = N_MEMBERS * ITEMS_PER_NODE as usize;
+    let start = Instant::now();
+    let mut delivered = None;
+    // SAFETY: the sessions are valid until destroyed at the end.
+    unsafe {
+        while hosts
+            .iter()
+            .any(|host| host.lock().finalized.len() < all_items)
+        {
+            assert!(
+                start.elapsed() < Duration::from_secs(60),
+                "the sessions should finalize all the data"
+            );
+            for (sender, session) in sessions.iter().enumerate() {
+                assert_eq!(aleph_session_run_step(*session), ALEPH_OK);
+                let outbox: Vec<_> = hosts[sender].lock().outbox.drain(..).collect();
+                for (data, recipient) in outbox {
+                    for (index, recipient_session) in sessions.iter().enumerate() {
+                        let addressed =
+                            recipient == ALEPH_RECIPIENT_EVERYONE || recipient == index as isize;
+                        if addressed && index != sender {
+                            let result = aleph_session_receive(
+                                *recipient_session,
+                                data.as_ptr(),
+                                data.len(),
+                            );
+                            assert_eq!(result, ALEPH_OK);
+                            delivered.get_or_insert_with(|| (data.clone(), index));
+                        }
+                    }
+                }
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        // A leading `u8::MAX` marks a versioned message, so the garbage has to start otherwise.
+        assert_eq!(
+            aleph_session_receive(sessions[0], [u8::MAX - 1; 3].as_ptr(), 3),
+            ALEPH_INVALID_ARGUMENT
+        );
+        // A valid message followed by anything else has to be rejected as a whole.
+        let (mut data, recipient) = delivered.expect("the sessions exchanged messages");
+        data.push(0);
+        assert_eq!(
+            aleph_session_receive(sessions[recipient], data.as_ptr(), data.len()),
+            ALEPH_INVALID_ARGUMENT
+        );
+        for session in &sessions {
+            finish_session(*session, start);
+        }
+    }
+
+    let finalized = hosts[0].lock().finalized.clone();
+    let mut items = finalized.clone();
+    items.sort();
+    items.dedup();
+    assert_eq!(items.len(), all_items);
+    for host in &hosts[1..] {
+        assert_eq!(host.lock().finalized, finalized);
+    }
+
+    // The restarted node reads back the whole backup of its previous run.
+    let mut host = hosts[0].lock();
+    assert!(!host.backup.is_empty());
+    let backup_len = host.backup.len();
+    host.backup_read = 0;
+    drop(host);
+    let user_data = hosts[0].as_ref() as *const Mutex<Host> as *mut c_void;
+    // SAFETY: the host outlives the session, which is destroyed at the end.
+    unsafe {
+        let session = create_session(0, user_data);
+        assert_eq!(aleph_session_run_step(session), ALEPH_OK);
+        assert_eq!(hosts[0].lock().backup_read, backup_len);
+        finish_session(session, start);
+    }
+}
@@ -1,18 +1,63 @@
 use crate::crypto::{PartialMultisignature, Signature};
 use aleph_bft_types::{
     Index, Keychain as KeychainT, MultiKeychain as MultiKeychainT, NodeCount, NodeIndex,
-    PartialMultisignature as PartialMultisignatureT, SignatureSet,
+    NodeWeights, PartialMultisignature as PartialMultisignatureT, SignatureSet,
 };
+use std::{convert::Infallible, sync::Arc, thread, time::Duration};
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+/// Marks the messages of signatures made with keys other than the first one.
+const KEY_TAG: &[u8] = b"KEY";
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
 pub struct Keychain {
     count: NodeCount,
     index: NodeIndex,
+    verification_delay: Duration,
+    key: u8,
+    key_count: u8,
+    node_weights: Option<Arc<NodeWeights>>,
 }
 
 impl Keychain {
     pub fn new(count: NodeCount, index: NodeIndex) -> Self {
-        Keychain { count, index }
+        Keychain {
+            count,
+            index,
+            verification_delay: Duration::ZERO,
+            key: 0,
+            key_count: 1,
+            node_weights: None,
+        }
+    }
+
+    /// Makes multisignatures complete once their signers weigh enough, instead of once there are
+    /// enough of them. The weights are shared by the clones of the keychain.
+    pub fn with_node_weights(self, node_weights: NodeWeights) -> Self {
+        Keychain {
+            node_weights: Some(Arc::new(node_weights)),
+            ..self
+        }
+    }
+
+    /// Makes every verification of a signature block for the given time, as if the signatures
+    /// were expensive to check.
+    pub fn with_verification_delay(self, verification_delay: Duration) -> Self {
+        Keychain {
+            verification_delay,
+            ..self
+        }
+    }
+
+    /// Makes the signatures made with any of the first `key_count` keys of every node verify, as
+    /// if the keys were rotated during the session. There is a single key by default.
+    pub fn with_key_count(self, key_count: u8) -> Self {
+        Keychain { key_count, ..self }
+    }
+
+    /// Makes the keychain sign with the given key of the node instead of the first one, see
+    /// [`Self::with_key_count`].
+    pub fn with_key(self, key: u8) -> Self {
+        Keychain { key, ..self }
     }
 
     pub fn new_vec(node_count: NodeCount) -> Vec<Self> {
@@ -30,17 +75,36 @@ impl Index for Keychain {
 
 impl KeychainT for Keychain {
     type Signature = Signature;
+    type SignError = Infallible;
 
     fn node_count(&self) -> NodeCount {
         self.count
     }
 
-    fn sign(&self, msg: &[u8]) -> Self::Signature {
-        Signature::new(msg.to_vec(), self.index)
+    fn sign(&self, msg: &[u8]) -> Result<Self::Signature, Self::SignError> {
+        let msg = match self.key {
+            0 => msg.to_vec(),
+            key => [KEY_TAG, &[key], msg].concat(),
+        };
+        Ok(Signature::new(msg, self.index))
     }
 
     fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
-        index == sgn.index() && msg == sgn.msg()
+        if !self.verification_delay.is_zero() {
+            thread::sleep(self.verification_delay);
+        }
+        if index != sgn.index() {
+            return false;
+        }
+        let signed_with_other_key = match sgn.msg().strip_prefix(KEY_TAG) {
+            Some([key, signed @ ..]) => *key != 0 && *key < self.key_count && msg == signed,
+            _ => false,
+        };
+        signed_with_other_key || msg == sgn.msg()
+    }
+
+    fn is_verification_cheap(&self) -> bool {
+        self.verification_delay.is_zero()
     }
 }
 
@@ -56,8 +120,11 @@ impl MultiKeychainT for Keychain {
     }
 
     fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool {
-        let signature_count = partial.iter().count();
-        if signature_count < self.node_count().consensus_threshold().0 {
+        let complete = match &self.node_weights {
+            Some(node_weights) => node_weights.is_quorum(partial.iter().map(|(i, _)| i)),
+            None => partial.iter().count() >= self.node_count().consensus_threshold().0,
+        };
+        if !complete {
             return false;
         }
         partial.iter().all(|(i, sgn)| self.verify(msg, sgn, i))
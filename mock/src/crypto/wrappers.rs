@@ -3,7 +3,13 @@ use aleph_bft_types::{
     Index, Keychain as KeychainT, MultiKeychain as MultiKeychainT, NodeCount, NodeIndex,
 };
 use codec::{Decode, Encode};
-use std::fmt::Debug;
+use std::{
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 pub trait MK:
     KeychainT<Signature = Signature> + MultiKeychainT<PartialMultisignature = PartialMultisignature>
@@ -35,21 +41,26 @@ impl<T: MK> Index for BadSigning<T> {
 
 impl<T: MK> KeychainT for BadSigning<T> {
     type Signature = T::Signature;
+    type SignError = T::SignError;
 
     fn node_count(&self) -> NodeCount {
         self.0.node_count()
     }
 
-    fn sign(&self, msg: &[u8]) -> Self::Signature {
-        let signature = self.0.sign(msg);
+    fn sign(&self, msg: &[u8]) -> Result<Self::Signature, Self::SignError> {
+        let signature = self.0.sign(msg)?;
         let mut msg = b"BAD".to_vec();
         msg.extend(signature.msg().clone());
-        Signature::new(msg, signature.index())
+        Ok(Signature::new(msg, signature.index()))
     }
 
     fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
         self.0.verify(msg, sgn, index)
     }
+
+    fn is_verification_cheap(&self) -> bool {
+        self.0.is_verification_cheap()
+    }
 }
 
 impl<T: MK> MultiKeychainT for BadSigning<T> {
@@ -67,3 +78,169 @@ impl<T: MK> MultiKeychainT for BadSigning<T> {
         self.0.is_complete(msg, partial)
     }
 }
+
+/// The error returned by [`FailingSigning`].
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub struct SigningFailure;
+
+impl Display for SigningFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "simulated signing failure")
+    }
+}
+
+/// Keychain wrapper which fails to sign whenever the given predicate holds for the number of
+/// the signing attempt, counting from zero. The counter is shared between clones.
+#[derive(Clone)]
+pub struct FailingSigning<T: MK> {
+    keychain: T,
+    attempts: Arc<AtomicUsize>,
+    fails: Arc<dyn Fn(usize) -> bool + Send + Sync>,
+}
+
+impl<T: MK> FailingSigning<T> {
+    pub fn new(keychain: T, fails: impl Fn(usize) -> bool + Send + Sync + 'static) -> Self {
+        FailingSigning {
+            keychain,
+            attempts: Arc::new(AtomicUsize::new(0)),
+            fails: Arc::new(fails),
+        }
+    }
+
+    /// The number of signing attempts so far.
+    pub fn attempts(&self) -> usize {
+        self.attempts.load(Ordering::SeqCst)
+    }
+}
+
+impl<T: MK> Debug for FailingSigning<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("FailingSigning")
+            .field("index", &self.index())
+            .field("attempts", &self.attempts())
+            .finish()
+    }
+}
+
+impl<T: MK> Index for FailingSigning<T> {
+    fn index(&self) -> NodeIndex {
+        self.keychain.index()
+    }
+}
+
+impl<T: MK> KeychainT for FailingSigning<T> {
+    type Signature = T::Signature;
+    type SignError = SigningFailure;
+
+    fn node_count(&self) -> NodeCount {
+        self.keychain.node_count()
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Self::Signature, Self::SignError> {
+        let attempt = self.attempts.fetch_add(1, Ordering::SeqCst);
+        if (self.fails)(attempt) {
+            return Err(SigningFailure);
+        }
+        self.keychain.sign(msg).map_err(|_| SigningFailure)
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+        self.keychain.verify(msg, sgn, index)
+    }
+
+    fn is_verification_cheap(&self) -> bool {
+        self.keychain.is_verification_cheap()
+    }
+}
+
+impl<T: MK> MultiKeychainT for FailingSigning<T> {
+    type PartialMultisignature = T::PartialMultisignature;
+
+    fn bootstrap_multi(
+        &self,
+        signature: &Self::Signature,
+        index: NodeIndex,
+    ) -> Self::PartialMultisignature {
+        self.keychain.bootstrap_multi(signature, index)
+    }
+
+    fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool {
+        self.keychain.is_complete(msg, partial)
+    }
+}
+
+/// Keychain wrapper which counts the verifications of signatures and multisignatures. The
+/// counter is shared between clones.
+#[derive(Clone)]
+pub struct CountingVerification<T: MK> {
+    keychain: T,
+    verifications: Arc<AtomicUsize>,
+}
+
+impl<T: MK> CountingVerification<T> {
+    pub fn new(keychain: T) -> Self {
+        CountingVerification {
+            keychain,
+            verifications: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// The number of verifications so far.
+    pub fn verifications(&self) -> usize {
+        self.verifications.load(Ordering::SeqCst)
+    }
+}
+
+impl<T: MK> Debug for CountingVerification<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("CountingVerification")
+            .field("index", &self.index())
+            .field("verifications", &self.verifications())
+            .finish()
+    }
+}
+
+impl<T: MK> Index for CountingVerification<T> {
+    fn index(&self) -> NodeIndex {
+        self.keychain.index()
+    }
+}
+
+impl<T: MK> KeychainT for CountingVerification<T> {
+    type Signature = T::Signature;
+    type SignError = T::SignError;
+
+    fn node_count(&self) -> NodeCount {
+        self.keychain.node_count()
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Self::Signature, Self::SignError> {
+        self.keychain.sign(msg)
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+        self.verifications.fetch_add(1, Ordering::SeqCst);
+        self.keychain.verify(msg, sgn, index)
+    }
+
+    fn is_verification_cheap(&self) -> bool {
+        self.keychain.is_verification_cheap()
+    }
+}
+
+impl<T: MK> MultiKeychainT for CountingVerification<T> {
+    type PartialMultisignature = T::PartialMultisignature;
+
+    fn bootstrap_multi(
+        &self,
+        signature: &Self::Signature,
+        index: NodeIndex,
+    ) -> Self::PartialMultisignature {
+        self.keychain.bootstrap_multi(signature, index)
+    }
+
+    fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool {
+        self.verifications.fetch_add(1, Ordering::SeqCst);
+        self.keychain.is_complete(msg, partial)
+    }
+}
@@ -1,15 +1,26 @@
-use aleph_bft_types::{DataProvider as DataProviderT, FinalizationHandler as FinalizationHandlerT};
+use crate::Hash64;
+use aleph_bft_types::{
+    DataProvider as DataProviderT, DeliveryCheckpoint, FinalizationHandler as FinalizationHandlerT,
+    FinalizationStateStore as FinalizationStateStoreT, Lease, LeaseStore as LeaseStoreT,
+};
 use async_trait::async_trait;
 use codec::{Decode, Encode};
-use futures::{channel::mpsc::unbounded, future::pending, AsyncWrite};
+use futures::{channel::mpsc::unbounded, future::pending, AsyncWrite, Future};
 use log::error;
 use parking_lot::Mutex;
 use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter, Result as FmtResult},
     io::{self},
     pin::Pin,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     task::{self, Poll},
+    time::Duration,
 };
+use tokio::time::{sleep, Sleep};
 
 type Receiver<T> = futures::channel::mpsc::UnboundedReceiver<T>;
 type Sender<T> = futures::channel::mpsc::UnboundedSender<T>;
@@ -78,6 +89,51 @@ impl DataProviderT for StalledDataProvider {
     }
 }
 
+#[derive(Clone, Copy, Debug)]
+enum Stall {
+    None,
+    For(Duration),
+}
+
+/// Provides the data of the wrapped [`DataProvider`], but can be told to stall, answering only
+/// after a delay, see [`StalledDataProvider`] for one never answering. The data is taken before stalling, so a request dropped while
+/// it stalls loses it. The clones share the stall, so a clone can stall a provider passed to a
+/// session.
+#[derive(Clone, Debug)]
+pub struct StallingDataProvider {
+    inner: DataProvider,
+    stall: Arc<Mutex<Stall>>,
+}
+
+impl StallingDataProvider {
+    pub fn new(inner: DataProvider) -> Self {
+        Self {
+            inner,
+            stall: Arc::new(Mutex::new(Stall::None)),
+        }
+    }
+
+    /// Answers the requests starting from now only after the given delay.
+    pub fn stall_for(&self, delay: Duration) {
+        *self.stall.lock() = Stall::For(delay);
+    }
+}
+
+#[async_trait]
+impl DataProviderT for StallingDataProvider {
+    type Output = Data;
+
+    async fn get_data(&mut self) -> Option<Data> {
+        let data = self.inner.get_data().await;
+        let stall = *self.stall.lock();
+        match stall {
+            Stall::None => {}
+            Stall::For(delay) => sleep(delay).await,
+        }
+        data
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct FinalizationHandler {
     tx: Sender<Data>,
@@ -99,6 +155,85 @@ impl FinalizationHandler {
     }
 }
 
+#[derive(Debug, Default)]
+struct FinalizationState {
+    checkpoint: Option<DeliveryCheckpoint<Hash64>>,
+    index: HashMap<Hash64, u64>,
+    batch_ids: HashMap<u64, Hash64>,
+}
+
+/// Keeps the finalization state in memory. The clones share the state, so a clone can be passed
+/// to a restarted session.
+#[derive(Clone, Debug, Default)]
+pub struct FinalizationStateStore {
+    state: Arc<Mutex<FinalizationState>>,
+}
+
+impl FinalizationStateStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl FinalizationStateStoreT<Hash64> for FinalizationStateStore {
+    fn put_checkpoint(&mut self, checkpoint: DeliveryCheckpoint<Hash64>) -> io::Result<()> {
+        self.state.lock().checkpoint = Some(checkpoint);
+        Ok(())
+    }
+
+    fn get_checkpoint(&self) -> io::Result<Option<DeliveryCheckpoint<Hash64>>> {
+        Ok(self.state.lock().checkpoint)
+    }
+
+    fn put_index_entries(&mut self, entries: Vec<(Hash64, u64)>) -> io::Result<()> {
+        self.state.lock().index.extend(entries);
+        Ok(())
+    }
+
+    fn get_index_entry(&self, unit: &Hash64) -> io::Result<Option<u64>> {
+        Ok(self.state.lock().index.get(unit).copied())
+    }
+
+    fn put_batch_id(&mut self, batch: u64, id: Hash64) -> io::Result<()> {
+        self.state.lock().batch_ids.insert(batch, id);
+        Ok(())
+    }
+
+    fn get_batch_id(&self, batch: u64) -> io::Result<Option<Hash64>> {
+        Ok(self.state.lock().batch_ids.get(&batch).copied())
+    }
+
+    fn prune_below(&mut self, batch: u64) -> io::Result<()> {
+        let mut state = self.state.lock();
+        state.index.retain(|_, number| *number >= batch);
+        state.batch_ids.retain(|number, _| *number >= batch);
+        Ok(())
+    }
+}
+
+/// Keeps the lease in memory. The clones share the lease, like instances sharing a storage.
+#[derive(Clone, Debug, Default)]
+pub struct LeaseStore {
+    lease: Arc<Mutex<Option<Lease>>>,
+}
+
+impl LeaseStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl LeaseStoreT for LeaseStore {
+    fn put_lease(&mut self, lease: Lease) -> io::Result<()> {
+        *self.lease.lock() = Some(lease);
+        Ok(())
+    }
+
+    fn get_lease(&self) -> io::Result<Option<Lease>> {
+        Ok(*self.lease.lock())
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct Saver {
     data: Arc<Mutex<Vec<u8>>>,
@@ -137,4 +272,151 @@ impl From<Arc<Mutex<Vec<u8>>>> for Saver {
     }
 }
 
+/// Saves like [`Saver`], but every write is pending once before it completes, like a write
+/// to a remote medium would be. The writes for which `fails` returns true, given the number of
+/// the write, fail instead.
+#[derive(Clone)]
+pub struct FailingSaver {
+    saver: Saver,
+    writes: Arc<AtomicUsize>,
+    fails: Arc<dyn Fn(usize) -> bool + Send + Sync>,
+    pending: bool,
+}
+
+impl FailingSaver {
+    pub fn new(saver: Saver, fails: impl Fn(usize) -> bool + Send + Sync + 'static) -> Self {
+        FailingSaver {
+            saver,
+            writes: Arc::new(AtomicUsize::new(0)),
+            fails: Arc::new(fails),
+            pending: false,
+        }
+    }
+
+    /// The number of completed or failed writes so far.
+    pub fn writes(&self) -> usize {
+        self.writes.load(Ordering::SeqCst)
+    }
+}
+
+impl Debug for FailingSaver {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("FailingSaver")
+            .field("writes", &self.writes())
+            .finish()
+    }
+}
+
+impl AsyncWrite for FailingSaver {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if !this.pending {
+            this.pending = true;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        this.pending = false;
+        let write = this.writes.fetch_add(1, Ordering::SeqCst);
+        if (this.fails)(write) {
+            return Poll::Ready(Err(io::Error::other("injected backup write failure")));
+        }
+        Pin::new(&mut this.saver).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().saver).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().saver).poll_close(cx)
+    }
+}
+
+/// Saves like [`Saver`], but panics on the write with the given number, counting from zero,
+/// like a buggy writer would.
+pub struct PanickingSaver {
+    saver: Saver,
+    writes: usize,
+    panic_at: usize,
+}
+
+impl PanickingSaver {
+    pub fn new(saver: Saver, panic_at: usize) -> Self {
+        PanickingSaver {
+            saver,
+            writes: 0,
+            panic_at,
+        }
+    }
+}
+
+impl AsyncWrite for PanickingSaver {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.writes == this.panic_at {
+            panic!("backup write number {} panicked", this.panic_at);
+        }
+        this.writes += 1;
+        Pin::new(&mut this.saver).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().saver).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().saver).poll_close(cx)
+    }
+}
+
+/// Saves like [`Saver`], but every write takes the given time, like writes to a slow disk do.
+pub struct SlowSaver {
+    saver: Saver,
+    delay: Duration,
+    write: Option<Pin<Box<Sleep>>>,
+}
+
+impl SlowSaver {
+    pub fn new(saver: Saver, delay: Duration) -> Self {
+        SlowSaver {
+            saver,
+            delay,
+            write: None,
+        }
+    }
+}
+
+impl AsyncWrite for SlowSaver {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let delay = this.delay;
+        let write = this.write.get_or_insert_with(|| Box::pin(sleep(delay)));
+        if write.as_mut().poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        this.write = None;
+        Pin::new(&mut this.saver).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().saver).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().saver).poll_close(cx)
+    }
+}
+
 pub type Loader = futures::io::Cursor<Vec<u8>>;
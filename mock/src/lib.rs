@@ -6,11 +6,18 @@ mod hasher;
 mod network;
 mod spawner;
 
-pub use crypto::{BadSigning, Keychain, PartialMultisignature, Signable, Signature};
-pub use dataio::{Data, DataProvider, FinalizationHandler, Loader, Saver, StalledDataProvider};
+pub use aleph_bft_types::TokioClock;
+pub use crypto::{
+    BadSigning, CountingVerification, FailingSigning, Keychain, PartialMultisignature, Signable,
+    Signature, SigningFailure,
+};
+pub use dataio::{
+    Data, DataProvider, FailingSaver, FinalizationHandler, FinalizationStateStore, LeaseStore,
+    Loader, PanickingSaver, Saver, SlowSaver, StalledDataProvider, StallingDataProvider,
+};
 pub use hasher::{Hash64, Hasher64};
 pub use network::{
-    Network, NetworkHook, NetworkReceiver, NetworkSender, Peer, ReconnectSender, Router,
-    UnreliableHook,
+    Network, NetworkGaps, NetworkHook, NetworkReceiver, NetworkSender, Peer, ReconnectSender,
+    Router, UnreliableHook,
 };
-pub use spawner::Spawner;
+pub use spawner::{KillableSpawner, Spawner};
@@ -7,23 +7,58 @@ use futures::{
     Future, StreamExt,
 };
 use log::debug;
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::{
     cell::RefCell,
     collections::HashMap,
     fmt::{Debug, Formatter},
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
+    time::{Duration, Instant},
 };
 
 pub type NetworkReceiver<D> = UnboundedReceiver<(D, NodeIndex)>;
 pub type NetworkSender<D> = UnboundedSender<(D, NodeIndex)>;
 
+/// Simulates outages of a [`Network`]: during a gap it yields no events, declaring the end of
+/// events transient, and once closed it yields no events for good. Messages sent to the node
+/// in the meantime wait for the end of the gap.
+#[derive(Clone, Debug, Default)]
+pub struct NetworkGaps {
+    gap_until: Arc<Mutex<Option<Instant>>>,
+    closed: Arc<Mutex<bool>>,
+}
+
+impl NetworkGaps {
+    /// Makes the network yield no events for the given duration, starting now.
+    pub fn gap_for(&self, duration: Duration) {
+        *self.gap_until.lock() = Some(Instant::now() + duration);
+    }
+
+    /// Closes the network for good.
+    pub fn close(&self) {
+        *self.closed.lock() = true;
+    }
+
+    fn in_gap(&self) -> bool {
+        matches!(*self.gap_until.lock(), Some(until) if Instant::now() < until)
+    }
+
+    fn is_closed(&self) -> bool {
+        *self.closed.lock()
+    }
+}
+
 #[derive(Debug)]
 pub struct Network<D: Debug> {
     rx: NetworkReceiver<D>,
     tx: NetworkSender<D>,
     peers: Vec<NodeIndex>,
     index: NodeIndex,
+    gaps: NetworkGaps,
+    in_gap: bool,
 }
 
 impl<D: Debug> Network<D> {
@@ -38,9 +73,16 @@ impl<D: Debug> Network<D> {
             tx,
             peers,
             index,
+            gaps: NetworkGaps::default(),
+            in_gap: false,
         }
     }
 
+    /// A handle for simulating outages of this network.
+    pub fn gaps(&self) -> NetworkGaps {
+        self.gaps.clone()
+    }
+
     pub fn index(&self) -> NodeIndex {
         self.index
     }
@@ -70,8 +112,16 @@ impl<D: Clone + Send + Debug + 'static> NetworkT<D> for Network<D> {
     }
 
     async fn next_event(&mut self) -> Option<D> {
+        self.in_gap = !self.gaps.is_closed() && self.gaps.in_gap();
+        if self.in_gap || self.gaps.is_closed() {
+            return None;
+        }
         Some(self.rx.next().await?.0)
     }
+
+    fn is_terminated(&self) -> bool {
+        !self.in_gap
+    }
 }
 
 pub struct Peer<D> {
@@ -124,6 +174,8 @@ pub struct Router<D: Debug> {
     peer_list: Vec<NodeIndex>,
     hook_list: RefCell<Vec<Box<dyn NetworkHook<D>>>>,
     peer_reconnect_rx: ReconnectReceiver<D>,
+    scheduler: Option<StdRng>,
+    pending: Vec<(D, NodeIndex, NodeIndex)>,
 }
 
 impl<D: Debug> Debug for Router<D> {
@@ -146,6 +198,8 @@ impl<D: Debug> Router<D> {
             peer_list,
             hook_list: RefCell::new(Vec::new()),
             peer_reconnect_rx,
+            scheduler: None,
+            pending: Vec::new(),
         };
         let mut networks = Vec::new();
         for ix in n_members.into_iterator() {
@@ -159,6 +213,14 @@ impl<D: Debug> Router<D> {
         self.hook_list.borrow_mut().push(Box::new(hook));
     }
 
+    /// Delivers the messages one at a time, each time picking one of the pending messages drawn
+    /// from a generator seeded with the given seed, instead of all of them in the order they were
+    /// sent. Together with a single threaded runtime it makes the order of delivery depend on the
+    /// seed.
+    pub fn deliver_in_seeded_order(&mut self, seed: u64) {
+        self.scheduler = Some(StdRng::seed_from_u64(seed));
+    }
+
     pub fn connect_peer(&mut self, peer: NodeIndex) -> Network<D> {
         assert!(
             self.peer_list.iter().any(|p| *p == peer),
@@ -183,21 +245,26 @@ impl<D: Debug> Router<D> {
     }
 }
 
-impl<D: Debug> Future for Router<D> {
+impl<D: Debug + Unpin> Future for Router<D> {
     type Output = ();
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = &mut self;
         let mut disconnected_peers: Vec<NodeIndex> = Vec::new();
         let mut buffer = Vec::new();
-        for (peer_id, peer) in this.peers.borrow_mut().iter_mut() {
+        let mut peers = this.peers.borrow_mut();
+        // The peers are polled in order, so that the messages are buffered in the same order.
+        let mut peer_ids: Vec<_> = peers.keys().cloned().collect();
+        peer_ids.sort();
+        for peer_id in peer_ids {
+            let peer = peers.get_mut(&peer_id).expect("just listed");
             loop {
                 // this call is responsible for waking this Future
                 match peer.rx.poll_next_unpin(cx) {
                     Poll::Ready(Some((data, recipient))) => {
-                        buffer.push((data, *peer_id, recipient));
+                        buffer.push((data, peer_id, recipient));
                     }
                     Poll::Ready(None) => {
-                        disconnected_peers.push(*peer_id);
+                        disconnected_peers.push(peer_id);
                         break;
                     }
                     Poll::Pending => {
@@ -206,6 +273,7 @@ impl<D: Debug> Future for Router<D> {
                 }
             }
         }
+        drop(peers);
         for peer_id in disconnected_peers {
             this.peers.borrow_mut().remove(&peer_id);
         }
@@ -233,6 +301,24 @@ impl<D: Debug> Future for Router<D> {
             buffer = new_buffer;
             new_buffer = Vec::new();
         }
+        let Router {
+            scheduler, pending, ..
+        } = &mut **this;
+        let buffer = match scheduler {
+            Some(scheduler) => {
+                pending.append(&mut buffer);
+                match pending.is_empty() {
+                    true => Vec::new(),
+                    false => {
+                        let next = scheduler.gen_range(0..pending.len());
+                        // The remaining messages are delivered in the next polls.
+                        cx.waker().wake_by_ref();
+                        vec![pending.remove(next)]
+                    }
+                }
+            }
+            None => buffer,
+        };
         for (data, sender, recipient) in buffer {
             if let Some(peer) = this.peers.borrow().get(&recipient) {
                 peer.tx.unbounded_send((data, sender)).ok();
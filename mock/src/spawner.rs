@@ -1,6 +1,9 @@
 use aleph_bft_types::{SpawnHandle, TaskHandle};
 use codec::{Decode, Encode};
 use futures::{channel::oneshot, Future};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Decode, Encode)]
 pub struct Spawner;
@@ -29,3 +32,55 @@ impl Spawner {
         Spawner {}
     }
 }
+
+/// A spawner with a kill switch, which abruptly stops all the tasks spawned with it,
+/// as if the process running them crashed.
+#[derive(Clone, Debug, Default)]
+pub struct KillableSpawner {
+    tasks: Arc<Mutex<Vec<JoinHandle<()>>>>,
+}
+
+impl SpawnHandle for KillableSpawner {
+    fn spawn(&self, _name: &str, task: impl Future<Output = ()> + Send + 'static) {
+        let mut tasks = self.tasks.lock();
+        tasks.retain(|task| !task.is_finished());
+        tasks.push(tokio::spawn(task));
+    }
+
+    fn spawn_essential(
+        &self,
+        _: &str,
+        task: impl Future<Output = ()> + Send + 'static,
+    ) -> TaskHandle {
+        let (res_tx, res_rx) = oneshot::channel();
+        self.spawn("essential", async move {
+            task.await;
+            let _ = res_tx.send(());
+        });
+        Box::pin(async move { res_rx.await.map_err(|_| ()) })
+    }
+}
+
+impl KillableSpawner {
+    pub fn new() -> Self {
+        KillableSpawner::default()
+    }
+
+    /// Aborts all the tasks spawned so far and waits until all of them are dropped.
+    /// Handles of the essential tasks return an error afterwards.
+    pub async fn kill(&self) {
+        // Tasks might manage to spawn new ones before getting aborted.
+        loop {
+            let tasks: Vec<_> = self.tasks.lock().drain(..).collect();
+            if tasks.is_empty() {
+                return;
+            }
+            for task in &tasks {
+                task.abort();
+            }
+            for task in tasks {
+                let _ = task.await;
+            }
+        }
+    }
+}
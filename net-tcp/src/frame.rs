@@ -0,0 +1,74 @@
+use std::io::{Error, ErrorKind, Result};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Writes the payload preceded by its length, as 4 big-endian bytes.
+pub async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, payload: &[u8]) -> Result<()> {
+    let len = u32::try_from(payload.len())
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, "frame too large to send"))?;
+    writer.write_all(&len.to_be_bytes()).await?;
+    writer.write_all(payload).await?;
+    writer.flush().await
+}
+
+/// Reads a payload written by [`write_frame`], failing on one larger than the given size without
+/// reading it, as the peer sending it is misbehaving. `Ok(None)` means the stream ended cleanly.
+pub async fn read_frame<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    max_size: usize,
+) -> Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len).await {
+        Ok(_) => (),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    if len > max_size {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("frame of {len} bytes exceeds the limit of {max_size}"),
+        ));
+    }
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).await?;
+    Ok(Some(payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frame::{read_frame, write_frame};
+    use std::io::ErrorKind;
+
+    #[tokio::test]
+    async fn reads_frames_back() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, b"aleph").await.unwrap();
+        write_frame(&mut buffer, b"").await.unwrap();
+        assert_eq!(&buffer[..4], &[0, 0, 0, 5]);
+
+        let mut reader = &buffer[..];
+        assert_eq!(
+            read_frame(&mut reader, 5).await.unwrap(),
+            Some(b"aleph".to_vec())
+        );
+        assert_eq!(read_frame(&mut reader, 5).await.unwrap(), Some(Vec::new()));
+        assert_eq!(read_frame(&mut reader, 5).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn rejects_too_large_frames() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, b"aleph").await.unwrap();
+        let error = read_frame(&mut &buffer[..], 4).await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn fails_on_truncated_frames() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, b"aleph").await.unwrap();
+        buffer.pop();
+        let error = read_frame(&mut &buffer[..], 5).await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,396 @@
+//! A [`Network`] over TCP, for running AlephBFT between nodes with known addresses.
+//!
+//! * Every node keeps a single connection to every peer for the messages it sends to that peer,
+//!   and accepts the connections of its peers for the messages they send to it.
+//! * Messages are encoded with SCALE and sent in frames, each preceded by its length as 4
+//!   big-endian bytes. A connection starts with a frame holding the index of the connecting node.
+//! * A broken connection is established again, waiting longer after every failed attempt, up to
+//!   a limit.
+//! * Every peer has a bounded queue of messages waiting to be sent to it. When it is full, e.g.
+//!   because the peer is down, new messages for the peer are dropped, as AlephBFT resends
+//!   whatever is still needed.
+//!
+//! The index a connecting node claims is not authenticated, so the messages have to be signed,
+//! as the ones of AlephBFT are, and the network has no protection against spamming peers.
+
+use aleph_bft_types::{Network, NodeIndex, Recipient};
+use codec::{Decode, Encode};
+use log::debug;
+use std::{collections::HashMap, io, net::SocketAddr, time::Duration};
+use tokio::{
+    net::TcpListener,
+    sync::mpsc::{self, error::TrySendError, Receiver, Sender},
+    task::JoinHandle,
+};
+
+mod frame;
+mod peer;
+mod status;
+
+pub use status::{ConnectionStatus, PeerStatus};
+
+const LOG_TARGET: &str = "AlephBFT-net-tcp";
+
+/// How many messages may wait for a single peer, and how many received ones may wait for the
+/// session, by default.
+pub const DEFAULT_QUEUE_SIZE: usize = 1024;
+
+/// The size of the largest message accepted by default, in bytes.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// The settings of a [`TcpNetwork`].
+#[derive(Clone, Debug)]
+pub struct TcpNetworkConfig {
+    /// How many messages may wait for a single peer, and how many received ones may wait for
+    /// the session.
+    pub queue_size: usize,
+    /// The size of the largest message accepted, in bytes. A peer sending a larger one is
+    /// disconnected.
+    pub max_frame_size: usize,
+    /// How long to wait before connecting again after the first failed attempt.
+    pub min_backoff: Duration,
+    /// The longest wait between attempts to connect.
+    pub max_backoff: Duration,
+}
+
+impl Default for TcpNetworkConfig {
+    fn default() -> Self {
+        TcpNetworkConfig {
+            queue_size: DEFAULT_QUEUE_SIZE,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            min_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl TcpNetworkConfig {
+    pub fn with_queue_size(self, queue_size: usize) -> Self {
+        TcpNetworkConfig { queue_size, ..self }
+    }
+
+    pub fn with_max_frame_size(self, max_frame_size: usize) -> Self {
+        TcpNetworkConfig {
+            max_frame_size,
+            ..self
+        }
+    }
+
+    pub fn with_backoff(self, min_backoff: Duration, max_backoff: Duration) -> Self {
+        TcpNetworkConfig {
+            min_backoff,
+            max_backoff,
+            ..self
+        }
+    }
+}
+
+/// A [`Network`] keeping a TCP connection to every node in its address book. The tasks handling
+/// the connections run on the tokio runtime it was created in, until it is dropped.
+pub struct TcpNetwork<M> {
+    own_index: NodeIndex,
+    outgoing: HashMap<NodeIndex, Sender<M>>,
+    loopback: Sender<M>,
+    incoming: Receiver<M>,
+    status: ConnectionStatus,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl<M: Encode + Decode + Send + 'static> TcpNetwork<M> {
+    /// Listens on the address of the node in the address book, connecting to all the others.
+    pub async fn bind(
+        own_index: NodeIndex,
+        addresses: HashMap<NodeIndex, SocketAddr>,
+        config: TcpNetworkConfig,
+    ) -> io::Result<Self> {
+        let own_address = addresses.get(&own_index).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the node is not in the address book",
+            )
+        })?;
+        let listener = TcpListener::bind(own_address).await?;
+        Ok(Self::with_listener(own_index, listener, addresses, config))
+    }
+
+    /// Accepts connections with the given listener, connecting to all the other nodes in the
+    /// address book. Useful when the port is chosen by the system, so the address book can only
+    /// be completed after binding.
+    pub fn with_listener(
+        own_index: NodeIndex,
+        listener: TcpListener,
+        addresses: HashMap<NodeIndex, SocketAddr>,
+        config: TcpNetworkConfig,
+    ) -> Self {
+        let peers: Vec<_> = addresses
+            .into_iter()
+            .filter(|(peer, _)| *peer != own_index)
+            .collect();
+        let status = ConnectionStatus::new(peers.iter().map(|(peer, _)| *peer));
+        let (loopback, incoming) = mpsc::channel(config.queue_size);
+        let mut tasks = vec![tokio::spawn(peer::run_listener(
+            own_index,
+            listener,
+            config.max_frame_size,
+            loopback.clone(),
+            status.clone(),
+        ))];
+        let mut outgoing = HashMap::new();
+        for (peer, address) in peers {
+            let (for_peer, queue) = mpsc::channel(config.queue_size);
+            tasks.push(tokio::spawn(peer::run_outgoing(
+                own_index,
+                peer,
+                address,
+                queue,
+                config.clone(),
+                status.clone(),
+            )));
+            outgoing.insert(peer, for_peer);
+        }
+        TcpNetwork {
+            own_index,
+            outgoing,
+            loopback,
+            incoming,
+            status,
+            tasks,
+        }
+    }
+}
+
+impl<M> TcpNetwork<M> {
+    /// A handle to the state of the connections, which stays valid after the network is moved.
+    pub fn status(&self) -> ConnectionStatus {
+        self.status.clone()
+    }
+
+    fn send_to(&self, data: M, peer: NodeIndex) {
+        if peer == self.own_index {
+            if self.loopback.try_send(data).is_err() {
+                debug!(target: LOG_TARGET, "Dropped a message to ourselves.");
+            }
+            return;
+        }
+        let Some(for_peer) = self.outgoing.get(&peer) else {
+            debug!(target: LOG_TARGET, "Dropped a message to unknown node {}.", peer.0);
+            return;
+        };
+        match for_peer.try_send(data) {
+            Ok(()) => (),
+            Err(TrySendError::Full(_)) => self.status.update(peer, |status| status.dropped += 1),
+            Err(TrySendError::Closed(_)) => {
+                debug!(target: LOG_TARGET, "Dropped a message to {}, as its connection is gone.", peer.0)
+            }
+        }
+    }
+}
+
+impl<M> Drop for TcpNetwork<M> {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: Clone + Send + 'static> Network<M> for TcpNetwork<M> {
+    fn send(&self, data: M, recipient: Recipient) {
+        match recipient {
+            Recipient::Node(peer) => self.send_to(data, peer),
+            Recipient::Everyone => {
+                for peer in self.outgoing.keys() {
+                    self.send_to(data.clone(), *peer);
+                }
+            }
+        }
+    }
+
+    async fn next_event(&mut self) -> Option<M> {
+        self.incoming.recv().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{TcpNetwork, TcpNetworkConfig};
+    use aleph_bft::{
+        create_config, run_session, DelayConfig, LocalIO, Network as _, NodeCount, NodeIndex,
+        Recipient, SpawnHandle, Terminator,
+    };
+    use aleph_bft_mock::{
+        Data, DataProvider, FinalizationHandler, Hasher64, Keychain, Loader, PartialMultisignature,
+        Saver, Signature, Spawner,
+    };
+    use futures::{channel::oneshot, StreamExt};
+    use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
+    use tokio::{
+        net::TcpListener,
+        time::{sleep, timeout},
+    };
+
+    type NetworkData = aleph_bft::NetworkData<Hasher64, Data, Signature, PartialMultisignature>;
+
+    const N_MEMBERS: NodeCount = NodeCount(4);
+    const N_DATA: usize = 10;
+
+    async fn listeners(n_members: NodeCount) -> (Vec<TcpListener>, HashMap<NodeIndex, SocketAddr>) {
+        let mut listeners = Vec::new();
+        let mut addresses = HashMap::new();
+        for node_ix in n_members.into_iterator() {
+            let listener = TcpListener::bind("127.0.0.1:0")
+                .await
+                .expect("binding to a free port works");
+            addresses.insert(
+                node_ix,
+                listener.local_addr().expect("the listener is bound"),
+            );
+            listeners.push(listener);
+        }
+        (listeners, addresses)
+    }
+
+    fn config() -> TcpNetworkConfig {
+        TcpNetworkConfig::default()
+            .with_backoff(Duration::from_millis(10), Duration::from_millis(100))
+    }
+
+    fn delay_config() -> DelayConfig {
+        DelayConfig {
+            tick_interval: Duration::from_millis(5),
+            unit_rebroadcast_interval_min: Duration::from_millis(400),
+            unit_rebroadcast_interval_max: Duration::from_millis(500),
+            unit_creation_delay: Arc::new(|_| Duration::from_millis(50)),
+            coord_request_delay: Arc::new(|_| Duration::from_millis(100)),
+            coord_request_recipients: Arc::new(|t| if t == 0 { 3 } else { 1 }),
+            parent_request_delay: Arc::new(|_| Duration::from_millis(50)),
+            parent_request_recipients: Arc::new(|_| 1),
+            newest_request_delay: Arc::new(|_| Duration::from_millis(50)),
+            data_fetch_timeout: None,
+        }
+    }
+
+    async fn wait_until(condition: impl Fn() -> bool) {
+        timeout(Duration::from_secs(10), async {
+            while !condition() {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the condition should hold eventually")
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn finalizes_data_over_sockets() {
+        let spawner = Spawner::new();
+        let (listeners, addresses) = listeners(N_MEMBERS).await;
+        let mut statuses = Vec::new();
+        let mut finalized_rxs = Vec::new();
+        let mut exits = Vec::new();
+        let mut handles = Vec::new();
+        for (node_ix, listener) in N_MEMBERS.into_iterator().zip(listeners) {
+            let network = TcpNetwork::<NetworkData>::with_listener(
+                node_ix,
+                listener,
+                addresses.clone(),
+                config(),
+            );
+            statuses.push(network.status());
+            let (finalization_handler, finalized_rx) = FinalizationHandler::new();
+            let local_io = LocalIO::new(
+                DataProvider::new_range(node_ix.0 * N_DATA, (node_ix.0 + 1) * N_DATA),
+                finalization_handler,
+                Saver::new(),
+                Loader::new(vec![]),
+            );
+            let config = create_config(N_MEMBERS, node_ix, 0, 5000, delay_config(), Duration::ZERO)
+                .expect("the config is valid");
+            let (exit_tx, exit_rx) = oneshot::channel();
+            handles.push(spawner.spawn_essential("member", async move {
+                run_session(
+                    config,
+                    local_io,
+                    network,
+                    Keychain::new(N_MEMBERS, node_ix),
+                    spawner,
+                    Terminator::create_root(exit_rx, "AlephBFT-member"),
+                )
+                .await
+                .expect("the session should end cleanly")
+            }));
+            finalized_rxs.push(finalized_rx);
+            exits.push(exit_tx);
+        }
+
+        let mut all_finalized = Vec::new();
+        for finalized_rx in finalized_rxs {
+            let finalized: Vec<_> = timeout(
+                Duration::from_secs(60),
+                finalized_rx.take(N_MEMBERS.0 * N_DATA).collect(),
+            )
+            .await
+            .expect("all the data should be finalized over the sockets");
+            all_finalized.push(finalized);
+        }
+        let mut expected = all_finalized[0].clone();
+        expected.sort();
+        assert_eq!(
+            expected,
+            (0..(N_MEMBERS.0 * N_DATA) as u32).collect::<Vec<_>>()
+        );
+        assert!(all_finalized
+            .iter()
+            .all(|finalized| *finalized == all_finalized[0]));
+        for (node_ix, status) in N_MEMBERS.into_iterator().zip(&statuses) {
+            let peers: Vec<_> = N_MEMBERS
+                .into_iterator()
+                .filter(|peer| *peer != node_ix)
+                .collect();
+            assert_eq!(status.connected_peers(), peers);
+        }
+
+        for exit in exits {
+            let _ = exit.send(());
+        }
+        for handle in handles {
+            let _ = handle.await;
+        }
+        wait_until(|| {
+            statuses
+                .iter()
+                .all(|status| status.connected_peers().is_empty())
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn reconnects_to_restarted_peers() {
+        let (mut listeners, addresses) = listeners(NodeCount(2)).await;
+        let second = listeners.pop().expect("there are two listeners");
+        let first = listeners.pop().expect("there are two listeners");
+        let mut sender =
+            TcpNetwork::<u64>::with_listener(NodeIndex(0), first, addresses.clone(), config());
+        let mut receiver =
+            TcpNetwork::<u64>::with_listener(NodeIndex(1), second, addresses.clone(), config());
+        let status = sender.status();
+        wait_until(|| status.is_connected(NodeIndex(1))).await;
+        sender.send(7, Recipient::Node(NodeIndex(1)));
+        assert_eq!(receiver.next_event().await, Some(7));
+
+        drop(receiver);
+        wait_until(|| !status.is_connected(NodeIndex(1))).await;
+        let mut receiver = TcpNetwork::<u64>::bind(NodeIndex(1), addresses, config())
+            .await
+            .expect("the port is free again");
+        wait_until(|| status.is_connected(NodeIndex(1))).await;
+        sender.send(8, Recipient::Everyone);
+        assert_eq!(receiver.next_event().await, Some(8));
+        assert_eq!(
+            status.peer(NodeIndex(1)).map(|status| status.connects),
+            Some(2)
+        );
+        sender.send(9, Recipient::Node(NodeIndex(0)));
+        assert_eq!(sender.next_event().await, Some(9));
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{
+    frame::{read_frame, write_frame},
+    ConnectionStatus, TcpNetworkConfig,
+};
+use aleph_bft_types::NodeIndex;
+use codec::{Decode, Encode};
+use log::{debug, error};
+use std::{cmp::min, io, net::SocketAddr, time::Duration};
+use tokio::{
+    io::AsyncReadExt,
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{Receiver, Sender},
+    task::JoinSet,
+    time::{sleep, timeout},
+};
+
+const LOG_TARGET: &str = "AlephBFT-net-tcp";
+
+/// How long establishing a connection may take before it is retried.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long to wait before accepting connections again, after accepting failed.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+async fn connect(own_index: NodeIndex, address: SocketAddr) -> io::Result<TcpStream> {
+    let mut stream = timeout(CONNECT_TIMEOUT, TcpStream::connect(address))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "connecting timed out"))??;
+    stream.set_nodelay(true)?;
+    write_frame(&mut stream, &own_index.encode()).await?;
+    Ok(stream)
+}
+
+/// Passes the messages from the queue over a connection to the peer, until the queue is closed.
+/// The connection is established again whenever it breaks, waiting longer after every failed
+/// attempt. The message being sent when the connection broke is lost.
+pub async fn run_outgoing<M: Encode>(
+    own_index: NodeIndex,
+    peer: NodeIndex,
+    address: SocketAddr,
+    mut queue: Receiver<M>,
+    config: TcpNetworkConfig,
+    status: ConnectionStatus,
+) {
+    let mut backoff = config.min_backoff;
+    loop {
+        let mut stream = match connect(own_index, address).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                debug!(target: LOG_TARGET, "Connecting to {} failed: {}.", peer.0, e);
+                sleep(backoff).await;
+                backoff = min(backoff * 2, config.max_backoff);
+                continue;
+            }
+        };
+        debug!(target: LOG_TARGET, "Connected to {}.", peer.0);
+        backoff = config.min_backoff;
+        status.update(peer, |status| {
+            status.outgoing = true;
+            status.connects += 1;
+        });
+        // The peer never writes to this connection, so it becoming readable means it was closed.
+        let mut closed = [0u8; 1];
+        loop {
+            tokio::select! {
+                message = queue.recv() => {
+                    let Some(message) = message else {
+                        status.update(peer, |status| status.outgoing = false);
+                        return;
+                    };
+                    if let Err(e) = write_frame(&mut stream, &message.encode()).await {
+                        debug!(target: LOG_TARGET, "Sending to {} failed: {}.", peer.0, e);
+                        break;
+                    }
+                }
+                _ = stream.read(&mut closed) => {
+                    debug!(target: LOG_TARGET, "Connection to {} closed.", peer.0);
+                    break;
+                }
+            }
+        }
+        status.update(peer, |status| status.outgoing = false);
+    }
+}
+
+/// Keeps the status of the incoming connections up to date, also when the reading is aborted.
+struct IncomingGuard {
+    peer: NodeIndex,
+    status: ConnectionStatus,
+}
+
+impl IncomingGuard {
+    fn new(peer: NodeIndex, status: ConnectionStatus) -> Self {
+        status.update(peer, |status| status.incoming += 1);
+        IncomingGuard { peer, status }
+    }
+}
+
+impl Drop for IncomingGuard {
+    fn drop(&mut self) {
+        self.status.update(self.peer, |status| status.incoming -= 1);
+    }
+}
+
+async fn run_incoming<M: Decode + Send>(
+    own_index: NodeIndex,
+    mut stream: TcpStream,
+    max_frame_size: usize,
+    incoming: Sender<M>,
+    status: ConnectionStatus,
+) {
+    let peer = match read_frame(&mut stream, max_frame_size).await {
+        Ok(Some(handshake)) => match NodeIndex::decode(&mut &handshake[..]) {
+            Ok(peer) => peer,
+            Err(e) => {
+                debug!(target: LOG_TARGET, "Malformed handshake: {}.", e);
+                return;
+            }
+        },
+        Ok(None) => return,
+        Err(e) => {
+            debug!(target: LOG_TARGET, "Reading a handshake failed: {}.", e);
+            return;
+        }
+    };
+    if peer == own_index || status.peer(peer).is_none() {
+        debug!(target: LOG_TARGET, "Connection from unknown node {}.", peer.0);
+        return;
+    }
+    let _guard = IncomingGuard::new(peer, status);
+    loop {
+        let frame = match read_frame(&mut stream, max_frame_size).await {
+            Ok(Some(frame)) => frame,
+            Ok(None) => {
+                debug!(target: LOG_TARGET, "Connection from {} closed.", peer.0);
+                return;
+            }
+            Err(e) => {
+                debug!(target: LOG_TARGET, "Receiving from {} failed: {}.", peer.0, e);
+                return;
+            }
+        };
+        match M::decode(&mut &frame[..]) {
+            // Waiting for room in the queue stops reading, which slows down the peer.
+            Ok(message) => {
+                if incoming.send(message).await.is_err() {
+                    return;
+                }
+            }
+            Err(e) => debug!(target: LOG_TARGET, "Malformed message from {}: {}.", peer.0, e),
+        }
+    }
+}
+
+/// Accepts connections, reading the messages coming over every one of them into the queue.
+/// The connections are read until the listener stops.
+pub async fn run_listener<M: Decode + Send + 'static>(
+    own_index: NodeIndex,
+    listener: TcpListener,
+    max_frame_size: usize,
+    incoming: Sender<M>,
+    status: ConnectionStatus,
+) {
+    let mut readers = JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    if let Err(e) = stream.set_nodelay(true) {
+                        debug!(target: LOG_TARGET, "Setting up a connection failed: {}.", e);
+                    }
+                    readers.spawn(run_incoming(
+                        own_index,
+                        stream,
+                        max_frame_size,
+                        incoming.clone(),
+                        status.clone(),
+                    ));
+                }
+                Err(e) => {
+                    error!(target: LOG_TARGET, "Accepting a connection failed: {}.", e);
+                    sleep(ACCEPT_BACKOFF).await;
+                }
+            },
+            Some(_) = readers.join_next() => (),
+        }
+    }
+}
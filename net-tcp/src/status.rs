@@ -0,0 +1,71 @@
+use aleph_bft_types::NodeIndex;
+use parking_lot::Mutex;
+use std::{collections::HashMap, sync::Arc};
+
+/// What is known about the connections with a single peer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PeerStatus {
+    /// Whether our connection to the peer, carrying the messages for it, is up.
+    pub outgoing: bool,
+    /// How many connections claiming to come from the peer are open.
+    pub incoming: usize,
+    /// How many times our connection to the peer was established.
+    pub connects: u64,
+    /// How many messages for the peer were dropped, because its queue was full.
+    pub dropped: u64,
+}
+
+impl PeerStatus {
+    /// Whether messages flow both ways.
+    pub fn is_connected(&self) -> bool {
+        self.outgoing && self.incoming > 0
+    }
+}
+
+/// A handle to the state of the connections of a [`crate::TcpNetwork`], which can be kept after
+/// the network is passed to the session.
+#[derive(Clone, Debug, Default)]
+pub struct ConnectionStatus {
+    peers: Arc<Mutex<HashMap<NodeIndex, PeerStatus>>>,
+}
+
+impl ConnectionStatus {
+    pub(crate) fn new(peers: impl IntoIterator<Item = NodeIndex>) -> Self {
+        let peers = peers
+            .into_iter()
+            .map(|peer| (peer, PeerStatus::default()))
+            .collect();
+        ConnectionStatus {
+            peers: Arc::new(Mutex::new(peers)),
+        }
+    }
+
+    /// The status of the connections with the peer, `None` if it is not in the address book.
+    pub fn peer(&self, peer: NodeIndex) -> Option<PeerStatus> {
+        self.peers.lock().get(&peer).copied()
+    }
+
+    /// Whether messages flow both ways between us and the peer.
+    pub fn is_connected(&self, peer: NodeIndex) -> bool {
+        self.peer(peer).is_some_and(|status| status.is_connected())
+    }
+
+    /// The peers messages flow both ways with, in ascending order.
+    pub fn connected_peers(&self) -> Vec<NodeIndex> {
+        let mut connected: Vec<_> = self
+            .peers
+            .lock()
+            .iter()
+            .filter(|(_, status)| status.is_connected())
+            .map(|(peer, _)| *peer)
+            .collect();
+        connected.sort();
+        connected
+    }
+
+    pub(crate) fn update(&self, peer: NodeIndex, update: impl FnOnce(&mut PeerStatus)) {
+        if let Some(status) = self.peers.lock().get_mut(&peer) {
+            update(status);
+        }
+    }
+}
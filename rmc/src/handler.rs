@@ -46,16 +46,17 @@ impl<H: Signable + Hash + Eq + Clone + Debug, MK: MultiKeychain> Handler<H, MK>
     }
 
     /// Signs hash and updates the internal state with it. Returns the signed
-    /// version of the hash for broadcast. Should be called at most once for a particular hash.
-    pub fn on_start_rmc(&mut self, hash: H) -> OnStartRmcResponse<H, MK> {
-        let signed_hash = Signed::sign_with_index(hash, &self.keychain);
-        if self.already_completed(signed_hash.as_signable().as_signable()) {
-            return OnStartRmcResponse::Noop;
+    /// version of the hash for broadcast. Should be called at most once for a particular hash,
+    /// unless signing failed, in which case the state is not affected.
+    pub fn on_start_rmc(&mut self, hash: H) -> Result<OnStartRmcResponse<H, MK>, MK::SignError> {
+        if self.already_completed(&hash) {
+            return Ok(OnStartRmcResponse::Noop);
         }
+        let signed_hash = Signed::sign_with_index(hash, &self.keychain)?;
         if let Some(multisigned) = self.handle_signed_hash(signed_hash.clone()) {
-            return OnStartRmcResponse::MultisignedHash(multisigned);
+            return Ok(OnStartRmcResponse::MultisignedHash(multisigned));
         }
-        OnStartRmcResponse::SignedHash(signed_hash)
+        Ok(OnStartRmcResponse::SignedHash(signed_hash))
     }
 
     /// Update the internal state with the signed hash. If the hash is incorrectly signed then
@@ -147,7 +148,8 @@ mod tests {
     ) {
         for i in nodes {
             let keychain_i = Keychain::new(count, i);
-            let signed_hash = Signed::sign_with_index(hash.clone(), &keychain_i);
+            let signed_hash = Signed::sign_with_index(hash.clone(), &keychain_i)
+                .expect("the keychain never fails");
             handler
                 .on_signed_hash(signed_hash.clone().into_unchecked())
                 .expect("the signatures should be correct");
@@ -163,7 +165,8 @@ mod tests {
         let mut multisigned = None;
         for i in nodes {
             let keychain_i = Keychain::new(count, i);
-            let signed_hash = Signed::sign_with_index(hash.clone(), &keychain_i);
+            let signed_hash = Signed::sign_with_index(hash.clone(), &keychain_i)
+                .expect("the keychain never fails");
             handler
                 .on_signed_hash(signed_hash.clone().into_unchecked())
                 .expect("the signatures should be correct");
@@ -179,10 +182,13 @@ mod tests {
     fn on_start_rmc_before_reaching_quorum_returns_signed() {
         let hash: Signable = "13".into();
         let keychain = Keychain::new(7.into(), 0.into());
-        let mut handler = Handler::new(keychain);
-        let expected = Signed::sign_with_index(hash.clone(), &keychain);
+        let mut handler = Handler::new(keychain.clone());
+        let expected =
+            Signed::sign_with_index(hash.clone(), &keychain).expect("the keychain never fails");
         assert_eq!(
-            handler.on_start_rmc(hash),
+            handler
+                .on_start_rmc(hash)
+                .expect("the keychain never fails"),
             OnStartRmcResponse::SignedHash(expected)
         );
     }
@@ -191,7 +197,7 @@ mod tests {
     fn on_start_rmc_reaching_quorum_returns_multisigned() {
         let hash: Signable = "13".into();
         let keychain = Keychain::new(7.into(), 0.into());
-        let mut handler = Handler::new(keychain);
+        let mut handler = Handler::new(keychain.clone());
         let multisigned = apply_signatures_and_get_multisigned(
             &mut handler,
             &hash,
@@ -199,12 +205,16 @@ mod tests {
             (1..5).map(|i| i.into()),
         )
         .expect("passed nodes set is non-empty");
-        let multisigned =
-            multisigned.add_signature(Signed::sign_with_index(hash.clone(), &keychain), &keychain); // should reach the quorum
+        let multisigned = multisigned.add_signature(
+            Signed::sign_with_index(hash.clone(), &keychain).expect("the keychain never fails"),
+            &keychain,
+        ); // should reach the quorum
         match multisigned {
             PartiallyMultisigned::Incomplete { .. } => panic!("multisignature should be complete"),
             PartiallyMultisigned::Complete { multisigned } => assert_eq!(
-                handler.on_start_rmc(hash),
+                handler
+                    .on_start_rmc(hash)
+                    .expect("the keychain never fails"),
                 OnStartRmcResponse::MultisignedHash(multisigned)
             ),
         }
@@ -216,7 +226,12 @@ mod tests {
         let keychain = Keychain::new(7.into(), 0.into());
         let mut handler = Handler::new(keychain);
         apply_signatures(&mut handler, &hash, 7.into(), (1..6).map(|i| i.into())); // should already reach the quorum
-        assert_eq!(handler.on_start_rmc(hash), OnStartRmcResponse::Noop);
+        assert_eq!(
+            handler
+                .on_start_rmc(hash)
+                .expect("the keychain never fails"),
+            OnStartRmcResponse::Noop
+        );
     }
 
     #[test]
@@ -225,7 +240,8 @@ mod tests {
         let keychain = Keychain::new(7.into(), 0.into());
         let mut handler = Handler::new(keychain);
         let peer_keychain = Keychain::new(7.into(), 1.into());
-        let peer_signed = Signed::sign_with_index(hash, &peer_keychain);
+        let peer_signed =
+            Signed::sign_with_index(hash, &peer_keychain).expect("the keychain never fails");
         assert_eq!(
             handler.on_signed_hash(peer_signed.into_unchecked()),
             Ok(None)
@@ -245,7 +261,8 @@ mod tests {
             (2..6).map(|i| i.into()),
         )
         .expect("passed nodes set is non-empty");
-        let peer_signed = Signed::sign_with_index(hash, &peer_keychain);
+        let peer_signed =
+            Signed::sign_with_index(hash, &peer_keychain).expect("the keychain never fails");
         let multisigned = multisigned.add_signature(peer_signed.clone(), &peer_keychain);
         match multisigned {
             PartiallyMultisigned::Incomplete { .. } => panic!("multisignature should be complete"),
@@ -260,9 +277,10 @@ mod tests {
     fn on_signed_hash_after_reaching_quorum_returns_none() {
         let hash: Signable = "13".into();
         let keychain = Keychain::new(7.into(), 0.into());
-        let mut handler = Handler::new(keychain);
+        let mut handler = Handler::new(keychain.clone());
         apply_signatures(&mut handler, &hash, 7.into(), (1..6).map(|i| i.into()));
-        let our_signed = Signed::sign_with_index(hash, &keychain);
+        let our_signed =
+            Signed::sign_with_index(hash, &keychain).expect("the keychain never fails");
         assert_eq!(
             handler.on_signed_hash(our_signed.into_unchecked()),
             Ok(None)
@@ -275,7 +293,8 @@ mod tests {
         let keychain = Keychain::new(7.into(), 0.into());
         let mut handler = Handler::new(keychain);
         let bad_keychain: BadSigning<Keychain> = Keychain::new(NodeCount(7), NodeIndex(1)).into();
-        let bad_signed = Signed::sign_with_index(hash, &bad_keychain);
+        let bad_signed =
+            Signed::sign_with_index(hash, &bad_keychain).expect("the keychain never fails");
         assert_eq!(
             handler.on_signed_hash(bad_signed.into_unchecked()),
             Err(Error::BadSignature)
@@ -1,7 +1,7 @@
+use aleph_bft_types::ClockSource;
 use async_trait::async_trait;
 use core::fmt::Debug;
 use futures::future::pending;
-use futures_timer::Delay;
 use std::{
     cmp::Reverse,
     collections::BinaryHeap,
@@ -51,6 +51,7 @@ impl IndexedInstant {
 /// one.
 pub struct DoublingDelayScheduler<T> {
     initial_delay: Duration,
+    clock: ClockSource,
     scheduled_instants: BinaryHeap<Reverse<IndexedInstant>>,
     scheduled_tasks: Vec<ScheduledTask<T>>,
 }
@@ -70,9 +71,27 @@ impl<T> DoublingDelayScheduler<T> {
         DoublingDelayScheduler::with_tasks(vec![], initial_delay)
     }
 
+    /// Like [`Self::new`], but measures the delays with the given clock.
+    pub fn with_clock(initial_delay: Duration, clock: ClockSource) -> Self {
+        DoublingDelayScheduler::with_tasks_and_clock(vec![], initial_delay, clock)
+    }
+
     pub fn with_tasks(initial_tasks: Vec<T>, initial_delay: Duration) -> Self {
+        DoublingDelayScheduler::with_tasks_and_clock(
+            initial_tasks,
+            initial_delay,
+            ClockSource::default(),
+        )
+    }
+
+    pub fn with_tasks_and_clock(
+        initial_tasks: Vec<T>,
+        initial_delay: Duration,
+        clock: ClockSource,
+    ) -> Self {
         let mut scheduler = DoublingDelayScheduler {
             initial_delay,
+            clock,
             scheduled_instants: BinaryHeap::new(),
             scheduled_tasks: Vec::new(),
         };
@@ -88,7 +107,7 @@ impl<T> DoublingDelayScheduler<T> {
 
     fn add_task_after(&mut self, task: T, delta: Duration) {
         let i = self.scheduled_tasks.len();
-        let instant = Instant::now().add(delta);
+        let instant = self.clock.now().add(delta);
         let indexed_instant = IndexedInstant::at(instant, i);
         self.scheduled_instants.push(Reverse(indexed_instant));
         let scheduled_task = ScheduledTask::new(task, self.initial_delay);
@@ -105,9 +124,8 @@ impl<T: Send + Sync + Clone> TaskScheduler<T> for DoublingDelayScheduler<T> {
     async fn next_task(&mut self) -> T {
         match self.scheduled_instants.peek() {
             Some(&Reverse(IndexedInstant(instant, _))) => {
-                let now = Instant::now();
-                if now < instant {
-                    Delay::new(instant - now).await;
+                if self.clock.now() < instant {
+                    self.clock.sleep_until(instant).await;
                 }
             }
             None => pending().await,
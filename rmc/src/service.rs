@@ -50,10 +50,11 @@ where
     /// completes the multisignature, it is scheduled for the broadcasts and then returned.
     /// If the multisignature is not completed, `None` is returned. If the multisignature was
     /// already completed when starting rmc, no tasks are scheduled. Otherwise the signed hash
-    /// is scheduled for the broadcasts.
-    pub fn start_rmc(&mut self, hash: H) -> Option<Multisigned<H, MK>> {
+    /// is scheduled for the broadcasts. If signing fails, the error is returned and nothing is
+    /// scheduled, so starting the rmc should be retried later.
+    pub fn start_rmc(&mut self, hash: H) -> Result<Option<Multisigned<H, MK>>, MK::SignError> {
         debug!(target: LOG_TARGET, "starting rmc for {:?}", hash);
-        match self.handler.on_start_rmc(hash) {
+        match self.handler.on_start_rmc(hash)? {
             OnStartRmcResponse::SignedHash(signed_hash) => {
                 self.scheduler
                     .add_task(Message::SignedHash(signed_hash.into_unchecked()));
@@ -62,11 +63,11 @@ where
                 self.scheduler.add_task(Message::MultisignedHash(
                     multisigned.clone().into_unchecked(),
                 ));
-                return Some(multisigned);
+                return Ok(Some(multisigned));
             }
             OnStartRmcResponse::Noop => {}
         }
-        None
+        Ok(None)
     }
 
     /// Processes a message which can be of two types. If the message is a hash signed by one
@@ -234,7 +235,9 @@ mod tests {
                             .rmc_services
                             .get_mut(node_index.0)
                             .expect("service should exist");
-                        if let Some(multisigned) = service.start_rmc(hash) {
+                        if let Some(multisigned) =
+                            service.start_rmc(hash).expect("the keychain never fails")
+                        {
                             assert_eq!(self.hashes.insert(node_index, multisigned), None);
                             // there should be only one multisig per node
                         }
@@ -269,6 +272,47 @@ mod tests {
         }
     }
 
+    /// Committees of at most three nodes need the signatures of all of them, down to a single
+    /// node completing the multisignature on its own.
+    #[tokio::test]
+    async fn small_committees() {
+        for n in 1..=3 {
+            let node_count = NodeCount(n);
+            let environment = TestEnvironment::new(node_count, |_, _| true);
+            let hash: Signable = "56".into();
+            for i in 0..node_count.0 {
+                environment.start_rmc(hash.clone(), NodeIndex(i));
+            }
+
+            let hashes = environment.collect_multisigned_hashes(node_count.0).await;
+            assert_eq!(hashes.len(), node_count.0);
+            for multisignature in hashes.values() {
+                assert_eq!(multisignature.as_signable(), &hash);
+            }
+        }
+    }
+
+    /// A committee of three nodes cannot complete a multisignature with one of them silent.
+    #[tokio::test]
+    async fn silent_node_stalls_committee_of_three() {
+        let node_count = NodeCount(3);
+        let silent = NodeIndex(2);
+        let environment = TestEnvironment::new(node_count, move |recipient, _| recipient != silent);
+        let hash: Signable = "56".into();
+        for i in 0..node_count.0 {
+            if NodeIndex(i) != silent {
+                environment.start_rmc(hash.clone(), NodeIndex(i));
+            }
+        }
+
+        let collected = tokio::time::timeout(
+            Duration::from_millis(200),
+            environment.collect_multisigned_hashes(1),
+        )
+        .await;
+        assert!(collected.is_err());
+    }
+
     /// Each message is delivered with 20% probability
     #[tokio::test]
     async fn faulty_network() {
@@ -322,11 +366,14 @@ mod tests {
         let bad_hash: Signable = "65".into();
         let bad_keychain: BadSigning<Keychain> = Keychain::new(node_count, 0.into()).into();
         let bad_msg = TestMessage::SignedHash(
-            Signed::sign_with_index(bad_hash.clone(), &bad_keychain).into(),
+            Signed::sign_with_index(bad_hash.clone(), &bad_keychain)
+                .expect("the keychain never fails")
+                .into(),
         );
         environment.broadcast_message(bad_msg, NodeIndex(0));
         let bad_msg = TestMessage::MultisignedHash(
             Signed::sign_with_index(bad_hash.clone(), &bad_keychain)
+                .expect("the keychain never fails")
                 .into_partially_multisigned(&bad_keychain)
                 .into_unchecked(),
         );
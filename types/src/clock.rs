@@ -0,0 +1,105 @@
+use futures::{future::BoxFuture, FutureExt};
+use futures_timer::Delay;
+use std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// A source of time for everything in a session that measures time or waits.
+///
+/// The default [`RealClock`] uses the system time. Other implementations make it possible to run
+/// sessions in simulated time, e.g. a simulation driver can make the time jump to the moment the
+/// next sleeper should be woken up, instead of actually waiting.
+pub trait Clock: Send + Sync + 'static {
+    /// The current time.
+    fn now(&self) -> Instant;
+    /// A future that resolves once the clock reaches `deadline`, immediately if it already did.
+    fn sleep_until(&self, deadline: Instant) -> BoxFuture<'static, ()>;
+}
+
+/// The system time, with waiting implemented using `futures-timer`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RealClock;
+
+impl Clock for RealClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> BoxFuture<'static, ()> {
+        Delay::new(deadline.saturating_duration_since(Instant::now())).boxed()
+    }
+}
+
+/// The time of the tokio runtime. When the runtime time is paused, e.g. with
+/// `#[tokio::test(start_paused = true)]`, it only moves forward when advanced explicitly or when
+/// all the tasks are waiting for a timer, so sessions run in simulated time.
+#[cfg(feature = "tokio")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokioClock;
+
+#[cfg(feature = "tokio")]
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        tokio::time::Instant::now().into_std()
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> BoxFuture<'static, ()> {
+        tokio::time::sleep_until(deadline.into()).boxed()
+    }
+}
+
+/// The system time, with waiting implemented using the timers of `async-std`. They are driven by
+/// a thread of their own when no `async-std` executor runs, so they work on any runtime.
+#[cfg(feature = "async-std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AsyncStdClock;
+
+#[cfg(feature = "async-std")]
+impl Clock for AsyncStdClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> BoxFuture<'static, ()> {
+        async_std::task::sleep(deadline.saturating_duration_since(Instant::now())).boxed()
+    }
+}
+
+/// A shareable handle to a [`Clock`], [`RealClock`] by default.
+#[derive(Clone)]
+pub struct ClockSource(Arc<dyn Clock>);
+
+impl ClockSource {
+    pub fn new(clock: impl Clock) -> Self {
+        ClockSource(Arc::new(clock))
+    }
+
+    /// The current time.
+    pub fn now(&self) -> Instant {
+        self.0.now()
+    }
+
+    /// A future that resolves once the clock reaches `deadline`.
+    pub fn sleep_until(&self, deadline: Instant) -> BoxFuture<'static, ()> {
+        self.0.sleep_until(deadline)
+    }
+
+    /// A future that resolves after `duration` passes according to the clock.
+    pub fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        self.sleep_until(self.now() + duration)
+    }
+}
+
+impl Default for ClockSource {
+    fn default() -> Self {
+        ClockSource::new(RealClock)
+    }
+}
+
+impl Debug for ClockSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("ClockSource").finish_non_exhaustive()
+    }
+}
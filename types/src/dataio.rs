@@ -1,6 +1,7 @@
 use async_trait::async_trait;
+use codec::{Decode, Encode};
 
-use crate::{Data, Hasher, NodeIndex, Round};
+use crate::{protocol::BatchId, Data, Hasher, NodeIndex, Round};
 
 /// The source of data items that consensus should order.
 ///
@@ -16,6 +17,59 @@ pub trait DataProvider: Sync + Send + 'static {
     type Output: Data;
     /// Outputs a new data item to be ordered.
     async fn get_data(&mut self) -> Option<Self::Output>;
+
+    /// Takes back a data item returned by [`DataProvider::get_data`] that will not be ordered
+    /// after all, e.g. because the [`BroadcastGate`] rejected it, so that it can be offered
+    /// again. By default the item is dropped.
+    fn return_unused(&mut self, _data: Self::Output) {}
+}
+
+/// What to do with our unit waiting at the [`BroadcastGate`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GateDecision {
+    /// Save and broadcast the unit with its data.
+    Release,
+    /// Return the data to the provider and create the unit without any data instead.
+    ReplaceWithEmpty,
+}
+
+/// A hook deciding whether the data of our unit may be ordered, e.g. once the application
+/// replicated it durably.
+///
+/// AlephBFT calls [`BroadcastGate::check`] with the data of every unit we create, before the unit
+/// is signed, saved to the backup or sent to anyone, and waits for the decision. A unit without
+/// data is never gated.
+#[async_trait]
+pub trait BroadcastGate<D: Data>: Sync + Send + 'static {
+    /// Decides what to do with the unit containing the given data. Taking longer than the
+    /// timeout in the configuration counts as the decision configured there.
+    async fn check(&self, data: &D) -> GateDecision;
+}
+
+/// A check whether the data of units of other nodes is available locally, e.g. whether the
+/// block body referenced by a hash in the data was fetched, so that nothing unavailable gets
+/// finalized.
+///
+/// AlephBFT calls [`DataAvailabilityChecker::available`] with the data of every unit of other
+/// nodes it reconstructs, and holds the unit, and the units above it, back from the dag until
+/// the data is available. A unit without data is never checked.
+#[async_trait]
+pub trait DataAvailabilityChecker<D: Data>: Sync + Send + 'static {
+    /// Whether the data is available. While it is not, the data is checked again after the
+    /// interval configured with `Config::with_availability_checks`.
+    async fn available(&self, data: &D) -> bool;
+}
+
+/// The source of the metadata attached to the units we create, e.g. the version of the software,
+/// for the applications of our peers to read.
+///
+/// AlephBFT calls [`UnitMetadataProvider::metadata`] for every unit we create. Consensus never
+/// interprets the metadata, it only rejects metadata exceeding the size configured with
+/// `Config::with_max_unit_metadata_size`, in which case the unit gets none.
+pub trait UnitMetadataProvider: Sync + Send + 'static {
+    /// The metadata of our unit of the given round, empty for none.
+    fn metadata(&self, round: Round) -> Vec<u8>;
 }
 
 /// The source of finalization of the units that consensus produces.
@@ -26,6 +80,65 @@ pub trait FinalizationHandler<D: Data>: Sync + Send + 'static {
     /// Data, provided by [DataProvider::get_data], has been finalized.
     /// The calls to this function follow the order of finalization.
     fn data_finalized(&mut self, data: D);
+
+    /// Data flagged by the local data policy has been finalized. It is called instead of
+    /// [`FinalizationHandler::data_finalized`], in the same place of the order. By default the
+    /// flag is ignored.
+    fn flagged_data_finalized(&mut self, flagged: Flagged<D>) {
+        self.data_finalized(flagged.data)
+    }
+
+    /// A unit has been finalized, with or without data. It is called for every finalized unit,
+    /// in order of finalization, e.g. for rewarding the creators of the ordered units. By default
+    /// its data is passed on to [`FinalizationHandler::data_finalized`] or
+    /// [`FinalizationHandler::flagged_data_finalized`].
+    fn unit_finalized(&mut self, unit: FinalizedUnitInfo<D>) {
+        match (unit.data, unit.flagged) {
+            (Some(data), false) => self.data_finalized(data),
+            (Some(data), true) => self.flagged_data_finalized(Flagged {
+                data,
+                creator: unit.creator,
+            }),
+            (None, _) => {}
+        }
+    }
+
+    /// A batch of units has been finalized, ending with the head the ordering chose for it. The
+    /// batches are the same on all the nodes, so they can be passed on e.g. transactionally, one
+    /// by one. By default every unit of the batch is passed on to
+    /// [`FinalizationHandler::unit_finalized`].
+    fn batch_finalized(&mut self, batch: Vec<FinalizedUnitInfo<D>>, _head: FinalizationInfo) {
+        for unit in batch {
+            self.unit_finalized(unit)
+        }
+    }
+}
+
+/// The head of a batch passed to [`FinalizationHandler::batch_finalized`], i.e. the last unit of
+/// the batch.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct FinalizationInfo {
+    pub creator: NodeIndex,
+    pub round: Round,
+}
+
+/// A finalized unit as seen by [`FinalizationHandler::unit_finalized`]. The hashes of the units
+/// and their parents are available through [`UnitFinalizationHandler`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FinalizedUnitInfo<D: Data> {
+    pub creator: NodeIndex,
+    pub round: Round,
+    pub data: Option<D>,
+    /// Whether the local data policy flagged the data of this unit.
+    pub flagged: bool,
+}
+
+/// Finalized data marked by the local data policy, together with the creator of the unit that
+/// contained it. Whether data is flagged never influences the ordering.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Flagged<D: Data> {
+    pub data: D,
+    pub creator: NodeIndex,
 }
 
 /// Represents state of the main internal data structure of AlephBFT (i.e. direct acyclic graph) used for
@@ -40,6 +153,8 @@ pub struct OrderedUnit<D: Data, H: Hasher> {
     pub hash: H::Hash,
     pub creator: NodeIndex,
     pub round: Round,
+    /// Whether the local data policy flagged the data of this unit.
+    pub flagged: bool,
 }
 
 /// The source of finalization of the units that consensus produces.
@@ -53,4 +168,74 @@ pub trait UnitFinalizationHandler: Sync + Send + 'static {
     /// A batch of units, that contains data provided by [DataProvider::get_data], has been finalized.
     /// The calls to this function follow the order of finalization.
     fn batch_finalized(&mut self, batch: Vec<OrderedUnit<Self::Data, Self::Hasher>>);
+
+    /// A batch of units has been finalized, together with its identifier, see
+    /// [`crate::protocol::batch_id`]. It is called instead of
+    /// [`UnitFinalizationHandler::batch_finalized`]. By default the identifier is ignored.
+    fn identified_batch_finalized(
+        &mut self,
+        _id: BatchId<Self::Hasher>,
+        batch: Vec<OrderedUnit<Self::Data, Self::Hasher>>,
+    ) {
+        self.batch_finalized(batch)
+    }
+}
+
+/// The point up to which the finalized batches were passed to the finalization handler.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DeliveryCheckpoint<Hash> {
+    /// The number of batches delivered since the beginning of the session.
+    pub batches: u64,
+    /// The hash of the head, i.e. the last unit, of the last delivered batch.
+    pub head: Hash,
+    /// The identifier of the last delivered batch.
+    pub batch_id: Hash,
+}
+
+/// A persistent store for the delivery checkpoint and the finalization index, i.e. the number
+/// of the batch every finalized unit was delivered in, together with the identifiers of the
+/// batches.
+///
+/// With a store, a restarted session does not pass the batches delivered before the restart to
+/// the finalization handler again, and can still answer which units were finalized. The store
+/// should be specific to the session, the same as the backup.
+pub trait FinalizationStateStore<Hash>: Send + 'static {
+    /// Persists the checkpoint, replacing the previous one.
+    fn put_checkpoint(&mut self, checkpoint: DeliveryCheckpoint<Hash>) -> std::io::Result<()>;
+    /// The last persisted checkpoint, if any.
+    fn get_checkpoint(&self) -> std::io::Result<Option<DeliveryCheckpoint<Hash>>>;
+    /// Persists the numbers of the batches the given units were delivered in.
+    fn put_index_entries(&mut self, entries: Vec<(Hash, u64)>) -> std::io::Result<()>;
+    /// The number of the batch the given unit was delivered in, if it is still in the index.
+    fn get_index_entry(&self, unit: &Hash) -> std::io::Result<Option<u64>>;
+    /// Persists the identifier of the batch with the given number.
+    fn put_batch_id(&mut self, batch: u64, id: Hash) -> std::io::Result<()>;
+    /// The identifier of the batch with the given number, if it is still in the index.
+    fn get_batch_id(&self, batch: u64) -> std::io::Result<Option<Hash>>;
+    /// Removes the index entries and the identifiers of the batches with numbers lower than the
+    /// given one.
+    fn prune_below(&mut self, batch: u64) -> std::io::Result<()>;
+}
+
+/// A claim of a running instance of a member to its seat in the committee, see [`LeaseStore`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Encode, Decode)]
+pub struct Lease {
+    /// Identifies the instance holding the lease, chosen at random when the session starts.
+    pub instance_id: u64,
+    /// Increased with every renewal, by whichever instance renews the lease.
+    pub counter: u64,
+    /// The time of the renewal, in milliseconds since the Unix epoch, for operators only.
+    pub timestamp: u64,
+}
+
+/// A persistent store for the lease of our seat in the committee, shared by all the instances
+/// that could run the member, e.g. kept on the same network storage as the backup.
+///
+/// The session renews the lease periodically and refuses to create units while another instance
+/// keeps renewing it, so that two instances with the same keys never both create units.
+pub trait LeaseStore: Send + 'static {
+    /// Persists the lease, replacing the previous one.
+    fn put_lease(&mut self, lease: Lease) -> std::io::Result<()>;
+    /// The last persisted lease, written by any instance, if any.
+    fn get_lease(&self) -> std::io::Result<Option<Lease>>;
 }
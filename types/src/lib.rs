@@ -1,15 +1,28 @@
 //! Traits that need to be implemented by the user.
 
+mod clock;
 mod dataio;
 mod network;
+pub mod protocol;
+#[cfg(feature = "reference")]
+pub mod reference;
 mod tasks;
 
 pub use aleph_bft_crypto::{
     IncompleteMultisignatureError, Index, Indexed, Keychain, MultiKeychain, Multisigned, NodeCount,
-    NodeIndex, NodeMap, NodeSubset, PartialMultisignature, PartiallyMultisigned, Signable,
-    Signature, SignatureError, SignatureSet, Signed, UncheckedSigned,
+    NodeIndex, NodeMap, NodeSubset, NodeWeights, PartialMultisignature, PartiallyMultisigned,
+    Signable, Signature, SignatureError, SignatureSet, Signed, UncheckedSigned, Weight,
+};
+#[cfg(feature = "async-std")]
+pub use clock::AsyncStdClock;
+#[cfg(feature = "tokio")]
+pub use clock::TokioClock;
+pub use clock::{Clock, ClockSource, RealClock};
+pub use dataio::{
+    BroadcastGate, DataAvailabilityChecker, DataProvider, DeliveryCheckpoint, FinalizationHandler,
+    FinalizationInfo, FinalizationStateStore, FinalizedUnitInfo, Flagged, GateDecision, Lease,
+    LeaseStore, OrderedUnit, UnitFinalizationHandler, UnitMetadataProvider,
 };
-pub use dataio::{DataProvider, FinalizationHandler, OrderedUnit, UnitFinalizationHandler};
 pub use network::{Network, Recipient};
 pub use tasks::{SpawnHandle, TaskHandle};
 
@@ -38,6 +38,14 @@ pub trait Network<D>: Send + 'static {
     /// Note on the implementation: this function should be implemented in a non-blocking manner.
     /// Otherwise, the performance might be affected negatively or the execution may end up in a deadlock.
     fn send(&self, data: D, recipient: Recipient);
-    /// Receive a message from the network.
+    /// Receive a message from the network. Returning `None` means that there are no events
+    /// right now, whether that is for good is decided by [`Network::is_terminated`].
     async fn next_event(&mut self) -> Option<D>;
+    /// Whether the network is closed for good. Called right after [`Network::next_event`]
+    /// returned `None`: if this returns `false`, the end of events is treated as transient, e.g.
+    /// because the transport is rebuilding its connections, and `next_event` is polled again after
+    /// a backoff. By default every end of events is permanent and ends the session.
+    fn is_terminated(&self) -> bool {
+        true
+    }
 }
@@ -0,0 +1,133 @@
+//! Constants and formulas that define the protocol and have to agree between all the nodes, as
+//! well as any external tooling interacting with them. Changing any of them is a protocol change,
+//! so every value is pinned by a test that has to be updated together with it.
+
+use crate::{Data, Hasher, NodeCount, OrderedUnit, Round, SessionId};
+use codec::Encode;
+use std::time::Duration;
+
+/// The version of the protocol, bumped on every change to the constants in this module or to the
+/// messages exchanged by the nodes.
+pub const PROTOCOL_VERSION: u8 = 2;
+
+/// The highest round a unit can ever have, the configured maximal round cannot exceed it.
+pub const MAX_ROUND: Round = Round::MAX;
+
+/// The initial delay between rebroadcasts of reliable multicast messages, doubled after every
+/// rebroadcast.
+pub const RMC_REBROADCAST_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// The number of nodes out of `n_members` that is required for secure consensus, i.e. the size
+/// of a quorum: `floor(2 * n_members / 3) + 1`.
+pub fn quorum(n_members: NodeCount) -> NodeCount {
+    n_members.consensus_threshold()
+}
+
+/// The identifier of a finalized batch, the same on every honest node.
+pub type BatchId<H> = <H as Hasher>::Hash;
+
+fn batch_id_preimage<H: Hasher>(
+    session_id: SessionId,
+    number: u64,
+    head: &H::Hash,
+    data_units: &[H::Hash],
+) -> Vec<u8> {
+    (session_id, number, head, data_units).encode()
+}
+
+/// The identifier of the batch with the given number in the session, counting from 0. It is the
+/// hash of the SCALE encoding of the session id, the number, the hash of the head of the batch
+/// and the hashes of the units of the batch that contain data, in the order of finalization.
+pub fn batch_id<H: Hasher>(
+    session_id: SessionId,
+    number: u64,
+    head: &H::Hash,
+    data_units: &[H::Hash],
+) -> BatchId<H> {
+    H::hash(&batch_id_preimage::<H>(
+        session_id, number, head, data_units,
+    ))
+}
+
+/// The identifier of the given batch with the given number in the session, as passed to the
+/// [`crate::UnitFinalizationHandler`], e.g. to check the identifiers against a copy of the DAG.
+/// An empty batch has no identifier, as no such batch is ever finalized.
+pub fn ordered_batch_id<D: Data, H: Hasher>(
+    session_id: SessionId,
+    number: u64,
+    batch: &[OrderedUnit<D, H>],
+) -> Option<BatchId<H>> {
+    let head = batch.last()?;
+    let data_units: Vec<_> = batch
+        .iter()
+        .filter(|unit| unit.data.is_some())
+        .map(|unit| unit.hash)
+        .collect();
+    Some(batch_id::<H>(session_id, number, &head.hash, &data_units))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        protocol::{
+            batch_id_preimage, quorum, MAX_ROUND, PROTOCOL_VERSION, RMC_REBROADCAST_BASE_DELAY,
+        },
+        Hasher, NodeCount,
+    };
+    use std::time::Duration;
+
+    /// Only the size of its hashes matters for the preimages.
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    struct PrefixHasher;
+
+    impl Hasher for PrefixHasher {
+        type Hash = [u8; 2];
+
+        fn hash(s: &[u8]) -> Self::Hash {
+            [s[0], s[1]]
+        }
+    }
+
+    #[test]
+    fn constants_are_pinned() {
+        assert_eq!(PROTOCOL_VERSION, 2);
+        assert_eq!(MAX_ROUND, 65535);
+        assert_eq!(RMC_REBROADCAST_BASE_DELAY, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn quorum_is_pinned() {
+        for (n_members, expected) in [(1, 1), (2, 2), (3, 3), (4, 3), (7, 5), (10, 7), (100, 67)] {
+            assert_eq!(quorum(NodeCount(n_members)), NodeCount(expected));
+        }
+    }
+
+    #[test]
+    fn batch_id_preimage_is_pinned() {
+        assert_eq!(
+            batch_id_preimage::<PrefixHasher>(7, 3, &[0xaa, 0xbb], &[[1, 2], [3, 4]]),
+            vec![7, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0xaa, 0xbb, 8, 1, 2, 3, 4]
+        );
+        assert_eq!(
+            batch_id_preimage::<PrefixHasher>(0, 0, &[0, 0], &[]),
+            vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    // Any change to the encoding of the batch identifiers changes this digest.
+    #[cfg(feature = "reference")]
+    #[test]
+    fn batch_id_is_pinned() {
+        use crate::{protocol::batch_id, reference::ReferenceHasher};
+
+        let head = ReferenceHasher::hash(b"head");
+        let data_units = [ReferenceHasher::hash(b"first"), head];
+        assert_eq!(
+            batch_id::<ReferenceHasher>(7, 3, &head, &data_units),
+            [
+                0x11, 0xed, 0x1a, 0x02, 0xb5, 0x6f, 0x24, 0x99, 0xd8, 0x07, 0x4a, 0x41, 0xf0, 0x58,
+                0x04, 0xb6,
+            ]
+        );
+    }
+}
@@ -0,0 +1,346 @@
+//! Reference implementations of [`Hasher`] and [`MultiKeychain`] with precisely specified,
+//! platform-independent outputs, meant for reproducible tests and conformance vectors.
+//!
+//! **These are test-only and insecure.** The hash is not collision resistant against an
+//! adversary and all the signing keys are fixed and public, so anyone can forge any signature.
+//! Never use them in a deployment.
+
+use crate::{
+    Hasher, Index, Keychain, MultiKeychain, NodeCount, NodeIndex, PartialMultisignature,
+    SignatureSet,
+};
+use codec::{Decode, Encode};
+use std::convert::Infallible;
+
+/// The fixed key used by [`ReferenceHasher`], the bytes `0x00, 0x01, ..., 0x0f`.
+pub const REFERENCE_KEY: [u8; 16] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13);
+    v[1] ^= v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16);
+    v[3] ^= v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21);
+    v[3] ^= v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17);
+    v[1] ^= v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+fn compress(v: &mut [u64; 4], m: u64) {
+    v[3] ^= m;
+    sip_round(v);
+    sip_round(v);
+    v[0] ^= m;
+}
+
+fn finalize_half(v: &mut [u64; 4]) -> u64 {
+    for _ in 0..4 {
+        sip_round(v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+/// SipHash-2-4 with the 128-bit output, exactly as in the reference implementation by
+/// Aumasson and Bernstein. Both halves of the output are serialized little-endian.
+pub fn siphash_2_4_128(key: &[u8; 16], data: &[u8]) -> [u8; 16] {
+    let k0 = u64::from_le_bytes(key[..8].try_into().expect("the slice has 8 bytes"));
+    let k1 = u64::from_le_bytes(key[8..].try_into().expect("the slice has 8 bytes"));
+    let mut v = [
+        0x736f6d6570736575 ^ k0,
+        0x646f72616e646f6d ^ k1 ^ 0xee,
+        0x6c7967656e657261 ^ k0,
+        0x7465646279746573 ^ k1,
+    ];
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        compress(
+            &mut v,
+            u64::from_le_bytes(chunk.try_into().expect("the chunk has 8 bytes")),
+        );
+    }
+    let mut last = [0; 8];
+    last[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    last[7] = data.len() as u8;
+    compress(&mut v, u64::from_le_bytes(last));
+    v[2] ^= 0xee;
+    let low = finalize_half(&mut v);
+    v[1] ^= 0xdd;
+    let high = finalize_half(&mut v);
+    let mut result = [0; 16];
+    result[..8].copy_from_slice(&low.to_le_bytes());
+    result[8..].copy_from_slice(&high.to_le_bytes());
+    result
+}
+
+/// A 128-bit hasher computing [`siphash_2_4_128`] keyed with [`REFERENCE_KEY`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ReferenceHasher;
+
+impl Hasher for ReferenceHasher {
+    type Hash = [u8; 16];
+
+    fn hash(x: &[u8]) -> Self::Hash {
+        siphash_2_4_128(&REFERENCE_KEY, x)
+    }
+}
+
+pub type ReferenceHash = <ReferenceHasher as Hasher>::Hash;
+
+/// A signature produced by [`ReferenceKeychain`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Decode, Encode)]
+pub struct ReferenceSignature([u8; 16]);
+
+impl ReferenceSignature {
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+}
+
+/// A multisignature of [`ReferenceKeychain`], simply the set of all the signatures.
+pub type ReferencePartialMultisignature = SignatureSet<ReferenceSignature>;
+
+/// A deterministic keychain of a committee member.
+///
+/// The key of the node with index `i` is the [`ReferenceHasher`] hash of `i` encoded as
+/// a little-endian `u64`, and its signature of `msg` is [`siphash_2_4_128`] keyed with that key
+/// over the same encoding of `i` followed by `msg`. The keys are public, so the signatures
+/// prove nothing - see the module documentation.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ReferenceKeychain {
+    count: NodeCount,
+    index: NodeIndex,
+}
+
+impl ReferenceKeychain {
+    pub fn new(count: NodeCount, index: NodeIndex) -> Self {
+        ReferenceKeychain { count, index }
+    }
+
+    pub fn new_vec(count: NodeCount) -> Vec<Self> {
+        count
+            .into_iterator()
+            .map(|index| Self::new(count, index))
+            .collect()
+    }
+
+    fn signature_of(index: NodeIndex, msg: &[u8]) -> ReferenceSignature {
+        let encoded_index = (index.0 as u64).to_le_bytes();
+        let key = ReferenceHasher::hash(&encoded_index);
+        let signed: Vec<u8> = encoded_index.iter().chain(msg).copied().collect();
+        ReferenceSignature(siphash_2_4_128(&key, &signed))
+    }
+}
+
+impl Index for ReferenceKeychain {
+    fn index(&self) -> NodeIndex {
+        self.index
+    }
+}
+
+impl Keychain for ReferenceKeychain {
+    type Signature = ReferenceSignature;
+    type SignError = Infallible;
+
+    fn node_count(&self) -> NodeCount {
+        self.count
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Self::Signature, Self::SignError> {
+        Ok(Self::signature_of(self.index, msg))
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+        index.0 < self.count.0 && Self::signature_of(index, msg) == *sgn
+    }
+}
+
+impl MultiKeychain for ReferenceKeychain {
+    type PartialMultisignature = ReferencePartialMultisignature;
+
+    fn bootstrap_multi(
+        &self,
+        signature: &Self::Signature,
+        index: NodeIndex,
+    ) -> Self::PartialMultisignature {
+        SignatureSet::with_size(self.count).add_signature(signature, index)
+    }
+
+    fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool {
+        let signature_count = partial.iter().count();
+        if signature_count < self.count.consensus_threshold().0 {
+            return false;
+        }
+        partial.iter().all(|(i, sgn)| self.verify(msg, sgn, i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        reference::{siphash_2_4_128, ReferenceHasher, ReferenceKeychain, REFERENCE_KEY},
+        Hasher, Keychain, MultiKeychain, NodeCount, NodeIndex, PartialMultisignature,
+    };
+
+    #[test]
+    fn matches_siphash_reference_vectors() {
+        // The first vectors of `vectors_sip128` from the reference implementation,
+        // the message being the bytes 0x00, 0x01, ... of the given length.
+        let vectors = [
+            (
+                0,
+                [
+                    0xa3, 0x81, 0x7f, 0x04, 0xba, 0x25, 0xa8, 0xe6, 0x6d, 0xf6, 0x72, 0x14, 0xc7,
+                    0x55, 0x02, 0x93,
+                ],
+            ),
+            (
+                1,
+                [
+                    0xda, 0x87, 0xc1, 0xd8, 0x6b, 0x99, 0xaf, 0x44, 0x34, 0x76, 0x59, 0x11, 0x9b,
+                    0x22, 0xfc, 0x45,
+                ],
+            ),
+            (
+                7,
+                [
+                    0xa1, 0xf1, 0xeb, 0xbe, 0xd8, 0xdb, 0xc1, 0x53, 0xc0, 0xb8, 0x4a, 0xa6, 0x1f,
+                    0xf0, 0x82, 0x39,
+                ],
+            ),
+            (
+                8,
+                [
+                    0x3b, 0x62, 0xa9, 0xba, 0x62, 0x58, 0xf5, 0x61, 0x0f, 0x83, 0xe2, 0x64, 0xf3,
+                    0x14, 0x97, 0xb4,
+                ],
+            ),
+            (
+                15,
+                [
+                    0x54, 0x93, 0xe9, 0x99, 0x33, 0xb0, 0xa8, 0x11, 0x7e, 0x08, 0xec, 0x0f, 0x97,
+                    0xcf, 0xc3, 0xd9,
+                ],
+            ),
+        ];
+        for (length, digest) in vectors {
+            let message: Vec<u8> = (0..length).collect();
+            assert_eq!(siphash_2_4_128(&REFERENCE_KEY, &message), digest);
+        }
+    }
+
+    #[test]
+    fn hasher_digests_are_pinned() {
+        assert_eq!(
+            ReferenceHasher::hash(b""),
+            [
+                0xa3, 0x81, 0x7f, 0x04, 0xba, 0x25, 0xa8, 0xe6, 0x6d, 0xf6, 0x72, 0x14, 0xc7, 0x55,
+                0x02, 0x93,
+            ]
+        );
+        assert_eq!(
+            ReferenceHasher::hash(b"AlephBFT"),
+            [
+                0x83, 0x62, 0x65, 0x66, 0x42, 0x7f, 0xf1, 0x25, 0xa6, 0x7b, 0xd1, 0x46, 0xa2, 0x62,
+                0x0c, 0x28,
+            ]
+        );
+        assert_eq!(
+            ReferenceHasher::hash(b"The quick brown fox jumps over the lazy dog"),
+            [
+                0x76, 0x28, 0xc9, 0x30, 0x1a, 0xa4, 0x41, 0x25, 0x55, 0xe6, 0x52, 0x27, 0xcd, 0x31,
+                0x96, 0x4e,
+            ]
+        );
+    }
+
+    #[test]
+    fn signatures_are_pinned() {
+        let keychains = ReferenceKeychain::new_vec(NodeCount(7));
+        let vectors = [
+            (
+                0,
+                b"".as_slice(),
+                [
+                    0x19, 0xb9, 0xdd, 0xe8, 0x21, 0xe8, 0x1f, 0x53, 0x1a, 0xe9, 0xf8, 0xd5, 0x36,
+                    0xd1, 0xfe, 0x02,
+                ],
+            ),
+            (
+                0,
+                b"AlephBFT",
+                [
+                    0x4c, 0x8f, 0x35, 0xe9, 0x82, 0xfd, 0xec, 0x60, 0x8c, 0xfd, 0x26, 0xf2, 0x02,
+                    0x43, 0x6b, 0x5a,
+                ],
+            ),
+            (
+                1,
+                b"AlephBFT",
+                [
+                    0xf6, 0xac, 0xb8, 0x16, 0x34, 0x90, 0x15, 0xd3, 0xda, 0x6f, 0xb5, 0x29, 0x01,
+                    0xac, 0xa5, 0xa1,
+                ],
+            ),
+            (
+                6,
+                b"AlephBFT",
+                [
+                    0x9a, 0x02, 0xf3, 0xcb, 0x95, 0x7d, 0xa8, 0x8c, 0x27, 0x84, 0x54, 0x22, 0xc7,
+                    0x9b, 0xfb, 0x7e,
+                ],
+            ),
+        ];
+        for (index, message, signature) in vectors {
+            assert_eq!(
+                keychains[index]
+                    .sign(message)
+                    .expect("the keychain never fails")
+                    .as_bytes(),
+                &signature
+            );
+        }
+    }
+
+    #[test]
+    fn verifies_only_correct_signatures() {
+        let keychains = ReferenceKeychain::new_vec(NodeCount(4));
+        let signature = keychains[1]
+            .sign(b"message")
+            .expect("the keychain never fails");
+        assert!(keychains[0].verify(b"message", &signature, NodeIndex(1)));
+        assert!(!keychains[0].verify(b"message", &signature, NodeIndex(2)));
+        assert!(!keychains[0].verify(b"massage", &signature, NodeIndex(1)));
+        let outsider = ReferenceKeychain::new(NodeCount(5), NodeIndex(4));
+        let outsider_signature = outsider.sign(b"message").expect("the keychain never fails");
+        assert!(!keychains[0].verify(b"message", &outsider_signature, NodeIndex(4)));
+    }
+
+    #[test]
+    fn multisignature_completes_at_threshold() {
+        let keychains = ReferenceKeychain::new_vec(NodeCount(4));
+        let message = b"message";
+        let mut partial = keychains[0].bootstrap_multi(
+            &keychains[0]
+                .sign(message)
+                .expect("the keychain never fails"),
+            NodeIndex(0),
+        );
+        for keychain in &keychains[1..3] {
+            assert!(!keychains[0].is_complete(message, &partial));
+            partial = partial.add_signature(
+                &keychain.sign(message).expect("the keychain never fails"),
+                keychain.index,
+            );
+        }
+        assert!(keychains[0].is_complete(message, &partial));
+        assert!(!keychains[0].is_complete(b"other message", &partial));
+    }
+}